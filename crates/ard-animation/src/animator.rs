@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use ard_assets::prelude::*;
+use ard_ecs::prelude::*;
+use ard_math::Mat4;
+
+use crate::{
+    clip::AnimationClip,
+    skeleton::{JointTransform, Skeleton},
+};
+
+/// Plays animation clips on the skeleton of an entity.
+#[derive(Component, Clone)]
+pub struct Animator {
+    /// The clip being played. The rest pose of the skeleton is used when `None`.
+    pub clip: Option<Handle<AnimationClip>>,
+    /// Playback position of `clip` in seconds.
+    pub time: f32,
+    /// Playback rate. Negative values play the clip backwards.
+    pub speed: f32,
+    /// Wraps around at the ends of the clip instead of stopping.
+    pub looping: bool,
+    /// Second clip blended on top of `clip`.
+    pub blend: Option<AnimationBlend>,
+    /// Scratch space the blended clip is sampled into, kept so it isn't reallocated every tick.
+    pub(crate) blend_pose: Vec<JointTransform>,
+}
+
+/// Blends a second clip with the primary clip of an [`Animator`]. The clip is sampled at the
+/// same fraction of its duration as the primary clip, so clips of different lengths (like a
+/// walk and a run) stay in step.
+#[derive(Clone)]
+pub struct AnimationBlend {
+    pub clip: Handle<AnimationClip>,
+    /// How much of the blended clip to use, where `0.0` is only the primary clip and `1.0` is
+    /// only the blended clip.
+    pub weight: f32,
+}
+
+/// The skeleton deforming the skinned mesh of an entity.
+#[derive(Component, Clone)]
+pub struct Skin(pub Arc<Skeleton>);
+
+/// Joint matrices of the current pose of an entity's skeleton, computed every tick by the
+/// animation system and read by the renderer to skin meshes.
+#[derive(Component, Clone, Default)]
+pub struct JointPalette(pub Vec<Mat4>);
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self {
+            clip: None,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            blend: None,
+            blend_pose: Vec::default(),
+        }
+    }
+}
+
+impl Animator {
+    pub fn new(clip: Handle<AnimationClip>) -> Self {
+        Self {
+            clip: Some(clip),
+            ..Default::default()
+        }
+    }
+
+    /// Advances playback by `dt` seconds through a clip lasting `duration` seconds.
+    pub fn advance(&mut self, dt: f32, duration: f32) {
+        self.time += dt * self.speed;
+
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+    }
+}
+
+/// Samples `clip` at `time` and, if provided, blends in a second clip sampled at the same
+/// fraction of its duration. The blended clip is sampled into `blend_pose`.
+pub fn evaluate_pose(
+    skeleton: &Skeleton,
+    clip: Option<&AnimationClip>,
+    time: f32,
+    blend: Option<(&AnimationClip, f32)>,
+    pose: &mut Vec<JointTransform>,
+    blend_pose: &mut Vec<JointTransform>,
+) {
+    skeleton.rest_pose(pose);
+
+    let clip = match clip {
+        Some(clip) => clip,
+        None => return,
+    };
+    clip.sample(time, pose);
+
+    let (blend_clip, weight) = match blend {
+        Some((blend_clip, weight)) if weight > 0.0 => (blend_clip, weight.min(1.0)),
+        _ => return,
+    };
+
+    let phase = if clip.duration() > 0.0 {
+        time / clip.duration()
+    } else {
+        0.0
+    };
+
+    skeleton.rest_pose(blend_pose);
+    blend_clip.sample(phase * blend_clip.duration(), blend_pose);
+
+    pose.iter_mut()
+        .zip(blend_pose.iter())
+        .for_each(|(joint, blend_joint)| *joint = joint.lerp(blend_joint, weight));
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    ops::{Add, Mul},
+};
+
+use ard_assets::prelude::*;
+use ard_gltf::{GltfAnimation, GltfAnimationValues, GltfInterpolation, GltfSkin, NodeIndex};
+use ard_math::{Quat, Vec3};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::skeleton::{mirror_rotation, mirror_translation, JointTransform};
+
+/// A set of keyframed joint transforms.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnimationClip {
+    name: String,
+    /// Time in seconds of the last keyframe of any channel.
+    duration: f32,
+    channels: Vec<AnimationChannel>,
+}
+
+/// Keyframes for a single property of a joint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationChannel {
+    /// Index of the joint in the skeleton being animated.
+    pub joint: usize,
+    pub interpolation: Interpolation,
+    /// Time of each keyframe in seconds. Must be increasing.
+    pub times: Vec<f32>,
+    /// Keyframe values. With [`Interpolation::CubicSpline`], each keyframe has three values in
+    /// the order in-tangent, value, out-tangent.
+    pub values: ChannelValues,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    CubicSpline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChannelValues {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+}
+
+pub struct AnimationClipLoader;
+
+impl AnimationClip {
+    pub fn new(name: impl Into<String>, channels: Vec<AnimationChannel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max);
+
+        Self {
+            name: name.into(),
+            duration,
+            channels,
+        }
+    }
+
+    /// Converts an imported animation into a clip for the joints of `skin`. Channels targeting
+    /// nodes that aren't joints of the skin are skipped, as are morph target weights.
+    ///
+    /// GLTF uses the opposite handedness, so keyframes are mirrored the same way the joints of
+    /// the skeleton are.
+    pub fn from_gltf(animation: &GltfAnimation, skin: &GltfSkin) -> Self {
+        let joint_of: HashMap<usize, usize> = skin
+            .joints
+            .iter()
+            .enumerate()
+            .map(|(joint, NodeIndex(node))| (*node, joint))
+            .collect();
+
+        let channels = animation
+            .channels
+            .iter()
+            .filter_map(|channel| {
+                let joint = *joint_of.get(&channel.target.0)?;
+                let values = match &channel.values {
+                    GltfAnimationValues::Translation(values) => ChannelValues::Translation(
+                        values.iter().map(|v| mirror_translation(*v)).collect(),
+                    ),
+                    GltfAnimationValues::Rotation(values) => ChannelValues::Rotation(
+                        values.iter().map(|v| mirror_rotation(*v)).collect(),
+                    ),
+                    GltfAnimationValues::Scale(values) => ChannelValues::Scale(values.clone()),
+                    GltfAnimationValues::Weights(_) => return None,
+                };
+
+                Some(AnimationChannel {
+                    joint,
+                    interpolation: match channel.interpolation {
+                        GltfInterpolation::Step => Interpolation::Step,
+                        GltfInterpolation::Linear => Interpolation::Linear,
+                        GltfInterpolation::CubicSpline => Interpolation::CubicSpline,
+                    },
+                    times: channel.times.clone(),
+                    values,
+                })
+            })
+            .collect();
+
+        Self::new(animation.name.clone(), channels)
+    }
+
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline(always)]
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    #[inline(always)]
+    pub fn channels(&self) -> &[AnimationChannel] {
+        &self.channels
+    }
+
+    /// Samples every channel at `time` seconds, overwriting the animated properties of `pose`.
+    /// Joints and properties without a channel are left untouched.
+    pub fn sample(&self, time: f32, pose: &mut [JointTransform]) {
+        for channel in &self.channels {
+            let joint = match pose.get_mut(channel.joint) {
+                Some(joint) => joint,
+                None => continue,
+            };
+
+            match &channel.values {
+                ChannelValues::Translation(values) => {
+                    joint.translation = sample_keys(
+                        channel.interpolation,
+                        &channel.times,
+                        values,
+                        time,
+                        Vec3::lerp,
+                    );
+                }
+                ChannelValues::Rotation(values) => {
+                    joint.rotation = sample_keys(
+                        channel.interpolation,
+                        &channel.times,
+                        values,
+                        time,
+                        Quat::slerp,
+                    )
+                    .normalize();
+                }
+                ChannelValues::Scale(values) => {
+                    joint.scale = sample_keys(
+                        channel.interpolation,
+                        &channel.times,
+                        values,
+                        time,
+                        Vec3::lerp,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Samples a keyframed value. Times before the first or after the last keyframe are clamped.
+fn sample_keys<T: Copy + Add<Output = T> + Mul<f32, Output = T>>(
+    interpolation: Interpolation,
+    times: &[f32],
+    values: &[T],
+    time: f32,
+    lerp: impl Fn(T, T, f32) -> T,
+) -> T {
+    // Cubic splines have an in and out tangent around every value
+    let value = |key: usize| match interpolation {
+        Interpolation::CubicSpline => values[key * 3 + 1],
+        _ => values[key],
+    };
+
+    let next = times.partition_point(|t| *t <= time);
+    if next == 0 {
+        return value(0);
+    }
+    if next == times.len() {
+        return value(times.len() - 1);
+    }
+
+    let prev = next - 1;
+    let dt = times[next] - times[prev];
+    let t = if dt > 0.0 {
+        (time - times[prev]) / dt
+    } else {
+        0.0
+    };
+
+    match interpolation {
+        Interpolation::Step => value(prev),
+        Interpolation::Linear => lerp(value(prev), value(next), t),
+        Interpolation::CubicSpline => {
+            let out_tangent = values[prev * 3 + 2];
+            let in_tangent = values[next * 3];
+            let t2 = t * t;
+            let t3 = t2 * t;
+            value(prev) * (2.0 * t3 - 3.0 * t2 + 1.0)
+                + out_tangent * (dt * (t3 - 2.0 * t2 + t))
+                + value(next) * (-2.0 * t3 + 3.0 * t2)
+                + in_tangent * (dt * (t3 - t2))
+        }
+    }
+}
+
+impl Asset for AnimationClip {
+    const EXTENSION: &'static str = "ard_anim";
+    type Loader = AnimationClipLoader;
+}
+
+#[async_trait]
+impl AssetLoader for AnimationClipLoader {
+    type Asset = AnimationClip;
+
+    async fn load(
+        &self,
+        _assets: Assets,
+        package: Package,
+        asset: &AssetName,
+    ) -> Result<AssetLoadResult<Self::Asset>, AssetLoadError> {
+        let data = package.read(asset.to_owned()).await?;
+        let asset = match bincode::deserialize::<AnimationClip>(&data) {
+            Ok(asset) => asset,
+            Err(err) => return Err(AssetLoadError::Other(err.to_string())),
+        };
+
+        Ok(AssetLoadResult::Loaded {
+            asset,
+            persistent: false,
+        })
+    }
+
+    async fn post_load(
+        &self,
+        _assets: Assets,
+        _package: Package,
+        _handle: Handle<Self::Asset>,
+    ) -> Result<AssetPostLoadResult, AssetLoadError> {
+        Ok(AssetPostLoadResult::Loaded)
+    }
+}
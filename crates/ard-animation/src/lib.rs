@@ -0,0 +1,29 @@
+use ard_assets::prelude::Assets;
+use ard_core::prelude::*;
+use ard_ecs::prelude::*;
+use clip::{AnimationClip, AnimationClipLoader};
+use system::AnimationSystem;
+
+pub mod animator;
+pub mod clip;
+pub mod skeleton;
+pub mod system;
+
+#[cfg(test)]
+mod tests;
+
+/// Samples animation clips and computes the joint palettes of skinned entities.
+#[derive(Resource, Clone)]
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&mut self, app: &mut AppBuilder) {
+        app.add_startup_function(late_init);
+        app.add_system(AnimationSystem);
+    }
+}
+
+fn late_init(app: &mut App) {
+    let assets = app.resources.get::<Assets>().unwrap();
+    assets.register::<AnimationClip>(AnimationClipLoader);
+}
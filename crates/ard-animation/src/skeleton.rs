@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use ard_gltf::{GltfNode, GltfSkin, NodeIndex};
+use ard_math::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Local transform of a joint relative to its parent.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JointTransform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Joint {
+    pub name: String,
+    /// Index of the parent joint. `None` for root joints.
+    pub parent: Option<usize>,
+    /// Transforms the mesh from model space into the local space of the joint.
+    pub inverse_bind: Mat4,
+    /// Transform of the joint when it isn't animated.
+    pub rest: JointTransform,
+}
+
+/// A hierarchy of joints used to deform a skinned mesh. Joints are indexed in the same order as
+/// the joint indices of the mesh's vertices.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+    /// Order joints are evaluated in such that parents are evaluated before their children.
+    order: Vec<usize>,
+    /// Transform applied to every root joint.
+    root: Mat4,
+}
+
+impl JointTransform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    #[inline(always)]
+    pub fn from_mat4(mat: Mat4) -> Self {
+        let (scale, rotation, translation) = mat.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    #[inline(always)]
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    /// Interpolates between `self` and `other`, where a `t` of `0.0` is `self`.
+    #[inline(always)]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+impl Default for JointTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Skeleton {
+    /// Creates a new skeleton. `root` is applied to every joint without a parent.
+    ///
+    /// # Panics
+    /// If any joint has a parent that doesn't exist or the hierarchy contains a cycle.
+    pub fn new(joints: Vec<Joint>, root: Mat4) -> Self {
+        // Sort joints by their depth in the hierarchy
+        let depth = |mut joint: usize| {
+            let mut depth = 0;
+            while let Some(parent) = joints[joint].parent {
+                assert!(
+                    parent < joints.len(),
+                    "joint `{joint}` has an invalid parent"
+                );
+                depth += 1;
+                assert!(depth <= joints.len(), "joint hierarchy contains a cycle");
+                joint = parent;
+            }
+            depth
+        };
+        let depths: Vec<_> = (0..joints.len()).map(depth).collect();
+        let mut order: Vec<_> = (0..joints.len()).collect();
+        order.sort_by_key(|joint| depths[*joint]);
+
+        Self {
+            joints,
+            order,
+            root,
+        }
+    }
+
+    /// Creates a skeleton from an imported skin. `nodes` are the root nodes of the model the skin
+    /// came from.
+    ///
+    /// GLTF uses the opposite handedness, so joints are mirrored the same way mesh vertices are.
+    /// Nodes between two joints that aren't joints themselves are ignored.
+    pub fn from_gltf(skin: &GltfSkin, nodes: &[GltfNode]) -> Self {
+        // Find every node and its parent
+        let mut parents = HashMap::<usize, usize>::default();
+        let mut all_nodes = HashMap::<usize, &GltfNode>::default();
+        fn walk<'a>(
+            node: &'a GltfNode,
+            parents: &mut HashMap<usize, usize>,
+            all_nodes: &mut HashMap<usize, &'a GltfNode>,
+        ) {
+            all_nodes.insert(node.index.0, node);
+            node.children.iter().for_each(|child| {
+                parents.insert(child.index.0, node.index.0);
+                walk(child, parents, all_nodes);
+            });
+        }
+        nodes
+            .iter()
+            .for_each(|node| walk(node, &mut parents, &mut all_nodes));
+
+        let joint_of: HashMap<usize, usize> = skin
+            .joints
+            .iter()
+            .enumerate()
+            .map(|(joint, NodeIndex(node))| (*node, joint))
+            .collect();
+
+        let mirror = Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+
+        // The root transform is the global transform of whatever the first root joint is
+        // attached to.
+        let mut root = None;
+
+        let joints = skin
+            .joints
+            .iter()
+            .zip(skin.inverse_bind_matrices.iter())
+            .map(|(NodeIndex(node), inverse_bind)| {
+                let gltf_node = all_nodes.get(node);
+                let local = gltf_node.map(|n| n.model).unwrap_or(Mat4::IDENTITY);
+
+                // Walk up the hierarchy until we hit another joint
+                let mut parent = None;
+                let mut ancestors = Mat4::IDENTITY;
+                let mut cur = *node;
+                while let Some(next) = parents.get(&cur) {
+                    if let Some(joint) = joint_of.get(next) {
+                        parent = Some(*joint);
+                        break;
+                    }
+                    ancestors =
+                        all_nodes.get(next).map(|n| n.model).unwrap_or_default() * ancestors;
+                    cur = *next;
+                }
+
+                if parent.is_none() && root.is_none() {
+                    root = Some(mirror * ancestors * mirror);
+                }
+
+                Joint {
+                    name: gltf_node.map(|n| n.name.clone()).unwrap_or_default(),
+                    parent,
+                    inverse_bind: mirror * *inverse_bind * mirror,
+                    rest: mirror_transform(JointTransform::from_mat4(local)),
+                }
+            })
+            .collect();
+
+        Self::new(joints, root.unwrap_or(Mat4::IDENTITY))
+    }
+
+    #[inline(always)]
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    #[inline(always)]
+    pub fn root(&self) -> Mat4 {
+        self.root
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.joints.is_empty()
+    }
+
+    /// Writes the rest transform of every joint into `pose`.
+    pub fn rest_pose(&self, pose: &mut Vec<JointTransform>) {
+        pose.clear();
+        pose.extend(self.joints.iter().map(|joint| joint.rest));
+    }
+
+    /// Converts a pose of local joint transforms into the matrices used to skin vertices, which
+    /// take vertices from model space in the bind pose to model space in the pose.
+    pub fn joint_matrices(&self, pose: &[JointTransform], out: &mut Vec<Mat4>) {
+        assert_eq!(pose.len(), self.joints.len());
+
+        // Compute the global transform of every joint
+        out.clear();
+        out.resize(self.joints.len(), Mat4::IDENTITY);
+        for &joint in &self.order {
+            let parent = match self.joints[joint].parent {
+                Some(parent) => out[parent],
+                None => self.root,
+            };
+            out[joint] = parent * pose[joint].to_mat4();
+        }
+
+        out.iter_mut()
+            .zip(self.joints.iter())
+            .for_each(|(mat, joint)| *mat *= joint.inverse_bind);
+    }
+}
+
+/// Mirrors a GLTF joint transform along the X axis.
+#[inline(always)]
+pub(crate) fn mirror_transform(transform: JointTransform) -> JointTransform {
+    JointTransform {
+        translation: mirror_translation(transform.translation),
+        rotation: mirror_rotation(transform.rotation),
+        scale: transform.scale,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn mirror_translation(translation: Vec3) -> Vec3 {
+    Vec3::new(-translation.x, translation.y, translation.z)
+}
+
+#[inline(always)]
+pub(crate) fn mirror_rotation(rotation: Quat) -> Quat {
+    Quat::from_xyzw(rotation.x, -rotation.y, -rotation.z, rotation.w)
+}
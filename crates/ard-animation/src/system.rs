@@ -0,0 +1,90 @@
+use ard_assets::prelude::*;
+use ard_core::prelude::*;
+use ard_ecs::prelude::*;
+use rayon::prelude::*;
+
+use crate::{
+    animator::{evaluate_pose, Animator, JointPalette, Skin},
+    clip::AnimationClip,
+};
+
+/// Advances animators and computes the joint palettes of every skinned entity.
+#[derive(SystemState, Default)]
+pub struct AnimationSystem;
+
+impl AnimationSystem {
+    fn tick(
+        &mut self,
+        tick: Tick,
+        commands: Commands,
+        queries: Queries<(Write<Animator>, Read<Skin>, Write<JointPalette>)>,
+        res: Res<(Read<Assets>,)>,
+    ) {
+        let assets = res.get::<Assets>().unwrap();
+        let dt = tick.0.as_secs_f32();
+
+        // Skinned entities need somewhere to put their palette
+        for (entity, _) in queries
+            .filter()
+            .without::<JointPalette>()
+            .make::<(Entity, Read<Skin>)>()
+        {
+            commands
+                .entities
+                .add_component(entity, JointPalette::default());
+        }
+
+        // Poses are independent of each other, so they're evaluated in parallel
+        let mut skinned: Vec<_> = queries
+            .make::<(Option<Write<Animator>>, Read<Skin>, Write<JointPalette>)>()
+            .collect();
+
+        skinned
+            .par_iter_mut()
+            .for_each_init(Vec::default, |pose, (animator, skin, palette)| {
+                let animator = match animator {
+                    Some(animator) => animator,
+                    None => {
+                        // Nothing is blended without an animator, so the scratch pose is unused
+                        evaluate_pose(&skin.0, None, 0.0, None, pose, &mut Vec::default());
+                        skin.0.joint_matrices(pose, &mut palette.0);
+                        return;
+                    }
+                };
+
+                // Handles are cloned so the animator can be advanced while the clips are read
+                let clip_handle = animator.clip.clone();
+                let blend = animator.blend.clone();
+                let clip = clip_handle
+                    .as_ref()
+                    .and_then(|handle| assets.get::<AnimationClip>(handle));
+                let blend_clip = blend
+                    .as_ref()
+                    .and_then(|blend| assets.get::<AnimationClip>(&blend.clip));
+
+                if let Some(clip) = &clip {
+                    animator.advance(dt, clip.duration());
+                }
+
+                evaluate_pose(
+                    &skin.0,
+                    clip.as_deref(),
+                    animator.time,
+                    blend_clip
+                        .as_deref()
+                        .zip(blend.as_ref().map(|blend| blend.weight)),
+                    pose,
+                    &mut animator.blend_pose,
+                );
+                skin.0.joint_matrices(pose, &mut palette.0);
+            });
+    }
+}
+
+impl From<AnimationSystem> for System {
+    fn from(value: AnimationSystem) -> Self {
+        SystemBuilder::new(value)
+            .with_handler(AnimationSystem::tick)
+            .build()
+    }
+}
@@ -0,0 +1,426 @@
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+use ard_gltf::{GltfModel, GltfNode, GltfNodeData, GltfSkin, NodeIndex};
+use ard_math::{Mat4, Quat, Vec3};
+
+use crate::{
+    animator::{evaluate_pose, Animator},
+    clip::{AnimationChannel, AnimationClip, ChannelValues, Interpolation},
+    skeleton::{Joint, JointTransform, Skeleton},
+};
+
+const EPSILON: f32 = 0.0001;
+
+/// Two joints stacked along the Y axis, like an arm with an elbow one unit above the shoulder.
+fn arm() -> Skeleton {
+    Skeleton::new(
+        vec![
+            Joint {
+                name: "shoulder".into(),
+                parent: None,
+                inverse_bind: Mat4::IDENTITY,
+                rest: JointTransform::IDENTITY,
+            },
+            Joint {
+                name: "elbow".into(),
+                parent: Some(0),
+                inverse_bind: Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0)),
+                rest: JointTransform {
+                    translation: Vec3::Y,
+                    ..Default::default()
+                },
+            },
+        ],
+        Mat4::IDENTITY,
+    )
+}
+
+/// Rotates both joints from 0 to 90 degrees around Z over one second.
+fn bend() -> AnimationClip {
+    let channel = |joint| AnimationChannel {
+        joint,
+        interpolation: Interpolation::Linear,
+        times: vec![0.0, 1.0],
+        values: ChannelValues::Rotation(vec![Quat::IDENTITY, Quat::from_rotation_z(FRAC_PI_2)]),
+    };
+    AnimationClip::new("bend", vec![channel(0), channel(1)])
+}
+
+fn palette(skeleton: &Skeleton, clip: Option<&AnimationClip>, time: f32) -> Vec<Mat4> {
+    let mut pose = Vec::default();
+    let mut palette = Vec::default();
+    evaluate_pose(skeleton, clip, time, None, &mut pose, &mut Vec::default());
+    skeleton.joint_matrices(&pose, &mut palette);
+    palette
+}
+
+#[test]
+fn rest_pose_is_bind_pose() {
+    let skeleton = arm();
+    for mat in palette(&skeleton, None, 0.0) {
+        assert!(mat.abs_diff_eq(Mat4::IDENTITY, EPSILON));
+    }
+}
+
+#[test]
+fn joint_matrices_at_half_second() {
+    let skeleton = arm();
+    let clip = bend();
+    let palette = palette(&skeleton, Some(&clip), 0.5);
+
+    // Shoulder is rotated 45 degrees
+    let expected = Mat4::from_rotation_z(FRAC_PI_2 * 0.5);
+    assert!(palette[0].abs_diff_eq(expected, EPSILON));
+
+    // Elbow is rotated 45 degrees on top of the shoulder, so the tip points along -X
+    let elbow = palette[1].transform_point3(Vec3::Y);
+    let tip = palette[1].transform_point3(Vec3::new(0.0, 2.0, 0.0));
+    let half_sqrt_2 = std::f32::consts::FRAC_1_SQRT_2;
+    assert!(elbow.abs_diff_eq(Vec3::new(-half_sqrt_2, half_sqrt_2, 0.0), EPSILON));
+    assert!(tip.abs_diff_eq(Vec3::new(-1.0 - half_sqrt_2, half_sqrt_2, 0.0), EPSILON));
+
+    let expected = Mat4::from_rotation_z(FRAC_PI_2 * 0.5)
+        * Mat4::from_translation(Vec3::Y)
+        * Mat4::from_rotation_z(FRAC_PI_2 * 0.5)
+        * Mat4::from_translation(-Vec3::Y);
+    assert!(palette[1].abs_diff_eq(expected, EPSILON));
+}
+
+#[test]
+fn sampling_clamps_outside_of_clip() {
+    let skeleton = arm();
+    let clip = bend();
+
+    let before = palette(&skeleton, Some(&clip), -1.0);
+    assert!(before[0].abs_diff_eq(Mat4::IDENTITY, EPSILON));
+
+    let after = palette(&skeleton, Some(&clip), 2.0);
+    assert!(after[0].abs_diff_eq(Mat4::from_rotation_z(FRAC_PI_2), EPSILON));
+}
+
+#[test]
+fn step_and_cubic_spline_sampling() {
+    let mut pose = vec![JointTransform::IDENTITY];
+
+    let step = AnimationClip::new(
+        "step",
+        vec![AnimationChannel {
+            joint: 0,
+            interpolation: Interpolation::Step,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Translation(vec![Vec3::ZERO, Vec3::X]),
+        }],
+    );
+    step.sample(0.99, &mut pose);
+    assert_eq!(pose[0].translation, Vec3::ZERO);
+    step.sample(1.0, &mut pose);
+    assert_eq!(pose[0].translation, Vec3::X);
+
+    // With flat tangents, a cubic spline is a smoothstep between the keyframes
+    let cubic = AnimationClip::new(
+        "cubic",
+        vec![AnimationChannel {
+            joint: 0,
+            interpolation: Interpolation::CubicSpline,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Translation(vec![
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                Vec3::X,
+                Vec3::ZERO,
+            ]),
+        }],
+    );
+    cubic.sample(0.25, &mut pose);
+    assert!((pose[0].translation.x - 0.15625).abs() < EPSILON);
+    cubic.sample(0.5, &mut pose);
+    assert!((pose[0].translation.x - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn looping_playback() {
+    let mut animator = Animator::default();
+
+    animator.advance(1.5, 1.0);
+    assert!((animator.time - 0.5).abs() < EPSILON);
+
+    animator.speed = -1.0;
+    animator.advance(0.75, 1.0);
+    assert!((animator.time - 0.75).abs() < EPSILON);
+
+    animator.looping = false;
+    animator.speed = 1.0;
+    animator.advance(10.0, 1.0);
+    assert_eq!(animator.time, 1.0);
+}
+
+#[test]
+fn blending_uses_matching_phase() {
+    let skeleton = arm();
+    let clip = bend();
+
+    // Holds the shoulder at 90 degrees for two seconds
+    let hold = AnimationClip::new(
+        "hold",
+        vec![AnimationChannel {
+            joint: 0,
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 2.0],
+            values: ChannelValues::Rotation(vec![
+                Quat::from_rotation_z(FRAC_PI_2),
+                Quat::from_rotation_z(FRAC_PI_2),
+            ]),
+        }],
+    );
+
+    let mut pose = Vec::default();
+    let mut blend_pose = Vec::default();
+    let mut palette = Vec::default();
+    evaluate_pose(
+        &skeleton,
+        Some(&clip),
+        0.5,
+        Some((&hold, 0.5)),
+        &mut pose,
+        &mut blend_pose,
+    );
+    skeleton.joint_matrices(&pose, &mut palette);
+
+    // Halfway between 45 and 90 degrees
+    let expected = Mat4::from_rotation_z(FRAC_PI_2 * 0.75);
+    assert!(palette[0].abs_diff_eq(expected, EPSILON));
+
+    // Zero weight is just the primary clip
+    evaluate_pose(
+        &skeleton,
+        Some(&clip),
+        0.5,
+        Some((&hold, 0.0)),
+        &mut pose,
+        &mut blend_pose,
+    );
+    skeleton.joint_matrices(&pose, &mut palette);
+    let expected = Mat4::from_rotation_z(FRAC_PI_2 * 0.5);
+    assert!(palette[0].abs_diff_eq(expected, EPSILON));
+}
+
+#[test]
+fn skeleton_from_gltf() {
+    let node = |index, model, children| GltfNode {
+        name: format!("node_{index}"),
+        index: NodeIndex(index),
+        model,
+        data: GltfNodeData::Empty,
+        children,
+    };
+
+    // An armature node that isn't a joint holds the two joints
+    let elbow = node(
+        2,
+        Mat4::from_translation(Vec3::new(1.0, 0.0, 0.0)),
+        Vec::default(),
+    );
+    let shoulder = node(1, Mat4::IDENTITY, vec![elbow]);
+    let armature = node(0, Mat4::from_translation(Vec3::Y), vec![shoulder]);
+
+    let skin = GltfSkin {
+        joints: vec![NodeIndex(2), NodeIndex(1)],
+        inverse_bind_matrices: vec![
+            Mat4::from_translation(Vec3::new(-1.0, -1.0, 0.0)),
+            Mat4::from_translation(-Vec3::Y),
+        ],
+    };
+
+    let skeleton = Skeleton::from_gltf(&skin, &[armature]);
+    let joints = skeleton.joints();
+    assert_eq!(joints[0].name, "node_2");
+    assert_eq!(joints[0].parent, Some(1));
+    assert_eq!(joints[1].parent, None);
+
+    // Mirrored along X
+    assert_eq!(joints[0].rest.translation, Vec3::new(-1.0, 0.0, 0.0));
+    assert!(skeleton
+        .root()
+        .abs_diff_eq(Mat4::from_translation(Vec3::Y), EPSILON));
+
+    // The rest pose still matches the bind pose after mirroring
+    for mat in palette(&skeleton, None, 0.0) {
+        assert!(mat.abs_diff_eq(Mat4::IDENTITY, EPSILON));
+    }
+}
+
+/// Builds a GLB file from its JSON and binary chunks.
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = bin.to_vec();
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let mut glb = Vec::default();
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+    glb
+}
+
+/// A figure rigged the same way as the RiggedFigure sample, with an armature node holding a
+/// chain of hips, spine, and neck joints, and a mesh skinned to them. The sample isn't part of
+/// the repository, so the rig is kept small enough that its pose can be worked out by hand.
+///
+/// The animation moves the hips forward one unit and turns them 90 degrees about Y while the
+/// spine bends 90 degrees about Z, all over one second.
+fn rigged_figure() -> GltfModel {
+    let mut bin = Vec::default();
+    let mut views = Vec::default();
+    let mut push = |bytes: Vec<u8>| {
+        views.push(format!(
+            r#"{{ "buffer": 0, "byteOffset": {}, "byteLength": {} }}"#,
+            bin.len(),
+            bytes.len()
+        ));
+        bin.extend_from_slice(&bytes);
+        bin.resize(bin.len().next_multiple_of(4), 0);
+    };
+    let floats = |values: &[f32]| -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    };
+
+    let rotate_y = Quat::from_rotation_y(FRAC_PI_2);
+    let rotate_z = Quat::from_rotation_z(FRAC_PI_2);
+
+    // Positions, indices, joints, and weights of a single triangle bound to the hips
+    push(floats(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]));
+    push([0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()).collect());
+    push(vec![0; 3 * 4 * 2]);
+    push(floats(&[1.0, 0.0, 0.0, 0.0].repeat(3)));
+
+    // Inverse bind matrices of the hips, spine, and neck
+    let inverse_binds: Vec<_> = [1.0, 1.5, 2.0]
+        .into_iter()
+        .flat_map(|height| Mat4::from_translation(Vec3::new(0.0, -height, 0.0)).to_cols_array())
+        .collect();
+    push(floats(&inverse_binds));
+
+    // Keyframe times, hip translations, hip rotations, and spine rotations
+    push(floats(&[0.0, 1.0]));
+    push(floats(&[0.0, 0.0, 0.0, 0.0, 0.0, 1.0]));
+    push(floats(
+        &[Quat::IDENTITY.to_array(), rotate_y.to_array()].concat(),
+    ));
+    push(floats(
+        &[Quat::IDENTITY.to_array(), rotate_z.to_array()].concat(),
+    ));
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0, 4] }}],
+            "nodes": [
+                {{ "name": "figure", "translation": [0.0, 1.0, 0.0], "children": [1] }},
+                {{ "name": "hips", "children": [2] }},
+                {{ "name": "spine", "translation": [0.0, 0.5, 0.0], "children": [3] }},
+                {{ "name": "neck", "translation": [0.0, 0.5, 0.0] }},
+                {{ "name": "body", "mesh": 0, "skin": 0 }}
+            ],
+            "skins": [{{ "joints": [1, 2, 3], "inverseBindMatrices": 4, "skeleton": 1 }}],
+            "meshes": [{{
+                "primitives": [{{
+                    "attributes": {{ "POSITION": 0, "JOINTS_0": 2, "WEIGHTS_0": 3 }},
+                    "indices": 1,
+                    "material": 0
+                }}]
+            }}],
+            "materials": [{{}}],
+            "animations": [{{
+                "name": "wave",
+                "samplers": [
+                    {{ "input": 5, "output": 6, "interpolation": "LINEAR" }},
+                    {{ "input": 5, "output": 7, "interpolation": "LINEAR" }},
+                    {{ "input": 5, "output": 8, "interpolation": "LINEAR" }}
+                ],
+                "channels": [
+                    {{ "sampler": 0, "target": {{ "node": 1, "path": "translation" }} }},
+                    {{ "sampler": 1, "target": {{ "node": 1, "path": "rotation" }} }},
+                    {{ "sampler": 2, "target": {{ "node": 2, "path": "rotation" }} }}
+                ]
+            }}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                {{ "bufferView": 2, "componentType": 5123, "count": 3, "type": "VEC4" }},
+                {{ "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC4" }},
+                {{ "bufferView": 4, "componentType": 5126, "count": 3, "type": "MAT4" }},
+                {{
+                    "bufferView": 5,
+                    "componentType": 5126,
+                    "count": 2,
+                    "type": "SCALAR",
+                    "min": [0.0],
+                    "max": [1.0]
+                }},
+                {{ "bufferView": 6, "componentType": 5126, "count": 2, "type": "VEC3" }},
+                {{ "bufferView": 7, "componentType": 5126, "count": 2, "type": "VEC4" }},
+                {{ "bufferView": 8, "componentType": 5126, "count": 2, "type": "VEC4" }}
+            ],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [{}]
+        }}"#,
+        bin.len(),
+        views.join(", "),
+    );
+
+    GltfModel::from_slice(&glb(&json, &bin)).unwrap()
+}
+
+#[test]
+fn rigged_figure_joint_matrices_at_half_second() {
+    let model = rigged_figure();
+    let skin = &model.skins[0];
+    let skeleton = Skeleton::from_gltf(skin, &model.roots);
+    let clip = AnimationClip::from_gltf(&model.animations[0], skin);
+    assert_eq!(skeleton.len(), 3);
+    assert_eq!(clip.duration(), 1.0);
+
+    let palette = palette(&skeleton, Some(&clip), 0.5);
+
+    // Halfway through, the hips are half a unit forward and turned 45 degrees, and the spine is
+    // bent 45 degrees. The neck starts two units up and is mirrored along X.
+    let neck = palette[2].transform_point3(Vec3::new(0.0, 2.0, 0.0));
+    let expected = Vec3::new(0.25, 1.5 + 0.5 * FRAC_1_SQRT_2, 0.75);
+    assert!(neck.abs_diff_eq(expected, EPSILON));
+
+    // The same pose built directly in GLTF space
+    let hips = Mat4::from_translation(Vec3::new(0.0, 1.0, 0.5)) * Mat4::from_rotation_y(FRAC_PI_4);
+    let spine =
+        hips * Mat4::from_translation(Vec3::new(0.0, 0.5, 0.0)) * Mat4::from_rotation_z(FRAC_PI_4);
+    let neck = spine * Mat4::from_translation(Vec3::new(0.0, 0.5, 0.0));
+
+    let mirror = Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+    let expected = [
+        hips * Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0)),
+        spine * Mat4::from_translation(Vec3::new(0.0, -1.5, 0.0)),
+        neck * Mat4::from_translation(Vec3::new(0.0, -2.0, 0.0)),
+    ];
+    for (mat, expected) in palette.iter().zip(expected) {
+        assert!(mat.abs_diff_eq(mirror * expected * mirror, EPSILON));
+    }
+}
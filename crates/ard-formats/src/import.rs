@@ -0,0 +1,11 @@
+use ::serde::{Deserialize, Serialize};
+
+use crate::{meshlet::MeshletSettings, simplify::LodSettings};
+
+/// How meshlets are built and levels of detail are generated when a mesh is imported.
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub struct ImportSettings {
+    pub meshlets: MeshletSettings,
+    /// Levels of detail to generate. `None` to only keep the original mesh.
+    pub lods: Option<LodSettings>,
+}
@@ -1,7 +1,9 @@
 pub mod cube_map;
+pub mod import;
 pub mod material;
 pub mod mesh;
 pub mod meshlet;
 pub mod model;
+pub mod simplify;
 pub mod texture;
 pub mod vertex;
@@ -1,4 +1,4 @@
-use ard_math::Vec4;
+use ard_math::{Mat3, Vec3, Vec4};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -17,6 +17,12 @@ pub enum MaterialType<TextureRef> {
         diffuse_map: Option<TextureRef>,
         normal_map: Option<TextureRef>,
         metallic_roughness_map: Option<TextureRef>,
+        emissive: Vec3,
+        emissive_map: Option<TextureRef>,
+        occlusion_strength: f32,
+        occlusion_map: Option<TextureRef>,
+        /// Transform applied to UVs before sampling any of the maps.
+        uv_transform: Mat3,
     },
 }
 
@@ -8,7 +8,8 @@ use half::f16;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    meshlet::{MeshClustifier, Meshlet},
+    meshlet::{MeshClustifier, MeshClustifierOutput, Meshlet, MeshletSettings},
+    simplify::{simplify, LodSettings},
     vertex::{VertexAttribute, VertexData, VertexLayout},
 };
 
@@ -18,6 +19,7 @@ pub struct MeshHeader {
     pub index_count: u32,
     pub vertex_count: u32,
     pub meshlet_count: u32,
+    pub lod_count: u32,
     pub vertex_layout: VertexLayout,
 }
 
@@ -26,11 +28,26 @@ pub struct MeshData {
     vertices: VertexData,
     indices: Vec<u8>,
     meshlets: Vec<Meshlet>,
+    lods: Vec<MeshLod>,
+}
+
+/// A level of detail of a mesh. Each level has its own meshlets, which follow the meshlets of the
+/// previous level.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+pub struct MeshLod {
+    pub meshlet_offset: u32,
+    pub meshlet_count: u32,
+    pub index_offset: u32,
+    pub index_count: u32,
+    /// Upper bound on the distance between this level and the original mesh in object space.
+    pub error: f32,
 }
 
 pub struct MeshDataBuilder {
     index_data: Vec<u32>,
     vertex_data: VertexData,
+    meshlet_settings: MeshletSettings,
+    lod_settings: Option<LodSettings>,
 }
 
 /// Volume bounded by the dimensions of a box and sphere.
@@ -59,6 +76,13 @@ impl MeshData {
         &self.meshlets
     }
 
+    /// Levels of detail from most to least detailed. There is always at least one level, which
+    /// is the original mesh.
+    #[inline(always)]
+    pub fn lods(&self) -> &[MeshLod] {
+        &self.lods
+    }
+
     #[inline(always)]
     pub fn index_count(&self) -> usize {
         self.indices.len()
@@ -105,6 +129,7 @@ impl MeshData {
                 queue_types: QueueTypes::TRANSFER,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("vertex_staging".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -132,6 +157,7 @@ impl MeshData {
                 queue_types: QueueTypes::TRANSFER,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("index_staging".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -156,6 +182,13 @@ impl MeshData {
         staging
     }
 
+    /// Meshlets of the most detailed level, which is what ray tracing uses.
+    #[inline(always)]
+    pub fn blas_meshlets(&self) -> &[Meshlet] {
+        let lod = &self.lods[0];
+        &self.meshlets[lod.meshlet_offset as usize..][..lod.meshlet_count as usize]
+    }
+
     pub fn blas_geometries<'a>(
         &'a self,
         vertex_data: &'a Buffer,
@@ -163,39 +196,75 @@ impl MeshData {
         index_data: &'a Buffer,
         index_data_array_element: usize,
         index_data_base: u64,
-    ) -> Vec<AccelerationStructureGeometry> {
-        self.meshlets
-            .iter()
-            .map(|meshlet| {
-                AccelerationStructureGeometry {
-                    // TODO: Make configurable
-                    flags: GeometryFlags::OPAQUE,
-                    vertex_format: Format::Rgb32SFloat,
-                    vertex_data,
-                    vertex_data_array_element,
-                    vertex_data_offset: 0,
-                    vertex_count: meshlet.vertex_count as usize,
-                    vertex_stride: std::mem::size_of::<Vec4>() as u64,
-                    index_type: MeshData::INDEX_TYPE,
-                    index_data,
-                    index_data_array_element,
-                    index_data_offset: index_data_base
-                        + (meshlet.index_offset as u64 * MeshData::INDEX_SIZE as u64),
-                    triangle_count: meshlet.primitive_count as usize,
-                }
-            })
-            .collect()
+    ) -> Vec<AccelerationStructureGeometry<'a>> {
+        meshlet_blas_geometries(
+            self.blas_meshlets(),
+            vertex_data,
+            vertex_data_array_element,
+            0,
+            index_data,
+            index_data_array_element,
+            index_data_base,
+        )
     }
 }
 
+/// Creates one BLAS geometry per meshlet. `vertex_data_offset` is in bytes and is added to every
+/// index, which lets meshes with indices relative to their first vertex use vertices anywhere in
+/// `vertex_data`.
+pub fn meshlet_blas_geometries<'a>(
+    meshlets: &[Meshlet],
+    vertex_data: &'a Buffer,
+    vertex_data_array_element: usize,
+    vertex_data_offset: u64,
+    index_data: &'a Buffer,
+    index_data_array_element: usize,
+    index_data_base: u64,
+) -> Vec<AccelerationStructureGeometry<'a>> {
+    meshlets
+        .iter()
+        .map(|meshlet| {
+            AccelerationStructureGeometry {
+                // TODO: Make configurable
+                flags: GeometryFlags::OPAQUE,
+                vertex_format: Format::Rgb32SFloat,
+                vertex_data,
+                vertex_data_array_element,
+                vertex_data_offset,
+                vertex_count: meshlet.vertex_count as usize,
+                vertex_stride: std::mem::size_of::<Vec4>() as u64,
+                index_type: MeshData::INDEX_TYPE,
+                index_data,
+                index_data_array_element,
+                index_data_offset: index_data_base
+                    + (meshlet.index_offset as u64 * MeshData::INDEX_SIZE as u64),
+                triangle_count: meshlet.primitive_count as usize,
+            }
+        })
+        .collect()
+}
+
 impl MeshDataBuilder {
     pub fn new(layout: VertexLayout, vertex_count: usize, index_count: usize) -> Self {
         Self {
             vertex_data: VertexData::new(vertex_count, layout),
             index_data: vec![0; index_count],
+            meshlet_settings: MeshletSettings::default(),
+            lod_settings: None,
         }
     }
 
+    /// Generates levels of detail when the mesh is built. No levels are generated by default.
+    pub fn lod_settings(mut self, settings: LodSettings) -> Self {
+        self.lod_settings = Some(settings);
+        self
+    }
+
+    pub fn meshlet_settings(mut self, settings: MeshletSettings) -> Self {
+        self.meshlet_settings = settings;
+        self
+    }
+
     pub fn add_indices(mut self, indices: &[u32]) -> Self {
         assert_eq!(indices.len(), self.index_data.len());
         self.index_data.copy_from_slice(indices);
@@ -253,13 +322,133 @@ impl MeshDataBuilder {
         self
     }
 
+    pub fn add_joints(mut self, src: &[UVec4]) -> Self {
+        assert_eq!(src.len(), self.vertex_data.len());
+        self.vertex_data
+            .joints_mut()
+            .iter_mut()
+            .zip(src.iter())
+            .for_each(|(dst, src)| {
+                *dst = src.to_array().map(|joint| joint as u16);
+            });
+        self
+    }
+
+    /// Weights are normalized so they sum to one before being quantized.
+    pub fn add_weights(mut self, src: &[Vec4]) -> Self {
+        assert_eq!(src.len(), self.vertex_data.len());
+        self.vertex_data
+            .weights_mut()
+            .iter_mut()
+            .zip(src.iter())
+            .for_each(|(dst, src)| {
+                let sum = src.x + src.y + src.z + src.w;
+                let weights = if sum > 0.0 { *src / sum } else { Vec4::X };
+                *dst = weights
+                    .to_array()
+                    .map(|weight| (weight.clamp(0.0, 1.0) * 65535.0).round() as u16);
+            });
+        self
+    }
+
     pub fn build(self) -> MeshData {
-        let res = MeshClustifier::new(self.vertex_data, self.index_data).build();
+        let lod_indices = self.simplify_lods();
+
+        let mut res = MeshClustifier::with_settings(
+            self.vertex_data.clone(),
+            self.index_data,
+            self.meshlet_settings,
+        )
+        .build();
+        let mut lods = vec![MeshLod {
+            meshlet_offset: 0,
+            meshlet_count: res.meshlets.len() as u32,
+            index_offset: 0,
+            index_count: res.indices.len() as u32,
+            error: 0.0,
+        }];
+
+        // Each level is clustered separately and appended after the previous level
+        for (indices, error) in lod_indices {
+            let lod = MeshClustifier::with_settings(
+                self.vertex_data.clone(),
+                indices,
+                self.meshlet_settings,
+            )
+            .build();
+            lods.push(Self::append_lod(&mut res, lod, error));
+        }
+
+        res.vertices.compute_bounds();
+
         MeshData {
             vertices: res.vertices,
             indices: res.indices,
             meshlets: res.meshlets,
+            lods,
+        }
+    }
+
+    /// Simplifies the indices for every level past the first, along with the error of each.
+    fn simplify_lods(&self) -> Vec<(Vec<u32>, f32)> {
+        let settings = match &self.lod_settings {
+            Some(settings) => settings,
+            None => return Vec::default(),
+        };
+
+        let positions = self.vertex_data.positions();
+        let radius = self.vertex_data.bounds().bounding_sphere().w;
+        let max_error = settings.max_error * radius;
+
+        let mut lods = Vec::default();
+        let mut target = self.index_data.len() as f32;
+        let mut prev_len = self.index_data.len();
+
+        for _ in 1..settings.levels.min(MeshLod::MAX_LODS) {
+            target *= settings.triangle_ratio;
+            let target_index_count = (target as usize / 3) * 3;
+
+            // Simplifying the original mesh each time keeps the error relative to the original
+            let lod = simplify(positions, &self.index_data, target_index_count, max_error);
+
+            // Stop once simplification stops making meaningful progress
+            if lod.indices.is_empty() || lod.indices.len() as f32 > prev_len as f32 * 0.9 {
+                break;
+            }
+
+            prev_len = lod.indices.len();
+            lods.push((lod.indices, lod.error));
         }
+
+        lods
+    }
+
+    fn append_lod(
+        dst: &mut MeshClustifierOutput,
+        src: MeshClustifierOutput,
+        error: f32,
+    ) -> MeshLod {
+        let vertex_base = dst.vertices.len() as u32;
+        let index_base = dst.indices.len() as u32;
+
+        let lod = MeshLod {
+            meshlet_offset: dst.meshlets.len() as u32,
+            meshlet_count: src.meshlets.len() as u32,
+            index_offset: index_base,
+            index_count: src.indices.len() as u32,
+            error,
+        };
+
+        (0..src.vertices.len() as u32).for_each(|i| dst.vertices.append_from(&src.vertices, i));
+        dst.indices.extend_from_slice(&src.indices);
+        dst.meshlets
+            .extend(src.meshlets.into_iter().map(|mut meshlet| {
+                meshlet.vertex_offset += vertex_base;
+                meshlet.index_offset += index_base;
+                meshlet
+            }));
+
+        lod
     }
 
     /// Convert a normalized vector to a signed 16-bit per channel value packed into a u64.
@@ -286,6 +475,18 @@ impl MeshDataBuilder {
     }
 }
 
+impl MeshLod {
+    /// Must match `MAX_MESH_LODS` in the shader interface.
+    pub const MAX_LODS: usize = 4;
+
+    /// Distance past which this level's error covers fewer than `pixel_error` pixels with a
+    /// perspective projection of vertical field of view `fov` radians and `screen_height` pixels.
+    #[inline]
+    pub fn switch_distance(&self, fov: f32, screen_height: f32, pixel_error: f32) -> f32 {
+        self.error * screen_height / (2.0 * (fov * 0.5).tan() * pixel_error)
+    }
+}
+
 impl MeshHeader {
     pub fn mesh_data_path(root: impl Into<AssetNameBuf>) -> AssetNameBuf {
         let mut path: AssetNameBuf = root.into();
@@ -12,6 +12,51 @@ pub struct Meshlet {
     pub bounds: ObjectBounds,
     pub vertex_count: u8,
     pub primitive_count: u8,
+    pub cone: MeshletCone,
+}
+
+/// Every triangle of a meshlet faces away from the axis by at most the angle of the cone, so the
+/// meshlet can be culled when the camera can only see the back of every triangle.
+///
+/// A meshlet is entirely back facing from a camera if
+///
+/// `dot(center - camera, normalize(axis)) >= cutoff * length(center - camera) + radius`
+///
+/// where `center` and `radius` are of any sphere containing the meshlet.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub struct MeshletCone {
+    /// Direction the triangles face away from. This is snapped to the signed 8-bit normalized
+    /// values the cone is stored with on the GPU, so it isn't unit length. Zero if the cone is
+    /// disabled.
+    pub axis: Vec3,
+    /// Sine of the angle between the axis and the triangle facing furthest from it. `1.0` if the
+    /// cone is disabled.
+    pub cutoff: f32,
+}
+
+/// Limits and extra data for the meshlets of a mesh.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MeshletSettings {
+    /// Clamped to [`Meshlet::MAX_VERTICES`].
+    pub max_vertices: usize,
+    /// Clamped to [`Meshlet::MAX_PRIMITIVES`].
+    pub max_primitives: usize,
+    /// Compute a [`MeshletCone`] for each meshlet. If disabled, meshlets are never cone culled.
+    pub cone_culling: bool,
+}
+
+/// Summary of the meshlets built for one or more meshes.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MeshletStats {
+    pub meshlet_count: usize,
+    pub vertex_count: usize,
+    pub primitive_count: usize,
+    /// Sum of the maximum vertices of every meshlet.
+    pub vertex_capacity: usize,
+    /// Sum of the maximum primitives of every meshlet.
+    pub primitive_capacity: usize,
+    /// Number of meshlets whose cone is disabled.
+    pub disabled_cones: usize,
 }
 
 impl Meshlet {
@@ -19,7 +64,151 @@ impl Meshlet {
     pub const MAX_PRIMITIVES: usize = 126;
 }
 
+impl MeshletCone {
+    /// A cone that never culls.
+    pub const DISABLED: Self = Self {
+        axis: Vec3::ZERO,
+        cutoff: 1.0,
+    };
+
+    /// Triangles whose normals are this close to facing away from the axis make the cone too wide
+    /// to cull anything worth the test.
+    const MIN_DOT: f32 = 0.1;
+
+    /// Computes the cone bounding the triangles. Degenerate triangles are ignored, and a disabled
+    /// cone is returned if there are no other triangles or they face too many directions.
+    ///
+    /// Triangles face the direction of `(p2 - p0) x (p1 - p0)`, which is the front face of
+    /// imported meshes.
+    pub fn from_triangles(positions: &[Vec4], indices: &[u8]) -> Self {
+        let normals: Vec<_> = indices
+            .chunks_exact(3)
+            .filter_map(|tri| {
+                let p0 = positions[tri[0] as usize].xyz();
+                let p1 = positions[tri[1] as usize].xyz();
+                let p2 = positions[tri[2] as usize].xyz();
+                (p2 - p0).cross(p1 - p0).try_normalize()
+            })
+            .collect();
+
+        let axis = match normals.iter().sum::<Vec3>().try_normalize() {
+            Some(axis) => axis,
+            None => return Self::DISABLED,
+        };
+
+        // The cutoff is computed using the axis as it is stored so culling stays conservative
+        let axis = (axis * 127.0).round() / 127.0;
+        let unit_axis = match axis.try_normalize() {
+            Some(axis) => axis,
+            None => return Self::DISABLED,
+        };
+
+        let min_dot = normals
+            .iter()
+            .map(|n| n.dot(unit_axis))
+            .fold(1.0_f32, f32::min);
+
+        if min_dot <= Self::MIN_DOT {
+            return Self::DISABLED;
+        }
+
+        Self {
+            axis,
+            cutoff: (1.0 - min_dot * min_dot).sqrt(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_disabled(&self) -> bool {
+        self.cutoff >= 1.0
+    }
+
+    /// Packs the cone into four signed 8-bit normalized values with the axis in `xyz` and the
+    /// cutoff in `w`. The cutoff is rounded up so the packed cone never culls more.
+    pub fn pack(&self) -> u32 {
+        if self.is_disabled() {
+            return (127_u32) << 24;
+        }
+
+        let snorm8 = |v: f32| ((v.clamp(-1.0, 1.0) * 127.0).round() as i8) as u8 as u32;
+        let cutoff = (self.cutoff.clamp(0.0, 1.0) * 127.0).ceil() as u32;
+
+        snorm8(self.axis.x)
+            | (snorm8(self.axis.y) << 8)
+            | (snorm8(self.axis.z) << 16)
+            | (cutoff << 24)
+    }
+}
+
+impl MeshletSettings {
+    pub const DEFAULT: Self = Self {
+        max_vertices: Meshlet::MAX_VERTICES,
+        max_primitives: Meshlet::MAX_PRIMITIVES,
+        cone_culling: true,
+    };
+
+    #[inline(always)]
+    fn max_vertices(&self) -> usize {
+        self.max_vertices.clamp(3, Meshlet::MAX_VERTICES)
+    }
+
+    #[inline(always)]
+    fn max_primitives(&self) -> usize {
+        self.max_primitives.clamp(1, Meshlet::MAX_PRIMITIVES)
+    }
+}
+
+impl Default for MeshletSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl MeshletStats {
+    pub fn from_meshlets(meshlets: &[Meshlet], settings: &MeshletSettings) -> Self {
+        Self {
+            meshlet_count: meshlets.len(),
+            vertex_count: meshlets.iter().map(|m| m.vertex_count as usize).sum(),
+            primitive_count: meshlets.iter().map(|m| m.primitive_count as usize).sum(),
+            vertex_capacity: meshlets.len() * settings.max_vertices(),
+            primitive_capacity: meshlets.len() * settings.max_primitives(),
+            disabled_cones: meshlets.iter().filter(|m| m.cone.is_disabled()).count(),
+        }
+    }
+
+    /// Adds the meshlets of another mesh.
+    pub fn merge(&mut self, other: &MeshletStats) {
+        self.meshlet_count += other.meshlet_count;
+        self.vertex_count += other.vertex_count;
+        self.primitive_count += other.primitive_count;
+        self.vertex_capacity += other.vertex_capacity;
+        self.primitive_capacity += other.primitive_capacity;
+        self.disabled_cones += other.disabled_cones;
+    }
+
+    /// How full meshlets are with vertices on average, from `0.0` to `1.0`.
+    #[inline]
+    pub fn vertex_occupancy(&self) -> f32 {
+        if self.vertex_capacity == 0 {
+            0.0
+        } else {
+            self.vertex_count as f32 / self.vertex_capacity as f32
+        }
+    }
+
+    /// How full meshlets are with primitives on average, from `0.0` to `1.0`.
+    #[inline]
+    pub fn primitive_occupancy(&self) -> f32 {
+        if self.primitive_capacity == 0 {
+            0.0
+        } else {
+            self.primitive_count as f32 / self.primitive_capacity as f32
+        }
+    }
+}
+
 pub struct MeshClustifier {
+    settings: MeshletSettings,
     vertices: VertexData,
     indices: Vec<u32>,
     unused_triangles: Vec<Triangle>,
@@ -75,6 +264,7 @@ impl WorkingMeshlet {
     fn add(
         &mut self,
         tri: Triangle,
+        settings: &MeshletSettings,
         indices: &[u32],
         positions: &[Vec4],
         triangle_neighbours: &[Vec<Triangle>],
@@ -85,7 +275,7 @@ impl WorkingMeshlet {
         }
 
         // Skip if we're at our primitive limit
-        if self.triangles.len() == Meshlet::MAX_PRIMITIVES {
+        if self.triangles.len() == settings.max_primitives() {
             return false;
         }
 
@@ -100,7 +290,7 @@ impl WorkingMeshlet {
             .sum();
 
         // Don't add if we couldn't hold the new vertices
-        if self.vertices.len() + new_verts > Meshlet::MAX_VERTICES {
+        if self.vertices.len() + new_verts > settings.max_vertices() {
             return false;
         }
 
@@ -154,7 +344,16 @@ impl WorkingMeshlet {
 }
 
 impl MeshClustifier {
+    #[inline(always)]
     pub fn new(vertices: VertexData, indices: Vec<u32>) -> Self {
+        Self::with_settings(vertices, indices, MeshletSettings::default())
+    }
+
+    pub fn with_settings(
+        vertices: VertexData,
+        indices: Vec<u32>,
+        settings: MeshletSettings,
+    ) -> Self {
         assert_eq!(indices.len() % 3, 0);
 
         // Map each vertex to the triangles it belongs to
@@ -203,6 +402,7 @@ impl MeshClustifier {
         });
 
         Self {
+            settings,
             unused_triangles,
             used_tri_lookup: vec![false; indices.len() / 3],
             vertices,
@@ -227,6 +427,7 @@ impl MeshClustifier {
         // Add the first triangle to the working meshlet.
         self.meshlet.add(
             Triangle::new(0),
+            &self.settings,
             &self.indices,
             self.vertices.positions(),
             &self.triangle_neighbours,
@@ -257,6 +458,7 @@ impl MeshClustifier {
             // Add the triangle to the meshlet
             if self.meshlet.add(
                 best_tri,
+                &self.settings,
                 &self.indices,
                 self.vertices.positions(),
                 &self.triangle_neighbours,
@@ -269,7 +471,7 @@ impl MeshClustifier {
             // that we might have room for more primitives. We should try to fill up our primitive
             // count by checking to see if there are any primitives that contain vertices that are
             // all within the meshlet. These would be in the border of the meshlet.
-            while self.meshlet.triangles.len() != Meshlet::MAX_PRIMITIVES {
+            while self.meshlet.triangles.len() != self.settings.max_primitives() {
                 match self.select_best_tri(
                     self.meshlet.border.iter().map(|t| *t),
                     &self.used_tri_lookup,
@@ -280,6 +482,7 @@ impl MeshClustifier {
                         // If we fail to add it here, we are full on primitives.
                         if self.meshlet.add(
                             tri,
+                            &self.settings,
                             &self.indices,
                             self.vertices.positions(),
                             &self.triangle_neighbours,
@@ -311,6 +514,7 @@ impl MeshClustifier {
             if let Some(tri) = starting_tri {
                 if self.meshlet.add(
                     tri,
+                    &self.settings,
                     &self.indices,
                     self.vertices.positions(),
                     &self.triangle_neighbours,
@@ -355,6 +559,7 @@ impl MeshClustifier {
                 bounds: ObjectBounds::default(),
                 vertex_count: wm.vertices.len() as u8,
                 primitive_count: wm.triangles.len() as u8,
+                cone: MeshletCone::DISABLED,
             };
 
             // Insert unique vertices into the new buffer, and creating a mapping from the old
@@ -376,10 +581,16 @@ impl MeshClustifier {
             });
 
             // Compute object bounds
-            meshlet.bounds = ObjectBounds::from_positions(
-                &out.vertices.positions()[meshlet.vertex_offset as usize
-                    ..(meshlet.vertex_offset as usize + meshlet.vertex_count as usize)],
-            );
+            let positions = &out.vertices.positions()[meshlet.vertex_offset as usize
+                ..(meshlet.vertex_offset as usize + meshlet.vertex_count as usize)];
+            meshlet.bounds = ObjectBounds::from_positions(positions);
+
+            if self.settings.cone_culling {
+                meshlet.cone = MeshletCone::from_triangles(
+                    positions,
+                    &out.indices[meshlet.index_offset as usize..],
+                );
+            }
 
             out.meshlets.push(meshlet);
         });
@@ -471,3 +682,161 @@ impl MeshClustifier {
         self.unused_triangles.swap_remove(idx);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vertex::VertexLayout;
+
+    /// A flat grid of `size` by `size` quads facing `+Y`.
+    fn grid(size: u32) -> (VertexData, Vec<u32>) {
+        let row = size + 1;
+        let mut vertices = VertexData::new((row * row) as usize, VertexLayout::POSITION);
+        vertices
+            .positions_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, pos)| {
+                let (x, z) = (i as u32 % row, i as u32 / row);
+                *pos = Vec4::new(x as f32, 0.0, z as f32, 1.0);
+            });
+        vertices.compute_bounds();
+
+        let mut indices = Vec::default();
+        for z in 0..size {
+            for x in 0..size {
+                let i = z * row + x;
+                indices.extend_from_slice(&[i, i + 1, i + row]);
+                indices.extend_from_slice(&[i + 1, i + row + 1, i + row]);
+            }
+        }
+
+        (vertices, indices)
+    }
+
+    /// Triangles of the output, mapped back to the original vertices by position.
+    fn output_triangles(out: &MeshClustifierOutput) -> Vec<[(i32, i32); 3]> {
+        let mut tris = Vec::default();
+        for meshlet in &out.meshlets {
+            let indices = &out.indices[meshlet.index_offset as usize..]
+                [..meshlet.primitive_count as usize * 3];
+            for tri in indices.chunks_exact(3) {
+                let mut verts = [tri[0], tri[1], tri[2]].map(|i| {
+                    let pos = out.vertices.positions()[(meshlet.vertex_offset + i as u32) as usize];
+                    (pos.x as i32, pos.z as i32)
+                });
+                // Rotate so the same triangle always compares equal
+                let min = (0..3).min_by_key(|i| verts[*i]).unwrap();
+                verts.rotate_left(min);
+                tris.push(verts);
+            }
+        }
+        tris
+    }
+
+    #[test]
+    fn every_triangle_is_covered_once() {
+        let (vertices, indices) = grid(16);
+        let tri_count = indices.len() / 3;
+
+        let mut expected: Vec<_> = indices
+            .chunks_exact(3)
+            .map(|tri| {
+                let mut verts = [0, 1, 2].map(|i| {
+                    let pos = vertices.positions()[tri[i] as usize];
+                    (pos.x as i32, pos.z as i32)
+                });
+                let min = (0..3).min_by_key(|i| verts[*i]).unwrap();
+                verts.rotate_left(min);
+                verts
+            })
+            .collect();
+
+        let settings = MeshletSettings {
+            max_vertices: 32,
+            max_primitives: 32,
+            cone_culling: true,
+        };
+        let out = MeshClustifier::with_settings(vertices, indices, settings).build();
+
+        // 512 triangles with at most 32 per meshlet
+        assert_eq!(tri_count, 512);
+        assert!(out.meshlets.len() >= 16);
+        for meshlet in &out.meshlets {
+            assert!(meshlet.vertex_count as usize <= 32);
+            assert!(meshlet.primitive_count as usize <= 32);
+        }
+
+        let mut found = output_triangles(&out);
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let stats = MeshletStats::from_meshlets(&out.meshlets, &settings);
+        assert_eq!(stats.meshlet_count, out.meshlets.len());
+        assert_eq!(stats.primitive_count, 512);
+        assert!(stats.primitive_occupancy() > 0.0 && stats.primitive_occupancy() <= 1.0);
+    }
+
+    #[test]
+    fn flat_meshlets_have_tight_cones() {
+        let (vertices, indices) = grid(4);
+        let out = MeshClustifier::new(vertices, indices).build();
+
+        for meshlet in &out.meshlets {
+            assert!(!meshlet.cone.is_disabled());
+            // The grid faces +Y with the winding used by imported meshes
+            assert!(meshlet.cone.axis.normalize().dot(Vec3::Y) > 0.999);
+            assert!(meshlet.cone.cutoff < 0.01);
+        }
+    }
+
+    #[test]
+    fn degenerate_triangles_disable_cones() {
+        let mut vertices = VertexData::new(4, VertexLayout::POSITION);
+        vertices.positions_mut().copy_from_slice(&[
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(2.0, 0.0, 0.0, 1.0),
+            Vec4::new(f32::NAN, 0.0, 0.0, 1.0),
+        ]);
+        vertices.compute_bounds();
+
+        // A line, a repeated vertex, and a triangle with a NaN position
+        let out = MeshClustifier::new(vertices, vec![0, 1, 2, 0, 0, 1, 0, 1, 3]).build();
+
+        for meshlet in &out.meshlets {
+            assert_eq!(meshlet.cone, MeshletCone::DISABLED);
+            assert!(!meshlet.cone.axis.is_nan() && !meshlet.cone.cutoff.is_nan());
+        }
+    }
+
+    #[test]
+    fn opposite_faces_disable_cones() {
+        let mut vertices = VertexData::new(3, VertexLayout::POSITION);
+        vertices.positions_mut().copy_from_slice(&[
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(0.0, 0.0, 1.0, 1.0),
+        ]);
+        vertices.compute_bounds();
+
+        // The same triangle with both windings
+        let out = MeshClustifier::new(vertices, vec![0, 1, 2, 0, 2, 1]).build();
+        assert_eq!(out.meshlets.len(), 1);
+        assert!(out.meshlets[0].cone.is_disabled());
+    }
+
+    #[test]
+    fn packed_cone_never_culls_more() {
+        let cone = MeshletCone {
+            axis: Vec3::new(0.0, 0.5, 0.5),
+            cutoff: 0.3,
+        };
+        let packed = cone.pack();
+        let cutoff = (packed >> 24) as i8 as f32 / 127.0;
+        assert!(cutoff >= 0.3);
+        assert_eq!((packed >> 8) as u8 as i8, 64);
+        assert_eq!(MeshletCone::DISABLED.pack() >> 24, 127);
+    }
+}
@@ -9,9 +9,17 @@ pub struct ModelHeader {
     pub materials: Vec<AssetNameBuf>,
     pub lights: Vec<Light>,
     pub mesh_groups: Vec<MeshGroup>,
+    pub skins: Vec<SkinHeader>,
     pub roots: Vec<Node>,
 }
 
+/// A skeleton used by skinned mesh groups and the animation clips that animate it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SkinHeader {
+    pub skeleton: AssetNameBuf,
+    pub clips: Vec<AssetNameBuf>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum Light {
     Point {
@@ -53,6 +61,11 @@ pub struct Node {
 pub enum NodeData {
     Empty,
     MeshGroup(u32),
+    /// A mesh group deformed by the skin at index `skin`.
+    SkinnedMeshGroup {
+        mesh_group: u32,
+        skin: u32,
+    },
     Light(u32),
 }
 
@@ -77,6 +90,22 @@ impl ModelHeader {
         path
     }
 
+    pub fn skeleton_path(root: impl Into<AssetNameBuf>, skin: usize) -> AssetNameBuf {
+        let mut path: AssetNameBuf = root.into();
+        path.push("skins");
+        path.push(skin.to_string());
+        path.push("skeleton.ard_skel");
+        path
+    }
+
+    pub fn clip_path(root: impl Into<AssetNameBuf>, skin: usize, idx: usize) -> AssetNameBuf {
+        let mut path: AssetNameBuf = root.into();
+        path.push("skins");
+        path.push(skin.to_string());
+        path.push(format!("{idx}.ard_anim"));
+        path
+    }
+
     pub fn mesh_path(root: impl Into<AssetNameBuf>, idx: usize) -> AssetNameBuf {
         let mut path: AssetNameBuf = root.into();
         path.push("meshes");
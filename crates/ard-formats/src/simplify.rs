@@ -0,0 +1,401 @@
+use ard_math::*;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// Options for generating levels of detail when a mesh is imported.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub struct LodSettings {
+    /// Number of levels to generate, including the original mesh. Clamped to
+    /// [`MeshLod::MAX_LODS`](crate::mesh::MeshLod::MAX_LODS).
+    pub levels: usize,
+    /// Fraction of the triangles of the previous level each level targets.
+    pub triangle_ratio: f32,
+    /// Largest error a level may have relative to the bounding radius of the mesh. No more levels
+    /// are generated once a level can't be simplified within this error.
+    pub max_error: f32,
+}
+
+/// Indices of a simplified mesh.
+pub struct Simplified {
+    pub indices: Vec<u32>,
+    /// Upper bound on the distance between the simplified and original surface.
+    pub error: f32,
+}
+
+/// Symmetric 4x4 matrix measuring the sum of squared distances from a point to a set of planes.
+#[derive(Debug, Default, Copy, Clone)]
+struct Quadric([f64; 10]);
+
+/// Boundary edges are weighted more so the outline of open meshes is kept.
+const BOUNDARY_WEIGHT: f64 = 4.0;
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            levels: 4,
+            triangle_ratio: 0.5,
+            max_error: 0.02,
+        }
+    }
+}
+
+impl Quadric {
+    fn from_plane(n: DVec3, d: f64, weight: f64) -> Self {
+        Self([
+            n.x * n.x * weight,
+            n.x * n.y * weight,
+            n.x * n.z * weight,
+            n.x * d * weight,
+            n.y * n.y * weight,
+            n.y * n.z * weight,
+            n.y * d * weight,
+            n.z * n.z * weight,
+            n.z * d * weight,
+            d * d * weight,
+        ])
+    }
+
+    #[inline]
+    fn add(&mut self, other: &Quadric) {
+        self.0
+            .iter_mut()
+            .zip(other.0.iter())
+            .for_each(|(a, b)| *a += *b);
+    }
+
+    /// Sum of squared distances from `p` to every plane.
+    #[inline]
+    fn error(&self, p: DVec3) -> f64 {
+        let q = &self.0;
+        let e = q[0] * p.x * p.x
+            + 2.0 * q[1] * p.x * p.y
+            + 2.0 * q[2] * p.x * p.z
+            + 2.0 * q[3] * p.x
+            + q[4] * p.y * p.y
+            + 2.0 * q[5] * p.y * p.z
+            + 2.0 * q[6] * p.y
+            + q[7] * p.z * p.z
+            + 2.0 * q[8] * p.z
+            + q[9];
+        e.max(0.0)
+    }
+}
+
+/// Simplifies a triangle mesh by collapsing edges until it has at most `target_index_count`
+/// indices or no edge can be collapsed without moving the surface more than `max_error`.
+///
+/// Vertices are only removed, never moved, so the result uses the original vertices. Vertices
+/// that share a position with another vertex, such as along a UV seam, are never removed so
+/// attributes stay continuous across the seam.
+pub fn simplify(
+    positions: &[Vec4],
+    indices: &[u32],
+    target_index_count: usize,
+    max_error: f32,
+) -> Simplified {
+    assert_eq!(indices.len() % 3, 0);
+
+    let pos = |i: u32| positions[i as usize].xyz().as_dvec3();
+
+    // Vertices sharing a position with another vertex are locked
+    let mut position_counts = FxHashMap::<[u32; 3], u32>::default();
+    for p in positions {
+        *position_counts
+            .entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()])
+            .or_default() += 1;
+    }
+    let locked: Vec<_> = positions
+        .iter()
+        .map(|p| position_counts[&[p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]] > 1)
+        .collect();
+
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edge_counts = FxHashMap::<(u32, u32), i32>::default();
+    for tri in indices.chunks_exact(3) {
+        let (p0, p1, p2) = (pos(tri[0]), pos(tri[1]), pos(tri[2]));
+        let n = match (p1 - p0).cross(p2 - p0).try_normalize() {
+            Some(n) => n,
+            None => continue,
+        };
+
+        let q = Quadric::from_plane(n, -n.dot(p0), 1.0);
+        tri.iter().for_each(|i| quadrics[*i as usize].add(&q));
+
+        for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            *edge_counts.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+
+    // Edges used by a single triangle are on the boundary. They get a plane perpendicular to the
+    // triangle so vertices can slide along the boundary but not away from it.
+    for tri in indices.chunks_exact(3) {
+        let (p0, p1, p2) = (pos(tri[0]), pos(tri[1]), pos(tri[2]));
+        let n = match (p1 - p0).cross(p2 - p0).try_normalize() {
+            Some(n) => n,
+            None => continue,
+        };
+
+        for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            if edge_counts[&(a.min(b), a.max(b))] != 1 {
+                continue;
+            }
+
+            let edge = pos(b) - pos(a);
+            let bn = match edge.cross(n).try_normalize() {
+                Some(bn) => bn,
+                None => continue,
+            };
+            let q = Quadric::from_plane(bn, -bn.dot(pos(a)), BOUNDARY_WEIGHT);
+            quadrics[a as usize].add(&q);
+            quadrics[b as usize].add(&q);
+        }
+    }
+
+    let max_cost = (max_error as f64) * (max_error as f64);
+    let mut indices = indices.to_vec();
+    let mut worst_cost = 0.0_f64;
+
+    loop {
+        if indices.len() <= target_index_count {
+            break;
+        }
+
+        // Triangles using each vertex
+        let mut vertex_tris = vec![Vec::new(); positions.len()];
+        indices.chunks_exact(3).enumerate().for_each(|(t, tri)| {
+            tri.iter().for_each(|i| vertex_tris[*i as usize].push(t));
+        });
+
+        // Find the cheapest way to collapse each edge
+        let mut candidates = FxHashMap::<(u32, u32), (f64, u32, u32)>::default();
+        for tri in indices.chunks_exact(3) {
+            for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+                let mut best: Option<(f64, u32, u32)> = None;
+                for (from, to) in [(a, b), (b, a)] {
+                    if locked[from as usize] {
+                        continue;
+                    }
+
+                    let mut q = quadrics[from as usize];
+                    q.add(&quadrics[to as usize]);
+                    let cost = q.error(pos(to));
+                    if best.map(|(c, _, _)| cost < c).unwrap_or(true) {
+                        best = Some((cost, from, to));
+                    }
+                }
+
+                if let Some(best) = best {
+                    candidates.insert((a.min(b), a.max(b)), best);
+                }
+            }
+        }
+
+        let mut candidates: Vec<_> = candidates.into_values().collect();
+        candidates.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Collapse edges in order of cost. Vertices around a collapse aren't touched again until
+        // the next pass since their triangles are out of date.
+        let mut collapse_to: Vec<u32> = (0..positions.len() as u32).collect();
+        let mut touched = vec![false; positions.len()];
+        let mut index_count = indices.len();
+        let mut collapsed = false;
+
+        for (cost, from, to) in candidates {
+            if index_count <= target_index_count || cost > max_cost {
+                break;
+            }
+
+            if touched[from as usize] || touched[to as usize] {
+                continue;
+            }
+
+            if flips(&indices, &vertex_tris[from as usize], from, to, &pos) {
+                continue;
+            }
+
+            collapse_to[from as usize] = to;
+            let q = quadrics[from as usize];
+            quadrics[to as usize].add(&q);
+            worst_cost = worst_cost.max(cost);
+            collapsed = true;
+
+            for t in &vertex_tris[from as usize] {
+                let tri = &indices[t * 3..t * 3 + 3];
+                if tri.contains(&to) {
+                    index_count -= 3;
+                }
+                tri.iter().for_each(|i| touched[*i as usize] = true);
+            }
+        }
+
+        if !collapsed {
+            break;
+        }
+
+        // Remap indices and remove triangles that collapsed
+        let mut out = Vec::with_capacity(index_count);
+        for tri in indices.chunks_exact(3) {
+            let tri = [
+                collapse_to[tri[0] as usize],
+                collapse_to[tri[1] as usize],
+                collapse_to[tri[2] as usize],
+            ];
+            if tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0] {
+                out.extend_from_slice(&tri);
+            }
+        }
+        indices = out;
+    }
+
+    Simplified {
+        indices,
+        error: worst_cost.sqrt() as f32,
+    }
+}
+
+/// Checks if moving `from` to the position of `to` would flip or collapse any of the triangles
+/// using `from` that aren't removed by the collapse.
+fn flips(indices: &[u32], tris: &[usize], from: u32, to: u32, pos: &impl Fn(u32) -> DVec3) -> bool {
+    tris.iter().any(|t| {
+        let tri = &indices[t * 3..t * 3 + 3];
+        if tri.contains(&to) {
+            return false;
+        }
+
+        let (p0, p1, p2) = (pos(tri[0]), pos(tri[1]), pos(tri[2]));
+        let old = (p1 - p0).cross(p2 - p0);
+
+        let moved = [tri[0], tri[1], tri[2]].map(|i| if i == from { pos(to) } else { pos(i) });
+        let new = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+
+        old.dot(new) <= 0.0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A closed sphere with `rings` by `segments` quads, welded at the seam and poles.
+    fn sphere(rings: u32, segments: u32) -> (Vec<Vec4>, Vec<u32>) {
+        let mut positions = vec![Vec4::new(0.0, 1.0, 0.0, 1.0)];
+        for r in 1..rings {
+            let theta = r as f32 / rings as f32 * std::f32::consts::PI;
+            for s in 0..segments {
+                let phi = s as f32 / segments as f32 * std::f32::consts::TAU;
+                positions.push(Vec4::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                    1.0,
+                ));
+            }
+        }
+        positions.push(Vec4::new(0.0, -1.0, 0.0, 1.0));
+        let bottom = positions.len() as u32 - 1;
+
+        let ring = |r: u32, s: u32| 1 + (r - 1) * segments + (s % segments);
+        let mut indices = Vec::default();
+        for s in 0..segments {
+            indices.extend_from_slice(&[0, ring(1, s + 1), ring(1, s)]);
+            indices.extend_from_slice(&[bottom, ring(rings - 1, s), ring(rings - 1, s + 1)]);
+        }
+        for r in 1..rings - 1 {
+            for s in 0..segments {
+                let (a, b) = (ring(r, s), ring(r, s + 1));
+                let (c, d) = (ring(r + 1, s), ring(r + 1, s + 1));
+                indices.extend_from_slice(&[a, b, c]);
+                indices.extend_from_slice(&[b, d, c]);
+            }
+        }
+
+        (positions, indices)
+    }
+
+    #[test]
+    fn reaches_target_within_error() {
+        let (positions, indices) = sphere(32, 64);
+        let res = simplify(&positions, &indices, indices.len() / 4, 0.1);
+
+        assert!(res.indices.len() <= indices.len() / 4);
+        assert!(!res.indices.is_empty());
+        assert!(res.error > 0.0 && res.error <= 0.1);
+
+        // Every vertex of the simplified sphere is still on the sphere
+        for i in &res.indices {
+            assert!((positions[*i as usize].xyz().length() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn error_limit_stops_simplification() {
+        let (positions, indices) = sphere(32, 64);
+        let loose = simplify(&positions, &indices, 0, 0.1);
+        let tight = simplify(&positions, &indices, 0, 0.001);
+
+        assert!(tight.error <= 0.001);
+        assert!(tight.indices.len() > loose.indices.len());
+    }
+
+    #[test]
+    fn lods_get_coarser_and_switch_further_away() {
+        use crate::{mesh::MeshDataBuilder, vertex::VertexLayout};
+
+        let (positions, indices) = sphere(32, 64);
+        let data = MeshDataBuilder::new(VertexLayout::POSITION, positions.len(), indices.len())
+            .add_positions(&positions)
+            .add_indices(&indices)
+            .lod_settings(LodSettings::default())
+            .build();
+
+        let lods = data.lods();
+        assert!(lods.len() > 1 && lods.len() <= LodSettings::default().levels);
+        assert_eq!(lods[0].index_count as usize, indices.len());
+
+        for pair in lods.windows(2) {
+            let (prev, next) = (&pair[0], &pair[1]);
+            assert!(next.index_count < prev.index_count);
+            assert_eq!(
+                next.meshlet_offset,
+                prev.meshlet_offset + prev.meshlet_count
+            );
+            assert_eq!(next.index_offset, prev.index_offset + prev.index_count);
+            assert!(next.error >= prev.error);
+
+            let fov = std::f32::consts::FRAC_PI_2;
+            assert!(
+                next.switch_distance(fov, 1080.0, 1.0) >= prev.switch_distance(fov, 1080.0, 1.0)
+            );
+        }
+
+        let last = lods.last().unwrap();
+        assert_eq!(
+            (last.meshlet_offset + last.meshlet_count) as usize,
+            data.meshlet_count()
+        );
+        assert_eq!(
+            (last.index_offset + last.index_count) as usize,
+            data.index_count()
+        );
+    }
+
+    #[test]
+    fn flat_plane_simplifies_without_error() {
+        let size = 8;
+        let row = size + 1;
+        let positions: Vec<_> = (0..row * row)
+            .map(|i| Vec4::new((i % row) as f32, 0.0, (i / row) as f32, 1.0))
+            .collect();
+        let mut indices = Vec::default();
+        for z in 0..size {
+            for x in 0..size {
+                let i = z * row + x;
+                indices.extend_from_slice(&[i, i + 1, i + row, i + 1, i + row + 1, i + row]);
+            }
+        }
+
+        let res = simplify(&positions, &indices, 6, 1e-4);
+        assert!(res.indices.len() < indices.len() / 4);
+        assert!(res.error < 1e-4);
+    }
+}
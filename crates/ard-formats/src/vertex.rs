@@ -14,6 +14,8 @@ bitflags! {
         const TANGENT   = 0b0000_0100;
         const UV0       = 0b0000_1000;
         const UV1       = 0b0001_0000;
+        const JOINTS    = 0b0010_0000;
+        const WEIGHTS   = 0b0100_0000;
     }
 }
 
@@ -24,15 +26,19 @@ pub enum VertexAttribute {
     Tangent,
     Uv0,
     Uv1,
+    Joints,
+    Weights,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct VertexData {
     positions: Vec<Vec4>,
     normals: Vec<[i16; 4]>,
     tangents: Vec<[i16; 4]>,
     uv0s: Vec<[f16; 2]>,
     uv1s: Vec<[f16; 2]>,
+    joints: Vec<[u16; 4]>,
+    weights: Vec<[u16; 4]>,
     bounds: ObjectBounds,
     len: usize,
 }
@@ -69,6 +75,16 @@ impl VertexData {
             } else {
                 Vec::default()
             },
+            joints: if layout.contains(VertexLayout::JOINTS) {
+                vec![Default::default(); len]
+            } else {
+                Vec::default()
+            },
+            weights: if layout.contains(VertexLayout::WEIGHTS) {
+                vec![Default::default(); len]
+            } else {
+                Vec::default()
+            },
             bounds: ObjectBounds::default(),
             len,
         }
@@ -97,6 +113,8 @@ impl VertexData {
             VertexAttribute::Tangent => bytemuck::cast_slice(&self.tangents),
             VertexAttribute::Uv0 => bytemuck::cast_slice(&self.uv0s),
             VertexAttribute::Uv1 => bytemuck::cast_slice(&self.uv1s),
+            VertexAttribute::Joints => bytemuck::cast_slice(&self.joints),
+            VertexAttribute::Weights => bytemuck::cast_slice(&self.weights),
         }
     }
 
@@ -125,6 +143,16 @@ impl VertexData {
         &self.uv1s
     }
 
+    #[inline(always)]
+    pub fn joints(&self) -> &[[u16; 4]] {
+        &self.joints
+    }
+
+    #[inline(always)]
+    pub fn weights(&self) -> &[[u16; 4]] {
+        &self.weights
+    }
+
     #[inline(always)]
     pub fn positions_mut(&mut self) -> &mut [Vec4] {
         &mut self.positions
@@ -150,6 +178,16 @@ impl VertexData {
         &mut self.uv1s
     }
 
+    #[inline(always)]
+    pub fn joints_mut(&mut self) -> &mut [[u16; 4]] {
+        &mut self.joints
+    }
+
+    #[inline(always)]
+    pub fn weights_mut(&mut self) -> &mut [[u16; 4]] {
+        &mut self.weights
+    }
+
     #[inline(always)]
     pub fn layout(&self) -> VertexLayout {
         let mut out = VertexLayout::empty();
@@ -174,6 +212,14 @@ impl VertexData {
             out |= VertexLayout::UV1;
         }
 
+        if !self.joints.is_empty() {
+            out |= VertexLayout::JOINTS;
+        }
+
+        if !self.weights.is_empty() {
+            out |= VertexLayout::WEIGHTS;
+        }
+
         out
     }
 
@@ -201,6 +247,14 @@ impl VertexData {
             self.uv1s.push(src.uv1s[src_idx]);
         }
 
+        if !src.joints.is_empty() {
+            self.joints.push(src.joints[src_idx]);
+        }
+
+        if !src.weights.is_empty() {
+            self.weights.push(src.weights[src_idx]);
+        }
+
         self.len += 1;
     }
 
@@ -212,7 +266,7 @@ impl VertexData {
 
 impl VertexAttribute {
     // TODO: Use `std::mem::variant_count` when it comes out of nightly
-    pub const COUNT: usize = 5;
+    pub const COUNT: usize = 7;
 
     pub const fn size(&self) -> usize {
         match self {
@@ -221,6 +275,8 @@ impl VertexAttribute {
             VertexAttribute::Tangent => std::mem::size_of::<i16>() * 4,
             VertexAttribute::Uv0 => std::mem::size_of::<f16>() * 2,
             VertexAttribute::Uv1 => std::mem::size_of::<f16>() * 2,
+            VertexAttribute::Joints => std::mem::size_of::<u16>() * 4,
+            VertexAttribute::Weights => std::mem::size_of::<u16>() * 4,
         }
     }
 
@@ -230,6 +286,9 @@ impl VertexAttribute {
 }
 
 impl VertexLayout {
+    /// Attributes needed to skin a mesh.
+    pub const SKIN: VertexLayout = VertexLayout::JOINTS.union(VertexLayout::WEIGHTS);
+
     /// Returns `true` if this vertex layout contains a subset of the vertex components of `other`.
     #[inline(always)]
     pub fn subset_of(&self, other: VertexLayout) -> bool {
@@ -245,6 +304,8 @@ impl From<VertexAttribute> for VertexLayout {
             VertexAttribute::Tangent => VertexLayout::TANGENT,
             VertexAttribute::Uv0 => VertexLayout::UV0,
             VertexAttribute::Uv1 => VertexLayout::UV1,
+            VertexAttribute::Joints => VertexLayout::JOINTS,
+            VertexAttribute::Weights => VertexLayout::WEIGHTS,
         }
     }
 }
@@ -259,6 +320,8 @@ impl TryFrom<VertexLayout> for VertexAttribute {
             VertexLayout::TANGENT => Ok(VertexAttribute::Tangent),
             VertexLayout::UV0 => Ok(VertexAttribute::Uv0),
             VertexLayout::UV1 => Ok(VertexAttribute::Uv1),
+            VertexLayout::JOINTS => Ok(VertexAttribute::Joints),
+            VertexLayout::WEIGHTS => Ok(VertexAttribute::Weights),
             _ => Err(VertexLayoutToAttributeError),
         }
     }
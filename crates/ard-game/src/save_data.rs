@@ -9,6 +9,7 @@ use ard_render_base::RenderingMode;
 use ard_render_material::material_instance::MaterialInstance;
 use ard_render_meshes::mesh::Mesh;
 use ard_render_objects::{PrevFrameModel, RenderFlags};
+use ard_render_particles::ParticleEmitter;
 use ard_save_load::{
     format::SaveFormat,
     load_data::Loader,
@@ -54,6 +55,7 @@ impl SceneAsset {
             .include_component::<RenderFlags>()
             .include_component::<MeshHandle>()
             .include_component::<MaterialHandle>()
+            .include_component::<ParticleEmitter>()
             .include_component::<Name>()
             .include_component::<MarkStatic>()
             .include_component::<Collider>()
@@ -82,6 +84,7 @@ impl SceneAsset {
             .load_component::<RenderFlags>()
             .load_component::<MeshHandle>()
             .load_component::<MaterialHandle>()
+            .load_component::<ParticleEmitter>()
             .load_component::<Name>()
             .load_component::<MarkStatic>()
             .load_component::<Collider>()
@@ -0,0 +1,199 @@
+//! Measures peak heap usage while reading every texture out of a large GLB.
+//!
+//! The model size in megabytes can be set with `ARD_GLTF_BENCH_MB` (defaults to 512). Use 2048 to
+//! reproduce importing a 2GB model.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use ard_gltf::GltfModel;
+
+/// Tracks the current and peak number of bytes allocated.
+struct PeakAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+const IMAGE_SIZE: usize = 8 * 1024 * 1024;
+
+const PNG_MAGIC: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// Writes a GLB containing `image_count` embedded images to disk.
+fn write_glb(path: &std::path::Path, image_count: usize) {
+    let images: Vec<_> = (0..image_count)
+        .map(|i| format!(r#"{{ "bufferView": {i}, "mimeType": "image/png" }}"#))
+        .collect();
+    let views: Vec<_> = (0..image_count)
+        .map(|i| {
+            format!(
+                r#"{{ "buffer": 0, "byteOffset": {}, "byteLength": {IMAGE_SIZE} }}"#,
+                i * IMAGE_SIZE
+            )
+        })
+        .collect();
+    let textures: Vec<_> = (0..image_count)
+        .map(|i| format!(r#"{{ "source": {i} }}"#))
+        .collect();
+    let materials: Vec<_> = (0..image_count)
+        .map(|i| {
+            format!(r#"{{ "pbrMetallicRoughness": {{ "baseColorTexture": {{ "index": {i} }} }} }}"#)
+        })
+        .collect();
+    let nodes: Vec<_> = (0..image_count).map(|_| r#"{ "mesh": 0 }"#).collect();
+    let primitives: Vec<_> = (0..image_count)
+        .map(|i| {
+            format!(r#"{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": {i} }}"#)
+        })
+        .collect();
+
+    let bin_len = image_count * IMAGE_SIZE + 44;
+    let mut json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [{}] }}],
+            "nodes": [{}],
+            "meshes": [{{ "primitives": [{}] }}],
+            "materials": [{}],
+            "textures": [{}],
+            "images": [{}],
+            "accessors": [{{
+                "bufferView": {image_count},
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "min": [0.0, 0.0, 0.0],
+                "max": [1.0, 1.0, 0.0]
+            }}, {{
+                "bufferView": {},
+                "componentType": 5123,
+                "count": 3,
+                "type": "SCALAR"
+            }}],
+            "buffers": [{{ "byteLength": {bin_len} }}],
+            "bufferViews": [
+                {},
+                {{ "buffer": 0, "byteOffset": {}, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": {}, "byteLength": 6 }}
+            ]
+        }}"#,
+        (0..image_count)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+        nodes.join(","),
+        primitives.join(","),
+        materials.join(","),
+        textures.join(","),
+        images.join(","),
+        image_count + 1,
+        views.join(","),
+        image_count * IMAGE_SIZE,
+        image_count * IMAGE_SIZE + 36,
+    );
+    while json.len() % 4 != 0 {
+        json.push(' ');
+    }
+
+    // Write the file in pieces so the benchmark doesn't start with the whole model in memory
+    use std::io::Write;
+    let mut f = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+    let total = 12 + 8 + json.len() + 8 + bin_len;
+    f.write_all(b"glTF").unwrap();
+    f.write_all(&2u32.to_le_bytes()).unwrap();
+    f.write_all(&(total as u32).to_le_bytes()).unwrap();
+    f.write_all(&(json.len() as u32).to_le_bytes()).unwrap();
+    f.write_all(b"JSON").unwrap();
+    f.write_all(json.as_bytes()).unwrap();
+    f.write_all(&(bin_len as u32).to_le_bytes()).unwrap();
+    f.write_all(b"BIN\0").unwrap();
+
+    let mut image = vec![0u8; IMAGE_SIZE];
+    image[..PNG_MAGIC.len()].copy_from_slice(PNG_MAGIC);
+    for _ in 0..image_count {
+        f.write_all(&image).unwrap();
+    }
+    for v in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+        f.write_all(bytemuck::cast_slice(&v)).unwrap();
+    }
+    f.write_all(bytemuck::cast_slice(&[0u16, 1, 2, 0])).unwrap();
+}
+
+/// Stand in for decoding an image, which needs about four times the encoded size.
+fn decode(data: &[u8]) -> usize {
+    let decoded = vec![1u8; data.len() * 4];
+    decoded.iter().step_by(4096).map(|v| *v as usize).sum()
+}
+
+fn measure(name: &str, f: impl FnOnce()) {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    let start = Instant::now();
+    f();
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    println!(
+        "{name:<24} peak heap {:>8.1} MB  {:>8.2?}",
+        peak as f64 / (1024.0 * 1024.0),
+        start.elapsed()
+    );
+}
+
+fn main() {
+    let size_mb: usize = std::env::var("ARD_GLTF_BENCH_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(512);
+    let image_count = (size_mb * 1024 * 1024 / IMAGE_SIZE).max(1);
+
+    let path = std::env::temp_dir().join(format!("ard-gltf-bench-{}.glb", std::process::id()));
+    write_glb(&path, image_count);
+    println!("{image_count} textures, {size_mb} MB");
+
+    // Every texture copied out of the model before decoding, which is how textures used to be
+    // loaded.
+    measure("copy all, then decode", || {
+        let model = GltfModel::from_gltf(&path).unwrap();
+        let copies: Vec<_> = (0..model.textures.len())
+            .map(|i| model.texture_data(i).unwrap().into_owned())
+            .collect();
+        std::mem::drop(model);
+        let total: usize = copies.iter().map(|data| decode(data)).sum();
+        assert!(total > 0);
+    });
+
+    for max_in_flight in [1, 4] {
+        measure(&format!("streaming ({max_in_flight} in flight)"), || {
+            let model = GltfModel::from_gltf(&path).unwrap();
+            let total = AtomicUsize::new(0);
+            model
+                .for_each_texture(max_in_flight, |_, _, data| {
+                    total.fetch_add(decode(data), Ordering::Relaxed);
+                })
+                .unwrap();
+            assert!(total.into_inner() > 0);
+        });
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
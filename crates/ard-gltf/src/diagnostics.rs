@@ -0,0 +1,153 @@
+use std::{ops::Deref, sync::Mutex};
+
+use thiserror::Error;
+
+use crate::TextureUsage;
+
+/// Problems found while importing a model, sorted so they are in the same order every time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GltfImportDiagnostics(Vec<GltfImportWarning>);
+
+/// A problem found while importing a model that didn't stop the import. The affected item is
+/// either skipped or imported as well as possible.
+///
+/// Meshes and primitives are identified by their index in the source GLTF file, and textures,
+/// skins, and animations by their GLTF index.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Error)]
+pub enum GltfImportWarning {
+    #[error("primitive {primitive} of mesh {mesh} has no indices")]
+    MissingIndices { mesh: usize, primitive: usize },
+    #[error("primitive {primitive} of mesh {mesh} has no positions")]
+    MissingPositions { mesh: usize, primitive: usize },
+    #[error("primitive {primitive} of mesh {mesh} has no material")]
+    MissingMaterial { mesh: usize, primitive: usize },
+    #[error("unable to load `{attribute}` of primitive {primitive} of mesh {mesh}: {err}")]
+    InvalidAttribute {
+        mesh: usize,
+        primitive: usize,
+        attribute: &'static str,
+        err: AccessorError,
+    },
+    #[error("primitive {primitive} of mesh {mesh} has {count} positions that aren't finite")]
+    NonFinitePositions {
+        mesh: usize,
+        primitive: usize,
+        count: usize,
+    },
+    #[error("primitive {primitive} of mesh {mesh} has {count} degenerate triangles")]
+    DegenerateTriangles {
+        mesh: usize,
+        primitive: usize,
+        count: usize,
+    },
+    #[error(
+        "primitive {primitive} of mesh {mesh} has {count} indices past the end of its \
+        {vertex_count} vertices"
+    )]
+    IndicesOutOfRange {
+        mesh: usize,
+        primitive: usize,
+        count: usize,
+        vertex_count: usize,
+    },
+    #[error("primitive {primitive} of mesh {mesh} has unsupported index type `{component_type}`")]
+    UnsupportedIndexType {
+        mesh: usize,
+        primitive: usize,
+        component_type: u32,
+    },
+    #[error("texture {texture} was used as `{old:?}` but is now used as `{new:?}`")]
+    TextureUsageConflict {
+        texture: usize,
+        old: TextureUsage,
+        new: TextureUsage,
+    },
+    /// The image is stored in a buffer view with a stride, which isn't supported.
+    #[error("texture {0} is using stride")]
+    UnsupportedTextureSource(usize),
+    #[error("texture {0} has no source")]
+    MissingTextureSource(usize),
+    #[error("texture {0} has an unknown source format")]
+    UnknownTextureFormat(usize),
+    #[error("skin {skin} has {matrices} inverse bind matrices but {joints} joints")]
+    MismatchedInverseBindMatrices {
+        skin: usize,
+        matrices: usize,
+        joints: usize,
+    },
+    #[error("unable to load channel {channel} of animation {animation}: {err}")]
+    InvalidAnimationChannel {
+        animation: usize,
+        channel: usize,
+        err: AccessorError,
+    },
+    #[error("channel {channel} of animation {animation} has mismatched keyframe counts")]
+    MismatchedKeyframes { animation: usize, channel: usize },
+}
+
+/// Reasons the data referenced by an accessor couldn't be read. Component types are the values
+/// used in GLTF files, such as `5126` for floats.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Error)]
+pub enum AccessorError {
+    #[error("expected component type `{expected}` but got `{found}`")]
+    UnexpectedComponentType { expected: u32, found: u32 },
+    #[error("component type `{0}` is not supported")]
+    UnsupportedComponentType(u32),
+    #[error("elements are bigger than the requested type")]
+    ElementTooLarge,
+    #[error("reads past the end of buffer {0}")]
+    OutOfBounds(usize),
+}
+
+impl GltfImportDiagnostics {
+    #[inline(always)]
+    pub fn entries(&self) -> &[GltfImportWarning] {
+        &self.0
+    }
+}
+
+impl Deref for GltfImportDiagnostics {
+    type Target = [GltfImportWarning];
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a GltfImportDiagnostics {
+    type Item = &'a GltfImportWarning;
+    type IntoIter = std::slice::Iter<'a, GltfImportWarning>;
+
+    #[inline(always)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Collects warnings from loaders running in parallel.
+#[derive(Default)]
+pub(crate) struct Warnings(Mutex<Vec<GltfImportWarning>>);
+
+impl Warnings {
+    #[inline]
+    pub fn push(&self, warning: GltfImportWarning) {
+        self.0.lock().unwrap().push(warning);
+    }
+
+    /// Gets every warning in a consistent order, regardless of which order they were found in.
+    pub fn into_diagnostics(self) -> GltfImportDiagnostics {
+        Self::sorted(self.0.into_inner().unwrap())
+    }
+
+    /// Same as [`Warnings::into_diagnostics`], but for warnings that are still being collected.
+    pub fn diagnostics(&self) -> GltfImportDiagnostics {
+        Self::sorted(self.0.lock().unwrap().clone())
+    }
+
+    fn sorted(mut warnings: Vec<GltfImportWarning>) -> GltfImportDiagnostics {
+        warnings.sort();
+        warnings.dedup();
+        GltfImportDiagnostics(warnings)
+    }
+}
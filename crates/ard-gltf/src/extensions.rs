@@ -0,0 +1,81 @@
+//! Extension data the `gltf` crate doesn't expose. This is read directly from the JSON chunk and
+//! only mirrors the parts of the document we care about. Anything else, including unknown
+//! extensions, is ignored.
+use serde::Deserialize;
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct Root {
+    pub materials: Vec<Material>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct Material {
+    pub pbr_metallic_roughness: PbrMetallicRoughness,
+    pub normal_texture: Option<TextureInfo>,
+    pub occlusion_texture: Option<TextureInfo>,
+    pub emissive_texture: Option<TextureInfo>,
+    pub extensions: MaterialExtensions,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct PbrMetallicRoughness {
+    pub base_color_texture: Option<TextureInfo>,
+    pub metallic_roughness_texture: Option<TextureInfo>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct MaterialExtensions {
+    #[serde(rename = "KHR_materials_emissive_strength")]
+    pub emissive_strength: Option<EmissiveStrength>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct EmissiveStrength {
+    pub emissive_strength: f32,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct TextureInfo {
+    pub extensions: TextureInfoExtensions,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct TextureInfoExtensions {
+    #[serde(rename = "KHR_texture_transform")]
+    pub texture_transform: Option<TextureTransform>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct TextureTransform {
+    pub offset: [f32; 2],
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    pub tex_coord: Option<u32>,
+}
+
+impl Default for EmissiveStrength {
+    fn default() -> Self {
+        Self {
+            emissive_strength: 1.0,
+        }
+    }
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0; 2],
+            rotation: 0.0,
+            scale: [1.0; 2],
+            tex_coord: None,
+        }
+    }
+}
@@ -1,25 +1,71 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, ops::Range, path::Path};
 
-use ard_math::{Mat4, Quat, Vec2, Vec3, Vec4};
+use ard_math::{Mat3, Mat4, Quat, UVec4, Vec2, Vec3, Vec4};
 use ard_pal::prelude::{Filter, Format, SamplerAddressMode};
+use base64::Engine;
 use bytemuck::{Pod, Zeroable};
-use gltf::{json::extensions::scene::khr_lights_punctual, Glb, Gltf};
+use gltf::{json::extensions::scene::khr_lights_punctual, Document, Glb};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use thiserror::Error;
 
+mod diagnostics;
+mod extensions;
+mod mesh;
+mod stream;
+
+use diagnostics::Warnings;
+pub use diagnostics::{AccessorError, GltfImportDiagnostics, GltfImportWarning};
+pub use stream::{GltfImportItem, GltfImportStream, ImportProgress, ImportProgressHandle};
+
+#[cfg(test)]
+mod tests;
+
 pub struct GltfModel {
     pub lights: Vec<GltfLight>,
     pub textures: Vec<GltfTexture>,
     pub materials: Vec<GltfMaterial>,
     pub mesh_groups: Vec<GltfMeshGroup>,
     pub meshes: Vec<GltfMesh>,
+    pub skins: Vec<GltfSkin>,
+    pub animations: Vec<GltfAnimation>,
     pub roots: Vec<GltfNode>,
+    diagnostics: GltfImportDiagnostics,
+    /// Buffers are kept around so texture data can be read from them on demand.
+    buffers: Vec<BufferData>,
+    loader: Box<Loader>,
 }
 
+/// Optional processing done while importing a model.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct GltfImportOptions {
+    /// Generate tangents for meshes that have normals and UVs but no tangents. See
+    /// [`GltfMesh::generate_tangents`].
+    pub generate_tangents: bool,
+}
+
+/// Loads external files referenced by a model given their percent-decoded URI.
+type Loader = dyn Fn(&str) -> std::io::Result<Vec<u8>> + Send + Sync;
+
 #[derive(Debug, Error)]
 pub enum GltfModelParseError {
     #[error("glb parsing error")]
     ParseError,
+    #[error("buffer {0} has no uri and there is no binary chunk")]
+    MissingBuffer(usize),
+    #[error("buffer {buffer} is {len} bytes but should be {expected} bytes")]
+    BufferTooSmall {
+        buffer: usize,
+        len: usize,
+        expected: usize,
+    },
+    #[error("buffer view {0} is out of bounds")]
+    ViewOutOfBounds(usize),
+    #[error("accessor {0} is sparse, which is not supported")]
+    SparseAccessor(usize),
+    #[error("invalid uri `{0}`")]
+    InvalidUri(String),
+    #[error("unable to load `{uri}`: {err}")]
+    Io { uri: String, err: std::io::Error },
 }
 
 pub enum GltfMaterial {
@@ -28,13 +74,39 @@ pub enum GltfMaterial {
         metallic: f32,
         roughness: f32,
         alpha_cutoff: f32,
-        diffuse_map: Option<usize>,
-        normal_map: Option<usize>,
-        metallic_roughness_map: Option<usize>,
+        diffuse_map: Option<GltfTextureRef>,
+        normal_map: Option<GltfTextureRef>,
+        metallic_roughness_map: Option<GltfTextureRef>,
+        /// Emissive color, already scaled by `KHR_materials_emissive_strength` if present.
+        emissive_factor: Vec3,
+        emissive_map: Option<GltfTextureRef>,
+        occlusion_strength: f32,
+        occlusion_map: Option<GltfTextureRef>,
         blending: BlendType,
     },
 }
 
+/// A reference from a material to a texture.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GltfTextureRef {
+    /// Index of the texture in the model.
+    pub texture: usize,
+    /// How UVs are transformed before sampling the texture.
+    pub transform: GltfTextureTransform,
+}
+
+/// UV transform from `KHR_texture_transform`. Defaults to the identity transform when the
+/// extension isn't used.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GltfTextureTransform {
+    pub offset: Vec2,
+    /// Rotation in radians counter-clockwise around the UV origin.
+    pub rotation: f32,
+    pub scale: Vec2,
+    /// Overrides which UV set the texture uses.
+    pub tex_coord: Option<u32>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BlendType {
     Opaque,
@@ -62,11 +134,20 @@ pub enum TextureUsage {
     ///
     /// This maps to the `TextureFormat::Rg8Unorm` format.
     MetallicRoughness,
+    /// Texture is used as an emissive color map.
+    ///
+    /// This maps to the `TextureFormat::Rgba8Srgb` format.
+    Emissive,
+    /// Texture is used as an ambient occlusion map. Occlusion is read from the red channel, so
+    /// this is often packed with the metallic roughness map.
+    ///
+    /// This maps to the `TextureFormat::Rgba8Unorm` format.
+    Occlusion,
 }
 
 pub struct GltfTexture {
-    /// Raw image data.
-    pub data: Vec<u8>,
+    /// Where the encoded image data is stored. Use [`GltfModel::texture_data`] to read it.
+    pub source: GltfImageSource,
     /// How to interpret the image data.
     pub src_format: TextureSourceFormat,
     /// What this image is used for in the model.
@@ -77,6 +158,22 @@ pub struct GltfTexture {
     pub mips: bool,
 }
 
+/// Location of the encoded data of a texture. Image data is not copied out of the model when it is
+/// loaded, so large models don't need to hold every image in memory twice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GltfImageSource {
+    /// Byte range within one of the model's buffers.
+    Buffer {
+        buffer: usize,
+        offset: usize,
+        len: usize,
+    },
+    /// External file or data URI, which is loaded when the data is requested.
+    Uri(String),
+    /// The image couldn't be resolved, so it has no data.
+    Missing,
+}
+
 pub struct GltfSampler {
     pub min_filter: Filter,
     pub mag_filter: Filter,
@@ -96,6 +193,10 @@ pub struct GltfMesh {
     pub uv1: Option<Vec<Vec2>>,
     pub uv2: Option<Vec<Vec2>>,
     pub uv3: Option<Vec<Vec2>>,
+    /// Indices into the joints of the skin used with this mesh.
+    pub joints: Option<Vec<UVec4>>,
+    /// How much each joint influences the vertex.
+    pub weights: Option<Vec<Vec4>>,
 }
 
 pub struct GltfMeshInstance {
@@ -124,9 +225,55 @@ pub enum GltfLight {
     },
 }
 
+/// Index of a node within the source GLTF file. Matches [`GltfNode::index`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeIndex(pub usize);
+
+pub struct GltfSkin {
+    /// Nodes used as joints by this skin.
+    pub joints: Vec<NodeIndex>,
+    /// Matrices that transform meshes into the local space of each joint. There is one per joint.
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+pub struct GltfAnimation {
+    pub name: String,
+    pub channels: Vec<GltfAnimationChannel>,
+}
+
+pub struct GltfAnimationChannel {
+    /// The node being animated.
+    pub target: NodeIndex,
+    /// How to interpolate between keyframes.
+    pub interpolation: GltfInterpolation,
+    /// Time of each keyframe in seconds.
+    pub times: Vec<f32>,
+    /// Keyframe values. With [`GltfInterpolation::CubicSpline`], each keyframe has three values
+    /// in the order in-tangent, value, out-tangent.
+    pub values: GltfAnimationValues,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GltfInterpolation {
+    Step,
+    Linear,
+    CubicSpline,
+}
+
+/// Keyframe values for the property being animated.
+pub enum GltfAnimationValues {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// Morph target weights. Each keyframe has one weight per morph target.
+    Weights(Vec<f32>),
+}
+
 pub struct GltfNode {
     /// The name of this node.
     pub name: String,
+    /// Index of this node in the source file so it can be referenced by skins.
+    pub index: NodeIndex,
     /// Model matrix for this node in local space.
     pub model: Mat4,
     /// Data contained within this node.
@@ -138,12 +285,14 @@ pub struct GltfNode {
 pub enum GltfNodeData {
     Empty,
     MeshGroup(usize),
+    SkinnedMeshGroup { mesh_group: usize, skin: usize },
     Light(usize),
 }
 
 #[derive(Clone, Default)]
 struct DataMapping {
     lights: HashMap<usize, usize>,
+    skins: HashMap<usize, usize>,
     mesh_groups: HashMap<usize, usize>,
     textures: HashMap<usize, (usize, TextureUsage)>,
     materials: HashMap<usize, usize>,
@@ -152,6 +301,7 @@ struct DataMapping {
 #[derive(Clone, Default)]
 struct InvDataMapping {
     lights: HashMap<usize, usize>,
+    skins: HashMap<usize, usize>,
     mesh_groups: HashMap<usize, usize>,
     /// Maps accessor index to associated primitives.
     meshes: HashMap<Accessor, Vec<Primitive>>,
@@ -166,6 +316,8 @@ struct InvDataMapping {
 #[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Primitive {
     mesh_idx: usize,
+    /// GLTF mesh and primitive index this primitive was first found in.
+    source: (usize, usize),
     indices: Accessor,
     positions: Accessor,
     normals: Option<Accessor>,
@@ -175,6 +327,8 @@ struct Primitive {
     uv1s: Option<Accessor>,
     uv2s: Option<Accessor>,
     uv3s: Option<Accessor>,
+    joints: Option<Accessor>,
+    weights: Option<Accessor>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -188,27 +342,234 @@ struct Accessor {
 }
 
 impl GltfModel {
+    /// Loads a self contained model. Any external files referenced by the model will fail to load,
+    /// so this is meant for GLB files or GLTF files using data URIs.
+    #[inline]
     pub fn from_slice(data: &[u8]) -> Result<Self, GltfModelParseError> {
-        // Load as GLB
-        let glb = Glb::from_slice(data)?;
-        let bin = glb.bin.unwrap().into_owned();
-        let gltf = Gltf::from_slice(&glb.json)?;
+        Self::from_slice_with_options(data, GltfImportOptions::default())
+    }
+
+    /// Same as [`GltfModel::from_slice`], but with optional processing.
+    #[inline]
+    pub fn from_slice_with_options(
+        data: &[u8],
+        options: GltfImportOptions,
+    ) -> Result<Self, GltfModelParseError> {
+        Self::from_vec_with_options(
+            data.to_vec(),
+            |_| {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "external files are not supported when loading from a slice",
+                ))
+            },
+            options,
+        )
+    }
+
+    /// Loads a GLB or GLTF file from disk. External files are resolved relative to the directory
+    /// containing the model.
+    #[inline]
+    pub fn from_gltf(path: &Path) -> Result<Self, GltfModelParseError> {
+        Self::from_gltf_with_options(path, GltfImportOptions::default())
+    }
+
+    /// Same as [`GltfModel::from_gltf`], but with optional processing.
+    pub fn from_gltf_with_options(
+        path: &Path,
+        options: GltfImportOptions,
+    ) -> Result<Self, GltfModelParseError> {
+        let data = std::fs::read(path).map_err(|err| GltfModelParseError::Io {
+            uri: path.display().to_string(),
+            err,
+        })?;
+        let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self::from_vec_with_options(data, move |uri| std::fs::read(root.join(uri)), options)
+    }
+
+    /// Loads a GLB or GLTF model. `loader` is called with the percent-decoded URI of every
+    /// external buffer and image the model references. Data URIs are decoded directly.
+    #[inline]
+    pub fn from_slice_with_loader(
+        data: &[u8],
+        loader: impl Fn(&str) -> std::io::Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Result<Self, GltfModelParseError> {
+        Self::from_vec_with_options(data.to_vec(), loader, GltfImportOptions::default())
+    }
+
+    /// Same as [`GltfModel::from_slice_with_loader`], but takes ownership of the data so the
+    /// binary chunk of a GLB file can be used without copying it.
+    #[inline]
+    pub fn from_vec_with_loader(
+        data: Vec<u8>,
+        loader: impl Fn(&str) -> std::io::Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Result<Self, GltfModelParseError> {
+        Self::from_vec_with_options(data, loader, GltfImportOptions::default())
+    }
+
+    /// Same as [`GltfModel::from_vec_with_loader`], but with optional processing.
+    pub fn from_vec_with_options(
+        data: Vec<u8>,
+        loader: impl Fn(&str) -> std::io::Result<Vec<u8>> + Send + Sync + 'static,
+        options: GltfImportOptions,
+    ) -> Result<Self, GltfModelParseError> {
+        let parsed = ParsedModel::parse(data, Box::new(loader))?;
+        let ParsedModel {
+            gltf: gltf_doc,
+            extensions,
+            buffers,
+            loader,
+            mapping,
+            inv_mapping,
+            roots,
+            warnings,
+        } = parsed;
+        let animations = load_gltf_animations(&gltf_doc, &buffers, &warnings)?;
+
+        // Construct all resources
+        let ((lights, skins), (textures, (materials, (meshes, mesh_groups)))) = rayon::join(
+            || {
+                (
+                    load_gltf_lights(&gltf_doc, &mapping),
+                    load_gltf_skins(&gltf_doc, &mapping, &buffers, &warnings),
+                )
+            },
+            || {
+                rayon::join(
+                    || load_gltf_textures(&gltf_doc, &mapping, &buffers, &*loader, &warnings),
+                    || {
+                        rayon::join(
+                            || load_gltf_materials(&gltf_doc, &extensions, &mapping, &inv_mapping),
+                            || {
+                                rayon::join(
+                                    || load_gltf_meshes(&inv_mapping, &buffers, options, &warnings),
+                                    || {
+                                        load_gltf_mesh_groups(
+                                            &gltf_doc,
+                                            &mapping,
+                                            &inv_mapping,
+                                            &warnings,
+                                        )
+                                    },
+                                )
+                            },
+                        )
+                    },
+                )
+            },
+        );
+
+        Ok(GltfModel {
+            lights,
+            textures: textures?,
+            materials,
+            mesh_groups,
+            meshes,
+            skins,
+            animations,
+            roots,
+            diagnostics: warnings.into_diagnostics(),
+            buffers,
+            loader,
+        })
+    }
+
+    /// Problems found while importing the model.
+    #[inline(always)]
+    pub fn diagnostics(&self) -> &GltfImportDiagnostics {
+        &self.diagnostics
+    }
+
+    /// Gets the encoded data of a texture. Images embedded in the model are borrowed from its
+    /// buffers, and external images are loaded on demand.
+    #[inline]
+    pub fn texture_data(&self, texture: usize) -> Result<Cow<'_, [u8]>, GltfModelParseError> {
+        read_image_source(&self.textures[texture].source, &self.buffers, &*self.loader)
+    }
+
+    /// Calls `f` with the encoded data of every texture. Textures are processed in parallel on a
+    /// pool of `max_in_flight` threads, so only that many images need to be loaded and decoded at
+    /// once. Stops at the first texture that fails to load.
+    pub fn for_each_texture(
+        &self,
+        max_in_flight: usize,
+        f: impl Fn(usize, &GltfTexture, &[u8]) + Send + Sync,
+    ) -> Result<(), GltfModelParseError> {
+        use rayon::prelude::*;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(max_in_flight.max(1))
+            .thread_name(|i| format!("gltf texture {i}"))
+            .build()
+            .expect("unable to create texture thread pool");
+
+        pool.install(|| {
+            (0..self.textures.len()).into_par_iter().try_for_each(|i| {
+                let data = self.texture_data(i)?;
+                f(i, &self.textures[i], &data);
+                Ok(())
+            })
+        })
+    }
+}
+
+/// A model whose node graph has been walked to find which resources are used, but that hasn't
+/// had any of those resources loaded yet.
+struct ParsedModel {
+    gltf: gltf::json::Root,
+    extensions: extensions::Root,
+    buffers: Vec<BufferData>,
+    loader: Box<Loader>,
+    mapping: DataMapping,
+    inv_mapping: InvDataMapping,
+    roots: Vec<GltfNode>,
+    warnings: Warnings,
+}
+
+impl ParsedModel {
+    fn parse(data: Vec<u8>, loader: Box<Loader>) -> Result<Self, GltfModelParseError> {
+        // Split GLB files into their JSON and binary chunks. The binary chunk stays in place
+        let (json, blob) = if data.starts_with(b"glTF") {
+            let glb = Glb::from_slice(&data)?;
+            let range = glb.bin.map(|bin| {
+                let start = bin.as_ptr() as usize - data.as_ptr() as usize;
+                start..(start + bin.len())
+            });
+            let json = glb.json.into_owned();
+            (json, range.map(|range| BufferData { bytes: data, range }))
+        } else {
+            (data, None)
+        };
+
+        let gltf_doc =
+            gltf::json::Root::from_slice(&json).map_err(|_| GltfModelParseError::ParseError)?;
+        let gltf_doc = Document::from_json(gltf_doc)?.into_json();
+        let extensions = parse_extensions(&json)?;
+        std::mem::drop(json);
+
+        let warnings = Warnings::default();
+        let buffers = load_gltf_buffers(&gltf_doc, blob, &*loader)?;
 
         // Mappings from GLTF item indices to our own internal ones
         let mut inv_mapping = InvDataMapping::default();
 
         // Determine what resources are actually used and also construct the scene graph
-        let gltf_doc = gltf.document.into_json();
         let mut roots = Vec::default();
         for scene in &gltf_doc.scenes {
             for node in &scene.nodes {
-                roots.push(parse_node(node.value(), &gltf_doc, &mut inv_mapping));
+                roots.push(parse_node(
+                    node.value(),
+                    &gltf_doc,
+                    &mut inv_mapping,
+                    &warnings,
+                ));
             }
         }
 
         // Clone and remap from gltf indices -> our indices to our indices -> gltf indices
         let mut mapping = DataMapping::default();
         mapping.lights = inv_mapping.lights.iter().map(|(i, j)| (*j, *i)).collect();
+        mapping.skins = inv_mapping.skins.iter().map(|(i, j)| (*j, *i)).collect();
         mapping.mesh_groups = inv_mapping
             .mesh_groups
             .iter()
@@ -225,34 +586,15 @@ impl GltfModel {
             .map(|(i, j)| (*j, *i))
             .collect();
 
-        // Construct all resources
-        let (lights, (textures, (materials, (meshes, mesh_groups)))) = rayon::join(
-            || load_gltf_lights(&gltf_doc, &mapping),
-            || {
-                rayon::join(
-                    || load_gltf_textures(&gltf_doc, &mapping, &bin),
-                    || {
-                        rayon::join(
-                            || load_gltf_materials(&gltf_doc, &mapping, &inv_mapping),
-                            || {
-                                rayon::join(
-                                    || load_gltf_meshes(&gltf_doc, &inv_mapping, &bin),
-                                    || load_gltf_mesh_groups(&gltf_doc, &mapping, &inv_mapping),
-                                )
-                            },
-                        )
-                    },
-                )
-            },
-        );
-
-        Ok(GltfModel {
-            lights,
-            textures,
-            materials,
-            mesh_groups,
-            meshes,
+        Ok(ParsedModel {
+            gltf: gltf_doc,
+            extensions,
+            buffers,
+            loader,
+            mapping,
+            inv_mapping,
             roots,
+            warnings,
         })
     }
 }
@@ -264,6 +606,8 @@ impl TextureUsage {
             TextureUsage::Diffuse => Format::Rgba8Srgb,
             TextureUsage::Normal => Format::Rgba8Unorm,
             TextureUsage::MetallicRoughness => Format::Rg8Unorm,
+            TextureUsage::Emissive => Format::Rgba8Srgb,
+            TextureUsage::Occlusion => Format::Rgba8Unorm,
         }
     }
 
@@ -273,6 +617,8 @@ impl TextureUsage {
             TextureUsage::Diffuse => Format::BC7Srgb,
             TextureUsage::Normal => Format::BC7Unorm,
             TextureUsage::MetallicRoughness => Format::BC7Unorm,
+            TextureUsage::Emissive => Format::BC7Srgb,
+            TextureUsage::Occlusion => Format::BC7Unorm,
         }
     }
 }
@@ -283,6 +629,65 @@ impl From<gltf::Error> for GltfModelParseError {
     }
 }
 
+impl GltfMaterial {
+    /// The UV transform to apply to every texture of the material. Only one transform is
+    /// supported per material, so this prefers the diffuse map and otherwise uses the first map
+    /// that is present.
+    pub fn uv_transform(&self) -> Mat3 {
+        match self {
+            GltfMaterial::Pbr {
+                diffuse_map,
+                normal_map,
+                metallic_roughness_map,
+                emissive_map,
+                occlusion_map,
+                ..
+            } => [
+                diffuse_map,
+                normal_map,
+                metallic_roughness_map,
+                emissive_map,
+                occlusion_map,
+            ]
+            .into_iter()
+            .find_map(|map| map.as_ref())
+            .map(|map| map.transform.to_mat3())
+            .unwrap_or(Mat3::IDENTITY),
+        }
+    }
+}
+
+impl GltfTextureTransform {
+    /// Matrix that transforms UVs as described by `KHR_texture_transform`.
+    pub fn to_mat3(&self) -> Mat3 {
+        Mat3::from_translation(self.offset)
+            * Mat3::from_angle(-self.rotation)
+            * Mat3::from_scale(self.scale)
+    }
+}
+
+impl Default for GltfTextureTransform {
+    fn default() -> Self {
+        GltfTextureTransform {
+            offset: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            tex_coord: None,
+        }
+    }
+}
+
+impl From<&extensions::TextureTransform> for GltfTextureTransform {
+    fn from(value: &extensions::TextureTransform) -> Self {
+        GltfTextureTransform {
+            offset: Vec2::from_array(value.offset),
+            rotation: value.rotation,
+            scale: Vec2::from_array(value.scale),
+            tex_coord: value.tex_coord,
+        }
+    }
+}
+
 impl Default for GltfSampler {
     fn default() -> Self {
         GltfSampler {
@@ -370,6 +775,22 @@ impl Primitive {
             return false;
         }
 
+        if !match (self.joints, other.joints) {
+            (Some(l), Some(r)) => l == r,
+            (None, Some(_)) | (None, None) => true,
+            (Some(_), None) => false,
+        } {
+            return false;
+        }
+
+        if !match (self.weights, other.weights) {
+            (Some(l), Some(r)) => l == r,
+            (None, Some(_)) | (None, None) => true,
+            (Some(_), None) => false,
+        } {
+            return false;
+        }
+
         true
     }
 
@@ -394,6 +815,8 @@ impl Primitive {
             && self.uv1s == other.uv1s
             && self.uv2s == other.uv2s
             && self.uv3s == other.uv3s
+            && self.joints == other.joints
+            && self.weights == other.weights
         {
             return false;
         }
@@ -439,6 +862,8 @@ impl Primitive {
         let uv1s = compare_accessors(&self.uv1s, &other.uv1s);
         let uv2s = compare_accessors(&self.uv2s, &other.uv2s);
         let uv3s = compare_accessors(&self.uv3s, &other.uv3s);
+        let joints = compare_accessors(&self.joints, &other.joints);
+        let weights = compare_accessors(&self.weights, &other.weights);
 
         // Check if we are exactly equal
         if normals == AccessorComp::Equal
@@ -448,6 +873,8 @@ impl Primitive {
             && uv1s == AccessorComp::Equal
             && uv2s == AccessorComp::Equal
             && uv3s == AccessorComp::Equal
+            && joints == AccessorComp::Equal
+            && weights == AccessorComp::Equal
         {
             return false;
         }
@@ -460,6 +887,8 @@ impl Primitive {
             || uv1s == AccessorComp::Unequal
             || uv2s == AccessorComp::Unequal
             || uv3s == AccessorComp::Unequal
+            || joints == AccessorComp::Unequal
+            || weights == AccessorComp::Unequal
         {
             return true;
         }
@@ -494,11 +923,24 @@ impl Primitive {
             other.uv3s = self.uv3s;
         }
 
+        if joints == AccessorComp::WeHaveSome {
+            other.joints = self.joints;
+        }
+
+        if weights == AccessorComp::WeHaveSome {
+            other.weights = self.weights;
+        }
+
         false
     }
 }
 
-fn parse_node(node_idx: usize, gltf: &gltf::json::Root, mapping: &mut InvDataMapping) -> GltfNode {
+fn parse_node(
+    node_idx: usize,
+    gltf: &gltf::json::Root,
+    mapping: &mut InvDataMapping,
+    warnings: &Warnings,
+) -> GltfNode {
     let node = &gltf.nodes[node_idx];
 
     // Either construct the model matrix or grab it from the file
@@ -520,6 +962,7 @@ fn parse_node(node_idx: usize, gltf: &gltf::json::Root, mapping: &mut InvDataMap
     // Create the node
     let mut out_node = GltfNode {
         name: node.name.as_deref().unwrap_or("").to_string(),
+        index: NodeIndex(node_idx),
         model,
         data: if let Some(mesh) = node.mesh {
             // Check if this mesh group has been inspected before
@@ -532,11 +975,22 @@ fn parse_node(node_idx: usize, gltf: &gltf::json::Root, mapping: &mut InvDataMap
                     &mut mapping.materials,
                     &mut mapping.meshes,
                     &mut mapping.mesh_count,
+                    warnings,
                 );
                 new_idx
             });
 
-            GltfNodeData::MeshGroup(index)
+            match node.skin {
+                Some(skin) => {
+                    let new_idx = mapping.skins.len();
+                    let skin = *mapping.skins.entry(skin.value()).or_insert(new_idx);
+                    GltfNodeData::SkinnedMeshGroup {
+                        mesh_group: index,
+                        skin,
+                    }
+                }
+                None => GltfNodeData::MeshGroup(index),
+            }
         } else if let Some(ext) = &node.extensions {
             if let Some(light) = &ext.khr_lights_punctual {
                 let new_idx = mapping.lights.len();
@@ -557,7 +1011,7 @@ fn parse_node(node_idx: usize, gltf: &gltf::json::Root, mapping: &mut InvDataMap
         for child in children {
             out_node
                 .children
-                .push(parse_node(child.value(), gltf, mapping));
+                .push(parse_node(child.value(), gltf, mapping, warnings));
         }
     }
 
@@ -571,19 +1025,26 @@ fn inspect_mesh_group(
     material_map: &mut HashMap<usize, usize>,
     mesh_map: &mut HashMap<Accessor, Vec<Primitive>>,
     mesh_count: &mut usize,
+    warnings: &Warnings,
 ) {
     let mesh_group = &gltf.meshes[mesh_group_idx];
-    for primitive in &mesh_group.primitives {
+    for (primitive_idx, primitive) in mesh_group.primitives.iter().enumerate() {
         // Construct the primitive ID and see if we've got it in the mapping
         let mut prim_id = to_primitive_id(gltf, primitive);
 
         if prim_id.indices.buffer == usize::MAX {
-            println!("WARNING: Primitive in mesh group {mesh_group_idx} has no indices.");
+            warnings.push(GltfImportWarning::MissingIndices {
+                mesh: mesh_group_idx,
+                primitive: primitive_idx,
+            });
             continue;
         }
 
         if prim_id.positions.buffer == usize::MAX {
-            println!("WARNING: Primtive in mesh group {mesh_group_idx} has no positions.");
+            warnings.push(GltfImportWarning::MissingPositions {
+                mesh: mesh_group_idx,
+                primitive: primitive_idx,
+            });
             continue;
         }
 
@@ -602,6 +1063,7 @@ fn inspect_mesh_group(
 
         if needs_new_primitive {
             prim_id.mesh_idx = *mesh_count;
+            prim_id.source = (mesh_group_idx, primitive_idx);
             *mesh_count += 1;
 
             primitives.push(prim_id);
@@ -625,52 +1087,75 @@ fn inspect_mesh_group(
         let pbr = &material.pbr_metallic_roughness;
 
         if let Some(tex) = &pbr.base_color_texture {
-            let idx = tex.index.value();
-            let new_idx = texture_map.len();
-            let (_, old_usage) = texture_map
-                .entry(idx)
-                .or_insert((new_idx, TextureUsage::Diffuse));
-            if *old_usage != TextureUsage::Diffuse {
-                println!(
-                    "WARNING: Texture at index `{}` was used as `{:?}` but is now used as `{:?}`.",
-                    idx,
-                    *old_usage,
-                    TextureUsage::Diffuse
-                );
-            }
+            register_texture(
+                texture_map,
+                tex.index.value(),
+                TextureUsage::Diffuse,
+                warnings,
+            );
         }
 
         if let Some(tex) = &pbr.metallic_roughness_texture {
-            let idx = tex.index.value();
-            let new_idx = texture_map.len();
-            let (_, old_usage) = texture_map
-                .entry(idx)
-                .or_insert((new_idx, TextureUsage::MetallicRoughness));
-            if *old_usage != TextureUsage::MetallicRoughness {
-                println!(
-                    "WARNING: Texture at index `{}` was used as `{:?}` but is now used as `{:?}`.",
-                    idx,
-                    *old_usage,
-                    TextureUsage::MetallicRoughness
-                );
-            }
+            register_texture(
+                texture_map,
+                tex.index.value(),
+                TextureUsage::MetallicRoughness,
+                warnings,
+            );
         }
 
         if let Some(tex) = &material.normal_texture {
-            let idx = tex.index.value();
-            let new_idx = texture_map.len();
-            let (_, old_usage) = texture_map
-                .entry(idx)
-                .or_insert((new_idx, TextureUsage::Normal));
-            if *old_usage != TextureUsage::Normal {
-                println!(
-                    "WARNING: Texture at index `{}` was used as `{:?}` but is now used as `{:?}`.",
-                    idx,
-                    *old_usage,
-                    TextureUsage::Normal
-                );
-            }
+            register_texture(
+                texture_map,
+                tex.index.value(),
+                TextureUsage::Normal,
+                warnings,
+            );
+        }
+
+        if let Some(tex) = &material.emissive_texture {
+            register_texture(
+                texture_map,
+                tex.index.value(),
+                TextureUsage::Emissive,
+                warnings,
+            );
         }
+
+        if let Some(tex) = &material.occlusion_texture {
+            register_texture(
+                texture_map,
+                tex.index.value(),
+                TextureUsage::Occlusion,
+                warnings,
+            );
+        }
+    }
+}
+
+/// Marks a texture as used, warning if it was already used in an incompatible way.
+fn register_texture(
+    texture_map: &mut HashMap<usize, (usize, TextureUsage)>,
+    idx: usize,
+    usage: TextureUsage,
+    warnings: &Warnings,
+) {
+    let new_idx = texture_map.len();
+    let (_, old_usage) = texture_map.entry(idx).or_insert((new_idx, usage));
+
+    // Occlusion is commonly packed into the red channel of the metallic roughness map
+    let packed = matches!(
+        (*old_usage, usage),
+        (TextureUsage::MetallicRoughness, TextureUsage::Occlusion)
+            | (TextureUsage::Occlusion, TextureUsage::MetallicRoughness)
+    );
+
+    if *old_usage != usage && !packed {
+        warnings.push(GltfImportWarning::TextureUsageConflict {
+            texture: idx,
+            old: *old_usage,
+            new: usage,
+        });
     }
 }
 
@@ -716,193 +1201,574 @@ fn load_gltf_lights(gltf: &gltf::json::Root, mapping: &DataMapping) -> Vec<GltfL
         .collect()
 }
 
+fn load_gltf_skins(
+    gltf: &gltf::json::Root,
+    mapping: &DataMapping,
+    buffers: &[BufferData],
+    warnings: &Warnings,
+) -> Vec<GltfSkin> {
+    use rayon::prelude::*;
+
+    (0..mapping.skins.len())
+        .into_par_iter()
+        .map(|i| {
+            let gltf_idx = *mapping.skins.get(&i).unwrap();
+            let gltf_skin = &gltf.skins[gltf_idx];
+            let joints: Vec<_> = gltf_skin
+                .joints
+                .iter()
+                .map(|joint| NodeIndex(joint.value()))
+                .collect();
+
+            // Inverse bind matrices default to identity when not provided
+            let inverse_bind_matrices = gltf_skin
+                .inverse_bind_matrices
+                .and_then(|accessor| {
+                    let accessor = to_primitive_accessor(gltf, &gltf.accessors[accessor.value()]);
+                    accessor_to_vec::<Mat4>(&accessor, buffers, gltf::accessor::DataType::F32).ok()
+                })
+                .filter(|matrices| {
+                    let valid = matrices.len() == joints.len();
+                    if !valid {
+                        warnings.push(GltfImportWarning::MismatchedInverseBindMatrices {
+                            skin: gltf_idx,
+                            matrices: matrices.len(),
+                            joints: joints.len(),
+                        });
+                    }
+                    valid
+                })
+                .unwrap_or_else(|| vec![Mat4::IDENTITY; joints.len()]);
+
+            GltfSkin {
+                joints,
+                inverse_bind_matrices,
+            }
+        })
+        .collect()
+}
+
+fn load_gltf_animations(
+    gltf: &gltf::json::Root,
+    buffers: &[BufferData],
+    warnings: &Warnings,
+) -> Result<Vec<GltfAnimation>, GltfModelParseError> {
+    use gltf::animation::{Interpolation, Property};
+    use rayon::prelude::*;
+
+    gltf.animations
+        .par_iter()
+        .enumerate()
+        .map(|(anim_idx, gltf_anim)| {
+            let mut channels = Vec::with_capacity(gltf_anim.channels.len());
+            for (channel_idx, gltf_channel) in gltf_anim.channels.iter().enumerate() {
+                let gltf_sampler = &gltf_anim.samplers[gltf_channel.sampler.value()];
+                let input = to_animation_accessor(gltf, gltf_sampler.input.value())?;
+                let output = to_animation_accessor(gltf, gltf_sampler.output.value())?;
+
+                let interpolation = match gltf_sampler.interpolation.unwrap() {
+                    Interpolation::Step => GltfInterpolation::Step,
+                    Interpolation::Linear => GltfInterpolation::Linear,
+                    Interpolation::CubicSpline => GltfInterpolation::CubicSpline,
+                };
+
+                let invalid_channel = |err| GltfImportWarning::InvalidAnimationChannel {
+                    animation: anim_idx,
+                    channel: channel_idx,
+                    err,
+                };
+
+                let times =
+                    match accessor_to_vec::<f32>(&input, buffers, gltf::accessor::DataType::F32) {
+                        Ok(times) => times,
+                        Err(err) => {
+                            warnings.push(invalid_channel(err));
+                            continue;
+                        }
+                    };
+
+                const F32: gltf::accessor::DataType = gltf::accessor::DataType::F32;
+                let values = match gltf_channel.target.path.unwrap() {
+                    Property::Translation => accessor_to_vec::<Vec3>(&output, buffers, F32)
+                        .map(GltfAnimationValues::Translation),
+                    Property::Rotation => {
+                        accessor_to_vec::<Vec4>(&output, buffers, F32).map(|rotations| {
+                            GltfAnimationValues::Rotation(
+                                rotations.into_iter().map(Quat::from_vec4).collect(),
+                            )
+                        })
+                    }
+                    Property::Scale => accessor_to_vec::<Vec3>(&output, buffers, F32)
+                        .map(GltfAnimationValues::Scale),
+                    Property::MorphTargetWeights => accessor_to_vec::<f32>(&output, buffers, F32)
+                        .map(GltfAnimationValues::Weights),
+                };
+                let values = match values {
+                    Ok(values) => values,
+                    Err(err) => {
+                        warnings.push(invalid_channel(err));
+                        continue;
+                    }
+                };
+
+                // Every keyframe needs a value, or three with cubic splines. Weights have one
+                // value per morph target, so they only need to be a multiple.
+                let per_keyframe = match interpolation {
+                    GltfInterpolation::CubicSpline => 3,
+                    _ => 1,
+                };
+                let expected = times.len() * per_keyframe;
+                let valid = match &values {
+                    GltfAnimationValues::Translation(values)
+                    | GltfAnimationValues::Scale(values) => values.len() == expected,
+                    GltfAnimationValues::Rotation(values) => values.len() == expected,
+                    GltfAnimationValues::Weights(values) => {
+                        expected != 0 && values.len() % expected == 0
+                    }
+                };
+                if !valid {
+                    warnings.push(GltfImportWarning::MismatchedKeyframes {
+                        animation: anim_idx,
+                        channel: channel_idx,
+                    });
+                    continue;
+                }
+
+                channels.push(GltfAnimationChannel {
+                    target: NodeIndex(gltf_channel.target.node.value()),
+                    interpolation,
+                    times,
+                    values,
+                });
+            }
+
+            Ok(GltfAnimation {
+                name: gltf_anim.name.as_deref().unwrap_or("").to_string(),
+                channels,
+            })
+        })
+        .collect()
+}
+
 fn load_gltf_materials(
     gltf: &gltf::json::Root,
+    extensions: &extensions::Root,
     mapping: &DataMapping,
     inv_mapping: &InvDataMapping,
 ) -> Vec<GltfMaterial> {
     use rayon::prelude::*;
 
-    let gltf_materials = &gltf.materials;
     (0..mapping.materials.len())
         .into_par_iter()
         .map(|i| {
             let gltf_idx = *mapping.materials.get(&i).unwrap();
-            let gltf_material = &gltf_materials[gltf_idx];
+            load_gltf_material(gltf, extensions, inv_mapping, gltf_idx)
+        })
+        .collect()
+}
 
-            GltfMaterial::Pbr {
-                base_color: Vec4::from(gltf_material.pbr_metallic_roughness.base_color_factor.0),
-                metallic: gltf_material.pbr_metallic_roughness.metallic_factor.0,
-                roughness: gltf_material.pbr_metallic_roughness.roughness_factor.0,
-                alpha_cutoff: if gltf_material.alpha_mode.unwrap()
-                    == gltf::material::AlphaMode::Opaque
-                {
-                    0.0
-                } else {
-                    gltf_material.alpha_cutoff.map(|v| v.0).unwrap_or(0.0)
-                },
-                diffuse_map: gltf_material
-                    .pbr_metallic_roughness
-                    .base_color_texture
-                    .as_ref()
-                    .map(|info| inv_mapping.textures.get(&info.index.value()).unwrap().0),
-                normal_map: gltf_material
-                    .normal_texture
-                    .as_ref()
-                    .map(|info| inv_mapping.textures.get(&info.index.value()).unwrap().0),
-                metallic_roughness_map: gltf_material
-                    .pbr_metallic_roughness
-                    .metallic_roughness_texture
-                    .as_ref()
-                    .map(|info| inv_mapping.textures.get(&info.index.value()).unwrap().0),
-                blending: match gltf_material.alpha_mode.unwrap() {
-                    gltf::material::AlphaMode::Opaque => BlendType::Opaque,
-                    gltf::material::AlphaMode::Mask => BlendType::Mask,
-                    gltf::material::AlphaMode::Blend => BlendType::Blend,
-                },
+fn load_gltf_material(
+    gltf: &gltf::json::Root,
+    extensions: &extensions::Root,
+    inv_mapping: &InvDataMapping,
+    gltf_idx: usize,
+) -> GltfMaterial {
+    let gltf_material = &gltf.materials[gltf_idx];
+    let ext_material = extensions.materials.get(gltf_idx);
+
+    let texture_ref = |index: gltf::json::Index<gltf::json::Texture>,
+                       ext: Option<&extensions::TextureInfo>| {
+        GltfTextureRef {
+            texture: inv_mapping.textures.get(&index.value()).unwrap().0,
+            transform: ext
+                .and_then(|ext| ext.extensions.texture_transform.as_ref())
+                .map(GltfTextureTransform::from)
+                .unwrap_or_default(),
+        }
+    };
+
+    let emissive_strength = ext_material
+        .and_then(|ext| ext.extensions.emissive_strength.as_ref())
+        .map(|ext| ext.emissive_strength)
+        .unwrap_or(1.0);
+
+    GltfMaterial::Pbr {
+        base_color: Vec4::from(gltf_material.pbr_metallic_roughness.base_color_factor.0),
+        metallic: gltf_material.pbr_metallic_roughness.metallic_factor.0,
+        roughness: gltf_material.pbr_metallic_roughness.roughness_factor.0,
+        alpha_cutoff: if gltf_material.alpha_mode.unwrap() == gltf::material::AlphaMode::Opaque {
+            0.0
+        } else {
+            gltf_material.alpha_cutoff.map(|v| v.0).unwrap_or(0.0)
+        },
+        diffuse_map: gltf_material
+            .pbr_metallic_roughness
+            .base_color_texture
+            .as_ref()
+            .map(|info| {
+                texture_ref(
+                    info.index,
+                    ext_material
+                        .and_then(|ext| ext.pbr_metallic_roughness.base_color_texture.as_ref()),
+                )
+            }),
+        normal_map: gltf_material.normal_texture.as_ref().map(|info| {
+            texture_ref(
+                info.index,
+                ext_material.and_then(|ext| ext.normal_texture.as_ref()),
+            )
+        }),
+        metallic_roughness_map: gltf_material
+            .pbr_metallic_roughness
+            .metallic_roughness_texture
+            .as_ref()
+            .map(|info| {
+                texture_ref(
+                    info.index,
+                    ext_material.and_then(|ext| {
+                        ext.pbr_metallic_roughness
+                            .metallic_roughness_texture
+                            .as_ref()
+                    }),
+                )
+            }),
+        emissive_factor: Vec3::from_array(gltf_material.emissive_factor.0) * emissive_strength,
+        emissive_map: gltf_material.emissive_texture.as_ref().map(|info| {
+            texture_ref(
+                info.index,
+                ext_material.and_then(|ext| ext.emissive_texture.as_ref()),
+            )
+        }),
+        occlusion_strength: gltf_material
+            .occlusion_texture
+            .as_ref()
+            .map(|info| info.strength.0)
+            .unwrap_or(1.0),
+        occlusion_map: gltf_material.occlusion_texture.as_ref().map(|info| {
+            texture_ref(
+                info.index,
+                ext_material.and_then(|ext| ext.occlusion_texture.as_ref()),
+            )
+        }),
+        blending: match gltf_material.alpha_mode.unwrap() {
+            gltf::material::AlphaMode::Opaque => BlendType::Opaque,
+            gltf::material::AlphaMode::Mask => BlendType::Mask,
+            gltf::material::AlphaMode::Blend => BlendType::Blend,
+        },
+    }
+}
+
+/// Reads the extension data the `gltf` crate doesn't expose from the JSON of a model.
+fn parse_extensions(json: &[u8]) -> Result<extensions::Root, GltfModelParseError> {
+    serde_json::from_slice(json).map_err(|_| GltfModelParseError::ParseError)
+}
+
+/// Contents of a GLTF buffer. The binary chunk of a GLB file is left inside the file it was read
+/// from instead of being copied out.
+struct BufferData {
+    bytes: Vec<u8>,
+    range: Range<usize>,
+}
+
+impl From<Vec<u8>> for BufferData {
+    fn from(bytes: Vec<u8>) -> Self {
+        BufferData {
+            range: 0..bytes.len(),
+            bytes,
+        }
+    }
+}
+
+impl std::ops::Deref for BufferData {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        &self.bytes[self.range.clone()]
+    }
+}
+
+/// Resolves the contents of every buffer in the model, indexed the same as the GLTF buffers.
+/// `blob` is the binary chunk of a GLB file, which is used by the first buffer if it has no URI.
+fn load_gltf_buffers(
+    gltf: &gltf::json::Root,
+    mut blob: Option<BufferData>,
+    loader: &Loader,
+) -> Result<Vec<BufferData>, GltfModelParseError> {
+    gltf.buffers
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| {
+            let data = match (&buffer.uri, i) {
+                (Some(uri), _) => BufferData::from(read_uri(uri, loader)?.0),
+                (None, 0) => blob.take().ok_or(GltfModelParseError::MissingBuffer(i))?,
+                (None, _) => return Err(GltfModelParseError::MissingBuffer(i)),
+            };
+
+            // The GLB binary chunk may be padded, so we only care that there is enough data
+            let expected = buffer.byte_length as usize;
+            if data.len() < expected {
+                return Err(GltfModelParseError::BufferTooSmall {
+                    buffer: i,
+                    len: data.len(),
+                    expected,
+                });
             }
+
+            Ok(data)
         })
         .collect()
 }
 
+/// Reads the data referenced by a URI. Data URIs are decoded in place and return their MIME type
+/// if they have one. Everything else is percent-decoded and passed to the loader.
+fn read_uri(uri: &str, loader: &Loader) -> Result<(Vec<u8>, Option<String>), GltfModelParseError> {
+    if let Some(data_uri) = uri.strip_prefix("data:") {
+        // GLTF only allows base64 encoded data URIs
+        let (header, data) = data_uri
+            .split_once(',')
+            .ok_or_else(|| GltfModelParseError::InvalidUri(uri.into()))?;
+        let mime_type = header
+            .strip_suffix(";base64")
+            .ok_or_else(|| GltfModelParseError::InvalidUri(uri.into()))?;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|_| GltfModelParseError::InvalidUri(uri.into()))?;
+        let mime_type = if mime_type.is_empty() {
+            None
+        } else {
+            Some(mime_type.to_owned())
+        };
+        return Ok((data, mime_type));
+    }
+
+    let path = urlencoding::decode(uri).map_err(|_| GltfModelParseError::InvalidUri(uri.into()))?;
+    let data = loader(&path).map_err(|err| GltfModelParseError::Io {
+        uri: path.into_owned(),
+        err,
+    })?;
+    Ok((data, None))
+}
+
+/// Reads the encoded data of an image. Images embedded in a buffer are borrowed from it.
+fn read_image_source<'a>(
+    source: &GltfImageSource,
+    buffers: &'a [BufferData],
+    loader: &Loader,
+) -> Result<Cow<'a, [u8]>, GltfModelParseError> {
+    match source {
+        GltfImageSource::Buffer {
+            buffer,
+            offset,
+            len,
+        } => Ok(Cow::Borrowed(&buffers[*buffer][*offset..(*offset + *len)])),
+        GltfImageSource::Uri(uri) => Ok(Cow::Owned(read_uri(uri, loader)?.0)),
+        GltfImageSource::Missing => Ok(Cow::Borrowed(&[])),
+    }
+}
+
+/// Guesses the MIME type of an image from its URI without reading it.
+fn uri_mime_type(uri: &str) -> Option<&str> {
+    if let Some(data_uri) = uri.strip_prefix("data:") {
+        return data_uri
+            .split_once(';')
+            .map(|(mime_type, _)| mime_type)
+            .filter(|mime_type| !mime_type.is_empty());
+    }
+
+    let extension = Path::new(uri).extension()?.to_str()?;
+    if extension.eq_ignore_ascii_case("png") {
+        Some("image/png")
+    } else if extension.eq_ignore_ascii_case("jpg") || extension.eq_ignore_ascii_case("jpeg") {
+        Some("image/jpeg")
+    } else {
+        None
+    }
+}
+
+/// Determines the source format of an image from its MIME type, falling back to the image header
+/// when the type isn't known.
+fn texture_source_format(mime_type: Option<&str>, data: &[u8]) -> Option<TextureSourceFormat> {
+    const PNG_MAGIC: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    const JPEG_MAGIC: &[u8] = &[0xFF, 0xD8, 0xFF];
+
+    match mime_type {
+        Some("image/jpeg") => Some(TextureSourceFormat::Jpeg),
+        Some("image/png") => Some(TextureSourceFormat::Png),
+        Some(_) => None,
+        None if data.starts_with(PNG_MAGIC) => Some(TextureSourceFormat::Png),
+        None if data.starts_with(JPEG_MAGIC) => Some(TextureSourceFormat::Jpeg),
+        None => None,
+    }
+}
+
 fn load_gltf_textures(
     gltf: &gltf::json::Root,
     mapping: &DataMapping,
-    bin: &[u8],
-) -> Vec<GltfTexture> {
+    buffers: &[BufferData],
+    loader: &Loader,
+    warnings: &Warnings,
+) -> Result<Vec<GltfTexture>, GltfModelParseError> {
     use rayon::prelude::*;
 
-    let gltf_textures = &gltf.textures;
     (0..mapping.textures.len())
         .into_par_iter()
         .map(|i| {
             let (gltf_idx, usage) = *mapping.textures.get(&i).unwrap();
-            let gltf_texture = &gltf_textures[gltf_idx];
-            let gltf_image = &gltf.images[gltf_texture.source.value()];
-            let gltf_view = match &gltf_image.buffer_view {
-                Some(view) => &gltf.buffer_views[view.value()],
-                None => {
-                    println!("WARNING: Texture {gltf_idx} is using URI and not a buffer view.");
-                    return GltfTexture {
-                        data: Vec::default(),
-                        src_format: TextureSourceFormat::Png,
-                        usage,
-                        sampler: GltfSampler::default(),
-                        mips: false,
-                    };
-                }
-            };
-            let mime_type = match &gltf_image.mime_type {
-                Some(mime_type) => mime_type,
-                None => {
-                    println!("WARNING: Texture {gltf_idx} has an unknown source format.");
-                    return GltfTexture {
-                        data: Vec::default(),
-                        src_format: TextureSourceFormat::Png,
-                        usage,
-                        sampler: GltfSampler::default(),
-                        mips: false,
-                    };
-                }
-            };
-            let src_format = match mime_type.0.as_str() {
-                "image/jpeg" => TextureSourceFormat::Jpeg,
-                "image/png" => TextureSourceFormat::Png,
-                _ => {
-                    println!("WARNING: Texture {gltf_idx} has an unknown source format.");
-                    return GltfTexture {
-                        data: Vec::default(),
-                        src_format: TextureSourceFormat::Png,
-                        usage,
-                        sampler: GltfSampler::default(),
-                        mips: false,
-                    };
-                }
-            };
-            let data = match gltf_view.byte_stride {
-                Some(_) => {
-                    println!("WARNING: Texture {gltf_idx} is using stride.");
-                    return GltfTexture {
-                        data: Vec::default(),
-                        src_format,
-                        usage,
-                        sampler: GltfSampler::default(),
-                        mips: false,
-                    };
-                }
-                None => {
-                    let offset = gltf_view.byte_offset.unwrap_or(0) as usize;
-                    let len = gltf_view.byte_length as usize;
-                    Vec::from(&bin[offset..(offset + len)])
-                }
-            };
-            let (sampler, mips) = match &gltf_texture.sampler {
-                Some(sampler_idx) => {
-                    let gltf_sampler = &gltf.samplers[sampler_idx.value()];
-                    let max = gltf_to_pal_mag_filter(
-                        gltf_sampler
-                            .mag_filter
-                            .map(|filter| filter.unwrap())
-                            .unwrap_or(gltf::texture::MagFilter::Linear),
-                    );
-                    let (min, mip) = gltf_to_pal_min_filter(
-                        gltf_sampler
-                            .min_filter
-                            .map(|filter| filter.unwrap())
-                            .unwrap_or(gltf::texture::MinFilter::Linear),
-                    );
-                    let wrap_u = gltf_to_pal_wrap_mode(gltf_sampler.wrap_s.unwrap());
-                    let wrap_v = gltf_to_pal_wrap_mode(gltf_sampler.wrap_t.unwrap());
-
-                    (
-                        GltfSampler {
-                            min_filter: min,
-                            mag_filter: max,
-                            mipmap_filter: mip.unwrap_or(Filter::Linear),
-                            address_u: wrap_u,
-                            address_v: wrap_v,
-                        },
-                        mip.is_some(),
-                    )
-                }
-                None => (GltfSampler::default(), false),
-            };
-
-            GltfTexture {
-                data,
-                src_format,
-                usage,
-                sampler,
-                mips,
-            }
+            load_gltf_texture(gltf, gltf_idx, usage, buffers, loader, warnings)
         })
         .collect()
 }
 
-fn load_gltf_meshes(
+/// Works out where the data for a texture is and how to sample it. Image data is only read if
+/// there's no other way to know its format.
+fn load_gltf_texture(
     gltf: &gltf::json::Root,
+    gltf_idx: usize,
+    usage: TextureUsage,
+    buffers: &[BufferData],
+    loader: &Loader,
+    warnings: &Warnings,
+) -> Result<GltfTexture, GltfModelParseError> {
+    let gltf_texture = &gltf.textures[gltf_idx];
+    let gltf_image = &gltf.images[gltf_texture.source.value()];
+    let mime_type = gltf_image.mime_type.as_ref().map(|mime| mime.0.as_str());
+    let missing = GltfTexture {
+        source: GltfImageSource::Missing,
+        src_format: TextureSourceFormat::Png,
+        usage,
+        sampler: GltfSampler::default(),
+        mips: false,
+    };
+
+    // Image data isn't copied here. We only look at enough of it to know the format
+    let (source, src_format) = match (&gltf_image.buffer_view, &gltf_image.uri) {
+        (Some(view_idx), _) => {
+            let gltf_view = &gltf.buffer_views[view_idx.value()];
+            if gltf_view.byte_stride.is_some() {
+                warnings.push(GltfImportWarning::UnsupportedTextureSource(gltf_idx));
+                return Ok(missing);
+            }
+
+            let buffer = gltf_view.buffer.value();
+            let offset = gltf_view.byte_offset.unwrap_or(0) as usize;
+            let len = gltf_view.byte_length as usize;
+            let data = buffers[buffer]
+                .get(offset..(offset + len))
+                .ok_or(GltfModelParseError::ViewOutOfBounds(view_idx.value()))?;
+
+            (
+                GltfImageSource::Buffer {
+                    buffer,
+                    offset,
+                    len,
+                },
+                texture_source_format(mime_type, data),
+            )
+        }
+        (None, Some(uri)) => {
+            // Only read external images if there's no other way to tell what they are
+            let src_format = match mime_type.or_else(|| uri_mime_type(uri)) {
+                Some(mime_type) => texture_source_format(Some(mime_type), &[]),
+                None => texture_source_format(None, &read_uri(uri, loader)?.0),
+            };
+            (GltfImageSource::Uri(uri.clone()), src_format)
+        }
+        (None, None) => {
+            warnings.push(GltfImportWarning::MissingTextureSource(gltf_idx));
+            return Ok(missing);
+        }
+    };
+    let src_format = match src_format {
+        Some(src_format) => src_format,
+        None => {
+            warnings.push(GltfImportWarning::UnknownTextureFormat(gltf_idx));
+            return Ok(missing);
+        }
+    };
+    let (sampler, mips) = match &gltf_texture.sampler {
+        Some(sampler_idx) => {
+            let gltf_sampler = &gltf.samplers[sampler_idx.value()];
+            let max = gltf_to_pal_mag_filter(
+                gltf_sampler
+                    .mag_filter
+                    .map(|filter| filter.unwrap())
+                    .unwrap_or(gltf::texture::MagFilter::Linear),
+            );
+            let (min, mip) = gltf_to_pal_min_filter(
+                gltf_sampler
+                    .min_filter
+                    .map(|filter| filter.unwrap())
+                    .unwrap_or(gltf::texture::MinFilter::Linear),
+            );
+            let wrap_u = gltf_to_pal_wrap_mode(gltf_sampler.wrap_s.unwrap());
+            let wrap_v = gltf_to_pal_wrap_mode(gltf_sampler.wrap_t.unwrap());
+
+            (
+                GltfSampler {
+                    min_filter: min,
+                    mag_filter: max,
+                    mipmap_filter: mip.unwrap_or(Filter::Linear),
+                    address_u: wrap_u,
+                    address_v: wrap_v,
+                },
+                mip.is_some(),
+            )
+        }
+        None => (GltfSampler::default(), false),
+    };
+
+    Ok(GltfTexture {
+        source,
+        src_format,
+        usage,
+        sampler,
+        mips,
+    })
+}
+
+fn load_gltf_meshes(
     mapping: &InvDataMapping,
-    bin: &[u8],
+    buffers: &[BufferData],
+    options: GltfImportOptions,
+    warnings: &Warnings,
 ) -> Vec<GltfMesh> {
     // Sort by our index so we can get the correct mapping
-    let mut primitives: Vec<_> = mapping
-        .meshes
-        .clone()
-        .into_iter()
-        .flat_map(|(_, prim)| prim.into_iter())
-        .collect();
-    primitives.sort_by_key(|e| e.mesh_idx);
-
-    primitives
+    sorted_primitives(mapping)
         .par_iter()
-        .map(|primitive| load_gltf_primitive(gltf, primitive, bin))
+        .map(|primitive| load_gltf_mesh(primitive, buffers, options, warnings))
         .collect()
 }
 
+/// Every unique primitive in the model, ordered by their mesh index.
+fn sorted_primitives(mapping: &InvDataMapping) -> Vec<Primitive> {
+    let mut primitives: Vec<_> = mapping.meshes.values().flatten().copied().collect();
+    primitives.sort_by_key(|e| e.mesh_idx);
+    primitives
+}
+
+fn load_gltf_mesh(
+    primitive: &Primitive,
+    buffers: &[BufferData],
+    options: GltfImportOptions,
+    warnings: &Warnings,
+) -> GltfMesh {
+    let (mesh_group, primitive_idx) = primitive.source;
+    let mut mesh = load_gltf_primitive(primitive, buffers, warnings);
+    mesh.validate(mesh_group, primitive_idx, warnings);
+
+    if options.generate_tangents && mesh.tangents.is_none() {
+        mesh.generate_tangents();
+    }
+
+    mesh
+}
+
 fn load_gltf_mesh_groups(
     gltf: &gltf::json::Root,
     mapping: &DataMapping,
     inv_mapping: &InvDataMapping,
+    warnings: &Warnings,
 ) -> Vec<GltfMeshGroup> {
     use rayon::prelude::*;
 
@@ -914,13 +1780,16 @@ fn load_gltf_mesh_groups(
             let gltf_mesh = &gltf_meshes[gltf_idx];
 
             let mut mesh_group = GltfMeshGroup(Vec::with_capacity(gltf_mesh.primitives.len()));
-            for primitive in &gltf_mesh.primitives {
+            for (primitive_idx, primitive) in gltf_mesh.primitives.iter().enumerate() {
                 let material = match &primitive.material {
                     Some(material_idx) => {
                         *inv_mapping.materials.get(&material_idx.value()).unwrap()
                     }
                     None => {
-                        println!("WARNING: Primitive in mesh group {gltf_idx} has no material.");
+                        warnings.push(GltfImportWarning::MissingMaterial {
+                            mesh: gltf_idx,
+                            primitive: primitive_idx,
+                        });
                         continue;
                     }
                 };
@@ -949,99 +1818,104 @@ fn load_gltf_mesh_groups(
         .collect()
 }
 
-fn load_gltf_primitive(gltf: &gltf::json::Root, primitive: &Primitive, bin: &[u8]) -> GltfMesh {
-    let positions = match accessor_to_vec::<Vec4>(
-        gltf,
-        &primitive.positions,
-        bin,
-        gltf::accessor::DataType::F32,
-    ) {
-        Some(res) => res,
-        None => {
-            println!("WARNING: Unable to load primitive.");
-            return GltfMesh::default();
-        }
+fn load_gltf_primitive(
+    primitive: &Primitive,
+    buffers: &[BufferData],
+    warnings: &Warnings,
+) -> GltfMesh {
+    let invalid = |attribute, err| {
+        warnings.push(GltfImportWarning::InvalidAttribute {
+            mesh: primitive.source.0,
+            primitive: primitive.source.1,
+            attribute,
+            err,
+        });
+        GltfMesh::default()
     };
 
+    let positions =
+        match accessor_to_vec::<Vec4>(&primitive.positions, buffers, gltf::accessor::DataType::F32)
+        {
+            Ok(res) => res,
+            Err(err) => return invalid("POSITION", err),
+        };
+
     let normals = if let Some(accessor) = primitive.normals {
-        match accessor_to_vec::<Vec4>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec4>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("NORMAL", err),
         }
     } else {
         Vec::default()
     };
 
     let tangents = if let Some(accessor) = primitive.tangents {
-        match accessor_to_vec::<Vec4>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec4>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("TANGENT", err),
         }
     } else {
         Vec::default()
     };
 
     let colors = if let Some(accessor) = primitive.colors {
-        match accessor_to_vec::<Vec4>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec4>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("COLOR_0", err),
         }
     } else {
         Vec::default()
     };
 
     let uv0 = if let Some(accessor) = primitive.uv0s {
-        match accessor_to_vec::<Vec2>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec2>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("TEXCOORD_0", err),
         }
     } else {
         Vec::default()
     };
 
     let uv1 = if let Some(accessor) = primitive.uv1s {
-        match accessor_to_vec::<Vec2>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec2>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("TEXCOORD_1", err),
         }
     } else {
         Vec::default()
     };
 
     let uv2 = if let Some(accessor) = primitive.uv2s {
-        match accessor_to_vec::<Vec2>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec2>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("TEXCOORD_2", err),
         }
     } else {
         Vec::default()
     };
 
     let uv3 = if let Some(accessor) = primitive.uv3s {
-        match accessor_to_vec::<Vec2>(gltf, &accessor, bin, gltf::accessor::DataType::F32) {
-            Some(res) => res,
-            None => {
-                println!("WARNING: Unable to load primitive.");
-                return GltfMesh::default();
-            }
+        match accessor_to_vec::<Vec2>(&accessor, buffers, gltf::accessor::DataType::F32) {
+            Ok(res) => res,
+            Err(err) => return invalid("TEXCOORD_3", err),
+        }
+    } else {
+        Vec::default()
+    };
+
+    let joints = if let Some(accessor) = primitive.joints {
+        match accessor_to_joints(&accessor, buffers) {
+            Ok(res) => res,
+            Err(err) => return invalid("JOINTS_0", err),
+        }
+    } else {
+        Vec::default()
+    };
+
+    let weights = if let Some(accessor) = primitive.weights {
+        match accessor_to_weights(&accessor, buffers) {
+            Ok(res) => res,
+            Err(err) => return invalid("WEIGHTS_0", err),
         }
     } else {
         Vec::default()
@@ -1056,16 +1930,12 @@ fn load_gltf_primitive(gltf: &gltf::json::Root, primitive: &Primitive, bin: &[u8
     let indices = match indices_accessor.component_type {
         U16 => {
             let u16_indices = match accessor_to_vec::<u16>(
-                gltf,
                 indices_accessor,
-                bin,
+                buffers,
                 gltf::accessor::DataType::U16,
             ) {
-                Some(res) => res,
-                None => {
-                    println!("WARNING: Unable to load primitive.");
-                    return GltfMesh::default();
-                }
+                Ok(res) => res,
+                Err(err) => return invalid("indices", err),
             };
             let mut as_u32 = Vec::with_capacity(u16_indices.len());
             for i in u16_indices {
@@ -1075,17 +1945,17 @@ fn load_gltf_primitive(gltf: &gltf::json::Root, primitive: &Primitive, bin: &[u8
             as_u32
         }
         U32 => {
-            match accessor_to_vec::<u32>(gltf, indices_accessor, bin, gltf::accessor::DataType::U32)
-            {
-                Some(res) => res,
-                None => {
-                    println!("WARNING: Unable to load primitive.");
-                    return GltfMesh::default();
-                }
+            match accessor_to_vec::<u32>(indices_accessor, buffers, gltf::accessor::DataType::U32) {
+                Ok(res) => res,
+                Err(err) => return invalid("indices", err),
             }
         }
-        _ => {
-            println!("WARNING: Unsupported index data type.");
+        other => {
+            warnings.push(GltfImportWarning::UnsupportedIndexType {
+                mesh: primitive.source.0,
+                primitive: primitive.source.1,
+                component_type: gltf_component_type(other),
+            });
             return GltfMesh::default();
         }
     };
@@ -1112,23 +1982,114 @@ fn load_gltf_primitive(gltf: &gltf::json::Root, primitive: &Primitive, bin: &[u8
         uv1: if uv1.is_empty() { None } else { Some(uv1) },
         uv2: if uv2.is_empty() { None } else { Some(uv2) },
         uv3: if uv3.is_empty() { None } else { Some(uv3) },
+        joints: if joints.is_empty() {
+            None
+        } else {
+            Some(joints)
+        },
+        weights: if weights.is_empty() {
+            None
+        } else {
+            Some(weights)
+        },
+    }
+}
+
+/// Loads joint indices, which are stored as either `u8` or `u16`.
+fn accessor_to_joints(
+    accessor: &Accessor,
+    buffers: &[BufferData],
+) -> Result<Vec<UVec4>, AccessorError> {
+    const U8: u32 = gltf::accessor::DataType::U8 as u32;
+    const U16: u32 = gltf::accessor::DataType::U16 as u32;
+
+    match accessor.component_type {
+        U8 => accessor_to_vec::<[u8; 4]>(accessor, buffers, gltf::accessor::DataType::U8).map(
+            |joints| {
+                joints
+                    .into_iter()
+                    .map(|j| UVec4::from_array(j.map(u32::from)))
+                    .collect()
+            },
+        ),
+        U16 => accessor_to_vec::<[u16; 4]>(accessor, buffers, gltf::accessor::DataType::U16).map(
+            |joints| {
+                joints
+                    .into_iter()
+                    .map(|j| UVec4::from_array(j.map(u32::from)))
+                    .collect()
+            },
+        ),
+        other => Err(AccessorError::UnsupportedComponentType(
+            gltf_component_type(other),
+        )),
     }
 }
 
+/// Loads joint weights, which are stored as either `f32` or normalized `u8` or `u16`.
+fn accessor_to_weights(
+    accessor: &Accessor,
+    buffers: &[BufferData],
+) -> Result<Vec<Vec4>, AccessorError> {
+    const U8: u32 = gltf::accessor::DataType::U8 as u32;
+    const U16: u32 = gltf::accessor::DataType::U16 as u32;
+    const F32: u32 = gltf::accessor::DataType::F32 as u32;
+
+    match accessor.component_type {
+        U8 => accessor_to_vec::<[u8; 4]>(accessor, buffers, gltf::accessor::DataType::U8).map(
+            |weights| {
+                weights
+                    .into_iter()
+                    .map(|w| Vec4::from_array(w.map(|w| w as f32 / u8::MAX as f32)))
+                    .collect()
+            },
+        ),
+        U16 => accessor_to_vec::<[u16; 4]>(accessor, buffers, gltf::accessor::DataType::U16).map(
+            |weights| {
+                weights
+                    .into_iter()
+                    .map(|w| Vec4::from_array(w.map(|w| w as f32 / u16::MAX as f32)))
+                    .collect()
+            },
+        ),
+        F32 => accessor_to_vec::<Vec4>(accessor, buffers, gltf::accessor::DataType::F32),
+        other => Err(AccessorError::UnsupportedComponentType(
+            gltf_component_type(other),
+        )),
+    }
+}
+
+/// Converts a component type stored in an [`Accessor`] into the value used in GLTF files, so it
+/// can be reported to users.
+fn gltf_component_type(component_type: u32) -> u32 {
+    use gltf::accessor::DataType;
+
+    [
+        DataType::I8,
+        DataType::U8,
+        DataType::I16,
+        DataType::U16,
+        DataType::U32,
+        DataType::F32,
+    ]
+    .into_iter()
+    .find(|ty| *ty as u32 == component_type)
+    .map(DataType::as_gl_enum)
+    .unwrap_or(component_type)
+}
+
 /// Takes an accessor and turns the data referenced into a buffer of another type.
 fn accessor_to_vec<T: Pod + Zeroable + 'static>(
-    gltf: &gltf::json::Root,
     accessor: &Accessor,
-    raw: &[u8],
+    buffers: &[BufferData],
     expected_data_type: gltf::accessor::DataType,
-) -> Option<Vec<T>> {
+) -> Result<Vec<T>, AccessorError> {
     // Don't support non-float data types
     if accessor.component_type != expected_data_type as u32 {
-        println!(
-            "WARNING: Expected `{:?}` accessor data type but got `{:?}`.",
-            expected_data_type, accessor.component_type
-        );
-        return None;
+        return Err(AccessorError::UnexpectedComponentType {
+            expected: expected_data_type.as_gl_enum(),
+            found: gltf_component_type(accessor.component_type),
+        });
     }
 
     let data_size = match expected_data_type {
@@ -1140,11 +2101,7 @@ fn accessor_to_vec<T: Pod + Zeroable + 'static>(
         gltf::accessor::DataType::F32 => std::mem::size_of::<f32>(),
     };
 
-    // Ensure the buffer is from the binary blob and not a uri
-    if gltf.buffers[accessor.buffer].uri.is_some() {
-        println!("WARNING: No support for vertex data from URI.");
-        return None;
-    }
+    let raw = &buffers[accessor.buffer][..];
 
     // Create a raw buffer for the point data
     // NOTE: We have to use unsafe here because bytemuck requires the alignments to be the same.
@@ -1171,13 +2128,21 @@ fn accessor_to_vec<T: Pod + Zeroable + 'static>(
 
     // Read size has to be less than or equal to the write size, otherwise we are copying OOB
     if read_size > write_size {
-        println!("WARNING: Vertex attribute is bigger than requested type.");
-        return None;
+        return Err(AccessorError::ElementTooLarge);
     }
 
     let mut read_offset = accessor.byte_offset as usize;
     let mut write_offset = 0;
 
+    // Make sure the accessor doesn't read past the end of its buffer
+    let read_end = match accessor.count as usize {
+        0 => read_offset,
+        count => read_offset + ((count - 1) * read_stride) + read_size,
+    };
+    if read_end > raw.len() {
+        return Err(AccessorError::OutOfBounds(accessor.buffer));
+    }
+
     // If our read stride and write sizes are equal, we're lucky. We can just do a straight memcpy
     if read_stride == write_size {
         let len = points.len();
@@ -1198,7 +2163,7 @@ fn accessor_to_vec<T: Pod + Zeroable + 'static>(
         let cap = points.capacity();
         let len = points.len();
         std::mem::forget(points);
-        Some(Vec::<T>::from_raw_parts(
+        Ok(Vec::<T>::from_raw_parts(
             ptr as *mut T,
             len / std::mem::size_of::<T>(),
             cap / std::mem::size_of::<T>(),
@@ -1209,6 +2174,7 @@ fn accessor_to_vec<T: Pod + Zeroable + 'static>(
 fn to_primitive_id(gltf: &gltf::json::Root, primitive: &gltf::json::mesh::Primitive) -> Primitive {
     let mut prim_id = Primitive {
         mesh_idx: usize::MAX,
+        source: (usize::MAX, usize::MAX),
         indices: Accessor::default(),
         positions: Accessor::default(),
         normals: None,
@@ -1218,6 +2184,8 @@ fn to_primitive_id(gltf: &gltf::json::Root, primitive: &gltf::json::mesh::Primit
         uv1s: None,
         uv2s: None,
         uv3s: None,
+        joints: None,
+        weights: None,
     };
 
     if let Some(id) = &primitive.indices {
@@ -1247,13 +2215,35 @@ fn to_primitive_id(gltf: &gltf::json::Root, primitive: &gltf::json::mesh::Primit
                 3 => prim_id.uv3s = Some(to_primitive_accessor(gltf, &gltf.accessors[id.value()])),
                 _ => {}
             },
-            _ => {}
+            gltf::Semantic::Joints(n) => {
+                if *n == 0 {
+                    prim_id.joints = Some(to_primitive_accessor(gltf, &gltf.accessors[id.value()]));
+                }
+            }
+            gltf::Semantic::Weights(n) => {
+                if *n == 0 {
+                    prim_id.weights =
+                        Some(to_primitive_accessor(gltf, &gltf.accessors[id.value()]));
+                }
+            }
         }
     }
 
     prim_id
 }
 
+/// Like [`to_primitive_accessor`], but fails on sparse accessors instead of reading garbage.
+fn to_animation_accessor(
+    gltf: &gltf::json::Root,
+    accessor_idx: usize,
+) -> Result<Accessor, GltfModelParseError> {
+    let accessor = &gltf.accessors[accessor_idx];
+    if accessor.sparse.is_some() || accessor.buffer_view.is_none() {
+        return Err(GltfModelParseError::SparseAccessor(accessor_idx));
+    }
+    Ok(to_primitive_accessor(gltf, accessor))
+}
+
 fn to_primitive_accessor(gltf: &gltf::json::Root, accessor: &gltf::json::Accessor) -> Accessor {
     let view = match &accessor.buffer_view {
         Some(view) => &gltf.buffer_views[view.value()],
@@ -1270,7 +2260,9 @@ fn to_primitive_accessor(gltf: &gltf::json::Root, accessor: &gltf::json::Accesso
             gltf::accessor::Dimensions::Vec2 => 2,
             gltf::accessor::Dimensions::Vec3 => 3,
             gltf::accessor::Dimensions::Vec4 => 4,
-            _ => 0,
+            gltf::accessor::Dimensions::Mat2 => 4,
+            gltf::accessor::Dimensions::Mat3 => 9,
+            gltf::accessor::Dimensions::Mat4 => 16,
         },
         count: accessor.count,
         byte_offset: accessor.byte_offset + view.byte_offset.unwrap_or(0),
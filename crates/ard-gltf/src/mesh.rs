@@ -0,0 +1,151 @@
+use ard_math::{Vec3, Vec4Swizzles};
+
+use crate::{diagnostics::Warnings, GltfImportWarning, GltfMesh};
+
+impl GltfMesh {
+    /// Generates tangents from the positions, normals, and first UV set of the mesh, replacing
+    /// any existing tangents. This follows MikkTSpace in weighting each triangle by the angle of
+    /// its corners and orthogonalizing against the vertex normal, but doesn't split vertices.
+    ///
+    /// Returns `false` and leaves the mesh untouched if it has no normals or UVs. Triangles with
+    /// out of range indices or without any UV area are ignored. The result only depends on the
+    /// mesh data, so it is the same every time.
+    pub fn generate_tangents(&mut self) -> bool {
+        let vertex_count = self.positions.len();
+        let (normals, uvs) = match (&self.normals, &self.uv0) {
+            (Some(normals), Some(uvs))
+                if normals.len() == vertex_count && uvs.len() == vertex_count =>
+            {
+                (normals, uvs)
+            }
+            _ => return false,
+        };
+
+        let normals: Vec<Vec3> = normals
+            .iter()
+            .map(|n| n.xyz().normalize_or_zero())
+            .collect();
+        let mut tangents = vec![Vec3::ZERO; vertex_count];
+        let mut bitangents = vec![Vec3::ZERO; vertex_count];
+
+        for tri in self.indices.chunks_exact(3) {
+            let tri = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+            if tri.iter().any(|i| *i >= vertex_count) {
+                continue;
+            }
+
+            let p = tri.map(|i| self.positions[i].xyz());
+            let uv = tri.map(|i| uvs[i]);
+
+            let edge1 = p[1] - p[0];
+            let edge2 = p[2] - p[0];
+            let delta_uv1 = uv[1] - uv[0];
+            let delta_uv2 = uv[2] - uv[0];
+
+            let det = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+            if !det.is_normal() {
+                continue;
+            }
+
+            let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / det;
+            let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) / det;
+
+            for corner in 0..3 {
+                let i = tri[corner];
+                let to_next = p[(corner + 1) % 3] - p[corner];
+                let to_prev = p[(corner + 2) % 3] - p[corner];
+                let angle = to_next.angle_between(to_prev);
+                if !angle.is_finite() {
+                    continue;
+                }
+
+                // Project onto the tangent plane of the vertex before weighting so large
+                // triangles don't dominate.
+                let n = normals[i];
+                let t = (tangent - n * n.dot(tangent)).normalize_or_zero();
+                tangents[i] += t * angle;
+                bitangents[i] += bitangent * angle;
+            }
+        }
+
+        self.tangents = Some(
+            (0..vertex_count)
+                .map(|i| {
+                    let n = normals[i];
+                    let t = (tangents[i] - n * n.dot(tangents[i])).normalize_or_zero();
+
+                    // Vertices without any usable triangles still need a valid basis
+                    let t = if t == Vec3::ZERO {
+                        n.any_orthonormal_vector()
+                    } else {
+                        t
+                    };
+
+                    // GLTF UVs have V pointing down, so the bitangent should point towards
+                    // decreasing V.
+                    let handedness = if n.cross(t).dot(bitangents[i]) > 0.0 {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+
+                    t.extend(handedness)
+                })
+                .collect(),
+        );
+
+        true
+    }
+
+    /// Checks the mesh for data that would render incorrectly or not at all.
+    pub(crate) fn validate(&self, mesh: usize, primitive: usize, warnings: &Warnings) {
+        let vertex_count = self.positions.len();
+
+        let non_finite = self
+            .positions
+            .iter()
+            .filter(|p| !p.xyz().is_finite())
+            .count();
+        if non_finite != 0 {
+            warnings.push(GltfImportWarning::NonFinitePositions {
+                mesh,
+                primitive,
+                count: non_finite,
+            });
+        }
+
+        let out_of_range = self
+            .indices
+            .iter()
+            .filter(|i| **i as usize >= vertex_count)
+            .count();
+        if out_of_range != 0 {
+            warnings.push(GltfImportWarning::IndicesOutOfRange {
+                mesh,
+                primitive,
+                count: out_of_range,
+                vertex_count,
+            });
+        }
+
+        let degenerate = self
+            .indices
+            .chunks_exact(3)
+            .filter(|tri| {
+                let p = |i: u32| self.positions.get(i as usize).map(|p| p.xyz());
+                match (p(tri[0]), p(tri[1]), p(tri[2])) {
+                    (Some(a), Some(b), Some(c)) => (b - a).cross(c - a).length_squared() == 0.0,
+                    // Already reported as out of range
+                    _ => false,
+                }
+            })
+            .count();
+        if degenerate != 0 {
+            warnings.push(GltfImportWarning::DegenerateTriangles {
+                mesh,
+                primitive,
+                count: degenerate,
+            });
+        }
+    }
+}
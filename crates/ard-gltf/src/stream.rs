@@ -0,0 +1,371 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    load_gltf_animations, load_gltf_lights, load_gltf_material, load_gltf_mesh,
+    load_gltf_mesh_groups, load_gltf_skins, load_gltf_texture, read_image_source,
+    sorted_primitives, GltfAnimation, GltfImportDiagnostics, GltfImportOptions, GltfLight,
+    GltfMaterial, GltfMesh, GltfMeshGroup, GltfModelParseError, GltfNode, GltfSkin, GltfTexture,
+    ParsedModel, Primitive,
+};
+
+/// A model that is imported in the background.
+///
+/// Creating the stream only walks the node graph and loads what is cheap to load, which is
+/// everything public on the stream. Materials, meshes, and textures are then loaded on a pool of
+/// background threads and yielded one at a time as they're ready, so they can be uploaded before
+/// the rest of the model is loaded and never all need to be in memory at once.
+///
+/// Dropping the stream cancels the import. See [`GltfImportStream::cancel`].
+pub struct GltfImportStream {
+    pub lights: Vec<GltfLight>,
+    pub mesh_groups: Vec<GltfMeshGroup>,
+    pub skins: Vec<GltfSkin>,
+    pub animations: Vec<GltfAnimation>,
+    pub roots: Vec<GltfNode>,
+    /// Number of materials that will be yielded. Indices used by mesh groups are less than this.
+    pub material_count: usize,
+    /// Number of meshes that will be yielded. Indices used by mesh groups are less than this.
+    pub mesh_count: usize,
+    /// Number of textures that will be yielded. Indices used by materials are less than this.
+    pub texture_count: usize,
+    items: mpsc::Receiver<Result<GltfImportItem, GltfModelParseError>>,
+    progress: ImportProgressHandle,
+    model: Arc<ParsedModel>,
+    worker: Option<JoinHandle<()>>,
+}
+
+/// A resource yielded by a [`GltfImportStream`]. Indices match the ones used by the stream and
+/// by [`GltfModel`](crate::GltfModel).
+pub enum GltfImportItem {
+    Material {
+        index: usize,
+        material: GltfMaterial,
+    },
+    Mesh {
+        index: usize,
+        mesh: GltfMesh,
+    },
+    Texture {
+        index: usize,
+        texture: GltfTexture,
+        /// Encoded image data.
+        data: Vec<u8>,
+    },
+}
+
+/// How far along an import is.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// Number of items that have been received from the stream.
+    pub items_done: usize,
+    /// Number of items the stream will yield in total.
+    pub items_total: usize,
+    /// Number of bytes reported with [`ImportProgressHandle::report_upload`].
+    pub bytes_uploaded: u64,
+}
+
+/// Shared handle to the progress of a [`GltfImportStream`]. The handle can be polled from
+/// another thread, such as by a loading bar, and can cancel the import.
+#[derive(Clone)]
+pub struct ImportProgressHandle(Arc<ProgressState>);
+
+struct ProgressState {
+    items_done: AtomicUsize,
+    items_total: usize,
+    bytes_uploaded: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+/// A single item for the background threads to load.
+#[derive(Debug, Copy, Clone)]
+enum StreamWork {
+    Material(usize),
+    Mesh(usize),
+    Texture(usize),
+}
+
+impl GltfImportStream {
+    /// Starts importing a GLB or GLTF file from disk. External files are resolved relative to the
+    /// directory containing the model.
+    ///
+    /// Up to `max_in_flight` items are loaded at once, and as many more can wait to be received.
+    pub fn from_gltf(
+        path: &Path,
+        options: GltfImportOptions,
+        max_in_flight: usize,
+    ) -> Result<Self, GltfModelParseError> {
+        let data = std::fs::read(path).map_err(|err| GltfModelParseError::Io {
+            uri: path.display().to_string(),
+            err,
+        })?;
+        let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self::from_vec(
+            data,
+            move |uri| std::fs::read(root.join(uri)),
+            options,
+            max_in_flight,
+        )
+    }
+
+    /// Starts importing a GLB or GLTF model. `loader` is called with the percent-decoded URI of
+    /// every external buffer and image the model references. The binary chunk of a GLB file is
+    /// used in place.
+    ///
+    /// Up to `max_in_flight` items are loaded at once, and as many more can wait to be received.
+    pub fn from_vec(
+        data: Vec<u8>,
+        loader: impl Fn(&str) -> std::io::Result<Vec<u8>> + Send + Sync + 'static,
+        options: GltfImportOptions,
+        max_in_flight: usize,
+    ) -> Result<Self, GltfModelParseError> {
+        let mut model = ParsedModel::parse(data, Box::new(loader))?;
+        let animations = load_gltf_animations(&model.gltf, &model.buffers, &model.warnings)?;
+        let lights = load_gltf_lights(&model.gltf, &model.mapping);
+        let skins = load_gltf_skins(&model.gltf, &model.mapping, &model.buffers, &model.warnings);
+        let mesh_groups = load_gltf_mesh_groups(
+            &model.gltf,
+            &model.mapping,
+            &model.inv_mapping,
+            &model.warnings,
+        );
+
+        let roots = std::mem::take(&mut model.roots);
+        let model = Arc::new(model);
+
+        let primitives = sorted_primitives(&model.inv_mapping);
+        let material_count = model.mapping.materials.len();
+        let mesh_count = primitives.len();
+        let texture_count = model.mapping.textures.len();
+
+        // Materials are cheap, so they're sent first so meshes and textures can be used as soon
+        // as they arrive
+        let work: Vec<_> = (0..material_count)
+            .map(StreamWork::Material)
+            .chain((0..mesh_count).map(StreamWork::Mesh))
+            .chain((0..texture_count).map(StreamWork::Texture))
+            .collect();
+
+        let progress = ImportProgressHandle(Arc::new(ProgressState {
+            items_done: AtomicUsize::new(0),
+            items_total: work.len(),
+            bytes_uploaded: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        }));
+
+        // The channel is bounded so decoded items don't pile up if they aren't received
+        let max_in_flight = max_in_flight.max(1);
+        let (send, items) = mpsc::sync_channel(max_in_flight);
+
+        let worker = std::thread::Builder::new()
+            .name("gltf import".into())
+            .spawn({
+                let model = model.clone();
+                let progress = progress.clone();
+                move || {
+                    use rayon::prelude::*;
+
+                    let pool = rayon::ThreadPoolBuilder::new()
+                        .num_threads(max_in_flight)
+                        .thread_name(|i| format!("gltf import {i}"))
+                        .build()
+                        .expect("unable to create import thread pool");
+
+                    // Stops early if the import is cancelled or the stream is dropped
+                    let _ = pool.install(|| {
+                        work.into_par_iter().try_for_each(|work| {
+                            if progress.is_cancelled() {
+                                return Err(());
+                            }
+
+                            let item = model.load_item(work, &primitives, options);
+                            send.send(item).map_err(|_| ())
+                        })
+                    });
+                }
+            })
+            .expect("unable to spawn import thread");
+
+        Ok(GltfImportStream {
+            lights,
+            mesh_groups,
+            skins,
+            animations,
+            roots,
+            material_count,
+            mesh_count,
+            texture_count,
+            items,
+            progress,
+            model,
+            worker: Some(worker),
+        })
+    }
+
+    /// Gets the next item without waiting. Returns `None` if no item is ready yet, or if the
+    /// stream is finished. Use [`GltfImportStream::is_finished`] to tell them apart.
+    pub fn try_next(&mut self) -> Option<Result<GltfImportItem, GltfModelParseError>> {
+        if self.progress.is_cancelled() {
+            return None;
+        }
+
+        let item = self.items.try_recv().ok()?;
+        self.progress.0.items_done.fetch_add(1, Ordering::Relaxed);
+        Some(item)
+    }
+
+    /// `true` if every item has been received or the import was cancelled.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        let progress = self.progress();
+        self.progress.is_cancelled() || progress.items_done == progress.items_total
+    }
+
+    #[inline(always)]
+    pub fn progress(&self) -> ImportProgress {
+        self.progress.get()
+    }
+
+    /// Gets a handle that can be used to poll progress or cancel the import from elsewhere.
+    #[inline(always)]
+    pub fn progress_handle(&self) -> ImportProgressHandle {
+        self.progress.clone()
+    }
+
+    /// Problems found so far. More may be found until the stream is finished.
+    #[inline]
+    pub fn diagnostics(&self) -> GltfImportDiagnostics {
+        self.model.warnings.diagnostics()
+    }
+
+    /// Stops loading items. Items that are being loaded are finished and then discarded, and no
+    /// more items are yielded.
+    #[inline(always)]
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+
+    /// Cancels the import and waits for items that are being loaded to finish, so no more work
+    /// is done in the background once this returns.
+    pub fn cancel_and_wait(mut self) {
+        let worker = self.worker.take();
+        std::mem::drop(self);
+        if let Some(worker) = worker {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Iterator for GltfImportStream {
+    type Item = Result<GltfImportItem, GltfModelParseError>;
+
+    /// Waits for the next item. Returns `None` once every item has been received or the import
+    /// was cancelled.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.progress.is_cancelled() {
+            return None;
+        }
+
+        let item = self.items.recv().ok()?;
+        self.progress.0.items_done.fetch_add(1, Ordering::Relaxed);
+        Some(item)
+    }
+}
+
+impl Drop for GltfImportStream {
+    fn drop(&mut self) {
+        // Threads waiting to send an item stop once the receiver is dropped
+        self.cancel();
+    }
+}
+
+impl ImportProgressHandle {
+    pub fn get(&self) -> ImportProgress {
+        ImportProgress {
+            items_done: self.0.items_done.load(Ordering::Relaxed),
+            items_total: self.0.items_total,
+            bytes_uploaded: self.0.bytes_uploaded.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Records that `bytes` of item data were uploaded. The stream doesn't upload anything
+    /// itself, so this is up to whoever receives the items.
+    #[inline(always)]
+    pub fn report_upload(&self, bytes: u64) {
+        self.0.bytes_uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Cancels the import. See [`GltfImportStream::cancel`].
+    #[inline(always)]
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl ImportProgress {
+    /// How much of the import is done, from `0.0` to `1.0`.
+    #[inline]
+    pub fn fraction(&self) -> f32 {
+        if self.items_total == 0 {
+            1.0
+        } else {
+            self.items_done as f32 / self.items_total as f32
+        }
+    }
+}
+
+impl ParsedModel {
+    fn load_item(
+        &self,
+        work: StreamWork,
+        primitives: &[Primitive],
+        options: GltfImportOptions,
+    ) -> Result<GltfImportItem, GltfModelParseError> {
+        match work {
+            StreamWork::Material(index) => {
+                let gltf_idx = *self.mapping.materials.get(&index).unwrap();
+                Ok(GltfImportItem::Material {
+                    index,
+                    material: load_gltf_material(
+                        &self.gltf,
+                        &self.extensions,
+                        &self.inv_mapping,
+                        gltf_idx,
+                    ),
+                })
+            }
+            StreamWork::Mesh(index) => Ok(GltfImportItem::Mesh {
+                index,
+                mesh: load_gltf_mesh(&primitives[index], &self.buffers, options, &self.warnings),
+            }),
+            StreamWork::Texture(index) => {
+                let (gltf_idx, usage) = *self.mapping.textures.get(&index).unwrap();
+                let texture = load_gltf_texture(
+                    &self.gltf,
+                    gltf_idx,
+                    usage,
+                    &self.buffers,
+                    &*self.loader,
+                    &self.warnings,
+                )?;
+                let data = read_image_source(&texture.source, &self.buffers, &*self.loader)?;
+                Ok(GltfImportItem::Texture {
+                    index,
+                    data: data.into_owned(),
+                    texture,
+                })
+            }
+        }
+    }
+}
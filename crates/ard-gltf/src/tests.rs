@@ -0,0 +1,1139 @@
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+};
+
+use base64::Engine;
+
+use crate::*;
+
+const POSITIONS: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+const INDICES: [u16; 3] = [0, 1, 2];
+
+/// Not a real image, but the loader only needs the header to determine the format.
+const IMAGE: &[u8] = &[
+    0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n', 1, 2, 3, 4,
+];
+
+/// Everything in the test model except for the buffers, buffer views, and images, which depend
+/// on how the model is stored.
+const MODEL: &str = r#"
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [{ "name": "triangle", "mesh": 0 }],
+    "meshes": [{
+        "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }]
+    }],
+    "materials": [{ "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } }],
+    "textures": [{ "source": 0 }],
+    "accessors": [
+        {
+            "bufferView": 0,
+            "componentType": 5126,
+            "count": 3,
+            "type": "VEC3",
+            "min": [0.0, 0.0, 0.0],
+            "max": [1.0, 1.0, 0.0]
+        },
+        { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+    ]"#;
+
+fn position_bytes() -> Vec<u8> {
+    bytemuck::cast_slice(&POSITIONS).to_vec()
+}
+
+fn index_bytes() -> Vec<u8> {
+    bytemuck::cast_slice(&INDICES).to_vec()
+}
+
+/// Builds the test model as a single GLB file.
+fn make_glb() -> Vec<u8> {
+    let positions = position_bytes();
+    let indices = index_bytes();
+
+    let mut bin = Vec::default();
+    bin.extend_from_slice(&positions);
+    bin.extend_from_slice(&indices);
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let image_offset = bin.len();
+    bin.extend_from_slice(IMAGE);
+
+    let json = format!(
+        r#"{{{MODEL},
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": {} }},
+                {{ "buffer": 0, "byteOffset": {}, "byteLength": {} }},
+                {{ "buffer": 0, "byteOffset": {image_offset}, "byteLength": {} }}
+            ],
+            "images": [{{ "bufferView": 2, "mimeType": "image/png" }}]
+        }}"#,
+        bin.len(),
+        positions.len(),
+        positions.len(),
+        indices.len(),
+        IMAGE.len(),
+    );
+    glb(&json, &bin)
+}
+
+/// Packs JSON and a binary chunk into a GLB file.
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = bin.to_vec();
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let mut glb = Vec::default();
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+    glb
+}
+
+/// Builds the test model as a GLTF file. Positions are stored in an external buffer with a
+/// percent-encoded name, indices in a data URI, and the image in an external file without a
+/// MIME type.
+fn make_gltf() -> String {
+    let positions = position_bytes();
+    let indices = index_bytes();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&indices);
+
+    format!(
+        r#"{{{MODEL},
+            "buffers": [
+                {{ "uri": "buffers/tri%20angle.bin", "byteLength": {} }},
+                {{ "uri": "data:application/octet-stream;base64,{encoded}", "byteLength": {} }}
+            ],
+            "bufferViews": [
+                {{ "buffer": 0, "byteLength": {} }},
+                {{ "buffer": 1, "byteLength": {} }}
+            ],
+            "images": [{{ "uri": "./albedo.png" }}]
+        }}"#,
+        positions.len(),
+        indices.len(),
+        positions.len(),
+        indices.len(),
+    )
+}
+
+/// Creates an empty directory for a test to write files into.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ard-gltf-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn assert_same_model(a: &GltfModel, b: &GltfModel) {
+    assert_eq!(a.meshes.len(), b.meshes.len());
+    for (a, b) in a.meshes.iter().zip(b.meshes.iter()) {
+        assert_eq!(a.indices, b.indices);
+        assert_eq!(a.positions, b.positions);
+        assert_eq!(a.normals, b.normals);
+        assert_eq!(a.tangents, b.tangents);
+        assert_eq!(a.colors, b.colors);
+        assert_eq!(a.uv0, b.uv0);
+    }
+
+    assert_eq!(a.textures.len(), b.textures.len());
+    for i in 0..a.textures.len() {
+        assert_eq!(a.texture_data(i).unwrap(), b.texture_data(i).unwrap());
+        assert_eq!(a.textures[i].src_format, b.textures[i].src_format);
+        assert_eq!(a.textures[i].usage, b.textures[i].usage);
+        assert_eq!(a.textures[i].mips, b.textures[i].mips);
+    }
+
+    assert_eq!(a.materials.len(), b.materials.len());
+    assert_eq!(a.mesh_groups.len(), b.mesh_groups.len());
+    assert_eq!(a.roots.len(), b.roots.len());
+    for (a, b) in a.roots.iter().zip(b.roots.iter()) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.model, b.model);
+    }
+}
+
+#[test]
+fn glb_from_slice() {
+    let model = GltfModel::from_slice(&make_glb()).unwrap();
+
+    assert_eq!(model.meshes.len(), 1);
+    assert_eq!(model.meshes[0].indices, vec![0, 1, 2]);
+    assert_eq!(model.meshes[0].positions[1].truncate(), Vec3::X);
+    assert_eq!(model.textures.len(), 1);
+    assert_eq!(model.texture_data(0).unwrap(), IMAGE);
+    assert_eq!(model.textures[0].usage, TextureUsage::Diffuse);
+}
+
+#[test]
+fn gltf_matches_glb() {
+    let dir = test_dir("gltf_matches_glb");
+    std::fs::create_dir_all(dir.join("buffers")).unwrap();
+    std::fs::write(dir.join("buffers/tri angle.bin"), position_bytes()).unwrap();
+    std::fs::write(dir.join("albedo.png"), IMAGE).unwrap();
+    std::fs::write(dir.join("model.gltf"), make_gltf()).unwrap();
+
+    let glb = GltfModel::from_slice(&make_glb()).unwrap();
+    let gltf = GltfModel::from_gltf(&dir.join("model.gltf")).unwrap();
+    assert_same_model(&glb, &gltf);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn gltf_with_loader() {
+    let gltf = make_gltf();
+    let model = GltfModel::from_slice_with_loader(gltf.as_bytes(), |uri| match uri {
+        "buffers/tri angle.bin" => Ok(position_bytes()),
+        "./albedo.png" => Ok(IMAGE.to_vec()),
+        _ => Err(std::io::ErrorKind::NotFound.into()),
+    })
+    .unwrap();
+
+    assert_same_model(&GltfModel::from_slice(&make_glb()).unwrap(), &model);
+}
+
+/// Embedded images are borrowed from the model and external images are only read when requested.
+#[test]
+fn texture_data_is_lazy() {
+    let model = GltfModel::from_slice(&make_glb()).unwrap();
+    assert!(matches!(
+        model.textures[0].source,
+        GltfImageSource::Buffer { .. }
+    ));
+    assert!(matches!(
+        model.texture_data(0).unwrap(),
+        Cow::Borrowed(IMAGE)
+    ));
+
+    let reads = Arc::new(AtomicUsize::new(0));
+    let model = GltfModel::from_slice_with_loader(make_gltf().as_bytes(), {
+        let reads = reads.clone();
+        move |uri| match uri {
+            "buffers/tri angle.bin" => Ok(position_bytes()),
+            "./albedo.png" => {
+                reads.fetch_add(1, Ordering::Relaxed);
+                Ok(IMAGE.to_vec())
+            }
+            _ => Err(std::io::ErrorKind::NotFound.into()),
+        }
+    })
+    .unwrap();
+    assert_eq!(
+        model.textures[0].source,
+        GltfImageSource::Uri("./albedo.png".into())
+    );
+    assert_eq!(model.textures[0].src_format, TextureSourceFormat::Png);
+    assert_eq!(reads.load(Ordering::Relaxed), 0);
+
+    assert_eq!(model.texture_data(0).unwrap(), IMAGE);
+    assert_eq!(reads.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn for_each_texture_visits_all() {
+    let model = GltfModel::from_slice(&make_glb()).unwrap();
+    let visited = Mutex::new(Vec::default());
+    model
+        .for_each_texture(1, |i, texture, data| {
+            assert_eq!(texture.usage, TextureUsage::Diffuse);
+            assert_eq!(data, IMAGE);
+            visited.lock().unwrap().push(i);
+        })
+        .unwrap();
+    assert_eq!(visited.into_inner().unwrap(), vec![0]);
+}
+
+#[test]
+fn external_files_need_loader() {
+    let gltf = make_gltf();
+    match GltfModel::from_slice(gltf.as_bytes()) {
+        Err(GltfModelParseError::Io { uri, .. }) => assert_eq!(uri, "buffers/tri angle.bin"),
+        _ => panic!("expected an io error"),
+    }
+}
+
+#[test]
+fn source_format_detection() {
+    assert_eq!(
+        texture_source_format(Some("image/jpeg"), IMAGE),
+        Some(TextureSourceFormat::Jpeg)
+    );
+    assert_eq!(
+        texture_source_format(None, IMAGE),
+        Some(TextureSourceFormat::Png)
+    );
+    assert_eq!(
+        texture_source_format(None, &[0xFF, 0xD8, 0xFF, 0xE0]),
+        Some(TextureSourceFormat::Jpeg)
+    );
+    assert_eq!(texture_source_format(Some("image/ktx2"), IMAGE), None);
+    assert_eq!(texture_source_format(None, &[0, 1, 2, 3]), None);
+}
+
+/// A single triangle skinned to a chain of two joints, laid out like the RiggedSimple sample.
+/// Joints are `u16` and weights are normalized `u8` to exercise the conversions.
+#[test]
+fn skinned_mesh() {
+    let joints: [[u16; 4]; 3] = [[0, 0, 0, 0], [0, 1, 0, 0], [1, 0, 0, 0]];
+    let weights: [[u8; 4]; 3] = [[255, 0, 0, 0], [51, 204, 0, 0], [255, 0, 0, 0]];
+    let inverse_bind_matrices = [
+        Mat4::IDENTITY,
+        Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0)),
+    ];
+
+    let mut bin = Vec::default();
+    bin.extend_from_slice(&position_bytes());
+    bin.extend_from_slice(&index_bytes());
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let joints_offset = bin.len();
+    bin.extend_from_slice(bytemuck::cast_slice(&joints));
+    let weights_offset = bin.len();
+    bin.extend_from_slice(bytemuck::cast_slice(&weights));
+    let ibm_offset = bin.len();
+    bin.extend_from_slice(bytemuck::cast_slice(&inverse_bind_matrices));
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0, 1] }}],
+            "nodes": [
+                {{ "name": "mesh", "mesh": 0, "skin": 0 }},
+                {{ "name": "root", "children": [2] }},
+                {{ "name": "tip", "translation": [0.0, 1.0, 0.0] }}
+            ],
+            "skins": [{{ "joints": [1, 2], "inverseBindMatrices": 4 }}],
+            "meshes": [{{
+                "primitives": [{{
+                    "attributes": {{ "POSITION": 0, "JOINTS_0": 2, "WEIGHTS_0": 3 }},
+                    "indices": 1,
+                    "material": 0
+                }}]
+            }}],
+            "materials": [{{}}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                {{ "bufferView": 2, "componentType": 5123, "count": 3, "type": "VEC4" }},
+                {{
+                    "bufferView": 3,
+                    "componentType": 5121,
+                    "normalized": true,
+                    "count": 3,
+                    "type": "VEC4"
+                }},
+                {{ "bufferView": 4, "componentType": 5126, "count": 2, "type": "MAT4" }}
+            ],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                {{ "buffer": 0, "byteOffset": {joints_offset}, "byteLength": 24 }},
+                {{ "buffer": 0, "byteOffset": {weights_offset}, "byteLength": 12 }},
+                {{ "buffer": 0, "byteOffset": {ibm_offset}, "byteLength": 128 }}
+            ]
+        }}"#,
+        bin.len(),
+    );
+
+    let model = GltfModel::from_slice(&glb(&json, &bin)).unwrap();
+
+    // Skin
+    assert_eq!(model.skins.len(), 1);
+    assert_eq!(model.skins[0].joints, vec![NodeIndex(1), NodeIndex(2)]);
+    assert_eq!(
+        model.skins[0].inverse_bind_matrices,
+        inverse_bind_matrices.to_vec()
+    );
+
+    // Nodes reference the skin and joints can be found by index
+    assert_eq!(model.roots.len(), 2);
+    assert!(matches!(
+        model.roots[0].data,
+        GltfNodeData::SkinnedMeshGroup {
+            mesh_group: 0,
+            skin: 0
+        }
+    ));
+    assert_eq!(model.roots[1].index, NodeIndex(1));
+    assert_eq!(model.roots[1].children[0].index, NodeIndex(2));
+
+    // Vertex attributes
+    let mesh = &model.meshes[0];
+    assert_eq!(
+        mesh.joints.as_ref().unwrap(),
+        &vec![UVec4::new(0, 0, 0, 0), UVec4::new(0, 1, 0, 0), UVec4::X]
+    );
+    let weights = mesh.weights.as_ref().unwrap();
+    assert_eq!(weights[0], Vec4::X);
+    assert!(weights[1].abs_diff_eq(Vec4::new(0.2, 0.8, 0.0, 0.0), 1e-6));
+    assert_eq!(weights[2], Vec4::X);
+}
+
+/// Builds a model with a single node animated by the given samplers and channels. All accessors
+/// read from a single buffer view covering `bin`.
+fn animated_glb(accessors: &str, samplers: &str, channels: &str, bin: &[u8]) -> Vec<u8> {
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "name": "spinner" }}],
+            "animations": [{{
+                "name": "spin",
+                "samplers": [{samplers}],
+                "channels": [{channels}]
+            }}],
+            "accessors": [{accessors}],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [{{ "buffer": 0, "byteLength": {} }}]
+        }}"#,
+        bin.len(),
+        bin.len(),
+    );
+    glb(&json, bin)
+}
+
+/// A looping rotation about Y with linear keyframes, plus a cubic spline translation whose
+/// tangents must come through untouched.
+#[test]
+fn animation() {
+    let times = [0.0f32, 1.0, 2.0];
+    let rotations = [
+        Quat::IDENTITY,
+        Quat::from_rotation_y(std::f32::consts::PI),
+        Quat::from_rotation_y(std::f32::consts::TAU),
+    ];
+    let spline_times = [0.0f32, 2.0];
+    let spline = [
+        // In tangent, value, out tangent for each keyframe
+        Vec3::ZERO,
+        Vec3::ZERO,
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(-1.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        Vec3::ZERO,
+    ];
+
+    let mut bin = Vec::default();
+    bin.extend_from_slice(bytemuck::cast_slice(&times));
+    let rotations_offset = bin.len();
+    for rotation in rotations {
+        bin.extend_from_slice(bytemuck::cast_slice(&rotation.to_array()));
+    }
+    let spline_times_offset = bin.len();
+    bin.extend_from_slice(bytemuck::cast_slice(&spline_times));
+    let spline_offset = bin.len();
+    for value in spline {
+        bin.extend_from_slice(bytemuck::cast_slice(&value.to_array()));
+    }
+
+    let accessors = format!(
+        r#"
+        {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "SCALAR",
+           "min": [0.0], "max": [2.0] }},
+        {{ "bufferView": 0, "byteOffset": {rotations_offset}, "componentType": 5126,
+           "count": 3, "type": "VEC4" }},
+        {{ "bufferView": 0, "byteOffset": {spline_times_offset}, "componentType": 5126,
+           "count": 2, "type": "SCALAR", "min": [0.0], "max": [2.0] }},
+        {{ "bufferView": 0, "byteOffset": {spline_offset}, "componentType": 5126,
+           "count": 6, "type": "VEC3" }}"#
+    );
+    let samplers = r#"
+        { "input": 0, "output": 1, "interpolation": "LINEAR" },
+        { "input": 2, "output": 3, "interpolation": "CUBICSPLINE" }"#;
+    let channels = r#"
+        { "sampler": 0, "target": { "node": 0, "path": "rotation" } },
+        { "sampler": 1, "target": { "node": 0, "path": "translation" } }"#;
+
+    let model = GltfModel::from_slice(&animated_glb(&accessors, samplers, channels, &bin)).unwrap();
+
+    assert_eq!(model.animations.len(), 1);
+    let animation = &model.animations[0];
+    assert_eq!(animation.name, "spin");
+    assert_eq!(animation.channels.len(), 2);
+
+    let rotation = &animation.channels[0];
+    assert_eq!(rotation.target, model.roots[0].index);
+    assert_eq!(rotation.interpolation, GltfInterpolation::Linear);
+    assert_eq!(rotation.times, times);
+    match &rotation.values {
+        GltfAnimationValues::Rotation(values) => assert_eq!(values, &rotations),
+        _ => panic!("expected rotation keyframes"),
+    }
+
+    let translation = &animation.channels[1];
+    assert_eq!(translation.interpolation, GltfInterpolation::CubicSpline);
+    assert_eq!(translation.times, spline_times);
+    match &translation.values {
+        GltfAnimationValues::Translation(values) => assert_eq!(values, &spline),
+        _ => panic!("expected translation keyframes"),
+    }
+}
+
+#[test]
+fn sparse_animation_fails() {
+    let times = [0.0f32, 1.0];
+    let scales = [Vec3::ONE, Vec3::ONE];
+    let sparse_indices = [1u32];
+    let sparse_values = [Vec3::splat(2.0)];
+
+    let mut bin = Vec::default();
+    bin.extend_from_slice(bytemuck::cast_slice(&times));
+    let scales_offset = bin.len();
+    for scale in scales {
+        bin.extend_from_slice(bytemuck::cast_slice(&scale.to_array()));
+    }
+    let indices_offset = bin.len();
+    bin.extend_from_slice(bytemuck::cast_slice(&sparse_indices));
+    let values_offset = bin.len();
+    bin.extend_from_slice(bytemuck::cast_slice(&sparse_values[0].to_array()));
+
+    let accessors = format!(
+        r#"
+        {{ "bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR",
+           "min": [0.0], "max": [1.0] }},
+        {{ "bufferView": 0, "byteOffset": {scales_offset}, "componentType": 5126,
+           "count": 2, "type": "VEC3",
+           "sparse": {{
+               "count": 1,
+               "indices": {{ "bufferView": 0, "byteOffset": {indices_offset},
+                             "componentType": 5125 }},
+               "values": {{ "bufferView": 0, "byteOffset": {values_offset} }}
+           }} }}"#
+    );
+    let samplers = r#"{ "input": 0, "output": 1 }"#;
+    let channels = r#"{ "sampler": 0, "target": { "node": 0, "path": "scale" } }"#;
+
+    match GltfModel::from_slice(&animated_glb(&accessors, samplers, channels, &bin)) {
+        Err(GltfModelParseError::SparseAccessor(1)) => {}
+        _ => panic!("expected a sparse accessor error"),
+    }
+}
+
+/// Emissive and occlusion maps are imported, with occlusion sharing the metallic roughness map
+/// like most ORM textures do.
+#[test]
+fn emissive_and_occlusion() {
+    let mut bin = Vec::default();
+    bin.extend_from_slice(&position_bytes());
+    bin.extend_from_slice(&index_bytes());
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let image_offset = bin.len();
+    bin.extend_from_slice(IMAGE);
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "mesh": 0 }}],
+            "meshes": [{{
+                "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }}]
+            }}],
+            "materials": [{{
+                "pbrMetallicRoughness": {{ "metallicRoughnessTexture": {{ "index": 1 }} }},
+                "emissiveFactor": [1.0, 0.5, 0.25],
+                "emissiveTexture": {{ "index": 0 }},
+                "occlusionTexture": {{ "index": 1, "strength": 0.5 }}
+            }}],
+            "textures": [{{ "source": 0 }}, {{ "source": 0 }}],
+            "images": [{{ "bufferView": 2, "mimeType": "image/png" }}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+            ],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                {{ "buffer": 0, "byteOffset": {image_offset}, "byteLength": {} }}
+            ]
+        }}"#,
+        bin.len(),
+        IMAGE.len(),
+    );
+
+    let model = GltfModel::from_slice(&glb(&json, &bin)).unwrap();
+    assert_eq!(model.textures.len(), 2);
+
+    let GltfMaterial::Pbr {
+        metallic_roughness_map,
+        emissive_factor,
+        emissive_map,
+        occlusion_strength,
+        occlusion_map,
+        ..
+    } = &model.materials[0];
+    assert_eq!(*emissive_factor, Vec3::new(1.0, 0.5, 0.25));
+    assert_eq!(*occlusion_strength, 0.5);
+    assert_eq!(occlusion_map, metallic_roughness_map);
+
+    let emissive = &model.textures[emissive_map.unwrap().texture];
+    assert_eq!(emissive.usage, TextureUsage::Emissive);
+    assert_eq!(emissive.usage.into_format(), Format::Rgba8Srgb);
+}
+
+/// `KHR_texture_transform` and `KHR_materials_emissive_strength` are applied, materials without
+/// them are left alone, and unknown extensions are ignored.
+#[test]
+fn texture_transform_and_emissive_strength() {
+    let mut bin = Vec::default();
+    bin.extend_from_slice(&position_bytes());
+    bin.extend_from_slice(&index_bytes());
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let image_offset = bin.len();
+    bin.extend_from_slice(IMAGE);
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "extensionsUsed": [
+                "KHR_texture_transform",
+                "KHR_materials_emissive_strength",
+                "EXT_made_up"
+            ],
+            "scene": 0,
+            "scenes": [{{ "nodes": [0, 1] }}],
+            "nodes": [{{ "mesh": 0 }}, {{ "mesh": 1 }}],
+            "meshes": [
+                {{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }}] }},
+                {{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 1 }}] }}
+            ],
+            "materials": [
+                {{
+                    "pbrMetallicRoughness": {{
+                        "baseColorTexture": {{
+                            "index": 0,
+                            "extensions": {{
+                                "KHR_texture_transform": {{
+                                    "offset": [0.5, 0.25],
+                                    "rotation": 1.5707964,
+                                    "scale": [2.0, 4.0],
+                                    "texCoord": 1
+                                }},
+                                "EXT_made_up": {{ "value": 1 }}
+                            }}
+                        }}
+                    }},
+                    "emissiveFactor": [1.0, 0.5, 0.25],
+                    "emissiveTexture": {{ "index": 0 }},
+                    "extensions": {{
+                        "KHR_materials_emissive_strength": {{ "emissiveStrength": 4.0 }},
+                        "EXT_made_up": {{}}
+                    }}
+                }},
+                {{
+                    "pbrMetallicRoughness": {{ "baseColorTexture": {{ "index": 0 }} }},
+                    "emissiveFactor": [1.0, 0.5, 0.25]
+                }}
+            ],
+            "textures": [{{ "source": 0 }}],
+            "images": [{{ "bufferView": 2, "mimeType": "image/png" }}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+            ],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                {{ "buffer": 0, "byteOffset": {image_offset}, "byteLength": {} }}
+            ]
+        }}"#,
+        bin.len(),
+        IMAGE.len(),
+    );
+
+    let model = GltfModel::from_slice(&glb(&json, &bin)).unwrap();
+    assert_eq!(model.materials.len(), 2);
+
+    // Find the materials by whether or not they have a transform, since material order isn't
+    // guaranteed to match the GLTF.
+    let (extended, plain): (Vec<_>, Vec<_>) = model.materials.iter().partition(|material| {
+        let GltfMaterial::Pbr { diffuse_map, .. } = material;
+        diffuse_map.unwrap().transform != GltfTextureTransform::default()
+    });
+    assert_eq!(extended.len(), 1);
+    assert_eq!(plain.len(), 1);
+
+    let GltfMaterial::Pbr {
+        diffuse_map,
+        emissive_factor,
+        emissive_map,
+        ..
+    } = extended[0];
+    let transform = diffuse_map.unwrap().transform;
+    assert_eq!(transform.offset, Vec2::new(0.5, 0.25));
+    assert_eq!(transform.rotation, 1.5707964);
+    assert_eq!(transform.scale, Vec2::new(2.0, 4.0));
+    assert_eq!(transform.tex_coord, Some(1));
+    assert_eq!(
+        emissive_map.unwrap().transform,
+        GltfTextureTransform::default()
+    );
+    assert_eq!(*emissive_factor, Vec3::new(4.0, 2.0, 1.0));
+
+    // Rotated a quarter turn, so U maps onto -V before scaling and offsetting.
+    let uv = extended[0]
+        .uv_transform()
+        .transform_point2(Vec2::new(1.0, 0.0));
+    assert!(uv.abs_diff_eq(Vec2::new(0.5, -1.75), 1e-5), "{uv}");
+
+    let GltfMaterial::Pbr {
+        emissive_factor, ..
+    } = plain[0];
+    assert_eq!(*emissive_factor, Vec3::new(1.0, 0.5, 0.25));
+    assert_eq!(plain[0].uv_transform(), Mat3::IDENTITY);
+}
+
+/// A unit quad in the XY plane facing +Z.
+fn quad(uvs: [Vec2; 4]) -> GltfMesh {
+    GltfMesh {
+        indices: vec![0, 1, 2, 2, 1, 3],
+        positions: vec![
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(0.0, 1.0, 0.0, 1.0),
+            Vec4::new(1.0, 1.0, 0.0, 1.0),
+        ],
+        normals: Some(vec![Vec4::Z; 4]),
+        uv0: Some(uvs.to_vec()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn generate_tangents() {
+    let mut mesh = quad([
+        Vec2::new(0.0, 1.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(0.0, 0.0),
+        Vec2::new(1.0, 0.0),
+    ]);
+    assert!(mesh.generate_tangents());
+    assert_eq!(mesh.tangents, Some(vec![Vec4::new(1.0, 0.0, 0.0, 1.0); 4]));
+
+    // Mirroring U flips both the tangent and the handedness
+    let mut mirrored = quad([
+        Vec2::new(1.0, 1.0),
+        Vec2::new(0.0, 1.0),
+        Vec2::new(1.0, 0.0),
+        Vec2::new(0.0, 0.0),
+    ]);
+    assert!(mirrored.generate_tangents());
+    assert_eq!(
+        mirrored.tangents,
+        Some(vec![Vec4::new(-1.0, 0.0, 0.0, -1.0); 4])
+    );
+
+    // Tangents need UVs
+    let mut no_uvs = GltfMesh {
+        uv0: None,
+        ..quad([Vec2::ZERO; 4])
+    };
+    assert!(!no_uvs.generate_tangents());
+    assert!(no_uvs.tangents.is_none());
+}
+
+/// Builds a GLB with a single mesh from the given vertices and indices.
+fn mesh_glb(
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    uvs: &[[f32; 2]],
+    indices: &[u16],
+) -> Vec<u8> {
+    let mut bin = Vec::default();
+    let mut view = |data: &[u8]| {
+        let offset = bin.len();
+        bin.extend_from_slice(data);
+        bin.resize(bin.len().next_multiple_of(4), 0);
+        format!(
+            r#"{{ "buffer": 0, "byteOffset": {offset}, "byteLength": {} }}"#,
+            data.len()
+        )
+    };
+    let views = [
+        view(bytemuck::cast_slice(positions)),
+        view(bytemuck::cast_slice(normals)),
+        view(bytemuck::cast_slice(uvs)),
+        view(bytemuck::cast_slice(indices)),
+    ];
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "mesh": 0 }}],
+            "meshes": [{{
+                "primitives": [{{
+                    "attributes": {{ "POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2 }},
+                    "indices": 3,
+                    "material": 0
+                }}]
+            }}],
+            "materials": [{{}}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": {},
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5126, "count": {}, "type": "VEC3" }},
+                {{ "bufferView": 2, "componentType": 5126, "count": {}, "type": "VEC2" }},
+                {{ "bufferView": 3, "componentType": 5123, "count": {}, "type": "SCALAR" }}
+            ],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [{}]
+        }}"#,
+        positions.len(),
+        normals.len(),
+        uvs.len(),
+        indices.len(),
+        bin.len(),
+        views.join(", "),
+    );
+
+    glb(&json, &bin)
+}
+
+#[test]
+fn generate_tangents_option() {
+    let data = mesh_glb(
+        &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        &[[0.0, 0.0, 1.0]; 3],
+        &[[0.0, 1.0], [1.0, 1.0], [0.0, 0.0]],
+        &[0, 1, 2],
+    );
+
+    let model = GltfModel::from_slice(&data).unwrap();
+    assert!(model.meshes[0].tangents.is_none());
+
+    let options = GltfImportOptions {
+        generate_tangents: true,
+    };
+    let model = GltfModel::from_slice_with_options(&data, options).unwrap();
+    assert_eq!(
+        model.meshes[0].tangents,
+        Some(vec![Vec4::new(1.0, 0.0, 0.0, 1.0); 3])
+    );
+    assert!(model.diagnostics().is_empty());
+}
+
+#[test]
+fn invalid_vertex_data_warnings() {
+    let data = mesh_glb(
+        &[
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [f32::NAN, 0.0, 0.0],
+        ],
+        &[[0.0, 0.0, 1.0]; 4],
+        &[[0.0, 0.0]; 4],
+        // One good triangle, one degenerate triangle, and one triangle past the vertices
+        &[0, 1, 2, 0, 0, 1, 0, 1, 9],
+    );
+
+    let options = GltfImportOptions {
+        generate_tangents: true,
+    };
+    let model = GltfModel::from_slice_with_options(&data, options).unwrap();
+    assert_eq!(
+        model.diagnostics().entries(),
+        &[
+            GltfImportWarning::NonFinitePositions {
+                mesh: 0,
+                primitive: 0,
+                count: 1,
+            },
+            GltfImportWarning::DegenerateTriangles {
+                mesh: 0,
+                primitive: 0,
+                count: 1,
+            },
+            GltfImportWarning::IndicesOutOfRange {
+                mesh: 0,
+                primitive: 0,
+                count: 1,
+                vertex_count: 4,
+            },
+        ]
+    );
+
+    // Bad triangles are skipped, so tangents are still generated
+    assert_eq!(model.meshes[0].tangents.as_ref().map(Vec::len), Some(4));
+}
+
+/// Warnings found by loaders running in parallel all end up in the diagnostics, in order.
+#[test]
+fn import_diagnostics() {
+    let mut bin = Vec::default();
+    bin.extend_from_slice(&position_bytes());
+    bin.extend_from_slice(&[0, 1, 2, 0]);
+    let image_offset = bin.len();
+    bin.extend_from_slice(IMAGE);
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "mesh": 0 }}],
+            "meshes": [{{
+                "primitives": [
+                    {{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }},
+                    {{ "attributes": {{ "POSITION": 0 }} }}
+                ]
+            }}],
+            "materials": [{{
+                "pbrMetallicRoughness": {{ "baseColorTexture": {{ "index": 0 }} }},
+                "normalTexture": {{ "index": 0 }}
+            }}],
+            "textures": [{{ "source": 0 }}],
+            "images": [{{ "bufferView": 2, "mimeType": "image/png" }}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5121, "count": 3, "type": "SCALAR" }}
+            ],
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                {{ "buffer": 0, "byteOffset": 36, "byteLength": 3 }},
+                {{ "buffer": 0, "byteOffset": {image_offset}, "byteLength": {} }}
+            ]
+        }}"#,
+        bin.len(),
+        IMAGE.len(),
+    );
+
+    let model = GltfModel::from_slice(&glb(&json, &bin)).unwrap();
+    assert_eq!(
+        model.diagnostics().entries(),
+        &[
+            GltfImportWarning::MissingIndices {
+                mesh: 0,
+                primitive: 1,
+            },
+            GltfImportWarning::MissingMaterial {
+                mesh: 0,
+                primitive: 1,
+            },
+            GltfImportWarning::UnsupportedIndexType {
+                mesh: 0,
+                primitive: 0,
+                component_type: 5121,
+            },
+            GltfImportWarning::TextureUsageConflict {
+                texture: 0,
+                old: TextureUsage::Diffuse,
+                new: TextureUsage::Normal,
+            },
+        ]
+    );
+}
+
+/// Streaming yields the same resources as loading the whole model.
+#[test]
+fn stream_yields_every_item() {
+    let model = GltfModel::from_slice(&make_glb()).unwrap();
+    let mut stream =
+        GltfImportStream::from_vec(make_glb(), |_| unreachable!(), Default::default(), 2).unwrap();
+
+    assert_eq!(stream.roots.len(), 1);
+    assert_eq!(stream.mesh_groups.len(), 1);
+    assert_eq!(
+        (
+            stream.material_count,
+            stream.mesh_count,
+            stream.texture_count
+        ),
+        (1, 1, 1)
+    );
+    assert_eq!(
+        stream.progress(),
+        ImportProgress {
+            items_done: 0,
+            items_total: 3,
+            bytes_uploaded: 0,
+        }
+    );
+
+    let mut items = [0; 3];
+    for item in &mut stream {
+        match item.unwrap() {
+            GltfImportItem::Material { index, .. } => items[0] += index + 1,
+            GltfImportItem::Mesh { index, mesh } => {
+                items[1] += index + 1;
+                assert_eq!(mesh.indices, model.meshes[index].indices);
+                assert_eq!(mesh.positions, model.meshes[index].positions);
+            }
+            GltfImportItem::Texture {
+                index,
+                texture,
+                data,
+            } => {
+                items[2] += index + 1;
+                assert_eq!(texture.usage, model.textures[index].usage);
+                assert_eq!(data, IMAGE);
+            }
+        }
+    }
+    assert_eq!(items, [1, 1, 1]);
+
+    stream.progress_handle().report_upload(64);
+    assert!(stream.is_finished());
+    assert_eq!(
+        stream.progress(),
+        ImportProgress {
+            items_done: 3,
+            items_total: 3,
+            bytes_uploaded: 64,
+        }
+    );
+    assert_eq!(stream.progress().fraction(), 1.0);
+}
+
+/// A model whose material uses four external images, so every texture needs the loader.
+fn external_textures_gltf() -> String {
+    let positions = base64::engine::general_purpose::STANDARD.encode(position_bytes());
+    let indices = base64::engine::general_purpose::STANDARD.encode(index_bytes());
+
+    format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [{{ "mesh": 0 }}],
+            "meshes": [{{
+                "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }}]
+            }}],
+            "materials": [{{
+                "pbrMetallicRoughness": {{
+                    "baseColorTexture": {{ "index": 0 }},
+                    "metallicRoughnessTexture": {{ "index": 1 }}
+                }},
+                "normalTexture": {{ "index": 2 }},
+                "emissiveTexture": {{ "index": 3 }}
+            }}],
+            "textures": [{{ "source": 0 }}, {{ "source": 1 }}, {{ "source": 2 }}, {{ "source": 3 }}],
+            "images": [{{ "uri": "0.png" }}, {{ "uri": "1.png" }}, {{ "uri": "2.png" }}, {{ "uri": "3.png" }}],
+            "accessors": [
+                {{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0]
+                }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+            ],
+            "buffers": [
+                {{ "uri": "data:application/octet-stream;base64,{positions}", "byteLength": 36 }},
+                {{ "uri": "data:application/octet-stream;base64,{indices}", "byteLength": 6 }}
+            ],
+            "bufferViews": [
+                {{ "buffer": 0, "byteLength": 36 }},
+                {{ "buffer": 1, "byteLength": 6 }}
+            ]
+        }}"#
+    )
+}
+
+/// Items aren't waited on when polling, and cancelling stops any more textures from being read.
+#[test]
+fn cancelled_stream_stops_loading() {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let (started_send, started) = mpsc::channel();
+    let (unblock, unblock_recv) = mpsc::channel::<()>();
+    let unblock_recv = Mutex::new(unblock_recv);
+
+    // Reading an image blocks until the test allows it to continue
+    let mut stream = GltfImportStream::from_vec(
+        external_textures_gltf().into_bytes(),
+        {
+            let reads = reads.clone();
+            move |_| {
+                reads.fetch_add(1, Ordering::Relaxed);
+                started_send.send(()).unwrap();
+                let _ = unblock_recv.lock().unwrap().recv();
+                Ok(IMAGE.to_vec())
+            }
+        },
+        Default::default(),
+        1,
+    )
+    .unwrap();
+    assert_eq!(stream.texture_count, 4);
+
+    // Materials and meshes come before textures
+    assert!(matches!(
+        stream.next(),
+        Some(Ok(GltfImportItem::Material { .. }))
+    ));
+    assert!(matches!(
+        stream.next(),
+        Some(Ok(GltfImportItem::Mesh { .. }))
+    ));
+
+    // The first texture is being read, so nothing is ready
+    started.recv().unwrap();
+    assert!(stream.try_next().is_none());
+    assert!(!stream.is_finished());
+    assert_eq!(stream.progress().items_done, 2);
+
+    stream.progress_handle().cancel();
+    assert!(stream.is_finished());
+    assert!(stream.try_next().is_none());
+    assert!(stream.next().is_none());
+
+    unblock.send(()).unwrap();
+    stream.cancel_and_wait();
+    assert_eq!(reads.load(Ordering::Relaxed), 1);
+}
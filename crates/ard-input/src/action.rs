@@ -0,0 +1,397 @@
+use std::collections::BTreeMap;
+
+use ard_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{InputState, Key, MouseButton};
+
+/// Maps named actions and axes to physical inputs.
+///
+/// Bindings are grouped into named contexts (e.g. "editor" or "gameplay"). Only contexts that
+/// have been pushed onto the context stack are active, and contexts higher on the stack take
+/// priority over lower ones. When an action or axis is defined in more than one active context,
+/// only the topmost definition is used.
+///
+/// Conflicts between bindings that share a trigger are resolved deterministically. Of all
+/// bindings whose modifiers are currently held, the one with the most modifiers wins, so `Ctrl+S`
+/// suppresses a plain `S` binding while `Ctrl` is held. Remaining ties go to the binding in the
+/// higher context, and then to the action whose name sorts first.
+///
+/// Only the bindings are serialized. The context stack is runtime state and must be rebuilt by
+/// whoever loads the map.
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct ActionMap {
+    contexts: BTreeMap<String, ActionContext>,
+    #[serde(skip)]
+    stack: Vec<String>,
+}
+
+/// A named set of action and axis bindings.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionContext {
+    actions: BTreeMap<String, Vec<Binding>>,
+    axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+/// A physical input that can trigger an action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    Key(Key),
+    MouseButton(MouseButton),
+    /// Scroll sources are momentary. They are both "down" and "held" on any frame the wheel
+    /// moves in their direction and are never "up".
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+/// A modifier that must be held for a chorded binding to trigger. Either the left or right
+/// variant of the key satisfies the modifier.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Super,
+}
+
+/// A trigger plus any modifiers that must be held with it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Binding {
+    pub input: InputSource,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+}
+
+/// A source of values for an axis.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// Contributes `1.0` while `positive` is held and `-1.0` while `negative` is held.
+    Digital {
+        negative: InputSource,
+        positive: InputSource,
+    },
+    /// Contributes a continuous value scaled by `scale`.
+    Analog { source: AnalogSource, scale: f32 },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AnalogSource {
+    MouseDeltaX,
+    MouseDeltaY,
+    ScrollX,
+    ScrollY,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets a context by name, if it exists.
+    #[inline]
+    pub fn context(&self, name: &str) -> Option<&ActionContext> {
+        self.contexts.get(name)
+    }
+
+    /// Gets a context by name, creating an empty one if it doesn't exist.
+    #[inline]
+    pub fn context_mut(&mut self, name: impl Into<String>) -> &mut ActionContext {
+        self.contexts.entry(name.into()).or_default()
+    }
+
+    /// Makes the named context active, above all currently active contexts. If the context
+    /// was already active, it is moved to the top.
+    pub fn push_context(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        self.stack.retain(|active| *active != name);
+        self.stack.push(name);
+    }
+
+    /// Deactivates the topmost context and returns its name.
+    #[inline]
+    pub fn pop_context(&mut self) -> Option<String> {
+        self.stack.pop()
+    }
+
+    /// Names of the active contexts, from highest to lowest priority.
+    #[inline]
+    pub fn active_contexts(&self) -> impl Iterator<Item = &str> {
+        self.stack.iter().rev().map(String::as_str)
+    }
+
+    /// Returns `true` if the action was just triggered.
+    #[inline]
+    pub fn action_down(&self, input: &InputState, action: &str) -> bool {
+        self.action(input, action, |source| source.down(input))
+    }
+
+    /// Returns `true` if the action's trigger was just released.
+    #[inline]
+    pub fn action_up(&self, input: &InputState, action: &str) -> bool {
+        self.action(input, action, |source| source.up(input))
+    }
+
+    /// Returns `true` while the action is being held.
+    #[inline]
+    pub fn action_held(&self, input: &InputState, action: &str) -> bool {
+        self.action(input, action, |source| source.held(input))
+    }
+
+    /// The current value of the axis, which is the sum of all of its bindings. Returns `0.0` if
+    /// the axis is not defined in any active context.
+    pub fn axis(&self, input: &InputState, axis: &str) -> f32 {
+        let bindings = match self
+            .active()
+            .find_map(|(_, context)| context.axes.get(axis))
+        {
+            Some(bindings) => bindings,
+            None => return 0.0,
+        };
+
+        bindings.iter().map(|binding| binding.value(input)).sum()
+    }
+
+    /// Active contexts, from highest to lowest priority, along with their depth in the stack.
+    fn active(&self) -> impl Iterator<Item = (usize, &ActionContext)> {
+        self.active_contexts()
+            .filter_map(|name| self.contexts.get(name))
+            .enumerate()
+    }
+
+    fn action(
+        &self,
+        input: &InputState,
+        action: &str,
+        triggered: impl Fn(InputSource) -> bool,
+    ) -> bool {
+        let (depth, bindings) = match self
+            .active()
+            .find_map(|(depth, context)| Some((depth, context.actions.get(action)?)))
+        {
+            Some(found) => found,
+            None => return false,
+        };
+
+        bindings.iter().any(|binding| {
+            triggered(binding.input)
+                && binding.modifiers_held(input)
+                && self.wins(input, binding, (depth, action))
+        })
+    }
+
+    /// Determines if `binding` has priority over every other binding with the same trigger
+    /// whose modifiers are held.
+    fn wins(&self, input: &InputState, binding: &Binding, owner: (usize, &str)) -> bool {
+        // Lower sorts first. More modifiers, then higher contexts, then action names.
+        fn priority<'a>(
+            binding: &Binding,
+            depth: usize,
+            action: &'a str,
+        ) -> (usize, usize, &'a str) {
+            (usize::MAX - binding.modifiers.len(), depth, action)
+        }
+        let ours = priority(binding, owner.0, owner.1);
+
+        for (depth, context) in self.active() {
+            for (action, bindings) in &context.actions {
+                // Actions shadowed by a higher context don't compete for inputs
+                if self.defining_depth(action) != Some(depth) {
+                    continue;
+                }
+
+                let beats_us = bindings.iter().any(|other| {
+                    other.input == binding.input
+                        && other.modifiers_held(input)
+                        && priority(other, depth, action) < ours
+                });
+
+                if beats_us {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Depth of the topmost active context that defines the action.
+    fn defining_depth(&self, action: &str) -> Option<usize> {
+        self.active()
+            .find(|(_, context)| context.actions.contains_key(action))
+            .map(|(depth, _)| depth)
+    }
+}
+
+impl ActionContext {
+    /// Adds a binding to an action.
+    pub fn bind(&mut self, action: impl Into<String>, binding: impl Into<Binding>) -> &mut Self {
+        let bindings = self.actions.entry(action.into()).or_default();
+        let binding = binding.into();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    /// Replaces every binding of an action.
+    pub fn rebind(&mut self, action: impl Into<String>, bindings: Vec<Binding>) -> &mut Self {
+        self.actions.insert(action.into(), bindings);
+        self
+    }
+
+    /// Removes an action and its bindings.
+    #[inline]
+    pub fn unbind(&mut self, action: &str) -> Option<Vec<Binding>> {
+        self.actions.remove(action)
+    }
+
+    /// Adds a binding to an axis.
+    pub fn bind_axis(&mut self, axis: impl Into<String>, binding: AxisBinding) -> &mut Self {
+        self.axes.entry(axis.into()).or_default().push(binding);
+        self
+    }
+
+    /// Replaces every binding of an axis.
+    pub fn rebind_axis(
+        &mut self,
+        axis: impl Into<String>,
+        bindings: Vec<AxisBinding>,
+    ) -> &mut Self {
+        self.axes.insert(axis.into(), bindings);
+        self
+    }
+
+    /// Removes an axis and its bindings.
+    #[inline]
+    pub fn unbind_axis(&mut self, axis: &str) -> Option<Vec<AxisBinding>> {
+        self.axes.remove(axis)
+    }
+
+    #[inline]
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    #[inline]
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+impl InputSource {
+    pub fn down(self, input: &InputState) -> bool {
+        match self {
+            InputSource::Key(key) => input.key_down(key),
+            InputSource::MouseButton(button) => input.mouse_button_down(button),
+            _ => self.scrolled(input),
+        }
+    }
+
+    pub fn up(self, input: &InputState) -> bool {
+        match self {
+            InputSource::Key(key) => input.key_up(key),
+            InputSource::MouseButton(button) => input.mouse_button_up(button),
+            _ => false,
+        }
+    }
+
+    pub fn held(self, input: &InputState) -> bool {
+        match self {
+            InputSource::Key(key) => input.key(key),
+            InputSource::MouseButton(button) => input.mouse_button(button),
+            _ => self.scrolled(input),
+        }
+    }
+
+    fn scrolled(self, input: &InputState) -> bool {
+        let (x, y) = input.mouse_scroll();
+        match self {
+            InputSource::ScrollUp => y > 0.0,
+            InputSource::ScrollDown => y < 0.0,
+            InputSource::ScrollLeft => x < 0.0,
+            InputSource::ScrollRight => x > 0.0,
+            _ => false,
+        }
+    }
+}
+
+impl Modifier {
+    pub fn held(self, input: &InputState) -> bool {
+        let (left, right) = match self {
+            Modifier::Ctrl => (Key::LCtrl, Key::RCtrl),
+            Modifier::Shift => (Key::LShift, Key::RShift),
+            Modifier::Alt => (Key::LAlt, Key::RAlt),
+            Modifier::Super => (Key::LWin, Key::RWin),
+        };
+        input.key(left) || input.key(right)
+    }
+}
+
+impl Binding {
+    pub fn new(input: impl Into<InputSource>) -> Self {
+        Self {
+            input: input.into(),
+            modifiers: Vec::default(),
+        }
+    }
+
+    /// Requires a modifier to be held for the binding to trigger.
+    pub fn with_modifier(mut self, modifier: Modifier) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+        }
+        self
+    }
+
+    #[inline]
+    fn modifiers_held(&self, input: &InputState) -> bool {
+        self.modifiers.iter().all(|modifier| modifier.held(input))
+    }
+}
+
+impl AxisBinding {
+    fn value(&self, input: &InputState) -> f32 {
+        match *self {
+            AxisBinding::Digital { negative, positive } => {
+                let mut value = 0.0;
+                if positive.held(input) {
+                    value += 1.0;
+                }
+                if negative.held(input) {
+                    value -= 1.0;
+                }
+                value
+            }
+            AxisBinding::Analog { source, scale } => {
+                let value = match source {
+                    AnalogSource::MouseDeltaX => input.mouse_delta().0,
+                    AnalogSource::MouseDeltaY => input.mouse_delta().1,
+                    AnalogSource::ScrollX => input.mouse_scroll().0,
+                    AnalogSource::ScrollY => input.mouse_scroll().1,
+                };
+                value as f32 * scale
+            }
+        }
+    }
+}
+
+impl From<Key> for InputSource {
+    fn from(key: Key) -> Self {
+        InputSource::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputSource {
+    fn from(button: MouseButton) -> Self {
+        InputSource::MouseButton(button)
+    }
+}
+
+impl<T: Into<InputSource>> From<T> for Binding {
+    fn from(input: T) -> Self {
+        Binding::new(input)
+    }
+}
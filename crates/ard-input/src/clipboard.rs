@@ -0,0 +1,56 @@
+use ard_ecs::prelude::*;
+
+/// Access to a clipboard. Implemented by a backend like `Winit`.
+pub trait ClipboardBackend: Send + Sync {
+    /// Gets the text currently on the clipboard, if there is any.
+    fn get_text(&mut self) -> Option<String>;
+
+    /// Replaces the contents of the clipboard with the given text.
+    fn set_text(&mut self, text: &str);
+}
+
+/// Interface for reading and writing clipboard text.
+///
+/// Defaults to a clipboard that only lives inside the application, which is used when no system
+/// clipboard is available.
+#[derive(Resource)]
+pub struct Clipboard {
+    backend: Box<dyn ClipboardBackend>,
+}
+
+#[derive(Default)]
+struct LocalClipboard(Option<String>);
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Self::new(LocalClipboard::default())
+    }
+}
+
+impl Clipboard {
+    pub fn new(backend: impl ClipboardBackend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    #[inline]
+    pub fn get_text(&mut self) -> Option<String> {
+        self.backend.get_text()
+    }
+
+    #[inline]
+    pub fn set_text(&mut self, text: &str) {
+        self.backend.set_text(text);
+    }
+}
+
+impl ClipboardBackend for LocalClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.0.clone()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.0 = Some(text.to_owned());
+    }
+}
@@ -1,4 +1,13 @@
+pub mod action;
+pub mod clipboard;
+
 use ard_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub use action::{
+    ActionContext, ActionMap, AnalogSource, AxisBinding, Binding, InputSource, Modifier,
+};
+pub use clipboard::{Clipboard, ClipboardBackend};
 
 /// Interface for user input. Populated by a backend like `Winit`.
 #[derive(Debug, Resource)]
@@ -9,10 +18,35 @@ pub struct InputState {
     key_state: [KeyState; u8::MAX as usize],
     mouse_state: [MouseState; u8::MAX as usize],
     input_string: String,
+    ime_events: Vec<ImeEvent>,
+    scroll_deltas: Vec<ScrollDelta>,
+    pinch_delta: f64,
+}
+
+/// A single scroll of the mouse wheel or touchpad.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScrollDelta {
+    /// Scrolled by a number of lines. Reported by most mouse wheels.
+    Lines(f64, f64),
+    /// Scrolled by an exact number of physical pixels. Reported by touchpads.
+    Pixels(f64, f64),
+}
+
+/// Text composition events from an input method editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImeEvent {
+    /// The IME was enabled. Composition events may follow.
+    Enabled,
+    /// The text currently being composed. An empty string clears the composition.
+    Preedit(String),
+    /// Composition finished and this text should be inserted.
+    Commit(String),
+    /// The IME was disabled.
+    Disabled,
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Key {
     A,
     B,
@@ -135,7 +169,7 @@ pub enum Key {
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum MouseButton {
     Left,
     Right,
@@ -166,6 +200,9 @@ impl Default for InputState {
             key_state: [KeyState::default(); u8::MAX as usize],
             mouse_state: [MouseState::default(); u8::MAX as usize],
             input_string: String::default(),
+            ime_events: Vec::default(),
+            scroll_deltas: Vec::default(),
+            pinch_delta: 0.0,
         }
     }
 }
@@ -180,17 +217,37 @@ impl InputState {
         &self.input_string
     }
 
+    /// IME events received this tick, in the order they arrived.
+    #[inline]
+    pub fn ime_events(&self) -> &[ImeEvent] {
+        &self.ime_events
+    }
+
     /// Current mouse position in screen coordinates.
     #[inline]
     pub fn mouse_pos(&self) -> (f64, f64) {
         self.mouse_position
     }
 
+    /// How far the mouse wheel was scrolled in lines. Touchpad scrolls count as a single line in
+    /// the direction they were scrolled.
     #[inline]
     pub fn mouse_scroll(&self) -> (f64, f64) {
         self.mouse_scroll
     }
 
+    /// Every scroll received this tick, in their original units.
+    #[inline]
+    pub fn scroll_deltas(&self) -> &[ScrollDelta] {
+        &self.scroll_deltas
+    }
+
+    /// How far a touchpad was pinched this tick. Positive values mean magnification.
+    #[inline]
+    pub fn pinch_delta(&self) -> f64 {
+        self.pinch_delta
+    }
+
     /// Relative position of the mouse from last frame.
     #[inline]
     pub fn mouse_delta(&self) -> (f64, f64) {
@@ -256,6 +313,18 @@ impl InputState {
         self.mouse_scroll.1 += delta.1;
     }
 
+    /// Signal a scroll in its original units. This does not affect
+    /// [`mouse_scroll`](InputState::mouse_scroll).
+    #[inline]
+    pub fn signal_scroll_delta(&mut self, delta: ScrollDelta) {
+        self.scroll_deltas.push(delta);
+    }
+
+    #[inline]
+    pub fn signal_pinch(&mut self, delta: f64) {
+        self.pinch_delta += delta;
+    }
+
     /// Signal that a key was just pressed down.
     #[inline]
     pub fn signal_key_down(&mut self, key: Key) {
@@ -299,12 +368,20 @@ impl InputState {
         self.input_string.push(c);
     }
 
+    #[inline]
+    pub fn signal_ime(&mut self, event: ImeEvent) {
+        self.ime_events.push(event);
+    }
+
     /// Indicates that input state should be reset for the next tick.
     #[inline]
     pub fn flush(&mut self) {
         self.mouse_delta = (0.0, 0.0);
         self.mouse_scroll = (0.0, 0.0);
+        self.scroll_deltas.clear();
+        self.pinch_delta = 0.0;
         self.input_string.clear();
+        self.ime_events.clear();
 
         for key in &mut self.key_state {
             key.down = false;
@@ -332,3 +409,6 @@ impl Into<u8> for MouseButton {
         unsafe { std::mem::transmute(self) }
     }
 }
+
+#[cfg(test)]
+mod tests;
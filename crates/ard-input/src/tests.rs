@@ -0,0 +1,164 @@
+use crate::*;
+
+fn press(input: &mut InputState, keys: &[Key]) {
+    for key in keys {
+        input.signal_key_down(*key);
+    }
+}
+
+fn chord_map() -> ActionMap {
+    let mut map = ActionMap::new();
+    map.context_mut("editor")
+        .bind("save", Binding::new(Key::S).with_modifier(Modifier::Ctrl))
+        .bind("move_back", Key::S);
+    map.push_context("editor");
+    map
+}
+
+#[test]
+fn chorded_binding() {
+    let map = chord_map();
+
+    // Ctrl+S triggers the chord and suppresses the plain binding
+    let mut input = InputState::new();
+    press(&mut input, &[Key::LCtrl, Key::S]);
+    assert!(map.action_down(&input, "save"));
+    assert!(map.action_held(&input, "save"));
+    assert!(!map.action_down(&input, "move_back"));
+    assert!(!map.action_held(&input, "move_back"));
+
+    // Either side of the modifier works
+    let mut input = InputState::new();
+    press(&mut input, &[Key::RCtrl, Key::S]);
+    assert!(map.action_down(&input, "save"));
+
+    // S alone only triggers the plain binding
+    let mut input = InputState::new();
+    press(&mut input, &[Key::S]);
+    assert!(!map.action_down(&input, "save"));
+    assert!(map.action_down(&input, "move_back"));
+
+    // Held but not pressed this frame
+    input.flush();
+    assert!(!map.action_down(&input, "move_back"));
+    assert!(map.action_held(&input, "move_back"));
+
+    input.signal_key_up(Key::S);
+    assert!(map.action_up(&input, "move_back"));
+    assert!(!map.action_held(&input, "move_back"));
+}
+
+#[test]
+fn context_shadowing() {
+    let mut map = ActionMap::new();
+    map.context_mut("gameplay")
+        .bind("jump", Key::Space)
+        .bind("fire", MouseButton::Left)
+        .bind("interact", Key::E);
+    map.context_mut("editor")
+        .bind("interact", Key::F)
+        .bind("play", Key::Space);
+    map.push_context("gameplay");
+
+    let mut input = InputState::new();
+    press(&mut input, &[Key::Space, Key::E]);
+    input.signal_mouse_button_down(MouseButton::Left);
+    assert!(map.action_down(&input, "jump"));
+    assert!(map.action_down(&input, "interact"));
+    assert!(!map.action_down(&input, "play"));
+
+    map.push_context("editor");
+    assert_eq!(
+        map.active_contexts().collect::<Vec<_>>(),
+        vec!["editor", "gameplay"]
+    );
+
+    // Inputs claimed by the editor no longer reach gameplay
+    assert!(map.action_down(&input, "play"));
+    assert!(!map.action_down(&input, "jump"));
+
+    // The editor's definition of "interact" replaces the gameplay one entirely
+    assert!(!map.action_down(&input, "interact"));
+    press(&mut input, &[Key::F]);
+    assert!(map.action_down(&input, "interact"));
+
+    // Actions the editor doesn't define fall through
+    assert!(map.action_down(&input, "fire"));
+
+    assert_eq!(map.pop_context().as_deref(), Some("editor"));
+    assert!(map.action_down(&input, "jump"));
+    assert!(!map.action_down(&input, "play"));
+}
+
+#[test]
+fn equal_priority_conflicts() {
+    let mut map = ActionMap::new();
+    map.context_mut("gameplay")
+        .bind("b", Key::Q)
+        .bind("a", Key::Q);
+    map.push_context("gameplay");
+
+    let mut input = InputState::new();
+    press(&mut input, &[Key::Q]);
+    assert!(map.action_down(&input, "a"));
+    assert!(!map.action_down(&input, "b"));
+}
+
+#[test]
+fn axes() {
+    let mut map = ActionMap::new();
+    map.context_mut("gameplay")
+        .bind_axis(
+            "move_x",
+            AxisBinding::Digital {
+                negative: Key::A.into(),
+                positive: Key::D.into(),
+            },
+        )
+        .bind_axis(
+            "look_x",
+            AxisBinding::Analog {
+                source: AnalogSource::MouseDeltaX,
+                scale: 0.5,
+            },
+        );
+
+    let mut input = InputState::new();
+    press(&mut input, &[Key::D]);
+    input.signal_mouse_movement((4.0, 2.0));
+
+    // Nothing is active until the context is pushed
+    assert_eq!(map.axis(&input, "move_x"), 0.0);
+
+    map.push_context("gameplay");
+    assert_eq!(map.axis(&input, "move_x"), 1.0);
+    assert_eq!(map.axis(&input, "look_x"), 2.0);
+
+    press(&mut input, &[Key::A]);
+    assert_eq!(map.axis(&input, "move_x"), 0.0);
+    assert_eq!(map.axis(&input, "missing"), 0.0);
+}
+
+#[test]
+fn ron_round_trip() {
+    let mut map = chord_map();
+    map.context_mut("editor")
+        .bind("zoom_in", InputSource::ScrollUp)
+        .bind_axis(
+            "zoom",
+            AxisBinding::Analog {
+                source: AnalogSource::ScrollY,
+                scale: 1.0,
+            },
+        );
+
+    let text = ron::to_string(&map).unwrap();
+    let loaded = ron::from_str::<ActionMap>(&text).unwrap();
+
+    assert_eq!(loaded.context("editor"), map.context("editor"));
+    assert_eq!(loaded.active_contexts().count(), 0);
+    assert_eq!(
+        loaded.context("editor").unwrap().bindings("save"),
+        &[Binding::new(Key::S).with_modifier(Modifier::Ctrl)]
+    );
+}
@@ -11,6 +11,10 @@ use crate::{
 
 pub enum BottomLevelAccelerationStructureData<'a, B: Backend> {
     Geometry(Vec<AccelerationStructureGeometry<'a, B>>),
+    /// Procedural geometry bounded by axis aligned boxes. Rays that enter a box invoke the
+    /// intersection shader of a [`Procedural`](crate::rt_pipeline::RayTracingShaderGroup::Procedural)
+    /// hit group.
+    Aabbs(Vec<AccelerationStructureAabbs<'a, B>>),
     CompactDst(u64),
 }
 
@@ -29,6 +33,18 @@ pub struct AccelerationStructureGeometry<'a, B: Backend> {
     pub triangle_count: usize,
 }
 
+/// A set of AABBs. Each AABB is six `f32`s: the minimum `x`, `y`, and `z` followed by the
+/// maximum `x`, `y`, and `z`.
+pub struct AccelerationStructureAabbs<'a, B: Backend> {
+    pub flags: GeometryFlags,
+    pub data: &'a Buffer<B>,
+    pub data_array_element: usize,
+    pub data_offset: u64,
+    /// Bytes between consecutive AABBs. Must be a multiple of `8` and at least `24`.
+    pub stride: u64,
+    pub count: usize,
+}
+
 pub struct BottomLevelAccelerationStructureCreateInfo<'a, B: Backend> {
     pub flags: BuildAccelerationStructureFlags,
     pub data: BottomLevelAccelerationStructureData<'a, B>,
@@ -44,6 +60,8 @@ pub struct BottomLevelAccelerationStructure<B: Backend> {
 
 #[derive(Debug, Error)]
 pub enum BottomLevelAccelerationStructureCreateError {
+    #[error("out of memory allocating `{requested}` bytes from heap `{heap}`")]
+    OutOfMemory { requested: u64, heap: usize },
     #[error("an error has occured: {0}")]
     Other(String),
 }
@@ -57,6 +75,15 @@ impl<B: Backend> BottomLevelAccelerationStructure<B> {
             BottomLevelAccelerationStructureData::Geometry(geo) => {
                 assert!(!geo.is_empty(), "BLAS must have geometries")
             }
+            BottomLevelAccelerationStructureData::Aabbs(aabbs) => {
+                assert!(!aabbs.is_empty(), "BLAS must have AABBs");
+                for aabbs in aabbs {
+                    assert!(
+                        aabbs.stride % 8 == 0 && aabbs.stride >= 24,
+                        "AABB stride must be a multiple of 8 and at least 24"
+                    );
+                }
+            }
             BottomLevelAccelerationStructureData::CompactDst(size) => {
                 assert_ne!(*size, 0, "BLAS must have storage capacity")
             }
@@ -69,6 +96,75 @@ impl<B: Backend> BottomLevelAccelerationStructure<B> {
         Ok(Self { ctx, id })
     }
 
+    /// Creates an empty BLAS large enough to hold a compacted copy of `self`.
+    ///
+    /// # Note
+    /// Blocks until the compact size written when `self` was built is available. Use
+    /// [`new_compact_dst_with_size`](Self::new_compact_dst_with_size) with a size from
+    /// [`Context::blas_compacted_size_poll`](crate::context::Context::blas_compacted_size_poll)
+    /// to avoid blocking.
+    #[inline(always)]
+    pub fn new_compact_dst(
+        &self,
+        queue_types: QueueTypes,
+        sharing_mode: SharingMode,
+        debug_name: Option<String>,
+    ) -> Result<Self, BottomLevelAccelerationStructureCreateError> {
+        self.new_compact_dst_with_size(self.compacted_size(), queue_types, sharing_mode, debug_name)
+    }
+
+    /// Creates an empty BLAS of `compacted_size` bytes to hold a compacted copy of `self`.
+    pub fn new_compact_dst_with_size(
+        &self,
+        compacted_size: u64,
+        queue_types: QueueTypes,
+        sharing_mode: SharingMode,
+        debug_name: Option<String>,
+    ) -> Result<Self, BottomLevelAccelerationStructureCreateError> {
+        Self::new(
+            self.ctx.clone(),
+            BottomLevelAccelerationStructureCreateInfo {
+                flags: self.build_flags() - BuildAccelerationStructureFlags::ALLOW_COMPACTION,
+                data: BottomLevelAccelerationStructureData::CompactDst(compacted_size),
+                queue_types,
+                sharing_mode,
+                debug_name,
+            },
+        )
+    }
+
+    /// Gets the size in bytes of the scratch buffer needed to build every BLAS in `blases` with
+    /// [`build_blas_batch`](crate::command_buffer::CommandBuffer::build_blas_batch).
+    #[inline(always)]
+    pub fn batch_scratch_size(blases: &[&Self]) -> u64 {
+        Self::batch_scratch_offsets(blases).1
+    }
+
+    /// Gets the offset of each BLAS' scratch memory within a batch, and the total size.
+    pub(crate) fn batch_scratch_offsets(blases: &[&Self]) -> (Vec<u64>, u64) {
+        let alignment = match blases.first() {
+            Some(blas) => blas
+                .ctx
+                .properties()
+                .ray_tracing
+                .min_scratch_offset_alignment
+                .max(1),
+            None => return (Vec::default(), 0),
+        };
+
+        let mut size = 0u64;
+        let offsets = blases
+            .iter()
+            .map(|blas| {
+                let offset = size.next_multiple_of(alignment);
+                size = offset + blas.scratch_buffer_size();
+                offset
+            })
+            .collect();
+
+        (offsets, size)
+    }
+
     #[inline(always)]
     pub fn device_ref(&self) -> u64 {
         unsafe { self.ctx.0.blas_device_ref(&self.id) }
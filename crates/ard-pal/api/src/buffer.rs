@@ -3,7 +3,10 @@ use std::{
     ptr::NonNull,
 };
 
-use crate::{context::Context, types::*, Backend};
+use crate::{
+    command_buffer::CopyBufferToBuffer, context::Context, frame_pacer::FrameToken, queue::Job,
+    types::*, Backend,
+};
 use bytemuck::Pod;
 use thiserror::Error;
 
@@ -23,10 +26,28 @@ pub struct BufferCreateInfo {
     pub sharing_mode: SharingMode,
     /// The backend *should* use the provided debug name for easy identification.
     pub debug_name: Option<String>,
+    /// What to do if the buffer doesn't fit in device local memory.
+    pub memory_fallback: MemoryFallback,
 }
 
+/// Offsets handed out by a [`RingBuffer`] are aligned to at least this many bytes, which covers
+/// vertex, index, and transfer offsets.
+const MIN_RING_BUFFER_ALIGNMENT: u64 = 16;
+
+/// The usages supported by [transient buffers](Context::transient_buffer).
+pub const TRANSIENT_BUFFER_USAGE: BufferUsage = BufferUsage::TRANSFER_SRC
+    .union(BufferUsage::TRANSFER_DST)
+    .union(BufferUsage::UNIFORM_BUFFER)
+    .union(BufferUsage::STORAGE_BUFFER)
+    .union(BufferUsage::VERTEX_BUFFER)
+    .union(BufferUsage::INDEX_BUFFER)
+    .union(BufferUsage::INDIRECT_BUFFER)
+    .union(BufferUsage::DEVICE_ADDRESS);
+
 #[derive(Debug, Error)]
 pub enum BufferCreateError {
+    #[error("out of memory allocating `{requested}` bytes from heap `{heap}`")]
+    OutOfMemory { requested: u64, heap: usize },
     #[error("an error has occured: {0}")]
     Other(String),
 }
@@ -37,6 +58,39 @@ pub enum BufferViewError {
     Other(String),
 }
 
+pub struct BufferViewCreateInfo<'a, B: Backend> {
+    /// The buffer to view. Must have [`UNIFORM_TEXEL_BUFFER`](BufferUsage::UNIFORM_TEXEL_BUFFER)
+    /// or [`STORAGE_TEXEL_BUFFER`](BufferUsage::STORAGE_TEXEL_BUFFER) usage.
+    pub buffer: &'a Buffer<B>,
+    /// The array element of the buffer to view.
+    pub array_element: usize,
+    /// Offset in bytes of the first texel from the start of the array element.
+    pub offset: u64,
+    /// Size in bytes of the view. Must be a whole number of texels.
+    pub range: u64,
+    /// The format texels are read and written with. Must support every texel buffer usage of
+    /// the buffer.
+    pub format: Format,
+}
+
+#[derive(Debug, Error)]
+pub enum BufferViewCreateError {
+    #[error("the buffer has neither `UNIFORM_TEXEL_BUFFER` nor `STORAGE_TEXEL_BUFFER` usage")]
+    MissingUsage,
+    #[error("format `{0:?}` does not support `{1:?}`")]
+    UnsupportedFormat(Format, FormatSupport),
+    #[error("array element `{element}` is out of bounds for a buffer with `{count}` elements")]
+    ArrayElementOutOfRange { element: usize, count: usize },
+    #[error("offset `{offset}` is not a multiple of the required alignment of `{alignment}`")]
+    MisalignedOffset { offset: u64, alignment: u64 },
+    #[error("bytes `{offset}..{end}` are out of bounds for a buffer of `{size}` bytes")]
+    OutOfBounds { offset: u64, end: u64, size: u64 },
+    #[error("range of `{range}` bytes is not a whole number of `{texel_size}` byte texels")]
+    PartialTexel { range: u64, texel_size: u64 },
+    #[error("view of `{texels}` texels exceeds the limit of `{max}`")]
+    TooManyTexels { texels: u64, max: u32 },
+}
+
 /// A GPU memory buffer. For the purposes of synchronization, this is considered a resource.
 pub struct Buffer<B: Backend> {
     ctx: Context<B>,
@@ -47,6 +101,7 @@ pub struct Buffer<B: Backend> {
     sharing_mode: SharingMode,
     array_elements: usize,
     debug_name: Option<String>,
+    memory_fallback: MemoryFallback,
     pub(crate) id: B::Buffer,
 }
 
@@ -63,6 +118,63 @@ pub struct BufferWriteView<'a, B: Backend> {
     len: usize,
 }
 
+/// A range of a buffer read and written by shaders as an array of formatted texels. Bound with
+/// [`DescriptorValue::UniformTexelBuffer`] or [`DescriptorValue::StorageTexelBuffer`].
+///
+/// Views are cheap to create. The backend *should* reuse the native object of an identical view
+/// of the same buffer and *must* keep it alive until the buffer is destroyed.
+///
+/// [`DescriptorValue::UniformTexelBuffer`]: crate::descriptor_set::DescriptorValue::UniformTexelBuffer
+/// [`DescriptorValue::StorageTexelBuffer`]: crate::descriptor_set::DescriptorValue::StorageTexelBuffer
+pub struct BufferView<'a, B: Backend> {
+    buffer: &'a Buffer<B>,
+    array_element: usize,
+    offset: u64,
+    range: u64,
+    format: Format,
+}
+
+pub struct RingBufferCreateInfo {
+    /// The initial size in bytes available to each frame. The ring buffer grows when a frame
+    /// writes more than this.
+    pub size: u64,
+    /// Describes the supported usage types of the buffer.
+    pub buffer_usage: BufferUsage,
+    /// Number of frames that can be in flight at once. Must match
+    /// [`Context::frames_in_flight`](crate::context::Context::frames_in_flight).
+    pub frames_in_flight: usize,
+    /// What queue(s) will access the buffer.
+    pub queue_types: QueueTypes,
+    /// The backend *should* use the provided debug name for easy identification.
+    pub debug_name: Option<String>,
+}
+
+/// A persistently mapped [`CpuToGpu`](MemoryUsage::CpuToGpu) buffer for data that is rewritten
+/// every frame, such as uniforms or dynamic geometry.
+///
+/// Each [frame slot](crate::frame_pacer::FrameToken::slot) gets its own array element of the
+/// buffer. Writes for a frame are suballocated one after the other from the array element of the
+/// frame, which is recycled the first time a newer frame with the same slot writes to it. Since
+/// the context only hands a slot out again once every job of the frame that last used it is
+/// complete, regions returned by [`write`](RingBuffer::write) are never read by in-flight work.
+///
+/// The buffer is replaced by a larger one when a frame runs out of space, so it must be bound
+/// after every write of the frame is done.
+pub struct RingBuffer<B: Backend> {
+    buffer: Buffer<B>,
+    /// Offsets returned by `write` are multiples of this.
+    alignment: u64,
+    slots: RingSlots,
+}
+
+/// Tracks which frame each slot of a [`RingBuffer`] belongs to and how much of it is used.
+struct RingSlots {
+    /// Index of the frame that last wrote to each slot.
+    frames: Vec<Option<u64>>,
+    /// Bytes of each slot used by its frame.
+    heads: Vec<u64>,
+}
+
 impl<B: Backend> Buffer<B> {
     /// Creates a new buffer.
     ///
@@ -87,6 +199,7 @@ impl<B: Backend> Buffer<B> {
         let queue_types = create_info.queue_types;
         let sharing_mode = create_info.sharing_mode;
         let debug_name = create_info.debug_name.clone();
+        let memory_fallback = create_info.memory_fallback;
         let id = unsafe { ctx.0.create_buffer(create_info)? };
         Ok(Self {
             ctx,
@@ -98,9 +211,78 @@ impl<B: Backend> Buffer<B> {
             sharing_mode,
             array_elements,
             debug_name,
+            memory_fallback,
         })
     }
 
+    /// Creates a new buffer and uploads initial data to it on the transfer queue.
+    ///
+    /// `data` is split into chunks of `create_info.size` bytes, each of which is copied into
+    /// consecutive array elements of the buffer. The buffer is given
+    /// [`TRANSFER_DST`](BufferUsage::TRANSFER_DST) usage and is made available to the transfer
+    /// queue. If the buffer is [`Exclusive`](SharingMode::Exclusive), ownership is handed to the
+    /// main queue, or the compute queue if the buffer isn't used on the main queue.
+    ///
+    /// Later submissions that use the buffer wait for the upload automatically. The returned
+    /// [`Job`] can be used to wait on the upload from the CPU.
+    ///
+    /// # Arguments
+    /// - `ctx` - The [`Context`] to create the buffer with.
+    /// - `create_info` - Describes the buffer to create.
+    /// - `data` - The data to upload to the buffer.
+    ///
+    /// # Panics
+    /// - If `data.is_empty()`.
+    /// - If `data` is larger than `create_info.size * create_info.array_elements`.
+    pub fn new_with_data(
+        ctx: Context<B>,
+        mut create_info: BufferCreateInfo,
+        data: &[u8],
+    ) -> Result<(Buffer<B>, Job<B>), BufferCreateError> {
+        assert!(
+            data.len() as u64 <= create_info.size * create_info.array_elements as u64,
+            "initial data is larger than the buffer"
+        );
+        create_info.buffer_usage |= BufferUsage::TRANSFER_DST;
+        create_info.queue_types |= QueueTypes::TRANSFER;
+
+        let staging = Buffer::new_staging(
+            ctx.clone(),
+            QueueType::Transfer,
+            create_info
+                .debug_name
+                .as_ref()
+                .map(|name| format!("{name}_staging")),
+            data,
+        )?;
+        let buffer = Buffer::new(ctx.clone(), create_info)?;
+
+        let queue = ctx.transfer();
+        let mut commands = queue.command_buffer();
+        let owner = upload_owner(buffer.queue_types);
+        for (array_element, chunk) in data.chunks(buffer.size as usize).enumerate() {
+            commands.copy_buffer_to_buffer(CopyBufferToBuffer {
+                src: &staging,
+                src_array_element: 0,
+                src_offset: (array_element as u64) * buffer.size,
+                dst: &buffer,
+                dst_array_element: array_element,
+                dst_offset: 0,
+                len: chunk.len() as u64,
+            });
+        }
+
+        if let (SharingMode::Exclusive, Some(owner)) = (buffer.sharing_mode, owner) {
+            for array_element in 0..buffer.array_elements {
+                commands.transfer_buffer_ownership(&buffer, array_element, owner, None);
+            }
+        }
+
+        // The staging buffer is destroyed once the upload is complete
+        let job = queue.submit(Some("buffer_upload"), commands);
+        Ok((buffer, job))
+    }
+
     /// Creates a new staging buffer. A staging buffer is typically used to transfer data from the
     /// CPU to a [`GpuOnly`](MemoryUsage::GpuOnly) buffer.
     ///
@@ -132,6 +314,7 @@ impl<B: Backend> Buffer<B> {
             queue_types: queue.into(),
             sharing_mode: SharingMode::Exclusive,
             debug_name,
+            memory_fallback: MemoryFallback::None,
         };
         let mut buffer = Buffer::new(ctx, create_info)?;
         let mut view = buffer.write(0).unwrap();
@@ -140,6 +323,32 @@ impl<B: Backend> Buffer<B> {
         Ok(buffer)
     }
 
+    /// Creates a new transient buffer. See [`Context::transient_buffer`].
+    pub(crate) fn new_transient(
+        ctx: Context<B>,
+        size: u64,
+        buffer_usage: BufferUsage,
+    ) -> Result<Self, BufferCreateError> {
+        assert_ne!(size, 0, "buffer size cannot be zero");
+        assert!(
+            TRANSIENT_BUFFER_USAGE.contains(buffer_usage),
+            "unsupported transient buffer usage"
+        );
+        let id = unsafe { ctx.0.create_transient_buffer(size, buffer_usage)? };
+        Ok(Self {
+            ctx,
+            id,
+            size,
+            buffer_usage,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::NON_PRESENT,
+            sharing_mode: SharingMode::Concurrent,
+            array_elements: 1,
+            debug_name: None,
+            memory_fallback: MemoryFallback::None,
+        })
+    }
+
     #[inline(always)]
     pub fn internal(&self) -> &B::Buffer {
         &self.id
@@ -165,6 +374,11 @@ impl<B: Backend> Buffer<B> {
         self.size
     }
 
+    #[inline(always)]
+    pub fn array_elements(&self) -> usize {
+        self.array_elements
+    }
+
     #[inline(always)]
     pub fn buffer_usage(&self) -> BufferUsage {
         self.buffer_usage
@@ -265,6 +479,7 @@ impl<B: Backend> Buffer<B> {
                 queue_types: buffer.queue_types,
                 sharing_mode: buffer.sharing_mode,
                 debug_name: buffer.debug_name.clone(),
+                memory_fallback: buffer.memory_fallback,
             },
         )
         .unwrap();
@@ -291,6 +506,250 @@ impl<B: Backend> Drop for Buffer<B> {
     }
 }
 
+impl<'a, B: Backend> BufferView<'a, B> {
+    /// Creates a new buffer view.
+    ///
+    /// # Arguments
+    /// - `create_info` - Describes the view to create.
+    pub fn new(create_info: BufferViewCreateInfo<'a, B>) -> Result<Self, BufferViewCreateError> {
+        let buffer = create_info.buffer;
+        let properties = buffer.ctx.properties();
+
+        let texel_usage = buffer.buffer_usage
+            & (BufferUsage::UNIFORM_TEXEL_BUFFER | BufferUsage::STORAGE_TEXEL_BUFFER);
+        if texel_usage.is_empty() {
+            return Err(BufferViewCreateError::MissingUsage);
+        }
+
+        let mut required = FormatSupport::empty();
+        if texel_usage.contains(BufferUsage::UNIFORM_TEXEL_BUFFER) {
+            required |= FormatSupport::UNIFORM_TEXEL_BUFFER;
+        }
+        if texel_usage.contains(BufferUsage::STORAGE_TEXEL_BUFFER) {
+            required |= FormatSupport::STORAGE_TEXEL_BUFFER;
+        }
+        if !properties
+            .format_support(create_info.format)
+            .contains(required)
+        {
+            return Err(BufferViewCreateError::UnsupportedFormat(
+                create_info.format,
+                required,
+            ));
+        }
+
+        if create_info.array_element >= buffer.array_elements {
+            return Err(BufferViewCreateError::ArrayElementOutOfRange {
+                element: create_info.array_element,
+                count: buffer.array_elements,
+            });
+        }
+
+        let alignment = properties
+            .limits
+            .texel_buffer_offset_alignment(create_info.format, texel_usage);
+        if !create_info.offset.is_multiple_of(alignment) {
+            return Err(BufferViewCreateError::MisalignedOffset {
+                offset: create_info.offset,
+                alignment,
+            });
+        }
+
+        let end = create_info.offset + create_info.range;
+        if create_info.range == 0 || end > buffer.size {
+            return Err(BufferViewCreateError::OutOfBounds {
+                offset: create_info.offset,
+                end,
+                size: buffer.size,
+            });
+        }
+
+        let texel_size = create_info.format.bytes_per_block() as u64;
+        if !create_info.range.is_multiple_of(texel_size) {
+            return Err(BufferViewCreateError::PartialTexel {
+                range: create_info.range,
+                texel_size,
+            });
+        }
+
+        let texels = create_info.range / texel_size;
+        let max = properties.limits.max_texel_buffer_elements;
+        if texels > max as u64 {
+            return Err(BufferViewCreateError::TooManyTexels { texels, max });
+        }
+
+        Ok(Self {
+            buffer,
+            array_element: create_info.array_element,
+            offset: create_info.offset,
+            range: create_info.range,
+            format: create_info.format,
+        })
+    }
+
+    #[inline(always)]
+    pub fn buffer(&self) -> &'a Buffer<B> {
+        self.buffer
+    }
+
+    #[inline(always)]
+    pub fn array_element(&self) -> usize {
+        self.array_element
+    }
+
+    #[inline(always)]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    #[inline(always)]
+    pub fn range(&self) -> u64 {
+        self.range
+    }
+
+    #[inline(always)]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+}
+
+impl<B: Backend> Clone for BufferView<'_, B> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B: Backend> Copy for BufferView<'_, B> {}
+
+impl<B: Backend> RingBuffer<B> {
+    /// Creates a new ring buffer.
+    ///
+    /// # Panics
+    /// - If `create_info.size` is `0`.
+    /// - If `create_info.frames_in_flight` is `0`.
+    pub fn new(
+        ctx: Context<B>,
+        create_info: RingBufferCreateInfo,
+    ) -> Result<Self, BufferCreateError> {
+        let limits = &ctx.properties().limits;
+        let mut alignment = MIN_RING_BUFFER_ALIGNMENT;
+        if create_info
+            .buffer_usage
+            .contains(BufferUsage::UNIFORM_BUFFER)
+        {
+            alignment = alignment.max(limits.min_uniform_buffer_offset_alignment);
+        }
+        if create_info
+            .buffer_usage
+            .contains(BufferUsage::STORAGE_BUFFER)
+        {
+            alignment = alignment.max(limits.min_storage_buffer_offset_alignment);
+        }
+
+        let buffer = Buffer::new(
+            ctx,
+            BufferCreateInfo {
+                size: create_info.size,
+                array_elements: create_info.frames_in_flight,
+                buffer_usage: create_info.buffer_usage,
+                memory_usage: MemoryUsage::CpuToGpu,
+                queue_types: create_info.queue_types,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: create_info.debug_name,
+                memory_fallback: MemoryFallback::None,
+            },
+        )?;
+
+        Ok(Self {
+            buffer,
+            alignment,
+            slots: RingSlots::new(create_info.frames_in_flight),
+        })
+    }
+
+    /// The buffer written to. Replaced when the ring buffer grows.
+    #[inline(always)]
+    pub fn buffer(&self) -> &Buffer<B> {
+        &self.buffer
+    }
+
+    /// Offsets returned by [`write`](RingBuffer::write) are multiples of this.
+    #[inline(always)]
+    pub fn alignment(&self) -> u64 {
+        self.alignment
+    }
+
+    /// Copies `bytes` into a region of the buffer reserved for the frame of `token`.
+    ///
+    /// Returns the array element and offset of the region. The region is valid until the frame
+    /// slot is handed out again by the context.
+    ///
+    /// # Panics
+    /// - If the slot of `token` is not less than the number of frames in flight.
+    /// - In debug builds, if `token` was begun with a different number of frames in flight, or
+    ///   if a newer frame has already written to the slot of `token`. Either way, the region
+    ///   could still be read by an incomplete job.
+    pub fn write(&mut self, token: &FrameToken, bytes: &[u8]) -> (usize, u64) {
+        let slot = token.slot();
+        let offset = self
+            .slots
+            .allocate(token.index(), slot, bytes.len() as u64, self.alignment);
+        let end = offset + bytes.len() as u64;
+
+        // Earlier writes of the frame must survive the move to the larger buffer
+        if let Some(buffer) = Buffer::expand(&self.buffer, end, true) {
+            self.buffer = buffer;
+        }
+
+        let mut view = self.buffer.write(slot).unwrap();
+        view[offset as usize..end as usize].copy_from_slice(bytes);
+
+        (slot, offset)
+    }
+}
+
+impl RingSlots {
+    fn new(frames_in_flight: usize) -> Self {
+        assert_ne!(frames_in_flight, 0, "at least one frame must be in flight");
+
+        Self {
+            frames: vec![None; frames_in_flight],
+            heads: vec![0; frames_in_flight],
+        }
+    }
+
+    /// Reserves `len` bytes of `slot` for `frame`, returning the offset of the region.
+    fn allocate(&mut self, frame: u64, slot: usize, len: u64, alignment: u64) -> u64 {
+        assert!(
+            slot < self.frames.len(),
+            "frame slot `{slot}` is out of bounds"
+        );
+        debug_assert_eq!(
+            (frame % self.frames.len() as u64) as usize,
+            slot,
+            "frame token was begun with a different number of frames in flight"
+        );
+
+        match self.frames[slot] {
+            Some(last) if last == frame => {}
+            last => {
+                debug_assert!(
+                    last.is_none_or(|last| last < frame),
+                    "frame `{frame}` wrote to a slot already reused by frame `{}`",
+                    last.unwrap_or_default()
+                );
+                self.frames[slot] = Some(frame);
+                self.heads[slot] = 0;
+            }
+        }
+
+        let offset = self.heads[slot].next_multiple_of(alignment);
+        self.heads[slot] = offset + len;
+        offset
+    }
+}
+
 impl<'a, B: Backend> BufferReadView<'a, B> {
     #[inline]
     pub fn into_raw(self) -> (NonNull<u8>, usize) {
@@ -387,3 +846,56 @@ impl<'a, B: Backend> Drop for BufferWriteView<'a, B> {
         }
     }
 }
+
+/// The queue that takes ownership of an [`Exclusive`](SharingMode::Exclusive) resource after
+/// initial data is uploaded to it on the transfer queue. `None` if the resource should stay on the
+/// transfer queue.
+pub(crate) fn upload_owner(queue_types: QueueTypes) -> Option<QueueType> {
+    if queue_types.contains(QueueTypes::MAIN) {
+        Some(QueueType::Main)
+    } else if queue_types.contains(QueueTypes::COMPUTE) {
+        Some(QueueType::Compute)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_slots_suballocate_within_a_frame() {
+        let mut slots = RingSlots::new(2);
+        assert_eq!(slots.allocate(0, 0, 20, 16), 0);
+        assert_eq!(slots.allocate(0, 0, 4, 16), 32);
+        assert_eq!(slots.allocate(0, 0, 0, 16), 48);
+        assert_eq!(slots.allocate(1, 1, 8, 16), 0);
+    }
+
+    #[test]
+    fn ring_slots_recycle_for_newer_frames() {
+        let mut slots = RingSlots::new(2);
+        slots.allocate(0, 0, 64, 16);
+        slots.allocate(1, 1, 64, 16);
+        assert_eq!(slots.allocate(2, 0, 8, 16), 0);
+        assert_eq!(slots.allocate(3, 1, 8, 16), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn ring_slots_reject_stale_frames() {
+        let mut slots = RingSlots::new(2);
+        slots.allocate(2, 0, 8, 16);
+        slots.allocate(0, 0, 8, 16);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn ring_slots_reject_mismatched_frames_in_flight() {
+        let mut slots = RingSlots::new(2);
+        slots.allocate(1, 0, 8, 16);
+    }
+}
@@ -4,20 +4,28 @@ use crate::{
     compute_pass::{ComputePass, ComputePassDispatch},
     compute_pipeline::ComputePipeline,
     cube_map::CubeMap,
-    descriptor_set::DescriptorSet,
+    descriptor_set::{DescriptorSet, DescriptorSetLayout, DescriptorSetUpdate},
     graphics_pipeline::GraphicsPipeline,
-    render_pass::{RenderPass, RenderPassDescriptor, VertexBind},
+    render_pass::{
+        ColorAttachmentDestination, DepthStencilAttachmentDestination, RenderPass,
+        RenderPassDescriptor, VertexBind,
+    },
     rt_pass::{RayTracingDispatch, RayTracingPass},
     rt_pipeline::RayTracingPipeline,
     surface::SurfaceImage,
-    texture::{Blit, Texture},
+    texture::{Blit, SparsePageRegion, Texture},
     tlas::TopLevelAccelerationStructure,
     types::{
-        BufferUsage, BuildAccelerationStructureFlags, CubeFace, Filter, IndexType, QueueType,
-        Scissor, ShaderStage, SharingMode, TextureUsage,
+        BufferUsage, BuildAccelerationStructureFlags, ClearColor, CubeFace, DynamicStates, Filter,
+        Format, IndexType, QueueType, Scissor, ShaderStage, SharingMode, TextureUsage,
     },
     Backend,
 };
+use std::ops::Range;
+
+/// The maximum number of bytes that can be written with
+/// [`update_buffer`](CommandBuffer::update_buffer).
+pub const MAX_BUFFER_UPDATE_SIZE: usize = 65536;
 
 pub enum BlitSource<'a, B: Backend> {
     Texture(&'a Texture<B>),
@@ -97,6 +105,9 @@ pub struct BufferCubeMapCopy {
     pub cube_map_mip_level: usize,
     /// The array element of the texture to read/write.
     pub cube_map_array_element: usize,
+    /// The face to read/write. If `None`, all six faces are read/written and are tightly packed
+    /// one after another in [`CubeFace::ALL`] order.
+    pub face: Option<CubeFace>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -117,11 +128,35 @@ pub struct TextureResolve {
     pub extent: (u32, u32, u32),
 }
 
+/// A resource synchronized by a split barrier. See
+/// [`signal_event`](CommandBuffer::signal_event).
+pub enum EventResource<'a, B: Backend> {
+    Buffer {
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+    },
+    Texture {
+        texture: &'a Texture<B>,
+        array_elements: Range<usize>,
+        mips: Range<usize>,
+    },
+    /// Every face of the cube map array element.
+    CubeMap {
+        cube_map: &'a CubeMap<B>,
+        array_element: usize,
+        mips: Range<usize>,
+    },
+}
+
 pub enum Command<'a, B: Backend> {
     BeginRenderPass(RenderPassDescriptor<'a, B>, Option<&'a str>),
     EndRenderPass(Option<&'a str>),
     BeginComputePass(ComputePipeline<B>, Option<&'a str>),
-    EndComputePass(ComputePassDispatch<'a, B>, Option<&'a str>),
+    EndComputePass(Option<&'a str>),
+    Dispatch(ComputePassDispatch<'a, B>),
+    /// Makes writes of every previous dispatch of a compute pass visible to the dispatches after
+    /// it.
+    ComputeBarrier,
     BeginRayTracingPass(RayTracingPipeline<B>, Option<&'a str>),
     EndRayTracingPass(RayTracingDispatch<'a, B>, Option<&'a str>),
     BindGraphicsPipeline(GraphicsPipeline<B>),
@@ -162,6 +197,12 @@ pub enum Command<'a, B: Backend> {
         first: usize,
         stage: ShaderStage,
     },
+    PushDescriptorSet {
+        layout: DescriptorSetLayout<B>,
+        index: usize,
+        bindings: Vec<DescriptorSetUpdate<'a, B>>,
+        stage: ShaderStage,
+    },
     BindVertexBuffers {
         first: usize,
         binds: Vec<VertexBind<'a, B>>,
@@ -176,12 +217,35 @@ pub enum Command<'a, B: Backend> {
         attachment: usize,
         scissor: Scissor,
     },
+    SetViewport {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        min_depth: f32,
+        max_depth: f32,
+    },
+    SetDepthBias {
+        constant: f32,
+        clamp: f32,
+        slope: f32,
+    },
+    SetLineWidth(f32),
+    SetStencilReference(u32),
+    SetBlendConstants([f32; 4]),
     Draw {
         vertex_count: usize,
         instance_count: usize,
         first_vertex: usize,
         first_instance: usize,
     },
+    DrawIndirect {
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        draw_count: usize,
+        stride: u64,
+    },
     DrawIndexed {
         index_count: usize,
         instance_count: usize,
@@ -207,7 +271,61 @@ pub enum Command<'a, B: Backend> {
         max_draw_count: usize,
     },
     DrawMeshTasks(u32, u32, u32),
+    DrawMeshTasksIndirect {
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        draw_count: usize,
+        stride: u64,
+    },
+    DrawMeshTasksIndirectCount {
+        draw_buffer: &'a Buffer<B>,
+        draw_array_element: usize,
+        draw_offset: u64,
+        draw_stride: u64,
+        count_buffer: &'a Buffer<B>,
+        count_array_element: usize,
+        count_offset: u64,
+        max_draw_count: usize,
+    },
+    /// Render pass commands recorded through [`RenderPass::split`]. Each inner list is one
+    /// slice.
+    RenderPassSlices(Vec<Vec<Command<'a, B>>>),
     CopyBufferToBuffer(CopyBufferToBuffer<'a, B>),
+    FillBuffer {
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        len: u64,
+        value: u32,
+    },
+    UpdateBuffer {
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        data: &'a [u8],
+    },
+    ClearTexture {
+        texture: &'a Texture<B>,
+        array_element: usize,
+        mip_level: usize,
+        value: ClearColor,
+    },
+    /// Clears all six faces of a cube map array element.
+    ClearCubeMap {
+        cube_map: &'a CubeMap<B>,
+        array_element: usize,
+        mip_level: usize,
+        value: ClearColor,
+    },
+    BindSparsePages {
+        texture: &'a Texture<B>,
+        regions: Vec<SparsePageRegion>,
+    },
+    UnbindSparsePages {
+        texture: &'a Texture<B>,
+        regions: Vec<SparsePageRegion>,
+    },
     CopyTextureToTexture(CopyTextureToTexture<'a, B>),
     CopyBufferToTexture {
         buffer: &'a Buffer<B>,
@@ -235,18 +353,33 @@ pub enum Command<'a, B: Backend> {
         blit: Blit,
         filter: Filter,
     },
+    GenerateMipMaps {
+        texture: &'a Texture<B>,
+        array_element: usize,
+        filter: Filter,
+    },
+    GenerateCubeMapMipMaps {
+        cube_map: &'a CubeMap<B>,
+        array_element: usize,
+        filter: Filter,
+    },
     SetTextureUsage {
         tex: &'a Texture<B>,
         new_usage: TextureUsage,
-        array_elem: usize,
-        base_mip: u32,
-        mip_count: usize,
+        array_elems: Range<usize>,
+        mips: Range<usize>,
     },
     BuildBlas {
         blas: &'a BottomLevelAccelerationStructure<B>,
         scratch: &'a Buffer<B>,
         scratch_array_element: usize,
     },
+    BuildBlasBatch {
+        /// Every BLAS to build along with the offset in bytes of its scratch memory.
+        blases: Vec<(&'a BottomLevelAccelerationStructure<B>, u64)>,
+        scratch: &'a Buffer<B>,
+        scratch_array_element: usize,
+    },
     BuildTlas {
         tlas: &'a TopLevelAccelerationStructure<B>,
         instance_count: usize,
@@ -255,6 +388,37 @@ pub enum Command<'a, B: Backend> {
         src: &'a Buffer<B>,
         src_array_element: usize,
     },
+    UpdateTlas {
+        tlas: &'a TopLevelAccelerationStructure<B>,
+        instance_count: usize,
+        scratch: &'a Buffer<B>,
+        scratch_array_element: usize,
+        src: &'a Buffer<B>,
+        src_array_element: usize,
+    },
+    BeginTimestamp(&'a str),
+    EndTimestamp,
+    BeginStatistics(&'a str),
+    EndStatistics,
+    BeginConditionalRendering {
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        inverted: bool,
+    },
+    EndConditionalRendering,
+    InsertLabel {
+        label: &'a str,
+        color: [f32; 4],
+    },
+    /// Begins a split barrier. Paired with the next matching `WaitEvent` in the same submit.
+    SignalEvent {
+        resources: Vec<EventResource<'a, B>>,
+    },
+    /// Ends the split barrier begun by the matching `SignalEvent`.
+    WaitEvent {
+        resources: Vec<EventResource<'a, B>>,
+    },
     WriteBlasCompactSize(&'a BottomLevelAccelerationStructure<B>),
     CompactBlas {
         src: &'a BottomLevelAccelerationStructure<B>,
@@ -266,6 +430,8 @@ pub enum Command<'a, B: Backend> {
 pub struct CommandBuffer<'a, B: Backend> {
     pub(crate) queue_ty: QueueType,
     pub(crate) commands: Vec<Command<'a, B>>,
+    pub(crate) statistics_active: bool,
+    pub(crate) conditional_rendering_active: bool,
 }
 
 impl<'a, B: Backend> CommandBuffer<'a, B> {
@@ -278,6 +444,18 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     /// # Panics
     /// - If the queue type this command buffer was created with does not support graphics
     /// commands.
+    /// - If the attachments don't share a sample count, or an attachment's sample count doesn't
+    ///   match its texture.
+    /// - If a resolve attachment doesn't resolve from a multi-sampled attachment.
+    /// - If a [`TextureSlice`](ColorAttachmentDestination::TextureSlice) attachment isn't of a 3D
+    ///   texture or is out of bounds.
+    /// - If a [`TextureArray`](ColorAttachmentDestination::TextureArray) attachment isn't of a 2D
+    ///   texture, is out of bounds, or doesn't bind between one and
+    ///   [`MAX_ATTACHMENT_LAYERS`](crate::render_pass::MAX_ATTACHMENT_LAYERS) layers.
+    /// - If an attachment doesn't have a layer for every view of the `view_mask`, or has more than
+    ///   one layer when the `view_mask` is zero.
+    /// - If a correlation mask isn't a subset of the `view_mask` or overlaps another correlation
+    ///   mask.
     ///
     pub fn render_pass(
         &mut self,
@@ -291,11 +469,36 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
             "queue `{:?}` does not support render passes",
             self.queue_ty
         );
+        descriptor.check_sample_counts();
+        descriptor
+            .color_attachments
+            .iter()
+            .map(|attachment| &attachment.dst)
+            .chain(descriptor.color_resolve_attachments.iter().map(|a| &a.dst))
+            .for_each(|dst: &ColorAttachmentDestination<B>| {
+                dst.check_slice();
+                dst.check_array();
+            });
+        descriptor
+            .depth_stencil_attachment
+            .iter()
+            .map(|attachment| &attachment.dst)
+            .chain(
+                descriptor
+                    .depth_stencil_resolve_attachment
+                    .iter()
+                    .map(|a| &a.dst),
+            )
+            .for_each(DepthStencilAttachmentDestination::check_array);
+        descriptor.check_views();
+        let attachments = descriptor.attachment_dims();
 
         self.commands
             .push(Command::BeginRenderPass(descriptor, debug_name));
         let mut render_pass = RenderPass {
             bound_pipeline: false,
+            dynamic_states: DynamicStates::empty(),
+            attachments,
             commands: Vec::default(),
         };
         pass(&mut render_pass);
@@ -307,7 +510,7 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     ///
     /// # Arguments
     /// - `pipeline` - The pipeline used for this compute pass.
-    /// - `pass` - A function that records compute commands. Returns the work groups for dispatch.
+    /// - `pass` - A function that records compute commands, including any number of dispatches.
     ///
     /// # Panics
     /// - If the queue type this command buffer was created with does not support compute commands.
@@ -315,7 +518,7 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         &mut self,
         pipeline: &ComputePipeline<B>,
         debug_name: Option<&'a str>,
-        pass: impl FnOnce(&mut ComputePass<'a, B>) -> ComputePassDispatch<'a, B>,
+        pass: impl FnOnce(&mut ComputePass<'a, B>),
     ) {
         assert!(
             self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Compute,
@@ -328,10 +531,175 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         let mut compute_pass = ComputePass {
             commands: Vec::default(),
         };
-        let dispatch = pass(&mut compute_pass);
+        pass(&mut compute_pass);
         self.commands.extend(compute_pass.commands);
-        self.commands
-            .push(Command::EndComputePass(dispatch, debug_name));
+        self.commands.push(Command::EndComputePass(debug_name));
+    }
+
+    /// Records the GPU time taken by a region of commands. Results can be read back with
+    /// [`Job::timestamps`](crate::queue::Job::timestamps) once the submission completes.
+    ///
+    /// # Arguments
+    /// - `label` - Name used to identify the region in the results.
+    /// - `region` - A function that records the commands to time.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support timestamps.
+    pub fn timestamp(&mut self, label: &'a str, region: impl FnOnce(&mut CommandBuffer<'a, B>)) {
+        assert!(
+            self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Compute,
+            "queue `{:?}` does not support timestamps",
+            self.queue_ty
+        );
+
+        self.commands.push(Command::BeginTimestamp(label));
+        region(self);
+        self.commands.push(Command::EndTimestamp);
+    }
+
+    /// Collects pipeline statistics for a region of commands. Results can be read back with
+    /// [`Job::statistics`](crate::queue::Job::statistics) once the submission completes.
+    ///
+    /// # Arguments
+    /// - `label` - Name used to identify the region in the results.
+    /// - `region` - A function that records the commands to collect statistics for.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support graphics.
+    /// - If called within another statistics region.
+    pub fn statistics(&mut self, label: &'a str, region: impl FnOnce(&mut CommandBuffer<'a, B>)) {
+        assert_eq!(
+            self.queue_ty,
+            QueueType::Main,
+            "queue `{:?}` does not support pipeline statistics",
+            self.queue_ty
+        );
+        assert!(
+            !self.statistics_active,
+            "statistics regions can not be nested"
+        );
+
+        self.statistics_active = true;
+        self.commands.push(Command::BeginStatistics(label));
+        region(self);
+        self.commands.push(Command::EndStatistics);
+        self.statistics_active = false;
+    }
+
+    /// Skips the draws and dispatches of a region of commands based on a 32-bit predicate read
+    /// from a buffer on the GPU. Commands are skipped when the predicate is zero, or when it is
+    /// non-zero if `inverted` is set.
+    ///
+    /// If [`GraphicsFeatures::conditional_rendering`](crate::context::GraphicsFeatures) is not
+    /// supported, the commands always execute.
+    ///
+    /// # Arguments
+    /// - `buffer` - The buffer containing the predicate.
+    /// - `array_element` - The array element of the buffer containing the predicate.
+    /// - `offset` - Offset in bytes of the predicate within the array element.
+    /// - `inverted` - Skips commands when the predicate is non-zero instead.
+    /// - `region` - A function that records the commands to execute conditionally.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support graphics or
+    ///   compute.
+    /// - If called within another conditional rendering region.
+    /// - If `buffer` does not have [`BufferUsage::CONDITIONAL_RENDERING`] usage.
+    /// - If `offset` is not a multiple of 4 or the predicate is out of bounds.
+    pub fn conditional_rendering(
+        &mut self,
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        inverted: bool,
+        region: impl FnOnce(&mut CommandBuffer<'a, B>),
+    ) {
+        assert!(
+            self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Compute,
+            "queue `{:?}` does not support conditional rendering",
+            self.queue_ty
+        );
+        assert!(
+            !self.conditional_rendering_active,
+            "conditional rendering regions can not be nested"
+        );
+        assert!(
+            buffer
+                .buffer_usage()
+                .contains(BufferUsage::CONDITIONAL_RENDERING),
+            "buffer must have `CONDITIONAL_RENDERING` usage"
+        );
+        assert_eq!(offset % 4, 0, "predicate offset must be a multiple of 4");
+        assert!(
+            offset + 4 <= buffer.size(),
+            "predicate is out of bounds of the buffer"
+        );
+        assert!(
+            array_element < buffer.array_elements(),
+            "array element out of bounds"
+        );
+
+        self.conditional_rendering_active = true;
+        self.commands.push(Command::BeginConditionalRendering {
+            buffer,
+            array_element,
+            offset,
+            inverted,
+        });
+        region(self);
+        self.commands.push(Command::EndConditionalRendering);
+        self.conditional_rendering_active = false;
+    }
+
+    /// Inserts a single named marker between commands. Markers show up in graphics debuggers
+    /// and are ignored if debugging is disabled. Commands are never reordered around a marker.
+    ///
+    /// # Arguments
+    /// - `label` - Text of the marker.
+    /// - `color` - RGBA color of the marker. Debuggers *may* ignore it.
+    #[inline(always)]
+    pub fn insert_label(&mut self, label: &'a str, color: [f32; 4]) {
+        self.commands.push(Command::InsertLabel { label, color });
+    }
+
+    /// Begins a split barrier for `resources`, which is finished by the matching
+    /// [`wait_event`](CommandBuffer::wait_event).
+    ///
+    /// Normally, commands using a resource written earlier wait on the write right before they
+    /// execute, which can drain the GPU when the write finished long ago. With a split barrier,
+    /// the writes recorded before the signal start becoming available at the signal, and commands
+    /// recorded after the wait only have to wait on whatever is still outstanding.
+    ///
+    /// # Arguments
+    /// - `resources` - The resources to synchronize.
+    ///
+    /// # Note
+    /// A signal is paired with the next wait recorded in the same command buffer that lists the
+    /// same resources. Unpaired signals and waits, such as a pair split across submissions, are
+    /// ignored and the resources are synchronized with regular barriers. Backends without split
+    /// barriers always use regular barriers.
+    ///
+    /// # Panics
+    /// - If `resources` is empty, or a range in it is empty or out of bounds.
+    #[inline(always)]
+    pub fn signal_event(&mut self, resources: Vec<EventResource<'a, B>>) {
+        check_event_resources(&resources);
+        self.commands.push(Command::SignalEvent { resources });
+    }
+
+    /// Finishes the split barrier begun by the matching
+    /// [`signal_event`](CommandBuffer::signal_event). Commands recorded after the wait that use
+    /// `resources` are synchronized with the writes recorded before the signal.
+    ///
+    /// # Arguments
+    /// - `resources` - The resources to synchronize. Must be the same as the signal.
+    ///
+    /// # Panics
+    /// - For the same reasons as [`signal_event`](CommandBuffer::signal_event).
+    #[inline(always)]
+    pub fn wait_event(&mut self, resources: Vec<EventResource<'a, B>>) {
+        check_event_resources(&resources);
+        self.commands.push(Command::WaitEvent { resources });
     }
 
     pub fn ray_trace_pass(
@@ -487,6 +855,261 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         self.commands.push(Command::CopyBufferToBuffer(copy));
     }
 
+    /// Fills a region of a buffer with a repeated 32-bit value.
+    ///
+    /// # Arguments
+    /// - `buffer` - The buffer to fill.
+    /// - `array_element` - The array element of the buffer to fill.
+    /// - `offset` - The offset in bytes within the array element to begin filling at.
+    /// - `len` - The number of bytes to fill.
+    /// - `value` - The value to write to every 4 bytes of the region.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support transfer
+    ///   commands.
+    /// - If `offset` or `len` is not a multiple of 4.
+    /// - If the region is out of the bounds of the buffer.
+    #[inline(always)]
+    pub fn fill_buffer(
+        &mut self,
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        len: u64,
+        value: u32,
+    ) {
+        assert!(
+            self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Transfer,
+            "queue `{:?}` does not support transfer commands",
+            self.queue_ty
+        );
+        assert_eq!(offset % 4, 0, "offset must be a multiple of 4");
+        assert_eq!(len % 4, 0, "length must be a multiple of 4");
+        assert!(offset < buffer.size(), "out of bound");
+        assert!(
+            len <= buffer.size() - offset,
+            "attempt to fill too many bytes"
+        );
+        self.commands.push(Command::FillBuffer {
+            buffer,
+            array_element,
+            offset,
+            len,
+            value,
+        });
+    }
+
+    /// Writes data inline into a buffer. This is meant for small updates, and the data is copied
+    /// into the command buffer when it is submitted.
+    ///
+    /// # Arguments
+    /// - `buffer` - The buffer to write to.
+    /// - `array_element` - The array element of the buffer to write to.
+    /// - `offset` - The offset in bytes within the array element to begin writing at.
+    /// - `data` - The data to write.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support transfer
+    ///   commands.
+    /// - If `offset` or `data.len()` is not a multiple of 4.
+    /// - If `data.len()` is greater than [`MAX_BUFFER_UPDATE_SIZE`].
+    /// - If the region is out of the bounds of the buffer.
+    #[inline(always)]
+    pub fn update_buffer(
+        &mut self,
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        data: &'a [u8],
+    ) {
+        assert!(
+            self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Transfer,
+            "queue `{:?}` does not support transfer commands",
+            self.queue_ty
+        );
+        assert_eq!(offset % 4, 0, "offset must be a multiple of 4");
+        assert_eq!(data.len() % 4, 0, "data length must be a multiple of 4");
+        assert!(
+            data.len() <= MAX_BUFFER_UPDATE_SIZE,
+            "attempt to update too many bytes"
+        );
+        assert!(offset < buffer.size(), "out of bound");
+        assert!(
+            data.len() as u64 <= buffer.size() - offset,
+            "attempt to update too many bytes"
+        );
+        self.commands.push(Command::UpdateBuffer {
+            buffer,
+            array_element,
+            offset,
+            data,
+        });
+    }
+
+    /// Sets every texel of a single mip of a texture array element to `value`. Color textures
+    /// take [`RgbaF32`](ClearColor::RgbaF32) or [`RU32`](ClearColor::RU32) values and
+    /// depth/stencil textures take [`D32S32`](ClearColor::D32S32) values.
+    ///
+    /// # Arguments
+    /// - `texture` - The texture to clear. Must have been created with
+    ///   [`TRANSFER_DST`](TextureUsage::TRANSFER_DST) usage.
+    /// - `array_element` - The array element of the texture to clear.
+    /// - `mip_level` - The mip level of the array element to clear.
+    /// - `value` - The value to write to every texel.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support clears. Color
+    ///   textures can be cleared on the main and compute queues and depth/stencil textures only on
+    ///   the main queue.
+    /// - If the texture was not created with `TRANSFER_DST` usage.
+    /// - If the texture has a compressed format.
+    /// - If `value` does not match the format of the texture.
+    /// - If `array_element` or `mip_level` is out of bounds.
+    #[inline(always)]
+    pub fn clear_texture(
+        &mut self,
+        texture: &'a Texture<B>,
+        array_element: usize,
+        mip_level: usize,
+        value: ClearColor,
+    ) {
+        check_clear(
+            self.queue_ty,
+            texture.format(),
+            texture.texture_usage(),
+            &value,
+        );
+        assert!(
+            array_element < texture.array_elements(),
+            "array element `{array_element}` is out of bounds"
+        );
+        assert!(
+            mip_level < texture.mip_count(),
+            "mip level `{mip_level}` is out of bounds"
+        );
+
+        self.commands.push(Command::ClearTexture {
+            texture,
+            array_element,
+            mip_level,
+            value,
+        });
+    }
+
+    /// Sets every texel of a single mip of all six faces of a cube map array element to `value`.
+    /// See [`clear_texture`](CommandBuffer::clear_texture).
+    ///
+    /// # Arguments
+    /// - `cube_map` - The cube map to clear. Must have been created with
+    ///   [`TRANSFER_DST`](TextureUsage::TRANSFER_DST) usage.
+    /// - `array_element` - The array element of the cube map to clear.
+    /// - `mip_level` - The mip level of the array element to clear.
+    /// - `value` - The value to write to every texel.
+    ///
+    /// # Panics
+    /// - For the same reasons as [`clear_texture`](CommandBuffer::clear_texture).
+    #[inline(always)]
+    pub fn clear_cube_map(
+        &mut self,
+        cube_map: &'a CubeMap<B>,
+        array_element: usize,
+        mip_level: usize,
+        value: ClearColor,
+    ) {
+        check_clear(
+            self.queue_ty,
+            cube_map.format(),
+            cube_map.texture_usage(),
+            &value,
+        );
+        assert!(
+            array_element < cube_map.array_elements(),
+            "array element `{array_element}` is out of bounds"
+        );
+        assert!(
+            mip_level < cube_map.mip_count(),
+            "mip level `{mip_level}` is out of bounds"
+        );
+
+        self.commands.push(Command::ClearCubeMap {
+            cube_map,
+            array_element,
+            mip_level,
+            value,
+        });
+    }
+
+    /// Binds memory to the pages of a sparse texture covered by `regions`. Pages that are already
+    /// bound keep their memory and contents. Newly bound pages have undefined contents.
+    ///
+    /// Binding is performed by the queue before any other command in the command buffer executes,
+    /// so the pages can be written to by commands later in the same command buffer.
+    ///
+    /// # Arguments
+    /// - `texture` - The sparse texture to bind pages of.
+    /// - `regions` - The regions of the texture to make resident.
+    ///
+    /// # Panics
+    /// - If this command buffer was not created for the transfer queue.
+    /// - If `texture` is not sparse.
+    /// - If a region is outside of the texture.
+    #[inline(always)]
+    pub fn bind_sparse_pages(&mut self, texture: &'a Texture<B>, regions: Vec<SparsePageRegion>) {
+        Self::check_sparse_regions(self.queue_ty, texture, &regions);
+        self.commands
+            .push(Command::BindSparsePages { texture, regions });
+    }
+
+    /// Unbinds memory from the pages of a sparse texture covered by `regions`. The memory is
+    /// freed once all previously submitted work using the texture has completed.
+    ///
+    /// Like [`bind_sparse_pages`](CommandBuffer::bind_sparse_pages), unbinding is performed
+    /// before any other command in the command buffer executes.
+    ///
+    /// # Arguments
+    /// - `texture` - The sparse texture to unbind pages of.
+    /// - `regions` - The regions of the texture to evict.
+    ///
+    /// # Panics
+    /// - If this command buffer was not created for the transfer queue.
+    /// - If `texture` is not sparse.
+    /// - If a region is outside of the texture.
+    #[inline(always)]
+    pub fn unbind_sparse_pages(&mut self, texture: &'a Texture<B>, regions: Vec<SparsePageRegion>) {
+        Self::check_sparse_regions(self.queue_ty, texture, &regions);
+        self.commands
+            .push(Command::UnbindSparsePages { texture, regions });
+    }
+
+    fn check_sparse_regions(
+        queue_ty: QueueType,
+        texture: &Texture<B>,
+        regions: &[SparsePageRegion],
+    ) {
+        assert_eq!(
+            queue_ty,
+            QueueType::Transfer,
+            "sparse binding is only supported on the transfer queue"
+        );
+        assert!(texture.is_sparse(), "texture is not sparse");
+
+        let dims = texture.dims();
+        for region in regions {
+            assert!(region.mip_level < texture.mip_count(), "mip out of bounds");
+            let mip_dims = (
+                (dims.0 >> region.mip_level).max(1),
+                (dims.1 >> region.mip_level).max(1),
+                (dims.2 >> region.mip_level).max(1),
+            );
+            assert!(
+                region.offset.0 + region.extent.0 <= mip_dims.0
+                    && region.offset.1 + region.extent.1 <= mip_dims.1
+                    && region.offset.2 + region.extent.2 <= mip_dims.2,
+                "region out of bounds"
+            );
+        }
+    }
+
     #[inline(always)]
     pub fn copy_texture_to_texture(&mut self, copy: CopyTextureToTexture<'a, B>) {
         assert!(
@@ -507,6 +1130,7 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     /// # Panics
     /// - If the queue type this command buffer was created with does not support transfer
     /// commands.
+    /// - If the copy does not cover whole texel blocks of the texture's format.
     #[inline(always)]
     pub fn copy_buffer_to_texture(
         &mut self,
@@ -519,6 +1143,7 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
             "queue `{:?}` does not support transfer commands",
             self.queue_ty
         );
+        check_block_alignment(texture, &copy);
 
         self.commands.push(Command::CopyBufferToTexture {
             buffer,
@@ -527,7 +1152,9 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         });
     }
 
-    /// Copies data from a texture into a buffer.
+    /// Copies data from a texture into a buffer. Only the depth aspect of depth/stencil textures
+    /// is copied. See [`Format::block_info`](crate::types::Format::block_info) for the size of
+    /// each copied texel block.
     ///
     /// # Arguments
     /// - `buffer` - The destination buffer to write to.
@@ -537,6 +1164,7 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     /// # Panics
     /// - If the queue type this command buffer was created with does not support transfer
     /// commands.
+    /// - If the copy does not cover whole texel blocks of the texture's format.
     #[inline(always)]
     pub fn copy_texture_to_buffer(
         &mut self,
@@ -549,6 +1177,7 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
             "queue `{:?}` does not support transfer commands",
             self.queue_ty
         );
+        check_block_alignment(texture, &copy);
 
         self.commands.push(Command::CopyTextureToBuffer {
             buffer,
@@ -651,35 +1280,113 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         });
     }
 
-    /// Prepares a texture to be used in a particular way.
+    /// Generates every mip of a texture array element from its base mip by repeatedly blitting
+    /// each mip into the next. See [`Blit::mip_chain`] for the extents used.
+    ///
+    /// # Arguments
+    /// - `texture` - The texture to generate mips for. Must have been created with
+    ///   [`TRANSFER_SRC`](TextureUsage::TRANSFER_SRC) and
+    ///   [`TRANSFER_DST`](TextureUsage::TRANSFER_DST) usage.
+    /// - `array_element` - The array element to generate mips for.
+    /// - `filter` - Filtering type used when downsampling.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support graphics
+    ///   commands.
+    #[inline(always)]
+    pub fn generate_mip_maps(
+        &mut self,
+        texture: &'a Texture<B>,
+        array_element: usize,
+        filter: Filter,
+    ) {
+        assert_eq!(
+            self.queue_ty,
+            QueueType::Main,
+            "queue `{:?}` does not support mip map generation",
+            self.queue_ty
+        );
+        self.commands.push(Command::GenerateMipMaps {
+            texture,
+            array_element,
+            filter,
+        });
+    }
+
+    /// Generates every mip of all six faces of a cube map array element from their base mips.
+    /// See [`generate_mip_maps`](CommandBuffer::generate_mip_maps).
+    ///
+    /// # Arguments
+    /// - `cube_map` - The cube map to generate mips for. Must have been created with
+    ///   [`TRANSFER_SRC`](TextureUsage::TRANSFER_SRC) and
+    ///   [`TRANSFER_DST`](TextureUsage::TRANSFER_DST) usage.
+    /// - `array_element` - The array element to generate mips for.
+    /// - `filter` - Filtering type used when downsampling.
+    ///
+    /// # Panics
+    /// - If the queue type this command buffer was created with does not support graphics
+    ///   commands.
+    #[inline(always)]
+    pub fn generate_cube_map_mip_maps(
+        &mut self,
+        cube_map: &'a CubeMap<B>,
+        array_element: usize,
+        filter: Filter,
+    ) {
+        assert_eq!(
+            self.queue_ty,
+            QueueType::Main,
+            "queue `{:?}` does not support mip map generation",
+            self.queue_ty
+        );
+
+        self.commands.push(Command::GenerateCubeMapMipMaps {
+            cube_map,
+            array_element,
+            filter,
+        });
+    }
+
+    /// Prepares a range of subresources of a texture to be used in a particular way. Only the
+    /// given array elements and mips are transitioned, so the rest of the texture can be used
+    /// differently at the same time.
     ///
     /// # Arguments
     /// - `tex` - The texture to prepare.
     /// - `new_usage` - The way the texture will be prepared to use.
-    /// - `array_elem` - Array element to prepare.
-    /// - `base_mip` - Base mip level to prepare.
-    /// - `mip_count` - The number of mips to prepare.
+    /// - `array_elems` - Array elements to prepare.
+    /// - `mips` - Mip levels to prepare.
     ///
     /// # Note
     /// Texture usage transitions are performed automatically, so this is almost never needed. The
     /// use case for this is if you're using unsafe commands and might need to manually transition
     /// a usage. Or, you want to perform a usage transition on an asyncronous job.
+    ///
+    /// # Panics
+    /// - If `new_usage` is not a single usage.
+    /// - If either range is empty or out of the bounds of the texture.
     #[inline(always)]
     pub fn set_texture_usage(
         &mut self,
         tex: &'a Texture<B>,
         new_usage: TextureUsage,
-        array_elem: usize,
-        base_mip: u32,
-        mip_count: usize,
+        array_elems: Range<usize>,
+        mips: Range<usize>,
     ) {
         assert!(new_usage.iter().count() == 1);
+        assert!(
+            !array_elems.is_empty() && array_elems.end <= tex.array_elements(),
+            "array elements `{array_elems:?}` are out of bounds"
+        );
+        assert!(
+            !mips.is_empty() && mips.end <= tex.mip_count(),
+            "mips `{mips:?}` are out of bounds"
+        );
         self.commands.push(Command::SetTextureUsage {
             tex,
             new_usage,
-            array_elem,
-            base_mip,
-            mip_count,
+            array_elems,
+            mips,
         });
     }
 
@@ -710,6 +1417,58 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         }
     }
 
+    /// Builds many BLAS' at once, sharing a single scratch buffer between them.
+    ///
+    /// # Arguments
+    /// - `blases` - The BLAS' to build.
+    /// - `scratch_buffer` - Scratch memory for every build. Must be at least
+    ///   [`batch_scratch_size`](BottomLevelAccelerationStructure::batch_scratch_size) bytes.
+    /// - `scratch_buffer_array_element` - The array element of the scratch buffer to use.
+    ///
+    /// # Note
+    /// Compact sizes are written for BLAS' built with
+    /// [`ALLOW_COMPACTION`](BuildAccelerationStructureFlags::ALLOW_COMPACTION), just like
+    /// [`build_bottom_level_acceleration_structure`](CommandBuffer::build_bottom_level_acceleration_structure).
+    pub fn build_blas_batch(
+        &mut self,
+        blases: &[&'a BottomLevelAccelerationStructure<B>],
+        scratch_buffer: &'a Buffer<B>,
+        scratch_buffer_array_element: usize,
+    ) {
+        assert!(
+            self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Compute,
+            "queue `{:?}` does not support compute commands",
+            self.queue_ty
+        );
+
+        if blases.is_empty() {
+            return;
+        }
+
+        let (offsets, scratch_size) =
+            BottomLevelAccelerationStructure::batch_scratch_offsets(blases);
+        assert!(
+            scratch_buffer.size() >= scratch_size,
+            "scratch buffer of size `{}` is too small for a batch needing `{scratch_size}` bytes",
+            scratch_buffer.size()
+        );
+
+        self.commands.push(Command::BuildBlasBatch {
+            blases: blases.iter().copied().zip(offsets).collect(),
+            scratch: scratch_buffer,
+            scratch_array_element: scratch_buffer_array_element,
+        });
+
+        for blas in blases {
+            if blas
+                .build_flags()
+                .contains(BuildAccelerationStructureFlags::ALLOW_COMPACTION)
+            {
+                self.commands.push(Command::WriteBlasCompactSize(blas));
+            }
+        }
+    }
+
     #[inline(always)]
     pub fn build_top_level_acceleration_structure(
         &mut self,
@@ -735,6 +1494,51 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         });
     }
 
+    /// Refits a TLAS to new instance data, which is much cheaper than a full build. Useful when
+    /// only instance transforms have changed.
+    ///
+    /// # Arguments
+    /// - `acceleration_structure` - The TLAS to update. It must have been built before.
+    /// - `instance_count` - Number of instances. Must match the count of the last full build.
+    /// - `scratch_buffer` - Scratch memory of at least
+    ///   [`update_scratch_buffer_size`](TopLevelAccelerationStructure::update_scratch_buffer_size)
+    ///   bytes.
+    /// - `src_buffer` - Instance data, laid out the same as for a full build.
+    ///
+    /// # Panics
+    /// - If the TLAS wasn't created with
+    ///   [`ALLOW_UPDATE`](BuildAccelerationStructureFlags::ALLOW_UPDATE).
+    #[inline(always)]
+    pub fn update_top_level_acceleration_structure(
+        &mut self,
+        acceleration_structure: &'a TopLevelAccelerationStructure<B>,
+        instance_count: usize,
+        scratch_buffer: &'a Buffer<B>,
+        scratch_buffer_array_element: usize,
+        src_buffer: &'a Buffer<B>,
+        src_buffer_array_element: usize,
+    ) {
+        assert!(
+            self.queue_ty == QueueType::Main || self.queue_ty == QueueType::Compute,
+            "queue `{:?}` does not support compute commands",
+            self.queue_ty
+        );
+        assert!(
+            acceleration_structure
+                .build_flags()
+                .contains(BuildAccelerationStructureFlags::ALLOW_UPDATE),
+            "TLAS must be created with `ALLOW_UPDATE` to be updated"
+        );
+        self.commands.push(Command::UpdateTlas {
+            tlas: acceleration_structure,
+            instance_count,
+            scratch: scratch_buffer,
+            scratch_array_element: scratch_buffer_array_element,
+            src: src_buffer,
+            src_array_element: src_buffer_array_element,
+        });
+    }
+
     #[inline(always)]
     pub fn compact_acceleration_structure(
         &mut self,
@@ -749,4 +1553,200 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
 
         self.commands.push(Command::CompactBlas { src, dst });
     }
+
+    /// Compacts every `(src, dst)` pair of BLAS'. Destinations are usually made with
+    /// [`new_compact_dst`](BottomLevelAccelerationStructure::new_compact_dst) once the job that
+    /// built the sources is complete.
+    ///
+    /// # Panics
+    /// - If a source wasn't built with
+    ///   [`ALLOW_COMPACTION`](BuildAccelerationStructureFlags::ALLOW_COMPACTION).
+    pub fn compact_blas_batch(
+        &mut self,
+        pairs: impl IntoIterator<
+            Item = (
+                &'a BottomLevelAccelerationStructure<B>,
+                &'a BottomLevelAccelerationStructure<B>,
+            ),
+        >,
+    ) {
+        for (src, dst) in pairs {
+            assert!(
+                src.build_flags()
+                    .contains(BuildAccelerationStructureFlags::ALLOW_COMPACTION),
+                "BLAS must be built with `ALLOW_COMPACTION` to be compacted"
+            );
+            self.compact_acceleration_structure(src, dst);
+        }
+    }
+}
+
+fn check_event_resources<B: Backend>(resources: &[EventResource<B>]) {
+    assert!(
+        !resources.is_empty(),
+        "split barriers need at least one resource"
+    );
+    let check_mips = |mips: &Range<usize>, mip_count: usize| {
+        assert!(
+            !mips.is_empty() && mips.end <= mip_count,
+            "mips `{mips:?}` are out of bounds"
+        );
+    };
+
+    for resource in resources {
+        match resource {
+            EventResource::Buffer {
+                buffer,
+                array_element,
+            } => {
+                assert!(
+                    *array_element < buffer.array_elements(),
+                    "array element `{array_element}` is out of bounds"
+                );
+            }
+            EventResource::Texture {
+                texture,
+                array_elements,
+                mips,
+            } => {
+                assert!(
+                    !array_elements.is_empty() && array_elements.end <= texture.array_elements(),
+                    "array elements `{array_elements:?}` are out of bounds"
+                );
+                check_mips(mips, texture.mip_count());
+            }
+            EventResource::CubeMap {
+                cube_map,
+                array_element,
+                mips,
+            } => {
+                assert!(
+                    *array_element < cube_map.array_elements(),
+                    "array element `{array_element}` is out of bounds"
+                );
+                check_mips(mips, cube_map.mip_count());
+            }
+        }
+    }
+}
+
+/// Checks that a texture of the given format and usage can be cleared to `value` on `queue_ty`.
+fn check_clear(queue_ty: QueueType, format: Format, usage: TextureUsage, value: &ClearColor) {
+    if format.is_color() {
+        assert!(
+            queue_ty == QueueType::Main || queue_ty == QueueType::Compute,
+            "queue `{queue_ty:?}` does not support clearing color textures"
+        );
+        assert!(
+            !matches!(value, ClearColor::D32S32(..)),
+            "`{format:?}` textures can't be cleared with a depth/stencil value"
+        );
+    } else {
+        assert_eq!(
+            queue_ty,
+            QueueType::Main,
+            "queue `{queue_ty:?}` does not support clearing depth/stencil textures"
+        );
+        assert!(
+            matches!(value, ClearColor::D32S32(..)),
+            "`{format:?}` textures must be cleared with a depth/stencil value"
+        );
+    }
+    assert_eq!(
+        format.block_dim(),
+        1,
+        "textures with the compressed format `{format:?}` can't be cleared"
+    );
+    assert!(
+        usage.contains(TextureUsage::TRANSFER_DST),
+        "texture must have `TRANSFER_DST` usage to be cleared"
+    );
+}
+
+/// Copies between buffers and block compressed textures must start on a block and cover whole
+/// blocks, except where the copy reaches the edge of the mip.
+fn check_block_alignment<B: Backend>(texture: &Texture<B>, copy: &BufferTextureCopy) {
+    let format = texture.format();
+    let block_dim = format.block_dim();
+    if block_dim == 1 {
+        return;
+    }
+
+    let (width, height, _) = texture.dims();
+    let mip_width = (width >> copy.texture_mip_level).max(1);
+    let mip_height = (height >> copy.texture_mip_level).max(1);
+    let (x, y, _) = copy.texture_offset;
+    let (extent_x, extent_y, _) = copy.texture_extent;
+
+    assert!(
+        x % block_dim == 0 && y % block_dim == 0,
+        "copy offset must be a multiple of the `{format:?}` block size"
+    );
+    assert!(
+        (extent_x % block_dim == 0 || x + extent_x == mip_width)
+            && (extent_y % block_dim == 0 || y + extent_y == mip_height),
+        "copy extent must cover whole `{format:?}` blocks"
+    );
+    assert_eq!(
+        copy.buffer_offset % format.bytes_per_block() as u64,
+        0,
+        "buffer offset must be a multiple of the `{format:?}` block size"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR: ClearColor = ClearColor::RgbaF32(0.0, 0.0, 0.0, 1.0);
+    const DEPTH: ClearColor = ClearColor::D32S32(1.0, 0);
+
+    #[test]
+    fn clear_matching_values() {
+        check_clear(
+            QueueType::Compute,
+            Format::Rgba8Unorm,
+            TextureUsage::TRANSFER_DST,
+            &COLOR,
+        );
+        check_clear(
+            QueueType::Main,
+            Format::D32SfloatS8Uint,
+            TextureUsage::TRANSFER_DST,
+            &DEPTH,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "compressed")]
+    fn clear_rejects_compressed_formats() {
+        check_clear(
+            QueueType::Main,
+            Format::BC7Unorm,
+            TextureUsage::TRANSFER_DST,
+            &COLOR,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "depth/stencil value")]
+    fn clear_rejects_mismatched_values() {
+        check_clear(
+            QueueType::Main,
+            Format::D32Sfloat,
+            TextureUsage::TRANSFER_DST,
+            &COLOR,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "does not support clearing depth/stencil")]
+    fn clear_depth_requires_main_queue() {
+        check_clear(
+            QueueType::Compute,
+            Format::D32Sfloat,
+            TextureUsage::TRANSFER_DST,
+            &DEPTH,
+        );
+    }
 }
@@ -0,0 +1,468 @@
+//! Checks that a list of [`Command`]s is well formed before it is handed to the backend.
+//!
+//! The scoped methods of [`CommandBuffer`](crate::command_buffer::CommandBuffer) make most
+//! malformed lists impossible to record, but backends walk the raw list and assume every pass is
+//! closed and only contains commands allowed within it. Validating on submission turns what
+//! would otherwise be a panic deep inside the backend into an error naming the offending pass.
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::{command_buffer::Command, context::DeviceLimits, Backend};
+
+/// The kind of pass a command is recorded within.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassKind {
+    Render,
+    Compute,
+    RayTracing,
+}
+
+/// The debug name of a pass, if it has one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassName(pub Option<String>);
+
+/// Where a command is allowed to be recorded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommandScope<'a> {
+    BeginPass(PassKind, Option<&'a str>),
+    EndPass(PassKind, Option<&'a str>),
+    /// Only valid within a render pass.
+    RenderPass(&'static str),
+    /// Only valid within a compute pass.
+    ComputePass(&'static str),
+    /// Valid within any kind of pass.
+    AnyPass(&'static str),
+    /// Only valid outside of passes.
+    Outside(&'static str),
+}
+
+#[derive(Debug, Error)]
+pub enum CommandValidationError {
+    #[error("{kind} pass {name} was begun within {outer_kind} pass {outer_name}")]
+    NestedPass {
+        kind: PassKind,
+        name: PassName,
+        outer_kind: PassKind,
+        outer_name: PassName,
+    },
+    #[error("{kind} pass {name} was ended without being begun")]
+    UnmatchedEnd { kind: PassKind, name: PassName },
+    #[error("{kind} pass {name} was ended within {open_kind} pass {open_name}")]
+    MismatchedEnd {
+        kind: PassKind,
+        name: PassName,
+        open_kind: PassKind,
+        open_name: PassName,
+    },
+    #[error("`{command}` must be recorded within a render pass")]
+    OutsideRenderPass { command: &'static str },
+    #[error("`{command}` must be recorded within a compute pass")]
+    OutsideComputePass { command: &'static str },
+    #[error("`{command}` must be recorded within a pass")]
+    OutsidePass { command: &'static str },
+    #[error("`{command}` can't be recorded within {kind} pass {name}")]
+    WithinPass {
+        command: &'static str,
+        kind: PassKind,
+        name: PassName,
+    },
+    #[error("{kind} pass {name} was never ended")]
+    UnclosedPass { kind: PassKind, name: PassName },
+    #[error("`{size}` bytes of push constants were pushed, but the device only supports `{max}`")]
+    PushConstantsTooLarge { size: usize, max: u32 },
+}
+
+/// Validates a list of commands that is about to be submitted.
+pub fn validate_commands<B: Backend>(
+    commands: &[Command<'_, B>],
+    limits: &DeviceLimits,
+) -> Result<(), CommandValidationError> {
+    let mut scopes = Vec::with_capacity(commands.len());
+    for command in commands {
+        scopes.push(command.scope());
+        check_limits(command, limits)?;
+
+        // Slices are recorded within the render pass that contains them
+        if let Command::RenderPassSlices(slices) = command {
+            for command in slices.iter().flatten() {
+                scopes.push(command.scope());
+                check_limits(command, limits)?;
+            }
+        }
+    }
+    validate_scopes(scopes)
+}
+
+/// Checks a single command against the limits of the device.
+fn check_limits<B: Backend>(
+    command: &Command<'_, B>,
+    limits: &DeviceLimits,
+) -> Result<(), CommandValidationError> {
+    match command {
+        Command::PushConstants { data, .. }
+            if data.len() > limits.max_push_constants_size as usize =>
+        {
+            Err(CommandValidationError::PushConstantsTooLarge {
+                size: data.len(),
+                max: limits.max_push_constants_size,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Validates a sequence of command scopes. Passes may not be nested and every pass must be
+/// ended by the same kind of pass it was begun with.
+pub fn validate_scopes<'a>(
+    scopes: impl IntoIterator<Item = CommandScope<'a>>,
+) -> Result<(), CommandValidationError> {
+    let mut open: Option<(PassKind, Option<&'a str>)> = None;
+
+    for scope in scopes {
+        match (scope, open) {
+            (CommandScope::BeginPass(kind, name), None) => open = Some((kind, name)),
+            (CommandScope::BeginPass(kind, name), Some((outer_kind, outer_name))) => {
+                return Err(CommandValidationError::NestedPass {
+                    kind,
+                    name: name.into(),
+                    outer_kind,
+                    outer_name: outer_name.into(),
+                });
+            }
+            (CommandScope::EndPass(kind, name), None) => {
+                return Err(CommandValidationError::UnmatchedEnd {
+                    kind,
+                    name: name.into(),
+                });
+            }
+            (CommandScope::EndPass(kind, name), Some((open_kind, open_name))) => {
+                if kind != open_kind {
+                    return Err(CommandValidationError::MismatchedEnd {
+                        kind,
+                        name: name.into(),
+                        open_kind,
+                        open_name: open_name.into(),
+                    });
+                }
+                open = None;
+            }
+            (CommandScope::RenderPass(command), None) => {
+                return Err(CommandValidationError::OutsideRenderPass { command });
+            }
+            (CommandScope::RenderPass(command), Some((kind, name))) => {
+                if kind != PassKind::Render {
+                    return Err(CommandValidationError::WithinPass {
+                        command,
+                        kind,
+                        name: name.into(),
+                    });
+                }
+            }
+            (CommandScope::ComputePass(command), None) => {
+                return Err(CommandValidationError::OutsideComputePass { command });
+            }
+            (CommandScope::ComputePass(command), Some((kind, name))) => {
+                if kind != PassKind::Compute {
+                    return Err(CommandValidationError::WithinPass {
+                        command,
+                        kind,
+                        name: name.into(),
+                    });
+                }
+            }
+            (CommandScope::AnyPass(command), None) => {
+                return Err(CommandValidationError::OutsidePass { command });
+            }
+            (CommandScope::AnyPass(_), Some(_)) | (CommandScope::Outside(_), None) => {}
+            (CommandScope::Outside(command), Some((kind, name))) => {
+                return Err(CommandValidationError::WithinPass {
+                    command,
+                    kind,
+                    name: name.into(),
+                });
+            }
+        }
+    }
+
+    match open {
+        Some((kind, name)) => Err(CommandValidationError::UnclosedPass {
+            kind,
+            name: name.into(),
+        }),
+        None => Ok(()),
+    }
+}
+
+impl<'a, B: Backend> Command<'a, B> {
+    /// Gets where the command is allowed to be recorded.
+    pub fn scope(&self) -> CommandScope<'a> {
+        match self {
+            Command::BeginRenderPass(_, name) => CommandScope::BeginPass(PassKind::Render, *name),
+            Command::EndRenderPass(name) => CommandScope::EndPass(PassKind::Render, *name),
+            Command::BeginComputePass(_, name) => CommandScope::BeginPass(PassKind::Compute, *name),
+            Command::EndComputePass(name) => CommandScope::EndPass(PassKind::Compute, *name),
+            Command::Dispatch(_) => CommandScope::ComputePass("Dispatch"),
+            Command::ComputeBarrier => CommandScope::ComputePass("ComputeBarrier"),
+            Command::BeginRayTracingPass(_, name) => {
+                CommandScope::BeginPass(PassKind::RayTracing, *name)
+            }
+            Command::EndRayTracingPass(_, name) => {
+                CommandScope::EndPass(PassKind::RayTracing, *name)
+            }
+            Command::BindGraphicsPipeline(_) => CommandScope::RenderPass("BindGraphicsPipeline"),
+            Command::BindVertexBuffers { .. } => CommandScope::RenderPass("BindVertexBuffers"),
+            Command::BindIndexBuffer { .. } => CommandScope::RenderPass("BindIndexBuffer"),
+            Command::Scissor { .. } => CommandScope::RenderPass("Scissor"),
+            Command::SetViewport { .. } => CommandScope::RenderPass("SetViewport"),
+            Command::SetDepthBias { .. } => CommandScope::RenderPass("SetDepthBias"),
+            Command::SetLineWidth(_) => CommandScope::RenderPass("SetLineWidth"),
+            Command::SetStencilReference(_) => CommandScope::RenderPass("SetStencilReference"),
+            Command::SetBlendConstants(_) => CommandScope::RenderPass("SetBlendConstants"),
+            Command::Draw { .. } => CommandScope::RenderPass("Draw"),
+            Command::DrawIndirect { .. } => CommandScope::RenderPass("DrawIndirect"),
+            Command::DrawIndexed { .. } => CommandScope::RenderPass("DrawIndexed"),
+            Command::DrawIndexedIndirect { .. } => CommandScope::RenderPass("DrawIndexedIndirect"),
+            Command::DrawIndexedIndirectCount { .. } => {
+                CommandScope::RenderPass("DrawIndexedIndirectCount")
+            }
+            Command::DrawMeshTasks(..) => CommandScope::RenderPass("DrawMeshTasks"),
+            Command::DrawMeshTasksIndirect { .. } => {
+                CommandScope::RenderPass("DrawMeshTasksIndirect")
+            }
+            Command::DrawMeshTasksIndirectCount { .. } => {
+                CommandScope::RenderPass("DrawMeshTasksIndirectCount")
+            }
+            Command::RenderPassSlices(_) => CommandScope::RenderPass("RenderPassSlices"),
+            Command::PushConstants { .. } => CommandScope::AnyPass("PushConstants"),
+            Command::BindDescriptorSets { .. } => CommandScope::AnyPass("BindDescriptorSets"),
+            Command::BindDescriptorSetsUnchecked { .. } => {
+                CommandScope::AnyPass("BindDescriptorSetsUnchecked")
+            }
+            Command::PushDescriptorSet { .. } => CommandScope::AnyPass("PushDescriptorSet"),
+            Command::TransferBufferOwnership { .. } => {
+                CommandScope::Outside("TransferBufferOwnership")
+            }
+            Command::TransferTextureOwnership { .. } => {
+                CommandScope::Outside("TransferTextureOwnership")
+            }
+            Command::TransferCubeMapOwnership { .. } => {
+                CommandScope::Outside("TransferCubeMapOwnership")
+            }
+            Command::CopyBufferToBuffer(_) => CommandScope::Outside("CopyBufferToBuffer"),
+            Command::FillBuffer { .. } => CommandScope::Outside("FillBuffer"),
+            Command::UpdateBuffer { .. } => CommandScope::Outside("UpdateBuffer"),
+            Command::ClearTexture { .. } => CommandScope::Outside("ClearTexture"),
+            Command::ClearCubeMap { .. } => CommandScope::Outside("ClearCubeMap"),
+            Command::BindSparsePages { .. } => CommandScope::Outside("BindSparsePages"),
+            Command::UnbindSparsePages { .. } => CommandScope::Outside("UnbindSparsePages"),
+            Command::CopyTextureToTexture(_) => CommandScope::Outside("CopyTextureToTexture"),
+            Command::CopyBufferToTexture { .. } => CommandScope::Outside("CopyBufferToTexture"),
+            Command::CopyTextureToBuffer { .. } => CommandScope::Outside("CopyTextureToBuffer"),
+            Command::CopyBufferToCubeMap { .. } => CommandScope::Outside("CopyBufferToCubeMap"),
+            Command::CopyCubeMapToBuffer { .. } => CommandScope::Outside("CopyCubeMapToBuffer"),
+            Command::Blit { .. } => CommandScope::Outside("Blit"),
+            Command::GenerateMipMaps { .. } => CommandScope::Outside("GenerateMipMaps"),
+            Command::GenerateCubeMapMipMaps { .. } => {
+                CommandScope::Outside("GenerateCubeMapMipMaps")
+            }
+            Command::SetTextureUsage { .. } => CommandScope::Outside("SetTextureUsage"),
+            Command::BuildBlas { .. } => CommandScope::Outside("BuildBlas"),
+            Command::BuildBlasBatch { .. } => CommandScope::Outside("BuildBlasBatch"),
+            Command::BuildTlas { .. } => CommandScope::Outside("BuildTlas"),
+            Command::UpdateTlas { .. } => CommandScope::Outside("UpdateTlas"),
+            Command::BeginTimestamp(_) => CommandScope::Outside("BeginTimestamp"),
+            Command::EndTimestamp => CommandScope::Outside("EndTimestamp"),
+            Command::BeginStatistics(_) => CommandScope::Outside("BeginStatistics"),
+            Command::EndStatistics => CommandScope::Outside("EndStatistics"),
+            Command::BeginConditionalRendering { .. } => {
+                CommandScope::Outside("BeginConditionalRendering")
+            }
+            Command::EndConditionalRendering => CommandScope::Outside("EndConditionalRendering"),
+            Command::InsertLabel { .. } => CommandScope::Outside("InsertLabel"),
+            Command::SignalEvent { .. } => CommandScope::Outside("SignalEvent"),
+            Command::WaitEvent { .. } => CommandScope::Outside("WaitEvent"),
+            Command::WriteBlasCompactSize(_) => CommandScope::Outside("WriteBlasCompactSize"),
+            Command::CompactBlas { .. } => CommandScope::Outside("CompactBlas"),
+        }
+    }
+}
+
+impl fmt::Display for PassKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassKind::Render => write!(f, "render"),
+            PassKind::Compute => write!(f, "compute"),
+            PassKind::RayTracing => write!(f, "ray tracing"),
+        }
+    }
+}
+
+impl fmt::Display for PassName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(name) => write!(f, "`{name}`"),
+            None => write!(f, "(unnamed)"),
+        }
+    }
+}
+
+impl From<Option<&str>> for PassName {
+    #[inline(always)]
+    fn from(name: Option<&str>) -> Self {
+        PassName(name.map(String::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAW: CommandScope = CommandScope::RenderPass("Draw");
+    const BIND: CommandScope = CommandScope::AnyPass("BindDescriptorSets");
+    const COPY: CommandScope = CommandScope::Outside("CopyBufferToBuffer");
+    const DISPATCH: CommandScope = CommandScope::ComputePass("Dispatch");
+
+    fn begin(kind: PassKind, name: &str) -> CommandScope<'_> {
+        CommandScope::BeginPass(kind, Some(name))
+    }
+
+    fn end(kind: PassKind, name: &str) -> CommandScope<'_> {
+        CommandScope::EndPass(kind, Some(name))
+    }
+
+    #[test]
+    fn well_formed_passes() {
+        let scopes = [
+            COPY,
+            begin(PassKind::Render, "gbuffer"),
+            BIND,
+            DRAW,
+            end(PassKind::Render, "gbuffer"),
+            begin(PassKind::Compute, "cull"),
+            BIND,
+            DISPATCH,
+            end(PassKind::Compute, "cull"),
+            COPY,
+        ];
+        assert!(validate_scopes(scopes).is_ok());
+    }
+
+    #[test]
+    fn missing_end_render_pass() {
+        let err = validate_scopes([begin(PassKind::Render, "gbuffer"), DRAW]).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandValidationError::UnclosedPass {
+                kind: PassKind::Render,
+                ..
+            }
+        ));
+        assert_eq!(err.to_string(), "render pass `gbuffer` was never ended");
+    }
+
+    #[test]
+    fn missing_end_compute_pass() {
+        let err = validate_scopes([begin(PassKind::Compute, "cull"), BIND, COPY]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`CopyBufferToBuffer` can't be recorded within compute pass `cull`"
+        );
+
+        let err = validate_scopes([begin(PassKind::Compute, "cull")]).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandValidationError::UnclosedPass {
+                kind: PassKind::Compute,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn nested_passes() {
+        let err = validate_scopes([
+            begin(PassKind::Render, "gbuffer"),
+            begin(PassKind::Compute, "cull"),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "compute pass `cull` was begun within render pass `gbuffer`"
+        );
+    }
+
+    #[test]
+    fn mismatched_end() {
+        let err = validate_scopes([
+            begin(PassKind::Render, "gbuffer"),
+            end(PassKind::Compute, "cull"),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, CommandValidationError::MismatchedEnd { .. }));
+    }
+
+    #[test]
+    fn draw_outside_render_pass() {
+        let err = validate_scopes([DRAW]).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandValidationError::OutsideRenderPass { command: "Draw" }
+        ));
+
+        let err = validate_scopes([
+            begin(PassKind::Compute, "cull"),
+            DRAW,
+            end(PassKind::Compute, "cull"),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, CommandValidationError::WithinPass { .. }));
+    }
+
+    #[test]
+    fn dispatch_outside_compute_pass() {
+        let err = validate_scopes([COPY, DISPATCH]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`Dispatch` must be recorded within a compute pass"
+        );
+
+        let err = validate_scopes([
+            begin(PassKind::Render, "gbuffer"),
+            DISPATCH,
+            end(PassKind::Render, "gbuffer"),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, CommandValidationError::WithinPass { .. }));
+    }
+
+    #[test]
+    fn multiple_dispatches() {
+        let scopes = [
+            begin(PassKind::Compute, "prefix_sum"),
+            BIND,
+            DISPATCH,
+            CommandScope::ComputePass("ComputeBarrier"),
+            DISPATCH,
+            end(PassKind::Compute, "prefix_sum"),
+        ];
+        assert!(validate_scopes(scopes).is_ok());
+    }
+
+    #[test]
+    fn bind_outside_pass() {
+        let err = validate_scopes([BIND]).unwrap_err();
+        assert!(matches!(err, CommandValidationError::OutsidePass { .. }));
+    }
+
+    #[test]
+    fn unnamed_passes() {
+        let err =
+            validate_scopes([CommandScope::BeginPass(PassKind::RayTracing, None)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ray tracing pass (unnamed) was never ended"
+        );
+    }
+}
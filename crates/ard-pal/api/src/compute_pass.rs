@@ -1,5 +1,33 @@
+//! Compute passes.
+//!
+//! # Debugging Shaders
+//!
+//! When the context was created with debugging enabled, shaders can call `debugPrintfEXT` after
+//! enabling `GL_EXT_debug_printf`. Output is logged as it's reported by the validation layers,
+//! prefixed with the debug name of the submission the shader ran in.
+//!
+//! Printing from every invocation is rarely useful, so for checking invariants the renderer
+//! provides GPU asserts in `gpu_assert.glsl`, which are only compiled into debug builds:
+//!
+//! ```glsl
+//! #define ARD_SET_GPU_ASSERTS 2
+//! #include "ard_bindings.glsl"
+//! #include "gpu_assert.glsl"
+//!
+//! void main() {
+//!     ARD_ASSERT(gl_GlobalInvocationID.x < draw_count, draw_count);
+//! }
+//! ```
+//!
+//! The `GpuAsserts` set must be bound at the chosen index. The first failure each frame records
+//! the line, the value passed to the assert, and the invocation ID, and is reported once the
+//! frame has finished on the GPU, so checking it never stalls.
+
 use crate::{
-    buffer::Buffer, command_buffer::Command, descriptor_set::DescriptorSet, types::ShaderStage,
+    buffer::Buffer,
+    command_buffer::Command,
+    descriptor_set::{DescriptorSet, DescriptorSetLayout, DescriptorSetUpdate},
+    types::ShaderStage,
     Backend,
 };
 
@@ -17,6 +45,45 @@ pub enum ComputePassDispatch<'a, B: Backend> {
 }
 
 impl<'a, B: Backend> ComputePass<'a, B> {
+    /// Dispatches the bound pipeline.
+    ///
+    /// A pass can dispatch any number of times. If a set bound to the pass has a read-write
+    /// storage binding, each dispatch waits for the writes of the dispatches before it.
+    ///
+    /// # Arguments
+    /// - `x`, `y`, `z` - The number of work groups to dispatch along each axis.
+    #[inline]
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.commands
+            .push(Command::Dispatch(ComputePassDispatch::Inline(x, y, z)));
+    }
+
+    /// Dispatches the bound pipeline with work group counts read from a buffer. Synchronized
+    /// like [`dispatch`](ComputePass::dispatch).
+    ///
+    /// # Arguments
+    /// - `buffer` - The indirect buffer to read the work group counts from, as three `u32`s.
+    /// - `array_element` - The array element of the indirect buffer to read from.
+    /// - `offset` - The offset in bytes within the array element to read from.
+    #[inline]
+    pub fn dispatch_indirect(&mut self, buffer: &'a Buffer<B>, array_element: usize, offset: u64) {
+        self.commands
+            .push(Command::Dispatch(ComputePassDispatch::Indirect {
+                buffer,
+                array_element,
+                offset,
+            }));
+    }
+
+    /// Makes the writes of every previous dispatch visible to the dispatches after the barrier.
+    ///
+    /// Only needed when writes can't be seen through the bound sets, such as writes to buffers
+    /// accessed through their device address.
+    #[inline]
+    pub fn barrier(&mut self) {
+        self.commands.push(Command::ComputeBarrier);
+    }
+
     #[inline]
     pub fn push_constants(&mut self, data: &[u8]) {
         self.commands.push(Command::PushConstants {
@@ -47,4 +114,30 @@ impl<'a, B: Backend> ComputePass<'a, B> {
             stage: ShaderStage::Compute,
         });
     }
+
+    /// Pushes descriptors directly into the scope, replacing the set at `index`. Pushed
+    /// descriptors avoid allocating a set for data that changes with every draw or dispatch.
+    ///
+    /// # Arguments
+    /// - `index` - The index of the set to replace.
+    /// - `layout` - The layout of the set. Must have been created with `push` enabled.
+    /// - `bindings` - The values to bind. Bindings not provided are left undefined.
+    ///
+    /// # Panics
+    /// - If `layout` is not a push descriptor layout.
+    #[inline]
+    pub fn push_set(
+        &mut self,
+        index: usize,
+        layout: &DescriptorSetLayout<B>,
+        bindings: Vec<DescriptorSetUpdate<'a, B>>,
+    ) {
+        assert!(layout.is_push(), "layout is not a push descriptor layout");
+        self.commands.push(Command::PushDescriptorSet {
+            layout: layout.clone(),
+            index,
+            bindings,
+            stage: ShaderStage::Compute,
+        });
+    }
 }
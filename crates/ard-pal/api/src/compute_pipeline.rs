@@ -1,4 +1,10 @@
-use crate::{context::Context, descriptor_set::DescriptorSetLayout, shader::Shader, Backend};
+use crate::{
+    context::Context,
+    descriptor_set::DescriptorSetLayout,
+    graphics_pipeline::{check_pipeline_limits, PipelineLimitError},
+    shader::Shader,
+    Backend,
+};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -17,6 +23,17 @@ pub struct ComputePipelineCreateInfo<B: Backend> {
 
 #[derive(Debug, Error)]
 pub enum ComputePipelineCreateError {
+    #[error("{0}")]
+    Limits(#[from] PipelineLimitError),
+    #[error(
+        "work group size `{size:?}` exceeds the device limits of `{max:?}` with \
+        `{max_invocations}` invocations"
+    )]
+    WorkGroupTooLarge {
+        size: (u32, u32, u32),
+        max: [u32; 3],
+        max_invocations: u32,
+    },
     #[error("an error occured: {0}")]
     Other(String),
 }
@@ -46,6 +63,27 @@ impl<B: Backend> ComputePipeline<B> {
         assert_ne!(create_info.work_group_size.1, 0, "work group size y is 0");
         assert_ne!(create_info.work_group_size.2, 0, "work group size z is 0");
 
+        let limits = &ctx.properties().limits;
+        check_pipeline_limits(
+            limits,
+            create_info.push_constants_size,
+            &create_info.layouts,
+        )?;
+
+        let size = create_info.work_group_size;
+        if size.0 > limits.max_compute_work_group_size[0]
+            || size.1 > limits.max_compute_work_group_size[1]
+            || size.2 > limits.max_compute_work_group_size[2]
+            || size.0 as u64 * size.1 as u64 * size.2 as u64
+                > limits.max_compute_work_group_invocations as u64
+        {
+            return Err(ComputePipelineCreateError::WorkGroupTooLarge {
+                size,
+                max: limits.max_compute_work_group_size,
+                max_invocations: limits.max_compute_work_group_invocations,
+            });
+        }
+
         let layouts = create_info.layouts.clone();
         let id = unsafe { ctx.0.create_compute_pipeline(create_info)? };
         Ok(Self(Arc::new(ComputePipelineInner { ctx, id, layouts })))
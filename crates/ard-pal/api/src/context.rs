@@ -1,15 +1,252 @@
-use std::sync::Arc;
+//! The [`Context`] and the properties of the device it was created on.
+//!
+//! # Memory Budgets
+//!
+//! Systems that can trade quality for memory, like texture streaming, should stay within the
+//! [`memory_budget`](Context::memory_budget) of each heap instead of the heap size. The budget
+//! accounts for memory used by other processes, and going over it makes the OS page memory out
+//! of the device, which is much slower than using lower detail resources.
+//!
+//! The expected pattern is:
+//! 1. Register a callback with [`on_memory_pressure`](Context::on_memory_pressure) that flags the
+//!    system as under pressure. Callbacks are invoked from whichever thread submits work, so they
+//!    should only record the event.
+//! 2. Once per frame, the system checks the flag and [`memory_budget`](Context::memory_budget).
+//!    While usage is over the threshold, it evicts the least recently used data (such as the
+//!    highest mips of distant textures) and holds off on loading more.
+//! 3. Once usage has dropped back under the threshold, loading resumes. Leaving some headroom
+//!    between the level eviction stops at and the callback threshold avoids thrashing.
+//!
+//! Budgets are refreshed by the backend as jobs are submitted, so polling them is cheap.
+//!
+//! # Sampler Anisotropy
+//!
+//! Anisotropic filtering is usually a user facing quality setting, so the context holds a
+//! [`default_anisotropy`](Context::default_anisotropy) that systems creating samplers for
+//! artist authored textures should use instead of picking a level themselves. Levels are always
+//! clamped to [`DeviceLimits::max_sampler_anisotropy`] by the backend.
+//!
+//! Samplers are baked into descriptor sets when they're written, so changing the default only
+//! affects descriptors written afterwards. To apply a new level, a system should:
+//! 1. Call [`set_default_anisotropy`](Context::set_default_anisotropy).
+//! 2. Rewrite every descriptor it wrote with the old level, reading the new level when building
+//!    the [`Sampler`](crate::texture::Sampler). Descriptor sets in use by frames in flight must
+//!    not be updated, so sets that are duplicated per frame are rewritten as each frame comes up.
+//!
+//! Samplers with the old level stay cached by the backend until the context is dropped, so
+//! switching back and forth doesn't create new samplers.
+//!
+//! # Frame Pacing
+//!
+//! Each frame should be bracketed by [`begin_frame`](Context::begin_frame), which blocks until
+//! fewer than [`frames_in_flight`](Context::frames_in_flight) frames are in flight, and
+//! [`end_frame`](Context::end_frame), which records the jobs submitted for the frame. Once a
+//! frame's [`slot`](FrameToken::slot) is handed out again, every job of the frame that previously
+//! used it is complete, so per-frame resources indexed by the slot are safe to reuse.
 
-use crate::{queue::Queue, types::QueueType, Backend};
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::{
+    blas::BottomLevelAccelerationStructure,
+    buffer::{Buffer, BufferCreateError},
+    frame_pacer::{FramePacer, FrameToken, DEFAULT_FRAMES_IN_FLIGHT},
+    graphics_pipeline::GraphicsPipeline,
+    queue::{Job, Queue, SubmitError},
+    render_pass::RenderPassDescriptor,
+    types::{
+        AnisotropyLevel, BufferUsage, Format, FormatSupport, MultiSamples, QueueType, SampleCounts,
+        TextureUsage,
+    },
+    Backend,
+};
 
 /// The context is the entry point for Pal. It is used to create all other Pal objects.
 ///
 /// The context also provides you with a selection of four [`Queues`](Queue).
-pub struct Context<B: Backend>(pub(crate) Arc<B>);
+pub struct Context<B: Backend>(pub(crate) Arc<B>, Arc<Mutex<FramePacer<B>>>);
+
+#[derive(Debug, Error)]
+pub enum PipelineCacheSaveError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("an error has occured: {0}")]
+    Other(String),
+}
 
 #[derive(Debug, Default)]
 pub struct GraphicsProperties {
+    pub features: GraphicsFeatures,
     pub mesh_shading: MeshShadingProperties,
+    pub sparse: SparseProperties,
+    pub ray_tracing: RayTracingProperties,
+    pub samples: SampleCountProperties,
+    /// The backend was created with debugging enabled, so the api validates usage that backends
+    /// would otherwise only catch at execution time, such as descriptor set updates that don't
+    /// match their layout.
+    pub validation: bool,
+    /// Supported usages of textures with optimal tiling for every format. Formats that aren't
+    /// listed aren't supported at all.
+    pub formats: Vec<(Format, FormatSupport)>,
+    /// The adapter the backend was created on.
+    pub adapter: AdapterInfo,
+    pub limits: DeviceLimits,
+}
+
+/// Limits of the device. Objects and commands that go over them are rejected by the api.
+///
+/// The default limits are generous enough to never be hit, which is what backends that don't
+/// report a limit use.
+#[derive(Debug, Copy, Clone)]
+pub struct DeviceLimits {
+    /// Size in bytes of the data that can be pushed with `push_constants`.
+    pub max_push_constants_size: u32,
+    /// Maximum width of a 1D texture.
+    pub max_texture_dimension_1d: u32,
+    /// Maximum width and height of a 2D texture.
+    pub max_texture_dimension_2d: u32,
+    /// Maximum width, height and depth of a 3D texture.
+    pub max_texture_dimension_3d: u32,
+    /// Maximum size of each face of a cube map.
+    pub max_cube_map_dimension: u32,
+    /// Maximum number of array elements of a texture. Each array element of a cube map uses six.
+    pub max_texture_array_elements: u32,
+    /// Maximum number of descriptor sets a pipeline can use.
+    pub max_bound_descriptor_sets: u32,
+    /// Maximum number of samplers accessible to a single shader stage. Textures and cube maps are
+    /// bound with their sampler, so they count against this limit as well.
+    pub max_per_stage_descriptor_samplers: u32,
+    /// Maximum number of uniform buffers accessible to a single shader stage.
+    pub max_per_stage_descriptor_uniform_buffers: u32,
+    /// Maximum number of storage buffers accessible to a single shader stage.
+    pub max_per_stage_descriptor_storage_buffers: u32,
+    /// Maximum number of textures and cube maps accessible to a single shader stage.
+    pub max_per_stage_descriptor_sampled_images: u32,
+    /// Maximum number of storage images accessible to a single shader stage.
+    pub max_per_stage_descriptor_storage_images: u32,
+    /// Maximum size in bytes of a storage buffer binding.
+    pub max_storage_buffer_range: u32,
+    /// Maximum number of work groups of a dispatch in each dimension.
+    pub max_compute_work_group_count: [u32; 3],
+    /// Maximum size of a work group in each dimension.
+    pub max_compute_work_group_size: [u32; 3],
+    /// Maximum number of invocations in a single work group.
+    pub max_compute_work_group_invocations: u32,
+    /// Required alignment in bytes of the offset of uniform buffer bindings.
+    pub min_uniform_buffer_offset_alignment: u64,
+    /// Required alignment in bytes of the offset of storage buffer bindings.
+    pub min_storage_buffer_offset_alignment: u64,
+    /// Required alignment in bytes of the offset of views into uniform texel buffers. See
+    /// [`DeviceLimits::texel_buffer_offset_alignment`].
+    pub min_uniform_texel_buffer_offset_alignment: u64,
+    /// Views into uniform texel buffers may instead be aligned to a single texel, if that is
+    /// smaller than `min_uniform_texel_buffer_offset_alignment`.
+    pub uniform_texel_buffer_offset_single_texel_alignment: bool,
+    /// Required alignment in bytes of the offset of views into storage texel buffers. See
+    /// [`DeviceLimits::texel_buffer_offset_alignment`].
+    pub min_storage_texel_buffer_offset_alignment: u64,
+    /// Views into storage texel buffers may instead be aligned to a single texel, if that is
+    /// smaller than `min_storage_texel_buffer_offset_alignment`.
+    pub storage_texel_buffer_offset_single_texel_alignment: bool,
+    /// Maximum number of texels addressable through a single buffer view.
+    pub max_texel_buffer_elements: u32,
+    /// Copies between buffers and textures are fastest when the buffer offset is a multiple of
+    /// this.
+    pub optimal_buffer_copy_offset_alignment: u64,
+    /// Copies between buffers and textures are fastest when the bytes between rows in the buffer
+    /// are a multiple of this.
+    pub optimal_buffer_copy_row_pitch_alignment: u64,
+    pub max_sampler_anisotropy: f32,
+    /// Number of nanoseconds it takes for a timestamp to be incremented by one.
+    pub timestamp_period: f32,
+}
+
+/// A physical device a backend can be created on. See [`Context::enumerate_adapters`].
+#[derive(Debug, Default, Clone)]
+pub struct AdapterInfo {
+    pub name: String,
+    /// PCI vendor id.
+    pub vendor_id: u32,
+    /// Vendor specific device id.
+    pub device_id: u32,
+    pub ty: AdapterType,
+    /// Version of the driver, as reported by the driver.
+    pub driver_version: String,
+    /// Size in bytes of the device local memory.
+    pub vram: u64,
+    /// The adapter meets every requirement of the backend, so a backend can be created on it.
+    pub supported: bool,
+    /// Optional features supported by the adapter. Always empty for unsupported adapters.
+    pub features: GraphicsFeatures,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AdapterType {
+    Discrete,
+    Integrated,
+    Virtual,
+    /// Software rasterizer.
+    Cpu,
+    #[default]
+    Other,
+}
+
+/// Which adapter a backend should be created on.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum AdapterPreference {
+    /// The most capable supported adapter, preferring discrete over integrated GPUs.
+    #[default]
+    Default,
+    /// The adapter at the given index of [`Context::enumerate_adapters`].
+    ByIndex(usize),
+    /// The first adapter whose name contains the given string, ignoring case.
+    ByName(String),
+    /// An integrated GPU if one is supported. Falls back to the default otherwise.
+    PreferIntegrated,
+    /// A discrete GPU if one is supported. Falls back to the default otherwise.
+    PreferDiscrete,
+}
+
+/// Optional features supported by the backend. Using a feature that isn't supported is undefined
+/// behavior.
+#[derive(Debug, Default, Clone)]
+pub struct GraphicsFeatures {
+    pub mesh_shading: bool,
+    pub ray_tracing: bool,
+    /// Sparse textures can be created and have their pages bound on the transfer queue.
+    pub sparse_residency: bool,
+    /// Samplers can use a [`ReductionMode`](crate::types::ReductionMode).
+    pub sampler_reduction: bool,
+    /// Samplers can use [`CustomFloat`](crate::types::BorderColor::CustomFloat) and
+    /// [`CustomInt`](crate::types::BorderColor::CustomInt) border colors.
+    pub custom_border_color: bool,
+    /// Draws and dispatches within a
+    /// [`conditional_rendering`](crate::command_buffer::CommandBuffer::conditional_rendering)
+    /// region are skipped based on a predicate. When unsupported, they always execute.
+    pub conditional_rendering: bool,
+    /// Shaders of any stage can trace rays inline with `rayQueryEXT` against a
+    /// [`TopLevelAccelerationStructure`](crate::tlas::TopLevelAccelerationStructure) bound to
+    /// their descriptor sets.
+    pub ray_query: bool,
+    /// [Memory budgets](Context::memory_budget) are reported by the driver and account for
+    /// memory used by other processes.
+    pub memory_budget: bool,
+    /// Lines can be drawn with widths other than `1.0`.
+    pub wide_lines: bool,
+    /// Depth bias can be clamped.
+    pub depth_bias_clamp: bool,
+    /// Blend factors can read from a second color output of the fragment shader. Only a single
+    /// color attachment can be blended this way.
+    pub dual_source_blend: bool,
+    /// Render passes can broadcast draws to multiple layers of their attachments with a
+    /// [`view_mask`](crate::render_pass::RenderPassDescriptor::view_mask). Pipelines used within
+    /// such a pass can read `gl_ViewIndex`.
+    pub multiview: bool,
 }
 
 #[derive(Debug, Default)]
@@ -18,6 +255,375 @@ pub struct MeshShadingProperties {
     pub preferred_task_work_group_invocations: u32,
 }
 
+#[derive(Debug, Default)]
+pub struct SparseProperties {
+    /// Size in bytes of a single page of memory bound to a sparse texture.
+    pub page_size: u64,
+    /// Dimensions in texels of a single page of a 2D sparse texture for every format that
+    /// supports sparse residency.
+    pub granularity: Vec<(Format, (u32, u32, u32))>,
+}
+
+#[derive(Debug, Default)]
+pub struct RayTracingProperties {
+    /// Required alignment in bytes of the scratch memory of each acceleration structure build.
+    pub min_scratch_offset_alignment: u64,
+}
+
+/// Sample counts supported by the device for each kind of texture usage. Single sampling is
+/// always supported.
+#[derive(Debug, Default)]
+pub struct SampleCountProperties {
+    /// Supported by color attachments.
+    pub color: SampleCounts,
+    /// Supported by the depth aspect of depth stencil attachments.
+    pub depth: SampleCounts,
+    /// Supported by the stencil aspect of depth stencil attachments.
+    pub stencil: SampleCounts,
+    /// Supported by storage textures.
+    pub storage: SampleCounts,
+}
+
+/// Invoked when memory usage of a heap crosses a fraction of its budget. See
+/// [`Context::on_memory_pressure`].
+pub type MemoryPressureCallback = Box<dyn Fn(&MemoryPressure) + Send + Sync>;
+
+/// Number of allocations listed by [`Context::report_memory`].
+pub const MEMORY_REPORT_ALLOCATIONS: usize = 32;
+
+/// The kind of resource a GPU memory allocation backs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MemoryCategory {
+    Buffer,
+    Texture,
+    CubeMap,
+    AccelerationStructure,
+}
+
+/// A snapshot of the GPU memory used by the context.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStats {
+    /// Every memory heap of the device.
+    pub heaps: Vec<MemoryHeapStats>,
+    /// Allocations of each category, indexed by [`MemoryCategory`].
+    pub categories: [MemoryCategoryStats; MemoryCategory::COUNT],
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MemoryHeapStats {
+    /// Size in bytes of the heap.
+    pub size: u64,
+    /// The heap is in device memory. Other heaps are in host memory visible to the device.
+    pub device_local: bool,
+    /// Sum of the sizes in bytes of live allocations made from the heap. Memory reserved by the
+    /// backend but not yet handed out is not included.
+    pub allocated: u64,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MemoryCategoryStats {
+    /// Number of live allocations.
+    pub count: u64,
+    /// Sum of the sizes in bytes of live allocations.
+    pub bytes: u64,
+}
+
+/// How much memory the process can use from each heap before the OS starts paging it out.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryBudget {
+    /// Every memory heap of the device, in the same order as [`MemoryStats::heaps`].
+    pub heaps: Vec<MemoryHeapBudget>,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MemoryHeapBudget {
+    /// Bytes the process can allocate from the heap without degrading performance.
+    pub budget: u64,
+    /// Bytes the process has allocated from the heap.
+    pub usage: u64,
+}
+
+/// Describes a heap whose usage went over the threshold a
+/// [`MemoryPressureCallback`] was registered with.
+#[derive(Debug, Copy, Clone)]
+pub struct MemoryPressure {
+    /// Index of the heap within [`MemoryBudget::heaps`].
+    pub heap: usize,
+    pub budget: MemoryHeapBudget,
+    /// The fraction of the budget the callback was registered with.
+    pub threshold: f32,
+}
+
+/// A single live allocation, as reported by [`Context::report_memory`].
+#[derive(Debug, Clone)]
+pub struct MemoryAllocationInfo {
+    /// Debug name of the resource the allocation backs.
+    pub name: String,
+    pub category: MemoryCategory,
+    /// Size in bytes of the allocation.
+    pub size: u64,
+}
+
+/// Resources that were destroyed but haven't been released by the backend yet. See
+/// [`Context::pending_garbage`].
+#[derive(Debug, Default, Clone)]
+pub struct GarbageReport {
+    pub items: Vec<PendingGarbage>,
+}
+
+/// A single destroyed resource waiting to be released.
+#[derive(Debug, Clone)]
+pub struct PendingGarbage {
+    pub kind: GarbageKind,
+    /// Debug name of the resource. Only known for resources backed by tracked memory.
+    pub debug_name: Option<String>,
+    /// Timeline values each queue must reach before the resource can be released.
+    pub wait_values: QueueTimelineValues,
+    /// The resource is still referenced by another object, such as a descriptor set, and won't be
+    /// released until that reference is dropped, even once the timeline values are reached.
+    pub referenced: bool,
+}
+
+/// A value on the timeline of each queue.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct QueueTimelineValues {
+    pub main: u64,
+    pub transfer: u64,
+    pub compute: u64,
+    pub present: u64,
+}
+
+/// The kind of resource a [`PendingGarbage`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GarbageKind {
+    Pipeline,
+    PipelineLayout,
+    Buffer,
+    AccelerationStructure,
+    Texture,
+    /// Memory unbound from the pages of a sparse texture.
+    SparsePages,
+    DescriptorSet,
+    /// Values replaced within a descriptor set that was updated while in use.
+    DescriptorBindings,
+    Swapchain,
+    Queries,
+    /// Events used by the split barriers of a submission.
+    Events,
+}
+
+impl AdapterPreference {
+    /// Picks the adapter to create a backend on. Returns `None` if the requested adapter doesn't
+    /// exist or isn't supported.
+    pub fn select(&self, adapters: &[AdapterInfo]) -> Option<usize> {
+        let supported = |idx: &usize| adapters[*idx].supported;
+        let of_type = |ty: AdapterType| {
+            (0..adapters.len())
+                .filter(supported)
+                .find(|idx| adapters[*idx].ty == ty)
+        };
+
+        match self {
+            AdapterPreference::Default => (0..adapters.len())
+                .filter(supported)
+                .max_by_key(|idx| adapters[*idx].ty.rank()),
+            AdapterPreference::ByIndex(idx) => Some(*idx)
+                .filter(|idx| *idx < adapters.len())
+                .filter(supported),
+            AdapterPreference::ByName(name) => {
+                let name = name.to_lowercase();
+                (0..adapters.len())
+                    .filter(supported)
+                    .find(|idx| adapters[*idx].name.to_lowercase().contains(&name))
+            }
+            AdapterPreference::PreferIntegrated => of_type(AdapterType::Integrated)
+                .or_else(|| AdapterPreference::Default.select(adapters)),
+            AdapterPreference::PreferDiscrete => of_type(AdapterType::Discrete)
+                .or_else(|| AdapterPreference::Default.select(adapters)),
+        }
+    }
+}
+
+impl AdapterType {
+    /// How capable adapters of this type usually are. Higher is better.
+    #[inline(always)]
+    fn rank(self) -> u32 {
+        match self {
+            AdapterType::Discrete => 4,
+            AdapterType::Integrated => 3,
+            AdapterType::Cpu => 2,
+            AdapterType::Virtual => 1,
+            AdapterType::Other => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for AdapterInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:?}", self.name, self.ty)?;
+        if !self.supported {
+            write!(f, ", unsupported")?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl MemoryCategory {
+    pub const COUNT: usize = 4;
+
+    pub const ALL: [MemoryCategory; Self::COUNT] = [
+        MemoryCategory::Buffer,
+        MemoryCategory::Texture,
+        MemoryCategory::CubeMap,
+        MemoryCategory::AccelerationStructure,
+    ];
+}
+
+impl MemoryHeapBudget {
+    /// Gets the fraction of the budget in use. Can be greater than `1.0` if the process is over
+    /// budget.
+    #[inline(always)]
+    pub fn fraction(&self) -> f32 {
+        if self.budget == 0 {
+            return 0.0;
+        }
+        self.usage as f32 / self.budget as f32
+    }
+}
+
+impl MemoryStats {
+    /// Gets the allocations of a single category.
+    #[inline(always)]
+    pub fn category(&self, category: MemoryCategory) -> &MemoryCategoryStats {
+        &self.categories[category as usize]
+    }
+
+    /// Sum of the sizes in bytes of every live allocation.
+    #[inline(always)]
+    pub fn allocated_bytes(&self) -> u64 {
+        self.heaps.iter().map(|heap| heap.allocated).sum()
+    }
+}
+
+impl Default for DeviceLimits {
+    fn default() -> Self {
+        Self {
+            max_push_constants_size: u32::MAX,
+            max_texture_dimension_1d: u32::MAX,
+            max_texture_dimension_2d: u32::MAX,
+            max_texture_dimension_3d: u32::MAX,
+            max_cube_map_dimension: u32::MAX,
+            max_texture_array_elements: u32::MAX,
+            max_bound_descriptor_sets: u32::MAX,
+            max_per_stage_descriptor_samplers: u32::MAX,
+            max_per_stage_descriptor_uniform_buffers: u32::MAX,
+            max_per_stage_descriptor_storage_buffers: u32::MAX,
+            max_per_stage_descriptor_sampled_images: u32::MAX,
+            max_per_stage_descriptor_storage_images: u32::MAX,
+            max_storage_buffer_range: u32::MAX,
+            max_compute_work_group_count: [u32::MAX; 3],
+            max_compute_work_group_size: [u32::MAX; 3],
+            max_compute_work_group_invocations: u32::MAX,
+            min_uniform_buffer_offset_alignment: 1,
+            min_storage_buffer_offset_alignment: 1,
+            min_uniform_texel_buffer_offset_alignment: 1,
+            uniform_texel_buffer_offset_single_texel_alignment: false,
+            min_storage_texel_buffer_offset_alignment: 1,
+            storage_texel_buffer_offset_single_texel_alignment: false,
+            max_texel_buffer_elements: u32::MAX,
+            optimal_buffer_copy_offset_alignment: 1,
+            optimal_buffer_copy_row_pitch_alignment: 1,
+            max_sampler_anisotropy: 16.0,
+            timestamp_period: 1.0,
+        }
+    }
+}
+
+impl DeviceLimits {
+    /// Gets the alignment in bytes required of the offset of a view with the given format into a
+    /// buffer with the given usage. Buffers with both texel buffer usages must satisfy both
+    /// alignments.
+    pub fn texel_buffer_offset_alignment(&self, format: Format, usage: BufferUsage) -> u64 {
+        // Three component formats only need to be aligned to a single component
+        let texel_size = match format {
+            Format::Rgb32SFloat => 4,
+            format => format.bytes_per_block() as u64,
+        };
+        let alignment = |min: u64, single_texel: bool| {
+            if single_texel {
+                min.min(texel_size)
+            } else {
+                min
+            }
+        };
+
+        let mut out = 1;
+        if usage.contains(BufferUsage::UNIFORM_TEXEL_BUFFER) {
+            out = out.max(alignment(
+                self.min_uniform_texel_buffer_offset_alignment,
+                self.uniform_texel_buffer_offset_single_texel_alignment,
+            ));
+        }
+        if usage.contains(BufferUsage::STORAGE_TEXEL_BUFFER) {
+            out = out.max(alignment(
+                self.min_storage_texel_buffer_offset_alignment,
+                self.storage_texel_buffer_offset_single_texel_alignment,
+            ));
+        }
+        out
+    }
+}
+
+impl GraphicsProperties {
+    /// Gets every sample count supported by a texture with the given format and usage.
+    pub fn supported_samples(&self, format: Format, usage: TextureUsage) -> SampleCounts {
+        let mut counts = SampleCounts::all();
+        if usage.contains(TextureUsage::COLOR_ATTACHMENT) {
+            counts &= self.samples.color;
+        }
+        if usage.contains(TextureUsage::DEPTH_STENCIL_ATTACHMENT) {
+            if format.is_depth() {
+                counts &= self.samples.depth;
+            }
+            if format.is_stencil() {
+                counts &= self.samples.stencil;
+            }
+        }
+        if usage.contains(TextureUsage::STORAGE) {
+            counts &= self.samples.storage;
+        }
+        counts | SampleCounts::COUNT_1
+    }
+
+    /// Gets the highest sample count supported by a texture with the given format and usage.
+    #[inline(always)]
+    pub fn max_supported_samples(&self, format: Format, usage: TextureUsage) -> MultiSamples {
+        self.supported_samples(format, usage).max_samples()
+    }
+
+    /// Gets the ways a texture with the given format can be used.
+    #[inline(always)]
+    pub fn format_support(&self, format: Format) -> FormatSupport {
+        self.formats
+            .iter()
+            .find(|(other, _)| *other == format)
+            .map(|(_, support)| *support)
+            .unwrap_or_default()
+    }
+}
+
+impl SparseProperties {
+    /// Gets the dimensions in texels of a single page of a 2D sparse texture with the given
+    /// format. Returns `None` if the format doesn't support sparse residency.
+    #[inline(always)]
+    pub fn granularity(&self, format: Format) -> Option<(u32, u32, u32)> {
+        self.granularity
+            .iter()
+            .find(|(other, _)| *other == format)
+            .map(|(_, granularity)| *granularity)
+    }
+}
+
 impl<B: Backend> Context<B> {
     /// Creates a new Pal instance.
     ///
@@ -27,7 +633,18 @@ impl<B: Backend> Context<B> {
     /// selection to choose from.
     #[inline(always)]
     pub fn new(backend: B) -> Self {
-        Self(Arc::new(backend))
+        Self(
+            Arc::new(backend),
+            Arc::new(Mutex::new(FramePacer::new(DEFAULT_FRAMES_IN_FLIGHT))),
+        )
+    }
+
+    /// Lists every adapter the backend could be created on, including the ones that don't meet
+    /// its requirements. Doesn't require a backend to be created. The order matches
+    /// [`AdapterPreference::ByIndex`].
+    #[inline(always)]
+    pub fn enumerate_adapters() -> Vec<AdapterInfo> {
+        unsafe { B::enumerate_adapters() }
     }
 
     /// Gets a reference to the primary queue.
@@ -51,6 +668,8 @@ impl<B: Backend> Context<B> {
     /// - [`copy_buffer_to_buffer`](crate::command_buffer::CommandBuffer::copy_buffer_to_buffer)
     /// - [`copy_buffer_to_texture`](crate::command_buffer::CommandBuffer::copy_buffer_to_texture)
     /// - [`copy_texture_to_buffer`](crate::command_buffer::CommandBuffer::copy_texture_to_buffer)
+    /// - [`bind_sparse_pages`](crate::command_buffer::CommandBuffer::bind_sparse_pages)
+    /// - [`unbind_sparse_pages`](crate::command_buffer::CommandBuffer::unbind_sparse_pages)
     #[inline(always)]
     pub fn transfer(&self) -> Queue<B> {
         Queue::new(self.clone(), QueueType::Transfer)
@@ -73,14 +692,419 @@ impl<B: Backend> Context<B> {
         Queue::new(self.clone(), QueueType::Present)
     }
 
+    /// Creates a transient buffer. Transient buffers are suballocated from large blocks owned by
+    /// the backend instead of getting their own allocation, which makes them cheap to create and
+    /// destroy every frame. The memory is reclaimed once the buffer is dropped and every job
+    /// submitted before the drop has completed.
+    ///
+    /// Transient buffers are [`CpuToGpu`](crate::types::MemoryUsage::CpuToGpu), have a single
+    /// array element, and are shared concurrently between the main, transfer, and compute queues.
+    ///
+    /// # Arguments
+    /// - `size` - The size in bytes of the buffer.
+    /// - `usage` - Describes the supported usage types of the buffer.
+    ///
+    /// # Panics
+    /// - If `size` is `0`.
+    /// - If `usage` is not contained in
+    ///   [`TRANSIENT_BUFFER_USAGE`](crate::buffer::TRANSIENT_BUFFER_USAGE).
+    #[inline(always)]
+    pub fn transient_buffer(
+        &self,
+        size: u64,
+        usage: BufferUsage,
+    ) -> Result<Buffer<B>, BufferCreateError> {
+        Buffer::new_transient(self.clone(), size, usage)
+    }
+
+    /// The maximum number of frames allowed in flight. Defaults to
+    /// [`DEFAULT_FRAMES_IN_FLIGHT`].
+    #[inline(always)]
+    pub fn frames_in_flight(&self) -> usize {
+        self.1.lock().unwrap().frames_in_flight()
+    }
+
+    /// Changes how many frames are allowed in flight. Blocks until every in-flight frame is
+    /// complete so slots handed out afterwards can't overlap with older ones.
+    ///
+    /// # Panics
+    /// - If `frames_in_flight` is `0`.
+    pub fn set_frames_in_flight(&self, frames_in_flight: usize) {
+        self.1
+            .lock()
+            .unwrap()
+            .set_frames_in_flight(&self.0, frames_in_flight);
+    }
+
+    /// Number of frames that have ended but might still be running on the GPU.
+    #[inline(always)]
+    pub fn pending_frames(&self) -> usize {
+        self.1.lock().unwrap().pending_frames()
+    }
+
+    /// Time the CPU spent blocked on the GPU when the most recent frame began. Consistently high
+    /// values mean the GPU is the bottleneck.
+    #[inline(always)]
+    pub fn last_frame_wait_time(&self) -> Duration {
+        self.1.lock().unwrap().last_wait_time()
+    }
+
+    /// Begins a new frame, blocking until fewer than the maximum number of frames are in flight.
+    pub fn begin_frame(&self) -> FrameToken {
+        self.1.lock().unwrap().begin_frame(&self.0)
+    }
+
+    /// Ends a frame, associating it with the jobs that must complete before its slot is reused.
+    /// Frames must end in the order they began.
+    pub fn end_frame(&self, token: FrameToken, jobs: impl IntoIterator<Item = Job<B>>) {
+        let jobs = jobs.into_iter().map(Job::into_id).collect();
+        self.1.lock().unwrap().end_frame(token, jobs);
+    }
+
+    /// Blocks until every in-flight frame is complete.
+    pub fn wait_frames_idle(&self) {
+        self.1.lock().unwrap().wait_idle(&self.0);
+    }
+
     #[inline(always)]
     pub fn properties(&self) -> &GraphicsProperties {
         unsafe { self.0.properties() }
     }
+
+    /// Writes the pipeline cache to disk so later runs can skip shader compilation. Does nothing if
+    /// the backend wasn't given a place to store the cache. The cache is also saved when the
+    /// context is dropped.
+    #[inline(always)]
+    pub fn save_pipeline_cache(&self) -> Result<(), PipelineCacheSaveError> {
+        unsafe { self.0.save_pipeline_cache() }
+    }
+
+    /// Begins compiling `pipelines` in the background for use with render passes matching
+    /// `render_pass`, so that they're ready by the time they're first drawn with.
+    #[inline(always)]
+    pub fn precompile_graphics_pipelines(
+        &self,
+        pipelines: &[GraphicsPipeline<B>],
+        render_pass: &RenderPassDescriptor<B>,
+    ) {
+        let pipelines: Vec<_> = pipelines
+            .iter()
+            .map(|pipeline| pipeline.internal())
+            .collect();
+        unsafe {
+            self.0
+                .precompile_graphics_pipelines(&pipelines, render_pass)
+        }
+    }
+
+    /// Gets the number of graphics pipelines still being compiled in the background. While this
+    /// is non-zero, some draws may have been skipped or used a fallback pipeline.
+    #[inline(always)]
+    pub fn pending_pipeline_compiles(&self) -> usize {
+        unsafe { self.0.pending_pipeline_compiles() }
+    }
+
+    /// Returns `true` if the device was lost, usually because of a GPU hang. Once lost, submitted
+    /// jobs never complete, so the context and everything created from it should be torn down.
+    #[inline(always)]
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost().is_some()
+    }
+
+    /// Gets the error describing how the device was lost, or `None` if it wasn't.
+    #[inline(always)]
+    pub fn device_lost(&self) -> Option<SubmitError> {
+        unsafe { self.0.device_lost() }
+    }
+
+    /// Gets a snapshot of the GPU memory used by the context. This only reads counters the
+    /// backend maintains as resources are created and destroyed, so it is cheap enough to poll
+    /// every frame.
+    #[inline(always)]
+    pub fn memory_stats(&self) -> MemoryStats {
+        unsafe { self.0.memory_stats() }
+    }
+
+    /// Gets how much memory can be used from each heap, as of the last time the backend refreshed
+    /// it. Budgets are refreshed when jobs are submitted, so this is cheap enough to poll every
+    /// frame.
+    ///
+    /// If [`memory_budget`](GraphicsFeatures::memory_budget) is unsupported, the budget of each
+    /// heap is its size and usage only counts memory allocated by this context.
+    #[inline(always)]
+    pub fn memory_budget(&self) -> MemoryBudget {
+        unsafe { self.0.memory_budget() }
+    }
+
+    /// Registers a callback that is invoked when memory usage of a heap goes over `threshold`,
+    /// a fraction of the heap's [`budget`](MemoryHeapBudget::budget). The callback is invoked
+    /// once each time usage crosses the threshold, not for as long as usage stays over it. See
+    /// the [module documentation](self) for how the callback is meant to be used.
+    ///
+    /// The callback is invoked on the thread that refreshed the budget, which is usually a
+    /// thread submitting work. It must not block.
+    ///
+    /// # Panics
+    /// - If `threshold` is not greater than `0.0`.
+    pub fn on_memory_pressure(
+        &self,
+        threshold: f32,
+        callback: impl Fn(&MemoryPressure) + Send + Sync + 'static,
+    ) {
+        assert!(
+            threshold > 0.0,
+            "memory pressure threshold must be greater than zero"
+        );
+        unsafe { self.0.on_memory_pressure(threshold, Box::new(callback)) }
+    }
+
+    /// Releases every destroyed resource that is no longer in use by the GPU, without submitting
+    /// any work. Resources are otherwise released when jobs are submitted or observed to be
+    /// complete, so this is only needed to reclaim memory deterministically, such as before
+    /// checking [`memory_stats`](Self::memory_stats) or at the end of a loading screen.
+    #[inline(always)]
+    pub fn collect_garbage(&self) {
+        unsafe { self.0.collect_garbage() }
+    }
+
+    /// Lists every destroyed resource the backend hasn't released yet and what it is waiting on.
+    /// Useful for tracking down resources that are never released because a reference to them is
+    /// kept alive.
+    #[inline(always)]
+    pub fn pending_garbage(&self) -> GarbageReport {
+        unsafe { self.0.pending_garbage() }
+    }
+
+    /// Writes a human readable summary of GPU memory usage to `report`, including the
+    /// [`MEMORY_REPORT_ALLOCATIONS`] largest live allocations and their debug names.
+    pub fn report_memory(&self, report: &mut dyn Write) -> std::io::Result<()> {
+        const MIB: f64 = 1024.0 * 1024.0;
+
+        let stats = self.memory_stats();
+        let mut allocations = unsafe { self.0.memory_allocations() };
+        allocations.sort_unstable_by_key(|allocation| std::cmp::Reverse(allocation.size));
+
+        writeln!(report, "heaps:")?;
+        for (i, heap) in stats.heaps.iter().enumerate() {
+            writeln!(
+                report,
+                "  {i}: {:.2} / {:.2} MiB{}",
+                heap.allocated as f64 / MIB,
+                heap.size as f64 / MIB,
+                if heap.device_local {
+                    " (device local)"
+                } else {
+                    ""
+                },
+            )?;
+        }
+
+        writeln!(report, "categories:")?;
+        for category in MemoryCategory::ALL {
+            let category_stats = stats.category(category);
+            writeln!(
+                report,
+                "  {category:?}: {} allocations, {:.2} MiB",
+                category_stats.count,
+                category_stats.bytes as f64 / MIB,
+            )?;
+        }
+
+        writeln!(
+            report,
+            "largest allocations ({} of {}):",
+            allocations.len().min(MEMORY_REPORT_ALLOCATIONS),
+            allocations.len()
+        )?;
+        for allocation in allocations.iter().take(MEMORY_REPORT_ALLOCATIONS) {
+            writeln!(
+                report,
+                "  {:.2} MiB {:?} {}",
+                allocation.size as f64 / MIB,
+                allocation.category,
+                allocation.name,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the anisotropy level samplers for artist authored textures should use. `None` means
+    /// anisotropic filtering is disabled, which is the default for new contexts.
+    #[inline(always)]
+    pub fn default_anisotropy(&self) -> Option<AnisotropyLevel> {
+        unsafe { self.0.default_anisotropy() }
+    }
+
+    /// Sets the level returned by [`default_anisotropy`](Self::default_anisotropy), clamped to
+    /// [`DeviceLimits::max_sampler_anisotropy`]. Descriptors that were already written keep the
+    /// level they were written with. See the [module documentation](self#sampler-anisotropy) for
+    /// how to apply the new level to them.
+    #[inline(always)]
+    pub fn set_default_anisotropy(&self, level: Option<AnisotropyLevel>) {
+        let max = self.properties().limits.max_sampler_anisotropy;
+        unsafe {
+            self.0
+                .set_default_anisotropy(level.map(|level| level.clamp_to(max)))
+        }
+    }
+
+    /// Asks RenderDoc to capture the next frame presented to any surface.
+    ///
+    /// Captures require Pal to be built with the `renderdoc` feature and the application to be
+    /// launched through RenderDoc, or with RenderDoc installed where the loader can find it. If
+    /// RenderDoc isn't attached, this does nothing and a warning is logged the first time.
+    #[inline(always)]
+    pub fn trigger_capture(&self) {
+        unsafe { self.0.trigger_capture() }
+    }
+
+    /// Starts a RenderDoc capture of all work submitted until
+    /// [`end_capture`](Self::end_capture) is called. Useful for capturing work that doesn't
+    /// present, like headless rendering or compute. Does nothing if a capture is already in
+    /// progress or RenderDoc isn't attached.
+    #[inline(always)]
+    pub fn begin_capture(&self) {
+        unsafe { self.0.begin_capture() }
+    }
+
+    /// Ends a capture started with [`begin_capture`](Self::begin_capture). Does nothing if no
+    /// capture is in progress.
+    #[inline(always)]
+    pub fn end_capture(&self) {
+        unsafe { self.0.end_capture() }
+    }
+
+    /// Gets the compacted size of `blas` without waiting for the job that built it.
+    ///
+    /// Returns `None` until the job that built `blas` has completed, or if `blas` wasn't built
+    /// with [`ALLOW_COMPACTION`](crate::types::BuildAccelerationStructureFlags::ALLOW_COMPACTION).
+    /// See [`poll_compacted_sizes`](Self::poll_compacted_sizes) to check many BLAS' at once.
+    #[inline(always)]
+    pub fn blas_compacted_size_poll(
+        &self,
+        blas: &BottomLevelAccelerationStructure<B>,
+    ) -> Option<u64> {
+        self.poll_compacted_sizes(&[blas])[0]
+    }
+
+    /// Gets the compacted size of each BLAS in `blases` without waiting. Sizes that aren't
+    /// available yet are `None`, as described by
+    /// [`blas_compacted_size_poll`](Self::blas_compacted_size_poll).
+    pub fn poll_compacted_sizes(
+        &self,
+        blases: &[&BottomLevelAccelerationStructure<B>],
+    ) -> Vec<Option<u64>> {
+        let ids: Vec<_> = blases.iter().map(|blas| blas.internal()).collect();
+        unsafe { self.0.blas_poll_compacted_sizes(&ids) }
+    }
+
+    /// Gets the ways a texture with the given format can be used on this device. See
+    /// [`GraphicsProperties::format_support`].
+    #[inline(always)]
+    pub fn format_properties(&self, format: Format) -> FormatSupport {
+        self.properties().format_support(format)
+    }
 }
 
 impl<B: Backend> Clone for Context<B> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self(self.0.clone(), self.1.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, ty: AdapterType, supported: bool) -> AdapterInfo {
+        AdapterInfo {
+            name: String::from(name),
+            ty,
+            supported,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adapter_selection() {
+        let adapters = [
+            adapter("Intel(R) UHD Graphics", AdapterType::Integrated, true),
+            adapter("NVIDIA GeForce RTX 3070", AdapterType::Discrete, true),
+            adapter("llvmpipe", AdapterType::Cpu, false),
+        ];
+
+        assert_eq!(AdapterPreference::Default.select(&adapters), Some(1));
+        assert_eq!(AdapterPreference::PreferDiscrete.select(&adapters), Some(1));
+        assert_eq!(
+            AdapterPreference::PreferIntegrated.select(&adapters),
+            Some(0)
+        );
+        assert_eq!(AdapterPreference::ByIndex(0).select(&adapters), Some(0));
+        assert_eq!(
+            AdapterPreference::ByName(String::from("geforce")).select(&adapters),
+            Some(1)
+        );
+
+        // Missing or unsupported adapters are never picked
+        assert_eq!(AdapterPreference::ByIndex(2).select(&adapters), None);
+        assert_eq!(AdapterPreference::ByIndex(3).select(&adapters), None);
+        assert_eq!(
+            AdapterPreference::ByName(String::from("llvmpipe")).select(&adapters),
+            None
+        );
+
+        // Preferences fall back to the most capable adapter
+        assert_eq!(
+            AdapterPreference::PreferIntegrated.select(&adapters[1..]),
+            Some(0)
+        );
+        assert_eq!(AdapterPreference::Default.select(&adapters[2..]), None);
+    }
+
+    #[test]
+    fn anisotropy_is_clamped_to_limits() {
+        assert_eq!(AnisotropyLevel::X16.clamp_to(16.0), AnisotropyLevel::X16);
+        assert_eq!(AnisotropyLevel::X16.clamp_to(12.0), AnisotropyLevel::X8);
+        assert_eq!(AnisotropyLevel::X2.clamp_to(16.0), AnisotropyLevel::X2);
+
+        // Devices without anisotropic filtering report a limit of one
+        assert_eq!(AnisotropyLevel::X4.clamp_to(1.0), AnisotropyLevel::X1);
+        assert_eq!(AnisotropyLevel::X4.clamp_to(0.0), AnisotropyLevel::X1);
+    }
+
+    #[test]
+    fn texel_buffer_offset_alignment() {
+        let limits = DeviceLimits {
+            min_uniform_texel_buffer_offset_alignment: 16,
+            uniform_texel_buffer_offset_single_texel_alignment: true,
+            min_storage_texel_buffer_offset_alignment: 64,
+            storage_texel_buffer_offset_single_texel_alignment: false,
+            ..Default::default()
+        };
+        let uniform = BufferUsage::UNIFORM_TEXEL_BUFFER;
+        let storage = BufferUsage::STORAGE_TEXEL_BUFFER;
+
+        // Single texel alignment never asks for more than the minimum
+        assert_eq!(
+            limits.texel_buffer_offset_alignment(Format::R32SFloat, uniform),
+            4
+        );
+        assert_eq!(
+            limits.texel_buffer_offset_alignment(Format::Rgba32SFloat, uniform),
+            16
+        );
+
+        // Three component formats are aligned to a single component
+        assert_eq!(
+            limits.texel_buffer_offset_alignment(Format::Rgb32SFloat, uniform),
+            4
+        );
+
+        // Buffers with both usages must satisfy both
+        assert_eq!(
+            limits.texel_buffer_offset_alignment(Format::R32SFloat, uniform | storage),
+            64
+        );
     }
 }
@@ -1,8 +1,15 @@
-use crate::{context::Context, types::*, Backend};
+use crate::{
+    buffer::{upload_owner, BufferCreateError},
+    command_buffer::BufferCubeMapCopy,
+    context::Context,
+    texture::{initial_layer_sizes, new_initial_data_staging, InitialDataError},
+    types::*,
+    Backend,
+};
 
 use thiserror::*;
 
-pub struct CubeMapCreateInfo {
+pub struct CubeMapCreateInfo<'a> {
     pub format: Format,
     pub size: u32,
     pub array_elements: usize,
@@ -12,10 +19,38 @@ pub struct CubeMapCreateInfo {
     pub queue_types: QueueTypes,
     pub sharing_mode: SharingMode,
     pub debug_name: Option<String>,
+    /// Data to upload to the cube map after creation, one slice per mip starting at the base mip.
+    /// Each slice contains every array element of the mip back to back. Each array element
+    /// contains its six faces in [`CubeFace::ALL`] order, each made of tightly packed texel
+    /// blocks (see [`Format::block_info`]). Trailing mips may be omitted.
+    ///
+    /// The upload is performed on the transfer queue, so the cube map is given
+    /// [`TRANSFER_DST`](TextureUsage::TRANSFER_DST) usage and is made available to the transfer
+    /// queue. [`Exclusive`](SharingMode::Exclusive) cube maps are handed to the main queue, or
+    /// the compute queue if the cube map isn't used on the main queue.
+    pub initial_data: Option<&'a [&'a [u8]]>,
 }
 
 #[derive(Debug, Error)]
 pub enum CubeMapCreateError {
+    #[error("format `{0:?}` does not support usage `{1:?}`")]
+    UnsupportedFormat(Format, TextureUsage),
+    #[error(
+        "cube map of size `{size}` with `{array_elements}` array elements exceeds the device \
+        limits of size `{max_size}` with `{max_array_elements}` array elements"
+    )]
+    ExceedsLimits {
+        size: u32,
+        array_elements: usize,
+        max_size: u32,
+        max_array_elements: usize,
+    },
+    #[error("{0}")]
+    InitialData(#[from] InitialDataError),
+    #[error("unable to create staging buffer: {0}")]
+    Staging(#[from] BufferCreateError),
+    #[error("out of memory allocating `{requested}` bytes from heap `{heap}`")]
+    OutOfMemory { requested: u64, heap: usize },
     #[error("an error has occured: {0}")]
     Other(String),
 }
@@ -23,7 +58,10 @@ pub enum CubeMapCreateError {
 pub struct CubeMap<B: Backend> {
     ctx: Context<B>,
     dim: u32,
+    format: Format,
     mip_count: usize,
+    array_elements: usize,
+    texture_usage: TextureUsage,
     queue_types: QueueTypes,
     sharing_mode: SharingMode,
     pub(crate) id: B::CubeMap,
@@ -32,21 +70,149 @@ pub struct CubeMap<B: Backend> {
 impl<B: Backend> CubeMap<B> {
     pub fn new(
         ctx: Context<B>,
-        create_info: CubeMapCreateInfo,
+        mut create_info: CubeMapCreateInfo,
     ) -> Result<Self, CubeMapCreateError> {
+        let initial_data = create_info.initial_data.take();
+        let element_sizes = match initial_data {
+            Some(data) => {
+                create_info.texture_usage |= TextureUsage::TRANSFER_DST;
+                create_info.queue_types |= QueueTypes::TRANSFER;
+                initial_layer_sizes(
+                    create_info.format,
+                    (create_info.size, create_info.size, 1),
+                    create_info.array_elements * 6,
+                    create_info.mip_levels,
+                    data,
+                )?
+                .into_iter()
+                // Faces are copied together, so each array element is staged as one layer
+                .map(|face_size| face_size * 6)
+                .collect()
+            }
+            None => Vec::default(),
+        };
+        let format = create_info.format;
+        if !ctx
+            .format_properties(format)
+            .contains(create_info.texture_usage.into())
+        {
+            return Err(CubeMapCreateError::UnsupportedFormat(
+                format,
+                create_info.texture_usage,
+            ));
+        }
+
+        let limits = &ctx.properties().limits;
+        let max_array_elements = limits.max_texture_array_elements as usize / 6;
+        if create_info.size > limits.max_cube_map_dimension
+            || create_info.array_elements > max_array_elements
+        {
+            return Err(CubeMapCreateError::ExceedsLimits {
+                size: create_info.size,
+                array_elements: create_info.array_elements,
+                max_size: limits.max_cube_map_dimension,
+                max_array_elements,
+            });
+        }
+
+        let array_elements = create_info.array_elements;
+        let debug_name = create_info.debug_name.clone();
+
         let size = create_info.size;
         let mip_count = create_info.mip_levels;
+        let texture_usage = create_info.texture_usage;
         let queue_types = create_info.queue_types;
         let sharing_mode = create_info.sharing_mode;
         let id = unsafe { ctx.0.create_cube_map(create_info)? };
-        Ok(Self {
+        let cube_map = Self {
             ctx,
             dim: size,
+            format,
             mip_count,
+            array_elements,
+            texture_usage,
             queue_types,
             sharing_mode,
             id,
-        })
+        };
+
+        if let Some(data) = initial_data.filter(|data| !data.is_empty()) {
+            cube_map.upload_initial_data(
+                data,
+                format,
+                &element_sizes,
+                array_elements,
+                debug_name,
+            )?;
+        }
+
+        Ok(cube_map)
+    }
+
+    /// Records and submits the copies for `initial_data`. The staging buffer is released once
+    /// the upload is complete.
+    fn upload_initial_data(
+        &self,
+        data: &[&[u8]],
+        format: Format,
+        element_sizes: &[usize],
+        array_elements: usize,
+        debug_name: Option<String>,
+    ) -> Result<(), BufferCreateError> {
+        let (staging, offsets) = new_initial_data_staging(
+            &self.ctx,
+            format,
+            data,
+            element_sizes,
+            debug_name.map(|name| format!("{name}_staging")),
+        )?;
+
+        let queue = self.ctx.transfer();
+        let mut commands = queue.command_buffer();
+        let mut offsets = offsets.into_iter();
+        for mip in 0..data.len() {
+            for array_element in 0..array_elements {
+                commands.copy_buffer_to_cube_map(
+                    self,
+                    &staging,
+                    BufferCubeMapCopy {
+                        buffer_offset: offsets.next().unwrap(),
+                        buffer_array_element: 0,
+                        cube_map_mip_level: mip,
+                        cube_map_array_element: array_element,
+                        face: None,
+                    },
+                );
+            }
+        }
+
+        if let (SharingMode::Exclusive, Some(owner)) =
+            (self.sharing_mode, upload_owner(self.queue_types))
+        {
+            for array_element in 0..array_elements {
+                for face in [
+                    CubeFace::North,
+                    CubeFace::East,
+                    CubeFace::South,
+                    CubeFace::West,
+                    CubeFace::Top,
+                    CubeFace::Bottom,
+                ] {
+                    commands.transfer_cube_map_ownership(
+                        self,
+                        array_element,
+                        0,
+                        self.mip_count,
+                        face,
+                        owner,
+                        None,
+                    );
+                }
+            }
+        }
+
+        queue.submit(Some("cube_map_upload"), commands);
+        Ok(())
     }
 
     #[inline(always)]
@@ -69,6 +235,11 @@ impl<B: Backend> CubeMap<B> {
         self.dim
     }
 
+    #[inline(always)]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
     /// Gets the size in bytes of a single array element of the cube map.
     #[inline(always)]
     pub fn size(&self) -> u64 {
@@ -79,6 +250,16 @@ impl<B: Backend> CubeMap<B> {
     pub fn mip_count(&self) -> usize {
         self.mip_count
     }
+
+    #[inline(always)]
+    pub fn array_elements(&self) -> usize {
+        self.array_elements
+    }
+
+    #[inline(always)]
+    pub fn texture_usage(&self) -> TextureUsage {
+        self.texture_usage
+    }
 }
 
 impl<B: Backend> Drop for CubeMap<B> {
@@ -90,7 +271,7 @@ impl<B: Backend> Drop for CubeMap<B> {
     }
 }
 
-impl Default for CubeMapCreateInfo {
+impl Default for CubeMapCreateInfo<'_> {
     #[inline(always)]
     fn default() -> Self {
         Self {
@@ -103,6 +284,7 @@ impl Default for CubeMapCreateInfo {
             queue_types: QueueTypes::all(),
             sharing_mode: SharingMode::Concurrent,
             debug_name: None,
+            initial_data: None,
         }
     }
 }
@@ -1,13 +1,14 @@
-use std::sync::Arc;
+use bitflags::bitflags;
+use std::{ops::Range, sync::Arc};
 use thiserror::Error;
 
 use crate::{
-    buffer::Buffer,
-    context::Context,
+    buffer::{Buffer, BufferView},
+    context::{Context, DeviceLimits, GraphicsFeatures},
     cube_map::CubeMap,
     texture::{Sampler, Texture},
     tlas::TopLevelAccelerationStructure,
-    types::{AccessType, ShaderStage},
+    types::{AccessType, BufferUsage, ShaderStage, TextureAspect, TextureUsage},
     Backend,
 };
 
@@ -16,12 +17,22 @@ pub struct DescriptorSetCreateInfo<B: Backend> {
     pub layout: DescriptorSetLayout<B>,
     /// The backend *should* use the provided debug name for easy identification.
     pub debug_name: Option<String>,
+    /// The number of array elements to allocate for the layout's
+    /// [`DescriptorBindingFlags::VARIABLE_COUNT`] binding. Must not exceed the count of the
+    /// binding. If `None`, the full count is allocated.
+    pub variable_count: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct DescriptorSetLayoutCreateInfo {
     /// The bindings of this set.
     pub bindings: Vec<DescriptorBinding>,
+    /// Descriptors for the layout are pushed directly into passes instead of being written to
+    /// descriptor sets. Sets cannot be created with push layouts. Backends without native push
+    /// descriptors *must* emulate them.
+    pub push: bool,
+    /// The backend *should* use the provided debug name for easy identification.
+    pub debug_name: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -34,6 +45,24 @@ pub struct DescriptorBinding {
     pub count: usize,
     /// The shader stages that have access to this binding.
     pub stage: ShaderStage,
+    /// Descriptor indexing behavior of the binding.
+    pub flags: DescriptorBindingFlags,
+}
+
+bitflags! {
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct DescriptorBindingFlags: u32 {
+        /// Elements of the binding can be updated while the set is in use by a queue, as long
+        /// as the elements being updated are not accessed by that work. Not supported by uniform
+        /// buffers, texel buffers or acceleration structures.
+        const UPDATE_AFTER_BIND = 0b001;
+        /// Elements of the binding that are never accessed by a shader don't need to be written.
+        const PARTIALLY_BOUND   = 0b010;
+        /// The number of elements in the binding is chosen when each set is created, with
+        /// `count` being the upper bound. Only the binding with the highest binding index may
+        /// have a variable count.
+        const VARIABLE_COUNT    = 0b100;
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -42,7 +71,10 @@ pub enum DescriptorType {
     Texture,
     /// A read-only sampled cube map.
     CubeMap,
-    /// A read-only top level acceleration structure.
+    /// A read-only sampled array of cube maps.
+    CubeMapArray,
+    /// A read-only top level acceleration structure. Bindings visible to stages outside of ray
+    /// tracing pipelines require [`ray_query`](crate::context::GraphicsFeatures::ray_query).
     TopLevelAccelerationStructure,
     /// A read-only uniform buffer object.
     UniformBuffer,
@@ -50,25 +82,102 @@ pub enum DescriptorType {
     StorageBuffer(AccessType),
     /// A read-only or read-write storage image object.
     StorageImage(AccessType),
+    /// A read-only view of a buffer as formatted texels.
+    UniformTexelBuffer,
+    /// A read-only or read-write view of a buffer as formatted texels.
+    StorageTexelBuffer(AccessType),
 }
 
 #[derive(Debug, Error)]
 pub enum DescriptorSetLayoutCreateError {
+    #[error("binding `{0}` has a variable count but is not the last binding of the layout")]
+    VariableCountNotLast(u32),
+    #[error("binding `{0}` of a push descriptor layout has descriptor indexing flags")]
+    PushBindingFlags(u32),
+    #[error("binding `{0}` has a type that cannot be updated after it is bound")]
+    UpdateAfterBindUnsupported(u32),
+    #[error(
+        "`{stage:?}` can access `{count}` descriptors counted by `{limit}`, but the device only \
+        supports `{max}`"
+    )]
+    TooManyDescriptors {
+        stage: ShaderStage,
+        /// Name of the [`DeviceLimits`] field that was exceeded.
+        limit: &'static str,
+        count: usize,
+        max: u32,
+    },
     #[error("an error has occured: {0}")]
     Other(String),
 }
 
 #[derive(Debug, Error)]
 pub enum DescriptorSetCreateError {
+    #[error("descriptor sets cannot be created with a push descriptor layout")]
+    PushLayout,
+    #[error("variable count of `{requested}` exceeds the bindings maximum of `{max}`")]
+    VariableCountTooLarge { requested: usize, max: usize },
+    #[error("a variable count was provided, but the layout has no variable count binding")]
+    NoVariableCountBinding,
     #[error("an error has occured: {0}")]
     Other(String),
 }
 
+/// Describes how a [`DescriptorSetUpdate`] doesn't match the layout of the set it updates.
+/// Only detected when the context has [`validation`](crate::context::GraphicsProperties::validation)
+/// enabled.
+#[derive(Debug, Error)]
+#[error("invalid update to binding `{binding}` of set `{}`: {reason}", .set.as_deref().unwrap_or("unnamed"))]
+pub struct DescriptorSetUpdateError {
+    /// Debug name of the updated set.
+    pub set: Option<String>,
+    /// The binding being updated.
+    pub binding: u32,
+    pub reason: DescriptorUpdateMismatch,
+}
+
+#[derive(Debug, Error)]
+pub enum DescriptorUpdateMismatch {
+    #[error("the layout has no such binding")]
+    NoBinding,
+    #[error("a `{found}` was bound, but the layout expects `{expected:?}`")]
+    WrongType {
+        expected: DescriptorType,
+        found: &'static str,
+    },
+    #[error("array element `{element}` is out of bounds for a binding with `{count}` elements")]
+    ArrayElementOutOfRange { element: usize, count: usize },
+    #[error("acceleration structures can't be bound to `{0:?}` without ray query support")]
+    UnsupportedStage(ShaderStage),
+    #[error("the bound resource is missing the `{0}` usage")]
+    MissingUsage(&'static str),
+    #[error("resource array element `{element}` is out of bounds for a resource with `{count}` elements")]
+    ResourceArrayElementOutOfRange { element: usize, count: usize },
+    #[error("mips `{base}..{end}` are out of bounds for a resource with `{count}` mips")]
+    MipsOutOfRange {
+        base: usize,
+        end: usize,
+        count: usize,
+    },
+    #[error(
+        "resource array elements `{start}..{end}` are out of bounds for a resource with \
+        `{count}` elements"
+    )]
+    ResourceArrayElementsOutOfRange {
+        start: usize,
+        end: usize,
+        count: usize,
+    },
+}
+
 pub struct DescriptorSetLayout<B: Backend>(Arc<DescriptorSetLayoutInner<B>>);
 
 pub struct DescriptorSet<B: Backend> {
     ctx: Context<B>,
     layout: DescriptorSetLayout<B>,
+    debug_name: Option<String>,
+    /// Number of elements allocated for the variable count binding, if it was overridden.
+    variable_count: Option<usize>,
     pub(crate) id: B::DescriptorSet,
 }
 
@@ -95,7 +204,8 @@ pub enum DescriptorValue<'a, B: Backend> {
         array_element: usize,
     },
     StorageImage {
-        /// The texture to bind.
+        /// The texture to bind. 1D, 2D, and 3D textures are bound as images of the same
+        /// dimensionality.
         texture: &'a Texture<B>,
         /// The array element of the texture to bind.
         array_element: usize,
@@ -103,7 +213,8 @@ pub enum DescriptorValue<'a, B: Backend> {
         mip: usize,
     },
     Texture {
-        /// The texture to bind.
+        /// The texture to bind. 1D, 2D, and 3D textures are bound as samplers of the same
+        /// dimensionality.
         texture: &'a Texture<B>,
         /// The array element of the texture to bind.
         array_element: usize,
@@ -113,6 +224,9 @@ pub enum DescriptorValue<'a, B: Backend> {
         base_mip: usize,
         /// The number of mip levels to bind.
         mip_count: usize,
+        /// The aspect of the texture to sample. If `None`, the color aspect is sampled for color
+        /// textures and the depth aspect for depth/stencil textures.
+        aspect: Option<TextureAspect>,
     },
     CubeMap {
         /// The cube map to bind.
@@ -126,11 +240,34 @@ pub enum DescriptorValue<'a, B: Backend> {
         /// The number of mip levels to bind.
         mip_count: usize,
     },
+    /// Binds a range of array elements of a cube map as a cube map array. Indexing the bound
+    /// array starts at the first element of the range.
+    CubeMapArray {
+        /// The cube map to bind.
+        cube_map: &'a CubeMap<B>,
+        /// The array elements of the cube map to bind.
+        array_elements: Range<usize>,
+        /// How the cube maps should be sampled.
+        sampler: Sampler,
+        /// The base mip to bind.
+        base_mip: usize,
+        /// The number of mip levels to bind.
+        mip_count: usize,
+    },
     TopLevelAccelerationStructure(&'a TopLevelAccelerationStructure<B>),
+    /// A view of a buffer with [`UNIFORM_TEXEL_BUFFER`](BufferUsage::UNIFORM_TEXEL_BUFFER) usage.
+    UniformTexelBuffer(BufferView<'a, B>),
+    /// A view of a buffer with [`STORAGE_TEXEL_BUFFER`](BufferUsage::STORAGE_TEXEL_BUFFER) usage.
+    StorageTexelBuffer(BufferView<'a, B>),
 }
 
 pub(crate) struct DescriptorSetLayoutInner<B: Backend> {
     ctx: Context<B>,
+    /// Kept so updates can be validated against the layout.
+    create_info: DescriptorSetLayoutCreateInfo,
+    push: bool,
+    /// The maximum count of the variable count binding, if the layout has one.
+    variable_count: Option<usize>,
     pub(crate) id: B::DescriptorSetLayout,
 }
 
@@ -145,9 +282,31 @@ impl<B: Backend> DescriptorSet<B> {
         ctx: Context<B>,
         create_info: DescriptorSetCreateInfo<B>,
     ) -> Result<Self, DescriptorSetCreateError> {
+        if create_info.layout.is_push() {
+            return Err(DescriptorSetCreateError::PushLayout);
+        }
+
+        if let Some(requested) = create_info.variable_count {
+            match create_info.layout.0.variable_count {
+                Some(max) if requested > max => {
+                    return Err(DescriptorSetCreateError::VariableCountTooLarge { requested, max })
+                }
+                Some(_) => {}
+                None => return Err(DescriptorSetCreateError::NoVariableCountBinding),
+            }
+        }
+
         let layout = create_info.layout.clone();
+        let debug_name = create_info.debug_name.clone();
+        let variable_count = create_info.variable_count;
         let id = unsafe { ctx.0.create_descriptor_set(create_info)? };
-        Ok(Self { ctx, layout, id })
+        Ok(Self {
+            ctx,
+            layout,
+            debug_name,
+            variable_count,
+            id,
+        })
     }
 
     #[inline(always)]
@@ -166,12 +325,29 @@ impl<B: Backend> DescriptorSet<B> {
     /// - `updates` - The updates to perform on the set.
     ///
     /// # Panics
-    /// TODO
+    /// - If a texture is bound with an aspect its format does not have.
+    /// - If a sampler uses a reduction mode or custom border color the device does not support.
+    /// - If [`validation`](crate::context::GraphicsProperties::validation) is enabled and an
+    ///   update doesn't match the layout of the set. See [`DescriptorSetUpdateError`].
     ///
     /// # Synchronization
     /// The backend *must* ensure that the descriptor set is not being accessed by any queue at the
-    /// time of the update.
+    /// time of the update, unless every update targets a binding with
+    /// [`DescriptorBindingFlags::UPDATE_AFTER_BIND`]. In that case, the backend *must not* wait
+    /// and the user *must* ensure the updated elements are not accessed by in-flight work.
     pub fn update(&mut self, updates: &[DescriptorSetUpdate<B>]) {
+        let properties = self.ctx.properties();
+        for update in updates {
+            update.value.check_aspect();
+            update.value.check_sampler(&properties.features);
+
+            if properties.validation {
+                if let Err(err) = self.validate_update(update) {
+                    panic!("{err}");
+                }
+            }
+        }
+
         unsafe {
             self.ctx
                 .0
@@ -180,6 +356,278 @@ impl<B: Backend> DescriptorSet<B> {
     }
 }
 
+impl<B: Backend> DescriptorSet<B> {
+    /// Checks that an update matches the layout of the set and that the bound resource can be
+    /// used the way the binding needs.
+    fn validate_update(
+        &self,
+        update: &DescriptorSetUpdate<B>,
+    ) -> Result<(), DescriptorSetUpdateError> {
+        let err = |reason| DescriptorSetUpdateError {
+            set: self.debug_name.clone(),
+            binding: update.binding,
+            reason,
+        };
+
+        let binding = self
+            .layout
+            .0
+            .create_info
+            .bindings
+            .iter()
+            .find(|binding| binding.binding == update.binding)
+            .ok_or_else(|| err(DescriptorUpdateMismatch::NoBinding))?;
+
+        // Type
+        let type_matches = matches!(
+            (&update.value, binding.ty),
+            (
+                DescriptorValue::UniformBuffer { .. },
+                DescriptorType::UniformBuffer
+            ) | (
+                DescriptorValue::StorageBuffer { .. },
+                DescriptorType::StorageBuffer(_)
+            ) | (
+                DescriptorValue::StorageImage { .. },
+                DescriptorType::StorageImage(_)
+            ) | (DescriptorValue::Texture { .. }, DescriptorType::Texture)
+                | (DescriptorValue::CubeMap { .. }, DescriptorType::CubeMap)
+                | (
+                    DescriptorValue::CubeMapArray { .. },
+                    DescriptorType::CubeMapArray
+                )
+                | (
+                    DescriptorValue::TopLevelAccelerationStructure(_),
+                    DescriptorType::TopLevelAccelerationStructure
+                )
+                | (
+                    DescriptorValue::UniformTexelBuffer(_),
+                    DescriptorType::UniformTexelBuffer
+                )
+                | (
+                    DescriptorValue::StorageTexelBuffer(_),
+                    DescriptorType::StorageTexelBuffer(_)
+                )
+        );
+        if !type_matches {
+            return Err(err(DescriptorUpdateMismatch::WrongType {
+                expected: binding.ty,
+                found: update.value.name(),
+            }));
+        }
+
+        // Array element
+        let count = if binding
+            .flags
+            .contains(DescriptorBindingFlags::VARIABLE_COUNT)
+        {
+            self.variable_count.unwrap_or(binding.count)
+        } else {
+            binding.count
+        };
+        if update.array_element >= count {
+            return Err(err(DescriptorUpdateMismatch::ArrayElementOutOfRange {
+                element: update.array_element,
+                count,
+            }));
+        }
+
+        // Stages
+        if let DescriptorValue::TopLevelAccelerationStructure(_) = &update.value {
+            let ray_tracing_only = matches!(
+                binding.stage,
+                ShaderStage::RayTracing
+                    | ShaderStage::RayGeneration
+                    | ShaderStage::RayMiss
+                    | ShaderStage::RayClosestHit
+                    | ShaderStage::RayAnyHit
+                    | ShaderStage::RayIntersection
+            );
+            if !ray_tracing_only && !self.ctx.properties().features.ray_query {
+                return Err(err(DescriptorUpdateMismatch::UnsupportedStage(
+                    binding.stage,
+                )));
+            }
+        }
+
+        // Resource
+        update.value.validate_resource().map_err(err)
+    }
+}
+
+impl<B: Backend> DescriptorValue<'_, B> {
+    /// Name of the kind of value for error messages.
+    fn name(&self) -> &'static str {
+        match self {
+            DescriptorValue::UniformBuffer { .. } => "UniformBuffer",
+            DescriptorValue::StorageBuffer { .. } => "StorageBuffer",
+            DescriptorValue::StorageImage { .. } => "StorageImage",
+            DescriptorValue::Texture { .. } => "Texture",
+            DescriptorValue::CubeMap { .. } => "CubeMap",
+            DescriptorValue::CubeMapArray { .. } => "CubeMapArray",
+            DescriptorValue::TopLevelAccelerationStructure(_) => "TopLevelAccelerationStructure",
+            DescriptorValue::UniformTexelBuffer(_) => "UniformTexelBuffer",
+            DescriptorValue::StorageTexelBuffer(_) => "StorageTexelBuffer",
+        }
+    }
+
+    /// Checks that the bound resource has the usage and subresources the value refers to.
+    fn validate_resource(&self) -> Result<(), DescriptorUpdateMismatch> {
+        let check_element = |element: usize, count: usize| {
+            if element >= count {
+                Err(DescriptorUpdateMismatch::ResourceArrayElementOutOfRange { element, count })
+            } else {
+                Ok(())
+            }
+        };
+        let check_mips = |base: usize, mip_count: usize, count: usize| {
+            if mip_count == 0 || base + mip_count > count {
+                Err(DescriptorUpdateMismatch::MipsOutOfRange {
+                    base,
+                    end: base + mip_count,
+                    count,
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        match self {
+            DescriptorValue::UniformBuffer {
+                buffer,
+                array_element,
+            } => {
+                if !buffer.buffer_usage().contains(BufferUsage::UNIFORM_BUFFER) {
+                    return Err(DescriptorUpdateMismatch::MissingUsage("UNIFORM_BUFFER"));
+                }
+                check_element(*array_element, buffer.array_elements())
+            }
+            DescriptorValue::StorageBuffer {
+                buffer,
+                array_element,
+            } => {
+                if !buffer.buffer_usage().contains(BufferUsage::STORAGE_BUFFER) {
+                    return Err(DescriptorUpdateMismatch::MissingUsage("STORAGE_BUFFER"));
+                }
+                check_element(*array_element, buffer.array_elements())
+            }
+            DescriptorValue::StorageImage {
+                texture,
+                array_element,
+                mip,
+            } => {
+                if !texture.texture_usage().contains(TextureUsage::STORAGE) {
+                    return Err(DescriptorUpdateMismatch::MissingUsage("STORAGE"));
+                }
+                check_element(*array_element, texture.array_elements())?;
+                check_mips(*mip, 1, texture.mip_count())
+            }
+            DescriptorValue::Texture {
+                texture,
+                array_element,
+                base_mip,
+                mip_count,
+                ..
+            } => {
+                if !texture.texture_usage().contains(TextureUsage::SAMPLED) {
+                    return Err(DescriptorUpdateMismatch::MissingUsage("SAMPLED"));
+                }
+                check_element(*array_element, texture.array_elements())?;
+                check_mips(*base_mip, *mip_count, texture.mip_count())
+            }
+            DescriptorValue::CubeMap {
+                cube_map,
+                base_mip,
+                mip_count,
+                ..
+            } => check_mips(*base_mip, *mip_count, cube_map.mip_count()),
+            DescriptorValue::CubeMapArray {
+                cube_map,
+                array_elements,
+                base_mip,
+                mip_count,
+                ..
+            } => {
+                if array_elements.is_empty() || array_elements.end > cube_map.array_elements() {
+                    return Err(DescriptorUpdateMismatch::ResourceArrayElementsOutOfRange {
+                        start: array_elements.start,
+                        end: array_elements.end,
+                        count: cube_map.array_elements(),
+                    });
+                }
+                check_mips(*base_mip, *mip_count, cube_map.mip_count())
+            }
+            DescriptorValue::TopLevelAccelerationStructure(_) => Ok(()),
+            // The rest of the view was checked when it was created
+            DescriptorValue::UniformTexelBuffer(view) => {
+                if !view
+                    .buffer()
+                    .buffer_usage()
+                    .contains(BufferUsage::UNIFORM_TEXEL_BUFFER)
+                {
+                    return Err(DescriptorUpdateMismatch::MissingUsage(
+                        "UNIFORM_TEXEL_BUFFER",
+                    ));
+                }
+                Ok(())
+            }
+            DescriptorValue::StorageTexelBuffer(view) => {
+                if !view
+                    .buffer()
+                    .buffer_usage()
+                    .contains(BufferUsage::STORAGE_TEXEL_BUFFER)
+                {
+                    return Err(DescriptorUpdateMismatch::MissingUsage(
+                        "STORAGE_TEXEL_BUFFER",
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks that textures are only bound with aspects their format has.
+    #[inline(always)]
+    fn check_aspect(&self) {
+        if let DescriptorValue::Texture {
+            texture,
+            aspect: Some(aspect),
+            ..
+        } = self
+        {
+            assert!(
+                texture.format().has_aspect(*aspect),
+                "texture of format `{:?}` has no `{:?}` aspect",
+                texture.format(),
+                aspect
+            );
+        }
+    }
+
+    /// Checks that samplers only use features supported by the device.
+    #[inline(always)]
+    fn check_sampler(&self, features: &GraphicsFeatures) {
+        let sampler = match self {
+            DescriptorValue::Texture { sampler, .. }
+            | DescriptorValue::CubeMap { sampler, .. }
+            | DescriptorValue::CubeMapArray { sampler, .. } => sampler,
+            _ => return,
+        };
+
+        assert!(
+            sampler.reduction_mode.is_none() || features.sampler_reduction,
+            "sampler reduction mode `{:?}` is not supported by this device",
+            sampler.reduction_mode.unwrap()
+        );
+        assert!(
+            !sampler.border_color.is_some_and(|color| color.is_custom())
+                || features.custom_border_color,
+            "custom border color `{:?}` is not supported by this device",
+            sampler.border_color.unwrap()
+        );
+    }
+}
+
 impl<B: Backend> Drop for DescriptorSet<B> {
     #[inline(always)]
     fn drop(&mut self) {
@@ -195,8 +643,67 @@ impl<B: Backend> DescriptorSetLayout<B> {
         ctx: Context<B>,
         create_info: DescriptorSetLayoutCreateInfo,
     ) -> Result<Self, DescriptorSetLayoutCreateError> {
-        let id = unsafe { ctx.0.create_descriptor_set_layout(create_info)? };
-        Ok(Self(Arc::new(DescriptorSetLayoutInner { ctx, id })))
+        let last_binding = create_info.bindings.iter().map(|b| b.binding).max();
+        let mut variable_count = None;
+        for binding in &create_info.bindings {
+            if create_info.push && !binding.flags.is_empty() {
+                return Err(DescriptorSetLayoutCreateError::PushBindingFlags(
+                    binding.binding,
+                ));
+            }
+
+            if binding
+                .flags
+                .contains(DescriptorBindingFlags::UPDATE_AFTER_BIND)
+                && matches!(
+                    binding.ty,
+                    DescriptorType::UniformBuffer
+                        | DescriptorType::TopLevelAccelerationStructure
+                        | DescriptorType::UniformTexelBuffer
+                        | DescriptorType::StorageTexelBuffer(_)
+                )
+            {
+                return Err(DescriptorSetLayoutCreateError::UpdateAfterBindUnsupported(
+                    binding.binding,
+                ));
+            }
+
+            if binding
+                .flags
+                .contains(DescriptorBindingFlags::VARIABLE_COUNT)
+            {
+                if Some(binding.binding) != last_binding {
+                    return Err(DescriptorSetLayoutCreateError::VariableCountNotLast(
+                        binding.binding,
+                    ));
+                }
+                variable_count = Some(binding.count);
+            }
+        }
+
+        check_descriptor_limits(&ctx.properties().limits, &create_info.bindings)?;
+
+        let push = create_info.push;
+        let id = unsafe { ctx.0.create_descriptor_set_layout(create_info.clone())? };
+        Ok(Self(Arc::new(DescriptorSetLayoutInner {
+            ctx,
+            create_info,
+            push,
+            variable_count,
+            id,
+        })))
+    }
+
+    /// The info the layout was created with.
+    #[inline(always)]
+    pub fn create_info(&self) -> &DescriptorSetLayoutCreateInfo {
+        &self.0.create_info
+    }
+
+    /// Returns `true` if descriptors for this layout are pushed instead of written to sets.
+    #[inline(always)]
+    pub fn is_push(&self) -> bool {
+        self.0.push
     }
 
     #[inline(always)]
@@ -212,6 +719,90 @@ impl<B: Backend> Clone for DescriptorSetLayout<B> {
     }
 }
 
+/// Checks the number of descriptors of each kind visible to every shader stage against the per
+/// stage limits of the device. Bindings that can be updated after they are bound have separate,
+/// much higher, limits and aren't counted. Texel buffers count as sampled and storage images.
+fn check_descriptor_limits(
+    limits: &DeviceLimits,
+    bindings: &[DescriptorBinding],
+) -> Result<(), DescriptorSetLayoutCreateError> {
+    type Counted = fn(DescriptorType) -> bool;
+    let per_stage: [(&'static str, u32, Counted); 5] = [
+        (
+            "max_per_stage_descriptor_samplers",
+            limits.max_per_stage_descriptor_samplers,
+            |ty| {
+                matches!(
+                    ty,
+                    DescriptorType::Texture
+                        | DescriptorType::CubeMap
+                        | DescriptorType::CubeMapArray
+                )
+            },
+        ),
+        (
+            "max_per_stage_descriptor_sampled_images",
+            limits.max_per_stage_descriptor_sampled_images,
+            |ty| {
+                matches!(
+                    ty,
+                    DescriptorType::Texture
+                        | DescriptorType::CubeMap
+                        | DescriptorType::CubeMapArray
+                        | DescriptorType::UniformTexelBuffer
+                )
+            },
+        ),
+        (
+            "max_per_stage_descriptor_uniform_buffers",
+            limits.max_per_stage_descriptor_uniform_buffers,
+            |ty| matches!(ty, DescriptorType::UniformBuffer),
+        ),
+        (
+            "max_per_stage_descriptor_storage_buffers",
+            limits.max_per_stage_descriptor_storage_buffers,
+            |ty| matches!(ty, DescriptorType::StorageBuffer(_)),
+        ),
+        (
+            "max_per_stage_descriptor_storage_images",
+            limits.max_per_stage_descriptor_storage_images,
+            |ty| {
+                matches!(
+                    ty,
+                    DescriptorType::StorageImage(_) | DescriptorType::StorageTexelBuffer(_)
+                )
+            },
+        ),
+    ];
+
+    for stage in ShaderStage::INDIVIDUAL {
+        for (limit, max, counted) in per_stage {
+            let count = bindings
+                .iter()
+                .filter(|binding| {
+                    !binding
+                        .flags
+                        .contains(DescriptorBindingFlags::UPDATE_AFTER_BIND)
+                        && binding.stage.includes(stage)
+                        && counted(binding.ty)
+                })
+                .map(|binding| binding.count)
+                .sum::<usize>();
+
+            if count > max as usize {
+                return Err(DescriptorSetLayoutCreateError::TooManyDescriptors {
+                    stage,
+                    limit,
+                    count,
+                    max,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl<B: Backend> Drop for DescriptorSetLayoutInner<B> {
     #[inline(always)]
     fn drop(&mut self) {
@@ -220,3 +811,54 @@ impl<B: Backend> Drop for DescriptorSetLayoutInner<B> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(ty: DescriptorType, count: usize, stage: ShaderStage) -> DescriptorBinding {
+        DescriptorBinding {
+            binding: 0,
+            ty,
+            count,
+            stage,
+            flags: DescriptorBindingFlags::empty(),
+        }
+    }
+
+    #[test]
+    fn descriptors_are_counted_per_stage() {
+        let limits = DeviceLimits {
+            max_per_stage_descriptor_storage_buffers: 4,
+            ..Default::default()
+        };
+        let storage = DescriptorType::StorageBuffer(AccessType::Read);
+
+        // Separate stages each stay within the limit
+        let bindings = [
+            binding(storage, 4, ShaderStage::Vertex),
+            binding(storage, 4, ShaderStage::Fragment),
+        ];
+        assert!(check_descriptor_limits(&limits, &bindings).is_ok());
+
+        // Graphics bindings are also visible to the fragment stage
+        let bindings = [
+            binding(storage, 2, ShaderStage::AllGraphics),
+            binding(storage, 3, ShaderStage::Fragment),
+        ];
+        assert!(matches!(
+            check_descriptor_limits(&limits, &bindings),
+            Err(DescriptorSetLayoutCreateError::TooManyDescriptors {
+                stage: ShaderStage::Fragment,
+                count: 5,
+                max: 4,
+                ..
+            })
+        ));
+
+        // Update after bind bindings have their own limits
+        let mut bindless = binding(storage, 1024, ShaderStage::AllStages);
+        bindless.flags = DescriptorBindingFlags::UPDATE_AFTER_BIND;
+        assert!(check_descriptor_limits(&limits, &[bindless]).is_ok());
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::Backend;
+
+/// The number of frames a [`Context`](crate::context::Context) lets be in flight by default.
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+/// Limits how many frames the CPU can get ahead of the GPU. Owned by the context, which exposes
+/// it through [`Context::begin_frame`](crate::context::Context::begin_frame) and
+/// [`Context::end_frame`](crate::context::Context::end_frame).
+///
+/// Backend jobs are stored instead of [`Job`](crate::queue::Job)s, since those hold a reference
+/// to the context.
+pub(crate) struct FramePacer<B: Backend> {
+    frames_in_flight: usize,
+    /// Jobs of every in-flight frame, oldest first.
+    in_flight: VecDeque<Vec<B::Job>>,
+    /// Number of frames begun so far.
+    frame_count: u64,
+    /// Time spent waiting in the most recent call to `begin_frame`.
+    last_wait_time: Duration,
+}
+
+/// Handle for a frame that was begun with
+/// [`Context::begin_frame`](crate::context::Context::begin_frame). Must be given back to
+/// [`Context::end_frame`](crate::context::Context::end_frame) once the frame is submitted.
+#[must_use]
+#[derive(Debug)]
+pub struct FrameToken {
+    index: u64,
+    slot: usize,
+    wait_time: Duration,
+}
+
+impl<B: Backend> FramePacer<B> {
+    pub fn new(frames_in_flight: usize) -> Self {
+        assert_ne!(frames_in_flight, 0, "at least one frame must be in flight");
+
+        Self {
+            frames_in_flight,
+            in_flight: VecDeque::with_capacity(frames_in_flight),
+            frame_count: 0,
+            last_wait_time: Duration::ZERO,
+        }
+    }
+
+    #[inline(always)]
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
+    #[inline(always)]
+    pub fn pending_frames(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    #[inline(always)]
+    pub fn last_wait_time(&self) -> Duration {
+        self.last_wait_time
+    }
+
+    /// Waits for every in-flight frame so that slots handed out with the old count can't overlap
+    /// with the new ones.
+    pub fn set_frames_in_flight(&mut self, backend: &B, frames_in_flight: usize) {
+        assert_ne!(frames_in_flight, 0, "at least one frame must be in flight");
+        self.wait_idle(backend);
+        self.frames_in_flight = frames_in_flight;
+    }
+
+    pub fn begin_frame(&mut self, backend: &B) -> FrameToken {
+        let start = Instant::now();
+
+        while self.in_flight.len() >= self.frames_in_flight {
+            // A lost device returns immediately instead of blocking, so the frame is freed either
+            // way
+            for job in self.in_flight.pop_front().unwrap() {
+                unsafe { backend.wait_on(&job, None) };
+            }
+        }
+
+        self.last_wait_time = start.elapsed();
+
+        let index = self.frame_count;
+        self.frame_count += 1;
+
+        FrameToken {
+            index,
+            slot: (index % self.frames_in_flight as u64) as usize,
+            wait_time: self.last_wait_time,
+        }
+    }
+
+    pub fn end_frame(&mut self, token: FrameToken, jobs: Vec<B::Job>) {
+        debug_assert_eq!(
+            token.index + 1,
+            self.frame_count,
+            "frames must end in the order they began"
+        );
+        self.in_flight.push_back(jobs);
+    }
+
+    pub fn wait_idle(&mut self, backend: &B) {
+        for jobs in self.in_flight.drain(..) {
+            for job in jobs {
+                unsafe { backend.wait_on(&job, None) };
+            }
+        }
+    }
+}
+
+impl FrameToken {
+    /// The number of frames begun before this one.
+    #[inline(always)]
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Index in `0..frames_in_flight` of this frame. No other in-flight frame shares the slot.
+    #[inline(always)]
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    /// Time the CPU spent blocked on the GPU before this frame could begin.
+    #[inline(always)]
+    pub fn wait_time(&self) -> Duration {
+        self.wait_time
+    }
+}
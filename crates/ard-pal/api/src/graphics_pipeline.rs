@@ -1,7 +1,11 @@
 use std::sync::Arc;
 
 use crate::{
-    context::Context, descriptor_set::DescriptorSetLayout, shader::Shader, types::*, Backend,
+    context::{Context, DeviceLimits},
+    descriptor_set::DescriptorSetLayout,
+    shader::Shader,
+    types::*,
+    Backend,
 };
 use thiserror::Error;
 
@@ -90,6 +94,37 @@ pub struct DepthStencilState {
     ///
     /// Ignored if `depth_clamp = false`.
     pub max_depth: f32,
+    /// Should stencil testing be enabled.
+    pub stencil_test: bool,
+    /// Stencil rules for front facing primitives.
+    ///
+    /// Ignored if `stencil_test = false`.
+    pub stencil_front: StencilOpState,
+    /// Stencil rules for back facing primitives.
+    ///
+    /// Ignored if `stencil_test = false`.
+    pub stencil_back: StencilOpState,
+}
+
+/// Describes how stencil values are tested and updated for one facing of primitives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StencilOpState {
+    /// What to do when the stencil test fails.
+    pub fail_op: StencilOp,
+    /// What to do when both the stencil and depth tests pass.
+    pub pass_op: StencilOp,
+    /// What to do when the stencil test passes but the depth test fails.
+    pub depth_fail_op: StencilOp,
+    /// What comparison operation should be used between the reference and stored values.
+    pub compare_op: CompareOp,
+    /// Bits of the reference and stored values that take part in the comparison.
+    pub compare_mask: u32,
+    /// Bits of the stored value that can be written to.
+    pub write_mask: u32,
+    /// Value to compare against.
+    ///
+    /// Ignored if the pipeline has `DynamicStates::STENCIL_REFERENCE`.
+    pub reference: u32,
 }
 
 /// Describes blending operations for color attachments of a graphics pipeline.
@@ -124,8 +159,25 @@ pub struct GraphicsPipelineCreateInfo<B: Backend> {
     pub depth_stencil: Option<DepthStencilState>,
     pub color_blend: ColorBlendState,
     pub push_constants_size: Option<u32>,
+    /// How the pipeline is compiled the first time it's used with a render pass.
+    pub compile_mode: PipelineCompileMode<B>,
     /// The backend *should* use the provided debug name for easy identification.
     pub debug_name: Option<String>,
+    /// State set by commands within render passes the pipeline is bound in.
+    pub dynamic_states: DynamicStates,
+}
+
+/// How a graphics pipeline is compiled the first time it's used with a render pass.
+#[derive(Clone)]
+pub enum PipelineCompileMode<B: Backend> {
+    /// Compiled when the command buffer using it is submitted, stalling the submit.
+    Blocking,
+    /// Compiled in the background. Until compilation finishes, draws use `fallback` or are
+    /// skipped if there isn't one. The fallback must have the same layouts and push constant size
+    /// as the pipeline. Completed pipelines are used starting from the next submit.
+    Async {
+        fallback: Option<GraphicsPipeline<B>>,
+    },
 }
 
 pub struct GraphicsPipeline<B: Backend>(pub(crate) Arc<GraphicsPipelineInner<B>>);
@@ -133,15 +185,35 @@ pub struct GraphicsPipeline<B: Backend>(pub(crate) Arc<GraphicsPipelineInner<B>>
 pub(crate) struct GraphicsPipelineInner<B: Backend> {
     ctx: Context<B>,
     pub(crate) layouts: Vec<DescriptorSetLayout<B>>,
+    pub(crate) dynamic_states: DynamicStates,
     pub(crate) id: B::GraphicsPipeline,
 }
 
+/// A pipeline goes over a limit of the device shared by every kind of pipeline.
+#[derive(Debug, Error)]
+pub enum PipelineLimitError {
+    #[error(
+        "`{size}` bytes of push constants were requested, but the device only supports `{max}`"
+    )]
+    PushConstantsTooLarge { size: u32, max: u32 },
+    #[error(
+        "`{count}` descriptor set layouts were provided, but the device only supports `{max}`"
+    )]
+    TooManyDescriptorSets { count: usize, max: u32 },
+}
+
 #[derive(Debug, Error)]
 pub enum GraphicsPipelineCreateError {
+    #[error("{0}")]
+    Limits(#[from] PipelineLimitError),
     #[error("no vertex attributes or bindings were provided")]
     NoAttributesOrBindings,
     #[error("no depth/stencil or color attachments provided")]
     NoAttachments,
+    #[error("dual source blend factors are not supported by the device")]
+    DualSourceBlendUnsupported,
+    #[error("dual source blend factors can only be used with a single color attachment")]
+    DualSourceBlendAttachments,
     #[error("an error occured: {0}")]
     Other(String),
 }
@@ -156,9 +228,45 @@ impl<B: Backend> GraphicsPipeline<B> {
         ctx: Context<B>,
         create_info: GraphicsPipelineCreateInfo<B>,
     ) -> Result<Self, GraphicsPipelineCreateError> {
+        check_pipeline_limits(
+            &ctx.properties().limits,
+            create_info.push_constants_size,
+            &create_info.layouts,
+        )?;
+
+        let dual_source = create_info
+            .color_blend
+            .attachments
+            .iter()
+            .any(|attachment| {
+                attachment.blend
+                    && [
+                        attachment.src_color_blend_factor,
+                        attachment.dst_color_blend_factor,
+                        attachment.src_alpha_blend_factor,
+                        attachment.dst_alpha_blend_factor,
+                    ]
+                    .into_iter()
+                    .any(BlendFactor::is_dual_source)
+            });
+        if dual_source {
+            if !ctx.properties().features.dual_source_blend {
+                return Err(GraphicsPipelineCreateError::DualSourceBlendUnsupported);
+            }
+            if create_info.color_blend.attachments.len() > 1 {
+                return Err(GraphicsPipelineCreateError::DualSourceBlendAttachments);
+            }
+        }
+
         let layouts = create_info.layouts.clone();
+        let dynamic_states = create_info.dynamic_states;
         let id = unsafe { ctx.0.create_graphics_pipeline(create_info)? };
-        Ok(Self(Arc::new(GraphicsPipelineInner { ctx, id, layouts })))
+        Ok(Self(Arc::new(GraphicsPipelineInner {
+            ctx,
+            id,
+            layouts,
+            dynamic_states,
+        })))
     }
 
     #[inline(always)]
@@ -170,6 +278,11 @@ impl<B: Backend> GraphicsPipeline<B> {
     pub fn layouts(&self) -> &[DescriptorSetLayout<B>] {
         &self.0.layouts
     }
+
+    #[inline(always)]
+    pub fn dynamic_states(&self) -> DynamicStates {
+        self.0.dynamic_states
+    }
 }
 
 impl<B: Backend> Clone for GraphicsPipeline<B> {
@@ -179,6 +292,31 @@ impl<B: Backend> Clone for GraphicsPipeline<B> {
     }
 }
 
+/// Checks the push constants and descriptor sets of a pipeline against the device limits.
+pub(crate) fn check_pipeline_limits<B: Backend>(
+    limits: &DeviceLimits,
+    push_constants_size: Option<u32>,
+    layouts: &[DescriptorSetLayout<B>],
+) -> Result<(), PipelineLimitError> {
+    if let Some(size) = push_constants_size {
+        if size > limits.max_push_constants_size {
+            return Err(PipelineLimitError::PushConstantsTooLarge {
+                size,
+                max: limits.max_push_constants_size,
+            });
+        }
+    }
+
+    if layouts.len() > limits.max_bound_descriptor_sets as usize {
+        return Err(PipelineLimitError::TooManyDescriptorSets {
+            count: layouts.len(),
+            max: limits.max_bound_descriptor_sets,
+        });
+    }
+
+    Ok(())
+}
+
 impl<B: Backend> Drop for GraphicsPipelineInner<B> {
     fn drop(&mut self) {
         unsafe {
@@ -187,6 +325,13 @@ impl<B: Backend> Drop for GraphicsPipelineInner<B> {
     }
 }
 
+impl<B: Backend> Default for PipelineCompileMode<B> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::Blocking
+    }
+}
+
 impl Default for VertexInputState {
     #[inline(always)]
     fn default() -> Self {
@@ -219,6 +364,24 @@ impl Default for DepthStencilState {
             depth_compare: CompareOp::Always,
             min_depth: 0.0,
             max_depth: 1.0,
+            stencil_test: false,
+            stencil_front: StencilOpState::default(),
+            stencil_back: StencilOpState::default(),
+        }
+    }
+}
+
+impl Default for StencilOpState {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            fail_op: StencilOp::Keep,
+            pass_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            compare_op: CompareOp::Always,
+            compare_mask: u32::MAX,
+            write_mask: u32::MAX,
+            reference: 0,
         }
     }
 }
@@ -0,0 +1,88 @@
+use std::{sync::mpsc, time::Duration};
+
+use crate::{queue::Job, types::JobStatus, Backend};
+
+/// How long the waiter blocks on a job before checking for newly registered jobs.
+const WAIT_SLICE: Duration = Duration::from_millis(1);
+
+/// CPU work handed to the spawner of a [`JobWaiter`].
+pub type JobWaiterTask = Box<dyn FnOnce() + Send>;
+
+type Callback = Box<dyn FnOnce(JobStatus) + Send>;
+
+/// Runs CPU work once [`Jobs`](Job) complete, without having to poll them.
+///
+/// Jobs are waited on by a dedicated thread. Once a job completes, or can never complete because
+/// the device was lost, its callback is given to the spawner, which is usually a thread pool.
+///
+/// # Note
+/// Dropping the waiter doesn't cancel anything. Callbacks for jobs that were already registered
+/// still run when their jobs complete.
+pub struct JobWaiter<B: Backend> {
+    sender: mpsc::Sender<(Job<B>, Callback)>,
+}
+
+impl<B> JobWaiter<B>
+where
+    B: Backend + Send + Sync,
+    B::Job: Send,
+{
+    /// Creates a waiter that runs callbacks using `spawn`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let waiter = JobWaiter::new(|task| rayon::spawn(task));
+    /// ```
+    pub fn new(spawn: impl Fn(JobWaiterTask) + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::Builder::new()
+            .name(String::from("pal_job_waiter"))
+            .spawn(move || Self::run(receiver, spawn))
+            .unwrap();
+
+        Self { sender }
+    }
+
+    /// Creates a waiter that runs callbacks on its own thread. Callbacks should be short, since
+    /// they delay every other callback.
+    pub fn inline() -> Self {
+        Self::new(|task| task())
+    }
+
+    /// Runs `f` once `job` is no longer running. `f` is given the final status of the job.
+    pub fn on_complete(&self, job: Job<B>, f: impl FnOnce(JobStatus) + Send + 'static) {
+        // The thread only exits once every sender is gone, so this can't fail
+        let _ = self.sender.send((job, Box::new(f)));
+    }
+
+    fn run(receiver: mpsc::Receiver<(Job<B>, Callback)>, spawn: impl Fn(JobWaiterTask)) {
+        let mut pending = Vec::<(Job<B>, Callback)>::default();
+
+        loop {
+            // With nothing to wait on, sleep until something is registered
+            if pending.is_empty() {
+                match receiver.recv() {
+                    Ok(entry) => pending.push(entry),
+                    Err(_) => return,
+                }
+            }
+            pending.extend(receiver.try_iter());
+
+            // Block on the oldest job for a bit, then hand off everything that's done
+            pending[0].0.wait_on(Some(WAIT_SLICE));
+
+            let mut i = 0;
+            while i < pending.len() {
+                let status = pending[i].0.poll_status();
+                if status == JobStatus::Running {
+                    i += 1;
+                    continue;
+                }
+
+                let (_, callback) = pending.remove(i);
+                spawn(Box::new(move || callback(status)));
+            }
+        }
+    }
+}
@@ -7,17 +7,22 @@
 pub mod blas;
 pub mod buffer;
 pub mod command_buffer;
+pub mod command_validation;
 pub mod compute_pass;
 pub mod compute_pipeline;
 pub mod context;
 pub mod cube_map;
 pub mod descriptor_set;
+pub mod frame_pacer;
 pub mod graphics_pipeline;
+pub mod job_waiter;
 pub mod queue;
+pub mod recorded_commands;
 pub mod render_pass;
 pub mod rt_pass;
 pub mod rt_pipeline;
 pub mod shader;
+pub mod staging_belt;
 pub mod surface;
 pub mod texture;
 pub mod tlas;
@@ -31,26 +36,33 @@ use blas::{
 use buffer::{BufferCreateError, BufferCreateInfo, BufferViewError};
 use command_buffer::Command;
 use compute_pipeline::{ComputePipelineCreateError, ComputePipelineCreateInfo};
-use context::GraphicsProperties;
+use context::{
+    AdapterInfo, GarbageReport, GraphicsProperties, MemoryAllocationInfo, MemoryBudget,
+    MemoryPressureCallback, MemoryStats, PipelineCacheSaveError,
+};
 use cube_map::{CubeMapCreateError, CubeMapCreateInfo};
 use descriptor_set::{
     DescriptorSetCreateError, DescriptorSetCreateInfo, DescriptorSetLayoutCreateError,
     DescriptorSetLayoutCreateInfo, DescriptorSetUpdate,
 };
 use graphics_pipeline::{GraphicsPipelineCreateError, GraphicsPipelineCreateInfo};
-use queue::SurfacePresentFailure;
+use queue::{SubmitError, SurfacePresentFailure};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use render_pass::RenderPassDescriptor;
 use rt_pipeline::{
     RayTracingPipelineCreateError, RayTracingPipelineCreateInfo, ShaderBindingTableData,
 };
 use shader::{ShaderCreateError, ShaderCreateInfo};
 use surface::{
     SurfaceCapabilities, SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo,
-    SurfaceImageAcquireError, SurfacePresentSuccess, SurfaceUpdateError,
+    SurfaceImageAcquireError, SurfacePresentSuccess, SurfaceProperties, SurfaceUpdateError,
 };
 use texture::{TextureCreateError, TextureCreateInfo};
 use tlas::{TopLevelAccelerationStructureCreateError, TopLevelAccelerationStructureCreateInfo};
-use types::{BuildAccelerationStructureFlags, JobStatus, QueueType};
+use types::{
+    AnisotropyLevel, BufferUsage, BuildAccelerationStructureFlags, JobStatus, PipelineStatistics,
+    QueueType,
+};
 
 /// TODO:
 /// - Describe [normative terminology](https://www.ietf.org/rfc/rfc2119.txt).
@@ -70,24 +82,45 @@ pub trait Backend: Sized + 'static {
     type DescriptorSetLayout;
     type DescriptorSet;
     type Job;
+    type CommandCache;
     type BottomLevelAccelerationStructure;
     type TopLevelAccelerationStructure;
+    type DrawIndirect: Copy + Clone;
     type DrawIndexedIndirect: Copy + Clone;
     type DispatchIndirect: Copy + Clone;
 
+    unsafe fn enumerate_adapters() -> Vec<AdapterInfo>;
     unsafe fn properties(&self) -> &GraphicsProperties;
+    unsafe fn save_pipeline_cache(&self) -> Result<(), PipelineCacheSaveError>;
+    unsafe fn precompile_graphics_pipelines(
+        &self,
+        pipelines: &[&Self::GraphicsPipeline],
+        render_pass: &RenderPassDescriptor<Self>,
+    );
+    unsafe fn pending_pipeline_compiles(&self) -> usize;
+    unsafe fn memory_stats(&self) -> MemoryStats;
+    unsafe fn memory_allocations(&self) -> Vec<MemoryAllocationInfo>;
+    unsafe fn memory_budget(&self) -> MemoryBudget;
+    unsafe fn on_memory_pressure(&self, threshold: f32, callback: MemoryPressureCallback);
+    unsafe fn collect_garbage(&self);
+    unsafe fn pending_garbage(&self) -> GarbageReport;
+    unsafe fn default_anisotropy(&self) -> Option<AnisotropyLevel>;
+    unsafe fn set_default_anisotropy(&self, level: Option<AnisotropyLevel>);
+    unsafe fn trigger_capture(&self);
+    unsafe fn begin_capture(&self);
+    unsafe fn end_capture(&self);
 
     // Surface
     unsafe fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
         &self,
         create_info: SurfaceCreateInfo<W>,
-    ) -> Result<Self::Surface, SurfaceCreateError>;
+    ) -> Result<(Self::Surface, SurfaceProperties), SurfaceCreateError>;
     unsafe fn destroy_surface(&self, id: &mut Self::Surface);
     unsafe fn update_surface(
         &self,
         id: &mut Self::Surface,
         config: SurfaceConfiguration,
-    ) -> Result<(u32, u32), SurfaceUpdateError>;
+    ) -> Result<SurfaceProperties, SurfaceUpdateError>;
     unsafe fn get_surface_capabilities(&self, id: &Self::Surface) -> SurfaceCapabilities;
     unsafe fn acquire_image(
         &self,
@@ -102,6 +135,7 @@ pub trait Backend: Sized + 'static {
         debug_name: Option<&str>,
         commands: Vec<Command<'_, Self>>,
         is_async: bool,
+        waits: &[&Self::Job],
     ) -> Self::Job;
     unsafe fn submit_commands_async_compute(
         &self,
@@ -110,6 +144,19 @@ pub trait Backend: Sized + 'static {
         commands: Vec<Command<'_, Self>>,
         compute_commands: Vec<Command<'_, Self>>,
     ) -> (Self::Job, Self::Job);
+    unsafe fn record_commands(
+        &self,
+        queue: QueueType,
+        commands: &mut Vec<Command<'_, Self>>,
+    ) -> Self::CommandCache;
+    unsafe fn submit_recorded_commands(
+        &self,
+        queue: QueueType,
+        debug_name: Option<&str>,
+        commands: &[Command<'_, Self>],
+        cache: &Self::CommandCache,
+        waits: &[&Self::Job],
+    ) -> Self::Job;
     unsafe fn present_image(
         &self,
         surface: &Self::Surface,
@@ -119,12 +166,20 @@ pub trait Backend: Sized + 'static {
     // Jobs
     unsafe fn wait_on(&self, job: &Self::Job, timeout: Option<Duration>) -> JobStatus;
     unsafe fn poll_status(&self, job: &Self::Job) -> JobStatus;
+    unsafe fn device_lost(&self) -> Option<SubmitError>;
+    unsafe fn job_timestamps(&self, job: &Self::Job) -> Vec<(String, Duration)>;
+    unsafe fn job_statistics(&self, job: &Self::Job) -> Vec<(String, PipelineStatistics)>;
 
     // Creating resources
     unsafe fn create_buffer(
         &self,
         create_info: BufferCreateInfo,
     ) -> Result<Self::Buffer, BufferCreateError>;
+    unsafe fn create_transient_buffer(
+        &self,
+        size: u64,
+        buffer_usage: BufferUsage,
+    ) -> Result<Self::Buffer, BufferCreateError>;
     unsafe fn create_texture(
         &self,
         create_info: TextureCreateInfo,
@@ -200,6 +255,7 @@ pub trait Backend: Sized + 'static {
     unsafe fn texture_size(&self, id: &Self::Texture) -> u64;
     unsafe fn cube_map_size(&self, id: &Self::CubeMap) -> u64;
     unsafe fn tlas_scratch_size(&self, id: &Self::TopLevelAccelerationStructure) -> u64;
+    unsafe fn tlas_update_scratch_size(&self, id: &Self::TopLevelAccelerationStructure) -> u64;
     unsafe fn tlas_build_flags(
         &self,
         id: &Self::TopLevelAccelerationStructure,
@@ -207,6 +263,10 @@ pub trait Backend: Sized + 'static {
     unsafe fn blas_device_ref(&self, id: &Self::BottomLevelAccelerationStructure) -> u64;
     unsafe fn blas_scratch_size(&self, id: &Self::BottomLevelAccelerationStructure) -> u64;
     unsafe fn blas_compacted_size(&self, id: &Self::BottomLevelAccelerationStructure) -> u64;
+    unsafe fn blas_poll_compacted_sizes(
+        &self,
+        ids: &[&Self::BottomLevelAccelerationStructure],
+    ) -> Vec<Option<u64>>;
     unsafe fn blas_build_flags(
         &self,
         id: &Self::BottomLevelAccelerationStructure,
@@ -1,10 +1,14 @@
 use std::time::Duration;
 
+use thiserror::Error;
+
 use crate::{
-    command_buffer::CommandBuffer,
+    command_buffer::{Command, CommandBuffer},
+    command_validation::validate_commands,
     context::Context,
+    recorded_commands::{RecordedCommands, RecordingResources},
     surface::{Surface, SurfaceImage, SurfacePresentError, SurfacePresentSuccess},
-    types::{JobStatus, QueueType},
+    types::{JobStatus, PipelineStatistics, QueueType},
     Backend,
 };
 
@@ -32,6 +36,15 @@ pub struct Job<B: Backend> {
     id: B::Job,
 }
 
+/// Describes why submitted work can no longer execute.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubmitError {
+    /// The device was lost. `debug_name` is the debug name of the submission where the loss was
+    /// first detected, which is usually the faulting one.
+    #[error("the device was lost during `{}`", .debug_name.as_deref().unwrap_or("unnamed submission"))]
+    DeviceLost { debug_name: Option<String> },
+}
+
 pub enum SurfacePresentFailure {
     BadImage,
     NoRender,
@@ -55,6 +68,8 @@ impl<B: Backend> Queue<B> {
         CommandBuffer {
             queue_ty: self.ty,
             commands: Vec::default(),
+            statistics_active: false,
+            conditional_rendering_active: false,
         }
     }
 
@@ -63,12 +78,54 @@ impl<B: Backend> Queue<B> {
     /// # Arguments
     /// - `debug_name` - The backend *should* use the provided debug name for easy identification.
     /// - `commands` - The command buffers to submit.
+    ///
+    /// # Panics
+    /// - If the command buffer contains a pass that was never ended or a command recorded outside
+    ///   of the pass it belongs in. See [`validate_commands`].
+    /// - If more push constants are pushed than the device supports.
     #[inline(always)]
     pub fn submit(&self, debug_name: Option<&str>, commands: CommandBuffer<B>) -> Job<B> {
+        check_commands(&self.ctx, debug_name, &commands.commands);
         let id = unsafe {
             self.ctx
                 .0
-                .submit_commands(self.ty, debug_name, commands.commands, false)
+                .submit_commands(self.ty, debug_name, commands.commands, false, &[])
+        };
+
+        Job {
+            id,
+            ctx: self.ctx.clone(),
+        }
+    }
+
+    /// Submits commands that must not begin until the given jobs are complete.
+    ///
+    /// Queues already wait on each other when they share resources. This is for dependencies
+    /// the queue can't see, like a frame using data uploaded by a job submitted from another
+    /// thread.
+    ///
+    /// # Arguments
+    /// - `debug_name` - The backend *should* use the provided debug name for easy identification.
+    /// - `commands` - The command buffers to submit.
+    /// - `waits` - Jobs, from any queue, to wait on.
+    ///
+    /// # Panics
+    /// - If the command buffer contains a pass that was never ended or a command recorded outside
+    ///   of the pass it belongs in. See [`validate_commands`].
+    /// - If more push constants are pushed than the device supports.
+    #[inline(always)]
+    pub fn submit_with_waits(
+        &self,
+        debug_name: Option<&str>,
+        commands: CommandBuffer<B>,
+        waits: &[&Job<B>],
+    ) -> Job<B> {
+        check_commands(&self.ctx, debug_name, &commands.commands);
+        let waits: Vec<_> = waits.iter().map(|job| &job.id).collect();
+        let id = unsafe {
+            self.ctx
+                .0
+                .submit_commands(self.ty, debug_name, commands.commands, false, &waits)
         };
 
         Job {
@@ -88,6 +145,9 @@ impl<B: Backend> Queue<B> {
     ///
     /// # Panics
     /// - If the primary command buffer is also being submitted to the compute queue.
+    /// - If either command buffer contains a pass that was never ended or a command recorded
+    ///   outside of the pass it belongs in. See [`validate_commands`].
+    /// - If more push constants are pushed than the device supports.
     #[inline(always)]
     pub fn submit_with_async_compute(
         &self,
@@ -96,6 +156,8 @@ impl<B: Backend> Queue<B> {
         compute_commands: CommandBuffer<B>,
     ) -> (Job<B>, Job<B>) {
         assert_ne!(self.ty, QueueType::Compute);
+        check_commands(&self.ctx, debug_name, &commands.commands);
+        check_commands(&self.ctx, debug_name, &compute_commands.commands);
 
         let (prim_id, comp_id) = unsafe {
             self.ctx.0.submit_commands_async_compute(
@@ -123,12 +185,80 @@ impl<B: Backend> Queue<B> {
     /// # Arguments
     /// - `debug_name` - The backend *should* use the provided debug name for easy identification.
     /// - `commands` - The command buffers to submit.
+    ///
+    /// # Panics
+    /// - If the command buffer contains a pass that was never ended or a command recorded outside
+    ///   of the pass it belongs in. See [`validate_commands`].
+    /// - If more push constants are pushed than the device supports.
     #[inline(always)]
     pub fn submit_async(&self, debug_name: Option<&str>, commands: CommandBuffer<B>) -> Job<B> {
+        check_commands(&self.ctx, debug_name, &commands.commands);
         let id = unsafe {
             self.ctx
                 .0
-                .submit_commands(self.ty, debug_name, commands.commands, true)
+                .submit_commands(self.ty, debug_name, commands.commands, true, &[])
+        };
+
+        Job {
+            id,
+            ctx: self.ctx.clone(),
+        }
+    }
+
+    /// Records commands once so they can be submitted any number of times with
+    /// [`submit_recorded`](Queue::submit_recorded).
+    ///
+    /// `record` can only record commands using resources handed over with
+    /// [`RecordingResources::retain`], or ones that live for `'static`.
+    ///
+    /// # Panics
+    /// - If the commands contain a pass that was never ended or a command recorded outside of the
+    ///   pass it belongs in. See [`validate_commands`].
+    /// - If more push constants are pushed than the device supports.
+    pub fn record(
+        &self,
+        record: impl for<'r> FnOnce(&mut CommandBuffer<'r, B>, &RecordingResources<'r>),
+    ) -> RecordedCommands<B> {
+        let resources = RecordingResources::new();
+        let mut commands = self.command_buffer();
+        record(&mut commands, &resources);
+        check_commands(&self.ctx, None, &commands.commands);
+
+        let mut commands = commands.commands;
+        unsafe {
+            let cache = self.ctx.0.record_commands(self.ty, &mut commands);
+            RecordedCommands::new(self.ty, commands, cache, resources)
+        }
+    }
+
+    /// Submits commands recorded with [`record`](Queue::record). They can be submitted again as
+    /// many times as needed.
+    ///
+    /// # Arguments
+    /// - `debug_name` - The backend *should* use the provided debug name for easy identification.
+    /// - `recorded` - The recorded commands to submit.
+    ///
+    /// # Panics
+    /// - If the commands were recorded for a different type of queue.
+    #[inline(always)]
+    pub fn submit_recorded(
+        &self,
+        debug_name: Option<&str>,
+        recorded: &RecordedCommands<B>,
+    ) -> Job<B> {
+        assert_eq!(
+            self.ty, recorded.queue_ty,
+            "commands recorded for queue `{:?}` submitted to queue `{:?}`",
+            recorded.queue_ty, self.ty
+        );
+        let id = unsafe {
+            self.ctx.0.submit_recorded_commands(
+                self.ty,
+                debug_name,
+                recorded.commands(),
+                &recorded.cache,
+                &[],
+            )
         };
 
         Job {
@@ -158,10 +288,17 @@ impl<B: Backend> Queue<B> {
 }
 
 impl<B: Backend> Job<B> {
+    #[inline(always)]
+    pub(crate) fn into_id(self) -> B::Job {
+        self.id
+    }
+
     /// Wait's for the job to complete with the given timeout. If `None` is provided, then this
     /// call *must* block as long as possible for the job is finished. Returns the status of the
     /// job by the time the timeout is reached.
     ///
+    /// If the device is lost, this returns [`JobStatus::DeviceLost`] instead of blocking forever.
+    ///
     /// # Arguments
     /// - `timeout` - The time to wait, or `None` if there should be no timeout.
     #[inline(always)]
@@ -174,4 +311,40 @@ impl<B: Backend> Job<B> {
     pub fn poll_status(&self) -> JobStatus {
         unsafe { self.ctx.0.poll_status(&self.id) }
     }
+
+    /// Gets the GPU time elapsed for each [`timestamp`](CommandBuffer::timestamp) region recorded
+    /// in the submitted command buffer, in the order the regions were recorded.
+    ///
+    /// # Note
+    /// Results are only available once the job is complete. If the job is still running, an
+    /// empty list is returned.
+    #[inline(always)]
+    pub fn timestamps(&self) -> Vec<(String, Duration)> {
+        unsafe { self.ctx.0.job_timestamps(&self.id) }
+    }
+
+    /// Gets the pipeline statistics for each [`statistics`](CommandBuffer::statistics) region
+    /// recorded in the submitted command buffer, in the order the regions were recorded.
+    ///
+    /// # Note
+    /// Results are only available once the job is complete. If the job is still running, an
+    /// empty list is returned.
+    #[inline(always)]
+    pub fn statistics(&self) -> Vec<(String, PipelineStatistics)> {
+        unsafe { self.ctx.0.job_statistics(&self.id) }
+    }
+}
+
+/// Panics with a description of the first malformed command, if any.
+fn check_commands<B: Backend>(
+    ctx: &Context<B>,
+    debug_name: Option<&str>,
+    commands: &[Command<'_, B>],
+) {
+    if let Err(err) = validate_commands(commands, &ctx.properties().limits) {
+        panic!(
+            "invalid commands submitted in job `{}`: {err}",
+            debug_name.unwrap_or("unnamed")
+        );
+    }
 }
@@ -0,0 +1,96 @@
+use std::{any::Any, cell::RefCell, marker::PhantomData, sync::Arc};
+
+use crate::{command_buffer::Command, types::QueueType, Backend};
+
+/// A list of commands that is recorded once and can be
+/// [submitted](crate::queue::Queue::submit_recorded) any number of times.
+///
+/// Commands recorded to a [`CommandBuffer`](crate::command_buffer::CommandBuffer) borrow the
+/// resources they use, so they have to be recorded again every time they're submitted. Recorded
+/// commands instead own strong references to every resource they use, which are handed over with
+/// [`RecordingResources::retain`] while recording. The resources are kept alive for as long as
+/// the recorded commands are, and are destroyed as usual once they're dropped and the GPU is done
+/// with them.
+///
+/// The backend *may* cache work that doesn't change between submissions, such as the order the
+/// commands execute in.
+pub struct RecordedCommands<B: Backend> {
+    pub(crate) queue_ty: QueueType,
+    // NOTE: The commands borrow from `resources`, so they must be dropped first.
+    pub(crate) commands: Vec<Command<'static, B>>,
+    pub(crate) cache: B::CommandCache,
+    _resources: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+/// Owns the resources used by commands while they're being recorded into [`RecordedCommands`].
+pub struct RecordingResources<'r> {
+    resources: RefCell<Vec<Box<dyn Any + Send + Sync>>>,
+    _lifetime: PhantomData<fn(&'r ()) -> &'r ()>,
+}
+
+impl<B: Backend> RecordedCommands<B> {
+    /// Creates recorded commands from commands that only borrow from `resources`.
+    ///
+    /// # Safety
+    /// Every reference in `commands` must either be `'static` or point into `resources`.
+    pub(crate) unsafe fn new(
+        queue_ty: QueueType,
+        commands: Vec<Command<'_, B>>,
+        cache: B::CommandCache,
+        resources: RecordingResources<'_>,
+    ) -> Self {
+        Self {
+            queue_ty,
+            commands: std::mem::transmute::<Vec<Command<'_, B>>, Vec<Command<'static, B>>>(
+                commands,
+            ),
+            cache,
+            _resources: resources.resources.into_inner(),
+        }
+    }
+
+    /// The type of queue the commands were recorded for.
+    #[inline(always)]
+    pub fn queue_ty(&self) -> QueueType {
+        self.queue_ty
+    }
+
+    /// Number of top level commands that were recorded.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// The recorded commands, borrowed for no longer than `self`.
+    #[inline(always)]
+    pub(crate) fn commands(&self) -> &[Command<'_, B>] {
+        &self.commands
+    }
+}
+
+impl<'r> RecordingResources<'r> {
+    pub(crate) fn new() -> Self {
+        Self {
+            resources: RefCell::default(),
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Hands a resource over to the recorded commands and returns a reference to it that
+    /// commands can be recorded with.
+    ///
+    /// The resource is kept alive until the recorded commands are dropped.
+    pub fn retain<T: ?Sized + Send + Sync + 'static>(&self, resource: Arc<T>) -> &'r T {
+        let value: *const T = Arc::as_ptr(&resource);
+        self.resources.borrow_mut().push(Box::new(resource));
+
+        // SAFETY: The value lives in the allocation of the `Arc`, which never moves and is owned
+        // by the recorded commands alongside every command that could hold the reference.
+        unsafe { &*value }
+    }
+}
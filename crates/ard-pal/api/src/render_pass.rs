@@ -1,17 +1,29 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use thiserror::Error;
+
 use crate::{
     buffer::Buffer,
     command_buffer::Command,
     cube_map::CubeMap,
-    descriptor_set::DescriptorSet,
+    descriptor_set::{DescriptorSet, DescriptorSetLayout, DescriptorSetUpdate},
     graphics_pipeline::GraphicsPipeline,
     surface::SurfaceImage,
     texture::Texture,
     types::{
-        CubeFace, IndexType, LoadOp, MultiSamples, ResolveMode, Scissor, ShaderStage, StoreOp,
+        CubeFace, DynamicStates, IndexType, LoadOp, MultiSamples, ResolveMode, Scissor,
+        ShaderStage, StoreOp, TextureType, Viewport,
     },
     Backend,
 };
 
+/// The most layers a single attachment can bind, one for each bit of a
+/// [`view_mask`](RenderPassDescriptor::view_mask).
+pub const MAX_ATTACHMENT_LAYERS: usize = u32::BITS as usize;
+
 /// Describes a render pass.
 pub struct RenderPassDescriptor<'a, B: Backend> {
     /// The color attachments used by the render pass.
@@ -22,6 +34,15 @@ pub struct RenderPassDescriptor<'a, B: Backend> {
     pub color_resolve_attachments: Vec<ColorResolveAttachment<'a, B>>,
     /// An optional depth stencil attachment used for multi-sample resolution.
     pub depth_stencil_resolve_attachment: Option<DepthStencilResolveAttachment<'a, B>>,
+    /// When non-zero, every draw is broadcast to each view whose bit is set, with view `i`
+    /// rendering to layer `i` of every attachment. Shaders can read the current view with
+    /// `gl_ViewIndex`.
+    ///
+    /// Requires [`multiview`](crate::context::GraphicsFeatures::multiview).
+    pub view_mask: u32,
+    /// Sets of views, each a subset of `view_mask`, which are spatially close and may be
+    /// rendered concurrently by the implementation. Only a hint.
+    pub correlation_masks: Vec<u32>,
 }
 
 /// Describes a color attachment of a render pass.
@@ -63,23 +84,46 @@ pub enum ColorAttachmentDestination<'a, B: Backend> {
         face: CubeFace,
         mip_level: usize,
     },
+    /// All six faces of a cube, bound as one layered attachment in face order.
     CubeMap {
         cube_map: &'a CubeMap<B>,
         array_element: usize,
         mip_level: usize,
     },
+    /// A single depth slice of a mip of a 3D texture.
+    TextureSlice {
+        texture: &'a Texture<B>,
+        mip_level: usize,
+        slice: u32,
+    },
+    /// A range of array elements of a mip of a 2D texture, bound as one layered attachment.
+    TextureArray {
+        texture: &'a Texture<B>,
+        base_layer: usize,
+        layer_count: usize,
+        mip_level: usize,
+    },
 }
 
 /// Describes the depth stencil attachment of a render pass.
 pub struct DepthStencilAttachment<'a, B: Backend> {
     /// The attachment destination.
     pub dst: DepthStencilAttachmentDestination<'a, B>,
-    /// How the depth stencil attachment should be loaded.
+    /// How the depth aspect of the attachment should be loaded.
     pub load_op: LoadOp,
-    /// How the depth stencil attachment should be stored.
+    /// How the depth aspect of the attachment should be stored.
     pub store_op: StoreOp,
     /// The number of samples to render per fragment.
     pub samples: MultiSamples,
+    /// How the stencil aspect should be loaded. Ignored for formats without stencil.
+    ///
+    /// A clear uses the stencil value of `ClearColor::D32S32`, independent of `load_op`.
+    pub stencil_load_op: LoadOp,
+    /// How the stencil aspect should be stored. Ignored for formats without stencil.
+    ///
+    /// The attachment is only read only if both `store_op` and `stencil_store_op` are
+    /// `StoreOp::None`.
+    pub stencil_store_op: StoreOp,
 }
 
 /// Describes the depth stencil attachment used for multi-sample resolution of a render pass.
@@ -109,24 +153,365 @@ pub enum DepthStencilAttachmentDestination<'a, B: Backend> {
         face: CubeFace,
         mip_level: usize,
     },
+    /// All six faces of a cube, bound as one layered attachment in face order.
     CubeMap {
         cube_map: &'a CubeMap<B>,
         array_element: usize,
         mip_level: usize,
     },
+    /// A range of array elements of a mip of a 2D texture, bound as one layered attachment.
+    TextureArray {
+        texture: &'a Texture<B>,
+        base_layer: usize,
+        layer_count: usize,
+        mip_level: usize,
+    },
 }
 
 pub struct RenderPass<'a, B: Backend> {
     pub(crate) bound_pipeline: bool,
+    /// Dynamic states enabled by the bound pipeline.
+    pub(crate) dynamic_states: DynamicStates,
+    /// Dimensions of every attachment, used to validate viewports and scissors.
+    pub(crate) attachments: Vec<(AttachmentRef, (u32, u32))>,
     pub(crate) commands: Vec<Command<'a, B>>,
 }
 
+/// Identifies an attachment of a [`RenderPassDescriptor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AttachmentRef {
+    Color(usize),
+    DepthStencil,
+    ColorResolve(usize),
+    DepthStencilResolve,
+}
+
+#[derive(Debug, Error)]
+pub enum RenderAreaError {
+    #[error(
+        "viewport {viewport:?} extends past the {}x{} bounds of {attachment}",
+        .extent.0,
+        .extent.1
+    )]
+    ViewportOutOfBounds {
+        viewport: Viewport,
+        attachment: AttachmentRef,
+        extent: (u32, u32),
+    },
+    #[error(
+        "scissor {scissor:?} extends past the {}x{} bounds of {attachment}",
+        .extent.0,
+        .extent.1
+    )]
+    ScissorOutOfBounds {
+        scissor: Scissor,
+        attachment: AttachmentRef,
+        extent: (u32, u32),
+    },
+    #[error("viewport {viewport:?} must have a positive size and a depth range within 0 and 1")]
+    InvalidViewport { viewport: Viewport },
+}
+
+/// A portion of a render pass which can be recorded independently of, and in parallel with,
+/// other slices of the same pass. See [`RenderPass::split`].
+pub struct RenderPassSlice<'a, B: Backend>(RenderPass<'a, B>);
+
 pub struct VertexBind<'a, B: Backend> {
     pub buffer: &'a Buffer<B>,
     pub array_element: usize,
     pub offset: u64,
 }
 
+impl<B: Backend> RenderPassDescriptor<'_, B> {
+    /// Checks that every attachment of the pass agrees on its sample count and that resolve
+    /// attachments resolve from a multi-sampled attachment.
+    pub(crate) fn check_sample_counts(&self) {
+        let mut samples = None;
+        let mut check = |name: String, declared: MultiSamples, actual: MultiSamples| {
+            assert_eq!(
+                declared, actual,
+                "{name} declares `{declared:?}` samples but its texture has `{actual:?}` samples"
+            );
+            match &samples {
+                Some((first, first_samples)) => assert_eq!(
+                    declared, *first_samples,
+                    "render pass attachments must share a sample count, but {first} uses \
+                    `{first_samples:?}` and {name} uses `{declared:?}`"
+                ),
+                None => samples = Some((name, declared)),
+            }
+        };
+
+        for (i, attachment) in self.color_attachments.iter().enumerate() {
+            check(
+                format!("color attachment {i}"),
+                attachment.samples,
+                attachment.dst.sample_count(),
+            );
+        }
+
+        if let Some(attachment) = &self.depth_stencil_attachment {
+            check(
+                String::from("the depth stencil attachment"),
+                attachment.samples,
+                attachment.dst.sample_count(),
+            );
+        }
+
+        for (i, attachment) in self.color_resolve_attachments.iter().enumerate() {
+            let src = self
+                .color_attachments
+                .get(attachment.src)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "color resolve attachment {i} resolves from color attachment {} which \
+                        does not exist",
+                        attachment.src
+                    )
+                });
+            assert_ne!(
+                src.samples,
+                MultiSamples::Count1,
+                "color resolve attachment {i} resolves from color attachment {} which is not \
+                multi-sampled",
+                attachment.src
+            );
+            assert_eq!(
+                attachment.dst.sample_count(),
+                MultiSamples::Count1,
+                "color resolve attachment {i} must be single-sampled"
+            );
+        }
+
+        if let Some(attachment) = &self.depth_stencil_resolve_attachment {
+            let src = self
+                .depth_stencil_attachment
+                .as_ref()
+                .expect("a depth stencil resolve attachment requires a depth stencil attachment");
+            assert_ne!(
+                src.samples,
+                MultiSamples::Count1,
+                "the depth stencil resolve attachment resolves from a depth stencil attachment \
+                which is not multi-sampled"
+            );
+            assert_eq!(
+                attachment.dst.sample_count(),
+                MultiSamples::Count1,
+                "the depth stencil resolve attachment must be single-sampled"
+            );
+        }
+    }
+}
+
+impl<B: Backend> RenderPassDescriptor<'_, B> {
+    /// Gets the dimensions of every attachment of the pass.
+    pub(crate) fn attachment_dims(&self) -> Vec<(AttachmentRef, (u32, u32))> {
+        let mut dims = Vec::with_capacity(
+            self.color_attachments.len() + self.color_resolve_attachments.len() + 2,
+        );
+        for (i, attachment) in self.color_attachments.iter().enumerate() {
+            dims.push((AttachmentRef::Color(i), attachment.dst.dims()));
+        }
+        if let Some(attachment) = &self.depth_stencil_attachment {
+            dims.push((AttachmentRef::DepthStencil, attachment.dst.dims()));
+        }
+        for (i, attachment) in self.color_resolve_attachments.iter().enumerate() {
+            dims.push((AttachmentRef::ColorResolve(i), attachment.dst.dims()));
+        }
+        if let Some(attachment) = &self.depth_stencil_resolve_attachment {
+            dims.push((AttachmentRef::DepthStencilResolve, attachment.dst.dims()));
+        }
+        dims
+    }
+
+    /// Checks that every attachment has a layer for each view of the pass and that the
+    /// correlation masks are disjoint subsets of the view mask.
+    pub(crate) fn check_views(&self) {
+        let mut layers = Vec::with_capacity(
+            self.color_attachments.len() + self.color_resolve_attachments.len() + 2,
+        );
+        for (i, attachment) in self.color_attachments.iter().enumerate() {
+            layers.push((AttachmentRef::Color(i), attachment.dst.layers()));
+        }
+        if let Some(attachment) = &self.depth_stencil_attachment {
+            layers.push((AttachmentRef::DepthStencil, attachment.dst.layers()));
+        }
+        for (i, attachment) in self.color_resolve_attachments.iter().enumerate() {
+            layers.push((AttachmentRef::ColorResolve(i), attachment.dst.layers()));
+        }
+        if let Some(attachment) = &self.depth_stencil_resolve_attachment {
+            layers.push((AttachmentRef::DepthStencilResolve, attachment.dst.layers()));
+        }
+
+        if let Some((attachment, count)) = layer_mismatch(self.view_mask, &layers) {
+            panic!(
+                "{attachment} has {count} layers, but a view mask of `{:#b}` requires {}",
+                self.view_mask,
+                required_layers(self.view_mask)
+            );
+        }
+
+        let mut correlated = 0;
+        for mask in &self.correlation_masks {
+            assert_eq!(
+                mask & !self.view_mask,
+                0,
+                "correlation mask `{mask:#b}` includes views outside of the view mask `{:#b}`",
+                self.view_mask
+            );
+            assert_eq!(
+                mask & correlated,
+                0,
+                "views of correlation mask `{mask:#b}` appear in more than one correlation mask"
+            );
+            correlated |= mask;
+        }
+    }
+}
+
+impl<B: Backend> ColorAttachmentDestination<'_, B> {
+    /// Gets the width and height of the attached mip.
+    pub(crate) fn dims(&self) -> (u32, u32) {
+        match self {
+            ColorAttachmentDestination::SurfaceImage(image) => image.dimensions(),
+            ColorAttachmentDestination::Texture {
+                texture, mip_level, ..
+            }
+            | ColorAttachmentDestination::TextureSlice {
+                texture, mip_level, ..
+            }
+            | ColorAttachmentDestination::TextureArray {
+                texture, mip_level, ..
+            } => mip_dims(texture.dims().0, texture.dims().1, *mip_level),
+            ColorAttachmentDestination::CubeFace {
+                cube_map,
+                mip_level,
+                ..
+            }
+            | ColorAttachmentDestination::CubeMap {
+                cube_map,
+                mip_level,
+                ..
+            } => mip_dims(cube_map.dim(), cube_map.dim(), *mip_level),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn sample_count(&self) -> MultiSamples {
+        match self {
+            ColorAttachmentDestination::Texture { texture, .. }
+            | ColorAttachmentDestination::TextureSlice { texture, .. }
+            | ColorAttachmentDestination::TextureArray { texture, .. } => texture.sample_count(),
+            _ => MultiSamples::Count1,
+        }
+    }
+
+    /// Gets the number of layers bound by the attachment.
+    #[inline(always)]
+    pub(crate) fn layers(&self) -> usize {
+        match self {
+            ColorAttachmentDestination::CubeMap { .. } => 6,
+            ColorAttachmentDestination::TextureArray { layer_count, .. } => *layer_count,
+            _ => 1,
+        }
+    }
+
+    /// Checks that texture slices are of 3D textures and within the depth of their mip.
+    #[inline(always)]
+    pub(crate) fn check_slice(&self) {
+        if let ColorAttachmentDestination::TextureSlice {
+            texture,
+            mip_level,
+            slice,
+        } = self
+        {
+            assert_eq!(
+                texture.ty(),
+                TextureType::Type3D,
+                "only 3D textures can be attached by slice"
+            );
+            assert!(*mip_level < texture.mip_count(), "mip level out of bounds");
+            assert!(
+                *slice < (texture.dims().2 >> mip_level).max(1),
+                "slice out of bounds"
+            );
+        }
+    }
+
+    /// Checks that texture arrays are of 2D textures and within the bounds of the texture.
+    #[inline(always)]
+    pub(crate) fn check_array(&self) {
+        if let ColorAttachmentDestination::TextureArray {
+            texture,
+            base_layer,
+            layer_count,
+            mip_level,
+        } = self
+        {
+            check_array(texture, *base_layer, *layer_count, *mip_level);
+        }
+    }
+}
+
+impl<B: Backend> DepthStencilAttachmentDestination<'_, B> {
+    /// Gets the width and height of the attached mip.
+    pub(crate) fn dims(&self) -> (u32, u32) {
+        match self {
+            DepthStencilAttachmentDestination::Texture {
+                texture, mip_level, ..
+            }
+            | DepthStencilAttachmentDestination::TextureArray {
+                texture, mip_level, ..
+            } => mip_dims(texture.dims().0, texture.dims().1, *mip_level),
+            DepthStencilAttachmentDestination::CubeFace {
+                cube_map,
+                mip_level,
+                ..
+            }
+            | DepthStencilAttachmentDestination::CubeMap {
+                cube_map,
+                mip_level,
+                ..
+            } => mip_dims(cube_map.dim(), cube_map.dim(), *mip_level),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn sample_count(&self) -> MultiSamples {
+        match self {
+            DepthStencilAttachmentDestination::Texture { texture, .. }
+            | DepthStencilAttachmentDestination::TextureArray { texture, .. } => {
+                texture.sample_count()
+            }
+            _ => MultiSamples::Count1,
+        }
+    }
+
+    /// Gets the number of layers bound by the attachment.
+    #[inline(always)]
+    pub(crate) fn layers(&self) -> usize {
+        match self {
+            DepthStencilAttachmentDestination::CubeMap { .. } => 6,
+            DepthStencilAttachmentDestination::TextureArray { layer_count, .. } => *layer_count,
+            _ => 1,
+        }
+    }
+
+    /// Checks that texture arrays are of 2D textures and within the bounds of the texture.
+    #[inline(always)]
+    pub(crate) fn check_array(&self) {
+        if let DepthStencilAttachmentDestination::TextureArray {
+            texture,
+            base_layer,
+            layer_count,
+            mip_level,
+        } = self
+        {
+            check_array(texture, *base_layer, *layer_count, *mip_level);
+        }
+    }
+}
+
 impl<'a, B: Backend> RenderPass<'a, B> {
     /// Binds a graphics pipeline to the pass.
     ///
@@ -135,6 +520,7 @@ impl<'a, B: Backend> RenderPass<'a, B> {
     #[inline]
     pub fn bind_pipeline(&mut self, pipeline: GraphicsPipeline<B>) {
         self.bound_pipeline = true;
+        self.dynamic_states = pipeline.dynamic_states();
         self.commands.push(Command::BindGraphicsPipeline(pipeline));
     }
 
@@ -186,6 +572,32 @@ impl<'a, B: Backend> RenderPass<'a, B> {
         });
     }
 
+    /// Pushes descriptors directly into the pass, replacing the set at `index`. Pushed
+    /// descriptors avoid allocating a set for data that changes with every draw or dispatch.
+    ///
+    /// # Arguments
+    /// - `index` - The index of the set to replace.
+    /// - `layout` - The layout of the set. Must have been created with `push` enabled.
+    /// - `bindings` - The values to bind. Bindings not provided are left undefined.
+    ///
+    /// # Panics
+    /// - If `layout` is not a push descriptor layout.
+    #[inline]
+    pub fn push_set(
+        &mut self,
+        index: usize,
+        layout: &DescriptorSetLayout<B>,
+        bindings: Vec<DescriptorSetUpdate<'a, B>>,
+    ) {
+        assert!(layout.is_push(), "layout is not a push descriptor layout");
+        self.commands.push(Command::PushDescriptorSet {
+            layout: layout.clone(),
+            index,
+            bindings,
+            stage: ShaderStage::AllGraphics,
+        });
+    }
+
     /// Binds vertex buffers to the pass.
     ///
     /// # Arguments
@@ -233,12 +645,132 @@ impl<'a, B: Backend> RenderPass<'a, B> {
     /// # Arguments
     /// - `idx` - The index of the attachment to apply the scissor to.
     /// - `scissor` - The scissor value.
+    ///
+    /// # Errors
+    /// - If the scissor extends past the bounds of any attachment of the pass. Nothing is
+    ///   recorded in that case.
     #[inline]
-    pub fn set_scissor(&mut self, attachment: usize, scissor: Scissor) {
+    pub fn set_scissor(
+        &mut self,
+        attachment: usize,
+        scissor: Scissor,
+    ) -> Result<(), RenderAreaError> {
+        let min = (scissor.x as i64, scissor.y as i64);
+        let max = (min.0 + scissor.width as i64, min.1 + scissor.height as i64);
+        if let Some((attachment, extent)) = out_of_bounds(&self.attachments, min, max) {
+            return Err(RenderAreaError::ScissorOutOfBounds {
+                scissor,
+                attachment,
+                extent,
+            });
+        }
+
         self.commands.push(Command::Scissor {
             attachment,
             scissor,
         });
+        Ok(())
+    }
+
+    /// Sets the region of the framebuffer that rendering is mapped to. Each render pass begins
+    /// with a viewport covering the entire framebuffer.
+    ///
+    /// # Arguments
+    /// - `viewport` - The viewport value.
+    ///
+    /// # Errors
+    /// - If the viewport has no area or its depth range isn't within `0.0` and `1.0`.
+    /// - If the viewport extends past the bounds of any attachment of the pass.
+    ///
+    /// Nothing is recorded if an error is returned.
+    #[inline]
+    pub fn set_viewport(&mut self, viewport: Viewport) -> Result<(), RenderAreaError> {
+        let depth_range = 0.0..=1.0;
+        if !(viewport.width > 0.0
+            && viewport.height > 0.0
+            && depth_range.contains(&viewport.min_depth)
+            && depth_range.contains(&viewport.max_depth))
+        {
+            return Err(RenderAreaError::InvalidViewport { viewport });
+        }
+
+        // Viewports may have fractional bounds, so they're checked against the pixel they end in
+        let min = (viewport.x.floor() as i64, viewport.y.floor() as i64);
+        let max = (
+            (viewport.x + viewport.width).ceil() as i64,
+            (viewport.y + viewport.height).ceil() as i64,
+        );
+        if let Some((attachment, extent)) = out_of_bounds(&self.attachments, min, max) {
+            return Err(RenderAreaError::ViewportOutOfBounds {
+                viewport,
+                attachment,
+                extent,
+            });
+        }
+
+        self.commands.push(Command::SetViewport {
+            x: viewport.x,
+            y: viewport.y,
+            width: viewport.width,
+            height: viewport.height,
+            min_depth: viewport.min_depth,
+            max_depth: viewport.max_depth,
+        });
+        Ok(())
+    }
+
+    /// Sets the depth bias added to fragments of subsequent draws.
+    ///
+    /// # Arguments
+    /// - `constant` - Constant depth value added to each fragment.
+    /// - `clamp` - Maximum, or minimum if negative, depth bias of a fragment. `0.0` disables
+    ///   clamping. Other values require
+    ///   [`depth_bias_clamp`](crate::context::GraphicsFeatures::depth_bias_clamp).
+    /// - `slope` - Factor applied to the depth slope of each fragment.
+    ///
+    /// # Panics
+    /// - If the bound pipeline doesn't enable [`DynamicStates::DEPTH_BIAS`].
+    #[inline]
+    pub fn set_depth_bias(&mut self, constant: f32, clamp: f32, slope: f32) {
+        self.check_dynamic_state(DynamicStates::DEPTH_BIAS, "depth bias");
+        self.commands.push(Command::SetDepthBias {
+            constant,
+            clamp,
+            slope,
+        });
+    }
+
+    /// Sets the width in pixels of lines rasterized by subsequent draws. Widths other than `1.0`
+    /// require [`wide_lines`](crate::context::GraphicsFeatures::wide_lines).
+    ///
+    /// # Panics
+    /// - If the bound pipeline doesn't enable [`DynamicStates::LINE_WIDTH`].
+    /// - If `width` is not greater than `0.0`.
+    #[inline]
+    pub fn set_line_width(&mut self, width: f32) {
+        self.check_dynamic_state(DynamicStates::LINE_WIDTH, "line width");
+        assert!(width > 0.0, "line width must be greater than zero");
+        self.commands.push(Command::SetLineWidth(width));
+    }
+
+    /// Sets the value stencil tests of subsequent draws compare against.
+    ///
+    /// # Panics
+    /// - If the bound pipeline doesn't enable [`DynamicStates::STENCIL_REFERENCE`].
+    #[inline]
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.check_dynamic_state(DynamicStates::STENCIL_REFERENCE, "stencil reference");
+        self.commands.push(Command::SetStencilReference(reference));
+    }
+
+    /// Sets the RGBA constants used by the `Constant*` blend factors of subsequent draws.
+    ///
+    /// # Panics
+    /// - If the bound pipeline doesn't enable [`DynamicStates::BLEND_CONSTANTS`].
+    #[inline]
+    pub fn set_blend_constants(&mut self, constants: [f32; 4]) {
+        self.check_dynamic_state(DynamicStates::BLEND_CONSTANTS, "blend constants");
+        self.commands.push(Command::SetBlendConstants(constants));
     }
 
     /// Draws an unindexed sequence of triangles.
@@ -270,6 +802,36 @@ impl<'a, B: Backend> RenderPass<'a, B> {
         });
     }
 
+    /// Draw a sequence of triangles with draw commands contained within an indirect buffer.
+    ///
+    /// # Arguments
+    /// - `buffer` - The indirect buffer to read commands from.
+    /// - `array_element` - The array element of the indirect buffer to read from.
+    /// - `offset` - The offset in bytes within the array element to read from.
+    /// - `draw_count` - The number of draw commands to read.
+    /// - `stride` - The stride in bytes for each draw command.
+    ///
+    /// # Panics
+    /// - If `stride == 0`.
+    #[inline]
+    pub fn draw_indirect(
+        &mut self,
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        draw_count: usize,
+        stride: u64,
+    ) {
+        assert_ne!(stride, 0, "stride cannot be 0");
+        self.commands.push(Command::DrawIndirect {
+            buffer,
+            array_element,
+            offset,
+            draw_count,
+            stride,
+        });
+    }
+
     /// Draw an indexed sequence of triangles.
     ///
     /// # Arguments
@@ -384,4 +946,277 @@ impl<'a, B: Backend> RenderPass<'a, B> {
         debug_assert_ne!(z, 0);
         self.commands.push(Command::DrawMeshTasks(x, y, z));
     }
+
+    /// Dispatch mesh tasks with dispatch commands contained within an indirect buffer. Each
+    /// command has the same layout as a compute dispatch (three unsigned 32-bit group counts).
+    ///
+    /// # Arguments
+    /// - `buffer` - The indirect buffer to read commands from.
+    /// - `array_element` - The array element of the indirect buffer to read from.
+    /// - `offset` - The offset in bytes within the array element to read from.
+    /// - `draw_count` - The number of dispatch commands to read.
+    /// - `stride` - The stride in bytes for each dispatch command.
+    ///
+    /// # Panics
+    /// - If `stride == 0`.
+    #[inline]
+    pub fn draw_mesh_tasks_indirect(
+        &mut self,
+        buffer: &'a Buffer<B>,
+        array_element: usize,
+        offset: u64,
+        draw_count: usize,
+        stride: u64,
+    ) {
+        assert_ne!(stride, 0, "stride cannot be 0");
+        self.commands.push(Command::DrawMeshTasksIndirect {
+            buffer,
+            array_element,
+            offset,
+            draw_count,
+            stride,
+        });
+    }
+
+    /// Dispatch mesh tasks with dispatch commands contained within an indirect buffer. An
+    /// unsigned 32-bit dispatch count is sourced from an alternative buffer.
+    ///
+    /// # Arguments
+    /// - `draw_buffer` - The indirect buffer to read commands from.
+    /// - `draw_array_element` - The array element of the indirect buffer to read from.
+    /// - `draw_offset` - The offset in bytes within the indirect buffer array element to read
+    ///   from.
+    /// - `count_buffer` - The buffer to read dispatch counts from.
+    /// - `count_array_element` - The array element of the dispatch count buffer to read from.
+    /// - `count_offset` - The offset in bytes within the dispatch count buffer array element to
+    ///   read from.
+    /// - `max_draw_count` - The maximum number of dispatch commands to read. The actual count is
+    ///   the minimum of `max_draw_count` and the value read from the `count_buffer`.
+    /// - `draw_stride` - The stride in bytes for each dispatch command.
+    ///
+    /// # Panics
+    /// - If `draw_stride == 0`.
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_mesh_tasks_indirect_count(
+        &mut self,
+        draw_buffer: &'a Buffer<B>,
+        draw_array_element: usize,
+        draw_offset: u64,
+        count_buffer: &'a Buffer<B>,
+        count_array_element: usize,
+        count_offset: u64,
+        max_draw_count: usize,
+        draw_stride: u64,
+    ) {
+        assert_ne!(draw_stride, 0, "draw stride cannot be 0");
+        self.commands.push(Command::DrawMeshTasksIndirectCount {
+            draw_buffer,
+            draw_array_element,
+            draw_offset,
+            draw_stride,
+            count_buffer,
+            count_array_element,
+            count_offset,
+            max_draw_count,
+        });
+    }
+
+    #[inline(always)]
+    fn check_dynamic_state(&self, state: DynamicStates, name: &str) {
+        assert!(
+            self.bound_pipeline,
+            "a pipeline must be bound before setting the {name}"
+        );
+        assert!(
+            self.dynamic_states.contains(state),
+            "the bound pipeline does not enable dynamic {name}"
+        );
+    }
+
+    /// Splits the pass into slices which can be recorded in parallel. The slices are executed in
+    /// order, after every command previously recorded to the pass.
+    ///
+    /// # Arguments
+    /// - `count` - The number of slices to create.
+    /// - `record` - A function that records commands into the slices.
+    ///
+    /// # Note
+    /// Each slice begins with the pipeline, descriptor sets, buffers, push constants, viewport,
+    /// and scissors bound to the pass before the split. State bound within a slice is not visible to
+    /// other slices or to commands recorded to the pass after the split.
+    ///
+    /// # Panics
+    /// - If `count == 0`.
+    pub fn split(&mut self, count: usize, record: impl FnOnce(&mut [RenderPassSlice<'a, B>])) {
+        assert_ne!(count, 0, "slice count cannot be 0");
+
+        let mut slices: Vec<_> = (0..count)
+            .map(|_| {
+                RenderPassSlice(RenderPass {
+                    bound_pipeline: self.bound_pipeline,
+                    dynamic_states: self.dynamic_states,
+                    attachments: self.attachments.clone(),
+                    commands: Vec::default(),
+                })
+            })
+            .collect();
+        record(&mut slices);
+
+        self.commands.push(Command::RenderPassSlices(
+            slices.into_iter().map(|slice| slice.0.commands).collect(),
+        ));
+    }
+}
+
+impl<'a, B: Backend> Deref for RenderPassSlice<'a, B> {
+    type Target = RenderPass<'a, B>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, B: Backend> DerefMut for RenderPassSlice<'a, B> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl fmt::Display for AttachmentRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachmentRef::Color(i) => write!(f, "color attachment {i}"),
+            AttachmentRef::DepthStencil => write!(f, "the depth stencil attachment"),
+            AttachmentRef::ColorResolve(i) => write!(f, "color resolve attachment {i}"),
+            AttachmentRef::DepthStencilResolve => {
+                write!(f, "the depth stencil resolve attachment")
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn mip_dims(width: u32, height: u32, mip_level: usize) -> (u32, u32) {
+    ((width >> mip_level).max(1), (height >> mip_level).max(1))
+}
+
+fn check_array<B: Backend>(
+    texture: &Texture<B>,
+    base_layer: usize,
+    layer_count: usize,
+    mip_level: usize,
+) {
+    assert_eq!(
+        texture.ty(),
+        TextureType::Type2D,
+        "only 2D textures can be attached as arrays"
+    );
+    assert!(
+        (1..=MAX_ATTACHMENT_LAYERS).contains(&layer_count),
+        "texture arrays must bind between 1 and {MAX_ATTACHMENT_LAYERS} layers"
+    );
+    assert!(
+        base_layer + layer_count <= texture.array_elements(),
+        "array layers out of bounds"
+    );
+    assert!(mip_level < texture.mip_count(), "mip level out of bounds");
+}
+
+/// Gets the number of layers an attachment needs to render every view of `view_mask`.
+#[inline(always)]
+fn required_layers(view_mask: u32) -> usize {
+    if view_mask == 0 {
+        1
+    } else {
+        (u32::BITS - view_mask.leading_zeros()) as usize
+    }
+}
+
+/// Finds the first attachment without a layer for every view of `view_mask`. Without multiview,
+/// attachments must be a single layer.
+fn layer_mismatch(
+    view_mask: u32,
+    layers: &[(AttachmentRef, usize)],
+) -> Option<(AttachmentRef, usize)> {
+    let required = required_layers(view_mask);
+    layers.iter().copied().find(|(_, count)| {
+        if view_mask == 0 {
+            *count != 1
+        } else {
+            *count < required
+        }
+    })
+}
+
+/// Finds the first attachment the rect from `min` to `max` extends past.
+fn out_of_bounds(
+    attachments: &[(AttachmentRef, (u32, u32))],
+    min: (i64, i64),
+    max: (i64, i64),
+) -> Option<(AttachmentRef, (u32, u32))> {
+    attachments.iter().copied().find(|(_, extent)| {
+        min.0 < 0 || min.1 < 0 || max.0 > extent.0 as i64 || max.1 > extent.1 as i64
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATTACHMENTS: [(AttachmentRef, (u32, u32)); 2] = [
+        (AttachmentRef::Color(0), (1920, 1080)),
+        (AttachmentRef::DepthStencil, (1280, 720)),
+    ];
+
+    #[test]
+    fn rect_within_bounds() {
+        assert_eq!(out_of_bounds(&ATTACHMENTS, (0, 0), (1280, 720)), None);
+        assert_eq!(out_of_bounds(&ATTACHMENTS, (640, 360), (1280, 720)), None);
+    }
+
+    #[test]
+    fn rect_names_smaller_attachment() {
+        assert_eq!(
+            out_of_bounds(&ATTACHMENTS, (0, 0), (1920, 1080)),
+            Some((AttachmentRef::DepthStencil, (1280, 720)))
+        );
+    }
+
+    #[test]
+    fn single_view_requires_single_layer() {
+        let layers = [
+            (AttachmentRef::Color(0), 1),
+            (AttachmentRef::DepthStencil, 6),
+        ];
+        assert_eq!(
+            layer_mismatch(0, &layers),
+            Some((AttachmentRef::DepthStencil, 6))
+        );
+        assert_eq!(layer_mismatch(0, &layers[..1]), None);
+    }
+
+    #[test]
+    fn views_require_layer_for_highest_view() {
+        let layers = [
+            (AttachmentRef::Color(0), 6),
+            (AttachmentRef::DepthStencil, 2),
+        ];
+        assert_eq!(layer_mismatch(0b11, &layers), None);
+        assert_eq!(
+            layer_mismatch(0b100, &layers),
+            Some((AttachmentRef::DepthStencil, 2))
+        );
+        assert_eq!(required_layers(0b111111), 6);
+    }
+
+    #[test]
+    fn negative_offsets_are_out_of_bounds() {
+        assert_eq!(
+            out_of_bounds(&ATTACHMENTS, (-1, 0), (16, 16)),
+            Some((AttachmentRef::Color(0), (1920, 1080)))
+        );
+    }
 }
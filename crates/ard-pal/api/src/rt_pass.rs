@@ -1,7 +1,22 @@
-use std::ops::Range;
+//! Ray tracing passes.
+//!
+//! # Debugging Shaders
+//!
+//! Ray tracing shaders can print and assert the same way as
+//! [compute shaders](crate::compute_pass#debugging-shaders). `gl_GlobalInvocationID` doesn't
+//! exist in ray tracing stages, so the invocation recorded by asserts must be defined before
+//! including `gpu_assert.glsl`:
+//!
+//! ```glsl
+//! #define ARD_ASSERT_INVOCATION gl_LaunchIDEXT
+//! #include "gpu_assert.glsl"
+//! ```
 
 use crate::{
-    buffer::Buffer, command_buffer::Command, descriptor_set::DescriptorSet, types::ShaderStage,
+    buffer::Buffer,
+    command_buffer::Command,
+    descriptor_set::{DescriptorSet, DescriptorSetLayout, DescriptorSetUpdate},
+    types::ShaderStage,
     Backend,
 };
 
@@ -21,9 +36,22 @@ pub enum RayTracingDispatchSource<'a, B: Backend> {
 pub struct RayTracingDispatch<'a, B: Backend> {
     pub src: RayTracingDispatchSource<'a, B>,
     pub shader_binding_table: &'a Buffer<B>,
-    pub raygen_offset: u64,
-    pub miss_offset: u64,
-    pub hit_range: Range<u64>,
+    /// Region holding the single ray generation record.
+    pub raygen: ShaderBindingTableRegion,
+    pub miss: ShaderBindingTableRegion,
+    pub hit: ShaderBindingTableRegion,
+}
+
+/// A region of records within a shader binding table. Usually produced by an
+/// [`SbtBuilder`](crate::rt_pipeline::SbtBuilder).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShaderBindingTableRegion {
+    /// Offset in bytes from the start of the table. Must be a multiple of the base alignment.
+    pub offset: u64,
+    /// Bytes between consecutive records.
+    pub stride: u64,
+    /// Size in bytes of the whole region.
+    pub size: u64,
 }
 
 impl<'a, B: Backend> RayTracingPass<'a, B> {
@@ -66,4 +94,30 @@ impl<'a, B: Backend> RayTracingPass<'a, B> {
             stage: ShaderStage::RayTracing,
         });
     }
+
+    /// Pushes descriptors directly into the scope, replacing the set at `index`. Pushed
+    /// descriptors avoid allocating a set for data that changes with every draw or dispatch.
+    ///
+    /// # Arguments
+    /// - `index` - The index of the set to replace.
+    /// - `layout` - The layout of the set. Must have been created with `push` enabled.
+    /// - `bindings` - The values to bind. Bindings not provided are left undefined.
+    ///
+    /// # Panics
+    /// - If `layout` is not a push descriptor layout.
+    #[inline]
+    pub fn push_set(
+        &mut self,
+        index: usize,
+        layout: &DescriptorSetLayout<B>,
+        bindings: Vec<DescriptorSetUpdate<'a, B>>,
+    ) {
+        assert!(layout.is_push(), "layout is not a push descriptor layout");
+        self.commands.push(Command::PushDescriptorSet {
+            layout: layout.clone(),
+            index,
+            bindings,
+            stage: ShaderStage::RayTracing,
+        });
+    }
 }
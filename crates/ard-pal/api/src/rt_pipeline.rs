@@ -1,7 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
-    context::Context, descriptor_set::DescriptorSetLayout, shader::Shader, types::ShaderStage,
+    context::Context,
+    descriptor_set::DescriptorSetLayout,
+    graphics_pipeline::{check_pipeline_limits, PipelineLimitError},
+    rt_pass::ShaderBindingTableRegion,
+    shader::Shader,
+    types::ShaderStage,
     Backend,
 };
 use thiserror::*;
@@ -39,8 +44,18 @@ pub enum RayTracingShaderGroup {
         closest_hit: Option<usize>,
         any_hit: Option<usize>,
     },
+    /// Hit group for AABB geometry. The intersection shader decides where rays hit the geometry
+    /// within each AABB.
+    Procedural {
+        intersection: usize,
+        closest_hit: Option<usize>,
+        any_hit: Option<usize>,
+    },
 }
 
+/// Handles for every shader group of a pipeline, in the order the groups were given, followed by
+/// the groups of each library. Procedural and triangle hit groups have handles of the same size,
+/// so both can be placed in the hit region of a table.
 pub struct ShaderBindingTableData {
     /// Raw binding table data from the API. Size is `entry_count * entry_size`.
     pub raw: Vec<u8>,
@@ -50,8 +65,55 @@ pub struct ShaderBindingTableData {
     pub entry_size: u64,
     /// The size required by the API for entries in a SBT.
     pub aligned_size: u64,
+    /// The alignment required for every record in a SBT.
+    pub handle_alignment: u64,
     /// The alignment required for the beginning of SBT tables.
     pub base_alignment: u64,
+    /// The largest stride allowed between records.
+    pub max_stride: u64,
+}
+
+/// Lays out a shader binding table.
+///
+/// Records are appended to the raygen, miss, and hit regions, each referencing a shader group by
+/// its index in [`ShaderBindingTableData`] and optionally carrying inline data that shaders read
+/// with `shaderRecordEXT`. Every record in a region shares the stride of its largest record, so
+/// instances can select their own hit record (e.g. to embed a material index) through their SBT
+/// offset.
+pub struct SbtBuilder<'a> {
+    data: &'a ShaderBindingTableData,
+    raygen: Option<SbtRecord>,
+    miss: Vec<SbtRecord>,
+    hit: Vec<SbtRecord>,
+}
+
+struct SbtRecord {
+    group: usize,
+    inline_data: Vec<u8>,
+}
+
+/// A shader binding table built by an [`SbtBuilder`].
+pub struct ShaderBindingTable {
+    /// Contents to copy into a buffer with
+    /// [`SHADER_BINDING_TABLE`](crate::types::BufferUsage::SHADER_BINDING_TABLE) usage.
+    pub data: Vec<u8>,
+    pub raygen: ShaderBindingTableRegion,
+    pub miss: ShaderBindingTableRegion,
+    pub hit: ShaderBindingTableRegion,
+}
+
+#[derive(Debug, Error)]
+pub enum SbtBuildError {
+    #[error("no ray generation record was provided")]
+    NoRaygen,
+    #[error("shader group `{group}` is out of bounds for a pipeline with `{count}` groups")]
+    InvalidGroup { group: usize, count: usize },
+    #[error("stride of `{stride}` for the {region} region exceeds the maximum of `{max}`")]
+    StrideTooLarge {
+        region: &'static str,
+        stride: u64,
+        max: u64,
+    },
 }
 
 pub struct RayTracingPipeline<B: Backend>(pub(crate) Arc<RayTracingPipelineInner<B>>);
@@ -64,6 +126,8 @@ pub(crate) struct RayTracingPipelineInner<B: Backend> {
 
 #[derive(Debug, Error)]
 pub enum RayTracingPipelineCreateError {
+    #[error("{0}")]
+    Limits(#[from] PipelineLimitError),
     #[error("an error occured: {0}")]
     Other(String),
 }
@@ -73,6 +137,12 @@ impl<B: Backend> RayTracingPipeline<B> {
         ctx: Context<B>,
         create_info: RayTracingPipelineCreateInfo<B>,
     ) -> Result<Self, RayTracingPipelineCreateError> {
+        check_pipeline_limits(
+            &ctx.properties().limits,
+            create_info.push_constants_size,
+            &create_info.layouts,
+        )?;
+
         let layouts = create_info.layouts.clone();
         let id = unsafe { ctx.0.create_ray_tracing_pipeline(create_info)? };
         Ok(Self(Arc::new(RayTracingPipelineInner { ctx, id, layouts })))
@@ -94,6 +164,127 @@ impl<B: Backend> RayTracingPipeline<B> {
     }
 }
 
+impl<'a> SbtBuilder<'a> {
+    pub fn new(data: &'a ShaderBindingTableData) -> Self {
+        Self {
+            data,
+            raygen: None,
+            miss: Vec::default(),
+            hit: Vec::default(),
+        }
+    }
+
+    /// Sets the ray generation record, replacing the previous one.
+    pub fn raygen(&mut self, group: usize, inline_data: &[u8]) -> &mut Self {
+        self.raygen = Some(SbtRecord::new(group, inline_data));
+        self
+    }
+
+    /// Appends a miss record. The miss index passed to `traceRayEXT` selects the record.
+    pub fn miss(&mut self, group: usize, inline_data: &[u8]) -> &mut Self {
+        self.miss.push(SbtRecord::new(group, inline_data));
+        self
+    }
+
+    /// Appends a hit record. Records are indexed in the order they were appended, which is what
+    /// instance SBT offsets and the offset passed to `traceRayEXT` refer to.
+    pub fn hit(&mut self, group: usize, inline_data: &[u8]) -> &mut Self {
+        self.hit.push(SbtRecord::new(group, inline_data));
+        self
+    }
+
+    /// Lays out every record, padding records to the handle alignment and regions to the base
+    /// alignment.
+    pub fn build(&self) -> Result<ShaderBindingTable, SbtBuildError> {
+        let raygen = match &self.raygen {
+            Some(raygen) => std::slice::from_ref(raygen),
+            None => return Err(SbtBuildError::NoRaygen),
+        };
+
+        let mut offset = 0;
+        let raygen_region = self.region_layout("raygen", raygen, &mut offset)?;
+        let miss_region = self.region_layout("miss", &self.miss, &mut offset)?;
+        let hit_region = self.region_layout("hit", &self.hit, &mut offset)?;
+
+        let mut data = vec![0; offset as usize];
+        for (records, region) in [
+            (raygen, &raygen_region),
+            (self.miss.as_slice(), &miss_region),
+            (self.hit.as_slice(), &hit_region),
+        ] {
+            let handle_size = self.data.entry_size as usize;
+            for (i, record) in records.iter().enumerate() {
+                let dst = (region.offset + i as u64 * region.stride) as usize;
+                let src = record.group * handle_size;
+                data[dst..(dst + handle_size)]
+                    .copy_from_slice(&self.data.raw[src..(src + handle_size)]);
+
+                let dst = dst + handle_size;
+                data[dst..(dst + record.inline_data.len())].copy_from_slice(&record.inline_data);
+            }
+        }
+
+        Ok(ShaderBindingTable {
+            data,
+            raygen: raygen_region,
+            miss: miss_region,
+            hit: hit_region,
+        })
+    }
+
+    /// Computes the layout of a region beginning at `offset` and moves `offset` past it.
+    fn region_layout(
+        &self,
+        name: &'static str,
+        records: &[SbtRecord],
+        offset: &mut u64,
+    ) -> Result<ShaderBindingTableRegion, SbtBuildError> {
+        if records.is_empty() {
+            return Ok(ShaderBindingTableRegion::default());
+        }
+
+        let mut stride = self.data.aligned_size;
+        for record in records {
+            if record.group >= self.data.entry_count {
+                return Err(SbtBuildError::InvalidGroup {
+                    group: record.group,
+                    count: self.data.entry_count,
+                });
+            }
+
+            let size = (self.data.entry_size + record.inline_data.len() as u64)
+                .next_multiple_of(self.data.handle_alignment);
+            stride = stride.max(size);
+        }
+
+        if stride > self.data.max_stride {
+            return Err(SbtBuildError::StrideTooLarge {
+                region: name,
+                stride,
+                max: self.data.max_stride,
+            });
+        }
+
+        let region = ShaderBindingTableRegion {
+            offset: offset.next_multiple_of(self.data.base_alignment),
+            stride,
+            size: stride * records.len() as u64,
+        };
+        *offset = region.offset + region.size;
+
+        Ok(region)
+    }
+}
+
+impl SbtRecord {
+    fn new(group: usize, inline_data: &[u8]) -> Self {
+        Self {
+            group,
+            inline_data: Vec::from(inline_data),
+        }
+    }
+}
+
 impl<B: Backend> Clone for RayTracingPipeline<B> {
     #[inline(always)]
     fn clone(&self) -> Self {
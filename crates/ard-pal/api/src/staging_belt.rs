@@ -0,0 +1,494 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use crate::{
+    buffer::{Buffer, BufferCreateError, BufferCreateInfo},
+    command_buffer::BufferTextureCopy,
+    context::{Context, DeviceLimits},
+    queue::Job,
+    texture::{lcm, Texture},
+    types::*,
+    Backend,
+};
+
+/// Number of chunks of completed flushes kept for reuse. Any others are dropped and freed by the
+/// garbage collector.
+const MAX_FREE_CHUNKS: usize = 4;
+
+/// The job of a flush. Set once the flush is submitted.
+type BatchJob<B> = Arc<OnceLock<Job<B>>>;
+
+pub struct StagingBeltCreateInfo {
+    /// Size in bytes of each staging buffer chunk. Uploads that don't fit in a chunk are split
+    /// across several.
+    pub chunk_size: u64,
+    /// The backend *should* use the provided debug name for easy identification.
+    pub debug_name: Option<String>,
+}
+
+/// A mip level of a texture to upload through a [`StagingBelt`].
+pub struct StagingTextureUpload<'a, K> {
+    /// Identifies the texture. Resolved into the texture itself when the belt is flushed.
+    pub texture: K,
+    /// Format of the texture.
+    pub format: Format,
+    /// Width, height, and depth of the mip level.
+    pub extent: (u32, u32, u32),
+    /// The mip level to upload to.
+    pub mip_level: usize,
+    /// The array element to upload to.
+    pub array_element: usize,
+    /// Tightly packed texel blocks of the whole mip level.
+    pub data: &'a [u8],
+    /// Usage of the mip level once the upload is complete.
+    pub usage: TextureUsage,
+    /// Queue to hand ownership of the mip level to once the upload is complete, or `None` if it
+    /// should stay with the transfer queue.
+    pub owner: Option<QueueType>,
+}
+
+/// Batches texture uploads into shared staging buffers and submits them to the transfer queue
+/// together.
+///
+/// Uploads can be enqueued from any thread with [`upload_texture`](StagingBelt::upload_texture).
+/// Their data is copied into the current chunk of staging memory right away, with rows laid out
+/// for the fastest copies the device supports. Nothing is submitted until
+/// [`flush`](StagingBelt::flush) is called, usually once per frame, which records every pending
+/// copy into a single transfer submission.
+///
+/// Chunks are reused once the submission that read from them is complete.
+pub struct StagingBelt<B: Backend, K = ()> {
+    ctx: Context<B>,
+    chunk_size: u64,
+    debug_name: Option<String>,
+    state: Mutex<BeltState<B, K>>,
+}
+
+/// Tracks the completion of an upload enqueued to a [`StagingBelt`].
+pub struct StagingHandle<B: Backend> {
+    job: BatchJob<B>,
+}
+
+struct BeltState<B: Backend, K> {
+    /// Chunks written since the last flush. Only the last one can have free space.
+    chunks: Vec<Buffer<B>>,
+    /// Bytes of the last chunk in use.
+    head: u64,
+    uploads: Vec<PendingUpload<K>>,
+    /// Job of the next flush, shared with the handles of its uploads.
+    batch: BatchJob<B>,
+    /// Chunks of flushed batches, oldest first, kept until their job completes.
+    in_flight: VecDeque<(BatchJob<B>, Vec<Buffer<B>>)>,
+    /// Chunks of completed batches that can be written to again.
+    free: Vec<Buffer<B>>,
+}
+
+struct PendingUpload<K> {
+    texture: K,
+    mip_level: usize,
+    array_element: usize,
+    usage: TextureUsage,
+    owner: Option<QueueType>,
+    /// Copies out of the chunks of the batch, with the index of the chunk they read from.
+    copies: Vec<(usize, BufferTextureCopy)>,
+}
+
+/// How the texel blocks of a mip level are laid out in staging memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct StagingLayout {
+    /// Width and height in texels of a texel block.
+    block_dim: u32,
+    /// Size in bytes of a texel block.
+    block_size: u64,
+    /// Bytes of a tightly packed row of blocks.
+    row_bytes: u64,
+    /// Bytes between the start of consecutive rows of blocks in staging memory.
+    row_pitch: u64,
+    /// Rows of blocks in each depth slice.
+    rows: u32,
+    /// Copies start at a multiple of this.
+    offset_alignment: u64,
+}
+
+impl<B: Backend, K> StagingBelt<B, K> {
+    /// Creates a new staging belt. Chunks are allocated as uploads need them.
+    ///
+    /// # Panics
+    /// - If `create_info.chunk_size` is `0`.
+    pub fn new(ctx: Context<B>, create_info: StagingBeltCreateInfo) -> Self {
+        assert_ne!(create_info.chunk_size, 0, "chunk size cannot be zero");
+
+        Self {
+            ctx,
+            chunk_size: create_info.chunk_size,
+            debug_name: create_info.debug_name,
+            state: Mutex::new(BeltState {
+                chunks: Vec::default(),
+                head: 0,
+                uploads: Vec::default(),
+                batch: Arc::default(),
+                in_flight: VecDeque::default(),
+                free: Vec::default(),
+            }),
+        }
+    }
+
+    /// Size in bytes of each chunk.
+    #[inline(always)]
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    /// Copies the data of a mip level into staging memory and enqueues the upload for the next
+    /// [`flush`](StagingBelt::flush).
+    ///
+    /// Mip levels that don't fit in the rest of the current chunk are split into several copies
+    /// along rows of texel blocks. A row too large for a chunk gets a chunk of its own.
+    ///
+    /// # Panics
+    /// - If `upload.data` is not the size of the tightly packed mip level.
+    pub fn upload_texture(
+        &self,
+        upload: StagingTextureUpload<K>,
+    ) -> Result<StagingHandle<B>, BufferCreateError> {
+        let layout =
+            StagingLayout::new(upload.format, upload.extent, &self.ctx.properties().limits);
+        let (width, height, depth) = upload.extent;
+        let slice_bytes = layout.row_bytes * layout.rows as u64;
+        assert_eq!(
+            upload.data.len() as u64,
+            slice_bytes * depth as u64,
+            "data is not the size of a `{width}x{height}x{depth}` `{:?}` mip level",
+            upload.format
+        );
+
+        let mut state = self.state.lock().unwrap();
+        let mut copies = Vec::default();
+
+        for z in 0..depth {
+            let slice = &upload.data[(z as u64 * slice_bytes) as usize..];
+            let mut row = 0;
+            while row < layout.rows {
+                let (chunk, offset, rows) = state.reserve(self, &layout, layout.rows - row)?;
+
+                let mut view = state.chunks[chunk].write(0).unwrap();
+                let src = &slice[(row as u64 * layout.row_bytes) as usize..];
+                if layout.row_pitch == layout.row_bytes {
+                    let len = (rows as u64 * layout.row_bytes) as usize;
+                    view[offset as usize..offset as usize + len].copy_from_slice(&src[..len]);
+                } else {
+                    let row_bytes = layout.row_bytes as usize;
+                    for (i, src_row) in src.chunks_exact(row_bytes).take(rows as usize).enumerate()
+                    {
+                        let dst = (offset + i as u64 * layout.row_pitch) as usize;
+                        view[dst..dst + row_bytes].copy_from_slice(src_row);
+                    }
+                }
+                std::mem::drop(view);
+
+                let y = row * layout.block_dim;
+                copies.push((
+                    chunk,
+                    BufferTextureCopy {
+                        buffer_offset: offset,
+                        buffer_row_length: (layout.row_pitch / layout.block_size) as u32
+                            * layout.block_dim,
+                        buffer_image_height: rows * layout.block_dim,
+                        buffer_array_element: 0,
+                        texture_offset: (0, y, z),
+                        texture_extent: (
+                            width,
+                            ((row + rows) * layout.block_dim).min(height) - y,
+                            1,
+                        ),
+                        texture_mip_level: upload.mip_level,
+                        texture_array_element: upload.array_element,
+                    },
+                ));
+                row += rows;
+            }
+        }
+
+        state.uploads.push(PendingUpload {
+            texture: upload.texture,
+            mip_level: upload.mip_level,
+            array_element: upload.array_element,
+            usage: upload.usage,
+            owner: upload.owner,
+            copies,
+        });
+
+        Ok(StagingHandle {
+            job: state.batch.clone(),
+        })
+    }
+
+    /// Submits every pending upload to the transfer queue in a single job. Does nothing if there
+    /// are no pending uploads.
+    ///
+    /// # Arguments
+    /// - `resolve` - Gets the texture each upload was enqueued for. Uploads resolved to `None`
+    ///   are skipped, which is useful for textures that were destroyed in the meantime. Their
+    ///   handles still complete with the rest of the job.
+    pub fn flush<'a>(&self, mut resolve: impl FnMut(&K) -> Option<&'a Texture<B>>) {
+        let (uploads, chunks, batch) = {
+            let mut state = self.state.lock().unwrap();
+            state.reclaim(self.chunk_size);
+            if state.uploads.is_empty() {
+                return;
+            }
+
+            state.head = 0;
+            (
+                std::mem::take(&mut state.uploads),
+                std::mem::take(&mut state.chunks),
+                std::mem::take(&mut state.batch),
+            )
+        };
+
+        let queue = self.ctx.transfer();
+        let mut commands = queue.command_buffer();
+        for upload in &uploads {
+            let texture = match resolve(&upload.texture) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            for (chunk, copy) in &upload.copies {
+                commands.copy_buffer_to_texture(texture, &chunks[*chunk], *copy);
+            }
+
+            let array_elements = upload.array_element..(upload.array_element + 1);
+            let mips = upload.mip_level..(upload.mip_level + 1);
+            commands.set_texture_usage(texture, upload.usage, array_elements, mips);
+
+            if let Some(owner) = upload.owner {
+                commands.transfer_texture_ownership(
+                    texture,
+                    upload.array_element,
+                    upload.mip_level,
+                    1,
+                    owner,
+                    Some(upload.usage),
+                );
+            }
+        }
+
+        let job = queue.submit_async(
+            Some(self.debug_name.as_deref().unwrap_or("staging_belt")),
+            commands,
+        );
+        let _ = batch.set(job);
+
+        self.state
+            .lock()
+            .unwrap()
+            .in_flight
+            .push_back((batch, chunks));
+    }
+}
+
+impl<B: Backend> StagingHandle<B> {
+    /// `true` once the upload has been submitted by [`StagingBelt::flush`].
+    #[inline(always)]
+    pub fn is_flushed(&self) -> bool {
+        self.job.get().is_some()
+    }
+
+    /// Polls the status of the upload without blocking. Uploads that haven't been flushed yet
+    /// are [`Running`](JobStatus::Running).
+    #[inline(always)]
+    pub fn poll_status(&self) -> JobStatus {
+        match self.job.get() {
+            Some(job) => job.poll_status(),
+            None => JobStatus::Running,
+        }
+    }
+
+    /// Blocks until the upload is complete. See [`Job::wait_on`].
+    ///
+    /// Returns [`Running`](JobStatus::Running) immediately if the upload hasn't been flushed
+    /// yet, since there is nothing to wait on.
+    #[inline(always)]
+    pub fn wait_on(&self, timeout: Option<Duration>) -> JobStatus {
+        match self.job.get() {
+            Some(job) => job.wait_on(timeout),
+            None => JobStatus::Running,
+        }
+    }
+}
+
+impl<B: Backend> Clone for StagingHandle<B> {
+    fn clone(&self) -> Self {
+        Self {
+            job: self.job.clone(),
+        }
+    }
+}
+
+impl<B: Backend, K> BeltState<B, K> {
+    /// Reserves space for up to `rows` rows of blocks, returning the chunk, the offset within it
+    /// and how many rows fit.
+    fn reserve(
+        &mut self,
+        belt: &StagingBelt<B, K>,
+        layout: &StagingLayout,
+        rows: u32,
+    ) -> Result<(usize, u64, u32), BufferCreateError> {
+        if let Some(chunk) = self.chunks.last() {
+            let offset = self.head.next_multiple_of(layout.offset_alignment);
+            let fit = layout
+                .rows_that_fit(chunk.size().saturating_sub(offset))
+                .min(rows);
+            if fit != 0 {
+                self.head = offset + layout.size_of(fit);
+                return Ok((self.chunks.len() - 1, offset, fit));
+            }
+        }
+
+        // A single row always fits in a fresh chunk
+        self.reclaim(belt.chunk_size);
+        let chunk = match self.free.pop() {
+            Some(chunk) if layout.row_bytes <= chunk.size() => chunk,
+            chunk => {
+                self.free.extend(chunk);
+                Buffer::new(
+                    belt.ctx.clone(),
+                    BufferCreateInfo {
+                        size: belt.chunk_size.max(layout.row_bytes),
+                        array_elements: 1,
+                        buffer_usage: BufferUsage::TRANSFER_SRC,
+                        memory_usage: MemoryUsage::CpuToGpu,
+                        queue_types: QueueTypes::TRANSFER,
+                        sharing_mode: SharingMode::Exclusive,
+                        debug_name: belt.debug_name.clone(),
+                        memory_fallback: MemoryFallback::None,
+                    },
+                )?
+            }
+        };
+
+        let fit = layout.rows_that_fit(chunk.size()).min(rows);
+        self.chunks.push(chunk);
+        self.head = layout.size_of(fit);
+        Ok((self.chunks.len() - 1, 0, fit))
+    }
+
+    /// Moves chunks of completed flushes to the free list. Oversized chunks, and any beyond what
+    /// is kept for reuse, are dropped instead.
+    fn reclaim(&mut self, chunk_size: u64) {
+        while let Some((job, _)) = self.in_flight.front() {
+            if job.get().unwrap().poll_status() == JobStatus::Running {
+                break;
+            }
+
+            let (_, chunks) = self.in_flight.pop_front().unwrap();
+            for chunk in chunks {
+                if chunk.size() == chunk_size && self.free.len() < MAX_FREE_CHUNKS {
+                    self.free.push(chunk);
+                }
+            }
+        }
+    }
+}
+
+impl StagingLayout {
+    fn new(format: Format, extent: (u32, u32, u32), limits: &DeviceLimits) -> Self {
+        let (block_size, block_dim) = format.block_info();
+        let block_size = block_size as u64;
+        let row_bytes = extent.0.div_ceil(block_dim) as u64 * block_size;
+
+        // Pitches and offsets must also be whole blocks, and offsets a multiple of four bytes
+        let pitch_alignment = lcm(
+            limits.optimal_buffer_copy_row_pitch_alignment.max(1),
+            block_size,
+        );
+        let offset_alignment = lcm(
+            lcm(
+                limits.optimal_buffer_copy_offset_alignment.max(1),
+                block_size,
+            ),
+            4,
+        );
+
+        Self {
+            block_dim,
+            block_size,
+            row_bytes,
+            row_pitch: row_bytes.next_multiple_of(pitch_alignment),
+            rows: extent.1.div_ceil(block_dim),
+            offset_alignment,
+        }
+    }
+
+    /// Bytes of staging memory taken by `rows` rows of blocks. The last row isn't padded.
+    #[inline(always)]
+    fn size_of(&self, rows: u32) -> u64 {
+        match rows {
+            0 => 0,
+            rows => (rows as u64 - 1) * self.row_pitch + self.row_bytes,
+        }
+    }
+
+    /// Number of rows of blocks of a slice that fit in `space` bytes.
+    #[inline(always)]
+    fn rows_that_fit(&self, space: u64) -> u32 {
+        if space < self.row_bytes {
+            return 0;
+        }
+        let rows = (space - self.row_bytes) / self.row_pitch + 1;
+        rows.min(self.rows as u64) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(offset_alignment: u64, row_pitch_alignment: u64) -> DeviceLimits {
+        DeviceLimits {
+            optimal_buffer_copy_offset_alignment: offset_alignment,
+            optimal_buffer_copy_row_pitch_alignment: row_pitch_alignment,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rows_are_padded_to_the_pitch_alignment() {
+        let layout = StagingLayout::new(Format::Rgba8Unorm, (10, 4, 1), &limits(512, 256));
+        assert_eq!(layout.row_bytes, 40);
+        assert_eq!(layout.row_pitch, 256);
+        assert_eq!(layout.rows, 4);
+        assert_eq!(layout.offset_alignment, 512);
+        assert_eq!(layout.size_of(4), 3 * 256 + 40);
+    }
+
+    #[test]
+    fn pitches_and_offsets_are_whole_blocks() {
+        // Three channel float texels are twelve bytes
+        let layout = StagingLayout::new(Format::Rgb32SFloat, (3, 1, 1), &limits(1, 8));
+        assert_eq!(layout.row_bytes, 36);
+        assert_eq!(layout.row_pitch, 48);
+        assert_eq!(layout.offset_alignment, 12);
+
+        // Compressed rows are rows of blocks
+        let layout = StagingLayout::new(Format::BC7Unorm, (10, 10, 1), &limits(1, 1));
+        assert_eq!(layout.block_dim, 4);
+        assert_eq!(layout.row_bytes, 3 * 16);
+        assert_eq!(layout.rows, 3);
+        assert_eq!(layout.offset_alignment, 16);
+    }
+
+    #[test]
+    fn rows_that_fit_ignore_padding_of_the_last_row() {
+        let layout = StagingLayout::new(Format::Rgba8Unorm, (10, 4, 1), &limits(1, 256));
+        assert_eq!(layout.rows_that_fit(39), 0);
+        assert_eq!(layout.rows_that_fit(40), 1);
+        assert_eq!(layout.rows_that_fit(256 + 39), 1);
+        assert_eq!(layout.rows_that_fit(256 + 40), 2);
+        assert_eq!(layout.rows_that_fit(1 << 20), 4);
+    }
+}
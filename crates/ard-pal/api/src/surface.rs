@@ -5,7 +5,7 @@ use thiserror::Error;
 
 use crate::{
     context::Context,
-    types::{Format, PresentMode},
+    types::{ColorSpace, Format, PresentMode},
     Backend,
 };
 
@@ -33,8 +33,24 @@ pub struct SurfaceConfiguration {
     pub height: u32,
     /// Preferred presentation mode of the surface.
     pub present_mode: PresentMode,
-    /// Preferred texture format of the surface.
+    /// Texture formats and color spaces the surface may use, in order of preference. The first
+    /// pairing supported by the surface is chosen. If none are supported, the backend falls back
+    /// to an SDR format if it can.
+    pub preferred_formats: Vec<(Format, ColorSpace)>,
+}
+
+/// The properties of a surface after a configuration is applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SurfaceProperties {
+    /// Width and height in pixels of the surface. This might not match the requested dimensions.
+    pub dimensions: (u32, u32),
+    /// Texture format of the surface images.
     pub format: Format,
+    /// Color space the surface images are presented in.
+    pub color_space: ColorSpace,
+    /// Presentation mode the surface uses. This is `PresentMode::Fifo` if the requested mode isn't
+    /// supported.
+    pub present_mode: PresentMode,
 }
 
 pub struct SurfaceCapabilities {
@@ -48,12 +64,13 @@ pub struct SurfaceCapabilities {
 
 pub struct Surface<B: Backend> {
     ctx: Context<B>,
-    dims: (u32, u32),
+    properties: SurfaceProperties,
     pub(crate) id: B::Surface,
 }
 
 pub struct SurfaceImage<B: Backend> {
     ctx: Context<B>,
+    dimensions: (u32, u32),
     pub(crate) id: B::SurfaceImage,
 }
 
@@ -61,6 +78,8 @@ pub struct SurfaceImage<B: Backend> {
 pub enum SurfaceCreateError {
     #[error("bad surface configuration: `{0}`")]
     BadConfig(SurfaceUpdateError),
+    #[error("surfaces can't be created by a headless backend")]
+    Headless,
     #[error("a error has occured: `{0}`")]
     Other(String),
 }
@@ -69,6 +88,8 @@ pub enum SurfaceCreateError {
 pub enum SurfaceUpdateError {
     #[error("at least one image is still pending presentation")]
     ImagePending,
+    #[error("none of the preferred formats are supported by the surface")]
+    UnsupportedFormat,
     #[error("a error has occured: `{0}`")]
     Other(String),
 }
@@ -77,6 +98,8 @@ pub enum SurfaceUpdateError {
 pub enum SurfaceImageAcquireError {
     #[error("no available images")]
     NoImages,
+    #[error("the surface is invalidated and needs a new config")]
+    Invalidated,
     #[error("a error has occured: `{0}`")]
     Other(String),
 }
@@ -103,9 +126,12 @@ impl<B: Backend> Surface<B> {
         ctx: Context<B>,
         create_info: SurfaceCreateInfo<W>,
     ) -> Result<Self, SurfaceCreateError> {
-        let dims = (create_info.config.width, create_info.config.height);
-        let id = unsafe { ctx.0.create_surface(create_info)? };
-        Ok(Self { ctx, id, dims })
+        let (id, properties) = unsafe { ctx.0.create_surface(create_info)? };
+        Ok(Self {
+            ctx,
+            properties,
+            id,
+        })
     }
 
     #[inline(always)]
@@ -115,7 +141,19 @@ impl<B: Backend> Surface<B> {
 
     #[inline(always)]
     pub fn dimensions(&self) -> (u32, u32) {
-        self.dims
+        self.properties.dimensions
+    }
+
+    /// Texture format of the surface images.
+    #[inline(always)]
+    pub fn format(&self) -> Format {
+        self.properties.format
+    }
+
+    /// Color space the surface images are presented in.
+    #[inline(always)]
+    pub fn color_space(&self) -> ColorSpace {
+        self.properties.color_space
     }
 
     /// Gets most up to date surface capabilities.
@@ -127,23 +165,30 @@ impl<B: Backend> Surface<B> {
     /// Update the configuration of the surface.
     ///
     /// There must not be any images pending presentation before the configuration is updated.
+    /// Returns the properties the surface ended up with, which includes the chosen format and
+    /// presentation mode.
     #[inline(always)]
     pub fn update_config(
         &mut self,
         config: SurfaceConfiguration,
-    ) -> Result<(), SurfaceUpdateError> {
+    ) -> Result<SurfaceProperties, SurfaceUpdateError> {
         unsafe {
-            self.dims = self.ctx.0.update_surface(&mut self.id, config)?;
+            self.properties = self.ctx.0.update_surface(&mut self.id, config)?;
         };
-        Ok(())
+        Ok(self.properties)
     }
 
     /// Acquire a new image from the surface to present.
+    ///
+    /// Surfaces are independent of each other, so images from multiple surfaces can be acquired
+    /// and presented in any order. If [`SurfaceImageAcquireError::Invalidated`] is returned, the
+    /// surface must be given a new configuration before another image can be acquired.
     #[inline(always)]
     pub fn acquire_image(&mut self) -> Result<SurfaceImage<B>, SurfaceImageAcquireError> {
         let id = unsafe { self.ctx.0.acquire_image(&mut self.id)? };
         Ok(SurfaceImage {
             ctx: self.ctx.clone(),
+            dimensions: self.properties.dimensions,
             id,
         })
     }
@@ -154,6 +199,12 @@ impl<B: Backend> SurfaceImage<B> {
     pub fn internal(&self) -> &B::SurfaceImage {
         &self.id
     }
+
+    /// Dimensions of the surface when the image was acquired.
+    #[inline(always)]
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
 }
 
 impl<B: Backend> Drop for Surface<B> {
@@ -1,15 +1,22 @@
+use std::time::Duration;
+
 use crate::{
+    buffer::upload_owner,
+    buffer::{Buffer, BufferCreateError, BufferCreateInfo},
+    command_buffer::BufferTextureCopy,
     context::Context,
+    queue::{Job, Queue},
     types::{
-        AnisotropyLevel, BorderColor, CompareOp, Filter, Format, MemoryUsage, MultiSamples,
-        QueueTypes, SamplerAddressMode, SharingMode, TextureType, TextureUsage,
+        AnisotropyLevel, BorderColor, BufferUsage, CompareOp, Filter, Format, JobStatus,
+        MemoryFallback, MemoryUsage, MultiSamples, QueueType, QueueTypes, ReductionMode,
+        SamplerAddressMode, SharingMode, TextureType, TextureUsage,
     },
     Backend,
 };
 use ordered_float::NotNan;
 use thiserror::Error;
 
-pub struct TextureCreateInfo {
+pub struct TextureCreateInfo<'a> {
     pub format: Format,
     pub ty: TextureType,
     pub width: u32,
@@ -23,6 +30,22 @@ pub struct TextureCreateInfo {
     pub queue_types: QueueTypes,
     pub sharing_mode: SharingMode,
     pub debug_name: Option<String>,
+    /// Creates the texture without any backing memory. Memory is bound in pages using
+    /// [`bind_sparse_pages`](crate::command_buffer::CommandBuffer::bind_sparse_pages). Requires
+    /// [`sparse_residency`](crate::context::GraphicsFeatures::sparse_residency).
+    pub sparse: bool,
+    /// Data to upload to the texture after creation, one slice per mip starting at the base mip.
+    /// Each slice contains every array element of the mip back to back, each made of tightly
+    /// packed texel blocks (see [`Format::block_info`]). Trailing mips may be omitted.
+    ///
+    /// The upload is performed on the transfer queue, so the texture is given
+    /// [`TRANSFER_DST`](TextureUsage::TRANSFER_DST) usage and is made available to the transfer
+    /// queue. [`Exclusive`](SharingMode::Exclusive) textures are handed to the main queue, or the
+    /// compute queue if the texture isn't used on the main queue.
+    pub initial_data: Option<&'a [&'a [u8]]>,
+    /// What to do if the texture doesn't fit in device local memory. Sparse textures allocate
+    /// their pages when they're bound, so this is ignored for them.
+    pub memory_fallback: MemoryFallback,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -35,6 +58,10 @@ pub struct Sampler {
     pub address_w: SamplerAddressMode,
     pub anisotropy: Option<AnisotropyLevel>,
     pub compare: Option<CompareOp>,
+    /// Combines the texels of the filter footprint using their component-wise minimum or
+    /// maximum instead of a weighted average. Requires
+    /// [`GraphicsFeatures::sampler_reduction`](crate::context::GraphicsFeatures).
+    pub reduction_mode: Option<ReductionMode>,
     pub min_lod: NotNan<f32>,
     pub max_lod: Option<NotNan<f32>>,
     pub border_color: Option<BorderColor>,
@@ -53,43 +80,308 @@ pub struct Blit {
     pub dst_array_element: usize,
 }
 
+impl Blit {
+    /// Blits that generate each mip of an array element from the mip before it, in the order
+    /// they must be performed. Each mip is half the size of the previous mip rounded down, with
+    /// a minimum size of 1.
+    ///
+    /// # Arguments
+    /// - `dims` - The width, height, and depth of the base mip.
+    /// - `mip_count` - The number of mips to generate, including the base mip.
+    /// - `array_element` - The array element to blit within.
+    pub fn mip_chain(
+        dims: (u32, u32, u32),
+        mip_count: usize,
+        array_element: usize,
+    ) -> impl Iterator<Item = Blit> {
+        (1..mip_count).map(move |mip| Blit {
+            src_min: (0, 0, 0),
+            src_max: mip_dims(dims, mip - 1),
+            src_mip: mip - 1,
+            src_array_element: array_element,
+            dst_min: (0, 0, 0),
+            dst_max: mip_dims(dims, mip),
+            dst_mip: mip,
+            dst_array_element: array_element,
+        })
+    }
+}
+
+/// A region of a sparse texture to bind or unbind memory for. Regions are expanded to cover
+/// every page they touch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SparsePageRegion {
+    pub array_element: usize,
+    pub mip_level: usize,
+    /// Offset in texels of the region within the mip level.
+    pub offset: (u32, u32, u32),
+    /// Size in texels of the region.
+    pub extent: (u32, u32, u32),
+}
+
 #[derive(Debug, Error)]
 pub enum TextureCreateError {
+    #[error("sparse textures are not supported by this device")]
+    SparseNotSupported,
+    #[error("sample count `{0:?}` is not supported, the maximum is `{1:?}`")]
+    UnsupportedSampleCount(MultiSamples, MultiSamples),
+    #[error("format `{0:?}` does not support usage `{1:?}`")]
+    UnsupportedFormat(Format, TextureUsage),
+    #[error("`{ty:?}` textures cannot be `{width}x{height}x{depth}` with `{array_elements}` array elements")]
+    InvalidDimensions {
+        ty: TextureType,
+        width: u32,
+        height: u32,
+        depth: u32,
+        array_elements: usize,
+    },
+    #[error(
+        "`{ty:?}` texture of `{width}x{height}x{depth}` with `{array_elements}` array elements \
+        exceeds the device limits of `{max_dimension}` texels per dimension with \
+        `{max_array_elements}` array elements"
+    )]
+    ExceedsLimits {
+        ty: TextureType,
+        width: u32,
+        height: u32,
+        depth: u32,
+        array_elements: usize,
+        max_dimension: u32,
+        max_array_elements: usize,
+    },
+    #[error("`{requested}` mip levels were requested, but the texture only has room for `{max}`")]
+    TooManyMips { requested: usize, max: usize },
+    #[error("{0}")]
+    InitialData(#[from] InitialDataError),
+    #[error("unable to create staging buffer: {0}")]
+    Staging(#[from] BufferCreateError),
+    #[error("out of memory allocating `{requested}` bytes from heap `{heap}`")]
+    OutOfMemory { requested: u64, heap: usize },
     #[error("an error has occured: {0}")]
     Other(String),
 }
 
+#[derive(Debug, Error)]
+pub enum InitialDataError {
+    #[error("initial data cannot be uploaded to sparse or multisampled textures")]
+    Unsupported,
+    #[error("initial data has `{found}` mips, but there are only `{max}`")]
+    TooManyMips { found: usize, max: usize },
+    #[error("initial data for mip `{mip}` is `{found}` bytes, but `{expected}` were expected")]
+    SizeMismatch {
+        mip: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
 pub struct Texture<B: Backend> {
     ctx: Context<B>,
+    ty: TextureType,
     dims: (u32, u32, u32),
     format: Format,
     mip_count: usize,
+    array_elements: usize,
+    texture_usage: TextureUsage,
     queue_types: QueueTypes,
     sharing_mode: SharingMode,
+    sparse: bool,
+    sample_count: MultiSamples,
     pub(crate) id: B::Texture,
 }
 
+/// Rows of texel blocks copied into readback buffers are aligned to this many bytes, which keeps
+/// copies on every backend's fast path.
+const READBACK_ROW_ALIGNMENT: u64 = 256;
+
+/// An in-flight copy of a texture mip back to the CPU. See [`Texture::read_back`].
+pub struct TextureReadback<B: Backend> {
+    job: Job<B>,
+    buffer: Buffer<B>,
+    width: u32,
+    height: u32,
+    depth: u32,
+    format: Format,
+    /// Bytes between consecutive rows of blocks within `buffer`.
+    row_pitch: usize,
+    /// Bytes within a tightly packed row of blocks.
+    row_bytes: usize,
+    /// Number of rows of blocks in a single depth slice.
+    rows: usize,
+}
+
+/// The result of a completed [`TextureReadback`].
+pub struct TextureReadbackData {
+    /// Tightly packed texel blocks. Rows of blocks are stored top to bottom, followed by each
+    /// depth slice.
+    pub data: Vec<u8>,
+    /// Width of the mip in texels.
+    pub width: u32,
+    /// Height of the mip in texels.
+    pub height: u32,
+    /// Depth of the mip in texels.
+    pub depth: u32,
+    /// Format of the texels. Depth/stencil formats only contain the depth aspect.
+    pub format: Format,
+}
+
 impl<B: Backend> Texture<B> {
     pub fn new(
         ctx: Context<B>,
-        create_info: TextureCreateInfo,
+        mut create_info: TextureCreateInfo,
     ) -> Result<Self, TextureCreateError> {
+        let initial_data = create_info.initial_data.take();
+        let layer_sizes = match initial_data {
+            Some(data) => {
+                if create_info.sparse || create_info.sample_count != MultiSamples::Count1 {
+                    return Err(InitialDataError::Unsupported.into());
+                }
+                create_info.texture_usage |= TextureUsage::TRANSFER_DST;
+                create_info.queue_types |= QueueTypes::TRANSFER;
+                initial_layer_sizes(
+                    create_info.format,
+                    (create_info.width, create_info.height, create_info.depth),
+                    create_info.array_elements,
+                    create_info.mip_levels,
+                    data,
+                )?
+            }
+            None => Vec::default(),
+        };
+        let array_elements = create_info.array_elements;
+        let debug_name = create_info.debug_name.clone();
+
+        let ty = create_info.ty;
         let dims = (create_info.width, create_info.height, create_info.depth);
         let mip_count = create_info.mip_levels;
         let format = create_info.format;
         let queue_types = create_info.queue_types;
         let sharing_mode = create_info.sharing_mode;
+        let sparse = create_info.sparse;
+        let sample_count = create_info.sample_count;
+        let texture_usage = create_info.texture_usage;
+
+        let properties = ctx.properties();
+        if !properties
+            .format_support(format)
+            .contains(create_info.texture_usage.into())
+        {
+            return Err(TextureCreateError::UnsupportedFormat(
+                format,
+                create_info.texture_usage,
+            ));
+        }
+
+        if !properties
+            .supported_samples(format, create_info.texture_usage)
+            .contains(sample_count.into())
+        {
+            return Err(TextureCreateError::UnsupportedSampleCount(
+                sample_count,
+                properties.max_supported_samples(format, create_info.texture_usage),
+            ));
+        }
+
+        // Unused dimensions must be 1, which is checked by the backend
+        let limits = &properties.limits;
+        let max_dimension = match ty {
+            TextureType::Type1D => limits.max_texture_dimension_1d,
+            TextureType::Type2D => limits.max_texture_dimension_2d,
+            TextureType::Type3D => limits.max_texture_dimension_3d,
+        };
+        let max_array_elements = limits.max_texture_array_elements as usize;
+        if dims.0.max(dims.1).max(dims.2) > max_dimension || array_elements > max_array_elements {
+            return Err(TextureCreateError::ExceedsLimits {
+                ty,
+                width: dims.0,
+                height: dims.1,
+                depth: dims.2,
+                array_elements,
+                max_dimension,
+                max_array_elements,
+            });
+        }
+
         let id = unsafe { ctx.0.create_texture(create_info)? };
 
-        Ok(Self {
+        let texture = Self {
             ctx,
+            ty,
             dims,
             id,
             format,
             queue_types,
             sharing_mode,
+            sparse,
+            sample_count,
             mip_count,
-        })
+            array_elements,
+            texture_usage,
+        };
+
+        if let Some(data) = initial_data.filter(|data| !data.is_empty()) {
+            texture.upload_initial_data(data, &layer_sizes, array_elements, debug_name)?;
+        }
+
+        Ok(texture)
+    }
+
+    /// Records and submits the copies for `initial_data`. The staging buffer is released once
+    /// the upload is complete.
+    fn upload_initial_data(
+        &self,
+        data: &[&[u8]],
+        layer_sizes: &[usize],
+        array_elements: usize,
+        debug_name: Option<String>,
+    ) -> Result<(), BufferCreateError> {
+        let (staging, offsets) = new_initial_data_staging(
+            &self.ctx,
+            self.format,
+            data,
+            layer_sizes,
+            debug_name.map(|name| format!("{name}_staging")),
+        )?;
+
+        let queue = self.ctx.transfer();
+        let mut commands = queue.command_buffer();
+        let mut offsets = offsets.into_iter();
+        for mip in 0..data.len() {
+            for array_element in 0..array_elements {
+                commands.copy_buffer_to_texture(
+                    self,
+                    &staging,
+                    BufferTextureCopy {
+                        buffer_offset: offsets.next().unwrap(),
+                        buffer_row_length: 0,
+                        buffer_image_height: 0,
+                        buffer_array_element: 0,
+                        texture_offset: (0, 0, 0),
+                        texture_extent: mip_dims(self.dims, mip),
+                        texture_mip_level: mip,
+                        texture_array_element: array_element,
+                    },
+                );
+            }
+        }
+
+        if let (SharingMode::Exclusive, Some(owner)) =
+            (self.sharing_mode, upload_owner(self.queue_types))
+        {
+            for array_element in 0..array_elements {
+                commands.transfer_texture_ownership(
+                    self,
+                    array_element,
+                    0,
+                    self.mip_count,
+                    owner,
+                    None,
+                );
+            }
+        }
+
+        queue.submit(Some("texture_upload"), commands);
+        Ok(())
     }
 
     #[inline(always)]
@@ -107,6 +399,11 @@ impl<B: Backend> Texture<B> {
         self.sharing_mode
     }
 
+    #[inline(always)]
+    pub fn ty(&self) -> TextureType {
+        self.ty
+    }
+
     #[inline(always)]
     pub fn dims(&self) -> (u32, u32, u32) {
         self.dims
@@ -127,6 +424,223 @@ impl<B: Backend> Texture<B> {
     pub fn mip_count(&self) -> usize {
         self.mip_count
     }
+
+    #[inline(always)]
+    pub fn array_elements(&self) -> usize {
+        self.array_elements
+    }
+
+    #[inline(always)]
+    pub fn texture_usage(&self) -> TextureUsage {
+        self.texture_usage
+    }
+
+    #[inline(always)]
+    pub fn is_sparse(&self) -> bool {
+        self.sparse
+    }
+
+    #[inline(always)]
+    pub fn sample_count(&self) -> MultiSamples {
+        self.sample_count
+    }
+
+    /// Copies a mip of the texture into CPU visible memory. The texture must have been created
+    /// with [`TextureUsage::TRANSFER_SRC`].
+    ///
+    /// # Arguments
+    /// - `queue` - The queue to perform the copy on.
+    /// - `mip` - The mip level to read.
+    /// - `array_element` - The array element to read.
+    ///
+    /// # Panics
+    /// - If `queue` does not support transfer commands.
+    /// - If `mip` is out of bounds.
+    /// - If the texture is multisampled.
+    pub fn read_back(
+        &self,
+        queue: &Queue<B>,
+        mip: usize,
+        array_element: usize,
+    ) -> Result<TextureReadback<B>, BufferCreateError> {
+        assert!(mip < self.mip_count, "mip level out of bounds");
+        assert_eq!(
+            self.sample_count,
+            MultiSamples::Count1,
+            "multisampled textures cannot be read back"
+        );
+
+        let (width, height, depth) = mip_dims(self.dims, mip);
+
+        // Compressed formats are copied in whole blocks, so partial blocks on the edges of the
+        // mip are rounded up
+        let (block_size, block_dim) = self.format.block_info();
+        let row_bytes = width.div_ceil(block_dim) as u64 * block_size as u64;
+        let rows = height.div_ceil(block_dim) as u64;
+
+        // The pitch must also be a whole number of blocks
+        let alignment = lcm(READBACK_ROW_ALIGNMENT, block_size as u64);
+        let row_pitch = row_bytes.next_multiple_of(alignment);
+
+        let buffer = Buffer::new(
+            self.ctx.clone(),
+            BufferCreateInfo {
+                size: row_pitch * rows * depth as u64,
+                array_elements: 1,
+                buffer_usage: BufferUsage::TRANSFER_DST,
+                memory_usage: MemoryUsage::GpuToCpu,
+                queue_types: queue.ty().into(),
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some(String::from("texture_readback")),
+                memory_fallback: MemoryFallback::None,
+            },
+        )?;
+
+        let mut commands = queue.command_buffer();
+        commands.copy_texture_to_buffer(
+            &buffer,
+            self,
+            BufferTextureCopy {
+                buffer_offset: 0,
+                buffer_row_length: (row_pitch / block_size as u64) as u32 * block_dim,
+                buffer_image_height: 0,
+                buffer_array_element: 0,
+                texture_offset: (0, 0, 0),
+                texture_extent: (width, height, depth),
+                texture_mip_level: mip,
+                texture_array_element: array_element,
+            },
+        );
+        let job = queue.submit(Some("texture_readback"), commands);
+
+        Ok(TextureReadback {
+            job,
+            buffer,
+            width,
+            height,
+            depth,
+            format: self.format,
+            row_pitch: row_pitch as usize,
+            row_bytes: row_bytes as usize,
+            rows: rows as usize,
+        })
+    }
+}
+
+impl<B: Backend> TextureReadback<B> {
+    /// Polls the status of the copy without blocking.
+    #[inline(always)]
+    pub fn poll_status(&self) -> JobStatus {
+        self.job.poll_status()
+    }
+
+    /// Waits for the copy to complete with the given timeout. See [`Job::wait_on`].
+    #[inline(always)]
+    pub fn wait_on(&self, timeout: Option<Duration>) -> JobStatus {
+        self.job.wait_on(timeout)
+    }
+
+    /// Waits for the copy to complete and returns the tightly packed texels.
+    pub fn finish(self) -> TextureReadbackData {
+        self.job.wait_on(None);
+
+        let view = self.buffer.read(0).unwrap();
+        let mut data = Vec::with_capacity(self.row_bytes * self.rows * self.depth as usize);
+        let rows = self.rows * self.depth as usize;
+        for row in view.chunks(self.row_pitch).take(rows) {
+            data.extend_from_slice(&row[..self.row_bytes]);
+        }
+
+        TextureReadbackData {
+            data,
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            format: self.format,
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn lcm(a: u64, b: u64) -> u64 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+#[inline(always)]
+fn mip_dims(dims: (u32, u32, u32), mip: usize) -> (u32, u32, u32) {
+    (
+        (dims.0 >> mip).max(1),
+        (dims.1 >> mip).max(1),
+        (dims.2 >> mip).max(1),
+    )
+}
+
+/// Validates initial data against the mips it will be uploaded to. Returns the size in bytes of
+/// a single layer of each provided mip. `layers` is the number of layers within each mip slice.
+pub(crate) fn initial_layer_sizes(
+    format: Format,
+    dims: (u32, u32, u32),
+    layers: usize,
+    mip_levels: usize,
+    data: &[&[u8]],
+) -> Result<Vec<usize>, InitialDataError> {
+    if data.len() > mip_levels {
+        return Err(InitialDataError::TooManyMips {
+            found: data.len(),
+            max: mip_levels,
+        });
+    }
+
+    let (block_size, block_dim) = format.block_info();
+    data.iter()
+        .enumerate()
+        .map(|(mip, data)| {
+            let (width, height, depth) = mip_dims(dims, mip);
+            let layer_size = width.div_ceil(block_dim) as usize
+                * height.div_ceil(block_dim) as usize
+                * depth as usize
+                * block_size as usize;
+            if data.len() != layer_size * layers {
+                return Err(InitialDataError::SizeMismatch {
+                    mip,
+                    expected: layer_size * layers,
+                    found: data.len(),
+                });
+            }
+            Ok(layer_size)
+        })
+        .collect()
+}
+
+/// Packs initial data into a staging buffer. Every layer is placed at an offset that is valid
+/// for buffer to texture copies. Returns the staging buffer and the offset of each layer, in
+/// order of mip and then layer.
+pub(crate) fn new_initial_data_staging<B: Backend>(
+    ctx: &Context<B>,
+    format: Format,
+    data: &[&[u8]],
+    layer_sizes: &[usize],
+    debug_name: Option<String>,
+) -> Result<(Buffer<B>, Vec<u64>), BufferCreateError> {
+    // Copies must begin on a texel block and on a multiple of 4 bytes
+    let alignment = lcm(4, format.block_info().0 as u64) as usize;
+
+    let mut staged = Vec::default();
+    let mut offsets = Vec::default();
+    for (data, layer_size) in data.iter().zip(layer_sizes) {
+        for layer in data.chunks_exact(*layer_size) {
+            staged.resize(staged.len().next_multiple_of(alignment), 0);
+            offsets.push(staged.len() as u64);
+            staged.extend_from_slice(layer);
+        }
+    }
+
+    let staging = Buffer::new_staging(ctx.clone(), QueueType::Transfer, debug_name, &staged)?;
+    Ok((staging, offsets))
 }
 
 impl<B: Backend> Drop for Texture<B> {
@@ -138,7 +652,7 @@ impl<B: Backend> Drop for Texture<B> {
     }
 }
 
-impl Default for TextureCreateInfo {
+impl Default for TextureCreateInfo<'_> {
     #[inline(always)]
     fn default() -> Self {
         Self {
@@ -155,6 +669,9 @@ impl Default for TextureCreateInfo {
             queue_types: QueueTypes::all(),
             sharing_mode: SharingMode::Concurrent,
             debug_name: None,
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
         }
     }
 }
@@ -16,6 +16,8 @@ pub struct TopLevelAccelerationStructureCreateInfo {
 
 #[derive(Debug, Error)]
 pub enum TopLevelAccelerationStructureCreateError {
+    #[error("out of memory allocating `{requested}` bytes from heap `{heap}`")]
+    OutOfMemory { requested: u64, heap: usize },
     #[error("an error has occured: {0}")]
     Other(String),
 }
@@ -41,11 +43,21 @@ impl<B: Backend> TopLevelAccelerationStructure<B> {
         })
     }
 
+    /// Gets the size in bytes of the scratch buffer needed for a full build.
     #[inline(always)]
     pub fn scratch_buffer_size(&self) -> u64 {
         unsafe { self.ctx.0.tlas_scratch_size(&self.id) }
     }
 
+    /// Gets the size in bytes of the scratch buffer needed to
+    /// [`update`](crate::command_buffer::CommandBuffer::update_top_level_acceleration_structure)
+    /// the TLAS. Is `0` if the TLAS wasn't created with
+    /// [`ALLOW_UPDATE`](BuildAccelerationStructureFlags::ALLOW_UPDATE).
+    #[inline(always)]
+    pub fn update_scratch_buffer_size(&self) -> u64 {
+        unsafe { self.ctx.0.tlas_update_scratch_size(&self.id) }
+    }
+
     #[inline(always)]
     pub fn sharing_mode(&self) -> SharingMode {
         self.sharing_mode
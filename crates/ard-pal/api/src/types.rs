@@ -1,4 +1,5 @@
 use bitflags::bitflags;
+use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -67,10 +68,21 @@ pub enum Format {
     Bgra8Unorm,
     Bgra8Srgb,
 
+    // RGB10A2
+    Rgb10A2Unorm,
+
     // Compressed
     BC6HUFloat,
     BC7Srgb,
     BC7Unorm,
+    Astc4x4Unorm,
+    Astc4x4Srgb,
+    Astc8x8Unorm,
+    Astc8x8Srgb,
+    Etc2Rgb8Unorm,
+    Etc2Rgb8Srgb,
+    Etc2Rgba8Unorm,
+    Etc2Rgba8Srgb,
 
     // Depth
     D16Unorm,
@@ -148,6 +160,47 @@ pub enum CompareOp {
     Always,
 }
 
+/// What happens to a stored stencil value after the stencil and depth tests.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StencilOp {
+    /// The value is left unchanged.
+    Keep,
+    /// The value is set to zero.
+    Zero,
+    /// The value is set to the stencil reference.
+    Replace,
+    /// The value is incremented, clamping at the maximum.
+    IncrementAndClamp,
+    /// The value is decremented, clamping at zero.
+    DecrementAndClamp,
+    /// The bits of the value are inverted.
+    Invert,
+    /// The value is incremented, wrapping to zero past the maximum.
+    IncrementAndWrap,
+    /// The value is decremented, wrapping to the maximum past zero.
+    DecrementAndWrap,
+}
+
+bitflags! {
+    /// Graphics pipeline state that is set by commands within a render pass instead of when the
+    /// pipeline is created.
+    #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    #[serde(transparent)]
+    pub struct DynamicStates: u32 {
+        /// Enables depth bias. See
+        /// [`set_depth_bias`](crate::render_pass::RenderPass::set_depth_bias).
+        const DEPTH_BIAS        = 0b0001;
+        /// See [`set_line_width`](crate::render_pass::RenderPass::set_line_width).
+        const LINE_WIDTH        = 0b0010;
+        /// See
+        /// [`set_stencil_reference`](crate::render_pass::RenderPass::set_stencil_reference).
+        const STENCIL_REFERENCE = 0b0100;
+        /// See
+        /// [`set_blend_constants`](crate::render_pass::RenderPass::set_blend_constants).
+        const BLEND_CONSTANTS   = 0b1000;
+    }
+}
+
 bitflags! {
     #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
     #[serde(transparent)]
@@ -172,6 +225,32 @@ pub enum BlendFactor {
     OneMinusSrcAlpha,
     DstAlpha,
     OneMinusDstAlpha,
+    /// Uses the blend constants. See
+    /// [`set_blend_constants`](crate::render_pass::RenderPass::set_blend_constants).
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    /// Uses the second color output of the fragment shader. Requires
+    /// [`dual_source_blend`](crate::context::GraphicsFeatures::dual_source_blend).
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha,
+}
+
+impl BlendFactor {
+    /// Does the factor read from the second color output of the fragment shader.
+    #[inline(always)]
+    pub const fn is_dual_source(self) -> bool {
+        matches!(
+            self,
+            BlendFactor::Src1Color
+                | BlendFactor::OneMinusSrc1Color
+                | BlendFactor::Src1Alpha
+                | BlendFactor::OneMinusSrc1Alpha
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -193,12 +272,41 @@ pub enum CubeFace {
     Bottom,
 }
 
+impl CubeFace {
+    /// Every face in the order they're stored in a cube map and in buffers holding every face.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::East,
+        CubeFace::West,
+        CubeFace::Top,
+        CubeFace::Bottom,
+        CubeFace::North,
+        CubeFace::South,
+    ];
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum JobStatus {
     /// The job is still running.
     Running,
     /// The job is complete.
     Complete,
+    /// The device was lost before the job completed, so it never will. See
+    /// [`Context::device_lost`](crate::context::Context::device_lost).
+    DeviceLost,
+}
+
+/// Invocation counts collected by a [`statistics`](crate::command_buffer::CommandBuffer::statistics)
+/// region.
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineStatistics {
+    /// Number of task shader invocations.
+    pub task_shader_invocations: u64,
+    /// Number of mesh shader invocations.
+    pub mesh_shader_invocations: u64,
+    /// Number of primitives that reached the clipping stage.
+    pub clipping_primitives: u64,
+    /// Number of fragment shader invocations.
+    pub fragment_shader_invocations: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -288,6 +396,21 @@ pub enum PresentMode {
     FifoRelaxed,
 }
 
+/// Color space that images presented to a surface are interpreted in.
+#[derive(
+    Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub enum ColorSpace {
+    /// Standard dynamic range with sRGB primaries and the sRGB transfer function.
+    #[default]
+    SrgbNonlinear,
+    /// High dynamic range with BT.2020 primaries and the ST 2084 (PQ) transfer function.
+    Hdr10St2084,
+    /// High dynamic range with sRGB primaries and a linear transfer function, where values
+    /// outside of `[0, 1]` are allowed. Also known as scRGB.
+    ExtendedSrgbLinear,
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ShaderStage {
     AllGraphics,
@@ -302,6 +425,43 @@ pub enum ShaderStage {
     RayMiss,
     RayClosestHit,
     RayAnyHit,
+    RayIntersection,
+}
+
+impl ShaderStage {
+    /// Every stage that isn't a group of other stages.
+    pub const INDIVIDUAL: [ShaderStage; 10] = [
+        ShaderStage::Vertex,
+        ShaderStage::Fragment,
+        ShaderStage::Compute,
+        ShaderStage::Mesh,
+        ShaderStage::Task,
+        ShaderStage::RayGeneration,
+        ShaderStage::RayMiss,
+        ShaderStage::RayClosestHit,
+        ShaderStage::RayAnyHit,
+        ShaderStage::RayIntersection,
+    ];
+
+    /// Returns `true` if `stage` is this stage or part of this group of stages.
+    #[inline]
+    pub fn includes(self, stage: ShaderStage) -> bool {
+        match self {
+            ShaderStage::AllStages => true,
+            // Graphics bindings are visible to ray tracing pipelines as well
+            ShaderStage::AllGraphics => stage != ShaderStage::Compute,
+            ShaderStage::RayTracing => matches!(
+                stage,
+                ShaderStage::RayTracing
+                    | ShaderStage::RayGeneration
+                    | ShaderStage::RayMiss
+                    | ShaderStage::RayClosestHit
+                    | ShaderStage::RayAnyHit
+                    | ShaderStage::RayIntersection
+            ),
+            _ => self == stage,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -332,6 +492,20 @@ pub enum BorderColor {
     IntOpaqueBlack,
     FloatOpaqueWhite,
     IntOpaqueWhite,
+    /// Requires [`GraphicsFeatures::custom_border_color`](crate::context::GraphicsFeatures).
+    CustomFloat([NotNan<f32>; 4]),
+    /// Requires [`GraphicsFeatures::custom_border_color`](crate::context::GraphicsFeatures).
+    CustomInt([i32; 4]),
+}
+
+impl BorderColor {
+    #[inline(always)]
+    pub fn is_custom(&self) -> bool {
+        matches!(
+            self,
+            BorderColor::CustomFloat(_) | BorderColor::CustomInt(_)
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -343,6 +517,46 @@ pub enum AnisotropyLevel {
     X16,
 }
 
+impl AnisotropyLevel {
+    /// Every level from lowest to highest.
+    pub const ALL: [AnisotropyLevel; 5] = [
+        AnisotropyLevel::X1,
+        AnisotropyLevel::X2,
+        AnisotropyLevel::X4,
+        AnisotropyLevel::X8,
+        AnisotropyLevel::X16,
+    ];
+
+    /// The maximum number of samples taken by the level.
+    #[inline]
+    pub fn samples(self) -> f32 {
+        match self {
+            AnisotropyLevel::X1 => 1.0,
+            AnisotropyLevel::X2 => 2.0,
+            AnisotropyLevel::X4 => 4.0,
+            AnisotropyLevel::X8 => 8.0,
+            AnisotropyLevel::X16 => 16.0,
+        }
+    }
+
+    /// Gets the highest level that takes at most `samples` samples. Never lower than `X1`.
+    #[inline]
+    pub fn from_samples(samples: f32) -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|level| level.samples() <= samples)
+            .unwrap_or(AnisotropyLevel::X1)
+    }
+
+    /// Lowers the level so that it takes at most `max_samples` samples, such as
+    /// [`DeviceLimits::max_sampler_anisotropy`](crate::context::DeviceLimits).
+    #[inline]
+    pub fn clamp_to(self, max_samples: f32) -> Self {
+        self.min(Self::from_samples(max_samples))
+    }
+}
+
 bitflags! {
     #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
     #[serde(transparent)]
@@ -358,6 +572,9 @@ bitflags! {
         const ACCELERATION_STRUCTURE_SCRATCH  = 0b0001_0000_0000;
         const ACCELERATION_STRUCTURE_READ     = 0b0010_0000_0000;
         const SHADER_BINDING_TABLE            = 0b0100_0000_0000;
+        const CONDITIONAL_RENDERING           = 0b1000_0000_0000;
+        const UNIFORM_TEXEL_BUFFER            = 0b0001_0000_0000_0000;
+        const STORAGE_TEXEL_BUFFER            = 0b0010_0000_0000_0000;
     }
 }
 
@@ -374,6 +591,43 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// A set of sample counts.
+    #[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    #[serde(transparent)]
+    pub struct SampleCounts: u32 {
+        const COUNT_1  = 0b00001;
+        const COUNT_2  = 0b00010;
+        const COUNT_4  = 0b00100;
+        const COUNT_8  = 0b01000;
+        const COUNT_16 = 0b10000;
+    }
+}
+
+bitflags! {
+    /// Ways a texture with a particular format can be used on the device.
+    #[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    #[serde(transparent)]
+    pub struct FormatSupport: u32 {
+        const TRANSFER_SRC             = 0b000000001;
+        const TRANSFER_DST             = 0b000000010;
+        const SAMPLED                  = 0b000000100;
+        const STORAGE                  = 0b000001000;
+        const COLOR_ATTACHMENT         = 0b000010000;
+        const DEPTH_STENCIL_ATTACHMENT = 0b000100000;
+        /// Sampled with linear filtering.
+        const LINEAR_FILTER            = 0b001000000;
+        /// Source of a blit.
+        const BLIT_SRC                 = 0b010000000;
+        /// Destination of a blit.
+        const BLIT_DST                 = 0b100000000;
+        /// Read through a [uniform texel buffer](crate::buffer::BufferView).
+        const UNIFORM_TEXEL_BUFFER     = 0b01000000000;
+        /// Read or written through a [storage texel buffer](crate::buffer::BufferView).
+        const STORAGE_TEXEL_BUFFER     = 0b10000000000;
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum TextureType {
     Type1D,
@@ -381,6 +635,14 @@ pub enum TextureType {
     Type3D,
 }
 
+/// A single aspect of a texture that can be sampled.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TextureAspect {
+    Color,
+    Depth,
+    Stencil,
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum MemoryUsage {
     Unknown,
@@ -389,13 +651,26 @@ pub enum MemoryUsage {
     GpuToCpu,
 }
 
+/// What to do when a resource can't be allocated in device local memory.
+#[derive(
+    Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub enum MemoryFallback {
+    /// Creation fails with an out of memory error.
+    #[default]
+    None,
+    /// The allocation is retried in host memory visible to the device. The resource works the
+    /// same, but is slower for the GPU to access.
+    AllowSystemMemory,
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum AccessType {
     Read,
     ReadWrite,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Scissor {
     pub x: i32,
     pub y: i32,
@@ -403,6 +678,18 @@ pub struct Scissor {
     pub height: u32,
 }
 
+/// A region of the framebuffer to render to. The origin is the top left corner of the
+/// framebuffer.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
 bitflags! {
     #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
     #[serde(transparent)]
@@ -425,6 +712,68 @@ bitflags! {
 }
 
 impl Format {
+    /// Every supported format.
+    pub const ALL: [Format; 58] = [
+        Format::R8Unorm,
+        Format::R8Snorm,
+        Format::R8UInt,
+        Format::R8SInt,
+        Format::R8Srgb,
+        Format::R16Unorm,
+        Format::R16Snorm,
+        Format::R16UInt,
+        Format::R16SInt,
+        Format::R16SFloat,
+        Format::R32UInt,
+        Format::R32SInt,
+        Format::R32SFloat,
+        Format::Rg8Unorm,
+        Format::Rg8Snorm,
+        Format::Rg8UInt,
+        Format::Rg8SInt,
+        Format::Rg8Srgb,
+        Format::Rg16Unorm,
+        Format::Rg16Snorm,
+        Format::Rg16UInt,
+        Format::Rg16SInt,
+        Format::Rg16SFloat,
+        Format::Rg32UInt,
+        Format::Rg32SInt,
+        Format::Rg32SFloat,
+        Format::Rgb32SFloat,
+        Format::Rgba8Unorm,
+        Format::Rgba8Snorm,
+        Format::Rgba8UInt,
+        Format::Rgba8SInt,
+        Format::Rgba8Srgb,
+        Format::Rgba16Unorm,
+        Format::Rgba16Snorm,
+        Format::Rgba16UInt,
+        Format::Rgba16SInt,
+        Format::Rgba16SFloat,
+        Format::Rgba32UInt,
+        Format::Rgba32SInt,
+        Format::Rgba32SFloat,
+        Format::Bgra8Unorm,
+        Format::Bgra8Srgb,
+        Format::Rgb10A2Unorm,
+        Format::BC6HUFloat,
+        Format::BC7Srgb,
+        Format::BC7Unorm,
+        Format::Astc4x4Unorm,
+        Format::Astc4x4Srgb,
+        Format::Astc8x8Unorm,
+        Format::Astc8x8Srgb,
+        Format::Etc2Rgb8Unorm,
+        Format::Etc2Rgb8Srgb,
+        Format::Etc2Rgba8Unorm,
+        Format::Etc2Rgba8Srgb,
+        Format::D16Unorm,
+        Format::D24UnormS8Uint,
+        Format::D32Sfloat,
+        Format::D32SfloatS8Uint,
+    ];
+
     #[inline(always)]
     pub fn is_color(&self) -> bool {
         !(self.is_depth() || self.is_stencil())
@@ -442,6 +791,109 @@ impl Format {
     pub fn is_stencil(&self) -> bool {
         matches!(*self, Format::D24UnormS8Uint | Format::D32SfloatS8Uint)
     }
+
+    /// Checks if textures of this format contain the given aspect.
+    #[inline(always)]
+    pub fn has_aspect(&self, aspect: TextureAspect) -> bool {
+        match aspect {
+            TextureAspect::Color => self.is_color(),
+            TextureAspect::Depth => self.is_depth(),
+            TextureAspect::Stencil => self.is_stencil(),
+        }
+    }
+
+    /// Size in bytes of a single texel block when copied into a buffer, and the width and height
+    /// of the block in texels. Only the depth aspect of depth/stencil formats is copied.
+    #[inline(always)]
+    pub fn block_info(&self) -> (u32, u32) {
+        (self.bytes_per_block(), self.block_dim())
+    }
+
+    /// Width and height in texels of a single texel block. Uncompressed formats have 1x1 blocks.
+    /// Copies to and from textures must begin on a block boundary and cover whole blocks, except
+    /// where the copy reaches the edge of the mip.
+    #[inline(always)]
+    pub fn block_dim(&self) -> u32 {
+        match *self {
+            Format::BC6HUFloat
+            | Format::BC7Srgb
+            | Format::BC7Unorm
+            | Format::Astc4x4Unorm
+            | Format::Astc4x4Srgb
+            | Format::Etc2Rgb8Unorm
+            | Format::Etc2Rgb8Srgb
+            | Format::Etc2Rgba8Unorm
+            | Format::Etc2Rgba8Srgb => 4,
+            Format::Astc8x8Unorm | Format::Astc8x8Srgb => 8,
+            _ => 1,
+        }
+    }
+
+    /// Size in bytes of a single texel block when copied into a buffer. Only the depth aspect of
+    /// depth/stencil formats is copied.
+    #[inline(always)]
+    pub fn bytes_per_block(&self) -> u32 {
+        match *self {
+            Format::R8Unorm
+            | Format::R8Snorm
+            | Format::R8UInt
+            | Format::R8SInt
+            | Format::R8Srgb => 1,
+            Format::R16Unorm
+            | Format::R16Snorm
+            | Format::R16UInt
+            | Format::R16SInt
+            | Format::R16SFloat
+            | Format::Rg8Unorm
+            | Format::Rg8Snorm
+            | Format::Rg8UInt
+            | Format::Rg8SInt
+            | Format::Rg8Srgb
+            | Format::D16Unorm => 2,
+            Format::R32UInt
+            | Format::R32SInt
+            | Format::R32SFloat
+            | Format::Rg16Unorm
+            | Format::Rg16Snorm
+            | Format::Rg16UInt
+            | Format::Rg16SInt
+            | Format::Rg16SFloat
+            | Format::Rgba8Unorm
+            | Format::Rgba8Snorm
+            | Format::Rgba8UInt
+            | Format::Rgba8SInt
+            | Format::Rgba8Srgb
+            | Format::Bgra8Unorm
+            | Format::Bgra8Srgb
+            | Format::Rgb10A2Unorm
+            | Format::D24UnormS8Uint
+            | Format::D32Sfloat
+            | Format::D32SfloatS8Uint => 4,
+            Format::Rg32UInt
+            | Format::Rg32SInt
+            | Format::Rg32SFloat
+            | Format::Rgba16Unorm
+            | Format::Rgba16Snorm
+            | Format::Rgba16UInt
+            | Format::Rgba16SInt
+            | Format::Rgba16SFloat
+            | Format::Etc2Rgb8Unorm
+            | Format::Etc2Rgb8Srgb => 8,
+            Format::Rgb32SFloat => 12,
+            Format::Rgba32UInt
+            | Format::Rgba32SInt
+            | Format::Rgba32SFloat
+            | Format::BC6HUFloat
+            | Format::BC7Srgb
+            | Format::BC7Unorm
+            | Format::Astc4x4Unorm
+            | Format::Astc4x4Srgb
+            | Format::Astc8x8Unorm
+            | Format::Astc8x8Srgb
+            | Format::Etc2Rgba8Unorm
+            | Format::Etc2Rgba8Srgb => 16,
+        }
+    }
 }
 
 impl MultiSamples {
@@ -457,6 +909,43 @@ impl MultiSamples {
     }
 }
 
+impl SampleCounts {
+    /// Gets the highest sample count within the set, or [`MultiSamples::Count1`] if the set is
+    /// empty.
+    #[inline(always)]
+    pub fn max_samples(&self) -> MultiSamples {
+        [
+            MultiSamples::Count16,
+            MultiSamples::Count8,
+            MultiSamples::Count4,
+            MultiSamples::Count2,
+        ]
+        .into_iter()
+        .find(|samples| self.contains(SampleCounts::from(*samples)))
+        .unwrap_or(MultiSamples::Count1)
+    }
+}
+
+impl From<TextureUsage> for FormatSupport {
+    /// The support a format must have to create a texture with the given usage.
+    fn from(value: TextureUsage) -> Self {
+        // Flags share the same bit layout
+        FormatSupport::from_bits_truncate(value.bits())
+    }
+}
+
+impl From<MultiSamples> for SampleCounts {
+    fn from(value: MultiSamples) -> Self {
+        match value {
+            MultiSamples::Count1 => SampleCounts::COUNT_1,
+            MultiSamples::Count2 => SampleCounts::COUNT_2,
+            MultiSamples::Count4 => SampleCounts::COUNT_4,
+            MultiSamples::Count8 => SampleCounts::COUNT_8,
+            MultiSamples::Count16 => SampleCounts::COUNT_16,
+        }
+    }
+}
+
 impl From<QueueType> for QueueTypes {
     fn from(value: QueueType) -> Self {
         match value {
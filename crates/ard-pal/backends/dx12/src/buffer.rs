@@ -0,0 +1,218 @@
+use std::{ptr::NonNull, sync::Arc};
+
+use api::{
+    buffer::{BufferCreateError, BufferCreateInfo, BufferViewError},
+    types::{BufferUsage, MemoryUsage, SharingMode},
+};
+use crossbeam_channel::Sender;
+use gpu_allocator::d3d12::{
+    Allocator, Resource, ResourceCategory, ResourceCreateDesc, ResourceStateOrBarrierLayout,
+    ResourceType,
+};
+use windows::{
+    core::HSTRING,
+    Win32::Graphics::{Direct3D12::*, Dxgi::Common::*},
+};
+
+use crate::{
+    util::{garbage_collector::Garbage, usage::UsageTracker},
+    Dx12Backend,
+};
+
+pub struct Buffer {
+    pub(crate) resource: ID3D12Resource,
+    pub(crate) allocation: Option<Resource>,
+    pub(crate) _sharing_mode: SharingMode,
+    pub(crate) _buffer_usage: BufferUsage,
+    pub(crate) _memory_usage: MemoryUsage,
+    pub(crate) _array_elements: usize,
+    /// This was the user requested size of each array element.
+    pub(crate) size: u64,
+    /// This is the per element size after alignment.
+    pub(crate) aligned_size: u64,
+    /// Pointer to the start of the buffer if it is host visible. Host visible buffers are
+    /// persistently mapped.
+    pub(crate) map: Option<NonNull<u8>>,
+    /// Upload and readback buffers can't leave their initial state.
+    pub(crate) fixed_state: Option<D3D12_RESOURCE_STATES>,
+    pub(crate) usage: UsageTracker,
+    pub(crate) ref_counter: BufferRefCounter,
+    on_drop: Sender<Garbage>,
+}
+
+#[derive(Clone)]
+pub(crate) struct BufferRefCounter(Arc<()>);
+
+unsafe impl Send for Buffer {}
+unsafe impl Sync for Buffer {}
+
+impl Buffer {
+    pub(crate) unsafe fn new(
+        on_drop: Sender<Garbage>,
+        allocator: &mut Allocator,
+        create_info: BufferCreateInfo,
+    ) -> Result<Self, BufferCreateError> {
+        // Constant buffer views must be 256 byte aligned. Everything else is viewed as a raw
+        // buffer which only needs 16 byte alignment.
+        let alignment_req = if create_info
+            .buffer_usage
+            .contains(BufferUsage::UNIFORM_BUFFER)
+        {
+            D3D12_CONSTANT_BUFFER_DATA_PLACEMENT_ALIGNMENT as u64
+        } else {
+            16
+        };
+
+        // Typed views address the buffer in whole texels, so array elements must start on a
+        // multiple of every texel size, including the 12 bytes of three component formats
+        let alignment_req = if create_info
+            .buffer_usage
+            .intersects(BufferUsage::UNIFORM_TEXEL_BUFFER | BufferUsage::STORAGE_TEXEL_BUFFER)
+        {
+            alignment_req * 3
+        } else {
+            alignment_req
+        };
+
+        // Round size to a multiple of the alignment
+        let aligned_size = create_info.size.next_multiple_of(alignment_req);
+
+        // Only device local memory can be written to from shaders
+        let fixed_state = crate::util::fixed_buffer_state(create_info.memory_usage);
+        let flags = if fixed_state.is_none()
+            && create_info
+                .buffer_usage
+                .intersects(BufferUsage::STORAGE_BUFFER | BufferUsage::STORAGE_TEXEL_BUFFER)
+        {
+            D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS
+        } else {
+            D3D12_RESOURCE_FLAG_NONE
+        };
+
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+            Alignment: 0,
+            Width: aligned_size * create_info.array_elements as u64,
+            Height: 1,
+            DepthOrArraySize: 1,
+            MipLevels: 1,
+            Format: DXGI_FORMAT_UNKNOWN,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+            Flags: flags,
+        };
+
+        let name = match &create_info.debug_name {
+            Some(name) => name.as_str(),
+            None => "unnamed_buffer",
+        };
+
+        let allocation = match allocator.create_resource(&ResourceCreateDesc {
+            name,
+            memory_location: crate::util::to_gpu_allocator_memory_location(
+                create_info.memory_usage,
+            ),
+            resource_category: ResourceCategory::Buffer,
+            resource_desc: &desc,
+            castable_formats: &[],
+            clear_value: None,
+            initial_state_or_layout: ResourceStateOrBarrierLayout::ResourceState(
+                fixed_state.unwrap_or(D3D12_RESOURCE_STATE_COMMON),
+            ),
+            resource_type: &ResourceType::Placed,
+        }) {
+            Ok(allocation) => allocation,
+            Err(err) => return Err(BufferCreateError::Other(err.to_string())),
+        };
+        let resource = allocation.resource().clone();
+
+        // Persistently map host visible memory
+        let map = if fixed_state.is_some() {
+            let mut ptr = std::ptr::null_mut();
+            if let Err(err) = resource.Map(0, None, Some(&mut ptr)) {
+                allocator.free_resource(allocation).unwrap();
+                return Err(BufferCreateError::Other(err.to_string()));
+            }
+            NonNull::new(ptr as *mut u8)
+        } else {
+            None
+        };
+
+        if create_info.debug_name.is_some() {
+            let _ = resource.SetName(&HSTRING::from(name));
+        }
+
+        Ok(Buffer {
+            resource,
+            allocation: Some(allocation),
+            size: create_info.size,
+            aligned_size,
+            _sharing_mode: create_info.sharing_mode,
+            _array_elements: create_info.array_elements,
+            _buffer_usage: create_info.buffer_usage,
+            _memory_usage: create_info.memory_usage,
+            map,
+            fixed_state,
+            usage: UsageTracker::default(),
+            on_drop,
+            ref_counter: BufferRefCounter::default(),
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn offset(&self, array_element: usize) -> u64 {
+        self.aligned_size * array_element as u64
+    }
+
+    #[inline(always)]
+    pub(crate) unsafe fn device_address(&self, array_element: usize) -> u64 {
+        self.resource.GetGPUVirtualAddress() + self.offset(array_element)
+    }
+
+    pub(crate) unsafe fn map(
+        &self,
+        ctx: &Dx12Backend,
+        idx: usize,
+    ) -> Result<(NonNull<u8>, u64), BufferViewError> {
+        let map = match self.map {
+            Some(map) => map,
+            None => {
+                return Err(BufferViewError::Other(String::from(
+                    "buffer is not host visible",
+                )))
+            }
+        };
+
+        // Wait until every queue that the buffer was used in has finished it's work
+        self.usage.wait(ctx);
+
+        let map = NonNull::new_unchecked(map.as_ptr().add(self.aligned_size as usize * idx));
+        Ok((map, self.size))
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        let _ = self.on_drop.send(Garbage::Buffer {
+            allocation: self.allocation.take().unwrap(),
+            ref_counter: self.ref_counter.clone(),
+        });
+    }
+}
+
+impl BufferRefCounter {
+    #[inline]
+    pub fn is_last(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+}
+
+impl Default for BufferRefCounter {
+    #[inline]
+    fn default() -> Self {
+        BufferRefCounter(Arc::new(()))
+    }
+}
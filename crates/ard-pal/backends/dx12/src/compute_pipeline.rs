@@ -0,0 +1,85 @@
+use api::compute_pipeline::{ComputePipelineCreateError, ComputePipelineCreateInfo};
+use bytemuck::{Pod, Zeroable};
+use crossbeam_channel::Sender;
+use windows::{core::HSTRING, Win32::Graphics::Direct3D12::*};
+
+use crate::util::{garbage_collector::Garbage, root_signature::RootSignature};
+
+pub struct ComputePipeline {
+    pub(crate) pipeline: ID3D12PipelineState,
+    pub(crate) root_signature: RootSignature,
+    garbage: Sender<Garbage>,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DispatchIndirect {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+unsafe impl Pod for DispatchIndirect {}
+unsafe impl Zeroable for DispatchIndirect {}
+
+unsafe impl Send for ComputePipeline {}
+unsafe impl Sync for ComputePipeline {}
+
+impl ComputePipeline {
+    pub(crate) unsafe fn new(
+        device: &ID3D12Device,
+        garbage: Sender<Garbage>,
+        create_info: ComputePipelineCreateInfo<crate::Dx12Backend>,
+    ) -> Result<Self, ComputePipelineCreateError> {
+        // NOTE: HLSL has no specialization constants, so the work group size must be baked into
+        // the shader with `[numthreads]` and match `work_group_size`.
+        let root_signature = match RootSignature::new(
+            device,
+            &create_info.layouts,
+            create_info.push_constants_size,
+            false,
+        ) {
+            Ok(root_signature) => root_signature,
+            Err(err) => return Err(ComputePipelineCreateError::Other(err)),
+        };
+
+        let code = &create_info.module.internal().code;
+        let desc = D3D12_COMPUTE_PIPELINE_STATE_DESC {
+            pRootSignature: std::mem::transmute_copy(&root_signature.signature),
+            CS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: code.as_ptr() as *const _,
+                BytecodeLength: code.len(),
+            },
+            NodeMask: 0,
+            CachedPSO: D3D12_CACHED_PIPELINE_STATE::default(),
+            Flags: D3D12_PIPELINE_STATE_FLAG_NONE,
+        };
+
+        let pipeline: ID3D12PipelineState = match device.CreateComputePipelineState(&desc) {
+            Ok(pipeline) => pipeline,
+            Err(err) => return Err(ComputePipelineCreateError::Other(err.to_string())),
+        };
+
+        // Name the pipeline if needed
+        if let Some(name) = create_info.debug_name {
+            let _ = pipeline.SetName(&HSTRING::from(name));
+        }
+
+        Ok(Self {
+            pipeline,
+            root_signature,
+            garbage,
+        })
+    }
+}
+
+impl Drop for ComputePipeline {
+    fn drop(&mut self) {
+        let _ = self
+            .garbage
+            .send(Garbage::PipelineState(self.pipeline.clone()));
+        let _ = self.garbage.send(Garbage::RootSignature(
+            self.root_signature.signature.clone(),
+        ));
+    }
+}
@@ -0,0 +1,211 @@
+use api::{
+    cube_map::{CubeMapCreateError, CubeMapCreateInfo},
+    types::{CubeFace, Format, MultiSamples, SharingMode, TextureUsage},
+};
+use crossbeam_channel::Sender;
+use gpu_allocator::d3d12::{Allocator, Resource};
+use windows::Win32::Graphics::{Direct3D12::*, Dxgi::Common::*};
+
+use crate::{
+    texture::TextureRefCounter,
+    util::{
+        cube_face_to_idx, descriptor_heap::DescriptorHeaps, garbage_collector::Garbage,
+        states::SubresourceStates, usage::UsageTracker,
+    },
+};
+
+/// Number of render target views per array element and mip level. One for each face and one for
+/// the entire cube.
+const VIEWS_PER_MIP: usize = 7;
+
+pub struct CubeMap {
+    pub(crate) resource: ID3D12Resource,
+    pub(crate) allocation: Option<Resource>,
+    /// Render target views for each array element, each mip level, and each face. This array is
+    /// flattened like so.
+    /// A0M0F0 -> A0M0F1 ... A0M0F5 -> A0M0C -> A0M1F0 -> ...
+    /// Where the face index is as in `cube_face_to_idx` and `C` is a view of the entire cube.
+    pub(crate) rtvs: Vec<u32>,
+    /// Depth stencil views flattened the same as `rtvs`. Each index is the first of two
+    /// descriptors. The first is writable and the second is read only.
+    pub(crate) dsvs: Vec<u32>,
+    pub(crate) ref_counter: TextureRefCounter,
+    pub(crate) format: Format,
+    pub(crate) mip_count: u32,
+    pub(crate) array_elements: usize,
+    pub(crate) _sharing_mode: SharingMode,
+    pub(crate) size: u64,
+    pub(crate) states: SubresourceStates,
+    pub(crate) usage: UsageTracker,
+    on_drop: Sender<Garbage>,
+}
+
+unsafe impl Send for CubeMap {}
+unsafe impl Sync for CubeMap {}
+
+impl CubeMap {
+    pub(crate) unsafe fn new(
+        device: &ID3D12Device,
+        on_drop: Sender<Garbage>,
+        allocator: &mut Allocator,
+        heaps: &mut DescriptorHeaps,
+        create_info: CubeMapCreateInfo,
+    ) -> Result<Self, CubeMapCreateError> {
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+            Alignment: 0,
+            Width: create_info.size as u64,
+            Height: create_info.size,
+            DepthOrArraySize: 6 * create_info.array_elements as u16,
+            MipLevels: create_info.mip_levels as u16,
+            Format: crate::util::to_dxgi_resource_format(create_info.format),
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+            Flags: crate::texture::to_d3d12_resource_flags(create_info.texture_usage),
+        };
+
+        let name = match &create_info.debug_name {
+            Some(name) => name.as_str(),
+            None => "unnamed_cube_map",
+        };
+
+        let allocation = match crate::texture::create_resource(
+            allocator,
+            &desc,
+            create_info.format,
+            create_info.texture_usage,
+            name,
+        ) {
+            Ok(allocation) => allocation,
+            Err(err) => return Err(CubeMapCreateError::Other(err)),
+        };
+        let resource = allocation.resource().clone();
+
+        // Create render target and depth stencil views
+        let mut rtvs = Vec::default();
+        let mut dsvs = Vec::default();
+        let is_color = create_info
+            .texture_usage
+            .contains(TextureUsage::COLOR_ATTACHMENT);
+        let is_depth = create_info
+            .texture_usage
+            .contains(TextureUsage::DEPTH_STENCIL_ATTACHMENT);
+
+        for array_elem in 0..create_info.array_elements as u32 {
+            for mip in 0..create_info.mip_levels as u32 {
+                for view in 0..VIEWS_PER_MIP as u32 {
+                    let (first_layer, layer_count) = if view == 6 {
+                        (array_elem * 6, 6)
+                    } else {
+                        ((array_elem * 6) + view, 1)
+                    };
+
+                    let res = if is_color {
+                        crate::texture::create_rtv(
+                            device,
+                            heaps,
+                            &resource,
+                            create_info.format,
+                            MultiSamples::Count1,
+                            mip,
+                            first_layer,
+                            layer_count,
+                        )
+                        .map(|rtv| rtvs.push(rtv))
+                    } else if is_depth {
+                        crate::texture::create_dsv(
+                            device,
+                            heaps,
+                            &resource,
+                            create_info.format,
+                            MultiSamples::Count1,
+                            mip,
+                            first_layer,
+                            layer_count,
+                        )
+                        .map(|dsv| dsvs.push(dsv))
+                    } else {
+                        Ok(())
+                    };
+
+                    if let Err(err) = res {
+                        rtvs.iter().for_each(|rtv| heaps.rtv.free(*rtv, 1));
+                        dsvs.iter().for_each(|dsv| heaps.dsv.free(*dsv, 2));
+                        allocator.free_resource(allocation).unwrap();
+                        return Err(CubeMapCreateError::Other(err));
+                    }
+                }
+            }
+        }
+
+        // Determine the size of the cube map
+        let size = device.GetResourceAllocationInfo(0, &[desc]).SizeInBytes
+            / create_info.array_elements as u64;
+
+        Ok(CubeMap {
+            resource,
+            allocation: Some(allocation),
+            rtvs,
+            dsvs,
+            ref_counter: TextureRefCounter::default(),
+            format: create_info.format,
+            mip_count: create_info.mip_levels as u32,
+            array_elements: create_info.array_elements,
+            _sharing_mode: create_info.sharing_mode,
+            size,
+            states: SubresourceStates::new(
+                create_info.mip_levels as u32,
+                6 * create_info.array_elements as u32,
+                crate::util::plane_count(create_info.format),
+            ),
+            usage: UsageTracker::default(),
+            on_drop,
+        })
+    }
+
+    #[inline(always)]
+    fn view_idx(&self, array_element: usize, mip_level: usize, face: Option<CubeFace>) -> usize {
+        let base = ((array_element * self.mip_count as usize) + mip_level) * VIEWS_PER_MIP;
+        match face {
+            Some(face) => base + cube_face_to_idx(face),
+            None => base + 6,
+        }
+    }
+
+    /// Render target view of a single face, or the entire cube if `face` is `None`.
+    #[inline(always)]
+    pub(crate) fn rtv(
+        &self,
+        array_element: usize,
+        mip_level: usize,
+        face: Option<CubeFace>,
+    ) -> u32 {
+        self.rtvs[self.view_idx(array_element, mip_level, face)]
+    }
+
+    /// Depth stencil view of a single face, or the entire cube if `face` is `None`.
+    #[inline(always)]
+    pub(crate) fn dsv(
+        &self,
+        array_element: usize,
+        mip_level: usize,
+        face: Option<CubeFace>,
+        read_only: bool,
+    ) -> u32 {
+        self.dsvs[self.view_idx(array_element, mip_level, face)] + read_only as u32
+    }
+}
+
+impl Drop for CubeMap {
+    fn drop(&mut self) {
+        let _ = self.on_drop.send(Garbage::Texture {
+            allocation: self.allocation.take().unwrap(),
+            ref_counter: self.ref_counter.clone(),
+            rtvs: std::mem::take(&mut self.rtvs),
+            dsvs: std::mem::take(&mut self.dsvs),
+        });
+    }
+}
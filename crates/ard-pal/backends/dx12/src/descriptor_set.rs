@@ -0,0 +1,763 @@
+use std::ops::Range;
+
+use api::{
+    command_buffer::Command,
+    descriptor_set::{
+        DescriptorBinding, DescriptorBindingFlags, DescriptorSetCreateError,
+        DescriptorSetCreateInfo, DescriptorSetLayoutCreateError, DescriptorSetLayoutCreateInfo,
+        DescriptorSetUpdate, DescriptorValue,
+    },
+    types::{MultiSamples, TextureType},
+};
+use crossbeam_channel::Sender;
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
+use windows::Win32::Graphics::{Direct3D12::*, Dxgi::Common::*};
+
+use crate::{
+    buffer::{Buffer, BufferRefCounter},
+    texture::TextureRefCounter,
+    util::{
+        descriptor_heap::DescriptorHeaps, garbage_collector::Garbage, states::SubresourceStates,
+        usage::UsageTracker,
+    },
+    Dx12Backend,
+};
+
+pub struct DescriptorSet {
+    /// Index of the first descriptor in the resource heap.
+    pub(crate) resources: Option<u32>,
+    /// Index of the first descriptor in the sampler heap.
+    pub(crate) samplers: Option<u32>,
+    pub(crate) resource_count: u32,
+    pub(crate) sampler_count: u32,
+    pub(crate) bound: DescriptorSetBindings,
+    /// Tracks which queues the set was last used in.
+    pub(crate) last_use: UsageTracker,
+    pub(crate) on_drop: Sender<Garbage>,
+}
+
+pub struct DescriptorSetLayout {
+    pub(crate) descriptor: DescriptorSetLayoutCreateInfo,
+    /// Offset of each binding within the sets resource descriptor table.
+    pub(crate) resource_offsets: Vec<u32>,
+    /// Offset of each binding within the sets sampler descriptor table. `None` if the binding
+    /// has no samplers.
+    pub(crate) sampler_offsets: Vec<Option<u32>>,
+    pub(crate) resource_count: u32,
+    pub(crate) sampler_count: u32,
+}
+
+pub(crate) type DescriptorSetBindings = SmallVec<[SmallVec<[Option<Binding>; 1]>; 4]>;
+
+pub(crate) struct Binding {
+    pub value: BoundValue,
+    pub state: D3D12_RESOURCE_STATES,
+}
+
+pub(crate) enum BoundValue {
+    Buffer {
+        _ref_counter: BufferRefCounter,
+        resource: ID3D12Resource,
+        fixed_state: Option<D3D12_RESOURCE_STATES>,
+        usage: UsageTracker,
+    },
+    Texture {
+        _ref_counter: TextureRefCounter,
+        resource: ID3D12Resource,
+        states: SubresourceStates,
+        usage: UsageTracker,
+        mips: Range<u32>,
+        layers: Range<u32>,
+    },
+}
+
+unsafe impl Send for DescriptorSet {}
+unsafe impl Sync for DescriptorSet {}
+
+impl DescriptorSetLayout {
+    pub(crate) fn new(
+        create_info: DescriptorSetLayoutCreateInfo,
+    ) -> Result<Self, DescriptorSetLayoutCreateError> {
+        let mut resource_offsets = Vec::with_capacity(create_info.bindings.len());
+        let mut sampler_offsets = Vec::with_capacity(create_info.bindings.len());
+        let mut resource_count = 0;
+        let mut sampler_count = 0;
+
+        for binding in &create_info.bindings {
+            resource_offsets.push(resource_count);
+            resource_count += binding.count as u32;
+
+            if crate::util::descriptor_type_has_sampler(binding.ty) {
+                sampler_offsets.push(Some(sampler_count));
+                sampler_count += binding.count as u32;
+            } else {
+                sampler_offsets.push(None);
+            }
+        }
+
+        if sampler_count > DescriptorHeaps::SAMPLER_COUNT {
+            return Err(DescriptorSetLayoutCreateError::Other(format!(
+                "layout requires {sampler_count} samplers but at most {} are supported",
+                DescriptorHeaps::SAMPLER_COUNT
+            )));
+        }
+
+        Ok(DescriptorSetLayout {
+            descriptor: create_info,
+            resource_offsets,
+            sampler_offsets,
+            resource_count,
+            sampler_count,
+        })
+    }
+
+    #[inline]
+    pub(crate) fn get_binding(&self, binding_value: u32) -> Option<(usize, &DescriptorBinding)> {
+        self.descriptor
+            .bindings
+            .iter()
+            .enumerate()
+            .find(|(_, binding)| binding.binding == binding_value)
+    }
+}
+
+impl DescriptorSet {
+    pub(crate) fn new(
+        heaps: &mut DescriptorHeaps,
+        garbage: Sender<Garbage>,
+        create_info: DescriptorSetCreateInfo<crate::Dx12Backend>,
+    ) -> Result<Self, DescriptorSetCreateError> {
+        let layout = create_info.layout.internal();
+
+        let mut bound = SmallVec::with_capacity(layout.descriptor.bindings.len());
+        for binding in &layout.descriptor.bindings {
+            // Descriptors are always reserved for the full count, but only the requested number
+            // of elements can be written
+            let count = if binding
+                .flags
+                .contains(DescriptorBindingFlags::VARIABLE_COUNT)
+            {
+                create_info.variable_count.unwrap_or(binding.count)
+            } else {
+                binding.count
+            };
+
+            let mut binds = SmallVec::with_capacity(count);
+            binds.resize_with(count, || None);
+            bound.push(binds);
+        }
+
+        // Allocate space in the shader visible heaps
+        let resources = if layout.resource_count > 0 {
+            match heaps.resources.allocate(layout.resource_count) {
+                Some(idx) => Some(idx),
+                None => {
+                    return Err(DescriptorSetCreateError::Other(String::from(
+                        "out of resource descriptors",
+                    )))
+                }
+            }
+        } else {
+            None
+        };
+
+        let samplers = if layout.sampler_count > 0 {
+            match heaps.samplers.allocate(layout.sampler_count) {
+                Some(idx) => Some(idx),
+                None => {
+                    if let Some(idx) = resources {
+                        heaps.resources.free(idx, layout.resource_count);
+                    }
+                    return Err(DescriptorSetCreateError::Other(String::from(
+                        "out of sampler descriptors",
+                    )));
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(DescriptorSet {
+            resources,
+            samplers,
+            resource_count: layout.resource_count,
+            sampler_count: layout.sampler_count,
+            bound,
+            last_use: UsageTracker::default(),
+            on_drop: garbage,
+        })
+    }
+
+    pub(crate) unsafe fn update(
+        &mut self,
+        ctx: &Dx12Backend,
+        layout: &DescriptorSetLayout,
+        updates: &[DescriptorSetUpdate<crate::Dx12Backend>],
+    ) {
+        // Wait until every queue that the set was used in has finished it's work. This means the
+        // descriptors can be overwritten in place. Bindings that are updated after binding can be
+        // overwritten while the set is in use.
+        let in_use = updates.iter().all(|update| {
+            layout
+                .get_binding(update.binding)
+                .is_some_and(|(_, binding)| {
+                    binding
+                        .flags
+                        .contains(DescriptorBindingFlags::UPDATE_AFTER_BIND)
+                })
+        });
+        if !in_use {
+            self.last_use.wait(ctx);
+        }
+        let mut replaced = Vec::default();
+
+        let heaps = ctx.heaps.lock().unwrap();
+
+        for update in updates {
+            let (idx, binding) = match layout.get_binding(update.binding) {
+                Some(binding) => binding,
+                None => {
+                    ard_log::warn!("attempt to update invalid binding `{}`", update.binding);
+                    continue;
+                }
+            };
+
+            // Partially bound arrays may have holes, but elements past the end are invalid
+            if update.array_element >= self.bound[idx].len() {
+                ard_log::warn!(
+                    "attempt to update element `{}` of binding `{}` with `{}` elements",
+                    update.array_element,
+                    update.binding,
+                    self.bound[idx].len()
+                );
+                continue;
+            }
+
+            let resource_handle = heaps.resources.cpu(
+                self.resources.unwrap()
+                    + layout.resource_offsets[idx]
+                    + update.array_element as u32,
+            );
+            let mut state = crate::util::descriptor_type_to_state(binding.ty, binding.stage);
+
+            let new_value = match &update.value {
+                DescriptorValue::UniformBuffer {
+                    buffer,
+                    array_element,
+                } => {
+                    let buffer = buffer.internal();
+                    let desc = D3D12_CONSTANT_BUFFER_VIEW_DESC {
+                        BufferLocation: buffer.device_address(*array_element),
+                        SizeInBytes: buffer
+                            .aligned_size
+                            .next_multiple_of(D3D12_CONSTANT_BUFFER_DATA_PLACEMENT_ALIGNMENT as u64)
+                            as u32,
+                    };
+                    ctx.device
+                        .CreateConstantBufferView(Some(&desc), resource_handle);
+                    buffer_binding(buffer)
+                }
+                DescriptorValue::StorageBuffer {
+                    buffer,
+                    array_element,
+                } => {
+                    let buffer = buffer.internal();
+                    let first_element = buffer.offset(*array_element) / 4;
+                    let num_elements = (buffer.aligned_size / 4) as u32;
+
+                    if state == D3D12_RESOURCE_STATE_UNORDERED_ACCESS {
+                        let desc = D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                            Format: DXGI_FORMAT_R32_TYPELESS,
+                            ViewDimension: D3D12_UAV_DIMENSION_BUFFER,
+                            Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                                Buffer: D3D12_BUFFER_UAV {
+                                    FirstElement: first_element,
+                                    NumElements: num_elements,
+                                    StructureByteStride: 0,
+                                    CounterOffsetInBytes: 0,
+                                    Flags: D3D12_BUFFER_UAV_FLAG_RAW,
+                                },
+                            },
+                        };
+                        ctx.device.CreateUnorderedAccessView(
+                            &buffer.resource,
+                            None,
+                            Some(&desc),
+                            resource_handle,
+                        );
+                    } else {
+                        let desc = D3D12_SHADER_RESOURCE_VIEW_DESC {
+                            Format: DXGI_FORMAT_R32_TYPELESS,
+                            ViewDimension: D3D12_SRV_DIMENSION_BUFFER,
+                            Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                            Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Buffer: D3D12_BUFFER_SRV {
+                                    FirstElement: first_element,
+                                    NumElements: num_elements,
+                                    StructureByteStride: 0,
+                                    Flags: D3D12_BUFFER_SRV_FLAG_RAW,
+                                },
+                            },
+                        };
+                        ctx.device.CreateShaderResourceView(
+                            &buffer.resource,
+                            Some(&desc),
+                            resource_handle,
+                        );
+                    }
+
+                    buffer_binding(buffer)
+                }
+                DescriptorValue::StorageImage {
+                    texture,
+                    array_element,
+                    mip,
+                } => {
+                    let texture = texture.internal();
+                    let format = crate::util::to_dxgi_uav_format(texture.format);
+                    let mip = *mip as u32;
+                    let desc = match texture.ty {
+                        TextureType::Type1D => D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                            Format: format,
+                            ViewDimension: D3D12_UAV_DIMENSION_TEXTURE1DARRAY,
+                            Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                                Texture1DArray: D3D12_TEX1D_ARRAY_UAV {
+                                    MipSlice: mip,
+                                    FirstArraySlice: *array_element as u32,
+                                    ArraySize: 1,
+                                },
+                            },
+                        },
+                        TextureType::Type2D => D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                            Format: format,
+                            ViewDimension: D3D12_UAV_DIMENSION_TEXTURE2DARRAY,
+                            Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                                Texture2DArray: D3D12_TEX2D_ARRAY_UAV {
+                                    MipSlice: mip,
+                                    FirstArraySlice: *array_element as u32,
+                                    ArraySize: 1,
+                                    PlaneSlice: 0,
+                                },
+                            },
+                        },
+                        TextureType::Type3D => D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                            Format: format,
+                            ViewDimension: D3D12_UAV_DIMENSION_TEXTURE3D,
+                            Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                                Texture3D: D3D12_TEX3D_UAV {
+                                    MipSlice: mip,
+                                    FirstWSlice: 0,
+                                    WSize: u32::MAX,
+                                },
+                            },
+                        },
+                    };
+                    ctx.device.CreateUnorderedAccessView(
+                        &texture.resource,
+                        None,
+                        Some(&desc),
+                        resource_handle,
+                    );
+
+                    BoundValue::Texture {
+                        _ref_counter: texture.ref_counter.clone(),
+                        resource: texture.resource.clone(),
+                        states: texture.states.clone(),
+                        usage: texture.usage.clone(),
+                        mips: mip..(mip + 1),
+                        layers: texture_layers(texture.ty, *array_element),
+                    }
+                }
+                DescriptorValue::Texture {
+                    texture,
+                    array_element,
+                    sampler,
+                    base_mip,
+                    mip_count,
+                    aspect,
+                } => {
+                    let texture = texture.internal();
+                    let base_mip = *base_mip as u32;
+                    let mip_count = *mip_count as u32;
+                    let array_element = *array_element as u32;
+                    let (format, plane) = match aspect {
+                        Some(aspect) => {
+                            crate::util::to_dxgi_aspect_srv_format(texture.format, *aspect)
+                        }
+                        None => (crate::util::to_dxgi_srv_format(texture.format), 0),
+                    };
+
+                    // Single element textures are viewed as non-arrays so that shaders can
+                    // declare them as such
+                    let is_array = texture.array_elements > 1;
+                    let (dimension, view) = match texture.ty {
+                        TextureType::Type1D if is_array => (
+                            D3D12_SRV_DIMENSION_TEXTURE1DARRAY,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Texture1DArray: D3D12_TEX1D_ARRAY_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    FirstArraySlice: array_element,
+                                    ArraySize: 1,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        ),
+                        TextureType::Type1D => (
+                            D3D12_SRV_DIMENSION_TEXTURE1D,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Texture1D: D3D12_TEX1D_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        ),
+                        TextureType::Type2D if texture.samples != MultiSamples::Count1 => (
+                            D3D12_SRV_DIMENSION_TEXTURE2DMSARRAY,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Texture2DMSArray: D3D12_TEX2DMS_ARRAY_SRV {
+                                    FirstArraySlice: array_element,
+                                    ArraySize: 1,
+                                },
+                            },
+                        ),
+                        TextureType::Type2D if is_array => (
+                            D3D12_SRV_DIMENSION_TEXTURE2DARRAY,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Texture2DArray: D3D12_TEX2D_ARRAY_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    FirstArraySlice: array_element,
+                                    ArraySize: 1,
+                                    PlaneSlice: plane,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        ),
+                        TextureType::Type2D => (
+                            D3D12_SRV_DIMENSION_TEXTURE2D,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Texture2D: D3D12_TEX2D_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    PlaneSlice: plane,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        ),
+                        TextureType::Type3D => (
+                            D3D12_SRV_DIMENSION_TEXTURE3D,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Texture3D: D3D12_TEX3D_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        ),
+                    };
+
+                    let desc = D3D12_SHADER_RESOURCE_VIEW_DESC {
+                        Format: format,
+                        ViewDimension: dimension,
+                        Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                        Anonymous: view,
+                    };
+                    ctx.device.CreateShaderResourceView(
+                        &texture.resource,
+                        Some(&desc),
+                        resource_handle,
+                    );
+                    self.write_sampler(ctx, &heaps, layout, idx, update.array_element, sampler);
+
+                    // Sampled depth textures must also be readable as depth
+                    if texture.format.is_depth() {
+                        state |= D3D12_RESOURCE_STATE_DEPTH_READ;
+                    }
+
+                    BoundValue::Texture {
+                        _ref_counter: texture.ref_counter.clone(),
+                        resource: texture.resource.clone(),
+                        states: texture.states.clone(),
+                        usage: texture.usage.clone(),
+                        mips: base_mip..(base_mip + mip_count),
+                        layers: texture_layers(texture.ty, array_element as usize),
+                    }
+                }
+                DescriptorValue::CubeMap {
+                    cube_map,
+                    array_element,
+                    sampler,
+                    base_mip,
+                    mip_count,
+                } => {
+                    let cube_map = cube_map.internal();
+                    let base_mip = *base_mip as u32;
+                    let mip_count = *mip_count as u32;
+                    let array_element = *array_element as u32;
+
+                    let (dimension, view) = if cube_map.array_elements > 1 {
+                        (
+                            D3D12_SRV_DIMENSION_TEXTURECUBEARRAY,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                TextureCubeArray: D3D12_TEXCUBE_ARRAY_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    First2DArrayFace: array_element * 6,
+                                    NumCubes: 1,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        )
+                    } else {
+                        (
+                            D3D12_SRV_DIMENSION_TEXTURECUBE,
+                            D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                TextureCube: D3D12_TEXCUBE_SRV {
+                                    MostDetailedMip: base_mip,
+                                    MipLevels: mip_count,
+                                    ResourceMinLODClamp: 0.0,
+                                },
+                            },
+                        )
+                    };
+
+                    let desc = D3D12_SHADER_RESOURCE_VIEW_DESC {
+                        Format: crate::util::to_dxgi_srv_format(cube_map.format),
+                        ViewDimension: dimension,
+                        Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                        Anonymous: view,
+                    };
+                    ctx.device.CreateShaderResourceView(
+                        &cube_map.resource,
+                        Some(&desc),
+                        resource_handle,
+                    );
+                    self.write_sampler(ctx, &heaps, layout, idx, update.array_element, sampler);
+
+                    if cube_map.format.is_depth() {
+                        state |= D3D12_RESOURCE_STATE_DEPTH_READ;
+                    }
+
+                    BoundValue::Texture {
+                        _ref_counter: cube_map.ref_counter.clone(),
+                        resource: cube_map.resource.clone(),
+                        states: cube_map.states.clone(),
+                        usage: cube_map.usage.clone(),
+                        mips: base_mip..(base_mip + mip_count),
+                        layers: (array_element * 6)..((array_element + 1) * 6),
+                    }
+                }
+                DescriptorValue::CubeMapArray {
+                    cube_map,
+                    array_elements,
+                    sampler,
+                    base_mip,
+                    mip_count,
+                } => {
+                    let cube_map = cube_map.internal();
+                    let base_mip = *base_mip as u32;
+                    let mip_count = *mip_count as u32;
+                    let first = array_elements.start as u32;
+                    let count = array_elements.len() as u32;
+
+                    let desc = D3D12_SHADER_RESOURCE_VIEW_DESC {
+                        Format: crate::util::to_dxgi_srv_format(cube_map.format),
+                        ViewDimension: D3D12_SRV_DIMENSION_TEXTURECUBEARRAY,
+                        Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                        Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                            TextureCubeArray: D3D12_TEXCUBE_ARRAY_SRV {
+                                MostDetailedMip: base_mip,
+                                MipLevels: mip_count,
+                                First2DArrayFace: first * 6,
+                                NumCubes: count,
+                                ResourceMinLODClamp: 0.0,
+                            },
+                        },
+                    };
+                    ctx.device.CreateShaderResourceView(
+                        &cube_map.resource,
+                        Some(&desc),
+                        resource_handle,
+                    );
+                    self.write_sampler(ctx, &heaps, layout, idx, update.array_element, sampler);
+
+                    if cube_map.format.is_depth() {
+                        state |= D3D12_RESOURCE_STATE_DEPTH_READ;
+                    }
+
+                    BoundValue::Texture {
+                        _ref_counter: cube_map.ref_counter.clone(),
+                        resource: cube_map.resource.clone(),
+                        states: cube_map.states.clone(),
+                        usage: cube_map.usage.clone(),
+                        mips: base_mip..(base_mip + mip_count),
+                        layers: (first * 6)..((first + count) * 6),
+                    }
+                }
+                DescriptorValue::TopLevelAccelerationStructure(_) => {
+                    ard_log::warn!("acceleration structures are not supported with DirectX 12");
+                    continue;
+                }
+                DescriptorValue::UniformTexelBuffer(view)
+                | DescriptorValue::StorageTexelBuffer(view) => {
+                    let buffer = view.buffer().internal();
+                    let texel_size = view.format().bytes_per_block() as u64;
+                    let first_element =
+                        (buffer.offset(view.array_element()) + view.offset()) / texel_size;
+                    let num_elements = (view.range() / texel_size) as u32;
+                    let format = crate::util::to_dxgi_format(view.format());
+
+                    if let DescriptorValue::StorageTexelBuffer(_) = &update.value {
+                        let desc = D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                            Format: format,
+                            ViewDimension: D3D12_UAV_DIMENSION_BUFFER,
+                            Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                                Buffer: D3D12_BUFFER_UAV {
+                                    FirstElement: first_element,
+                                    NumElements: num_elements,
+                                    StructureByteStride: 0,
+                                    CounterOffsetInBytes: 0,
+                                    Flags: D3D12_BUFFER_UAV_FLAG_NONE,
+                                },
+                            },
+                        };
+                        ctx.device.CreateUnorderedAccessView(
+                            &buffer.resource,
+                            None,
+                            Some(&desc),
+                            resource_handle,
+                        );
+                    } else {
+                        let desc = D3D12_SHADER_RESOURCE_VIEW_DESC {
+                            Format: format,
+                            ViewDimension: D3D12_SRV_DIMENSION_BUFFER,
+                            Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                            Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                                Buffer: D3D12_BUFFER_SRV {
+                                    FirstElement: first_element,
+                                    NumElements: num_elements,
+                                    StructureByteStride: 0,
+                                    Flags: D3D12_BUFFER_SRV_FLAG_NONE,
+                                },
+                            },
+                        };
+                        ctx.device.CreateShaderResourceView(
+                            &buffer.resource,
+                            Some(&desc),
+                            resource_handle,
+                        );
+                    }
+
+                    buffer_binding(buffer)
+                }
+            };
+
+            // It's safe to drop the old value now if the set is not being used by any queues.
+            // Otherwise, it must outlive in-flight work.
+            let old = self.bound[idx][update.array_element].replace(Binding {
+                value: new_value,
+                state,
+            });
+            if let (true, Some(old)) = (in_use, old) {
+                replaced.push(old);
+            }
+        }
+
+        if !replaced.is_empty() {
+            let _ = self.on_drop.send(Garbage::Bindings(replaced));
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn write_sampler(
+        &self,
+        ctx: &Dx12Backend,
+        heaps: &DescriptorHeaps,
+        layout: &DescriptorSetLayout,
+        binding_idx: usize,
+        array_element: usize,
+        sampler: &api::texture::Sampler,
+    ) {
+        let offset = match layout.sampler_offsets[binding_idx] {
+            Some(offset) => offset,
+            None => return,
+        };
+        let handle = heaps
+            .samplers
+            .cpu(self.samplers.unwrap() + offset + array_element as u32);
+        ctx.device
+            .CreateSampler(&crate::util::to_d3d12_sampler_desc(sampler), handle);
+    }
+}
+
+impl Drop for DescriptorSet {
+    fn drop(&mut self) {
+        let _ = self.on_drop.send(Garbage::DescriptorSet {
+            resources: self.resources.map(|idx| (idx, self.resource_count)),
+            samplers: self.samplers.map(|idx| (idx, self.sampler_count)),
+            bindings: std::mem::take(&mut self.bound),
+        });
+    }
+}
+
+#[inline(always)]
+fn buffer_binding(buffer: &Buffer) -> BoundValue {
+    BoundValue::Buffer {
+        _ref_counter: buffer.ref_counter.clone(),
+        resource: buffer.resource.clone(),
+        fixed_state: buffer.fixed_state,
+        usage: buffer.usage.clone(),
+    }
+}
+
+#[inline(always)]
+fn texture_layers(ty: TextureType, array_element: usize) -> Range<u32> {
+    match ty {
+        // 3D textures only have a single "layer"
+        TextureType::Type3D => 0..1,
+        _ => (array_element as u32)..(array_element as u32 + 1),
+    }
+}
+
+/// Creates a temporary set for every pushed set in `commands`, including those within slices.
+/// Sets are keyed by the address of the command that pushed them and are destroyed once the
+/// submission using them is complete.
+pub(crate) unsafe fn push_sets(
+    ctx: &Dx12Backend,
+    commands: &[Command<'_, Dx12Backend>],
+) -> FxHashMap<usize, DescriptorSet> {
+    let mut sets = FxHashMap::default();
+    let mut prepare = |command: &Command<'_, Dx12Backend>| {
+        let (layout, bindings) = match command {
+            Command::PushDescriptorSet {
+                layout, bindings, ..
+            } => (layout, bindings),
+            _ => return,
+        };
+
+        let mut set = DescriptorSet::new(
+            &mut ctx.heaps.lock().unwrap(),
+            ctx.garbage.sender(),
+            DescriptorSetCreateInfo {
+                layout: layout.clone(),
+                debug_name: None,
+                variable_count: None,
+            },
+        )
+        .unwrap();
+        set.update(ctx, layout.internal(), bindings);
+        sets.insert(command as *const _ as usize, set);
+    };
+
+    for command in commands {
+        match command {
+            Command::RenderPassSlices(slices) => slices.iter().flatten().for_each(&mut prepare),
+            command => prepare(command),
+        }
+    }
+
+    sets
+}
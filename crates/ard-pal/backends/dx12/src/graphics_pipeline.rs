@@ -0,0 +1,269 @@
+use std::{ffi::CStr, sync::Mutex};
+
+use api::{
+    graphics_pipeline::{GraphicsPipelineCreateError, GraphicsPipelineCreateInfo, ShaderStages},
+    types::{DynamicStates, FrontFace},
+};
+use crossbeam_channel::Sender;
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
+use windows::{
+    core::{HSTRING, PCSTR},
+    Win32::Graphics::{Direct3D::*, Direct3D12::*, Dxgi::Common::*},
+};
+
+use crate::util::{garbage_collector::Garbage, root_signature::RootSignature};
+
+/// Semantic name used for every vertex attribute. The semantic index is the attributes location.
+const VERTEX_SEMANTIC: &CStr = c"TEXCOORD";
+
+pub struct GraphicsPipeline {
+    descriptor: GraphicsPipelineCreateInfo<crate::Dx12Backend>,
+    pub(crate) root_signature: RootSignature,
+    /// Vertex stride of each vertex binding, indexed by binding.
+    pub(crate) strides: Vec<u32>,
+    pub(crate) topology: D3D_PRIMITIVE_TOPOLOGY,
+    /// Stencil reference set when the pipeline is bound. `None` if stencil testing is disabled or
+    /// the reference is dynamic.
+    pub(crate) stencil_reference: Option<u32>,
+    /// Pipeline state objects are created lazily for each kind of render pass they are used in.
+    pipelines: Mutex<FxHashMap<RenderPassKey, ID3D12PipelineState>>,
+    garbage: Sender<Garbage>,
+}
+
+/// Everything about a render pass that must be known when creating a pipeline state object.
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
+pub(crate) struct RenderPassKey {
+    pub rtv_formats: SmallVec<[i32; 8]>,
+    pub dsv_format: i32,
+    pub samples: u32,
+    pub read_only_depth: bool,
+}
+
+unsafe impl Send for GraphicsPipeline {}
+unsafe impl Sync for GraphicsPipeline {}
+
+impl GraphicsPipeline {
+    pub(crate) unsafe fn new(
+        device: &ID3D12Device,
+        garbage: Sender<Garbage>,
+        descriptor: GraphicsPipelineCreateInfo<crate::Dx12Backend>,
+    ) -> Result<Self, GraphicsPipelineCreateError> {
+        if matches!(descriptor.stages, ShaderStages::MeshShading { .. }) {
+            return Err(GraphicsPipelineCreateError::Other(String::from(
+                "mesh shading is not supported by the DirectX 12 backend",
+            )));
+        }
+
+        let root_signature = match RootSignature::new(
+            device,
+            &descriptor.layouts,
+            descriptor.push_constants_size,
+            true,
+        ) {
+            Ok(root_signature) => root_signature,
+            Err(err) => return Err(GraphicsPipelineCreateError::Other(err)),
+        };
+
+        let binding_count = descriptor
+            .vertex_input
+            .bindings
+            .iter()
+            .map(|binding| binding.binding as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut strides = vec![0; binding_count];
+        for binding in &descriptor.vertex_input.bindings {
+            strides[binding.binding as usize] = binding.stride;
+        }
+
+        // D3D12 has no static stencil reference, so it is set on bind instead
+        let stencil_reference = match &descriptor.depth_stencil {
+            Some(depth_stencil)
+                if depth_stencil.stencil_test
+                    && !descriptor
+                        .dynamic_states
+                        .contains(DynamicStates::STENCIL_REFERENCE) =>
+            {
+                Some(depth_stencil.stencil_front.reference)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            strides,
+            topology: crate::util::to_d3d_topology(descriptor.vertex_input.topology),
+            stencil_reference,
+            descriptor,
+            root_signature,
+            pipelines: Mutex::new(FxHashMap::default()),
+            garbage,
+        })
+    }
+
+    /// Retrieves a pipeline state object compatible with a render pass, or creates a new one if
+    /// needed.
+    pub(crate) unsafe fn get(
+        &self,
+        device: &ID3D12Device,
+        render_pass: &RenderPassKey,
+    ) -> ID3D12PipelineState {
+        let mut pipelines = self.pipelines.lock().unwrap();
+        if let Some(pipeline) = pipelines.get(render_pass) {
+            return pipeline.clone();
+        }
+
+        // Need to create a new pipeline
+        let mut elements = Vec::with_capacity(self.descriptor.vertex_input.attributes.len());
+        for attribute in &self.descriptor.vertex_input.attributes {
+            let binding = self
+                .descriptor
+                .vertex_input
+                .bindings
+                .iter()
+                .find(|binding| binding.binding == attribute.binding)
+                .expect("vertex attribute references an invalid binding");
+
+            elements.push(D3D12_INPUT_ELEMENT_DESC {
+                SemanticName: PCSTR(VERTEX_SEMANTIC.as_ptr() as *const u8),
+                SemanticIndex: attribute.location,
+                Format: crate::util::to_dxgi_format(attribute.format),
+                InputSlot: attribute.binding,
+                AlignedByteOffset: attribute.offset,
+                InputSlotClass: crate::util::to_d3d12_input_classification(binding.input_rate),
+                InstanceDataStepRate: match binding.input_rate {
+                    api::types::VertexInputRate::Vertex => 0,
+                    api::types::VertexInputRate::Instance => 1,
+                },
+            });
+        }
+
+        let (vertex, fragment) = match &self.descriptor.stages {
+            ShaderStages::Traditional { vertex, fragment } => (vertex, fragment),
+            ShaderStages::MeshShading { .. } => unreachable!("rejected on creation"),
+        };
+        let vertex = &vertex.internal().code;
+        let fragment = fragment.as_ref().map(|fragment| &fragment.internal().code);
+
+        let rasterizer = D3D12_RASTERIZER_DESC {
+            FillMode: crate::util::to_d3d12_fill_mode(self.descriptor.rasterization.polygon_mode),
+            CullMode: crate::util::to_d3d12_cull_mode(self.descriptor.rasterization.cull_mode),
+            FrontCounterClockwise: (self.descriptor.rasterization.front_face
+                == FrontFace::CounterClockwise)
+                .into(),
+            DepthBias: 0,
+            DepthBiasClamp: 0.0,
+            SlopeScaledDepthBias: 0.0,
+            DepthClipEnable: match &self.descriptor.depth_stencil {
+                Some(depth_stencil) => !depth_stencil.depth_clamp,
+                None => true,
+            }
+            .into(),
+            MultisampleEnable: (render_pass.samples > 1).into(),
+            AntialiasedLineEnable: false.into(),
+            ForcedSampleCount: 0,
+            ConservativeRaster: D3D12_CONSERVATIVE_RASTERIZATION_MODE_OFF,
+        };
+
+        let depth_stencil = match &self.descriptor.depth_stencil {
+            Some(depth_stencil) => D3D12_DEPTH_STENCIL_DESC {
+                DepthEnable: depth_stencil.depth_test.into(),
+                DepthWriteMask: if depth_stencil.depth_write && !render_pass.read_only_depth {
+                    D3D12_DEPTH_WRITE_MASK_ALL
+                } else {
+                    D3D12_DEPTH_WRITE_MASK_ZERO
+                },
+                DepthFunc: crate::util::to_d3d12_compare_op(depth_stencil.depth_compare),
+                // D3D12 shares stencil masks between faces, so the front face masks are used
+                StencilEnable: depth_stencil.stencil_test.into(),
+                StencilReadMask: depth_stencil.stencil_front.compare_mask as u8,
+                StencilWriteMask: if render_pass.read_only_depth {
+                    0
+                } else {
+                    depth_stencil.stencil_front.write_mask as u8
+                },
+                FrontFace: crate::util::to_d3d12_stencil_op_desc(&depth_stencil.stencil_front),
+                BackFace: crate::util::to_d3d12_stencil_op_desc(&depth_stencil.stencil_back),
+            },
+            None => D3D12_DEPTH_STENCIL_DESC::default(),
+        };
+
+        let mut blend = D3D12_BLEND_DESC::default();
+        for (i, attachment) in self.descriptor.color_blend.attachments.iter().enumerate() {
+            blend.RenderTarget[i] = D3D12_RENDER_TARGET_BLEND_DESC {
+                BlendEnable: attachment.blend.into(),
+                LogicOpEnable: false.into(),
+                SrcBlend: crate::util::to_d3d12_blend(attachment.src_color_blend_factor),
+                DestBlend: crate::util::to_d3d12_blend(attachment.dst_color_blend_factor),
+                BlendOp: crate::util::to_d3d12_blend_op(attachment.color_blend_op),
+                SrcBlendAlpha: crate::util::to_d3d12_blend(attachment.src_alpha_blend_factor),
+                DestBlendAlpha: crate::util::to_d3d12_blend(attachment.dst_alpha_blend_factor),
+                BlendOpAlpha: crate::util::to_d3d12_blend_op(attachment.alpha_blend_op),
+                LogicOp: D3D12_LOGIC_OP_NOOP,
+                RenderTargetWriteMask: crate::util::to_d3d12_write_mask(attachment.write_mask),
+            };
+        }
+        blend.IndependentBlendEnable = (self.descriptor.color_blend.attachments.len() > 1).into();
+
+        let mut rtv_formats = [DXGI_FORMAT_UNKNOWN; 8];
+        for (i, format) in render_pass.rtv_formats.iter().enumerate() {
+            rtv_formats[i] = DXGI_FORMAT(*format);
+        }
+
+        let desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+            pRootSignature: std::mem::transmute_copy(&self.root_signature.signature),
+            VS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: vertex.as_ptr() as *const _,
+                BytecodeLength: vertex.len(),
+            },
+            PS: match fragment {
+                Some(fragment) => D3D12_SHADER_BYTECODE {
+                    pShaderBytecode: fragment.as_ptr() as *const _,
+                    BytecodeLength: fragment.len(),
+                },
+                None => D3D12_SHADER_BYTECODE::default(),
+            },
+            BlendState: blend,
+            SampleMask: u32::MAX,
+            RasterizerState: rasterizer,
+            DepthStencilState: depth_stencil,
+            InputLayout: D3D12_INPUT_LAYOUT_DESC {
+                pInputElementDescs: elements.as_ptr(),
+                NumElements: elements.len() as u32,
+            },
+            IBStripCutValue: D3D12_INDEX_BUFFER_STRIP_CUT_VALUE_DISABLED,
+            PrimitiveTopologyType: crate::util::to_d3d12_topology_type(
+                self.descriptor.vertex_input.topology,
+            ),
+            NumRenderTargets: render_pass.rtv_formats.len() as u32,
+            RTVFormats: rtv_formats,
+            DSVFormat: DXGI_FORMAT(render_pass.dsv_format),
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: render_pass.samples,
+                Quality: 0,
+            },
+            ..Default::default()
+        };
+
+        let pipeline: ID3D12PipelineState = device.CreateGraphicsPipelineState(&desc).unwrap();
+
+        // Name the pipeline if requested
+        if let Some(name) = &self.descriptor.debug_name {
+            let _ = pipeline.SetName(&HSTRING::from(format!("{}_{}", name, pipelines.len())));
+        }
+
+        pipelines.insert(render_pass.clone(), pipeline.clone());
+        pipeline
+    }
+}
+
+impl Drop for GraphicsPipeline {
+    fn drop(&mut self) {
+        for (_, pipeline) in self.pipelines.get_mut().unwrap().drain() {
+            let _ = self.garbage.send(Garbage::PipelineState(pipeline));
+        }
+        let _ = self.garbage.send(Garbage::RootSignature(
+            self.root_signature.signature.clone(),
+        ));
+    }
+}
@@ -0,0 +1,9 @@
+use api::types::QueueType;
+
+pub struct Job {
+    pub(crate) ty: QueueType,
+    pub(crate) target_value: u64,
+    /// Debug name of the submission, used to report which submission faulted if the device is
+    /// removed while waiting on the job.
+    pub(crate) debug_name: Option<String>,
+}
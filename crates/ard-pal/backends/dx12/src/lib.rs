@@ -0,0 +1,2927 @@
+#![cfg(windows)]
+
+//! DirectX 12 backend for Pal.
+//!
+//! # Shader Interface
+//!
+//! Shaders must be compiled ahead of time into DXIL containers. Resources are bound using the
+//! following conventions:
+//!
+//! - Binding `N` of descriptor set `S` lives at register `N` of space `S`. The register class
+//!   (`b`, `t`, or `u`) depends on the descriptor type.
+//! - Textures and cube maps have their sampler at register `sN` of space `S`.
+//! - Push constants are a constant buffer at register `b0` of space `1024`.
+//! - Vertex attributes use the `TEXCOORD` semantic with their location as the semantic index.
+//! - Textures with a single array element are viewed as `Texture2D` instead of
+//!   `Texture2DArray`.
+//!
+//! Ray tracing and mesh shading are not supported. Check
+//! [`GraphicsFeatures`](api::context::GraphicsFeatures) before using them.
+//!
+//! Textures and cube maps are cleared through their render target and depth stencil views, so
+//! they must also have [`COLOR_ATTACHMENT`](api::types::TextureUsage::COLOR_ATTACHMENT) or
+//! [`DEPTH_STENCIL_ATTACHMENT`](api::types::TextureUsage::DEPTH_STENCIL_ATTACHMENT) usage to be
+//! cleared.
+
+use api::{
+    blas::{
+        BottomLevelAccelerationStructureCreateError, BottomLevelAccelerationStructureCreateInfo,
+    },
+    buffer::{BufferCreateError, BufferCreateInfo, BufferViewError},
+    command_buffer::{BlitDestination, BlitSource, Command},
+    compute_pass::ComputePassDispatch,
+    compute_pipeline::{ComputePipelineCreateError, ComputePipelineCreateInfo},
+    context::{
+        AdapterInfo, AdapterType, DeviceLimits, GarbageReport, GraphicsFeatures,
+        GraphicsProperties, MemoryAllocationInfo, MemoryBudget, MemoryPressureCallback,
+        MemoryStats, PipelineCacheSaveError, SampleCountProperties,
+    },
+    cube_map::{CubeMapCreateError, CubeMapCreateInfo},
+    descriptor_set::{
+        DescriptorSetCreateError, DescriptorSetCreateInfo, DescriptorSetLayoutCreateError,
+        DescriptorSetLayoutCreateInfo, DescriptorSetUpdate,
+    },
+    graphics_pipeline::{GraphicsPipelineCreateError, GraphicsPipelineCreateInfo},
+    queue::{SubmitError, SurfacePresentFailure},
+    render_pass::{
+        ColorAttachmentDestination, DepthStencilAttachmentDestination, RenderPassDescriptor,
+    },
+    rt_pipeline::{
+        RayTracingPipelineCreateError, RayTracingPipelineCreateInfo, ShaderBindingTableData,
+    },
+    shader::{ShaderCreateError, ShaderCreateInfo},
+    surface::{
+        SurfaceCapabilities, SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo,
+        SurfaceImageAcquireError, SurfacePresentSuccess, SurfaceProperties, SurfaceUpdateError,
+    },
+    texture::{TextureCreateError, TextureCreateInfo},
+    tlas::{TopLevelAccelerationStructureCreateError, TopLevelAccelerationStructureCreateInfo},
+    types::*,
+    Backend,
+};
+use buffer::Buffer;
+use compute_pipeline::{ComputePipeline, DispatchIndirect};
+use cube_map::CubeMap;
+use descriptor_set::{BoundValue, DescriptorSet, DescriptorSetLayout};
+use gpu_allocator::{
+    d3d12::{
+        Allocator, AllocatorCreateDesc, ID3D12DeviceVersion, Resource, ResourceCategory,
+        ResourceCreateDesc, ResourceStateOrBarrierLayout, ResourceType,
+    },
+    MemoryLocation,
+};
+use graphics_pipeline::{GraphicsPipeline, RenderPassKey};
+use job::Job;
+use queue::Dx12Queue;
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use render_pass::{DrawIndexedIndirect, DrawIndirect};
+use rustc_hash::FxHashMap;
+use shader::Shader;
+use smallvec::SmallVec;
+use std::{
+    ffi::c_void,
+    mem::ManuallyDrop,
+    ops::{Range, Shr},
+    ptr::NonNull,
+    sync::{atomic::AtomicU64, Mutex, OnceLock},
+};
+use surface::{Surface, SurfaceImage};
+use texture::Texture;
+use thiserror::Error;
+use util::{
+    command_signatures::CommandSignatures,
+    descriptor_heap::DescriptorHeaps,
+    garbage_collector::{Garbage, GarbageCleanupArgs, GarbageCollector, TimelineValues},
+    root_signature::RootSignature,
+    states::{Barriers, SubresourceStates},
+    usage::UsageTracker,
+};
+use windows::{
+    core::{Interface, HSTRING},
+    Win32::{
+        Foundation::{BOOL, RECT},
+        Graphics::{Direct3D::*, Direct3D12::*, Dxgi::Common::*, Dxgi::*},
+    },
+};
+
+pub mod buffer;
+pub mod compute_pipeline;
+pub mod cube_map;
+pub mod descriptor_set;
+pub mod graphics_pipeline;
+pub mod job;
+pub mod queue;
+pub mod render_pass;
+pub mod shader;
+pub mod surface;
+pub mod texture;
+pub mod util;
+
+pub struct Dx12BackendCreateInfo {
+    /// Enables the D3D12 debug layer.
+    pub debug: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Dx12BackendCreateError {
+    #[error("d3d12 error: {0}")]
+    Dx12(windows::core::Error),
+    #[error("no suitable graphics device was found")]
+    NoDevice,
+    #[error("allocator error: {0}")]
+    Allocator(String),
+}
+
+pub struct Dx12Backend {
+    pub(crate) factory: IDXGIFactory4,
+    pub(crate) _adapter: IDXGIAdapter1,
+    pub(crate) device: ID3D12Device,
+    pub(crate) graphics_properties: GraphicsProperties,
+    /// Indicates that the display supports variable refresh rates, so immediate presentation is
+    /// possible.
+    pub(crate) allow_tearing: bool,
+    pub(crate) main: Mutex<Dx12Queue>,
+    pub(crate) transfer: Mutex<Dx12Queue>,
+    pub(crate) compute: Mutex<Dx12Queue>,
+    pub(crate) allocator: ManuallyDrop<Mutex<Allocator>>,
+    pub(crate) heaps: Mutex<DescriptorHeaps>,
+    pub(crate) garbage: GarbageCollector,
+    pub(crate) command_signatures: Mutex<CommandSignatures>,
+    pub(crate) surface_ids: AtomicU64,
+    /// Level reported by `Context::default_anisotropy`.
+    pub(crate) default_anisotropy: Mutex<Option<AnisotropyLevel>>,
+    /// Set the first time the device is detected as removed. Nothing is submitted afterwards.
+    pub(crate) device_lost: OnceLock<SubmitError>,
+}
+
+unsafe impl Send for Dx12Backend {}
+unsafe impl Sync for Dx12Backend {}
+
+impl Backend for Dx12Backend {
+    type Buffer = Buffer;
+    type Texture = Texture;
+    type CubeMap = CubeMap;
+    type Surface = Surface;
+    type SurfaceImage = SurfaceImage;
+    type Shader = Shader;
+    type GraphicsPipeline = GraphicsPipeline;
+    type ComputePipeline = ComputePipeline;
+    type RayTracingPipeline = ();
+    type DescriptorSetLayout = DescriptorSetLayout;
+    type DescriptorSet = DescriptorSet;
+    type Job = Job;
+    type CommandCache = ();
+    type BottomLevelAccelerationStructure = ();
+    type TopLevelAccelerationStructure = ();
+    type DrawIndirect = DrawIndirect;
+    type DrawIndexedIndirect = DrawIndexedIndirect;
+    type DispatchIndirect = DispatchIndirect;
+
+    unsafe fn enumerate_adapters() -> Vec<AdapterInfo> {
+        let factory: IDXGIFactory4 = match CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(0)) {
+            Ok(factory) => factory,
+            Err(_) => return Vec::default(),
+        };
+
+        let mut adapters = Vec::default();
+        for i in 0.. {
+            let adapter = match factory.EnumAdapters1(i) {
+                Ok(adapter) => adapter,
+                Err(_) => break,
+            };
+            if let Ok(desc) = adapter.GetDesc1() {
+                adapters.push(Self::adapter_info(&desc));
+            }
+        }
+        adapters
+    }
+
+    #[inline(always)]
+    unsafe fn properties(&self) -> &GraphicsProperties {
+        &self.graphics_properties
+    }
+
+    unsafe fn save_pipeline_cache(&self) -> Result<(), PipelineCacheSaveError> {
+        // Pipelines aren't cached by this backend, so there is nothing to save
+        Ok(())
+    }
+
+    unsafe fn precompile_graphics_pipelines(
+        &self,
+        _pipelines: &[&Self::GraphicsPipeline],
+        _render_pass: &RenderPassDescriptor<Self>,
+    ) {
+        // Pipeline state objects are always created when first used, regardless of compile mode
+    }
+
+    unsafe fn pending_pipeline_compiles(&self) -> usize {
+        0
+    }
+
+    unsafe fn memory_stats(&self) -> MemoryStats {
+        // Allocations aren't tracked by this backend
+        MemoryStats::default()
+    }
+
+    unsafe fn memory_allocations(&self) -> Vec<MemoryAllocationInfo> {
+        Vec::default()
+    }
+
+    unsafe fn memory_budget(&self) -> MemoryBudget {
+        // Budgets aren't queried by this backend
+        MemoryBudget::default()
+    }
+
+    unsafe fn on_memory_pressure(&self, _threshold: f32, _callback: MemoryPressureCallback) {
+        // Budgets aren't queried by this backend, so pressure is never detected
+    }
+
+    unsafe fn collect_garbage(&self) {
+        // Resources are released when dropped by this backend
+    }
+
+    unsafe fn pending_garbage(&self) -> GarbageReport {
+        GarbageReport::default()
+    }
+
+    #[inline(always)]
+    unsafe fn default_anisotropy(&self) -> Option<AnisotropyLevel> {
+        *self.default_anisotropy.lock().unwrap()
+    }
+
+    #[inline(always)]
+    unsafe fn set_default_anisotropy(&self, level: Option<AnisotropyLevel>) {
+        // Every D3D12 device supports the highest level, so nothing else needs to change
+        *self.default_anisotropy.lock().unwrap() = level;
+    }
+
+    // Frame captures are only supported by the Vulkan backend
+    #[inline(always)]
+    unsafe fn trigger_capture(&self) {}
+
+    #[inline(always)]
+    unsafe fn begin_capture(&self) {}
+
+    #[inline(always)]
+    unsafe fn end_capture(&self) {}
+
+    #[inline(always)]
+    unsafe fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
+        &self,
+        create_info: SurfaceCreateInfo<W>,
+    ) -> Result<(Self::Surface, SurfaceProperties), SurfaceCreateError> {
+        Surface::new(self, create_info)
+    }
+
+    #[inline(always)]
+    unsafe fn destroy_surface(&self, surface: &mut Self::Surface) {
+        surface.destroy(self);
+    }
+
+    #[inline(always)]
+    unsafe fn update_surface(
+        &self,
+        surface: &mut Self::Surface,
+        config: SurfaceConfiguration,
+    ) -> Result<SurfaceProperties, SurfaceUpdateError> {
+        surface.update_config(self, config)
+    }
+
+    unsafe fn get_surface_capabilities(&self, _surface: &Self::Surface) -> SurfaceCapabilities {
+        let mut present_modes = vec![PresentMode::Fifo, PresentMode::Mailbox];
+        if self.allow_tearing {
+            present_modes.push(PresentMode::Immediate);
+        }
+
+        SurfaceCapabilities {
+            min_size: (1, 1),
+            max_size: (
+                D3D12_REQ_TEXTURE2D_U_OR_V_DIMENSION,
+                D3D12_REQ_TEXTURE2D_U_OR_V_DIMENSION,
+            ),
+            present_modes,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn acquire_image(
+        &self,
+        surface: &mut Self::Surface,
+    ) -> Result<Self::SurfaceImage, SurfaceImageAcquireError> {
+        surface.acquire_image(self)
+    }
+
+    #[inline(always)]
+    unsafe fn present_image(
+        &self,
+        surface: &Self::Surface,
+        image: &mut Self::SurfaceImage,
+    ) -> Result<SurfacePresentSuccess, SurfacePresentFailure> {
+        surface.present(self, image)
+    }
+
+    #[inline(always)]
+    unsafe fn destroy_surface_image(&self, _image: &mut Self::SurfaceImage) {
+        // Back buffers only rotate when presented, so unused images need no cleanup
+    }
+
+    unsafe fn submit_commands(
+        &self,
+        queue: QueueType,
+        debug_name: Option<&str>,
+        commands: Vec<Command<'_, Self>>,
+        _is_async: bool,
+        waits: &[&Self::Job],
+    ) -> Self::Job {
+        puffin::profile_function!();
+        self.submit_commands_inner(queue, debug_name, &commands, None, waits)
+    }
+
+    unsafe fn submit_commands_async_compute(
+        &self,
+        queue: QueueType,
+        debug_name: Option<&str>,
+        commands: Vec<Command<'_, Self>>,
+        compute_commands: Vec<Command<'_, Self>>,
+    ) -> (Self::Job, Self::Job) {
+        puffin::profile_function!();
+
+        // Submit to the primary queue first
+        let prim_job = self.submit_commands_inner(queue, debug_name, &commands, None, &[]);
+
+        // Then submit the async compute job
+        let comp_debug_name = debug_name.map(|name| format!("{name} (Async Compute)"));
+        let comp_job = self.submit_commands_inner(
+            QueueType::Compute,
+            comp_debug_name.as_deref(),
+            &compute_commands,
+            Some(&prim_job),
+            &[],
+        );
+
+        (prim_job, comp_job)
+    }
+
+    unsafe fn record_commands(
+        &self,
+        _queue: QueueType,
+        _commands: &mut Vec<Command<'_, Self>>,
+    ) -> Self::CommandCache {
+    }
+
+    unsafe fn submit_recorded_commands(
+        &self,
+        queue: QueueType,
+        debug_name: Option<&str>,
+        commands: &[Command<'_, Self>],
+        _cache: &Self::CommandCache,
+        waits: &[&Self::Job],
+    ) -> Self::Job {
+        puffin::profile_function!();
+        // Commands aren't sorted, so there is nothing to cache and they are recorded as usual
+        self.submit_commands_inner(queue, debug_name, commands, None, waits)
+    }
+
+    unsafe fn wait_on(&self, job: &Self::Job, timeout: Option<std::time::Duration>) -> JobStatus {
+        let mut queue = self.queue(job.ty).lock().unwrap();
+
+        // See if we've already synced to this value
+        if queue.cpu_sync_value() >= job.target_value {
+            return JobStatus::Complete;
+        }
+
+        // Fences report every value as reached once the device is removed, so removal must be
+        // checked before and after waiting
+        if self.check_device_removed(job.debug_name.as_deref()) {
+            return JobStatus::DeviceLost;
+        }
+
+        // Otherwise we have to wait
+        let timeout = timeout.map(|timeout| timeout.as_millis().min(u32::MAX as u128 - 1) as u32);
+        if !queue.wait(job.target_value, timeout) {
+            JobStatus::Running
+        } else if self.check_device_removed(job.debug_name.as_deref()) {
+            JobStatus::DeviceLost
+        } else {
+            queue.set_cpu_sync_value(job.target_value);
+            JobStatus::Complete
+        }
+    }
+
+    unsafe fn poll_status(&self, job: &Self::Job) -> JobStatus {
+        let queue = self.queue(job.ty).lock().unwrap();
+        if queue.cpu_sync_value() >= job.target_value {
+            JobStatus::Complete
+        } else if self.check_device_removed(job.debug_name.as_deref()) {
+            JobStatus::DeviceLost
+        } else if queue.current_timeline_value() >= job.target_value {
+            JobStatus::Complete
+        } else {
+            JobStatus::Running
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn device_lost(&self) -> Option<SubmitError> {
+        self.check_device_removed(None);
+        self.device_lost.get().cloned()
+    }
+
+    unsafe fn job_timestamps(&self, _job: &Self::Job) -> Vec<(String, std::time::Duration)> {
+        // Timestamp queries are not recorded, so there is nothing to report
+        Vec::default()
+    }
+
+    unsafe fn job_statistics(&self, _job: &Self::Job) -> Vec<(String, PipelineStatistics)> {
+        // Statistics queries are not recorded, so there is nothing to report
+        Vec::default()
+    }
+
+    #[inline(always)]
+    unsafe fn create_buffer(
+        &self,
+        create_info: BufferCreateInfo,
+    ) -> Result<Self::Buffer, BufferCreateError> {
+        Buffer::new(
+            self.garbage.sender(),
+            &mut self.allocator.lock().unwrap(),
+            create_info,
+        )
+    }
+
+    unsafe fn create_transient_buffer(
+        &self,
+        size: u64,
+        buffer_usage: BufferUsage,
+    ) -> Result<Self::Buffer, BufferCreateError> {
+        // Suballocation is not implemented for this backend yet, so transient buffers get their
+        // own allocation
+        self.create_buffer(BufferCreateInfo {
+            size,
+            array_elements: 1,
+            buffer_usage,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::NON_PRESENT,
+            sharing_mode: SharingMode::Concurrent,
+            debug_name: None,
+            memory_fallback: MemoryFallback::None,
+        })
+    }
+
+    #[inline(always)]
+    unsafe fn create_texture(
+        &self,
+        create_info: TextureCreateInfo,
+    ) -> Result<Self::Texture, TextureCreateError> {
+        // Reserved resources aren't implemented
+        if create_info.sparse {
+            return Err(TextureCreateError::SparseNotSupported);
+        }
+
+        Texture::new(
+            &self.device,
+            self.garbage.sender(),
+            &mut self.allocator.lock().unwrap(),
+            &mut self.heaps.lock().unwrap(),
+            create_info,
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn create_cube_map(
+        &self,
+        create_info: CubeMapCreateInfo,
+    ) -> Result<Self::CubeMap, CubeMapCreateError> {
+        CubeMap::new(
+            &self.device,
+            self.garbage.sender(),
+            &mut self.allocator.lock().unwrap(),
+            &mut self.heaps.lock().unwrap(),
+            create_info,
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn create_shader(
+        &self,
+        create_info: ShaderCreateInfo,
+    ) -> Result<Self::Shader, ShaderCreateError> {
+        Shader::new(create_info)
+    }
+
+    #[inline(always)]
+    unsafe fn create_graphics_pipeline(
+        &self,
+        create_info: GraphicsPipelineCreateInfo<Self>,
+    ) -> Result<Self::GraphicsPipeline, GraphicsPipelineCreateError> {
+        GraphicsPipeline::new(&self.device, self.garbage.sender(), create_info)
+    }
+
+    #[inline(always)]
+    unsafe fn create_compute_pipeline(
+        &self,
+        create_info: ComputePipelineCreateInfo<Self>,
+    ) -> Result<Self::ComputePipeline, ComputePipelineCreateError> {
+        ComputePipeline::new(&self.device, self.garbage.sender(), create_info)
+    }
+
+    #[inline(always)]
+    unsafe fn create_descriptor_set(
+        &self,
+        create_info: DescriptorSetCreateInfo<Self>,
+    ) -> Result<Self::DescriptorSet, DescriptorSetCreateError> {
+        DescriptorSet::new(
+            &mut self.heaps.lock().unwrap(),
+            self.garbage.sender(),
+            create_info,
+        )
+    }
+
+    #[inline(always)]
+    unsafe fn create_descriptor_set_layout(
+        &self,
+        create_info: DescriptorSetLayoutCreateInfo,
+    ) -> Result<Self::DescriptorSetLayout, DescriptorSetLayoutCreateError> {
+        DescriptorSetLayout::new(create_info)
+    }
+
+    unsafe fn create_bottom_level_acceleration_structure(
+        &self,
+        _create_info: BottomLevelAccelerationStructureCreateInfo<Self>,
+    ) -> Result<Self::BottomLevelAccelerationStructure, BottomLevelAccelerationStructureCreateError>
+    {
+        Err(BottomLevelAccelerationStructureCreateError::Other(
+            String::from(RAY_TRACING_UNSUPPORTED),
+        ))
+    }
+
+    unsafe fn create_top_level_acceleration_structure(
+        &self,
+        _create_info: TopLevelAccelerationStructureCreateInfo,
+    ) -> Result<Self::TopLevelAccelerationStructure, TopLevelAccelerationStructureCreateError> {
+        Err(TopLevelAccelerationStructureCreateError::Other(
+            String::from(RAY_TRACING_UNSUPPORTED),
+        ))
+    }
+
+    unsafe fn create_ray_tracing_pipeline(
+        &self,
+        _create_info: RayTracingPipelineCreateInfo<Self>,
+    ) -> Result<Self::RayTracingPipeline, RayTracingPipelineCreateError> {
+        Err(RayTracingPipelineCreateError::Other(String::from(
+            RAY_TRACING_UNSUPPORTED,
+        )))
+    }
+
+    unsafe fn destroy_buffer(&self, _buffer: &mut Self::Buffer) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_texture(&self, _id: &mut Self::Texture) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_cube_map(&self, _id: &mut Self::CubeMap) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_shader(&self, _shader: &mut Self::Shader) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_graphics_pipeline(&self, _pipeline: &mut Self::GraphicsPipeline) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_compute_pipeline(&self, _pipeline: &mut Self::ComputePipeline) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_descriptor_set(&self, _set: &mut Self::DescriptorSet) {
+        // Handled in drop
+    }
+
+    unsafe fn destroy_descriptor_set_layout(&self, _layout: &mut Self::DescriptorSetLayout) {
+        // Not needed
+    }
+
+    unsafe fn destroy_bottom_level_acceleration_structure(
+        &self,
+        _id: &mut Self::BottomLevelAccelerationStructure,
+    ) {
+        // Can't be created
+    }
+
+    unsafe fn destroy_top_level_acceleration_structure(
+        &self,
+        _id: &mut Self::TopLevelAccelerationStructure,
+    ) {
+        // Can't be created
+    }
+
+    unsafe fn destroy_ray_tracing_pipeline(&self, _id: &mut Self::RayTracingPipeline) {
+        // Can't be created
+    }
+
+    #[inline(always)]
+    unsafe fn buffer_device_ref(&self, id: &Self::Buffer, array_element: usize) -> u64 {
+        id.device_address(array_element)
+    }
+
+    #[inline(always)]
+    unsafe fn texture_size(&self, id: &Self::Texture) -> u64 {
+        id.size
+    }
+
+    #[inline(always)]
+    unsafe fn cube_map_size(&self, id: &Self::CubeMap) -> u64 {
+        id.size
+    }
+
+    unsafe fn blas_device_ref(&self, _id: &Self::BottomLevelAccelerationStructure) -> u64 {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn blas_scratch_size(&self, _id: &Self::BottomLevelAccelerationStructure) -> u64 {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn blas_compacted_size(&self, _id: &Self::BottomLevelAccelerationStructure) -> u64 {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn blas_poll_compacted_sizes(
+        &self,
+        _ids: &[&Self::BottomLevelAccelerationStructure],
+    ) -> Vec<Option<u64>> {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn blas_build_flags(
+        &self,
+        _id: &Self::BottomLevelAccelerationStructure,
+    ) -> BuildAccelerationStructureFlags {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn tlas_scratch_size(&self, _id: &Self::TopLevelAccelerationStructure) -> u64 {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn tlas_update_scratch_size(&self, _id: &Self::TopLevelAccelerationStructure) -> u64 {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn tlas_build_flags(
+        &self,
+        _id: &Self::TopLevelAccelerationStructure,
+    ) -> BuildAccelerationStructureFlags {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    unsafe fn shader_binding_table_data(
+        &self,
+        _id: &Self::RayTracingPipeline,
+    ) -> ShaderBindingTableData {
+        unreachable!("{RAY_TRACING_UNSUPPORTED}")
+    }
+
+    #[inline(always)]
+    unsafe fn map_memory(
+        &self,
+        id: &Self::Buffer,
+        idx: usize,
+    ) -> Result<(NonNull<u8>, u64), BufferViewError> {
+        id.map(self, idx)
+    }
+
+    unsafe fn unmap_memory(&self, _id: &Self::Buffer) {
+        // Host visible buffers are persistently mapped
+    }
+
+    unsafe fn flush_range(&self, _id: &Self::Buffer, _idx: usize) {
+        // Not needed because upload heaps are coherent
+    }
+
+    unsafe fn invalidate_range(&self, _id: &Self::Buffer, _idx: usize) {
+        // Not needed because readback heaps are coherent
+    }
+
+    #[inline(always)]
+    unsafe fn update_descriptor_sets(
+        &self,
+        id: &mut Self::DescriptorSet,
+        layout: &Self::DescriptorSetLayout,
+        updates: &[DescriptorSetUpdate<Self>],
+    ) {
+        id.update(self, layout, updates);
+    }
+}
+
+const RAY_TRACING_UNSUPPORTED: &str = "ray tracing is not supported by the DirectX 12 backend";
+const CLEAR_VIEWS_MISSING: &str =
+    "the DirectX 12 backend can only clear textures with attachment usage";
+
+impl Dx12Backend {
+    pub fn new(create_info: Dx12BackendCreateInfo) -> Result<Self, Dx12BackendCreateError> {
+        unsafe {
+            // Debug layer must be enabled before the device is created
+            if create_info.debug {
+                let mut debug: Option<ID3D12Debug> = None;
+                if D3D12GetDebugInterface(&mut debug).is_ok() {
+                    if let Some(debug) = debug {
+                        debug.EnableDebugLayer();
+                    }
+                }
+            }
+
+            let factory: IDXGIFactory4 = CreateDXGIFactory2(if create_info.debug {
+                DXGI_CREATE_FACTORY_DEBUG
+            } else {
+                DXGI_CREATE_FACTORY_FLAGS(0)
+            })?;
+
+            // Pick the highest performance hardware adapter that supports D3D12
+            let (adapter, device) = match Self::select_device(&factory) {
+                Some(device) => device,
+                None => return Err(Dx12BackendCreateError::NoDevice),
+            };
+
+            // Variable refresh rate displays require tearing to be allowed
+            let allow_tearing = match factory.cast::<IDXGIFactory5>() {
+                Ok(factory) => {
+                    let mut allow = BOOL(0);
+                    factory
+                        .CheckFeatureSupport(
+                            DXGI_FEATURE_PRESENT_ALLOW_TEARING,
+                            &mut allow as *mut BOOL as *mut c_void,
+                            std::mem::size_of::<BOOL>() as u32,
+                        )
+                        .is_ok()
+                        && allow.as_bool()
+                }
+                Err(_) => false,
+            };
+
+            let main = Dx12Queue::new(&device, QueueType::Main)?;
+            let transfer = Dx12Queue::new(&device, QueueType::Transfer)?;
+            let compute = Dx12Queue::new(&device, QueueType::Compute)?;
+
+            let allocator = match Allocator::new(&AllocatorCreateDesc {
+                device: ID3D12DeviceVersion::Device(device.clone()),
+                debug_settings: Default::default(),
+                allocation_sizes: Default::default(),
+            }) {
+                Ok(allocator) => allocator,
+                Err(err) => return Err(Dx12BackendCreateError::Allocator(err.to_string())),
+            };
+
+            let heaps = DescriptorHeaps::new(&device)?;
+
+            let features = GraphicsFeatures {
+                sampler_reduction: Self::min_max_filtering(&device),
+                custom_border_color: true,
+                // Supported by every feature level
+                dual_source_blend: true,
+                ..Default::default()
+            };
+            let limits = Self::device_limits(&main);
+            let adapter_info = AdapterInfo {
+                supported: true,
+                features: features.clone(),
+                ..Self::adapter_info(&adapter.GetDesc1()?)
+            };
+
+            Ok(Self {
+                factory,
+                _adapter: adapter,
+                graphics_properties: GraphicsProperties {
+                    // Feature level 11 requires 8x support for common render target and depth formats
+                    samples: SampleCountProperties {
+                        color: SampleCounts::COUNT_1
+                            | SampleCounts::COUNT_2
+                            | SampleCounts::COUNT_4
+                            | SampleCounts::COUNT_8,
+                        depth: SampleCounts::COUNT_1
+                            | SampleCounts::COUNT_2
+                            | SampleCounts::COUNT_4
+                            | SampleCounts::COUNT_8,
+                        stencil: SampleCounts::COUNT_1
+                            | SampleCounts::COUNT_2
+                            | SampleCounts::COUNT_4
+                            | SampleCounts::COUNT_8,
+                        storage: SampleCounts::COUNT_1,
+                    },
+                    formats: Self::format_support(&device),
+                    features,
+                    validation: create_info.debug,
+                    adapter: adapter_info,
+                    limits,
+                    ..Default::default()
+                },
+                allow_tearing,
+                main: Mutex::new(main),
+                transfer: Mutex::new(transfer),
+                compute: Mutex::new(compute),
+                allocator: ManuallyDrop::new(Mutex::new(allocator)),
+                heaps: Mutex::new(heaps),
+                garbage: GarbageCollector::new(),
+                command_signatures: Mutex::new(CommandSignatures::default()),
+                surface_ids: AtomicU64::new(0),
+                default_anisotropy: Mutex::new(None),
+                device_lost: OnceLock::new(),
+                device,
+            })
+        }
+    }
+
+    unsafe fn select_device(factory: &IDXGIFactory4) -> Option<(IDXGIAdapter1, ID3D12Device)> {
+        let factory6 = factory.cast::<IDXGIFactory6>().ok();
+
+        for i in 0.. {
+            let adapter: IDXGIAdapter1 = match &factory6 {
+                Some(factory) => {
+                    match factory
+                        .EnumAdapterByGpuPreference(i, DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE)
+                    {
+                        Ok(adapter) => adapter,
+                        Err(_) => break,
+                    }
+                }
+                None => match factory.EnumAdapters1(i) {
+                    Ok(adapter) => adapter,
+                    Err(_) => break,
+                },
+            };
+
+            // Skip WARP
+            let desc = match adapter.GetDesc1() {
+                Ok(desc) => desc,
+                Err(_) => continue,
+            };
+            if (desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32) != 0 {
+                continue;
+            }
+
+            let mut device: Option<ID3D12Device> = None;
+            if D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_12_0, &mut device).is_ok() {
+                if let Some(device) = device {
+                    let _ = device.SetName(&HSTRING::from("ard_device"));
+                    return Some((adapter, device));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Limits guaranteed by feature level 12. Descriptor counts aren't limited by resource binding
+    /// tier 3, so they keep the default.
+    unsafe fn device_limits(main: &Dx12Queue) -> DeviceLimits {
+        // Timestamps are reported in ticks of the queue's clock
+        let timestamp_period = match main.queue.GetTimestampFrequency() {
+            Ok(frequency) if frequency > 0 => (1_000_000_000.0 / frequency as f64) as f32,
+            _ => 1.0,
+        };
+
+        DeviceLimits {
+            // Root signatures are limited to 64 DWORDs, which are shared with the descriptor
+            // tables of each set
+            max_push_constants_size: 128,
+            max_texture_dimension_1d: D3D12_REQ_TEXTURE1D_U_DIMENSION,
+            max_texture_dimension_2d: D3D12_REQ_TEXTURE2D_U_OR_V_DIMENSION,
+            max_texture_dimension_3d: D3D12_REQ_TEXTURE3D_U_V_OR_W_DIMENSION,
+            max_cube_map_dimension: D3D12_REQ_TEXTURECUBE_DIMENSION,
+            max_texture_array_elements: D3D12_REQ_TEXTURE2D_ARRAY_AXIS_DIMENSION,
+            max_compute_work_group_count: [D3D12_CS_DISPATCH_MAX_THREAD_GROUPS_PER_DIMENSION; 3],
+            max_compute_work_group_size: [
+                D3D12_CS_THREAD_GROUP_MAX_X,
+                D3D12_CS_THREAD_GROUP_MAX_Y,
+                D3D12_CS_THREAD_GROUP_MAX_Z,
+            ],
+            max_compute_work_group_invocations: D3D12_CS_THREAD_GROUP_MAX_THREADS_PER_GROUP,
+            min_uniform_buffer_offset_alignment: D3D12_CONSTANT_BUFFER_DATA_PLACEMENT_ALIGNMENT
+                as u64,
+            min_storage_buffer_offset_alignment: D3D12_RAW_UAV_SRV_BYTE_ALIGNMENT as u64,
+            // Typed views start on a whole texel, so offsets must be a multiple of every texel
+            // size
+            min_uniform_texel_buffer_offset_alignment: 48,
+            min_storage_texel_buffer_offset_alignment: 48,
+            max_texel_buffer_elements: 1 << D3D12_REQ_BUFFER_RESOURCE_TEXEL_COUNT_2_TO_EXP,
+            // Anything less aligned is repacked through an intermediate buffer before the copy
+            optimal_buffer_copy_offset_alignment: D3D12_TEXTURE_DATA_PLACEMENT_ALIGNMENT as u64,
+            optimal_buffer_copy_row_pitch_alignment: D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as u64,
+            max_sampler_anisotropy: D3D12_REQ_MAXANISOTROPY as f32,
+            timestamp_period,
+            ..Default::default()
+        }
+    }
+
+    /// Describes an adapter. Optional features aren't known until a device is created.
+    fn adapter_info(desc: &DXGI_ADAPTER_DESC1) -> AdapterInfo {
+        let name_len = desc
+            .Description
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(desc.Description.len());
+        let software = (desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32) != 0;
+
+        AdapterInfo {
+            name: String::from_utf16_lossy(&desc.Description[..name_len]),
+            vendor_id: desc.VendorId,
+            device_id: desc.DeviceId,
+            // DXGI doesn't report whether a hardware adapter is discrete or integrated
+            ty: if software {
+                AdapterType::Cpu
+            } else {
+                AdapterType::Other
+            },
+            driver_version: String::default(),
+            vram: desc.DedicatedVideoMemory as u64,
+            // WARP is never used
+            supported: !software,
+            features: GraphicsFeatures::default(),
+        }
+    }
+
+    /// Min/max filtering is only supported by devices with tier 2 tiled resources.
+    unsafe fn min_max_filtering(device: &ID3D12Device) -> bool {
+        let mut options = D3D12_FEATURE_DATA_D3D12_OPTIONS::default();
+        device
+            .CheckFeatureSupport(
+                D3D12_FEATURE_D3D12_OPTIONS,
+                &mut options as *mut D3D12_FEATURE_DATA_D3D12_OPTIONS as *mut c_void,
+                std::mem::size_of::<D3D12_FEATURE_DATA_D3D12_OPTIONS>() as u32,
+            )
+            .is_ok()
+            && options.TiledResourcesTier.0 >= D3D12_TILED_RESOURCES_TIER_2.0
+    }
+
+    /// Finds the supported usages of every format D3D12 can represent.
+    unsafe fn format_support(device: &ID3D12Device) -> Vec<(Format, FormatSupport)> {
+        let support1 = |format: DXGI_FORMAT| {
+            let mut data = D3D12_FEATURE_DATA_FORMAT_SUPPORT {
+                Format: format,
+                ..Default::default()
+            };
+            match device.CheckFeatureSupport(
+                D3D12_FEATURE_FORMAT_SUPPORT,
+                &mut data as *mut D3D12_FEATURE_DATA_FORMAT_SUPPORT as *mut c_void,
+                std::mem::size_of::<D3D12_FEATURE_DATA_FORMAT_SUPPORT>() as u32,
+            ) {
+                Ok(_) => data.Support1,
+                Err(_) => D3D12_FORMAT_SUPPORT1_NONE,
+            }
+        };
+
+        Format::ALL
+            .iter()
+            .filter(|format| util::to_dxgi_format(**format) != DXGI_FORMAT_UNKNOWN)
+            .filter_map(|format| {
+                let typed = support1(util::to_dxgi_format(*format));
+                if !typed.contains(D3D12_FORMAT_SUPPORT1_TEXTURE2D) {
+                    return None;
+                }
+
+                // Copies are supported by every texture format
+                let mut support = FormatSupport::TRANSFER_SRC | FormatSupport::TRANSFER_DST;
+                if typed.contains(D3D12_FORMAT_SUPPORT1_RENDER_TARGET) {
+                    support |= FormatSupport::COLOR_ATTACHMENT;
+                }
+                if typed.contains(D3D12_FORMAT_SUPPORT1_DEPTH_STENCIL) {
+                    support |= FormatSupport::DEPTH_STENCIL_ATTACHMENT;
+                }
+
+                // Shaders view textures with different formats than the resource itself
+                let srv = support1(util::to_dxgi_srv_format(*format));
+                if srv.contains(D3D12_FORMAT_SUPPORT1_SHADER_LOAD) {
+                    support |= FormatSupport::SAMPLED;
+                }
+                if srv.contains(D3D12_FORMAT_SUPPORT1_SHADER_SAMPLE) {
+                    support |= FormatSupport::LINEAR_FILTER;
+                }
+                if support1(util::to_dxgi_uav_format(*format))
+                    .contains(D3D12_FORMAT_SUPPORT1_TYPED_UNORDERED_ACCESS_VIEW)
+                {
+                    support |= FormatSupport::STORAGE;
+                }
+
+                // Texel buffers are typed buffer views
+                if typed.contains(D3D12_FORMAT_SUPPORT1_BUFFER) {
+                    if typed.contains(D3D12_FORMAT_SUPPORT1_SHADER_LOAD) {
+                        support |= FormatSupport::UNIFORM_TEXEL_BUFFER;
+                    }
+                    if typed.contains(D3D12_FORMAT_SUPPORT1_TYPED_UNORDERED_ACCESS_VIEW) {
+                        support |= FormatSupport::STORAGE_TEXEL_BUFFER;
+                    }
+                }
+
+                Some((*format, support))
+            })
+            .collect()
+    }
+
+    #[inline(always)]
+    fn queue(&self, ty: QueueType) -> &Mutex<Dx12Queue> {
+        match ty {
+            QueueType::Main | QueueType::Present => &self.main,
+            QueueType::Transfer => &self.transfer,
+            QueueType::Compute => &self.compute,
+        }
+    }
+
+    unsafe fn submit_commands_inner(
+        &self,
+        queue: QueueType,
+        debug_name: Option<&str>,
+        commands: &[Command<'_, Self>],
+        async_with: Option<&Job>,
+        explicit_waits: &[&Job],
+    ) -> Job {
+        // Nothing can execute once the device is removed
+        if self.device_lost.get().is_some() {
+            return Self::lost_job(queue, debug_name);
+        }
+
+        // Pushed sets must be written before the heaps are locked
+        let pushed = descriptor_set::push_sets(self, commands);
+
+        // Lock down all neccesary objects
+        let mut allocator = self.allocator.lock().unwrap();
+        let mut heaps = self.heaps.lock().unwrap();
+        let mut signatures = self.command_signatures.lock().unwrap();
+        let mut queues = [
+            self.main.lock().unwrap(),
+            self.transfer.lock().unwrap(),
+            self.compute.lock().unwrap(),
+        ];
+
+        let queue_idx = util::queue_type_to_idx(queue);
+        let target_value = queues[queue_idx].target_timeline_value() + 1;
+
+        // Acquire a command list from the queue
+        let command_list = match queues[queue_idx].allocate_command_list(&self.device) {
+            Ok(command_list) => command_list,
+            Err(err) => {
+                self.set_device_lost(debug_name, &err);
+                return Self::lost_job(queue, debug_name);
+            }
+        };
+        let list = command_list.list.clone();
+
+        if let Some(name) = debug_name {
+            begin_event(&list, name);
+        }
+
+        list.SetDescriptorHeaps(&[
+            Some(heaps.resources.heap().clone()),
+            Some(heaps.samplers.heap().clone()),
+        ]);
+
+        // Record commands
+        let mut recorder = Recorder {
+            device: &self.device,
+            list: list.clone(),
+            queue,
+            target_value,
+            heaps: &heaps,
+            pushed: &pushed,
+            allocator: &mut allocator,
+            signatures: &mut signatures,
+            barriers: Barriers::default(),
+            waits: [0; 3],
+            temporaries: Vec::default(),
+            surface_images: Vec::default(),
+            bind_point: BindPoint::Graphics,
+            root_signature: None,
+            vertex_strides: &[],
+            render_pass: RenderPassKey::default(),
+            scissors: SmallVec::default(),
+            resolves: Vec::default(),
+        };
+
+        for command in commands {
+            recorder.record(command);
+        }
+
+        // Surface images must be returned to the present state
+        for image in std::mem::take(&mut recorder.surface_images) {
+            image.states().transition_all(
+                &mut recorder.barriers,
+                image.resource(),
+                D3D12_RESOURCE_STATE_PRESENT,
+            );
+        }
+        recorder.barriers.flush(&list);
+
+        let mut waits = recorder.waits;
+        for job in explicit_waits {
+            let i = util::queue_type_to_idx(job.ty);
+            waits[i] = waits[i].max(job.target_value);
+        }
+        let temporaries = std::mem::take(&mut recorder.temporaries);
+        std::mem::drop(recorder);
+
+        if debug_name.is_some() {
+            list.EndEvent();
+        }
+        if let Err(err) = list.Close() {
+            self.set_device_lost(debug_name, &err);
+            return Self::lost_job(queue, debug_name);
+        }
+
+        // Wait on every other queue that last used a resource in this submission
+        let mut wait_values = SmallVec::<[(ID3D12Fence, u64); 2]>::default();
+        for (i, value) in waits.into_iter().enumerate() {
+            if i == queue_idx {
+                continue;
+            }
+
+            // If we were asked to run async with the job, we wait for the work before it instead
+            let value = match async_with {
+                Some(job) if util::queue_type_to_idx(job.ty) == i && value >= job.target_value => {
+                    job.target_value - 1
+                }
+                _ => value,
+            };
+
+            if value > 0 {
+                wait_values.push((queues[i].fence().clone(), value));
+            }
+        }
+        let wait_values: SmallVec<[(&ID3D12Fence, u64); 2]> = wait_values
+            .iter()
+            .map(|(fence, value)| (fence, *value))
+            .collect();
+
+        let target_value = match queues[queue_idx].submit(command_list, &wait_values) {
+            Ok(target_value) => target_value,
+            Err(err) => {
+                self.set_device_lost(debug_name, &err);
+                return Self::lost_job(queue, debug_name);
+            }
+        };
+
+        // Temporary resources are destroyed once the submission is complete
+        let sender = self.garbage.sender();
+        for temporary in temporaries {
+            let _ = sender.send(Garbage::Temporary(temporary));
+        }
+
+        // Cleanup garbage
+        let current = TimelineValues {
+            main: queues[0].current_timeline_value(),
+            transfer: queues[1].current_timeline_value(),
+            compute: queues[2].current_timeline_value(),
+        };
+
+        let target = TimelineValues {
+            main: queues[0].target_timeline_value(),
+            transfer: queues[1].target_timeline_value(),
+            compute: queues[2].target_timeline_value(),
+        };
+
+        self.garbage.cleanup(GarbageCleanupArgs {
+            allocator: &mut allocator,
+            heaps: &mut heaps,
+            current,
+            target,
+            override_ref_counter: false,
+        });
+
+        Job {
+            ty: queue,
+            target_value,
+            debug_name: debug_name.map(String::from),
+        }
+    }
+
+    /// Records that the device was removed. Only the first submission to detect the removal is
+    /// reported.
+    fn set_device_lost(&self, debug_name: Option<&str>, err: &windows::core::Error) {
+        self.device_lost.get_or_init(|| {
+            ard_log::error!(
+                "device removed during `{}`: {err}",
+                debug_name.unwrap_or("unnamed submission")
+            );
+            SubmitError::DeviceLost {
+                debug_name: debug_name.map(String::from),
+            }
+        });
+    }
+
+    /// Checks if the device has been removed, recording it if so.
+    unsafe fn check_device_removed(&self, debug_name: Option<&str>) -> bool {
+        if self.device_lost.get().is_some() {
+            return true;
+        }
+
+        match self.device.GetDeviceRemovedReason() {
+            Ok(()) => false,
+            Err(err) => {
+                self.set_device_lost(debug_name, &err);
+                true
+            }
+        }
+    }
+
+    /// A job for work that was never submitted because the device was removed. It can never
+    /// complete.
+    #[inline(always)]
+    fn lost_job(queue: QueueType, debug_name: Option<&str>) -> Job {
+        Job {
+            ty: queue,
+            target_value: u64::MAX,
+            debug_name: debug_name.map(String::from),
+        }
+    }
+}
+
+impl Drop for Dx12Backend {
+    fn drop(&mut self) {
+        unsafe {
+            let main = self.main.get_mut().unwrap();
+            let transfer = self.transfer.get_mut().unwrap();
+            let compute = self.compute.get_mut().unwrap();
+
+            // Wait for all work to complete
+            main.wait(main.target_timeline_value(), None);
+            transfer.wait(transfer.target_timeline_value(), None);
+            compute.wait(compute.target_timeline_value(), None);
+
+            let heaps = self.heaps.get_mut().unwrap();
+            let mut allocator = self.allocator.lock().unwrap();
+
+            loop {
+                let current = TimelineValues {
+                    main: main.current_timeline_value(),
+                    transfer: transfer.current_timeline_value(),
+                    compute: compute.current_timeline_value(),
+                };
+
+                let target = TimelineValues {
+                    main: main.target_timeline_value(),
+                    transfer: transfer.target_timeline_value(),
+                    compute: compute.target_timeline_value(),
+                };
+
+                self.garbage.cleanup(GarbageCleanupArgs {
+                    allocator: &mut allocator,
+                    heaps,
+                    current,
+                    target,
+                    override_ref_counter: true,
+                });
+
+                if self.garbage.is_empty() {
+                    break;
+                }
+            }
+
+            main.release();
+            transfer.release();
+            compute.release();
+
+            std::mem::drop(allocator);
+            ManuallyDrop::drop(&mut self.allocator);
+        }
+    }
+}
+
+/// Which kind of pipeline root arguments are currently being bound to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum BindPoint {
+    Graphics,
+    Compute,
+}
+
+/// State used while recording a single command list.
+struct Recorder<'a> {
+    device: &'a ID3D12Device,
+    list: ID3D12GraphicsCommandList,
+    queue: QueueType,
+    /// The fence value the queue will have when the command list is complete.
+    target_value: u64,
+    heaps: &'a DescriptorHeaps,
+    /// Sets written by each [`Command::PushDescriptorSet`], keyed by the command's address.
+    pushed: &'a FxHashMap<usize, DescriptorSet>,
+    allocator: &'a mut Allocator,
+    signatures: &'a mut CommandSignatures,
+    barriers: Barriers,
+    /// Fence value of each queue that must be waited on before the command list can execute.
+    waits: [u64; 3],
+    /// Resources that must live until the command list is complete.
+    temporaries: Vec<Resource>,
+    /// Surface images that were written to.
+    surface_images: Vec<&'a SurfaceImage>,
+    bind_point: BindPoint,
+    root_signature: Option<&'a RootSignature>,
+    vertex_strides: &'a [u32],
+    render_pass: RenderPassKey,
+    scissors: SmallVec<[RECT; 8]>,
+    /// Resolves to perform at the end of the current render pass.
+    resolves: Vec<PendingResolve<'a>>,
+}
+
+/// A range of subresources of a texture, cube map, or surface image.
+#[derive(Clone)]
+struct TextureRange<'a> {
+    resource: &'a ID3D12Resource,
+    states: &'a SubresourceStates,
+    /// Surface images are only ever used by the main queue, so they aren't tracked.
+    usage: Option<&'a UsageTracker>,
+    mips: Range<u32>,
+    layers: Range<u32>,
+}
+
+struct AttachmentTarget<'a> {
+    range: TextureRange<'a>,
+    /// Index of the render target view or depth stencil view.
+    view: u32,
+    dims: (u32, u32),
+    format: Format,
+}
+
+struct PendingResolve<'a> {
+    src: TextureRange<'a>,
+    dst: TextureRange<'a>,
+    format: DXGI_FORMAT,
+    /// Depth resolves must use `ResolveSubresourceRegion` with an explicit mode.
+    depth_mode: Option<D3D12_RESOLVE_MODE>,
+}
+
+/// Region of a single texture subresource copied to or from a buffer.
+struct BufferTextureRegion<'a> {
+    range: TextureRange<'a>,
+    format: Format,
+    offset: (u32, u32, u32),
+    extent: (u32, u32, u32),
+}
+
+impl<'a> Recorder<'a> {
+    unsafe fn record(&mut self, command: &'a Command<'a, Dx12Backend>) {
+        match command {
+            Command::BeginRenderPass(descriptor, debug_name) => {
+                self.begin_render_pass(descriptor, *debug_name);
+            }
+            Command::EndRenderPass(debug_name) => {
+                self.end_render_pass();
+                if debug_name.is_some() {
+                    self.list.EndEvent();
+                }
+            }
+            Command::BeginComputePass(pipeline, debug_name) => {
+                if let Some(name) = debug_name {
+                    begin_event(&self.list, name);
+                }
+
+                let pipeline = pipeline.internal();
+                self.list.SetPipelineState(&pipeline.pipeline);
+                self.list
+                    .SetComputeRootSignature(&pipeline.root_signature.signature);
+                self.bind_point = BindPoint::Compute;
+                self.root_signature = Some(&pipeline.root_signature);
+            }
+            Command::Dispatch(dispatch) => {
+                match dispatch {
+                    ComputePassDispatch::Inline(x, y, z) => {
+                        self.barriers.flush(&self.list);
+                        self.list.Dispatch(*x, *y, *z);
+                    }
+                    ComputePassDispatch::Indirect {
+                        buffer,
+                        array_element,
+                        offset,
+                    } => {
+                        let buffer = buffer.internal();
+                        self.use_buffer(buffer, D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT);
+                        self.barriers.flush(&self.list);
+                        let signature = self.signatures.dispatch(self.device);
+                        self.list.ExecuteIndirect(
+                            &signature,
+                            1,
+                            &buffer.resource,
+                            buffer.offset(*array_element) + *offset,
+                            None,
+                            0,
+                        );
+                    }
+                }
+
+                // Writes must be visible to the next dispatch and whatever follows the pass
+                self.barriers.uav(None);
+            }
+            // Every dispatch is already followed by a UAV barrier
+            Command::ComputeBarrier => {}
+            Command::EndComputePass(debug_name) => {
+                self.root_signature = None;
+
+                if debug_name.is_some() {
+                    self.list.EndEvent();
+                }
+            }
+            Command::BeginRayTracingPass(..) | Command::EndRayTracingPass(..) => {
+                unreachable!("{RAY_TRACING_UNSUPPORTED}")
+            }
+            Command::BindGraphicsPipeline(pipeline) => {
+                let pipeline = pipeline.internal();
+                let pso = pipeline.get(self.device, &self.render_pass);
+                self.list.SetPipelineState(&pso);
+                self.list
+                    .SetGraphicsRootSignature(&pipeline.root_signature.signature);
+                self.list.IASetPrimitiveTopology(pipeline.topology);
+                if let Some(reference) = pipeline.stencil_reference {
+                    self.list.OMSetStencilRef(reference);
+                }
+                self.root_signature = Some(&pipeline.root_signature);
+                self.vertex_strides = &pipeline.strides;
+            }
+            Command::PushConstants { data, .. } => {
+                let param = match self.root_signature.and_then(|root| root.push_constants) {
+                    Some(param) => param,
+                    None => return,
+                };
+
+                // Root constants are set in units of 32 bits
+                let mut values = vec![0u32; data.len().div_ceil(4)];
+                bytemuck::cast_slice_mut::<u32, u8>(&mut values)[..data.len()]
+                    .copy_from_slice(data);
+
+                match self.bind_point {
+                    BindPoint::Graphics => self.list.SetGraphicsRoot32BitConstants(
+                        param,
+                        values.len() as u32,
+                        values.as_ptr() as *const c_void,
+                        0,
+                    ),
+                    BindPoint::Compute => self.list.SetComputeRoot32BitConstants(
+                        param,
+                        values.len() as u32,
+                        values.as_ptr() as *const c_void,
+                        0,
+                    ),
+                }
+            }
+            Command::TransferBufferOwnership { .. }
+            | Command::TransferTextureOwnership { .. }
+            | Command::TransferCubeMapOwnership { .. } => {
+                // Every queue is a direct queue sharing resource states, so ownership doesn't
+                // need to be transfered
+            }
+            Command::BindDescriptorSets { sets, first, .. } => {
+                self.bind_descriptor_sets(sets, *first, true);
+            }
+            Command::BindDescriptorSetsUnchecked { sets, first, .. } => {
+                self.bind_descriptor_sets(sets, *first, false);
+            }
+            Command::PushDescriptorSet { index, .. } => {
+                let set = &self.pushed[&(command as *const _ as usize)];
+                self.bind_descriptor_set(*index, set, true);
+            }
+            Command::BindVertexBuffers { first, binds } => {
+                let mut views = SmallVec::<[D3D12_VERTEX_BUFFER_VIEW; 4]>::default();
+                for (i, bind) in binds.iter().enumerate() {
+                    let buffer = bind.buffer.internal();
+                    self.use_buffer(buffer, D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER);
+                    views.push(D3D12_VERTEX_BUFFER_VIEW {
+                        BufferLocation: buffer.device_address(bind.array_element) + bind.offset,
+                        SizeInBytes: (buffer.size - bind.offset) as u32,
+                        StrideInBytes: self
+                            .vertex_strides
+                            .get(*first + i)
+                            .copied()
+                            .unwrap_or_default(),
+                    });
+                }
+                self.list.IASetVertexBuffers(*first as u32, Some(&views));
+            }
+            Command::BindIndexBuffer {
+                buffer,
+                array_element,
+                offset,
+                ty,
+            } => {
+                let buffer = buffer.internal();
+                self.use_buffer(buffer, D3D12_RESOURCE_STATE_INDEX_BUFFER);
+                self.list.IASetIndexBuffer(Some(&D3D12_INDEX_BUFFER_VIEW {
+                    BufferLocation: buffer.device_address(*array_element) + *offset,
+                    SizeInBytes: (buffer.size - *offset) as u32,
+                    Format: util::to_dxgi_index_format(*ty),
+                }));
+            }
+            Command::Scissor {
+                attachment,
+                scissor,
+            } => {
+                if let Some(rect) = self.scissors.get_mut(*attachment) {
+                    *rect = RECT {
+                        left: scissor.x,
+                        top: scissor.y,
+                        right: scissor.x + scissor.width as i32,
+                        bottom: scissor.y + scissor.height as i32,
+                    };
+                }
+                self.list.RSSetScissorRects(&self.scissors);
+            }
+            Command::SetDepthBias { .. } | Command::SetLineWidth(_) => {
+                // Neither can be set dynamically by the command lists this backend uses
+            }
+            Command::SetStencilReference(reference) => {
+                self.list.OMSetStencilRef(*reference);
+            }
+            Command::SetBlendConstants(constants) => {
+                self.list.OMSetBlendFactor(Some(constants));
+            }
+            Command::SetViewport {
+                x,
+                y,
+                width,
+                height,
+                min_depth,
+                max_depth,
+            } => {
+                self.list.RSSetViewports(&[D3D12_VIEWPORT {
+                    TopLeftX: *x,
+                    TopLeftY: *y,
+                    Width: *width,
+                    Height: *height,
+                    MinDepth: *min_depth,
+                    MaxDepth: *max_depth,
+                }]);
+            }
+            Command::Draw {
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            } => {
+                self.barriers.flush(&self.list);
+                self.list.DrawInstanced(
+                    *vertex_count as u32,
+                    *instance_count as u32,
+                    *first_vertex as u32,
+                    *first_instance as u32,
+                );
+            }
+            Command::DrawIndexed {
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                first_instance,
+            } => {
+                self.barriers.flush(&self.list);
+                self.list.DrawIndexedInstanced(
+                    *index_count as u32,
+                    *instance_count as u32,
+                    *first_index as u32,
+                    *vertex_offset as i32,
+                    *first_instance as u32,
+                );
+            }
+            Command::DrawIndirect {
+                buffer,
+                array_element,
+                offset,
+                draw_count,
+                stride,
+            } => {
+                let buffer = buffer.internal();
+                self.use_buffer(buffer, D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT);
+                self.barriers.flush(&self.list);
+                let signature = self.signatures.draw(self.device, *stride as u32);
+                self.list.ExecuteIndirect(
+                    &signature,
+                    *draw_count as u32,
+                    &buffer.resource,
+                    buffer.offset(*array_element) + *offset,
+                    None,
+                    0,
+                );
+            }
+            Command::DrawIndexedIndirect {
+                buffer,
+                array_element,
+                offset,
+                draw_count,
+                stride,
+            } => {
+                let buffer = buffer.internal();
+                self.use_buffer(buffer, D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT);
+                self.barriers.flush(&self.list);
+                let signature = self.signatures.draw_indexed(self.device, *stride as u32);
+                self.list.ExecuteIndirect(
+                    &signature,
+                    *draw_count as u32,
+                    &buffer.resource,
+                    buffer.offset(*array_element) + *offset,
+                    None,
+                    0,
+                );
+            }
+            Command::DrawIndexedIndirectCount {
+                draw_buffer,
+                draw_array_element,
+                draw_offset,
+                draw_stride,
+                count_buffer,
+                count_array_element,
+                count_offset,
+                max_draw_count,
+            } => {
+                let draw_buffer = draw_buffer.internal();
+                let count_buffer = count_buffer.internal();
+                self.use_buffer(draw_buffer, D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT);
+                self.use_buffer(count_buffer, D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT);
+                self.barriers.flush(&self.list);
+                let signature = self
+                    .signatures
+                    .draw_indexed(self.device, *draw_stride as u32);
+                self.list.ExecuteIndirect(
+                    &signature,
+                    *max_draw_count as u32,
+                    &draw_buffer.resource,
+                    draw_buffer.offset(*draw_array_element) + *draw_offset,
+                    &count_buffer.resource,
+                    count_buffer.offset(*count_array_element) + *count_offset,
+                );
+            }
+            Command::DrawMeshTasks(..)
+            | Command::DrawMeshTasksIndirect { .. }
+            | Command::DrawMeshTasksIndirectCount { .. } => {
+                unreachable!("mesh shading is not supported by the DirectX 12 backend")
+            }
+            // Slices are recorded inline, one after another
+            Command::RenderPassSlices(slices) => {
+                for command in slices.iter().flatten() {
+                    self.record(command);
+                }
+            }
+            Command::CopyBufferToBuffer(copy) => {
+                let src = copy.src.internal();
+                let dst = copy.dst.internal();
+                self.use_buffer(src, D3D12_RESOURCE_STATE_COPY_SOURCE);
+                self.use_buffer(dst, D3D12_RESOURCE_STATE_COPY_DEST);
+                self.barriers.flush(&self.list);
+                self.list.CopyBufferRegion(
+                    &dst.resource,
+                    dst.offset(copy.dst_array_element) + copy.dst_offset,
+                    &src.resource,
+                    src.offset(copy.src_array_element) + copy.src_offset,
+                    copy.len,
+                );
+            }
+            Command::FillBuffer {
+                buffer,
+                array_element,
+                offset,
+                len,
+                value,
+            } => {
+                // D3D12 has no buffer fill outside of UAV clears, so the pattern is uploaded
+                let data: Vec<u8> = std::iter::repeat_n(value.to_le_bytes(), (*len / 4) as usize)
+                    .flatten()
+                    .collect();
+                let buffer = buffer.internal();
+                self.upload_to_buffer(buffer, buffer.offset(*array_element) + *offset, &data);
+            }
+            Command::UpdateBuffer {
+                buffer,
+                array_element,
+                offset,
+                data,
+            } => {
+                let buffer = buffer.internal();
+                self.upload_to_buffer(buffer, buffer.offset(*array_element) + *offset, data);
+            }
+            Command::ClearTexture {
+                texture,
+                array_element,
+                mip_level,
+                value,
+            } => {
+                let texture = texture.internal();
+                let mip = *mip_level as u32;
+                let view = if texture.format.is_color() {
+                    assert!(!texture.rtvs.is_empty(), "{CLEAR_VIEWS_MISSING}");
+                    texture.rtv(*array_element, *mip_level)
+                } else {
+                    assert!(!texture.dsvs.is_empty(), "{CLEAR_VIEWS_MISSING}");
+                    texture.dsv(*array_element, *mip_level, false)
+                };
+                self.clear_view(
+                    TextureRange::texture(texture, *array_element, mip..(mip + 1)),
+                    texture.format,
+                    view,
+                    *value,
+                );
+            }
+            Command::ClearCubeMap {
+                cube_map,
+                array_element,
+                mip_level,
+                value,
+            } => {
+                let cube_map = cube_map.internal();
+                let mip = *mip_level as u32;
+                let view = if cube_map.format.is_color() {
+                    assert!(!cube_map.rtvs.is_empty(), "{CLEAR_VIEWS_MISSING}");
+                    cube_map.rtv(*array_element, *mip_level, None)
+                } else {
+                    assert!(!cube_map.dsvs.is_empty(), "{CLEAR_VIEWS_MISSING}");
+                    cube_map.dsv(*array_element, *mip_level, None, false)
+                };
+                self.clear_view(
+                    TextureRange::cube_map(cube_map, *array_element, None, mip..(mip + 1)),
+                    cube_map.format,
+                    view,
+                    *value,
+                );
+            }
+            // Sparse textures can't be created
+            Command::BindSparsePages { .. } | Command::UnbindSparsePages { .. } => unreachable!(),
+            Command::CopyTextureToTexture(copy) => {
+                let src = copy.src.internal();
+                let dst = copy.dst.internal();
+                let src_mip = copy.src_mip_level as u32;
+                let dst_mip = copy.dst_mip_level as u32;
+                self.copy_texture_region(
+                    TextureRange::texture(src, copy.src_array_element, src_mip..(src_mip + 1)),
+                    copy.src_offset,
+                    TextureRange::texture(dst, copy.dst_array_element, dst_mip..(dst_mip + 1)),
+                    copy.dst_offset,
+                    copy.extent,
+                );
+            }
+            Command::CopyBufferToTexture {
+                buffer,
+                texture,
+                copy,
+            }
+            | Command::CopyTextureToBuffer {
+                buffer,
+                texture,
+                copy,
+            } => {
+                let buffer = buffer.internal();
+                let tex = texture.internal();
+                let mip = copy.texture_mip_level as u32;
+                self.copy_buffer_texture(
+                    buffer,
+                    buffer.offset(copy.buffer_array_element) + copy.buffer_offset,
+                    copy.buffer_row_length,
+                    copy.buffer_image_height,
+                    BufferTextureRegion {
+                        range: TextureRange::texture(
+                            tex,
+                            copy.texture_array_element,
+                            mip..(mip + 1),
+                        ),
+                        format: tex.format,
+                        offset: copy.texture_offset,
+                        extent: copy.texture_extent,
+                    },
+                    matches!(command, Command::CopyBufferToTexture { .. }),
+                );
+            }
+            Command::CopyBufferToCubeMap {
+                buffer,
+                cube_map,
+                copy,
+            }
+            | Command::CopyCubeMapToBuffer {
+                buffer,
+                cube_map,
+                copy,
+            } => {
+                let to_cube_map = matches!(command, Command::CopyBufferToCubeMap { .. });
+                let size = cube_map.dim().shr(copy.cube_map_mip_level).max(1);
+                let buffer = buffer.internal();
+                let cube_map = cube_map.internal();
+                let mip = copy.cube_map_mip_level as u32;
+
+                // Faces are tightly packed one after another
+                let (block_size, block_dim) = util::format_block_info(cube_map.format);
+                let face_size = (size.div_ceil(block_dim) as u64).pow(2) * block_size as u64;
+                let base_offset = buffer.offset(copy.buffer_array_element) + copy.buffer_offset;
+                let faces = match &copy.face {
+                    Some(face) => std::slice::from_ref(face),
+                    None => &CUBE_FACES,
+                };
+
+                for (i, face) in faces.iter().copied().enumerate() {
+                    self.copy_buffer_texture(
+                        buffer,
+                        base_offset + (i as u64 * face_size),
+                        0,
+                        0,
+                        BufferTextureRegion {
+                            range: TextureRange::cube_map(
+                                cube_map,
+                                copy.cube_map_array_element,
+                                Some(face),
+                                mip..(mip + 1),
+                            ),
+                            format: cube_map.format,
+                            offset: (0, 0, 0),
+                            extent: (size, size, 1),
+                        },
+                        to_cube_map,
+                    );
+                }
+            }
+            Command::Blit {
+                src,
+                dst,
+                blit,
+                filter: _,
+            } => {
+                let src_mip = blit.src_mip as u32;
+                let dst_mip = blit.dst_mip as u32;
+
+                let (src_range, src_format) = match src {
+                    BlitSource::Texture(texture) => {
+                        let texture = texture.internal();
+                        (
+                            TextureRange::texture(
+                                texture,
+                                blit.src_array_element,
+                                src_mip..(src_mip + 1),
+                            ),
+                            texture.format,
+                        )
+                    }
+                    BlitSource::CubeMap { cube_map, face } => {
+                        let cube_map = cube_map.internal();
+                        (
+                            TextureRange::cube_map(
+                                cube_map,
+                                blit.src_array_element,
+                                Some(*face),
+                                src_mip..(src_mip + 1),
+                            ),
+                            cube_map.format,
+                        )
+                    }
+                };
+
+                let (dst_range, dst_format) = match dst {
+                    BlitDestination::Texture(texture) => {
+                        let texture = texture.internal();
+                        (
+                            TextureRange::texture(
+                                texture,
+                                blit.dst_array_element,
+                                dst_mip..(dst_mip + 1),
+                            ),
+                            texture.format,
+                        )
+                    }
+                    BlitDestination::CubeMap { cube_map, face } => {
+                        let cube_map = cube_map.internal();
+                        (
+                            TextureRange::cube_map(
+                                cube_map,
+                                blit.dst_array_element,
+                                Some(*face),
+                                dst_mip..(dst_mip + 1),
+                            ),
+                            cube_map.format,
+                        )
+                    }
+                    BlitDestination::SurfaceImage(image) => {
+                        let image = image.internal();
+                        image.signal_draw(self.target_value);
+                        self.surface_images.push(image);
+                        (TextureRange::surface_image(image), image.format())
+                    }
+                };
+
+                // D3D12 has no blit, so only plain copies are supported
+                let src_extent = (
+                    blit.src_max.0 - blit.src_min.0,
+                    blit.src_max.1 - blit.src_min.1,
+                    blit.src_max.2 - blit.src_min.2,
+                );
+                let dst_extent = (
+                    blit.dst_max.0 - blit.dst_min.0,
+                    blit.dst_max.1 - blit.dst_min.1,
+                    blit.dst_max.2 - blit.dst_min.2,
+                );
+                if src_extent != dst_extent
+                    || util::to_dxgi_resource_format(src_format)
+                        != util::to_dxgi_resource_format(dst_format)
+                {
+                    ard_log::warn!(
+                        "the DirectX 12 backend does not support blits that scale or convert formats"
+                    );
+                    return;
+                }
+
+                self.copy_texture_region(
+                    src_range,
+                    blit.src_min,
+                    dst_range,
+                    blit.dst_min,
+                    src_extent,
+                );
+            }
+            Command::GenerateMipMaps { .. } | Command::GenerateCubeMapMipMaps { .. } => {
+                // Downsampling requires a scaling blit, which D3D12 doesn't have
+                ard_log::warn!("the DirectX 12 backend does not support mip map generation");
+            }
+            Command::SetTextureUsage {
+                tex,
+                new_usage,
+                array_elems,
+                mips,
+            } => {
+                let mut range = TextureRange::texture(
+                    tex.internal(),
+                    array_elems.start,
+                    (mips.start as u32)..(mips.end as u32),
+                );
+                if tex.internal().ty != TextureType::Type3D {
+                    range.layers = (array_elems.start as u32)..(array_elems.end as u32);
+                }
+                self.use_texture(&range, util::to_d3d12_texture_state(*new_usage));
+            }
+            // Timestamp and statistics queries are not implemented for this backend yet
+            Command::BeginTimestamp(_)
+            | Command::EndTimestamp
+            | Command::BeginStatistics(_)
+            | Command::EndStatistics => {}
+            Command::BeginConditionalRendering { .. } => {
+                // D3D12 predication reads 64-bit values, so 32-bit predicates can't be used
+                ard_log::warn!(
+                    "the DirectX 12 backend does not support conditional rendering, executing region unconditionally"
+                );
+            }
+            Command::EndConditionalRendering => {}
+            // Plain markers have no color, so it's dropped
+            Command::InsertLabel { label, .. } => set_marker(&self.list, label),
+            // Split barriers are not implemented for this backend, so every resource is
+            // synchronized with a regular barrier right before it's used
+            Command::SignalEvent { .. } | Command::WaitEvent { .. } => {}
+            Command::BuildBlas { .. }
+            | Command::BuildBlasBatch { .. }
+            | Command::BuildTlas { .. }
+            | Command::UpdateTlas { .. }
+            | Command::WriteBlasCompactSize(_)
+            | Command::CompactBlas { .. } => {
+                unreachable!("{RAY_TRACING_UNSUPPORTED}")
+            }
+        }
+    }
+
+    unsafe fn begin_render_pass(
+        &mut self,
+        descriptor: &'a RenderPassDescriptor<'a, Dx12Backend>,
+        debug_name: Option<&str>,
+    ) {
+        assert_eq!(
+            descriptor.view_mask, 0,
+            "multiview render passes are not supported by the DirectX 12 backend"
+        );
+
+        if let Some(name) = debug_name {
+            begin_event(&self.list, name);
+        }
+
+        let mut key = RenderPassKey::default();
+        let mut dims = (0, 0);
+
+        // Color attachments
+        let mut rtvs = SmallVec::<[D3D12_CPU_DESCRIPTOR_HANDLE; 8]>::default();
+        let mut color_ranges = SmallVec::<[TextureRange<'a>; 8]>::default();
+        let mut clears = SmallVec::<[(D3D12_CPU_DESCRIPTOR_HANDLE, [f32; 4]); 8]>::default();
+        for attachment in &descriptor.color_attachments {
+            let target = self.color_target(&attachment.dst);
+            self.use_texture(&target.range, D3D12_RESOURCE_STATE_RENDER_TARGET);
+
+            let handle = self.heaps.rtv.cpu(target.view);
+            if let LoadOp::Clear(color) = attachment.load_op {
+                clears.push((handle, util::to_d3d12_clear_color(color)));
+            }
+
+            rtvs.push(handle);
+            color_ranges.push(target.range);
+            key.rtv_formats.push(util::to_dxgi_format(target.format).0);
+            key.samples = util::to_d3d12_sample_count(attachment.samples);
+            dims = target.dims;
+        }
+
+        // Depth stencil attachment
+        let mut dsv = None;
+        let mut depth_range = None;
+        let mut depth_clear = None;
+        if let Some(attachment) = &descriptor.depth_stencil_attachment {
+            // Depth attachments where neither aspect is stored are read only
+            let read_only = attachment.store_op == StoreOp::None
+                && attachment.stencil_store_op == StoreOp::None;
+            let target = self.depth_target(&attachment.dst, read_only);
+            self.use_texture(
+                &target.range,
+                if read_only {
+                    D3D12_RESOURCE_STATE_DEPTH_READ
+                } else {
+                    D3D12_RESOURCE_STATE_DEPTH_WRITE
+                },
+            );
+
+            let handle = self.heaps.dsv.cpu(target.view);
+            // Depth and stencil are cleared independently
+            let mut flags = D3D12_CLEAR_FLAGS(0);
+            let mut depth = 0.0;
+            let mut stencil = 0;
+            if let LoadOp::Clear(ClearColor::D32S32(d, _)) = attachment.load_op {
+                flags |= D3D12_CLEAR_FLAG_DEPTH;
+                depth = d;
+            }
+            if let LoadOp::Clear(ClearColor::D32S32(_, s)) = attachment.stencil_load_op {
+                if target.format.is_stencil() {
+                    flags |= D3D12_CLEAR_FLAG_STENCIL;
+                    stencil = s as u8;
+                }
+            }
+            if flags.0 != 0 {
+                depth_clear = Some((flags, depth, stencil));
+            }
+
+            dsv = Some(handle);
+            depth_range = Some(target.range);
+            key.dsv_format = util::to_dxgi_format(target.format).0;
+            key.read_only_depth = read_only;
+            key.samples = util::to_d3d12_sample_count(attachment.samples);
+            dims = target.dims;
+        }
+
+        // Resolves are performed when the pass ends
+        for attachment in &descriptor.color_resolve_attachments {
+            let target = self.color_target(&attachment.dst);
+            self.resolves.push(PendingResolve {
+                src: color_ranges[attachment.src].clone(),
+                dst: target.range,
+                format: util::to_dxgi_format(target.format),
+                depth_mode: None,
+            });
+        }
+
+        if let (Some(attachment), Some(src)) =
+            (&descriptor.depth_stencil_resolve_attachment, &depth_range)
+        {
+            let target = self.depth_target(&attachment.dst, false);
+            self.resolves.push(PendingResolve {
+                src: src.clone(),
+                dst: target.range,
+                format: util::to_dxgi_srv_format(target.format),
+                depth_mode: Some(to_d3d12_resolve_mode(attachment.depth_resolve_mode)),
+            });
+        }
+
+        self.barriers.flush(&self.list);
+        self.list.OMSetRenderTargets(
+            rtvs.len() as u32,
+            Some(rtvs.as_ptr()),
+            false,
+            dsv.as_ref().map(|dsv| dsv as *const _),
+        );
+
+        for (handle, color) in clears {
+            self.list.ClearRenderTargetView(handle, &color, None);
+        }
+
+        if let (Some(dsv), Some((flags, depth, stencil))) = (dsv, depth_clear) {
+            self.list
+                .ClearDepthStencilView(dsv, flags, depth, stencil, &[]);
+        }
+
+        // Unlike Vulkan, D3D12 has the origin in the top left so no flipping is needed
+        self.list.RSSetViewports(&[D3D12_VIEWPORT {
+            TopLeftX: 0.0,
+            TopLeftY: 0.0,
+            Width: dims.0 as f32,
+            Height: dims.1 as f32,
+            MinDepth: 0.0,
+            MaxDepth: 1.0,
+        }]);
+
+        self.scissors.clear();
+        self.scissors.resize(
+            rtvs.len().max(1),
+            RECT {
+                left: 0,
+                top: 0,
+                right: dims.0 as i32,
+                bottom: dims.1 as i32,
+            },
+        );
+        self.list.RSSetScissorRects(&self.scissors);
+
+        self.bind_point = BindPoint::Graphics;
+        self.render_pass = key;
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        for resolve in std::mem::take(&mut self.resolves) {
+            self.use_texture(&resolve.src, D3D12_RESOURCE_STATE_RESOLVE_SOURCE);
+            self.use_texture(&resolve.dst, D3D12_RESOURCE_STATE_RESOLVE_DEST);
+            self.barriers.flush(&self.list);
+
+            for (src_layer, dst_layer) in resolve.src.layers.clone().zip(resolve.dst.layers.clone())
+            {
+                let src_subresource = resolve.src.states.subresource(0, src_layer, 0);
+                let dst_subresource =
+                    resolve
+                        .dst
+                        .states
+                        .subresource(resolve.dst.mips.start, dst_layer, 0);
+
+                match resolve.depth_mode {
+                    Some(mode) => {
+                        // NOTE: Depth resolves need programmable sample positions tier 2
+                        let list = self.list.cast::<ID3D12GraphicsCommandList1>().unwrap();
+                        list.ResolveSubresourceRegion(
+                            resolve.dst.resource,
+                            dst_subresource,
+                            0,
+                            0,
+                            resolve.src.resource,
+                            src_subresource,
+                            None,
+                            resolve.format,
+                            mode,
+                        );
+                    }
+                    None => self.list.ResolveSubresource(
+                        resolve.dst.resource,
+                        dst_subresource,
+                        resolve.src.resource,
+                        src_subresource,
+                        resolve.format,
+                    ),
+                }
+            }
+        }
+
+        // Writes to storage resources must be visible to whatever comes next
+        self.barriers.uav(None);
+        self.root_signature = None;
+        self.vertex_strides = &[];
+    }
+
+    unsafe fn color_target(
+        &mut self,
+        dst: &'a ColorAttachmentDestination<'a, Dx12Backend>,
+    ) -> AttachmentTarget<'a> {
+        match dst {
+            ColorAttachmentDestination::SurfaceImage(image) => {
+                let image = image.internal();
+                image.signal_draw(self.target_value);
+                self.surface_images.push(image);
+                AttachmentTarget {
+                    range: TextureRange::surface_image(image),
+                    view: image.rtv(),
+                    dims: image.dims(),
+                    format: image.format(),
+                }
+            }
+            ColorAttachmentDestination::Texture {
+                texture,
+                array_element,
+                mip_level,
+            } => {
+                let (width, height, _) = texture.dims();
+                let texture = texture.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::texture(texture, *array_element, mip..(mip + 1)),
+                    view: texture.rtv(*array_element, *mip_level),
+                    dims: (width.shr(mip).max(1), height.shr(mip).max(1)),
+                    format: texture.format,
+                }
+            }
+            ColorAttachmentDestination::TextureSlice {
+                texture,
+                mip_level,
+                slice,
+            } => {
+                let (width, height, _) = texture.dims();
+                let texture = texture.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::texture(texture, 0, mip..(mip + 1)),
+                    view: texture.slice_rtv(*mip_level, *slice),
+                    dims: (width.shr(mip).max(1), height.shr(mip).max(1)),
+                    format: texture.format,
+                }
+            }
+            ColorAttachmentDestination::CubeFace {
+                cube_map,
+                array_element,
+                face,
+                mip_level,
+            } => {
+                let size = cube_map.dim().shr(*mip_level).max(1);
+                let cube_map = cube_map.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::cube_map(
+                        cube_map,
+                        *array_element,
+                        Some(*face),
+                        mip..(mip + 1),
+                    ),
+                    view: cube_map.rtv(*array_element, *mip_level, Some(*face)),
+                    dims: (size, size),
+                    format: cube_map.format,
+                }
+            }
+            ColorAttachmentDestination::CubeMap {
+                cube_map,
+                array_element,
+                mip_level,
+            } => {
+                let size = cube_map.dim().shr(*mip_level).max(1);
+                let cube_map = cube_map.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::cube_map(cube_map, *array_element, None, mip..(mip + 1)),
+                    view: cube_map.rtv(*array_element, *mip_level, None),
+                    dims: (size, size),
+                    format: cube_map.format,
+                }
+            }
+            // Without multiview, arrays are always a single layer
+            ColorAttachmentDestination::TextureArray {
+                texture,
+                base_layer,
+                mip_level,
+                ..
+            } => {
+                let (width, height, _) = texture.dims();
+                let texture = texture.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::texture(texture, *base_layer, mip..(mip + 1)),
+                    view: texture.rtv(*base_layer, *mip_level),
+                    dims: (width.shr(mip).max(1), height.shr(mip).max(1)),
+                    format: texture.format,
+                }
+            }
+        }
+    }
+
+    unsafe fn depth_target(
+        &mut self,
+        dst: &'a DepthStencilAttachmentDestination<'a, Dx12Backend>,
+        read_only: bool,
+    ) -> AttachmentTarget<'a> {
+        match dst {
+            DepthStencilAttachmentDestination::Texture {
+                texture,
+                array_element,
+                mip_level,
+            } => {
+                let (width, height, _) = texture.dims();
+                let texture = texture.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::texture(texture, *array_element, mip..(mip + 1)),
+                    view: texture.dsv(*array_element, *mip_level, read_only),
+                    dims: (width.shr(mip).max(1), height.shr(mip).max(1)),
+                    format: texture.format,
+                }
+            }
+            DepthStencilAttachmentDestination::CubeFace {
+                cube_map,
+                array_element,
+                face,
+                mip_level,
+            } => {
+                let size = cube_map.dim().shr(*mip_level).max(1);
+                let cube_map = cube_map.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::cube_map(
+                        cube_map,
+                        *array_element,
+                        Some(*face),
+                        mip..(mip + 1),
+                    ),
+                    view: cube_map.dsv(*array_element, *mip_level, Some(*face), read_only),
+                    dims: (size, size),
+                    format: cube_map.format,
+                }
+            }
+            DepthStencilAttachmentDestination::CubeMap {
+                cube_map,
+                array_element,
+                mip_level,
+            } => {
+                let size = cube_map.dim().shr(*mip_level).max(1);
+                let cube_map = cube_map.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::cube_map(cube_map, *array_element, None, mip..(mip + 1)),
+                    view: cube_map.dsv(*array_element, *mip_level, None, read_only),
+                    dims: (size, size),
+                    format: cube_map.format,
+                }
+            }
+            // Without multiview, arrays are always a single layer
+            DepthStencilAttachmentDestination::TextureArray {
+                texture,
+                base_layer,
+                mip_level,
+                ..
+            } => {
+                let (width, height, _) = texture.dims();
+                let texture = texture.internal();
+                let mip = *mip_level as u32;
+                AttachmentTarget {
+                    range: TextureRange::texture(texture, *base_layer, mip..(mip + 1)),
+                    view: texture.dsv(*base_layer, *mip_level, read_only),
+                    dims: (width.shr(mip).max(1), height.shr(mip).max(1)),
+                    format: texture.format,
+                }
+            }
+        }
+    }
+
+    unsafe fn bind_descriptor_sets(
+        &mut self,
+        sets: &[&'a api::descriptor_set::DescriptorSet<Dx12Backend>],
+        first: usize,
+        track: bool,
+    ) {
+        for (i, set) in sets.iter().enumerate() {
+            self.bind_descriptor_set(first + i, set.internal(), track);
+        }
+    }
+
+    unsafe fn bind_descriptor_set(&mut self, index: usize, set: &DescriptorSet, track: bool) {
+        let params = match self.root_signature.and_then(|root| root.sets.get(index)) {
+            Some(params) => *params,
+            None => return,
+        };
+
+        // Unchecked sets are expected to have their resources in the correct state already
+        if track {
+            self.use_set(set);
+        } else {
+            set.last_use
+                .track(self.queue, self.target_value, &mut self.waits);
+        }
+
+        if let (Some(param), Some(start)) = (params.resources, set.resources) {
+            self.set_descriptor_table(param, self.heaps.resources.gpu(start));
+        }
+
+        if let (Some(param), Some(start)) = (params.samplers, set.samplers) {
+            self.set_descriptor_table(param, self.heaps.samplers.gpu(start));
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn set_descriptor_table(&self, param: u32, handle: D3D12_GPU_DESCRIPTOR_HANDLE) {
+        match self.bind_point {
+            BindPoint::Graphics => self.list.SetGraphicsRootDescriptorTable(param, handle),
+            BindPoint::Compute => self.list.SetComputeRootDescriptorTable(param, handle),
+        }
+    }
+
+    /// Transitions every resource bound to a set into the state required by its binding.
+    fn use_set(&mut self, set: &DescriptorSet) {
+        set.last_use
+            .track(self.queue, self.target_value, &mut self.waits);
+
+        for binding in set.bound.iter().flatten().flatten() {
+            match &binding.value {
+                BoundValue::Buffer {
+                    resource,
+                    fixed_state,
+                    usage,
+                    ..
+                } => {
+                    if fixed_state.is_none() {
+                        self.barriers.transition_buffer(resource, binding.state);
+                    }
+                    usage.track(self.queue, self.target_value, &mut self.waits);
+                }
+                BoundValue::Texture {
+                    resource,
+                    states,
+                    usage,
+                    mips,
+                    layers,
+                    ..
+                } => {
+                    states.transition(
+                        &mut self.barriers,
+                        resource,
+                        mips.clone(),
+                        layers.clone(),
+                        binding.state,
+                    );
+                    usage.track(self.queue, self.target_value, &mut self.waits);
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn use_buffer(&mut self, buffer: &Buffer, state: D3D12_RESOURCE_STATES) {
+        if buffer.fixed_state.is_none() {
+            self.barriers.transition_buffer(&buffer.resource, state);
+        }
+        buffer
+            .usage
+            .track(self.queue, self.target_value, &mut self.waits);
+    }
+
+    #[inline(always)]
+    fn use_texture(&mut self, range: &TextureRange, state: D3D12_RESOURCE_STATES) {
+        range.states.transition(
+            &mut self.barriers,
+            range.resource,
+            range.mips.clone(),
+            range.layers.clone(),
+            state,
+        );
+        if let Some(usage) = range.usage {
+            usage.track(self.queue, self.target_value, &mut self.waits);
+        }
+    }
+
+    /// Clears a texture range through its render target view, or its depth stencil view if
+    /// `format` is a depth format.
+    unsafe fn clear_view(
+        &mut self,
+        range: TextureRange<'a>,
+        format: Format,
+        view: u32,
+        value: ClearColor,
+    ) {
+        if format.is_color() {
+            self.use_texture(&range, D3D12_RESOURCE_STATE_RENDER_TARGET);
+            self.barriers.flush(&self.list);
+            self.list.ClearRenderTargetView(
+                self.heaps.rtv.cpu(view),
+                &util::to_d3d12_clear_color(value),
+                None,
+            );
+        } else {
+            let (depth, stencil) = match value {
+                ClearColor::D32S32(depth, stencil) => (depth, stencil as u8),
+                _ => unreachable!("depth/stencil textures are cleared with depth/stencil values"),
+            };
+            let mut flags = D3D12_CLEAR_FLAG_DEPTH;
+            if format.is_stencil() {
+                flags |= D3D12_CLEAR_FLAG_STENCIL;
+            }
+
+            self.use_texture(&range, D3D12_RESOURCE_STATE_DEPTH_WRITE);
+            self.barriers.flush(&self.list);
+            self.list
+                .ClearDepthStencilView(self.heaps.dsv.cpu(view), flags, depth, stencil, &[]);
+        }
+    }
+
+    /// Copies a region between single subresources of two textures.
+    unsafe fn copy_texture_region(
+        &mut self,
+        src: TextureRange,
+        src_offset: (u32, u32, u32),
+        dst: TextureRange,
+        dst_offset: (u32, u32, u32),
+        extent: (u32, u32, u32),
+    ) {
+        self.use_texture(&src, D3D12_RESOURCE_STATE_COPY_SOURCE);
+        self.use_texture(&dst, D3D12_RESOURCE_STATE_COPY_DEST);
+        self.barriers.flush(&self.list);
+
+        let src_location = subresource_location(
+            src.resource,
+            src.states.subresource(src.mips.start, src.layers.start, 0),
+        );
+        let dst_location = subresource_location(
+            dst.resource,
+            dst.states.subresource(dst.mips.start, dst.layers.start, 0),
+        );
+
+        self.list.CopyTextureRegion(
+            &dst_location,
+            dst_offset.0,
+            dst_offset.1,
+            dst_offset.2,
+            &src_location,
+            Some(&D3D12_BOX {
+                left: src_offset.0,
+                top: src_offset.1,
+                front: src_offset.2,
+                right: src_offset.0 + extent.0,
+                bottom: src_offset.1 + extent.1,
+                back: src_offset.2 + extent.2,
+            }),
+        );
+    }
+
+    /// Copies between a buffer and a single subresource of a texture.
+    ///
+    /// D3D12 requires placed footprints to be 512 byte aligned with rows that are 256 byte
+    /// aligned. When the buffer doesn't meet these requirements, the data is repacked through a
+    /// temporary buffer.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn copy_buffer_texture(
+        &mut self,
+        buffer: &Buffer,
+        buffer_offset: u64,
+        buffer_row_length: u32,
+        buffer_image_height: u32,
+        region: BufferTextureRegion,
+        to_texture: bool,
+    ) {
+        let (block_size, block_dim) = util::format_block_info(region.format);
+        let extent = (
+            region.extent.0.next_multiple_of(block_dim),
+            region.extent.1.next_multiple_of(block_dim),
+            region.extent.2,
+        );
+
+        // Layout of the data in the buffer
+        let row_texels = match buffer_row_length {
+            0 => region.extent.0,
+            len => len,
+        };
+        let height_texels = match buffer_image_height {
+            0 => region.extent.1,
+            height => height,
+        };
+        let rows = extent.1 / block_dim;
+        let row_bytes = (extent.0 / block_dim) as u64 * block_size as u64;
+        let row_pitch = row_texels.div_ceil(block_dim) as u64 * block_size as u64;
+        let slice_pitch = row_pitch * height_texels.div_ceil(block_dim) as u64;
+
+        self.use_buffer(
+            buffer,
+            if to_texture {
+                D3D12_RESOURCE_STATE_COPY_SOURCE
+            } else {
+                D3D12_RESOURCE_STATE_COPY_DEST
+            },
+        );
+        self.use_texture(
+            &region.range,
+            if to_texture {
+                D3D12_RESOURCE_STATE_COPY_DEST
+            } else {
+                D3D12_RESOURCE_STATE_COPY_SOURCE
+            },
+        );
+
+        let is_placeable = buffer_offset
+            .is_multiple_of(D3D12_TEXTURE_DATA_PLACEMENT_ALIGNMENT as u64)
+            && row_pitch.is_multiple_of(D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as u64)
+            && (extent.2 == 1 || slice_pitch == row_pitch * rows as u64);
+
+        // Find where the footprint lives
+        let (footprint_resource, footprint_offset, footprint_pitch) = if is_placeable {
+            (buffer.resource.clone(), buffer_offset, row_pitch)
+        } else {
+            let staging_pitch =
+                row_bytes.next_multiple_of(D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as u64);
+            let staging = self.create_staging(staging_pitch * rows as u64 * extent.2 as u64);
+            let resource = staging.resource().clone();
+            self.temporaries.push(staging);
+            self.barriers
+                .transition_buffer(&resource, D3D12_RESOURCE_STATE_COPY_DEST);
+            (resource, 0, staging_pitch)
+        };
+
+        // Repack into the staging buffer before the copy
+        if to_texture && !is_placeable {
+            self.barriers.flush(&self.list);
+            self.copy_rows(
+                (&buffer.resource, buffer_offset, row_pitch, slice_pitch),
+                (
+                    &footprint_resource,
+                    0,
+                    footprint_pitch,
+                    footprint_pitch * rows as u64,
+                ),
+                row_bytes,
+                rows,
+                extent.2,
+            );
+            self.barriers
+                .transition_buffer(&footprint_resource, D3D12_RESOURCE_STATE_COPY_SOURCE);
+        }
+
+        self.barriers.flush(&self.list);
+
+        let footprint = footprint_location(
+            &footprint_resource,
+            D3D12_PLACED_SUBRESOURCE_FOOTPRINT {
+                Offset: footprint_offset,
+                Footprint: D3D12_SUBRESOURCE_FOOTPRINT {
+                    Format: if region.format.is_depth() {
+                        util::to_dxgi_srv_format(region.format)
+                    } else {
+                        util::to_dxgi_format(region.format)
+                    },
+                    Width: extent.0,
+                    Height: extent.1,
+                    Depth: extent.2,
+                    RowPitch: footprint_pitch as u32,
+                },
+            },
+        );
+        let subresource = subresource_location(
+            region.range.resource,
+            region
+                .range
+                .states
+                .subresource(region.range.mips.start, region.range.layers.start, 0),
+        );
+
+        if to_texture {
+            self.list.CopyTextureRegion(
+                &subresource,
+                region.offset.0,
+                region.offset.1,
+                region.offset.2,
+                &footprint,
+                None,
+            );
+        } else {
+            self.list.CopyTextureRegion(
+                &footprint,
+                0,
+                0,
+                0,
+                &subresource,
+                Some(&D3D12_BOX {
+                    left: region.offset.0,
+                    top: region.offset.1,
+                    front: region.offset.2,
+                    right: region.offset.0 + extent.0,
+                    bottom: region.offset.1 + extent.1,
+                    back: region.offset.2 + extent.2,
+                }),
+            );
+        }
+
+        // Repack out of the staging buffer after the copy
+        if !to_texture && !is_placeable {
+            self.barriers
+                .transition_buffer(&footprint_resource, D3D12_RESOURCE_STATE_COPY_SOURCE);
+            self.barriers.flush(&self.list);
+            self.copy_rows(
+                (
+                    &footprint_resource,
+                    0,
+                    footprint_pitch,
+                    footprint_pitch * rows as u64,
+                ),
+                (&buffer.resource, buffer_offset, row_pitch, slice_pitch),
+                row_bytes,
+                rows,
+                extent.2,
+            );
+        }
+    }
+
+    /// Copies rows of texels between buffers with different pitches. Each side is the buffer,
+    /// offset, row pitch, and slice pitch.
+    unsafe fn copy_rows(
+        &self,
+        src: (&ID3D12Resource, u64, u64, u64),
+        dst: (&ID3D12Resource, u64, u64, u64),
+        row_bytes: u64,
+        rows: u32,
+        slices: u32,
+    ) {
+        for slice in 0..slices as u64 {
+            for row in 0..rows as u64 {
+                self.list.CopyBufferRegion(
+                    dst.0,
+                    dst.1 + (slice * dst.3) + (row * dst.2),
+                    src.0,
+                    src.1 + (slice * src.3) + (row * src.2),
+                    row_bytes,
+                );
+            }
+        }
+    }
+
+    unsafe fn create_staging(&mut self, size: u64) -> Resource {
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+            Alignment: 0,
+            Width: size,
+            Height: 1,
+            DepthOrArraySize: 1,
+            MipLevels: 1,
+            Format: DXGI_FORMAT_UNKNOWN,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+            Flags: D3D12_RESOURCE_FLAG_NONE,
+        };
+
+        self.allocator
+            .create_resource(&ResourceCreateDesc {
+                name: "staging_buffer",
+                memory_location: MemoryLocation::GpuOnly,
+                resource_category: ResourceCategory::Buffer,
+                resource_desc: &desc,
+                castable_formats: &[],
+                clear_value: None,
+                initial_state_or_layout: ResourceStateOrBarrierLayout::ResourceState(
+                    D3D12_RESOURCE_STATE_COMMON,
+                ),
+                resource_type: &ResourceType::Placed,
+            })
+            .unwrap()
+    }
+
+    /// Writes data to a buffer by copying it from a temporary upload buffer.
+    unsafe fn upload_to_buffer(&mut self, dst: &Buffer, dst_offset: u64, data: &[u8]) {
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+            Alignment: 0,
+            Width: data.len() as u64,
+            Height: 1,
+            DepthOrArraySize: 1,
+            MipLevels: 1,
+            Format: DXGI_FORMAT_UNKNOWN,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+            Flags: D3D12_RESOURCE_FLAG_NONE,
+        };
+
+        let upload = self
+            .allocator
+            .create_resource(&ResourceCreateDesc {
+                name: "upload_buffer",
+                memory_location: MemoryLocation::CpuToGpu,
+                resource_category: ResourceCategory::Buffer,
+                resource_desc: &desc,
+                castable_formats: &[],
+                clear_value: None,
+                initial_state_or_layout: ResourceStateOrBarrierLayout::ResourceState(
+                    D3D12_RESOURCE_STATE_GENERIC_READ,
+                ),
+                resource_type: &ResourceType::Placed,
+            })
+            .unwrap();
+
+        let mut ptr = std::ptr::null_mut();
+        upload.resource().Map(0, None, Some(&mut ptr)).unwrap();
+        std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len());
+        upload.resource().Unmap(0, None);
+
+        self.use_buffer(dst, D3D12_RESOURCE_STATE_COPY_DEST);
+        self.barriers.flush(&self.list);
+        self.list.CopyBufferRegion(
+            &dst.resource,
+            dst_offset,
+            upload.resource(),
+            0,
+            data.len() as u64,
+        );
+        self.temporaries.push(upload);
+    }
+}
+
+impl<'a> TextureRange<'a> {
+    fn texture(texture: &'a Texture, array_element: usize, mips: Range<u32>) -> Self {
+        Self {
+            resource: &texture.resource,
+            states: &texture.states,
+            usage: Some(&texture.usage),
+            mips,
+            layers: match texture.ty {
+                // 3D textures only have a single "layer"
+                TextureType::Type3D => 0..1,
+                _ => (array_element as u32)..(array_element as u32 + 1),
+            },
+        }
+    }
+
+    /// A single face of a cube map, or the entire cube if `face` is `None`.
+    fn cube_map(
+        cube_map: &'a CubeMap,
+        array_element: usize,
+        face: Option<CubeFace>,
+        mips: Range<u32>,
+    ) -> Self {
+        let first_layer = array_element as u32 * 6;
+        Self {
+            resource: &cube_map.resource,
+            states: &cube_map.states,
+            usage: Some(&cube_map.usage),
+            mips,
+            layers: match face {
+                Some(face) => {
+                    let layer = first_layer + util::cube_face_to_idx(face) as u32;
+                    layer..(layer + 1)
+                }
+                None => first_layer..(first_layer + 6),
+            },
+        }
+    }
+
+    fn surface_image(image: &'a SurfaceImage) -> Self {
+        Self {
+            resource: image.resource(),
+            states: image.states(),
+            usage: None,
+            mips: 0..1,
+            layers: 0..1,
+        }
+    }
+}
+
+/// Every cube face in the order they are laid out in memory.
+const CUBE_FACES: [CubeFace; 6] = [
+    CubeFace::East,
+    CubeFace::West,
+    CubeFace::Top,
+    CubeFace::Bottom,
+    CubeFace::North,
+    CubeFace::South,
+];
+
+impl From<windows::core::Error> for Dx12BackendCreateError {
+    fn from(value: windows::core::Error) -> Self {
+        Dx12BackendCreateError::Dx12(value)
+    }
+}
+
+#[inline(always)]
+fn to_d3d12_resolve_mode(mode: ResolveMode) -> D3D12_RESOLVE_MODE {
+    match mode {
+        // NOTE: There is no "sample zero" mode, so we average instead
+        ResolveMode::SampleZero | ResolveMode::Average => D3D12_RESOLVE_MODE_AVERAGE,
+        ResolveMode::Min => D3D12_RESOLVE_MODE_MIN,
+        ResolveMode::Max => D3D12_RESOLVE_MODE_MAX,
+    }
+}
+
+/// Begins a PIX event with the provided name.
+unsafe fn begin_event(list: &ID3D12GraphicsCommandList, name: &str) {
+    // Metadata of `0` indicates a null terminated wide string
+    let name: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    list.BeginEvent(
+        0,
+        Some(name.as_ptr() as *const c_void),
+        (name.len() * std::mem::size_of::<u16>()) as u32,
+    );
+}
+
+unsafe fn set_marker(list: &ID3D12GraphicsCommandList, name: &str) {
+    // Metadata of `0` indicates a null terminated wide string
+    let name: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    list.SetMarker(
+        0,
+        Some(name.as_ptr() as *const c_void),
+        (name.len() * std::mem::size_of::<u16>()) as u32,
+    );
+}
+
+#[inline(always)]
+unsafe fn subresource_location(
+    resource: &ID3D12Resource,
+    subresource: u32,
+) -> D3D12_TEXTURE_COPY_LOCATION {
+    D3D12_TEXTURE_COPY_LOCATION {
+        // SAFETY: The location does not hold a reference to the resource and never outlives it.
+        pResource: std::mem::transmute_copy(resource),
+        Type: D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX,
+        Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+            SubresourceIndex: subresource,
+        },
+    }
+}
+
+#[inline(always)]
+unsafe fn footprint_location(
+    resource: &ID3D12Resource,
+    footprint: D3D12_PLACED_SUBRESOURCE_FOOTPRINT,
+) -> D3D12_TEXTURE_COPY_LOCATION {
+    D3D12_TEXTURE_COPY_LOCATION {
+        // SAFETY: See `subresource_location`.
+        pResource: std::mem::transmute_copy(resource),
+        Type: D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT,
+        Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+            PlacedFootprint: footprint,
+        },
+    }
+}
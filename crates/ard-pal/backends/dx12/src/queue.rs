@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+
+use api::types::QueueType;
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        Graphics::Direct3D12::*,
+        System::Threading::{CreateEventW, WaitForSingleObject, INFINITE},
+    },
+};
+
+pub(crate) struct Dx12Queue {
+    pub queue: ID3D12CommandQueue,
+    /// All work performed on this queue increments the value of this fence.
+    fence: ID3D12Fence,
+    /// Event used when waiting on the fence from the CPU.
+    event: HANDLE,
+    /// Queue of free command lists.
+    free: VecDeque<ActiveCommandList>,
+    /// The fence value this queue will set when work is complete.
+    target_value: u64,
+    /// The last fence value this queue was synced on the CPU to.
+    cpu_sync_value: u64,
+}
+
+struct ActiveCommandList {
+    allocator: ID3D12CommandAllocator,
+    list: ID3D12GraphicsCommandList,
+    /// What value the fence must have for this command lists work to be complete.
+    target: u64,
+}
+
+/// A command list ready to be recorded into.
+pub(crate) struct CommandList {
+    pub allocator: ID3D12CommandAllocator,
+    pub list: ID3D12GraphicsCommandList,
+}
+
+impl Dx12Queue {
+    pub unsafe fn new(device: &ID3D12Device, ty: QueueType) -> windows::core::Result<Self> {
+        // NOTE: Every queue is a direct queue. Copy and compute command lists are restricted in
+        // which resource states they may transition between, which would require every resource
+        // to be tracked per queue type.
+        let desc = D3D12_COMMAND_QUEUE_DESC {
+            Type: D3D12_COMMAND_LIST_TYPE_DIRECT,
+            Priority: D3D12_COMMAND_QUEUE_PRIORITY_NORMAL.0,
+            Flags: D3D12_COMMAND_QUEUE_FLAG_NONE,
+            NodeMask: 0,
+        };
+        let queue: ID3D12CommandQueue = device.CreateCommandQueue(&desc)?;
+        let fence: ID3D12Fence = device.CreateFence(0, D3D12_FENCE_FLAG_NONE)?;
+        let event = CreateEventW(None, false, false, None)?;
+
+        let (queue_name, fence_name) = match ty {
+            QueueType::Main => ("main_queue", "main_fence"),
+            QueueType::Transfer => ("transfer_queue", "transfer_fence"),
+            QueueType::Compute => ("compute_queue", "compute_fence"),
+            QueueType::Present => ("present_queue", "present_fence"),
+        };
+        queue.SetName(&HSTRING::from(queue_name))?;
+        fence.SetName(&HSTRING::from(fence_name))?;
+
+        Ok(Self {
+            queue,
+            fence,
+            event,
+            free: VecDeque::default(),
+            target_value: 0,
+            cpu_sync_value: 0,
+        })
+    }
+
+    #[inline(always)]
+    pub fn fence(&self) -> &ID3D12Fence {
+        &self.fence
+    }
+
+    #[inline(always)]
+    pub fn target_timeline_value(&self) -> u64 {
+        self.target_value
+    }
+
+    #[inline(always)]
+    pub fn cpu_sync_value(&self) -> u64 {
+        self.cpu_sync_value
+    }
+
+    #[inline(always)]
+    pub fn set_cpu_sync_value(&mut self, value: u64) {
+        self.cpu_sync_value = value;
+    }
+
+    #[inline(always)]
+    pub unsafe fn current_timeline_value(&self) -> u64 {
+        self.fence.GetCompletedValue()
+    }
+
+    /// Blocks until the fence reaches the provided value or the timeout (in milliseconds)
+    /// expires. Returns `true` if the value was reached.
+    pub unsafe fn wait(&self, value: u64, timeout: Option<u32>) -> bool {
+        if self.fence.GetCompletedValue() >= value {
+            return true;
+        }
+
+        self.fence.SetEventOnCompletion(value, self.event).unwrap();
+        WaitForSingleObject(self.event, timeout.unwrap_or(INFINITE));
+        self.fence.GetCompletedValue() >= value
+    }
+
+    pub unsafe fn allocate_command_list(
+        &mut self,
+        device: &ID3D12Device,
+    ) -> windows::core::Result<CommandList> {
+        // Check current fence value
+        let current_value = self.current_timeline_value();
+
+        // Reuse a command list if one is finished
+        if let Some(active) = self.free.front() {
+            if active.target <= current_value {
+                let active = self.free.pop_front().unwrap();
+                active.allocator.Reset()?;
+                active.list.Reset(&active.allocator, None)?;
+                return Ok(CommandList {
+                    allocator: active.allocator,
+                    list: active.list,
+                });
+            }
+        }
+
+        // Otherwise make a new one
+        let allocator: ID3D12CommandAllocator =
+            device.CreateCommandAllocator(D3D12_COMMAND_LIST_TYPE_DIRECT)?;
+        let list: ID3D12GraphicsCommandList =
+            device.CreateCommandList(0, D3D12_COMMAND_LIST_TYPE_DIRECT, &allocator, None)?;
+
+        Ok(CommandList { allocator, list })
+    }
+
+    /// Submits a closed command list to the queue. The queue will wait for each provided fence
+    /// to reach its value before executing. Returns the value the queues fence will have when
+    /// the work is complete.
+    pub unsafe fn submit(
+        &mut self,
+        command_list: CommandList,
+        waits: &[(&ID3D12Fence, u64)],
+    ) -> windows::core::Result<u64> {
+        for (fence, value) in waits {
+            if *value > 0 {
+                self.queue.Wait(*fence, *value)?;
+            }
+        }
+
+        self.queue
+            .ExecuteCommandLists(&[Some(command_list.list.clone().into())]);
+
+        self.target_value += 1;
+        self.queue.Signal(&self.fence, self.target_value)?;
+
+        self.free.push_back(ActiveCommandList {
+            allocator: command_list.allocator,
+            list: command_list.list,
+            target: self.target_value,
+        });
+
+        Ok(self.target_value)
+    }
+
+    pub unsafe fn release(&mut self) {
+        self.free.clear();
+        let _ = CloseHandle(self.event);
+    }
+}
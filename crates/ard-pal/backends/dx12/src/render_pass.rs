@@ -0,0 +1,28 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Matches `D3D12_DRAW_ARGUMENTS`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DrawIndirect {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+unsafe impl Pod for DrawIndirect {}
+unsafe impl Zeroable for DrawIndirect {}
+
+/// Matches `D3D12_DRAW_INDEXED_ARGUMENTS`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DrawIndexedIndirect {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub vertex_offset: i32,
+    pub first_instance: u32,
+}
+
+unsafe impl Pod for DrawIndexedIndirect {}
+unsafe impl Zeroable for DrawIndexedIndirect {}
@@ -0,0 +1,24 @@
+use api::shader::{ShaderCreateError, ShaderCreateInfo};
+
+/// A compiled DXIL shader.
+///
+/// D3D12 has no shader module object, so the bytecode is kept around until it is consumed by a
+/// pipeline.
+pub struct Shader {
+    pub(crate) code: Vec<u8>,
+}
+
+impl Shader {
+    pub(crate) fn new(create_info: ShaderCreateInfo) -> Result<Self, ShaderCreateError> {
+        // DXIL is stored in a DXBC container
+        if create_info.code.len() < 4 || &create_info.code[0..4] != b"DXBC" {
+            return Err(ShaderCreateError::Other(String::from(
+                "shader code is not a DXIL container",
+            )));
+        }
+
+        Ok(Shader {
+            code: create_info.code.to_vec(),
+        })
+    }
+}
@@ -0,0 +1,441 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use api::{
+    queue::SurfacePresentFailure,
+    surface::{
+        SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo, SurfaceImageAcquireError,
+        SurfacePresentSuccess, SurfaceProperties, SurfaceUpdateError,
+    },
+    types::{ColorSpace, Format, PresentMode},
+};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
+use windows::{
+    core::{Interface, HSTRING},
+    Win32::{
+        Foundation::{DXGI_STATUS_OCCLUDED, HWND},
+        Graphics::{Direct3D12::*, Dxgi::Common::*, Dxgi::*},
+    },
+};
+
+use crate::{util::states::SubresourceStates, Dx12Backend};
+
+/// Number of images in every swapchain.
+const IMAGE_COUNT: u32 = 3;
+
+pub struct Surface {
+    /// Unique ID used to match images to their surface.
+    pub(crate) id: u64,
+    pub(crate) hwnd: HWND,
+    pub(crate) swapchain: Option<IDXGISwapChain3>,
+    pub(crate) format: Format,
+    pub(crate) color_space: ColorSpace,
+    pub(crate) present_mode: PresentMode,
+    pub(crate) resolution: (u32, u32),
+    pub(crate) images: Vec<SwapchainImage>,
+    /// Main queue fence value that must be reached before each image can be reused.
+    pub(crate) fence_values: Vec<AtomicU64>,
+    /// Counter for the number of images acquired.
+    pub(crate) images_acquired: usize,
+    debug_name: Option<String>,
+}
+
+pub(crate) struct SwapchainImage {
+    resource: ID3D12Resource,
+    rtv: u32,
+    states: SubresourceStates,
+}
+
+pub struct SurfaceImage {
+    /// Source surface.
+    surface: u64,
+    /// Image dimensions.
+    dims: (u32, u32),
+    /// Actual image object.
+    resource: ID3D12Resource,
+    /// Render target view of the image.
+    rtv: u32,
+    /// Format of the image.
+    format: Format,
+    /// Index of the surface image acquired.
+    image_idx: usize,
+    states: SubresourceStates,
+    /// Indicates that the surface image has been used and is available for present.
+    used: AtomicBool,
+    /// Main queue fence value signaled when the last use of the image is complete.
+    fence_value: AtomicU64,
+}
+
+unsafe impl Send for Surface {}
+unsafe impl Sync for Surface {}
+unsafe impl Send for SurfaceImage {}
+unsafe impl Sync for SurfaceImage {}
+
+impl Surface {
+    pub(crate) unsafe fn new<W: HasWindowHandle + HasDisplayHandle>(
+        ctx: &Dx12Backend,
+        create_info: SurfaceCreateInfo<W>,
+    ) -> Result<(Self, SurfaceProperties), SurfaceCreateError> {
+        let window = match create_info.window {
+            api::surface::WindowSource::Raw { window, .. } => window,
+            api::surface::WindowSource::Reference(r) => r.window_handle().unwrap().as_raw(),
+        };
+
+        let hwnd = match window {
+            RawWindowHandle::Win32(handle) => HWND(handle.hwnd.get() as *mut _),
+            _ => {
+                return Err(SurfaceCreateError::Other(String::from(
+                    "only Win32 windows are supported by the DirectX 12 backend",
+                )))
+            }
+        };
+
+        let mut surface = Surface {
+            id: ctx.surface_ids.fetch_add(1, Ordering::Relaxed),
+            hwnd,
+            swapchain: None,
+            format: Format::Bgra8Unorm,
+            color_space: ColorSpace::SrgbNonlinear,
+            present_mode: create_info.config.present_mode,
+            resolution: (0, 0),
+            images: Vec::default(),
+            fence_values: Vec::default(),
+            images_acquired: 0,
+            debug_name: create_info.debug_name,
+        };
+
+        // Update the surface with the provided configuration
+        match surface.update_config(ctx, create_info.config) {
+            Ok(properties) => Ok((surface, properties)),
+            Err(err) => Err(SurfaceCreateError::BadConfig(err)),
+        }
+    }
+
+    pub(crate) unsafe fn present(
+        &self,
+        ctx: &Dx12Backend,
+        image: &mut SurfaceImage,
+    ) -> Result<SurfacePresentSuccess, SurfacePresentFailure> {
+        if image.surface() != self.id {
+            return Err(SurfacePresentFailure::BadImage);
+        }
+
+        if !image.is_signaled() {
+            return Err(SurfacePresentFailure::NoRender);
+        }
+
+        let swapchain = self.swapchain.as_ref().unwrap();
+        let (sync_interval, flags) = match self.present_mode {
+            PresentMode::Immediate if ctx.allow_tearing => (0, DXGI_PRESENT_ALLOW_TEARING),
+            PresentMode::Immediate | PresentMode::Mailbox => (0, DXGI_PRESENT(0)),
+            _ => (1, DXGI_PRESENT(0)),
+        };
+
+        self.fence_values[image.index()]
+            .store(image.fence_value.load(Ordering::Relaxed), Ordering::Relaxed);
+
+        let res = swapchain.Present(sync_interval, flags);
+        if res == DXGI_STATUS_OCCLUDED {
+            return Ok(SurfacePresentSuccess::Invalidated);
+        }
+        if let Err(err) = res.ok() {
+            return Err(SurfacePresentFailure::Other(err.to_string()));
+        }
+
+        Ok(SurfacePresentSuccess::Ok)
+    }
+
+    pub(crate) unsafe fn update_config(
+        &mut self,
+        ctx: &Dx12Backend,
+        config: SurfaceConfiguration,
+    ) -> Result<SurfaceProperties, SurfaceUpdateError> {
+        assert!(config.width != 0, "width was 0");
+        assert!(config.height != 0, "height was 0");
+        if self.images_acquired != 0 {
+            return Err(SurfaceUpdateError::ImagePending);
+        }
+
+        self.release(ctx);
+
+        // Flip model swapchains can only use a handful of formats. Unsupported formats are
+        // skipped and SDR is used as a last resort.
+        let candidates = config
+            .preferred_formats
+            .iter()
+            .copied()
+            .filter(|(format, _)| {
+                matches!(
+                    format,
+                    Format::Bgra8Unorm
+                        | Format::Bgra8Srgb
+                        | Format::Rgba8Unorm
+                        | Format::Rgba8Srgb
+                        | Format::Rgba16SFloat
+                        | Format::Rgb10A2Unorm
+                )
+            })
+            .chain(std::iter::once((
+                Format::Bgra8Unorm,
+                ColorSpace::SrgbNonlinear,
+            )));
+
+        // Color space support can only be queried from an existing swapchain, so the swapchain
+        // is resized to each candidate format until one can be presented
+        let mut selected = None;
+        for (format, color_space) in candidates {
+            let swapchain = self.configure_swapchain(ctx, &config, format)?;
+            let dxgi_color_space = crate::util::to_dxgi_color_space(color_space);
+            let support = swapchain
+                .CheckColorSpaceSupport(dxgi_color_space)
+                .unwrap_or(0);
+            if support & DXGI_SWAP_CHAIN_COLOR_SPACE_SUPPORT_FLAG_PRESENT.0 as u32 != 0
+                && swapchain.SetColorSpace1(dxgi_color_space).is_ok()
+            {
+                selected = Some((swapchain, format, color_space));
+                break;
+            }
+        }
+
+        let (swapchain, format, color_space) = match selected {
+            Some(selected) => selected,
+            None => return Err(SurfaceUpdateError::UnsupportedFormat),
+        };
+        if !config.preferred_formats.contains(&(format, color_space)) {
+            ard_log::warn!(
+                "none of the preferred surface formats are supported, using {format:?} with \
+                {color_space:?}"
+            );
+        }
+
+        // Get swapchain images and create their views
+        let mut heaps = ctx.heaps.lock().unwrap();
+        for i in 0..IMAGE_COUNT {
+            let resource: ID3D12Resource = match swapchain.GetBuffer(i) {
+                Ok(resource) => resource,
+                Err(err) => return Err(SurfaceUpdateError::Other(err.to_string())),
+            };
+
+            let rtv = match heaps.rtv.allocate(1) {
+                Some(rtv) => rtv,
+                None => {
+                    return Err(SurfaceUpdateError::Other(String::from(
+                        "out of render target views",
+                    )))
+                }
+            };
+            let desc = D3D12_RENDER_TARGET_VIEW_DESC {
+                Format: crate::util::to_dxgi_format(format),
+                ViewDimension: D3D12_RTV_DIMENSION_TEXTURE2D,
+                Anonymous: D3D12_RENDER_TARGET_VIEW_DESC_0 {
+                    Texture2D: D3D12_TEX2D_RTV {
+                        MipSlice: 0,
+                        PlaneSlice: 0,
+                    },
+                },
+            };
+            ctx.device
+                .CreateRenderTargetView(&resource, Some(&desc), heaps.rtv.cpu(rtv));
+
+            if let Some(name) = &self.debug_name {
+                let _ = resource.SetName(&HSTRING::from(format!("{name}_image_{i}")));
+            }
+
+            self.images.push(SwapchainImage {
+                resource,
+                rtv,
+                states: SubresourceStates::new(1, 1, 1),
+            });
+            self.fence_values.push(AtomicU64::new(0));
+        }
+
+        self.format = format;
+        self.color_space = color_space;
+        // Report the mode presentation actually behaves like. Immediate presents can't tear
+        // without tearing support, and relaxed FIFO isn't available at all.
+        self.present_mode = match config.present_mode {
+            PresentMode::Immediate if !ctx.allow_tearing => PresentMode::Mailbox,
+            PresentMode::FifoRelaxed => PresentMode::Fifo,
+            present_mode => present_mode,
+        };
+        self.resolution = (config.width, config.height);
+
+        Ok(SurfaceProperties {
+            dimensions: self.resolution,
+            format,
+            color_space,
+            present_mode: self.present_mode,
+        })
+    }
+
+    /// Creates the swapchain with the given format, or resizes the existing one to use it.
+    unsafe fn configure_swapchain(
+        &mut self,
+        ctx: &Dx12Backend,
+        config: &SurfaceConfiguration,
+        format: Format,
+    ) -> Result<IDXGISwapChain3, SurfaceUpdateError> {
+        // Flip model swapchains can't use sRGB formats directly. Instead, the render target views
+        // of the images are sRGB.
+        let buffer_format = crate::util::to_dxgi_uav_format(format);
+
+        let flags = if ctx.allow_tearing {
+            DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as u32
+        } else {
+            0
+        };
+
+        let swapchain = match &self.swapchain {
+            Some(swapchain) => {
+                if let Err(err) = swapchain.ResizeBuffers(
+                    IMAGE_COUNT,
+                    config.width,
+                    config.height,
+                    buffer_format,
+                    DXGI_SWAP_CHAIN_FLAG(flags as i32),
+                ) {
+                    return Err(SurfaceUpdateError::Other(err.to_string()));
+                }
+                swapchain.clone()
+            }
+            None => {
+                let desc = DXGI_SWAP_CHAIN_DESC1 {
+                    Width: config.width,
+                    Height: config.height,
+                    Format: buffer_format,
+                    Stereo: false.into(),
+                    SampleDesc: DXGI_SAMPLE_DESC {
+                        Count: 1,
+                        Quality: 0,
+                    },
+                    BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+                    BufferCount: IMAGE_COUNT,
+                    Scaling: DXGI_SCALING_STRETCH,
+                    SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
+                    AlphaMode: DXGI_ALPHA_MODE_UNSPECIFIED,
+                    Flags: flags,
+                };
+
+                let queue = ctx.main.lock().unwrap().queue.clone();
+                let swapchain = match ctx
+                    .factory
+                    .CreateSwapChainForHwnd(&queue, self.hwnd, &desc, None, None)
+                    .and_then(|swapchain| swapchain.cast::<IDXGISwapChain3>())
+                {
+                    Ok(swapchain) => swapchain,
+                    Err(err) => return Err(SurfaceUpdateError::Other(err.to_string())),
+                };
+                let _ = ctx
+                    .factory
+                    .MakeWindowAssociation(self.hwnd, DXGI_MWA_NO_ALT_ENTER);
+                swapchain
+            }
+        };
+
+        self.swapchain = Some(swapchain.clone());
+        Ok(swapchain)
+    }
+
+    pub(crate) unsafe fn acquire_image(
+        &mut self,
+        ctx: &Dx12Backend,
+    ) -> Result<SurfaceImage, SurfaceImageAcquireError> {
+        if self.images_acquired + 1 > self.images.len() {
+            return Err(SurfaceImageAcquireError::NoImages);
+        }
+
+        let image_idx = self.swapchain.as_ref().unwrap().GetCurrentBackBufferIndex() as usize;
+
+        // Wait for the last frame that used this image to finish
+        let fence_value = self.fence_values[image_idx].load(Ordering::Relaxed);
+        ctx.main.lock().unwrap().wait(fence_value, None);
+
+        // Images are returned to the common state after presenting
+        let image = &self.images[image_idx];
+        image.states.reset(D3D12_RESOURCE_STATE_PRESENT);
+
+        Ok(SurfaceImage {
+            surface: self.id,
+            dims: self.resolution,
+            resource: image.resource.clone(),
+            rtv: image.rtv,
+            format: self.format,
+            image_idx,
+            states: image.states.clone(),
+            used: AtomicBool::new(false),
+            fence_value: AtomicU64::new(0),
+        })
+    }
+
+    pub(crate) unsafe fn release(&mut self, ctx: &Dx12Backend) {
+        // Wait for every image to be finished with
+        let max_value = self
+            .fence_values
+            .iter()
+            .map(|value| value.load(Ordering::Relaxed))
+            .max()
+            .unwrap_or(0);
+        ctx.main.lock().unwrap().wait(max_value, None);
+
+        let mut heaps = ctx.heaps.lock().unwrap();
+        for image in self.images.drain(..) {
+            heaps.rtv.free(image.rtv, 1);
+        }
+        self.fence_values.clear();
+    }
+
+    /// Releases the swapchain entirely.
+    pub(crate) unsafe fn destroy(&mut self, ctx: &Dx12Backend) {
+        self.release(ctx);
+        self.swapchain = None;
+    }
+}
+
+impl SurfaceImage {
+    #[inline(always)]
+    pub(crate) fn resource(&self) -> &ID3D12Resource {
+        &self.resource
+    }
+
+    #[inline(always)]
+    pub(crate) fn rtv(&self) -> u32 {
+        self.rtv
+    }
+
+    #[inline(always)]
+    pub(crate) fn states(&self) -> &SubresourceStates {
+        &self.states
+    }
+
+    #[inline(always)]
+    pub(crate) fn index(&self) -> usize {
+        self.image_idx
+    }
+
+    #[inline(always)]
+    pub(crate) fn surface(&self) -> u64 {
+        self.surface
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_signaled(&self) -> bool {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Marks the image as drawn to by work that completes when the main queue reaches
+    /// `fence_value`.
+    #[inline(always)]
+    pub(crate) fn signal_draw(&self, fence_value: u64) {
+        self.used.store(true, Ordering::Relaxed);
+        self.fence_value.fetch_max(fence_value, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub(crate) fn dims(&self) -> (u32, u32) {
+        self.dims
+    }
+
+    #[inline(always)]
+    pub(crate) fn format(&self) -> Format {
+        self.format
+    }
+}
@@ -0,0 +1,456 @@
+use std::sync::Arc;
+
+use api::{
+    texture::{TextureCreateError, TextureCreateInfo},
+    types::*,
+};
+use crossbeam_channel::Sender;
+use gpu_allocator::{
+    d3d12::{
+        Allocator, Resource, ResourceCategory, ResourceCreateDesc, ResourceStateOrBarrierLayout,
+        ResourceType,
+    },
+    MemoryLocation,
+};
+use windows::{
+    core::HSTRING,
+    Win32::Graphics::{Direct3D12::*, Dxgi::Common::*},
+};
+
+use crate::util::{
+    descriptor_heap::DescriptorHeaps, garbage_collector::Garbage, states::SubresourceStates,
+    usage::UsageTracker,
+};
+
+pub struct Texture {
+    pub(crate) resource: ID3D12Resource,
+    pub(crate) allocation: Option<Resource>,
+    /// Render target view for each array element and mip level. This array is flattened like so.
+    /// A0M0 -> A0M1 -> A0M2 ... A1M0 -> A1M1 -> A1M2 -> ...
+    ///
+    /// 3D textures instead have a view for every depth slice of every mip.
+    /// M0S0 -> M0S1 -> ... M1S0 -> ...
+    pub(crate) rtvs: Vec<u32>,
+    /// Depth stencil views flattened the same as `rtvs`. Each index is the first of two
+    /// descriptors. The first is writable and the second is read only.
+    pub(crate) dsvs: Vec<u32>,
+    pub(crate) _image_usage: TextureUsage,
+    pub(crate) array_elements: usize,
+    pub(crate) _sharing_mode: SharingMode,
+    pub(crate) size: u64,
+    pub(crate) ref_counter: TextureRefCounter,
+    pub(crate) format: Format,
+    pub(crate) ty: TextureType,
+    pub(crate) samples: MultiSamples,
+    pub(crate) mip_count: u32,
+    pub(crate) depth: u32,
+    pub(crate) states: SubresourceStates,
+    pub(crate) usage: UsageTracker,
+    on_drop: Sender<Garbage>,
+}
+
+#[derive(Clone)]
+pub(crate) struct TextureRefCounter(Arc<()>);
+
+unsafe impl Send for Texture {}
+unsafe impl Sync for Texture {}
+
+impl Texture {
+    pub(crate) unsafe fn new(
+        device: &ID3D12Device,
+        on_drop: Sender<Garbage>,
+        allocator: &mut Allocator,
+        heaps: &mut DescriptorHeaps,
+        create_info: TextureCreateInfo,
+    ) -> Result<Self, TextureCreateError> {
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: match create_info.ty {
+                TextureType::Type1D => D3D12_RESOURCE_DIMENSION_TEXTURE1D,
+                TextureType::Type2D => D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+                TextureType::Type3D => D3D12_RESOURCE_DIMENSION_TEXTURE3D,
+            },
+            Alignment: 0,
+            Width: create_info.width as u64,
+            Height: create_info.height,
+            DepthOrArraySize: match create_info.ty {
+                TextureType::Type3D => create_info.depth as u16,
+                _ => create_info.array_elements as u16,
+            },
+            MipLevels: create_info.mip_levels as u16,
+            Format: crate::util::to_dxgi_resource_format(create_info.format),
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: crate::util::to_d3d12_sample_count(create_info.sample_count),
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+            Flags: to_d3d12_resource_flags(create_info.texture_usage),
+        };
+
+        let name = match &create_info.debug_name {
+            Some(name) => name.as_str(),
+            None => "unnamed_texture",
+        };
+
+        let allocation = match create_resource(
+            allocator,
+            &desc,
+            create_info.format,
+            create_info.texture_usage,
+            name,
+        ) {
+            Ok(allocation) => allocation,
+            Err(err) => return Err(TextureCreateError::Other(err)),
+        };
+        let resource = allocation.resource().clone();
+
+        // Create render target and depth stencil views
+        let samples = create_info.sample_count;
+        let mut rtvs = Vec::default();
+        let mut dsvs = Vec::default();
+        if create_info.ty == TextureType::Type2D {
+            for array_elem in 0..create_info.array_elements as u32 {
+                for mip in 0..create_info.mip_levels as u32 {
+                    let res = if create_info
+                        .texture_usage
+                        .contains(TextureUsage::COLOR_ATTACHMENT)
+                    {
+                        create_rtv(
+                            device,
+                            heaps,
+                            &resource,
+                            create_info.format,
+                            samples,
+                            mip,
+                            array_elem,
+                            1,
+                        )
+                        .map(|rtv| rtvs.push(rtv))
+                    } else if create_info
+                        .texture_usage
+                        .contains(TextureUsage::DEPTH_STENCIL_ATTACHMENT)
+                    {
+                        create_dsv(
+                            device,
+                            heaps,
+                            &resource,
+                            create_info.format,
+                            samples,
+                            mip,
+                            array_elem,
+                            1,
+                        )
+                        .map(|dsv| dsvs.push(dsv))
+                    } else {
+                        Ok(())
+                    };
+
+                    if let Err(err) = res {
+                        rtvs.iter().for_each(|rtv| heaps.rtv.free(*rtv, 1));
+                        dsvs.iter().for_each(|dsv| heaps.dsv.free(*dsv, 2));
+                        allocator.free_resource(allocation).unwrap();
+                        return Err(TextureCreateError::Other(err));
+                    }
+                }
+            }
+        } else if create_info.ty == TextureType::Type3D
+            && create_info
+                .texture_usage
+                .contains(TextureUsage::COLOR_ATTACHMENT)
+        {
+            for mip in 0..create_info.mip_levels as u32 {
+                for slice in 0..(create_info.depth >> mip).max(1) {
+                    let res =
+                        create_slice_rtv(device, heaps, &resource, create_info.format, mip, slice);
+
+                    match res {
+                        Ok(rtv) => rtvs.push(rtv),
+                        Err(err) => {
+                            rtvs.iter().for_each(|rtv| heaps.rtv.free(*rtv, 1));
+                            allocator.free_resource(allocation).unwrap();
+                            return Err(TextureCreateError::Other(err));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Determine the size of the texture
+        let size = device.GetResourceAllocationInfo(0, &[desc]).SizeInBytes
+            / create_info.array_elements as u64;
+
+        Ok(Texture {
+            resource,
+            allocation: Some(allocation),
+            rtvs,
+            dsvs,
+            _image_usage: create_info.texture_usage,
+            array_elements: create_info.array_elements,
+            _sharing_mode: create_info.sharing_mode,
+            size,
+            ref_counter: TextureRefCounter::default(),
+            format: create_info.format,
+            ty: create_info.ty,
+            samples,
+            mip_count: create_info.mip_levels as u32,
+            depth: create_info.depth,
+            states: SubresourceStates::new(
+                create_info.mip_levels as u32,
+                match create_info.ty {
+                    TextureType::Type3D => 1,
+                    _ => create_info.array_elements as u32,
+                },
+                crate::util::plane_count(create_info.format),
+            ),
+            usage: UsageTracker::default(),
+            on_drop,
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn rtv(&self, array_element: usize, mip_level: usize) -> u32 {
+        self.rtvs[(array_element * self.mip_count as usize) + mip_level]
+    }
+
+    #[inline(always)]
+    pub(crate) fn slice_rtv(&self, mip_level: usize, slice: u32) -> u32 {
+        let first_slice: u32 = (0..mip_level).map(|mip| (self.depth >> mip).max(1)).sum();
+        self.rtvs[(first_slice + slice) as usize]
+    }
+
+    #[inline(always)]
+    pub(crate) fn dsv(&self, array_element: usize, mip_level: usize, read_only: bool) -> u32 {
+        self.dsvs[(array_element * self.mip_count as usize) + mip_level] + read_only as u32
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        let _ = self.on_drop.send(Garbage::Texture {
+            allocation: self.allocation.take().unwrap(),
+            ref_counter: self.ref_counter.clone(),
+            rtvs: std::mem::take(&mut self.rtvs),
+            dsvs: std::mem::take(&mut self.dsvs),
+        });
+    }
+}
+
+impl TextureRefCounter {
+    #[inline]
+    pub fn is_last(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+}
+
+impl Default for TextureRefCounter {
+    #[inline]
+    fn default() -> Self {
+        TextureRefCounter(Arc::new(()))
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_resource_flags(usage: TextureUsage) -> D3D12_RESOURCE_FLAGS {
+    let mut flags = D3D12_RESOURCE_FLAG_NONE;
+    if usage.contains(TextureUsage::COLOR_ATTACHMENT) {
+        flags |= D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET;
+    }
+    if usage.contains(TextureUsage::DEPTH_STENCIL_ATTACHMENT) {
+        flags |= D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL;
+    }
+    if usage.contains(TextureUsage::STORAGE) {
+        flags |= D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS;
+    }
+    flags
+}
+
+/// Creates the resource for a texture or cube map.
+///
+/// Textures in D3D12 must live in device local memory, so the requested memory usage is ignored.
+pub(crate) unsafe fn create_resource(
+    allocator: &mut Allocator,
+    desc: &D3D12_RESOURCE_DESC,
+    format: Format,
+    usage: TextureUsage,
+    name: &str,
+) -> Result<Resource, String> {
+    // Render targets and depth buffers get an optimized clear value
+    let clear_value = if usage.contains(TextureUsage::DEPTH_STENCIL_ATTACHMENT) {
+        Some(D3D12_CLEAR_VALUE {
+            Format: crate::util::to_dxgi_format(format),
+            Anonymous: D3D12_CLEAR_VALUE_0 {
+                DepthStencil: D3D12_DEPTH_STENCIL_VALUE {
+                    Depth: 0.0,
+                    Stencil: 0,
+                },
+            },
+        })
+    } else if usage.contains(TextureUsage::COLOR_ATTACHMENT) {
+        Some(D3D12_CLEAR_VALUE {
+            Format: crate::util::to_dxgi_format(format),
+            Anonymous: D3D12_CLEAR_VALUE_0 { Color: [0.0; 4] },
+        })
+    } else {
+        None
+    };
+
+    let allocation = allocator
+        .create_resource(&ResourceCreateDesc {
+            name,
+            memory_location: MemoryLocation::GpuOnly,
+            resource_category: if clear_value.is_some() {
+                ResourceCategory::RtvDsvTexture
+            } else {
+                ResourceCategory::OtherTexture
+            },
+            resource_desc: desc,
+            castable_formats: &[],
+            clear_value: clear_value.as_ref(),
+            initial_state_or_layout: ResourceStateOrBarrierLayout::ResourceState(
+                D3D12_RESOURCE_STATE_COMMON,
+            ),
+            resource_type: &ResourceType::Placed,
+        })
+        .map_err(|err| err.to_string())?;
+
+    let _ = allocation.resource().SetName(&HSTRING::from(name));
+
+    Ok(allocation)
+}
+
+/// Creates a render target view for a range of array layers of a single mip.
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe fn create_rtv(
+    device: &ID3D12Device,
+    heaps: &mut DescriptorHeaps,
+    resource: &ID3D12Resource,
+    format: Format,
+    samples: MultiSamples,
+    mip: u32,
+    first_layer: u32,
+    layer_count: u32,
+) -> Result<u32, String> {
+    let idx = match heaps.rtv.allocate(1) {
+        Some(idx) => idx,
+        None => return Err(String::from("out of render target views")),
+    };
+
+    let desc = if samples == MultiSamples::Count1 {
+        D3D12_RENDER_TARGET_VIEW_DESC {
+            Format: crate::util::to_dxgi_format(format),
+            ViewDimension: D3D12_RTV_DIMENSION_TEXTURE2DARRAY,
+            Anonymous: D3D12_RENDER_TARGET_VIEW_DESC_0 {
+                Texture2DArray: D3D12_TEX2D_ARRAY_RTV {
+                    MipSlice: mip,
+                    FirstArraySlice: first_layer,
+                    ArraySize: layer_count,
+                    PlaneSlice: 0,
+                },
+            },
+        }
+    } else {
+        D3D12_RENDER_TARGET_VIEW_DESC {
+            Format: crate::util::to_dxgi_format(format),
+            ViewDimension: D3D12_RTV_DIMENSION_TEXTURE2DMSARRAY,
+            Anonymous: D3D12_RENDER_TARGET_VIEW_DESC_0 {
+                Texture2DMSArray: D3D12_TEX2DMS_ARRAY_RTV {
+                    FirstArraySlice: first_layer,
+                    ArraySize: layer_count,
+                },
+            },
+        }
+    };
+
+    device.CreateRenderTargetView(resource, Some(&desc), heaps.rtv.cpu(idx));
+    Ok(idx)
+}
+
+/// Creates a render target view for a single depth slice of a mip of a 3D texture.
+unsafe fn create_slice_rtv(
+    device: &ID3D12Device,
+    heaps: &mut DescriptorHeaps,
+    resource: &ID3D12Resource,
+    format: Format,
+    mip: u32,
+    slice: u32,
+) -> Result<u32, String> {
+    let idx = match heaps.rtv.allocate(1) {
+        Some(idx) => idx,
+        None => return Err(String::from("out of render target views")),
+    };
+
+    let desc = D3D12_RENDER_TARGET_VIEW_DESC {
+        Format: crate::util::to_dxgi_format(format),
+        ViewDimension: D3D12_RTV_DIMENSION_TEXTURE3D,
+        Anonymous: D3D12_RENDER_TARGET_VIEW_DESC_0 {
+            Texture3D: D3D12_TEX3D_RTV {
+                MipSlice: mip,
+                FirstWSlice: slice,
+                WSize: 1,
+            },
+        },
+    };
+
+    device.CreateRenderTargetView(resource, Some(&desc), heaps.rtv.cpu(idx));
+    Ok(idx)
+}
+
+/// Creates a writable and a read only depth stencil view for a range of array layers of a
+/// single mip. The returned index is of the writable view and the read only view follows it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe fn create_dsv(
+    device: &ID3D12Device,
+    heaps: &mut DescriptorHeaps,
+    resource: &ID3D12Resource,
+    format: Format,
+    samples: MultiSamples,
+    mip: u32,
+    first_layer: u32,
+    layer_count: u32,
+) -> Result<u32, String> {
+    let idx = match heaps.dsv.allocate(2) {
+        Some(idx) => idx,
+        None => return Err(String::from("out of depth stencil views")),
+    };
+
+    let read_only_flags = if format.is_stencil() {
+        D3D12_DSV_FLAG_READ_ONLY_DEPTH | D3D12_DSV_FLAG_READ_ONLY_STENCIL
+    } else {
+        D3D12_DSV_FLAG_READ_ONLY_DEPTH
+    };
+
+    for (i, flags) in [D3D12_DSV_FLAG_NONE, read_only_flags]
+        .into_iter()
+        .enumerate()
+    {
+        let desc = if samples == MultiSamples::Count1 {
+            D3D12_DEPTH_STENCIL_VIEW_DESC {
+                Format: crate::util::to_dxgi_format(format),
+                ViewDimension: D3D12_DSV_DIMENSION_TEXTURE2DARRAY,
+                Flags: flags,
+                Anonymous: D3D12_DEPTH_STENCIL_VIEW_DESC_0 {
+                    Texture2DArray: D3D12_TEX2D_ARRAY_DSV {
+                        MipSlice: mip,
+                        FirstArraySlice: first_layer,
+                        ArraySize: layer_count,
+                    },
+                },
+            }
+        } else {
+            D3D12_DEPTH_STENCIL_VIEW_DESC {
+                Format: crate::util::to_dxgi_format(format),
+                ViewDimension: D3D12_DSV_DIMENSION_TEXTURE2DMSARRAY,
+                Flags: flags,
+                Anonymous: D3D12_DEPTH_STENCIL_VIEW_DESC_0 {
+                    Texture2DMSArray: D3D12_TEX2DMS_ARRAY_DSV {
+                        FirstArraySlice: first_layer,
+                        ArraySize: layer_count,
+                    },
+                },
+            }
+        };
+
+        device.CreateDepthStencilView(resource, Some(&desc), heaps.dsv.cpu(idx + i as u32));
+    }
+
+    Ok(idx)
+}
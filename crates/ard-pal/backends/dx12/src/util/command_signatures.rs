@@ -0,0 +1,90 @@
+use rustc_hash::FxHashMap;
+use windows::Win32::Graphics::Direct3D12::*;
+
+use crate::{
+    compute_pipeline::DispatchIndirect,
+    render_pass::{DrawIndexedIndirect, DrawIndirect},
+};
+
+/// Cache of command signatures used by `ExecuteIndirect`.
+///
+/// None of the signatures change root arguments, so they can be shared between every pipeline.
+#[derive(Default)]
+pub(crate) struct CommandSignatures {
+    /// Draw signatures keyed by their stride.
+    draw: FxHashMap<u32, ID3D12CommandSignature>,
+    /// Indexed draw signatures keyed by their stride.
+    draw_indexed: FxHashMap<u32, ID3D12CommandSignature>,
+    dispatch: Option<ID3D12CommandSignature>,
+}
+
+impl CommandSignatures {
+    pub unsafe fn draw(&mut self, device: &ID3D12Device, stride: u32) -> ID3D12CommandSignature {
+        // A stride of zero means the draws are tightly packed
+        let stride = if stride == 0 {
+            std::mem::size_of::<DrawIndirect>() as u32
+        } else {
+            stride
+        };
+
+        self.draw
+            .entry(stride)
+            .or_insert_with(|| create_signature(device, D3D12_INDIRECT_ARGUMENT_TYPE_DRAW, stride))
+            .clone()
+    }
+
+    pub unsafe fn draw_indexed(
+        &mut self,
+        device: &ID3D12Device,
+        stride: u32,
+    ) -> ID3D12CommandSignature {
+        // A stride of zero means the draws are tightly packed
+        let stride = if stride == 0 {
+            std::mem::size_of::<DrawIndexedIndirect>() as u32
+        } else {
+            stride
+        };
+
+        self.draw_indexed
+            .entry(stride)
+            .or_insert_with(|| {
+                create_signature(device, D3D12_INDIRECT_ARGUMENT_TYPE_DRAW_INDEXED, stride)
+            })
+            .clone()
+    }
+
+    pub unsafe fn dispatch(&mut self, device: &ID3D12Device) -> ID3D12CommandSignature {
+        self.dispatch
+            .get_or_insert_with(|| {
+                create_signature(
+                    device,
+                    D3D12_INDIRECT_ARGUMENT_TYPE_DISPATCH,
+                    std::mem::size_of::<DispatchIndirect>() as u32,
+                )
+            })
+            .clone()
+    }
+}
+
+unsafe fn create_signature(
+    device: &ID3D12Device,
+    ty: D3D12_INDIRECT_ARGUMENT_TYPE,
+    stride: u32,
+) -> ID3D12CommandSignature {
+    let argument = D3D12_INDIRECT_ARGUMENT_DESC {
+        Type: ty,
+        ..Default::default()
+    };
+    let desc = D3D12_COMMAND_SIGNATURE_DESC {
+        ByteStride: stride,
+        NumArgumentDescs: 1,
+        pArgumentDescs: &argument,
+        NodeMask: 0,
+    };
+
+    let mut signature = None;
+    device
+        .CreateCommandSignature(&desc, None, &mut signature)
+        .unwrap();
+    signature.unwrap()
+}
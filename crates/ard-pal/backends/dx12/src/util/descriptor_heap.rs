@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// A descriptor heap that hands out contiguous ranges of descriptors.
+pub(crate) struct DescriptorHeap {
+    heap: ID3D12DescriptorHeap,
+    increment: u32,
+    cpu_start: D3D12_CPU_DESCRIPTOR_HANDLE,
+    gpu_start: D3D12_GPU_DESCRIPTOR_HANDLE,
+    /// Free ranges of descriptors sorted by their starting index.
+    free: Vec<Range<u32>>,
+}
+
+impl DescriptorHeap {
+    pub unsafe fn new(
+        device: &ID3D12Device,
+        ty: D3D12_DESCRIPTOR_HEAP_TYPE,
+        count: u32,
+        shader_visible: bool,
+    ) -> windows::core::Result<Self> {
+        let desc = D3D12_DESCRIPTOR_HEAP_DESC {
+            Type: ty,
+            NumDescriptors: count,
+            Flags: if shader_visible {
+                D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE
+            } else {
+                D3D12_DESCRIPTOR_HEAP_FLAG_NONE
+            },
+            NodeMask: 0,
+        };
+        let heap: ID3D12DescriptorHeap = device.CreateDescriptorHeap(&desc)?;
+
+        Ok(Self {
+            increment: device.GetDescriptorHandleIncrementSize(ty),
+            cpu_start: heap.GetCPUDescriptorHandleForHeapStart(),
+            gpu_start: if shader_visible {
+                heap.GetGPUDescriptorHandleForHeapStart()
+            } else {
+                D3D12_GPU_DESCRIPTOR_HANDLE::default()
+            },
+            heap,
+            #[allow(clippy::single_range_in_vec_init)]
+            free: vec![0..count],
+        })
+    }
+
+    #[inline(always)]
+    pub fn heap(&self) -> &ID3D12DescriptorHeap {
+        &self.heap
+    }
+
+    /// Allocates `count` contiguous descriptors and returns the index of the first one. Returns
+    /// `None` if the heap is out of space.
+    pub fn allocate(&mut self, count: u32) -> Option<u32> {
+        let (i, range) = self
+            .free
+            .iter()
+            .enumerate()
+            .find(|(_, range)| range.len() as u32 >= count)?;
+
+        let start = range.start;
+        if range.len() as u32 == count {
+            self.free.remove(i);
+        } else {
+            self.free[i].start += count;
+        }
+
+        Some(start)
+    }
+
+    /// Returns a range of descriptors previously allocated with `allocate`.
+    pub fn free(&mut self, start: u32, count: u32) {
+        if count == 0 {
+            return;
+        }
+
+        let idx = self.free.partition_point(|range| range.start < start);
+        self.free.insert(idx, start..(start + count));
+
+        // Merge with the next range
+        if idx + 1 < self.free.len() && self.free[idx].end == self.free[idx + 1].start {
+            self.free[idx].end = self.free[idx + 1].end;
+            self.free.remove(idx + 1);
+        }
+
+        // Merge with the previous range
+        if idx > 0 && self.free[idx - 1].end == self.free[idx].start {
+            self.free[idx - 1].end = self.free[idx].end;
+            self.free.remove(idx);
+        }
+    }
+
+    #[inline(always)]
+    pub fn cpu(&self, idx: u32) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        D3D12_CPU_DESCRIPTOR_HANDLE {
+            ptr: self.cpu_start.ptr + (idx * self.increment) as usize,
+        }
+    }
+
+    #[inline(always)]
+    pub fn gpu(&self, idx: u32) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        D3D12_GPU_DESCRIPTOR_HANDLE {
+            ptr: self.gpu_start.ptr + (idx * self.increment) as u64,
+        }
+    }
+}
+
+/// All descriptor heaps used by the backend.
+pub(crate) struct DescriptorHeaps {
+    /// Shader visible heap for CBVs, SRVs, and UAVs.
+    pub resources: DescriptorHeap,
+    /// Shader visible heap for samplers.
+    pub samplers: DescriptorHeap,
+    /// CPU only heap for render target views.
+    pub rtv: DescriptorHeap,
+    /// CPU only heap for depth stencil views.
+    pub dsv: DescriptorHeap,
+}
+
+impl DescriptorHeaps {
+    pub const RESOURCE_COUNT: u32 = 500_000;
+    pub const SAMPLER_COUNT: u32 = D3D12_MAX_SHADER_VISIBLE_SAMPLER_HEAP_SIZE;
+    pub const RTV_COUNT: u32 = 16_384;
+    pub const DSV_COUNT: u32 = 4_096;
+
+    pub unsafe fn new(device: &ID3D12Device) -> windows::core::Result<Self> {
+        Ok(Self {
+            resources: DescriptorHeap::new(
+                device,
+                D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+                Self::RESOURCE_COUNT,
+                true,
+            )?,
+            samplers: DescriptorHeap::new(
+                device,
+                D3D12_DESCRIPTOR_HEAP_TYPE_SAMPLER,
+                Self::SAMPLER_COUNT,
+                true,
+            )?,
+            rtv: DescriptorHeap::new(
+                device,
+                D3D12_DESCRIPTOR_HEAP_TYPE_RTV,
+                Self::RTV_COUNT,
+                false,
+            )?,
+            dsv: DescriptorHeap::new(
+                device,
+                D3D12_DESCRIPTOR_HEAP_TYPE_DSV,
+                Self::DSV_COUNT,
+                false,
+            )?,
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Mutex;
+
+use crossbeam_channel::{Receiver, Sender};
+use gpu_allocator::d3d12::{Allocator, Resource};
+use windows::Win32::Graphics::Direct3D12::*;
+
+use crate::{
+    buffer::BufferRefCounter,
+    descriptor_set::{Binding, DescriptorSetBindings},
+    texture::TextureRefCounter,
+};
+
+use super::descriptor_heap::DescriptorHeaps;
+
+pub(crate) struct GarbageCollector {
+    sender: Sender<Garbage>,
+    receiver: Receiver<Garbage>,
+    to_destroy: Mutex<Vec<ToDestroy>>,
+}
+
+pub(crate) enum Garbage {
+    PipelineState(ID3D12PipelineState),
+    RootSignature(ID3D12RootSignature),
+    Buffer {
+        allocation: Resource,
+        ref_counter: BufferRefCounter,
+    },
+    Texture {
+        allocation: Resource,
+        ref_counter: TextureRefCounter,
+        rtvs: Vec<u32>,
+        dsvs: Vec<u32>,
+    },
+    DescriptorSet {
+        resources: Option<(u32, u32)>,
+        samplers: Option<(u32, u32)>,
+        bindings: DescriptorSetBindings,
+    },
+    /// Values replaced within a set that was updated while in use.
+    Bindings(Vec<Binding>),
+    /// Internal resource used for the duration of a single submission.
+    Temporary(Resource),
+}
+
+pub(crate) struct GarbageCleanupArgs<'a> {
+    pub allocator: &'a mut Allocator,
+    pub heaps: &'a mut DescriptorHeaps,
+    pub current: TimelineValues,
+    pub target: TimelineValues,
+    pub override_ref_counter: bool,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct TimelineValues {
+    pub main: u64,
+    pub transfer: u64,
+    pub compute: u64,
+}
+
+struct ToDestroy {
+    garbage: Garbage,
+    values: TimelineValues,
+}
+
+impl GarbageCollector {
+    pub fn new() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        Self {
+            sender,
+            receiver,
+            to_destroy: Mutex::new(Vec::default()),
+        }
+    }
+
+    #[inline(always)]
+    pub fn sender(&self) -> Sender<Garbage> {
+        self.sender.clone()
+    }
+
+    /// Returns `true` if there is no garbage waiting to be destroyed.
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty() && self.to_destroy.lock().unwrap().is_empty()
+    }
+
+    pub unsafe fn cleanup(&self, args: GarbageCleanupArgs) {
+        // Receive all incoming garbage
+        let mut to_destroy = self.to_destroy.lock().unwrap();
+        while let Ok(garbage) = self.receiver.try_recv() {
+            to_destroy.push(ToDestroy {
+                garbage,
+                values: args.target,
+            });
+        }
+
+        let mut i = 0;
+        while i < to_destroy.len() {
+            let garbage = &to_destroy[i];
+
+            let in_use = !args.override_ref_counter
+                && match &garbage.garbage {
+                    Garbage::Buffer { ref_counter, .. } => !ref_counter.is_last(),
+                    Garbage::Texture { ref_counter, .. } => !ref_counter.is_last(),
+                    _ => false,
+                };
+
+            let complete = garbage.values.main <= args.current.main
+                && garbage.values.transfer <= args.current.transfer
+                && garbage.values.compute <= args.current.compute;
+
+            if in_use || !complete {
+                i += 1;
+                continue;
+            }
+
+            match to_destroy.swap_remove(i).garbage {
+                // COM objects are released when dropped
+                Garbage::PipelineState(pipeline) => std::mem::drop(pipeline),
+                Garbage::RootSignature(signature) => std::mem::drop(signature),
+                Garbage::Buffer { allocation, .. } | Garbage::Temporary(allocation) => {
+                    args.allocator.free_resource(allocation).unwrap();
+                }
+                Garbage::Texture {
+                    allocation,
+                    rtvs,
+                    dsvs,
+                    ..
+                } => {
+                    for rtv in rtvs {
+                        args.heaps.rtv.free(rtv, 1);
+                    }
+                    for dsv in dsvs {
+                        args.heaps.dsv.free(dsv, 2);
+                    }
+                    args.allocator.free_resource(allocation).unwrap();
+                }
+                Garbage::DescriptorSet {
+                    resources,
+                    samplers,
+                    bindings,
+                } => {
+                    if let Some((start, count)) = resources {
+                        args.heaps.resources.free(start, count);
+                    }
+                    if let Some((start, count)) = samplers {
+                        args.heaps.samplers.free(start, count);
+                    }
+                    std::mem::drop(bindings);
+                }
+                Garbage::Bindings(bindings) => {
+                    std::mem::drop(bindings);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,588 @@
+pub mod command_signatures;
+pub mod descriptor_heap;
+pub mod garbage_collector;
+pub mod root_signature;
+pub mod states;
+pub mod usage;
+
+use api::{
+    descriptor_set::DescriptorType, graphics_pipeline::StencilOpState, texture::Sampler, types::*,
+};
+use windows::Win32::Graphics::{Direct3D::*, Direct3D12::*, Dxgi::Common::*};
+
+#[inline(always)]
+pub(crate) fn to_dxgi_format(format: Format) -> DXGI_FORMAT {
+    match format {
+        Format::R8Unorm => DXGI_FORMAT_R8_UNORM,
+        Format::R8Snorm => DXGI_FORMAT_R8_SNORM,
+        Format::R8UInt => DXGI_FORMAT_R8_UINT,
+        Format::R8SInt => DXGI_FORMAT_R8_SINT,
+        // D3D12 has no single channel sRGB format
+        Format::R8Srgb => DXGI_FORMAT_R8_UNORM,
+        Format::R16Unorm => DXGI_FORMAT_R16_UNORM,
+        Format::R16Snorm => DXGI_FORMAT_R16_SNORM,
+        Format::R16UInt => DXGI_FORMAT_R16_UINT,
+        Format::R16SInt => DXGI_FORMAT_R16_SINT,
+        Format::R16SFloat => DXGI_FORMAT_R16_FLOAT,
+        Format::R32UInt => DXGI_FORMAT_R32_UINT,
+        Format::R32SInt => DXGI_FORMAT_R32_SINT,
+        Format::R32SFloat => DXGI_FORMAT_R32_FLOAT,
+        Format::Rg8Unorm => DXGI_FORMAT_R8G8_UNORM,
+        Format::Rg8Snorm => DXGI_FORMAT_R8G8_SNORM,
+        Format::Rg8UInt => DXGI_FORMAT_R8G8_UINT,
+        Format::Rg8SInt => DXGI_FORMAT_R8G8_SINT,
+        // D3D12 has no two channel sRGB format
+        Format::Rg8Srgb => DXGI_FORMAT_R8G8_UNORM,
+        Format::Rg16Unorm => DXGI_FORMAT_R16G16_UNORM,
+        Format::Rg16Snorm => DXGI_FORMAT_R16G16_SNORM,
+        Format::Rg16UInt => DXGI_FORMAT_R16G16_UINT,
+        Format::Rg16SInt => DXGI_FORMAT_R16G16_SINT,
+        Format::Rg16SFloat => DXGI_FORMAT_R16G16_FLOAT,
+        Format::Rg32UInt => DXGI_FORMAT_R32G32_UINT,
+        Format::Rg32SInt => DXGI_FORMAT_R32G32_SINT,
+        Format::Rg32SFloat => DXGI_FORMAT_R32G32_FLOAT,
+        Format::Rgb32SFloat => DXGI_FORMAT_R32G32B32_FLOAT,
+        Format::Rgba8Unorm => DXGI_FORMAT_R8G8B8A8_UNORM,
+        Format::Rgba8Snorm => DXGI_FORMAT_R8G8B8A8_SNORM,
+        Format::Rgba8UInt => DXGI_FORMAT_R8G8B8A8_UINT,
+        Format::Rgba8SInt => DXGI_FORMAT_R8G8B8A8_SINT,
+        Format::Rgba8Srgb => DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+        Format::Rgba16Unorm => DXGI_FORMAT_R16G16B16A16_UNORM,
+        Format::Rgba16Snorm => DXGI_FORMAT_R16G16B16A16_SNORM,
+        Format::Rgba16UInt => DXGI_FORMAT_R16G16B16A16_UINT,
+        Format::Rgba16SInt => DXGI_FORMAT_R16G16B16A16_SINT,
+        Format::Rgba16SFloat => DXGI_FORMAT_R16G16B16A16_FLOAT,
+        Format::Rgba32UInt => DXGI_FORMAT_R32G32B32A32_UINT,
+        Format::Rgba32SInt => DXGI_FORMAT_R32G32B32A32_SINT,
+        Format::Rgba32SFloat => DXGI_FORMAT_R32G32B32A32_FLOAT,
+        Format::Bgra8Unorm => DXGI_FORMAT_B8G8R8A8_UNORM,
+        Format::Bgra8Srgb => DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+        Format::Rgb10A2Unorm => DXGI_FORMAT_R10G10B10A2_UNORM,
+        Format::BC6HUFloat => DXGI_FORMAT_BC6H_UF16,
+        Format::BC7Srgb => DXGI_FORMAT_BC7_UNORM_SRGB,
+        Format::BC7Unorm => DXGI_FORMAT_BC7_UNORM,
+        // D3D12 has no ASTC or ETC2 support
+        Format::Astc4x4Unorm
+        | Format::Astc4x4Srgb
+        | Format::Astc8x8Unorm
+        | Format::Astc8x8Srgb
+        | Format::Etc2Rgb8Unorm
+        | Format::Etc2Rgb8Srgb
+        | Format::Etc2Rgba8Unorm
+        | Format::Etc2Rgba8Srgb => DXGI_FORMAT_UNKNOWN,
+        Format::D16Unorm => DXGI_FORMAT_D16_UNORM,
+        Format::D24UnormS8Uint => DXGI_FORMAT_D24_UNORM_S8_UINT,
+        Format::D32Sfloat => DXGI_FORMAT_D32_FLOAT,
+        Format::D32SfloatS8Uint => DXGI_FORMAT_D32_FLOAT_S8X24_UINT,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_dxgi_color_space(color_space: ColorSpace) -> DXGI_COLOR_SPACE_TYPE {
+    match color_space {
+        ColorSpace::SrgbNonlinear => DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+        ColorSpace::Hdr10St2084 => DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+        ColorSpace::ExtendedSrgbLinear => DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709,
+    }
+}
+
+/// Depth textures that are sampled must be created with a typeless format so that both depth
+/// stencil views and shader resource views can be made from them. sRGB textures are also typeless
+/// so that they can be viewed as UNORM by storage images.
+#[inline(always)]
+pub(crate) fn to_dxgi_resource_format(format: Format) -> DXGI_FORMAT {
+    match format {
+        Format::Rgba8Unorm | Format::Rgba8Srgb => DXGI_FORMAT_R8G8B8A8_TYPELESS,
+        Format::Bgra8Unorm | Format::Bgra8Srgb => DXGI_FORMAT_B8G8R8A8_TYPELESS,
+        Format::BC7Unorm | Format::BC7Srgb => DXGI_FORMAT_BC7_TYPELESS,
+        Format::D16Unorm => DXGI_FORMAT_R16_TYPELESS,
+        Format::D24UnormS8Uint => DXGI_FORMAT_R24G8_TYPELESS,
+        Format::D32Sfloat => DXGI_FORMAT_R32_TYPELESS,
+        Format::D32SfloatS8Uint => DXGI_FORMAT_R32G8X24_TYPELESS,
+        other => to_dxgi_format(other),
+    }
+}
+
+/// Format used when viewing a texture from a shader.
+#[inline(always)]
+pub(crate) fn to_dxgi_srv_format(format: Format) -> DXGI_FORMAT {
+    match format {
+        Format::D16Unorm => DXGI_FORMAT_R16_UNORM,
+        Format::D24UnormS8Uint => DXGI_FORMAT_R24_UNORM_X8_TYPELESS,
+        Format::D32Sfloat => DXGI_FORMAT_R32_FLOAT,
+        Format::D32SfloatS8Uint => DXGI_FORMAT_R32_FLOAT_X8X24_TYPELESS,
+        other => to_dxgi_format(other),
+    }
+}
+
+/// Format and plane used when viewing a single aspect of a texture from a shader. Stencil is
+/// stored in the second plane of depth stencil formats.
+#[inline(always)]
+pub(crate) fn to_dxgi_aspect_srv_format(
+    format: Format,
+    aspect: TextureAspect,
+) -> (DXGI_FORMAT, u32) {
+    match (format, aspect) {
+        (Format::D24UnormS8Uint, TextureAspect::Stencil) => (DXGI_FORMAT_X24_TYPELESS_G8_UINT, 1),
+        (Format::D32SfloatS8Uint, TextureAspect::Stencil) => {
+            (DXGI_FORMAT_X32_TYPELESS_G8X24_UINT, 1)
+        }
+        (other, _) => (to_dxgi_srv_format(other), 0),
+    }
+}
+
+/// Storage images can't use sRGB formats.
+#[inline(always)]
+pub(crate) fn to_dxgi_uav_format(format: Format) -> DXGI_FORMAT {
+    match format {
+        Format::Rgba8Srgb => DXGI_FORMAT_R8G8B8A8_UNORM,
+        Format::Bgra8Srgb => DXGI_FORMAT_B8G8R8A8_UNORM,
+        other => to_dxgi_srv_format(other),
+    }
+}
+
+/// Number of planes a format has. Depth stencil formats store stencil in a second plane.
+#[inline(always)]
+pub(crate) fn plane_count(format: Format) -> u32 {
+    if format.is_stencil() {
+        2
+    } else {
+        1
+    }
+}
+
+/// Size in bytes of a single texel block, and the dimensions of the block in texels.
+#[inline(always)]
+pub(crate) fn format_block_info(format: Format) -> (u32, u32) {
+    match format {
+        Format::R8Unorm | Format::R8Snorm | Format::R8UInt | Format::R8SInt | Format::R8Srgb => {
+            (1, 1)
+        }
+        Format::R16Unorm
+        | Format::R16Snorm
+        | Format::R16UInt
+        | Format::R16SInt
+        | Format::R16SFloat
+        | Format::Rg8Unorm
+        | Format::Rg8Snorm
+        | Format::Rg8UInt
+        | Format::Rg8SInt
+        | Format::Rg8Srgb
+        | Format::D16Unorm => (2, 1),
+        Format::R32UInt
+        | Format::R32SInt
+        | Format::R32SFloat
+        | Format::Rg16Unorm
+        | Format::Rg16Snorm
+        | Format::Rg16UInt
+        | Format::Rg16SInt
+        | Format::Rg16SFloat
+        | Format::Rgba8Unorm
+        | Format::Rgba8Snorm
+        | Format::Rgba8UInt
+        | Format::Rgba8SInt
+        | Format::Rgba8Srgb
+        | Format::Bgra8Unorm
+        | Format::Bgra8Srgb
+        | Format::Rgb10A2Unorm
+        | Format::D24UnormS8Uint
+        | Format::D32Sfloat => (4, 1),
+        Format::Rg32UInt
+        | Format::Rg32SInt
+        | Format::Rg32SFloat
+        | Format::Rgba16Unorm
+        | Format::Rgba16Snorm
+        | Format::Rgba16UInt
+        | Format::Rgba16SInt
+        | Format::Rgba16SFloat
+        | Format::D32SfloatS8Uint => (8, 1),
+        Format::Rgb32SFloat => (12, 1),
+        Format::Rgba32UInt | Format::Rgba32SInt | Format::Rgba32SFloat => (16, 1),
+        Format::Etc2Rgb8Unorm | Format::Etc2Rgb8Srgb => (8, 4),
+        Format::BC6HUFloat
+        | Format::BC7Srgb
+        | Format::BC7Unorm
+        | Format::Astc4x4Unorm
+        | Format::Astc4x4Srgb
+        | Format::Etc2Rgba8Unorm
+        | Format::Etc2Rgba8Srgb => (16, 4),
+        Format::Astc8x8Unorm | Format::Astc8x8Srgb => (16, 8),
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_sample_count(samples: MultiSamples) -> u32 {
+    samples.count() as u32
+}
+
+#[inline(always)]
+pub(crate) fn to_gpu_allocator_memory_location(
+    memory_usage: MemoryUsage,
+) -> gpu_allocator::MemoryLocation {
+    match memory_usage {
+        MemoryUsage::Unknown => gpu_allocator::MemoryLocation::Unknown,
+        MemoryUsage::GpuOnly => gpu_allocator::MemoryLocation::GpuOnly,
+        MemoryUsage::CpuToGpu => gpu_allocator::MemoryLocation::CpuToGpu,
+        MemoryUsage::GpuToCpu => gpu_allocator::MemoryLocation::GpuToCpu,
+    }
+}
+
+/// Resources in upload and readback heaps have a fixed state for their whole lifetime.
+#[inline(always)]
+pub(crate) fn fixed_buffer_state(memory_usage: MemoryUsage) -> Option<D3D12_RESOURCE_STATES> {
+    match memory_usage {
+        MemoryUsage::CpuToGpu => Some(D3D12_RESOURCE_STATE_GENERIC_READ),
+        MemoryUsage::GpuToCpu => Some(D3D12_RESOURCE_STATE_COPY_DEST),
+        MemoryUsage::Unknown | MemoryUsage::GpuOnly => None,
+    }
+}
+
+/// The state a resource must be in to be accessed through a descriptor of the given type.
+#[inline(always)]
+pub(crate) fn descriptor_type_to_state(
+    ty: DescriptorType,
+    stage: ShaderStage,
+) -> D3D12_RESOURCE_STATES {
+    let shader_resource = match stage {
+        ShaderStage::Fragment => D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+        ShaderStage::Vertex
+        | ShaderStage::Compute
+        | ShaderStage::Mesh
+        | ShaderStage::Task
+        | ShaderStage::RayTracing
+        | ShaderStage::RayGeneration
+        | ShaderStage::RayMiss
+        | ShaderStage::RayClosestHit
+        | ShaderStage::RayAnyHit
+        | ShaderStage::RayIntersection => D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+        ShaderStage::AllGraphics | ShaderStage::AllStages => {
+            D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE
+        }
+    };
+
+    match ty {
+        DescriptorType::Texture | DescriptorType::CubeMap | DescriptorType::CubeMapArray => {
+            shader_resource
+        }
+        DescriptorType::UniformBuffer => D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER,
+        DescriptorType::StorageBuffer(AccessType::Read) => shader_resource,
+        DescriptorType::StorageBuffer(AccessType::ReadWrite) | DescriptorType::StorageImage(_) => {
+            D3D12_RESOURCE_STATE_UNORDERED_ACCESS
+        }
+        DescriptorType::TopLevelAccelerationStructure => {
+            D3D12_RESOURCE_STATE_RAYTRACING_ACCELERATION_STRUCTURE
+        }
+        DescriptorType::UniformTexelBuffer => shader_resource,
+        DescriptorType::StorageTexelBuffer(_) => D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+    }
+}
+
+/// The descriptor range a descriptor type is placed in within a root signature.
+#[inline(always)]
+pub(crate) fn descriptor_type_to_range_type(ty: DescriptorType) -> D3D12_DESCRIPTOR_RANGE_TYPE {
+    match ty {
+        DescriptorType::Texture
+        | DescriptorType::CubeMap
+        | DescriptorType::CubeMapArray
+        | DescriptorType::TopLevelAccelerationStructure
+        | DescriptorType::StorageBuffer(AccessType::Read)
+        | DescriptorType::UniformTexelBuffer => D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+        DescriptorType::UniformBuffer => D3D12_DESCRIPTOR_RANGE_TYPE_CBV,
+        DescriptorType::StorageBuffer(AccessType::ReadWrite)
+        | DescriptorType::StorageImage(_)
+        | DescriptorType::StorageTexelBuffer(_) => D3D12_DESCRIPTOR_RANGE_TYPE_UAV,
+    }
+}
+
+/// Indicates if a descriptor type also consumes a slot in the sampler table.
+#[inline(always)]
+pub(crate) fn descriptor_type_has_sampler(ty: DescriptorType) -> bool {
+    matches!(
+        ty,
+        DescriptorType::Texture | DescriptorType::CubeMap | DescriptorType::CubeMapArray
+    )
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_texture_state(usage: TextureUsage) -> D3D12_RESOURCE_STATES {
+    if usage.contains(TextureUsage::STORAGE) {
+        D3D12_RESOURCE_STATE_UNORDERED_ACCESS
+    } else if usage.contains(TextureUsage::SAMPLED) {
+        D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE
+    } else if usage.contains(TextureUsage::COLOR_ATTACHMENT) {
+        D3D12_RESOURCE_STATE_RENDER_TARGET
+    } else if usage.contains(TextureUsage::DEPTH_STENCIL_ATTACHMENT) {
+        D3D12_RESOURCE_STATE_DEPTH_WRITE
+    } else if usage.contains(TextureUsage::TRANSFER_DST) {
+        D3D12_RESOURCE_STATE_COPY_DEST
+    } else if usage.contains(TextureUsage::TRANSFER_SRC) {
+        D3D12_RESOURCE_STATE_COPY_SOURCE
+    } else {
+        D3D12_RESOURCE_STATE_COMMON
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_topology_type(topology: PrimitiveTopology) -> D3D12_PRIMITIVE_TOPOLOGY_TYPE {
+    match topology {
+        PrimitiveTopology::PontList => D3D12_PRIMITIVE_TOPOLOGY_TYPE_POINT,
+        PrimitiveTopology::LineList => D3D12_PRIMITIVE_TOPOLOGY_TYPE_LINE,
+        PrimitiveTopology::TriangleList => D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d_topology(topology: PrimitiveTopology) -> D3D_PRIMITIVE_TOPOLOGY {
+    match topology {
+        PrimitiveTopology::PontList => D3D_PRIMITIVE_TOPOLOGY_POINTLIST,
+        PrimitiveTopology::LineList => D3D_PRIMITIVE_TOPOLOGY_LINELIST,
+        PrimitiveTopology::TriangleList => D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_fill_mode(mode: PolygonMode) -> D3D12_FILL_MODE {
+    match mode {
+        PolygonMode::Fill => D3D12_FILL_MODE_SOLID,
+        PolygonMode::Line => D3D12_FILL_MODE_WIREFRAME,
+        // D3D12 can't rasterize polygons as points
+        PolygonMode::Point => D3D12_FILL_MODE_SOLID,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_cull_mode(mode: CullMode) -> D3D12_CULL_MODE {
+    match mode {
+        CullMode::None => D3D12_CULL_MODE_NONE,
+        CullMode::Front => D3D12_CULL_MODE_FRONT,
+        CullMode::Back => D3D12_CULL_MODE_BACK,
+        // D3D12 has no way to cull all faces
+        CullMode::FrontAndBack => D3D12_CULL_MODE_NONE,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_compare_op(op: CompareOp) -> D3D12_COMPARISON_FUNC {
+    match op {
+        CompareOp::Never => D3D12_COMPARISON_FUNC_NEVER,
+        CompareOp::Less => D3D12_COMPARISON_FUNC_LESS,
+        CompareOp::Equal => D3D12_COMPARISON_FUNC_EQUAL,
+        CompareOp::LessOrEqual => D3D12_COMPARISON_FUNC_LESS_EQUAL,
+        CompareOp::Greater => D3D12_COMPARISON_FUNC_GREATER,
+        CompareOp::NotEqual => D3D12_COMPARISON_FUNC_NOT_EQUAL,
+        CompareOp::GreaterOrEqual => D3D12_COMPARISON_FUNC_GREATER_EQUAL,
+        CompareOp::Always => D3D12_COMPARISON_FUNC_ALWAYS,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_stencil_op(op: StencilOp) -> D3D12_STENCIL_OP {
+    match op {
+        StencilOp::Keep => D3D12_STENCIL_OP_KEEP,
+        StencilOp::Zero => D3D12_STENCIL_OP_ZERO,
+        StencilOp::Replace => D3D12_STENCIL_OP_REPLACE,
+        StencilOp::IncrementAndClamp => D3D12_STENCIL_OP_INCR_SAT,
+        StencilOp::DecrementAndClamp => D3D12_STENCIL_OP_DECR_SAT,
+        StencilOp::Invert => D3D12_STENCIL_OP_INVERT,
+        StencilOp::IncrementAndWrap => D3D12_STENCIL_OP_INCR,
+        StencilOp::DecrementAndWrap => D3D12_STENCIL_OP_DECR,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_stencil_op_desc(state: &StencilOpState) -> D3D12_DEPTH_STENCILOP_DESC {
+    D3D12_DEPTH_STENCILOP_DESC {
+        StencilFailOp: to_d3d12_stencil_op(state.fail_op),
+        StencilDepthFailOp: to_d3d12_stencil_op(state.depth_fail_op),
+        StencilPassOp: to_d3d12_stencil_op(state.pass_op),
+        StencilFunc: to_d3d12_compare_op(state.compare_op),
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_blend(factor: BlendFactor) -> D3D12_BLEND {
+    match factor {
+        BlendFactor::Zero => D3D12_BLEND_ZERO,
+        BlendFactor::One => D3D12_BLEND_ONE,
+        BlendFactor::SrcColor => D3D12_BLEND_SRC_COLOR,
+        BlendFactor::OneMinusSrcColor => D3D12_BLEND_INV_SRC_COLOR,
+        BlendFactor::DstColor => D3D12_BLEND_DEST_COLOR,
+        BlendFactor::OneMinusDstColor => D3D12_BLEND_INV_DEST_COLOR,
+        BlendFactor::SrcAlpha => D3D12_BLEND_SRC_ALPHA,
+        BlendFactor::OneMinusSrcAlpha => D3D12_BLEND_INV_SRC_ALPHA,
+        BlendFactor::DstAlpha => D3D12_BLEND_DEST_ALPHA,
+        BlendFactor::OneMinusDstAlpha => D3D12_BLEND_INV_DEST_ALPHA,
+        // D3D12 has a single blend factor, so color and alpha constants are the same
+        BlendFactor::ConstantColor | BlendFactor::ConstantAlpha => D3D12_BLEND_BLEND_FACTOR,
+        BlendFactor::OneMinusConstantColor | BlendFactor::OneMinusConstantAlpha => {
+            D3D12_BLEND_INV_BLEND_FACTOR
+        }
+        BlendFactor::Src1Color => D3D12_BLEND_SRC1_COLOR,
+        BlendFactor::OneMinusSrc1Color => D3D12_BLEND_INV_SRC1_COLOR,
+        BlendFactor::Src1Alpha => D3D12_BLEND_SRC1_ALPHA,
+        BlendFactor::OneMinusSrc1Alpha => D3D12_BLEND_INV_SRC1_ALPHA,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_blend_op(op: BlendOp) -> D3D12_BLEND_OP {
+    match op {
+        BlendOp::Add => D3D12_BLEND_OP_ADD,
+        BlendOp::Subtract => D3D12_BLEND_OP_SUBTRACT,
+        BlendOp::ReverseSubtract => D3D12_BLEND_OP_REV_SUBTRACT,
+        BlendOp::Min => D3D12_BLEND_OP_MIN,
+        BlendOp::Max => D3D12_BLEND_OP_MAX,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_write_mask(components: ColorComponents) -> u8 {
+    let mut mask = 0;
+    if components.contains(ColorComponents::R) {
+        mask |= D3D12_COLOR_WRITE_ENABLE_RED.0;
+    }
+    if components.contains(ColorComponents::G) {
+        mask |= D3D12_COLOR_WRITE_ENABLE_GREEN.0;
+    }
+    if components.contains(ColorComponents::B) {
+        mask |= D3D12_COLOR_WRITE_ENABLE_BLUE.0;
+    }
+    if components.contains(ColorComponents::A) {
+        mask |= D3D12_COLOR_WRITE_ENABLE_ALPHA.0;
+    }
+    mask as u8
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_input_classification(rate: VertexInputRate) -> D3D12_INPUT_CLASSIFICATION {
+    match rate {
+        VertexInputRate::Vertex => D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+        VertexInputRate::Instance => D3D12_INPUT_CLASSIFICATION_PER_INSTANCE_DATA,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_dxgi_index_format(ty: IndexType) -> DXGI_FORMAT {
+    match ty {
+        IndexType::U16 => DXGI_FORMAT_R16_UINT,
+        IndexType::U32 => DXGI_FORMAT_R32_UINT,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_address_mode(mode: SamplerAddressMode) -> D3D12_TEXTURE_ADDRESS_MODE {
+    match mode {
+        SamplerAddressMode::Repeat => D3D12_TEXTURE_ADDRESS_MODE_WRAP,
+        SamplerAddressMode::MirroredRepeat => D3D12_TEXTURE_ADDRESS_MODE_MIRROR,
+        SamplerAddressMode::ClampToEdge => D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+        SamplerAddressMode::ClampToBorder => D3D12_TEXTURE_ADDRESS_MODE_BORDER,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_border_color(color: BorderColor) -> [f32; 4] {
+    match color {
+        BorderColor::FloatTransparentBlack | BorderColor::IntTransparentBlack => [0.0; 4],
+        BorderColor::FloatOpaqueBlack | BorderColor::IntOpaqueBlack => [0.0, 0.0, 0.0, 1.0],
+        BorderColor::FloatOpaqueWhite | BorderColor::IntOpaqueWhite => [1.0; 4],
+        BorderColor::CustomFloat(color) => color.map(|c| c.into_inner()),
+        // Border colors are always specified as floats and converted to the texture's format
+        BorderColor::CustomInt(color) => color.map(|c| c as f32),
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_anisotropy(level: AnisotropyLevel) -> u32 {
+    match level {
+        AnisotropyLevel::X1 => 1,
+        AnisotropyLevel::X2 => 2,
+        AnisotropyLevel::X4 => 4,
+        AnisotropyLevel::X8 => 8,
+        AnisotropyLevel::X16 => 16,
+    }
+}
+
+pub(crate) fn to_d3d12_sampler_desc(sampler: &Sampler) -> D3D12_SAMPLER_DESC {
+    let linear = |filter: Filter| filter == Filter::Linear;
+    let min = linear(sampler.min_filter) as i32;
+    let mag = linear(sampler.mag_filter) as i32;
+    let mip = linear(sampler.mipmap_filter) as i32;
+
+    // Filter bit layout is `MIN << 4 | MAG << 2 | MIP` with the comparison and anisotropic flags
+    // in the upper bits.
+    let mut filter = (min << 4) | (mag << 2) | mip;
+    if sampler.anisotropy.is_some() {
+        filter = D3D12_FILTER_ANISOTROPIC.0;
+    }
+    if sampler.compare.is_some() {
+        filter |= D3D12_FILTER_COMPARISON_MIN_MAG_MIP_POINT.0;
+    }
+    match sampler.reduction_mode {
+        Some(ReductionMode::Min) => filter |= D3D12_FILTER_MINIMUM_MIN_MAG_MIP_POINT.0,
+        Some(ReductionMode::Max) => filter |= D3D12_FILTER_MAXIMUM_MIN_MAG_MIP_POINT.0,
+        None => {}
+    }
+
+    D3D12_SAMPLER_DESC {
+        Filter: D3D12_FILTER(filter),
+        AddressU: to_d3d12_address_mode(sampler.address_u),
+        AddressV: to_d3d12_address_mode(sampler.address_v),
+        AddressW: to_d3d12_address_mode(sampler.address_w),
+        MipLODBias: 0.0,
+        MaxAnisotropy: sampler.anisotropy.map(to_d3d12_anisotropy).unwrap_or(1),
+        ComparisonFunc: sampler
+            .compare
+            .map(to_d3d12_compare_op)
+            .unwrap_or(D3D12_COMPARISON_FUNC_NEVER),
+        BorderColor: sampler
+            .border_color
+            .map(to_d3d12_border_color)
+            .unwrap_or_default(),
+        MinLOD: sampler.min_lod.into_inner(),
+        MaxLOD: match sampler.max_lod {
+            Some(lod) => lod.into_inner(),
+            None => D3D12_FLOAT32_MAX,
+        },
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_d3d12_clear_color(color: ClearColor) -> [f32; 4] {
+    match color {
+        ClearColor::RgbaF32(r, g, b, a) => [r, g, b, a],
+        ClearColor::RU32(r) => [r as f32, 0.0, 0.0, 0.0],
+        ClearColor::D32S32(d, _) => [d, 0.0, 0.0, 0.0],
+    }
+}
+
+/// Cube map faces are stored in the order +X, -X, +Y, -Y, +Z, -Z.
+#[inline(always)]
+pub(crate) const fn cube_face_to_idx(face: CubeFace) -> usize {
+    match face {
+        CubeFace::East => 0,
+        CubeFace::West => 1,
+        CubeFace::Top => 2,
+        CubeFace::Bottom => 3,
+        CubeFace::North => 4,
+        CubeFace::South => 5,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn queue_type_to_idx(ty: QueueType) -> usize {
+    match ty {
+        QueueType::Main | QueueType::Present => 0,
+        QueueType::Transfer => 1,
+        QueueType::Compute => 2,
+    }
+}
+
+#[inline(always)]
+pub(crate) fn idx_to_queue_type(idx: usize) -> QueueType {
+    match idx {
+        0 => QueueType::Main,
+        1 => QueueType::Transfer,
+        2 => QueueType::Compute,
+        _ => unreachable!("there are only three queues"),
+    }
+}
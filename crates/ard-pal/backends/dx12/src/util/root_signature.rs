@@ -0,0 +1,170 @@
+use api::descriptor_set::DescriptorSetLayout;
+use windows::Win32::Graphics::Direct3D12::*;
+
+use crate::Dx12Backend;
+
+/// Register space used for push constants. Descriptor sets use their set index as their
+/// register space, so this must be larger than any reasonable number of sets.
+pub const PUSH_CONSTANT_SPACE: u32 = 1024;
+
+/// A root signature generated from a list of descriptor set layouts.
+///
+/// Each set is mapped to up to two descriptor tables. One for CBVs/SRVs/UAVs and another for the
+/// samplers of combined image samplers. Binding `N` of set `S` lives at register `N` of space `S`
+/// for whichever register class the descriptor type uses.
+pub(crate) struct RootSignature {
+    pub signature: ID3D12RootSignature,
+    pub sets: Vec<RootSetParameters>,
+    /// Root parameter index for push constants.
+    pub push_constants: Option<u32>,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct RootSetParameters {
+    pub resources: Option<u32>,
+    pub samplers: Option<u32>,
+}
+
+impl RootSignature {
+    pub unsafe fn new(
+        device: &ID3D12Device,
+        layouts: &[DescriptorSetLayout<Dx12Backend>],
+        push_constants_size: Option<u32>,
+        input_assembler: bool,
+    ) -> Result<Self, String> {
+        // Gather ranges for every set first so that pointers remain stable
+        let mut resource_ranges = Vec::with_capacity(layouts.len());
+        let mut sampler_ranges = Vec::with_capacity(layouts.len());
+
+        for (space, layout) in layouts.iter().enumerate() {
+            let layout = layout.internal();
+            let mut resources = Vec::with_capacity(layout.descriptor.bindings.len());
+            let mut samplers = Vec::default();
+
+            for (i, binding) in layout.descriptor.bindings.iter().enumerate() {
+                resources.push(D3D12_DESCRIPTOR_RANGE {
+                    RangeType: super::descriptor_type_to_range_type(binding.ty),
+                    NumDescriptors: binding.count as u32,
+                    BaseShaderRegister: binding.binding,
+                    RegisterSpace: space as u32,
+                    OffsetInDescriptorsFromTableStart: layout.resource_offsets[i],
+                });
+
+                if let Some(offset) = layout.sampler_offsets[i] {
+                    samplers.push(D3D12_DESCRIPTOR_RANGE {
+                        RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_SAMPLER,
+                        NumDescriptors: binding.count as u32,
+                        BaseShaderRegister: binding.binding,
+                        RegisterSpace: space as u32,
+                        OffsetInDescriptorsFromTableStart: offset,
+                    });
+                }
+            }
+
+            resource_ranges.push(resources);
+            sampler_ranges.push(samplers);
+        }
+
+        // Create root parameters
+        let mut params = Vec::with_capacity(layouts.len() * 2 + 1);
+        let mut sets = Vec::with_capacity(layouts.len());
+        let mut push_constants = None;
+
+        if let Some(size) = push_constants_size {
+            push_constants = Some(params.len() as u32);
+            params.push(D3D12_ROOT_PARAMETER {
+                ParameterType: D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+                Anonymous: D3D12_ROOT_PARAMETER_0 {
+                    Constants: D3D12_ROOT_CONSTANTS {
+                        ShaderRegister: 0,
+                        RegisterSpace: PUSH_CONSTANT_SPACE,
+                        Num32BitValues: size.div_ceil(4),
+                    },
+                },
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+            });
+        }
+
+        for (resources, samplers) in resource_ranges.iter().zip(sampler_ranges.iter()) {
+            let mut set = RootSetParameters::default();
+
+            if !resources.is_empty() {
+                set.resources = Some(params.len() as u32);
+                params.push(D3D12_ROOT_PARAMETER {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+                    Anonymous: D3D12_ROOT_PARAMETER_0 {
+                        DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE {
+                            NumDescriptorRanges: resources.len() as u32,
+                            pDescriptorRanges: resources.as_ptr(),
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+                });
+            }
+
+            if !samplers.is_empty() {
+                set.samplers = Some(params.len() as u32);
+                params.push(D3D12_ROOT_PARAMETER {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+                    Anonymous: D3D12_ROOT_PARAMETER_0 {
+                        DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE {
+                            NumDescriptorRanges: samplers.len() as u32,
+                            pDescriptorRanges: samplers.as_ptr(),
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+                });
+            }
+
+            sets.push(set);
+        }
+
+        let desc = D3D12_ROOT_SIGNATURE_DESC {
+            NumParameters: params.len() as u32,
+            pParameters: params.as_ptr(),
+            NumStaticSamplers: 0,
+            pStaticSamplers: std::ptr::null(),
+            Flags: if input_assembler {
+                D3D12_ROOT_SIGNATURE_FLAG_ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT
+            } else {
+                D3D12_ROOT_SIGNATURE_FLAG_NONE
+            },
+        };
+
+        // Serialize and create the signature
+        let mut blob = None;
+        let mut error = None;
+        if let Err(err) = D3D12SerializeRootSignature(
+            &desc,
+            D3D_ROOT_SIGNATURE_VERSION_1,
+            &mut blob,
+            Some(&mut error),
+        ) {
+            return Err(match error {
+                Some(error) => {
+                    let msg = std::slice::from_raw_parts(
+                        error.GetBufferPointer() as *const u8,
+                        error.GetBufferSize(),
+                    );
+                    String::from_utf8_lossy(msg).into_owned()
+                }
+                None => err.to_string(),
+            });
+        }
+
+        let blob = blob.unwrap();
+        let signature = match device.CreateRootSignature(
+            0,
+            std::slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize()),
+        ) {
+            Ok(signature) => signature,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        Ok(Self {
+            signature,
+            sets,
+            push_constants,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    mem::ManuallyDrop,
+    sync::{Arc, Mutex},
+};
+
+use rustc_hash::FxHashMap;
+use windows::{core::Interface, Win32::Graphics::Direct3D12::*};
+
+/// Tracks the current state of every subresource of a texture.
+///
+/// Unlike buffers, textures do not decay back to the common state at the end of a command list,
+/// so their state must persist between submissions.
+#[derive(Clone)]
+pub(crate) struct SubresourceStates(Arc<Mutex<SubresourceStatesInner>>);
+
+struct SubresourceStatesInner {
+    /// Flattened like D3D12 subresource indices. `mip + (array * mip_count)` for each plane.
+    states: Vec<D3D12_RESOURCE_STATES>,
+    mip_count: u32,
+    array_layers: u32,
+    plane_count: u32,
+}
+
+/// Records resource barriers for a single command list.
+#[derive(Default)]
+pub(crate) struct Barriers {
+    barriers: Vec<D3D12_RESOURCE_BARRIER>,
+    /// Buffers decay to the common state whenever a command list finishes, so their states only
+    /// need to be tracked for the duration of a single submission.
+    buffers: FxHashMap<usize, D3D12_RESOURCE_STATES>,
+}
+
+impl SubresourceStates {
+    pub fn new(mip_count: u32, array_layers: u32, plane_count: u32) -> Self {
+        Self(Arc::new(Mutex::new(SubresourceStatesInner {
+            states: vec![
+                D3D12_RESOURCE_STATE_COMMON;
+                (mip_count * array_layers * plane_count) as usize
+            ],
+            mip_count,
+            array_layers,
+            plane_count,
+        })))
+    }
+
+    /// Computes the D3D12 subresource index of a mip level, array layer, and plane.
+    #[inline(always)]
+    pub fn subresource(&self, mip: u32, layer: u32, plane: u32) -> u32 {
+        let inner = self.0.lock().unwrap();
+        mip + (layer * inner.mip_count) + (plane * inner.mip_count * inner.array_layers)
+    }
+
+    /// Transitions a range of mips of a range of array layers into a new state.
+    pub fn transition(
+        &self,
+        barriers: &mut Barriers,
+        resource: &ID3D12Resource,
+        mips: std::ops::Range<u32>,
+        layers: std::ops::Range<u32>,
+        new_state: D3D12_RESOURCE_STATES,
+    ) {
+        let mut inner = self.0.lock().unwrap();
+        for plane in 0..inner.plane_count {
+            for layer in layers.clone() {
+                for mip in mips.clone() {
+                    let subresource = mip
+                        + (layer * inner.mip_count)
+                        + (plane * inner.mip_count * inner.array_layers);
+                    let old_state = inner.states[subresource as usize];
+                    barriers.transition(resource, subresource, old_state, new_state);
+                    inner.states[subresource as usize] = new_state;
+                }
+            }
+        }
+    }
+
+    /// Transitions every subresource into a new state.
+    pub fn transition_all(
+        &self,
+        barriers: &mut Barriers,
+        resource: &ID3D12Resource,
+        new_state: D3D12_RESOURCE_STATES,
+    ) {
+        let (mip_count, array_layers) = {
+            let inner = self.0.lock().unwrap();
+            (inner.mip_count, inner.array_layers)
+        };
+        self.transition(barriers, resource, 0..mip_count, 0..array_layers, new_state);
+    }
+
+    /// Forces every subresource into the provided state without recording a barrier. Used when
+    /// the state of the resource is changed externally (e.g. swapchain images after present).
+    pub fn reset(&self, state: D3D12_RESOURCE_STATES) {
+        let mut inner = self.0.lock().unwrap();
+        inner.states.iter_mut().for_each(|s| *s = state);
+    }
+}
+
+impl Barriers {
+    /// Records a transition for a subresource. UAV to UAV transitions are turned into UAV
+    /// barriers instead.
+    pub fn transition(
+        &mut self,
+        resource: &ID3D12Resource,
+        subresource: u32,
+        old_state: D3D12_RESOURCE_STATES,
+        new_state: D3D12_RESOURCE_STATES,
+    ) {
+        if old_state == new_state {
+            if new_state == D3D12_RESOURCE_STATE_UNORDERED_ACCESS {
+                self.uav(Some(resource));
+            }
+            return;
+        }
+
+        self.barriers.push(D3D12_RESOURCE_BARRIER {
+            Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
+            Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            Anonymous: D3D12_RESOURCE_BARRIER_0 {
+                Transition: ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
+                    // SAFETY: The barrier does not hold a reference to the resource. Barriers are
+                    // always flushed before the command referencing the resource ends.
+                    pResource: unsafe { std::mem::transmute_copy(resource) },
+                    Subresource: subresource,
+                    StateBefore: old_state,
+                    StateAfter: new_state,
+                }),
+            },
+        });
+    }
+
+    /// Records a transition for an entire buffer.
+    pub fn transition_buffer(
+        &mut self,
+        resource: &ID3D12Resource,
+        new_state: D3D12_RESOURCE_STATES,
+    ) {
+        let old_state = self
+            .buffers
+            .entry(resource.as_raw() as usize)
+            .or_insert(D3D12_RESOURCE_STATE_COMMON);
+
+        // Read only states can be combined instead of transitioned
+        let is_read = |state: D3D12_RESOURCE_STATES| {
+            state != D3D12_RESOURCE_STATE_COMMON
+                && (state.0 & D3D12_RESOURCE_STATE_GENERIC_READ.0) == state.0
+        };
+        if is_read(*old_state) && is_read(new_state) {
+            if (old_state.0 & new_state.0) == new_state.0 {
+                return;
+            }
+            let combined = D3D12_RESOURCE_STATES(old_state.0 | new_state.0);
+            let prev = *old_state;
+            *old_state = combined;
+            self.transition(
+                resource,
+                D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+                prev,
+                combined,
+            );
+            return;
+        }
+
+        let prev = *old_state;
+        *old_state = new_state;
+        self.transition(
+            resource,
+            D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+            prev,
+            new_state,
+        );
+    }
+
+    /// Records a UAV barrier. If `resource` is `None`, all UAV accesses must complete.
+    pub fn uav(&mut self, resource: Option<&ID3D12Resource>) {
+        self.barriers.push(D3D12_RESOURCE_BARRIER {
+            Type: D3D12_RESOURCE_BARRIER_TYPE_UAV,
+            Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+            Anonymous: D3D12_RESOURCE_BARRIER_0 {
+                UAV: ManuallyDrop::new(D3D12_RESOURCE_UAV_BARRIER {
+                    // SAFETY: See `transition`.
+                    pResource: match resource {
+                        Some(resource) => unsafe { std::mem::transmute_copy(resource) },
+                        None => ManuallyDrop::new(None),
+                    },
+                }),
+            },
+        });
+    }
+
+    /// Submits all recorded barriers to the command list.
+    pub unsafe fn flush(&mut self, list: &ID3D12GraphicsCommandList) {
+        if self.barriers.is_empty() {
+            return;
+        }
+        list.ResourceBarrier(&self.barriers);
+        self.barriers.clear();
+    }
+}
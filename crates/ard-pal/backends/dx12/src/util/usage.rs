@@ -0,0 +1,51 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use api::types::QueueType;
+
+use crate::{job::Job, Dx12Backend};
+
+/// Tracks the last fence value of each queue that a resource was used in so that the CPU can
+/// wait for the GPU to finish with it.
+#[derive(Default, Clone)]
+pub(crate) struct UsageTracker(Arc<[AtomicU64; 3]>);
+
+impl UsageTracker {
+    #[inline(always)]
+    pub fn mark(&self, queue: QueueType, value: u64) {
+        self.0[super::queue_type_to_idx(queue)].fetch_max(value, Ordering::Relaxed);
+    }
+
+    /// Marks the resource as used by a submission and accumulates, per queue, the last value
+    /// that must be waited on before the submission can touch the resource.
+    #[inline(always)]
+    pub fn track(&self, queue: QueueType, value: u64, waits: &mut [u64; 3]) {
+        for (wait, last) in waits.iter_mut().zip(self.0.iter()) {
+            *wait = (*wait).max(last.load(Ordering::Relaxed));
+        }
+        self.mark(queue, value);
+    }
+
+    /// Blocks until every queue has finished using the resource.
+    pub unsafe fn wait(&self, ctx: &Dx12Backend) {
+        use api::Backend;
+
+        for (i, value) in self.0.iter().enumerate() {
+            let target_value = value.load(Ordering::Relaxed);
+            if target_value == 0 {
+                continue;
+            }
+
+            ctx.wait_on(
+                &Job {
+                    ty: super::idx_to_queue_type(i),
+                    target_value,
+                    debug_name: None,
+                },
+                None,
+            );
+        }
+    }
+}
@@ -1,12 +1,47 @@
 use api::{
-    context::GraphicsProperties, rt_pipeline::ShaderBindingTableData, surface::SurfaceCapabilities,
+    context::{AdapterInfo, GraphicsProperties},
+    rt_pipeline::ShaderBindingTableData,
+    surface::SurfaceCapabilities,
     Backend,
 };
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
+/// Labels of every query region in a submission so jobs can report them.
+#[derive(Default)]
+pub struct EmptyJob {
+    timestamps: Vec<String>,
+    statistics: Vec<String>,
+}
+
+impl EmptyJob {
+    fn new(commands: &[api::command_buffer::Command<'_, EmptyBackend>]) -> Self {
+        let mut job = EmptyJob::default();
+        for command in commands {
+            match command {
+                api::command_buffer::Command::BeginTimestamp(label) => {
+                    job.timestamps.push(label.to_string())
+                }
+                api::command_buffer::Command::BeginStatistics(label) => {
+                    job.statistics.push(label.to_string())
+                }
+                _ => {}
+            }
+        }
+        job
+    }
+}
+
 #[allow(dead_code)]
 pub struct EmptyBackend(GraphicsProperties);
 
+/// Empty surfaces have no size and use the default SDR format.
+const EMPTY_SURFACE: api::surface::SurfaceProperties = api::surface::SurfaceProperties {
+    dimensions: (0, 0),
+    format: api::types::Format::Bgra8Unorm,
+    color_space: api::types::ColorSpace::SrgbNonlinear,
+    present_mode: api::types::PresentMode::Fifo,
+};
+
 impl Backend for EmptyBackend {
     type Buffer = ();
     type Texture = ();
@@ -19,21 +54,80 @@ impl Backend for EmptyBackend {
     type RayTracingPipeline = ();
     type DescriptorSetLayout = ();
     type DescriptorSet = ();
-    type Job = ();
+    type Job = EmptyJob;
+    type CommandCache = ();
     type BottomLevelAccelerationStructure = ();
     type TopLevelAccelerationStructure = ();
+    type DrawIndirect = ();
     type DrawIndexedIndirect = ();
     type DispatchIndirect = ();
 
+    unsafe fn enumerate_adapters() -> Vec<AdapterInfo> {
+        Vec::default()
+    }
+
     unsafe fn properties(&self) -> &GraphicsProperties {
         &self.0
     }
 
+    unsafe fn save_pipeline_cache(&self) -> Result<(), api::context::PipelineCacheSaveError> {
+        Ok(())
+    }
+
+    unsafe fn precompile_graphics_pipelines(
+        &self,
+        _pipelines: &[&Self::GraphicsPipeline],
+        _render_pass: &api::render_pass::RenderPassDescriptor<Self>,
+    ) {
+    }
+
+    unsafe fn pending_pipeline_compiles(&self) -> usize {
+        0
+    }
+
+    unsafe fn memory_stats(&self) -> api::context::MemoryStats {
+        api::context::MemoryStats::default()
+    }
+
+    unsafe fn memory_allocations(&self) -> Vec<api::context::MemoryAllocationInfo> {
+        Vec::default()
+    }
+
+    unsafe fn memory_budget(&self) -> api::context::MemoryBudget {
+        api::context::MemoryBudget::default()
+    }
+
+    unsafe fn on_memory_pressure(
+        &self,
+        _threshold: f32,
+        _callback: api::context::MemoryPressureCallback,
+    ) {
+    }
+
+    unsafe fn collect_garbage(&self) {}
+
+    unsafe fn pending_garbage(&self) -> api::context::GarbageReport {
+        api::context::GarbageReport::default()
+    }
+
+    unsafe fn default_anisotropy(&self) -> Option<api::types::AnisotropyLevel> {
+        None
+    }
+
+    unsafe fn set_default_anisotropy(&self, _level: Option<api::types::AnisotropyLevel>) {}
+
+    unsafe fn trigger_capture(&self) {}
+
+    unsafe fn begin_capture(&self) {}
+
+    unsafe fn end_capture(&self) {}
+
     unsafe fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
         &self,
         _create_info: api::surface::SurfaceCreateInfo<W>,
-    ) -> Result<Self::Surface, api::surface::SurfaceCreateError> {
-        Ok(())
+    ) -> Result<(Self::Surface, api::surface::SurfaceProperties), api::surface::SurfaceCreateError>
+    {
+        Ok(((), EMPTY_SURFACE))
     }
 
     unsafe fn destroy_surface(&self, _id: &mut Self::Surface) {}
@@ -50,8 +144,8 @@ impl Backend for EmptyBackend {
         &self,
         _id: &mut Self::Surface,
         _config: api::surface::SurfaceConfiguration,
-    ) -> Result<(u32, u32), api::surface::SurfaceUpdateError> {
-        Ok((0, 0))
+    ) -> Result<api::surface::SurfaceProperties, api::surface::SurfaceUpdateError> {
+        Ok(EMPTY_SURFACE)
     }
 
     unsafe fn acquire_image(
@@ -67,19 +161,39 @@ impl Backend for EmptyBackend {
         &self,
         _queue: api::types::QueueType,
         _debug_name: Option<&str>,
-        _commands: Vec<api::command_buffer::Command<'_, Self>>,
+        commands: Vec<api::command_buffer::Command<'_, Self>>,
         _is_async: bool,
+        _waits: &[&Self::Job],
     ) -> Self::Job {
+        EmptyJob::new(&commands)
     }
 
     unsafe fn submit_commands_async_compute(
         &self,
         _queue: api::types::QueueType,
         _debug_name: Option<&str>,
-        _commands: Vec<api::command_buffer::Command<'_, Self>>,
-        _compute_commands: Vec<api::command_buffer::Command<'_, Self>>,
+        commands: Vec<api::command_buffer::Command<'_, Self>>,
+        compute_commands: Vec<api::command_buffer::Command<'_, Self>>,
     ) -> (Self::Job, Self::Job) {
-        ((), ())
+        (EmptyJob::new(&commands), EmptyJob::new(&compute_commands))
+    }
+
+    unsafe fn record_commands(
+        &self,
+        _queue: api::types::QueueType,
+        _commands: &mut Vec<api::command_buffer::Command<'_, Self>>,
+    ) -> Self::CommandCache {
+    }
+
+    unsafe fn submit_recorded_commands(
+        &self,
+        _queue: api::types::QueueType,
+        _debug_name: Option<&str>,
+        commands: &[api::command_buffer::Command<'_, Self>],
+        _cache: &Self::CommandCache,
+        _waits: &[&Self::Job],
+    ) -> Self::Job {
+        EmptyJob::new(commands)
     }
 
     unsafe fn present_image(
@@ -102,6 +216,27 @@ impl Backend for EmptyBackend {
         api::types::JobStatus::Complete
     }
 
+    unsafe fn device_lost(&self) -> Option<api::queue::SubmitError> {
+        None
+    }
+
+    unsafe fn job_timestamps(&self, job: &Self::Job) -> Vec<(String, std::time::Duration)> {
+        job.timestamps
+            .iter()
+            .map(|label| (label.clone(), std::time::Duration::ZERO))
+            .collect()
+    }
+
+    unsafe fn job_statistics(
+        &self,
+        job: &Self::Job,
+    ) -> Vec<(String, api::types::PipelineStatistics)> {
+        job.statistics
+            .iter()
+            .map(|label| (label.clone(), api::types::PipelineStatistics::default()))
+            .collect()
+    }
+
     unsafe fn create_buffer(
         &self,
         _create_info: api::buffer::BufferCreateInfo,
@@ -109,10 +244,21 @@ impl Backend for EmptyBackend {
         Ok(())
     }
 
+    unsafe fn create_transient_buffer(
+        &self,
+        _size: u64,
+        _buffer_usage: api::types::BufferUsage,
+    ) -> Result<Self::Buffer, api::buffer::BufferCreateError> {
+        Ok(())
+    }
+
     unsafe fn create_texture(
         &self,
-        _create_info: api::texture::TextureCreateInfo,
+        create_info: api::texture::TextureCreateInfo,
     ) -> Result<Self::Texture, api::texture::TextureCreateError> {
+        if create_info.sparse {
+            return Err(api::texture::TextureCreateError::SparseNotSupported);
+        }
         Ok(())
     }
 
@@ -236,6 +382,13 @@ impl Backend for EmptyBackend {
         todo!()
     }
 
+    unsafe fn blas_poll_compacted_sizes(
+        &self,
+        _ids: &[&Self::BottomLevelAccelerationStructure],
+    ) -> Vec<Option<u64>> {
+        todo!()
+    }
+
     unsafe fn buffer_device_ref(&self, _id: &Self::Buffer, _array_element: usize) -> u64 {
         todo!()
     }
@@ -268,6 +421,10 @@ impl Backend for EmptyBackend {
         todo!()
     }
 
+    unsafe fn tlas_update_scratch_size(&self, _id: &Self::TopLevelAccelerationStructure) -> u64 {
+        todo!()
+    }
+
     unsafe fn tlas_build_flags(
         &self,
         _id: &Self::TopLevelAccelerationStructure,
@@ -293,3 +450,80 @@ impl Backend for EmptyBackend {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use api::context::Context;
+
+    use super::*;
+
+    fn context() -> Context<EmptyBackend> {
+        Context::new(EmptyBackend(GraphicsProperties::default()))
+    }
+
+    fn submit(ctx: &Context<EmptyBackend>) -> api::queue::Job<EmptyBackend> {
+        let queue = ctx.main();
+        queue.submit(Some("frame"), queue.command_buffer())
+    }
+
+    #[test]
+    fn frame_slots_are_reused_in_order() {
+        let ctx = context();
+        ctx.set_frames_in_flight(3);
+
+        for index in 0..10 {
+            let token = ctx.begin_frame();
+            assert_eq!(token.index(), index);
+            assert_eq!(token.slot(), (index % 3) as usize);
+            ctx.end_frame(token, [submit(&ctx)]);
+        }
+    }
+
+    #[test]
+    fn frames_in_flight_is_a_bound() {
+        let ctx = context();
+        assert_eq!(
+            ctx.frames_in_flight(),
+            api::frame_pacer::DEFAULT_FRAMES_IN_FLIGHT
+        );
+
+        // Frames only wait once every slot is taken
+        for _ in 0..2 {
+            let token = ctx.begin_frame();
+            ctx.end_frame(token, [submit(&ctx)]);
+        }
+        assert_eq!(ctx.pending_frames(), 2);
+
+        // After that, beginning a frame waits on the oldest one
+        for _ in 0..4 {
+            let token = ctx.begin_frame();
+            assert_eq!(ctx.pending_frames(), 1);
+            ctx.end_frame(token, [submit(&ctx), submit(&ctx)]);
+            assert_eq!(ctx.pending_frames(), 2);
+        }
+
+        ctx.wait_frames_idle();
+        assert_eq!(ctx.pending_frames(), 0);
+    }
+
+    #[test]
+    fn changing_frames_in_flight_waits_for_every_frame() {
+        let ctx = context();
+        let token = ctx.begin_frame();
+        ctx.end_frame(token, [submit(&ctx)]);
+
+        ctx.set_frames_in_flight(1);
+        assert_eq!(ctx.pending_frames(), 0);
+        assert_eq!(ctx.frames_in_flight(), 1);
+
+        let token = ctx.begin_frame();
+        assert_eq!(token.slot(), 0);
+        ctx.end_frame(token, []);
+    }
+
+    #[test]
+    #[should_panic]
+    fn frames_in_flight_cant_be_zero() {
+        context().set_frames_in_flight(0);
+    }
+}
@@ -6,6 +6,7 @@ use api::{
         BottomLevelAccelerationStructureData,
     },
     buffer::Buffer,
+    context::MemoryCategory,
     types::{BuildAccelerationStructureFlags, MemoryUsage, SharingMode},
     Backend,
 };
@@ -165,6 +166,74 @@ impl BottomLevelAccelerationStructure {
 
                 sizes
             }
+            BottomLevelAccelerationStructureData::Aabbs(data) => {
+                geometries = ArcSwap::new(Arc::new(
+                    data.iter()
+                        .map(|aabbs| {
+                            let mut data = vk::AccelerationStructureGeometryDataKHR::default();
+                            data.aabbs = vk::AccelerationStructureGeometryAabbsDataKHR::default()
+                                .data({
+                                    let mut addr = aabbs.data.internal().device_address_const(
+                                        &ctx.device,
+                                        aabbs.data_array_element,
+                                    );
+                                    addr.device_address += aabbs.data_offset;
+                                    addr
+                                })
+                                .stride(aabbs.stride);
+
+                            vk::AccelerationStructureGeometryKHR::default()
+                                .flags(crate::util::to_vk_geometry_flags(aabbs.flags))
+                                .geometry_type(vk::GeometryTypeKHR::AABBS)
+                                .geometry(data)
+                        })
+                        .collect::<Vec<_>>(),
+                ));
+
+                build_ranges = ArcSwap::new(Arc::new(
+                    data.iter()
+                        .map(|aabbs| {
+                            vk::AccelerationStructureBuildRangeInfoKHR::default()
+                                .primitive_count(aabbs.count as u32)
+                                .primitive_offset(0)
+                                .first_vertex(0)
+                                .transform_offset(0)
+                        })
+                        .collect::<Vec<_>>(),
+                ));
+
+                let num_aabbs: Vec<_> = data.iter().map(|aabbs| aabbs.count as u32).collect();
+
+                // Buffer references for resource tracking.
+                let mut refs = FxHashMap::default();
+                data.iter().for_each(|aabbs| {
+                    refs.entry((aabbs.data.internal().buffer, aabbs.data_array_element))
+                        .or_insert_with(|| BlasBufferRef {
+                            _ref_counter: aabbs.data.internal().ref_counter.clone(),
+                            id: aabbs.data.internal().id,
+                            sharing_mode: aabbs.data.internal().sharing_mode,
+                            aligned_size: aabbs.data.internal().aligned_size,
+                        });
+                });
+                buffer_refs = ArcSwap::new(Arc::new(refs));
+
+                // Figure out how big the BLAS needs to be
+                let geos = geometries.load();
+                let build_geo_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
+                    .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
+                    .flags(crate::util::to_vk_as_build_flags(build_info.flags))
+                    .geometries(&geos);
+
+                let mut sizes = vk::AccelerationStructureBuildSizesInfoKHR::default();
+                ctx.as_loader.get_acceleration_structure_build_sizes(
+                    vk::AccelerationStructureBuildTypeKHR::DEVICE,
+                    &build_geo_info,
+                    &num_aabbs,
+                    &mut sizes,
+                );
+
+                sizes
+            }
             // Size comes directly from the user
             BottomLevelAccelerationStructureData::CompactDst(size) => {
                 // Empty ranges until we copy
@@ -216,7 +285,7 @@ impl BottomLevelAccelerationStructure {
             location: crate::util::to_gpu_allocator_memory_location(MemoryUsage::GpuOnly),
             linear: true,
         };
-        let block = match allocator.allocate(&request) {
+        let block = match allocator.allocate(&request, MemoryCategory::AccelerationStructure) {
             Ok(block) => block,
             Err(err) => {
                 println!(
@@ -224,9 +293,7 @@ impl BottomLevelAccelerationStructure {
                     buffer_create_info.size, mem_reqs.alignment, mem_reqs.size
                 );
                 ctx.device.destroy_buffer(buffer, None);
-                return Err(BottomLevelAccelerationStructureCreateError::Other(
-                    err.to_string(),
-                ));
+                return Err(err.into());
             }
         };
 
@@ -243,7 +310,7 @@ impl BottomLevelAccelerationStructure {
         }
 
         // Setup debug name is requested
-        if let Some(name) = build_info.debug_name {
+        if let Some(name) = &build_info.debug_name {
             if let Some(debug) = &ctx.debug {
                 let name = CString::new(format!("{name}_buffer")).unwrap();
                 let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
@@ -273,6 +340,13 @@ impl BottomLevelAccelerationStructure {
                 ))
             }
         };
+        if let Some(name) = &build_info.debug_name {
+            crate::util::set_debug_name(
+                ctx.debug.as_ref().map(|debug| &debug.device),
+                acceleration_struct,
+                name,
+            );
+        }
 
         Ok(BottomLevelAccelerationStructure {
             geometries,
@@ -331,6 +405,8 @@ impl BottomLevelAccelerationStructure {
                 &Job {
                     ty: old.queue,
                     target_value: old.timeline_value,
+                    queries: None,
+                    debug_name: None,
                 },
                 None,
             );
@@ -340,9 +416,20 @@ impl BottomLevelAccelerationStructure {
             .queries
             .lock()
             .unwrap()
-            .get_accel_struct_compact(&ctx.device, query);
+            .poll_accel_struct_compact(&ctx.device, query);
 
-        s
+        s.unwrap_or(0)
+    }
+
+    /// Gets the compacted size without waiting. Returns `None` if the size isn't available yet
+    /// or if the BLAS doesn't support compaction.
+    #[inline(always)]
+    pub(crate) unsafe fn poll_compacted_size(
+        &self,
+        device: &ash::Device,
+        queries: &mut Queries,
+    ) -> Option<u64> {
+        queries.poll_accel_struct_compact(device, self.compact_size_query?)
     }
 
     pub(crate) unsafe fn build(
@@ -353,23 +440,57 @@ impl BottomLevelAccelerationStructure {
         scratch: &Buffer<crate::VulkanBackend>,
         scratch_array_element: usize,
     ) {
-        let ranges = self.build_ranges.load();
-        let geometries = self.geometries.load();
-
-        let build_geo_info = [vk::AccelerationStructureBuildGeometryInfoKHR::default()
-            .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
-            .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
-            .dst_acceleration_structure(self.acceleration_struct)
-            .flags(crate::util::to_vk_as_build_flags(self.flags))
-            .geometries(&geometries)
-            .scratch_data(
-                scratch
-                    .internal()
-                    .device_address(device, scratch_array_element),
-            )];
-        let infos = [ranges.as_slice()];
-
-        as_loader.cmd_build_acceleration_structures(commands, &build_geo_info, &infos);
+        Self::build_batch(
+            device,
+            commands,
+            as_loader,
+            &[(self, 0)],
+            scratch,
+            scratch_array_element,
+        );
+    }
+
+    /// Builds every BLAS with a single command. Each BLAS is paired with the offset of its
+    /// scratch memory within `scratch`.
+    pub(crate) unsafe fn build_batch(
+        device: &ash::Device,
+        commands: vk::CommandBuffer,
+        as_loader: &ash::khr::acceleration_structure::Device,
+        blases: &[(&Self, u64)],
+        scratch: &Buffer<crate::VulkanBackend>,
+        scratch_array_element: usize,
+    ) {
+        let ranges: Vec<_> = blases
+            .iter()
+            .map(|(blas, _)| blas.build_ranges.load())
+            .collect();
+        let geometries: Vec<_> = blases
+            .iter()
+            .map(|(blas, _)| blas.geometries.load())
+            .collect();
+        let scratch_base = scratch
+            .internal()
+            .device_address(device, scratch_array_element)
+            .device_address;
+
+        let build_geo_infos: Vec<_> = blases
+            .iter()
+            .zip(geometries.iter())
+            .map(|((blas, scratch_offset), geometries)| {
+                vk::AccelerationStructureBuildGeometryInfoKHR::default()
+                    .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
+                    .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
+                    .dst_acceleration_structure(blas.acceleration_struct)
+                    .flags(crate::util::to_vk_as_build_flags(blas.flags))
+                    .geometries(geometries)
+                    .scratch_data(vk::DeviceOrHostAddressKHR {
+                        device_address: scratch_base + scratch_offset,
+                    })
+            })
+            .collect();
+        let infos: Vec<_> = ranges.iter().map(|ranges| ranges.as_slice()).collect();
+
+        as_loader.cmd_build_acceleration_structures(commands, &build_geo_infos, &infos);
     }
 
     pub(crate) unsafe fn write_compact_size(
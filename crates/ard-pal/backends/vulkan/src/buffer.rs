@@ -1,19 +1,28 @@
-use std::{ffi::CString, mem::ManuallyDrop, ptr::NonNull, sync::Arc};
+use std::{
+    ffi::CString,
+    mem::ManuallyDrop,
+    ptr::NonNull,
+    sync::{Arc, Mutex},
+};
 
 use api::{
     buffer::{BufferCreateError, BufferCreateInfo, BufferViewError},
-    types::{BufferUsage, MemoryUsage, SharingMode},
+    context::MemoryCategory,
+    types::{BufferUsage, Format, MemoryUsage, SharingMode},
     Backend,
 };
 use ash::vk;
 use crossbeam_channel::Sender;
-use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme, Allocator};
+use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme};
+use rustc_hash::FxHashMap;
 
 use crate::{
     job::Job,
     util::{
         garbage_collector::Garbage,
         id_gen::{IdGenerator, ResourceId},
+        memory::TrackedAllocator,
+        transient::TransientBuffers,
         usage::BufferRegion,
     },
     PhysicalDeviceProperties, QueueFamilyIndices, VulkanBackend,
@@ -22,7 +31,9 @@ use crate::{
 pub struct Buffer {
     pub(crate) buffer: vk::Buffer,
     pub(crate) id: ResourceId,
-    pub(crate) block: ManuallyDrop<Allocation>,
+    pub(crate) memory: BufferMemory,
+    /// Offset of the first array element within `buffer`.
+    pub(crate) base_offset: u64,
     pub(crate) sharing_mode: SharingMode,
     pub(crate) _buffer_usage: BufferUsage,
     pub(crate) _memory_usage: MemoryUsage,
@@ -31,10 +42,30 @@ pub struct Buffer {
     pub(crate) size: u64,
     /// This is the per element size after alignment.
     pub(crate) aligned_size: u64,
+    /// Texel views created for binding the buffer in descriptor sets. They live as long as the
+    /// buffer so sets binding the same range share a view.
+    pub(crate) texel_views: Mutex<FxHashMap<TexelViewKey, vk::BufferView>>,
     pub(crate) ref_counter: BufferRefCounter,
+    /// Used to name views created after the buffer.
+    pub(crate) debug_name: Option<String>,
     on_drop: Sender<Garbage>,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TexelViewKey {
+    pub array_element: usize,
+    pub offset: u64,
+    pub range: u64,
+    pub format: Format,
+}
+
+pub(crate) enum BufferMemory {
+    /// The buffer owns its allocation.
+    Dedicated(ManuallyDrop<Allocation>),
+    /// The buffer is suballocated from a transient block.
+    Transient { block: usize },
+}
+
 #[derive(Clone)]
 pub(crate) struct BufferRefCounter(Arc<()>);
 
@@ -45,40 +76,13 @@ impl Buffer {
         debug: Option<&ash::ext::debug_utils::Device>,
         on_drop: Sender<Garbage>,
         id_gen: &IdGenerator,
-        allocator: &mut Allocator,
+        allocator: &mut TrackedAllocator,
         props: &PhysicalDeviceProperties,
         create_info: BufferCreateInfo,
     ) -> Result<Self, BufferCreateError> {
         // Determine memory alignment requirements
-        let mut alignment_req = 0;
-        if create_info.memory_usage == MemoryUsage::CpuToGpu {
-            alignment_req = alignment_req.max(props.limits.non_coherent_atom_size);
-        }
-        if create_info
-            .buffer_usage
-            .contains(BufferUsage::UNIFORM_BUFFER)
-        {
-            alignment_req = alignment_req.max(props.limits.min_uniform_buffer_offset_alignment);
-        }
-        if create_info
-            .buffer_usage
-            .contains(BufferUsage::STORAGE_BUFFER)
-        {
-            alignment_req = alignment_req.max(props.limits.min_storage_buffer_offset_alignment);
-        }
-        if create_info
-            .buffer_usage
-            .contains(BufferUsage::ACCELERATION_STRUCTURE_SCRATCH)
-        {
-            alignment_req =
-                alignment_req.max(props.min_acceleration_structure_scratch_offset_alignment as u64);
-        }
-        if create_info
-            .buffer_usage
-            .contains(BufferUsage::SHADER_BINDING_TABLE)
-        {
-            alignment_req = alignment_req.max(props.shader_group_base_alignment as u64);
-        }
+        let alignment_req =
+            alignment_requirement(props, create_info.memory_usage, create_info.buffer_usage);
 
         // Round size to a multiple of the alignment
         let aligned_size = create_info.size.next_multiple_of(alignment_req);
@@ -112,11 +116,15 @@ impl Buffer {
             location: crate::util::to_gpu_allocator_memory_location(create_info.memory_usage),
             linear: true,
         };
-        let block = match allocator.allocate(&request) {
+        let block = match allocator.allocate_with_fallback(
+            &request,
+            MemoryCategory::Buffer,
+            create_info.memory_fallback,
+        ) {
             Ok(block) => block,
             Err(err) => {
                 device.destroy_buffer(buffer, None);
-                return Err(BufferCreateError::Other(err.to_string()));
+                return Err(err.into());
             }
         };
 
@@ -128,9 +136,9 @@ impl Buffer {
         }
 
         // Setup debug name is requested
-        if let Some(name) = create_info.debug_name {
+        if let Some(name) = &create_info.debug_name {
             if let Some(debug) = debug {
-                let name = CString::new(name).unwrap();
+                let name = CString::new(name.as_str()).unwrap();
                 let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
                     .object_handle(buffer)
                     .object_name(&name);
@@ -142,21 +150,95 @@ impl Buffer {
         Ok(Buffer {
             buffer,
             id: id_gen.create(),
-            block: ManuallyDrop::new(block),
+            memory: BufferMemory::Dedicated(ManuallyDrop::new(block)),
+            base_offset: 0,
             size: create_info.size,
             aligned_size,
             sharing_mode: create_info.sharing_mode,
             _array_elements: create_info.array_elements,
             _buffer_usage: create_info.buffer_usage,
             _memory_usage: create_info.memory_usage,
+            texel_views: Mutex::default(),
+            on_drop,
+            ref_counter: BufferRefCounter::default(),
+            debug_name: create_info.debug_name,
+        })
+    }
+
+    /// Creates a buffer suballocated from the transient blocks. Transient buffers are
+    /// `CpuToGpu`, have a single array element, and are shared by every queue.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) unsafe fn new_transient(
+        device: &ash::Device,
+        qfi: &QueueFamilyIndices,
+        on_drop: Sender<Garbage>,
+        id_gen: &IdGenerator,
+        allocator: &mut TrackedAllocator,
+        transient: &mut TransientBuffers,
+        props: &PhysicalDeviceProperties,
+        size: u64,
+        buffer_usage: BufferUsage,
+    ) -> Result<Self, BufferCreateError> {
+        // Index buffers bound at an offset must be aligned to the index size
+        let alignment_req =
+            alignment_requirement(props, MemoryUsage::CpuToGpu, buffer_usage).max(4);
+        let aligned_size = size.next_multiple_of(alignment_req);
+        let suballocation =
+            transient.allocate(device, qfi, allocator, aligned_size, alignment_req)?;
+
+        Ok(Buffer {
+            buffer: suballocation.buffer,
+            id: id_gen.create(),
+            memory: BufferMemory::Transient {
+                block: suballocation.block,
+            },
+            base_offset: suballocation.offset,
+            size,
+            aligned_size,
+            sharing_mode: SharingMode::Concurrent,
+            _array_elements: 1,
+            _buffer_usage: buffer_usage,
+            _memory_usage: MemoryUsage::CpuToGpu,
+            texel_views: Mutex::default(),
             on_drop,
             ref_counter: BufferRefCounter::default(),
+            debug_name: None,
         })
     }
 
     #[inline(always)]
     pub(crate) fn offset(&self, array_element: usize) -> u64 {
-        self.aligned_size * array_element as u64
+        self.base_offset + self.aligned_size * array_element as u64
+    }
+
+    /// Finds or creates the texel view described by `key`.
+    pub(crate) unsafe fn get_texel_view(
+        &self,
+        device: &ash::Device,
+        debug: Option<&ash::ext::debug_utils::Device>,
+        key: TexelViewKey,
+    ) -> vk::BufferView {
+        *self
+            .texel_views
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| {
+                let create_info = vk::BufferViewCreateInfo::default()
+                    .buffer(self.buffer)
+                    .format(crate::util::to_vk_format(key.format))
+                    .offset(self.offset(key.array_element) + key.offset)
+                    .range(key.range);
+                let view = device.create_buffer_view(&create_info, None).unwrap();
+                if let Some(name) = &self.debug_name {
+                    crate::util::set_debug_name(
+                        debug,
+                        view,
+                        &format!("{name}_texel_view_{}_{}", key.array_element, key.offset),
+                    );
+                }
+                view
+            })
     }
 
     pub(crate) unsafe fn map(
@@ -178,14 +260,18 @@ impl Buffer {
                 &Job {
                     ty: old.queue,
                     target_value: old.timeline_value,
+                    queries: None,
+                    debug_name: None,
                 },
                 None,
             );
         }
 
-        let map = self.block.mapped_ptr().unwrap();
-        let map =
-            NonNull::new_unchecked((map.as_ptr() as *mut u8).add(self.aligned_size as usize * idx));
+        let map = match &self.memory {
+            BufferMemory::Dedicated(block) => block.mapped_ptr().unwrap().cast::<u8>(),
+            BufferMemory::Transient { block } => ctx.transient.lock().unwrap().mapped_ptr(*block),
+        };
+        let map = NonNull::new_unchecked(map.as_ptr().add(self.offset(idx) as usize));
         Ok((map, self.size))
     }
 
@@ -218,15 +304,58 @@ impl Buffer {
 
 impl Drop for Buffer {
     fn drop(&mut self) {
-        let _ = self.on_drop.send(Garbage::Buffer {
-            buffer: self.buffer,
-            id: self.id,
-            allocation: unsafe { ManuallyDrop::take(&mut self.block) },
-            ref_counter: self.ref_counter.clone(),
+        let views = self
+            .texel_views
+            .get_mut()
+            .unwrap()
+            .drain()
+            .map(|(_, view)| view)
+            .collect();
+
+        let _ = self.on_drop.send(match &mut self.memory {
+            BufferMemory::Dedicated(block) => Garbage::Buffer {
+                buffer: self.buffer,
+                id: self.id,
+                views,
+                allocation: unsafe { ManuallyDrop::take(block) },
+                ref_counter: self.ref_counter.clone(),
+            },
+            BufferMemory::Transient { block } => Garbage::TransientBuffer {
+                block: *block,
+                id: self.id,
+                views,
+                ref_counter: self.ref_counter.clone(),
+            },
         });
     }
 }
 
+/// Determines the alignment of each array element of a buffer.
+fn alignment_requirement(
+    props: &PhysicalDeviceProperties,
+    memory_usage: MemoryUsage,
+    buffer_usage: BufferUsage,
+) -> u64 {
+    let mut alignment_req = 0;
+    if memory_usage == MemoryUsage::CpuToGpu {
+        alignment_req = alignment_req.max(props.limits.non_coherent_atom_size);
+    }
+    if buffer_usage.contains(BufferUsage::UNIFORM_BUFFER) {
+        alignment_req = alignment_req.max(props.limits.min_uniform_buffer_offset_alignment);
+    }
+    if buffer_usage.contains(BufferUsage::STORAGE_BUFFER) {
+        alignment_req = alignment_req.max(props.limits.min_storage_buffer_offset_alignment);
+    }
+    if buffer_usage.contains(BufferUsage::ACCELERATION_STRUCTURE_SCRATCH) {
+        alignment_req =
+            alignment_req.max(props.min_acceleration_structure_scratch_offset_alignment as u64);
+    }
+    if buffer_usage.contains(BufferUsage::SHADER_BINDING_TABLE) {
+        alignment_req = alignment_req.max(props.shader_group_base_alignment as u64);
+    }
+    alignment_req
+}
+
 impl BufferRefCounter {
     #[inline]
     pub fn is_last(&self) -> bool {
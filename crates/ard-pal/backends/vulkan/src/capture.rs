@@ -0,0 +1,147 @@
+use std::sync::Once;
+
+#[cfg(feature = "renderdoc")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Frame captures through the RenderDoc in-application API.
+///
+/// Every call is a no-op if RenderDoc isn't attached. The first time a capture is requested
+/// without it, a warning is logged explaining why.
+pub struct FrameCapture {
+    #[cfg(feature = "renderdoc")]
+    api: Option<renderdoc::RenderDoc>,
+    #[cfg(feature = "renderdoc")]
+    capturing: AtomicBool,
+    warned: Once,
+}
+
+impl FrameCapture {
+    /// Looks for RenderDoc. Must be called before the Vulkan instance is created so RenderDoc can
+    /// hook it.
+    pub fn load() -> Self {
+        Self {
+            #[cfg(feature = "renderdoc")]
+            api: renderdoc::RenderDoc::load().inspect(|_| {
+                ard_log::info!("RenderDoc attached. Frame captures are available.");
+            }),
+            #[cfg(feature = "renderdoc")]
+            capturing: AtomicBool::new(false),
+            warned: Once::new(),
+        }
+    }
+
+    pub fn trigger(&self) {
+        #[cfg(feature = "renderdoc")]
+        if let Some(api) = &self.api {
+            unsafe { api.trigger() };
+            return;
+        }
+
+        self.warn_unavailable();
+    }
+
+    pub fn begin(&self) {
+        #[cfg(feature = "renderdoc")]
+        if let Some(api) = &self.api {
+            if !self.capturing.swap(true, Ordering::AcqRel) {
+                unsafe { api.begin() };
+            }
+            return;
+        }
+
+        self.warn_unavailable();
+    }
+
+    pub fn end(&self) {
+        #[cfg(feature = "renderdoc")]
+        if let Some(api) = &self.api {
+            if self.capturing.swap(false, Ordering::AcqRel) {
+                unsafe { api.end() };
+            }
+        }
+    }
+
+    fn warn_unavailable(&self) {
+        self.warned.call_once(|| {
+            if cfg!(feature = "renderdoc") {
+                ard_log::warn!(
+                    "Frame capture requested, but RenderDoc isn't attached. \
+                    Launch the application through RenderDoc to capture frames."
+                );
+            } else {
+                ard_log::warn!(
+                    "Frame capture requested, but Pal was built without the `renderdoc` feature."
+                );
+            }
+        });
+    }
+}
+
+#[cfg(feature = "renderdoc")]
+mod renderdoc {
+    use std::{
+        ffi::{c_int, c_void},
+        ptr,
+    };
+
+    use renderdoc_sys::{eRENDERDOC_API_Version_1_4_1, RENDERDOC_Version, RENDERDOC_API_1_4_1};
+
+    #[cfg(windows)]
+    const LIBRARY_NAME: &str = "renderdoc.dll";
+    #[cfg(not(windows))]
+    const LIBRARY_NAME: &str = "librenderdoc.so";
+
+    type GetApi = unsafe extern "C" fn(RENDERDOC_Version, *mut *mut c_void) -> c_int;
+
+    pub struct RenderDoc {
+        api: *const RENDERDOC_API_1_4_1,
+        // Keeps the library loaded for as long as the function table is used
+        _library: libloading::Library,
+    }
+
+    // The in-application API is thread safe
+    unsafe impl Send for RenderDoc {}
+    unsafe impl Sync for RenderDoc {}
+
+    impl RenderDoc {
+        /// If the application was launched through RenderDoc the library is already loaded, so
+        /// this returns the existing handle instead of injecting a new copy.
+        pub fn load() -> Option<Self> {
+            let library = unsafe { libloading::Library::new(LIBRARY_NAME).ok()? };
+
+            let mut api = ptr::null_mut();
+            let supported = unsafe {
+                let get_api = library.get::<GetApi>(b"RENDERDOC_GetAPI\0").ok()?;
+                get_api(eRENDERDOC_API_Version_1_4_1, &mut api) == 1
+            };
+
+            if !supported || api.is_null() {
+                return None;
+            }
+
+            Some(Self {
+                api: api as *const RENDERDOC_API_1_4_1,
+                _library: library,
+            })
+        }
+
+        pub unsafe fn trigger(&self) {
+            if let Some(trigger) = (*self.api).TriggerCapture {
+                trigger();
+            }
+        }
+
+        /// Null device and window handles capture whichever device and window are active.
+        pub unsafe fn begin(&self) {
+            if let Some(start) = (*self.api).StartFrameCapture {
+                start(ptr::null_mut(), ptr::null_mut());
+            }
+        }
+
+        pub unsafe fn end(&self) {
+            if let Some(end) = (*self.api).EndFrameCapture {
+                end(ptr::null_mut(), ptr::null_mut());
+            }
+        }
+    }
+}
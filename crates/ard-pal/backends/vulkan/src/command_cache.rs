@@ -0,0 +1,40 @@
+use std::sync::Mutex;
+
+use api::command_buffer::Command;
+
+use crate::util::{command_sort::CommandSorting, usage::UsageJournal};
+
+/// Work kept between submissions of recorded commands.
+pub struct CommandCache {
+    /// `None` if the commands can't reuse their sorting.
+    pub(crate) sorted: Option<Mutex<SortedCommands>>,
+}
+
+#[derive(Default)]
+pub(crate) struct SortedCommands {
+    pub sorting: CommandSorting,
+    /// How the last sort changed the usage of resources. `None` if the commands have never been
+    /// sorted or the sort can't be replayed.
+    pub usage: Option<UsageJournal>,
+}
+
+impl CommandCache {
+    pub(crate) fn new(commands: &[Command<'_, crate::VulkanBackend>]) -> Self {
+        // Pushed descriptors are written to new sets every submission and sparse binds change
+        // memory every submission, so their usage can't be replayed
+        let reusable = commands.iter().all(|command| match command {
+            Command::RenderPassSlices(slices) => slices
+                .iter()
+                .flatten()
+                .all(|command| !matches!(command, Command::PushDescriptorSet { .. })),
+            Command::PushDescriptorSet { .. }
+            | Command::BindSparsePages { .. }
+            | Command::UnbindSparsePages { .. } => false,
+            _ => true,
+        });
+
+        Self {
+            sorted: reusable.then(|| Mutex::new(SortedCommands::default())),
+        }
+    }
+}
@@ -1,11 +1,11 @@
-use std::ffi::CString;
+use std::{ffi::CString, sync::Mutex};
 
 use api::compute_pipeline::{ComputePipelineCreateError, ComputePipelineCreateInfo};
 use ash::vk;
 use bytemuck::{Pod, Zeroable};
 use crossbeam_channel::Sender;
 
-use crate::util::garbage_collector::Garbage;
+use crate::util::{garbage_collector::Garbage, pipeline_cache::PipelineCache};
 
 pub struct ComputePipeline {
     pub(crate) layout: vk::PipelineLayout,
@@ -29,6 +29,7 @@ impl ComputePipeline {
         device: &ash::Device,
         debug: Option<&ash::ext::debug_utils::Device>,
         garbage: Sender<Garbage>,
+        pipelines: &Mutex<PipelineCache>,
         create_info: ComputePipelineCreateInfo<crate::VulkanBackend>,
     ) -> Result<Self, ComputePipelineCreateError> {
         let push_constant_ranges = create_info.push_constants_size.map(|size| {
@@ -53,6 +54,9 @@ impl ComputePipeline {
         let layout = device
             .create_pipeline_layout(&layout_create_info, None)
             .unwrap();
+        if let Some(name) = &create_info.debug_name {
+            crate::util::set_debug_name(debug, layout, &format!("{name}_layout"));
+        }
 
         // Create the pipeline
         let map_entries = [
@@ -86,18 +90,22 @@ impl ComputePipeline {
             .name(&entry_point)
             .specialization_info(&specialization);
 
+        let mut feedback = vk::PipelineCreationFeedback::default();
+        let mut feedback_info = vk::PipelineCreationFeedbackCreateInfo::default()
+            .pipeline_creation_feedback(&mut feedback);
+
         let pipeline_info = [vk::ComputePipelineCreateInfo::default()
+            .push_next(&mut feedback_info)
             .stage(stage)
             .layout(layout)];
 
-        let pipeline = match device.create_compute_pipelines(
-            vk::PipelineCache::null(),
-            &pipeline_info,
-            None,
-        ) {
+        // The driver cache is internally synchronized, so the lock isn't held during creation
+        let cache = pipelines.lock().unwrap().cache();
+        let pipeline = match device.create_compute_pipelines(cache, &pipeline_info, None) {
             Ok(pipeline) => pipeline[0],
             Err((_, err)) => return Err(ComputePipelineCreateError::Other(err.to_string())),
         };
+        pipelines.lock().unwrap().record(&feedback);
 
         // Name the pipeline if needed
         if let Some(name) = create_info.debug_name {
@@ -1,12 +1,14 @@
-use std::{ffi::CString, mem::ManuallyDrop};
+use std::{ffi::CString, mem::ManuallyDrop, ops::Range};
 
 use api::{
+    command_buffer::BufferCubeMapCopy,
+    context::MemoryCategory,
     cube_map::{CubeMapCreateError, CubeMapCreateInfo},
     types::{CubeFace, SharingMode},
 };
 use ash::vk;
 use crossbeam_channel::Sender;
-use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme, Allocator};
+use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme};
 
 use crate::{
     texture::TextureRefCounter,
@@ -14,6 +16,7 @@ use crate::{
         cube_face_to_idx,
         garbage_collector::Garbage,
         id_gen::{IdGenerator, ResourceId},
+        memory::TrackedAllocator,
     },
     QueueFamilyIndices,
 };
@@ -35,6 +38,8 @@ pub struct CubeMap {
     pub(crate) aspect_flags: vk::ImageAspectFlags,
     pub(crate) sharing_mode: SharingMode,
     pub(crate) size: u64,
+    /// Used to name views created after the cube map.
+    pub(crate) debug_name: Option<String>,
     on_drop: Sender<Garbage>,
 }
 
@@ -45,7 +50,7 @@ impl CubeMap {
         qfi: &QueueFamilyIndices,
         debug: Option<&ash::ext::debug_utils::Device>,
         on_drop: Sender<Garbage>,
-        allocator: &mut Allocator,
+        allocator: &mut TrackedAllocator,
         create_info: CubeMapCreateInfo,
     ) -> Result<Self, CubeMapCreateError> {
         // Create the image
@@ -93,11 +98,11 @@ impl CubeMap {
             linear: false,
         };
 
-        let block = match allocator.allocate(&request) {
+        let block = match allocator.allocate(&request, MemoryCategory::CubeMap) {
             Ok(block) => block,
             Err(err) => {
                 device.destroy_image(image, None);
-                return Err(CubeMapCreateError::Other(err.to_string()));
+                return Err(err.into());
             }
         };
 
@@ -264,7 +269,7 @@ impl CubeMap {
         }
 
         // Setup debug name is requested
-        if let Some(name) = create_info.debug_name {
+        if let Some(name) = &create_info.debug_name {
             if let Some(debug) = debug {
                 let cstr_name = CString::new(name.as_str()).unwrap();
                 let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
@@ -281,6 +286,15 @@ impl CubeMap {
 
                     debug.set_debug_utils_object_name(&name_info).unwrap();
                 }
+
+                for (i, view) in face_views.iter().enumerate() {
+                    let name = CString::new(format!("{}_face_view_{}", &name, i)).unwrap();
+                    let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
+                        .object_handle(*view)
+                        .object_name(&name);
+
+                    debug.set_debug_utils_object_name(&name_info).unwrap();
+                }
             }
         }
 
@@ -298,6 +312,7 @@ impl CubeMap {
             _array_elements: create_info.array_elements,
             size: mem_reqs.size / create_info.array_elements as u64,
             aspect_flags,
+            debug_name: create_info.debug_name,
         })
     }
 
@@ -306,6 +321,21 @@ impl CubeMap {
         (array_elem * 6) + cube_face_to_idx(face)
     }
 
+    /// Array layers of the image read or written by a buffer copy.
+    #[inline(always)]
+    pub(crate) fn copy_layers(copy: &BufferCubeMapCopy) -> Range<u32> {
+        match copy.face {
+            Some(face) => {
+                let layer = Self::to_array_elem(copy.cube_map_array_element, face) as u32;
+                layer..(layer + 1)
+            }
+            None => {
+                let base = copy.cube_map_array_element as u32 * 6;
+                base..(base + 6)
+            }
+        }
+    }
+
     #[inline(always)]
     pub(crate) fn get_face_view(
         &self,
@@ -334,7 +364,7 @@ impl Drop for CubeMap {
                 views.extend(face_views);
                 views
             },
-            allocation: unsafe { ManuallyDrop::take(&mut self.block) },
+            allocations: vec![unsafe { ManuallyDrop::take(&mut self.block) }],
             ref_counter: self.ref_counter.clone(),
         });
     }
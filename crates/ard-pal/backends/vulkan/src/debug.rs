@@ -0,0 +1,233 @@
+use std::{borrow::Cow, collections::VecDeque, ffi::CStr, sync::Mutex};
+
+use ash::vk;
+
+/// Message IDs that are always ignored.
+const IGNORED_MESSAGE_IDS: [i32; 2] = [
+    // `OutputNotConsumed` warnings
+    101294395,
+    // False positive from depth ms resolve
+    -2069901625,
+];
+
+/// Number of recent submissions reported alongside validation errors.
+const BREADCRUMB_COUNT: usize = 8;
+
+/// Output of `debugPrintfEXT` is reported with a message ID name containing this.
+const DEBUG_PRINTF_MESSAGE_ID: &str = "DEBUG-PRINTF";
+
+/// Controls how validation layer messages are reported. Only used if debugging is enabled.
+#[derive(Debug, Clone, Default)]
+pub struct VulkanDebugConfig {
+    /// Messages less severe than this are dropped.
+    pub min_severity: DebugSeverity,
+    /// Message IDs to ignore on top of the ones that are always ignored.
+    pub ignored_message_ids: Vec<i32>,
+    /// Panics when an error is reported. The panic can't unwind through the driver, so this
+    /// aborts the process. Useful to fail CI runs on validation errors.
+    pub panic_on_error: bool,
+}
+
+/// Severity of a validation layer message.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugSeverity {
+    Verbose,
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/// State shared with the debug messenger callback. Must outlive the messenger.
+pub(crate) struct DebugMessengerState {
+    config: VulkanDebugConfig,
+    /// Debug names of the most recent submissions, oldest first.
+    breadcrumbs: Mutex<VecDeque<String>>,
+}
+
+impl DebugSeverity {
+    fn from_vk(severity: vk::DebugUtilsMessageSeverityFlagsEXT) -> Self {
+        match severity {
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => DebugSeverity::Error,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => DebugSeverity::Warning,
+            vk::DebugUtilsMessageSeverityFlagsEXT::INFO => DebugSeverity::Info,
+            _ => DebugSeverity::Verbose,
+        }
+    }
+
+    /// The messenger severities needed to receive messages of this severity and above.
+    pub(crate) fn to_vk_messenger_flags(self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        let mut flags = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR;
+        if self <= DebugSeverity::Warning {
+            flags |= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING;
+        }
+        if self <= DebugSeverity::Info {
+            flags |= vk::DebugUtilsMessageSeverityFlagsEXT::INFO;
+        }
+        if self <= DebugSeverity::Verbose {
+            flags |= vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE;
+        }
+        flags
+    }
+}
+
+impl DebugMessengerState {
+    pub fn new(config: VulkanDebugConfig) -> Self {
+        Self {
+            config,
+            breadcrumbs: Mutex::new(VecDeque::with_capacity(BREADCRUMB_COUNT)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn config(&self) -> &VulkanDebugConfig {
+        &self.config
+    }
+
+    /// Records a submission so it can be reported if a validation error follows.
+    pub fn push_breadcrumb(&self, debug_name: Option<&str>) {
+        let mut breadcrumbs = self.breadcrumbs.lock().unwrap();
+        if breadcrumbs.len() == BREADCRUMB_COUNT {
+            breadcrumbs.pop_front();
+        }
+        breadcrumbs.push_back(debug_name.unwrap_or("unnamed submission").to_owned());
+    }
+
+    fn breadcrumbs(&self) -> String {
+        let breadcrumbs = self.breadcrumbs.lock().unwrap();
+        breadcrumbs
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+
+    fn is_ignored(&self, message_id: i32) -> bool {
+        IGNORED_MESSAGE_IDS.contains(&message_id)
+            || self.config.ignored_message_ids.contains(&message_id)
+    }
+}
+
+/// # Safety
+/// `user_data` must point to the [`DebugMessengerState`] the messenger was created with.
+pub(crate) unsafe extern "system" fn vulkan_debug_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    user_data: *mut std::os::raw::c_void,
+) -> vk::Bool32 {
+    let state = &*(user_data as *const DebugMessengerState);
+    let callback_data = *p_callback_data;
+    let message_id_number = callback_data.message_id_number;
+
+    let message_id_name = if callback_data.p_message_id_name.is_null() {
+        Cow::from("")
+    } else {
+        CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
+    };
+
+    let mut message = if callback_data.p_message.is_null() {
+        Cow::from("")
+    } else {
+        CStr::from_ptr(callback_data.p_message).to_string_lossy()
+    };
+
+    let labels = if callback_data.p_cmd_buf_labels.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(
+            callback_data.p_cmd_buf_labels,
+            callback_data.cmd_buf_label_count as usize,
+        )
+    };
+    let label_names: Vec<_> = labels
+        .iter()
+        .filter(|label| !label.p_label_name.is_null())
+        .map(|label| CStr::from_ptr(label.p_label_name).to_string_lossy())
+        .collect();
+
+    // Shader printf output is always logged. The outermost label is the debug name of the
+    // submission the shader ran in.
+    if message_id_name.contains(DEBUG_PRINTF_MESSAGE_ID) {
+        let submission = label_names
+            .first()
+            .map(|name| name.as_ref())
+            .unwrap_or("unnamed submission");
+        ard_log::info!("[{submission}] {message}");
+        return vk::FALSE;
+    }
+
+    let severity = DebugSeverity::from_vk(message_severity);
+    if severity < state.config.min_severity || state.is_ignored(message_id_number) {
+        return vk::FALSE;
+    }
+
+    // List the debug names of the objects and command buffer regions involved
+    let objects = if callback_data.p_objects.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(callback_data.p_objects, callback_data.object_count as usize)
+    };
+    let object_names: Vec<_> = objects
+        .iter()
+        .filter(|object| !object.p_object_name.is_null())
+        .map(|object| CStr::from_ptr(object.p_object_name).to_string_lossy())
+        .collect();
+    if !object_names.is_empty() {
+        message = Cow::from(format!("{message}\nObjects: {}", object_names.join(", ")));
+    }
+
+    if !label_names.is_empty() {
+        message = Cow::from(format!("{message}\nRegions: {}", label_names.join(" > ")));
+    }
+
+    match severity {
+        DebugSeverity::Verbose => ard_log::trace!(
+            "{message_type:?} [{message_id_name} ({message_id_number})] : {message}"
+        ),
+        DebugSeverity::Info => {
+            ard_log::info!("{message_type:?} [{message_id_name} ({message_id_number})] : {message}")
+        }
+        DebugSeverity::Warning => {
+            ard_log::warn!("{message_type:?} [{message_id_name} ({message_id_number})] : {message}")
+        }
+        DebugSeverity::Error => {
+            ard_log::error!(
+                "{message_type:?} [{message_id_name} ({message_id_number})] : {message}\nRecent submissions: {}",
+                state.breadcrumbs()
+            );
+
+            if state.config.panic_on_error {
+                panic!("vulkan validation error `{message_id_name}`");
+            }
+        }
+    }
+
+    vk::FALSE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breadcrumbs_keep_most_recent() {
+        let state = DebugMessengerState::new(VulkanDebugConfig::default());
+        for i in 0..(BREADCRUMB_COUNT + 2) {
+            state.push_breadcrumb(Some(&i.to_string()));
+        }
+        state.push_breadcrumb(None);
+
+        let breadcrumbs = state.breadcrumbs();
+        assert!(breadcrumbs.starts_with("3 -> "));
+        assert!(breadcrumbs.ends_with("-> unnamed submission"));
+    }
+
+    #[test]
+    fn messenger_flags_include_more_severe() {
+        let flags = DebugSeverity::Warning.to_vk_messenger_flags();
+        assert!(flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR));
+        assert!(flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING));
+        assert!(!flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO));
+    }
+}
@@ -1,20 +1,24 @@
+use std::ops::Range;
+
 use api::{
+    command_buffer::Command,
     descriptor_set::{
-        DescriptorBinding, DescriptorSetCreateError, DescriptorSetCreateInfo,
-        DescriptorSetLayoutCreateError, DescriptorSetLayoutCreateInfo, DescriptorSetUpdate,
-        DescriptorType, DescriptorValue,
+        DescriptorBinding, DescriptorBindingFlags, DescriptorSetCreateError,
+        DescriptorSetCreateInfo, DescriptorSetLayoutCreateError, DescriptorSetLayoutCreateInfo,
+        DescriptorSetUpdate, DescriptorType, DescriptorValue,
     },
     types::{AccessType, ShaderStage, SharingMode},
     Backend,
 };
 use ash::vk;
 use crossbeam_channel::Sender;
+use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
 
 use crate::{
-    buffer::BufferRefCounter,
+    buffer::{BufferRefCounter, TexelViewKey},
     job::Job,
-    texture::TextureRefCounter,
+    texture::{SampledViewKey, TextureRefCounter},
     util::{
         descriptor_pool::DescriptorPools,
         garbage_collector::Garbage,
@@ -27,6 +31,11 @@ pub struct DescriptorSet {
     pub(crate) set: vk::DescriptorSet,
     pub(crate) id: ResourceId,
     pub(crate) layout: vk::DescriptorSetLayout,
+    /// Number of elements allocated for the variable count binding. `0` if the layout has none.
+    pub(crate) variable_count: u32,
+    /// If the layout has a read-write storage binding. Dispatches using the set must wait for
+    /// the writes of earlier dispatches in the same pass.
+    pub(crate) writes: bool,
     pub(crate) bound: DescriptorSetBindings,
     pub(crate) on_drop: Sender<Garbage>,
 }
@@ -38,6 +47,40 @@ pub struct DescriptorSetLayout {
 
 pub(crate) type DescriptorSetBindings = SmallVec<[SmallVec<[Option<Binding>; 1]>; 4]>;
 
+/// Descriptors pushed by the commands of a single submission. Each is keyed by the address of the
+/// command that pushed it. The sets are released to the garbage collector when dropped.
+pub(crate) struct PushedSets<'a> {
+    loader: Option<&'a ash::khr::push_descriptor::Device>,
+    sets: FxHashMap<usize, PushedSet>,
+}
+
+struct PushedSet {
+    /// Holds the bound values. The handle is null if the descriptors are pushed.
+    set: DescriptorSet,
+    /// The writes to push. `None` if the layout can't be pushed, in which case `set` is bound
+    /// instead.
+    writes: Option<DescriptorWrites>,
+}
+
+/// Descriptor writes that reference the values they write, so they can be kept around and pushed
+/// while recording.
+#[derive(Default)]
+pub(crate) struct DescriptorWrites {
+    writes: Vec<PendingWrite>,
+    buffers: Vec<vk::DescriptorBufferInfo>,
+    images: Vec<vk::DescriptorImageInfo>,
+    texel_buffers: Vec<vk::BufferView>,
+    tlas: Vec<vk::AccelerationStructureKHR>,
+}
+
+struct PendingWrite {
+    binding: u32,
+    array_element: u32,
+    ty: vk::DescriptorType,
+    /// Index of the value within the list matching `ty`.
+    idx: usize,
+}
+
 pub(crate) struct Binding {
     pub value: BoundValue,
     pub stage: vk::PipelineStageFlags2,
@@ -52,6 +95,7 @@ pub(crate) enum BoundValue {
         sharing_mode: SharingMode,
         array_element: usize,
         aligned_size: usize,
+        offset: u64,
     },
     StorageBuffer {
         _ref_counter: BufferRefCounter,
@@ -60,6 +104,7 @@ pub(crate) enum BoundValue {
         sharing_mode: SharingMode,
         array_element: usize,
         aligned_size: usize,
+        offset: u64,
     },
     StorageImage {
         _ref_counter: TextureRefCounter,
@@ -75,13 +120,13 @@ pub(crate) enum BoundValue {
         _ref_counter: TextureRefCounter,
         image: vk::Image,
         id: ResourceId,
-        view: vk::ImageView,
         sharing_mode: SharingMode,
         aspect_mask: vk::ImageAspectFlags,
         base_mip: u32,
         mip_count: u32,
         array_element: usize,
     },
+    /// A single cube map or a cube map array.
     CubeMap {
         _ref_counter: TextureRefCounter,
         image: vk::Image,
@@ -91,7 +136,7 @@ pub(crate) enum BoundValue {
         aspect_mask: vk::ImageAspectFlags,
         base_mip: u32,
         mip_count: u32,
-        array_element: usize,
+        array_elements: Range<usize>,
     },
     Tlas {
         _ref_counter: BufferRefCounter,
@@ -106,10 +151,11 @@ impl DescriptorSetLayout {
     pub(crate) unsafe fn new(
         device: &ash::Device,
         pools: &mut DescriptorPools,
+        debug: Option<&ash::ext::debug_utils::Device>,
         create_info: DescriptorSetLayoutCreateInfo,
     ) -> Result<Self, DescriptorSetLayoutCreateError> {
         // Pre-cache the pool
-        let pool = pools.get(device, create_info.clone());
+        let pool = pools.get(device, debug, create_info.clone());
         Ok(DescriptorSetLayout {
             descriptor: create_info,
             layout: pool.layout(),
@@ -117,11 +163,12 @@ impl DescriptorSetLayout {
     }
 
     #[inline]
-    pub(crate) fn get_binding(&self, binding_value: u32) -> Option<&DescriptorBinding> {
+    pub(crate) fn get_binding(&self, binding_value: u32) -> Option<(usize, &DescriptorBinding)> {
         self.descriptor
             .bindings
             .iter()
-            .find(|&binding| binding.binding == binding_value)
+            .enumerate()
+            .find(|(_, binding)| binding.binding == binding_value)
     }
 }
 
@@ -134,20 +181,51 @@ impl DescriptorSet {
         create_info: DescriptorSetCreateInfo<crate::VulkanBackend>,
         set_ids: &IdGenerator,
     ) -> Result<Self, DescriptorSetCreateError> {
+        let mut variable_count = 0;
+        let mut writes = false;
         let mut bound =
             SmallVec::with_capacity(create_info.layout.internal().descriptor.bindings.len());
         for binding in &create_info.layout.internal().descriptor.bindings {
-            let mut binds = SmallVec::with_capacity(binding.count);
-            binds.resize_with(binding.count, || None);
+            let count = if binding
+                .flags
+                .contains(DescriptorBindingFlags::VARIABLE_COUNT)
+            {
+                let count = create_info.variable_count.unwrap_or(binding.count);
+                variable_count = count as u32;
+                count
+            } else {
+                binding.count
+            };
+
+            writes |= matches!(
+                binding.ty,
+                DescriptorType::StorageBuffer(AccessType::ReadWrite)
+                    | DescriptorType::StorageImage(AccessType::ReadWrite)
+                    | DescriptorType::StorageTexelBuffer(AccessType::ReadWrite)
+            );
+
+            let mut binds = SmallVec::with_capacity(count);
+            binds.resize_with(count, || None);
             bound.push(binds);
         }
 
-        let pool = pools.get(device, create_info.layout.internal().descriptor.clone());
-        let set = pool.allocate(device, debug, create_info.debug_name);
+        // Sets for push descriptor layouts only track the bound values
+        let pool = pools.get(
+            device,
+            debug,
+            create_info.layout.internal().descriptor.clone(),
+        );
+        let set = if pool.is_push() {
+            vk::DescriptorSet::null()
+        } else {
+            pool.allocate(device, debug, create_info.debug_name, variable_count)
+        };
         Ok(DescriptorSet {
             set,
             id: set_ids.create(),
             layout: pool.layout(),
+            variable_count,
+            writes,
             on_drop: garbage,
             bound,
         })
@@ -159,41 +237,92 @@ impl DescriptorSet {
         layout: &DescriptorSetLayout,
         updates: &[DescriptorSetUpdate<crate::VulkanBackend>],
     ) {
-        const SAMPLEABLE_ASPECTS: vk::ImageAspectFlags = vk::ImageAspectFlags::from_raw(
-            vk::ImageAspectFlags::COLOR.as_raw() | vk::ImageAspectFlags::DEPTH.as_raw(),
-        );
+        // Bindings that are updated after binding can be written while the set is in use, so
+        // there is no need to wait
+        let in_use = updates.iter().all(|update| {
+            layout
+                .get_binding(update.binding)
+                .is_some_and(|(_, binding)| {
+                    binding
+                        .flags
+                        .contains(DescriptorBindingFlags::UPDATE_AFTER_BIND)
+                })
+        });
 
         // Wait until the last queue that the buffer was used in has finished it's work
         let resc_state = ctx.resource_state.write().unwrap();
-        let mut sampler_cache = ctx.samplers.lock().unwrap();
 
         // NOTE: The reason we set the usage to `None` is because we have to wait for the previous
         // usage to complete. This implies that no one is using this set anymore and thus no
         // waits are further needed.
-        if let Some(old) = resc_state.get_set_queue_usage(self.id) {
-            ctx.wait_on(
-                &Job {
-                    ty: old.queue,
-                    target_value: old.timeline_value,
-                },
-                None,
-            );
+        if !in_use {
+            if let Some(old) = resc_state.get_set_queue_usage(self.id) {
+                ctx.wait_on(
+                    &Job {
+                        ty: old.queue,
+                        target_value: old.timeline_value,
+                        queries: None,
+                        debug_name: None,
+                    },
+                    None,
+                );
+            }
         }
 
-        let mut writes = Vec::with_capacity(updates.len());
-        let mut buffers = Vec::default();
-        let mut images = Vec::default();
-        let mut tlas_writes = Vec::default();
+        let writes = self.write(ctx, layout, updates, in_use);
+        writes.with_writes(self.set, |writes| {
+            ctx.device.update_descriptor_sets(writes, &[]);
+        });
+    }
+
+    /// Binds the new values and creates the writes needed to update the set with them. If
+    /// `in_use` is `false`, the set *must not* be in use by any queue. Otherwise, replaced values
+    /// are kept alive by the garbage collector until in-flight work is complete.
+    pub(crate) unsafe fn write(
+        &mut self,
+        ctx: &VulkanBackend,
+        layout: &DescriptorSetLayout,
+        updates: &[DescriptorSetUpdate<crate::VulkanBackend>],
+        in_use: bool,
+    ) -> DescriptorWrites {
+        const SAMPLEABLE_ASPECTS: vk::ImageAspectFlags = vk::ImageAspectFlags::from_raw(
+            vk::ImageAspectFlags::COLOR.as_raw() | vk::ImageAspectFlags::DEPTH.as_raw(),
+        );
+
+        let mut sampler_cache = ctx.samplers.lock().unwrap();
+        let mut writes = DescriptorWrites::default();
+        let mut replaced = Vec::default();
 
         for update in updates {
+            let (idx, binding) = match layout.get_binding(update.binding) {
+                Some(binding) => binding,
+                None => {
+                    ard_log::warn!("attempt to update invalid binding `{}`", update.binding);
+                    continue;
+                }
+            };
+
+            // Partially bound arrays may have holes, but elements past the end are invalid
+            let slot = match self.bound[idx].get_mut(update.array_element) {
+                Some(slot) => slot,
+                None => {
+                    ard_log::warn!(
+                        "attempt to update element `{}` of binding `{}` with `{}` elements",
+                        update.array_element,
+                        update.binding,
+                        self.bound[idx].len()
+                    );
+                    continue;
+                }
+            };
+
             // Deal with the old value
-            if let Some(old) = self.bound[update.binding as usize][update.array_element].take() {
-                match old.value {
+            match slot.take() {
+                // The set might still be in use, so the old value must outlive that work
+                Some(old) if in_use => replaced.push(old),
+                Some(old) => match old.value {
                     // It's safe to destroy the image view now because we guarantee the set is not
                     // being used by any queues
-                    BoundValue::Texture { view, .. } => {
-                        ctx.device.destroy_image_view(view, None);
-                    }
                     BoundValue::CubeMap { view, .. } => {
                         ctx.device.destroy_image_view(view, None);
                     }
@@ -201,12 +330,12 @@ impl DescriptorSet {
                         ctx.device.destroy_image_view(view, None);
                     }
                     _ => {}
-                }
+                },
+                None => {}
             }
 
             // Bind new value
-            self.bound[update.binding as usize][update.array_element] = Some({
-                let binding = layout.get_binding(update.binding).unwrap();
+            *slot = Some({
                 let access = match binding.ty {
                     DescriptorType::Texture => vk::AccessFlags2::SHADER_READ,
                     DescriptorType::UniformBuffer => vk::AccessFlags2::UNIFORM_READ,
@@ -217,14 +346,19 @@ impl DescriptorSet {
                                 | vk::AccessFlags2::SHADER_STORAGE_WRITE
                         }
                     },
-                    DescriptorType::StorageImage(ty) => match ty {
-                        AccessType::Read => vk::AccessFlags2::SHADER_STORAGE_READ,
-                        AccessType::ReadWrite => {
-                            vk::AccessFlags2::SHADER_STORAGE_READ
-                                | vk::AccessFlags2::SHADER_STORAGE_WRITE
+                    DescriptorType::StorageImage(ty) | DescriptorType::StorageTexelBuffer(ty) => {
+                        match ty {
+                            AccessType::Read => vk::AccessFlags2::SHADER_STORAGE_READ,
+                            AccessType::ReadWrite => {
+                                vk::AccessFlags2::SHADER_STORAGE_READ
+                                    | vk::AccessFlags2::SHADER_STORAGE_WRITE
+                            }
                         }
-                    },
-                    DescriptorType::CubeMap => vk::AccessFlags2::SHADER_READ,
+                    }
+                    DescriptorType::UniformTexelBuffer => vk::AccessFlags2::SHADER_SAMPLED_READ,
+                    DescriptorType::CubeMap | DescriptorType::CubeMapArray => {
+                        vk::AccessFlags2::SHADER_READ
+                    }
                     DescriptorType::TopLevelAccelerationStructure => {
                         vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
                     }
@@ -255,6 +389,7 @@ impl DescriptorSet {
                     ShaderStage::RayMiss => vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
                     ShaderStage::RayClosestHit => vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
                     ShaderStage::RayAnyHit => vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+                    ShaderStage::RayIntersection => vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
                 };
 
                 match &update.value {
@@ -265,22 +400,15 @@ impl DescriptorSet {
                         let sharing_mode = buffer.sharing_mode();
                         let buffer = buffer.internal();
 
-                        buffers.push(
+                        writes.buffer(
+                            update,
+                            vk::DescriptorType::UNIFORM_BUFFER,
                             vk::DescriptorBufferInfo::default()
                                 .buffer(buffer.buffer)
-                                .offset(buffer.aligned_size * (*array_element) as u64)
+                                .offset(buffer.offset(*array_element))
                                 .range(buffer.aligned_size),
                         );
 
-                        writes.push((
-                            vk::WriteDescriptorSet::default()
-                                .dst_set(self.set)
-                                .dst_binding(update.binding)
-                                .dst_array_element(update.array_element as u32)
-                                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER),
-                            buffers.len() - 1..buffers.len(),
-                        ));
-
                         Binding {
                             access,
                             stage,
@@ -291,6 +419,7 @@ impl DescriptorSet {
                                 sharing_mode,
                                 array_element: *array_element,
                                 aligned_size: buffer.aligned_size as usize,
+                                offset: buffer.offset(*array_element),
                             },
                         }
                     }
@@ -301,22 +430,15 @@ impl DescriptorSet {
                         let sharing_mode = buffer.sharing_mode();
                         let buffer = buffer.internal();
 
-                        buffers.push(
+                        writes.buffer(
+                            update,
+                            vk::DescriptorType::STORAGE_BUFFER,
                             vk::DescriptorBufferInfo::default()
                                 .buffer(buffer.buffer)
                                 .offset(buffer.offset(*array_element))
                                 .range(buffer.aligned_size),
                         );
 
-                        writes.push((
-                            vk::WriteDescriptorSet::default()
-                                .dst_set(self.set)
-                                .dst_binding(update.binding)
-                                .dst_array_element(update.array_element as u32)
-                                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER),
-                            buffers.len() - 1..buffers.len(),
-                        ));
-
                         Binding {
                             access,
                             stage,
@@ -327,6 +449,7 @@ impl DescriptorSet {
                                 sharing_mode,
                                 array_element: *array_element,
                                 aligned_size: buffer.aligned_size as usize,
+                                offset: buffer.offset(*array_element),
                             },
                         }
                     }
@@ -336,47 +459,39 @@ impl DescriptorSet {
                         sampler,
                         base_mip,
                         mip_count,
+                        aspect,
                     } => {
                         let sharing_mode = texture.sharing_mode();
                         let texture = texture.internal();
 
-                        // Create a view for the texture
-                        let create_info = vk::ImageViewCreateInfo::default()
-                            .format(texture.format)
-                            .view_type(vk::ImageViewType::TYPE_2D)
-                            .subresource_range(vk::ImageSubresourceRange {
-                                aspect_mask: texture.aspect_flags & SAMPLEABLE_ASPECTS,
-                                base_mip_level: *base_mip as u32,
-                                level_count: *mip_count as u32,
-                                base_array_layer: *array_element as u32,
-                                layer_count: 1,
-                            })
-                            .components(vk::ComponentMapping {
-                                r: vk::ComponentSwizzle::R,
-                                g: vk::ComponentSwizzle::G,
-                                b: vk::ComponentSwizzle::B,
-                                a: vk::ComponentSwizzle::A,
-                            })
-                            .image(texture.image);
-
-                        let view = ctx.device.create_image_view(&create_info, None).unwrap();
+                        // Find or create a view of the requested aspect
+                        let view = texture.get_sampled_view(
+                            &ctx.device,
+                            ctx.debug.as_ref().map(|debug| &debug.device),
+                            SampledViewKey {
+                                aspect: match aspect {
+                                    Some(aspect) => crate::util::to_vk_aspect(*aspect),
+                                    None => texture.aspect_flags & SAMPLEABLE_ASPECTS,
+                                },
+                                base_mip: *base_mip as u32,
+                                mip_count: *mip_count as u32,
+                                array_element: *array_element as u32,
+                            },
+                        );
 
-                        images.push(
+                        writes.image(
+                            update,
+                            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
                             vk::DescriptorImageInfo::default()
-                                .sampler(sampler_cache.get(&ctx.device, *sampler))
+                                .sampler(sampler_cache.get(
+                                    &ctx.device,
+                                    ctx.debug.as_ref().map(|debug| &debug.device),
+                                    *sampler,
+                                ))
                                 .image_view(view)
                                 .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
                         );
 
-                        writes.push((
-                            vk::WriteDescriptorSet::default()
-                                .dst_set(self.set)
-                                .dst_binding(update.binding)
-                                .dst_array_element(update.array_element as u32)
-                                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER),
-                            images.len() - 1..images.len(),
-                        ));
-
                         Binding {
                             access,
                             stage,
@@ -385,7 +500,6 @@ impl DescriptorSet {
                                 image: texture.image,
                                 id: texture.id,
                                 sharing_mode,
-                                view,
                                 aspect_mask: texture.aspect_flags,
                                 mip_count: *mip_count as u32,
                                 base_mip: *base_mip as u32,
@@ -404,7 +518,7 @@ impl DescriptorSet {
                         // Create a view for the texture
                         let create_info = vk::ImageViewCreateInfo::default()
                             .format(texture.format)
-                            .view_type(vk::ImageViewType::TYPE_2D)
+                            .view_type(texture.view_type)
                             .subresource_range(vk::ImageSubresourceRange {
                                 aspect_mask: texture.aspect_flags & SAMPLEABLE_ASPECTS,
                                 base_mip_level: *mip as u32,
@@ -421,22 +535,22 @@ impl DescriptorSet {
                             .image(texture.image);
 
                         let view = ctx.device.create_image_view(&create_info, None).unwrap();
+                        if let Some(name) = &texture.debug_name {
+                            crate::util::set_debug_name(
+                                ctx.debug.as_ref().map(|debug| &debug.device),
+                                view,
+                                &format!("{name}_storage_view_{array_element}_{mip}"),
+                            );
+                        }
 
-                        images.push(
+                        writes.image(
+                            update,
+                            vk::DescriptorType::STORAGE_IMAGE,
                             vk::DescriptorImageInfo::default()
                                 .image_view(view)
                                 .image_layout(vk::ImageLayout::GENERAL),
                         );
 
-                        writes.push((
-                            vk::WriteDescriptorSet::default()
-                                .dst_set(self.set)
-                                .dst_binding(update.binding)
-                                .dst_array_element(update.array_element as u32)
-                                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE),
-                            images.len() - 1..images.len(),
-                        ));
-
                         Binding {
                             access,
                             stage,
@@ -452,26 +566,52 @@ impl DescriptorSet {
                             },
                         }
                     }
-                    DescriptorValue::CubeMap {
-                        cube_map,
-                        array_element,
-                        sampler,
-                        base_mip,
-                        mip_count,
-                    } => {
+                    DescriptorValue::CubeMap { .. } | DescriptorValue::CubeMapArray { .. } => {
+                        let (cube_map, array_elements, view_type, sampler, base_mip, mip_count) =
+                            match &update.value {
+                                DescriptorValue::CubeMap {
+                                    cube_map,
+                                    array_element,
+                                    sampler,
+                                    base_mip,
+                                    mip_count,
+                                } => (
+                                    *cube_map,
+                                    *array_element..(*array_element + 1),
+                                    vk::ImageViewType::CUBE,
+                                    sampler,
+                                    base_mip,
+                                    mip_count,
+                                ),
+                                DescriptorValue::CubeMapArray {
+                                    cube_map,
+                                    array_elements,
+                                    sampler,
+                                    base_mip,
+                                    mip_count,
+                                } => (
+                                    *cube_map,
+                                    array_elements.clone(),
+                                    vk::ImageViewType::CUBE_ARRAY,
+                                    sampler,
+                                    base_mip,
+                                    mip_count,
+                                ),
+                                _ => unreachable!(),
+                            };
                         let sharing_mode = cube_map.sharing_mode();
                         let cube_map = cube_map.internal();
 
                         // Create a view for the texture
                         let create_info = vk::ImageViewCreateInfo::default()
                             .format(cube_map.format)
-                            .view_type(vk::ImageViewType::CUBE)
+                            .view_type(view_type)
                             .subresource_range(vk::ImageSubresourceRange {
                                 aspect_mask: cube_map.aspect_flags & SAMPLEABLE_ASPECTS,
                                 base_mip_level: *base_mip as u32,
                                 level_count: *mip_count as u32,
-                                base_array_layer: 6 * *array_element as u32,
-                                layer_count: 6,
+                                base_array_layer: 6 * array_elements.start as u32,
+                                layer_count: 6 * array_elements.len() as u32,
                             })
                             .components(vk::ComponentMapping {
                                 r: vk::ComponentSwizzle::R,
@@ -482,23 +622,27 @@ impl DescriptorSet {
                             .image(cube_map.image);
 
                         let view = ctx.device.create_image_view(&create_info, None).unwrap();
+                        if let Some(name) = &cube_map.debug_name {
+                            crate::util::set_debug_name(
+                                ctx.debug.as_ref().map(|debug| &debug.device),
+                                view,
+                                &format!("{name}_sampled_view_{}_{base_mip}", array_elements.start),
+                            );
+                        }
 
-                        images.push(
+                        writes.image(
+                            update,
+                            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
                             vk::DescriptorImageInfo::default()
-                                .sampler(sampler_cache.get(&ctx.device, *sampler))
+                                .sampler(sampler_cache.get(
+                                    &ctx.device,
+                                    ctx.debug.as_ref().map(|debug| &debug.device),
+                                    *sampler,
+                                ))
                                 .image_view(view)
                                 .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
                         );
 
-                        writes.push((
-                            vk::WriteDescriptorSet::default()
-                                .dst_set(self.set)
-                                .dst_binding(update.binding)
-                                .dst_array_element(update.array_element as u32)
-                                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER),
-                            images.len() - 1..images.len(),
-                        ));
-
                         Binding {
                             access,
                             stage,
@@ -511,27 +655,12 @@ impl DescriptorSet {
                                 aspect_mask: cube_map.aspect_flags,
                                 mip_count: *mip_count as u32,
                                 base_mip: *base_mip as u32,
-                                array_element: *array_element,
+                                array_elements,
                             },
                         }
                     }
                     DescriptorValue::TopLevelAccelerationStructure(tlas) => {
-                        tlas_writes.push(
-                            vk::WriteDescriptorSetAccelerationStructureKHR::default()
-                                .acceleration_structures(std::slice::from_ref(
-                                    &tlas.internal().acceleration_struct,
-                                )),
-                        );
-                        let idx = tlas_writes.len() - 1;
-
-                        writes.push((
-                            vk::WriteDescriptorSet::default()
-                                .dst_set(self.set)
-                                .dst_binding(update.binding)
-                                .dst_array_element(update.array_element as u32)
-                                .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR),
-                            idx..tlas_writes.len(),
-                        ));
+                        writes.tlas(update, tlas.internal().acceleration_struct);
 
                         Binding {
                             access,
@@ -545,31 +674,302 @@ impl DescriptorSet {
                             },
                         }
                     }
+                    // Texel buffers are tracked like the buffers they view
+                    DescriptorValue::UniformTexelBuffer(view)
+                    | DescriptorValue::StorageTexelBuffer(view) => {
+                        let sharing_mode = view.buffer().sharing_mode();
+                        let buffer = view.buffer().internal();
+                        let array_element = view.array_element();
+
+                        let texel_view = buffer.get_texel_view(
+                            &ctx.device,
+                            ctx.debug.as_ref().map(|debug| &debug.device),
+                            TexelViewKey {
+                                array_element,
+                                offset: view.offset(),
+                                range: view.range(),
+                                format: view.format(),
+                            },
+                        );
+
+                        let _ref_counter = buffer.ref_counter.clone();
+                        let aligned_size = buffer.aligned_size as usize;
+                        let offset = buffer.offset(array_element);
+                        match &update.value {
+                            DescriptorValue::UniformTexelBuffer(_) => {
+                                writes.texel_buffer(
+                                    update,
+                                    vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                                    texel_view,
+                                );
+
+                                Binding {
+                                    access,
+                                    stage,
+                                    value: BoundValue::UniformBuffer {
+                                        _ref_counter,
+                                        buffer: buffer.buffer,
+                                        id: buffer.id,
+                                        sharing_mode,
+                                        array_element,
+                                        aligned_size,
+                                        offset,
+                                    },
+                                }
+                            }
+                            _ => {
+                                writes.texel_buffer(
+                                    update,
+                                    vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                                    texel_view,
+                                );
+
+                                Binding {
+                                    access,
+                                    stage,
+                                    value: BoundValue::StorageBuffer {
+                                        _ref_counter,
+                                        buffer: buffer.buffer,
+                                        id: buffer.id,
+                                        sharing_mode,
+                                        array_element,
+                                        aligned_size,
+                                        offset,
+                                    },
+                                }
+                            }
+                        }
+                    }
                 }
             });
         }
 
-        // Map temp writes to final writes
-        let writes: Vec<_> = writes
-            .into_iter()
-            .map(|(mut write, range)| match write.descriptor_type {
-                vk::DescriptorType::COMBINED_IMAGE_SAMPLER | vk::DescriptorType::STORAGE_IMAGE => {
-                    write.image_info(&images[range])
-                }
-                vk::DescriptorType::STORAGE_BUFFER | vk::DescriptorType::UNIFORM_BUFFER => {
-                    write.buffer_info(&buffers[range])
-                }
-                vk::DescriptorType::ACCELERATION_STRUCTURE_KHR => {
-                    write.p_next = <*const _>::cast(&tlas_writes[range.start]);
-                    write.descriptor_count = 1;
-                    write
+        if !replaced.is_empty() {
+            let _ = self.on_drop.send(Garbage::Bindings(replaced));
+        }
+
+        writes
+    }
+}
+
+impl DescriptorWrites {
+    #[inline]
+    fn buffer(
+        &mut self,
+        update: &DescriptorSetUpdate<crate::VulkanBackend>,
+        ty: vk::DescriptorType,
+        info: vk::DescriptorBufferInfo,
+    ) {
+        self.buffers.push(info);
+        self.push(update, ty, self.buffers.len() - 1);
+    }
+
+    #[inline]
+    fn image(
+        &mut self,
+        update: &DescriptorSetUpdate<crate::VulkanBackend>,
+        ty: vk::DescriptorType,
+        info: vk::DescriptorImageInfo,
+    ) {
+        self.images.push(info);
+        self.push(update, ty, self.images.len() - 1);
+    }
+
+    #[inline]
+    fn texel_buffer(
+        &mut self,
+        update: &DescriptorSetUpdate<crate::VulkanBackend>,
+        ty: vk::DescriptorType,
+        view: vk::BufferView,
+    ) {
+        self.texel_buffers.push(view);
+        self.push(update, ty, self.texel_buffers.len() - 1);
+    }
+
+    #[inline]
+    fn tlas(
+        &mut self,
+        update: &DescriptorSetUpdate<crate::VulkanBackend>,
+        tlas: vk::AccelerationStructureKHR,
+    ) {
+        self.tlas.push(tlas);
+        self.push(
+            update,
+            vk::DescriptorType::ACCELERATION_STRUCTURE_KHR,
+            self.tlas.len() - 1,
+        );
+    }
+
+    #[inline]
+    fn push(
+        &mut self,
+        update: &DescriptorSetUpdate<crate::VulkanBackend>,
+        ty: vk::DescriptorType,
+        idx: usize,
+    ) {
+        self.writes.push(PendingWrite {
+            binding: update.binding,
+            array_element: update.array_element as u32,
+            ty,
+            idx,
+        });
+    }
+
+    /// Calls `f` with the writes targeting `set`.
+    pub(crate) fn with_writes<R>(
+        &self,
+        set: vk::DescriptorSet,
+        f: impl FnOnce(&[vk::WriteDescriptorSet]) -> R,
+    ) -> R {
+        let tlas_writes: Vec<_> = self
+            .tlas
+            .iter()
+            .map(|tlas| {
+                vk::WriteDescriptorSetAccelerationStructureKHR::default()
+                    .acceleration_structures(std::slice::from_ref(tlas))
+            })
+            .collect();
+
+        let writes: Vec<_> = self
+            .writes
+            .iter()
+            .map(|write| {
+                let out = vk::WriteDescriptorSet::default()
+                    .dst_set(set)
+                    .dst_binding(write.binding)
+                    .dst_array_element(write.array_element)
+                    .descriptor_type(write.ty);
+
+                match write.ty {
+                    vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                    | vk::DescriptorType::STORAGE_IMAGE => {
+                        out.image_info(std::slice::from_ref(&self.images[write.idx]))
+                    }
+                    vk::DescriptorType::STORAGE_BUFFER | vk::DescriptorType::UNIFORM_BUFFER => {
+                        out.buffer_info(std::slice::from_ref(&self.buffers[write.idx]))
+                    }
+                    vk::DescriptorType::UNIFORM_TEXEL_BUFFER
+                    | vk::DescriptorType::STORAGE_TEXEL_BUFFER => {
+                        out.texel_buffer_view(std::slice::from_ref(&self.texel_buffers[write.idx]))
+                    }
+                    vk::DescriptorType::ACCELERATION_STRUCTURE_KHR => {
+                        let mut out = out;
+                        out.p_next = <*const _>::cast(&tlas_writes[write.idx]);
+                        out.descriptor_count = 1;
+                        out
+                    }
+                    _ => unreachable!("should handle all possible types"),
                 }
-                _ => unreachable!("should handle all possible types"),
             })
             .collect();
 
-        ctx.device.update_descriptor_sets(&writes, &[]);
+        f(&writes)
+    }
+}
+
+impl<'a> PushedSets<'a> {
+    /// Writes the descriptors pushed by every command, including those within slices.
+    pub(crate) unsafe fn new(
+        ctx: &'a VulkanBackend,
+        commands: &[Command<'_, crate::VulkanBackend>],
+    ) -> Self {
+        let mut pushed = PushedSets {
+            loader: ctx.push_descriptor_loader.as_ref(),
+            sets: FxHashMap::default(),
+        };
+
+        for command in commands {
+            match command {
+                Command::RenderPassSlices(slices) => {
+                    for command in slices.iter().flatten() {
+                        pushed.prepare(ctx, command);
+                    }
+                }
+                command => pushed.prepare(ctx, command),
+            }
+        }
+
+        pushed
+    }
+
+    unsafe fn prepare(&mut self, ctx: &VulkanBackend, command: &Command<'_, crate::VulkanBackend>) {
+        let (layout, bindings) = match command {
+            Command::PushDescriptorSet {
+                layout, bindings, ..
+            } => (layout, bindings),
+            _ => return,
+        };
+
+        let mut set = DescriptorSet::new(
+            &ctx.device,
+            &mut ctx.pools.lock().unwrap(),
+            ctx.garbage.sender(),
+            ctx.debug.as_ref().map(|debug| &debug.device),
+            DescriptorSetCreateInfo {
+                layout: layout.clone(),
+                debug_name: None,
+                variable_count: None,
+            },
+            &ctx.set_ids,
+        )
+        .unwrap();
+
+        // Without a set the layout can be pushed. Otherwise, the set came from the pool's free
+        // list and is written like any other set.
+        let writes = if set.set == vk::DescriptorSet::null() {
+            Some(set.write(ctx, layout.internal(), bindings, false))
+        } else {
+            set.update(ctx, layout.internal(), bindings);
+            None
+        };
+
+        self.sets.insert(key(command), PushedSet { set, writes });
     }
+
+    /// The set holding the values pushed by `command`.
+    #[inline(always)]
+    pub(crate) fn get(&self, command: &Command<'_, crate::VulkanBackend>) -> &DescriptorSet {
+        &self.sets[&key(command)].set
+    }
+
+    /// Records the descriptors pushed by `command`.
+    pub(crate) unsafe fn record(
+        &self,
+        device: &ash::Device,
+        cb: vk::CommandBuffer,
+        bind_point: vk::PipelineBindPoint,
+        layout: vk::PipelineLayout,
+        command: &Command<'_, crate::VulkanBackend>,
+    ) {
+        let index = match command {
+            Command::PushDescriptorSet { index, .. } => *index as u32,
+            _ => unreachable!(),
+        };
+
+        let pushed = &self.sets[&key(command)];
+        match &pushed.writes {
+            Some(writes) => writes.with_writes(vk::DescriptorSet::null(), |writes| {
+                self.loader
+                    .unwrap()
+                    .cmd_push_descriptor_set(cb, bind_point, layout, index, writes);
+            }),
+            None => {
+                device.cmd_bind_descriptor_sets(
+                    cb,
+                    bind_point,
+                    layout,
+                    index,
+                    &[pushed.set.set],
+                    &[],
+                );
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn key(command: &Command<'_, crate::VulkanBackend>) -> usize {
+    command as *const _ as usize
 }
 
 impl Drop for DescriptorSet {
@@ -578,6 +978,7 @@ impl Drop for DescriptorSet {
             set: self.set,
             id: self.id,
             layout: self.layout,
+            variable_count: self.variable_count,
             bindings: std::mem::take(&mut self.bound),
         });
     }
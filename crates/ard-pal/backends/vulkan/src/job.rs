@@ -1,6 +1,36 @@
 use api::types::QueueType;
+use crossbeam_channel::Sender;
+
+use crate::util::{garbage_collector::Garbage, queries::Query};
 
 pub struct Job {
     pub(crate) ty: QueueType,
     pub(crate) target_value: u64,
+    pub(crate) queries: Option<JobQueries>,
+    /// Debug name of the submission, used to report which submission faulted if the device is
+    /// lost while waiting on the job.
+    pub(crate) debug_name: Option<String>,
+}
+
+/// Queries written by a job.
+pub(crate) struct JobQueries {
+    /// Label of each timestamp region along with the queries written at the start and end of the
+    /// region.
+    pub timestamps: Vec<(String, Query, Query)>,
+    /// Label of each statistics region along with the query used for the region.
+    pub statistics: Vec<(String, Query)>,
+    pub on_drop: Sender<Garbage>,
+}
+
+impl Drop for JobQueries {
+    fn drop(&mut self) {
+        let _ = self.on_drop.send(Garbage::Queries {
+            timestamps: self
+                .timestamps
+                .drain(..)
+                .flat_map(|(_, begin, end)| [begin, end])
+                .collect(),
+            statistics: self.statistics.drain(..).map(|(_, query)| query).collect(),
+        });
+    }
 }
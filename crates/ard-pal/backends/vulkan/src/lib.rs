@@ -6,74 +6,97 @@ use api::{
     command_buffer::{BlitDestination, BlitSource, Command},
     compute_pass::ComputePassDispatch,
     compute_pipeline::{ComputePipelineCreateError, ComputePipelineCreateInfo},
-    context::{GraphicsProperties, MeshShadingProperties},
+    context::{
+        AdapterInfo, AdapterPreference, AdapterType, DeviceLimits, GarbageReport, GraphicsFeatures,
+        GraphicsProperties, MemoryAllocationInfo, MemoryBudget, MemoryPressureCallback,
+        MemoryStats, MeshShadingProperties, PipelineCacheSaveError, RayTracingProperties,
+        SampleCountProperties, SparseProperties,
+    },
     cube_map::{CubeMapCreateError, CubeMapCreateInfo},
     descriptor_set::{
         DescriptorSetCreateError, DescriptorSetCreateInfo, DescriptorSetLayoutCreateError,
         DescriptorSetLayoutCreateInfo, DescriptorSetUpdate,
     },
     graphics_pipeline::{GraphicsPipelineCreateError, GraphicsPipelineCreateInfo},
-    queue::SurfacePresentFailure,
-    render_pass::{ColorAttachmentDestination, DepthStencilAttachmentDestination},
-    rt_pass::RayTracingDispatchSource,
+    queue::{SubmitError, SurfacePresentFailure},
+    render_pass::{
+        ColorAttachmentDestination, DepthStencilAttachmentDestination, RenderPassDescriptor,
+    },
+    rt_pass::{RayTracingDispatchSource, ShaderBindingTableRegion},
     rt_pipeline::{
         RayTracingPipelineCreateError, RayTracingPipelineCreateInfo, ShaderBindingTableData,
     },
     shader::{ShaderCreateError, ShaderCreateInfo},
     surface::{
         SurfaceCapabilities, SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo,
-        SurfaceImageAcquireError, SurfacePresentSuccess, SurfaceUpdateError,
+        SurfaceImageAcquireError, SurfacePresentSuccess, SurfaceProperties, SurfaceUpdateError,
     },
     texture::{TextureCreateError, TextureCreateInfo},
     tlas::{TopLevelAccelerationStructureCreateError, TopLevelAccelerationStructureCreateInfo},
     types::*,
     Backend,
 };
-use ash::vk::{self, DebugUtilsMessageSeverityFlagsEXT};
+use ash::vk;
 use blas::BottomLevelAccelerationStructure;
 use buffer::Buffer;
+use capture::FrameCapture;
+use command_cache::CommandCache;
 use compute_pipeline::{ComputePipeline, DispatchIndirect};
 use crossbeam_utils::sync::ShardedLock;
 use cube_map::CubeMap;
-use descriptor_set::{DescriptorSet, DescriptorSetLayout};
+use descriptor_set::{DescriptorSet, DescriptorSetLayout, PushedSets};
 use gpu_allocator::vulkan::*;
 use graphics_pipeline::GraphicsPipeline;
-use job::Job;
+use job::{Job, JobQueries};
 use queue::VkQueue;
-use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use render_pass::{DrawIndexedIndirect, FramebufferCache, RenderPassCache, VkRenderPass};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle};
+use rayon::prelude::*;
+use render_pass::{DrawIndexedIndirect, DrawIndirect, FramebufferCache, RenderPassCache};
 use rt_pipeline::RayTracingPipeline;
+use rustc_hash::FxHashMap;
 use shader::Shader;
 use std::{
-    borrow::Cow,
     ffi::{CStr, CString},
     mem::ManuallyDrop,
     ops::Shr,
+    path::PathBuf,
     ptr::NonNull,
-    sync::Mutex,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
 };
 use surface::{Surface, SurfaceImage};
-use texture::Texture;
+use texture::{Texture, TextureMemory};
 use thiserror::Error;
 use tlas::TopLevelAccelerationStructure;
 use util::{
     command_sort::CommandSorting,
+    compute_layouts::ComputeLayouts,
     descriptor_pool::DescriptorPools,
-    garbage_collector::{GarbageCleanupArgs, GarbageCollector, TimelineValues},
+    events::Events,
+    garbage_collector::{Garbage, GarbageCleanupArgs, GarbageCollector, TimelineValues},
     id_gen::IdGenerator,
-    pipeline_cache::PipelineCache,
-    queries::Queries,
+    memory::{MemoryBudgets, MemoryTracker, TrackedAllocator},
+    pipeline_cache::{PipelineCache, PipelineCacheHeader},
+    queries::{Queries, Query},
     sampler_cache::SamplerCache,
     semaphores::{SemaphoreTracker, WaitInfo},
+    sparse::{SparseBinds, SPARSE_PAGE_SIZE},
+    transient::TransientBuffers,
     usage::GlobalResourceUsage,
 };
 
 use crate::util::command_sort::CommandSortingInfo;
+use debug::{vulkan_debug_callback, DebugMessengerState};
+
+pub use debug::{DebugSeverity, VulkanDebugConfig};
 
 pub mod blas;
 pub mod buffer;
+pub mod capture;
+pub mod command_cache;
 pub mod compute_pipeline;
 pub mod cube_map;
+pub mod debug;
 pub mod descriptor_set;
 pub mod graphics_pipeline;
 pub mod job;
@@ -89,10 +112,21 @@ pub mod util;
 pub struct VulkanBackendCreateInfo<'a, D: HasDisplayHandle> {
     pub app_name: String,
     pub engine_name: String,
-    /// A window is required to find a queue that supports presentation.
-    pub display_handle: &'a D,
+    /// Display used to find a queue that supports presentation. If `None`, the backend is
+    /// headless: no surfaces can be created and `QueueType::Present` shares the main queue.
+    pub display_handle: Option<&'a D>,
     /// Enables debugging layers and extensions.
     pub debug: bool,
+    /// How validation layer messages are reported if `debug` is enabled.
+    pub debug_config: VulkanDebugConfig,
+    /// File the pipeline cache is loaded from and saved to. Speeds up pipeline creation on later
+    /// runs. Missing or incompatible files are ignored.
+    pub pipeline_cache_path: Option<PathBuf>,
+    /// Limits the bytes allocated from each device local heap. Allocations over the budget fail
+    /// as if the heap were full, which is useful to test how the app copes with less VRAM.
+    pub device_memory_budget: Option<u64>,
+    /// Which adapter to create the backend on.
+    pub adapter_preference: AdapterPreference,
 }
 
 #[derive(Debug, Error)]
@@ -103,6 +137,16 @@ pub enum VulkanBackendCreateError {
     AshLoadError(ash::LoadingError),
     #[error("no suitable graphics device was found")]
     NoDevice,
+    #[error("unsupported display: {0}")]
+    UnsupportedDisplay(String),
+    #[error(
+        "adapter {requested:?} doesn't exist or isn't supported. available adapters: {}",
+        list_adapters(.available)
+    )]
+    AdapterUnavailable {
+        requested: AdapterPreference,
+        available: Vec<AdapterInfo>,
+    },
 }
 
 pub struct VulkanBackend {
@@ -117,18 +161,29 @@ pub struct VulkanBackend {
     pub(crate) device: ash::Device,
     pub(crate) surface_loader: ash::khr::surface::Instance,
     pub(crate) swapchain_loader: ash::khr::swapchain::Device,
+    /// `true` if the backend was created without a display handle.
+    pub(crate) headless: bool,
     pub(crate) mesh_shading_loader: ash::ext::mesh_shader::Device,
     pub(crate) rt_loader: ash::khr::ray_tracing_pipeline::Device,
     pub(crate) as_loader: ash::khr::acceleration_structure::Device,
+    /// `None` if push descriptors are not supported.
+    pub(crate) push_descriptor_loader: Option<ash::khr::push_descriptor::Device>,
+    /// `None` if conditional rendering is not supported.
+    pub(crate) conditional_rendering_loader: Option<ash::ext::conditional_rendering::Device>,
     pub(crate) main: ShardedLock<VkQueue>,
     pub(crate) transfer: ShardedLock<VkQueue>,
     pub(crate) present: ShardedLock<VkQueue>,
     pub(crate) compute: ShardedLock<VkQueue>,
-    pub(crate) allocator: ManuallyDrop<Mutex<Allocator>>,
+    pub(crate) allocator: ManuallyDrop<Mutex<TrackedAllocator>>,
+    pub(crate) memory: Arc<MemoryTracker>,
+    pub(crate) budgets: MemoryBudgets,
     pub(crate) render_passes: RenderPassCache,
     pub(crate) framebuffers: FramebufferCache,
     pub(crate) garbage: GarbageCollector,
     pub(crate) queries: Mutex<Queries>,
+    /// Events used by split barriers.
+    pub(crate) events: Mutex<Events>,
+    pub(crate) transient: Mutex<TransientBuffers>,
     pub(crate) buffer_ids: IdGenerator,
     pub(crate) image_ids: IdGenerator,
     pub(crate) set_ids: IdGenerator,
@@ -137,12 +192,17 @@ pub struct VulkanBackend {
     pub(crate) pools: Mutex<DescriptorPools>,
     pub(crate) pipelines: Mutex<PipelineCache>,
     pub(crate) samplers: Mutex<SamplerCache>,
+    /// Set the first time the device is detected as lost. Nothing is submitted afterwards.
+    pub(crate) device_lost: OnceLock<SubmitError>,
+    pub(crate) capture: FrameCapture,
 }
 
 pub(crate) struct VkDebug {
     pub instance: ash::ext::debug_utils::Instance,
     pub device: ash::ext::debug_utils::Device,
     pub messenger: vk::DebugUtilsMessengerEXT,
+    /// Pointed to by the messenger, so it must be destroyed after it.
+    pub state: Box<DebugMessengerState>,
 }
 
 #[derive(Default)]
@@ -169,6 +229,41 @@ struct PhysicalDeviceQuery {
     // pub rt_props: vk::PhysicalDeviceRayTracingPipelinePropertiesKHR<'static>,
     // pub mesh_shading_properties: vk::PhysicalDeviceMeshShaderPropertiesEXT<'static>,
     pub features: vk::PhysicalDeviceFeatures,
+    /// Sparse images can be created and bound on the transfer queue.
+    pub sparse_residency: bool,
+    /// Samplers can use min/max reduction modes.
+    pub sampler_filter_minmax: bool,
+    /// `VK_EXT_custom_border_color` is supported, including colors without a format.
+    pub custom_border_color: bool,
+    /// `VK_EXT_conditional_rendering` is supported, including inheritance into secondary command
+    /// buffers.
+    pub conditional_rendering: bool,
+    /// `VK_KHR_ray_query` is supported, so shaders in any stage can trace rays inline.
+    pub ray_query: bool,
+    /// `VK_EXT_memory_budget` is supported, so heap budgets can be queried.
+    pub memory_budget: bool,
+    /// The maximum number of descriptors that can be pushed. `None` if `VK_KHR_push_descriptor`
+    /// is not supported.
+    pub max_push_descriptors: Option<u32>,
+}
+
+impl PhysicalDeviceQuery {
+    fn graphics_features(&self) -> GraphicsFeatures {
+        GraphicsFeatures {
+            mesh_shading: true,
+            ray_tracing: true,
+            sparse_residency: self.sparse_residency,
+            sampler_reduction: self.sampler_filter_minmax,
+            custom_border_color: self.custom_border_color,
+            conditional_rendering: self.conditional_rendering,
+            ray_query: self.ray_query,
+            memory_budget: self.memory_budget,
+            wide_lines: self.features.wide_lines == vk::TRUE,
+            depth_bias_clamp: self.features.depth_bias_clamp == vk::TRUE,
+            dual_source_blend: self.features.dual_src_blend == vk::TRUE,
+            multiview: true,
+        }
+    }
 }
 
 pub struct PhysicalDeviceProperties {
@@ -178,7 +273,15 @@ pub struct PhysicalDeviceProperties {
     pub max_preferred_task_work_group_invocations: u32,
     pub min_acceleration_structure_scratch_offset_alignment: u32,
     pub shader_group_base_alignment: u32,
+    pub max_shader_group_stride: u32,
+    pub uniform_texel_buffer_offset_alignment: u64,
+    pub uniform_texel_buffer_offset_single_texel_alignment: bool,
+    pub storage_texel_buffer_offset_alignment: u64,
+    pub storage_texel_buffer_offset_single_texel_alignment: bool,
     pub limits: vk::PhysicalDeviceLimits,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub pipeline_cache_uuid: [u8; vk::UUID_SIZE],
 }
 
 impl Backend for VulkanBackend {
@@ -196,19 +299,132 @@ impl Backend for VulkanBackend {
     type BottomLevelAccelerationStructure = BottomLevelAccelerationStructure;
     type TopLevelAccelerationStructure = TopLevelAccelerationStructure;
     type Job = Job;
+    type CommandCache = CommandCache;
+    type DrawIndirect = DrawIndirect;
     type DrawIndexedIndirect = DrawIndexedIndirect;
     type DispatchIndirect = DispatchIndirect;
 
     #[inline(always)]
+    unsafe fn enumerate_adapters() -> Vec<AdapterInfo> {
+        let entry = match ash::Entry::load() {
+            Ok(entry) => entry,
+            Err(_) => return Vec::default(),
+        };
+
+        // No surface extensions are needed, so presentation support isn't checked
+        let app_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_3);
+        let instance = match entry.create_instance(
+            &vk::InstanceCreateInfo::default().application_info(&app_info),
+            None,
+        ) {
+            Ok(instance) => instance,
+            Err(_) => return Vec::default(),
+        };
+
+        let adapters = query_physical_devices(&instance, None, &required_device_extensions(false))
+            .into_iter()
+            .map(|(info, _)| info)
+            .collect();
+        instance.destroy_instance(None);
+        adapters
+    }
+
     unsafe fn properties(&self) -> &GraphicsProperties {
         &self.graphics_properties
     }
 
+    unsafe fn save_pipeline_cache(&self) -> Result<(), PipelineCacheSaveError> {
+        self.pipelines.lock().unwrap().save(&self.device)
+    }
+
+    unsafe fn precompile_graphics_pipelines(
+        &self,
+        pipelines: &[&Self::GraphicsPipeline],
+        render_pass: &RenderPassDescriptor<Self>,
+    ) {
+        let render_pass = self.render_passes.get(&self.device, render_pass);
+        self.pipelines.lock().unwrap().precompile(
+            &self.device,
+            self.debug.as_ref().map(|utils| &utils.device),
+            pipelines,
+            render_pass,
+        );
+    }
+
+    #[inline(always)]
+    unsafe fn pending_pipeline_compiles(&self) -> usize {
+        self.pipelines.lock().unwrap().pending()
+    }
+
+    #[inline(always)]
+    unsafe fn memory_stats(&self) -> MemoryStats {
+        self.memory.stats()
+    }
+
+    #[inline(always)]
+    unsafe fn memory_allocations(&self) -> Vec<MemoryAllocationInfo> {
+        self.memory.allocations()
+    }
+
+    #[inline(always)]
+    unsafe fn memory_budget(&self) -> MemoryBudget {
+        self.budgets.budget()
+    }
+
+    #[inline(always)]
+    unsafe fn on_memory_pressure(&self, threshold: f32, callback: MemoryPressureCallback) {
+        self.budgets.on_pressure(threshold, callback);
+    }
+
+    #[inline(always)]
+    unsafe fn collect_garbage(&self) {
+        self.collect_garbage_inner(true);
+    }
+
+    #[inline(always)]
+    unsafe fn default_anisotropy(&self) -> Option<AnisotropyLevel> {
+        self.samplers.lock().unwrap().default_anisotropy
+    }
+
+    #[inline(always)]
+    unsafe fn set_default_anisotropy(&self, level: Option<AnisotropyLevel>) {
+        self.samplers.lock().unwrap().default_anisotropy = level;
+    }
+
+    #[inline(always)]
+    unsafe fn trigger_capture(&self) {
+        self.capture.trigger();
+    }
+
+    #[inline(always)]
+    unsafe fn begin_capture(&self) {
+        self.capture.begin();
+    }
+
+    #[inline(always)]
+    unsafe fn end_capture(&self) {
+        self.capture.end();
+    }
+
+    unsafe fn pending_garbage(&self) -> GarbageReport {
+        let target = TimelineValues {
+            main: self.main.read().unwrap().target_timeline_value(),
+            transfer: self.transfer.read().unwrap().target_timeline_value(),
+            compute: self.compute.read().unwrap().target_timeline_value(),
+            present: self.present.read().unwrap().target_timeline_value(),
+        };
+        self.garbage.report(target, &self.memory)
+    }
+
     #[inline(always)]
     unsafe fn create_surface<W: HasWindowHandle + HasDisplayHandle>(
         &self,
         create_info: SurfaceCreateInfo<W>,
-    ) -> Result<Self::Surface, SurfaceCreateError> {
+    ) -> Result<(Self::Surface, SurfaceProperties), SurfaceCreateError> {
+        if self.headless {
+            return Err(SurfaceCreateError::Headless);
+        }
+
         Surface::new(
             self,
             create_info,
@@ -219,14 +435,7 @@ impl Backend for VulkanBackend {
 
     #[inline(always)]
     unsafe fn destroy_surface(&self, surface: &mut Self::Surface) {
-        // This shouldn't happen often, so we'll wait for all work to complete
-        self.device.device_wait_idle().unwrap();
-        surface.release(
-            self,
-            &self.image_ids,
-            &mut self.resource_state.write().unwrap(),
-        );
-        self.surface_loader.destroy_surface(surface.surface, None);
+        surface.retire(self, &self.resource_state.read().unwrap(), true);
     }
 
     #[inline(always)]
@@ -234,15 +443,7 @@ impl Backend for VulkanBackend {
         &self,
         surface: &mut Self::Surface,
         config: SurfaceConfiguration,
-    ) -> Result<(u32, u32), SurfaceUpdateError> {
-        self.device.device_wait_idle().unwrap();
-
-        // Signal that the views are about to be destroyed
-        for (_, _, view) in &surface.images {
-            self.framebuffers.view_destroyed(&self.device, *view);
-        }
-
-        // Then update the config
+    ) -> Result<SurfaceProperties, SurfaceUpdateError> {
         surface.update_config(
             self,
             config,
@@ -258,6 +459,14 @@ impl Backend for VulkanBackend {
             .get_physical_device_surface_capabilities(self.physical_device, id.surface)
             .unwrap();
 
+        let present_modes = self
+            .surface_loader
+            .get_physical_device_surface_present_modes(self.physical_device, id.surface)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(crate::util::from_vk_present_mode)
+            .collect();
+
         SurfaceCapabilities {
             min_size: (
                 capabilities.min_image_extent.width,
@@ -267,7 +476,7 @@ impl Backend for VulkanBackend {
                 capabilities.max_image_extent.width,
                 capabilities.max_image_extent.height,
             ),
-            present_modes: Vec::default(), // TODO
+            present_modes,
         }
     }
 
@@ -285,17 +494,19 @@ impl Backend for VulkanBackend {
         surface: &Self::Surface,
         image: &mut Self::SurfaceImage,
     ) -> Result<SurfacePresentSuccess, SurfacePresentFailure> {
-        surface.present(
-            image,
-            &self.swapchain_loader,
-            self.present.try_read().unwrap().queue,
-        )
+        // Presentation requires exclusive access to the queue, even when presenting to different
+        // surfaces
+        let present = self.present.write().unwrap();
+        surface.present(image, &self.swapchain_loader, present.queue)
     }
 
     #[inline(always)]
     unsafe fn destroy_surface_image(&self, image: &mut Self::SurfaceImage) {
+        // Images that were never rendered to must still be given back to the swapchain
         if !image.is_signaled() {
-            todo!()
+            if let Err(err) = image.release_unused(self) {
+                self.set_device_lost(Some("release_unused_surface_image"), err);
+            }
         }
     }
 
@@ -305,9 +516,11 @@ impl Backend for VulkanBackend {
         debug_name: Option<&str>,
         commands: Vec<Command<'_, Self>>,
         is_async: bool,
+        waits: &[&Self::Job],
     ) -> Job {
         puffin::profile_function!();
-        self.submit_commands_inner(queue, debug_name, commands, is_async, None)
+        let commands = util::mip_maps::expand_mip_maps(commands);
+        self.submit_commands_inner(queue, debug_name, &commands, None, is_async, None, waits)
     }
 
     unsafe fn submit_commands_async_compute(
@@ -320,36 +533,71 @@ impl Backend for VulkanBackend {
         puffin::profile_function!();
 
         // Submit to the primary queue first
-        let prim_job = self.submit_commands_inner(queue, debug_name, commands, false, None);
+        let commands = util::mip_maps::expand_mip_maps(commands);
+        let prim_job =
+            self.submit_commands_inner(queue, debug_name, &commands, None, false, None, &[]);
 
         // Then submit the async compute job
         let comp_debug_name = debug_name.map(|name| format!("{name} (Async Compute)"));
+        let compute_commands = util::mip_maps::expand_mip_maps(compute_commands);
         let comp_job = self.submit_commands_inner(
             QueueType::Compute,
             comp_debug_name.as_ref().map(|n| n.as_str()),
-            compute_commands,
+            &compute_commands,
+            None,
             false,
             Some(&prim_job),
+            &[],
         );
 
         (prim_job, comp_job)
     }
 
+    unsafe fn record_commands(
+        &self,
+        _queue: QueueType,
+        commands: &mut Vec<Command<'_, Self>>,
+    ) -> Self::CommandCache {
+        *commands = util::mip_maps::expand_mip_maps(std::mem::take(commands));
+        CommandCache::new(commands)
+    }
+
+    unsafe fn submit_recorded_commands(
+        &self,
+        queue: QueueType,
+        debug_name: Option<&str>,
+        commands: &[Command<'_, Self>],
+        cache: &Self::CommandCache,
+        waits: &[&Self::Job],
+    ) -> Self::Job {
+        puffin::profile_function!();
+        self.submit_commands_inner(queue, debug_name, commands, Some(cache), false, None, waits)
+    }
+
     unsafe fn wait_on(&self, job: &Self::Job, timeout: Option<std::time::Duration>) -> JobStatus {
-        let mut queue = match job.ty {
-            QueueType::Main => self.main.write().unwrap(),
-            QueueType::Transfer => self.transfer.write().unwrap(),
-            QueueType::Compute => self.compute.write().unwrap(),
-            QueueType::Present => self.present.write().unwrap(),
+        let queue = match job.ty {
+            QueueType::Main => &self.main,
+            QueueType::Transfer => &self.transfer,
+            QueueType::Compute => &self.compute,
+            QueueType::Present => &self.present,
         };
 
         // See if we've already synced to this value
-        if queue.cpu_sync_value() >= job.target_value {
-            return JobStatus::Complete;
+        let semaphore = {
+            let queue = queue.read().unwrap();
+            if queue.cpu_sync_value() >= job.target_value {
+                return JobStatus::Complete;
+            }
+            queue.semaphore()
+        };
+
+        if self.device_lost.get().is_some() {
+            return JobStatus::DeviceLost;
         }
 
-        // Otherwise we have to wait
-        let semaphore = [queue.semaphore()];
+        // Otherwise we have to wait. The queue isn't locked while waiting so other threads can
+        // keep submitting to it.
+        let semaphore = [semaphore];
         let value = [job.target_value];
         let wait = vk::SemaphoreWaitInfo::default()
             .semaphores(&semaphore)
@@ -358,38 +606,112 @@ impl Backend for VulkanBackend {
         match self.device.wait_semaphores(
             &wait,
             match timeout {
-                Some(timeout) => timeout.as_millis() as u64,
+                Some(timeout) => timeout.as_nanos().min(u64::MAX as u128) as u64,
                 None => u64::MAX,
             },
         ) {
             Ok(_) => {
-                queue.set_cpu_sync_value(job.target_value);
+                self.job_completed(queue, job.target_value);
                 JobStatus::Complete
             }
+            Err(vk::Result::ERROR_DEVICE_LOST) => {
+                self.set_device_lost(job.debug_name.as_deref(), vk::Result::ERROR_DEVICE_LOST);
+                JobStatus::DeviceLost
+            }
             Err(_) => JobStatus::Running,
         }
     }
 
     unsafe fn poll_status(&self, job: &Self::Job) -> JobStatus {
         let queue = match job.ty {
-            QueueType::Main => self.main.read().unwrap(),
-            QueueType::Transfer => self.transfer.read().unwrap(),
-            QueueType::Compute => self.compute.read().unwrap(),
-            QueueType::Present => self.present.read().unwrap(),
+            QueueType::Main => &self.main,
+            QueueType::Transfer => &self.transfer,
+            QueueType::Compute => &self.compute,
+            QueueType::Present => &self.present,
         };
-        let semaphore = queue.semaphore();
-        if self.device.get_semaphore_counter_value(semaphore).unwrap() >= job.target_value {
-            JobStatus::Complete
-        } else {
-            JobStatus::Running
+
+        // See if we've already synced to this value
+        let semaphore = {
+            let queue = queue.read().unwrap();
+            if queue.cpu_sync_value() >= job.target_value {
+                return JobStatus::Complete;
+            }
+            queue.semaphore()
+        };
+
+        match self.device.get_semaphore_counter_value(semaphore) {
+            Ok(value) if value >= job.target_value => {
+                self.job_completed(queue, job.target_value);
+                JobStatus::Complete
+            }
+            Ok(_) if self.device_lost.get().is_some() => JobStatus::DeviceLost,
+            Ok(_) => JobStatus::Running,
+            Err(err) => {
+                self.set_device_lost(job.debug_name.as_deref(), err);
+                JobStatus::DeviceLost
+            }
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn device_lost(&self) -> Option<SubmitError> {
+        self.device_lost.get().cloned()
+    }
+
+    unsafe fn job_timestamps(&self, job: &Self::Job) -> Vec<(String, Duration)> {
+        let job_queries = match &job.queries {
+            Some(job_queries) => job_queries,
+            None => return Vec::default(),
+        };
+
+        if self.poll_status(job) != JobStatus::Complete {
+            return Vec::default();
+        }
+
+        // Timestamps are measured in ticks, each of which is `timestamp_period` nanoseconds
+        let period = self.properties.limits.timestamp_period as f64;
+        let queries = self.queries.lock().unwrap();
+        job_queries
+            .timestamps
+            .iter()
+            .map(|(label, begin, end)| {
+                let begin = queries.get_timestamp(&self.device, *begin);
+                let end = queries.get_timestamp(&self.device, *end);
+                let nanos = end.saturating_sub(begin) as f64 * period;
+                (label.clone(), Duration::from_nanos(nanos as u64))
+            })
+            .collect()
+    }
+
+    unsafe fn job_statistics(&self, job: &Self::Job) -> Vec<(String, PipelineStatistics)> {
+        let job_queries = match &job.queries {
+            Some(job_queries) => job_queries,
+            None => return Vec::default(),
+        };
+
+        if self.poll_status(job) != JobStatus::Complete {
+            return Vec::default();
         }
+
+        let queries = self.queries.lock().unwrap();
+        job_queries
+            .statistics
+            .iter()
+            .map(|(label, query)| (label.clone(), queries.get_statistics(&self.device, *query)))
+            .collect()
     }
 
     #[inline(always)]
     unsafe fn create_buffer(
         &self,
-        create_info: BufferCreateInfo,
+        mut create_info: BufferCreateInfo,
     ) -> Result<Self::Buffer, BufferCreateError> {
+        // Predicate buffers are still usable as regular buffers when conditional rendering is
+        // unsupported. Regions using them are just executed unconditionally.
+        if self.conditional_rendering_loader.is_none() {
+            create_info.buffer_usage &= !BufferUsage::CONDITIONAL_RENDERING;
+        }
+
         Buffer::new(
             &self.device,
             &self.queue_family_indices,
@@ -402,11 +724,42 @@ impl Backend for VulkanBackend {
         )
     }
 
+    #[inline(always)]
+    unsafe fn create_transient_buffer(
+        &self,
+        size: u64,
+        buffer_usage: BufferUsage,
+    ) -> Result<Self::Buffer, BufferCreateError> {
+        // NOTE: The allocator must be locked before the transient blocks to match the order used
+        // during garbage collection.
+        let mut allocator = self.allocator.lock().unwrap();
+        Buffer::new_transient(
+            &self.device,
+            &self.queue_family_indices,
+            self.garbage.sender(),
+            &self.buffer_ids,
+            &mut allocator,
+            &mut self.transient.lock().unwrap(),
+            &self.properties,
+            size,
+            buffer_usage,
+        )
+    }
+
     #[inline(always)]
     unsafe fn create_texture(
         &self,
         create_info: TextureCreateInfo,
     ) -> Result<Self::Texture, TextureCreateError> {
+        // Only 2D sparse images are supported
+        if create_info.sparse
+            && (!self.graphics_properties.features.sparse_residency
+                || create_info.ty != TextureType::Type2D
+                || create_info.sample_count != MultiSamples::Count1)
+        {
+            return Err(TextureCreateError::SparseNotSupported);
+        }
+
         Texture::new(
             &self.device,
             &self.image_ids,
@@ -453,6 +806,7 @@ impl Backend for VulkanBackend {
     ) -> Result<Self::GraphicsPipeline, GraphicsPipelineCreateError> {
         Ok(GraphicsPipeline::new(
             &self.device,
+            self.debug.as_ref().map(|utils| &utils.device),
             self.garbage.sender(),
             create_info,
         ))
@@ -467,6 +821,7 @@ impl Backend for VulkanBackend {
             &self.device,
             self.debug.as_ref().map(|utils| &utils.device),
             self.garbage.sender(),
+            &self.pipelines,
             create_info,
         )
     }
@@ -514,7 +869,12 @@ impl Backend for VulkanBackend {
         &self,
         create_info: DescriptorSetLayoutCreateInfo,
     ) -> Result<Self::DescriptorSetLayout, DescriptorSetLayoutCreateError> {
-        DescriptorSetLayout::new(&self.device, &mut self.pools.lock().unwrap(), create_info)
+        DescriptorSetLayout::new(
+            &self.device,
+            &mut self.pools.lock().unwrap(),
+            self.debug.as_ref().map(|utils| &utils.device),
+            create_info,
+        )
     }
 
     unsafe fn destroy_buffer(&self, _buffer: &mut Self::Buffer) {
@@ -589,6 +949,11 @@ impl Backend for VulkanBackend {
         id.scratch_size()
     }
 
+    #[inline(always)]
+    unsafe fn tlas_update_scratch_size(&self, id: &Self::TopLevelAccelerationStructure) -> u64 {
+        id.update_scratch_size()
+    }
+
     #[inline(always)]
     unsafe fn tlas_build_flags(
         &self,
@@ -615,6 +980,16 @@ impl Backend for VulkanBackend {
         id.compacted_size(self)
     }
 
+    unsafe fn blas_poll_compacted_sizes(
+        &self,
+        ids: &[&Self::BottomLevelAccelerationStructure],
+    ) -> Vec<Option<u64>> {
+        let mut queries = self.queries.lock().unwrap();
+        ids.iter()
+            .map(|id| id.poll_compacted_size(&self.device, &mut queries))
+            .collect()
+    }
+
     #[inline(always)]
     unsafe fn blas_build_flags(
         &self,
@@ -686,6 +1061,9 @@ impl VulkanBackend {
         create_info: VulkanBackendCreateInfo<D>,
     ) -> Result<Self, VulkanBackendCreateError> {
         let app_name = CString::new(create_info.app_name).unwrap();
+        let display_handle = create_info
+            .display_handle
+            .map(|display| display.display_handle().unwrap().as_raw());
         let vk_version = vk::API_VERSION_1_3;
 
         // Get required instance layers
@@ -701,49 +1079,45 @@ impl VulkanBackend {
             Vec::default()
         };
 
+        // RenderDoc must be loaded before the instance is created so it can hook it
+        let capture = FrameCapture::load();
+
+        // Dynamically load Vulkan
+        let entry = unsafe { ash::Entry::load()? };
+
         // Get required instance extensions
         let instance_extensions = {
-            let mut extensions = ash_window::enumerate_required_extensions(
-                create_info
-                    .display_handle
-                    .display_handle()
-                    .unwrap()
-                    .as_raw(),
-            )?
-            .iter()
-            .map(|ext| unsafe { CStr::from_ptr(*ext) })
-            .collect::<Vec<_>>();
+            let mut extensions = match display_handle {
+                Some(display_handle) => ash_window::enumerate_required_extensions(display_handle)?
+                    .iter()
+                    .map(|ext| unsafe { CStr::from_ptr(*ext) })
+                    .collect::<Vec<_>>(),
+                None => Vec::default(),
+            };
 
             if create_info.debug {
                 extensions.push(ash::ext::debug_utils::NAME);
+                extensions.push(ash::ext::validation_features::NAME);
             }
 
-            extensions
-                .into_iter()
-                .map(|r| r.as_ptr())
-                .collect::<Vec<_>>()
-        };
+            // Needed for HDR surface color spaces, so it's only enabled when available
+            let supported = unsafe { entry.enumerate_instance_extension_properties(None)? };
+            if display_handle.is_some()
+                && supported.iter().any(|ext| {
+                    ext.extension_name_as_c_str() == Ok(ash::ext::swapchain_colorspace::NAME)
+                })
+            {
+                extensions.push(ash::ext::swapchain_colorspace::NAME);
+            }
 
-        // Get required device extensions
-        let device_extensions = {
-            let extensions = vec![
-                ash::khr::swapchain::NAME,
-                ash::ext::mesh_shader::NAME,
-                ash::khr::acceleration_structure::NAME,
-                ash::khr::ray_tracing_pipeline::NAME,
-                ash::khr::deferred_host_operations::NAME,
-                ash::ext::pipeline_library_group_handles::NAME,
-                c"VK_KHR_pipeline_library",
-                c"VK_KHR_ray_tracing_maintenance1",
-            ];
             extensions
                 .into_iter()
                 .map(|r| r.as_ptr())
                 .collect::<Vec<_>>()
         };
 
-        // Dynamically load Vulkan
-        let entry = unsafe { ash::Entry::load()? };
+        // Get required device extensions
+        let device_extensions = required_device_extensions(display_handle.is_some());
 
         // Create the instance
         let app_info = vk::ApplicationInfo::default()
@@ -753,39 +1127,63 @@ impl VulkanBackend {
             .engine_version(0)
             .api_version(vk_version);
 
-        let instance_create_info = vk::InstanceCreateInfo::default()
+        // Shader printf is routed through the debug messenger. `VK_KHR_shader_non_semantic_info`
+        // is core in Vulkan 1.3, so only the validation feature needs enabling.
+        let enabled_validation_features = [vk::ValidationFeatureEnableEXT::DEBUG_PRINTF];
+        let mut validation_features = vk::ValidationFeaturesEXT::default()
+            .enabled_validation_features(&enabled_validation_features);
+
+        let mut instance_create_info = vk::InstanceCreateInfo::default()
             .application_info(&app_info)
             .enabled_layer_names(&layer_names)
             .enabled_extension_names(&instance_extensions);
 
+        if create_info.debug {
+            instance_create_info = instance_create_info.push_next(&mut validation_features);
+        }
+
         let instance = unsafe { entry.create_instance(&instance_create_info, None)? };
 
         // Closure to check for presentation support. This depends on the windowing system being
         // used
-        let display_handle = create_info.display_handle.display_handle().unwrap();
-        let presentation_support = match display_handle.as_raw() {
-            raw_window_handle::RawDisplayHandle::Windows(_) => {
-                let surface_instance = ash::khr::win32_surface::Instance::new(&entry, &instance);
-                move |physical_device, queue_family_index| unsafe {
-                    surface_instance.get_physical_device_win32_presentation_support(
-                        physical_device,
-                        queue_family_index,
-                    )
-                }
+        let presentation_support = match display_handle {
+            Some(display_handle) => {
+                Some(unsafe { presentation_support(&entry, &instance, display_handle)? })
             }
-            _ => todo!("support for other window systems"),
+            None => None,
         };
 
         // Create a surface to check for presentation compatibility
         let surface_loader = ash::khr::surface::Instance::new(&entry, &instance);
 
         // Query for a physical device
-        let pd_query = unsafe {
-            match pick_physical_device(&instance, presentation_support, &device_extensions) {
-                Some(pd) => pd,
-                None => return Err(VulkanBackendCreateError::NoDevice),
+        let (adapter, pd_query) = {
+            let mut adapters = unsafe {
+                query_physical_devices(
+                    &instance,
+                    presentation_support.as_deref(),
+                    &device_extensions,
+                )
+            };
+            let infos: Vec<_> = adapters.iter().map(|(info, _)| info.clone()).collect();
+
+            match create_info.adapter_preference.select(&infos) {
+                Some(idx) => {
+                    let (info, query) = adapters.swap_remove(idx);
+                    (info, query.unwrap())
+                }
+                None => match create_info.adapter_preference {
+                    AdapterPreference::ByIndex(_) | AdapterPreference::ByName(_) => {
+                        return Err(VulkanBackendCreateError::AdapterUnavailable {
+                            requested: create_info.adapter_preference,
+                            available: infos,
+                        })
+                    }
+                    _ => return Err(VulkanBackendCreateError::NoDevice),
+                },
             }
         };
+        ard_log::info!("using adapter {adapter}");
 
         // Queue requests
         let mut priorities = Vec::with_capacity(pd_query.queue_family_indices.unique.len());
@@ -803,9 +1201,14 @@ impl VulkanBackend {
                 cur_priorities.push(0.5);
             }
 
+            // Headless backends submit present work to the main queue
             if pd_query.queue_family_indices.present == *q {
-                queue_indices.2 = cur_priorities.len();
-                cur_priorities.push(1.0);
+                if display_handle.is_some() {
+                    queue_indices.2 = cur_priorities.len();
+                    cur_priorities.push(1.0);
+                } else {
+                    queue_indices.2 = queue_indices.0;
+                }
             }
 
             if pd_query.queue_family_indices.compute == *q {
@@ -836,9 +1239,20 @@ impl VulkanBackend {
             .depth_clamp(true)
             .sample_rate_shading(true)
             .sampler_anisotropy(true)
+            .image_cube_array(true)
+            .pipeline_statistics_query(true)
             .shader_int64(true)
             .shader_int16(true)
-            .independent_blend(true);
+            .independent_blend(true)
+            .wide_lines(pd_query.features.wide_lines == vk::TRUE)
+            .depth_bias_clamp(pd_query.features.depth_bias_clamp == vk::TRUE)
+            .dual_src_blend(pd_query.features.dual_src_blend == vk::TRUE)
+            .sparse_binding(pd_query.sparse_residency)
+            .sparse_residency_image2_d(pd_query.sparse_residency)
+            .texture_compression_astc_ldr(
+                pd_query.features.texture_compression_astc_ldr == vk::TRUE,
+            )
+            .texture_compression_etc2(pd_query.features.texture_compression_etc2 == vk::TRUE);
 
         let mut features11 = vk::PhysicalDeviceVulkan11Features::default()
             .multiview(true)
@@ -848,9 +1262,16 @@ impl VulkanBackend {
             .timeline_semaphore(true)
             .buffer_device_address(true)
             .runtime_descriptor_array(true)
+            .descriptor_binding_partially_bound(true)
+            .descriptor_binding_variable_descriptor_count(true)
+            .descriptor_binding_update_unused_while_pending(true)
+            .descriptor_binding_sampled_image_update_after_bind(true)
+            .descriptor_binding_storage_image_update_after_bind(true)
+            .descriptor_binding_storage_buffer_update_after_bind(true)
             .draw_indirect_count(true)
             .uniform_buffer_standard_layout(true)
-            .host_query_reset(true);
+            .host_query_reset(true)
+            .sampler_filter_minmax(pd_query.sampler_filter_minmax);
 
         let mut features13 = vk::PhysicalDeviceVulkan13Features::default()
             .synchronization2(true)
@@ -859,7 +1280,8 @@ impl VulkanBackend {
         let mut ms_features = vk::PhysicalDeviceMeshShaderFeaturesEXT::default()
             .mesh_shader(true)
             .task_shader(true)
-            .multiview_mesh_shader(true);
+            .multiview_mesh_shader(true)
+            .mesh_shader_queries(true);
 
         let mut rt_features = vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::default()
             .ray_tracing_pipeline(true)
@@ -872,6 +1294,16 @@ impl VulkanBackend {
         let mut pl_features = vk::PhysicalDevicePipelineLibraryGroupHandlesFeaturesEXT::default()
             .pipeline_library_group_handles(true);
 
+        let mut cbc_features = vk::PhysicalDeviceCustomBorderColorFeaturesEXT::default()
+            .custom_border_colors(true)
+            .custom_border_color_without_format(true);
+
+        let mut cr_features = vk::PhysicalDeviceConditionalRenderingFeaturesEXT::default()
+            .conditional_rendering(true)
+            .inherited_conditional_rendering(true);
+
+        let mut rq_features = vk::PhysicalDeviceRayQueryFeaturesKHR::default().ray_query(true);
+
         let mut features2 = vk::PhysicalDeviceFeatures2::default()
             .features(features)
             .push_next(&mut features11)
@@ -882,6 +1314,27 @@ impl VulkanBackend {
             .push_next(&mut as_features)
             .push_next(&mut pl_features);
 
+        // Enable optional extensions the device supports
+        let mut device_extensions = device_extensions;
+        if pd_query.max_push_descriptors.is_some() {
+            device_extensions.push(ash::khr::push_descriptor::NAME.as_ptr());
+        }
+        if pd_query.custom_border_color {
+            device_extensions.push(ash::ext::custom_border_color::NAME.as_ptr());
+            features2 = features2.push_next(&mut cbc_features);
+        }
+        if pd_query.conditional_rendering {
+            device_extensions.push(ash::ext::conditional_rendering::NAME.as_ptr());
+            features2 = features2.push_next(&mut cr_features);
+        }
+        if pd_query.ray_query {
+            device_extensions.push(ash::khr::ray_query::NAME.as_ptr());
+            features2 = features2.push_next(&mut rq_features);
+        }
+        if pd_query.memory_budget {
+            device_extensions.push(ash::ext::memory_budget::NAME.as_ptr());
+        }
+
         let device_create_info = vk::DeviceCreateInfo::default()
             .queue_create_infos(&queue_infos)
             .enabled_extension_names(&device_extensions)
@@ -894,7 +1347,14 @@ impl VulkanBackend {
         let swapchain_loader = ash::khr::swapchain::Device::new(&instance, &device);
 
         // Create the memory allocator
-        let allocator = ManuallyDrop::new(Mutex::new(
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(pd_query.device) };
+        let memory = Arc::new(MemoryTracker::new(
+            &memory_properties,
+            create_info.device_memory_budget,
+        ));
+        let budgets = MemoryBudgets::new(pd_query.memory_budget);
+        let allocator = ManuallyDrop::new(Mutex::new(TrackedAllocator::new(
             Allocator::new(&AllocatorCreateDesc {
                 instance: instance.clone(),
                 device: device.clone(),
@@ -911,19 +1371,31 @@ impl VulkanBackend {
                 allocation_sizes: Default::default(),
             })
             .expect("unable to create GPU memory allocator"),
-        ));
+            memory.clone(),
+        )));
 
         // Device loaders
         let mesh_shading_loader = ash::ext::mesh_shader::Device::new(&instance, &device);
         let rt_loader = ash::khr::ray_tracing_pipeline::Device::new(&instance, &device);
         let as_loader = ash::khr::acceleration_structure::Device::new(&instance, &device);
+        let push_descriptor_loader = pd_query
+            .max_push_descriptors
+            .map(|_| ash::khr::push_descriptor::Device::new(&instance, &device));
+        let conditional_rendering_loader = if pd_query.conditional_rendering {
+            Some(ash::ext::conditional_rendering::Device::new(
+                &instance, &device,
+            ))
+        } else {
+            None
+        };
 
         // Create debugging utilities if requested
         let debug = if create_info.debug {
+            let state = Box::new(DebugMessengerState::new(create_info.debug_config));
             let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::default()
+                // Shader printf output is reported as info, so it's always requested
                 .message_severity(
-                    vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-                        | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                    state.config().min_severity.to_vk_messenger_flags()
                         | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
                 )
                 .message_type(
@@ -931,7 +1403,8 @@ impl VulkanBackend {
                         | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
                         | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
                 )
-                .pfn_user_callback(Some(vulkan_debug_callback));
+                .pfn_user_callback(Some(vulkan_debug_callback))
+                .user_data(&*state as *const DebugMessengerState as *mut std::ffi::c_void);
             let debug_utils_loader = ash::ext::debug_utils::Instance::new(&entry, &instance);
             let debug_messenger =
                 unsafe { debug_utils_loader.create_debug_utils_messenger(&debug_info, None)? };
@@ -940,6 +1413,7 @@ impl VulkanBackend {
                 instance: debug_utils_loader,
                 device: ash::ext::debug_utils::Device::new(&instance, &device),
                 messenger: debug_messenger,
+                state,
             })
         } else {
             None
@@ -995,7 +1469,20 @@ impl VulkanBackend {
             )?
         };
 
+        let pipelines = unsafe {
+            PipelineCache::new(
+                &device,
+                PipelineCacheHeader {
+                    vendor_id: pd_query.properties.vendor_id,
+                    device_id: pd_query.properties.device_id,
+                    uuid: pd_query.properties.pipeline_cache_uuid,
+                },
+                create_info.pipeline_cache_path,
+            )?
+        };
+
         let graphics_properties = GraphicsProperties {
+            features: pd_query.graphics_features(),
             mesh_shading: MeshShadingProperties {
                 preferred_mesh_work_group_invocations: pd_query
                     .properties
@@ -1004,8 +1491,41 @@ impl VulkanBackend {
                     .properties
                     .max_preferred_task_work_group_invocations,
             },
+            sparse: if pd_query.sparse_residency {
+                SparseProperties {
+                    page_size: SPARSE_PAGE_SIZE,
+                    granularity: unsafe { sparse_granularity(&instance, pd_query.device) },
+                }
+            } else {
+                SparseProperties::default()
+            },
+            ray_tracing: RayTracingProperties {
+                min_scratch_offset_alignment: pd_query
+                    .properties
+                    .min_acceleration_structure_scratch_offset_alignment
+                    as u64,
+            },
+            samples: SampleCountProperties {
+                color: crate::util::from_vk_sample_counts(
+                    pd_query.properties.limits.framebuffer_color_sample_counts,
+                ),
+                depth: crate::util::from_vk_sample_counts(
+                    pd_query.properties.limits.framebuffer_depth_sample_counts,
+                ),
+                stencil: crate::util::from_vk_sample_counts(
+                    pd_query.properties.limits.framebuffer_stencil_sample_counts,
+                ),
+                storage: crate::util::from_vk_sample_counts(
+                    pd_query.properties.limits.storage_image_sample_counts,
+                ),
+            },
+            validation: create_info.debug,
+            formats: unsafe { format_support(&instance, pd_query.device, &pd_query.features) },
+            adapter,
+            limits: device_limits(&pd_query.properties),
         };
 
+        let max_sampler_anisotropy = pd_query.properties.limits.max_sampler_anisotropy;
         let ctx = Self {
             entry,
             instance,
@@ -1018,50 +1538,98 @@ impl VulkanBackend {
             device,
             surface_loader,
             swapchain_loader,
+            headless: display_handle.is_none(),
             mesh_shading_loader,
             rt_loader,
             as_loader,
+            push_descriptor_loader,
+            conditional_rendering_loader,
             main: ShardedLock::new(main),
             transfer: ShardedLock::new(transfer),
             present: ShardedLock::new(present),
             compute: ShardedLock::new(compute),
             allocator,
+            memory,
+            budgets,
             render_passes: RenderPassCache::default(),
             framebuffers: FramebufferCache::default(),
             garbage: GarbageCollector::new(),
             queries: Mutex::new(Queries::default()),
+            events: Mutex::new(Events::default()),
+            transient: Mutex::new(TransientBuffers::default()),
             resource_state: ShardedLock::new(GlobalResourceUsage::default()),
-            pools: Mutex::new(DescriptorPools::default()),
-            pipelines: Mutex::new(PipelineCache::default()),
-            samplers: Mutex::new(SamplerCache::default()),
+            pools: Mutex::new(DescriptorPools::new(pd_query.max_push_descriptors)),
+            pipelines: Mutex::new(pipelines),
+            samplers: Mutex::new(SamplerCache::new(max_sampler_anisotropy)),
             cmd_sort: Mutex::new(CommandSorting::default()),
             buffer_ids: IdGenerator::default(),
             image_ids: IdGenerator::default(),
             set_ids: IdGenerator::default(),
+            device_lost: OnceLock::new(),
+            capture,
+        };
+
+        // Budgets are otherwise empty until the first submission
+        unsafe {
+            ctx.budgets
+                .refresh(&ctx.instance, ctx.physical_device, &ctx.memory)
         };
 
         Ok(ctx)
     }
 
+    #[allow(clippy::too_many_arguments)]
     unsafe fn submit_commands_inner<'a>(
         &self,
         queue: QueueType,
         debug_name: Option<&str>,
-        commands: Vec<Command<'_, Self>>,
+        commands: &[Command<'_, Self>],
+        cache: Option<&CommandCache>,
         is_async: bool,
         async_with: Option<&Job>,
+        explicit_waits: &[&Job],
     ) -> Job {
+        // Nothing can execute once the device is lost
+        if self.device_lost.get().is_some() {
+            return Self::lost_job(queue, debug_name);
+        }
+
+        if let Some(debug) = &self.debug {
+            debug.state.push_breadcrumb(debug_name);
+        }
+
+        self.budgets
+            .refresh(&self.instance, self.physical_device, &self.memory);
+
+        // Pushed descriptors are written before anything is locked since writing them may need
+        // to lock the resource state
+        let pushed = PushedSets::new(self, commands);
+
         // Lock down all neccesary objects
         let mut resc_state = self.resource_state.write().unwrap();
         let mut allocator = self.allocator.lock().unwrap();
         let mut pools = self.pools.lock().unwrap();
         let mut pipelines = self.pipelines.lock().unwrap();
+        pipelines.receive_compiled();
         let mut main = self.main.write().unwrap();
         let mut transfer = self.transfer.write().unwrap();
         let mut compute = self.compute.write().unwrap();
         let mut present = self.present.write().unwrap();
-        let mut sorting = self.cmd_sort.lock().unwrap();
+        let mut cmd_sort = self.cmd_sort.lock().unwrap();
+        let mut sorted = cache
+            .and_then(|cache| cache.sorted.as_ref())
+            .map(|sorted| sorted.lock().unwrap());
         let mut queries = self.queries.lock().unwrap();
+        let mut events = self.events.lock().unwrap();
+        let mut transient = self.transient.lock().unwrap();
+
+        // Sparse binds are performed before the command buffer and signal their own timeline value
+        let has_sparse_binds = commands.iter().any(|command| {
+            matches!(
+                command,
+                Command::BindSparsePages { .. } | Command::UnbindSparsePages { .. }
+            )
+        });
 
         // State
         let next_target_value = match queue {
@@ -1071,18 +1639,26 @@ impl VulkanBackend {
             QueueType::Present => &present,
         }
         .target_timeline_value()
-            + 1;
+            + 1
+            + has_sparse_binds as u64;
 
         let mut semaphore_tracker = SemaphoreTracker::default();
 
         // Acquire a command buffer from the queue
-        let cb = match queue {
+        let cb = match match queue {
             QueueType::Main => &mut main,
             QueueType::Transfer => &mut transfer,
             QueueType::Compute => &mut compute,
             QueueType::Present => &mut present,
         }
-        .allocate_command_buffer(&self.device, self.debug.as_ref().map(|utils| &utils.device));
+        .allocate_command_buffer(&self.device, self.debug.as_ref().map(|utils| &utils.device))
+        {
+            Ok(cb) => cb,
+            Err(err) => {
+                self.set_device_lost(debug_name, err);
+                return Self::lost_job(queue, debug_name);
+            }
+        };
         let begin_info = vk::CommandBufferBeginInfo::default()
             .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
         self.device.begin_command_buffer(cb, &begin_info).unwrap();
@@ -1096,24 +1672,117 @@ impl VulkanBackend {
             }
         }
 
-        // Generate a DAG for the submitted commands
-        let mut sort_info = CommandSortingInfo {
-            global: &mut resc_state,
-            semaphores: &mut semaphore_tracker,
-            commands: &commands,
-            queue_families: &self.queue_family_indices,
-            queue,
-            timeline_value: next_target_value,
-            wait_queues: [None; 4],
-            is_async,
+        // Recorded commands reuse the DAG from their last submission if the resources they use
+        // are in the same state as they were then
+        let replay = match sorted.as_ref().and_then(|sorted| sorted.usage.as_ref()) {
+            Some(usage) => resc_state.journal_matches(usage, queue, is_async),
+            None => false,
+        };
+
+        let mut wait_queues = [None; 4];
+        let sorting = match sorted.as_deref_mut() {
+            Some(sorted) if replay => {
+                let usage = sorted.usage.as_ref().unwrap();
+                resc_state.replay_journal(usage, next_target_value, &mut wait_queues);
+                &sorted.sorting
+            }
+            sorted => {
+                // Generate a DAG for the submitted commands
+                if sorted.is_some() {
+                    resc_state.begin_journal();
+                }
+
+                let mut sort_info = CommandSortingInfo {
+                    global: &mut resc_state,
+                    semaphores: &mut semaphore_tracker,
+                    commands,
+                    queue_families: &self.queue_family_indices,
+                    queue,
+                    timeline_value: next_target_value,
+                    wait_queues: [None; 4],
+                    is_async,
+                    validate: self.debug.is_some(),
+                    pushed: &pushed,
+                    conditional_rendering: self.conditional_rendering_loader.is_some(),
+                };
+
+                match sorted {
+                    Some(sorted) => {
+                        sorted.sorting.create_dag(&mut sort_info);
+                        wait_queues = sort_info.wait_queues;
+                        let usage = resc_state.end_journal(queue, is_async, next_target_value);
+
+                        // Semaphores registered while sorting, like those of surface images,
+                        // are different every submission
+                        sorted.usage = semaphore_tracker.is_empty().then_some(usage);
+                        &sorted.sorting
+                    }
+                    None => {
+                        cmd_sort.create_dag(&mut sort_info);
+                        wait_queues = sort_info.wait_queues;
+                        &*cmd_sort
+                    }
+                }
+            }
         };
-        sorting.create_dag(&mut sort_info);
+
+        // Allocate queries for timestamp and statistics regions
+        let mut region_queries = FxHashMap::default();
+        let mut timestamps = Vec::default();
+        let mut statistics = Vec::default();
+        let mut open_timestamps = Vec::default();
+        let mut open_statistics = None;
+        for (i, command) in commands.iter().enumerate() {
+            match command {
+                Command::BeginTimestamp(label) => {
+                    let query = queries.allocate_timestamp(&self.device);
+                    region_queries.insert(i, query);
+                    open_timestamps.push((label.to_string(), query));
+                }
+                Command::EndTimestamp => {
+                    let query = queries.allocate_timestamp(&self.device);
+                    region_queries.insert(i, query);
+                    let (label, begin) = open_timestamps.pop().unwrap();
+                    timestamps.push((label, begin, query));
+                }
+                Command::BeginStatistics(label) => {
+                    let query = queries.allocate_statistics(&self.device);
+                    region_queries.insert(i, query);
+                    open_statistics = Some((label.to_string(), query));
+                }
+                Command::EndStatistics => {
+                    let (label, query) = open_statistics.take().unwrap();
+                    region_queries.insert(i, query);
+                    statistics.push((label, query));
+                }
+                Command::WriteBlasCompactSize(blas) => {
+                    if let Some(query) = blas.internal().compact_size_query {
+                        queries.invalidate_accel_struct_compact(query);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Every split barrier that replaced a regular barrier needs an event
+        let split_events: Vec<_> = sorting
+            .split_barriers()
+            .iter()
+            .map(|split| {
+                if split.is_empty() {
+                    vk::Event::null()
+                } else {
+                    events.allocate(&self.device)
+                }
+            })
+            .collect();
 
         // Execute all commands
         sorting.execute_commands(
             &self.device,
             cb,
-            &commands,
+            &split_events,
+            commands,
             |cb, device, idx, commands| unsafe {
                 VulkanBackend::execute_command(
                     cb,
@@ -1121,20 +1790,23 @@ impl VulkanBackend {
                     &self.mesh_shading_loader,
                     &self.as_loader,
                     &self.rt_loader,
-                    &self.properties,
+                    self.conditional_rendering_loader.as_ref(),
+                    &mut main,
                     &queries,
+                    &region_queries,
                     idx,
                     commands,
                     &self.render_passes,
                     &self.framebuffers,
                     &mut pipelines,
+                    &pushed,
                     self.debug.as_ref(),
                 );
             },
         );
 
         // Grab detected semaphores
-        for (i, timeline_value) in sort_info.wait_queues.iter().enumerate() {
+        for (i, timeline_value) in wait_queues.iter().enumerate() {
             let timeline_value = match *timeline_value {
                 Some(value) => value,
                 None => continue,
@@ -1183,39 +1855,226 @@ impl VulkanBackend {
             }
         }
 
-        // Submit to the queue
-        if debug_name.is_some() {
-            if let Some(debug) = &self.debug {
-                debug.device.cmd_end_debug_utils_label(cb);
+        // Jobs the submission was explicitly asked to wait on. Submissions to the same queue are
+        // already ordered.
+        for job in explicit_waits {
+            if job.ty == queue {
+                continue;
             }
-        }
-
-        self.device.end_command_buffer(cb).unwrap();
 
-        match queue {
+            let semaphore = match job.ty {
+                QueueType::Main => main.semaphore(),
+                QueueType::Transfer => transfer.semaphore(),
+                QueueType::Compute => compute.semaphore(),
+                QueueType::Present => present.semaphore(),
+            };
+
+            semaphore_tracker.register_wait(
+                semaphore,
+                WaitInfo {
+                    value: Some(job.target_value),
+                    stage: vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                },
+            );
+        }
+
+        // Submit to the queue
+        if debug_name.is_some() {
+            if let Some(debug) = &self.debug {
+                debug.device.cmd_end_debug_utils_label(cb);
+            }
+        }
+
+        self.device.end_command_buffer(cb).unwrap();
+
+        // Bind sparse pages. The binds take over every wait so the command buffer only has to
+        // wait on the binds.
+        if has_sparse_binds {
+            let mut binds = SparseBinds::default();
+            for command in commands {
+                let (texture, regions, bind) = match command {
+                    Command::BindSparsePages { texture, regions } => (texture, regions, true),
+                    Command::UnbindSparsePages { texture, regions } => (texture, regions, false),
+                    _ => continue,
+                };
+
+                let mut pages = match &texture.internal().memory {
+                    TextureMemory::Sparse(pages) => pages.lock().unwrap(),
+                    TextureMemory::Dedicated(_) => unreachable!("texture is not sparse"),
+                };
+                for region in regions {
+                    if bind {
+                        pages.bind(&mut allocator, region, &mut binds);
+                    } else {
+                        pages.unbind(region, &mut binds);
+                    }
+                }
+            }
+
+            if let Err(err) =
+                transfer.bind_sparse(&self.device, &binds, semaphore_tracker.take_waits())
+            {
+                self.set_device_lost(debug_name, err);
+                return Self::lost_job(queue, debug_name);
+            }
+
+            if !binds.freed.is_empty() {
+                let _ = self
+                    .garbage
+                    .sender()
+                    .send(Garbage::SparsePages(binds.freed));
+            }
+        }
+
+        if let Err(err) = match queue {
             QueueType::Main => &mut main,
             QueueType::Transfer => &mut transfer,
             QueueType::Compute => &mut compute,
             QueueType::Present => &mut present,
         }
         .submit(&self.device, cb, semaphore_tracker)
-        .unwrap();
+        {
+            self.set_device_lost(debug_name, err);
+            return Self::lost_job(queue, debug_name);
+        }
+
+        // Events can be reused once the submission completes
+        let split_events: Vec<_> = split_events
+            .into_iter()
+            .filter(|event| *event != vk::Event::null())
+            .collect();
+        if !split_events.is_empty() {
+            let _ = self.garbage.sender().send(Garbage::Events(split_events));
+        }
+
+        // Perform garbage collection. If the device was lost after submission, the job will
+        // report it when waited on, and garbage is held onto until the context is dropped.
+        let current_values = main.current_timeline_value(&self.device).and_then(|main| {
+            Ok(TimelineValues {
+                main,
+                transfer: transfer.current_timeline_value(&self.device)?,
+                compute: compute.current_timeline_value(&self.device)?,
+                present: present.current_timeline_value(&self.device)?,
+            })
+        });
+
+        match current_values {
+            Ok(current_values) => {
+                let target_values = TimelineValues {
+                    main: main.target_timeline_value(),
+                    transfer: transfer.target_timeline_value(),
+                    compute: compute.target_timeline_value(),
+                    present: present.target_timeline_value(),
+                };
 
-        // Perform garbage collection
-        let current_values = TimelineValues {
-            main: main.current_timeline_value(&self.device),
-            transfer: transfer.current_timeline_value(&self.device),
-            compute: compute.current_timeline_value(&self.device),
+                self.garbage.cleanup(GarbageCleanupArgs {
+                    device: &self.device,
+                    as_loader: &self.as_loader,
+                    swapchain_loader: &self.swapchain_loader,
+                    surface_loader: &self.surface_loader,
+                    framebuffers: &self.framebuffers,
+                    buffer_ids: &self.buffer_ids,
+                    image_ids: &self.image_ids,
+                    set_ids: &self.set_ids,
+                    allocator: &mut allocator,
+                    pools: &mut pools,
+                    pipelines: &mut pipelines,
+                    global_usage: &mut resc_state,
+                    queries: &mut queries,
+                    events: &mut events,
+                    transient: &mut transient,
+                    current: current_values,
+                    target: target_values,
+                    override_ref_counter: false,
+                });
+            }
+            Err(err) => self.set_device_lost(debug_name, err),
+        }
+
+        Job {
+            ty: queue,
+            target_value: next_target_value,
+            queries: if timestamps.is_empty() && statistics.is_empty() {
+                None
+            } else {
+                Some(JobQueries {
+                    timestamps,
+                    statistics,
+                    on_drop: self.garbage.sender(),
+                })
+            },
+            debug_name: debug_name.map(String::from),
+        }
+    }
+
+    /// Records that the device was lost. Only the first submission to detect the loss is
+    /// reported.
+    /// Records that the CPU has observed a job complete and releases garbage it was holding.
+    unsafe fn job_completed(&self, queue: &ShardedLock<VkQueue>, target_value: u64) {
+        {
+            let mut queue = queue.write().unwrap();
+            if queue.cpu_sync_value() >= target_value {
+                return;
+            }
+            queue.set_cpu_sync_value(target_value);
+        }
+        self.collect_garbage_inner(false);
+    }
+
+    /// Destroys garbage no longer in use by any queue without submitting anything.
+    ///
+    /// When not `blocking`, nothing is done if another thread holds the resource state. That
+    /// thread is usually submitting, which collects garbage anyway. This also keeps internal
+    /// waits, which hold the resource state, from deadlocking.
+    unsafe fn collect_garbage_inner(&self, blocking: bool) {
+        if !self.garbage.has_pending() {
+            return;
+        }
+
+        // Same lock order as submission
+        let mut resc_state = if blocking {
+            self.resource_state.write().unwrap()
+        } else {
+            match self.resource_state.try_write() {
+                Ok(resc_state) => resc_state,
+                Err(_) => return,
+            }
         };
-        let target_values = TimelineValues {
-            main: main.target_timeline_value(),
-            transfer: transfer.target_timeline_value(),
-            compute: compute.target_timeline_value(),
+        let mut allocator = self.allocator.lock().unwrap();
+        let mut pools = self.pools.lock().unwrap();
+        let mut pipelines = self.pipelines.lock().unwrap();
+        pipelines.receive_compiled();
+        let main = self.main.write().unwrap();
+        let transfer = self.transfer.write().unwrap();
+        let compute = self.compute.write().unwrap();
+        let present = self.present.write().unwrap();
+        let mut queries = self.queries.lock().unwrap();
+        let mut events = self.events.lock().unwrap();
+        let mut transient = self.transient.lock().unwrap();
+
+        let current = main.current_timeline_value(&self.device).and_then(|main| {
+            Ok(TimelineValues {
+                main,
+                transfer: transfer.current_timeline_value(&self.device)?,
+                compute: compute.current_timeline_value(&self.device)?,
+                present: present.current_timeline_value(&self.device)?,
+            })
+        });
+
+        let current = match current {
+            Ok(current) => current,
+            Err(err) => {
+                self.set_device_lost(None, err);
+                return;
+            }
         };
 
         self.garbage.cleanup(GarbageCleanupArgs {
             device: &self.device,
             as_loader: &self.as_loader,
+            swapchain_loader: &self.swapchain_loader,
+            surface_loader: &self.surface_loader,
+            framebuffers: &self.framebuffers,
             buffer_ids: &self.buffer_ids,
             image_ids: &self.image_ids,
             set_ids: &self.set_ids,
@@ -1224,14 +2083,40 @@ impl VulkanBackend {
             pipelines: &mut pipelines,
             global_usage: &mut resc_state,
             queries: &mut queries,
-            current: current_values,
-            target: target_values,
+            events: &mut events,
+            transient: &mut transient,
+            current,
+            target: TimelineValues {
+                main: main.target_timeline_value(),
+                transfer: transfer.target_timeline_value(),
+                compute: compute.target_timeline_value(),
+                present: present.target_timeline_value(),
+            },
             override_ref_counter: false,
         });
+    }
+
+    fn set_device_lost(&self, debug_name: Option<&str>, err: vk::Result) {
+        self.device_lost.get_or_init(|| {
+            ard_log::error!(
+                "device lost during `{}`: {err}",
+                debug_name.unwrap_or("unnamed submission")
+            );
+            SubmitError::DeviceLost {
+                debug_name: debug_name.map(String::from),
+            }
+        });
+    }
 
+    /// A job for work that was never submitted because the device was lost. It can never
+    /// complete.
+    #[inline(always)]
+    fn lost_job(queue: QueueType, debug_name: Option<&str>) -> Job {
         Job {
             ty: queue,
-            target_value: next_target_value,
+            target_value: u64::MAX,
+            queries: None,
+            debug_name: debug_name.map(String::from),
         }
     }
 
@@ -1241,13 +2126,16 @@ impl VulkanBackend {
         mesh_shading: &ash::ext::mesh_shader::Device,
         as_loader: &ash::khr::acceleration_structure::Device,
         rt_loader: &ash::khr::ray_tracing_pipeline::Device,
-        props: &PhysicalDeviceProperties,
+        conditional_rendering: Option<&ash::ext::conditional_rendering::Device>,
+        main_queue: &mut VkQueue,
         queries: &Queries,
+        region_queries: &FxHashMap<usize, Query>,
         command_idx: usize,
         commands: &[Command<'a, crate::VulkanBackend>],
         render_passes: &RenderPassCache,
         framebuffers: &FramebufferCache,
         pipelines: &mut PipelineCache,
+        pushed: &PushedSets,
         debug: Option<&VkDebug>,
     ) {
         match &commands[command_idx] {
@@ -1255,18 +2143,21 @@ impl VulkanBackend {
                 cb,
                 device,
                 mesh_shading,
+                conditional_rendering.is_some(),
+                main_queue,
                 command_idx,
                 commands,
                 render_passes,
                 framebuffers,
                 pipelines,
+                pushed,
                 debug,
             ),
             Command::BeginComputePass(_, _) => {
-                Self::execute_compute_pass(cb, device, command_idx, commands, debug)
+                Self::execute_compute_pass(cb, device, command_idx, commands, pushed, debug)
             }
             Command::BeginRayTracingPass(_, _) => {
-                Self::execute_rt_pass(cb, device, rt_loader, props, command_idx, commands, debug)
+                Self::execute_rt_pass(cb, device, rt_loader, command_idx, commands, pushed, debug)
             }
             Command::CopyBufferToBuffer(copy) => {
                 let src = copy.src.internal();
@@ -1277,6 +2168,74 @@ impl VulkanBackend {
                     .size(copy.len)];
                 device.cmd_copy_buffer(cb, src.buffer, dst.buffer, &region);
             }
+            Command::FillBuffer {
+                buffer,
+                array_element,
+                offset,
+                len,
+                value,
+            } => {
+                let buffer = buffer.internal();
+                device.cmd_fill_buffer(
+                    cb,
+                    buffer.buffer,
+                    buffer.offset(*array_element) + *offset,
+                    *len,
+                    *value,
+                );
+            }
+            Command::UpdateBuffer {
+                buffer,
+                array_element,
+                offset,
+                data,
+            } => {
+                let buffer = buffer.internal();
+                device.cmd_update_buffer(
+                    cb,
+                    buffer.buffer,
+                    buffer.offset(*array_element) + *offset,
+                    data,
+                );
+            }
+            Command::ClearTexture {
+                texture,
+                array_element,
+                mip_level,
+                value,
+            } => {
+                let texture = texture.internal();
+                Self::clear_image(
+                    device,
+                    cb,
+                    texture.image,
+                    texture.aspect_flags,
+                    *array_element as u32,
+                    1,
+                    *mip_level as u32,
+                    value,
+                );
+            }
+            Command::ClearCubeMap {
+                cube_map,
+                array_element,
+                mip_level,
+                value,
+            } => {
+                let cube_map = cube_map.internal();
+                Self::clear_image(
+                    device,
+                    cb,
+                    cube_map.image,
+                    cube_map.aspect_flags,
+                    *array_element as u32 * 6,
+                    6,
+                    *mip_level as u32,
+                    value,
+                );
+            }
+            // Performed by the queue before the command buffer is submitted
+            Command::BindSparsePages { .. } | Command::UnbindSparsePages { .. } => {}
             Command::CopyTextureToTexture(copy) => {
                 let src = copy.src.internal();
                 let dst = copy.dst.internal();
@@ -1359,12 +2318,21 @@ impl VulkanBackend {
             } => {
                 let src = texture.internal();
                 let dst = buffer.internal();
+
+                // Buffer copies can only contain a single aspect, so only depth is copied from
+                // depth/stencil textures
+                let aspect_mask = if src.aspect_flags.contains(vk::ImageAspectFlags::DEPTH) {
+                    vk::ImageAspectFlags::DEPTH
+                } else {
+                    src.aspect_flags
+                };
+
                 let copy = [vk::BufferImageCopy::default()
                     .buffer_offset(dst.offset(copy.buffer_array_element) + copy.buffer_offset)
                     .buffer_row_length(copy.buffer_row_length)
                     .buffer_image_height(copy.buffer_image_height)
                     .image_subresource(vk::ImageSubresourceLayers {
-                        aspect_mask: src.aspect_flags,
+                        aspect_mask,
                         mip_level: copy.texture_mip_level as u32,
                         base_array_layer: copy.texture_array_element as u32,
                         layer_count: 1,
@@ -1395,6 +2363,7 @@ impl VulkanBackend {
                 let size = cube_map.dim().shr(copy.cube_map_mip_level).max(1);
                 let dst = cube_map.internal();
                 let src = buffer.internal();
+                let layers = CubeMap::copy_layers(copy);
                 let copy = [vk::BufferImageCopy::default()
                     .buffer_offset(src.offset(copy.buffer_array_element) + copy.buffer_offset)
                     .buffer_row_length(0)
@@ -1402,8 +2371,8 @@ impl VulkanBackend {
                     .image_subresource(vk::ImageSubresourceLayers {
                         aspect_mask: dst.aspect_flags,
                         mip_level: copy.cube_map_mip_level as u32,
-                        base_array_layer: copy.cube_map_array_element as u32 * 6,
-                        layer_count: 6,
+                        base_array_layer: layers.start,
+                        layer_count: layers.len() as u32,
                     })
                     .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
                     .image_extent(vk::Extent3D {
@@ -1427,6 +2396,7 @@ impl VulkanBackend {
                 let size = cube_map.dim().shr(copy.cube_map_mip_level).max(1);
                 let src = cube_map.internal();
                 let dst = buffer.internal();
+                let layers = CubeMap::copy_layers(copy);
                 let copy = [vk::BufferImageCopy::default()
                     .buffer_offset(dst.offset(copy.buffer_array_element) + copy.buffer_offset)
                     .buffer_row_length(0)
@@ -1434,8 +2404,8 @@ impl VulkanBackend {
                     .image_subresource(vk::ImageSubresourceLayers {
                         aspect_mask: src.aspect_flags,
                         mip_level: copy.cube_map_mip_level as u32,
-                        base_array_layer: copy.cube_map_array_element as u32 * 6,
-                        layer_count: 6,
+                        base_array_layer: layers.start,
+                        layer_count: layers.len() as u32,
                     })
                     .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
                     .image_extent(vk::Extent3D {
@@ -1574,6 +2544,9 @@ impl VulkanBackend {
             Command::SetTextureUsage { .. } => {
                 // Handled in barrier
             }
+            Command::SignalEvent { .. } | Command::WaitEvent { .. } => {
+                // Handled by the command sorter
+            }
             Command::BuildBlas {
                 blas,
                 scratch,
@@ -1582,6 +2555,24 @@ impl VulkanBackend {
                 blas.internal()
                     .build(device, cb, as_loader, scratch, *scratch_array_element);
             }
+            Command::BuildBlasBatch {
+                blases,
+                scratch,
+                scratch_array_element,
+            } => {
+                let blases: Vec<_> = blases
+                    .iter()
+                    .map(|(blas, offset)| (blas.internal(), *offset))
+                    .collect();
+                BottomLevelAccelerationStructure::build_batch(
+                    device,
+                    cb,
+                    as_loader,
+                    &blases,
+                    scratch,
+                    *scratch_array_element,
+                );
+            }
             Command::BuildTlas {
                 tlas,
                 instance_count,
@@ -1599,8 +2590,100 @@ impl VulkanBackend {
                     *scratch_array_element,
                     src,
                     *src_array_element,
+                    false,
+                );
+            }
+            Command::UpdateTlas {
+                tlas,
+                instance_count,
+                scratch,
+                scratch_array_element,
+                src,
+                src_array_element,
+            } => {
+                tlas.internal().build(
+                    device,
+                    cb,
+                    as_loader,
+                    *instance_count,
+                    scratch,
+                    *scratch_array_element,
+                    src,
+                    *src_array_element,
+                    true,
+                );
+            }
+            Command::BeginTimestamp(_) => {
+                let query = region_queries[&command_idx];
+                device.cmd_write_timestamp(
+                    cb,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    queries.timestamp_pool(query.pool),
+                    query.idx as u32,
+                );
+            }
+            Command::EndTimestamp => {
+                let query = region_queries[&command_idx];
+                device.cmd_write_timestamp(
+                    cb,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    queries.timestamp_pool(query.pool),
+                    query.idx as u32,
+                );
+            }
+            Command::BeginStatistics(_) => {
+                let query = region_queries[&command_idx];
+                device.cmd_begin_query(
+                    cb,
+                    queries.statistics_pool(query.pool),
+                    query.idx as u32,
+                    vk::QueryControlFlags::empty(),
                 );
             }
+            Command::EndStatistics => {
+                let query = region_queries[&command_idx];
+                device.cmd_end_query(cb, queries.statistics_pool(query.pool), query.idx as u32);
+            }
+            Command::BeginConditionalRendering {
+                buffer,
+                array_element,
+                offset,
+                inverted,
+            } => match conditional_rendering {
+                Some(loader) => {
+                    let begin_info = vk::ConditionalRenderingBeginInfoEXT::default()
+                        .buffer(buffer.internal().buffer)
+                        .offset(buffer.internal().offset(*array_element) + *offset)
+                        .flags(if *inverted {
+                            vk::ConditionalRenderingFlagsEXT::INVERTED
+                        } else {
+                            vk::ConditionalRenderingFlagsEXT::empty()
+                        });
+                    // ash doesn't wrap this extension
+                    (loader.fp().cmd_begin_conditional_rendering_ext)(cb, &begin_info);
+                }
+                None => {
+                    if debug.is_some() {
+                        ard_log::warn!(
+                            "conditional rendering is not supported, executing region unconditionally"
+                        );
+                    }
+                }
+            },
+            Command::EndConditionalRendering => {
+                if let Some(loader) = conditional_rendering {
+                    (loader.fp().cmd_end_conditional_rendering_ext)(cb);
+                }
+            }
+            Command::InsertLabel { label, color } => {
+                if let Some(debug) = debug {
+                    let name = CString::new(*label).unwrap();
+                    let label = vk::DebugUtilsLabelEXT::default()
+                        .label_name(&name)
+                        .color(*color);
+                    debug.device.cmd_insert_debug_utils_label(cb, &label);
+                }
+            }
             Command::WriteBlasCompactSize(blas) => {
                 blas.internal().write_compact_size(cb, as_loader, queries);
             }
@@ -1615,477 +2698,976 @@ impl VulkanBackend {
         cb: vk::CommandBuffer,
         device: &ash::Device,
         mesh_shading: &ash::ext::mesh_shader::Device,
+        conditional_rendering: bool,
+        main_queue: &mut VkQueue,
         command_idx: usize,
         commands: &[Command<'a, crate::VulkanBackend>],
         render_passes: &RenderPassCache,
         framebuffers: &FramebufferCache,
         pipelines: &mut PipelineCache,
+        pushed: &PushedSets,
         debug: Option<&VkDebug>,
     ) {
-        let mut active_layout = vk::PipelineLayout::default();
-        let mut active_render_pass = VkRenderPass::default();
+        let (descriptor, debug_name) = match &commands[command_idx] {
+            Command::BeginRenderPass(descriptor, debug_name) => (descriptor, debug_name),
+            _ => unreachable!(),
+        };
 
-        for command in &commands[command_idx..] {
-            match command {
-                Command::BeginRenderPass(descriptor, debug_name) => {
-                    if let Some(name) = *debug_name {
-                        if let Some(debug) = debug {
-                            let name = CString::new(name).unwrap();
-                            let label = vk::DebugUtilsLabelEXT::default().label_name(&name);
-                            debug.device.cmd_begin_debug_utils_label(cb, &label);
-                        }
-                    }
+        if let Some(name) = *debug_name {
+            if let Some(debug) = debug {
+                let name = CString::new(name).unwrap();
+                let label = vk::DebugUtilsLabelEXT::default().label_name(&name);
+                debug.device.cmd_begin_debug_utils_label(cb, &label);
+            }
+        }
 
-                    // Get the render pass described
-                    active_render_pass = render_passes.get(device, descriptor);
+        // Get the render pass described
+        let active_render_pass = render_passes.get(device, descriptor);
+
+        // Resolve every pipeline used within the pass up front so that slices can be recorded on
+        // other threads without touching the pipeline cache. Pipelines still compiling in the
+        // background resolve to `None`
+        let pass_commands = &commands[(command_idx + 1)..];
+        let mut resolved = FxHashMap::default();
+        let mut split = false;
+        let mut resolve = |command: &Command<'a, crate::VulkanBackend>| {
+            if let Command::BindGraphicsPipeline(pipeline) = command {
+                resolved
+                    .entry(pipeline.internal().layout())
+                    .or_insert_with(|| {
+                        pipeline.internal().get(
+                            device,
+                            pipelines,
+                            debug.as_ref().map(|utils| &utils.device),
+                            active_render_pass,
+                        )
+                    });
+            }
+        };
+        for command in pass_commands {
+            match command {
+                Command::EndRenderPass(_) => break,
+                Command::RenderPassSlices(slices) => {
+                    split = true;
+                    slices.iter().flatten().for_each(&mut resolve);
+                }
+                command => resolve(command),
+            }
+        }
 
-                    // Find the render pass
-                    let mut dims = (0, 0);
-                    let mut views = Vec::with_capacity(
-                        descriptor.color_attachments.len()
-                            + descriptor.color_resolve_attachments.len(),
+        // Find the render pass
+        let mut dims = (0, 0);
+        let mut views = Vec::with_capacity(
+            descriptor.color_attachments.len() + descriptor.color_resolve_attachments.len(),
+        );
+        for attachment in &descriptor.color_attachments {
+            views.push(match &attachment.dst {
+                ColorAttachmentDestination::SurfaceImage(image) => {
+                    // Indicate that the surface image has been drawn to
+                    image.internal().signal_draw();
+                    dims = image.internal().dims();
+                    image.internal().view()
+                }
+                ColorAttachmentDestination::Texture {
+                    texture,
+                    array_element,
+                    mip_level,
+                } => {
+                    dims = (
+                        texture.dims().0.shr(mip_level).max(1),
+                        texture.dims().1.shr(mip_level).max(1),
                     );
-                    for attachment in &descriptor.color_attachments {
-                        views.push(match &attachment.dst {
-                            ColorAttachmentDestination::SurfaceImage(image) => {
-                                // Indicate that the surface image has been drawn to
-                                image.internal().signal_draw();
-                                dims = image.internal().dims();
-                                image.internal().view()
-                            }
-                            ColorAttachmentDestination::Texture {
-                                texture,
-                                array_element,
-                                mip_level,
-                            } => {
-                                dims = (
-                                    texture.dims().0.shr(mip_level).max(1),
-                                    texture.dims().1.shr(mip_level).max(1),
-                                );
-                                texture.internal().get_view(*array_element, *mip_level)
-                            }
-                            ColorAttachmentDestination::CubeFace {
-                                cube_map,
-                                array_element,
-                                face,
-                                mip_level,
-                            } => {
-                                dims = (
-                                    cube_map.dim().shr(mip_level).max(1),
-                                    cube_map.dim().shr(mip_level).max(1),
-                                );
-                                cube_map
-                                    .internal()
-                                    .get_face_view(*array_element, *mip_level, *face)
-                            }
-                            ColorAttachmentDestination::CubeMap {
-                                cube_map,
-                                array_element,
-                                mip_level,
-                            } => {
-                                dims = (
-                                    cube_map.dim().shr(mip_level).max(1),
-                                    cube_map.dim().shr(mip_level).max(1),
-                                );
-                                cube_map.internal().get_view(*array_element, *mip_level)
-                            }
-                        });
-                    }
+                    texture.internal().get_view(*array_element, *mip_level)
+                }
+                ColorAttachmentDestination::TextureSlice {
+                    texture,
+                    mip_level,
+                    slice,
+                } => {
+                    dims = (
+                        texture.dims().0.shr(mip_level).max(1),
+                        texture.dims().1.shr(mip_level).max(1),
+                    );
+                    texture.internal().get_slice_view(*mip_level, *slice)
+                }
+                ColorAttachmentDestination::CubeFace {
+                    cube_map,
+                    array_element,
+                    face,
+                    mip_level,
+                } => {
+                    dims = (
+                        cube_map.dim().shr(mip_level).max(1),
+                        cube_map.dim().shr(mip_level).max(1),
+                    );
+                    cube_map
+                        .internal()
+                        .get_face_view(*array_element, *mip_level, *face)
+                }
+                ColorAttachmentDestination::CubeMap {
+                    cube_map,
+                    array_element,
+                    mip_level,
+                } => {
+                    dims = (
+                        cube_map.dim().shr(mip_level).max(1),
+                        cube_map.dim().shr(mip_level).max(1),
+                    );
+                    cube_map.internal().get_view(*array_element, *mip_level)
+                }
+                ColorAttachmentDestination::TextureArray {
+                    texture,
+                    base_layer,
+                    layer_count,
+                    mip_level,
+                } => {
+                    dims = (
+                        texture.dims().0.shr(mip_level).max(1),
+                        texture.dims().1.shr(mip_level).max(1),
+                    );
+                    texture.internal().get_layered_view(
+                        device,
+                        debug.map(|d| &d.device),
+                        *mip_level,
+                        *base_layer,
+                        *layer_count,
+                    )
+                }
+            });
+        }
 
-                    for attachment in &descriptor.color_resolve_attachments {
-                        views.push(match &attachment.dst {
-                            ColorAttachmentDestination::SurfaceImage(image) => {
-                                // Indicate that the surface image has been drawn to
-                                image.internal().signal_draw();
-                                dims = image.internal().dims();
-                                image.internal().view()
-                            }
-                            ColorAttachmentDestination::Texture {
-                                texture,
-                                array_element,
-                                mip_level,
-                            } => {
-                                dims = (
-                                    texture.dims().0.shr(mip_level).max(1),
-                                    texture.dims().1.shr(mip_level).max(1),
-                                );
-                                texture.internal().get_view(*array_element, *mip_level)
-                            }
-                            ColorAttachmentDestination::CubeFace {
-                                cube_map,
-                                array_element,
-                                face,
-                                mip_level,
-                            } => {
-                                dims = (
-                                    cube_map.dim().shr(mip_level).max(1),
-                                    cube_map.dim().shr(mip_level).max(1),
-                                );
-                                cube_map
-                                    .internal()
-                                    .get_face_view(*array_element, *mip_level, *face)
-                            }
-                            ColorAttachmentDestination::CubeMap {
-                                cube_map,
-                                array_element,
-                                mip_level,
-                            } => {
-                                dims = (
-                                    cube_map.dim().shr(mip_level).max(1),
-                                    cube_map.dim().shr(mip_level).max(1),
-                                );
-                                cube_map.internal().get_view(*array_element, *mip_level)
-                            }
-                        });
-                    }
+        for attachment in &descriptor.color_resolve_attachments {
+            views.push(match &attachment.dst {
+                ColorAttachmentDestination::SurfaceImage(image) => {
+                    // Indicate that the surface image has been drawn to
+                    image.internal().signal_draw();
+                    dims = image.internal().dims();
+                    image.internal().view()
+                }
+                ColorAttachmentDestination::Texture {
+                    texture,
+                    array_element,
+                    mip_level,
+                } => {
+                    dims = (
+                        texture.dims().0.shr(mip_level).max(1),
+                        texture.dims().1.shr(mip_level).max(1),
+                    );
+                    texture.internal().get_view(*array_element, *mip_level)
+                }
+                ColorAttachmentDestination::TextureSlice {
+                    texture,
+                    mip_level,
+                    slice,
+                } => {
+                    dims = (
+                        texture.dims().0.shr(mip_level).max(1),
+                        texture.dims().1.shr(mip_level).max(1),
+                    );
+                    texture.internal().get_slice_view(*mip_level, *slice)
+                }
+                ColorAttachmentDestination::CubeFace {
+                    cube_map,
+                    array_element,
+                    face,
+                    mip_level,
+                } => {
+                    dims = (
+                        cube_map.dim().shr(mip_level).max(1),
+                        cube_map.dim().shr(mip_level).max(1),
+                    );
+                    cube_map
+                        .internal()
+                        .get_face_view(*array_element, *mip_level, *face)
+                }
+                ColorAttachmentDestination::CubeMap {
+                    cube_map,
+                    array_element,
+                    mip_level,
+                } => {
+                    dims = (
+                        cube_map.dim().shr(mip_level).max(1),
+                        cube_map.dim().shr(mip_level).max(1),
+                    );
+                    cube_map.internal().get_view(*array_element, *mip_level)
+                }
+                ColorAttachmentDestination::TextureArray {
+                    texture,
+                    base_layer,
+                    layer_count,
+                    mip_level,
+                } => {
+                    dims = (
+                        texture.dims().0.shr(mip_level).max(1),
+                        texture.dims().1.shr(mip_level).max(1),
+                    );
+                    texture.internal().get_layered_view(
+                        device,
+                        debug.map(|d| &d.device),
+                        *mip_level,
+                        *base_layer,
+                        *layer_count,
+                    )
+                }
+            });
+        }
 
-                    fn deptch_stencil_attachment_get_view(
-                        dst: &DepthStencilAttachmentDestination<'_, crate::VulkanBackend>,
-                    ) -> (vk::ImageView, u32, u32) {
-                        match dst {
-                            DepthStencilAttachmentDestination::Texture {
-                                texture,
-                                array_element,
-                                mip_level,
-                            } => {
-                                let (width, height, _) = texture.dims();
-                                let view = texture.internal().get_view(*array_element, *mip_level);
-                                (view, width, height)
-                            }
-                            DepthStencilAttachmentDestination::CubeFace {
-                                cube_map,
-                                array_element,
-                                face,
-                                mip_level,
-                            } => {
-                                let dim = cube_map.dim();
-                                let view = cube_map.internal().get_face_view(
-                                    *array_element,
-                                    *mip_level,
-                                    *face,
-                                );
-                                (view, dim, dim)
-                            }
-                            DepthStencilAttachmentDestination::CubeMap {
-                                cube_map,
-                                array_element,
-                                mip_level,
-                            } => {
-                                let dim = cube_map.dim();
-                                let view = cube_map.internal().get_view(*array_element, *mip_level);
-                                (view, dim, dim)
-                            }
-                        }
-                    }
+        unsafe fn deptch_stencil_attachment_get_view(
+            device: &ash::Device,
+            debug: Option<&ash::ext::debug_utils::Device>,
+            dst: &DepthStencilAttachmentDestination<'_, crate::VulkanBackend>,
+        ) -> (vk::ImageView, u32, u32) {
+            match dst {
+                DepthStencilAttachmentDestination::Texture {
+                    texture,
+                    array_element,
+                    mip_level,
+                } => {
+                    let (width, height, _) = texture.dims();
+                    let view = texture.internal().get_view(*array_element, *mip_level);
+                    (view, width, height)
+                }
+                DepthStencilAttachmentDestination::CubeFace {
+                    cube_map,
+                    array_element,
+                    face,
+                    mip_level,
+                } => {
+                    let dim = cube_map.dim();
+                    let view = cube_map
+                        .internal()
+                        .get_face_view(*array_element, *mip_level, *face);
+                    (view, dim, dim)
+                }
+                DepthStencilAttachmentDestination::CubeMap {
+                    cube_map,
+                    array_element,
+                    mip_level,
+                } => {
+                    let dim = cube_map.dim();
+                    let view = cube_map.internal().get_view(*array_element, *mip_level);
+                    (view, dim, dim)
+                }
+                DepthStencilAttachmentDestination::TextureArray {
+                    texture,
+                    base_layer,
+                    layer_count,
+                    mip_level,
+                } => {
+                    let (width, height, _) = texture.dims();
+                    let view = texture.internal().get_layered_view(
+                        device,
+                        debug,
+                        *mip_level,
+                        *base_layer,
+                        *layer_count,
+                    );
+                    (
+                        view,
+                        width.shr(mip_level).max(1),
+                        height.shr(mip_level).max(1),
+                    )
+                }
+            }
+        }
 
-                    if let Some(attachment) = &descriptor.depth_stencil_attachment {
-                        let (view, width, height) =
-                            deptch_stencil_attachment_get_view(&attachment.dst);
-                        dims = (width, height);
-                        views.push(view);
-                    }
+        if let Some(attachment) = &descriptor.depth_stencil_attachment {
+            let (view, width, height) = deptch_stencil_attachment_get_view(
+                device,
+                debug.map(|d| &d.device),
+                &attachment.dst,
+            );
+            dims = (width, height);
+            views.push(view);
+        }
 
-                    if let Some(attachment) = &descriptor.depth_stencil_resolve_attachment {
-                        let (view, width, height) =
-                            deptch_stencil_attachment_get_view(&attachment.dst);
-                        dims = (width, height);
-                        views.push(view);
-                    }
+        if let Some(attachment) = &descriptor.depth_stencil_resolve_attachment {
+            let (view, width, height) = deptch_stencil_attachment_get_view(
+                device,
+                debug.map(|d| &d.device),
+                &attachment.dst,
+            );
+            dims = (width, height);
+            views.push(view);
+        }
 
-                    // Find the framebuffer
-                    let framebuffer = framebuffers.get(
-                        device,
-                        active_render_pass.pass,
-                        views,
-                        vk::Extent2D {
-                            width: dims.0,
-                            height: dims.1,
-                        },
-                    );
+        // Find the framebuffer
+        let framebuffer = framebuffers.get(
+            device,
+            active_render_pass.pass,
+            views,
+            vk::Extent2D {
+                width: dims.0,
+                height: dims.1,
+            },
+        );
 
-                    // Find clear values
-                    let mut clear_values = Vec::with_capacity(descriptor.color_attachments.len());
-                    for attachment in &descriptor.color_attachments {
-                        if let LoadOp::Clear(clear_color) = &attachment.load_op {
-                            let color = match clear_color {
-                                ClearColor::RgbaF32(r, g, b, a) => vk::ClearColorValue {
-                                    float32: [*r, *g, *b, *a],
-                                },
-                                ClearColor::RU32(r) => vk::ClearColorValue {
-                                    uint32: [*r, 0, 0, 0],
-                                },
-                                ClearColor::D32S32(_, _) => {
-                                    panic!("invalid color clear color type")
-                                }
-                            };
-                            clear_values.push(vk::ClearValue { color });
-                        } else {
-                            clear_values.push(vk::ClearValue::default());
-                        }
+        // Find clear values
+        let mut clear_values = Vec::with_capacity(descriptor.color_attachments.len());
+        for attachment in &descriptor.color_attachments {
+            if let LoadOp::Clear(clear_color) = &attachment.load_op {
+                let color = match clear_color {
+                    ClearColor::RgbaF32(r, g, b, a) => vk::ClearColorValue {
+                        float32: [*r, *g, *b, *a],
+                    },
+                    ClearColor::RU32(r) => vk::ClearColorValue {
+                        uint32: [*r, 0, 0, 0],
+                    },
+                    ClearColor::D32S32(_, _) => {
+                        panic!("invalid color clear color type")
                     }
+                };
+                clear_values.push(vk::ClearValue { color });
+            } else {
+                clear_values.push(vk::ClearValue::default());
+            }
+        }
 
-                    for attachment in &descriptor.color_resolve_attachments {
-                        if let LoadOp::Clear(clear_color) = &attachment.load_op {
-                            let color = match clear_color {
-                                ClearColor::RgbaF32(r, g, b, a) => vk::ClearColorValue {
-                                    float32: [*r, *g, *b, *a],
-                                },
-                                ClearColor::RU32(r) => vk::ClearColorValue {
-                                    uint32: [*r, 0, 0, 0],
-                                },
-                                ClearColor::D32S32(_, _) => {
-                                    panic!("invalid color clear color type")
-                                }
-                            };
-                            clear_values.push(vk::ClearValue { color });
-                        } else {
-                            clear_values.push(vk::ClearValue::default());
-                        }
+        for attachment in &descriptor.color_resolve_attachments {
+            if let LoadOp::Clear(clear_color) = &attachment.load_op {
+                let color = match clear_color {
+                    ClearColor::RgbaF32(r, g, b, a) => vk::ClearColorValue {
+                        float32: [*r, *g, *b, *a],
+                    },
+                    ClearColor::RU32(r) => vk::ClearColorValue {
+                        uint32: [*r, 0, 0, 0],
+                    },
+                    ClearColor::D32S32(_, _) => {
+                        panic!("invalid color clear color type")
                     }
+                };
+                clear_values.push(vk::ClearValue { color });
+            } else {
+                clear_values.push(vk::ClearValue::default());
+            }
+        }
 
-                    if let Some(attachment) = &descriptor.depth_stencil_attachment {
-                        if let LoadOp::Clear(clear_color) = &attachment.load_op {
-                            let depth_stencil = match clear_color {
-                                ClearColor::D32S32(d, s) => vk::ClearDepthStencilValue {
-                                    depth: *d,
-                                    stencil: *s,
-                                },
-                                _ => panic!("invalid depth clear color"),
-                            };
-                            clear_values.push(vk::ClearValue { depth_stencil })
-                        } else {
-                            clear_values.push(vk::ClearValue::default());
-                        }
-                    }
+        if let Some(attachment) = &descriptor.depth_stencil_attachment {
+            // Depth and stencil are cleared independently
+            let mut depth_stencil = vk::ClearDepthStencilValue::default();
+            if let LoadOp::Clear(clear_color) = &attachment.load_op {
+                match clear_color {
+                    ClearColor::D32S32(d, _) => depth_stencil.depth = *d,
+                    _ => panic!("invalid depth clear color"),
+                }
+            }
+            if let LoadOp::Clear(clear_color) = &attachment.stencil_load_op {
+                match clear_color {
+                    ClearColor::D32S32(_, s) => depth_stencil.stencil = *s,
+                    _ => panic!("invalid stencil clear color"),
+                }
+            }
+            clear_values.push(vk::ClearValue { depth_stencil });
+        }
 
-                    if let Some(attachment) = &descriptor.depth_stencil_resolve_attachment {
-                        if let LoadOp::Clear(clear_color) = &attachment.load_op {
-                            let depth_stencil = match clear_color {
-                                ClearColor::D32S32(d, s) => vk::ClearDepthStencilValue {
-                                    depth: *d,
-                                    stencil: *s,
-                                },
-                                _ => panic!("invalid depth clear color"),
-                            };
-                            clear_values.push(vk::ClearValue { depth_stencil })
-                        } else {
-                            clear_values.push(vk::ClearValue::default());
-                        }
-                    }
+        if let Some(attachment) = &descriptor.depth_stencil_resolve_attachment {
+            if let LoadOp::Clear(clear_color) = &attachment.load_op {
+                let depth_stencil = match clear_color {
+                    ClearColor::D32S32(d, s) => vk::ClearDepthStencilValue {
+                        depth: *d,
+                        stencil: *s,
+                    },
+                    _ => panic!("invalid depth clear color"),
+                };
+                clear_values.push(vk::ClearValue { depth_stencil })
+            } else {
+                clear_values.push(vk::ClearValue::default());
+            }
+        }
 
-                    // Initial viewport configuration
-                    // NOTE: Viewport is flipped to account for Vulkan coordinate system
-                    let viewport = [vk::Viewport {
-                        width: dims.0 as f32,
-                        height: -(dims.1 as f32),
-                        x: 0.0,
-                        y: dims.1 as f32,
-                        min_depth: 0.0,
-                        max_depth: 1.0,
-                    }];
-
-                    let scissor = [vk::Rect2D {
-                        extent: vk::Extent2D {
-                            width: dims.0,
-                            height: dims.1,
-                        },
-                        offset: vk::Offset2D { x: 0, y: 0 },
-                    }];
-
-                    device.cmd_set_viewport(cb, 0, &viewport);
-                    device.cmd_set_scissor(cb, 0, &scissor);
-
-                    // Begin the render pass
-                    let begin_info = vk::RenderPassBeginInfo::default()
-                        .render_pass(active_render_pass.pass)
-                        .clear_values(&clear_values)
-                        .framebuffer(framebuffer)
-                        .render_area(vk::Rect2D {
-                            offset: vk::Offset2D { x: 0, y: 0 },
-                            extent: vk::Extent2D {
-                                width: dims.0,
-                                height: dims.1,
-                            },
-                        });
+        // Initial viewport configuration
+        // NOTE: Viewport is flipped to account for Vulkan coordinate system
+        let viewport = [vk::Viewport {
+            width: dims.0 as f32,
+            height: -(dims.1 as f32),
+            x: 0.0,
+            y: dims.1 as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        let scissor = [vk::Rect2D {
+            extent: vk::Extent2D {
+                width: dims.0,
+                height: dims.1,
+            },
+            offset: vk::Offset2D { x: 0, y: 0 },
+        }];
+
+        device.cmd_set_viewport(cb, 0, &viewport);
+        device.cmd_set_scissor(cb, 0, &scissor);
+
+        // Begin the render pass
+        let begin_info = vk::RenderPassBeginInfo::default()
+            .render_pass(active_render_pass.pass)
+            .clear_values(&clear_values)
+            .framebuffer(framebuffer)
+            .render_area(vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent: vk::Extent2D {
+                    width: dims.0,
+                    height: dims.1,
+                },
+            });
 
-                    let subpass_info =
-                        vk::SubpassBeginInfo::default().contents(vk::SubpassContents::INLINE);
+        // Split passes are made entirely of secondary command buffers
+        let subpass_info = vk::SubpassBeginInfo::default().contents(if split {
+            vk::SubpassContents::SECONDARY_COMMAND_BUFFERS
+        } else {
+            vk::SubpassContents::INLINE
+        });
 
-                    device.cmd_begin_render_pass2(cb, &begin_info, &subpass_info);
-                }
-                Command::EndRenderPass(debug_name) => {
-                    device.cmd_end_render_pass(cb);
-                    if debug_name.is_some() {
-                        if let Some(debug) = debug {
-                            debug.device.cmd_end_debug_utils_label(cb);
-                        }
-                    }
+        device.cmd_begin_render_pass2(cb, &begin_info, &subpass_info);
+
+        if split {
+            Self::execute_split_render_pass(
+                cb,
+                device,
+                mesh_shading,
+                conditional_rendering,
+                main_queue,
+                pass_commands,
+                &resolved,
+                active_render_pass.pass,
+                framebuffer,
+                dims,
+                pushed,
+            );
+        } else {
+            let mut active_layout = vk::PipelineLayout::default();
+            for command in pass_commands {
+                if let Command::EndRenderPass(_) = command {
                     break;
                 }
-                Command::BindGraphicsPipeline(pipeline) => {
-                    active_layout = pipeline.internal().layout();
-                    let pipeline = pipeline.internal().get(
-                        device,
-                        pipelines,
-                        debug.as_ref().map(|utils| &utils.device),
-                        active_render_pass,
-                    );
-                    device.cmd_bind_pipeline(cb, vk::PipelineBindPoint::GRAPHICS, pipeline);
-                }
-                Command::PushConstants { stage, data } => device.cmd_push_constants(
+                Self::record_render_pass_command(
                     cb,
-                    active_layout,
-                    crate::util::to_vk_shader_stage(*stage),
-                    0,
-                    data,
-                ),
-                Command::BindDescriptorSets { sets, first, .. } => {
-                    let mut vk_sets = Vec::with_capacity(sets.len());
-                    for set in sets {
-                        vk_sets.push(set.internal().set);
-                    }
+                    device,
+                    mesh_shading,
+                    &resolved,
+                    pushed,
+                    &mut active_layout,
+                    command,
+                );
+            }
+        }
 
-                    if active_layout != vk::PipelineLayout::default() {
-                        device.cmd_bind_descriptor_sets(
-                            cb,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            active_layout,
-                            *first as u32,
-                            &vk_sets,
-                            &[],
-                        );
+        device.cmd_end_render_pass(cb);
+        if debug_name.is_some() {
+            if let Some(debug) = debug {
+                debug.device.cmd_end_debug_utils_label(cb);
+            }
+        }
+    }
+
+    /// Records the commands of a render pass containing slices. Slices are recorded in parallel
+    /// into their own secondary command buffers. Commands recorded directly to the pass between
+    /// slices are grouped into secondary command buffers on this thread.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn execute_split_render_pass<'a>(
+        cb: vk::CommandBuffer,
+        device: &ash::Device,
+        mesh_shading: &ash::ext::mesh_shader::Device,
+        conditional_rendering: bool,
+        main_queue: &mut VkQueue,
+        pass_commands: &[Command<'a, crate::VulkanBackend>],
+        resolved: &FxHashMap<vk::PipelineLayout, Option<vk::Pipeline>>,
+        render_pass: vk::RenderPass,
+        framebuffer: vk::Framebuffer,
+        dims: (u32, u32),
+        pushed: &PushedSets,
+    ) {
+        // State commands recorded directly to the pass. These are replayed at the start of every
+        // secondary command buffer since secondary command buffers don't inherit state.
+        let mut state = Vec::default();
+        // Commands recorded directly to the pass since the last set of slices.
+        let mut segment = Vec::default();
+        // Number of state commands recorded before the current segment began.
+        let mut segment_state = 0;
+        let mut secondary = Vec::default();
+
+        let record = |cb: vk::CommandBuffer,
+                      state: &[&Command<'a, crate::VulkanBackend>],
+                      commands: &[&Command<'a, crate::VulkanBackend>]| {
+            Self::record_secondary_render_pass(
+                cb,
+                device,
+                mesh_shading,
+                conditional_rendering,
+                resolved,
+                render_pass,
+                framebuffer,
+                dims,
+                pushed,
+                state,
+                commands,
+            );
+        };
+
+        for command in pass_commands {
+            match command {
+                Command::EndRenderPass(_) => break,
+                Command::RenderPassSlices(slices) => {
+                    if !segment.is_empty() {
+                        let segment_cb =
+                            main_queue.allocate_secondary_command_buffers(device, 1)[0];
+                        record(segment_cb, &state[..segment_state], &segment);
+                        secondary.push(segment_cb);
+                        segment.clear();
                     }
+
+                    let slice_cbs =
+                        main_queue.allocate_secondary_command_buffers(device, slices.len());
+                    slices
+                        .par_iter()
+                        .zip(slice_cbs.par_iter())
+                        .for_each(|(slice, slice_cb)| {
+                            let slice: Vec<_> = slice.iter().collect();
+                            record(*slice_cb, &state, &slice);
+                        });
+                    secondary.extend(slice_cbs);
+                    segment_state = state.len();
                 }
-                Command::BindDescriptorSetsUnchecked { sets, first, .. } => {
-                    let mut vk_sets = Vec::with_capacity(sets.len());
-                    for set in sets {
-                        vk_sets.push(set.internal().set);
-                    }
+                Command::BindGraphicsPipeline(_)
+                | Command::PushConstants { .. }
+                | Command::BindDescriptorSets { .. }
+                | Command::BindDescriptorSetsUnchecked { .. }
+                | Command::PushDescriptorSet { .. }
+                | Command::BindVertexBuffers { .. }
+                | Command::BindIndexBuffer { .. }
+                | Command::Scissor { .. }
+                | Command::SetViewport { .. }
+                | Command::SetDepthBias { .. }
+                | Command::SetLineWidth(_)
+                | Command::SetStencilReference(_)
+                | Command::SetBlendConstants(_) => {
+                    state.push(command);
+                    segment.push(command);
+                }
+                command => segment.push(command),
+            }
+        }
+
+        if !segment.is_empty() {
+            let segment_cb = main_queue.allocate_secondary_command_buffers(device, 1)[0];
+            record(segment_cb, &state[..segment_state], &segment);
+            secondary.push(segment_cb);
+        }
+
+        device.cmd_execute_commands(cb, &secondary);
+    }
+
+    /// Records render pass commands into a secondary command buffer after replaying `state`.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn record_secondary_render_pass(
+        cb: vk::CommandBuffer,
+        device: &ash::Device,
+        mesh_shading: &ash::ext::mesh_shader::Device,
+        conditional_rendering: bool,
+        resolved: &FxHashMap<vk::PipelineLayout, Option<vk::Pipeline>>,
+        render_pass: vk::RenderPass,
+        framebuffer: vk::Framebuffer,
+        dims: (u32, u32),
+        pushed: &PushedSets,
+        state: &[&Command<'_, crate::VulkanBackend>],
+        commands: &[&Command<'_, crate::VulkanBackend>],
+    ) {
+        // Slices may be executed inside of a conditional rendering region
+        let mut cr_inheritance = vk::CommandBufferInheritanceConditionalRenderingInfoEXT::default()
+            .conditional_rendering_enable(true);
+        let mut inheritance = vk::CommandBufferInheritanceInfo::default()
+            .render_pass(render_pass)
+            .subpass(0)
+            .framebuffer(framebuffer);
+        if conditional_rendering {
+            inheritance = inheritance.push_next(&mut cr_inheritance);
+        }
+        let begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(
+                vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT
+                    | vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE,
+            )
+            .inheritance_info(&inheritance);
+        device.begin_command_buffer(cb, &begin_info).unwrap();
+
+        // Dynamic state isn't inherited either
+        // NOTE: Viewport is flipped to account for Vulkan coordinate system
+        let viewport = [vk::Viewport {
+            width: dims.0 as f32,
+            height: -(dims.1 as f32),
+            x: 0.0,
+            y: dims.1 as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        let scissor = [vk::Rect2D {
+            extent: vk::Extent2D {
+                width: dims.0,
+                height: dims.1,
+            },
+            offset: vk::Offset2D { x: 0, y: 0 },
+        }];
+
+        device.cmd_set_viewport(cb, 0, &viewport);
+        device.cmd_set_scissor(cb, 0, &scissor);
+
+        let mut active_layout = vk::PipelineLayout::default();
+        for command in state.iter().chain(commands.iter()) {
+            Self::record_render_pass_command(
+                cb,
+                device,
+                mesh_shading,
+                resolved,
+                pushed,
+                &mut active_layout,
+                command,
+            );
+        }
 
-                    if active_layout != vk::PipelineLayout::default() {
-                        device.cmd_bind_descriptor_sets(
-                            cb,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            active_layout,
-                            *first as u32,
-                            &vk_sets,
-                            &[],
-                        );
-                    }
-                }
-                Command::BindVertexBuffers { first, binds } => {
-                    let mut buffers = Vec::with_capacity(binds.len());
-                    let mut offsets = Vec::with_capacity(binds.len());
-                    for bind in binds {
-                        let buffer = bind.buffer.internal();
-                        buffers.push(buffer.buffer);
-                        offsets.push(buffer.offset(bind.array_element) + bind.offset);
+        device.end_command_buffer(cb).unwrap();
+    }
+
+    /// Records a single command within a render pass. Commands that depend on the bound pipeline
+    /// are skipped while it is still compiling, which is tracked with a null `active_layout`.
+    unsafe fn record_render_pass_command(
+        cb: vk::CommandBuffer,
+        device: &ash::Device,
+        mesh_shading: &ash::ext::mesh_shader::Device,
+        resolved: &FxHashMap<vk::PipelineLayout, Option<vk::Pipeline>>,
+        pushed: &PushedSets,
+        active_layout: &mut vk::PipelineLayout,
+        command: &Command<'_, crate::VulkanBackend>,
+    ) {
+        let skip = *active_layout == vk::PipelineLayout::null();
+        match command {
+            Command::BindGraphicsPipeline(pipeline) => {
+                let layout = pipeline.internal().layout();
+                match resolved[&layout] {
+                    Some(pipeline) => {
+                        *active_layout = layout;
+                        device.cmd_bind_pipeline(cb, vk::PipelineBindPoint::GRAPHICS, pipeline);
                     }
-                    device.cmd_bind_vertex_buffers(cb, *first as u32, &buffers, &offsets);
-                }
-                Command::BindIndexBuffer {
-                    buffer,
-                    array_element,
-                    offset,
-                    ty,
-                } => {
-                    let buffer = buffer.internal();
-                    device.cmd_bind_index_buffer(
-                        cb,
-                        buffer.buffer,
-                        buffer.offset(*array_element) + offset,
-                        crate::util::to_vk_index_type(*ty),
-                    );
+                    None => *active_layout = vk::PipelineLayout::null(),
                 }
-                Command::Scissor {
-                    attachment,
-                    scissor,
-                } => {
-                    device.cmd_set_scissor(
-                        cb,
-                        *attachment as u32,
-                        &[vk::Rect2D {
-                            offset: vk::Offset2D {
-                                x: scissor.x,
-                                y: scissor.y,
-                            },
-                            extent: vk::Extent2D {
-                                width: scissor.width,
-                                height: scissor.height,
-                            },
-                        }],
-                    );
+            }
+            Command::PushConstants { .. }
+            | Command::Draw { .. }
+            | Command::DrawIndirect { .. }
+            | Command::DrawIndexed { .. }
+            | Command::DrawIndexedIndirect { .. }
+            | Command::DrawIndexedIndirectCount { .. }
+            | Command::DrawMeshTasks(..)
+            | Command::DrawMeshTasksIndirect { .. }
+            | Command::DrawMeshTasksIndirectCount { .. }
+                if skip => {}
+            Command::PushConstants { stage, data } => device.cmd_push_constants(
+                cb,
+                *active_layout,
+                crate::util::to_vk_shader_stage(*stage),
+                0,
+                data,
+            ),
+            Command::BindDescriptorSets { sets, first, .. } => {
+                let mut vk_sets = Vec::with_capacity(sets.len());
+                for set in sets {
+                    vk_sets.push(set.internal().set);
                 }
-                Command::Draw {
-                    vertex_count,
-                    instance_count,
-                    first_vertex,
-                    first_instance,
-                } => {
-                    device.cmd_draw(
+
+                if *active_layout != vk::PipelineLayout::default() {
+                    device.cmd_bind_descriptor_sets(
                         cb,
-                        *vertex_count as u32,
-                        *instance_count as u32,
-                        *first_vertex as u32,
-                        *first_instance as u32,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        *active_layout,
+                        *first as u32,
+                        &vk_sets,
+                        &[],
                     );
                 }
-                Command::DrawIndexed {
-                    index_count,
-                    instance_count,
-                    first_index,
-                    vertex_offset,
-                    first_instance,
-                } => {
-                    device.cmd_draw_indexed(
-                        cb,
-                        *index_count as u32,
-                        *instance_count as u32,
-                        *first_index as u32,
-                        *vertex_offset as i32,
-                        *first_instance as u32,
-                    );
+            }
+            Command::BindDescriptorSetsUnchecked { sets, first, .. } => {
+                let mut vk_sets = Vec::with_capacity(sets.len());
+                for set in sets {
+                    vk_sets.push(set.internal().set);
                 }
-                Command::DrawIndexedIndirect {
-                    buffer,
-                    array_element,
-                    offset,
-                    draw_count,
-                    stride,
-                } => {
-                    device.cmd_draw_indexed_indirect(
+
+                if *active_layout != vk::PipelineLayout::default() {
+                    device.cmd_bind_descriptor_sets(
                         cb,
-                        buffer.internal().buffer,
-                        buffer.internal().offset(*array_element) + *offset,
-                        *draw_count as u32,
-                        *stride as u32,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        *active_layout,
+                        *first as u32,
+                        &vk_sets,
+                        &[],
                     );
                 }
-                Command::DrawIndexedIndirectCount {
-                    draw_buffer,
-                    draw_array_element,
-                    draw_offset,
-                    draw_stride,
-                    count_buffer,
-                    count_array_element,
-                    count_offset,
-                    max_draw_count,
-                } => {
-                    device.cmd_draw_indexed_indirect_count(
+            }
+            Command::PushDescriptorSet { .. } => {
+                if *active_layout != vk::PipelineLayout::default() {
+                    pushed.record(
+                        device,
                         cb,
-                        draw_buffer.internal().buffer,
-                        draw_buffer.internal().offset(*draw_array_element) + *draw_offset,
-                        count_buffer.internal().buffer,
-                        count_buffer.internal().offset(*count_array_element) + *count_offset,
-                        *max_draw_count as u32,
-                        *draw_stride as u32,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        *active_layout,
+                        command,
                     );
                 }
-                Command::DrawMeshTasks(x, y, z) => {
-                    mesh_shading.cmd_draw_mesh_tasks(cb, *x, *y, *z);
+            }
+            Command::BindVertexBuffers { first, binds } => {
+                let mut buffers = Vec::with_capacity(binds.len());
+                let mut offsets = Vec::with_capacity(binds.len());
+                for bind in binds {
+                    let buffer = bind.buffer.internal();
+                    buffers.push(buffer.buffer);
+                    offsets.push(buffer.offset(bind.array_element) + bind.offset);
                 }
-                _ => unreachable!(),
+                device.cmd_bind_vertex_buffers(cb, *first as u32, &buffers, &offsets);
+            }
+            Command::BindIndexBuffer {
+                buffer,
+                array_element,
+                offset,
+                ty,
+            } => {
+                let buffer = buffer.internal();
+                device.cmd_bind_index_buffer(
+                    cb,
+                    buffer.buffer,
+                    buffer.offset(*array_element) + offset,
+                    crate::util::to_vk_index_type(*ty),
+                );
+            }
+            Command::SetViewport {
+                x,
+                y,
+                width,
+                height,
+                min_depth,
+                max_depth,
+            } => {
+                // NOTE: Viewport is flipped to account for Vulkan coordinate system
+                device.cmd_set_viewport(
+                    cb,
+                    0,
+                    &[vk::Viewport {
+                        width: *width,
+                        height: -*height,
+                        x: *x,
+                        y: *y + *height,
+                        min_depth: *min_depth,
+                        max_depth: *max_depth,
+                    }],
+                );
+            }
+            Command::SetDepthBias {
+                constant,
+                clamp,
+                slope,
+            } => {
+                device.cmd_set_depth_bias(cb, *constant, *clamp, *slope);
+            }
+            Command::SetLineWidth(width) => {
+                device.cmd_set_line_width(cb, *width);
+            }
+            Command::SetStencilReference(reference) => {
+                device.cmd_set_stencil_reference(
+                    cb,
+                    vk::StencilFaceFlags::FRONT_AND_BACK,
+                    *reference,
+                );
+            }
+            Command::SetBlendConstants(constants) => {
+                device.cmd_set_blend_constants(cb, constants);
+            }
+            Command::Scissor {
+                attachment,
+                scissor,
+            } => {
+                device.cmd_set_scissor(
+                    cb,
+                    *attachment as u32,
+                    &[vk::Rect2D {
+                        offset: vk::Offset2D {
+                            x: scissor.x,
+                            y: scissor.y,
+                        },
+                        extent: vk::Extent2D {
+                            width: scissor.width,
+                            height: scissor.height,
+                        },
+                    }],
+                );
+            }
+            Command::Draw {
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            } => {
+                device.cmd_draw(
+                    cb,
+                    *vertex_count as u32,
+                    *instance_count as u32,
+                    *first_vertex as u32,
+                    *first_instance as u32,
+                );
+            }
+            Command::DrawIndirect {
+                buffer,
+                array_element,
+                offset,
+                draw_count,
+                stride,
+            } => {
+                device.cmd_draw_indirect(
+                    cb,
+                    buffer.internal().buffer,
+                    buffer.internal().offset(*array_element) + *offset,
+                    *draw_count as u32,
+                    *stride as u32,
+                );
+            }
+            Command::DrawIndexed {
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                first_instance,
+            } => {
+                device.cmd_draw_indexed(
+                    cb,
+                    *index_count as u32,
+                    *instance_count as u32,
+                    *first_index as u32,
+                    *vertex_offset as i32,
+                    *first_instance as u32,
+                );
+            }
+            Command::DrawIndexedIndirect {
+                buffer,
+                array_element,
+                offset,
+                draw_count,
+                stride,
+            } => {
+                device.cmd_draw_indexed_indirect(
+                    cb,
+                    buffer.internal().buffer,
+                    buffer.internal().offset(*array_element) + *offset,
+                    *draw_count as u32,
+                    *stride as u32,
+                );
+            }
+            Command::DrawIndexedIndirectCount {
+                draw_buffer,
+                draw_array_element,
+                draw_offset,
+                draw_stride,
+                count_buffer,
+                count_array_element,
+                count_offset,
+                max_draw_count,
+            } => {
+                device.cmd_draw_indexed_indirect_count(
+                    cb,
+                    draw_buffer.internal().buffer,
+                    draw_buffer.internal().offset(*draw_array_element) + *draw_offset,
+                    count_buffer.internal().buffer,
+                    count_buffer.internal().offset(*count_array_element) + *count_offset,
+                    *max_draw_count as u32,
+                    *draw_stride as u32,
+                );
+            }
+            Command::DrawMeshTasks(x, y, z) => {
+                mesh_shading.cmd_draw_mesh_tasks(cb, *x, *y, *z);
+            }
+            Command::DrawMeshTasksIndirect {
+                buffer,
+                array_element,
+                offset,
+                draw_count,
+                stride,
+            } => {
+                mesh_shading.cmd_draw_mesh_tasks_indirect(
+                    cb,
+                    buffer.internal().buffer,
+                    buffer.internal().offset(*array_element) + *offset,
+                    *draw_count as u32,
+                    *stride as u32,
+                );
+            }
+            Command::DrawMeshTasksIndirectCount {
+                draw_buffer,
+                draw_array_element,
+                draw_offset,
+                draw_stride,
+                count_buffer,
+                count_array_element,
+                count_offset,
+                max_draw_count,
+            } => {
+                mesh_shading.cmd_draw_mesh_tasks_indirect_count(
+                    cb,
+                    draw_buffer.internal().buffer,
+                    draw_buffer.internal().offset(*draw_array_element) + *draw_offset,
+                    count_buffer.internal().buffer,
+                    count_buffer.internal().offset(*count_array_element) + *count_offset,
+                    *max_draw_count as u32,
+                    *draw_stride as u32,
+                );
             }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Clears the layers of a single mip of an image in `TRANSFER_DST_OPTIMAL` layout.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn clear_image(
+        device: &ash::Device,
+        cb: vk::CommandBuffer,
+        image: vk::Image,
+        aspect_mask: vk::ImageAspectFlags,
+        base_array_layer: u32,
+        layer_count: u32,
+        mip_level: u32,
+        value: &ClearColor,
+    ) {
+        let range = [vk::ImageSubresourceRange {
+            aspect_mask,
+            base_mip_level: mip_level,
+            level_count: 1,
+            base_array_layer,
+            layer_count,
+        }];
+
+        match value {
+            ClearColor::RgbaF32(r, g, b, a) => device.cmd_clear_color_image(
+                cb,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    float32: [*r, *g, *b, *a],
+                },
+                &range,
+            ),
+            ClearColor::RU32(r) => device.cmd_clear_color_image(
+                cb,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    uint32: [*r, 0, 0, 0],
+                },
+                &range,
+            ),
+            ClearColor::D32S32(depth, stencil) => device.cmd_clear_depth_stencil_image(
+                cb,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearDepthStencilValue {
+                    depth: *depth,
+                    stencil: *stencil,
+                },
+                &range,
+            ),
         }
     }
 
@@ -2094,9 +3676,37 @@ impl VulkanBackend {
         device: &ash::Device,
         command_idx: usize,
         commands: &[Command<'a, crate::VulkanBackend>],
+        pushed: &PushedSets,
         debug: Option<&VkDebug>,
     ) {
         let mut active_layout = vk::PipelineLayout::default();
+        // If a set bound to the pass can be written to by a dispatch
+        let mut writes = false;
+        // If a dispatch has been recorded since the last barrier
+        let mut dispatched = false;
+
+        let mut layouts = ComputeLayouts::new(commands, command_idx, pushed);
+
+        // Dispatches within the pass don't pass through the command sorter, so they are
+        // synchronized with each other here
+        let barrier = |cb: vk::CommandBuffer, images: &[vk::ImageMemoryBarrier2]| {
+            let barrier = [vk::MemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::COMPUTE_SHADER)
+                .src_access_mask(vk::AccessFlags2::SHADER_WRITE)
+                .dst_stage_mask(
+                    vk::PipelineStageFlags2::COMPUTE_SHADER
+                        | vk::PipelineStageFlags2::DRAW_INDIRECT,
+                )
+                .dst_access_mask(
+                    vk::AccessFlags2::SHADER_READ
+                        | vk::AccessFlags2::SHADER_WRITE
+                        | vk::AccessFlags2::INDIRECT_COMMAND_READ,
+                )];
+            let dependency = vk::DependencyInfo::default()
+                .memory_barriers(&barrier)
+                .image_memory_barriers(images);
+            device.cmd_pipeline_barrier2(cb, &dependency);
+        };
 
         for command in &commands[command_idx..] {
             match command {
@@ -2116,7 +3726,13 @@ impl VulkanBackend {
                         pipeline.internal().pipeline,
                     );
                 }
-                Command::EndComputePass(dispatch, debug_name) => {
+                Command::Dispatch(dispatch) => {
+                    let images = layouts.transitions();
+                    if (writes && dispatched) || !images.is_empty() {
+                        barrier(cb, &images);
+                    }
+                    dispatched = true;
+
                     match dispatch {
                         ComputePassDispatch::Inline(x, y, z) => {
                             device.cmd_dispatch(cb, *x, *y, *z);
@@ -2133,7 +3749,14 @@ impl VulkanBackend {
                             );
                         }
                     }
-
+                }
+                Command::ComputeBarrier => {
+                    if dispatched {
+                        barrier(cb, &[]);
+                        dispatched = false;
+                    }
+                }
+                Command::EndComputePass(debug_name) => {
                     if debug_name.is_some() {
                         if let Some(debug) = debug {
                             debug.device.cmd_end_debug_utils_label(cb);
@@ -2149,8 +3772,10 @@ impl VulkanBackend {
                     data,
                 ),
                 Command::BindDescriptorSets { sets, first, .. } => {
+                    layouts.bind(command, pushed);
                     let mut vk_sets = Vec::with_capacity(sets.len());
                     for set in sets {
+                        writes |= set.internal().writes;
                         vk_sets.push(set.internal().set);
                     }
 
@@ -2164,8 +3789,10 @@ impl VulkanBackend {
                     );
                 }
                 Command::BindDescriptorSetsUnchecked { sets, first, .. } => {
+                    layouts.bind(command, pushed);
                     let mut vk_sets = Vec::with_capacity(sets.len());
                     for set in sets {
+                        writes |= set.internal().writes;
                         vk_sets.push(set.internal().set);
                     }
 
@@ -2178,18 +3805,30 @@ impl VulkanBackend {
                         &[],
                     );
                 }
+                Command::PushDescriptorSet { .. } => {
+                    layouts.bind(command, pushed);
+                    writes |= pushed.get(command).writes;
+                    pushed.record(
+                        device,
+                        cb,
+                        vk::PipelineBindPoint::COMPUTE,
+                        active_layout,
+                        command,
+                    );
+                }
                 _ => unreachable!(),
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     unsafe fn execute_rt_pass<'a>(
         cb: vk::CommandBuffer,
         device: &ash::Device,
         rt_loader: &ash::khr::ray_tracing_pipeline::Device,
-        props: &PhysicalDeviceProperties,
         command_idx: usize,
         commands: &[Command<'a, crate::VulkanBackend>],
+        pushed: &PushedSets,
         debug: Option<&VkDebug>,
     ) {
         let mut active_layout = vk::PipelineLayout::default();
@@ -2213,27 +3852,17 @@ impl VulkanBackend {
                     );
                 }
                 Command::EndRayTracingPass(dispatch, debug_name) => {
-                    let entry_size = props
-                        .shader_group_handle_size
-                        .next_multiple_of(props.shader_group_handle_alignment)
-                        as u64;
-
                     let sbt_base = dispatch.shader_binding_table.device_ref(0);
+                    let to_vk_region = |region: &ShaderBindingTableRegion| {
+                        vk::StridedDeviceAddressRegionKHR::default()
+                            .device_address(sbt_base + region.offset)
+                            .stride(region.stride)
+                            .size(region.size)
+                    };
 
-                    let raygen_region = vk::StridedDeviceAddressRegionKHR::default()
-                        .device_address(sbt_base + dispatch.raygen_offset)
-                        .stride(entry_size)
-                        .size(entry_size);
-
-                    let miss_region = vk::StridedDeviceAddressRegionKHR::default()
-                        .device_address(sbt_base + dispatch.miss_offset)
-                        .stride(entry_size)
-                        .size(entry_size);
-
-                    let hit_region = vk::StridedDeviceAddressRegionKHR::default()
-                        .device_address(sbt_base + dispatch.hit_range.start)
-                        .stride(entry_size)
-                        .size(dispatch.hit_range.end - dispatch.hit_range.start);
+                    let raygen_region = to_vk_region(&dispatch.raygen);
+                    let miss_region = to_vk_region(&dispatch.miss);
+                    let hit_region = to_vk_region(&dispatch.hit);
 
                     match dispatch.src {
                         RayTracingDispatchSource::Inline(x, y, z) => {
@@ -2312,6 +3941,13 @@ impl VulkanBackend {
                         &[],
                     );
                 }
+                Command::PushDescriptorSet { .. } => pushed.record(
+                    device,
+                    cb,
+                    vk::PipelineBindPoint::RAY_TRACING_KHR,
+                    active_layout,
+                    command,
+                ),
                 _ => unreachable!(),
             }
         }
@@ -2321,34 +3957,56 @@ impl VulkanBackend {
 impl Drop for VulkanBackend {
     fn drop(&mut self) {
         unsafe {
-            self.device.device_wait_idle().unwrap();
+            // Waiting fails if the device was lost, but there's nothing left to wait for then
+            let _ = self.device.device_wait_idle();
             let main = self.main.get_mut().unwrap();
             let transfer = self.transfer.get_mut().unwrap();
             let compute = self.compute.get_mut().unwrap();
+            let present = self.present.get_mut().unwrap();
 
             let resc_state = self.resource_state.get_mut().unwrap();
             let mut allocator = self.allocator.lock().unwrap();
             let mut pools = self.pools.lock().unwrap();
             let mut pipelines = self.pipelines.lock().unwrap();
             let mut samplers = self.samplers.lock().unwrap();
+
+            // Background compiles use pipeline layouts and render passes that are about to be
+            // destroyed
+            pipelines.wait_all_compiled();
             let mut queries = self.queries.lock().unwrap();
+            let mut events = self.events.lock().unwrap();
+            let mut transient = self.transient.lock().unwrap();
 
             loop {
+                // A lost device will never signal anything, so everything is treated as complete
                 let current = TimelineValues {
-                    main: main.current_timeline_value(&self.device),
-                    transfer: transfer.current_timeline_value(&self.device),
-                    compute: compute.current_timeline_value(&self.device),
+                    main: main
+                        .current_timeline_value(&self.device)
+                        .unwrap_or(u64::MAX),
+                    transfer: transfer
+                        .current_timeline_value(&self.device)
+                        .unwrap_or(u64::MAX),
+                    compute: compute
+                        .current_timeline_value(&self.device)
+                        .unwrap_or(u64::MAX),
+                    present: present
+                        .current_timeline_value(&self.device)
+                        .unwrap_or(u64::MAX),
                 };
 
                 let target = TimelineValues {
                     main: main.target_timeline_value(),
                     transfer: transfer.target_timeline_value(),
                     compute: compute.target_timeline_value(),
+                    present: present.target_timeline_value(),
                 };
 
                 self.garbage.cleanup(GarbageCleanupArgs {
                     device: &self.device,
                     as_loader: &self.as_loader,
+                    swapchain_loader: &self.swapchain_loader,
+                    surface_loader: &self.surface_loader,
+                    framebuffers: &self.framebuffers,
                     buffer_ids: &self.buffer_ids,
                     image_ids: &self.image_ids,
                     set_ids: &self.set_ids,
@@ -2356,6 +4014,8 @@ impl Drop for VulkanBackend {
                     pools: &mut pools,
                     pipelines: &mut pipelines,
                     queries: &mut queries,
+                    events: &mut events,
+                    transient: &mut transient,
                     global_usage: resc_state,
                     current,
                     target,
@@ -2367,7 +4027,12 @@ impl Drop for VulkanBackend {
             }
 
             queries.release(&self.device);
+            events.release(&self.device);
+            transient.release(&self.device, &mut allocator);
             pools.release(&self.device);
+            if let Err(err) = pipelines.save(&self.device) {
+                ard_log::warn!("unable to save pipeline cache: {err}");
+            }
             pipelines.release_all(&self.device);
             samplers.release(&self.device);
             std::mem::drop(allocator);
@@ -2390,11 +4055,12 @@ impl Drop for VulkanBackend {
 }
 
 impl QueueFamilyIndices {
-    // Returns `None` if we can't fill out all queue family types.
+    // Returns `None` if we can't fill out all queue family types. Without `presentation_support`
+    // the present queue is the main queue.
     fn find(
         instance: &ash::Instance,
         device: vk::PhysicalDevice,
-        presentation_support: &impl Fn(vk::PhysicalDevice, u32) -> bool,
+        presentation_support: Option<&dyn Fn(vk::PhysicalDevice, u32) -> bool>,
     ) -> Option<QueueFamilyIndices> {
         let mut properties =
             unsafe { instance.get_physical_device_queue_family_properties(device) };
@@ -2421,23 +4087,28 @@ impl QueueFamilyIndices {
         properties[main].queue_count -= 1;
 
         // Find presentation queue. Would be nice to be different from main.
-        for (family_idx, _) in properties.iter().enumerate() {
-            let surface_support = presentation_support(device, family_idx as u32);
+        match presentation_support {
+            Some(presentation_support) => {
+                for (family_idx, _) in properties.iter().enumerate() {
+                    let surface_support = presentation_support(device, family_idx as u32);
+
+                    if surface_support && properties[family_idx].queue_count > 0 {
+                        present = family_idx;
+                        if family_idx != main {
+                            break;
+                        }
+                    }
+                }
 
-            if surface_support && properties[family_idx].queue_count > 0 {
-                present = family_idx;
-                if family_idx != main {
-                    break;
+                if present == usize::MAX {
+                    return None;
                 }
-            }
-        }
 
-        if present == usize::MAX {
-            return None;
+                properties[present].queue_count -= 1;
+            }
+            None => present = main,
         }
 
-        properties[present].queue_count -= 1;
-
         // Look for a dedicated transfer queue. Supported on some devices. Fallback is main.
         for (family_idx, family) in properties.iter().enumerate() {
             if family.queue_flags.contains(vk::QueueFlags::TRANSFER)
@@ -2521,79 +4192,476 @@ impl QueueFamilyIndices {
 unsafe impl Send for VulkanBackend {}
 unsafe impl Sync for VulkanBackend {}
 
-unsafe fn pick_physical_device(
+/// Creates a closure that checks if a queue family of a physical device can present to the
+/// provided display.
+unsafe fn presentation_support(
+    entry: &ash::Entry,
+    instance: &ash::Instance,
+    display: RawDisplayHandle,
+) -> Result<Box<dyn Fn(vk::PhysicalDevice, u32) -> bool>, VulkanBackendCreateError> {
+    match display {
+        RawDisplayHandle::Windows(_) => {
+            let surface_instance = ash::khr::win32_surface::Instance::new(entry, instance);
+            Ok(Box::new(move |physical_device, queue_family_index| {
+                surface_instance.get_physical_device_win32_presentation_support(
+                    physical_device,
+                    queue_family_index,
+                )
+            }))
+        }
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        ))]
+        RawDisplayHandle::Xlib(handle) => {
+            let display = match handle.display {
+                Some(display) => display.as_ptr(),
+                None => {
+                    return Err(VulkanBackendCreateError::UnsupportedDisplay(String::from(
+                        "xlib display handle has no display",
+                    )))
+                }
+            };
+
+            // Presentation support is queried against the default visual of the screen
+            let xlib = match x11_dl::xlib::Xlib::open() {
+                Ok(xlib) => xlib,
+                Err(err) => {
+                    return Err(VulkanBackendCreateError::UnsupportedDisplay(
+                        err.to_string(),
+                    ))
+                }
+            };
+            let visual = (xlib.XDefaultVisual)(display.cast(), handle.screen);
+            let visual_id = (xlib.XVisualIDFromVisual)(visual) as vk::VisualID;
+
+            let surface_instance = ash::khr::xlib_surface::Instance::new(entry, instance);
+            Ok(Box::new(move |physical_device, queue_family_index| {
+                surface_instance.get_physical_device_xlib_presentation_support(
+                    physical_device,
+                    queue_family_index,
+                    display.cast(),
+                    visual_id,
+                )
+            }))
+        }
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        ))]
+        RawDisplayHandle::Xcb(handle) => {
+            use x11rb::connection::Connection;
+
+            let connection = match handle.connection {
+                Some(connection) => connection.as_ptr(),
+                None => {
+                    return Err(VulkanBackendCreateError::UnsupportedDisplay(String::from(
+                        "xcb display handle has no connection",
+                    )))
+                }
+            };
+
+            // Presentation support is queried against the root visual of the screen. The
+            // connection is borrowed, so it must not be dropped here
+            let visual_id = {
+                let conn =
+                    match x11rb::xcb_ffi::XCBConnection::from_raw_xcb_connection(connection, false)
+                    {
+                        Ok(conn) => conn,
+                        Err(err) => {
+                            return Err(VulkanBackendCreateError::UnsupportedDisplay(
+                                err.to_string(),
+                            ))
+                        }
+                    };
+
+                match conn.setup().roots.get(handle.screen as usize) {
+                    Some(screen) => screen.root_visual,
+                    None => {
+                        return Err(VulkanBackendCreateError::UnsupportedDisplay(format!(
+                            "xcb screen {} does not exist",
+                            handle.screen
+                        )))
+                    }
+                }
+            };
+
+            let surface_instance = ash::khr::xcb_surface::Instance::new(entry, instance);
+            Ok(Box::new(move |physical_device, queue_family_index| {
+                surface_instance.get_physical_device_xcb_presentation_support(
+                    physical_device,
+                    queue_family_index,
+                    &mut *connection.cast(),
+                    visual_id,
+                )
+            }))
+        }
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+        ))]
+        RawDisplayHandle::Wayland(handle) => {
+            let display = handle.display.as_ptr();
+            let surface_instance = ash::khr::wayland_surface::Instance::new(entry, instance);
+            Ok(Box::new(move |physical_device, queue_family_index| {
+                surface_instance.get_physical_device_wayland_presentation_support(
+                    physical_device,
+                    queue_family_index,
+                    &mut *display.cast(),
+                )
+            }))
+        }
+        other => Err(VulkanBackendCreateError::UnsupportedDisplay(format!(
+            "{other:?}"
+        ))),
+    }
+}
+
+/// Queries every physical device along with a description of it. Devices that don't meet the
+/// requirements of the backend have no query.
+unsafe fn query_physical_devices(
     instance: &ash::Instance,
-    presentation_support: impl Fn(vk::PhysicalDevice, u32) -> bool,
+    presentation_support: Option<&dyn Fn(vk::PhysicalDevice, u32) -> bool>,
     extensions: &[*const i8],
-) -> Option<PhysicalDeviceQuery> {
+) -> Vec<(AdapterInfo, Option<PhysicalDeviceQuery>)> {
     let devices = match instance.enumerate_physical_devices() {
         Ok(devices) => devices,
-        Err(_) => return None,
+        Err(_) => return Vec::default(),
     };
 
-    let mut device_type = vk::PhysicalDeviceType::OTHER;
-    let mut query = None;
+    let mut adapters = Vec::with_capacity(devices.len());
     for device in devices {
         let mut mesh_shading_properties = vk::PhysicalDeviceMeshShaderPropertiesEXT::default();
         let mut rt_props = vk::PhysicalDeviceRayTracingPipelinePropertiesKHR::default();
         let mut accel_struct_props =
             vk::PhysicalDeviceAccelerationStructurePropertiesKHR::default();
+        let mut push_descriptor_props = vk::PhysicalDevicePushDescriptorPropertiesKHR::default();
+        let mut texel_buffer_alignment =
+            vk::PhysicalDeviceTexelBufferAlignmentProperties::default();
 
         let mut properties = vk::PhysicalDeviceProperties2::default()
             .push_next(&mut mesh_shading_properties)
             .push_next(&mut rt_props)
-            .push_next(&mut accel_struct_props);
+            .push_next(&mut accel_struct_props)
+            .push_next(&mut push_descriptor_props)
+            .push_next(&mut texel_buffer_alignment);
 
         instance.get_physical_device_properties2(device, &mut properties);
         let features = instance.get_physical_device_features(device);
+        let mut info = adapter_info(instance, device);
 
         // Must support requested extensions
         if let Some(missing) = check_device_extensions(instance, device, extensions) {
-            let device_name = CStr::from_bytes_until_nul(bytemuck::cast_slice(
-                properties.properties.device_name.as_slice(),
-            ))
-            .unwrap();
-            ard_log::info!("{device_name:?} missing ext {missing}");
+            ard_log::info!("{:?} missing ext {missing}", info.name);
+            adapters.push((info, None));
             continue;
         }
 
         // Must support all queue family indices
-        let qfi = QueueFamilyIndices::find(instance, device, &presentation_support);
-        if qfi.is_none() {
-            continue;
-        }
+        let qfi = match QueueFamilyIndices::find(instance, device, presentation_support) {
+            Some(qfi) => qfi,
+            None => {
+                adapters.push((info, None));
+                continue;
+            }
+        };
 
-        // Pick this device if it's better than the old one
-        if device_type_rank(properties.properties.device_type) >= device_type_rank(device_type) {
-            let limits = properties.properties.limits;
+        let limits = properties.properties.limits;
+        let vendor_id = properties.properties.vendor_id;
+        let device_id = properties.properties.device_id;
+        let pipeline_cache_uuid = properties.properties.pipeline_cache_uuid;
+
+        // Sparse binds are performed on the transfer queue
+        let families = instance.get_physical_device_queue_family_properties(device);
+        let sparse_residency = features.sparse_binding == vk::TRUE
+            && features.sparse_residency_image2_d == vk::TRUE
+            && families[qfi.transfer as usize]
+                .queue_flags
+                .contains(vk::QueueFlags::SPARSE_BINDING);
+
+        // Push descriptors are optional and emulated when missing
+        let push_descriptors = check_device_extensions(
+            instance,
+            device,
+            &[ash::khr::push_descriptor::NAME.as_ptr()],
+        )
+        .is_none();
 
-            device_type = properties.properties.device_type;
-            query = Some(PhysicalDeviceQuery {
-                device,
-                features,
-                properties: PhysicalDeviceProperties {
-                    shader_group_handle_size: rt_props.shader_group_handle_size,
-                    shader_group_handle_alignment: rt_props.shader_group_handle_alignment,
-                    max_preferred_mesh_work_group_invocations: mesh_shading_properties
-                        .max_preferred_mesh_work_group_invocations,
-                    max_preferred_task_work_group_invocations: mesh_shading_properties
-                        .max_preferred_task_work_group_invocations,
-                    min_acceleration_structure_scratch_offset_alignment: accel_struct_props
-                        .min_acceleration_structure_scratch_offset_alignment,
-                    shader_group_base_alignment: rt_props.shader_group_base_alignment,
-                    limits,
-                },
-                queue_family_indices: qfi.unwrap(),
-            });
-        }
+        // Optional sampler features
+        let mut features12 = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features2 = vk::PhysicalDeviceFeatures2::default().push_next(&mut features12);
+        instance.get_physical_device_features2(device, &mut features2);
+        let sampler_filter_minmax = features12.sampler_filter_minmax == vk::TRUE;
+
+        let custom_border_color = check_device_extensions(
+            instance,
+            device,
+            &[ash::ext::custom_border_color::NAME.as_ptr()],
+        )
+        .is_none()
+            && {
+                let mut cbc_features = vk::PhysicalDeviceCustomBorderColorFeaturesEXT::default();
+                let mut features2 =
+                    vk::PhysicalDeviceFeatures2::default().push_next(&mut cbc_features);
+                instance.get_physical_device_features2(device, &mut features2);
+                cbc_features.custom_border_colors == vk::TRUE
+                    && cbc_features.custom_border_color_without_format == vk::TRUE
+            };
+
+        let conditional_rendering = check_device_extensions(
+            instance,
+            device,
+            &[ash::ext::conditional_rendering::NAME.as_ptr()],
+        )
+        .is_none()
+            && {
+                let mut cr_features = vk::PhysicalDeviceConditionalRenderingFeaturesEXT::default();
+                let mut features2 =
+                    vk::PhysicalDeviceFeatures2::default().push_next(&mut cr_features);
+                instance.get_physical_device_features2(device, &mut features2);
+                cr_features.conditional_rendering == vk::TRUE
+                    && cr_features.inherited_conditional_rendering == vk::TRUE
+            };
+
+        let ray_query =
+            check_device_extensions(instance, device, &[ash::khr::ray_query::NAME.as_ptr()])
+                .is_none()
+                && {
+                    let mut rq_features = vk::PhysicalDeviceRayQueryFeaturesKHR::default();
+                    let mut features2 =
+                        vk::PhysicalDeviceFeatures2::default().push_next(&mut rq_features);
+                    instance.get_physical_device_features2(device, &mut features2);
+                    rq_features.ray_query == vk::TRUE
+                };
+
+        let memory_budget =
+            check_device_extensions(instance, device, &[ash::ext::memory_budget::NAME.as_ptr()])
+                .is_none();
+
+        let query = PhysicalDeviceQuery {
+            device,
+            features,
+            properties: PhysicalDeviceProperties {
+                shader_group_handle_size: rt_props.shader_group_handle_size,
+                shader_group_handle_alignment: rt_props.shader_group_handle_alignment,
+                max_preferred_mesh_work_group_invocations: mesh_shading_properties
+                    .max_preferred_mesh_work_group_invocations,
+                max_preferred_task_work_group_invocations: mesh_shading_properties
+                    .max_preferred_task_work_group_invocations,
+                min_acceleration_structure_scratch_offset_alignment: accel_struct_props
+                    .min_acceleration_structure_scratch_offset_alignment,
+                shader_group_base_alignment: rt_props.shader_group_base_alignment,
+                max_shader_group_stride: rt_props.max_shader_group_stride,
+                uniform_texel_buffer_offset_alignment: texel_buffer_alignment
+                    .uniform_texel_buffer_offset_alignment_bytes,
+                uniform_texel_buffer_offset_single_texel_alignment: texel_buffer_alignment
+                    .uniform_texel_buffer_offset_single_texel_alignment
+                    == vk::TRUE,
+                storage_texel_buffer_offset_alignment: texel_buffer_alignment
+                    .storage_texel_buffer_offset_alignment_bytes,
+                storage_texel_buffer_offset_single_texel_alignment: texel_buffer_alignment
+                    .storage_texel_buffer_offset_single_texel_alignment
+                    == vk::TRUE,
+                limits,
+                vendor_id,
+                device_id,
+                pipeline_cache_uuid,
+            },
+            queue_family_indices: qfi,
+            sparse_residency,
+            sampler_filter_minmax,
+            custom_border_color,
+            conditional_rendering,
+            ray_query,
+            memory_budget,
+            max_push_descriptors: push_descriptors
+                .then_some(push_descriptor_props.max_push_descriptors),
+        };
+
+        info.supported = true;
+        info.features = query.graphics_features();
+        adapters.push((info, Some(query)));
+    }
+
+    adapters
+}
+
+/// Describes a physical device.
+unsafe fn adapter_info(instance: &ash::Instance, device: vk::PhysicalDevice) -> AdapterInfo {
+    let mut driver_props = vk::PhysicalDeviceDriverProperties::default();
+    let mut properties2 = vk::PhysicalDeviceProperties2::default().push_next(&mut driver_props);
+    instance.get_physical_device_properties2(device, &mut properties2);
+    let properties = properties2.properties;
+
+    let name = properties
+        .device_name_as_c_str()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    // The encoding of the version is vendor specific, so prefer the driver's own description
+    let driver_version = match driver_props.driver_info_as_c_str() {
+        Ok(info) if !info.is_empty() => info.to_string_lossy().into_owned(),
+        _ => format!(
+            "{}.{}.{}",
+            vk::api_version_major(properties.driver_version),
+            vk::api_version_minor(properties.driver_version),
+            vk::api_version_patch(properties.driver_version)
+        ),
+    };
+
+    let memory = instance.get_physical_device_memory_properties(device);
+    let vram = memory
+        .memory_heaps_as_slice()
+        .iter()
+        .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+        .map(|heap| heap.size)
+        .sum();
+
+    AdapterInfo {
+        name,
+        vendor_id: properties.vendor_id,
+        device_id: properties.device_id,
+        ty: match properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => AdapterType::Discrete,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => AdapterType::Integrated,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => AdapterType::Virtual,
+            vk::PhysicalDeviceType::CPU => AdapterType::Cpu,
+            _ => AdapterType::Other,
+        },
+        driver_version,
+        vram,
+        supported: false,
+        features: GraphicsFeatures::default(),
     }
+}
+
+/// Finds the page size of a 2D sparse texture for every format that supports sparse residency.
+unsafe fn sparse_granularity(
+    instance: &ash::Instance,
+    device: vk::PhysicalDevice,
+) -> Vec<(Format, (u32, u32, u32))> {
+    Format::ALL
+        .iter()
+        .filter_map(|format| {
+            let properties = instance.get_physical_device_sparse_image_format_properties(
+                device,
+                crate::util::to_vk_format(*format),
+                vk::ImageType::TYPE_2D,
+                vk::SampleCountFlags::TYPE_1,
+                vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+                vk::ImageTiling::OPTIMAL,
+            );
+            properties.first().map(|properties| {
+                let granularity = properties.image_granularity;
+                (
+                    *format,
+                    (granularity.width, granularity.height, granularity.depth),
+                )
+            })
+        })
+        .collect()
+}
 
-    query
+/// Finds the supported usages of every format with optimal tiling and as texel buffers.
+/// Compressed formats are only supported when their compression feature is, since they can't be
+/// used otherwise.
+unsafe fn format_support(
+    instance: &ash::Instance,
+    device: vk::PhysicalDevice,
+    features: &vk::PhysicalDeviceFeatures,
+) -> Vec<(Format, FormatSupport)> {
+    Format::ALL
+        .iter()
+        .filter(|format| match format {
+            Format::Astc4x4Unorm
+            | Format::Astc4x4Srgb
+            | Format::Astc8x8Unorm
+            | Format::Astc8x8Srgb => features.texture_compression_astc_ldr == vk::TRUE,
+            Format::Etc2Rgb8Unorm
+            | Format::Etc2Rgb8Srgb
+            | Format::Etc2Rgba8Unorm
+            | Format::Etc2Rgba8Srgb => features.texture_compression_etc2 == vk::TRUE,
+            _ => true,
+        })
+        .filter_map(|format| {
+            let properties = instance
+                .get_physical_device_format_properties(device, crate::util::to_vk_format(*format));
+            let support = crate::util::from_vk_format_features(
+                properties.optimal_tiling_features,
+                properties.buffer_features,
+            );
+            (!support.is_empty()).then_some((*format, support))
+        })
+        .collect()
 }
 
 /// Check that a physical devices supports required device extensions.
 ///
 /// Returns `None` on a success, or `Some` containing the name of the missing extension.
+fn device_limits(properties: &PhysicalDeviceProperties) -> DeviceLimits {
+    let limits = &properties.limits;
+    DeviceLimits {
+        max_push_constants_size: limits.max_push_constants_size,
+        max_texture_dimension_1d: limits.max_image_dimension1_d,
+        max_texture_dimension_2d: limits.max_image_dimension2_d,
+        max_texture_dimension_3d: limits.max_image_dimension3_d,
+        max_cube_map_dimension: limits.max_image_dimension_cube,
+        max_texture_array_elements: limits.max_image_array_layers,
+        max_bound_descriptor_sets: limits.max_bound_descriptor_sets,
+        max_per_stage_descriptor_samplers: limits.max_per_stage_descriptor_samplers,
+        max_per_stage_descriptor_uniform_buffers: limits.max_per_stage_descriptor_uniform_buffers,
+        max_per_stage_descriptor_storage_buffers: limits.max_per_stage_descriptor_storage_buffers,
+        max_per_stage_descriptor_sampled_images: limits.max_per_stage_descriptor_sampled_images,
+        max_per_stage_descriptor_storage_images: limits.max_per_stage_descriptor_storage_images,
+        max_storage_buffer_range: limits.max_storage_buffer_range,
+        max_compute_work_group_count: limits.max_compute_work_group_count,
+        max_compute_work_group_size: limits.max_compute_work_group_size,
+        max_compute_work_group_invocations: limits.max_compute_work_group_invocations,
+        min_uniform_buffer_offset_alignment: limits.min_uniform_buffer_offset_alignment,
+        min_storage_buffer_offset_alignment: limits.min_storage_buffer_offset_alignment,
+        min_uniform_texel_buffer_offset_alignment: properties.uniform_texel_buffer_offset_alignment,
+        uniform_texel_buffer_offset_single_texel_alignment: properties
+            .uniform_texel_buffer_offset_single_texel_alignment,
+        min_storage_texel_buffer_offset_alignment: properties.storage_texel_buffer_offset_alignment,
+        storage_texel_buffer_offset_single_texel_alignment: properties
+            .storage_texel_buffer_offset_single_texel_alignment,
+        max_texel_buffer_elements: limits.max_texel_buffer_elements,
+        optimal_buffer_copy_offset_alignment: limits.optimal_buffer_copy_offset_alignment,
+        optimal_buffer_copy_row_pitch_alignment: limits.optimal_buffer_copy_row_pitch_alignment,
+        max_sampler_anisotropy: limits.max_sampler_anisotropy,
+        timestamp_period: limits.timestamp_period,
+    }
+}
+
+/// Device extensions the backend can't run without.
+fn required_device_extensions(swapchain: bool) -> Vec<*const i8> {
+    let mut extensions = vec![
+        ash::ext::mesh_shader::NAME,
+        ash::khr::acceleration_structure::NAME,
+        ash::khr::ray_tracing_pipeline::NAME,
+        ash::khr::deferred_host_operations::NAME,
+        ash::ext::pipeline_library_group_handles::NAME,
+        c"VK_KHR_pipeline_library",
+        c"VK_KHR_ray_tracing_maintenance1",
+    ];
+    if swapchain {
+        extensions.push(ash::khr::swapchain::NAME);
+    }
+    extensions
+        .into_iter()
+        .map(|r| r.as_ptr())
+        .collect::<Vec<_>>()
+}
+
+fn list_adapters(adapters: &[AdapterInfo]) -> String {
+    adapters
+        .iter()
+        .enumerate()
+        .map(|(idx, adapter)| format!("[{idx}] {adapter}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 unsafe fn check_device_extensions(
     instance: &ash::Instance,
     device: vk::PhysicalDevice,
@@ -2625,84 +4693,6 @@ unsafe fn check_device_extensions(
     None
 }
 
-fn device_type_rank(ty: vk::PhysicalDeviceType) -> u32 {
-    match ty {
-        vk::PhysicalDeviceType::DISCRETE_GPU => 4,
-        vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
-        vk::PhysicalDeviceType::CPU => 2,
-        vk::PhysicalDeviceType::VIRTUAL_GPU => 1,
-        _ => 0,
-    }
-}
-
-unsafe extern "system" fn vulkan_debug_callback(
-    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
-    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _user_data: *mut std::os::raw::c_void,
-) -> vk::Bool32 {
-    let callback_data = *p_callback_data;
-    let message_id_number = callback_data.message_id_number;
-
-    match message_id_number {
-        // Ignore `OutputNotConsumed` warnings
-        101294395 => return vk::FALSE,
-        // Ignore false positive from depth ms resolve
-        -2069901625 => return vk::FALSE,
-        _ => {}
-    };
-
-    let message_id_name = if callback_data.p_message_id_name.is_null() {
-        Cow::from("")
-    } else {
-        CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
-    };
-
-    let message = if callback_data.p_message.is_null() {
-        Cow::from("")
-    } else {
-        CStr::from_ptr(callback_data.p_message).to_string_lossy()
-    };
-
-    match message_severity {
-        DebugUtilsMessageSeverityFlagsEXT::VERBOSE => print!(
-            "{:?}:\n{:?} [{} ({})] : {}\n",
-            message_severity,
-            message_type,
-            message_id_name,
-            &message_id_number.to_string(),
-            message,
-        ),
-        DebugUtilsMessageSeverityFlagsEXT::INFO => print!(
-            "{:?}:\n{:?} [{} ({})] : {}\n",
-            message_severity,
-            message_type,
-            message_id_name,
-            &message_id_number.to_string(),
-            message,
-        ),
-        DebugUtilsMessageSeverityFlagsEXT::WARNING => print!(
-            "{:?}:\n{:?} [{} ({})] : {}\n",
-            message_severity,
-            message_type,
-            message_id_name,
-            &message_id_number.to_string(),
-            message,
-        ),
-        DebugUtilsMessageSeverityFlagsEXT::ERROR => print!(
-            "{:?}:\n{:?} [{} ({})] : {}\n",
-            message_severity,
-            message_type,
-            message_id_name,
-            &message_id_number.to_string(),
-            message,
-        ),
-        _ => {}
-    }
-
-    vk::FALSE
-}
-
 impl From<vk::Result> for VulkanBackendCreateError {
     fn from(res: vk::Result) -> Self {
         VulkanBackendCreateError::Vulkan(res)
@@ -1,9 +1,12 @@
 use std::{collections::VecDeque, ffi::CString};
 
 use api::types::QueueType;
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 
-use crate::util::semaphores::{SemaphoreTracker, WaitInfo};
+use crate::util::{
+    semaphores::{SemaphoreTracker, WaitInfo},
+    sparse::SparseBinds,
+};
 
 pub(crate) struct VkQueue {
     pub queue: vk::Queue,
@@ -20,6 +23,18 @@ pub(crate) struct VkQueue {
     target_value: u64,
     /// The last timeline value this queue was synced on the CPU to.
     cpu_sync_value: u64,
+    /// Queue family used to create new command pools.
+    queue_family: u32,
+    /// Pools for secondary command buffers. Each pool is only ever recorded to by one thread at a
+    /// time, so command buffers from different pools can be recorded in parallel.
+    secondary_pools: Vec<SecondaryCommandPool>,
+    /// Secondary command buffers allocated since the last submit along with their pool.
+    pending_secondary: Vec<(usize, vk::CommandBuffer)>,
+}
+
+struct SecondaryCommandPool {
+    pool: vk::CommandPool,
+    free: VecDeque<ActiveCommandBuffer>,
 }
 
 struct ActiveCommandBuffer {
@@ -104,6 +119,9 @@ impl VkQueue {
             command_buffer_count: 0,
             target_value: 0,
             cpu_sync_value: 0,
+            queue_family,
+            secondary_pools: Vec::default(),
+            pending_secondary: Vec::default(),
         })
     }
 
@@ -128,17 +146,18 @@ impl VkQueue {
     }
 
     #[inline(always)]
-    pub unsafe fn current_timeline_value(&self, device: &ash::Device) -> u64 {
-        device.get_semaphore_counter_value(self.semaphore).unwrap()
+    pub unsafe fn current_timeline_value(&self, device: &ash::Device) -> VkResult<u64> {
+        device.get_semaphore_counter_value(self.semaphore)
     }
 
+    /// Fails if the current timeline value can't be read, which happens when the device is lost.
     pub unsafe fn allocate_command_buffer(
         &mut self,
         device: &ash::Device,
         debug: Option<&ash::ext::debug_utils::Device>,
-    ) -> vk::CommandBuffer {
+    ) -> VkResult<vk::CommandBuffer> {
         // Check current timeline value
-        let cur_value = device.get_semaphore_counter_value(self.semaphore).unwrap();
+        let cur_value = device.get_semaphore_counter_value(self.semaphore)?;
 
         // Attempt to get free command buffer
         let command_buffer = if let Some(free) = self.free.front() {
@@ -151,7 +170,7 @@ impl VkQueue {
             None
         };
 
-        match command_buffer {
+        Ok(match command_buffer {
             Some(cb) => cb.command_buffer,
             // If there was no free command buffer, we will allocate one
             None => {
@@ -192,7 +211,54 @@ impl VkQueue {
                 self.command_buffer_count += 1;
                 cb
             }
+        })
+    }
+
+    /// Allocates `count` secondary command buffers, each from a different pool, so that they can
+    /// be recorded in parallel. The command buffers are recycled once the next submission on this
+    /// queue completes.
+    pub unsafe fn allocate_secondary_command_buffers(
+        &mut self,
+        device: &ash::Device,
+        count: usize,
+    ) -> Vec<vk::CommandBuffer> {
+        // The primary command buffer would have failed to allocate if the device was lost, so
+        // nothing is recycled if it's lost while recording
+        let cur_value = device
+            .get_semaphore_counter_value(self.semaphore)
+            .unwrap_or(0);
+
+        let mut command_buffers = Vec::with_capacity(count);
+        for i in 0..count {
+            if i == self.secondary_pools.len() {
+                let create_info = vk::CommandPoolCreateInfo::default()
+                    .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+                    .queue_family_index(self.queue_family);
+                self.secondary_pools.push(SecondaryCommandPool {
+                    pool: device.create_command_pool(&create_info, None).unwrap(),
+                    free: VecDeque::default(),
+                });
+            }
+
+            let pool = &mut self.secondary_pools[i];
+            let command_buffer = match pool.free.front() {
+                Some(free) if cur_value >= free.target => {
+                    pool.free.pop_front().unwrap().command_buffer
+                }
+                _ => {
+                    let alloc_info = vk::CommandBufferAllocateInfo::default()
+                        .command_buffer_count(1)
+                        .command_pool(pool.pool)
+                        .level(vk::CommandBufferLevel::SECONDARY);
+                    device.allocate_command_buffers(&alloc_info).unwrap()[0]
+                }
+            };
+
+            self.pending_secondary.push((i, command_buffer));
+            command_buffers.push(command_buffer);
         }
+
+        command_buffers
     }
 
     pub unsafe fn submit(
@@ -200,7 +266,7 @@ impl VkQueue {
         device: &ash::Device,
         command_buffer: vk::CommandBuffer,
         mut semaphore_tracker: SemaphoreTracker,
-    ) -> ash::prelude::VkResult<()> {
+    ) -> VkResult<()> {
         // Always signal and wait on ourselves
         semaphore_tracker.register_wait(
             self.semaphore,
@@ -219,6 +285,15 @@ impl VkQueue {
             target: self.target_value,
         });
 
+        for (pool, command_buffer) in self.pending_secondary.drain(..) {
+            self.secondary_pools[pool]
+                .free
+                .push_back(ActiveCommandBuffer {
+                    command_buffer,
+                    target: self.target_value,
+                });
+        }
+
         // Semaphores
         let mut signals = Vec::with_capacity(semaphores.signals.len());
         let mut signal_values = Vec::with_capacity(semaphores.signals.len());
@@ -252,8 +327,72 @@ impl VkQueue {
         device.queue_submit(self.queue, &submit_info, vk::Fence::null())
     }
 
+    /// Binds memory to sparse images. Like command buffer submission, this waits on all previous
+    /// work on this queue and increments the timeline value.
+    pub unsafe fn bind_sparse(
+        &mut self,
+        device: &ash::Device,
+        binds: &SparseBinds,
+        mut semaphore_tracker: SemaphoreTracker,
+    ) -> VkResult<()> {
+        semaphore_tracker.register_wait(
+            self.semaphore,
+            WaitInfo {
+                value: Some(self.target_value),
+                stage: vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            },
+        );
+        semaphore_tracker.register_signal(self.semaphore, Some(self.target_value + 1));
+        self.target_value += 1;
+        let semaphores = semaphore_tracker.finish();
+
+        let (waits, wait_values): (Vec<_>, Vec<_>) = semaphores
+            .waits
+            .iter()
+            .map(|(semaphore, info)| (*semaphore, info.value.unwrap_or_default()))
+            .unzip();
+        let (signals, signal_values): (Vec<_>, Vec<_>) = semaphores
+            .signals
+            .iter()
+            .map(|(semaphore, value)| (*semaphore, value.unwrap_or_default()))
+            .unzip();
+
+        let image_binds: Vec<_> = binds
+            .image
+            .iter()
+            .map(|(image, binds)| {
+                vk::SparseImageMemoryBindInfo::default()
+                    .image(*image)
+                    .binds(binds)
+            })
+            .collect();
+        let opaque_binds: Vec<_> = binds
+            .opaque
+            .iter()
+            .map(|(image, binds)| {
+                vk::SparseImageOpaqueMemoryBindInfo::default()
+                    .image(*image)
+                    .binds(binds)
+            })
+            .collect();
+
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::default()
+            .signal_semaphore_values(&signal_values)
+            .wait_semaphore_values(&wait_values);
+        let bind_info = [vk::BindSparseInfo::default()
+            .image_binds(&image_binds)
+            .image_opaque_binds(&opaque_binds)
+            .wait_semaphores(&waits)
+            .signal_semaphores(&signals)
+            .push_next(&mut timeline_info)];
+        device.queue_bind_sparse(self.queue, &bind_info, vk::Fence::null())
+    }
+
     pub unsafe fn release(&self, device: &ash::Device) {
         device.destroy_command_pool(self.command_pool, None);
+        for pool in &self.secondary_pools {
+            device.destroy_command_pool(pool.pool, None);
+        }
         device.destroy_semaphore(self.semaphore, None);
     }
 }
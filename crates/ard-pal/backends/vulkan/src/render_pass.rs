@@ -11,6 +11,18 @@ use bytemuck::{Pod, Zeroable};
 use dashmap::DashMap;
 use rustc_hash::FxHashMap;
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DrawIndirect {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+unsafe impl Pod for DrawIndirect {}
+unsafe impl Zeroable for DrawIndirect {}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct DrawIndexedIndirect {
@@ -57,7 +69,8 @@ pub(crate) struct VkRenderPassDescriptor {
     pub color_resolve_attachments: Vec<VkAttachment>,
     pub depth_stencil_attachment: Option<VkAttachment>,
     pub depth_stencil_resolve_attachment: Option<VkAttachment>,
-    pub is_cube_render: bool,
+    pub view_mask: u32,
+    pub correlation_masks: Vec<u32>,
 }
 
 #[derive(Hash, PartialEq, Eq)]
@@ -67,6 +80,8 @@ pub(crate) struct VkAttachment {
     pub final_layout: vk::ImageLayout,
     pub load_op: vk::AttachmentLoadOp,
     pub store_op: vk::AttachmentStoreOp,
+    pub stencil_load_op: vk::AttachmentLoadOp,
+    pub stencil_store_op: vk::AttachmentStoreOp,
     pub samples: vk::SampleCountFlags,
     pub resolve_src: usize,
 }
@@ -82,7 +97,6 @@ impl RenderPassCache {
         let descriptor = VkRenderPassDescriptor::from_descriptor(pass);
         *self.passes.entry(descriptor).or_insert_with(|| {
             let mut sample_count = vk::SampleCountFlags::TYPE_1;
-            let mut is_cube_render = false;
 
             // Create attachment descriptors
             let mut attachments = Vec::with_capacity(
@@ -91,14 +105,15 @@ impl RenderPassCache {
             for attachment in &pass.color_attachments {
                 let final_layout = match &attachment.dst {
                     ColorAttachmentDestination::SurfaceImage(_) => vk::ImageLayout::PRESENT_SRC_KHR,
-                    ColorAttachmentDestination::Texture { .. } => {
+                    ColorAttachmentDestination::Texture { .. }
+                    | ColorAttachmentDestination::TextureSlice { .. }
+                    | ColorAttachmentDestination::TextureArray { .. } => {
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
                     }
                     ColorAttachmentDestination::CubeFace { .. } => {
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
                     }
                     ColorAttachmentDestination::CubeMap { .. } => {
-                        is_cube_render = true;
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
                     }
                 };
@@ -115,7 +130,9 @@ impl RenderPassCache {
                             ColorAttachmentDestination::SurfaceImage(image) => {
                                 image.internal().format()
                             }
-                            ColorAttachmentDestination::Texture { texture, .. } => {
+                            ColorAttachmentDestination::Texture { texture, .. }
+                            | ColorAttachmentDestination::TextureSlice { texture, .. }
+                            | ColorAttachmentDestination::TextureArray { texture, .. } => {
                                 texture.internal().format
                             }
                             ColorAttachmentDestination::CubeFace { cube_map, .. } => {
@@ -132,14 +149,15 @@ impl RenderPassCache {
             for attachment in &pass.color_resolve_attachments {
                 let final_layout = match &attachment.dst {
                     ColorAttachmentDestination::SurfaceImage(_) => vk::ImageLayout::PRESENT_SRC_KHR,
-                    ColorAttachmentDestination::Texture { .. } => {
+                    ColorAttachmentDestination::Texture { .. }
+                    | ColorAttachmentDestination::TextureSlice { .. }
+                    | ColorAttachmentDestination::TextureArray { .. } => {
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
                     }
                     ColorAttachmentDestination::CubeFace { .. } => {
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
                     }
                     ColorAttachmentDestination::CubeMap { .. } => {
-                        is_cube_render = true;
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
                     }
                 };
@@ -154,7 +172,9 @@ impl RenderPassCache {
                             ColorAttachmentDestination::SurfaceImage(image) => {
                                 image.internal().format()
                             }
-                            ColorAttachmentDestination::Texture { texture, .. } => {
+                            ColorAttachmentDestination::Texture { texture, .. }
+                            | ColorAttachmentDestination::TextureSlice { texture, .. }
+                            | ColorAttachmentDestination::TextureArray { texture, .. } => {
                                 texture.internal().format
                             }
                             ColorAttachmentDestination::CubeFace { cube_map, .. } => {
@@ -173,21 +193,24 @@ impl RenderPassCache {
             if let Some(attachment) = &pass.depth_stencil_attachment {
                 sample_count = crate::util::to_vk_sample_count(attachment.samples);
 
-                let final_layout = crate::util::depth_store_op_to_layout(attachment.store_op);
+                let final_layout = crate::util::depth_store_op_to_layout(
+                    attachment.store_op,
+                    attachment.stencil_store_op,
+                );
 
                 if final_layout != vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL {
                     read_only_depth = false;
                 }
 
                 let format = match attachment.dst {
-                    DepthStencilAttachmentDestination::Texture { texture, .. } => {
+                    DepthStencilAttachmentDestination::Texture { texture, .. }
+                    | DepthStencilAttachmentDestination::TextureArray { texture, .. } => {
                         texture.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeFace { cube_map, .. } => {
                         cube_map.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeMap { cube_map, .. } => {
-                        is_cube_render = true;
                         cube_map.internal().format
                     }
                 };
@@ -198,27 +221,30 @@ impl RenderPassCache {
                         .final_layout(final_layout)
                         .load_op(crate::util::to_vk_load_op(attachment.load_op))
                         .store_op(crate::util::to_vk_store_op(attachment.store_op))
+                        .stencil_load_op(crate::util::to_vk_load_op(attachment.stencil_load_op))
+                        .stencil_store_op(crate::util::to_vk_store_op(attachment.stencil_store_op))
                         .format(format)
                         .samples(sample_count),
                 );
             }
 
             if let Some(attachment) = &pass.depth_stencil_resolve_attachment {
-                let final_layout = crate::util::depth_store_op_to_layout(attachment.store_op);
+                let final_layout =
+                    crate::util::depth_store_op_to_layout(attachment.store_op, attachment.store_op);
 
                 if final_layout != vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL {
                     read_only_depth = false;
                 }
 
                 let format = match attachment.dst {
-                    DepthStencilAttachmentDestination::Texture { texture, .. } => {
+                    DepthStencilAttachmentDestination::Texture { texture, .. }
+                    | DepthStencilAttachmentDestination::TextureArray { texture, .. } => {
                         texture.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeFace { cube_map, .. } => {
                         cube_map.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeMap { cube_map, .. } => {
-                        is_cube_render = true;
                         cube_map.internal().format
                     }
                 };
@@ -229,6 +255,8 @@ impl RenderPassCache {
                         .final_layout(final_layout)
                         .load_op(crate::util::to_vk_load_op(attachment.load_op))
                         .store_op(crate::util::to_vk_store_op(attachment.store_op))
+                        .stencil_load_op(crate::util::to_vk_load_op(attachment.load_op))
+                        .stencil_store_op(crate::util::to_vk_store_op(attachment.store_op))
                         .format(format)
                         .samples(vk::SampleCountFlags::TYPE_1),
                 );
@@ -264,7 +292,7 @@ impl RenderPassCache {
 
             let subpass = vk::SubpassDescription2::default()
                 .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-                .view_mask(if is_cube_render { 0b111111 } else { 0 })
+                .view_mask(pass.view_mask)
                 .color_attachments(&attachment_refs)
                 .resolve_attachments(&resolve_attachment_refs);
 
@@ -274,7 +302,10 @@ impl RenderPassCache {
                         (pass.color_attachments.len() + pass.color_resolve_attachments.len())
                             as u32,
                     )
-                    .layout(crate::util::depth_store_op_to_layout(attachment.store_op));
+                    .layout(crate::util::depth_store_op_to_layout(
+                        attachment.store_op,
+                        attachment.stencil_store_op,
+                    ));
                 subpass.depth_stencil_attachment(&depth_attachment)
             } else {
                 subpass
@@ -286,7 +317,10 @@ impl RenderPassCache {
                         (pass.color_attachments.len() + pass.color_resolve_attachments.len() + 1)
                             as u32,
                     )
-                    .layout(crate::util::depth_store_op_to_layout(resolve.store_op));
+                    .layout(crate::util::depth_store_op_to_layout(
+                        resolve.store_op,
+                        resolve.store_op,
+                    ));
 
                 depth_resolve = vk::SubpassDescriptionDepthStencilResolve::default()
                     .depth_resolve_mode(crate::util::to_vk_resolve_mode(resolve.depth_resolve_mode))
@@ -300,19 +334,12 @@ impl RenderPassCache {
 
             let subpass = [subpass.push_next(&mut depth_resolve)];
 
-            let cube_correlation_mask = [0b111111];
-            let tex_correlation_mask = [];
-
             // Create the render pass
             unsafe {
                 let create_info = vk::RenderPassCreateInfo2::default()
                     .attachments(&attachments)
                     .subpasses(&subpass)
-                    .correlated_view_masks(if is_cube_render {
-                        &cube_correlation_mask
-                    } else {
-                        &tex_correlation_mask
-                    });
+                    .correlated_view_masks(&pass.correlation_masks);
 
                 VkRenderPass {
                     pass: device.create_render_pass2(&create_info, None).unwrap(),
@@ -441,7 +468,11 @@ impl VkRenderPassDescriptor {
     pub fn from_descriptor(
         descriptor: &RenderPassDescriptor<crate::VulkanBackend>,
     ) -> VkRenderPassDescriptor {
-        let mut out = VkRenderPassDescriptor::default();
+        let mut out = VkRenderPassDescriptor {
+            view_mask: descriptor.view_mask,
+            correlation_masks: descriptor.correlation_masks.clone(),
+            ..Default::default()
+        };
         out.color_attachments = Vec::with_capacity(descriptor.color_attachments.len());
         out.color_resolve_attachments =
             Vec::with_capacity(descriptor.color_resolve_attachments.len());
@@ -457,7 +488,9 @@ impl VkRenderPassDescriptor {
                     },
                     vk::ImageLayout::PRESENT_SRC_KHR,
                 ),
-                ColorAttachmentDestination::Texture { texture, .. } => (
+                ColorAttachmentDestination::Texture { texture, .. }
+                | ColorAttachmentDestination::TextureSlice { texture, .. }
+                | ColorAttachmentDestination::TextureArray { texture, .. } => (
                     texture.internal().format,
                     match &attachment.load_op {
                         LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
@@ -475,18 +508,15 @@ impl VkRenderPassDescriptor {
                     },
                     vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                 ),
-                ColorAttachmentDestination::CubeMap { cube_map, .. } => {
-                    out.is_cube_render = true;
-                    (
-                        cube_map.internal().format,
-                        match &attachment.load_op {
-                            LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
-                            LoadOp::Load => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                            LoadOp::Clear(_) => vk::ImageLayout::UNDEFINED,
-                        },
-                        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                    )
-                }
+                ColorAttachmentDestination::CubeMap { cube_map, .. } => (
+                    cube_map.internal().format,
+                    match &attachment.load_op {
+                        LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
+                        LoadOp::Load => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                        LoadOp::Clear(_) => vk::ImageLayout::UNDEFINED,
+                    },
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                ),
             };
 
             out.color_attachments.push(VkAttachment {
@@ -495,6 +525,8 @@ impl VkRenderPassDescriptor {
                 final_layout,
                 load_op: crate::util::to_vk_load_op(attachment.load_op),
                 store_op: crate::util::to_vk_store_op(attachment.store_op),
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
                 samples: crate::util::to_vk_sample_count(attachment.samples),
                 resolve_src: 0,
             });
@@ -511,7 +543,9 @@ impl VkRenderPassDescriptor {
                     },
                     vk::ImageLayout::PRESENT_SRC_KHR,
                 ),
-                ColorAttachmentDestination::Texture { texture, .. } => (
+                ColorAttachmentDestination::Texture { texture, .. }
+                | ColorAttachmentDestination::TextureSlice { texture, .. }
+                | ColorAttachmentDestination::TextureArray { texture, .. } => (
                     texture.internal().format,
                     match &attachment.load_op {
                         LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
@@ -529,18 +563,15 @@ impl VkRenderPassDescriptor {
                     },
                     vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                 ),
-                ColorAttachmentDestination::CubeMap { cube_map, .. } => {
-                    out.is_cube_render = true;
-                    (
-                        cube_map.internal().format,
-                        match &attachment.load_op {
-                            LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
-                            LoadOp::Load => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                            LoadOp::Clear(_) => vk::ImageLayout::UNDEFINED,
-                        },
-                        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                    )
-                }
+                ColorAttachmentDestination::CubeMap { cube_map, .. } => (
+                    cube_map.internal().format,
+                    match &attachment.load_op {
+                        LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
+                        LoadOp::Load => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                        LoadOp::Clear(_) => vk::ImageLayout::UNDEFINED,
+                    },
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                ),
             };
 
             out.color_resolve_attachments.push(VkAttachment {
@@ -549,28 +580,32 @@ impl VkRenderPassDescriptor {
                 final_layout,
                 load_op: crate::util::to_vk_load_op(attachment.load_op),
                 store_op: crate::util::to_vk_store_op(attachment.store_op),
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
                 samples: vk::SampleCountFlags::TYPE_1,
                 resolve_src: attachment.src,
             });
         }
 
         if let Some(attachment) = &descriptor.depth_stencil_attachment {
-            let initial_layout = match &attachment.load_op {
-                LoadOp::DontCare => vk::ImageLayout::UNDEFINED,
-                LoadOp::Load => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                LoadOp::Clear(_) => vk::ImageLayout::UNDEFINED,
+            // Contents must be preserved if either aspect is loaded
+            let initial_layout = match (&attachment.load_op, &attachment.stencil_load_op) {
+                (LoadOp::Load, _) | (_, LoadOp::Load) => {
+                    vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+                }
+                _ => vk::ImageLayout::UNDEFINED,
             };
 
             out.depth_stencil_attachment = Some(VkAttachment {
                 image_format: match attachment.dst {
-                    DepthStencilAttachmentDestination::Texture { texture, .. } => {
+                    DepthStencilAttachmentDestination::Texture { texture, .. }
+                    | DepthStencilAttachmentDestination::TextureArray { texture, .. } => {
                         texture.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeFace { cube_map, .. } => {
                         cube_map.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeMap { cube_map, .. } => {
-                        out.is_cube_render = true;
                         cube_map.internal().format
                     }
                 },
@@ -578,6 +613,8 @@ impl VkRenderPassDescriptor {
                 final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
                 load_op: crate::util::to_vk_load_op(attachment.load_op),
                 store_op: crate::util::to_vk_store_op(attachment.store_op),
+                stencil_load_op: crate::util::to_vk_load_op(attachment.stencil_load_op),
+                stencil_store_op: crate::util::to_vk_store_op(attachment.stencil_store_op),
                 samples: crate::util::to_vk_sample_count(attachment.samples),
                 resolve_src: 0,
             })
@@ -592,14 +629,14 @@ impl VkRenderPassDescriptor {
 
             out.depth_stencil_resolve_attachment = Some(VkAttachment {
                 image_format: match attachment.dst {
-                    DepthStencilAttachmentDestination::Texture { texture, .. } => {
+                    DepthStencilAttachmentDestination::Texture { texture, .. }
+                    | DepthStencilAttachmentDestination::TextureArray { texture, .. } => {
                         texture.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeFace { cube_map, .. } => {
                         cube_map.internal().format
                     }
                     DepthStencilAttachmentDestination::CubeMap { cube_map, .. } => {
-                        out.is_cube_render = true;
                         cube_map.internal().format
                     }
                 },
@@ -607,6 +644,8 @@ impl VkRenderPassDescriptor {
                 final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
                 load_op: crate::util::to_vk_load_op(attachment.load_op),
                 store_op: crate::util::to_vk_store_op(attachment.store_op),
+                stencil_load_op: crate::util::to_vk_load_op(attachment.load_op),
+                stencil_store_op: crate::util::to_vk_store_op(attachment.store_op),
                 samples: vk::SampleCountFlags::TYPE_1,
                 resolve_src: 0,
             })
@@ -11,6 +11,8 @@ pub struct RayTracingPipeline {
     pub(crate) layout: vk::PipelineLayout,
     pub(crate) pipeline: vk::Pipeline,
     group_count: usize,
+    /// The pipeline, or one of its libraries, has procedural hit groups.
+    has_procedural: bool,
     garbage: Sender<Garbage>,
 }
 
@@ -24,7 +26,8 @@ impl RayTracingPipeline {
                 stage_flags: vk::ShaderStageFlags::RAYGEN_KHR
                     | vk::ShaderStageFlags::ANY_HIT_KHR
                     | vk::ShaderStageFlags::CLOSEST_HIT_KHR
-                    | vk::ShaderStageFlags::MISS_KHR,
+                    | vk::ShaderStageFlags::MISS_KHR
+                    | vk::ShaderStageFlags::INTERSECTION_KHR,
                 offset: 0,
                 size,
             }]
@@ -45,6 +48,10 @@ impl RayTracingPipeline {
             .device
             .create_pipeline_layout(&layout_create_info, None)
             .unwrap();
+        let debug = ctx.debug.as_ref().map(|debug| &debug.device);
+        if let Some(name) = &create_info.debug_name {
+            crate::util::set_debug_name(debug, layout, &format!("{name}_layout"));
+        }
 
         // Create shader stages
         let stages: Vec<_> = create_info
@@ -90,9 +97,33 @@ impl RayTracingPipeline {
                     )
                     .any_hit_shader(any_hit.map(|i| i as u32).unwrap_or(vk::SHADER_UNUSED_KHR))
                     .intersection_shader(vk::SHADER_UNUSED_KHR),
+                RayTracingShaderGroup::Procedural {
+                    intersection,
+                    closest_hit,
+                    any_hit,
+                } => vk::RayTracingShaderGroupCreateInfoKHR::default()
+                    .ty(vk::RayTracingShaderGroupTypeKHR::PROCEDURAL_HIT_GROUP)
+                    .general_shader(vk::SHADER_UNUSED_KHR)
+                    .closest_hit_shader(
+                        closest_hit
+                            .map(|i| i as u32)
+                            .unwrap_or(vk::SHADER_UNUSED_KHR),
+                    )
+                    .any_hit_shader(any_hit.map(|i| i as u32).unwrap_or(vk::SHADER_UNUSED_KHR))
+                    .intersection_shader(*intersection as u32),
             })
             .collect();
 
+        // AABBs are only skipped during traversal when nothing could ever hit them
+        let has_procedural = create_info
+            .groups
+            .iter()
+            .any(|group| matches!(group, RayTracingShaderGroup::Procedural { .. }))
+            || create_info
+                .libraries
+                .iter()
+                .any(|lib| lib.internal().has_procedural);
+
         let libraries: Vec<_> = create_info
             .libraries
             .iter()
@@ -108,11 +139,20 @@ impl RayTracingPipeline {
             None => vk::RayTracingPipelineInterfaceCreateInfoKHR::default(),
         };
 
+        let mut feedback = vk::PipelineCreationFeedback::default();
+        let mut feedback_info = vk::PipelineCreationFeedbackCreateInfo::default()
+            .pipeline_creation_feedback(&mut feedback);
+
         let mut rt_pipeline_create_info = [vk::RayTracingPipelineCreateInfoKHR::default()
+            .push_next(&mut feedback_info)
             .library_info(&rt_libraries)
             .stages(&stages)
             .groups(&groups)
-            .flags(vk::PipelineCreateFlags::RAY_TRACING_SKIP_AABBS_KHR)
+            .flags(if has_procedural {
+                vk::PipelineCreateFlags::empty()
+            } else {
+                vk::PipelineCreateFlags::RAY_TRACING_SKIP_AABBS_KHR
+            })
             .max_pipeline_ray_recursion_depth(create_info.max_ray_recursion_depth)
             .layout(layout)];
 
@@ -123,9 +163,10 @@ impl RayTracingPipeline {
             rt_pipeline_create_info[0].p_library_interface = &rt_interface;
         }
 
+        let cache = ctx.pipelines.lock().unwrap().cache();
         let pipeline = match ctx.rt_loader.create_ray_tracing_pipelines(
             vk::DeferredOperationKHR::null(),
-            vk::PipelineCache::null(),
+            cache,
             &rt_pipeline_create_info,
             None,
         ) {
@@ -133,6 +174,12 @@ impl RayTracingPipeline {
             // TODO: Destroy pipelines here
             Err((_, err)) => return Err(RayTracingPipelineCreateError::Other(err.to_string())),
         };
+        ctx.pipelines.lock().unwrap().record(&feedback);
+
+        // Name the pipeline if needed
+        if let Some(name) = &create_info.debug_name {
+            crate::util::set_debug_name(debug, pipeline, name);
+        }
 
         let mut group_count = groups.len();
         for lib in &create_info.libraries {
@@ -143,6 +190,7 @@ impl RayTracingPipeline {
             layout,
             pipeline,
             group_count,
+            has_procedural,
             garbage: ctx.garbage.sender(),
         })
     }
@@ -170,7 +218,9 @@ impl RayTracingPipeline {
                 .shader_group_handle_size
                 .next_multiple_of(ctx.properties.shader_group_handle_alignment)
                 as u64,
+            handle_alignment: ctx.properties.shader_group_handle_alignment as u64,
             base_alignment: ctx.properties.shader_group_base_alignment as u64,
+            max_stride: ctx.properties.max_shader_group_stride as u64,
         }
     }
 }
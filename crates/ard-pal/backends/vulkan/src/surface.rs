@@ -7,15 +7,18 @@ use api::{
     queue::SurfacePresentFailure,
     surface::{
         SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo, SurfaceImageAcquireError,
-        SurfacePresentSuccess, SurfaceUpdateError,
+        SurfacePresentSuccess, SurfaceProperties, SurfaceUpdateError,
     },
+    types::{ColorSpace, Format, PresentMode, QueueType},
 };
-use ash::vk;
+use ash::{prelude::VkResult, vk};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
 use crate::{
     util::{
+        garbage_collector::{Garbage, TimelineValues},
         id_gen::{IdGenerator, ResourceId},
+        semaphores::{SemaphoreTracker, WaitInfo},
         usage::{GlobalResourceUsage, ImageRegion},
     },
     VulkanBackend,
@@ -27,7 +30,9 @@ pub struct Surface {
     pub(crate) format: vk::SurfaceFormatKHR,
     pub(crate) resolution: vk::Extent2D,
     pub(crate) images: Vec<(vk::Image, ResourceId, vk::ImageView)>,
-    /// Semaphores for image availability.
+    /// Semaphores for image availability. `available` semaphores are used in acquisition order
+    /// while `presentable` semaphores belong to the image with the same index, since an image
+    /// can't be reacquired until the presentation engine is done waiting on its semaphore.
     pub(crate) semaphores: Vec<SurfaceImageSemaphores>,
     /// Rolling index for the next available image.
     pub(crate) next_semaphore: usize,
@@ -39,6 +44,8 @@ pub struct Surface {
 pub struct SurfaceImage {
     /// Source surface.
     surface: vk::SurfaceKHR,
+    /// Swapchain the image was acquired from.
+    swapchain: vk::SwapchainKHR,
     /// Image dimensions.
     dims: (u32, u32),
     /// Actual image object.
@@ -71,7 +78,7 @@ impl Surface {
         create_info: SurfaceCreateInfo<W>,
         image_ids: &IdGenerator,
         global_usage: &mut GlobalResourceUsage,
-    ) -> Result<Self, SurfaceCreateError> {
+    ) -> Result<(Self, SurfaceProperties), SurfaceCreateError> {
         // Create and name the surface
         let (display, window) = match create_info.window {
             api::surface::WindowSource::Raw {
@@ -116,11 +123,10 @@ impl Surface {
         };
 
         // Update the surface with the provided configuration
-        if let Err(err) = surface.update_config(ctx, create_info.config, image_ids, global_usage) {
-            return Err(SurfaceCreateError::BadConfig(err));
+        match surface.update_config(ctx, create_info.config, image_ids, global_usage) {
+            Ok(properties) => Ok((surface, properties)),
+            Err(err) => Err(SurfaceCreateError::BadConfig(err)),
         }
-
-        Ok(surface)
     }
 
     pub(crate) unsafe fn present(
@@ -164,15 +170,13 @@ impl Surface {
         config: SurfaceConfiguration,
         image_ids: &IdGenerator,
         global_usage: &mut GlobalResourceUsage,
-    ) -> Result<(u32, u32), SurfaceUpdateError> {
+    ) -> Result<SurfaceProperties, SurfaceUpdateError> {
         assert!(config.width != 0, "width was 0");
         assert!(config.height != 0, "height was 0");
         if self.images_acquired != 0 {
             return Err(SurfaceUpdateError::ImagePending);
         }
 
-        self.release(ctx, image_ids, global_usage);
-
         let surface_capabilities = match ctx
             .surface_loader
             .get_physical_device_surface_capabilities(ctx.physical_device, self.surface)
@@ -197,6 +201,13 @@ impl Surface {
             Err(err) => return Err(SurfaceUpdateError::Other(err.to_string())),
         };
 
+        // Determine an appropriate format and color space
+        let (surface_format, format, color_space) =
+            match select_surface_format(&config.preferred_formats, &formats) {
+                Some(selected) => selected,
+                None => return Err(SurfaceUpdateError::UnsupportedFormat),
+            };
+
         // Choose number of images
         let mut desired_image_count = surface_capabilities.min_image_count + 1;
         if surface_capabilities.max_image_count > 0
@@ -229,30 +240,10 @@ impl Surface {
 
         // Determine a compatible presentation mode and fallback if the requested one is not
         // available.
-        let present_mode = {
-            let mut present_mode = crate::util::to_vk_present_mode(config.present_mode);
+        let present_mode = select_present_mode(config.present_mode, &present_modes);
+        let vk_present_mode = crate::util::to_vk_present_mode(present_mode);
 
-            // Fallback if it's not available
-            if !present_modes.contains(&present_mode) {
-                present_mode = vk::PresentModeKHR::IMMEDIATE;
-            }
-
-            present_mode
-        };
-
-        // Determine an approprite format and color space
-        self.format = {
-            let vk_format = crate::util::to_vk_format(config.format);
-            let mut out_format = formats[0];
-            for format in formats {
-                if format.format == vk_format
-                    && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
-                {
-                    out_format = format;
-                }
-            }
-            out_format
-        };
+        self.format = surface_format;
 
         // Determine if we need exclusive or concurrent access to the images
         let (indices, sharing_mode) = {
@@ -286,14 +277,22 @@ impl Surface {
             .queue_family_indices(&indices)
             .pre_transform(pre_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
-            .present_mode(present_mode)
+            .present_mode(vk_present_mode)
             .clipped(true)
-            .image_array_layers(1);
+            .image_array_layers(1)
+            .old_swapchain(self.swapchain);
 
-        self.swapchain = match ctx
+        let swapchain = ctx
             .swapchain_loader
-            .create_swapchain(&swapchain_create_info, None)
-        {
+            .create_swapchain(&swapchain_create_info, None);
+
+        // The old swapchain is retired instead of destroyed so we don't have to wait for the
+        // device to become idle. This must happen after the new swapchain is created, since the
+        // garbage collector could destroy it before creation reads it. It's retired even if
+        // creation failed, because it can't be used after being passed as `old_swapchain`.
+        self.retire(ctx, global_usage, false);
+
+        self.swapchain = match swapchain {
             Ok(swapchain) => swapchain,
             Err(err) => return Err(SurfaceUpdateError::Other(err.to_string())),
         };
@@ -400,7 +399,12 @@ impl Surface {
             }
         }
 
-        Ok((surface_resolution.width, surface_resolution.height))
+        Ok(SurfaceProperties {
+            dimensions: (surface_resolution.width, surface_resolution.height),
+            format,
+            color_space,
+            present_mode,
+        })
     }
 
     pub(crate) unsafe fn acquire_image(
@@ -413,16 +417,23 @@ impl Surface {
 
         // Acquire the image
         self.next_semaphore = (self.next_semaphore + 1) % self.semaphores.len();
-        let semaphores = self.semaphores[self.next_semaphore];
+        let available = self.semaphores[self.next_semaphore].available;
         let image_idx = match ctx.swapchain_loader.acquire_next_image(
             self.swapchain,
             u64::MAX,
-            semaphores.available,
+            available,
             vk::Fence::null(),
         ) {
             Ok((idx, _)) => idx as usize,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                return Err(SurfaceImageAcquireError::Invalidated)
+            }
             Err(err) => return Err(SurfaceImageAcquireError::Other(err.to_string())),
         };
+        let semaphores = SurfaceImageSemaphores {
+            available,
+            presentable: self.semaphores[image_idx].presentable,
+        };
 
         // Layout is undefined after presenting, so if the
         // image is reaquired we must update its layout
@@ -438,6 +449,7 @@ impl Surface {
 
         Ok(SurfaceImage {
             surface: self.surface,
+            swapchain: self.swapchain,
             dims: (self.resolution.width, self.resolution.height),
             image: self.images[image_idx].0,
             id: self.images[image_idx].1,
@@ -449,30 +461,129 @@ impl Surface {
         })
     }
 
-    pub(crate) unsafe fn release(
+    /// Sends the swapchain and its images to the garbage collector. They are destroyed once
+    /// every submission that used the images is complete.
+    pub(crate) unsafe fn retire(
         &mut self,
         ctx: &VulkanBackend,
-        image_ids: &IdGenerator,
-        global_usage: &mut GlobalResourceUsage,
+        global_usage: &GlobalResourceUsage,
+        destroy_surface: bool,
     ) {
-        for semaphores in self.semaphores.drain(..) {
-            ctx.device.destroy_semaphore(semaphores.available, None);
-            ctx.device.destroy_semaphore(semaphores.presentable, None);
-        }
+        // Only wait on the queues that actually used the images
+        let mut values = TimelineValues {
+            main: 0,
+            transfer: 0,
+            compute: 0,
+            present: ctx.present.read().unwrap().target_timeline_value(),
+        };
 
-        for (_, id, view) in self.images.drain(..) {
-            image_ids.free(id);
-            global_usage.remove_image(id);
-            ctx.device.destroy_image_view(view, None);
-        }
+        for (_, id, _) in &self.images {
+            let usage = match global_usage.get_image_queue_usage(&ImageRegion {
+                id: *id,
+                array_elem: 0,
+                base_mip_level: 0,
+                mip_count: 1,
+            }) {
+                Some(usage) => usage,
+                None => continue,
+            };
 
-        if self.swapchain != vk::SwapchainKHR::null() {
-            ctx.swapchain_loader.destroy_swapchain(self.swapchain, None);
+            let value = match usage.queue {
+                QueueType::Main => &mut values.main,
+                QueueType::Transfer => &mut values.transfer,
+                QueueType::Compute => &mut values.compute,
+                QueueType::Present => &mut values.present,
+            };
+            *value = (*value).max(usage.timeline_value);
         }
+
+        ctx.garbage.retire(
+            Garbage::Swapchain {
+                swapchain: std::mem::replace(&mut self.swapchain, vk::SwapchainKHR::null()),
+                images: self
+                    .images
+                    .drain(..)
+                    .map(|(_, id, view)| (id, view))
+                    .collect(),
+                semaphores: std::mem::take(&mut self.semaphores),
+                surface: if destroy_surface {
+                    Some(self.surface)
+                } else {
+                    None
+                },
+            },
+            values,
+        );
     }
 }
 
 impl SurfaceImage {
+    /// Returns an image that was acquired but never rendered to back to the swapchain. This is
+    /// done by submitting an empty batch that waits for the image to become available,
+    /// transitions it for presentation, and then presenting it.
+    ///
+    /// Returns an error if the batch couldn't be submitted, which means the device was lost.
+    pub(crate) unsafe fn release_unused(&mut self, ctx: &VulkanBackend) -> VkResult<()> {
+        // Nothing can be returned to a swapchain on a lost device
+        if ctx.device_lost.get().is_some() {
+            return Ok(());
+        }
+
+        let mut present = ctx.present.write().unwrap();
+        let cb = present
+            .allocate_command_buffer(&ctx.device, ctx.debug.as_ref().map(|utils| &utils.device))?;
+        let begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        ctx.device.begin_command_buffer(cb, &begin_info).unwrap();
+
+        let barrier = [vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vk::AccessFlags2::NONE)
+            .dst_stage_mask(vk::PipelineStageFlags2::BOTTOM_OF_PIPE)
+            .dst_access_mask(vk::AccessFlags2::NONE)
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(self.image)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            })];
+        let dependency_info = vk::DependencyInfo::default().image_memory_barriers(&barrier);
+        ctx.device.cmd_pipeline_barrier2(cb, &dependency_info);
+        ctx.device.end_command_buffer(cb).unwrap();
+
+        let mut semaphore_tracker = SemaphoreTracker::default();
+        semaphore_tracker.register_wait(
+            self.semaphores.available,
+            WaitInfo {
+                value: None,
+                stage: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            },
+        );
+        semaphore_tracker.register_signal(self.semaphores.presentable, None);
+        present.submit(&ctx.device, cb, semaphore_tracker)?;
+        self.signal_draw();
+
+        // The swapchain might be out of date, but the image is given back either way
+        let idx = [self.image_idx as u32];
+        let swapchain = [self.swapchain];
+        let presentable = [self.semaphores.presentable];
+        let present_info = vk::PresentInfoKHR::default()
+            .image_indices(&idx)
+            .swapchains(&swapchain)
+            .wait_semaphores(&presentable);
+        let _ = ctx
+            .swapchain_loader
+            .queue_present(present.queue, &present_info);
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub(crate) fn image(&self) -> vk::Image {
         self.image
@@ -523,3 +634,137 @@ impl SurfaceImage {
         self.format
     }
 }
+
+/// Picks the requested present mode if it is supported. Otherwise, falls back to FIFO which is
+/// guaranteed to be available.
+fn select_present_mode(requested: PresentMode, available: &[vk::PresentModeKHR]) -> PresentMode {
+    if available.contains(&crate::util::to_vk_present_mode(requested)) {
+        return requested;
+    }
+
+    ard_log::warn!("present mode {requested:?} is not supported by the surface, using FIFO");
+    PresentMode::Fifo
+}
+
+/// Picks the first preferred format and color space pairing supported by the surface. If none
+/// are supported, falls back to the first supported SDR format.
+fn select_surface_format(
+    preferred: &[(Format, ColorSpace)],
+    available: &[vk::SurfaceFormatKHR],
+) -> Option<(vk::SurfaceFormatKHR, Format, ColorSpace)> {
+    let find = |format: Format, color_space: ColorSpace| {
+        let vk_color_space = crate::util::to_vk_color_space(color_space);
+        available
+            .iter()
+            .find(|surface_format| {
+                surface_format.color_space == vk_color_space
+                    && crate::util::from_vk_surface_format(surface_format.format) == Some(format)
+            })
+            .map(|surface_format| (*surface_format, format, color_space))
+    };
+
+    if let Some(selected) = preferred
+        .iter()
+        .find_map(|(format, color_space)| find(*format, *color_space))
+    {
+        return Some(selected);
+    }
+
+    let fallback = available.iter().find_map(|surface_format| {
+        let format = crate::util::from_vk_surface_format(surface_format.format)?;
+        find(format, ColorSpace::SrgbNonlinear)
+    })?;
+    ard_log::warn!(
+        "none of the preferred surface formats are supported, using {:?} with {:?}",
+        fallback.1,
+        fallback.2
+    );
+    Some(fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_present_mode() {
+        let available = [vk::PresentModeKHR::FIFO, vk::PresentModeKHR::MAILBOX];
+        assert_eq!(
+            select_present_mode(PresentMode::Mailbox, &available),
+            PresentMode::Mailbox
+        );
+    }
+
+    #[test]
+    fn unsupported_present_mode_falls_back_to_fifo() {
+        let available = [vk::PresentModeKHR::FIFO];
+        assert_eq!(
+            select_present_mode(PresentMode::Immediate, &available),
+            PresentMode::Fifo
+        );
+        assert_eq!(
+            select_present_mode(PresentMode::Mailbox, &available),
+            PresentMode::Fifo
+        );
+    }
+
+    fn surface_format(format: vk::Format, color_space: vk::ColorSpaceKHR) -> vk::SurfaceFormatKHR {
+        vk::SurfaceFormatKHR {
+            format,
+            color_space,
+        }
+    }
+
+    #[test]
+    fn first_supported_preferred_format() {
+        let available = [
+            surface_format(
+                vk::Format::B8G8R8A8_UNORM,
+                vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            ),
+            surface_format(
+                vk::Format::A2B10G10R10_UNORM_PACK32,
+                vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+            ),
+        ];
+        let preferred = [
+            (Format::Rgba16SFloat, ColorSpace::ExtendedSrgbLinear),
+            (Format::Rgb10A2Unorm, ColorSpace::Hdr10St2084),
+            (Format::Bgra8Unorm, ColorSpace::SrgbNonlinear),
+        ];
+        let (selected, format, color_space) =
+            select_surface_format(&preferred, &available).unwrap();
+        assert_eq!(selected, available[1]);
+        assert_eq!(format, Format::Rgb10A2Unorm);
+        assert_eq!(color_space, ColorSpace::Hdr10St2084);
+    }
+
+    #[test]
+    fn unsupported_format_falls_back_to_sdr() {
+        let available = [
+            surface_format(
+                vk::Format::R16G16B16A16_SFLOAT,
+                vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+            ),
+            surface_format(
+                vk::Format::R8G8B8A8_UNORM,
+                vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            ),
+        ];
+        let preferred = [(Format::Rgb10A2Unorm, ColorSpace::Hdr10St2084)];
+        let (selected, format, color_space) =
+            select_surface_format(&preferred, &available).unwrap();
+        assert_eq!(selected, available[1]);
+        assert_eq!(format, Format::Rgba8Unorm);
+        assert_eq!(color_space, ColorSpace::SrgbNonlinear);
+    }
+
+    #[test]
+    fn no_supported_format() {
+        let available = [surface_format(
+            vk::Format::R16G16B16A16_SFLOAT,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+        )];
+        assert!(select_surface_format(&[], &available).is_none());
+    }
+}
@@ -1,27 +1,45 @@
-use std::{ffi::CString, mem::ManuallyDrop, sync::Arc};
+use std::{
+    ffi::CString,
+    mem::ManuallyDrop,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
     util::{
         garbage_collector::Garbage,
         id_gen::{IdGenerator, ResourceId},
+        memory::TrackedAllocator,
+        sparse::SparsePages,
     },
     QueueFamilyIndices,
 };
 use api::{
+    context::MemoryCategory,
     texture::{TextureCreateError, TextureCreateInfo},
     types::*,
 };
 use ash::vk;
 use crossbeam_channel::Sender;
-use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme, Allocator};
+use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme};
+use rustc_hash::FxHashMap;
 
 pub struct Texture {
     pub(crate) image: vk::Image,
     pub(crate) id: ResourceId,
     /// Image view for each array element and mip level. This array is flattened like so.
     /// A0M0 -> A0M1 -> A0M2 ... A1M0 -> A1M1 -> A1M2 -> ...
+    ///
+    /// 3D color attachments are followed by a 2D view of every depth slice of every mip.
+    /// M0S0 -> M0S1 -> ... M1S0 -> ...
     pub(crate) views: Vec<vk::ImageView>,
-    pub(crate) block: ManuallyDrop<Allocation>,
+    pub(crate) view_type: vk::ImageViewType,
+    /// Views created for sampling the texture in descriptor sets. They live as long as the
+    /// texture so sets binding the same range share a view.
+    pub(crate) sampled_views: Mutex<FxHashMap<SampledViewKey, vk::ImageView>>,
+    /// Array views of a range of layers of a single mip, created for layered attachments. Keyed
+    /// by mip, base layer, and layer count.
+    pub(crate) layered_views: Mutex<FxHashMap<(u32, u32, u32), vk::ImageView>>,
+    pub(crate) memory: TextureMemory,
     pub(crate) _image_usage: TextureUsage,
     pub(crate) _memory_usage: MemoryUsage,
     pub(crate) _array_elements: usize,
@@ -30,10 +48,28 @@ pub struct Texture {
     pub(crate) ref_counter: TextureRefCounter,
     pub(crate) format: vk::Format,
     pub(crate) mip_count: u32,
+    pub(crate) depth: u32,
     pub(crate) aspect_flags: vk::ImageAspectFlags,
+    /// Used to name views created after the texture.
+    pub(crate) debug_name: Option<String>,
     on_drop: Sender<Garbage>,
 }
 
+pub(crate) enum TextureMemory {
+    /// The texture owns a single allocation bound to the whole image.
+    Dedicated(ManuallyDrop<Allocation>),
+    /// The texture is sparse and memory is bound to it one page at a time.
+    Sparse(Mutex<SparsePages>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct SampledViewKey {
+    pub aspect: vk::ImageAspectFlags,
+    pub base_mip: u32,
+    pub mip_count: u32,
+    pub array_element: u32,
+}
+
 #[derive(Clone)]
 pub(crate) struct TextureRefCounter(Arc<()>);
 
@@ -44,19 +80,59 @@ impl Texture {
         qfi: &QueueFamilyIndices,
         debug: Option<&ash::ext::debug_utils::Device>,
         on_drop: Sender<Garbage>,
-        allocator: &mut Allocator,
+        allocator: &mut TrackedAllocator,
         create_info: TextureCreateInfo,
     ) -> Result<Self, TextureCreateError> {
+        // Validate dimensions
+        let valid_dims = create_info.width > 0
+            && create_info.height > 0
+            && create_info.depth > 0
+            && create_info.array_elements > 0
+            && match create_info.ty {
+                TextureType::Type1D => create_info.height == 1 && create_info.depth == 1,
+                TextureType::Type2D => create_info.depth == 1,
+                TextureType::Type3D => create_info.array_elements == 1,
+            };
+        if !valid_dims {
+            return Err(TextureCreateError::InvalidDimensions {
+                ty: create_info.ty,
+                width: create_info.width,
+                height: create_info.height,
+                depth: create_info.depth,
+                array_elements: create_info.array_elements,
+            });
+        }
+
+        let max_mips = create_info
+            .width
+            .max(create_info.height)
+            .max(create_info.depth)
+            .ilog2() as usize
+            + 1;
+        if create_info.mip_levels > max_mips {
+            return Err(TextureCreateError::TooManyMips {
+                requested: create_info.mip_levels,
+                max: max_mips,
+            });
+        }
+
+        // 3D textures rendered to need 2D views of their slices
+        let slice_views = create_info.ty == TextureType::Type3D
+            && create_info
+                .texture_usage
+                .contains(TextureUsage::COLOR_ATTACHMENT);
+
         // Create the image
         let format = crate::util::to_vk_format(create_info.format);
         let qfi = qfi.queue_types_to_indices(create_info.queue_types);
+        let extent = vk::Extent3D {
+            width: create_info.width,
+            height: create_info.height,
+            depth: create_info.depth,
+        };
         let image_create_info = vk::ImageCreateInfo::default()
             .image_type(crate::util::to_vk_image_type(create_info.ty))
-            .extent(vk::Extent3D {
-                width: create_info.width,
-                height: create_info.height,
-                depth: create_info.depth,
-            })
+            .extent(extent)
             .mip_levels(create_info.mip_levels as u32)
             .array_layers(create_info.array_elements as u32)
             .format(format)
@@ -70,80 +146,121 @@ impl Texture {
             })
             .queue_family_indices(&qfi)
             .samples(crate::util::to_vk_sample_count(create_info.sample_count))
-            .flags(vk::ImageCreateFlags::empty());
+            .flags(
+                if create_info.sparse {
+                    vk::ImageCreateFlags::SPARSE_BINDING | vk::ImageCreateFlags::SPARSE_RESIDENCY
+                } else {
+                    vk::ImageCreateFlags::empty()
+                } | if slice_views {
+                    vk::ImageCreateFlags::TYPE_2D_ARRAY_COMPATIBLE
+                } else {
+                    vk::ImageCreateFlags::empty()
+                },
+            );
 
         let image = match device.create_image(&image_create_info, None) {
             Ok(image) => image,
             Err(err) => return Err(TextureCreateError::Other(err.to_string())),
         };
 
+        let aspect_flags = if create_info.format.is_color() {
+            vk::ImageAspectFlags::COLOR
+        } else {
+            vk::ImageAspectFlags::DEPTH
+                | if create_info.format.is_stencil() {
+                    vk::ImageAspectFlags::STENCIL
+                } else {
+                    vk::ImageAspectFlags::empty()
+                }
+        };
+
         // Determine memory requirements
         let mem_reqs = device.get_image_memory_requirements(image);
 
-        // Allocate memory
-        let request = AllocationCreateDesc {
-            name: match &create_info.debug_name {
-                Some(name) => name,
-                None => "image",
-            },
-            allocation_scheme: AllocationScheme::GpuAllocatorManaged,
-            requirements: mem_reqs,
-            location: crate::util::to_gpu_allocator_memory_location(create_info.memory_usage),
-            linear: false,
-        };
+        // Sparse textures have memory bound to them later, one page at a time
+        let memory = if create_info.sparse {
+            match SparsePages::new(device, image, aspect_flags, extent) {
+                Some(pages) => TextureMemory::Sparse(Mutex::new(pages)),
+                None => {
+                    device.destroy_image(image, None);
+                    return Err(TextureCreateError::SparseNotSupported);
+                }
+            }
+        } else {
+            // Allocate memory
+            let request = AllocationCreateDesc {
+                name: match &create_info.debug_name {
+                    Some(name) => name,
+                    None => "image",
+                },
+                allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+                requirements: mem_reqs,
+                location: crate::util::to_gpu_allocator_memory_location(create_info.memory_usage),
+                linear: false,
+            };
 
-        let block = match allocator.allocate(&request) {
-            Ok(block) => block,
-            Err(err) => {
+            let block = match allocator.allocate_with_fallback(
+                &request,
+                MemoryCategory::Texture,
+                create_info.memory_fallback,
+            ) {
+                Ok(block) => block,
+                Err(err) => {
+                    device.destroy_image(image, None);
+                    return Err(err.into());
+                }
+            };
+
+            // Bind image to memory
+            if let Err(err) = device.bind_image_memory(image, block.memory(), block.offset()) {
+                allocator.free(block).unwrap();
                 device.destroy_image(image, None);
                 return Err(TextureCreateError::Other(err.to_string()));
             }
-        };
 
-        // Bind image to memory
-        if let Err(err) = device.bind_image_memory(image, block.memory(), block.offset()) {
-            allocator.free(block).unwrap();
-            device.destroy_image(image, None);
-            return Err(TextureCreateError::Other(err.to_string()));
-        }
+            TextureMemory::Dedicated(ManuallyDrop::new(block))
+        };
 
         // Create views
-        let mut views = Vec::with_capacity(create_info.array_elements * create_info.mip_levels);
-        let aspect_flags = if create_info.format.is_color() {
-            vk::ImageAspectFlags::COLOR
-        } else {
-            vk::ImageAspectFlags::DEPTH
-                | if create_info.format.is_stencil() {
-                    vk::ImageAspectFlags::STENCIL
-                } else {
-                    vk::ImageAspectFlags::empty()
-                }
+        let view_type = crate::util::to_vk_image_view_type(create_info.ty);
+        let create_view = |view_type, mip, layer| {
+            let view_create_info = vk::ImageViewCreateInfo::default()
+                .format(format)
+                .view_type(view_type)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: aspect_flags,
+                    base_mip_level: mip,
+                    level_count: 1,
+                    base_array_layer: layer,
+                    layer_count: 1,
+                })
+                .components(vk::ComponentMapping {
+                    r: vk::ComponentSwizzle::R,
+                    g: vk::ComponentSwizzle::G,
+                    b: vk::ComponentSwizzle::B,
+                    a: vk::ComponentSwizzle::A,
+                })
+                .image(image);
+            device.create_image_view(&view_create_info, None).unwrap()
         };
+
+        let mut views = Vec::with_capacity(create_info.array_elements * create_info.mip_levels);
         for i in 0..create_info.array_elements {
             for j in 0..create_info.mip_levels {
-                let view_create_info = vk::ImageViewCreateInfo::default()
-                    .format(format)
-                    .view_type(vk::ImageViewType::TYPE_2D)
-                    .subresource_range(vk::ImageSubresourceRange {
-                        aspect_mask: aspect_flags,
-                        base_mip_level: j as u32,
-                        level_count: 1,
-                        base_array_layer: i as u32,
-                        layer_count: 1,
-                    })
-                    .components(vk::ComponentMapping {
-                        r: vk::ComponentSwizzle::R,
-                        g: vk::ComponentSwizzle::G,
-                        b: vk::ComponentSwizzle::B,
-                        a: vk::ComponentSwizzle::A,
-                    })
-                    .image(image);
-                views.push(device.create_image_view(&view_create_info, None).unwrap());
+                views.push(create_view(view_type, j as u32, i as u32));
+            }
+        }
+
+        if slice_views {
+            for j in 0..create_info.mip_levels {
+                for k in 0..(create_info.depth >> j).max(1) {
+                    views.push(create_view(vk::ImageViewType::TYPE_2D, j as u32, k));
+                }
             }
         }
 
         // Setup debug name is requested
-        if let Some(name) = create_info.debug_name {
+        if let Some(name) = &create_info.debug_name {
             if let Some(debug) = debug {
                 let cstr_name = CString::new(name.as_str()).unwrap();
                 let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
@@ -167,7 +284,10 @@ impl Texture {
             image,
             id: id_gen.create(),
             views,
-            block: ManuallyDrop::new(block),
+            view_type,
+            sampled_views: Mutex::default(),
+            layered_views: Mutex::default(),
+            memory,
             _image_usage: create_info.texture_usage,
             _memory_usage: create_info.memory_usage,
             _array_elements: create_info.array_elements,
@@ -178,6 +298,8 @@ impl Texture {
             format,
             aspect_flags,
             mip_count: create_info.mip_levels as u32,
+            depth: create_info.depth,
+            debug_name: create_info.debug_name,
         })
     }
 
@@ -185,15 +307,130 @@ impl Texture {
     pub(crate) fn get_view(&self, array_elem: usize, mip: usize) -> vk::ImageView {
         self.views[(array_elem * self.mip_count as usize) + mip]
     }
+
+    /// Gets a view for sampling the texture, creating it if it doesn't exist yet.
+    pub(crate) unsafe fn get_sampled_view(
+        &self,
+        device: &ash::Device,
+        debug: Option<&ash::ext::debug_utils::Device>,
+        key: SampledViewKey,
+    ) -> vk::ImageView {
+        *self
+            .sampled_views
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| {
+                let create_info = vk::ImageViewCreateInfo::default()
+                    .format(self.format)
+                    .view_type(self.view_type)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: key.aspect,
+                        base_mip_level: key.base_mip,
+                        level_count: key.mip_count,
+                        base_array_layer: key.array_element,
+                        layer_count: 1,
+                    })
+                    .components(vk::ComponentMapping {
+                        r: vk::ComponentSwizzle::R,
+                        g: vk::ComponentSwizzle::G,
+                        b: vk::ComponentSwizzle::B,
+                        a: vk::ComponentSwizzle::A,
+                    })
+                    .image(self.image);
+                let view = device.create_image_view(&create_info, None).unwrap();
+                if let Some(name) = &self.debug_name {
+                    crate::util::set_debug_name(
+                        debug,
+                        view,
+                        &format!("{name}_sampled_view_{}_{}", key.array_element, key.base_mip),
+                    );
+                }
+                view
+            })
+    }
+
+    /// Gets an array view of `layer_count` layers of a mip for use as a layered attachment,
+    /// creating it if it doesn't exist yet.
+    pub(crate) unsafe fn get_layered_view(
+        &self,
+        device: &ash::Device,
+        debug: Option<&ash::ext::debug_utils::Device>,
+        mip: usize,
+        base_layer: usize,
+        layer_count: usize,
+    ) -> vk::ImageView {
+        let key = (mip as u32, base_layer as u32, layer_count as u32);
+        *self
+            .layered_views
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| {
+                let create_info = vk::ImageViewCreateInfo::default()
+                    .format(self.format)
+                    .view_type(vk::ImageViewType::TYPE_2D_ARRAY)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: self.aspect_flags,
+                        base_mip_level: key.0,
+                        level_count: 1,
+                        base_array_layer: key.1,
+                        layer_count: key.2,
+                    })
+                    .components(vk::ComponentMapping {
+                        r: vk::ComponentSwizzle::R,
+                        g: vk::ComponentSwizzle::G,
+                        b: vk::ComponentSwizzle::B,
+                        a: vk::ComponentSwizzle::A,
+                    })
+                    .image(self.image);
+                let view = device.create_image_view(&create_info, None).unwrap();
+                if let Some(name) = &self.debug_name {
+                    crate::util::set_debug_name(
+                        debug,
+                        view,
+                        &format!("{name}_layered_view_{}_{}_{}", key.0, key.1, key.2),
+                    );
+                }
+                view
+            })
+    }
+
+    /// Gets a 2D view of a single depth slice of a mip of a 3D texture. Only valid for 3D
+    /// textures created with `COLOR_ATTACHMENT` usage.
+    #[inline(always)]
+    pub(crate) fn get_slice_view(&self, mip: usize, slice: u32) -> vk::ImageView {
+        let first_slice: u32 = (0..mip).map(|j| (self.depth >> j).max(1)).sum();
+        self.views[self.mip_count as usize + (first_slice + slice) as usize]
+    }
 }
 
 impl Drop for Texture {
     fn drop(&mut self) {
+        let mut views = std::mem::take(&mut self.views);
+        views.extend(
+            self.sampled_views
+                .get_mut()
+                .unwrap()
+                .drain()
+                .map(|(_, view)| view),
+        );
+        views.extend(
+            self.layered_views
+                .get_mut()
+                .unwrap()
+                .drain()
+                .map(|(_, view)| view),
+        );
+
         let _ = self.on_drop.send(Garbage::Texture {
             image: self.image,
             id: self.id,
-            views: std::mem::take(&mut self.views),
-            allocation: unsafe { ManuallyDrop::take(&mut self.block) },
+            views,
+            allocations: match &mut self.memory {
+                TextureMemory::Dedicated(block) => vec![unsafe { ManuallyDrop::take(block) }],
+                TextureMemory::Sparse(pages) => pages.get_mut().unwrap().release(),
+            },
             ref_counter: self.ref_counter.clone(),
         });
     }
@@ -2,6 +2,7 @@ use std::{ffi::CString, mem::ManuallyDrop};
 
 use api::{
     buffer::Buffer,
+    context::MemoryCategory,
     tlas::{TopLevelAccelerationStructureCreateError, TopLevelAccelerationStructureCreateInfo},
     types::{BuildAccelerationStructureFlags, MemoryUsage, SharingMode},
 };
@@ -24,6 +25,7 @@ pub struct TopLevelAccelerationStructure {
     pub(crate) acceleration_struct: vk::AccelerationStructureKHR,
     pub(crate) sharing_mode: SharingMode,
     pub(crate) scratch_size: u64,
+    pub(crate) update_scratch_size: u64,
     pub(crate) flags: BuildAccelerationStructureFlags,
     on_drop: Sender<Garbage>,
 }
@@ -93,13 +95,11 @@ impl TopLevelAccelerationStructure {
             location: crate::util::to_gpu_allocator_memory_location(MemoryUsage::GpuOnly),
             linear: true,
         };
-        let block = match allocator.allocate(&request) {
+        let block = match allocator.allocate(&request, MemoryCategory::AccelerationStructure) {
             Ok(block) => block,
             Err(err) => {
                 ctx.device.destroy_buffer(buffer, None);
-                return Err(TopLevelAccelerationStructureCreateError::Other(
-                    err.to_string(),
-                ));
+                return Err(err.into());
             }
         };
 
@@ -116,7 +116,7 @@ impl TopLevelAccelerationStructure {
         }
 
         // Setup debug name is requested
-        if let Some(name) = create_info.debug_name {
+        if let Some(name) = &create_info.debug_name {
             if let Some(debug) = &ctx.debug {
                 let name = CString::new(format!("{name}_buffer")).unwrap();
                 let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
@@ -146,6 +146,13 @@ impl TopLevelAccelerationStructure {
                 ))
             }
         };
+        if let Some(name) = &create_info.debug_name {
+            crate::util::set_debug_name(
+                ctx.debug.as_ref().map(|debug| &debug.device),
+                acceleration_struct,
+                name,
+            );
+        }
 
         Ok(TopLevelAccelerationStructure {
             on_drop: ctx.garbage.sender(),
@@ -157,6 +164,14 @@ impl TopLevelAccelerationStructure {
             acceleration_struct,
             sharing_mode: create_info.sharing_mode,
             scratch_size: sizes.build_scratch_size,
+            update_scratch_size: if create_info
+                .flags
+                .contains(BuildAccelerationStructureFlags::ALLOW_UPDATE)
+            {
+                sizes.update_scratch_size
+            } else {
+                0
+            },
             flags: create_info.flags,
         })
     }
@@ -166,6 +181,12 @@ impl TopLevelAccelerationStructure {
         self.scratch_size
     }
 
+    #[inline(always)]
+    pub(crate) fn update_scratch_size(&self) -> u64 {
+        self.update_scratch_size
+    }
+
+    /// Builds the TLAS from scratch, or refits the previous build in place if `update` is set.
     pub(crate) unsafe fn build(
         &self,
         device: &ash::Device,
@@ -176,6 +197,7 @@ impl TopLevelAccelerationStructure {
         scratch_array_element: usize,
         src: &Buffer<crate::VulkanBackend>,
         src_array_element: usize,
+        update: bool,
     ) {
         let instances = vk::AccelerationStructureGeometryInstancesDataKHR::default()
             .array_of_pointers(true)
@@ -190,9 +212,22 @@ impl TopLevelAccelerationStructure {
             .flags(vk::GeometryFlagsKHR::OPAQUE)
             .geometry(vk::AccelerationStructureGeometryDataKHR { instances })];
 
+        let (mode, src_acceleration_struct) = if update {
+            (
+                vk::BuildAccelerationStructureModeKHR::UPDATE,
+                self.acceleration_struct,
+            )
+        } else {
+            (
+                vk::BuildAccelerationStructureModeKHR::BUILD,
+                vk::AccelerationStructureKHR::null(),
+            )
+        };
+
         let build_geo_info = [vk::AccelerationStructureBuildGeometryInfoKHR::default()
             .ty(vk::AccelerationStructureTypeKHR::TOP_LEVEL)
-            .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
+            .mode(mode)
+            .src_acceleration_structure(src_acceleration_struct)
             .dst_acceleration_structure(self.acceleration_struct)
             .flags(crate::util::to_vk_as_build_flags(self.flags))
             .geometries(&geo)
@@ -3,16 +3,16 @@ use api::{
     buffer::Buffer,
     command_buffer::{
         BlitDestination, BlitSource, BufferCubeMapCopy, BufferTextureCopy, Command,
-        CopyBufferToBuffer, CopyTextureToTexture,
+        CopyBufferToBuffer, CopyTextureToTexture, EventResource,
     },
     compute_pass::ComputePassDispatch,
     cube_map::CubeMap,
-    descriptor_set::DescriptorSet,
     render_pass::{
         ColorAttachmentDestination, DepthStencilAttachmentDestination, RenderPassDescriptor,
+        MAX_ATTACHMENT_LAYERS,
     },
     rt_pass::RayTracingDispatchSource,
-    texture::{Blit, Texture},
+    texture::{Blit, SparsePageRegion, Texture},
     tlas::TopLevelAccelerationStructure,
     types::{BufferUsage, CubeFace, LoadOp, QueueType, SharingMode, StoreOp, TextureUsage},
 };
@@ -25,12 +25,14 @@ use rustc_hash::FxHashMap;
 use std::ops::Range;
 
 use crate::{
-    descriptor_set::BoundValue,
+    descriptor_set::{BoundValue, DescriptorSet, PushedSets},
     util::usage::{GlobalBufferUsage, PipelineBarrier},
     QueueFamilyIndices,
 };
 
 use super::{
+    events::{EventKey, SplitBarriers},
+    id_gen::ResourceId,
     semaphores::{SemaphoreTracker, WaitInfo},
     usage::{
         BufferRegion, GlobalImageUsage, GlobalResourceUsage, GlobalSetUsage, ImageRegion,
@@ -50,6 +52,8 @@ pub(crate) struct CommandSorting {
     buffer_barriers: Vec<vk::BufferMemoryBarrier2<'static>>,
     /// All image memory barriers.
     image_barriers: Vec<vk::ImageMemoryBarrier2<'static>>,
+    /// Barriers performed by signal and wait pairs instead of right before their commands.
+    split_barriers: SplitBarriers,
 }
 
 unsafe impl Send for CommandSorting {}
@@ -65,6 +69,12 @@ pub(crate) struct CommandSortingInfo<'a> {
     pub timeline_value: u64,
     /// Timeline value for each queue type. u64::MAX indicates queue type is unused.
     pub wait_queues: [Option<u64>; 4],
+    /// Validate queue ownership of resources. Enabled alongside the debug layer.
+    pub validate: bool,
+    /// Sets holding the descriptors pushed by the commands.
+    pub pushed: &'a PushedSets<'a>,
+    /// `VK_EXT_conditional_rendering` is enabled on the device.
+    pub conditional_rendering: bool,
 }
 
 #[derive(Default)]
@@ -90,6 +100,7 @@ impl CommandSorting {
         self.memory_barriers.clear();
         self.buffer_barriers.clear();
         self.image_barriers.clear();
+        self.split_barriers.clear();
 
         if info.commands.len() > self.commands.len() {
             self.commands
@@ -104,15 +115,48 @@ impl CommandSorting {
             command.memory = Range::default();
         });
 
+        // Query and conditional rendering regions, as well as labels, act as fences so that
+        // commands can't be reordered around them. `fence` is the last region boundary seen and
+        // `since_fence` holds every top level command recorded after it.
+        let mut fence = None;
+        let mut since_fence: Vec<usize> = Vec::default();
+
         let mut i = 0;
         while i < info.commands.len() {
             let command = &info.commands[i];
 
+            match command {
+                Command::BeginTimestamp(_)
+                | Command::EndTimestamp
+                | Command::BeginStatistics(_)
+                | Command::EndStatistics
+                | Command::BeginConditionalRendering { .. }
+                | Command::EndConditionalRendering
+                | Command::InsertLabel { .. } => {
+                    if since_fence.is_empty() {
+                        since_fence.extend(fence);
+                    }
+                    for dependency in since_fence.drain(..) {
+                        self.commands[dependency].dependents.push(i);
+                        self.commands[i].dependency_count += 1;
+                    }
+                    fence = Some(i);
+                }
+                _ => {
+                    if let Some(fence) = fence {
+                        self.commands[fence].dependents.push(i);
+                        self.commands[i].dependency_count += 1;
+                    }
+                    since_fence.push(i);
+                }
+            }
+
             let old_memory_barrier_count = self.memory_barriers.len();
             let old_buffer_barrier_count = self.buffer_barriers.len();
             let old_image_barrier_count = self.image_barriers.len();
 
             let new_i = self.inspect_command(info, command, i);
+            merge_image_barriers(&mut self.image_barriers, old_image_barrier_count);
 
             let command = &mut self.commands[i];
             command.buffers = old_buffer_barrier_count..self.buffer_barriers.len();
@@ -125,21 +169,41 @@ impl CommandSorting {
 
             i = new_i;
         }
+
+        for split in self.split_barriers.iter_mut() {
+            merge_image_barriers(&mut split.images, 0);
+        }
+    }
+
+    #[inline(always)]
+    pub fn split_barriers(&self) -> &SplitBarriers {
+        &self.split_barriers
     }
 
+    /// Records the commands in sorted order. `events` holds the event used by each split
+    /// barrier.
+    ///
+    /// The DAG is left untouched, so it can be executed again by recorded commands.
     pub unsafe fn execute_commands<'a>(
-        &'a mut self,
+        &'a self,
         device: &Device,
         cb: vk::CommandBuffer,
+        events: &[vk::Event],
         commands: &[Command<'a, crate::VulkanBackend>],
         mut exec: impl FnMut(vk::CommandBuffer, &Device, usize, &[Command<'a, crate::VulkanBackend>]),
     ) {
-        let mut next_commands = std::mem::take(&mut self.next_commands);
+        let mut next_commands = self.next_commands.clone();
+        let mut dependency_counts: Vec<_> = self
+            .commands
+            .iter()
+            .map(|command| command.dependency_count)
+            .collect();
 
         let mut memory_barriers = Vec::default();
         let mut memory_barriers_map = FxHashMap::default();
         let mut buffer_barriers = Vec::default();
         let mut image_barriers = Vec::default();
+        let mut waited = vec![false; self.split_barriers.len()];
 
         let initial_memory_barrier = [vk::MemoryBarrier2::default()
             .src_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
@@ -180,6 +244,26 @@ impl CommandSorting {
                 }
             }
 
+            // Wait on the split barriers consumed by the commands about to execute
+            let mut wait_events = Vec::default();
+            let mut wait_infos = Vec::default();
+            for (i, split) in self.split_barriers.iter().enumerate() {
+                if !waited[i]
+                    && split
+                        .consumers
+                        .iter()
+                        .any(|consumer| next_commands.contains(consumer))
+                {
+                    waited[i] = true;
+                    wait_events.push(events[i]);
+                    wait_infos.push(split.dependency_info());
+                }
+            }
+
+            if !wait_events.is_empty() {
+                device.cmd_wait_events2(cb, &wait_events, &wait_infos);
+            }
+
             // Execute the barrier if needed
             if !memory_barriers_map.is_empty()
                 || !buffer_barriers.is_empty()
@@ -211,17 +295,21 @@ impl CommandSorting {
                 // Execute
                 exec(cb, device, command_idx, commands);
 
+                // Begin the split barriers signaled by the command
+                for (i, split) in self.split_barriers.iter().enumerate() {
+                    if split.signal == command_idx && !split.is_empty() {
+                        device.cmd_set_event2(cb, events[i], &split.dependency_info());
+                    }
+                }
+
                 // Update next commands
-                let mut deps = std::mem::take(&mut self.commands[command_idx].dependents);
-                for dep in deps.drain(..) {
-                    let dep_command = &mut self.commands[dep];
-                    dep_command.dependency_count -= 1;
+                for dep in &self.commands[command_idx].dependents {
+                    dependency_counts[*dep] -= 1;
 
-                    if dep_command.dependency_count == 0 {
-                        new_commands.push(dep);
+                    if dependency_counts[*dep] == 0 {
+                        new_commands.push(*dep);
                     }
                 }
-                self.commands[command_idx].dependents = deps;
             }
             next_commands = new_commands;
         }
@@ -301,6 +389,70 @@ impl CommandSorting {
                 self.inspect_copy_buffer_to_buffer(info, command_idx, copy);
                 command_idx + 1
             }
+            Command::FillBuffer {
+                buffer,
+                array_element,
+                ..
+            }
+            | Command::UpdateBuffer {
+                buffer,
+                array_element,
+                ..
+            } => {
+                self.inspect_buffer_transfer_write(info, command_idx, buffer, *array_element);
+                command_idx + 1
+            }
+            Command::ClearTexture {
+                texture,
+                array_element,
+                mip_level,
+                ..
+            } => {
+                let texture = texture.internal();
+                self.inspect_clear(
+                    info,
+                    command_idx,
+                    texture.id,
+                    texture.image,
+                    texture.sharing_mode,
+                    texture.aspect_flags,
+                    *array_element as u32..(*array_element as u32 + 1),
+                    *mip_level as u32,
+                );
+                command_idx + 1
+            }
+            Command::ClearCubeMap {
+                cube_map,
+                array_element,
+                mip_level,
+                ..
+            } => {
+                let cube_map = cube_map.internal();
+                let base_face = *array_element as u32 * 6;
+                for face in base_face..(base_face + 6) {
+                    Self::validate_cube_map_owner(
+                        info,
+                        &ImageRegion {
+                            id: cube_map.id,
+                            array_elem: face,
+                            base_mip_level: *mip_level as u32,
+                            mip_count: 1,
+                        },
+                        cube_map.sharing_mode,
+                    );
+                }
+                self.inspect_clear(
+                    info,
+                    command_idx,
+                    cube_map.id,
+                    cube_map.image,
+                    cube_map.sharing_mode,
+                    cube_map.aspect_flags,
+                    base_face..(base_face + 6),
+                    *mip_level as u32,
+                );
+                command_idx + 1
+            }
             Command::CopyTextureToTexture(copy) => {
                 self.inspect_copy_texture_to_texture(info, command_idx, copy);
                 command_idx + 1
@@ -344,18 +496,16 @@ impl CommandSorting {
             Command::SetTextureUsage {
                 tex,
                 new_usage,
-                array_elem,
-                base_mip,
-                mip_count,
+                array_elems,
+                mips,
             } => {
                 self.inspect_set_texture_usage(
                     info,
                     command_idx,
                     tex,
                     *new_usage,
-                    *array_elem,
-                    *base_mip,
-                    *mip_count,
+                    array_elems.clone(),
+                    mips.clone(),
                 );
                 command_idx + 1
             }
@@ -367,6 +517,20 @@ impl CommandSorting {
                 self.inspect_blas_build(info, command_idx, blas, scratch, *scratch_array_element);
                 command_idx + 1
             }
+            Command::BuildBlasBatch {
+                blases,
+                scratch,
+                scratch_array_element,
+            } => {
+                self.inspect_blas_batch_build(
+                    info,
+                    command_idx,
+                    blases,
+                    scratch,
+                    *scratch_array_element,
+                );
+                command_idx + 1
+            }
             Command::BuildTlas {
                 tlas,
                 scratch,
@@ -383,6 +547,27 @@ impl CommandSorting {
                     *scratch_array_element,
                     src,
                     *src_array_element,
+                    false,
+                );
+                command_idx + 1
+            }
+            Command::UpdateTlas {
+                tlas,
+                scratch,
+                scratch_array_element,
+                src,
+                src_array_element,
+                ..
+            } => {
+                self.inspect_tlas_build(
+                    info,
+                    command_idx,
+                    tlas,
+                    scratch,
+                    *scratch_array_element,
+                    src,
+                    *src_array_element,
+                    true,
                 );
                 command_idx + 1
             }
@@ -394,6 +579,29 @@ impl CommandSorting {
                 self.inspect_blas_compact(info, command_idx, src, dst);
                 command_idx + 1
             }
+            Command::BindSparsePages { texture, regions }
+            | Command::UnbindSparsePages { texture, regions } => {
+                self.inspect_sparse_pages(info, command_idx, texture, regions);
+                command_idx + 1
+            }
+            Command::BeginConditionalRendering {
+                buffer,
+                array_element,
+                ..
+            } => {
+                self.inspect_conditional_rendering(info, command_idx, buffer, *array_element);
+                command_idx + 1
+            }
+            Command::SignalEvent { resources } => {
+                self.inspect_signal_event(info, command_idx, resources);
+                command_idx + 1
+            }
+            Command::WaitEvent { resources } => {
+                let mut keys = Vec::default();
+                for_each_event_subresource(resources, |key, _| keys.push(key));
+                self.split_barriers.wait(command_idx, keys);
+                command_idx + 1
+            }
             _ => command_idx + 1,
         }
     }
@@ -416,7 +624,7 @@ impl CommandSorting {
             self.inspect_depth_stencil_attachment(
                 info,
                 command_idx,
-                attachment.store_op,
+                (attachment.store_op, attachment.stencil_store_op),
                 &attachment.load_op,
                 &attachment.dst,
                 false,
@@ -427,7 +635,7 @@ impl CommandSorting {
             self.inspect_depth_stencil_attachment(
                 info,
                 command_idx,
-                attachment.store_op,
+                (attachment.store_op, attachment.store_op),
                 &attachment.load_op,
                 &attachment.dst,
                 true,
@@ -474,7 +682,7 @@ impl CommandSorting {
         destination: &ColorAttachmentDestination<'_, crate::VulkanBackend>,
     ) {
         struct InspectionSource {
-            regions: ArrayVec<ImageRegion, 6>,
+            regions: ArrayVec<ImageRegion, MAX_ATTACHMENT_LAYERS>,
             image: vk::Image,
             sharing_mode: SharingMode,
             final_layout: vk::ImageLayout,
@@ -528,6 +736,25 @@ impl CommandSorting {
                     final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                 }
             }
+            ColorAttachmentDestination::TextureSlice {
+                texture, mip_level, ..
+            } => {
+                // Layouts apply to the whole mip, so the entire mip is tracked
+                let mut regions = ArrayVec::new();
+                regions.push(ImageRegion {
+                    id: texture.internal().id,
+                    array_elem: 0,
+                    base_mip_level: *mip_level as u32,
+                    mip_count: 1,
+                });
+
+                InspectionSource {
+                    regions,
+                    image: texture.internal().image,
+                    sharing_mode: texture.internal().sharing_mode,
+                    final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                }
+            }
             ColorAttachmentDestination::CubeFace {
                 cube_map,
                 array_element,
@@ -543,6 +770,10 @@ impl CommandSorting {
                     mip_count: 1,
                 });
 
+                for region in &regions {
+                    Self::validate_cube_map_owner(info, region, cube_map.internal().sharing_mode);
+                }
+
                 InspectionSource {
                     regions,
                     image: cube_map.internal().image,
@@ -565,6 +796,10 @@ impl CommandSorting {
                     });
                 }
 
+                for region in &regions {
+                    Self::validate_cube_map_owner(info, region, cube_map.internal().sharing_mode);
+                }
+
                 InspectionSource {
                     regions,
                     image: cube_map.internal().image,
@@ -572,6 +807,29 @@ impl CommandSorting {
                     final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                 }
             }
+            ColorAttachmentDestination::TextureArray {
+                texture,
+                base_layer,
+                layer_count,
+                mip_level,
+            } => {
+                let mut regions = ArrayVec::new();
+                for i in *base_layer..(*base_layer + *layer_count) {
+                    regions.push(ImageRegion {
+                        id: texture.internal().id,
+                        array_elem: i as u32,
+                        base_mip_level: *mip_level as u32,
+                        mip_count: 1,
+                    });
+                }
+
+                InspectionSource {
+                    regions,
+                    image: texture.internal().image,
+                    sharing_mode: texture.internal().sharing_mode,
+                    final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                }
+            }
         };
 
         let new_usage = GlobalImageUsage {
@@ -589,7 +847,7 @@ impl CommandSorting {
             layout: src.final_layout,
         };
 
-        let mut old_usages = [GlobalImageUsage::default(); 6];
+        let mut old_usages = [GlobalImageUsage::default(); MAX_ATTACHMENT_LAYERS];
         src.regions.iter().enumerate().for_each(|(i, region)| {
             info.global
                 .use_image(region, &new_usage, &mut old_usages[i..(i + 1)]);
@@ -623,13 +881,13 @@ impl CommandSorting {
         &mut self,
         info: &mut CommandSortingInfo,
         command_idx: usize,
-        store_op: StoreOp,
+        (store_op, stencil_store_op): (StoreOp, StoreOp),
         _load_op: &LoadOp,
         destination: &DepthStencilAttachmentDestination<'_, crate::VulkanBackend>,
         is_resolve_attachment: bool,
     ) {
         struct InspectionSource {
-            regions: ArrayVec<ImageRegion, 6>,
+            regions: ArrayVec<ImageRegion, MAX_ATTACHMENT_LAYERS>,
             image: vk::Image,
             sharing_mode: SharingMode,
             aspect_flags: vk::ImageAspectFlags,
@@ -671,6 +929,10 @@ impl CommandSorting {
                     mip_count: 1,
                 });
 
+                for region in &regions {
+                    Self::validate_cube_map_owner(info, region, cube_map.internal().sharing_mode);
+                }
+
                 InspectionSource {
                     regions,
                     image: cube_map.internal().image,
@@ -693,6 +955,10 @@ impl CommandSorting {
                     });
                 }
 
+                for region in &regions {
+                    Self::validate_cube_map_owner(info, region, cube_map.internal().sharing_mode);
+                }
+
                 InspectionSource {
                     regions,
                     image: cube_map.internal().image,
@@ -700,9 +966,32 @@ impl CommandSorting {
                     aspect_flags: cube_map.internal().aspect_flags,
                 }
             }
+            DepthStencilAttachmentDestination::TextureArray {
+                texture,
+                base_layer,
+                layer_count,
+                mip_level,
+            } => {
+                let mut regions = ArrayVec::new();
+                for i in *base_layer..(*base_layer + *layer_count) {
+                    regions.push(ImageRegion {
+                        id: texture.internal().id,
+                        array_elem: i as u32,
+                        base_mip_level: *mip_level as u32,
+                        mip_count: 1,
+                    });
+                }
+
+                InspectionSource {
+                    regions,
+                    image: texture.internal().image,
+                    sharing_mode: texture.internal().sharing_mode,
+                    aspect_flags: texture.internal().aspect_flags,
+                }
+            }
         };
 
-        let final_layout = crate::util::depth_store_op_to_layout(store_op);
+        let final_layout = crate::util::depth_store_op_to_layout(store_op, stencil_store_op);
 
         let new_usage = GlobalImageUsage {
             queue: Some(QueueUsage {
@@ -724,8 +1013,10 @@ impl CommandSorting {
                 }
             } else {
                 SubResourceUsage {
-                    access: match store_op {
-                        StoreOp::None => vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ,
+                    access: match (store_op, stencil_store_op) {
+                        (StoreOp::None, StoreOp::None) => {
+                            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
+                        }
                         _ => {
                             vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
                                 | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE
@@ -738,7 +1029,7 @@ impl CommandSorting {
             layout: final_layout,
         };
 
-        let mut old_usages = [GlobalImageUsage::default(); 6];
+        let mut old_usages = [GlobalImageUsage::default(); MAX_ATTACHMENT_LAYERS];
         src.regions.iter().enumerate().for_each(|(i, region)| {
             info.global
                 .use_image(region, &new_usage, &mut old_usages[i..(i + 1)]);
@@ -870,15 +1161,36 @@ impl CommandSorting {
                         command_idx,
                         vk::PipelineStageFlags2::VERTEX_SHADER
                             | vk::PipelineStageFlags2::FRAGMENT_SHADER,
-                        set,
+                        set.internal(),
                     );
                 }
                 true
             }
+            Command::PushDescriptorSet { .. } => {
+                let pushed = info.pushed;
+                self.inspect_descriptor_set(
+                    info,
+                    command_idx,
+                    vk::PipelineStageFlags2::VERTEX_SHADER
+                        | vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                    pushed.get(rp_command),
+                );
+                true
+            }
             Command::DrawIndexedIndirect {
                 buffer,
                 array_element,
                 ..
+            }
+            | Command::DrawIndirect {
+                buffer,
+                array_element,
+                ..
+            }
+            | Command::DrawMeshTasksIndirect {
+                buffer,
+                array_element,
+                ..
             } => {
                 let new_usage = GlobalBufferUsage {
                     queue: Some(QueueUsage {
@@ -927,6 +1239,13 @@ impl CommandSorting {
                 count_buffer,
                 count_array_element,
                 ..
+            }
+            | Command::DrawMeshTasksIndirectCount {
+                draw_buffer,
+                draw_array_element,
+                count_buffer,
+                count_array_element,
+                ..
             } => {
                 let new_usage = GlobalBufferUsage {
                     queue: Some(QueueUsage {
@@ -995,6 +1314,12 @@ impl CommandSorting {
 
                 true
             }
+            Command::RenderPassSlices(slices) => {
+                for command in slices.iter().flatten() {
+                    self.inspect_render_pass_command(info, command_idx, command);
+                }
+                true
+            }
             Command::EndRenderPass(_) => false,
             _ => true,
         }
@@ -1013,12 +1338,22 @@ impl CommandSorting {
                         info,
                         command_idx,
                         vk::PipelineStageFlags2::COMPUTE_SHADER,
-                        set,
+                        set.internal(),
                     );
                 }
                 true
             }
-            Command::EndComputePass(dispatch, _) => {
+            Command::PushDescriptorSet { .. } => {
+                let pushed = info.pushed;
+                self.inspect_descriptor_set(
+                    info,
+                    command_idx,
+                    vk::PipelineStageFlags2::COMPUTE_SHADER,
+                    pushed.get(cp_command),
+                );
+                true
+            }
+            Command::Dispatch(dispatch) => {
                 if let ComputePassDispatch::Indirect {
                     buffer,
                     array_element,
@@ -1064,8 +1399,9 @@ impl CommandSorting {
                         (info.queue, info.timeline_value),
                     );
                 }
-                false
+                true
             }
+            Command::EndComputePass(_) => false,
             _ => true,
         }
     }
@@ -1083,11 +1419,21 @@ impl CommandSorting {
                         info,
                         command_idx,
                         vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
-                        set,
+                        set.internal(),
                     );
                 }
                 true
             }
+            Command::PushDescriptorSet { .. } => {
+                let pushed = info.pushed;
+                self.inspect_descriptor_set(
+                    info,
+                    command_idx,
+                    vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+                    pushed.get(rtp_command),
+                );
+                true
+            }
             Command::EndRayTracingPass(dispatch, _) => {
                 // Check possible indirect dispatch
                 if let RayTracingDispatchSource::Indirect {
@@ -1187,7 +1533,7 @@ impl CommandSorting {
         info: &mut CommandSortingInfo,
         command_idx: usize,
         valid_stages: vk::PipelineStageFlags2,
-        set: &DescriptorSet<crate::VulkanBackend>,
+        set: &DescriptorSet,
     ) {
         // Use set
         let new_usage = GlobalSetUsage {
@@ -1199,13 +1545,12 @@ impl CommandSorting {
             }),
         };
 
-        info.global.use_set(set.internal().id, &new_usage);
+        info.global.use_set(set.id, &new_usage);
 
         let mut out_usages: [GlobalImageUsage; 14] = Default::default();
 
         // Use set resources
-        set.internal()
-            .bound
+        set.bound
             .iter()
             .flat_map(|binding| binding.iter().flatten())
             .for_each(|elem| match &elem.value {
@@ -1215,6 +1560,7 @@ impl CommandSorting {
                     array_element,
                     aligned_size,
                     sharing_mode,
+                    offset,
                     ..
                 } => {
                     let new_usage = GlobalBufferUsage {
@@ -1246,7 +1592,7 @@ impl CommandSorting {
                         *buffer,
                         *sharing_mode,
                         *aligned_size as u64,
-                        (*aligned_size * *array_element) as u64,
+                        *offset,
                     );
 
                     self.dependency_check(
@@ -1262,6 +1608,7 @@ impl CommandSorting {
                     array_element,
                     aligned_size,
                     sharing_mode,
+                    offset,
                     ..
                 } => {
                     let new_usage = GlobalBufferUsage {
@@ -1293,7 +1640,7 @@ impl CommandSorting {
                         *buffer,
                         *sharing_mode,
                         *aligned_size as u64,
-                        (*aligned_size * *array_element) as u64,
+                        *offset,
                     );
 
                     self.dependency_check(
@@ -1420,12 +1767,12 @@ impl CommandSorting {
                     aspect_mask,
                     base_mip,
                     mip_count,
-                    array_element,
+                    array_elements,
                     sharing_mode,
                     ..
                 } => {
-                    for face in 0..6 {
-                        let array_element = ((*array_element * 6) + face) as u32;
+                    for layer in (array_elements.start * 6)..(array_elements.end * 6) {
+                        let array_element = layer as u32;
 
                         let image_region = ImageRegion {
                             id: *id,
@@ -1433,6 +1780,7 @@ impl CommandSorting {
                             base_mip_level: *base_mip,
                             mip_count: *mip_count,
                         };
+                        Self::validate_cube_map_owner(info, &image_region, *sharing_mode);
 
                         let new_src_usage = GlobalImageUsage {
                             queue: Some(QueueUsage {
@@ -1610,27 +1958,14 @@ impl CommandSorting {
         );
     }
 
-    fn inspect_copy_texture_to_texture(
+    fn inspect_buffer_transfer_write(
         &mut self,
         info: &mut CommandSortingInfo,
         command_idx: usize,
-        copy: &CopyTextureToTexture<crate::VulkanBackend>,
+        buffer: &Buffer<crate::VulkanBackend>,
+        array_element: usize,
     ) {
-        let new_src_usage = GlobalImageUsage {
-            queue: Some(QueueUsage {
-                queue: info.queue,
-                timeline_value: info.timeline_value,
-                command_idx,
-                is_async: info.is_async,
-            }),
-            sub_resource: SubResourceUsage {
-                access: vk::AccessFlags2::TRANSFER_READ,
-                stage: vk::PipelineStageFlags2::TRANSFER,
-            },
-            layout: ImageLayout::TRANSFER_SRC_OPTIMAL,
-        };
-
-        let new_dst_usage = GlobalImageUsage {
+        let new_usage = GlobalBufferUsage {
             queue: Some(QueueUsage {
                 queue: info.queue,
                 timeline_value: info.timeline_value,
@@ -1641,47 +1976,227 @@ impl CommandSorting {
                 access: vk::AccessFlags2::TRANSFER_WRITE,
                 stage: vk::PipelineStageFlags2::TRANSFER,
             },
-            layout: ImageLayout::TRANSFER_DST_OPTIMAL,
         };
 
-        let mut old_src_usage = [GlobalImageUsage::default()];
-        info.global.use_image(
-            &ImageRegion {
-                id: copy.src.internal().id,
-                array_elem: copy.src_array_element as u32,
-                base_mip_level: copy.src_mip_level as u32,
-                mip_count: 1,
-            },
-            &new_src_usage,
-            &mut old_src_usage,
-        );
-
-        let mut old_dst_usage = [GlobalImageUsage::default()];
-        info.global.use_image(
-            &ImageRegion {
-                id: copy.dst.internal().id,
-                array_elem: copy.dst_array_element as u32,
-                base_mip_level: copy.dst_mip_level as u32,
-                mip_count: 1,
+        let old_usage = info.global.use_buffer(
+            &BufferRegion {
+                id: buffer.internal().id,
+                array_elem: array_element as u32,
             },
-            &new_dst_usage,
-            &mut old_dst_usage,
+            &new_usage,
         );
 
-        self.image_barrier_check(
+        self.buffer_barrier_check(
             info.queue_families,
             info.queue_families.to_index(info.queue),
-            &old_src_usage[0],
-            &new_src_usage,
-            copy.src.internal().image,
-            copy.src.internal().sharing_mode,
-            copy.src.internal().aspect_flags,
-            copy.src_array_element as u32,
-            copy.src_mip_level as u32,
+            &old_usage,
+            &new_usage,
+            buffer.internal().buffer,
+            buffer.internal().sharing_mode,
+            buffer.internal().aligned_size,
+            buffer.internal().offset(array_element),
         );
 
         self.dependency_check(
-            old_src_usage[0].queue.as_ref(),
+            old_usage.queue.as_ref(),
+            command_idx,
+            &mut info.wait_queues,
+            (info.queue, info.timeline_value),
+        );
+    }
+
+    fn inspect_conditional_rendering(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        buffer: &Buffer<crate::VulkanBackend>,
+        array_element: usize,
+    ) {
+        // The predicate is only read by the conditional rendering stage when the extension is
+        // enabled. Otherwise, it's treated as an indirect read so the region still waits on
+        // whatever wrote the predicate.
+        let sub_resource = if info.conditional_rendering {
+            SubResourceUsage {
+                access: vk::AccessFlags2::CONDITIONAL_RENDERING_READ_EXT,
+                stage: vk::PipelineStageFlags2::CONDITIONAL_RENDERING_EXT,
+            }
+        } else {
+            SubResourceUsage {
+                access: vk::AccessFlags2::INDIRECT_COMMAND_READ,
+                stage: vk::PipelineStageFlags2::DRAW_INDIRECT,
+            }
+        };
+
+        let new_usage = GlobalBufferUsage {
+            queue: Some(QueueUsage {
+                queue: info.queue,
+                timeline_value: info.timeline_value,
+                command_idx,
+                is_async: info.is_async,
+            }),
+            sub_resource,
+        };
+
+        let old_usage = info.global.use_buffer(
+            &BufferRegion {
+                id: buffer.internal().id,
+                array_elem: array_element as u32,
+            },
+            &new_usage,
+        );
+
+        self.buffer_barrier_check(
+            info.queue_families,
+            info.queue_families.to_index(info.queue),
+            &old_usage,
+            &new_usage,
+            buffer.internal().buffer,
+            buffer.internal().sharing_mode,
+            buffer.internal().aligned_size,
+            buffer.internal().offset(array_element),
+        );
+
+        self.dependency_check(
+            old_usage.queue.as_ref(),
+            command_idx,
+            &mut info.wait_queues,
+            (info.queue, info.timeline_value),
+        );
+    }
+
+    /// Transitions the cleared layers of a mip into `TRANSFER_DST_OPTIMAL`.
+    #[allow(clippy::too_many_arguments)]
+    fn inspect_clear(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        id: ResourceId,
+        image: vk::Image,
+        sharing_mode: SharingMode,
+        aspect_flags: vk::ImageAspectFlags,
+        layers: Range<u32>,
+        mip_level: u32,
+    ) {
+        let new_usage = GlobalImageUsage {
+            queue: Some(QueueUsage {
+                queue: info.queue,
+                timeline_value: info.timeline_value,
+                command_idx,
+                is_async: info.is_async,
+            }),
+            sub_resource: SubResourceUsage {
+                access: vk::AccessFlags2::TRANSFER_WRITE,
+                stage: vk::PipelineStageFlags2::CLEAR,
+            },
+            layout: ImageLayout::TRANSFER_DST_OPTIMAL,
+        };
+
+        for array_elem in layers {
+            let mut old_usage = [GlobalImageUsage::default()];
+            info.global.use_image(
+                &ImageRegion {
+                    id,
+                    array_elem,
+                    base_mip_level: mip_level,
+                    mip_count: 1,
+                },
+                &new_usage,
+                &mut old_usage,
+            );
+
+            self.image_barrier_check(
+                info.queue_families,
+                info.queue_families.to_index(info.queue),
+                &old_usage[0],
+                &new_usage,
+                image,
+                sharing_mode,
+                aspect_flags,
+                array_elem,
+                mip_level,
+            );
+
+            self.dependency_check(
+                old_usage[0].queue.as_ref(),
+                command_idx,
+                &mut info.wait_queues,
+                (info.queue, info.timeline_value),
+            );
+        }
+    }
+
+    fn inspect_copy_texture_to_texture(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        copy: &CopyTextureToTexture<crate::VulkanBackend>,
+    ) {
+        let new_src_usage = GlobalImageUsage {
+            queue: Some(QueueUsage {
+                queue: info.queue,
+                timeline_value: info.timeline_value,
+                command_idx,
+                is_async: info.is_async,
+            }),
+            sub_resource: SubResourceUsage {
+                access: vk::AccessFlags2::TRANSFER_READ,
+                stage: vk::PipelineStageFlags2::TRANSFER,
+            },
+            layout: ImageLayout::TRANSFER_SRC_OPTIMAL,
+        };
+
+        let new_dst_usage = GlobalImageUsage {
+            queue: Some(QueueUsage {
+                queue: info.queue,
+                timeline_value: info.timeline_value,
+                command_idx,
+                is_async: info.is_async,
+            }),
+            sub_resource: SubResourceUsage {
+                access: vk::AccessFlags2::TRANSFER_WRITE,
+                stage: vk::PipelineStageFlags2::TRANSFER,
+            },
+            layout: ImageLayout::TRANSFER_DST_OPTIMAL,
+        };
+
+        let mut old_src_usage = [GlobalImageUsage::default()];
+        info.global.use_image(
+            &ImageRegion {
+                id: copy.src.internal().id,
+                array_elem: copy.src_array_element as u32,
+                base_mip_level: copy.src_mip_level as u32,
+                mip_count: 1,
+            },
+            &new_src_usage,
+            &mut old_src_usage,
+        );
+
+        let mut old_dst_usage = [GlobalImageUsage::default()];
+        info.global.use_image(
+            &ImageRegion {
+                id: copy.dst.internal().id,
+                array_elem: copy.dst_array_element as u32,
+                base_mip_level: copy.dst_mip_level as u32,
+                mip_count: 1,
+            },
+            &new_dst_usage,
+            &mut old_dst_usage,
+        );
+
+        self.image_barrier_check(
+            info.queue_families,
+            info.queue_families.to_index(info.queue),
+            &old_src_usage[0],
+            &new_src_usage,
+            copy.src.internal().image,
+            copy.src.internal().sharing_mode,
+            copy.src.internal().aspect_flags,
+            copy.src_array_element as u32,
+            copy.src_mip_level as u32,
+        );
+
+        self.dependency_check(
+            old_src_usage[0].queue.as_ref(),
             command_idx,
             &mut info.wait_queues,
             (info.queue, info.timeline_value),
@@ -1953,19 +2468,18 @@ impl CommandSorting {
             (info.queue, info.timeline_value),
         );
 
-        for i in 0..6 {
-            let array_elem = ((copy.cube_map_array_element * 6) + i) as u32;
+        for array_elem in crate::cube_map::CubeMap::copy_layers(copy) {
+            let image_region = ImageRegion {
+                id: dst.internal().id,
+                array_elem,
+                base_mip_level: copy.cube_map_mip_level as u32,
+                mip_count: 1,
+            };
+            Self::validate_cube_map_owner(info, &image_region, dst.internal().sharing_mode);
+
             let mut old_dst_usage = [GlobalImageUsage::default()];
-            info.global.use_image(
-                &ImageRegion {
-                    id: dst.internal().id,
-                    array_elem,
-                    base_mip_level: copy.cube_map_mip_level as u32,
-                    mip_count: 1,
-                },
-                &new_dst_usage,
-                &mut old_dst_usage,
-            );
+            info.global
+                .use_image(&image_region, &new_dst_usage, &mut old_dst_usage);
 
             self.image_barrier_check(
                 info.queue_families,
@@ -1996,14 +2510,14 @@ impl CommandSorting {
         dst: &Buffer<crate::VulkanBackend>,
         copy: &BufferCubeMapCopy,
     ) {
-        for i in 0..6 {
-            let array_elem = ((copy.cube_map_array_element * 6) + i) as u32;
+        for array_elem in crate::cube_map::CubeMap::copy_layers(copy) {
             let image_region = ImageRegion {
-                id: dst.internal().id,
+                id: src.internal().id,
                 array_elem,
                 base_mip_level: copy.cube_map_mip_level as u32,
                 mip_count: 1,
             };
+            Self::validate_cube_map_owner(info, &image_region, src.internal().sharing_mode);
 
             let new_src_usage = GlobalImageUsage {
                 queue: Some(QueueUsage {
@@ -2174,6 +2688,14 @@ impl CommandSorting {
             }
         };
 
+        if let BlitSource::CubeMap { .. } = src {
+            Self::validate_cube_map_owner(info, &src_image_region, src_sharing_mode);
+        }
+
+        if let BlitDestination::CubeMap { .. } = dst {
+            Self::validate_cube_map_owner(info, &dst_image_region, dst_sharing_mode);
+        }
+
         let new_src_usage = GlobalImageUsage {
             queue: Some(QueueUsage {
                 queue: info.queue,
@@ -2256,6 +2778,32 @@ impl CommandSorting {
         blas: &BottomLevelAccelerationStructure<crate::VulkanBackend>,
         scratch: &Buffer<crate::VulkanBackend>,
         scratch_array_element: usize,
+    ) {
+        self.inspect_blas_build_dst(info, command_idx, blas);
+        self.inspect_blas_build_scratch(info, command_idx, scratch, scratch_array_element);
+    }
+
+    fn inspect_blas_batch_build(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        blases: &[(&BottomLevelAccelerationStructure<crate::VulkanBackend>, u64)],
+        scratch: &Buffer<crate::VulkanBackend>,
+        scratch_array_element: usize,
+    ) {
+        for (blas, _) in blases {
+            self.inspect_blas_build_dst(info, command_idx, blas);
+        }
+
+        // Every build in the batch uses a disjoint region of the same scratch memory
+        self.inspect_blas_build_scratch(info, command_idx, scratch, scratch_array_element);
+    }
+
+    fn inspect_blas_build_dst(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        blas: &BottomLevelAccelerationStructure<crate::VulkanBackend>,
     ) {
         // Inspect the BLAS' internal buffer
         let new_dst_usage = GlobalBufferUsage {
@@ -2343,7 +2891,15 @@ impl CommandSorting {
                     (info.queue, info.timeline_value),
                 );
             });
+    }
 
+    fn inspect_blas_build_scratch(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        scratch: &Buffer<crate::VulkanBackend>,
+        scratch_array_element: usize,
+    ) {
         // Inspect the scratch buffer
         let new_dst_usage = GlobalBufferUsage {
             queue: Some(QueueUsage {
@@ -2395,8 +2951,9 @@ impl CommandSorting {
         scratch_array_element: usize,
         src: &Buffer<crate::VulkanBackend>,
         src_array_element: usize,
+        is_update: bool,
     ) {
-        // Inspect the BLAS' internal buffer
+        // Inspect the TLAS' internal buffer. Updates read the previous build as well.
         let new_dst_usage = GlobalBufferUsage {
             queue: Some(QueueUsage {
                 queue: info.queue,
@@ -2405,7 +2962,12 @@ impl CommandSorting {
                 is_async: info.is_async,
             }),
             sub_resource: SubResourceUsage {
-                access: vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+                access: if is_update {
+                    vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
+                        | vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR
+                } else {
+                    vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR
+                },
                 stage: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
             },
         };
@@ -2663,27 +3225,81 @@ impl CommandSorting {
         );
     }
 
+    /// Sparse binds don't access the contents of the texture, so they only need to wait for other
+    /// queues to finish with the affected mip levels before pages are swapped out.
+    fn inspect_sparse_pages(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        texture: &Texture<crate::VulkanBackend>,
+        regions: &[SparsePageRegion],
+    ) {
+        for region in regions {
+            let image_region = ImageRegion {
+                id: texture.internal().id,
+                array_elem: region.array_element as u32,
+                base_mip_level: region.mip_level as u32,
+                mip_count: 1,
+            };
+
+            let old_usage = info.global.get_image_queue_usage(&image_region);
+            self.dependency_check(
+                old_usage.as_ref(),
+                command_idx,
+                &mut info.wait_queues,
+                (info.queue, info.timeline_value),
+            );
+        }
+    }
+
+    fn inspect_signal_event(
+        &mut self,
+        info: &mut CommandSortingInfo,
+        command_idx: usize,
+        resources: &[EventResource<'_, crate::VulkanBackend>],
+    ) {
+        let mut keys = Vec::default();
+        let mut last_uses = Vec::default();
+        for_each_event_subresource(resources, |key, region| {
+            keys.push(key);
+            last_uses.extend(match region {
+                EventRegion::Buffer(region) => info.global.last_buffer_use(&region),
+                EventRegion::Image(region) => info.global.last_image_use(&region),
+            });
+        });
+
+        // The event is set once every command already using the resources has executed
+        let mut dependencies: Vec<_> = last_uses
+            .into_iter()
+            .filter(|usage| {
+                usage.queue == info.queue
+                    && usage.timeline_value == info.timeline_value
+                    && usage.command_idx != usize::MAX
+            })
+            .map(|usage| usage.command_idx)
+            .collect();
+        dependencies.sort_unstable();
+        dependencies.dedup();
+
+        for dependency in dependencies {
+            self.commands[dependency].dependents.push(command_idx);
+            self.commands[command_idx].dependency_count += 1;
+        }
+
+        self.split_barriers.signal(command_idx, keys);
+    }
+
     fn inspect_set_texture_usage(
         &mut self,
         info: &mut CommandSortingInfo,
         command_idx: usize,
         texture: &Texture<crate::VulkanBackend>,
         new_usage: TextureUsage,
-        array_element: usize,
-        base_mip: u32,
-        mip_count: usize,
+        array_elems: Range<usize>,
+        mips: Range<usize>,
     ) {
-        let layout = match new_usage {
-            TextureUsage::COLOR_ATTACHMENT => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-            TextureUsage::DEPTH_STENCIL_ATTACHMENT => {
-                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
-            }
-            TextureUsage::TRANSFER_DST => vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            TextureUsage::TRANSFER_SRC => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-            TextureUsage::STORAGE => vk::ImageLayout::GENERAL,
-            TextureUsage::SAMPLED => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            _ => unreachable!("command guarantees only one usage"),
-        };
+        let layout = crate::util::texture_usage_to_layout(new_usage)
+            .expect("command guarantees only one usage");
 
         let new_usage = GlobalImageUsage {
             queue: Some(QueueUsage {
@@ -2699,36 +3315,38 @@ impl CommandSorting {
             layout,
         };
 
-        let image_region = ImageRegion {
-            id: texture.internal().id,
-            array_elem: array_element as u32,
-            base_mip_level: base_mip,
-            mip_count: mip_count as u32,
-        };
+        let mut old_usages = vec![GlobalImageUsage::default(); mips.len()];
+        for array_element in array_elems {
+            let image_region = ImageRegion {
+                id: texture.internal().id,
+                array_elem: array_element as u32,
+                base_mip_level: mips.start as u32,
+                mip_count: mips.len() as u32,
+            };
 
-        let mut old_usages = vec![GlobalImageUsage::default(); mip_count];
-        info.global
-            .use_image(&image_region, &new_usage, &mut old_usages);
+            info.global
+                .use_image(&image_region, &new_usage, &mut old_usages);
 
-        for (i, old_usage) in old_usages.into_iter().enumerate() {
-            self.image_barrier_check(
-                info.queue_families,
-                info.queue_families.to_index(info.queue),
-                &old_usage,
-                &new_usage,
-                texture.internal().image,
-                texture.internal().sharing_mode,
-                texture.internal().aspect_flags,
-                array_element as u32,
-                base_mip + i as u32,
-            );
+            for (mip, old_usage) in mips.clone().zip(&old_usages) {
+                self.image_barrier_check(
+                    info.queue_families,
+                    info.queue_families.to_index(info.queue),
+                    old_usage,
+                    &new_usage,
+                    texture.internal().image,
+                    texture.internal().sharing_mode,
+                    texture.internal().aspect_flags,
+                    array_element as u32,
+                    mip as u32,
+                );
 
-            self.dependency_check(
-                old_usage.queue.as_ref(),
-                command_idx,
-                &mut info.wait_queues,
-                (info.queue, info.timeline_value),
-            );
+                self.dependency_check(
+                    old_usage.queue.as_ref(),
+                    command_idx,
+                    &mut info.wait_queues,
+                    (info.queue, info.timeline_value),
+                );
+            }
         }
     }
 
@@ -2859,6 +3477,7 @@ impl CommandSorting {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn inspect_transfer_cube_map_ownership(
         &mut self,
         info: &mut CommandSortingInfo,
@@ -2871,17 +3490,18 @@ impl CommandSorting {
         new_queue: QueueType,
         usage_hint: Option<TextureUsage>,
     ) {
-        let array_element =
-            crate::cube_map::CubeMap::to_array_elem(array_element as usize, face) as u32;
+        let region = ImageRegion {
+            id: cube_map.internal().id,
+            array_elem: crate::cube_map::CubeMap::to_array_elem(array_element as usize, face)
+                as u32,
+            base_mip_level: base_mip,
+            mip_count,
+        };
+        Self::validate_cube_map_owner(info, &region, cube_map.internal().sharing_mode);
 
         let mut old_usages = vec![GlobalImageUsage::default(); mip_count as usize];
         info.global.use_image(
-            &ImageRegion {
-                id: cube_map.internal().id,
-                array_elem: array_element,
-                base_mip_level: base_mip,
-                mip_count,
-            },
+            &region,
             &GlobalImageUsage {
                 queue: Some(QueueUsage {
                     queue: info.queue,
@@ -2900,48 +3520,31 @@ impl CommandSorting {
             &mut old_usages,
         );
 
-        let (dst_access, dst_stage) = (
-            vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
-            vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
-        );
-
-        match usage_hint {
-            Some(usage) => match crate::util::texture_usage_to_stage_access(usage) {
-                Some(res) => res,
-                None => (
-                    vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
-                    vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
-                ),
-            },
-            None => (
-                vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
-                vk::PipelineStageFlags2::BOTTOM_OF_PIPE,
-            ),
-        };
-
-        for (i, old_usage) in old_usages.iter_mut().enumerate() {
-            // Replace old usage
-            let old_queue = std::mem::replace(
-                &mut old_usage.queue,
-                Some(QueueUsage {
-                    queue: info.queue,
-                    timeline_value: info.timeline_value,
-                    command_idx,
-                    is_async: info.is_async,
-                }),
-            );
-
+        for (i, old_usage) in old_usages.iter().enumerate() {
             let (src_access, src_stage) =
                 (old_usage.sub_resource.access, old_usage.sub_resource.stage);
 
             // If another command used this resource this command buffer, we add it as a dependency
             self.dependency_check(
-                old_queue.as_ref(),
+                old_usage.queue.as_ref(),
                 command_idx,
                 &mut info.wait_queues,
                 (info.queue, info.timeline_value),
             );
 
+            // The layout transition is performed as part of the transfer so the acquire on the
+            // new queue can match the release exactly. Without a hint the layout is kept.
+            let new_layout = usage_hint
+                .and_then(crate::util::texture_usage_to_layout)
+                .unwrap_or(old_usage.layout);
+            let mip_region = ImageRegion {
+                base_mip_level: base_mip + i as u32,
+                mip_count: 1,
+                ..region
+            };
+            info.global
+                .release_image(&mip_region, new_queue, new_layout);
+
             self.image_barriers.push(
                 vk::ImageMemoryBarrier2::default()
                     .src_queue_family_index(info.queue_families.to_index(info.queue))
@@ -2949,22 +3552,54 @@ impl CommandSorting {
                     .src_stage_mask(src_stage)
                     .old_layout(old_usage.layout)
                     .dst_queue_family_index(info.queue_families.to_index(new_queue))
-                    .dst_access_mask(dst_access)
-                    .dst_stage_mask(dst_stage)
-                    .new_layout(old_usage.layout)
+                    .dst_access_mask(vk::AccessFlags2::empty())
+                    .dst_stage_mask(vk::PipelineStageFlags2::empty())
+                    .new_layout(new_layout)
                     .image(cube_map.internal().image)
                     .subresource_range(
                         vk::ImageSubresourceRange::default()
                             .aspect_mask(cube_map.internal().aspect_flags)
-                            .base_mip_level(base_mip + i as u32)
+                            .base_mip_level(mip_region.base_mip_level)
                             .level_count(1)
-                            .base_array_layer(array_element)
+                            .base_array_layer(region.array_elem)
                             .layer_count(1),
                     ),
             );
         }
     }
 
+    /// Reports an exclusive cube map being used on a queue it was never transferred to. Only
+    /// performed when the debug layer is enabled.
+    fn validate_cube_map_owner(
+        info: &CommandSortingInfo,
+        region: &ImageRegion,
+        sharing_mode: SharingMode,
+    ) {
+        if !info.validate || sharing_mode != SharingMode::Exclusive {
+            return;
+        }
+
+        let owner = match info.global.foreign_image_owner(region, info.queue) {
+            Some(owner) => owner,
+            None => return,
+        };
+
+        // Queues from the same family can share the image without a transfer
+        if info.queue_families.to_index(owner) == info.queue_families.to_index(info.queue) {
+            return;
+        }
+
+        ard_log::error!(
+            "cube map layer {} (mips {}..{}) is owned by the {:?} queue but was used on the \
+            {:?} queue. Use `transfer_cube_map_ownership` first.",
+            region.array_elem,
+            region.base_mip_level,
+            region.base_mip_level + region.mip_count,
+            owner,
+            info.queue,
+        );
+    }
+
     #[inline(always)]
     fn buffer_barrier_check(
         &mut self,
@@ -2986,15 +3621,22 @@ impl CommandSorting {
             None => return false,
         };
 
+        let split = self.split_barrier(
+            EventKey::Buffer { buffer, offset },
+            old.queue.as_ref(),
+            new.queue.as_ref(),
+        );
+
         match barrier {
-            PipelineBarrier::Memory(barrier) => {
-                self.memory_barriers.push(MemoryBarrier {
+            PipelineBarrier::Memory(barrier) => match split {
+                Some(split) => self.split_barriers.get_mut(split).push_memory(barrier),
+                None => self.memory_barriers.push(MemoryBarrier {
                     src_stage: barrier.src_stage_mask,
                     src_access: barrier.src_access_mask,
                     dst_stage: barrier.dst_stage_mask,
                     dst_access: barrier.dst_access_mask,
-                });
-            }
+                }),
+            },
             PipelineBarrier::Buffer(mut barrier) => {
                 barrier.buffer = buffer;
 
@@ -3013,7 +3655,10 @@ impl CommandSorting {
                 barrier.size = size;
                 barrier.offset = offset;
 
-                self.buffer_barriers.push(barrier);
+                match split {
+                    Some(split) => self.split_barriers.get_mut(split).push_buffer(barrier),
+                    None => self.buffer_barriers.push(barrier),
+                }
             }
             PipelineBarrier::Image(_) => unreachable!(),
         }
@@ -3043,15 +3688,26 @@ impl CommandSorting {
             None => return false,
         };
 
+        let split = self.split_barrier(
+            EventKey::Image {
+                image,
+                array_layer: array_element,
+                mip: mip_level,
+            },
+            old.queue.as_ref(),
+            new.queue.as_ref(),
+        );
+
         match barrier {
-            PipelineBarrier::Memory(barrier) => {
-                self.memory_barriers.push(MemoryBarrier {
+            PipelineBarrier::Memory(barrier) => match split {
+                Some(split) => self.split_barriers.get_mut(split).push_memory(barrier),
+                None => self.memory_barriers.push(MemoryBarrier {
                     src_stage: barrier.src_stage_mask,
                     src_access: barrier.src_access_mask,
                     dst_stage: barrier.dst_stage_mask,
                     dst_access: barrier.dst_access_mask,
-                });
-            }
+                }),
+            },
             PipelineBarrier::Image(mut barrier) => {
                 barrier.image = image;
                 barrier.subresource_range = vk::ImageSubresourceRange::default()
@@ -3097,7 +3753,10 @@ impl CommandSorting {
                     }
                 }
 
-                self.image_barriers.push(barrier);
+                match split {
+                    Some(split) => self.split_barriers.get_mut(split).push_image(barrier),
+                    None => self.image_barriers.push(barrier),
+                }
             }
             PipelineBarrier::Buffer(_) => unreachable!(),
         }
@@ -3105,6 +3764,27 @@ impl CommandSorting {
         true
     }
 
+    /// Finds the split barrier that performs the barrier for `key` in place of the command using
+    /// it. The command is made to execute after the signal.
+    fn split_barrier(
+        &mut self,
+        key: EventKey,
+        old: Option<&QueueUsage>,
+        new: Option<&QueueUsage>,
+    ) -> Option<usize> {
+        let split = self.split_barriers.find(key, old, new)?;
+        let consumer = new.unwrap().command_idx;
+
+        let barrier = self.split_barriers.get_mut(split);
+        if barrier.add_consumer(consumer) {
+            let signal = barrier.signal;
+            self.commands[signal].dependents.push(consumer);
+            self.commands[consumer].dependency_count += 1;
+        }
+
+        Some(split)
+    }
+
     #[inline(always)]
     fn dependency_check(
         &mut self,
@@ -3145,6 +3825,136 @@ impl CommandSorting {
     }
 }
 
+/// Region of a buffer array element or single image subresource covered by a split barrier.
+enum EventRegion {
+    Buffer(BufferRegion),
+    Image(ImageRegion),
+}
+
+/// Calls `f` with every buffer array element and single image subresource in `resources`.
+fn for_each_event_subresource(
+    resources: &[EventResource<'_, crate::VulkanBackend>],
+    mut f: impl FnMut(EventKey, EventRegion),
+) {
+    for resource in resources {
+        let (id, image, layers, mips) = match resource {
+            EventResource::Buffer {
+                buffer,
+                array_element,
+            } => {
+                let internal = buffer.internal();
+                f(
+                    EventKey::Buffer {
+                        buffer: internal.buffer,
+                        offset: internal.offset(*array_element),
+                    },
+                    EventRegion::Buffer(BufferRegion {
+                        id: internal.id,
+                        array_elem: *array_element as u32,
+                    }),
+                );
+                continue;
+            }
+            EventResource::Texture {
+                texture,
+                array_elements,
+                mips,
+            } => {
+                let internal = texture.internal();
+                (internal.id, internal.image, array_elements.clone(), mips)
+            }
+            EventResource::CubeMap {
+                cube_map,
+                array_element,
+                mips,
+            } => {
+                let internal = cube_map.internal();
+                let first_face = *array_element * 6;
+                (
+                    internal.id,
+                    internal.image,
+                    first_face..(first_face + 6),
+                    mips,
+                )
+            }
+        };
+
+        for layer in layers {
+            for mip in mips.clone() {
+                f(
+                    EventKey::Image {
+                        image,
+                        array_layer: layer as u32,
+                        mip: mip as u32,
+                    },
+                    EventRegion::Image(ImageRegion {
+                        id,
+                        array_elem: layer as u32,
+                        base_mip_level: mip as u32,
+                        mip_count: 1,
+                    }),
+                );
+            }
+        }
+    }
+}
+
+/// Merges the image barriers starting at `first` which perform the same transition on adjacent
+/// subresources. Barriers are generated for a single mip of a single array layer at a time, so
+/// without merging, transitioning a mip chain would take one barrier per level.
+fn merge_image_barriers(barriers: &mut Vec<vk::ImageMemoryBarrier2<'static>>, first: usize) {
+    if barriers.len() - first < 2 {
+        return;
+    }
+
+    // Mips of a layer are generated in order, followed by the next layer, so merging mips first
+    // lets whole layers merge afterwards
+    merge_adjacent(barriers, first, |a, b| {
+        a.base_array_layer == b.base_array_layer
+            && a.layer_count == b.layer_count
+            && a.base_mip_level + a.level_count == b.base_mip_level
+    });
+    merge_adjacent(barriers, first, |a, b| {
+        a.base_mip_level == b.base_mip_level
+            && a.level_count == b.level_count
+            && a.base_array_layer + a.layer_count == b.base_array_layer
+    });
+}
+
+/// Merges each barrier into the one before it when both perform the same transition and
+/// `follows` says the subresources of the second come right after those of the first.
+fn merge_adjacent(
+    barriers: &mut Vec<vk::ImageMemoryBarrier2<'static>>,
+    first: usize,
+    follows: impl Fn(&vk::ImageSubresourceRange, &vk::ImageSubresourceRange) -> bool,
+) {
+    let mut merged = first;
+    for i in (first + 1)..barriers.len() {
+        let (prev, next) = (&barriers[merged], &barriers[i]);
+        let same_transition = prev.image == next.image
+            && prev.old_layout == next.old_layout
+            && prev.new_layout == next.new_layout
+            && prev.src_stage_mask == next.src_stage_mask
+            && prev.src_access_mask == next.src_access_mask
+            && prev.dst_stage_mask == next.dst_stage_mask
+            && prev.dst_access_mask == next.dst_access_mask
+            && prev.src_queue_family_index == next.src_queue_family_index
+            && prev.dst_queue_family_index == next.dst_queue_family_index
+            && prev.subresource_range.aspect_mask == next.subresource_range.aspect_mask;
+
+        if same_transition && follows(&prev.subresource_range, &next.subresource_range) {
+            let next = next.subresource_range;
+            let range = &mut barriers[merged].subresource_range;
+            range.level_count = (next.base_mip_level + next.level_count) - range.base_mip_level;
+            range.layer_count = (next.base_array_layer + next.layer_count) - range.base_array_layer;
+        } else {
+            merged += 1;
+            barriers[merged] = barriers[i];
+        }
+    }
+    barriers.truncate(merged + 1);
+}
+
 impl From<MemoryBarrier> for vk::MemoryBarrier2<'static> {
     #[inline(always)]
     fn from(value: MemoryBarrier) -> Self {
@@ -3155,3 +3965,85 @@ impl From<MemoryBarrier> for vk::MemoryBarrier2<'static> {
             .dst_stage_mask(value.dst_stage)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    fn barrier(
+        layer: u32,
+        mip: u32,
+        new_layout: vk::ImageLayout,
+    ) -> vk::ImageMemoryBarrier2<'static> {
+        vk::ImageMemoryBarrier2::default()
+            .image(vk::Image::from_raw(1))
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(new_layout)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: mip,
+                level_count: 1,
+                base_array_layer: layer,
+                layer_count: 1,
+            })
+    }
+
+    fn ranges(barriers: &[vk::ImageMemoryBarrier2]) -> Vec<(u32, u32, u32, u32)> {
+        barriers
+            .iter()
+            .map(|barrier| {
+                let range = barrier.subresource_range;
+                (
+                    range.base_array_layer,
+                    range.layer_count,
+                    range.base_mip_level,
+                    range.level_count,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mip_chains_merge_into_one_barrier() {
+        let general = vk::ImageLayout::GENERAL;
+        let mut barriers = vec![barrier(7, 0, general)];
+        for layer in 0..2 {
+            for mip in 0..4 {
+                barriers.push(barrier(layer, mip, general));
+            }
+        }
+
+        // Barriers from before the command are left alone
+        merge_image_barriers(&mut barriers, 1);
+        assert_eq!(ranges(&barriers), [(7, 1, 0, 1), (0, 2, 0, 4)]);
+    }
+
+    #[test]
+    fn downsample_chain_only_merges_matching_transitions() {
+        // Sampling mip 1 while writing mip 2 must keep the barriers separate, and mip 0 is never
+        // touched
+        let mut barriers = vec![
+            barrier(0, 1, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+            barrier(0, 2, vk::ImageLayout::GENERAL),
+            barrier(0, 3, vk::ImageLayout::GENERAL),
+        ];
+        merge_image_barriers(&mut barriers, 0);
+        assert_eq!(ranges(&barriers), [(0, 1, 1, 1), (0, 1, 2, 2)]);
+    }
+
+    #[test]
+    fn gaps_are_not_merged() {
+        let general = vk::ImageLayout::GENERAL;
+        let mut barriers = vec![
+            barrier(0, 0, general),
+            barrier(0, 2, general),
+            barrier(2, 2, general),
+        ];
+        merge_image_barriers(&mut barriers, 0);
+        assert_eq!(
+            ranges(&barriers),
+            [(0, 1, 0, 1), (0, 1, 2, 1), (2, 1, 2, 1)]
+        );
+    }
+}
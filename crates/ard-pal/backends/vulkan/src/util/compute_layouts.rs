@@ -0,0 +1,227 @@
+use api::command_buffer::Command;
+use ash::vk;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    descriptor_set::{BoundValue, DescriptorSet, PushedSets},
+    VulkanBackend,
+};
+
+/// A single mip level of a single array layer of an image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Subresource {
+    image: vk::Image,
+    array_layer: u32,
+    mip: u32,
+}
+
+/// Tracks image layouts between the dispatches of a compute pass.
+///
+/// The command sorter treats a compute pass as a single command, so it only transitions a
+/// subresource into the layout of its first use within the pass, while expecting the pass to
+/// leave it in the layout of its last use. When a pass uses the same subresource in more than
+/// one layout (e.g. writing a mip with one dispatch and sampling it in the next), the
+/// subresource is transitioned here between dispatches.
+#[derive(Default)]
+pub(crate) struct ComputeLayouts<'a> {
+    /// Current layout and aspect of every subresource that needs transitions within the pass.
+    layouts: FxHashMap<Subresource, (vk::ImageLayout, vk::ImageAspectFlags)>,
+    /// Sets currently bound to the pass. Only sets checked by the command sorter are tracked.
+    bound: Vec<Option<&'a DescriptorSet>>,
+}
+
+impl<'a> ComputeLayouts<'a> {
+    /// Finds the subresources that need transitions within the compute pass starting at `begin`.
+    pub(crate) fn new(
+        commands: &'a [Command<'a, VulkanBackend>],
+        begin: usize,
+        pushed: &'a PushedSets,
+    ) -> Self {
+        let mut tracker = Self::default();
+        let pass = &commands[begin..];
+        let pass = match pass
+            .iter()
+            .position(|command| matches!(command, Command::EndComputePass(_)))
+        {
+            Some(end) => &pass[..end],
+            None => pass,
+        };
+
+        // A single dispatch can't need transitions
+        if pass
+            .iter()
+            .filter(|command| matches!(command, Command::Dispatch(_)))
+            .count()
+            < 2
+        {
+            return tracker;
+        }
+
+        // Layout of the first use of every subresource and if any use had a different layout
+        let mut uses = FxHashMap::default();
+        for command in pass {
+            if let Command::Dispatch(_) = command {
+                tracker.for_each_bound_image(|subresource, layout, aspect| {
+                    uses.entry(subresource)
+                        .and_modify(|(first, _, mixed)| *mixed |= *first != layout)
+                        .or_insert((layout, aspect, false));
+                });
+            } else {
+                tracker.bind(command, pushed);
+            }
+        }
+
+        tracker.bound.clear();
+        tracker.layouts = uses
+            .into_iter()
+            .filter(|(_, (_, _, mixed))| *mixed)
+            .map(|(subresource, (layout, aspect, _))| (subresource, (layout, aspect)))
+            .collect();
+        tracker
+    }
+
+    /// Updates the bound sets if `command` binds any.
+    pub(crate) fn bind(&mut self, command: &'a Command<'a, VulkanBackend>, pushed: &'a PushedSets) {
+        match command {
+            Command::BindDescriptorSets { sets, first, .. } => {
+                for (i, set) in sets.iter().enumerate() {
+                    *self.slot(*first + i) = Some(set.internal());
+                }
+            }
+            Command::BindDescriptorSetsUnchecked { sets, first, .. } => {
+                for i in 0..sets.len() {
+                    *self.slot(*first + i) = None;
+                }
+            }
+            Command::PushDescriptorSet { index, .. } => {
+                *self.slot(*index) = Some(pushed.get(command));
+            }
+            _ => {}
+        }
+    }
+
+    /// Transitions the subresources used by the bound sets into the layouts the next dispatch
+    /// expects. The barriers also make writes from earlier dispatches visible.
+    pub(crate) fn transitions(&mut self) -> Vec<vk::ImageMemoryBarrier2<'static>> {
+        let mut barriers = Vec::default();
+        if self.layouts.is_empty() {
+            return barriers;
+        }
+
+        let mut layouts = std::mem::take(&mut self.layouts);
+        self.for_each_bound_image(|subresource, layout, _| {
+            let (current, aspect) = match layouts.get_mut(&subresource) {
+                Some(current) => current,
+                None => return,
+            };
+
+            if *current == layout {
+                return;
+            }
+
+            barriers.push(
+                vk::ImageMemoryBarrier2::default()
+                    .src_stage_mask(vk::PipelineStageFlags2::COMPUTE_SHADER)
+                    .src_access_mask(vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::SHADER_WRITE)
+                    .dst_stage_mask(vk::PipelineStageFlags2::COMPUTE_SHADER)
+                    .dst_access_mask(vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::SHADER_WRITE)
+                    .old_layout(*current)
+                    .new_layout(layout)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(subresource.image)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: *aspect,
+                        base_mip_level: subresource.mip,
+                        level_count: 1,
+                        base_array_layer: subresource.array_layer,
+                        layer_count: 1,
+                    }),
+            );
+            *current = layout;
+        });
+        self.layouts = layouts;
+
+        barriers
+    }
+
+    #[inline]
+    fn slot(&mut self, index: usize) -> &mut Option<&'a DescriptorSet> {
+        if index >= self.bound.len() {
+            self.bound.resize(index + 1, None);
+        }
+        &mut self.bound[index]
+    }
+
+    /// Calls `f` with every image subresource in the bound sets and the layout it's used in.
+    fn for_each_bound_image(
+        &self,
+        mut f: impl FnMut(Subresource, vk::ImageLayout, vk::ImageAspectFlags),
+    ) {
+        for set in self.bound.iter().flatten() {
+            set.bound
+                .iter()
+                .flat_map(|binding| binding.iter().flatten())
+                .for_each(|elem| match &elem.value {
+                    BoundValue::StorageImage {
+                        image,
+                        aspect_mask,
+                        mip,
+                        array_element,
+                        ..
+                    } => f(
+                        Subresource {
+                            image: *image,
+                            array_layer: *array_element as u32,
+                            mip: *mip,
+                        },
+                        vk::ImageLayout::GENERAL,
+                        *aspect_mask,
+                    ),
+                    BoundValue::Texture {
+                        image,
+                        aspect_mask,
+                        base_mip,
+                        mip_count,
+                        array_element,
+                        ..
+                    } => {
+                        for mip in *base_mip..(*base_mip + *mip_count) {
+                            f(
+                                Subresource {
+                                    image: *image,
+                                    array_layer: *array_element as u32,
+                                    mip,
+                                },
+                                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                                *aspect_mask,
+                            );
+                        }
+                    }
+                    BoundValue::CubeMap {
+                        image,
+                        aspect_mask,
+                        base_mip,
+                        mip_count,
+                        array_elements,
+                        ..
+                    } => {
+                        for layer in (array_elements.start * 6)..(array_elements.end * 6) {
+                            for mip in *base_mip..(*base_mip + *mip_count) {
+                                f(
+                                    Subresource {
+                                        image: *image,
+                                        array_layer: layer as u32,
+                                        mip,
+                                    },
+                                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                                    *aspect_mask,
+                                );
+                            }
+                        }
+                    }
+                    _ => {}
+                });
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, ffi::CString};
 
 use super::fast_int_hasher::FIHashMap;
-use api::descriptor_set::DescriptorSetLayoutCreateInfo;
+use api::descriptor_set::{DescriptorBindingFlags, DescriptorSetLayoutCreateInfo};
 use ash::vk;
 use rustc_hash::FxHashMap;
 
@@ -12,6 +12,9 @@ const SETS_PER_POOL: usize = 16;
 pub(crate) struct DescriptorPools {
     pools: FxHashMap<DescriptorSetLayoutCreateInfo, DescriptorPool>,
     layout_to_create_info: FIHashMap<vk::DescriptorSetLayout, DescriptorSetLayoutCreateInfo>,
+    /// The maximum number of descriptors in a push descriptor layout. `None` if push descriptors
+    /// are not supported.
+    max_push_descriptors: Option<u32>,
 }
 
 pub(crate) struct DescriptorPool {
@@ -22,21 +25,42 @@ pub(crate) struct DescriptorPool {
     pools: Vec<vk::DescriptorPool>,
     /// Current number of sets allocated from the top pool.
     size: usize,
-    /// Free list of descriptor sets.
-    free: Vec<vk::DescriptorSet>,
+    /// Free list of descriptor sets along with the number of elements allocated for their
+    /// variable count binding.
+    free: Vec<(vk::DescriptorSet, u32)>,
     /// Pool sizes to use when making a new descriptor pool.
     sizes: Vec<vk::DescriptorPoolSize>,
+    /// The layout was created for push descriptors, so no sets can be allocated.
+    push: bool,
+    /// Pools must be created with `UPDATE_AFTER_BIND` since the layout has bindings that use it.
+    update_after_bind: bool,
+    /// The layout has a variable count binding.
+    variable_count: bool,
 }
 
 impl DescriptorPools {
+    pub fn new(max_push_descriptors: Option<u32>) -> Self {
+        Self {
+            max_push_descriptors,
+            ..Default::default()
+        }
+    }
+
     #[inline]
     pub unsafe fn get(
         &mut self,
         device: &ash::Device,
-        create_info: DescriptorSetLayoutCreateInfo,
+        debug: Option<&ash::ext::debug_utils::Device>,
+        mut create_info: DescriptorSetLayoutCreateInfo,
     ) -> &mut DescriptorPool {
+        // Layouts with the same bindings are shared, so the name is not part of the key. The
+        // layout keeps the name it was first created with.
+        let name = create_info.debug_name.take();
         if !self.pools.contains_key(&create_info) {
-            let pool = DescriptorPool::new(device, &create_info);
+            let pool = DescriptorPool::new(device, &create_info, self.max_push_descriptors);
+            if let Some(name) = &name {
+                super::set_debug_name(debug, pool.layout[0], name);
+            }
             self.layout_to_create_info
                 .insert(pool.layout[0], create_info.clone());
             self.pools.insert(create_info.clone(), pool);
@@ -64,9 +88,14 @@ impl DescriptorPools {
 }
 
 impl DescriptorPool {
-    pub unsafe fn new(device: &ash::Device, create_info: &DescriptorSetLayoutCreateInfo) -> Self {
+    pub unsafe fn new(
+        device: &ash::Device,
+        create_info: &DescriptorSetLayoutCreateInfo,
+        max_push_descriptors: Option<u32>,
+    ) -> Self {
         // Convert the api layout into a vulkan layout
         let mut bindings = Vec::default();
+        let mut binding_flags = Vec::default();
         for binding in &create_info.bindings {
             bindings.push(
                 vk::DescriptorSetLayoutBinding::default()
@@ -75,9 +104,37 @@ impl DescriptorPool {
                     .descriptor_type(super::to_vk_descriptor_type(binding.ty))
                     .stage_flags(crate::util::to_vk_shader_stage(binding.stage)),
             );
+            binding_flags.push(to_vk_binding_flags(binding.flags));
+        }
+
+        let all_flags = create_info
+            .bindings
+            .iter()
+            .fold(DescriptorBindingFlags::empty(), |flags, binding| {
+                flags | binding.flags
+            });
+        let update_after_bind = all_flags.contains(DescriptorBindingFlags::UPDATE_AFTER_BIND);
+        let variable_count = all_flags.contains(DescriptorBindingFlags::VARIABLE_COUNT);
+
+        // Layouts that can't be pushed fall back to sets allocated from the pool
+        let descriptor_count: u32 = bindings.iter().map(|b| b.descriptor_count).sum();
+        let push =
+            create_info.push && max_push_descriptors.is_some_and(|max| descriptor_count <= max);
+
+        let mut flags = vk::DescriptorSetLayoutCreateFlags::empty();
+        if push {
+            flags |= vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR;
+        }
+        if update_after_bind {
+            flags |= vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL;
         }
 
-        let create_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        let mut flags_info =
+            vk::DescriptorSetLayoutBindingFlagsCreateInfo::default().binding_flags(&binding_flags);
+        let create_info = vk::DescriptorSetLayoutCreateInfo::default()
+            .bindings(&bindings)
+            .flags(flags)
+            .push_next(&mut flags_info);
 
         // Create the layout
         let layout = device
@@ -109,6 +166,9 @@ impl DescriptorPool {
             size: 0,
             free: Vec::default(),
             sizes,
+            push,
+            update_after_bind,
+            variable_count,
         }
     }
 
@@ -117,18 +177,35 @@ impl DescriptorPool {
         self.layout[0]
     }
 
+    #[inline(always)]
+    pub fn is_push(&self) -> bool {
+        self.push
+    }
+
+    /// Returns a set to the free list. `variable_count` *must* be the count the set was allocated
+    /// with.
     #[inline]
-    pub fn free(&mut self, set: vk::DescriptorSet) {
-        self.free.push(set);
+    pub fn free(&mut self, set: vk::DescriptorSet, variable_count: u32) {
+        self.free.push((set, variable_count));
     }
 
+    /// Allocates a set. `variable_count` is the number of elements to allocate for the variable
+    /// count binding, and is ignored if the layout has none.
     pub unsafe fn allocate(
         &mut self,
         device: &ash::Device,
         debug: Option<&ash::ext::debug_utils::Device>,
         name: Option<String>,
+        variable_count: u32,
     ) -> vk::DescriptorSet {
-        let set = match self.free.pop() {
+        // Free sets can only be reused if they were allocated with the same variable count
+        let free = self
+            .free
+            .iter()
+            .rposition(|(_, count)| *count == variable_count)
+            .map(|idx| self.free.swap_remove(idx).0);
+
+        let set = match free {
             Some(free) => free,
             None => {
                 // Allocate a new pool if required
@@ -137,16 +214,28 @@ impl DescriptorPool {
                     self.pools.push({
                         let create_info = vk::DescriptorPoolCreateInfo::default()
                             .max_sets(self.size as u32)
-                            .pool_sizes(&self.sizes);
+                            .pool_sizes(&self.sizes)
+                            .flags(if self.update_after_bind {
+                                vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND
+                            } else {
+                                vk::DescriptorPoolCreateFlags::empty()
+                            });
                         device.create_descriptor_pool(&create_info, None).unwrap()
                     });
                 }
 
                 // Allocate new set
                 self.size -= 1;
-                let alloc_info = vk::DescriptorSetAllocateInfo::default()
+                let counts = [variable_count];
+                let mut count_info =
+                    vk::DescriptorSetVariableDescriptorCountAllocateInfo::default()
+                        .descriptor_counts(&counts);
+                let mut alloc_info = vk::DescriptorSetAllocateInfo::default()
                     .descriptor_pool(*self.pools.last().unwrap())
                     .set_layouts(&self.layout);
+                if self.variable_count {
+                    alloc_info = alloc_info.push_next(&mut count_info);
+                }
                 device.allocate_descriptor_sets(&alloc_info).unwrap()[0]
             }
         };
@@ -173,3 +262,18 @@ impl DescriptorPool {
         device.destroy_descriptor_set_layout(self.layout[0], None);
     }
 }
+
+fn to_vk_binding_flags(flags: DescriptorBindingFlags) -> vk::DescriptorBindingFlags {
+    let mut out = vk::DescriptorBindingFlags::empty();
+    if flags.contains(DescriptorBindingFlags::UPDATE_AFTER_BIND) {
+        out |= vk::DescriptorBindingFlags::UPDATE_AFTER_BIND
+            | vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING;
+    }
+    if flags.contains(DescriptorBindingFlags::PARTIALLY_BOUND) {
+        out |= vk::DescriptorBindingFlags::PARTIALLY_BOUND;
+    }
+    if flags.contains(DescriptorBindingFlags::VARIABLE_COUNT) {
+        out |= vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT;
+    }
+    out
+}
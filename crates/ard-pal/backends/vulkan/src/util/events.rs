@@ -0,0 +1,364 @@
+use ash::vk;
+use rustc_hash::FxHashSet;
+
+use super::usage::QueueUsage;
+
+/// Pool of events used by split barriers.
+#[derive(Default)]
+pub(crate) struct Events {
+    free: Vec<vk::Event>,
+}
+
+/// A subresource synchronized by a split barrier.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum EventKey {
+    /// An array element of a buffer, identified by its offset.
+    Buffer { buffer: vk::Buffer, offset: u64 },
+    /// A single mip level of a single array layer of an image.
+    Image {
+        image: vk::Image,
+        array_layer: u32,
+        mip: u32,
+    },
+}
+
+/// The split barriers formed by the signal and wait commands of a single submission.
+#[derive(Default)]
+pub(crate) struct SplitBarriers {
+    /// Signals that haven't been paired with a wait yet along with the resources they cover.
+    unpaired: Vec<(usize, Vec<EventKey>)>,
+    barriers: Vec<SplitBarrier>,
+}
+
+/// A signal and wait pair. Barriers that would otherwise be performed right before the commands
+/// consuming the resources are instead split between setting the event at the signal and waiting
+/// on it right before the first consumer.
+#[derive(Default)]
+pub(crate) struct SplitBarrier {
+    /// Index of the signal command.
+    pub signal: usize,
+    /// Index of the wait command.
+    pub wait: usize,
+    keys: FxHashSet<EventKey>,
+    /// Commands whose barriers were moved into the split barrier.
+    pub consumers: Vec<usize>,
+    pub memory: Vec<vk::MemoryBarrier2<'static>>,
+    pub buffers: Vec<vk::BufferMemoryBarrier2<'static>>,
+    pub images: Vec<vk::ImageMemoryBarrier2<'static>>,
+}
+
+impl Events {
+    pub unsafe fn allocate(&mut self, device: &ash::Device) -> vk::Event {
+        match self.free.pop() {
+            Some(event) => event,
+            None => device
+                .create_event(&vk::EventCreateInfo::default(), None)
+                .unwrap(),
+        }
+    }
+
+    /// Returns an event to the pool. The event must not be in use by any queue.
+    pub unsafe fn free(&mut self, device: &ash::Device, event: vk::Event) {
+        device.reset_event(event).unwrap();
+        self.free.push(event);
+    }
+
+    pub unsafe fn release(&mut self, device: &ash::Device) {
+        for event in self.free.drain(..) {
+            device.destroy_event(event, None);
+        }
+    }
+}
+
+impl SplitBarriers {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.unpaired.clear();
+        self.barriers.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.barriers.len()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &SplitBarrier> {
+        self.barriers.iter()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut SplitBarrier> {
+        self.barriers.iter_mut()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, idx: usize) -> &mut SplitBarrier {
+        &mut self.barriers[idx]
+    }
+
+    /// Records a signal command covering `keys`.
+    pub fn signal(&mut self, command_idx: usize, mut keys: Vec<EventKey>) {
+        keys.sort_unstable();
+        keys.dedup();
+        self.unpaired.push((command_idx, keys));
+    }
+
+    /// Pairs a wait command covering `keys` with the oldest unpaired signal covering the same
+    /// resources. Returns `false` if there is no such signal, in which case the wait is ignored.
+    pub fn wait(&mut self, command_idx: usize, mut keys: Vec<EventKey>) -> bool {
+        keys.sort_unstable();
+        keys.dedup();
+
+        let signal = match self.unpaired.iter().position(|(_, other)| *other == keys) {
+            Some(signal) => signal,
+            None => return false,
+        };
+
+        let (signal, keys) = self.unpaired.remove(signal);
+        self.barriers.push(SplitBarrier {
+            signal,
+            wait: command_idx,
+            keys: keys.into_iter().collect(),
+            ..Default::default()
+        });
+        true
+    }
+
+    /// Finds the split barrier that can perform the barrier between `old` and `new` usages of
+    /// `key`. This is only possible when the old usage was recorded in the same submission before
+    /// the signal and the new usage was recorded after the wait.
+    pub fn find(
+        &self,
+        key: EventKey,
+        old: Option<&QueueUsage>,
+        new: Option<&QueueUsage>,
+    ) -> Option<usize> {
+        let (old, new) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            _ => return None,
+        };
+
+        // Events can't synchronize across queues or submissions
+        if old.queue != new.queue
+            || old.timeline_value != new.timeline_value
+            || old.command_idx == usize::MAX
+        {
+            return None;
+        }
+
+        self.barriers.iter().rposition(|barrier| {
+            old.command_idx < barrier.signal
+                && barrier.wait < new.command_idx
+                && barrier.keys.contains(&key)
+        })
+    }
+}
+
+impl SplitBarrier {
+    /// `true` if no barriers were moved into the split barrier, so the event is never used.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty() && self.buffers.is_empty() && self.images.is_empty()
+    }
+
+    #[inline]
+    pub fn dependency_info(&self) -> vk::DependencyInfo<'_> {
+        vk::DependencyInfo::default()
+            .memory_barriers(&self.memory)
+            .buffer_memory_barriers(&self.buffers)
+            .image_memory_barriers(&self.images)
+    }
+
+    /// Records `command_idx` as consuming the split barrier. Returns `false` if it already was.
+    #[inline]
+    pub fn add_consumer(&mut self, command_idx: usize) -> bool {
+        if self.consumers.last() == Some(&command_idx) {
+            return false;
+        }
+        self.consumers.push(command_idx);
+        true
+    }
+
+    pub fn push_memory(&mut self, barrier: vk::MemoryBarrier2<'static>) {
+        match self.memory.iter_mut().find(|other| {
+            other.src_stage_mask == barrier.src_stage_mask
+                && other.src_access_mask == barrier.src_access_mask
+        }) {
+            Some(other) => {
+                other.dst_stage_mask |= barrier.dst_stage_mask;
+                other.dst_access_mask |= barrier.dst_access_mask;
+            }
+            None => self.memory.push(barrier),
+        }
+    }
+
+    pub fn push_buffer(&mut self, barrier: vk::BufferMemoryBarrier2<'static>) {
+        match self.buffers.iter_mut().find(|other| {
+            other.buffer == barrier.buffer
+                && other.offset == barrier.offset
+                && other.size == barrier.size
+                && other.src_stage_mask == barrier.src_stage_mask
+                && other.src_access_mask == barrier.src_access_mask
+        }) {
+            Some(other) => {
+                other.dst_stage_mask |= barrier.dst_stage_mask;
+                other.dst_access_mask |= barrier.dst_access_mask;
+            }
+            None => self.buffers.push(barrier),
+        }
+    }
+
+    /// Adds an image barrier. Consumers reading a subresource after another consumer
+    /// transitioned it report the already transitioned layout, so a barrier for a subresource
+    /// that's already in the list is folded into the existing one.
+    pub fn push_image(&mut self, barrier: vk::ImageMemoryBarrier2<'static>) {
+        let range = barrier.subresource_range;
+        match self.images.iter_mut().find(|other| {
+            let other_range = other.subresource_range;
+            other.image == barrier.image
+                && other_range.aspect_mask == range.aspect_mask
+                && other_range.base_mip_level == range.base_mip_level
+                && other_range.level_count == range.level_count
+                && other_range.base_array_layer == range.base_array_layer
+                && other_range.layer_count == range.layer_count
+        }) {
+            Some(other) => {
+                other.src_stage_mask |= barrier.src_stage_mask;
+                other.src_access_mask |= barrier.src_access_mask;
+                other.dst_stage_mask |= barrier.dst_stage_mask;
+                other.dst_access_mask |= barrier.dst_access_mask;
+            }
+            None => self.images.push(barrier),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::types::QueueType;
+    use ash::vk::Handle;
+
+    fn image(mip: u32) -> EventKey {
+        EventKey::Image {
+            image: vk::Image::from_raw(1),
+            array_layer: 0,
+            mip,
+        }
+    }
+
+    fn usage(timeline_value: u64, command_idx: usize) -> QueueUsage {
+        QueueUsage {
+            queue: QueueType::Main,
+            timeline_value,
+            command_idx,
+            is_async: false,
+        }
+    }
+
+    #[test]
+    fn waits_pair_with_signals_of_the_same_resources() {
+        let mut split = SplitBarriers::default();
+        split.signal(1, vec![image(0), image(1)]);
+        split.signal(2, vec![image(2)]);
+
+        // Order of the resources doesn't matter
+        assert!(split.wait(4, vec![image(2)]));
+        assert!(split.wait(5, vec![image(1), image(0), image(1)]));
+        assert!(!split.wait(6, vec![image(0)]));
+
+        let pairs: Vec<_> = split
+            .iter()
+            .map(|split| (split.signal, split.wait))
+            .collect();
+        assert_eq!(pairs, [(2, 4), (1, 5)]);
+    }
+
+    #[test]
+    fn only_barriers_spanning_the_pair_are_split() {
+        let mut split = SplitBarriers::default();
+        split.signal(2, vec![image(0)]);
+        assert!(split.wait(5, vec![image(0)]));
+
+        let find = |key, old, new| split.find(key, Some(&usage(1, old)), Some(&usage(1, new)));
+        assert_eq!(find(image(0), 1, 6), Some(0));
+
+        // Written after the signal or consumed before the wait
+        assert_eq!(find(image(0), 3, 6), None);
+        assert_eq!(find(image(0), 1, 4), None);
+
+        // Not covered by the signal
+        assert_eq!(find(image(1), 1, 6), None);
+    }
+
+    #[test]
+    fn pairs_across_submissions_fall_back() {
+        let mut split = SplitBarriers::default();
+        split.signal(2, vec![image(0)]);
+        assert!(split.wait(5, vec![image(0)]));
+
+        // Written by an earlier submission
+        assert_eq!(
+            split.find(image(0), Some(&usage(1, usize::MAX)), Some(&usage(2, 6))),
+            None
+        );
+        assert_eq!(split.find(image(0), None, Some(&usage(2, 6))), None);
+
+        // Written on another queue
+        let mut compute = usage(2, 1);
+        compute.queue = QueueType::Compute;
+        assert_eq!(
+            split.find(image(0), Some(&compute), Some(&usage(2, 6))),
+            None
+        );
+
+        // A signal without a wait in the same submission is never paired
+        let mut split = SplitBarriers::default();
+        split.signal(2, vec![image(0)]);
+        assert_eq!(split.len(), 0);
+        split.clear();
+        assert!(!split.wait(1, vec![image(0)]));
+    }
+
+    #[test]
+    fn consumers_of_the_same_subresource_share_a_barrier() {
+        let mut split = SplitBarrier::default();
+        let image_barrier = |old_layout, dst_stage| {
+            vk::ImageMemoryBarrier2::default()
+                .image(vk::Image::from_raw(1))
+                .src_stage_mask(vk::PipelineStageFlags2::COMPUTE_SHADER)
+                .src_access_mask(vk::AccessFlags2::SHADER_WRITE)
+                .dst_stage_mask(dst_stage)
+                .dst_access_mask(vk::AccessFlags2::SHADER_SAMPLED_READ)
+                .old_layout(old_layout)
+                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+        };
+
+        // The first consumer transitions the image and the second sees the new layout
+        split.push_image(image_barrier(
+            vk::ImageLayout::GENERAL,
+            vk::PipelineStageFlags2::FRAGMENT_SHADER,
+        ));
+        split.push_image(image_barrier(
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+        ));
+
+        assert_eq!(split.images.len(), 1);
+        assert_eq!(split.images[0].old_layout, vk::ImageLayout::GENERAL);
+        assert_eq!(
+            split.images[0].dst_stage_mask,
+            vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER
+        );
+        assert!(split.add_consumer(6));
+        assert!(!split.add_consumer(6));
+    }
+}
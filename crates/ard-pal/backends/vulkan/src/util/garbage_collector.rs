@@ -6,22 +6,28 @@ use std::{
     },
 };
 
+use api::context::{GarbageKind, GarbageReport, PendingGarbage, QueueTimelineValues};
 use ash::vk;
 use crossbeam_channel::{Receiver, Sender};
-use gpu_allocator::vulkan::{Allocation, Allocator};
+use gpu_allocator::vulkan::Allocation;
 
 use crate::{
     buffer::BufferRefCounter,
-    descriptor_set::{BoundValue, DescriptorSetBindings},
+    descriptor_set::{Binding, BoundValue, DescriptorSetBindings},
+    render_pass::FramebufferCache,
+    surface::SurfaceImageSemaphores,
     texture::TextureRefCounter,
 };
 
 use super::{
     descriptor_pool::DescriptorPools,
+    events::Events,
     fast_int_hasher::FIHashMap,
     id_gen::{IdGenerator, ResourceId},
+    memory::{MemoryTracker, TrackedAllocator},
     pipeline_cache::PipelineCache,
     queries::{Queries, Query},
+    transient::TransientBuffers,
     usage::GlobalResourceUsage,
 };
 
@@ -39,9 +45,17 @@ pub(crate) enum Garbage {
     Buffer {
         buffer: vk::Buffer,
         id: ResourceId,
+        views: Vec<vk::BufferView>,
         allocation: Allocation,
         ref_counter: BufferRefCounter,
     },
+    /// A buffer suballocated from a transient block.
+    TransientBuffer {
+        block: usize,
+        id: ResourceId,
+        views: Vec<vk::BufferView>,
+        ref_counter: BufferRefCounter,
+    },
     AccelerationStructure {
         buffer: vk::Buffer,
         accelleration_struct: vk::AccelerationStructureKHR,
@@ -54,15 +68,36 @@ pub(crate) enum Garbage {
         image: vk::Image,
         id: ResourceId,
         views: Vec<vk::ImageView>,
-        allocation: Allocation,
+        /// A single allocation, or every page bound to a sparse texture.
+        allocations: Vec<Allocation>,
         ref_counter: TextureRefCounter,
     },
+    /// Memory unbound from the pages of a sparse texture.
+    SparsePages(Vec<Allocation>),
     DescriptorSet {
         set: vk::DescriptorSet,
         id: ResourceId,
         layout: vk::DescriptorSetLayout,
+        variable_count: u32,
         bindings: DescriptorSetBindings,
     },
+    /// Values replaced within a set that was updated while in use.
+    Bindings(Vec<Binding>),
+    /// A swapchain that was replaced or destroyed along with its images.
+    Swapchain {
+        swapchain: vk::SwapchainKHR,
+        images: Vec<(ResourceId, vk::ImageView)>,
+        semaphores: Vec<SurfaceImageSemaphores>,
+        /// The surface is also destroyed if this is `Some`.
+        surface: Option<vk::SurfaceKHR>,
+    },
+    /// Queries owned by a job.
+    Queries {
+        timestamps: Vec<Query>,
+        statistics: Vec<Query>,
+    },
+    /// Events used by the split barriers of a submission.
+    Events(Vec<vk::Event>),
 }
 
 #[derive(Copy, Clone)]
@@ -70,19 +105,25 @@ pub(crate) struct TimelineValues {
     pub main: u64,
     pub transfer: u64,
     pub compute: u64,
+    pub present: u64,
 }
 
 pub(crate) struct GarbageCleanupArgs<'a> {
     pub device: &'a ash::Device,
     pub as_loader: &'a ash::khr::acceleration_structure::Device,
+    pub swapchain_loader: &'a ash::khr::swapchain::Device,
+    pub surface_loader: &'a ash::khr::surface::Instance,
+    pub framebuffers: &'a FramebufferCache,
     pub buffer_ids: &'a IdGenerator,
     pub image_ids: &'a IdGenerator,
     pub set_ids: &'a IdGenerator,
-    pub allocator: &'a mut Allocator,
+    pub allocator: &'a mut TrackedAllocator,
     pub pools: &'a mut DescriptorPools,
     pub pipelines: &'a mut PipelineCache,
     pub global_usage: &'a mut GlobalResourceUsage,
     pub queries: &'a mut Queries,
+    pub events: &'a mut Events,
+    pub transient: &'a mut TransientBuffers,
     pub current: TimelineValues,
     pub target: TimelineValues,
     pub override_ref_counter: bool,
@@ -110,53 +151,66 @@ impl GarbageCollector {
         self.to_destroy.lock().unwrap().is_empty()
     }
 
+    /// Returns `true` if there is garbage that hasn't been destroyed, including garbage that
+    /// hasn't been received yet.
+    #[inline]
+    pub fn has_pending(&self) -> bool {
+        !self.receiver.is_empty() || !self.is_empty()
+    }
+
     pub fn sender(&self) -> Sender<Garbage> {
         self.sender.clone()
     }
 
+    /// Queues garbage to be destroyed once the provided timeline values are reached instead of
+    /// the target values at the next cleanup.
+    pub fn retire(&self, garbage: Garbage, values: TimelineValues) {
+        let id = self.garbage_id.fetch_add(1, Ordering::Relaxed);
+        self.to_destroy
+            .lock()
+            .unwrap()
+            .insert(id, ToDestroy { garbage, values });
+    }
+
+    /// Lists all garbage that hasn't been destroyed. Incoming garbage is received first, waiting
+    /// on the provided target values.
+    pub fn report(&self, target: TimelineValues, memory: &MemoryTracker) -> GarbageReport {
+        let mut to_destroy = self.to_destroy.lock().unwrap();
+        self.receive(&mut to_destroy, target);
+
+        GarbageReport {
+            items: to_destroy
+                .values()
+                .map(|garbage| PendingGarbage {
+                    kind: garbage.garbage.kind(),
+                    debug_name: garbage
+                        .garbage
+                        .allocation()
+                        .and_then(|allocation| memory.name_of(allocation)),
+                    wait_values: garbage.values.into(),
+                    referenced: garbage.garbage.is_referenced(),
+                })
+                .collect(),
+        }
+    }
+
     pub unsafe fn cleanup(&self, args: GarbageCleanupArgs) {
         // Receive all incoming garbage
         let mut to_destroy = self.to_destroy.lock().unwrap();
-        while let Ok(garbage) = self.receiver.try_recv() {
-            let id = self.garbage_id.fetch_add(1, Ordering::Relaxed);
-            to_destroy.insert(
-                id,
-                ToDestroy {
-                    garbage,
-                    values: args.target,
-                },
-            );
-        }
+        self.receive(&mut to_destroy, args.target);
 
         // Mark everything that is not being used by any queue
         let mut marked = self.marked.lock().unwrap();
         marked.clear();
         for (id, garbage) in to_destroy.iter() {
-            if !args.override_ref_counter {
-                match &garbage.garbage {
-                    Garbage::Buffer { ref_counter, .. } => {
-                        if !ref_counter.is_last() {
-                            continue;
-                        }
-                    }
-                    Garbage::Texture { ref_counter, .. } => {
-                        if !ref_counter.is_last() {
-                            continue;
-                        }
-                    }
-                    Garbage::AccelerationStructure { ref_counter, .. } => {
-                        if !ref_counter.is_last() {
-                            continue;
-                        }
-                    }
-                    _ => {}
+            // Layouts must outlive pipelines being compiled with them in the background
+            if let Garbage::PipelineLayout(layout) = &garbage.garbage {
+                if args.pipelines.is_compiling(*layout) {
+                    continue;
                 }
             }
 
-            if garbage.values.main <= args.current.main
-                && garbage.values.transfer <= args.current.transfer
-                && garbage.values.compute <= args.current.compute
-            {
+            if garbage.is_ready(&args.current, args.override_ref_counter) {
                 marked.push(*id);
             }
         }
@@ -174,15 +228,29 @@ impl GarbageCollector {
                 }
                 Garbage::Buffer {
                     buffer,
+                    views,
                     allocation,
                     id,
                     ..
                 } => {
+                    for view in views {
+                        args.device.destroy_buffer_view(view, None);
+                    }
                     args.device.destroy_buffer(buffer, None);
                     args.allocator.free(allocation).unwrap();
                     args.buffer_ids.free(id);
                     args.global_usage.remove_buffer(id);
                 }
+                Garbage::TransientBuffer {
+                    block, id, views, ..
+                } => {
+                    for view in views {
+                        args.device.destroy_buffer_view(view, None);
+                    }
+                    args.transient.free(block);
+                    args.buffer_ids.free(id);
+                    args.global_usage.remove_buffer(id);
+                }
                 Garbage::AccelerationStructure {
                     accelleration_struct,
                     buffer,
@@ -205,29 +273,40 @@ impl GarbageCollector {
                     image,
                     id,
                     views,
-                    allocation,
+                    allocations,
                     ..
                 } => {
                     args.device.destroy_image(image, None);
                     for view in views {
                         args.device.destroy_image_view(view, None);
                     }
-                    args.allocator.free(allocation).unwrap();
+                    for allocation in allocations {
+                        args.allocator.free(allocation).unwrap();
+                    }
                     args.image_ids.free(id);
                     args.global_usage.remove_image(id);
                 }
+                Garbage::SparsePages(allocations) => {
+                    for allocation in allocations {
+                        args.allocator.free(allocation).unwrap();
+                    }
+                }
                 Garbage::DescriptorSet {
                     set,
                     id,
                     layout,
+                    variable_count,
                     bindings,
                 } => {
-                    args.pools.get_by_layout(layout).unwrap().free(set);
+                    // Sets for pushed descriptors have no handle
+                    if set != vk::DescriptorSet::null() {
+                        args.pools
+                            .get_by_layout(layout)
+                            .unwrap()
+                            .free(set, variable_count);
+                    }
                     for element in bindings.into_iter().flatten().flatten() {
                         match element.value {
-                            BoundValue::Texture { view, .. } => {
-                                args.device.destroy_image_view(view, None);
-                            }
                             BoundValue::CubeMap { view, .. } => {
                                 args.device.destroy_image_view(view, None);
                             }
@@ -239,7 +318,205 @@ impl GarbageCollector {
                     }
                     args.set_ids.free(id);
                 }
+                Garbage::Bindings(bindings) => {
+                    for element in bindings {
+                        match element.value {
+                            BoundValue::CubeMap { view, .. }
+                            | BoundValue::StorageImage { view, .. } => {
+                                args.device.destroy_image_view(view, None);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Garbage::Swapchain {
+                    swapchain,
+                    images,
+                    semaphores,
+                    surface,
+                } => {
+                    for (id, view) in images {
+                        args.framebuffers.view_destroyed(args.device, view);
+                        args.device.destroy_image_view(view, None);
+                        args.image_ids.free(id);
+                        args.global_usage.remove_image(id);
+                    }
+                    for semaphores in semaphores {
+                        args.device.destroy_semaphore(semaphores.available, None);
+                        args.device.destroy_semaphore(semaphores.presentable, None);
+                    }
+                    if swapchain != vk::SwapchainKHR::null() {
+                        args.swapchain_loader.destroy_swapchain(swapchain, None);
+                    }
+                    if let Some(surface) = surface {
+                        args.surface_loader.destroy_surface(surface, None);
+                    }
+                }
+                Garbage::Queries {
+                    timestamps,
+                    statistics,
+                } => {
+                    for query in timestamps {
+                        args.queries.free_timestamp(args.device, query);
+                    }
+                    for query in statistics {
+                        args.queries.free_statistics(args.device, query);
+                    }
+                }
+                Garbage::Events(events) => {
+                    for event in events {
+                        args.events.free(args.device, event);
+                    }
+                }
             }
         }
     }
+
+    /// Moves incoming garbage into `to_destroy`, waiting on the provided target values.
+    fn receive(&self, to_destroy: &mut FIHashMap<u32, ToDestroy>, target: TimelineValues) {
+        while let Ok(garbage) = self.receiver.try_recv() {
+            let id = self.garbage_id.fetch_add(1, Ordering::Relaxed);
+            to_destroy.insert(
+                id,
+                ToDestroy {
+                    garbage,
+                    values: target,
+                },
+            );
+        }
+    }
+}
+
+impl ToDestroy {
+    /// Returns `true` if no queue or other object can still be using the garbage.
+    #[inline]
+    fn is_ready(&self, current: &TimelineValues, override_ref_counter: bool) -> bool {
+        (override_ref_counter || !self.garbage.is_referenced())
+            && self.values.main <= current.main
+            && self.values.transfer <= current.transfer
+            && self.values.compute <= current.compute
+            && self.values.present <= current.present
+    }
+}
+
+impl Garbage {
+    /// Returns `true` if another object, such as a descriptor set, still holds a reference to
+    /// the resource.
+    fn is_referenced(&self) -> bool {
+        match self {
+            Garbage::Buffer { ref_counter, .. }
+            | Garbage::TransientBuffer { ref_counter, .. }
+            | Garbage::AccelerationStructure { ref_counter, .. } => !ref_counter.is_last(),
+            Garbage::Texture { ref_counter, .. } => !ref_counter.is_last(),
+            _ => false,
+        }
+    }
+
+    fn kind(&self) -> GarbageKind {
+        match self {
+            Garbage::PipelineLayout(_) => GarbageKind::PipelineLayout,
+            Garbage::Pipeline(_) => GarbageKind::Pipeline,
+            Garbage::Buffer { .. } | Garbage::TransientBuffer { .. } => GarbageKind::Buffer,
+            Garbage::AccelerationStructure { .. } => GarbageKind::AccelerationStructure,
+            Garbage::Texture { .. } => GarbageKind::Texture,
+            Garbage::SparsePages(_) => GarbageKind::SparsePages,
+            Garbage::DescriptorSet { .. } => GarbageKind::DescriptorSet,
+            Garbage::Bindings(_) => GarbageKind::DescriptorBindings,
+            Garbage::Swapchain { .. } => GarbageKind::Swapchain,
+            Garbage::Queries { .. } => GarbageKind::Queries,
+            Garbage::Events(_) => GarbageKind::Events,
+        }
+    }
+
+    /// Gets the memory backing the resource, which is used to look up its debug name.
+    fn allocation(&self) -> Option<&Allocation> {
+        match self {
+            Garbage::Buffer { allocation, .. }
+            | Garbage::AccelerationStructure { allocation, .. } => Some(allocation),
+            Garbage::Texture { allocations, .. } => allocations.first(),
+            _ => None,
+        }
+    }
+}
+
+impl From<TimelineValues> for QueueTimelineValues {
+    fn from(values: TimelineValues) -> Self {
+        QueueTimelineValues {
+            main: values.main,
+            transfer: values.transfer,
+            compute: values.compute,
+            present: values.present,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(main: u64) -> TimelineValues {
+        TimelineValues {
+            main,
+            transfer: 0,
+            compute: 0,
+            present: 0,
+        }
+    }
+
+    fn buffer(ids: &IdGenerator, ref_counter: BufferRefCounter) -> Garbage {
+        Garbage::Buffer {
+            buffer: vk::Buffer::null(),
+            id: ids.create(),
+            views: Vec::default(),
+            allocation: Allocation::default(),
+            ref_counter,
+        }
+    }
+
+    #[test]
+    fn garbage_waits_on_submitted_jobs() {
+        let ids = IdGenerator::default();
+        let gc = GarbageCollector::new();
+
+        // Dropped while a job on the main queue that uses it is still running
+        gc.sender()
+            .send(buffer(&ids, BufferRefCounter::default()))
+            .unwrap();
+        assert!(gc.has_pending());
+
+        let memory = MemoryTracker::new(&vk::PhysicalDeviceMemoryProperties::default(), None);
+        let report = gc.report(values(3), &memory);
+        assert_eq!(report.items.len(), 1);
+        assert_eq!(report.items[0].kind, GarbageKind::Buffer);
+        assert_eq!(report.items[0].wait_values.main, 3);
+        assert!(!report.items[0].referenced);
+
+        // Not released until waiting on the job advances the current value, even though nothing
+        // else is submitted
+        let to_destroy = gc.to_destroy.lock().unwrap();
+        let garbage = to_destroy.values().next().unwrap();
+        assert!(!garbage.is_ready(&values(2), false));
+        assert!(garbage.is_ready(&values(3), false));
+    }
+
+    #[test]
+    fn referenced_garbage_is_held() {
+        let ids = IdGenerator::default();
+        let gc = GarbageCollector::new();
+
+        // A descriptor set still holds the buffer
+        let ref_counter = BufferRefCounter::default();
+        gc.retire(buffer(&ids, ref_counter.clone()), values(1));
+
+        let memory = MemoryTracker::new(&vk::PhysicalDeviceMemoryProperties::default(), None);
+        assert!(gc.report(values(1), &memory).items[0].referenced);
+
+        let to_destroy = gc.to_destroy.lock().unwrap();
+        let garbage = to_destroy.values().next().unwrap();
+        assert!(!garbage.is_ready(&values(1), false));
+        assert!(garbage.is_ready(&values(1), true));
+
+        std::mem::drop(ref_counter);
+        assert!(garbage.is_ready(&values(1), false));
+    }
 }
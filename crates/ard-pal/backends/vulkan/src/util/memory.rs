@@ -0,0 +1,632 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use api::{
+    blas::BottomLevelAccelerationStructureCreateError,
+    buffer::BufferCreateError,
+    context::{
+        MemoryAllocationInfo, MemoryBudget, MemoryCategory, MemoryCategoryStats, MemoryHeapBudget,
+        MemoryHeapStats, MemoryPressure, MemoryPressureCallback, MemoryStats,
+    },
+    cube_map::CubeMapCreateError,
+    texture::TextureCreateError,
+    tlas::TopLevelAccelerationStructureCreateError,
+    types::MemoryFallback,
+};
+use ash::vk;
+use gpu_allocator::{
+    vulkan::{Allocation, AllocationCreateDesc, Allocator},
+    AllocationError, MemoryLocation,
+};
+use rustc_hash::FxHashMap;
+
+/// Budgets are refreshed at most this often, so submitting many jobs in a frame only queries
+/// them once.
+const BUDGET_REFRESH_INTERVAL: Duration = Duration::from_millis(8);
+
+/// Wraps the GPU allocator so that every allocation is tagged with the category of resource it
+/// backs and counted by a [`MemoryTracker`].
+pub(crate) struct TrackedAllocator {
+    allocator: Allocator,
+    tracker: Arc<MemoryTracker>,
+}
+
+/// Counts live allocations per heap and per category. Kept outside of the allocator lock so
+/// statistics can be polled without waiting on resource creation.
+pub(crate) struct MemoryTracker {
+    heaps: Vec<HeapCounter>,
+    /// Property flags and heap index of every memory type.
+    memory_types: Vec<(vk::MemoryPropertyFlags, usize)>,
+    categories: [CategoryCounter; MemoryCategory::COUNT],
+    /// Every live allocation, keyed by its memory and offset.
+    live: Mutex<FxHashMap<(vk::DeviceMemory, u64), LiveAllocation>>,
+}
+
+#[derive(Debug)]
+pub(crate) enum AllocateError {
+    /// The heap the allocation was made from is full, or allocating from it would go over the
+    /// memory budget.
+    OutOfMemory {
+        requested: u64,
+        heap: usize,
+    },
+    Other(String),
+}
+
+/// Per heap memory budgets, refreshed lazily as jobs are submitted.
+pub(crate) struct MemoryBudgets {
+    /// `VK_EXT_memory_budget` is supported. Otherwise, budgets are derived from the tracker.
+    supported: bool,
+    state: Mutex<BudgetState>,
+}
+
+struct BudgetState {
+    budget: MemoryBudget,
+    last_refresh: Option<Instant>,
+    listeners: Vec<PressureListener>,
+}
+
+struct PressureListener {
+    threshold: f32,
+    callback: Arc<MemoryPressureCallback>,
+    /// Heaps that were over the threshold at the last refresh.
+    pressured: Vec<bool>,
+}
+
+struct HeapCounter {
+    size: u64,
+    device_local: bool,
+    /// Maximum number of bytes that can be allocated from the heap. `None` if the only limit is
+    /// the heap itself.
+    budget: Option<u64>,
+    allocated: AtomicU64,
+}
+
+#[derive(Default)]
+struct CategoryCounter {
+    count: AtomicU64,
+    bytes: AtomicU64,
+}
+
+struct LiveAllocation {
+    name: String,
+    category: MemoryCategory,
+    heap: usize,
+    size: u64,
+}
+
+impl TrackedAllocator {
+    #[inline(always)]
+    pub fn new(allocator: Allocator, tracker: Arc<MemoryTracker>) -> Self {
+        Self { allocator, tracker }
+    }
+
+    pub fn allocate(
+        &mut self,
+        desc: &AllocationCreateDesc<'_>,
+        category: MemoryCategory,
+    ) -> Result<Allocation, AllocateError> {
+        let memory_type_bits = desc.requirements.memory_type_bits;
+        let allocation = match self.allocator.allocate(desc) {
+            Ok(allocation) => allocation,
+            Err(AllocationError::OutOfMemory) => {
+                return Err(AllocateError::OutOfMemory {
+                    requested: desc.requirements.size,
+                    heap: self.tracker.expected_heap(desc.location, memory_type_bits),
+                })
+            }
+            Err(err) => return Err(AllocateError::Other(err.to_string())),
+        };
+
+        let size = allocation.size();
+        let heap = self
+            .tracker
+            .heap_of(allocation.memory_properties(), memory_type_bits);
+        if !self.tracker.reserve(heap, size) {
+            self.allocator
+                .free(allocation)
+                .map_err(|err| AllocateError::Other(err.to_string()))?;
+            return Err(AllocateError::OutOfMemory {
+                requested: size,
+                heap,
+            });
+        }
+
+        self.tracker.track(&allocation, desc.name, category, heap);
+        Ok(allocation)
+    }
+
+    /// Allocates memory, retrying in host memory if the allocation doesn't fit in device local
+    /// memory and `fallback` allows it.
+    pub fn allocate_with_fallback(
+        &mut self,
+        desc: &AllocationCreateDesc<'_>,
+        category: MemoryCategory,
+        fallback: MemoryFallback,
+    ) -> Result<Allocation, AllocateError> {
+        let err = match self.allocate(desc, category) {
+            Ok(allocation) => return Ok(allocation),
+            Err(err) => err,
+        };
+
+        let heap = match (&err, fallback) {
+            (AllocateError::OutOfMemory { heap, .. }, MemoryFallback::AllowSystemMemory) => *heap,
+            _ => return Err(err),
+        };
+
+        let mut requirements = desc.requirements;
+        requirements.memory_type_bits = match self
+            .tracker
+            .fallback_types(heap, requirements.memory_type_bits)
+        {
+            Some(memory_type_bits) => memory_type_bits,
+            None => return Err(err),
+        };
+
+        self.allocate(
+            &AllocationCreateDesc {
+                requirements,
+                location: MemoryLocation::CpuToGpu,
+                ..*desc
+            },
+            category,
+        )
+    }
+
+    pub fn free(&mut self, allocation: Allocation) -> gpu_allocator::Result<()> {
+        self.tracker.untrack(&allocation);
+        self.allocator.free(allocation)
+    }
+}
+
+impl MemoryTracker {
+    /// Creates a tracker for a device with the given memory properties. If `device_budget` is
+    /// provided, allocations from each device local heap are limited to that many bytes.
+    pub fn new(
+        properties: &vk::PhysicalDeviceMemoryProperties,
+        device_budget: Option<u64>,
+    ) -> Self {
+        let heaps = properties.memory_heaps_as_slice();
+        let memory_types = properties.memory_types_as_slice();
+
+        Self {
+            heaps: heaps
+                .iter()
+                .map(|heap| {
+                    let device_local = heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL);
+                    HeapCounter {
+                        size: heap.size,
+                        device_local,
+                        budget: if device_local { device_budget } else { None },
+                        allocated: AtomicU64::new(0),
+                    }
+                })
+                .collect(),
+            memory_types: memory_types
+                .iter()
+                .map(|ty| (ty.property_flags, ty.heap_index as usize))
+                .collect(),
+            categories: Default::default(),
+            live: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        let mut categories = [MemoryCategoryStats::default(); MemoryCategory::COUNT];
+        for (stats, counter) in categories.iter_mut().zip(self.categories.iter()) {
+            stats.count = counter.count.load(Ordering::Relaxed);
+            stats.bytes = counter.bytes.load(Ordering::Relaxed);
+        }
+
+        MemoryStats {
+            heaps: self
+                .heaps
+                .iter()
+                .map(|heap| MemoryHeapStats {
+                    size: heap.size,
+                    device_local: heap.device_local,
+                    allocated: heap.allocated.load(Ordering::Relaxed),
+                })
+                .collect(),
+            categories,
+        }
+    }
+
+    pub fn allocations(&self) -> Vec<MemoryAllocationInfo> {
+        self.live
+            .lock()
+            .unwrap()
+            .values()
+            .map(|allocation| MemoryAllocationInfo {
+                name: allocation.name.clone(),
+                category: allocation.category,
+                size: allocation.size,
+            })
+            .collect()
+    }
+
+    /// Gets the debug name of a live allocation.
+    pub fn name_of(&self, allocation: &Allocation) -> Option<String> {
+        // SAFETY: The memory handle is only used as a key.
+        let key = (unsafe { allocation.memory() }, allocation.offset());
+        self.live
+            .lock()
+            .unwrap()
+            .get(&key)
+            .map(|allocation| allocation.name.clone())
+    }
+
+    /// Accounts for `size` bytes allocated from `heap`. Returns `false` without changing anything
+    /// if the heap doesn't have enough budget left.
+    fn reserve(&self, heap: usize, size: u64) -> bool {
+        let heap = &self.heaps[heap];
+        heap.allocated
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |allocated| {
+                let allocated = allocated + size;
+                match heap.budget {
+                    Some(budget) if allocated > budget => None,
+                    _ => Some(allocated),
+                }
+            })
+            .is_ok()
+    }
+
+    /// Records an allocation whose size has already been [reserved](Self::reserve).
+    fn track(&self, allocation: &Allocation, name: &str, category: MemoryCategory, heap: usize) {
+        let size = allocation.size();
+        let counter = &self.categories[category as usize];
+        counter.count.fetch_add(1, Ordering::Relaxed);
+        counter.bytes.fetch_add(size, Ordering::Relaxed);
+
+        // SAFETY: The memory handle is only used as a key.
+        let key = (unsafe { allocation.memory() }, allocation.offset());
+        self.live.lock().unwrap().insert(
+            key,
+            LiveAllocation {
+                name: name.to_owned(),
+                category,
+                heap,
+                size,
+            },
+        );
+    }
+
+    fn untrack(&self, allocation: &Allocation) {
+        // SAFETY: The memory handle is only used as a key.
+        let key = (unsafe { allocation.memory() }, allocation.offset());
+        let allocation = match self.live.lock().unwrap().remove(&key) {
+            Some(allocation) => allocation,
+            None => return,
+        };
+
+        self.heaps[allocation.heap]
+            .allocated
+            .fetch_sub(allocation.size, Ordering::Relaxed);
+        let counter = &self.categories[allocation.category as usize];
+        counter.count.fetch_sub(1, Ordering::Relaxed);
+        counter.bytes.fetch_sub(allocation.size, Ordering::Relaxed);
+    }
+
+    /// Gets the memory types an allocation that didn't fit in `heap` can be retried in. Returns
+    /// `None` if `heap` isn't device local or none of `memory_type_bits` are in host memory.
+    fn fallback_types(&self, heap: usize, memory_type_bits: u32) -> Option<u32> {
+        if !self.heaps[heap].device_local {
+            return None;
+        }
+
+        let memory_type_bits = self
+            .memory_types
+            .iter()
+            .enumerate()
+            .filter(|(_, (flags, heap))| {
+                flags.contains(vk::MemoryPropertyFlags::HOST_VISIBLE)
+                    && !self.heaps[*heap].device_local
+            })
+            .fold(0, |bits, (i, _)| bits | (1 << i))
+            & memory_type_bits;
+
+        (memory_type_bits != 0).then_some(memory_type_bits)
+    }
+
+    /// Finds the heap an allocation in `location` would be made from. Used to report which heap
+    /// ran out of memory when the allocator fails.
+    fn expected_heap(&self, location: MemoryLocation, memory_type_bits: u32) -> usize {
+        let preferred = match location {
+            MemoryLocation::GpuOnly => vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            MemoryLocation::CpuToGpu | MemoryLocation::GpuToCpu => {
+                vk::MemoryPropertyFlags::HOST_VISIBLE
+            }
+            MemoryLocation::Unknown => vk::MemoryPropertyFlags::empty(),
+        };
+
+        self.memory_types
+            .iter()
+            .enumerate()
+            .find(|(i, (flags, _))| (memory_type_bits & (1 << i)) != 0 && flags.contains(preferred))
+            .map(|(_, (_, heap))| *heap)
+            .unwrap_or_default()
+    }
+
+    /// Finds the heap an allocation was made from. The allocator picks the first memory type
+    /// allowed by the requirements with suitable properties, so the first allowed type with
+    /// exactly the properties of the allocation is the one it used.
+    fn heap_of(&self, properties: vk::MemoryPropertyFlags, memory_type_bits: u32) -> usize {
+        self.memory_types
+            .iter()
+            .enumerate()
+            .find(|(i, (flags, _))| (memory_type_bits & (1 << i)) != 0 && *flags == properties)
+            .map(|(_, (_, heap))| *heap)
+            .unwrap_or_default()
+    }
+}
+
+impl MemoryBudgets {
+    pub fn new(supported: bool) -> Self {
+        Self {
+            supported,
+            state: Mutex::new(BudgetState {
+                budget: MemoryBudget::default(),
+                last_refresh: None,
+                listeners: Vec::default(),
+            }),
+        }
+    }
+
+    #[inline(always)]
+    pub fn budget(&self) -> MemoryBudget {
+        self.state.lock().unwrap().budget.clone()
+    }
+
+    pub fn on_pressure(&self, threshold: f32, callback: MemoryPressureCallback) {
+        self.state.lock().unwrap().listeners.push(PressureListener {
+            threshold,
+            callback: Arc::new(callback),
+            pressured: Vec::default(),
+        });
+    }
+
+    /// Queries the budget of every heap if it hasn't been refreshed recently, and invokes the
+    /// callbacks of listeners whose threshold was crossed.
+    pub unsafe fn refresh(
+        &self,
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        tracker: &MemoryTracker,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if let Some(last_refresh) = state.last_refresh {
+            if now.duration_since(last_refresh) < BUDGET_REFRESH_INTERVAL {
+                return;
+            }
+        }
+        state.last_refresh = Some(now);
+
+        state.budget = if self.supported {
+            let mut budget_props = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+            let mut props =
+                vk::PhysicalDeviceMemoryProperties2::default().push_next(&mut budget_props);
+            instance.get_physical_device_memory_properties2(physical_device, &mut props);
+            let heap_count = props.memory_properties.memory_heap_count as usize;
+            MemoryBudget {
+                heaps: (0..heap_count)
+                    .map(|heap| MemoryHeapBudget {
+                        budget: budget_props.heap_budget[heap],
+                        usage: budget_props.heap_usage[heap],
+                    })
+                    .collect(),
+            }
+        } else {
+            MemoryBudget {
+                heaps: tracker
+                    .stats()
+                    .heaps
+                    .iter()
+                    .map(|heap| MemoryHeapBudget {
+                        budget: heap.size,
+                        usage: heap.allocated,
+                    })
+                    .collect(),
+            }
+        };
+
+        // Callbacks are invoked without the lock held so they can query the budget
+        let BudgetState {
+            budget, listeners, ..
+        } = &mut *state;
+        let mut pressures = Vec::default();
+        for listener in listeners.iter_mut() {
+            for pressure in listener.update(budget) {
+                pressures.push((listener.callback.clone(), pressure));
+            }
+        }
+        std::mem::drop(state);
+
+        for (callback, pressure) in pressures {
+            callback(&pressure);
+        }
+    }
+}
+
+impl PressureListener {
+    /// Finds the heaps that went over the threshold since the last update.
+    fn update(&mut self, budget: &MemoryBudget) -> Vec<MemoryPressure> {
+        self.pressured.resize(budget.heaps.len(), false);
+        let mut pressures = Vec::default();
+        for (heap, (heap_budget, pressured)) in budget
+            .heaps
+            .iter()
+            .zip(self.pressured.iter_mut())
+            .enumerate()
+        {
+            let over = heap_budget.fraction() >= self.threshold;
+            if over && !*pressured {
+                pressures.push(MemoryPressure {
+                    heap,
+                    budget: *heap_budget,
+                    threshold: self.threshold,
+                });
+            }
+            *pressured = over;
+        }
+        pressures
+    }
+}
+
+macro_rules! impl_from_allocate_error {
+    ($($error:ty),*) => {
+        $(
+            impl From<AllocateError> for $error {
+                fn from(err: AllocateError) -> Self {
+                    match err {
+                        AllocateError::OutOfMemory { requested, heap } => {
+                            Self::OutOfMemory { requested, heap }
+                        }
+                        AllocateError::Other(err) => Self::Other(err),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_allocate_error!(
+    BufferCreateError,
+    TextureCreateError,
+    CubeMapCreateError,
+    TopLevelAccelerationStructureCreateError,
+    BottomLevelAccelerationStructureCreateError
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    /// A discrete GPU with device local, host, and resizable BAR memory types.
+    fn discrete_tracker(device_budget: Option<u64>) -> MemoryTracker {
+        let mut properties = vk::PhysicalDeviceMemoryProperties {
+            memory_type_count: 3,
+            memory_heap_count: 2,
+            ..Default::default()
+        };
+        properties.memory_heaps[0] = vk::MemoryHeap {
+            size: 256 * MIB,
+            flags: vk::MemoryHeapFlags::DEVICE_LOCAL,
+        };
+        properties.memory_heaps[1] = vk::MemoryHeap {
+            size: 1024 * MIB,
+            flags: vk::MemoryHeapFlags::empty(),
+        };
+        properties.memory_types[0] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            heap_index: 0,
+        };
+        properties.memory_types[1] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+            heap_index: 1,
+        };
+        properties.memory_types[2] = vk::MemoryType {
+            property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL
+                | vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+            heap_index: 0,
+        };
+        MemoryTracker::new(&properties, device_budget)
+    }
+
+    #[test]
+    fn pressure_fires_once_per_crossing() {
+        let mut listener = PressureListener {
+            threshold: 0.9,
+            callback: Arc::new(Box::new(|_| {})),
+            pressured: Vec::default(),
+        };
+        let budget = |usage: [u64; 2]| MemoryBudget {
+            heaps: usage
+                .iter()
+                .map(|usage| MemoryHeapBudget {
+                    budget: 100,
+                    usage: *usage,
+                })
+                .collect(),
+        };
+
+        assert!(listener.update(&budget([50, 50])).is_empty());
+
+        let pressures = listener.update(&budget([95, 50]));
+        assert_eq!(pressures.len(), 1);
+        assert_eq!(pressures[0].heap, 0);
+        assert_eq!(pressures[0].budget.usage, 95);
+
+        // Staying over the threshold doesn't fire again
+        assert!(listener.update(&budget([99, 50])).is_empty());
+
+        // Dropping under and crossing again does
+        assert!(listener.update(&budget([80, 50])).is_empty());
+        let pressures = listener.update(&budget([90, 100]));
+        assert_eq!(
+            pressures.iter().map(|p| p.heap).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn budget_limits_device_local_heaps() {
+        let tracker = discrete_tracker(Some(MIB));
+
+        assert!(tracker.reserve(0, MIB / 2));
+        assert!(!tracker.reserve(0, MIB));
+        assert!(tracker.reserve(0, MIB / 2));
+        assert!(!tracker.reserve(0, 1));
+        assert_eq!(tracker.stats().heaps[0].allocated, MIB);
+
+        // Host memory isn't limited by the budget
+        assert!(tracker.reserve(1, 16 * MIB));
+        assert_eq!(tracker.stats().heaps[1].allocated, 16 * MIB);
+    }
+
+    #[test]
+    fn out_of_memory_reports_requested_heap() {
+        let tracker = discrete_tracker(None);
+
+        assert_eq!(tracker.expected_heap(MemoryLocation::GpuOnly, 0b111), 0);
+        assert_eq!(tracker.expected_heap(MemoryLocation::CpuToGpu, 0b111), 1);
+        assert_eq!(tracker.expected_heap(MemoryLocation::CpuToGpu, 0b100), 0);
+
+        let err = TextureCreateError::from(AllocateError::OutOfMemory {
+            requested: 2 * MIB,
+            heap: 0,
+        });
+        assert!(matches!(
+            err,
+            TextureCreateError::OutOfMemory {
+                requested,
+                heap: 0,
+            } if requested == 2 * MIB
+        ));
+    }
+
+    #[test]
+    fn fallback_only_uses_host_memory() {
+        let tracker = discrete_tracker(Some(MIB));
+        assert!(!tracker.reserve(0, 2 * MIB));
+
+        // Resizable BAR memory is in the device local heap, so only the host type is allowed
+        assert_eq!(tracker.fallback_types(0, 0b111), Some(0b010));
+        assert!(tracker.reserve(1, 2 * MIB));
+
+        // Nothing to fall back to if the resource can't live in host memory
+        assert_eq!(tracker.fallback_types(0, 0b101), None);
+
+        // Running out of host memory has no fallback
+        assert_eq!(tracker.fallback_types(1, 0b111), None);
+    }
+}
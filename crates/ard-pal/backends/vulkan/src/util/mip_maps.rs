@@ -0,0 +1,69 @@
+use api::{
+    command_buffer::{BlitDestination, BlitSource, Command},
+    texture::Blit,
+    types::CubeFace,
+};
+
+use crate::VulkanBackend;
+
+/// Replaces mip map generation commands with the chain of blits that perform them. Each blit
+/// reads the mip written by the blit before it, so the usage tracker places the transitions
+/// between levels.
+pub fn expand_mip_maps(
+    commands: Vec<Command<'_, VulkanBackend>>,
+) -> Vec<Command<'_, VulkanBackend>> {
+    if !commands.iter().any(|command| {
+        matches!(
+            command,
+            Command::GenerateMipMaps { .. } | Command::GenerateCubeMapMipMaps { .. }
+        )
+    }) {
+        return commands;
+    }
+
+    let mut expanded = Vec::with_capacity(commands.len());
+    for command in commands {
+        match command {
+            Command::GenerateMipMaps {
+                texture,
+                array_element,
+                filter,
+            } => {
+                for blit in Blit::mip_chain(texture.dims(), texture.mip_count(), array_element) {
+                    expanded.push(Command::Blit {
+                        src: BlitSource::Texture(texture),
+                        dst: BlitDestination::Texture(texture),
+                        blit,
+                        filter,
+                    });
+                }
+            }
+            Command::GenerateCubeMapMipMaps {
+                cube_map,
+                array_element,
+                filter,
+            } => {
+                let dims = (cube_map.dim(), cube_map.dim(), 1);
+                for face in [
+                    CubeFace::North,
+                    CubeFace::East,
+                    CubeFace::South,
+                    CubeFace::West,
+                    CubeFace::Top,
+                    CubeFace::Bottom,
+                ] {
+                    for blit in Blit::mip_chain(dims, cube_map.mip_count(), array_element) {
+                        expanded.push(Command::Blit {
+                            src: BlitSource::CubeMap { cube_map, face },
+                            dst: BlitDestination::CubeMap { cube_map, face },
+                            blit,
+                            filter,
+                        });
+                    }
+                }
+            }
+            command => expanded.push(command),
+        }
+    }
+    expanded
+}
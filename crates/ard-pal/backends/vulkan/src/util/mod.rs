@@ -1,16 +1,24 @@
+use std::ffi::CString;
+
 use api::{descriptor_set::DescriptorType, types::*};
 use ash::vk;
 use gpu_allocator::MemoryLocation;
 
 pub mod command_sort;
+pub mod compute_layouts;
 pub mod descriptor_pool;
+pub mod events;
 pub mod fast_int_hasher;
 pub mod garbage_collector;
 pub mod id_gen;
+pub mod memory;
+pub mod mip_maps;
 pub mod pipeline_cache;
 pub mod queries;
 pub mod sampler_cache;
 pub mod semaphores;
+pub mod sparse;
+pub mod transient;
 pub mod usage;
 
 // TODO: Pretty sure this is wrong
@@ -45,6 +53,40 @@ pub(crate) const fn to_vk_present_mode(present_mode: PresentMode) -> vk::Present
     }
 }
 
+#[inline(always)]
+pub(crate) const fn from_vk_present_mode(present_mode: vk::PresentModeKHR) -> Option<PresentMode> {
+    match present_mode {
+        vk::PresentModeKHR::IMMEDIATE => Some(PresentMode::Immediate),
+        vk::PresentModeKHR::MAILBOX => Some(PresentMode::Mailbox),
+        vk::PresentModeKHR::FIFO => Some(PresentMode::Fifo),
+        vk::PresentModeKHR::FIFO_RELAXED => Some(PresentMode::FifoRelaxed),
+        _ => None,
+    }
+}
+
+#[inline(always)]
+pub(crate) const fn to_vk_color_space(color_space: ColorSpace) -> vk::ColorSpaceKHR {
+    match color_space {
+        ColorSpace::SrgbNonlinear => vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        ColorSpace::Hdr10St2084 => vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+        ColorSpace::ExtendedSrgbLinear => vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+    }
+}
+
+/// Maps formats that surfaces commonly support back to their API equivalent.
+#[inline(always)]
+pub(crate) const fn from_vk_surface_format(format: vk::Format) -> Option<Format> {
+    match format {
+        vk::Format::B8G8R8A8_UNORM => Some(Format::Bgra8Unorm),
+        vk::Format::B8G8R8A8_SRGB => Some(Format::Bgra8Srgb),
+        vk::Format::R8G8B8A8_UNORM => Some(Format::Rgba8Unorm),
+        vk::Format::R8G8B8A8_SRGB => Some(Format::Rgba8Srgb),
+        vk::Format::R16G16B16A16_SFLOAT => Some(Format::Rgba16SFloat),
+        vk::Format::A2B10G10R10_UNORM_PACK32 => Some(Format::Rgb10A2Unorm),
+        _ => None,
+    }
+}
+
 #[inline(always)]
 pub(crate) const fn to_vk_format(format: Format) -> vk::Format {
     match format {
@@ -101,10 +143,21 @@ pub(crate) const fn to_vk_format(format: Format) -> vk::Format {
         // BGRA8
         Format::Bgra8Unorm => vk::Format::R8G8B8A8_UNORM,
         Format::Bgra8Srgb => vk::Format::B8G8R8A8_SRGB,
+
+        // RGB10A2
+        Format::Rgb10A2Unorm => vk::Format::A2B10G10R10_UNORM_PACK32,
         // Compressed
         Format::BC6HUFloat => vk::Format::BC6H_UFLOAT_BLOCK,
         Format::BC7Srgb => vk::Format::BC7_SRGB_BLOCK,
         Format::BC7Unorm => vk::Format::BC7_UNORM_BLOCK,
+        Format::Astc4x4Unorm => vk::Format::ASTC_4X4_UNORM_BLOCK,
+        Format::Astc4x4Srgb => vk::Format::ASTC_4X4_SRGB_BLOCK,
+        Format::Astc8x8Unorm => vk::Format::ASTC_8X8_UNORM_BLOCK,
+        Format::Astc8x8Srgb => vk::Format::ASTC_8X8_SRGB_BLOCK,
+        Format::Etc2Rgb8Unorm => vk::Format::ETC2_R8G8B8_UNORM_BLOCK,
+        Format::Etc2Rgb8Srgb => vk::Format::ETC2_R8G8B8_SRGB_BLOCK,
+        Format::Etc2Rgba8Unorm => vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+        Format::Etc2Rgba8Srgb => vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK,
         // Depth
         Format::D16Unorm => vk::Format::D16_UNORM,
         Format::D24UnormS8Uint => vk::Format::D24_UNORM_S8_UINT,
@@ -146,10 +199,14 @@ pub(crate) const fn to_vk_descriptor_type(ty: DescriptorType) -> vk::DescriptorT
         DescriptorType::UniformBuffer => vk::DescriptorType::UNIFORM_BUFFER,
         DescriptorType::StorageBuffer(_) => vk::DescriptorType::STORAGE_BUFFER,
         DescriptorType::StorageImage(_) => vk::DescriptorType::STORAGE_IMAGE,
-        DescriptorType::CubeMap => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+        DescriptorType::CubeMap | DescriptorType::CubeMapArray => {
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+        }
         DescriptorType::TopLevelAccelerationStructure => {
             vk::DescriptorType::ACCELERATION_STRUCTURE_KHR
         }
+        DescriptorType::UniformTexelBuffer => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+        DescriptorType::StorageTexelBuffer(_) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
     }
 }
 
@@ -172,6 +229,7 @@ pub(crate) fn to_vk_shader_stage(ss: ShaderStage) -> vk::ShaderStageFlags {
                 | vk::ShaderStageFlags::ANY_HIT_KHR
                 | vk::ShaderStageFlags::CLOSEST_HIT_KHR
                 | vk::ShaderStageFlags::MISS_KHR
+                | vk::ShaderStageFlags::INTERSECTION_KHR
         }
         ShaderStage::Vertex => vk::ShaderStageFlags::VERTEX,
         ShaderStage::Fragment => vk::ShaderStageFlags::FRAGMENT,
@@ -184,11 +242,13 @@ pub(crate) fn to_vk_shader_stage(ss: ShaderStage) -> vk::ShaderStageFlags {
                 | vk::ShaderStageFlags::ANY_HIT_KHR
                 | vk::ShaderStageFlags::CLOSEST_HIT_KHR
                 | vk::ShaderStageFlags::MISS_KHR
+                | vk::ShaderStageFlags::INTERSECTION_KHR
         }
         ShaderStage::RayGeneration => vk::ShaderStageFlags::RAYGEN_KHR,
         ShaderStage::RayMiss => vk::ShaderStageFlags::MISS_KHR,
         ShaderStage::RayClosestHit => vk::ShaderStageFlags::CLOSEST_HIT_KHR,
         ShaderStage::RayAnyHit => vk::ShaderStageFlags::ANY_HIT_KHR,
+        ShaderStage::RayIntersection => vk::ShaderStageFlags::INTERSECTION_KHR,
     }
 }
 
@@ -242,6 +302,20 @@ pub(crate) const fn to_vk_compare_op(co: CompareOp) -> vk::CompareOp {
     }
 }
 
+#[inline(always)]
+pub(crate) const fn to_vk_stencil_op(op: StencilOp) -> vk::StencilOp {
+    match op {
+        StencilOp::Keep => vk::StencilOp::KEEP,
+        StencilOp::Zero => vk::StencilOp::ZERO,
+        StencilOp::Replace => vk::StencilOp::REPLACE,
+        StencilOp::IncrementAndClamp => vk::StencilOp::INCREMENT_AND_CLAMP,
+        StencilOp::DecrementAndClamp => vk::StencilOp::DECREMENT_AND_CLAMP,
+        StencilOp::Invert => vk::StencilOp::INVERT,
+        StencilOp::IncrementAndWrap => vk::StencilOp::INCREMENT_AND_WRAP,
+        StencilOp::DecrementAndWrap => vk::StencilOp::DECREMENT_AND_WRAP,
+    }
+}
+
 #[inline(always)]
 pub(crate) const fn to_vk_blend_factor(bf: BlendFactor) -> vk::BlendFactor {
     match bf {
@@ -255,6 +329,14 @@ pub(crate) const fn to_vk_blend_factor(bf: BlendFactor) -> vk::BlendFactor {
         BlendFactor::OneMinusSrcAlpha => vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
         BlendFactor::DstAlpha => vk::BlendFactor::DST_ALPHA,
         BlendFactor::OneMinusDstAlpha => vk::BlendFactor::ONE_MINUS_DST_ALPHA,
+        BlendFactor::ConstantColor => vk::BlendFactor::CONSTANT_COLOR,
+        BlendFactor::OneMinusConstantColor => vk::BlendFactor::ONE_MINUS_CONSTANT_COLOR,
+        BlendFactor::ConstantAlpha => vk::BlendFactor::CONSTANT_ALPHA,
+        BlendFactor::OneMinusConstantAlpha => vk::BlendFactor::ONE_MINUS_CONSTANT_ALPHA,
+        BlendFactor::Src1Color => vk::BlendFactor::SRC1_COLOR,
+        BlendFactor::OneMinusSrc1Color => vk::BlendFactor::ONE_MINUS_SRC1_COLOR,
+        BlendFactor::Src1Alpha => vk::BlendFactor::SRC1_ALPHA,
+        BlendFactor::OneMinusSrc1Alpha => vk::BlendFactor::ONE_MINUS_SRC1_ALPHA,
     }
 }
 
@@ -278,7 +360,7 @@ pub(crate) const fn to_vk_filter(f: Filter) -> vk::Filter {
 }
 
 #[inline(always)]
-pub(crate) const fn _to_vk_reduction_mode(rm: ReductionMode) -> vk::SamplerReductionMode {
+pub(crate) const fn to_vk_reduction_mode(rm: ReductionMode) -> vk::SamplerReductionMode {
     match rm {
         ReductionMode::Min => vk::SamplerReductionMode::MIN,
         ReductionMode::Max => vk::SamplerReductionMode::MAX,
@@ -304,6 +386,8 @@ pub(crate) const fn to_vk_border_color(bc: BorderColor) -> vk::BorderColor {
         BorderColor::IntOpaqueBlack => vk::BorderColor::INT_OPAQUE_BLACK,
         BorderColor::FloatOpaqueWhite => vk::BorderColor::FLOAT_OPAQUE_WHITE,
         BorderColor::IntOpaqueWhite => vk::BorderColor::INT_OPAQUE_WHITE,
+        BorderColor::CustomFloat(_) => vk::BorderColor::FLOAT_CUSTOM_EXT,
+        BorderColor::CustomInt(_) => vk::BorderColor::INT_CUSTOM_EXT,
     }
 }
 
@@ -345,6 +429,75 @@ pub(crate) const fn to_vk_sharing_mode(sm: SharingMode) -> vk::SharingMode {
     }
 }
 
+#[inline(always)]
+pub(crate) const fn from_vk_sample_counts(counts: vk::SampleCountFlags) -> SampleCounts {
+    // Flags share the same bit layout
+    SampleCounts::from_bits_truncate(counts.as_raw())
+}
+
+/// Converts the features of a format. `buffer_features` are the features of buffers viewed with
+/// the format, which only determine texel buffer support.
+pub(crate) fn from_vk_format_features(
+    features: vk::FormatFeatureFlags,
+    buffer_features: vk::FormatFeatureFlags,
+) -> FormatSupport {
+    let texel_buffers = [
+        (
+            vk::FormatFeatureFlags::UNIFORM_TEXEL_BUFFER,
+            FormatSupport::UNIFORM_TEXEL_BUFFER,
+        ),
+        (
+            vk::FormatFeatureFlags::STORAGE_TEXEL_BUFFER,
+            FormatSupport::STORAGE_TEXEL_BUFFER,
+        ),
+    ]
+    .into_iter()
+    .filter(|(vk_feature, _)| buffer_features.contains(*vk_feature))
+    .fold(FormatSupport::empty(), |support, (_, feature)| {
+        support | feature
+    });
+
+    let texture = [
+        (
+            vk::FormatFeatureFlags::TRANSFER_SRC,
+            FormatSupport::TRANSFER_SRC,
+        ),
+        (
+            vk::FormatFeatureFlags::TRANSFER_DST,
+            FormatSupport::TRANSFER_DST,
+        ),
+        (
+            vk::FormatFeatureFlags::SAMPLED_IMAGE,
+            FormatSupport::SAMPLED,
+        ),
+        (
+            vk::FormatFeatureFlags::STORAGE_IMAGE,
+            FormatSupport::STORAGE,
+        ),
+        (
+            vk::FormatFeatureFlags::COLOR_ATTACHMENT,
+            FormatSupport::COLOR_ATTACHMENT,
+        ),
+        (
+            vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+            FormatSupport::DEPTH_STENCIL_ATTACHMENT,
+        ),
+        (
+            vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+            FormatSupport::LINEAR_FILTER,
+        ),
+        (vk::FormatFeatureFlags::BLIT_SRC, FormatSupport::BLIT_SRC),
+        (vk::FormatFeatureFlags::BLIT_DST, FormatSupport::BLIT_DST),
+    ]
+    .into_iter()
+    .filter(|(vk_feature, _)| features.contains(*vk_feature))
+    .fold(FormatSupport::empty(), |support, (_, feature)| {
+        support | feature
+    });
+
+    texture | texel_buffers
+}
+
 #[inline(always)]
 pub(crate) const fn to_vk_sample_count(ms: MultiSamples) -> vk::SampleCountFlags {
     match ms {
@@ -406,6 +559,15 @@ pub(crate) fn to_vk_buffer_usage(bu: BufferUsage) -> vk::BufferUsageFlags {
         out |= vk::BufferUsageFlags::SHADER_BINDING_TABLE_KHR
             | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS;
     }
+    if bu.contains(BufferUsage::CONDITIONAL_RENDERING) {
+        out |= vk::BufferUsageFlags::CONDITIONAL_RENDERING_EXT;
+    }
+    if bu.contains(BufferUsage::UNIFORM_TEXEL_BUFFER) {
+        out |= vk::BufferUsageFlags::UNIFORM_TEXEL_BUFFER;
+    }
+    if bu.contains(BufferUsage::STORAGE_TEXEL_BUFFER) {
+        out |= vk::BufferUsageFlags::STORAGE_TEXEL_BUFFER;
+    }
     out
 }
 
@@ -442,6 +604,24 @@ pub(crate) const fn to_vk_image_type(it: TextureType) -> vk::ImageType {
     }
 }
 
+#[inline(always)]
+pub(crate) const fn to_vk_aspect(aspect: TextureAspect) -> vk::ImageAspectFlags {
+    match aspect {
+        TextureAspect::Color => vk::ImageAspectFlags::COLOR,
+        TextureAspect::Depth => vk::ImageAspectFlags::DEPTH,
+        TextureAspect::Stencil => vk::ImageAspectFlags::STENCIL,
+    }
+}
+
+#[inline(always)]
+pub(crate) const fn to_vk_image_view_type(it: TextureType) -> vk::ImageViewType {
+    match it {
+        TextureType::Type1D => vk::ImageViewType::TYPE_1D,
+        TextureType::Type2D => vk::ImageViewType::TYPE_2D,
+        TextureType::Type3D => vk::ImageViewType::TYPE_3D,
+    }
+}
+
 #[inline(always)]
 pub(crate) const fn to_gpu_allocator_memory_location(mu: MemoryUsage) -> MemoryLocation {
     match mu {
@@ -542,11 +722,47 @@ pub fn texture_usage_to_stage_access(
     }
 }
 
+/// Gets the image layout for a single texture usage. Returns `None` if the usage has more or
+/// less than one flag set.
 #[inline(always)]
-pub fn depth_store_op_to_layout(store_op: StoreOp) -> vk::ImageLayout {
-    match store_op {
-        StoreOp::DontCare => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-        StoreOp::Store => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-        StoreOp::None => vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+pub(crate) fn texture_usage_to_layout(usage: TextureUsage) -> Option<vk::ImageLayout> {
+    match usage {
+        TextureUsage::COLOR_ATTACHMENT => Some(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL),
+        TextureUsage::DEPTH_STENCIL_ATTACHMENT => {
+            Some(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+        }
+        TextureUsage::TRANSFER_DST => Some(vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+        TextureUsage::TRANSFER_SRC => Some(vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+        TextureUsage::STORAGE => Some(vk::ImageLayout::GENERAL),
+        TextureUsage::SAMPLED => Some(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+        _ => None,
+    }
+}
+
+#[inline(always)]
+/// Depth stencil attachments are only read only if neither aspect is written to.
+pub fn depth_store_op_to_layout(
+    depth_store_op: StoreOp,
+    stencil_store_op: StoreOp,
+) -> vk::ImageLayout {
+    match (depth_store_op, stencil_store_op) {
+        (StoreOp::None, StoreOp::None) => vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        _ => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+    }
+}
+
+/// Names a Vulkan object so it can be identified in graphics debuggers and validation messages.
+/// Does nothing if debugging is disabled.
+pub(crate) unsafe fn set_debug_name<T: vk::Handle>(
+    debug: Option<&ash::ext::debug_utils::Device>,
+    handle: T,
+    name: &str,
+) {
+    if let Some(debug) = debug {
+        let name = CString::new(name).unwrap();
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
+            .object_handle(handle)
+            .object_name(&name);
+        debug.set_debug_utils_object_name(&name_info).unwrap();
     }
 }
@@ -1,21 +1,131 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
 use super::fast_int_hasher::FIHashMap;
-use ash::vk;
+use api::context::PipelineCacheSaveError;
+use ash::{prelude::VkResult, vk};
+use crossbeam_channel::{Receiver, Sender};
+use rustc_hash::FxHashSet;
+
+use crate::{graphics_pipeline::GraphicsPipeline, render_pass::VkRenderPass};
+
+/// Size in bytes of the header at the start of serialized pipeline cache data.
+const HEADER_SIZE: usize = 32;
 
-#[derive(Default)]
 pub(crate) struct PipelineCache {
     /// Given a pipeline layout and render pass, produces a unique matching pipeline.
     pipelines: FIHashMap<vk::PipelineLayout, FIHashMap<vk::RenderPass, vk::Pipeline>>,
+    /// Driver cache every pipeline is created with.
+    cache: vk::PipelineCache,
+    /// Where the driver cache is saved. `None` if it isn't persisted.
+    path: Option<PathBuf>,
+    stats: PipelineCreationStats,
+    /// Layout and render pass pairs with pipelines being compiled in the background.
+    compiling: FxHashSet<(vk::PipelineLayout, vk::RenderPass)>,
+    compiled_send: Sender<CompiledPipeline>,
+    compiled_recv: Receiver<CompiledPipeline>,
+    /// Pipelines are compiled on their own threads so they can't stall command recording.
+    compile_pool: rayon::ThreadPool,
+}
+
+/// A graphics pipeline compiled in the background.
+pub(crate) struct CompiledPipeline {
+    pub layout: vk::PipelineLayout,
+    pub pass: vk::RenderPass,
+    pub pipeline: vk::Pipeline,
+    pub feedback: vk::PipelineCreationFeedback,
+}
+
+/// Identifies the device serialized pipeline cache data was created for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct PipelineCacheHeader {
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub uuid: [u8; vk::UUID_SIZE],
+}
+
+#[derive(Default)]
+struct PipelineCreationStats {
+    hits: u32,
+    hit_time: Duration,
+    misses: u32,
+    miss_time: Duration,
 }
 
 impl PipelineCache {
+    pub unsafe fn new(
+        device: &ash::Device,
+        header: PipelineCacheHeader,
+        path: Option<PathBuf>,
+    ) -> VkResult<Self> {
+        let initial_data = path
+            .as_deref()
+            .and_then(|path| load(path, &header))
+            .unwrap_or_default();
+
+        let create_info = vk::PipelineCacheCreateInfo::default().initial_data(&initial_data);
+        let cache = match device.create_pipeline_cache(&create_info, None) {
+            Ok(cache) => cache,
+            // The driver can still reject data with a valid header, so start fresh
+            Err(err) if !initial_data.is_empty() => {
+                ard_log::warn!("pipeline cache data was rejected ({err}), starting fresh");
+                device.create_pipeline_cache(&vk::PipelineCacheCreateInfo::default(), None)?
+            }
+            Err(err) => return Err(err),
+        };
+
+        let thread_count = std::thread::available_parallelism()
+            .map(|count| count.get() / 2)
+            .unwrap_or(1)
+            .max(1);
+        let compile_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(thread_count)
+            .thread_name(|idx| format!("pipeline_compiler_{idx}"))
+            .build()
+            .unwrap();
+
+        let (compiled_send, compiled_recv) = crossbeam_channel::unbounded();
+
+        Ok(Self {
+            pipelines: FIHashMap::default(),
+            cache,
+            path,
+            stats: PipelineCreationStats::default(),
+            compiling: FxHashSet::default(),
+            compiled_send,
+            compiled_recv,
+            compile_pool,
+        })
+    }
+
+    #[inline(always)]
+    pub fn cache(&self) -> vk::PipelineCache {
+        self.cache
+    }
+
+    /// Number of pipelines that exist or are being compiled for a layout.
     #[inline(always)]
     pub fn count(&self, layout: vk::PipelineLayout) -> usize {
+        let compiling = self.compiling.iter().filter(|(l, _)| *l == layout).count();
         match self.pipelines.get(&layout) {
-            Some(passes) => passes.len(),
-            None => 0,
+            Some(passes) => passes.len() + compiling,
+            None => compiling,
         }
     }
 
+    #[inline(always)]
+    pub fn is_compiling(&self, layout: vk::PipelineLayout) -> bool {
+        self.compiling.iter().any(|(l, _)| *l == layout)
+    }
+
+    /// Number of pipelines still being compiled in the background.
+    pub fn pending(&mut self) -> usize {
+        self.receive_compiled();
+        self.compiling.len()
+    }
+
     #[inline(always)]
     pub fn get(&self, layout: vk::PipelineLayout, pass: vk::RenderPass) -> Option<vk::Pipeline> {
         match self.pipelines.get(&layout) {
@@ -39,6 +149,130 @@ impl PipelineCache {
             .or_default() = pipeline;
     }
 
+    /// Compiles a pipeline for a layout and render pass on the compile pool. Does nothing if the
+    /// pipeline already exists or is being compiled.
+    pub fn spawn(
+        &mut self,
+        layout: vk::PipelineLayout,
+        pass: vk::RenderPass,
+        compile: impl FnOnce(vk::PipelineCache) -> (vk::Pipeline, vk::PipelineCreationFeedback)
+            + Send
+            + 'static,
+    ) {
+        if self.get(layout, pass).is_some() || !self.compiling.insert((layout, pass)) {
+            return;
+        }
+
+        let cache = self.cache;
+        let compiled = self.compiled_send.clone();
+        self.compile_pool.spawn(move || {
+            let (pipeline, feedback) = compile(cache);
+            let _ = compiled.send(CompiledPipeline {
+                layout,
+                pass,
+                pipeline,
+                feedback,
+            });
+        });
+    }
+
+    /// Begins compiling every variant for a render pass in the background.
+    pub unsafe fn precompile(
+        &mut self,
+        device: &ash::Device,
+        debug: Option<&ash::ext::debug_utils::Device>,
+        variants: &[&GraphicsPipeline],
+        render_pass: VkRenderPass,
+    ) {
+        for variant in variants {
+            variant.compile_async(device, self, debug, render_pass);
+        }
+    }
+
+    /// Moves pipelines that finished compiling in the background into the cache.
+    pub fn receive_compiled(&mut self) {
+        while let Ok(compiled) = self.compiled_recv.try_recv() {
+            self.finish_compile(compiled);
+        }
+    }
+
+    /// Blocks until the pipeline for a layout and render pass finishes compiling in the
+    /// background. Returns immediately if it isn't being compiled.
+    pub fn wait_compiled(&mut self, layout: vk::PipelineLayout, pass: vk::RenderPass) {
+        while self.compiling.contains(&(layout, pass)) {
+            let compiled = self.compiled_recv.recv().unwrap();
+            self.finish_compile(compiled);
+        }
+    }
+
+    /// Blocks until every pipeline being compiled in the background is finished.
+    pub fn wait_all_compiled(&mut self) {
+        while !self.compiling.is_empty() {
+            let compiled = self.compiled_recv.recv().unwrap();
+            self.finish_compile(compiled);
+        }
+    }
+
+    fn finish_compile(&mut self, compiled: CompiledPipeline) {
+        self.compiling.remove(&(compiled.layout, compiled.pass));
+        self.record(&compiled.feedback);
+        self.insert(compiled.layout, compiled.pass, compiled.pipeline);
+    }
+
+    /// Records creation feedback for a pipeline created with the driver cache.
+    pub fn record(&mut self, feedback: &vk::PipelineCreationFeedback) {
+        if !feedback
+            .flags
+            .contains(vk::PipelineCreationFeedbackFlags::VALID)
+        {
+            return;
+        }
+
+        let duration = Duration::from_nanos(feedback.duration);
+        if feedback
+            .flags
+            .contains(vk::PipelineCreationFeedbackFlags::APPLICATION_PIPELINE_CACHE_HIT)
+        {
+            self.stats.hits += 1;
+            self.stats.hit_time += duration;
+        } else {
+            self.stats.misses += 1;
+            self.stats.miss_time += duration;
+        }
+    }
+
+    /// Atomically writes the driver cache to disk, if it is persisted.
+    pub unsafe fn save(&self, device: &ash::Device) -> Result<(), PipelineCacheSaveError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let data = device
+            .get_pipeline_cache_data(self.cache)
+            .map_err(|err| PipelineCacheSaveError::Other(err.to_string()))?;
+
+        // Write to a temporary file first so a crash mid-write can't leave a truncated cache
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, &data)?;
+        std::fs::rename(&tmp_path, path)?;
+
+        let stats = &self.stats;
+        ard_log::info!(
+            "saved {} byte pipeline cache to {path:?}. {} hits (avg {:?}), {} misses (avg {:?})",
+            data.len(),
+            stats.hits,
+            stats.hit_time.checked_div(stats.hits).unwrap_or_default(),
+            stats.misses,
+            stats
+                .miss_time
+                .checked_div(stats.misses)
+                .unwrap_or_default(),
+        );
+
+        Ok(())
+    }
+
     pub unsafe fn release(&mut self, device: &ash::Device, layout: vk::PipelineLayout) {
         if let Some(mut passes) = self.pipelines.remove(&layout) {
             for (_, pipeline) in passes.drain() {
@@ -53,5 +287,88 @@ impl PipelineCache {
                 device.destroy_pipeline(pipeline, None);
             }
         }
+        device.destroy_pipeline_cache(self.cache, None);
+    }
+}
+
+/// Loads pipeline cache data from disk. Returns `None` if the file is missing or the data wasn't
+/// created for this device.
+fn load(path: &Path, header: &PipelineCacheHeader) -> Option<Vec<u8>> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            ard_log::warn!("unable to read pipeline cache at {path:?}: {err}");
+            return None;
+        }
+    };
+
+    if !is_compatible(&data, header) {
+        ard_log::warn!("pipeline cache at {path:?} is corrupt or from another device, ignoring");
+        return None;
+    }
+
+    ard_log::info!("loaded {} byte pipeline cache from {path:?}", data.len());
+    Some(data)
+}
+
+/// Checks that serialized pipeline cache data has a version one header matching the device.
+fn is_compatible(data: &[u8], header: &PipelineCacheHeader) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+
+    let read_u32 =
+        |offset: usize| u32::from_ne_bytes(data[offset..(offset + 4)].try_into().unwrap());
+
+    let header_size = read_u32(0) as usize;
+    let version = read_u32(4);
+
+    header_size >= HEADER_SIZE
+        && header_size <= data.len()
+        && version == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+        && read_u32(8) == header.vendor_id
+        && read_u32(12) == header.device_id
+        && data[16..HEADER_SIZE] == header.uuid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: PipelineCacheHeader = PipelineCacheHeader {
+        vendor_id: 0x10de,
+        device_id: 0x2684,
+        uuid: [7; vk::UUID_SIZE],
+    };
+
+    fn serialize(header: &PipelineCacheHeader) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_SIZE + 4);
+        data.extend_from_slice(&(HEADER_SIZE as u32).to_ne_bytes());
+        data.extend_from_slice(&1u32.to_ne_bytes());
+        data.extend_from_slice(&header.vendor_id.to_ne_bytes());
+        data.extend_from_slice(&header.device_id.to_ne_bytes());
+        data.extend_from_slice(&header.uuid);
+        data.extend_from_slice(&[0; 4]);
+        data
+    }
+
+    #[test]
+    fn matching_header_is_compatible() {
+        assert!(is_compatible(&serialize(&HEADER), &HEADER));
+    }
+
+    #[test]
+    fn mismatched_or_truncated_header_is_rejected() {
+        let other_device = PipelineCacheHeader {
+            uuid: [8; vk::UUID_SIZE],
+            ..HEADER
+        };
+        assert!(!is_compatible(&serialize(&other_device), &HEADER));
+        assert!(!is_compatible(
+            &serialize(&HEADER)[..HEADER_SIZE - 1],
+            &HEADER
+        ));
+        assert!(!is_compatible(&[], &HEADER));
     }
 }
@@ -1,11 +1,27 @@
+use api::types::PipelineStatistics;
 use ash::vk;
+use rustc_hash::FxHashMap;
 
 const QUERIES_PER_POOL: u32 = 128;
 
+/// Statistics collected by pipeline statistics queries. Results are written in bit order, so
+/// changing these requires updating `get_statistics`.
+const STATISTICS_FLAGS: vk::QueryPipelineStatisticFlags = vk::QueryPipelineStatisticFlags::from_raw(
+    vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES.as_raw()
+        | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS.as_raw()
+        | vk::QueryPipelineStatisticFlags::TASK_SHADER_INVOCATIONS_EXT.as_raw()
+        | vk::QueryPipelineStatisticFlags::MESH_SHADER_INVOCATIONS_EXT.as_raw(),
+);
+
 #[derive(Default)]
 pub struct Queries {
     accel_struct_compact_pools: Vec<vk::QueryPool>,
     accel_struct_compact_free: Vec<Query>,
+    accel_struct_compact_sizes: CompactSizes,
+    timestamp_pools: Vec<vk::QueryPool>,
+    timestamp_free: Vec<Query>,
+    statistics_pools: Vec<vk::QueryPool>,
+    statistics_free: Vec<Query>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -14,23 +30,82 @@ pub struct Query {
     pub idx: usize,
 }
 
+/// Compacted sizes that have been read back from their queries.
+///
+/// Once a size is read, its query is reset so it can be written again, so the size has to be
+/// kept here until the query is written again or freed.
+#[derive(Default)]
+struct CompactSizes(FxHashMap<Query, u64>);
+
+impl CompactSizes {
+    /// Gets the size for `query`, using `read` to read it from the query if it hasn't been read
+    /// yet. `read` returns `None` if the result isn't available.
+    ///
+    /// The second value is `true` if the size was just read.
+    fn poll(&mut self, query: Query, read: impl FnOnce() -> Option<u64>) -> (Option<u64>, bool) {
+        if let Some(size) = self.0.get(&query) {
+            return (Some(*size), false);
+        }
+
+        match read() {
+            Some(size) => {
+                self.0.insert(query, size);
+                (Some(size), true)
+            }
+            None => (None, false),
+        }
+    }
+
+    /// Forgets the size for `query` because it's about to be written again.
+    #[inline(always)]
+    fn invalidate(&mut self, query: Query) {
+        self.0.remove(&query);
+    }
+}
+
 impl Queries {
     #[inline(always)]
     pub fn accel_struct_pool(&self, idx: usize) -> vk::QueryPool {
         self.accel_struct_compact_pools[idx]
     }
 
-    pub unsafe fn get_accel_struct_compact(&self, device: &ash::Device, query: Query) -> u64 {
-        let mut res = [vk::DeviceSize::default()];
-        device
-            .get_query_pool_results(
-                self.accel_struct_compact_pools[query.pool],
+    /// Gets the compacted size written to a query without waiting for it. Returns `None` if the
+    /// query hasn't been written yet.
+    ///
+    /// The query is reset once the size is read, so it can be written again.
+    pub unsafe fn poll_accel_struct_compact(
+        &mut self,
+        device: &ash::Device,
+        query: Query,
+    ) -> Option<u64> {
+        let pool = self.accel_struct_compact_pools[query.pool];
+        let (size, just_read) = self.accel_struct_compact_sizes.poll(query, || {
+            // The second value is the availability of the result
+            let mut res = [[0u64; 2]];
+            match device.get_query_pool_results(
+                pool,
                 query.idx as u32,
                 &mut res,
-                vk::QueryResultFlags::empty(),
-            )
-            .unwrap();
-        res[0]
+                vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WITH_AVAILABILITY,
+            ) {
+                Ok(()) | Err(vk::Result::NOT_READY) => {}
+                Err(err) => panic!("unable to read compacted size: {err:?}"),
+            }
+            let [size, available] = res[0];
+            (available != 0).then_some(size)
+        });
+
+        if just_read {
+            device.reset_query_pool(pool, query.idx as u32, 1);
+        }
+
+        size
+    }
+
+    /// Must be called when a command that writes to the query is submitted.
+    #[inline(always)]
+    pub fn invalidate_accel_struct_compact(&mut self, query: Query) {
+        self.accel_struct_compact_sizes.invalidate(query);
     }
 
     pub unsafe fn allocate_accel_struct_compact(&mut self, device: &ash::Device) -> Query {
@@ -65,6 +140,7 @@ impl Queries {
     }
 
     pub unsafe fn free_accel_struct_compact(&mut self, device: &ash::Device, query: Query) {
+        self.accel_struct_compact_sizes.invalidate(query);
         device.reset_query_pool(
             self.accel_struct_compact_pools[query.pool],
             query.idx as u32,
@@ -73,9 +149,176 @@ impl Queries {
         self.accel_struct_compact_free.push(query);
     }
 
+    #[inline(always)]
+    pub(crate) fn timestamp_pool(&self, idx: usize) -> vk::QueryPool {
+        self.timestamp_pools[idx]
+    }
+
+    /// Gets the raw timestamp written to a query, in ticks.
+    pub(crate) unsafe fn get_timestamp(&self, device: &ash::Device, query: Query) -> u64 {
+        let mut res = [0u64];
+        device
+            .get_query_pool_results(
+                self.timestamp_pools[query.pool],
+                query.idx as u32,
+                &mut res,
+                vk::QueryResultFlags::TYPE_64,
+            )
+            .unwrap();
+        res[0]
+    }
+
+    pub(crate) unsafe fn allocate_timestamp(&mut self, device: &ash::Device) -> Query {
+        if let Some(free) = self.timestamp_free.pop() {
+            return free;
+        }
+
+        let create_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(QUERIES_PER_POOL);
+        let pool = device.create_query_pool(&create_info, None).unwrap();
+        device.reset_query_pool(pool, 0, QUERIES_PER_POOL);
+
+        let ret = Query {
+            pool: self.timestamp_pools.len(),
+            idx: 0,
+        };
+
+        (1..QUERIES_PER_POOL as usize).for_each(|i| {
+            self.timestamp_free.push(Query {
+                pool: self.timestamp_pools.len(),
+                idx: i,
+            })
+        });
+
+        self.timestamp_pools.push(pool);
+
+        ret
+    }
+
+    pub(crate) unsafe fn free_timestamp(&mut self, device: &ash::Device, query: Query) {
+        device.reset_query_pool(self.timestamp_pools[query.pool], query.idx as u32, 1);
+        self.timestamp_free.push(query);
+    }
+
+    #[inline(always)]
+    pub(crate) fn statistics_pool(&self, idx: usize) -> vk::QueryPool {
+        self.statistics_pools[idx]
+    }
+
+    pub(crate) unsafe fn get_statistics(
+        &self,
+        device: &ash::Device,
+        query: Query,
+    ) -> PipelineStatistics {
+        let mut res = [[0u64; 4]];
+        device
+            .get_query_pool_results(
+                self.statistics_pools[query.pool],
+                query.idx as u32,
+                &mut res,
+                vk::QueryResultFlags::TYPE_64,
+            )
+            .unwrap();
+        let [clipping_primitives, fragment_shader_invocations, task_shader_invocations, mesh_shader_invocations] =
+            res[0];
+        PipelineStatistics {
+            task_shader_invocations,
+            mesh_shader_invocations,
+            clipping_primitives,
+            fragment_shader_invocations,
+        }
+    }
+
+    pub(crate) unsafe fn allocate_statistics(&mut self, device: &ash::Device) -> Query {
+        if let Some(free) = self.statistics_free.pop() {
+            return free;
+        }
+
+        let create_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::PIPELINE_STATISTICS)
+            .query_count(QUERIES_PER_POOL)
+            .pipeline_statistics(STATISTICS_FLAGS);
+        let pool = device.create_query_pool(&create_info, None).unwrap();
+        device.reset_query_pool(pool, 0, QUERIES_PER_POOL);
+
+        let ret = Query {
+            pool: self.statistics_pools.len(),
+            idx: 0,
+        };
+
+        (1..QUERIES_PER_POOL as usize).for_each(|i| {
+            self.statistics_free.push(Query {
+                pool: self.statistics_pools.len(),
+                idx: i,
+            })
+        });
+
+        self.statistics_pools.push(pool);
+
+        ret
+    }
+
+    pub(crate) unsafe fn free_statistics(&mut self, device: &ash::Device, query: Query) {
+        device.reset_query_pool(self.statistics_pools[query.pool], query.idx as u32, 1);
+        self.statistics_free.push(query);
+    }
+
     pub unsafe fn release(&self, device: &ash::Device) {
-        self.accel_struct_compact_pools.iter().for_each(|pool| {
-            device.destroy_query_pool(*pool, None);
+        self.accel_struct_compact_pools
+            .iter()
+            .chain(self.timestamp_pools.iter())
+            .chain(self.statistics_pools.iter())
+            .for_each(|pool| {
+                device.destroy_query_pool(*pool, None);
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: Query = Query { pool: 0, idx: 3 };
+
+    #[test]
+    fn unavailable_size_is_not_cached() {
+        let mut sizes = CompactSizes::default();
+
+        assert_eq!(sizes.poll(QUERY, || None), (None, false));
+
+        // The query is read again until it's available
+        let mut reads = 0;
+        sizes.poll(QUERY, || {
+            reads += 1;
+            None
         });
+        assert_eq!(reads, 1);
+    }
+
+    #[test]
+    fn available_size_is_read_once() {
+        let mut sizes = CompactSizes::default();
+
+        assert_eq!(sizes.poll(QUERY, || Some(256)), (Some(256), true));
+        assert_eq!(
+            sizes.poll(QUERY, || panic!("size should not be read again")),
+            (Some(256), false)
+        );
+        assert_eq!(
+            sizes.poll(Query { pool: 1, idx: 3 }, || None),
+            (None, false)
+        );
+    }
+
+    #[test]
+    fn invalidated_size_is_read_again() {
+        let mut sizes = CompactSizes::default();
+        sizes.poll(QUERY, || Some(256));
+
+        sizes.invalidate(QUERY);
+
+        assert_eq!(sizes.poll(QUERY, || None), (None, false));
+        assert_eq!(sizes.poll(QUERY, || Some(128)), (Some(128), true));
     }
 }
@@ -1,19 +1,38 @@
 use api::{
     texture::Sampler,
-    types::{AnisotropyLevel, Filter},
+    types::{AnisotropyLevel, BorderColor, Filter},
 };
 use ash::vk;
 use rustc_hash::FxHashMap;
 
-#[derive(Default)]
 pub(crate) struct SamplerCache {
     samplers: FxHashMap<Sampler, vk::Sampler>,
+    /// Highest anisotropy supported by the device. Higher levels are clamped to this.
+    max_anisotropy: f32,
+    /// Level reported by `Context::default_anisotropy`.
+    pub default_anisotropy: Option<AnisotropyLevel>,
 }
 
 impl SamplerCache {
-    pub unsafe fn get(&mut self, device: &ash::Device, sampler: Sampler) -> vk::Sampler {
+    pub fn new(max_anisotropy: f32) -> Self {
+        Self {
+            samplers: FxHashMap::default(),
+            max_anisotropy,
+            default_anisotropy: None,
+        }
+    }
+
+    pub unsafe fn get(
+        &mut self,
+        device: &ash::Device,
+        debug: Option<&ash::ext::debug_utils::Device>,
+        sampler: Sampler,
+    ) -> vk::Sampler {
         *self.samplers.entry(sampler).or_insert_with(|| {
-            let create_info = vk::SamplerCreateInfo::default()
+            let mut reduction_info = vk::SamplerReductionModeCreateInfo::default();
+            let mut border_color_info = vk::SamplerCustomBorderColorCreateInfoEXT::default();
+
+            let mut create_info = vk::SamplerCreateInfo::default()
                 .min_filter(crate::util::to_vk_filter(sampler.min_filter))
                 .mag_filter(crate::util::to_vk_filter(sampler.mag_filter))
                 .mipmap_mode(match sampler.mipmap_filter {
@@ -25,13 +44,7 @@ impl SamplerCache {
                 .address_mode_w(crate::util::to_vk_address_mode(sampler.address_w))
                 .anisotropy_enable(sampler.anisotropy.is_some())
                 .max_anisotropy(match sampler.anisotropy {
-                    Some(anisotropy) => match anisotropy {
-                        AnisotropyLevel::X1 => 1.0,
-                        AnisotropyLevel::X2 => 2.0,
-                        AnisotropyLevel::X4 => 4.0,
-                        AnisotropyLevel::X8 => 8.0,
-                        AnisotropyLevel::X16 => 16.0,
-                    },
+                    Some(anisotropy) => anisotropy.samples().min(self.max_anisotropy),
                     None => 0.0,
                 })
                 .compare_enable(sampler.compare.is_some())
@@ -50,7 +63,40 @@ impl SamplerCache {
                 })
                 .unnormalized_coordinates(sampler.unnormalize_coords);
 
-            device.create_sampler(&create_info, None).unwrap()
+            if let Some(reduction_mode) = sampler.reduction_mode {
+                reduction_info = reduction_info
+                    .reduction_mode(crate::util::to_vk_reduction_mode(reduction_mode));
+                create_info = create_info.push_next(&mut reduction_info);
+            }
+
+            // Custom colors are provided without a format, so they apply to any texture
+            let custom_color = match sampler.border_color {
+                Some(BorderColor::CustomFloat(color)) => Some(vk::ClearColorValue {
+                    float32: color.map(|c| c.into_inner()),
+                }),
+                Some(BorderColor::CustomInt(color)) => Some(vk::ClearColorValue { int32: color }),
+                _ => None,
+            };
+            if let Some(custom_color) = custom_color {
+                border_color_info = border_color_info
+                    .custom_border_color(custom_color)
+                    .format(vk::Format::UNDEFINED);
+                create_info = create_info.push_next(&mut border_color_info);
+            }
+
+            let handle = device.create_sampler(&create_info, None).unwrap();
+
+            // Samplers are shared, so they're named after their filtering and addressing
+            crate::util::set_debug_name(
+                debug,
+                handle,
+                &format!(
+                    "sampler_{:?}_{:?}_{:?}",
+                    sampler.min_filter, sampler.mag_filter, sampler.address_u
+                ),
+            );
+
+            handle
         })
     }
 
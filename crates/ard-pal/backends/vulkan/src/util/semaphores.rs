@@ -44,6 +44,21 @@ impl SemaphoreTracker {
         }
     }
 
+    /// `true` if no semaphores have been registered.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.wait_semaphores.is_empty() && self.signal_semaphores.is_empty()
+    }
+
+    /// Moves every registered wait into a new tracker, leaving signals behind.
+    #[inline(always)]
+    pub fn take_waits(&mut self) -> SemaphoreTracker {
+        SemaphoreTracker {
+            wait_semaphores: std::mem::take(&mut self.wait_semaphores),
+            signal_semaphores: FIHashMap::default(),
+        }
+    }
+
     #[inline(always)]
     pub fn finish(self) -> OutSemaphores {
         OutSemaphores {
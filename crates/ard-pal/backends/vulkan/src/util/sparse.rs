@@ -0,0 +1,399 @@
+use api::{context::MemoryCategory, texture::SparsePageRegion};
+use ash::vk;
+use gpu_allocator::{
+    vulkan::{Allocation, AllocationCreateDesc, AllocationScheme},
+    MemoryLocation,
+};
+use rustc_hash::FxHashMap;
+
+use super::memory::TrackedAllocator;
+
+/// Size in bytes of a standard sparse block.
+pub(crate) const SPARSE_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Tracks the memory bound to every resident page of a sparse image.
+pub(crate) struct SparsePages {
+    image: vk::Image,
+    aspect_mask: vk::ImageAspectFlags,
+    /// Size of the image at mip level 0.
+    extent: vk::Extent3D,
+    /// Dimensions in texels of a single page.
+    granularity: vk::Extent3D,
+    /// Requirements for the memory of a single page.
+    page_requirements: vk::MemoryRequirements,
+    mip_tail: MipTail,
+    /// Memory bound to each resident page.
+    pages: FxHashMap<PageKey, Allocation>,
+    /// Memory bound to each resident mip tail, keyed by array element. Only key `0` is used if
+    /// the image has a single mip tail.
+    mip_tails: FxHashMap<u32, Allocation>,
+}
+
+/// The smallest mip levels of a sparse image are packed together and must be bound as a whole.
+struct MipTail {
+    first_lod: u32,
+    size: u64,
+    offset: u64,
+    stride: u64,
+    single: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct PageKey {
+    array_element: u32,
+    mip_level: u32,
+    /// Coordinates of the page in units of pages.
+    page: (u32, u32, u32),
+}
+
+/// Bind operations gathered from the commands of a single submission.
+#[derive(Default)]
+pub(crate) struct SparseBinds {
+    pub image: Vec<(vk::Image, Vec<vk::SparseImageMemoryBind>)>,
+    pub opaque: Vec<(vk::Image, Vec<vk::SparseMemoryBind>)>,
+    /// Memory that was unbound and can be freed once the binds are complete.
+    pub freed: Vec<Allocation>,
+}
+
+impl SparsePages {
+    /// Returns `None` if the image has no sparse requirements for the provided aspect.
+    pub(crate) unsafe fn new(
+        device: &ash::Device,
+        image: vk::Image,
+        aspect_mask: vk::ImageAspectFlags,
+        extent: vk::Extent3D,
+    ) -> Option<Self> {
+        let mem_reqs = device.get_image_memory_requirements(image);
+        let sparse_reqs = device.get_image_sparse_memory_requirements(image);
+        let reqs = sparse_reqs
+            .iter()
+            .find(|reqs| aspect_mask.contains(reqs.format_properties.aspect_mask))?;
+
+        Some(Self {
+            image,
+            aspect_mask,
+            extent,
+            granularity: reqs.format_properties.image_granularity,
+            page_requirements: vk::MemoryRequirements {
+                size: mem_reqs.alignment,
+                alignment: mem_reqs.alignment,
+                memory_type_bits: mem_reqs.memory_type_bits,
+            },
+            mip_tail: MipTail {
+                first_lod: reqs.image_mip_tail_first_lod,
+                size: reqs.image_mip_tail_size,
+                offset: reqs.image_mip_tail_offset,
+                stride: reqs.image_mip_tail_stride,
+                single: reqs
+                    .format_properties
+                    .flags
+                    .contains(vk::SparseImageFormatFlags::SINGLE_MIPTAIL),
+            },
+            pages: FxHashMap::default(),
+            mip_tails: FxHashMap::default(),
+        })
+    }
+
+    /// Binds memory to every page touched by `region` that isn't already resident. Regions
+    /// within the mip tail bind the entire mip tail.
+    pub(crate) unsafe fn bind(
+        &mut self,
+        allocator: &mut TrackedAllocator,
+        region: &SparsePageRegion,
+        out: &mut SparseBinds,
+    ) {
+        let array_element = region.array_element as u32;
+        let mip_level = region.mip_level as u32;
+
+        if mip_level >= self.mip_tail.first_lod {
+            let key = if self.mip_tail.single {
+                0
+            } else {
+                array_element
+            };
+            if self.mip_tails.contains_key(&key) {
+                return;
+            }
+
+            let requirements = vk::MemoryRequirements {
+                size: self.mip_tail.size,
+                ..self.page_requirements
+            };
+            let allocation = Self::allocate(allocator, requirements);
+            opaque_binds(out, self.image).push(vk::SparseMemoryBind {
+                resource_offset: self.mip_tail_offset(key),
+                size: self.mip_tail.size,
+                memory: allocation.memory(),
+                memory_offset: allocation.offset(),
+                flags: vk::SparseMemoryBindFlags::empty(),
+            });
+            self.mip_tails.insert(key, allocation);
+            return;
+        }
+
+        for (key, offset, extent) in touched_pages(self.extent, self.granularity, region) {
+            if self.pages.contains_key(&key) {
+                continue;
+            }
+
+            let allocation = Self::allocate(allocator, self.page_requirements);
+            image_binds(out, self.image).push(vk::SparseImageMemoryBind {
+                subresource: self.subresource(&key),
+                offset,
+                extent,
+                memory: allocation.memory(),
+                memory_offset: allocation.offset(),
+                flags: vk::SparseMemoryBindFlags::empty(),
+            });
+            self.pages.insert(key, allocation);
+        }
+    }
+
+    /// Unbinds memory from every resident page touched by `region`. Regions within the mip tail
+    /// unbind the entire mip tail.
+    pub(crate) fn unbind(&mut self, region: &SparsePageRegion, out: &mut SparseBinds) {
+        let array_element = region.array_element as u32;
+        let mip_level = region.mip_level as u32;
+
+        if mip_level >= self.mip_tail.first_lod {
+            let key = if self.mip_tail.single {
+                0
+            } else {
+                array_element
+            };
+            if let Some(allocation) = self.mip_tails.remove(&key) {
+                opaque_binds(out, self.image).push(vk::SparseMemoryBind {
+                    resource_offset: self.mip_tail_offset(key),
+                    size: self.mip_tail.size,
+                    memory: vk::DeviceMemory::null(),
+                    memory_offset: 0,
+                    flags: vk::SparseMemoryBindFlags::empty(),
+                });
+                out.freed.push(allocation);
+            }
+            return;
+        }
+
+        for (key, offset, extent) in touched_pages(self.extent, self.granularity, region) {
+            if let Some(allocation) = self.pages.remove(&key) {
+                image_binds(out, self.image).push(vk::SparseImageMemoryBind {
+                    subresource: self.subresource(&key),
+                    offset,
+                    extent,
+                    memory: vk::DeviceMemory::null(),
+                    memory_offset: 0,
+                    flags: vk::SparseMemoryBindFlags::empty(),
+                });
+                out.freed.push(allocation);
+            }
+        }
+    }
+
+    /// Takes every allocation bound to the image.
+    pub(crate) fn release(&mut self) -> Vec<Allocation> {
+        self.pages
+            .drain()
+            .map(|(_, allocation)| allocation)
+            .chain(self.mip_tails.drain().map(|(_, allocation)| allocation))
+            .collect()
+    }
+
+    #[inline(always)]
+    fn mip_tail_offset(&self, key: u32) -> u64 {
+        self.mip_tail.offset + (key as u64 * self.mip_tail.stride)
+    }
+
+    #[inline(always)]
+    fn subresource(&self, key: &PageKey) -> vk::ImageSubresource {
+        vk::ImageSubresource {
+            aspect_mask: self.aspect_mask,
+            mip_level: key.mip_level,
+            array_layer: key.array_element,
+        }
+    }
+
+    fn allocate(
+        allocator: &mut TrackedAllocator,
+        requirements: vk::MemoryRequirements,
+    ) -> Allocation {
+        allocator
+            .allocate(
+                &AllocationCreateDesc {
+                    name: "sparse_page",
+                    requirements,
+                    location: MemoryLocation::GpuOnly,
+                    linear: false,
+                    allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+                },
+                MemoryCategory::Texture,
+            )
+            .expect("unable to allocate sparse page")
+    }
+}
+
+/// Lists every page touched by `region` along with the texel offset and extent of the page
+/// clamped to the size of the mip level.
+fn touched_pages(
+    extent: vk::Extent3D,
+    granularity: vk::Extent3D,
+    region: &SparsePageRegion,
+) -> Vec<(PageKey, vk::Offset3D, vk::Extent3D)> {
+    let mip = region.mip_level as u32;
+    let mip_extent = (
+        (extent.width >> mip).max(1),
+        (extent.height >> mip).max(1),
+        (extent.depth >> mip).max(1),
+    );
+
+    // Convert the region into a range of pages on each axis
+    let axis = |offset: u32, len: u32, size: u32, page: u32| {
+        let end = offset.saturating_add(len).min(size);
+        if offset >= end {
+            0..0
+        } else {
+            (offset / page)..end.div_ceil(page)
+        }
+    };
+    let x = axis(
+        region.offset.0,
+        region.extent.0,
+        mip_extent.0,
+        granularity.width,
+    );
+    let y = axis(
+        region.offset.1,
+        region.extent.1,
+        mip_extent.1,
+        granularity.height,
+    );
+    let z = axis(
+        region.offset.2,
+        region.extent.2,
+        mip_extent.2,
+        granularity.depth,
+    );
+
+    let mut pages = Vec::with_capacity(x.len() * y.len() * z.len());
+    for pz in z {
+        for py in y.clone() {
+            for px in x.clone() {
+                let offset = (
+                    px * granularity.width,
+                    py * granularity.height,
+                    pz * granularity.depth,
+                );
+                pages.push((
+                    PageKey {
+                        array_element: region.array_element as u32,
+                        mip_level: mip,
+                        page: (px, py, pz),
+                    },
+                    vk::Offset3D {
+                        x: offset.0 as i32,
+                        y: offset.1 as i32,
+                        z: offset.2 as i32,
+                    },
+                    vk::Extent3D {
+                        width: granularity.width.min(mip_extent.0 - offset.0),
+                        height: granularity.height.min(mip_extent.1 - offset.1),
+                        depth: granularity.depth.min(mip_extent.2 - offset.2),
+                    },
+                ));
+            }
+        }
+    }
+
+    pages
+}
+
+fn image_binds(out: &mut SparseBinds, image: vk::Image) -> &mut Vec<vk::SparseImageMemoryBind> {
+    if out.image.last().map(|(other, _)| *other) != Some(image) {
+        out.image.push((image, Vec::default()));
+    }
+    &mut out.image.last_mut().unwrap().1
+}
+
+fn opaque_binds(out: &mut SparseBinds, image: vk::Image) -> &mut Vec<vk::SparseMemoryBind> {
+    if out.opaque.last().map(|(other, _)| *other) != Some(image) {
+        out.opaque.push((image, Vec::default()));
+    }
+    &mut out.opaque.last_mut().unwrap().1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vk::Extent3D = vk::Extent3D {
+        width: 1000,
+        height: 600,
+        depth: 1,
+    };
+
+    const GRANULARITY: vk::Extent3D = vk::Extent3D {
+        width: 128,
+        height: 128,
+        depth: 1,
+    };
+
+    #[test]
+    fn regions_expand_to_page_bounds() {
+        let pages = touched_pages(
+            EXTENT,
+            GRANULARITY,
+            &SparsePageRegion {
+                array_element: 0,
+                mip_level: 0,
+                offset: (100, 100, 0),
+                extent: (200, 20, 1),
+            },
+        );
+
+        let coords: Vec<_> = pages.iter().map(|(key, _, _)| key.page).collect();
+        assert_eq!(coords, vec![(0, 0, 0), (1, 0, 0), (2, 0, 0)]);
+        assert_eq!(pages[1].1, vk::Offset3D { x: 128, y: 0, z: 0 });
+    }
+
+    #[test]
+    fn edge_pages_are_clamped_to_mip_size() {
+        // Mip 1 is 500x300
+        let pages = touched_pages(
+            EXTENT,
+            GRANULARITY,
+            &SparsePageRegion {
+                array_element: 0,
+                mip_level: 1,
+                offset: (0, 0, 0),
+                extent: (u32::MAX / 2, u32::MAX / 2, 1),
+            },
+        );
+
+        assert_eq!(pages.len(), 4 * 3);
+        let (_, offset, extent) = pages.last().unwrap();
+        assert_eq!(
+            *offset,
+            vk::Offset3D {
+                x: 384,
+                y: 256,
+                z: 0
+            }
+        );
+        assert_eq!(extent.width, 500 - 384);
+        assert_eq!(extent.height, 300 - 256);
+    }
+
+    #[test]
+    fn empty_regions_touch_nothing() {
+        let pages = touched_pages(
+            EXTENT,
+            GRANULARITY,
+            &SparsePageRegion {
+                array_element: 0,
+                mip_level: 0,
+                offset: (EXTENT.width, 0, 0),
+                extent: (64, 64, 1),
+            },
+        );
+        assert!(pages.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+use std::ptr::NonNull;
+
+use api::{
+    buffer::{BufferCreateError, TRANSIENT_BUFFER_USAGE},
+    context::MemoryCategory,
+    types::QueueTypes,
+};
+use ash::vk;
+use gpu_allocator::{
+    vulkan::{Allocation, AllocationCreateDesc, AllocationScheme},
+    MemoryLocation,
+};
+
+use crate::QueueFamilyIndices;
+
+use super::memory::TrackedAllocator;
+
+/// Size of the blocks transient buffers are suballocated from. Requests larger than this get a
+/// block of their own.
+pub(crate) const TRANSIENT_BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Backing memory for transient buffers.
+#[derive(Default)]
+pub(crate) struct TransientBuffers {
+    arena: TransientArena,
+    blocks: Vec<TransientBlock>,
+}
+
+/// A suballocation made from a transient block.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct TransientAllocation {
+    pub block: usize,
+    pub buffer: vk::Buffer,
+    pub offset: u64,
+}
+
+struct TransientBlock {
+    buffer: vk::Buffer,
+    allocation: Allocation,
+}
+
+/// Bookkeeping for linear suballocation out of a set of blocks. Blocks are never freed while the
+/// arena is alive. Instead, once every suballocation in a block has been reclaimed, the block is
+/// rewound and reused.
+#[derive(Default)]
+struct TransientArena {
+    blocks: Vec<BlockState>,
+    /// The block currently being suballocated from.
+    active: Option<usize>,
+}
+
+struct BlockState {
+    size: u64,
+    /// Offset of the first free byte in the block.
+    head: u64,
+    /// Number of suballocations that have not been reclaimed.
+    live: usize,
+}
+
+impl TransientBuffers {
+    pub(crate) unsafe fn allocate(
+        &mut self,
+        device: &ash::Device,
+        qfi: &QueueFamilyIndices,
+        allocator: &mut TrackedAllocator,
+        size: u64,
+        alignment: u64,
+    ) -> Result<TransientAllocation, BufferCreateError> {
+        let (block, offset) = match self.arena.allocate(size, alignment) {
+            Some(res) => res,
+            None => {
+                let block_size = size.max(TRANSIENT_BLOCK_SIZE);
+                self.blocks
+                    .push(TransientBlock::new(device, qfi, allocator, block_size)?);
+                self.arena.add_block(block_size);
+                self.arena.allocate(size, alignment).unwrap()
+            }
+        };
+
+        Ok(TransientAllocation {
+            block,
+            buffer: self.blocks[block].buffer,
+            offset,
+        })
+    }
+
+    /// Reclaims a suballocation. Must only be called once the GPU is done with it.
+    #[inline(always)]
+    pub(crate) fn free(&mut self, block: usize) {
+        self.arena.free(block);
+    }
+
+    #[inline(always)]
+    pub(crate) fn mapped_ptr(&self, block: usize) -> NonNull<u8> {
+        self.blocks[block]
+            .allocation
+            .mapped_ptr()
+            .unwrap()
+            .cast::<u8>()
+    }
+
+    pub(crate) unsafe fn release(
+        &mut self,
+        device: &ash::Device,
+        allocator: &mut TrackedAllocator,
+    ) {
+        for block in self.blocks.drain(..) {
+            device.destroy_buffer(block.buffer, None);
+            allocator.free(block.allocation).unwrap();
+        }
+        self.arena = TransientArena::default();
+    }
+}
+
+impl TransientBlock {
+    unsafe fn new(
+        device: &ash::Device,
+        qfi: &QueueFamilyIndices,
+        allocator: &mut TrackedAllocator,
+        size: u64,
+    ) -> Result<Self, BufferCreateError> {
+        // Blocks are shared by every queue so transient buffers never need ownership transfers
+        let qfi = qfi.queue_types_to_indices(QueueTypes::NON_PRESENT);
+        let create_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(crate::util::to_vk_buffer_usage(TRANSIENT_BUFFER_USAGE))
+            .sharing_mode(if qfi.len() == 1 {
+                vk::SharingMode::EXCLUSIVE
+            } else {
+                vk::SharingMode::CONCURRENT
+            })
+            .queue_family_indices(&qfi);
+        let buffer = match device.create_buffer(&create_info, None) {
+            Ok(buffer) => buffer,
+            Err(err) => return Err(BufferCreateError::Other(err.to_string())),
+        };
+
+        let request = AllocationCreateDesc {
+            name: "transient_block",
+            allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+            requirements: device.get_buffer_memory_requirements(buffer),
+            location: MemoryLocation::CpuToGpu,
+            linear: true,
+        };
+        let allocation = match allocator.allocate(&request, MemoryCategory::Buffer) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                device.destroy_buffer(buffer, None);
+                return Err(err.into());
+            }
+        };
+
+        if let Err(err) =
+            device.bind_buffer_memory(buffer, allocation.memory(), allocation.offset())
+        {
+            allocator.free(allocation).unwrap();
+            device.destroy_buffer(buffer, None);
+            return Err(BufferCreateError::Other(err.to_string()));
+        }
+
+        Ok(Self { buffer, allocation })
+    }
+}
+
+impl TransientArena {
+    /// Suballocates `size` bytes from the active block, or from a block that has been fully
+    /// reclaimed. Returns the block index and the offset within the block, or `None` if a new
+    /// block of at least `size` bytes must be added.
+    fn allocate(&mut self, size: u64, alignment: u64) -> Option<(usize, u64)> {
+        if let Some(active) = self.active {
+            if let Some(offset) = self.blocks[active].bump(size, alignment) {
+                return Some((active, offset));
+            }
+        }
+
+        for (i, block) in self.blocks.iter_mut().enumerate() {
+            if block.live != 0 {
+                continue;
+            }
+
+            if let Some(offset) = block.bump(size, alignment) {
+                self.active = Some(i);
+                return Some((i, offset));
+            }
+        }
+
+        None
+    }
+
+    /// Adds a new block and makes it the active block.
+    fn add_block(&mut self, size: u64) {
+        self.blocks.push(BlockState {
+            size,
+            head: 0,
+            live: 0,
+        });
+        self.active = Some(self.blocks.len() - 1);
+    }
+
+    fn free(&mut self, block: usize) {
+        let block = &mut self.blocks[block];
+        block.live -= 1;
+        if block.live == 0 {
+            block.head = 0;
+        }
+    }
+}
+
+impl BlockState {
+    #[inline(always)]
+    fn bump(&mut self, size: u64, alignment: u64) -> Option<u64> {
+        let offset = self.head.next_multiple_of(alignment);
+        if offset + size > self.size {
+            return None;
+        }
+
+        self.head = offset + size;
+        self.live += 1;
+        Some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    #[test]
+    fn transient_memory_is_stable() {
+        const FRAMES_IN_FLIGHT: usize = 2;
+        const ALLOCATIONS_PER_FRAME: usize = 4096;
+
+        let mut arena = TransientArena::default();
+        let mut in_flight = VecDeque::default();
+        let mut block_counts = Vec::default();
+
+        for frame in 0..300 {
+            let mut allocations = Vec::with_capacity(ALLOCATIONS_PER_FRAME);
+            for i in 0..ALLOCATIONS_PER_FRAME {
+                // Vary the sizes so blocks don't fill up evenly
+                let size = 64 + ((frame * 31 + i * 17) % 2048) as u64;
+                let (block, offset) = match arena.allocate(size, 64) {
+                    Some(res) => res,
+                    None => {
+                        arena.add_block(size.max(TRANSIENT_BLOCK_SIZE));
+                        arena.allocate(size, 64).unwrap()
+                    }
+                };
+                assert_eq!(offset % 64, 0);
+                allocations.push(block);
+            }
+            in_flight.push_back(allocations);
+
+            // Reclaim the allocations of the oldest frame once it's "complete"
+            if in_flight.len() > FRAMES_IN_FLIGHT {
+                for block in in_flight.pop_front().unwrap() {
+                    arena.free(block);
+                }
+            }
+
+            block_counts.push(arena.blocks.len());
+        }
+
+        // Memory usage should stop growing once the first few frames have been reclaimed
+        let settled = block_counts[FRAMES_IN_FLIGHT + 1];
+        assert!(block_counts.iter().all(|count| *count <= settled));
+    }
+
+    #[test]
+    fn suballocations_do_not_overlap() {
+        let mut arena = TransientArena::default();
+        arena.add_block(1024);
+
+        let mut ranges = Vec::default();
+        while let Some((block, offset)) = arena.allocate(100, 16) {
+            assert_eq!(block, 0);
+            ranges.push(offset..(offset + 100));
+        }
+
+        assert!(ranges.windows(2).all(|w| w[0].end <= w[1].start));
+        assert!(ranges.last().unwrap().end <= 1024);
+    }
+
+    #[test]
+    fn reclaimed_blocks_are_reused() {
+        let mut arena = TransientArena::default();
+        arena.add_block(256);
+        let (first, _) = arena.allocate(256, 1).unwrap();
+        assert!(arena.allocate(1, 1).is_none());
+
+        arena.free(first);
+        assert_eq!(arena.allocate(256, 1), Some((first, 0)));
+    }
+}
@@ -1,5 +1,6 @@
 use api::types::{QueueType, SharingMode};
 use ash::vk;
+use rustc_hash::FxHashMap;
 
 use super::id_gen::ResourceId;
 
@@ -10,6 +11,32 @@ pub(crate) struct GlobalResourceUsage {
     buffers: Vec<Vec<InternalGlobalBufferUsage>>,
     // First dim is image ID. Second is array element. Third is mip level.
     images: Vec<Vec<Vec<InternalGlobalImageUsage>>>,
+    /// Entries as they were before being first touched since the journal began.
+    journal: Option<Box<JournalState>>,
+}
+
+#[derive(Default)]
+struct JournalState {
+    buffers: FxHashMap<(ResourceId, u32), InternalGlobalBufferUsage>,
+    images: FxHashMap<(ResourceId, u32, u32), InternalGlobalImageUsage>,
+    sets: FxHashMap<ResourceId, GlobalSetUsage>,
+}
+
+/// How a submission changed the usage of every resource it touched, so the same changes can be
+/// applied again without inspecting its commands.
+pub(crate) struct UsageJournal {
+    queue: QueueType,
+    is_async: bool,
+    timeline_value: u64,
+    buffers: Vec<JournalEntry<(ResourceId, u32), InternalGlobalBufferUsage>>,
+    images: Vec<JournalEntry<(ResourceId, u32, u32), InternalGlobalImageUsage>>,
+    sets: Vec<JournalEntry<ResourceId, GlobalSetUsage>>,
+}
+
+struct JournalEntry<K, V> {
+    key: K,
+    before: V,
+    after: V,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -82,6 +109,17 @@ struct InternalGlobalImageUsage {
     read_command: Option<usize>,
     read_sub_resource: SubResourceUsage,
     layout: vk::ImageLayout,
+    /// Set when ownership of the image has been released but not yet acquired.
+    released: Option<ReleasedImage>,
+}
+
+/// A queue family ownership release that is waiting for the matching acquire.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct ReleasedImage {
+    /// The queue the image was released to.
+    queue: QueueType,
+    /// Layout of the image before the release. The acquire must transition from this layout.
+    old_layout: vk::ImageLayout,
 }
 
 #[derive(Copy, Clone)]
@@ -120,6 +158,48 @@ impl GlobalResourceUsage {
         })
     }
 
+    #[inline(always)]
+    pub fn get_image_queue_usage(&self, region: &ImageRegion) -> Option<QueueUsage> {
+        let queue_usage = self
+            .images
+            .get(region.id.as_idx())
+            .and_then(|array_elems| array_elems.get(region.array_elem as usize))
+            .and_then(|mips| mips.get(region.base_mip_level as usize))
+            .and_then(|usage| usage.queue.as_ref());
+
+        queue_usage.map(|q| QueueUsage {
+            queue: q.queue,
+            timeline_value: q.timeline_value,
+            command_idx: usize::MAX,
+            is_async: q.is_async,
+        })
+    }
+
+    /// Gets the most recent command to read or write the buffer region. The command index is
+    /// only meaningful if the usage is from the current submission.
+    #[inline(always)]
+    pub fn last_buffer_use(&self, region: &BufferRegion) -> Option<QueueUsage> {
+        let usage = self
+            .buffers
+            .get(region.id.as_idx())
+            .and_then(|array_elems| array_elems.get(region.array_elem as usize))?;
+        let queue = usage.queue?;
+        Some(last_use(queue, usage.read_command, usage.write_command))
+    }
+
+    /// Gets the most recent command to read or write the base mip of the image region. See
+    /// [`last_buffer_use`](GlobalResourceUsage::last_buffer_use).
+    #[inline(always)]
+    pub fn last_image_use(&self, region: &ImageRegion) -> Option<QueueUsage> {
+        let usage = self
+            .images
+            .get(region.id.as_idx())
+            .and_then(|array_elems| array_elems.get(region.array_elem as usize))
+            .and_then(|mips| mips.get(region.base_mip_level as usize))?;
+        let queue = usage.queue?;
+        Some(last_use(queue, usage.read_command, usage.write_command))
+    }
+
     #[inline(always)]
     pub fn use_buffer(
         &mut self,
@@ -202,9 +282,23 @@ impl GlobalResourceUsage {
         else {
             let idx = old_usage.write_command.unwrap_or(usize::MAX);
 
+            // Reads since the last write can come from different stages (e.g. a TLAS traced
+            // against in a fragment shader and then in a compute shader), so the next write must
+            // wait on all of them.
+            let reads_since_write = match (old_usage.read_command, old_usage.write_command) {
+                (Some(r), Some(w)) => r > w,
+                (Some(_), None) => true,
+                _ => false,
+            };
+
             old_usage.queue = new_usage.queue.map(|queue| queue.into());
             old_usage.read_command = new_usage.queue.map(|queue| queue.command_idx);
-            old_usage.read_sub_resource = new_usage.sub_resource;
+            if reads_since_write {
+                old_usage.read_sub_resource.access |= new_usage.sub_resource.access;
+                old_usage.read_sub_resource.stage |= new_usage.sub_resource.stage;
+            } else {
+                old_usage.read_sub_resource = new_usage.sub_resource;
+            }
 
             (old_usage.write_sub_resource, idx)
         };
@@ -232,6 +326,7 @@ impl GlobalResourceUsage {
             .iter_mut()
             .zip(old)
             .for_each(|(out, old_usage)| {
+                let released = old_usage.released.take();
                 let needs_layout_transition = new_usage.layout != vk::ImageLayout::UNDEFINED
                     && new_usage.layout != old_usage.layout;
 
@@ -256,7 +351,12 @@ impl GlobalResourceUsage {
                             is_async: old.is_async,
                         }),
                         sub_resource: SubResourceUsage::default(),
-                        layout: old_usage.layout,
+                        // NOTE: The acquire half of an ownership transfer must perform the same
+                        // layout transition as the release.
+                        layout: match released {
+                            Some(released) => released.old_layout,
+                            None => old_usage.layout,
+                        },
                     };
 
                     // Update the old usage with the new values
@@ -347,6 +447,44 @@ impl GlobalResourceUsage {
             });
     }
 
+    /// Marks the image region as released to `new_queue`. The layout of the region becomes
+    /// `new_layout` once the matching acquire is performed.
+    #[inline(always)]
+    pub fn release_image(
+        &mut self,
+        region: &ImageRegion,
+        new_queue: QueueType,
+        new_layout: vk::ImageLayout,
+    ) {
+        for usage in self.get_image_entries(region) {
+            usage.released = Some(ReleasedImage {
+                queue: new_queue,
+                old_layout: usage.layout,
+            });
+            usage.layout = new_layout;
+        }
+    }
+
+    /// Finds the queue that owns the image region if it isn't `queue`. A region that has been
+    /// released is owned by the queue it was released to.
+    pub fn foreign_image_owner(&self, region: &ImageRegion, queue: QueueType) -> Option<QueueType> {
+        let mips = self
+            .images
+            .get(region.id.as_idx())
+            .and_then(|array_elems| array_elems.get(region.array_elem as usize))?;
+        let base_mip = region.base_mip_level as usize;
+        let end_mip = (base_mip + region.mip_count as usize).min(mips.len());
+
+        mips.get(base_mip..end_mip)?.iter().find_map(|usage| {
+            let owner = match (usage.released, usage.queue) {
+                (Some(released), _) => released.queue,
+                (None, Some(usage)) => usage.queue,
+                (None, None) => return None,
+            };
+            (owner != queue).then_some(owner)
+        })
+    }
+
     #[inline(always)]
     pub fn set_image_layout(&mut self, region: &ImageRegion, new_layout: vk::ImageLayout) {
         if let Some(array_elems) = self.images.get_mut(region.id.as_idx()) {
@@ -380,6 +518,164 @@ impl GlobalResourceUsage {
         }
     }
 
+    /// Begins journaling the entries touched from now on. See [`UsageJournal`].
+    #[inline(always)]
+    pub fn begin_journal(&mut self) {
+        self.journal = Some(Box::default());
+    }
+
+    /// Ends the journal started with [`begin_journal`](GlobalResourceUsage::begin_journal).
+    /// `queue`, `is_async`, and `timeline_value` describe the journaled submission.
+    pub fn end_journal(
+        &mut self,
+        queue: QueueType,
+        is_async: bool,
+        timeline_value: u64,
+    ) -> UsageJournal {
+        let journal = self.journal.take().unwrap_or_default();
+
+        UsageJournal {
+            queue,
+            is_async,
+            timeline_value,
+            buffers: journal
+                .buffers
+                .into_iter()
+                .map(|((id, array_elem), before)| JournalEntry {
+                    key: (id, array_elem),
+                    before,
+                    after: *self.get_buffer_entry(&BufferRegion { id, array_elem }),
+                })
+                .collect(),
+            images: journal
+                .images
+                .into_iter()
+                .map(|((id, array_elem, mip), before)| JournalEntry {
+                    key: (id, array_elem, mip),
+                    before,
+                    after: self.get_image_entries(&ImageRegion {
+                        id,
+                        array_elem,
+                        base_mip_level: mip,
+                        mip_count: 1,
+                    })[0],
+                })
+                .collect(),
+            sets: journal
+                .sets
+                .into_iter()
+                .map(|(id, before)| JournalEntry {
+                    key: id,
+                    before,
+                    after: *self.get_set_entry(id),
+                })
+                .collect(),
+        }
+    }
+
+    /// `true` if a submission to `queue` would change usage exactly as `journal` describes.
+    ///
+    /// Usages from previous submissions are synchronized with semaphores rather than barriers,
+    /// so only the queue that owns each entry and the layout of images have to match.
+    pub fn journal_matches(
+        &mut self,
+        journal: &UsageJournal,
+        queue: QueueType,
+        is_async: bool,
+    ) -> bool {
+        if journal.queue != queue || journal.is_async != is_async {
+            return false;
+        }
+
+        journal.buffers.iter().all(|entry| {
+            let (id, array_elem) = entry.key;
+            let current = self.get_buffer_entry(&BufferRegion { id, array_elem });
+            owner(current.queue) == owner(entry.before.queue)
+        }) && journal.images.iter().all(|entry| {
+            let (id, array_elem, mip) = entry.key;
+            let current = &self.get_image_entries(&ImageRegion {
+                id,
+                array_elem,
+                base_mip_level: mip,
+                mip_count: 1,
+            })[0];
+            owner(current.queue) == owner(entry.before.queue)
+                && current.layout == entry.before.layout
+                && current.released == entry.before.released
+        }) && journal.sets.iter().all(|entry| {
+            let current = self.get_set_entry(entry.key);
+            current.queue.map(|q| (q.queue, q.is_async))
+                == entry.before.queue.map(|q| (q.queue, q.is_async))
+        })
+    }
+
+    /// Applies the changes described by `journal` as if its submission was made again with
+    /// `timeline_value`. Every queue the submission must wait on is added to `wait_queues`.
+    ///
+    /// The journal must [match](GlobalResourceUsage::journal_matches) the current usage.
+    pub fn replay_journal(
+        &mut self,
+        journal: &UsageJournal,
+        timeline_value: u64,
+        wait_queues: &mut [Option<u64>; 4],
+    ) {
+        let replay =
+            |after: Option<InternalQueueUsage>, current: Option<InternalQueueUsage>| match after {
+                Some(after)
+                    if after.queue == journal.queue
+                        && after.timeline_value == journal.timeline_value =>
+                {
+                    Some(InternalQueueUsage {
+                        timeline_value,
+                        ..after
+                    })
+                }
+                _ => current,
+            };
+
+        for entry in &journal.buffers {
+            let (id, array_elem) = entry.key;
+            let current = self.get_buffer_entry(&BufferRegion { id, array_elem });
+            wait_on(wait_queues, current.queue);
+            *current = InternalGlobalBufferUsage {
+                queue: replay(entry.after.queue, current.queue),
+                ..entry.after
+            };
+        }
+
+        for entry in &journal.images {
+            let (id, array_elem, mip) = entry.key;
+            let current = &mut self.get_image_entries(&ImageRegion {
+                id,
+                array_elem,
+                base_mip_level: mip,
+                mip_count: 1,
+            })[0];
+            wait_on(wait_queues, current.queue);
+            *current = InternalGlobalImageUsage {
+                queue: replay(entry.after.queue, current.queue),
+                ..entry.after
+            };
+        }
+
+        for entry in &journal.sets {
+            let current = self.get_set_entry(entry.key);
+            wait_on(wait_queues, current.queue.map(InternalQueueUsage::from));
+            current.queue = match entry.after.queue {
+                Some(after)
+                    if after.queue == journal.queue
+                        && after.timeline_value == journal.timeline_value =>
+                {
+                    Some(QueueUsage {
+                        timeline_value,
+                        ..after
+                    })
+                }
+                _ => current.queue,
+            };
+        }
+    }
+
     fn get_image_entries(&mut self, region: &ImageRegion) -> &mut [InternalGlobalImageUsage] {
         let idx = region.id.as_idx();
         let array_elem = region.array_elem as usize;
@@ -403,6 +699,15 @@ impl GlobalResourceUsage {
             mips.resize(total_mips, InternalGlobalImageUsage::default());
         }
 
+        if let Some(journal) = &mut self.journal {
+            for (mip, usage) in mips.iter().enumerate().take(total_mips).skip(base_mip) {
+                journal
+                    .images
+                    .entry((region.id, region.array_elem, mip as u32))
+                    .or_insert(*usage);
+            }
+        }
+
         &mut mips[base_mip..total_mips]
     }
 
@@ -420,6 +725,13 @@ impl GlobalResourceUsage {
             array_elems.resize(array_elem + 1, InternalGlobalBufferUsage::default());
         }
 
+        if let Some(journal) = &mut self.journal {
+            journal
+                .buffers
+                .entry((region.id, region.array_elem))
+                .or_insert(array_elems[array_elem]);
+        }
+
         &mut array_elems[array_elem]
     }
 
@@ -430,6 +742,10 @@ impl GlobalResourceUsage {
             self.sets.resize(idx + 1, GlobalSetUsage::default());
         }
 
+        if let Some(journal) = &mut self.journal {
+            journal.sets.entry(id).or_insert(self.sets[idx]);
+        }
+
         &mut self.sets[idx]
     }
 }
@@ -572,6 +888,43 @@ impl GlobalImageUsage {
     }
 }
 
+#[inline(always)]
+fn last_use(
+    queue: InternalQueueUsage,
+    read_command: Option<usize>,
+    write_command: Option<usize>,
+) -> QueueUsage {
+    QueueUsage {
+        queue: queue.queue,
+        timeline_value: queue.timeline_value,
+        command_idx: read_command.max(write_command).unwrap_or(usize::MAX),
+        is_async: queue.is_async,
+    }
+}
+
+/// The queue owning an entry, ignoring which submission it was used in.
+#[inline(always)]
+fn owner(queue: Option<InternalQueueUsage>) -> Option<(QueueType, bool)> {
+    queue.map(|queue| (queue.queue, queue.is_async))
+}
+
+/// Registers the queue of a usage from a previous submission as needing to be waited on.
+#[inline(always)]
+fn wait_on(wait_queues: &mut [Option<u64>; 4], queue: Option<InternalQueueUsage>) {
+    let queue = match queue {
+        Some(queue) if !queue.is_async => queue,
+        _ => return,
+    };
+
+    let value = match queue.queue {
+        QueueType::Main => &mut wait_queues[0],
+        QueueType::Transfer => &mut wait_queues[1],
+        QueueType::Compute => &mut wait_queues[2],
+        QueueType::Present => &mut wait_queues[3],
+    };
+    *value = Some(value.map_or(queue.timeline_value, |old| old.max(queue.timeline_value)));
+}
+
 #[inline(always)]
 fn requires_ownership_transfer(old: Option<&QueueUsage>, new: Option<&QueueUsage>) -> bool {
     match (old, new) {
@@ -589,3 +942,197 @@ impl From<QueueUsage> for InternalQueueUsage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::id_gen::IdGenerator;
+
+    fn usage(
+        queue: QueueType,
+        access: vk::AccessFlags2,
+        layout: vk::ImageLayout,
+    ) -> GlobalImageUsage {
+        GlobalImageUsage {
+            queue: Some(QueueUsage {
+                queue,
+                timeline_value: 1,
+                command_idx: 0,
+                is_async: false,
+            }),
+            sub_resource: SubResourceUsage {
+                access,
+                stage: vk::PipelineStageFlags2::TRANSFER,
+            },
+            layout,
+        }
+    }
+
+    #[test]
+    fn cube_map_ownership_transfer() {
+        let ids = IdGenerator::default();
+        let id = ids.create();
+        let mut global = GlobalResourceUsage::default();
+        let faces: Vec<_> = (0..6)
+            .map(|face| ImageRegion {
+                id,
+                array_elem: face,
+                base_mip_level: 0,
+                mip_count: 1,
+            })
+            .collect();
+
+        // Upload every face on the transfer queue and release them to main
+        let upload = usage(
+            QueueType::Transfer,
+            vk::AccessFlags2::TRANSFER_WRITE,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        );
+        for face in &faces {
+            global.use_image(face, &upload, &mut [GlobalImageUsage::default()]);
+        }
+
+        // Main doesn't own the faces until they've been released
+        assert_eq!(
+            global.foreign_image_owner(&faces[0], QueueType::Main),
+            Some(QueueType::Transfer)
+        );
+
+        for face in &faces {
+            global.release_image(face, QueueType::Main, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+        }
+
+        assert_eq!(global.foreign_image_owner(&faces[0], QueueType::Main), None);
+        assert_eq!(
+            global.foreign_image_owner(&faces[0], QueueType::Transfer),
+            Some(QueueType::Main)
+        );
+
+        // The acquire must perform the same layout transition as the release
+        let read = usage(
+            QueueType::Main,
+            vk::AccessFlags2::TRANSFER_READ,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        );
+        for face in &faces {
+            let mut old = [GlobalImageUsage::default()];
+            global.use_image(face, &read, &mut old);
+
+            let barrier = match old[0].into_barrier(&read, SharingMode::Exclusive) {
+                Some(PipelineBarrier::Image(barrier)) => barrier,
+                _ => panic!("expected an image barrier"),
+            };
+            assert_eq!(old[0].queue.unwrap().queue, QueueType::Transfer);
+            assert_eq!(barrier.old_layout, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+            assert_eq!(barrier.new_layout, vk::ImageLayout::TRANSFER_SRC_OPTIMAL);
+            assert_eq!(barrier.src_access_mask, vk::AccessFlags2::empty());
+        }
+
+        assert_eq!(global.foreign_image_owner(&faces[0], QueueType::Main), None);
+        assert_eq!(
+            global.foreign_image_owner(&faces[0], QueueType::Transfer),
+            Some(QueueType::Main)
+        );
+    }
+
+    #[test]
+    fn downsample_chain_tracks_mips_independently() {
+        let ids = IdGenerator::default();
+        let id = ids.create();
+        let mut global = GlobalResourceUsage::default();
+        let mip = |level| ImageRegion {
+            id,
+            array_elem: 0,
+            base_mip_level: level,
+            mip_count: 1,
+        };
+        let at = |mut usage: GlobalImageUsage, command_idx| {
+            usage.queue.as_mut().unwrap().command_idx = command_idx;
+            usage
+        };
+        let write = usage(
+            QueueType::Main,
+            vk::AccessFlags2::SHADER_WRITE,
+            vk::ImageLayout::GENERAL,
+        );
+        let read = usage(
+            QueueType::Main,
+            vk::AccessFlags2::SHADER_READ,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        );
+
+        global.use_image(&mip(0), &at(write, 0), &mut [GlobalImageUsage::default()]);
+
+        // Each command samples the mip written by the previous one and writes the next
+        for level in 0..4 {
+            let command_idx = level as usize + 1;
+
+            let mut old = [GlobalImageUsage::default()];
+            global.use_image(&mip(level), &at(read, command_idx), &mut old);
+            assert_eq!(old[0].layout, vk::ImageLayout::GENERAL);
+            assert_eq!(old[0].queue.unwrap().command_idx, command_idx - 1);
+
+            // The next mip has never been used, so it doesn't wait on the rest of the image
+            let mut old = [GlobalImageUsage::default()];
+            global.use_image(&mip(level + 1), &at(write, command_idx), &mut old);
+            assert_eq!(old[0].layout, vk::ImageLayout::UNDEFINED);
+            assert!(old[0].queue.is_none());
+        }
+    }
+
+    #[test]
+    fn journal_replays_when_usage_matches() {
+        let ids = IdGenerator::default();
+        let id = ids.create();
+        let mut global = GlobalResourceUsage::default();
+        let region = ImageRegion {
+            id,
+            array_elem: 0,
+            base_mip_level: 0,
+            mip_count: 1,
+        };
+        let at = |mut usage: GlobalImageUsage, timeline_value| {
+            usage.queue.as_mut().unwrap().timeline_value = timeline_value;
+            usage
+        };
+        let write = usage(
+            QueueType::Main,
+            vk::AccessFlags2::SHADER_WRITE,
+            vk::ImageLayout::GENERAL,
+        );
+        let read = usage(
+            QueueType::Compute,
+            vk::AccessFlags2::SHADER_READ,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        );
+
+        // The first submission sees an unused image
+        global.begin_journal();
+        global.use_image(&region, &at(write, 1), &mut [GlobalImageUsage::default()]);
+        let journal = global.end_journal(QueueType::Main, false, 1);
+        global.use_image(&region, &at(read, 2), &mut [GlobalImageUsage::default()]);
+        assert!(!global.journal_matches(&journal, QueueType::Main, false));
+
+        // Following submissions see the image read by the compute queue
+        global.begin_journal();
+        global.use_image(&region, &at(write, 3), &mut [GlobalImageUsage::default()]);
+        let journal = global.end_journal(QueueType::Main, false, 3);
+        global.use_image(&region, &at(read, 4), &mut [GlobalImageUsage::default()]);
+        assert!(global.journal_matches(&journal, QueueType::Main, false));
+        assert!(!global.journal_matches(&journal, QueueType::Main, true));
+
+        let mut wait_queues = [None; 4];
+        global.replay_journal(&journal, 5, &mut wait_queues);
+        assert_eq!(wait_queues, [None, None, Some(4), None]);
+
+        let replayed = global.get_image_queue_usage(&region).unwrap();
+        assert_eq!(replayed.queue, QueueType::Main);
+        assert_eq!(replayed.timeline_value, 5);
+
+        // Using the image again after the replay syncs with the replayed write
+        let mut old = [GlobalImageUsage::default()];
+        global.use_image(&region, &at(read, 6), &mut old);
+        assert_eq!(old[0].layout, vk::ImageLayout::GENERAL);
+        assert_eq!(old[0].queue.unwrap().timeline_value, 5);
+    }
+}
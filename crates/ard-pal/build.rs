@@ -20,6 +20,22 @@ fn main() {
         Path::new("./examples/shaders/cube.frag"),
         Path::new("./examples/shaders/"),
     );
+    compile(
+        Path::new("./examples/shaders/cube_map.frag"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/sparse.frag"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/volume.frag"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/volume.comp"),
+        Path::new("./examples/shaders/"),
+    );
     compile(
         Path::new("./examples/shaders/uniform_buffer.vert"),
         Path::new("./examples/shaders/"),
@@ -40,6 +56,38 @@ fn main() {
         Path::new("./examples/shaders/test1_wgpu.comp"),
         Path::new("./examples/shaders/"),
     );
+    compile(
+        Path::new("./examples/shaders/spheres.rgen"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/spheres.rmiss"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/spheres.rint"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/spheres.rchit"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/ray_query_ao.comp"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/face_color.vert"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/face_color_multiview.vert"),
+        Path::new("./examples/shaders/"),
+    );
+    compile(
+        Path::new("./examples/shaders/texel_buffer.comp"),
+        Path::new("./examples/shaders/"),
+    );
 }
 
 fn compile(in_path: &Path, out_path: &Path) {
@@ -67,4 +115,131 @@ fn compile(in_path: &Path, out_path: &Path) {
         let err = String::from_utf8(stderr).unwrap();
         panic!("unable to compile {in_path:?}:\n{err}");
     }
+
+    if std::env::var_os("CARGO_FEATURE_DX12").is_some() {
+        compile_dxil(in_path, &out_name);
+    }
+}
+
+/// Register space the DirectX 12 backend binds push constants to.
+const DX12_PUSH_CONSTANT_SPACE: u32 = 1024;
+
+/// Cross compiles SPIR-V into DXIL for the DirectX 12 backend. The output is next to the SPIR-V
+/// with a `.dxil` extension instead.
+fn compile_dxil(in_path: &Path, spv_path: &Path) {
+    let dxil_path = spv_path.with_extension("dxil");
+
+    let stage = in_path.extension().unwrap().to_str().unwrap();
+    let source = std::fs::read_to_string(in_path).unwrap();
+    let profile = match stage {
+        "vert" => "vs_6_1",
+        "frag" => "ps_6_1",
+        "comp" if !source.contains("GL_EXT_ray_query") => "cs_6_1",
+        // The DirectX 12 backend doesn't support ray tracing, so examples never create these
+        // shaders with it. They still need a file to include.
+        _ => {
+            std::fs::write(&dxil_path, []).unwrap();
+            return;
+        }
+    };
+
+    // SPIR-V -> HLSL. Bindings map to `register(N, spaceS)` and vertex inputs to `TEXCOORDN`,
+    // which is what the backend expects.
+    let hlsl_path = spv_path.with_extension("hlsl");
+    let output = Command::new("spirv-cross")
+        .arg(spv_path)
+        .arg("--hlsl")
+        .arg("--shader-model")
+        .arg("61")
+        .arg("--output")
+        .arg(&hlsl_path)
+        .output()
+        .expect("unable to run spirv-cross");
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        panic!("unable to cross compile {spv_path:?}:\n{err}");
+    }
+
+    // Push constants can't be given a register in GLSL
+    if let Some(block) = push_constant_block_name(&std::fs::read(spv_path).unwrap()) {
+        let hlsl = std::fs::read_to_string(&hlsl_path).unwrap();
+        let decl = format!("cbuffer {block}");
+        let hlsl = hlsl
+            .lines()
+            .map(|line| {
+                if line == decl || line.starts_with(&format!("{decl} ")) {
+                    format!("{decl} : register(b0, space{DX12_PUSH_CONSTANT_SPACE})")
+                } else {
+                    line.to_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(&hlsl_path, hlsl).unwrap();
+    }
+
+    // HLSL -> DXIL
+    let output = Command::new("dxc")
+        .arg("-T")
+        .arg(profile)
+        .arg("-E")
+        .arg("main")
+        .arg("-Fo")
+        .arg(&dxil_path)
+        .arg(&hlsl_path)
+        .output()
+        .expect("unable to run dxc");
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        panic!("unable to compile {hlsl_path:?}:\n{err}");
+    }
+}
+
+/// Finds the name of the block type used for push constants in a SPIR-V module.
+fn push_constant_block_name(spv: &[u8]) -> Option<String> {
+    const OP_NAME: u32 = 5;
+    const OP_TYPE_POINTER: u32 = 32;
+    const OP_VARIABLE: u32 = 59;
+    const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+
+    let words: Vec<u32> = spv
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect();
+
+    let mut names = std::collections::HashMap::new();
+    let mut pointees = std::collections::HashMap::new();
+    let mut push_constant_ptr = None;
+
+    // Skip the header
+    let mut i = 5;
+    while i < words.len() {
+        let word_count = (words[i] >> 16) as usize;
+        let operands = &words[(i + 1)..(i + word_count.max(1))];
+
+        match words[i] & 0xFFFF {
+            OP_NAME => {
+                let name: Vec<u8> = operands[1..]
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .take_while(|c| *c != 0)
+                    .collect();
+                names.insert(operands[0], String::from_utf8(name).unwrap());
+            }
+            OP_TYPE_POINTER => {
+                pointees.insert(operands[0], operands[2]);
+            }
+            OP_VARIABLE if operands[2] == STORAGE_CLASS_PUSH_CONSTANT => {
+                push_constant_ptr = Some(operands[0]);
+            }
+            _ => {}
+        }
+
+        i += word_count.max(1);
+    }
+
+    let block = pointees.get(&push_constant_ptr?)?;
+    names.get(block).cloned()
 }
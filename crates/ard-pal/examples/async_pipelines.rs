@@ -0,0 +1,301 @@
+/// This example stress tests asynchronous pipeline compilation. Every frame introduces a batch of
+/// never before seen pipeline variants. When compiled while recording, the first frame using them
+/// stalls. When compiled in the background, draws using them are skipped until they're ready, so
+/// frame times should stay bounded no matter how many new variants show up.
+use std::time::{Duration, Instant};
+
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Number of new pipeline variants introduced every frame.
+const VARIANTS_PER_FRAME: usize = 16;
+
+/// Number of frames that introduce new variants.
+const FRAME_COUNT: usize = 8;
+
+/// The slowest frame allowed when compiling asynchronously.
+const FRAME_BUDGET: Duration = Duration::from_millis(16);
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+struct Scene {
+    context: Context,
+    vertex_shader: Shader,
+    fragment_shader: Shader,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    target: Texture,
+    /// Number of variants created so far. Used to vary their state and names.
+    variant_count: usize,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Async Pipelines")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Async Pipelines", Some(&window), false);
+
+        let mut scene = Scene::new(Context::new(backend));
+
+        let blocking = scene.run(|| PipelineCompileMode::Blocking);
+        let asynchronous = scene.run(|| PipelineCompileMode::Async { fallback: None });
+
+        // Wait for the stragglers so we can report how long compilation actually took
+        let start = Instant::now();
+        while scene.context.pending_pipeline_compiles() > 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let drain = start.elapsed();
+
+        println!("{VARIANTS_PER_FRAME} new variants per frame over {FRAME_COUNT} frames:");
+        println!("  slowest blocking frame: {blocking:?}");
+        println!("  slowest async frame:    {asynchronous:?}");
+        println!("  remaining compile time: {drain:?}");
+
+        assert!(
+            asynchronous <= FRAME_BUDGET,
+            "async frame took {asynchronous:?}, budget is {FRAME_BUDGET:?}"
+        );
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+impl Scene {
+    fn new(context: Context) -> Self {
+        // Create and upload triangle buffers
+        let buffers = util::create_triangle(&context);
+
+        let mut command_buffer = context.main().command_buffer();
+        command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+            src: &buffers.index_staging,
+            src_array_element: 0,
+            src_offset: 0,
+            dst: &buffers.index,
+            dst_array_element: 0,
+            dst_offset: 0,
+            len: buffers.index.size(),
+        });
+        command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+            src: &buffers.vertex_staging,
+            src_array_element: 0,
+            src_offset: 0,
+            dst: &buffers.vertex,
+            dst_array_element: 0,
+            dst_offset: 0,
+            len: buffers.vertex.size(),
+        });
+        context
+            .main()
+            .submit(Some("staging_upload"), command_buffer)
+            .wait_on(None);
+
+        let vertex_shader = Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code: util::shader_code!("triangle.vert"),
+                debug_name: Some(String::from("vertex_shader")),
+            },
+        )
+        .unwrap();
+
+        let fragment_shader = Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code: util::shader_code!("triangle.frag"),
+                debug_name: Some(String::from("fragment_shader")),
+            },
+        )
+        .unwrap();
+
+        let target = Texture::new(
+            context.clone(),
+            TextureCreateInfo {
+                format: Format::Rgba8Unorm,
+                ty: TextureType::Type2D,
+                width: WIDTH,
+                height: HEIGHT,
+                depth: 1,
+                array_elements: 1,
+                mip_levels: 1,
+                sample_count: MultiSamples::Count1,
+                texture_usage: TextureUsage::COLOR_ATTACHMENT,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some(String::from("target")),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        Self {
+            context,
+            vertex_shader,
+            fragment_shader,
+            vertex_buffer: buffers.vertex,
+            index_buffer: buffers.index,
+            target,
+            variant_count: 0,
+        }
+    }
+
+    /// Creates a new pipeline. Every pipeline has its own layout, so each one is compiled
+    /// separately even if its state matches an earlier one.
+    fn create_variant(&mut self, compile_mode: PipelineCompileMode) -> GraphicsPipeline {
+        const CULL_MODES: [CullMode; 3] = [CullMode::None, CullMode::Front, CullMode::Back];
+        const FRONT_FACES: [FrontFace; 2] = [FrontFace::CounterClockwise, FrontFace::Clockwise];
+
+        let idx = self.variant_count;
+        self.variant_count += 1;
+
+        GraphicsPipeline::new(
+            self.context.clone(),
+            GraphicsPipelineCreateInfo {
+                stages: ShaderStages::Traditional {
+                    vertex: self.vertex_shader.clone(),
+                    fragment: Some(self.fragment_shader.clone()),
+                },
+                layouts: Vec::default(),
+                vertex_input: VertexInputState {
+                    attributes: vec![
+                        VertexInputAttribute {
+                            location: 0,
+                            binding: 0,
+                            format: Format::Rgba32SFloat,
+                            offset: 0,
+                        },
+                        VertexInputAttribute {
+                            location: 1,
+                            binding: 0,
+                            format: Format::Rgba32SFloat,
+                            offset: 16,
+                        },
+                    ],
+                    bindings: vec![VertexInputBinding {
+                        binding: 0,
+                        stride: 32,
+                        input_rate: VertexInputRate::Vertex,
+                    }],
+                    topology: PrimitiveTopology::TriangleList,
+                },
+                rasterization: RasterizationState {
+                    polygon_mode: PolygonMode::Fill,
+                    cull_mode: CULL_MODES[idx % CULL_MODES.len()],
+                    front_face: FRONT_FACES[(idx / CULL_MODES.len()) % FRONT_FACES.len()],
+                },
+                depth_stencil: None,
+                color_blend: ColorBlendState {
+                    attachments: vec![ColorBlendAttachment {
+                        write_mask: ColorComponents::R | ColorComponents::G | ColorComponents::B,
+                        ..Default::default()
+                    }],
+                },
+                push_constants_size: None,
+                compile_mode,
+                debug_name: Some(format!("variant_{idx}")),
+                dynamic_states: DynamicStates::empty(),
+            },
+        )
+        .unwrap()
+    }
+
+    /// Renders `FRAME_COUNT` frames that each introduce new variants, returning the slowest time
+    /// spent recording and submitting a frame.
+    fn run(&mut self, compile_mode: impl Fn() -> PipelineCompileMode) -> Duration {
+        let mut slowest = Duration::ZERO;
+
+        for _ in 0..FRAME_COUNT {
+            let variants: Vec<_> = (0..VARIANTS_PER_FRAME)
+                .map(|_| self.create_variant(compile_mode()))
+                .collect();
+
+            let start = Instant::now();
+
+            let mut command_buffer = self.context.main().command_buffer();
+            command_buffer.render_pass(
+                RenderPassDescriptor {
+                    color_attachments: vec![ColorAttachment {
+                        dst: ColorAttachmentDestination::Texture {
+                            texture: &self.target,
+                            array_element: 0,
+                            mip_level: 0,
+                        },
+                        load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+                        store_op: StoreOp::Store,
+                        samples: MultiSamples::Count1,
+                    }],
+                    color_resolve_attachments: Vec::default(),
+                    depth_stencil_attachment: None,
+                    depth_stencil_resolve_attachment: None,
+                    view_mask: 0,
+                    correlation_masks: Vec::default(),
+                },
+                None,
+                |pass| {
+                    pass.bind_vertex_buffers(
+                        0,
+                        vec![VertexBind {
+                            buffer: &self.vertex_buffer,
+                            array_element: 0,
+                            offset: 0,
+                        }],
+                    );
+                    pass.bind_index_buffer(&self.index_buffer, 0, 0, IndexType::U16);
+                    for variant in &variants {
+                        pass.bind_pipeline(variant.clone());
+                        pass.draw_indexed(3, 1, 0, 0, 0);
+                    }
+                },
+            );
+            let job = self
+                .context
+                .main()
+                .submit(Some("main_pass"), command_buffer);
+
+            slowest = slowest.max(start.elapsed());
+            job.wait_on(None);
+        }
+
+        slowest
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
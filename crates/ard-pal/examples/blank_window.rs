@@ -1,13 +1,15 @@
 /// This example demonstrates how to create a blank window and explains all of the objects used.
 use api::surface::SurfacePresentSuccess;
 use ard_pal::prelude::*;
-use vulkan::{VulkanBackend, VulkanBackendCreateInfo};
 use winit::{
     dpi::PhysicalSize,
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
 };
 
+#[path = "./util.rs"]
+mod util;
+
 fn main() {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
@@ -18,13 +20,7 @@ fn main() {
 
     // First, initialize the backend you want to use. This depends on the API, but we're using
     // Vulkan here
-    let backend = VulkanBackend::new(VulkanBackendCreateInfo {
-        app_name: String::from("Blank Window"),
-        engine_name: String::from("pal"),
-        window: &window,
-        debug: true,
-    })
-    .unwrap();
+    let backend = util::create_backend("Blank Window", Some(&window), true);
 
     // Second, you initialize the context
     let context = Context::new(backend);
@@ -37,7 +33,7 @@ fn main() {
                 width: 1280,
                 height: 720,
                 present_mode: PresentMode::Fifo,
-                format: Format::Bgra8Unorm,
+                preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
             },
             window: &window,
             debug_name: Some(String::from("surface")),
@@ -83,6 +79,8 @@ fn main() {
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_attachment: None,
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     None,
                     |_pass| {
@@ -109,7 +107,10 @@ fn main() {
                                 width: dims.width,
                                 height: dims.height,
                                 present_mode: PresentMode::Fifo,
-                                format: Format::Bgra8Unorm,
+                                preferred_formats: vec![(
+                                    Format::Bgra8Unorm,
+                                    ColorSpace::SrgbNonlinear,
+                                )],
                             })
                             .unwrap();
                     }
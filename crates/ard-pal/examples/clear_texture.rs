@@ -0,0 +1,252 @@
+/// This example clears a single mip of a texture array element, all six faces of a cube map, and
+/// a depth texture outside of any render pass. Everything is read back to make sure every texel
+/// holds the clear value and nothing else was touched.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+const SIZE: u32 = 32;
+
+/// Array element and mip of the texture that is cleared.
+const CLEARED_ELEMENT: usize = 1;
+const CLEARED_MIP: usize = 1;
+
+const COLOR: [u8; 4] = [255, 0, 255, 128];
+const DEPTH: f32 = 0.25;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Clear Texture")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Clear Texture", Some(&window), true);
+
+        run(Context::new(backend));
+        println!("every cleared texel holds the clear value");
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    let color_value = ClearColor::RgbaF32(
+        COLOR[0] as f32 / 255.0,
+        COLOR[1] as f32 / 255.0,
+        COLOR[2] as f32 / 255.0,
+        COLOR[3] as f32 / 255.0,
+    );
+
+    let texture = build_texture(&context, Format::Rgba8Unorm, 2, 2, "color_texture");
+    let depth = build_texture(&context, Format::D32Sfloat, 1, 1, "depth_texture");
+    let cube_map = CubeMap::new(
+        context.clone(),
+        CubeMapCreateInfo {
+            format: Format::Rgba8Unorm,
+            size: SIZE,
+            array_elements: 1,
+            mip_levels: 1,
+            texture_usage: TextureUsage::TRANSFER_DST | TextureUsage::TRANSFER_SRC,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("cube_map")),
+            initial_data: None,
+        },
+    )
+    .unwrap();
+
+    let mut command_buffer = context.main().command_buffer();
+
+    // Zero everything first so the untouched regions of the texture are known
+    for array_element in 0..2 {
+        for mip_level in 0..2 {
+            command_buffer.clear_texture(
+                &texture,
+                array_element,
+                mip_level,
+                ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0),
+            );
+        }
+    }
+    command_buffer.clear_texture(&texture, CLEARED_ELEMENT, CLEARED_MIP, color_value);
+    command_buffer.clear_cube_map(&cube_map, 0, 0, color_value);
+    command_buffer.clear_texture(&depth, 0, 0, ClearColor::D32S32(DEPTH, 0));
+
+    // Read back both mips of both array elements
+    let mip_size = |mip: usize| (SIZE >> mip).max(1);
+    let mip_bytes = |mip: usize| (mip_size(mip) * mip_size(mip) * 4) as u64;
+    let texture_data = build_readback(&context, 2 * (mip_bytes(0) + mip_bytes(1)), "readback");
+    let mut regions = Vec::default();
+    let mut offset = 0;
+    for array_element in 0..2 {
+        for mip in 0..2 {
+            command_buffer.copy_texture_to_buffer(
+                &texture_data,
+                &texture,
+                BufferTextureCopy {
+                    buffer_offset: offset,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    buffer_array_element: 0,
+                    texture_offset: (0, 0, 0),
+                    texture_extent: (mip_size(mip), mip_size(mip), 1),
+                    texture_mip_level: mip,
+                    texture_array_element: array_element,
+                },
+            );
+            regions.push((
+                array_element,
+                mip,
+                offset as usize..(offset + mip_bytes(mip)) as usize,
+            ));
+            offset += mip_bytes(mip);
+        }
+    }
+
+    let cube_map_data = build_readback(&context, cube_map.size(), "cube_map_readback");
+    command_buffer.copy_cube_map_to_buffer(
+        &cube_map_data,
+        &cube_map,
+        BufferCubeMapCopy {
+            buffer_offset: 0,
+            buffer_array_element: 0,
+            cube_map_mip_level: 0,
+            cube_map_array_element: 0,
+            face: None,
+        },
+    );
+
+    let depth_data = build_readback(&context, mip_bytes(0), "depth_readback");
+    command_buffer.copy_texture_to_buffer(
+        &depth_data,
+        &depth,
+        BufferTextureCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            buffer_array_element: 0,
+            texture_offset: (0, 0, 0),
+            texture_extent: (SIZE, SIZE, 1),
+            texture_mip_level: 0,
+            texture_array_element: 0,
+        },
+    );
+
+    context
+        .main()
+        .submit(Some("clear_texture"), command_buffer)
+        .wait_on(None);
+
+    let texture_data = texture_data.read(0).unwrap();
+    for (array_element, mip, range) in regions {
+        let expected = if array_element == CLEARED_ELEMENT && mip == CLEARED_MIP {
+            COLOR
+        } else {
+            [0; 4]
+        };
+        assert!(
+            texture_data[range]
+                .chunks_exact(4)
+                .all(|texel| texel == expected),
+            "array element {array_element} mip {mip} holds the wrong value"
+        );
+    }
+
+    let cube_map_data = cube_map_data.read(0).unwrap();
+    assert!(
+        cube_map_data[..(mip_bytes(0) * 6) as usize]
+            .chunks_exact(4)
+            .all(|texel| texel == COLOR),
+        "a cube map face holds the wrong value"
+    );
+
+    let depth_data = depth_data.read(0).unwrap();
+    assert!(
+        bytemuck::cast_slice::<u8, f32>(&depth_data[..mip_bytes(0) as usize])
+            .iter()
+            .all(|depth| *depth == DEPTH),
+        "the depth texture holds the wrong value"
+    );
+}
+
+fn build_texture(
+    context: &Context,
+    format: Format,
+    array_elements: usize,
+    mip_levels: usize,
+    name: &str,
+) -> Texture {
+    Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format,
+            ty: TextureType::Type2D,
+            width: SIZE,
+            height: SIZE,
+            depth: 1,
+            array_elements,
+            mip_levels,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::TRANSFER_DST | TextureUsage::TRANSFER_SRC,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap()
+}
+
+fn build_readback(context: &Context, size: u64, name: &str) -> Buffer {
+    Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size,
+            array_elements: 1,
+            buffer_usage: BufferUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuToCpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap()
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
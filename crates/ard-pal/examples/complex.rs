@@ -2,7 +2,6 @@
 /// the sense that the operations performed make little sense. This simply demonstrates how many
 /// of the features fit together.
 use ard_pal::prelude::*;
-use vulkan::{VulkanBackend, VulkanBackendCreateInfo};
 use winit::{
     dpi::PhysicalSize,
     event::{Event, WindowEvent},
@@ -10,6 +9,9 @@ use winit::{
     window::WindowBuilder,
 };
 
+#[path = "./util.rs"]
+mod util;
+
 fn main() {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
@@ -18,13 +20,7 @@ fn main() {
         .build(&event_loop)
         .unwrap();
 
-    let backend = VulkanBackend::new(VulkanBackendCreateInfo {
-        app_name: String::from("Complex"),
-        engine_name: String::from("pal"),
-        window: &window,
-        debug: true,
-    })
-    .unwrap();
+    let backend = util::create_backend("Complex", Some(&window), true);
 
     let context = Context::new(backend);
 
@@ -35,7 +31,7 @@ fn main() {
                 width: 1280,
                 height: 720,
                 present_mode: PresentMode::Fifo,
-                format: Format::Bgra8Unorm,
+                preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
             },
             window: &window,
             debug_name: Some(String::from("surface")),
@@ -55,6 +51,7 @@ fn main() {
             queue_types: QueueTypes::TRANSFER,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("color_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -72,6 +69,7 @@ fn main() {
             queue_types: QueueTypes::NON_PRESENT,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("vertex_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -86,6 +84,7 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("index_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -100,6 +99,7 @@ fn main() {
             queue_types: QueueTypes::COMPUTE | QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("index_buffer_intermediate")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -108,7 +108,7 @@ fn main() {
     let vertex_compute_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/vertex_compute.comp.spv"),
+            code: util::shader_code!("vertex_compute.comp"),
             debug_name: Some(String::from("vertex_compute_shader")),
         },
     )
@@ -122,7 +122,10 @@ fn main() {
                 binding: 0,
                 count: 1,
                 stage: ShaderStage::Compute,
+                flags: DescriptorBindingFlags::empty(),
             }],
+            push: false,
+            debug_name: None,
         },
     )
     .unwrap();
@@ -132,6 +135,7 @@ fn main() {
         DescriptorSetCreateInfo {
             layout: compute_layout.clone(),
             debug_name: Some(String::from("vertex_compute_set")),
+            variable_count: None,
         },
     )
     .unwrap();
@@ -161,7 +165,7 @@ fn main() {
     let index_compute_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/index_compute.comp.spv"),
+            code: util::shader_code!("index_compute.comp"),
             debug_name: Some(String::from("index_compute_shader")),
         },
     )
@@ -172,6 +176,7 @@ fn main() {
         DescriptorSetCreateInfo {
             layout: compute_layout.clone(),
             debug_name: Some(String::from("index_compute_set")),
+            variable_count: None,
         },
     )
     .unwrap();
@@ -201,7 +206,7 @@ fn main() {
     let vertex_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/triangle.vert.spv"),
+            code: util::shader_code!("triangle.vert"),
             debug_name: Some(String::from("vertex_shader")),
         },
     )
@@ -210,7 +215,7 @@ fn main() {
     let fragment_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/triangle.frag.spv"),
+            code: util::shader_code!("triangle.frag"),
             debug_name: Some(String::from("fragment_shader")),
         },
     )
@@ -261,7 +266,9 @@ fn main() {
                 }],
             },
             push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
             debug_name: Some(String::from("graphics_pipeline")),
+            dynamic_states: DynamicStates::empty(),
         },
     )
     .unwrap();
@@ -343,7 +350,7 @@ fn main() {
 
                 command_buffer.compute_pass(&vertex_compute_pipeline, None, |pass| {
                     pass.bind_sets(0, vec![&vertex_compute_set]);
-                    ComputePassDispatch::Inline(1, 1, 1)
+                    pass.dispatch(1, 1, 1);
                 });
 
                 // Since the vertex buffer was marked as having an `Exclusive` usage, we must
@@ -372,7 +379,7 @@ fn main() {
                 // 1. Generate indices
                 command_buffer.compute_pass(&index_compute_pipeline, None, |pass| {
                     pass.bind_sets(0, vec![&index_compute_set]);
-                    ComputePassDispatch::Inline(1, 1, 1)
+                    pass.dispatch(1, 1, 1);
                 });
 
                 // 2. Copy indices
@@ -398,6 +405,8 @@ fn main() {
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_attachment: None,
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     None,
                     |pass| {
@@ -435,7 +444,10 @@ fn main() {
                                 width: dims.width,
                                 height: dims.height,
                                 present_mode: PresentMode::Fifo,
-                                format: Format::Bgra8Unorm,
+                                preferred_formats: vec![(
+                                    Format::Bgra8Unorm,
+                                    ColorSpace::SrgbNonlinear,
+                                )],
                             })
                             .unwrap();
                     }
@@ -0,0 +1,144 @@
+/// This example checks that uploading a single face of a cube map leaves the other faces alone.
+/// It runs without a window and panics if any face read back from the GPU doesn't match.
+///
+/// Every face of a two element cube map array is filled with its own color when the cube map is
+/// created. A single face of the second element is then overwritten, as is done when only part
+/// of a reflection probe changes, and every face of both elements is read back.
+use ard_pal::prelude::*;
+use winit::raw_window_handle::DisplayHandle;
+
+#[path = "./util.rs"]
+mod util;
+
+/// Width and height of each cube map face.
+const CUBE_MAP_SIZE: u32 = 16;
+
+const ARRAY_ELEMENTS: usize = 2;
+
+const FACE_BYTES: usize = (CUBE_MAP_SIZE * CUBE_MAP_SIZE * 4) as usize;
+
+/// The face that is overwritten and the array element it belongs to.
+const UPDATED_FACE: CubeFace = CubeFace::North;
+const UPDATED_ELEMENT: usize = 1;
+
+const UPDATED_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+fn main() {
+    let backend = util::create_backend("Cube Face Upload", None::<&DisplayHandle>, true);
+    let context = Context::new(backend);
+
+    let pixels = run(&context);
+    for array_element in 0..ARRAY_ELEMENTS {
+        for (i, face) in CubeFace::ALL.into_iter().enumerate() {
+            let expected = if (array_element, face) == (UPDATED_ELEMENT, UPDATED_FACE) {
+                UPDATED_COLOR
+            } else {
+                face_color(array_element, i)
+            };
+
+            let offset = ((array_element * CubeFace::ALL.len()) + i) * FACE_BYTES;
+            let texels = &pixels[offset..(offset + FACE_BYTES)];
+            assert!(
+                texels.chunks_exact(4).all(|texel| texel == expected),
+                "face `{face:?}` of array element `{array_element}` doesn't match"
+            );
+        }
+    }
+
+    println!("only face `{UPDATED_FACE:?}` of array element `{UPDATED_ELEMENT}` was updated");
+}
+
+/// The color every face is filled with when the cube map is created.
+fn face_color(array_element: usize, face: usize) -> [u8; 4] {
+    [(face * 40) as u8, (array_element * 100) as u8, 128, 255]
+}
+
+/// Uploads the single face and returns the tightly packed RGBA8 texels of every face.
+fn run(context: &Context) -> Vec<u8> {
+    let mut initial_data = Vec::with_capacity(FACE_BYTES * 6 * ARRAY_ELEMENTS);
+    for array_element in 0..ARRAY_ELEMENTS {
+        for face in 0..CubeFace::ALL.len() {
+            let color = face_color(array_element, face);
+            initial_data.extend(std::iter::repeat_n(color, FACE_BYTES / 4).flatten());
+        }
+    }
+
+    let cube_map = CubeMap::new(
+        context.clone(),
+        CubeMapCreateInfo {
+            format: Format::Rgba8Unorm,
+            size: CUBE_MAP_SIZE,
+            array_elements: ARRAY_ELEMENTS,
+            mip_levels: 1,
+            texture_usage: TextureUsage::SAMPLED
+                | TextureUsage::TRANSFER_SRC
+                | TextureUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("cube_map")),
+            initial_data: Some(&[&initial_data]),
+        },
+    )
+    .unwrap();
+
+    let face_data: Vec<u8> = std::iter::repeat_n(UPDATED_COLOR, FACE_BYTES / 4)
+        .flatten()
+        .collect();
+    let staging = Buffer::new_staging(
+        context.clone(),
+        QueueType::Main,
+        Some(String::from("face_staging")),
+        &face_data,
+    )
+    .unwrap();
+
+    let readback = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: initial_data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuToCpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("cube_map_readback")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.copy_buffer_to_cube_map(
+        &cube_map,
+        &staging,
+        BufferCubeMapCopy {
+            buffer_offset: 0,
+            buffer_array_element: 0,
+            cube_map_mip_level: 0,
+            cube_map_array_element: UPDATED_ELEMENT,
+            face: Some(UPDATED_FACE),
+        },
+    );
+    for array_element in 0..ARRAY_ELEMENTS {
+        command_buffer.copy_cube_map_to_buffer(
+            &readback,
+            &cube_map,
+            BufferCubeMapCopy {
+                buffer_offset: (array_element * FACE_BYTES * 6) as u64,
+                buffer_array_element: 0,
+                cube_map_mip_level: 0,
+                cube_map_array_element: array_element,
+                face: None,
+            },
+        );
+    }
+
+    context
+        .main()
+        .submit(Some("cube_face_upload"), command_buffer)
+        .wait_on(None);
+
+    let view = readback.read(0).unwrap();
+    view[..initial_data.len()].to_vec()
+}
@@ -0,0 +1,370 @@
+/// This example moves an exclusive cube map between queues and should run without any
+/// validation errors.
+///
+/// Every face is uploaded on the transfer queue and then released to the main queue. The main
+/// queue generates the rest of the mip chain using blits and finally samples the cube map in a
+/// render pass.
+use ard_pal::prelude::*;
+use ordered_float::NotNan;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Width and height of each cube map face.
+const CUBE_MAP_SIZE: u32 = 64;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+const FACES: [CubeFace; 6] = [
+    CubeFace::East,
+    CubeFace::West,
+    CubeFace::Top,
+    CubeFace::Bottom,
+    CubeFace::North,
+    CubeFace::South,
+];
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Cube Map Ownership")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Cube Map Ownership", Some(&window), true);
+
+        run(Context::new(backend));
+        println!("cube map transferred, mipped, and sampled");
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    let mip_levels = (CUBE_MAP_SIZE.ilog2() + 1) as usize;
+
+    let cube_map = CubeMap::new(
+        context.clone(),
+        CubeMapCreateInfo {
+            format: Format::Rgba8Unorm,
+            size: CUBE_MAP_SIZE,
+            array_elements: 1,
+            mip_levels,
+            texture_usage: TextureUsage::SAMPLED
+                | TextureUsage::TRANSFER_SRC
+                | TextureUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("cube_map")),
+            initial_data: None,
+        },
+    )
+    .unwrap();
+
+    // Give every face a different color so corrupted faces are easy to spot
+    let face_bytes = (CUBE_MAP_SIZE * CUBE_MAP_SIZE * 4) as usize;
+    let mut data = Vec::with_capacity(face_bytes * FACES.len());
+    for i in 0..FACES.len() {
+        let color = [(i * 40) as u8, 255 - (i * 40) as u8, 128, 255];
+        data.extend(std::iter::repeat_n(color, face_bytes / 4).flatten());
+    }
+
+    let staging = Buffer::new_staging(
+        context.clone(),
+        QueueType::Transfer,
+        Some(String::from("cube_map_staging")),
+        &data,
+    )
+    .unwrap();
+
+    // Upload every face of the base mip on the transfer queue and hand them to the main queue.
+    // The hint lets the release perform the layout transition the first blit needs.
+    let mut command_buffer = context.transfer().command_buffer();
+    command_buffer.copy_buffer_to_cube_map(
+        &cube_map,
+        &staging,
+        BufferCubeMapCopy {
+            buffer_offset: 0,
+            buffer_array_element: 0,
+            cube_map_mip_level: 0,
+            cube_map_array_element: 0,
+            face: None,
+        },
+    );
+    for face in FACES {
+        command_buffer.transfer_cube_map_ownership(
+            &cube_map,
+            0,
+            0,
+            1,
+            face,
+            QueueType::Main,
+            Some(TextureUsage::TRANSFER_SRC),
+        );
+    }
+    context
+        .transfer()
+        .submit(Some("cube_map_upload"), command_buffer);
+
+    // Generate the rest of the mip chain on the main queue
+    let mut command_buffer = context.main().command_buffer();
+    for mip in 1..mip_levels {
+        let src_size = CUBE_MAP_SIZE >> (mip - 1);
+        let dst_size = CUBE_MAP_SIZE >> mip;
+        for face in FACES {
+            command_buffer.blit(
+                BlitSource::CubeMap {
+                    cube_map: &cube_map,
+                    face,
+                },
+                BlitDestination::CubeMap {
+                    cube_map: &cube_map,
+                    face,
+                },
+                Blit {
+                    src_min: (0, 0, 0),
+                    src_max: (src_size, src_size, 1),
+                    src_mip: mip - 1,
+                    src_array_element: 0,
+                    dst_min: (0, 0, 0),
+                    dst_max: (dst_size, dst_size, 1),
+                    dst_mip: mip,
+                    dst_array_element: 0,
+                },
+                Filter::Linear,
+            );
+        }
+    }
+
+    // Sample every mip of the cube map in a render pass
+    let buffers = util::create_triangle(&context);
+    command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+        src: &buffers.index_staging,
+        src_array_element: 0,
+        src_offset: 0,
+        dst: &buffers.index,
+        dst_array_element: 0,
+        dst_offset: 0,
+        len: buffers.index.size(),
+    });
+    command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+        src: &buffers.vertex_staging,
+        src_array_element: 0,
+        src_offset: 0,
+        dst: &buffers.vertex,
+        dst_array_element: 0,
+        dst_offset: 0,
+        len: buffers.vertex.size(),
+    });
+
+    let layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![DescriptorBinding {
+                binding: 0,
+                count: 1,
+                stage: ShaderStage::Fragment,
+                ty: DescriptorType::CubeMap,
+                flags: DescriptorBindingFlags::empty(),
+            }],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let mut set = DescriptorSet::new(
+        context.clone(),
+        DescriptorSetCreateInfo {
+            layout: layout.clone(),
+            debug_name: Some(String::from("cube_map_set")),
+            variable_count: None,
+        },
+    )
+    .unwrap();
+
+    set.update(&[DescriptorSetUpdate {
+        binding: 0,
+        array_element: 0,
+        value: DescriptorValue::CubeMap {
+            cube_map: &cube_map,
+            array_element: 0,
+            sampler: Sampler {
+                min_filter: Filter::Linear,
+                mag_filter: Filter::Linear,
+                mipmap_filter: Filter::Linear,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                anisotropy: None,
+                compare: None,
+                reduction_mode: None,
+                min_lod: NotNan::new(0.0).unwrap(),
+                max_lod: None,
+                unnormalize_coords: false,
+                border_color: None,
+            },
+            base_mip: 0,
+            mip_count: mip_levels,
+        },
+    }]);
+
+    let pipeline = GraphicsPipeline::new(
+        context.clone(),
+        GraphicsPipelineCreateInfo {
+            stages: ShaderStages::Traditional {
+                vertex: Shader::new(
+                    context.clone(),
+                    ShaderCreateInfo {
+                        code: util::shader_code!("triangle.vert"),
+                        debug_name: Some(String::from("vertex_shader")),
+                    },
+                )
+                .unwrap(),
+                fragment: Some(
+                    Shader::new(
+                        context.clone(),
+                        ShaderCreateInfo {
+                            code: util::shader_code!("cube_map.frag"),
+                            debug_name: Some(String::from("fragment_shader")),
+                        },
+                    )
+                    .unwrap(),
+                ),
+            },
+            layouts: vec![layout],
+            vertex_input: VertexInputState {
+                attributes: vec![
+                    VertexInputAttribute {
+                        location: 0,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 0,
+                    },
+                    VertexInputAttribute {
+                        location: 1,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 16,
+                    },
+                ],
+                bindings: vec![VertexInputBinding {
+                    binding: 0,
+                    stride: 32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                topology: PrimitiveTopology::TriangleList,
+            },
+            rasterization: RasterizationState::default(),
+            depth_stencil: None,
+            color_blend: ColorBlendState {
+                attachments: vec![ColorBlendAttachment {
+                    write_mask: ColorComponents::R | ColorComponents::G | ColorComponents::B,
+                    ..Default::default()
+                }],
+            },
+            push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
+            debug_name: Some(String::from("cube_map_pipeline")),
+            dynamic_states: DynamicStates::empty(),
+        },
+    )
+    .unwrap();
+
+    let target = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::COLOR_ATTACHMENT,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("target")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    command_buffer.render_pass(
+        RenderPassDescriptor {
+            color_attachments: vec![ColorAttachment {
+                dst: ColorAttachmentDestination::Texture {
+                    texture: &target,
+                    array_element: 0,
+                    mip_level: 0,
+                },
+                load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+                store_op: StoreOp::Store,
+                samples: MultiSamples::Count1,
+            }],
+            color_resolve_attachments: Vec::default(),
+            depth_stencil_attachment: None,
+            depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
+        },
+        Some("sample_cube_map"),
+        |pass| {
+            pass.bind_pipeline(pipeline.clone());
+            pass.bind_sets(0, vec![&set]);
+            pass.bind_vertex_buffers(
+                0,
+                vec![VertexBind {
+                    buffer: &buffers.vertex,
+                    array_element: 0,
+                    offset: 0,
+                }],
+            );
+            pass.bind_index_buffer(&buffers.index, 0, 0, IndexType::U16);
+            pass.draw_indexed(3, 1, 0, 0, 0);
+        },
+    );
+
+    // The main queue waits on the upload automatically since it uses the cube map
+    context
+        .main()
+        .submit(Some("cube_map_mips"), command_buffer)
+        .wait_on(None);
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
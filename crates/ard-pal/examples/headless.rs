@@ -0,0 +1,240 @@
+/// This example renders a frame without a window or display server. The backend is created
+/// without a display handle, a triangle is drawn into an offscreen texture, and the texture is
+/// read back and written to a PNG. This is the path used for golden image tests in CI.
+///
+/// The image is written to the path given as the first argument, or `headless.png` by default.
+use std::path::PathBuf;
+
+use ard_pal::prelude::*;
+use winit::raw_window_handle::DisplayHandle;
+
+#[path = "./util.rs"]
+mod util;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+const CLEAR_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
+
+/// Triangle corners in normalized device coordinates followed by their colors.
+const VERTICES: [[f32; 8]; 3] = [
+    [-0.5, -0.5, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0],
+    [0.5, -0.5, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0],
+    [0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0],
+];
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("headless.png"));
+
+    let backend = util::create_backend("Headless", None::<&DisplayHandle>, true);
+    let context = Context::new(backend);
+
+    let pixels = render(&context);
+    image::save_buffer(
+        &path,
+        &pixels,
+        WIDTH,
+        HEIGHT,
+        image::ExtendedColorType::Rgba8,
+    )
+    .unwrap();
+    println!("wrote {}", path.display());
+}
+
+/// Draws the triangle into an offscreen texture and returns the tightly packed RGBA8 texels.
+fn render(context: &Context) -> Vec<u8> {
+    let target = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::TRANSFER_SRC,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("headless_target")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let readback = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: (WIDTH * HEIGHT * 4) as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuToCpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("headless_readback")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let vertices = build_vertices(context);
+    let pipeline = build_pipeline(context);
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.render_pass(
+        RenderPassDescriptor {
+            color_attachments: vec![ColorAttachment {
+                dst: ColorAttachmentDestination::Texture {
+                    texture: &target,
+                    array_element: 0,
+                    mip_level: 0,
+                },
+                load_op: LoadOp::Clear(ClearColor::RgbaF32(
+                    CLEAR_COLOR[0],
+                    CLEAR_COLOR[1],
+                    CLEAR_COLOR[2],
+                    CLEAR_COLOR[3],
+                )),
+                store_op: StoreOp::Store,
+                samples: MultiSamples::Count1,
+            }],
+            color_resolve_attachments: Vec::default(),
+            depth_stencil_attachment: None,
+            depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
+        },
+        Some("headless"),
+        |pass| {
+            pass.bind_pipeline(pipeline.clone());
+            pass.bind_vertex_buffers(
+                0,
+                vec![VertexBind {
+                    buffer: &vertices,
+                    array_element: 0,
+                    offset: 0,
+                }],
+            );
+            pass.draw(3, 1, 0, 0);
+        },
+    );
+    command_buffer.copy_texture_to_buffer(
+        &readback,
+        &target,
+        BufferTextureCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            buffer_array_element: 0,
+            texture_offset: (0, 0, 0),
+            texture_extent: (WIDTH, HEIGHT, 1),
+            texture_mip_level: 0,
+            texture_array_element: 0,
+        },
+    );
+
+    context
+        .main()
+        .submit(Some("headless"), command_buffer)
+        .wait_on(None);
+
+    let view = readback.read(0).unwrap();
+    view[..(WIDTH * HEIGHT * 4) as usize].to_vec()
+}
+
+fn build_vertices(context: &Context) -> Buffer {
+    let data: &[u8] = bytemuck::cast_slice(&VERTICES);
+
+    let mut buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::VERTEX_BUFFER,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("headless_vertices")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = buffer.write(0).unwrap();
+    view.copy_from_slice(data);
+    std::mem::drop(view);
+    buffer
+}
+
+fn build_pipeline(context: &Context) -> GraphicsPipeline {
+    let shader = |code: &[u8], name: &str| {
+        Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code,
+                debug_name: Some(String::from(name)),
+            },
+        )
+        .unwrap()
+    };
+
+    GraphicsPipeline::new(
+        context.clone(),
+        GraphicsPipelineCreateInfo {
+            stages: ShaderStages::Traditional {
+                vertex: shader(util::shader_code!("triangle.vert"), "vertex"),
+                fragment: Some(shader(util::shader_code!("triangle.frag"), "fragment")),
+            },
+            layouts: Vec::default(),
+            vertex_input: VertexInputState {
+                attributes: vec![
+                    VertexInputAttribute {
+                        location: 0,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 0,
+                    },
+                    VertexInputAttribute {
+                        location: 1,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 16,
+                    },
+                ],
+                bindings: vec![VertexInputBinding {
+                    binding: 0,
+                    stride: 32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                topology: PrimitiveTopology::TriangleList,
+            },
+            rasterization: RasterizationState {
+                polygon_mode: PolygonMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::CounterClockwise,
+            },
+            depth_stencil: None,
+            color_blend: ColorBlendState {
+                attachments: vec![ColorBlendAttachment {
+                    write_mask: ColorComponents::R
+                        | ColorComponents::G
+                        | ColorComponents::B
+                        | ColorComponents::A,
+                    ..Default::default()
+                }],
+            },
+            push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
+            debug_name: Some(String::from("headless_pipeline")),
+            dynamic_states: DynamicStates::empty(),
+        },
+    )
+    .unwrap()
+}
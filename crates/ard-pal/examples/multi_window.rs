@@ -0,0 +1,405 @@
+/// This example opens two windows, each with its own surface, and renders a different scene to
+/// each of them every frame. Windows can be resized or closed independently without affecting
+/// the other, and the example exits once both are closed. Pressing V toggles vsync for the focused
+/// window, which should happen without a hitch. It should run without any validation errors.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+
+/// Two triangles making up a quad in the same vertex layout as the triangle from `util`.
+const QUAD_VERTICES: &[f32] = &[
+    -0.5, -0.5, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, // Bottom left
+    0.5, -0.5, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, // Bottom right
+    0.5, 0.5, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0, // Top right
+    -0.5, 0.5, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, // Top left
+];
+const QUAD_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+
+#[derive(Default)]
+struct App {
+    state: Option<State>,
+}
+
+struct State {
+    context: Context,
+    pipeline: GraphicsPipeline,
+    triangle: util::MeshBuffers,
+    quad: util::MeshBuffers,
+    windows: Vec<WindowState>,
+}
+
+/// A window and the surface presenting to it. The surface is declared first so that it is
+/// dropped before the window it presents to.
+struct WindowState {
+    surface: Surface,
+    window: Window,
+    /// The scene drawn to this window.
+    scene: Scene,
+    /// If presentation waits for vertical blanking.
+    vsync: bool,
+}
+
+#[derive(Copy, Clone)]
+enum Scene {
+    Triangle,
+    Quad,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.state.is_some() {
+            return;
+        }
+
+        let create_window = |title: &str| {
+            event_loop
+                .create_window(
+                    Window::default_attributes()
+                        .with_title(title)
+                        .with_inner_size(PhysicalSize::new(WIDTH, HEIGHT)),
+                )
+                .unwrap()
+        };
+        let triangle_window = create_window("Multi Window (Triangle)");
+        let quad_window = create_window("Multi Window (Quad)");
+
+        let backend = util::create_backend("Multi Window", Some(&triangle_window), true);
+
+        let context = Context::new(backend);
+
+        let windows = vec![
+            WindowState::new(&context, triangle_window, Scene::Triangle, "triangle"),
+            WindowState::new(&context, quad_window, Scene::Quad, "quad"),
+        ];
+
+        let triangle = util::create_triangle(&context);
+        let quad = create_quad(&context);
+
+        // Upload both meshes once up front
+        let mut command_buffer = context.main().command_buffer();
+        for mesh in [&triangle, &quad] {
+            command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+                src: &mesh.index_staging,
+                src_array_element: 0,
+                src_offset: 0,
+                dst: &mesh.index,
+                dst_array_element: 0,
+                dst_offset: 0,
+                len: mesh.index.size(),
+            });
+            command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+                src: &mesh.vertex_staging,
+                src_array_element: 0,
+                src_offset: 0,
+                dst: &mesh.vertex,
+                dst_array_element: 0,
+                dst_offset: 0,
+                len: mesh.vertex.size(),
+            });
+        }
+        context
+            .main()
+            .submit(Some("upload"), command_buffer)
+            .wait_on(None);
+
+        let pipeline = create_pipeline(&context);
+
+        self.state = Some(State {
+            context,
+            pipeline,
+            triangle,
+            quad,
+            windows,
+        });
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
+        let state = match &mut self.state {
+            Some(state) => state,
+            None => return,
+        };
+
+        match event {
+            // Closing one window leaves the other running
+            WindowEvent::CloseRequested => {
+                state.windows.retain(|window| window.window.id() != id);
+                if state.windows.is_empty() {
+                    event_loop.exit();
+                }
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(KeyCode::KeyV),
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => {
+                if let Some(window) = state.windows.iter_mut().find(|w| w.window.id() == id) {
+                    window.vsync = !window.vsync;
+                    println!("{:?} vsync: {}", window.window.title(), window.vsync);
+                    let size = window.window.inner_size();
+                    if size.width != 0 && size.height != 0 {
+                        window.reconfigure(size);
+                    }
+                }
+            }
+            WindowEvent::RedrawRequested => {
+                if let Some(window) = state.windows.iter_mut().find(|w| w.window.id() == id) {
+                    let mesh = match window.scene {
+                        Scene::Triangle => &state.triangle,
+                        Scene::Quad => &state.quad,
+                    };
+                    window.render(&state.context, &state.pipeline, mesh);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _: &ActiveEventLoop) {
+        if let Some(state) = &self.state {
+            for window in &state.windows {
+                window.window.request_redraw();
+            }
+        }
+    }
+}
+
+impl WindowState {
+    fn new(context: &Context, window: Window, scene: Scene, name: &str) -> Self {
+        let size = window.inner_size();
+        let surface = Surface::new(
+            context.clone(),
+            SurfaceCreateInfo {
+                config: SurfaceConfiguration {
+                    width: size.width,
+                    height: size.height,
+                    present_mode: PresentMode::Fifo,
+                    preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
+                },
+                window: WindowSource::Reference(&window),
+                debug_name: Some(format!("{name}_surface")),
+            },
+        )
+        .unwrap();
+
+        Self {
+            surface,
+            window,
+            scene,
+            vsync: true,
+        }
+    }
+
+    fn render(&mut self, context: &Context, pipeline: &GraphicsPipeline, mesh: &util::MeshBuffers) {
+        // Minimized windows are skipped without stalling the other window
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let surface_image = match self.surface.acquire_image() {
+            Ok(image) => image,
+            Err(SurfaceImageAcquireError::Invalidated) => {
+                self.reconfigure(size);
+                return;
+            }
+            Err(err) => panic!("{err}"),
+        };
+
+        let clear_color = match self.scene {
+            Scene::Triangle => ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0),
+            Scene::Quad => ClearColor::RgbaF32(0.1, 0.1, 0.3, 0.0),
+        };
+        let index_count = (mesh.index.size() / std::mem::size_of::<u16>() as u64) as usize;
+
+        let mut command_buffer = context.main().command_buffer();
+        command_buffer.render_pass(
+            RenderPassDescriptor {
+                color_attachments: vec![ColorAttachment {
+                    dst: ColorAttachmentDestination::SurfaceImage(&surface_image),
+                    load_op: LoadOp::Clear(clear_color),
+                    store_op: StoreOp::Store,
+                    samples: MultiSamples::Count1,
+                }],
+                color_resolve_attachments: Vec::default(),
+                depth_stencil_attachment: None,
+                depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
+            },
+            Some("draw_scene"),
+            |pass| {
+                pass.bind_pipeline(pipeline.clone());
+                pass.bind_vertex_buffers(
+                    0,
+                    vec![VertexBind {
+                        buffer: &mesh.vertex,
+                        array_element: 0,
+                        offset: 0,
+                    }],
+                );
+                pass.bind_index_buffer(&mesh.index, 0, 0, IndexType::U16);
+                pass.draw_indexed(index_count, 1, 0, 0, 0);
+            },
+        );
+        context.main().submit(Some("scene"), command_buffer);
+
+        if let SurfacePresentSuccess::Invalidated = context
+            .present()
+            .present(&self.surface, surface_image)
+            .unwrap()
+        {
+            self.reconfigure(size);
+        }
+    }
+
+    /// Vsync can be switched at any point no images are pending presentation. The old swapchain
+    /// is reused by the backend, so the switch doesn't drop frames.
+    fn reconfigure(&mut self, size: PhysicalSize<u32>) {
+        self.surface
+            .update_config(SurfaceConfiguration {
+                width: size.width,
+                height: size.height,
+                present_mode: if self.vsync {
+                    PresentMode::Fifo
+                } else {
+                    PresentMode::Immediate
+                },
+                preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
+            })
+            .unwrap();
+    }
+}
+
+fn create_quad(context: &Context) -> util::MeshBuffers {
+    util::MeshBuffers {
+        vertex: Buffer::new(
+            context.clone(),
+            BufferCreateInfo {
+                size: std::mem::size_of_val(QUAD_VERTICES) as u64,
+                array_elements: 1,
+                buffer_usage: BufferUsage::VERTEX_BUFFER | BufferUsage::TRANSFER_DST,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some(String::from("quad_vertex_buffer")),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap(),
+        vertex_staging: Buffer::new_staging(
+            context.clone(),
+            QueueType::Main,
+            Some(String::from("quad_vertex_staging")),
+            bytemuck::cast_slice(QUAD_VERTICES),
+        )
+        .unwrap(),
+        index: Buffer::new(
+            context.clone(),
+            BufferCreateInfo {
+                size: std::mem::size_of_val(QUAD_INDICES) as u64,
+                array_elements: 1,
+                buffer_usage: BufferUsage::INDEX_BUFFER | BufferUsage::TRANSFER_DST,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some(String::from("quad_index_buffer")),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap(),
+        index_staging: Buffer::new_staging(
+            context.clone(),
+            QueueType::Main,
+            Some(String::from("quad_index_staging")),
+            bytemuck::cast_slice(QUAD_INDICES),
+        )
+        .unwrap(),
+    }
+}
+
+fn create_pipeline(context: &Context) -> GraphicsPipeline {
+    GraphicsPipeline::new(
+        context.clone(),
+        GraphicsPipelineCreateInfo {
+            stages: ShaderStages::Traditional {
+                vertex: Shader::new(
+                    context.clone(),
+                    ShaderCreateInfo {
+                        code: util::shader_code!("triangle.vert"),
+                        debug_name: Some(String::from("vertex_shader")),
+                    },
+                )
+                .unwrap(),
+                fragment: Some(
+                    Shader::new(
+                        context.clone(),
+                        ShaderCreateInfo {
+                            code: util::shader_code!("triangle.frag"),
+                            debug_name: Some(String::from("fragment_shader")),
+                        },
+                    )
+                    .unwrap(),
+                ),
+            },
+            layouts: Vec::default(),
+            vertex_input: VertexInputState {
+                attributes: vec![
+                    VertexInputAttribute {
+                        location: 0,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 0,
+                    },
+                    VertexInputAttribute {
+                        location: 1,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 16,
+                    },
+                ],
+                bindings: vec![VertexInputBinding {
+                    binding: 0,
+                    stride: 32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                topology: PrimitiveTopology::TriangleList,
+            },
+            rasterization: RasterizationState::default(),
+            depth_stencil: None,
+            color_blend: ColorBlendState {
+                attachments: vec![ColorBlendAttachment {
+                    write_mask: ColorComponents::R | ColorComponents::G | ColorComponents::B,
+                    ..Default::default()
+                }],
+            },
+            push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
+            debug_name: Some(String::from("scene_pipeline")),
+            dynamic_states: DynamicStates::empty(),
+        },
+    )
+    .unwrap()
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.run_app(&mut App::default()).unwrap();
+}
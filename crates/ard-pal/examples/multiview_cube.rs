@@ -0,0 +1,385 @@
+/// This example renders every face of a cube map two ways. First with six passes, one for each
+/// face, and then with a single multiview pass that broadcasts each draw to all six faces. The
+/// same is done for a six layer texture array. Both results are read back to make sure they're
+/// identical.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+const SIZE: u32 = 64;
+
+/// Faces in array layer order, which is also the order views of a multiview pass render to.
+const FACES: [CubeFace; 6] = [
+    CubeFace::East,
+    CubeFace::West,
+    CubeFace::Top,
+    CubeFace::Bottom,
+    CubeFace::North,
+    CubeFace::South,
+];
+
+/// Size in bytes of every face.
+const FACE_SIZE: u64 = (SIZE * SIZE * 4) as u64;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Multiview Cube")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Multiview Cube", Some(&window), true);
+
+        let context = Context::new(backend);
+        if context.properties().features.multiview {
+            run(context);
+            println!("multiview output matches six separate passes");
+        } else {
+            println!("multiview is not supported on this device");
+        }
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    let single_pipeline = build_pipeline(
+        &context,
+        "single_view_pipeline",
+        util::shader_code!("face_color.vert"),
+        Some(std::mem::size_of::<u32>() as u32),
+    );
+    let multiview_pipeline = build_pipeline(
+        &context,
+        "multiview_pipeline",
+        util::shader_code!("face_color_multiview.vert"),
+        None,
+    );
+
+    // Cube maps
+    let separate_cube = build_cube_map(&context, "separate_cube");
+    let multiview_cube = build_cube_map(&context, "multiview_cube");
+
+    let mut command_buffer = context.main().command_buffer();
+    for (i, face) in FACES.into_iter().enumerate() {
+        command_buffer.render_pass(
+            descriptor(
+                ColorAttachmentDestination::CubeFace {
+                    cube_map: &separate_cube,
+                    array_element: 0,
+                    face,
+                    mip_level: 0,
+                },
+                0,
+            ),
+            Some("separate_cube_face"),
+            |pass| {
+                pass.bind_pipeline(single_pipeline.clone());
+                pass.push_constants(bytemuck::bytes_of(&(i as u32)));
+                pass.draw(3, 1, 0, 0);
+            },
+        );
+    }
+    command_buffer.render_pass(
+        descriptor(
+            ColorAttachmentDestination::CubeMap {
+                cube_map: &multiview_cube,
+                array_element: 0,
+                mip_level: 0,
+            },
+            0b111111,
+        ),
+        Some("multiview_cube"),
+        |pass| {
+            pass.bind_pipeline(multiview_pipeline.clone());
+            pass.draw(3, 1, 0, 0);
+        },
+    );
+
+    let separate_cube_data = build_readback(&context, "separate_cube_readback");
+    let multiview_cube_data = build_readback(&context, "multiview_cube_readback");
+    for (buffer, cube_map) in [
+        (&separate_cube_data, &separate_cube),
+        (&multiview_cube_data, &multiview_cube),
+    ] {
+        command_buffer.copy_cube_map_to_buffer(
+            buffer,
+            cube_map,
+            BufferCubeMapCopy {
+                buffer_offset: 0,
+                buffer_array_element: 0,
+                cube_map_mip_level: 0,
+                cube_map_array_element: 0,
+                face: None,
+            },
+        );
+    }
+
+    // Texture arrays
+    let separate_array = build_texture_array(&context, "separate_array");
+    let multiview_array = build_texture_array(&context, "multiview_array");
+
+    for i in 0..FACES.len() {
+        command_buffer.render_pass(
+            descriptor(
+                ColorAttachmentDestination::Texture {
+                    texture: &separate_array,
+                    array_element: i,
+                    mip_level: 0,
+                },
+                0,
+            ),
+            Some("separate_array_layer"),
+            |pass| {
+                pass.bind_pipeline(single_pipeline.clone());
+                pass.push_constants(bytemuck::bytes_of(&(i as u32)));
+                pass.draw(3, 1, 0, 0);
+            },
+        );
+    }
+    command_buffer.render_pass(
+        descriptor(
+            ColorAttachmentDestination::TextureArray {
+                texture: &multiview_array,
+                base_layer: 0,
+                layer_count: FACES.len(),
+                mip_level: 0,
+            },
+            0b111111,
+        ),
+        Some("multiview_array"),
+        |pass| {
+            pass.bind_pipeline(multiview_pipeline.clone());
+            pass.draw(3, 1, 0, 0);
+        },
+    );
+
+    let separate_array_data = build_readback(&context, "separate_array_readback");
+    let multiview_array_data = build_readback(&context, "multiview_array_readback");
+    for (buffer, texture) in [
+        (&separate_array_data, &separate_array),
+        (&multiview_array_data, &multiview_array),
+    ] {
+        for i in 0..FACES.len() {
+            command_buffer.copy_texture_to_buffer(
+                buffer,
+                texture,
+                BufferTextureCopy {
+                    buffer_offset: i as u64 * FACE_SIZE,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    buffer_array_element: 0,
+                    texture_offset: (0, 0, 0),
+                    texture_extent: (SIZE, SIZE, 1),
+                    texture_mip_level: 0,
+                    texture_array_element: i,
+                },
+            );
+        }
+    }
+
+    context
+        .main()
+        .submit(Some("multiview_cube"), command_buffer)
+        .wait_on(None);
+
+    let separate_cube_data = separate_cube_data.read(0).unwrap();
+    let multiview_cube_data = multiview_cube_data.read(0).unwrap();
+    let separate_array_data = separate_array_data.read(0).unwrap();
+    let multiview_array_data = multiview_array_data.read(0).unwrap();
+
+    // Every face must have been written with its own color
+    for i in 0..FACES.len() {
+        let face = (i * FACE_SIZE as usize)..((i + 1) * FACE_SIZE as usize);
+        let expected = face_color(i as u32);
+        assert!(separate_cube_data[face.clone()]
+            .chunks_exact(4)
+            .all(|texel| texel == expected));
+        assert_eq!(
+            separate_cube_data[face.clone()],
+            multiview_cube_data[face.clone()],
+            "cube face {:?} differs between the separate and multiview passes",
+            FACES[i]
+        );
+        assert_eq!(
+            separate_array_data[face.clone()],
+            multiview_array_data[face],
+            "array layer {i} differs between the separate and multiview passes"
+        );
+    }
+}
+
+/// Color written to a face by `face_color.vert`.
+fn face_color(face: u32) -> [u8; 4] {
+    [
+        ((face & 1) * 255) as u8,
+        (((face >> 1) & 1) * 255) as u8,
+        (face as f32 / 5.0 * 255.0).round() as u8,
+        255,
+    ]
+}
+
+fn descriptor(dst: ColorAttachmentDestination, view_mask: u32) -> RenderPassDescriptor {
+    RenderPassDescriptor {
+        color_attachments: vec![ColorAttachment {
+            dst,
+            load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+            store_op: StoreOp::Store,
+            samples: MultiSamples::Count1,
+        }],
+        color_resolve_attachments: Vec::default(),
+        depth_stencil_attachment: None,
+        depth_stencil_resolve_attachment: None,
+        view_mask,
+        // Every face is as likely to be visible as any other, so there's no correlation
+        correlation_masks: Vec::default(),
+    }
+}
+
+fn build_cube_map(context: &Context, name: &str) -> CubeMap {
+    CubeMap::new(
+        context.clone(),
+        CubeMapCreateInfo {
+            format: Format::Rgba8Unorm,
+            size: SIZE,
+            array_elements: 1,
+            mip_levels: 1,
+            texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::TRANSFER_SRC,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            initial_data: None,
+        },
+    )
+    .unwrap()
+}
+
+fn build_texture_array(context: &Context, name: &str) -> Texture {
+    Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: SIZE,
+            height: SIZE,
+            depth: 1,
+            array_elements: FACES.len(),
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::TRANSFER_SRC,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap()
+}
+
+/// Creates a buffer large enough to hold every face.
+fn build_readback(context: &Context, name: &str) -> Buffer {
+    Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: FACE_SIZE * FACES.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuToCpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap()
+}
+
+fn build_pipeline(
+    context: &Context,
+    name: &str,
+    vertex: &[u8],
+    push_constants_size: Option<u32>,
+) -> GraphicsPipeline {
+    let shader = |code: &[u8], name: &str| {
+        Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code,
+                debug_name: Some(String::from(name)),
+            },
+        )
+        .unwrap()
+    };
+
+    GraphicsPipeline::new(
+        context.clone(),
+        GraphicsPipelineCreateInfo {
+            stages: ShaderStages::Traditional {
+                vertex: shader(vertex, "vertex"),
+                fragment: Some(shader(util::shader_code!("triangle.frag"), "fragment")),
+            },
+            layouts: Vec::default(),
+            vertex_input: VertexInputState {
+                attributes: Vec::default(),
+                bindings: Vec::default(),
+                topology: PrimitiveTopology::TriangleList,
+            },
+            rasterization: RasterizationState {
+                polygon_mode: PolygonMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::CounterClockwise,
+            },
+            depth_stencil: None,
+            color_blend: ColorBlendState {
+                attachments: vec![ColorBlendAttachment {
+                    write_mask: ColorComponents::R
+                        | ColorComponents::G
+                        | ColorComponents::B
+                        | ColorComponents::A,
+                    ..Default::default()
+                }],
+            },
+            push_constants_size,
+            compile_mode: PipelineCompileMode::Blocking,
+            debug_name: Some(String::from(name)),
+            dynamic_states: DynamicStates::empty(),
+        },
+    )
+    .unwrap()
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
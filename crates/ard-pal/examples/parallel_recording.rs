@@ -0,0 +1,287 @@
+/// This example compares the cost of submitting a render pass containing many draws when it is
+/// recorded serially against when it is split into slices recorded in parallel. The slices are
+/// filled on rayon worker threads and the backend translates each slice into its own secondary
+/// command buffer, so submission time should drop as the number of slices grows.
+use std::time::{Duration, Instant};
+
+use ard_pal::prelude::*;
+use rayon::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Number of draws recorded each frame.
+const DRAW_COUNT: usize = 10_000;
+
+/// Number of slices to split the pass into.
+const SLICE_COUNT: usize = 8;
+
+/// Number of frames to average timings over.
+const FRAME_COUNT: u32 = 100;
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+struct Scene {
+    context: Context,
+    pipeline: GraphicsPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    target: Texture,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Parallel Recording")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Parallel Recording", Some(&window), false);
+
+        let scene = Scene::new(Context::new(backend));
+
+        let serial = scene.benchmark(|pass| {
+            for i in 0..DRAW_COUNT {
+                pass.draw_indexed(3, 1, 0, 0, i);
+            }
+        });
+
+        let parallel = scene.benchmark(|pass| {
+            pass.split(SLICE_COUNT, |slices| {
+                let per_slice = DRAW_COUNT.div_ceil(SLICE_COUNT);
+                slices
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(slice_idx, slice)| {
+                        let start = slice_idx * per_slice;
+                        let end = (start + per_slice).min(DRAW_COUNT);
+                        for i in start..end {
+                            slice.draw_indexed(3, 1, 0, 0, i);
+                        }
+                    });
+            });
+        });
+
+        println!("{DRAW_COUNT} draws, averaged over {FRAME_COUNT} frames:");
+        println!("  serial:           {serial:?}");
+        println!("  split {SLICE_COUNT} ways:      {parallel:?}");
+        println!(
+            "  speedup:          {:.2}x",
+            serial.as_secs_f64() / parallel.as_secs_f64()
+        );
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+impl Scene {
+    fn new(context: Context) -> Self {
+        // Create and upload triangle buffers
+        let buffers = util::create_triangle(&context);
+
+        let mut command_buffer = context.main().command_buffer();
+        command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+            src: &buffers.index_staging,
+            src_array_element: 0,
+            src_offset: 0,
+            dst: &buffers.index,
+            dst_array_element: 0,
+            dst_offset: 0,
+            len: buffers.index.size(),
+        });
+        command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+            src: &buffers.vertex_staging,
+            src_array_element: 0,
+            src_offset: 0,
+            dst: &buffers.vertex,
+            dst_array_element: 0,
+            dst_offset: 0,
+            len: buffers.vertex.size(),
+        });
+        context
+            .main()
+            .submit(Some("staging_upload"), command_buffer)
+            .wait_on(None);
+
+        let vertex_shader = Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code: util::shader_code!("triangle.vert"),
+                debug_name: Some(String::from("vertex_shader")),
+            },
+        )
+        .unwrap();
+
+        let fragment_shader = Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code: util::shader_code!("triangle.frag"),
+                debug_name: Some(String::from("fragment_shader")),
+            },
+        )
+        .unwrap();
+
+        let pipeline = GraphicsPipeline::new(
+            context.clone(),
+            GraphicsPipelineCreateInfo {
+                stages: ShaderStages::Traditional {
+                    vertex: vertex_shader,
+                    fragment: Some(fragment_shader),
+                },
+                layouts: Vec::default(),
+                vertex_input: VertexInputState {
+                    attributes: vec![
+                        VertexInputAttribute {
+                            location: 0,
+                            binding: 0,
+                            format: Format::Rgba32SFloat,
+                            offset: 0,
+                        },
+                        VertexInputAttribute {
+                            location: 1,
+                            binding: 0,
+                            format: Format::Rgba32SFloat,
+                            offset: 16,
+                        },
+                    ],
+                    bindings: vec![VertexInputBinding {
+                        binding: 0,
+                        stride: 32,
+                        input_rate: VertexInputRate::Vertex,
+                    }],
+                    topology: PrimitiveTopology::TriangleList,
+                },
+                rasterization: RasterizationState::default(),
+                depth_stencil: None,
+                color_blend: ColorBlendState {
+                    attachments: vec![ColorBlendAttachment {
+                        write_mask: ColorComponents::R | ColorComponents::G | ColorComponents::B,
+                        ..Default::default()
+                    }],
+                },
+                push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
+                debug_name: Some(String::from("graphics_pipeline")),
+                dynamic_states: DynamicStates::empty(),
+            },
+        )
+        .unwrap();
+
+        let target = Texture::new(
+            context.clone(),
+            TextureCreateInfo {
+                format: Format::Rgba8Unorm,
+                ty: TextureType::Type2D,
+                width: WIDTH,
+                height: HEIGHT,
+                depth: 1,
+                array_elements: 1,
+                mip_levels: 1,
+                sample_count: MultiSamples::Count1,
+                texture_usage: TextureUsage::COLOR_ATTACHMENT,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some(String::from("target")),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        Self {
+            context,
+            pipeline,
+            vertex_buffer: buffers.vertex,
+            index_buffer: buffers.index,
+            target,
+        }
+    }
+
+    /// Records and submits `FRAME_COUNT` frames using `draw` to fill the pass, returning the
+    /// average time spent recording and submitting a frame.
+    fn benchmark(&self, draw: impl Fn(&mut RenderPass)) -> Duration {
+        let mut total = Duration::ZERO;
+
+        for _ in 0..FRAME_COUNT {
+            let start = Instant::now();
+
+            let mut command_buffer = self.context.main().command_buffer();
+            command_buffer.render_pass(
+                RenderPassDescriptor {
+                    color_attachments: vec![ColorAttachment {
+                        dst: ColorAttachmentDestination::Texture {
+                            texture: &self.target,
+                            array_element: 0,
+                            mip_level: 0,
+                        },
+                        load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+                        store_op: StoreOp::Store,
+                        samples: MultiSamples::Count1,
+                    }],
+                    color_resolve_attachments: Vec::default(),
+                    depth_stencil_attachment: None,
+                    depth_stencil_resolve_attachment: None,
+                    view_mask: 0,
+                    correlation_masks: Vec::default(),
+                },
+                None,
+                |pass| {
+                    pass.bind_pipeline(self.pipeline.clone());
+                    pass.bind_vertex_buffers(
+                        0,
+                        vec![VertexBind {
+                            buffer: &self.vertex_buffer,
+                            array_element: 0,
+                            offset: 0,
+                        }],
+                    );
+                    pass.bind_index_buffer(&self.index_buffer, 0, 0, IndexType::U16);
+                    draw(pass);
+                },
+            );
+            let job = self
+                .context
+                .main()
+                .submit(Some("main_pass"), command_buffer);
+
+            total += start.elapsed();
+            job.wait_on(None);
+        }
+
+        total / FRAME_COUNT
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
@@ -4,9 +4,11 @@ use std::{
 };
 
 use ard_pal::prelude::*;
-use vulkan::{VulkanBackend, VulkanBackendCreateInfo};
 use winit::{dpi::PhysicalSize, event_loop::EventLoop, window::WindowBuilder};
 
+#[path = "./util.rs"]
+mod util;
+
 const TEST1_RUN_COUNT: usize = 10000;
 const TEST1_BUFFER_COUNT: usize = 1024;
 const TEST1_BUFFER_SIZE: u64 = 128;
@@ -22,13 +24,7 @@ fn main() {
         .build(&event_loop)
         .unwrap();
 
-    let pal_backend = VulkanBackend::new(VulkanBackendCreateInfo {
-        app_name: String::from("performance_test"),
-        engine_name: String::from("pal"),
-        window: &window,
-        debug: false,
-    })
-    .unwrap();
+    let pal_backend = util::create_backend("performance_test", Some(&window), false);
     let pal = Context::new(pal_backend);
 
     println!("Initializing Wgpu...");
@@ -77,6 +73,7 @@ fn main() {
                     queue_types: QueueTypes::COMPUTE,
                     sharing_mode: SharingMode::Exclusive,
                     debug_name: None,
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -124,7 +121,10 @@ fn pal_test1(pal: &Context, buffers: &[Buffer]) -> Duration {
                 ty: DescriptorType::StorageBuffer(AccessType::ReadWrite),
                 count: TEST1_BUFFER_COUNT,
                 stage: ShaderStage::Compute,
+                flags: DescriptorBindingFlags::empty(),
             }],
+            push: false,
+            debug_name: None,
         },
     )
     .unwrap();
@@ -134,6 +134,7 @@ fn pal_test1(pal: &Context, buffers: &[Buffer]) -> Duration {
         DescriptorSetCreateInfo {
             layout: layout.clone(),
             debug_name: None,
+            variable_count: None,
         },
     )
     .unwrap();
@@ -151,7 +152,7 @@ fn pal_test1(pal: &Context, buffers: &[Buffer]) -> Duration {
     }
     set.update(&updates);
 
-    const SHADER_BIN: &'static [u8] = include_bytes!("./shaders/test1_pal.comp.spv");
+    const SHADER_BIN: &'static [u8] = util::shader_code!("test1_pal.comp");
     let shader = Shader::new(
         pal.clone(),
         ShaderCreateInfo {
@@ -176,7 +177,7 @@ fn pal_test1(pal: &Context, buffers: &[Buffer]) -> Duration {
     let mut command_buffer = pal.compute().command_buffer();
     command_buffer.compute_pass(&pipeline, None, |pass| {
         pass.bind_sets(0, vec![&set]);
-        ComputePassDispatch::Inline(1, 1, 1)
+        pass.dispatch(1, 1, 1);
     });
     pal.compute().submit(None, command_buffer);
 
@@ -0,0 +1,361 @@
+/// This example ray traces a grid of analytic spheres. Each sphere is bounded by an AABB in a
+/// procedural BLAS and is hit using an intersection shader. It should run without any validation
+/// errors on hardware that supports ray tracing.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Number of spheres along each axis of the grid. Must match `spheres.rint`.
+const GRID_SIZE: usize = 8;
+
+/// Distance between sphere centers. Must match `spheres.rint`.
+const SPACING: f32 = 1.0;
+
+/// Radius of each sphere. Must match `spheres.rint`.
+const RADIUS: f32 = 0.4;
+
+const WIDTH: u32 = 512;
+const HEIGHT: u32 = 512;
+
+/// Size of `VkAccelerationStructureInstanceKHR`.
+const INSTANCE_SIZE: usize = 64;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Procedural Spheres")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Procedural Spheres", Some(&window), true);
+
+        let context = Context::new(backend);
+        if context.properties().features.ray_tracing {
+            run(context);
+            println!("traced {} procedural spheres", GRID_SIZE * GRID_SIZE);
+        } else {
+            println!("ray tracing is not supported on this device");
+        }
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    // One AABB per sphere, centered on the origin
+    let offset = (GRID_SIZE as f32 - 1.0) * SPACING * 0.5;
+    let mut aabbs = Vec::with_capacity(GRID_SIZE * GRID_SIZE * 6);
+    for y in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            let cx = x as f32 * SPACING - offset;
+            let cy = y as f32 * SPACING - offset;
+            aabbs.extend_from_slice(&[
+                cx - RADIUS,
+                cy - RADIUS,
+                -RADIUS,
+                cx + RADIUS,
+                cy + RADIUS,
+                RADIUS,
+            ]);
+        }
+    }
+
+    let aabb_buffer = build_input_buffer(&context, "sphere_aabbs", bytemuck::cast_slice(&aabbs));
+
+    let blas = BottomLevelAccelerationStructure::new(
+        context.clone(),
+        BottomLevelAccelerationStructureCreateInfo {
+            flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE,
+            data: BottomLevelAccelerationStructureData::Aabbs(vec![AccelerationStructureAabbs {
+                flags: GeometryFlags::OPAQUE,
+                data: &aabb_buffer,
+                data_array_element: 0,
+                data_offset: 0,
+                stride: (6 * std::mem::size_of::<f32>()) as u64,
+                count: GRID_SIZE * GRID_SIZE,
+            }]),
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("spheres_blas")),
+        },
+    )
+    .unwrap();
+
+    // A single instance of the BLAS with an identity transform
+    let mut instance = [0u8; INSTANCE_SIZE];
+    let transform: [f32; 12] = [
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, 1.0, 0.0, 0.0, //
+        0.0, 0.0, 1.0, 0.0, //
+    ];
+    instance[0..48].copy_from_slice(bytemuck::cast_slice(&transform));
+    // Custom index of 0 and a mask of 0xFF
+    instance[48..52].copy_from_slice(&(0xFFu32 << 24).to_ne_bytes());
+    // SBT offset of 0 and no flags
+    instance[52..56].copy_from_slice(&0u32.to_ne_bytes());
+    instance[56..64].copy_from_slice(&blas.device_ref().to_ne_bytes());
+
+    let instance_buffer = build_input_buffer(&context, "sphere_instance", &instance);
+
+    // The TLAS reads instances through an array of pointers
+    let instance_ptrs = build_input_buffer(
+        &context,
+        "sphere_instance_ptrs",
+        bytemuck::bytes_of(&instance_buffer.device_ref(0)),
+    );
+
+    let tlas = TopLevelAccelerationStructure::new(
+        context.clone(),
+        TopLevelAccelerationStructureCreateInfo {
+            flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE,
+            capacity: 1,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("spheres_tlas")),
+        },
+    )
+    .unwrap();
+
+    let scratch = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: blas.scratch_buffer_size().max(tlas.scratch_buffer_size()),
+            array_elements: 1,
+            buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_SCRATCH,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("spheres_scratch")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let target = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::STORAGE,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("spheres_target")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![
+                DescriptorBinding {
+                    binding: 0,
+                    count: 1,
+                    stage: ShaderStage::RayGeneration,
+                    ty: DescriptorType::TopLevelAccelerationStructure,
+                    flags: DescriptorBindingFlags::empty(),
+                },
+                DescriptorBinding {
+                    binding: 1,
+                    count: 1,
+                    stage: ShaderStage::RayGeneration,
+                    ty: DescriptorType::StorageImage(AccessType::ReadWrite),
+                    flags: DescriptorBindingFlags::empty(),
+                },
+            ],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let mut set = DescriptorSet::new(
+        context.clone(),
+        DescriptorSetCreateInfo {
+            layout: layout.clone(),
+            debug_name: Some(String::from("spheres_set")),
+            variable_count: None,
+        },
+    )
+    .unwrap();
+
+    set.update(&[
+        DescriptorSetUpdate {
+            binding: 0,
+            array_element: 0,
+            value: DescriptorValue::TopLevelAccelerationStructure(&tlas),
+        },
+        DescriptorSetUpdate {
+            binding: 1,
+            array_element: 0,
+            value: DescriptorValue::StorageImage {
+                texture: &target,
+                array_element: 0,
+                mip: 0,
+            },
+        },
+    ]);
+
+    let shader = |code: &[u8], name: &str| {
+        Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code,
+                debug_name: Some(String::from(name)),
+            },
+        )
+        .unwrap()
+    };
+
+    let pipeline = RayTracingPipeline::new(
+        context.clone(),
+        RayTracingPipelineCreateInfo {
+            stages: vec![
+                RayTracingShaderStage {
+                    shader: shader(util::shader_code!("spheres.rgen"), "raygen"),
+                    stage: ShaderStage::RayGeneration,
+                },
+                RayTracingShaderStage {
+                    shader: shader(util::shader_code!("spheres.rmiss"), "miss"),
+                    stage: ShaderStage::RayMiss,
+                },
+                RayTracingShaderStage {
+                    shader: shader(util::shader_code!("spheres.rint"), "intersection"),
+                    stage: ShaderStage::RayIntersection,
+                },
+                RayTracingShaderStage {
+                    shader: shader(util::shader_code!("spheres.rchit"), "closest_hit"),
+                    stage: ShaderStage::RayClosestHit,
+                },
+            ],
+            groups: vec![
+                RayTracingShaderGroup::RayGeneration(0),
+                RayTracingShaderGroup::Miss(1),
+                RayTracingShaderGroup::Procedural {
+                    intersection: 2,
+                    closest_hit: Some(3),
+                    any_hit: None,
+                },
+            ],
+            max_ray_recursion_depth: 1,
+            layouts: vec![layout],
+            push_constants_size: None,
+            library_info: None,
+            libraries: Vec::default(),
+            debug_name: Some(String::from("spheres_pipeline")),
+        },
+    )
+    .unwrap();
+
+    // Groups are in the order they were given to the pipeline
+    let sbt_data = pipeline.shader_binding_table_data();
+    let table = SbtBuilder::new(&sbt_data)
+        .raygen(0, &[])
+        .miss(1, &[])
+        .hit(2, &[])
+        .build()
+        .unwrap();
+
+    let mut sbt = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: table.data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::SHADER_BINDING_TABLE | BufferUsage::DEVICE_ADDRESS,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("spheres_sbt")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = sbt.write(0).unwrap();
+    view[..table.data.len()].copy_from_slice(&table.data);
+    std::mem::drop(view);
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.build_bottom_level_acceleration_structure(&blas, &scratch, 0);
+    command_buffer.build_top_level_acceleration_structure(&tlas, 1, &scratch, 0, &instance_ptrs, 0);
+    command_buffer.ray_trace_pass(&pipeline, Some("trace_spheres"), |pass| {
+        pass.bind_sets(0, vec![&set]);
+        RayTracingDispatch {
+            src: RayTracingDispatchSource::Inline(WIDTH, HEIGHT, 1),
+            shader_binding_table: &sbt,
+            raygen: table.raygen,
+            miss: table.miss,
+            hit: table.hit,
+        }
+    });
+
+    context
+        .main()
+        .submit(Some("procedural_spheres"), command_buffer)
+        .wait_on(None);
+}
+
+/// Creates a buffer the GPU reads while building acceleration structures.
+fn build_input_buffer(context: &Context, name: &str, data: &[u8]) -> Buffer {
+    let mut buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_READ,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = buffer.write(0).unwrap();
+    view.copy_from_slice(data);
+    std::mem::drop(view);
+    buffer
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
@@ -0,0 +1,322 @@
+/// This example computes ambient occlusion for a cube sitting on a ground plane. A compute shader
+/// traces inline rays with `rayQueryEXT` against the scene TLAS, so no ray tracing pipeline or
+/// shader binding table is needed. It should run without any validation errors on hardware that
+/// supports ray queries.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Half the width of the ground plane. Must match `ray_query_ao.comp`.
+const SCENE_EXTENT: f32 = 4.0;
+
+/// Work group size of the compute shader along each axis.
+const WORK_GROUP_SIZE: u32 = 8;
+
+const WIDTH: u32 = 512;
+const HEIGHT: u32 = 512;
+
+/// Size of `VkAccelerationStructureInstanceKHR`.
+const INSTANCE_SIZE: usize = 64;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Ray Query AO")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Ray Query AO", Some(&window), true);
+
+        let context = Context::new(backend);
+        if context.properties().features.ray_query {
+            run(context);
+            println!("ambient occlusion computed with ray queries");
+        } else {
+            println!("ray queries are not supported on this device");
+        }
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    // Ground plane followed by a 2x2x2 cube resting on it. Positions are padded to `vec4`s.
+    #[rustfmt::skip]
+    let vertices: [f32; 48] = [
+        -SCENE_EXTENT, 0.0, -SCENE_EXTENT, 1.0,
+        SCENE_EXTENT, 0.0, -SCENE_EXTENT, 1.0,
+        SCENE_EXTENT, 0.0, SCENE_EXTENT, 1.0,
+        -SCENE_EXTENT, 0.0, SCENE_EXTENT, 1.0,
+        -1.0, 0.0, -1.0, 1.0,
+        1.0, 0.0, -1.0, 1.0,
+        1.0, 0.0, 1.0, 1.0,
+        -1.0, 0.0, 1.0, 1.0,
+        -1.0, 2.0, -1.0, 1.0,
+        1.0, 2.0, -1.0, 1.0,
+        1.0, 2.0, 1.0, 1.0,
+        -1.0, 2.0, 1.0, 1.0,
+    ];
+
+    #[rustfmt::skip]
+    let indices: [u16; 42] = [
+        // Ground
+        0, 1, 2, 0, 2, 3,
+        // Cube bottom and top
+        4, 5, 6, 4, 6, 7,
+        8, 10, 9, 8, 11, 10,
+        // Cube sides
+        4, 9, 5, 4, 8, 9,
+        5, 10, 6, 5, 9, 10,
+        6, 11, 7, 6, 10, 11,
+        7, 8, 4, 7, 11, 8,
+    ];
+
+    let vertex_buffer =
+        build_input_buffer(&context, "scene_vertices", bytemuck::cast_slice(&vertices));
+    let index_buffer =
+        build_input_buffer(&context, "scene_indices", bytemuck::cast_slice(&indices));
+
+    let blas = BottomLevelAccelerationStructure::new(
+        context.clone(),
+        BottomLevelAccelerationStructureCreateInfo {
+            flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE,
+            data: BottomLevelAccelerationStructureData::Geometry(vec![
+                AccelerationStructureGeometry {
+                    flags: GeometryFlags::OPAQUE,
+                    vertex_format: Format::Rgb32SFloat,
+                    vertex_data: &vertex_buffer,
+                    vertex_data_array_element: 0,
+                    vertex_data_offset: 0,
+                    vertex_count: vertices.len() / 4,
+                    vertex_stride: (4 * std::mem::size_of::<f32>()) as u64,
+                    index_type: IndexType::U16,
+                    index_data: &index_buffer,
+                    index_data_array_element: 0,
+                    index_data_offset: 0,
+                    triangle_count: indices.len() / 3,
+                },
+            ]),
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("scene_blas")),
+        },
+    )
+    .unwrap();
+
+    // A single instance of the BLAS with an identity transform
+    let mut instance = [0u8; INSTANCE_SIZE];
+    let transform: [f32; 12] = [
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, 1.0, 0.0, 0.0, //
+        0.0, 0.0, 1.0, 0.0, //
+    ];
+    instance[0..48].copy_from_slice(bytemuck::cast_slice(&transform));
+    // Custom index of 0 and a mask of 0xFF
+    instance[48..52].copy_from_slice(&(0xFFu32 << 24).to_ne_bytes());
+    // SBT offset of 0 and no flags
+    instance[52..56].copy_from_slice(&0u32.to_ne_bytes());
+    instance[56..64].copy_from_slice(&blas.device_ref().to_ne_bytes());
+
+    let instance_buffer = build_input_buffer(&context, "scene_instance", &instance);
+
+    // The TLAS reads instances through an array of pointers
+    let instance_ptrs = build_input_buffer(
+        &context,
+        "scene_instance_ptrs",
+        bytemuck::bytes_of(&instance_buffer.device_ref(0)),
+    );
+
+    let tlas = TopLevelAccelerationStructure::new(
+        context.clone(),
+        TopLevelAccelerationStructureCreateInfo {
+            flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE,
+            capacity: 1,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("scene_tlas")),
+        },
+    )
+    .unwrap();
+
+    let scratch = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: blas.scratch_buffer_size().max(tlas.scratch_buffer_size()),
+            array_elements: 1,
+            buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_SCRATCH,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("scene_scratch")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let ao = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::STORAGE,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("ao")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    // The TLAS is bound to the compute stage, which is only valid with ray queries
+    let layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![
+                DescriptorBinding {
+                    binding: 0,
+                    count: 1,
+                    stage: ShaderStage::Compute,
+                    ty: DescriptorType::TopLevelAccelerationStructure,
+                    flags: DescriptorBindingFlags::empty(),
+                },
+                DescriptorBinding {
+                    binding: 1,
+                    count: 1,
+                    stage: ShaderStage::Compute,
+                    ty: DescriptorType::StorageImage(AccessType::ReadWrite),
+                    flags: DescriptorBindingFlags::empty(),
+                },
+            ],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let mut set = DescriptorSet::new(
+        context.clone(),
+        DescriptorSetCreateInfo {
+            layout: layout.clone(),
+            debug_name: Some(String::from("ao_set")),
+            variable_count: None,
+        },
+    )
+    .unwrap();
+
+    set.update(&[
+        DescriptorSetUpdate {
+            binding: 0,
+            array_element: 0,
+            value: DescriptorValue::TopLevelAccelerationStructure(&tlas),
+        },
+        DescriptorSetUpdate {
+            binding: 1,
+            array_element: 0,
+            value: DescriptorValue::StorageImage {
+                texture: &ao,
+                array_element: 0,
+                mip: 0,
+            },
+        },
+    ]);
+
+    let pipeline = ComputePipeline::new(
+        context.clone(),
+        ComputePipelineCreateInfo {
+            layouts: vec![layout],
+            module: Shader::new(
+                context.clone(),
+                ShaderCreateInfo {
+                    code: util::shader_code!("ray_query_ao.comp"),
+                    debug_name: Some(String::from("ao_shader")),
+                },
+            )
+            .unwrap(),
+            work_group_size: (WORK_GROUP_SIZE, WORK_GROUP_SIZE, 1),
+            push_constants_size: None,
+            debug_name: Some(String::from("ao_pipeline")),
+        },
+    )
+    .unwrap();
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.build_bottom_level_acceleration_structure(&blas, &scratch, 0);
+    command_buffer.build_top_level_acceleration_structure(&tlas, 1, &scratch, 0, &instance_ptrs, 0);
+    command_buffer.compute_pass(&pipeline, Some("ray_query_ao"), |pass| {
+        pass.bind_sets(0, vec![&set]);
+        pass.dispatch(
+            WIDTH.div_ceil(WORK_GROUP_SIZE),
+            HEIGHT.div_ceil(WORK_GROUP_SIZE),
+            1,
+        );
+    });
+
+    context
+        .main()
+        .submit(Some("ray_query_ao"), command_buffer)
+        .wait_on(None);
+}
+
+/// Creates a buffer the GPU reads while building acceleration structures.
+fn build_input_buffer(context: &Context, name: &str, data: &[u8]) -> Buffer {
+    let mut buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_READ,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = buffer.write(0).unwrap();
+    view.copy_from_slice(data);
+    std::mem::drop(view);
+    buffer
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
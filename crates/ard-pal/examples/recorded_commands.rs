@@ -0,0 +1,227 @@
+/// This example compares the CPU cost of submitting a heavy pass that is identical every frame
+/// when it is recorded from scratch each frame against when it is recorded once and resubmitted.
+/// Recorded commands let the backend skip sorting the commands after the first few submissions.
+/// It runs without a window.
+///
+/// The pass is made of many compute passes, each writing to one of a handful of buffers, so
+/// sorting has to work out the dependencies between them.
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ard_pal::prelude::*;
+use winit::raw_window_handle::DisplayHandle;
+
+#[path = "./util.rs"]
+mod util;
+
+/// Number of compute passes in the static pass.
+const PASS_COUNT: usize = 2_000;
+
+/// Number of buffers the compute passes write to.
+const SET_COUNT: usize = 16;
+
+/// Number of frames to average timings over.
+const FRAME_COUNT: u32 = 100;
+
+const POINT_COUNT: usize = 256;
+
+const WORK_GROUP_SIZE: u32 = 64;
+
+fn main() {
+    let backend = util::create_backend("Recorded Commands", None::<&DisplayHandle>, false);
+    let context = Context::new(backend);
+
+    let (pipeline, sets) = create_resources(&context);
+
+    // Record the pass every frame
+    let immediate = benchmark(|| {
+        let mut command_buffer = context.main().command_buffer();
+        for i in 0..PASS_COUNT {
+            command_buffer.compute_pass(&pipeline, None, |pass| {
+                pass.bind_sets(0, vec![&sets[i % SET_COUNT]]);
+                pass.dispatch((POINT_COUNT as u32).div_ceil(WORK_GROUP_SIZE), 1, 1);
+            });
+        }
+        context.main().submit(Some("static_pass"), command_buffer)
+    });
+
+    // Record the pass once
+    let recorded = context.main().record(|commands, resources| {
+        let sets: Vec<_> = sets
+            .iter()
+            .map(|set| resources.retain(set.clone()))
+            .collect();
+
+        for i in 0..PASS_COUNT {
+            commands.compute_pass(&pipeline, None, |pass| {
+                pass.bind_sets(0, vec![sets[i % SET_COUNT]]);
+                pass.dispatch((POINT_COUNT as u32).div_ceil(WORK_GROUP_SIZE), 1, 1);
+            });
+        }
+    });
+    let resubmitted = benchmark(|| {
+        context
+            .main()
+            .submit_recorded(Some("static_pass"), &recorded)
+    });
+
+    println!("{PASS_COUNT} compute passes, averaged over {FRAME_COUNT} frames:");
+    println!("  recorded every frame: {immediate:?}");
+    println!("  recorded once:        {resubmitted:?}");
+    println!(
+        "  speedup:              {:.2}x",
+        immediate.as_secs_f64() / resubmitted.as_secs_f64()
+    );
+}
+
+/// Submits `FRAME_COUNT` frames using `submit`, returning the average time spent recording and
+/// submitting a frame.
+fn benchmark(submit: impl Fn() -> Job) -> Duration {
+    let mut total = Duration::ZERO;
+
+    for _ in 0..FRAME_COUNT {
+        let start = Instant::now();
+        let job = submit();
+        total += start.elapsed();
+        job.wait_on(None);
+    }
+
+    total / FRAME_COUNT
+}
+
+/// Creates the pipeline used by every compute pass and the sets for each buffer written to.
+fn create_resources(context: &Context) -> (ComputePipeline, Vec<Arc<DescriptorSet>>) {
+    let positions = vec![[1.0_f32, 2.0, 3.0, 1.0]; POINT_COUNT];
+    let data: &[u8] = bytemuck::cast_slice(&positions);
+
+    let mut position_buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::UNIFORM_TEXEL_BUFFER,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("positions")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = position_buffer.write(0).unwrap();
+    view[..data.len()].copy_from_slice(data);
+    std::mem::drop(view);
+
+    let layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![
+                DescriptorBinding {
+                    binding: 0,
+                    count: 1,
+                    stage: ShaderStage::Compute,
+                    ty: DescriptorType::UniformTexelBuffer,
+                    flags: DescriptorBindingFlags::empty(),
+                },
+                DescriptorBinding {
+                    binding: 1,
+                    count: 1,
+                    stage: ShaderStage::Compute,
+                    ty: DescriptorType::StorageTexelBuffer(AccessType::ReadWrite),
+                    flags: DescriptorBindingFlags::empty(),
+                },
+            ],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let distance_size = (POINT_COUNT * std::mem::size_of::<f32>()) as u64;
+    let sets = (0..SET_COUNT)
+        .map(|i| {
+            // The set keeps the buffer alive once it's bound
+            let distance_buffer = Buffer::new(
+                context.clone(),
+                BufferCreateInfo {
+                    size: distance_size,
+                    array_elements: 1,
+                    buffer_usage: BufferUsage::STORAGE_TEXEL_BUFFER,
+                    memory_usage: MemoryUsage::GpuOnly,
+                    queue_types: QueueTypes::MAIN,
+                    sharing_mode: SharingMode::Exclusive,
+                    debug_name: Some(format!("distances_{i}")),
+                    memory_fallback: MemoryFallback::None,
+                },
+            )
+            .unwrap();
+
+            let mut set = DescriptorSet::new(
+                context.clone(),
+                DescriptorSetCreateInfo {
+                    layout: layout.clone(),
+                    debug_name: Some(format!("distances_set_{i}")),
+                    variable_count: None,
+                },
+            )
+            .unwrap();
+
+            set.update(&[
+                DescriptorSetUpdate {
+                    binding: 0,
+                    array_element: 0,
+                    value: DescriptorValue::UniformTexelBuffer(
+                        BufferView::new(BufferViewCreateInfo {
+                            buffer: &position_buffer,
+                            array_element: 0,
+                            offset: 0,
+                            range: data.len() as u64,
+                            format: Format::Rgba32SFloat,
+                        })
+                        .unwrap(),
+                    ),
+                },
+                DescriptorSetUpdate {
+                    binding: 1,
+                    array_element: 0,
+                    value: DescriptorValue::StorageTexelBuffer(
+                        BufferView::new(BufferViewCreateInfo {
+                            buffer: &distance_buffer,
+                            array_element: 0,
+                            offset: 0,
+                            range: distance_size,
+                            format: Format::R32SFloat,
+                        })
+                        .unwrap(),
+                    ),
+                },
+            ]);
+
+            Arc::new(set)
+        })
+        .collect();
+
+    let pipeline = ComputePipeline::new(
+        context.clone(),
+        ComputePipelineCreateInfo {
+            layouts: vec![layout],
+            module: Shader::new(
+                context.clone(),
+                ShaderCreateInfo {
+                    code: util::shader_code!("texel_buffer.comp"),
+                    debug_name: Some(String::from("texel_buffer_shader")),
+                },
+            )
+            .unwrap(),
+            work_group_size: (WORK_GROUP_SIZE, 1, 1),
+            push_constants_size: None,
+            debug_name: Some(String::from("recorded_commands_pipeline")),
+        },
+    )
+    .unwrap();
+
+    (pipeline, sets)
+}
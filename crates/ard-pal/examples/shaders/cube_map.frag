@@ -0,0 +1,12 @@
+#version 450 core
+
+layout(location = 0) out vec4 OUT_COLOR;
+layout(location = 0) in vec4 IN_COLOR;
+
+layout(set = 0, binding = 0) uniform samplerCube cube_map;
+
+void main() {
+    // Use the interpolated vertex color as a direction so every face is sampled
+    vec3 dir = normalize(IN_COLOR.rgb * 2.0 - 1.0);
+    OUT_COLOR = vec4(textureLod(cube_map, dir, IN_COLOR.r * 6.0).rgb, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 450 core
+
+layout(location = 0) out vec4 OUT_COLOR;
+
+layout(push_constant) uniform PushConstants {
+    uint face;
+};
+
+// Fullscreen triangle colored uniquely for the face being rendered. Must match
+// `face_color_multiview.vert`.
+void main() {
+    vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+    OUT_COLOR = vec4(float(face & 1), float((face >> 1) & 1), float(face) / 5.0, 1.0);
+}
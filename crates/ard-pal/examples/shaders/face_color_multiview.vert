@@ -0,0 +1,13 @@
+#version 450 core
+#extension GL_EXT_multiview : require
+
+layout(location = 0) out vec4 OUT_COLOR;
+
+// Fullscreen triangle colored uniquely for the view being rendered. Must match
+// `face_color.vert`.
+void main() {
+    uint face = gl_ViewIndex;
+    vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+    OUT_COLOR = vec4(float(face & 1), float((face >> 1) & 1), float(face) / 5.0, 1.0);
+}
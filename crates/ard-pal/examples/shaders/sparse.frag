@@ -0,0 +1,12 @@
+#version 450 core
+
+layout(location = 0) out vec4 OUT_COLOR;
+layout(location = 0) in vec4 IN_COLOR;
+
+layout(set = 0, binding = 0) uniform sampler2D sparse_texture;
+
+void main() {
+    // Use the interpolated vertex color as texture coordinates so resident and non-resident
+    // pages are both sampled
+    OUT_COLOR = vec4(textureLod(sparse_texture, IN_COLOR.rg, 0.0).rgb, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 450 core
+
+layout(location = 0) out vec4 OUT_COLOR;
+layout(location = 0) in vec4 IN_COLOR;
+
+layout(set = 0, binding = 0) uniform sampler3D volume;
+
+void main() {
+    // Use the interpolated vertex color as texture coordinates so every depth of the volume
+    // is sampled
+    OUT_COLOR = vec4(texture(volume, IN_COLOR.rgb).rgb, 1.0);
+}
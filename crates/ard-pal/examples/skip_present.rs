@@ -0,0 +1,106 @@
+/// This example acquires surface images and drops them without rendering to or presenting them,
+/// which can happen when a window is minimized or when rendering fails. The backend must give
+/// the images back to the surface, so this should run without panicking or producing validation
+/// errors until the window closes itself.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Number of frames to skip presentation for before exiting.
+const FRAME_COUNT: usize = 120;
+
+#[derive(Default)]
+struct App {
+    state: Option<State>,
+    frames: usize,
+}
+
+struct State {
+    surface: Surface,
+    _context: Context,
+    window: Window,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.state.is_some() {
+            return;
+        }
+
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Skip Present")
+                    .with_inner_size(PhysicalSize::new(1280, 720)),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Skip Present", Some(&window), true);
+
+        let context = Context::new(backend);
+
+        let surface = Surface::new(
+            context.clone(),
+            SurfaceCreateInfo {
+                config: SurfaceConfiguration {
+                    width: 1280,
+                    height: 720,
+                    present_mode: PresentMode::Fifo,
+                    preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
+                },
+                window: WindowSource::Reference(&window),
+                debug_name: Some(String::from("surface")),
+            },
+        )
+        .unwrap();
+
+        self.state = Some(State {
+            surface,
+            _context: context,
+            window,
+        });
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let state = match &mut self.state {
+            Some(state) => state,
+            None => return,
+        };
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::RedrawRequested => {
+                // Acquire an image and immediately drop it without rendering or presenting
+                let surface_image = state.surface.acquire_image().unwrap();
+                std::mem::drop(surface_image);
+
+                self.frames += 1;
+                if self.frames >= FRAME_COUNT {
+                    println!("skipped presentation for {FRAME_COUNT} frames");
+                    event_loop.exit();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _: &ActiveEventLoop) {
+        if let Some(state) = &self.state {
+            state.window.request_redraw();
+        }
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.run_app(&mut App::default()).unwrap();
+}
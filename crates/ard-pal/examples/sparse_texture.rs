@@ -0,0 +1,402 @@
+/// This example streams pages of a sparse texture in and out on the transfer queue while the main
+/// queue samples it, and should run without any validation errors.
+///
+/// A single page of the base mip is made resident and filled, the texture is sampled, and then
+/// the page is evicted and a different page is made resident in its place.
+use ard_pal::prelude::*;
+use ordered_float::NotNan;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Width and height of the sparse texture.
+const TEXTURE_SIZE: u32 = 2048;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Sparse Texture")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Sparse Texture", Some(&window), true);
+
+        let context = Context::new(backend);
+        if context.properties().features.sparse_residency {
+            run(context);
+            println!("sparse pages bound, sampled, and evicted");
+        } else {
+            println!("sparse textures are not supported by this device");
+        }
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+struct Scene {
+    context: Context,
+    texture: Texture,
+    pipeline: GraphicsPipeline,
+    set: DescriptorSet,
+    buffers: util::MeshBuffers,
+    target: Texture,
+}
+
+fn run(context: Context) {
+    let mip_levels = (TEXTURE_SIZE.ilog2() + 1) as usize;
+    let (page_width, page_height, _) = context
+        .properties()
+        .sparse
+        .granularity(Format::Rgba8Unorm)
+        .expect("format supports sparse residency");
+
+    let texture = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            depth: 1,
+            array_elements: 1,
+            mip_levels,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::SAMPLED | TextureUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
+            sharing_mode: SharingMode::Concurrent,
+            debug_name: Some(String::from("sparse_texture")),
+            sparse: true,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    // Every page is filled with the same data
+    let page_bytes = (page_width * page_height * 4) as usize;
+    let staging = Buffer::new_staging(
+        context.clone(),
+        QueueType::Transfer,
+        Some(String::from("page_staging")),
+        &[255u8, 128, 0, 255].repeat(page_bytes / 4),
+    )
+    .unwrap();
+
+    let scene = Scene::new(context.clone(), texture);
+
+    // Make the first page and the mip tail resident
+    let first_page = SparsePageRegion {
+        array_element: 0,
+        mip_level: 0,
+        offset: (0, 0, 0),
+        extent: (page_width, page_height, 1),
+    };
+    let mip_tail = SparsePageRegion {
+        array_element: 0,
+        mip_level: mip_levels - 1,
+        offset: (0, 0, 0),
+        extent: (1, 1, 1),
+    };
+
+    let mut command_buffer = context.transfer().command_buffer();
+    command_buffer.bind_sparse_pages(&scene.texture, vec![first_page, mip_tail]);
+    upload_page(&mut command_buffer, &scene.texture, &staging, first_page);
+    context
+        .transfer()
+        .submit(Some("bind_first_page"), command_buffer);
+    scene.draw(Some("sample_first_page"));
+
+    // Swap the first page for the page diagonal to it
+    let second_page = SparsePageRegion {
+        offset: (page_width, page_height, 0),
+        ..first_page
+    };
+
+    let mut command_buffer = context.transfer().command_buffer();
+    command_buffer.unbind_sparse_pages(&scene.texture, vec![first_page]);
+    command_buffer.bind_sparse_pages(&scene.texture, vec![second_page]);
+    upload_page(&mut command_buffer, &scene.texture, &staging, second_page);
+    context
+        .transfer()
+        .submit(Some("swap_pages"), command_buffer);
+    scene.draw(Some("sample_second_page"));
+}
+
+fn upload_page<'a>(
+    command_buffer: &mut CommandBuffer<'a>,
+    texture: &'a Texture,
+    staging: &'a Buffer,
+    page: SparsePageRegion,
+) {
+    command_buffer.copy_buffer_to_texture(
+        texture,
+        staging,
+        BufferTextureCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            buffer_array_element: 0,
+            texture_offset: page.offset,
+            texture_extent: page.extent,
+            texture_mip_level: page.mip_level,
+            texture_array_element: page.array_element,
+        },
+    );
+}
+
+impl Scene {
+    fn new(context: Context, texture: Texture) -> Self {
+        let buffers = util::create_triangle(&context);
+
+        let mut command_buffer = context.main().command_buffer();
+        command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+            src: &buffers.index_staging,
+            src_array_element: 0,
+            src_offset: 0,
+            dst: &buffers.index,
+            dst_array_element: 0,
+            dst_offset: 0,
+            len: buffers.index.size(),
+        });
+        command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+            src: &buffers.vertex_staging,
+            src_array_element: 0,
+            src_offset: 0,
+            dst: &buffers.vertex,
+            dst_array_element: 0,
+            dst_offset: 0,
+            len: buffers.vertex.size(),
+        });
+        context
+            .main()
+            .submit(Some("staging_upload"), command_buffer)
+            .wait_on(None);
+
+        let layout = DescriptorSetLayout::new(
+            context.clone(),
+            DescriptorSetLayoutCreateInfo {
+                bindings: vec![DescriptorBinding {
+                    binding: 0,
+                    count: 1,
+                    stage: ShaderStage::Fragment,
+                    ty: DescriptorType::Texture,
+                    flags: DescriptorBindingFlags::empty(),
+                }],
+                push: false,
+                debug_name: None,
+            },
+        )
+        .unwrap();
+
+        let mut set = DescriptorSet::new(
+            context.clone(),
+            DescriptorSetCreateInfo {
+                layout: layout.clone(),
+                debug_name: Some(String::from("sparse_set")),
+                variable_count: None,
+            },
+        )
+        .unwrap();
+
+        set.update(&[DescriptorSetUpdate {
+            binding: 0,
+            array_element: 0,
+            value: DescriptorValue::Texture {
+                texture: &texture,
+                array_element: 0,
+                sampler: Sampler {
+                    min_filter: Filter::Linear,
+                    mag_filter: Filter::Linear,
+                    mipmap_filter: Filter::Linear,
+                    address_u: SamplerAddressMode::ClampToEdge,
+                    address_v: SamplerAddressMode::ClampToEdge,
+                    address_w: SamplerAddressMode::ClampToEdge,
+                    anisotropy: None,
+                    compare: None,
+                    reduction_mode: None,
+                    min_lod: NotNan::new(0.0).unwrap(),
+                    max_lod: None,
+                    unnormalize_coords: false,
+                    border_color: None,
+                },
+                base_mip: 0,
+                mip_count: texture.mip_count(),
+                aspect: None,
+            },
+        }]);
+
+        let pipeline = GraphicsPipeline::new(
+            context.clone(),
+            GraphicsPipelineCreateInfo {
+                stages: ShaderStages::Traditional {
+                    vertex: Shader::new(
+                        context.clone(),
+                        ShaderCreateInfo {
+                            code: util::shader_code!("triangle.vert"),
+                            debug_name: Some(String::from("vertex_shader")),
+                        },
+                    )
+                    .unwrap(),
+                    fragment: Some(
+                        Shader::new(
+                            context.clone(),
+                            ShaderCreateInfo {
+                                code: util::shader_code!("sparse.frag"),
+                                debug_name: Some(String::from("fragment_shader")),
+                            },
+                        )
+                        .unwrap(),
+                    ),
+                },
+                layouts: vec![layout],
+                vertex_input: VertexInputState {
+                    attributes: vec![
+                        VertexInputAttribute {
+                            location: 0,
+                            binding: 0,
+                            format: Format::Rgba32SFloat,
+                            offset: 0,
+                        },
+                        VertexInputAttribute {
+                            location: 1,
+                            binding: 0,
+                            format: Format::Rgba32SFloat,
+                            offset: 16,
+                        },
+                    ],
+                    bindings: vec![VertexInputBinding {
+                        binding: 0,
+                        stride: 32,
+                        input_rate: VertexInputRate::Vertex,
+                    }],
+                    topology: PrimitiveTopology::TriangleList,
+                },
+                rasterization: RasterizationState::default(),
+                depth_stencil: None,
+                color_blend: ColorBlendState {
+                    attachments: vec![ColorBlendAttachment {
+                        write_mask: ColorComponents::R | ColorComponents::G | ColorComponents::B,
+                        ..Default::default()
+                    }],
+                },
+                push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
+                debug_name: Some(String::from("sparse_pipeline")),
+                dynamic_states: DynamicStates::empty(),
+            },
+        )
+        .unwrap();
+
+        let target = Texture::new(
+            context.clone(),
+            TextureCreateInfo {
+                format: Format::Rgba8Unorm,
+                ty: TextureType::Type2D,
+                width: WIDTH,
+                height: HEIGHT,
+                depth: 1,
+                array_elements: 1,
+                mip_levels: 1,
+                sample_count: MultiSamples::Count1,
+                texture_usage: TextureUsage::COLOR_ATTACHMENT,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some(String::from("target")),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        Self {
+            context,
+            texture,
+            pipeline,
+            set,
+            buffers,
+            target,
+        }
+    }
+
+    /// Samples the sparse texture in a render pass and waits for it to complete. The main queue
+    /// waits on the transfer queue automatically since the texture was just written.
+    fn draw(&self, debug_name: Option<&str>) {
+        let mut command_buffer = self.context.main().command_buffer();
+        command_buffer.render_pass(
+            RenderPassDescriptor {
+                color_attachments: vec![ColorAttachment {
+                    dst: ColorAttachmentDestination::Texture {
+                        texture: &self.target,
+                        array_element: 0,
+                        mip_level: 0,
+                    },
+                    load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+                    store_op: StoreOp::Store,
+                    samples: MultiSamples::Count1,
+                }],
+                color_resolve_attachments: Vec::default(),
+                depth_stencil_attachment: None,
+                depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
+            },
+            debug_name,
+            |pass| {
+                pass.bind_pipeline(self.pipeline.clone());
+                pass.bind_sets(0, vec![&self.set]);
+                pass.bind_vertex_buffers(
+                    0,
+                    vec![VertexBind {
+                        buffer: &self.buffers.vertex,
+                        array_element: 0,
+                        offset: 0,
+                    }],
+                );
+                pass.bind_index_buffer(&self.buffers.index, 0, 0, IndexType::U16);
+                pass.draw_indexed(3, 1, 0, 0, 0);
+            },
+        );
+
+        self.context
+            .main()
+            .submit(debug_name, command_buffer)
+            .wait_on(None);
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
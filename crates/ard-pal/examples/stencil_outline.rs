@@ -0,0 +1,376 @@
+/// This example outlines a triangle using the stencil buffer. The triangle is drawn first and
+/// marks the pixels it covers in the stencil buffer. A larger copy of the triangle is then drawn
+/// only where the stencil buffer is unmarked, leaving an outline around the original. The result
+/// is read back to make sure the mask was respected.
+use ard_pal::prelude::*;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+const WIDTH: u32 = 512;
+const HEIGHT: u32 = 512;
+
+/// How much larger the outline is than the triangle.
+const OUTLINE_SCALE: f32 = 1.3;
+
+/// Value written to the stencil buffer where the triangle is drawn.
+const MASK_REFERENCE: u32 = 1;
+
+const FILL_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const OUTLINE_COLOR: [f32; 4] = [1.0, 0.5, 0.0, 1.0];
+
+/// Triangle corners in normalized device coordinates.
+const CORNERS: [[f32; 2]; 3] = [[-0.5, -0.5], [0.5, -0.5], [0.0, 0.5]];
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Stencil Outline")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Stencil Outline", Some(&window), true);
+
+        run(Context::new(backend));
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    let depth_format = [Format::D24UnormS8Uint, Format::D32SfloatS8Uint]
+        .into_iter()
+        .find(|format| {
+            context
+                .properties()
+                .format_support(*format)
+                .contains(FormatSupport::DEPTH_STENCIL_ATTACHMENT)
+        })
+        .expect("no depth stencil format is supported");
+
+    let fill_buffer = build_triangle(&context, "fill_vertices", 1.0, FILL_COLOR);
+    let outline_buffer = build_triangle(&context, "outline_vertices", OUTLINE_SCALE, OUTLINE_COLOR);
+
+    let target = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type2D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::TRANSFER_SRC,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("outline_target")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let depth_stencil = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: depth_format,
+            ty: TextureType::Type2D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("outline_depth_stencil")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let readback = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: (WIDTH * HEIGHT * 4) as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::TRANSFER_DST,
+            memory_usage: MemoryUsage::GpuToCpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("outline_readback")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    // The triangle always passes the stencil test and marks every pixel it covers
+    let fill_pipeline = build_pipeline(
+        &context,
+        "fill_pipeline",
+        StencilOpState {
+            pass_op: StencilOp::Replace,
+            compare_op: CompareOp::Always,
+            reference: MASK_REFERENCE,
+            ..Default::default()
+        },
+    );
+
+    // The outline only passes where the triangle wasn't drawn and leaves the mask untouched
+    let outline_pipeline = build_pipeline(
+        &context,
+        "outline_pipeline",
+        StencilOpState {
+            compare_op: CompareOp::NotEqual,
+            reference: MASK_REFERENCE,
+            write_mask: 0,
+            ..Default::default()
+        },
+    );
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.render_pass(
+        RenderPassDescriptor {
+            color_attachments: vec![ColorAttachment {
+                dst: ColorAttachmentDestination::Texture {
+                    texture: &target,
+                    array_element: 0,
+                    mip_level: 0,
+                },
+                load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+                store_op: StoreOp::Store,
+                samples: MultiSamples::Count1,
+            }],
+            color_resolve_attachments: Vec::default(),
+            // Depth is never tested, so only the stencil aspect needs to be cleared
+            depth_stencil_attachment: Some(DepthStencilAttachment {
+                dst: DepthStencilAttachmentDestination::Texture {
+                    texture: &depth_stencil,
+                    array_element: 0,
+                    mip_level: 0,
+                },
+                load_op: LoadOp::DontCare,
+                store_op: StoreOp::DontCare,
+                samples: MultiSamples::Count1,
+                stencil_load_op: LoadOp::Clear(ClearColor::D32S32(0.0, 0)),
+                stencil_store_op: StoreOp::DontCare,
+            }),
+            depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
+        },
+        Some("stencil_outline"),
+        |pass| {
+            pass.bind_pipeline(fill_pipeline.clone());
+            pass.bind_vertex_buffers(
+                0,
+                vec![VertexBind {
+                    buffer: &fill_buffer,
+                    array_element: 0,
+                    offset: 0,
+                }],
+            );
+            pass.draw(3, 1, 0, 0);
+
+            pass.bind_pipeline(outline_pipeline.clone());
+            pass.bind_vertex_buffers(
+                0,
+                vec![VertexBind {
+                    buffer: &outline_buffer,
+                    array_element: 0,
+                    offset: 0,
+                }],
+            );
+            pass.draw(3, 1, 0, 0);
+        },
+    );
+    command_buffer.copy_texture_to_buffer(
+        &readback,
+        &target,
+        BufferTextureCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            buffer_array_element: 0,
+            texture_offset: (0, 0, 0),
+            texture_extent: (WIDTH, HEIGHT, 1),
+            texture_mip_level: 0,
+            texture_array_element: 0,
+        },
+    );
+
+    context
+        .main()
+        .submit(Some("stencil_outline"), command_buffer)
+        .wait_on(None);
+
+    let view = readback.read(0).unwrap();
+    let pixel = |x: f32, y: f32| {
+        // Positive Y is up in normalized device coordinates
+        let col = (((x + 1.0) * 0.5) * WIDTH as f32) as usize;
+        let row = (((1.0 - y) * 0.5) * HEIGHT as f32) as usize;
+        let idx = (row * WIDTH as usize + col) * 4;
+        [view[idx], view[idx + 1], view[idx + 2], view[idx + 3]]
+    };
+    let to_rgba8 = |color: [f32; 4]| color.map(|c| (c * 255.0).round() as u8);
+
+    // Center of the triangle is the fill, just below its bottom edge is the outline, and the
+    // corner of the image isn't covered by either
+    let centroid = CORNERS.iter().map(|c| c[1]).sum::<f32>() / 3.0;
+    assert_eq!(pixel(0.0, centroid), to_rgba8(FILL_COLOR));
+    assert_eq!(pixel(0.0, -0.55), to_rgba8(OUTLINE_COLOR));
+    assert_eq!(pixel(-0.9, 0.9), [0, 0, 0, 0]);
+
+    println!("outlined triangle using {depth_format:?}");
+}
+
+/// Creates a vertex buffer with a copy of the triangle scaled about its centroid.
+fn build_triangle(context: &Context, name: &str, scale: f32, color: [f32; 4]) -> Buffer {
+    let centroid = CORNERS.iter().fold([0.0; 2], |acc, c| {
+        [acc[0] + c[0] / 3.0, acc[1] + c[1] / 3.0]
+    });
+
+    // Position followed by color, matching `triangle.vert`
+    let mut vertices = Vec::with_capacity(CORNERS.len() * 8);
+    for corner in CORNERS {
+        vertices.extend_from_slice(&[
+            centroid[0] + (corner[0] - centroid[0]) * scale,
+            centroid[1] + (corner[1] - centroid[1]) * scale,
+            0.0,
+            1.0,
+        ]);
+        vertices.extend_from_slice(&color);
+    }
+    let data: &[u8] = bytemuck::cast_slice(&vertices);
+
+    let mut buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::VERTEX_BUFFER,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from(name)),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = buffer.write(0).unwrap();
+    view.copy_from_slice(data);
+    std::mem::drop(view);
+    buffer
+}
+
+/// Creates a pipeline that applies the same stencil rules to both faces.
+fn build_pipeline(context: &Context, name: &str, stencil: StencilOpState) -> GraphicsPipeline {
+    let shader = |code: &[u8], name: &str| {
+        Shader::new(
+            context.clone(),
+            ShaderCreateInfo {
+                code,
+                debug_name: Some(String::from(name)),
+            },
+        )
+        .unwrap()
+    };
+
+    GraphicsPipeline::new(
+        context.clone(),
+        GraphicsPipelineCreateInfo {
+            stages: ShaderStages::Traditional {
+                vertex: shader(util::shader_code!("triangle.vert"), "vertex"),
+                fragment: Some(shader(util::shader_code!("triangle.frag"), "fragment")),
+            },
+            layouts: Vec::default(),
+            vertex_input: VertexInputState {
+                attributes: vec![
+                    VertexInputAttribute {
+                        location: 0,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 0,
+                    },
+                    VertexInputAttribute {
+                        location: 1,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 16,
+                    },
+                ],
+                bindings: vec![VertexInputBinding {
+                    binding: 0,
+                    stride: 32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                topology: PrimitiveTopology::TriangleList,
+            },
+            rasterization: RasterizationState {
+                polygon_mode: PolygonMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::CounterClockwise,
+            },
+            depth_stencil: Some(DepthStencilState {
+                stencil_test: true,
+                stencil_front: stencil,
+                stencil_back: stencil,
+                ..Default::default()
+            }),
+            color_blend: ColorBlendState {
+                attachments: vec![ColorBlendAttachment {
+                    write_mask: ColorComponents::R
+                        | ColorComponents::G
+                        | ColorComponents::B
+                        | ColorComponents::A,
+                    ..Default::default()
+                }],
+            },
+            push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
+            debug_name: Some(String::from(name)),
+            dynamic_states: DynamicStates::empty(),
+        },
+    )
+    .unwrap()
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
@@ -0,0 +1,178 @@
+/// This example reads positions through a texel buffer in a compute shader. It runs without a
+/// window and panics if the results read back from the GPU don't match.
+///
+/// The positions are viewed as `Rgba32SFloat` texels through a uniform texel buffer and the
+/// distance of each from the origin is written to an `R32SFloat` storage texel buffer.
+use ard_pal::prelude::*;
+use winit::raw_window_handle::DisplayHandle;
+
+#[path = "./util.rs"]
+mod util;
+
+const POINT_COUNT: usize = 1000;
+
+const WORK_GROUP_SIZE: u32 = 64;
+
+fn main() {
+    let backend = util::create_backend("Texel Buffer", None::<&DisplayHandle>, true);
+    let context = Context::new(backend);
+
+    let positions: Vec<[f32; 4]> = (0..POINT_COUNT)
+        .map(|i| {
+            let t = i as f32 * 0.1;
+            [t.cos() * t, t.sin() * t, t * 0.5, 1.0]
+        })
+        .collect();
+
+    let distances = run(&context, &positions);
+    for (i, (position, distance)) in positions.iter().zip(distances).enumerate() {
+        let expected =
+            (position[0] * position[0] + position[1] * position[1] + position[2] * position[2])
+                .sqrt();
+        assert!(
+            (distance - expected).abs() <= expected * 1e-5,
+            "distance `{distance}` of point `{i}` should be `{expected}`"
+        );
+    }
+
+    println!("computed the distance of {POINT_COUNT} points through texel buffers");
+}
+
+/// Computes the distance of every position from the origin on the GPU.
+fn run(context: &Context, positions: &[[f32; 4]]) -> Vec<f32> {
+    let data: &[u8] = bytemuck::cast_slice(positions);
+
+    let mut position_buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: data.len() as u64,
+            array_elements: 1,
+            buffer_usage: BufferUsage::UNIFORM_TEXEL_BUFFER,
+            memory_usage: MemoryUsage::CpuToGpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("positions")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let mut view = position_buffer.write(0).unwrap();
+    view[..data.len()].copy_from_slice(data);
+    std::mem::drop(view);
+
+    let distance_size = (positions.len() * std::mem::size_of::<f32>()) as u64;
+    let distance_buffer = Buffer::new(
+        context.clone(),
+        BufferCreateInfo {
+            size: distance_size,
+            array_elements: 1,
+            buffer_usage: BufferUsage::STORAGE_TEXEL_BUFFER,
+            memory_usage: MemoryUsage::GpuToCpu,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("distances")),
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![
+                DescriptorBinding {
+                    binding: 0,
+                    count: 1,
+                    stage: ShaderStage::Compute,
+                    ty: DescriptorType::UniformTexelBuffer,
+                    flags: DescriptorBindingFlags::empty(),
+                },
+                DescriptorBinding {
+                    binding: 1,
+                    count: 1,
+                    stage: ShaderStage::Compute,
+                    ty: DescriptorType::StorageTexelBuffer(AccessType::ReadWrite),
+                    flags: DescriptorBindingFlags::empty(),
+                },
+            ],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let mut set = DescriptorSet::new(
+        context.clone(),
+        DescriptorSetCreateInfo {
+            layout: layout.clone(),
+            debug_name: Some(String::from("texel_buffer_set")),
+            variable_count: None,
+        },
+    )
+    .unwrap();
+
+    set.update(&[
+        DescriptorSetUpdate {
+            binding: 0,
+            array_element: 0,
+            value: DescriptorValue::UniformTexelBuffer(
+                BufferView::new(BufferViewCreateInfo {
+                    buffer: &position_buffer,
+                    array_element: 0,
+                    offset: 0,
+                    range: data.len() as u64,
+                    format: Format::Rgba32SFloat,
+                })
+                .unwrap(),
+            ),
+        },
+        DescriptorSetUpdate {
+            binding: 1,
+            array_element: 0,
+            value: DescriptorValue::StorageTexelBuffer(
+                BufferView::new(BufferViewCreateInfo {
+                    buffer: &distance_buffer,
+                    array_element: 0,
+                    offset: 0,
+                    range: distance_size,
+                    format: Format::R32SFloat,
+                })
+                .unwrap(),
+            ),
+        },
+    ]);
+
+    let pipeline = ComputePipeline::new(
+        context.clone(),
+        ComputePipelineCreateInfo {
+            layouts: vec![layout],
+            module: Shader::new(
+                context.clone(),
+                ShaderCreateInfo {
+                    code: util::shader_code!("texel_buffer.comp"),
+                    debug_name: Some(String::from("texel_buffer_shader")),
+                },
+            )
+            .unwrap(),
+            work_group_size: (WORK_GROUP_SIZE, 1, 1),
+            push_constants_size: None,
+            debug_name: Some(String::from("texel_buffer_pipeline")),
+        },
+    )
+    .unwrap();
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.compute_pass(&pipeline, Some("distances"), |pass| {
+        pass.bind_sets(0, vec![&set]);
+        pass.dispatch((positions.len() as u32).div_ceil(WORK_GROUP_SIZE), 1, 1);
+    });
+
+    context
+        .main()
+        .submit(Some("texel_buffer"), command_buffer)
+        .wait_on(None);
+
+    let view = distance_buffer.read(0).unwrap();
+    bytemuck::cast_slice(&view[..distance_size as usize]).to_vec()
+}
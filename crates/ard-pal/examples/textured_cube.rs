@@ -10,7 +10,6 @@ mod util;
 use ard_pal::prelude::*;
 use glam::{Mat4, Vec3};
 use ordered_float::NotNan;
-use vulkan::{VulkanBackend, VulkanBackendCreateInfo};
 use winit::{
     dpi::PhysicalSize,
     event::{Event, WindowEvent},
@@ -18,10 +17,10 @@ use winit::{
     window::WindowBuilder,
 };
 
-const TRIANGLE_VERTEX_BIN: &'static [u8] = include_bytes!("./shaders/triangle.vert.spv");
-const TRIANGLE_FRAGMENT_BIN: &'static [u8] = include_bytes!("./shaders/triangle.frag.spv");
-const CUBE_VERTEX_BIN: &'static [u8] = include_bytes!("./shaders/cube.vert.spv");
-const CUBE_FRAGMENT_BIN: &'static [u8] = include_bytes!("./shaders/cube.frag.spv");
+const TRIANGLE_VERTEX_BIN: &'static [u8] = util::shader_code!("triangle.vert");
+const TRIANGLE_FRAGMENT_BIN: &'static [u8] = util::shader_code!("triangle.frag");
+const CUBE_VERTEX_BIN: &'static [u8] = util::shader_code!("cube.vert");
+const CUBE_FRAGMENT_BIN: &'static [u8] = util::shader_code!("cube.frag");
 
 fn main() {
     let event_loop = EventLoop::new();
@@ -31,13 +30,7 @@ fn main() {
         .build(&event_loop)
         .unwrap();
 
-    let backend = VulkanBackend::new(VulkanBackendCreateInfo {
-        app_name: String::from("Textured Cube"),
-        engine_name: String::from("pal"),
-        window: &window,
-        debug: true,
-    })
-    .unwrap();
+    let backend = util::create_backend("Textured Cube", Some(&window), true);
 
     let context = Context::new(backend);
 
@@ -48,7 +41,7 @@ fn main() {
                 width: 1280,
                 height: 720,
                 present_mode: PresentMode::Fifo,
-                format: Format::Bgra8Unorm,
+                preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
             },
             window: &window,
             debug_name: Some(String::from("surface")),
@@ -174,7 +167,9 @@ fn main() {
                     }],
                 },
                 push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("triangle_graphics_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap()
@@ -197,6 +192,9 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("triangle_texture")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -211,6 +209,7 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("uniform_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -226,14 +225,18 @@ fn main() {
                         count: 1,
                         stage: ShaderStage::Fragment,
                         ty: DescriptorType::Texture,
+                        flags: DescriptorBindingFlags::empty(),
                     },
                     DescriptorBinding {
                         binding: 1,
                         count: 1,
                         stage: ShaderStage::Vertex,
                         ty: DescriptorType::UniformBuffer,
+                        flags: DescriptorBindingFlags::empty(),
                     },
                 ],
+                push: false,
+                debug_name: None,
             },
         )
         .unwrap();
@@ -243,6 +246,7 @@ fn main() {
             DescriptorSetCreateInfo {
                 layout: layout.clone(),
                 debug_name: Some(String::from("cube_set")),
+                variable_count: None,
             },
         )
         .unwrap();
@@ -264,6 +268,7 @@ fn main() {
                         address_w: SamplerAddressMode::ClampToEdge,
                         anisotropy: None,
                         compare: None,
+                        reduction_mode: None,
                         min_lod: NotNan::new(0.0).unwrap(),
                         max_lod: None,
                         unnormalize_coords: false,
@@ -271,6 +276,7 @@ fn main() {
                     },
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -343,6 +349,9 @@ fn main() {
                     depth_compare: CompareOp::Less,
                     min_depth: 0.0,
                     max_depth: 1.0,
+                    stencil_test: false,
+                    stencil_front: StencilOpState::default(),
+                    stencil_back: StencilOpState::default(),
                 }),
                 color_blend: ColorBlendState {
                     attachments: vec![ColorBlendAttachment {
@@ -351,7 +360,9 @@ fn main() {
                     }],
                 },
                 push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("cube_graphics_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -376,6 +387,9 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("depth_buffer")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -447,6 +461,8 @@ fn main() {
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_attachment: None,
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     None,
                     |pass| {
@@ -483,8 +499,12 @@ fn main() {
                             load_op: LoadOp::Clear(ClearColor::D32S32(1.0, 0)),
                             store_op: StoreOp::DontCare,
                             samples: MultiSamples::Count1,
+                            stencil_load_op: LoadOp::DontCare,
+                            stencil_store_op: StoreOp::DontCare,
                         }),
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     None,
                     |pass| {
@@ -513,7 +533,10 @@ fn main() {
                                 width: dims.width,
                                 height: dims.height,
                                 present_mode: PresentMode::Fifo,
-                                format: Format::Bgra8Unorm,
+                                preferred_formats: vec![(
+                                    Format::Bgra8Unorm,
+                                    ColorSpace::SrgbNonlinear,
+                                )],
                             })
                             .unwrap();
                     }
@@ -1,7 +1,6 @@
 /// This example demonstrates how to draw a simple triangle, including the use of staging buffers
 /// and the async transfer queue.
 use ard_pal::prelude::*;
-use vulkan::{VulkanBackend, VulkanBackendCreateInfo};
 use winit::{
     dpi::PhysicalSize,
     event::{Event, WindowEvent},
@@ -20,13 +19,7 @@ fn main() {
         .build(&event_loop)
         .unwrap();
 
-    let backend = VulkanBackend::new(VulkanBackendCreateInfo {
-        app_name: String::from("Triangle"),
-        engine_name: String::from("pal"),
-        window: &window,
-        debug: true,
-    })
-    .unwrap();
+    let backend = util::create_backend("Triangle", Some(&window), true);
 
     let context = Context::new(backend);
 
@@ -37,7 +30,7 @@ fn main() {
                 width: 1280,
                 height: 720,
                 present_mode: PresentMode::Fifo,
-                format: Format::Bgra8Unorm,
+                preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
             },
             window: &window,
             debug_name: Some(String::from("surface")),
@@ -84,7 +77,7 @@ fn main() {
     let vertex_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/triangle.vert.spv"),
+            code: util::shader_code!("triangle.vert"),
             debug_name: Some(String::from("vertex_shader")),
         },
     )
@@ -93,7 +86,7 @@ fn main() {
     let fragment_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/triangle.frag.spv"),
+            code: util::shader_code!("triangle.frag"),
             debug_name: Some(String::from("fragment_shader")),
         },
     )
@@ -144,7 +137,9 @@ fn main() {
                 }],
             },
             push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
             debug_name: Some(String::from("graphics_pipeline")),
+            dynamic_states: DynamicStates::empty(),
         },
     )
     .unwrap();
@@ -178,6 +173,8 @@ fn main() {
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_attachment: None,
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     None,
                     |pass| {
@@ -210,7 +207,10 @@ fn main() {
                                 width: dims.width,
                                 height: dims.height,
                                 present_mode: PresentMode::Fifo,
-                                format: Format::Bgra8Unorm,
+                                preferred_formats: vec![(
+                                    Format::Bgra8Unorm,
+                                    ColorSpace::SrgbNonlinear,
+                                )],
                             })
                             .unwrap();
                     }
@@ -1,8 +1,10 @@
 use ard_pal::prelude::*;
+
+#[path = "./util.rs"]
+mod util;
 /// This example demonstrates how to use uniform buffers, including writing data directly from the
 /// CPU.
 use bytemuck::{Pod, Zeroable};
-use vulkan::{VulkanBackend, VulkanBackendCreateInfo};
 use winit::{
     dpi::PhysicalSize,
     event::{Event, WindowEvent},
@@ -27,13 +29,7 @@ fn main() {
         .build(&event_loop)
         .unwrap();
 
-    let backend = VulkanBackend::new(VulkanBackendCreateInfo {
-        app_name: String::from("Uniform Buffer"),
-        engine_name: String::from("pal"),
-        window: &window,
-        debug: true,
-    })
-    .unwrap();
+    let backend = util::create_backend("Uniform Buffer", Some(&window), true);
 
     let context = Context::new(backend);
 
@@ -44,7 +40,7 @@ fn main() {
                 width: 1280,
                 height: 720,
                 present_mode: PresentMode::Fifo,
-                format: Format::Bgra8Unorm,
+                preferred_formats: vec![(Format::Bgra8Unorm, ColorSpace::SrgbNonlinear)],
             },
             window: &window,
             debug_name: Some(String::from("surface")),
@@ -77,6 +73,7 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("vertex_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -101,6 +98,7 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("index_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -142,6 +140,7 @@ fn main() {
             queue_types: QueueTypes::MAIN,
             sharing_mode: SharingMode::Exclusive,
             debug_name: Some(String::from("uniform_buffer")),
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -155,7 +154,10 @@ fn main() {
                 ty: DescriptorType::UniformBuffer,
                 count: 1,
                 stage: ShaderStage::Vertex,
+                flags: DescriptorBindingFlags::empty(),
             }],
+            push: false,
+            debug_name: None,
         },
     )
     .unwrap();
@@ -166,6 +168,7 @@ fn main() {
         DescriptorSetCreateInfo {
             layout: layout.clone(),
             debug_name: Some(String::from("uniform_compute_set")),
+            variable_count: None,
         },
     )
     .unwrap();
@@ -184,7 +187,7 @@ fn main() {
     let vertex_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/uniform_buffer.vert.spv"),
+            code: util::shader_code!("uniform_buffer.vert"),
             debug_name: Some(String::from("vertex_shader")),
         },
     )
@@ -193,7 +196,7 @@ fn main() {
     let fragment_shader = Shader::new(
         context.clone(),
         ShaderCreateInfo {
-            code: include_bytes!("./shaders/triangle.frag.spv"),
+            code: util::shader_code!("triangle.frag"),
             debug_name: Some(String::from("fragment_shader")),
         },
     )
@@ -244,7 +247,9 @@ fn main() {
                 }],
             },
             push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
             debug_name: Some(String::from("graphics_pipeline")),
+            dynamic_states: DynamicStates::empty(),
         },
     )
     .unwrap();
@@ -295,6 +300,8 @@ fn main() {
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_attachment: None,
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     None,
                     |pass| {
@@ -330,7 +337,10 @@ fn main() {
                                 width: dims.width,
                                 height: dims.height,
                                 present_mode: PresentMode::Fifo,
-                                format: Format::Bgra8Unorm,
+                                preferred_formats: vec![(
+                                    Format::Bgra8Unorm,
+                                    ColorSpace::SrgbNonlinear,
+                                )],
                             })
                             .unwrap();
                     }
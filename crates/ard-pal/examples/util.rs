@@ -1,4 +1,64 @@
+#![allow(dead_code)]
+
 use ard_pal::prelude::*;
+use winit::raw_window_handle::HasDisplayHandle;
+
+/// Includes a compiled shader from `./shaders/` in the format of the backend Pal was built with.
+/// `name` is the GLSL source, like `"triangle.vert"`.
+#[cfg(not(feature = "dx12"))]
+#[allow(unused_macros)]
+macro_rules! shader_code {
+    ($name:literal) => {
+        include_bytes!(concat!("./shaders/", $name, ".spv"))
+    };
+}
+
+#[cfg(feature = "dx12")]
+#[allow(unused_macros)]
+macro_rules! shader_code {
+    ($name:literal) => {
+        include_bytes!(concat!("./shaders/", $name, ".dxil"))
+    };
+}
+
+#[allow(unused_imports)]
+pub(crate) use shader_code;
+
+#[cfg(not(any(feature = "vulkan", feature = "dx12")))]
+compile_error!("the examples need the `vulkan` or `dx12` backend");
+
+/// Creates the backend Pal was built with. Without a display, the backend is headless.
+pub fn create_backend<D: HasDisplayHandle>(
+    app_name: &str,
+    display_handle: Option<&D>,
+    debug: bool,
+) -> ard_pal::Backend {
+    #[cfg(feature = "dx12")]
+    {
+        use ard_pal::backend::{Dx12Backend, Dx12BackendCreateInfo};
+
+        // D3D12 devices aren't created for a particular display
+        let _ = (app_name, display_handle);
+        Dx12Backend::new(Dx12BackendCreateInfo { debug }).unwrap()
+    }
+
+    #[cfg(all(feature = "vulkan", not(feature = "dx12")))]
+    {
+        use ard_pal::backend::{VulkanBackend, VulkanBackendCreateInfo};
+
+        VulkanBackend::new(VulkanBackendCreateInfo {
+            app_name: String::from(app_name),
+            engine_name: String::from("pal"),
+            display_handle,
+            debug,
+            debug_config: Default::default(),
+            pipeline_cache_path: None,
+            device_memory_budget: None,
+            adapter_preference: AdapterPreference::Default,
+        })
+        .unwrap()
+    }
+}
 
 pub struct MeshBuffers {
     pub vertex: Buffer,
@@ -26,6 +86,7 @@ pub fn create_triangle(ctx: &Context) -> MeshBuffers {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some(String::from("triangle_vertex_buffer")),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap(),
@@ -46,6 +107,7 @@ pub fn create_triangle(ctx: &Context) -> MeshBuffers {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some(String::from("triangle_index_buffer")),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap(),
@@ -59,7 +121,6 @@ pub fn create_triangle(ctx: &Context) -> MeshBuffers {
     }
 }
 
-#[allow(dead_code)]
 pub fn create_cube(ctx: &Context) -> MeshBuffers {
     const INDICES: &'static [u16] = &[
         0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
@@ -92,6 +153,7 @@ pub fn create_cube(ctx: &Context) -> MeshBuffers {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some(String::from("cube_vertex_buffer")),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap(),
@@ -112,6 +174,7 @@ pub fn create_cube(ctx: &Context) -> MeshBuffers {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some(String::from("cube_index_buffer")),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap(),
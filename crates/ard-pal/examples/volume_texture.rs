@@ -0,0 +1,358 @@
+/// This example fills a 3D texture from a compute shader, samples it in a fragment shader, and
+/// renders the result into a single slice of another 3D texture. It should run without any
+/// validation errors.
+use ard_pal::prelude::*;
+use ordered_float::NotNan;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+#[path = "./util.rs"]
+mod util;
+
+/// Width, height, and depth of the volume written by the compute shader.
+const VOLUME_SIZE: u32 = 64;
+
+/// Work group size of the compute shader along each axis.
+const WORK_GROUP_SIZE: u32 = 4;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+
+/// Number of slices in the render target and the slice that is rendered to.
+const TARGET_DEPTH: u32 = 4;
+const TARGET_SLICE: u32 = 2;
+
+#[derive(Default)]
+struct App {
+    done: bool,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.done {
+            return;
+        }
+
+        // The window is only needed to create the backend, so it is never shown
+        let window = event_loop
+            .create_window(
+                Window::default_attributes()
+                    .with_title("Volume Texture")
+                    .with_visible(false),
+            )
+            .unwrap();
+
+        let backend = util::create_backend("Volume Texture", Some(&window), true);
+
+        run(Context::new(backend));
+        println!("volume written, sampled, and rendered to a slice");
+
+        self.done = true;
+        event_loop.exit();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let WindowEvent::CloseRequested = event {
+            event_loop.exit();
+        }
+    }
+}
+
+fn run(context: Context) {
+    let buffers = util::create_triangle(&context);
+
+    let volume = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type3D,
+            width: VOLUME_SIZE,
+            height: VOLUME_SIZE,
+            depth: VOLUME_SIZE,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::STORAGE | TextureUsage::SAMPLED,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("volume")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    let target = Texture::new(
+        context.clone(),
+        TextureCreateInfo {
+            format: Format::Rgba8Unorm,
+            ty: TextureType::Type3D,
+            width: WIDTH,
+            height: HEIGHT,
+            depth: TARGET_DEPTH,
+            array_elements: 1,
+            mip_levels: 1,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::COLOR_ATTACHMENT,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: Some(String::from("target")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
+        },
+    )
+    .unwrap();
+
+    // Compute pipeline that writes the volume
+    let compute_layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![DescriptorBinding {
+                binding: 0,
+                count: 1,
+                stage: ShaderStage::Compute,
+                ty: DescriptorType::StorageImage(AccessType::ReadWrite),
+                flags: DescriptorBindingFlags::empty(),
+            }],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let mut compute_set = DescriptorSet::new(
+        context.clone(),
+        DescriptorSetCreateInfo {
+            layout: compute_layout.clone(),
+            debug_name: Some(String::from("volume_compute_set")),
+            variable_count: None,
+        },
+    )
+    .unwrap();
+
+    compute_set.update(&[DescriptorSetUpdate {
+        binding: 0,
+        array_element: 0,
+        value: DescriptorValue::StorageImage {
+            texture: &volume,
+            array_element: 0,
+            mip: 0,
+        },
+    }]);
+
+    let compute_pipeline = ComputePipeline::new(
+        context.clone(),
+        ComputePipelineCreateInfo {
+            layouts: vec![compute_layout],
+            module: Shader::new(
+                context.clone(),
+                ShaderCreateInfo {
+                    code: util::shader_code!("volume.comp"),
+                    debug_name: Some(String::from("compute_shader")),
+                },
+            )
+            .unwrap(),
+            work_group_size: (WORK_GROUP_SIZE, WORK_GROUP_SIZE, WORK_GROUP_SIZE),
+            push_constants_size: None,
+            debug_name: Some(String::from("volume_compute_pipeline")),
+        },
+    )
+    .unwrap();
+
+    // Graphics pipeline that samples the volume
+    let layout = DescriptorSetLayout::new(
+        context.clone(),
+        DescriptorSetLayoutCreateInfo {
+            bindings: vec![DescriptorBinding {
+                binding: 0,
+                count: 1,
+                stage: ShaderStage::Fragment,
+                ty: DescriptorType::Texture,
+                flags: DescriptorBindingFlags::empty(),
+            }],
+            push: false,
+            debug_name: None,
+        },
+    )
+    .unwrap();
+
+    let mut set = DescriptorSet::new(
+        context.clone(),
+        DescriptorSetCreateInfo {
+            layout: layout.clone(),
+            debug_name: Some(String::from("volume_set")),
+            variable_count: None,
+        },
+    )
+    .unwrap();
+
+    set.update(&[DescriptorSetUpdate {
+        binding: 0,
+        array_element: 0,
+        value: DescriptorValue::Texture {
+            texture: &volume,
+            array_element: 0,
+            sampler: Sampler {
+                min_filter: Filter::Linear,
+                mag_filter: Filter::Linear,
+                mipmap_filter: Filter::Linear,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                anisotropy: None,
+                compare: None,
+                reduction_mode: None,
+                min_lod: NotNan::new(0.0).unwrap(),
+                max_lod: None,
+                unnormalize_coords: false,
+                border_color: None,
+            },
+            base_mip: 0,
+            mip_count: 1,
+            aspect: None,
+        },
+    }]);
+
+    let pipeline = GraphicsPipeline::new(
+        context.clone(),
+        GraphicsPipelineCreateInfo {
+            stages: ShaderStages::Traditional {
+                vertex: Shader::new(
+                    context.clone(),
+                    ShaderCreateInfo {
+                        code: util::shader_code!("triangle.vert"),
+                        debug_name: Some(String::from("vertex_shader")),
+                    },
+                )
+                .unwrap(),
+                fragment: Some(
+                    Shader::new(
+                        context.clone(),
+                        ShaderCreateInfo {
+                            code: util::shader_code!("volume.frag"),
+                            debug_name: Some(String::from("fragment_shader")),
+                        },
+                    )
+                    .unwrap(),
+                ),
+            },
+            layouts: vec![layout],
+            vertex_input: VertexInputState {
+                attributes: vec![
+                    VertexInputAttribute {
+                        location: 0,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 0,
+                    },
+                    VertexInputAttribute {
+                        location: 1,
+                        binding: 0,
+                        format: Format::Rgba32SFloat,
+                        offset: 16,
+                    },
+                ],
+                bindings: vec![VertexInputBinding {
+                    binding: 0,
+                    stride: 32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                topology: PrimitiveTopology::TriangleList,
+            },
+            rasterization: RasterizationState::default(),
+            depth_stencil: None,
+            color_blend: ColorBlendState {
+                attachments: vec![ColorBlendAttachment {
+                    write_mask: ColorComponents::R | ColorComponents::G | ColorComponents::B,
+                    ..Default::default()
+                }],
+            },
+            push_constants_size: None,
+            compile_mode: PipelineCompileMode::Blocking,
+            debug_name: Some(String::from("volume_pipeline")),
+            dynamic_states: DynamicStates::empty(),
+        },
+    )
+    .unwrap();
+
+    let mut command_buffer = context.main().command_buffer();
+    command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+        src: &buffers.index_staging,
+        src_array_element: 0,
+        src_offset: 0,
+        dst: &buffers.index,
+        dst_array_element: 0,
+        dst_offset: 0,
+        len: buffers.index.size(),
+    });
+    command_buffer.copy_buffer_to_buffer(CopyBufferToBuffer {
+        src: &buffers.vertex_staging,
+        src_array_element: 0,
+        src_offset: 0,
+        dst: &buffers.vertex,
+        dst_array_element: 0,
+        dst_offset: 0,
+        len: buffers.vertex.size(),
+    });
+
+    // Fill the volume
+    command_buffer.compute_pass(&compute_pipeline, Some("write_volume"), |pass| {
+        pass.bind_sets(0, vec![&compute_set]);
+        let groups = VOLUME_SIZE.div_ceil(WORK_GROUP_SIZE);
+        pass.dispatch(groups, groups, groups);
+    });
+
+    // Sample the volume into a single slice of the target
+    command_buffer.render_pass(
+        RenderPassDescriptor {
+            color_attachments: vec![ColorAttachment {
+                dst: ColorAttachmentDestination::TextureSlice {
+                    texture: &target,
+                    mip_level: 0,
+                    slice: TARGET_SLICE,
+                },
+                load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
+                store_op: StoreOp::Store,
+                samples: MultiSamples::Count1,
+            }],
+            color_resolve_attachments: Vec::default(),
+            depth_stencil_attachment: None,
+            depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
+        },
+        Some("sample_volume"),
+        |pass| {
+            pass.bind_pipeline(pipeline.clone());
+            pass.bind_sets(0, vec![&set]);
+            pass.bind_vertex_buffers(
+                0,
+                vec![VertexBind {
+                    buffer: &buffers.vertex,
+                    array_element: 0,
+                    offset: 0,
+                }],
+            );
+            pass.bind_index_buffer(&buffers.index, 0, 0, IndexType::U16);
+            pass.draw_indexed(3, 1, 0, 0, 0);
+        },
+    );
+
+    context
+        .main()
+        .submit(Some("volume"), command_buffer)
+        .wait_on(None);
+}
+
+fn main() {
+    let event_loop = EventLoop::new().unwrap();
+    event_loop.run_app(&mut App::default()).unwrap();
+}
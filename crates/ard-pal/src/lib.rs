@@ -1,8 +1,20 @@
+// DirectX 12 is opt-in, so it takes priority over the default Vulkan backend when enabled
+#[cfg(all(feature = "dx12", not(windows)))]
+compile_error!("the `dx12` backend is only available on Windows");
+
 cfg_if::cfg_if! {
-    if #[cfg(feature = "vulkan")] {
+    if #[cfg(feature = "dx12")] {
+        pub type Backend = dx12::Dx12Backend;
+        pub mod backend {
+            pub use dx12::{Dx12Backend, Dx12BackendCreateError, Dx12BackendCreateInfo};
+        }
+    } else if #[cfg(feature = "vulkan")] {
         pub type Backend = vulkan::VulkanBackend;
         pub mod backend {
-            pub use vulkan::{VulkanBackend, VulkanBackendCreateError, VulkanBackendCreateInfo};
+            pub use vulkan::{
+                DebugSeverity, VulkanBackend, VulkanBackendCreateError, VulkanBackendCreateInfo,
+                VulkanDebugConfig,
+            };
         }
     } else {
         pub type Backend = empty::EmptyBackend;
@@ -18,13 +30,23 @@ pub mod prelude {
     // Context
     pub type Context = api::context::Context<crate::Backend>;
     pub type GraphicsProperties = api::context::GraphicsProperties;
+    pub type GraphicsFeatures = api::context::GraphicsFeatures;
+    pub type SparseProperties = api::context::SparseProperties;
+    pub type RayTracingProperties = api::context::RayTracingProperties;
+    pub type SampleCountProperties = api::context::SampleCountProperties;
+    pub use api::context::{
+        AdapterInfo, AdapterPreference, AdapterType, DeviceLimits, GarbageKind, GarbageReport,
+        MemoryAllocationInfo, MemoryBudget, MemoryCategory, MemoryCategoryStats, MemoryHeapBudget,
+        MemoryHeapStats, MemoryPressure, MemoryPressureCallback, MemoryStats, PendingGarbage,
+        PipelineCacheSaveError, QueueTimelineValues, MEMORY_REPORT_ALLOCATIONS,
+    };
 
     // Surface
     pub type Surface = api::surface::Surface<crate::Backend>;
     pub type SurfaceImage = api::surface::SurfaceImage<crate::Backend>;
     pub use api::surface::{
-        SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo, SurfacePresentSuccess,
-        WindowSource,
+        SurfaceConfiguration, SurfaceCreateError, SurfaceCreateInfo, SurfaceImageAcquireError,
+        SurfacePresentSuccess, WindowSource,
     };
 
     // Compute pass
@@ -32,14 +54,17 @@ pub mod prelude {
     pub type ComputePassDispatch<'a> = api::compute_pass::ComputePassDispatch<'a, crate::Backend>;
 
     // Render pass
+    pub type DrawIndirect = <crate::Backend as api::Backend>::DrawIndirect;
     pub type DrawIndexedIndirect = <crate::Backend as api::Backend>::DrawIndexedIndirect;
     pub type RenderPass<'a> = api::render_pass::RenderPass<'a, crate::Backend>;
     pub type RenderPassDescriptor<'a> = api::render_pass::RenderPassDescriptor<'a, crate::Backend>;
+    pub type RenderPassSlice<'a> = api::render_pass::RenderPassSlice<'a, crate::Backend>;
     pub type ColorAttachmentDestination<'a> =
         api::render_pass::ColorAttachmentDestination<'a, crate::Backend>;
     pub use api::render_pass::{
-        ColorAttachment, ColorResolveAttachment, DepthStencilAttachment,
-        DepthStencilAttachmentDestination, DepthStencilResolveAttachment, VertexBind,
+        AttachmentRef, ColorAttachment, ColorResolveAttachment, DepthStencilAttachment,
+        DepthStencilAttachmentDestination, DepthStencilResolveAttachment, RenderAreaError,
+        VertexBind,
     };
 
     // Command buffer
@@ -48,10 +73,20 @@ pub mod prelude {
         CopyTextureToTexture, TextureResolve,
     };
     pub type CommandBuffer<'a> = api::command_buffer::CommandBuffer<'a, crate::Backend>;
+    pub type EventResource<'a> = api::command_buffer::EventResource<'a, crate::Backend>;
 
     // Queue
     pub type Queue = api::queue::Queue<crate::Backend>;
     pub type Job = api::queue::Job<crate::Backend>;
+    pub type RecordedCommands = api::recorded_commands::RecordedCommands<crate::Backend>;
+    pub use api::recorded_commands::RecordingResources;
+    pub type JobWaiter = api::job_waiter::JobWaiter<crate::Backend>;
+    pub use api::frame_pacer::{FrameToken, DEFAULT_FRAMES_IN_FLIGHT};
+    pub use api::job_waiter::JobWaiterTask;
+    pub type StagingBelt<K = ()> = api::staging_belt::StagingBelt<crate::Backend, K>;
+    pub type StagingHandle = api::staging_belt::StagingHandle<crate::Backend>;
+    pub use api::queue::SubmitError;
+    pub use api::staging_belt::{StagingBeltCreateInfo, StagingTextureUpload};
 
     // Shader
     pub type Shader = api::shader::Shader<crate::Backend>;
@@ -60,10 +95,11 @@ pub mod prelude {
     // Graphics pipeline
     pub type GraphicsPipeline = api::graphics_pipeline::GraphicsPipeline<crate::Backend>;
     pub type MeshShadingShader = api::graphics_pipeline::MeshShadingShader<crate::Backend>;
+    pub type PipelineCompileMode = api::graphics_pipeline::PipelineCompileMode<crate::Backend>;
     pub use api::graphics_pipeline::{
         ColorBlendAttachment, ColorBlendState, DepthStencilState, GraphicsPipelineCreateError,
-        GraphicsPipelineCreateInfo, RasterizationState, ShaderStages, VertexInputAttribute,
-        VertexInputBinding, VertexInputState,
+        GraphicsPipelineCreateInfo, PipelineLimitError, RasterizationState, ShaderStages,
+        StencilOpState, VertexInputAttribute, VertexInputBinding, VertexInputState,
     };
 
     // Compute pipeline
@@ -78,20 +114,32 @@ pub mod prelude {
     pub type RayTracingDispatch<'a> = api::rt_pass::RayTracingDispatch<'a, crate::Backend>;
     pub type RayTracingDispatchSource<'a> =
         api::rt_pass::RayTracingDispatchSource<'a, crate::Backend>;
+    pub use api::rt_pass::ShaderBindingTableRegion;
     pub use api::rt_pipeline::{
         PipelineLibraryInfo, RayTracingPipelineCreateError, RayTracingShaderGroup,
-        RayTracingShaderStage, ShaderBindingTableData,
+        RayTracingShaderStage, SbtBuildError, SbtBuilder, ShaderBindingTable,
+        ShaderBindingTableData,
     };
 
     // Buffer
     pub type Buffer = api::buffer::Buffer<crate::Backend>;
     pub type BufferReadView<'a> = api::buffer::BufferReadView<'a, crate::Backend>;
     pub type BufferWriteView<'a> = api::buffer::BufferWriteView<'a, crate::Backend>;
-    pub use api::buffer::{BufferCreateError, BufferCreateInfo, BufferViewError};
+    pub type BufferView<'a> = api::buffer::BufferView<'a, crate::Backend>;
+    pub type BufferViewCreateInfo<'a> = api::buffer::BufferViewCreateInfo<'a, crate::Backend>;
+    pub type RingBuffer = api::buffer::RingBuffer<crate::Backend>;
+    pub use api::buffer::{
+        BufferCreateError, BufferCreateInfo, BufferViewCreateError, BufferViewError,
+        RingBufferCreateInfo,
+    };
 
     // Texture
     pub type Texture = api::texture::Texture<crate::Backend>;
-    pub use api::texture::{Blit, Sampler, TextureCreateError, TextureCreateInfo};
+    pub type TextureReadback = api::texture::TextureReadback<crate::Backend>;
+    pub use api::texture::{
+        Blit, InitialDataError, Sampler, SparsePageRegion, TextureCreateError, TextureCreateInfo,
+        TextureReadbackData,
+    };
 
     // Cube map
     pub type CubeMap = api::cube_map::CubeMap<crate::Backend>;
@@ -102,9 +150,9 @@ pub mod prelude {
     pub type DescriptorSet = api::descriptor_set::DescriptorSet<crate::Backend>;
     pub type DescriptorValue<'a> = api::descriptor_set::DescriptorValue<'a, crate::Backend>;
     pub use api::descriptor_set::{
-        DescriptorBinding, DescriptorSetCreateError, DescriptorSetCreateInfo,
-        DescriptorSetLayoutCreateError, DescriptorSetLayoutCreateInfo, DescriptorSetUpdate,
-        DescriptorType,
+        DescriptorBinding, DescriptorBindingFlags, DescriptorSetCreateError,
+        DescriptorSetCreateInfo, DescriptorSetLayoutCreateError, DescriptorSetLayoutCreateInfo,
+        DescriptorSetUpdate, DescriptorType,
     };
 
     // BLAS
@@ -112,6 +160,8 @@ pub mod prelude {
         api::blas::BottomLevelAccelerationStructure<crate::Backend>;
     pub type AccelerationStructureGeometry<'a> =
         api::blas::AccelerationStructureGeometry<'a, crate::Backend>;
+    pub type AccelerationStructureAabbs<'a> =
+        api::blas::AccelerationStructureAabbs<'a, crate::Backend>;
     pub type BottomLevelAccelerationStructureCreateInfo<'a> =
         api::blas::BottomLevelAccelerationStructureCreateInfo<'a, crate::Backend>;
     pub type BottomLevelAccelerationStructureData<'a> =
@@ -1,23 +1,65 @@
+use std::fmt;
+
 use ard_assets::prelude::*;
 use ard_ecs::{prelude::*, system::data::SystemData};
 use ard_save_load::{
+    entity_map::MappedEntity,
     format::SaveFormat,
+    load_data::{LoadError, Loader},
+    overrides::Overrides,
     save_data::{SaveData, Saver},
 };
-use ard_transform::Children;
+use ard_transform::{Children, SetParent};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct PrefabAsset {
     data: SaveData,
+    /// Number of root entities. The roots are always the first entities of the prefab.
+    root_count: usize,
+    nested: Vec<NestedPrefab>,
+}
+
+/// Another prefab instantiated along with this one.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NestedPrefab {
+    pub asset: AssetNameBuf,
+    /// Entity of this prefab to parent the nested prefab's roots to. When `None`, they become
+    /// roots of the instance.
+    pub parent: Option<MappedEntity>,
+    /// Overrides applied to the nested prefab. Entities are local to the nested prefab.
+    pub overrides: Overrides,
+}
+
+/// Entities created when instantiating a prefab.
+#[derive(Default)]
+pub struct PrefabInstantiation {
+    /// Every entity created, including those of nested prefabs.
+    pub entities: Vec<Entity>,
+    pub roots: Vec<Entity>,
+}
+
+pub enum PrefabError<F: SaveFormat> {
+    Load {
+        asset: Option<AssetNameBuf>,
+        err: LoadError<F>,
+    },
+    /// A nested prefab couldn't be loaded.
+    MissingPrefab(AssetNameBuf),
+    /// A prefab contains itself. Holds the chain of nested prefabs that lead back to it.
+    Cycle(Vec<AssetNameBuf>),
 }
 
 pub struct PrefabLoader;
 
 impl PrefabAsset {
-    pub fn new(data: SaveData) -> Self {
-        Self { data }
+    pub fn new(data: SaveData, root_count: usize) -> Self {
+        Self {
+            data,
+            root_count,
+            nested: Vec::default(),
+        }
     }
 
     pub fn new_from_root<F: SaveFormat>(
@@ -26,8 +68,19 @@ impl PrefabAsset {
         assets: Assets,
         queries: &Queries<Everything>,
     ) -> Result<Self, F::SerializeError> {
-        // Collect all children. First child must be the root
-        let mut entities = vec![entity];
+        Self::from_entities(&[entity], saver, assets, queries)
+    }
+
+    /// Captures `roots` and all of their descendants. References to entities outside of the
+    /// captured subtrees are nulled.
+    pub fn from_entities<F: SaveFormat>(
+        roots: &[Entity],
+        saver: Saver<F>,
+        assets: Assets,
+        queries: &Queries<Everything>,
+    ) -> Result<Self, F::SerializeError> {
+        // Collect all children. Roots must come first so they're mapped to the first entities
+        let mut entities = Vec::from_iter(roots.iter().cloned());
         fn walk_children(
             parent: Entity,
             queries: &Queries<impl SystemData>,
@@ -43,22 +96,202 @@ impl PrefabAsset {
                 walk_children(*child, queries, out);
             });
         }
-        walk_children(entity, queries, &mut entities);
+        roots
+            .iter()
+            .for_each(|root| walk_children(*root, queries, &mut entities));
 
         // Mark external entities as null
         let saver = saver.null_external_entities();
 
         Ok(Self {
             data: saver.save(assets, queries, &entities)?.0,
+            root_count: roots.len(),
+            nested: Vec::default(),
         })
     }
 
+    /// Instantiates `nested` along with this prefab.
+    #[inline(always)]
+    pub fn with_nested(mut self, nested: NestedPrefab) -> Self {
+        self.nested.push(nested);
+        self
+    }
+
     #[inline(always)]
     pub fn data(&self) -> &SaveData {
         &self.data
     }
+
+    #[inline(always)]
+    pub fn root_count(&self) -> usize {
+        self.root_count
+    }
+
+    #[inline(always)]
+    pub fn nested(&self) -> &[NestedPrefab] {
+        &self.nested
+    }
+
+    /// Spawns a fresh copy of the prefab and its nested prefabs. `overrides` refer to entities by
+    /// their index within this prefab.
+    ///
+    /// Roots of the instance have no parent. Nothing is created if any part of the prefab fails
+    /// to load.
+    pub fn instantiate<F: SaveFormat + 'static>(
+        &self,
+        loader: fn() -> Loader<F>,
+        assets: &Assets,
+        commands: &EntityCommands,
+        overrides: Overrides,
+    ) -> Result<PrefabInstantiation, PrefabError<F>> {
+        let mut instantiation = PrefabInstantiation::default();
+        let mut stack = Vec::default();
+
+        match self.instantiate_into(
+            loader,
+            assets,
+            commands,
+            overrides,
+            &mut stack,
+            &mut instantiation.entities,
+        ) {
+            Ok(roots) => {
+                roots.iter().for_each(|root| {
+                    commands.add_component(
+                        *root,
+                        SetParent {
+                            new_parent: None,
+                            index: 0,
+                        },
+                    )
+                });
+                instantiation.roots = roots;
+                Ok(instantiation)
+            }
+            Err(err) => {
+                commands.destroy(&instantiation.entities);
+                Err(err)
+            }
+        }
+    }
+
+    /// Loads the prefab and its nested prefabs, pushing every entity created into `entities`.
+    /// `stack` holds the names of the prefabs currently being instantiated.
+    fn instantiate_into<F: SaveFormat + 'static>(
+        &self,
+        loader: fn() -> Loader<F>,
+        assets: &Assets,
+        commands: &EntityCommands,
+        overrides: Overrides,
+        stack: &mut Vec<AssetNameBuf>,
+        entities: &mut Vec<Entity>,
+    ) -> Result<Vec<Entity>, PrefabError<F>> {
+        let mut ours = vec![Entity::null(); self.data.entity_count];
+        commands.create_empty(&mut ours);
+        entities.extend_from_slice(&ours);
+
+        loader()
+            .overrides(overrides)
+            .load_with_external(
+                self.data.clone(),
+                assets.clone(),
+                commands,
+                Some(&ours),
+                &[],
+            )
+            .map_err(|err| PrefabError::Load {
+                asset: stack.last().cloned(),
+                err,
+            })?;
+
+        for nested in &self.nested {
+            if stack.contains(&nested.asset) {
+                let mut chain = stack.clone();
+                chain.push(nested.asset.clone());
+                return Err(PrefabError::Cycle(chain));
+            }
+
+            let handle = match assets.load::<PrefabAsset>(&nested.asset) {
+                Some(handle) => handle,
+                None => return Err(PrefabError::MissingPrefab(nested.asset.clone())),
+            };
+            assets.wait_for_load(&handle);
+            let prefab = match assets.get(&handle) {
+                Some(prefab) => prefab,
+                None => return Err(PrefabError::MissingPrefab(nested.asset.clone())),
+            };
+
+            stack.push(nested.asset.clone());
+            let roots = prefab.instantiate_into(
+                loader,
+                assets,
+                commands,
+                nested.overrides.clone(),
+                stack,
+                entities,
+            )?;
+            stack.pop();
+
+            let new_parent = nested.parent.map(|parent| ours[parent.0 as usize]);
+            roots.iter().for_each(|root| {
+                commands.add_component(
+                    *root,
+                    SetParent {
+                        new_parent,
+                        index: usize::MAX,
+                    },
+                )
+            });
+
+            if new_parent.is_none() {
+                ours.extend_from_slice(&roots);
+            }
+        }
+
+        let nested_roots = ours.split_off(self.data.entity_count);
+        ours.truncate(self.root_count);
+        ours.extend(nested_roots);
+        Ok(ours)
+    }
+}
+
+impl<F: SaveFormat> fmt::Debug for PrefabError<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load { asset, err } => f
+                .debug_struct("Load")
+                .field("asset", asset)
+                .field("err", err)
+                .finish(),
+            Self::MissingPrefab(asset) => f.debug_tuple("MissingPrefab").field(asset).finish(),
+            Self::Cycle(chain) => f.debug_tuple("Cycle").field(chain).finish(),
+        }
+    }
 }
 
+impl<F: SaveFormat> fmt::Display for PrefabError<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load {
+                asset: Some(asset),
+                err,
+            } => write!(f, "unable to load nested prefab `{asset}`: {err}"),
+            Self::Load { asset: None, err } => write!(f, "unable to load prefab: {err}"),
+            Self::MissingPrefab(asset) => write!(f, "nested prefab `{asset}` could not be loaded"),
+            Self::Cycle(chain) => {
+                let chain = chain
+                    .iter()
+                    .map(|asset| asset.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                write!(f, "prefab contains itself: {chain}")
+            }
+        }
+    }
+}
+
+impl<F: SaveFormat> std::error::Error for PrefabError<F> {}
+
 impl Asset for PrefabAsset {
     const EXTENSION: &'static str = "ard_pfb";
 
@@ -7,8 +7,8 @@ use ard_render::factory::Factory;
 use ard_render_base::RenderingMode;
 use ard_render_material::material_instance::{MaterialInstance, TextureSlot};
 use ard_render_pbr::{
-    PbrMaterialData, PBR_MATERIAL_DIFFUSE_SLOT, PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT,
-    PBR_MATERIAL_NORMAL_SLOT,
+    pbr_uv_transform, PbrMaterialData, PBR_MATERIAL_DIFFUSE_SLOT, PBR_MATERIAL_EMISSIVE_SLOT,
+    PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT, PBR_MATERIAL_NORMAL_SLOT, PBR_MATERIAL_OCCLUSION_SLOT,
 };
 use async_trait::async_trait;
 
@@ -113,6 +113,11 @@ impl AssetLoader for MaterialLoader {
                 ref diffuse_map,
                 ref normal_map,
                 ref metallic_roughness_map,
+                emissive,
+                ref emissive_map,
+                occlusion_strength,
+                ref occlusion_map,
+                uv_transform,
             } => {
                 let instance = match self.factory.create_pbr_material_instance() {
                     Ok(instance) => instance,
@@ -120,6 +125,7 @@ impl AssetLoader for MaterialLoader {
                 };
 
                 // Apply material properties
+                let (uv_transform, uv_offset) = pbr_uv_transform(uv_transform);
                 self.factory.set_material_data(
                     &instance,
                     &PbrMaterialData {
@@ -127,95 +133,52 @@ impl AssetLoader for MaterialLoader {
                         color: base_color,
                         metallic,
                         roughness,
+                        occlusion_strength,
+                        emissive: emissive.extend(0.0),
+                        uv_transform,
+                        uv_offset,
                     },
                 );
 
                 // Apply material textures
-                match diffuse_map {
-                    Some(tex) => 'tex: {
-                        let tex_handle = match assets.load_async(&tex).await {
-                            Some(handle) => handle,
-                            None => {
-                                warn!("Texture `{tex}` does not exist.");
-                                textures.push(None);
-                                break 'tex;
-                            }
-                        };
-                        let tex_asset = match assets.get::<TextureAsset>(&tex_handle) {
-                            Some(asset) => asset,
-                            None => {
-                                warn!("Texture `{tex}` could not be loaded.");
-                                textures.push(None);
-                                break 'tex;
-                            }
-                        };
-
-                        textures.push(Some(tex_handle.clone()));
-                        self.factory.set_material_texture_slot(
-                            &instance,
-                            PBR_MATERIAL_DIFFUSE_SLOT,
-                            Some(&tex_asset.texture),
-                        );
-                    }
-                    None => {}
-                }
-
-                match normal_map {
-                    Some(tex) => 'tex: {
-                        let tex_handle = match assets.load_async(&tex).await {
-                            Some(handle) => handle,
-                            None => {
-                                warn!("Texture `{tex}` does not exist.");
-                                textures.push(None);
-                                break 'tex;
-                            }
-                        };
-                        let tex_asset = match assets.get::<TextureAsset>(&tex_handle) {
-                            Some(asset) => asset,
-                            None => {
-                                warn!("Texture `{tex}` could not be loaded.");
-                                textures.push(None);
-                                break 'tex;
-                            }
-                        };
-
-                        textures.push(Some(tex_handle.clone()));
-                        self.factory.set_material_texture_slot(
-                            &instance,
-                            PBR_MATERIAL_NORMAL_SLOT,
-                            Some(&tex_asset.texture),
-                        );
-                    }
-                    None => {}
-                }
-
-                match metallic_roughness_map {
-                    Some(tex) => 'tex: {
-                        let tex_handle = match assets.load_async(&tex).await {
-                            Some(handle) => handle,
-                            None => {
-                                warn!("Texture `{tex}` does not exist.");
-                                textures.push(None);
-                                break 'tex;
-                            }
-                        };
-                        let tex_asset = match assets.get::<TextureAsset>(&tex_handle) {
-                            Some(asset) => asset,
-                            None => {
-                                warn!("Texture `{tex}` could not be loaded.");
-                                textures.push(None);
-                                break 'tex;
-                            }
-                        };
-
-                        textures.push(Some(tex_handle.clone()));
-                        self.factory.set_material_texture_slot(
-                            &instance,
-                            PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT,
-                            Some(&tex_asset.texture),
-                        );
+                let maps = [
+                    (diffuse_map, PBR_MATERIAL_DIFFUSE_SLOT),
+                    (normal_map, PBR_MATERIAL_NORMAL_SLOT),
+                    (metallic_roughness_map, PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT),
+                    (emissive_map, PBR_MATERIAL_EMISSIVE_SLOT),
+                    (occlusion_map, PBR_MATERIAL_OCCLUSION_SLOT),
+                ];
+
+                for (map, slot) in maps {
+                    match map {
+                        Some(tex) => 'tex: {
+                            let tex_handle = match assets.load_async(&tex).await {
+                                Some(handle) => handle,
+                                None => {
+                                    warn!("Texture `{tex}` does not exist.");
+                                    textures.push(None);
+                                    break 'tex;
+                                }
+                            };
+                            let tex_asset = match assets.get::<TextureAsset>(&tex_handle) {
+                                Some(asset) => asset,
+                                None => {
+                                    warn!("Texture `{tex}` could not be loaded.");
+                                    textures.push(None);
+                                    break 'tex;
+                                }
+                            };
+
+                            textures.push(Some(tex_handle.clone()));
+                            self.factory.set_material_texture_slot(
+                                &instance,
+                                slot,
+                                Some(&tex_asset.texture),
+                            );
+                        }
+                        // Keep one entry per slot so textures can be looked up by slot
+                        None => textures.push(None),
                     }
-                    None => {}
                 }
 
                 instance
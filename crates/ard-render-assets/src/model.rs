@@ -1,9 +1,18 @@
+use std::sync::Arc;
+
+use ard_animation::{
+    animator::{Animator, Skin},
+    clip::AnimationClip,
+    skeleton::Skeleton,
+};
 use ard_assets::prelude::*;
 use ard_formats::model::{MeshGroup, ModelHeader};
+use ard_log::warn;
 use ard_math::Mat4;
+use ard_render::factory::Factory;
 use ard_render_base::RenderingMode;
 use ard_render_material::material_instance::MaterialInstance;
-use ard_render_meshes::mesh::Mesh;
+use ard_render_meshes::{mesh::Mesh, skinned::SkinnedMesh};
 use ard_render_objects::RenderFlags;
 use ard_transform::Model;
 use async_trait::async_trait;
@@ -18,12 +27,15 @@ pub struct ModelAsset {
     pub materials: Vec<Handle<MaterialAsset>>,
     pub mesh_groups: Vec<MeshGroup>,
     pub meshes: Vec<Handle<MeshAsset>>,
+    /// Loading clips requires the `AnimationPlugin`.
+    pub skins: Vec<ModelSkin>,
     pub node_count: usize,
     pub roots: Vec<Node>,
 }
 
 pub struct ModelAssetInstance {
     pub meshes: ModelAssetInstanceMeshes,
+    pub skinned: ModelAssetInstanceSkinned,
 }
 
 pub struct ModelAssetInstanceMeshes {
@@ -34,6 +46,24 @@ pub struct ModelAssetInstanceMeshes {
     pub flags: Vec<RenderFlags>,
 }
 
+/// Each skinned mesh is its own instance, so entities created from the same asset can be posed
+/// independently.
+pub struct ModelAssetInstanceSkinned {
+    pub meshes: Vec<SkinnedMesh>,
+    pub materials: Vec<MaterialInstance>,
+    pub models: Vec<Model>,
+    pub rendering_mode: Vec<RenderingMode>,
+    pub flags: Vec<RenderFlags>,
+    pub skins: Vec<Skin>,
+    /// Plays the first clip of the skin, if it has any.
+    pub animators: Vec<Animator>,
+}
+
+pub struct ModelSkin {
+    pub skeleton: Arc<Skeleton>,
+    pub clips: Vec<Handle<AnimationClip>>,
+}
+
 // pub enum Light {
 //     Point(PointLight),
 //     Spot,
@@ -56,6 +86,8 @@ pub enum NodeData {
     Empty,
     /// Index of a mesh group.
     MeshGroup(usize),
+    /// Index of a mesh group and the skin deforming it.
+    SkinnedMeshGroup { mesh_group: usize, skin: usize },
     // Light(usize),
 }
 
@@ -133,6 +165,38 @@ impl AssetLoader for ModelLoader {
         .into_iter()
         .collect::<Result<Vec<_>, _>>()?;
 
+        let skins = futures::future::join_all(header.skins.into_iter().map(|skin| {
+            let assets = assets.clone();
+            let package = package.clone();
+            async move {
+                let skeleton = package.read(skin.skeleton).await?;
+                let skeleton = match bincode::deserialize::<Skeleton>(&skeleton) {
+                    Ok(skeleton) => skeleton,
+                    Err(err) => return Err(AssetLoadError::Other(err.to_string())),
+                };
+
+                let mut clips = Vec::with_capacity(skin.clips.len());
+                for path in skin.clips {
+                    match assets.load_async::<AnimationClip>(&path).await {
+                        Some(handle) => clips.push(handle),
+                        None => {
+                            return Err(AssetLoadError::Other(format!(
+                                "could not load animation clip {path:?}"
+                            )))
+                        }
+                    }
+                }
+
+                Ok(ModelSkin {
+                    skeleton: Arc::new(skeleton),
+                    clips,
+                })
+            }
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
         // Begin constructing the asset
         let mut model = ModelAsset {
             // lights: Vec::default(),
@@ -140,6 +204,7 @@ impl AssetLoader for ModelLoader {
             materials,
             mesh_groups: header.mesh_groups.clone(),
             meshes,
+            skins,
             roots: Vec::with_capacity(header.roots.len()),
             node_count: 0,
         };
@@ -155,6 +220,12 @@ impl AssetLoader for ModelLoader {
                 data: match node.data {
                     ard_formats::model::NodeData::Empty => NodeData::Empty,
                     ard_formats::model::NodeData::MeshGroup(id) => NodeData::MeshGroup(id as usize),
+                    ard_formats::model::NodeData::SkinnedMeshGroup { mesh_group, skin } => {
+                        NodeData::SkinnedMeshGroup {
+                            mesh_group: mesh_group as usize,
+                            skin: skin as usize,
+                        }
+                    }
                     ard_formats::model::NodeData::Light(_) => NodeData::Empty, // NodeData::Light(id as usize),
                 },
             };
@@ -191,7 +262,9 @@ impl AssetLoader for ModelLoader {
 }
 
 impl ModelAsset {
-    pub fn instantiate(&self, assets: &Assets) -> ModelAssetInstance {
+    /// Skinned meshes are created through `factory`, so each instance has its own copy of their
+    /// vertices.
+    pub fn instantiate(&self, assets: &Assets, factory: &Factory) -> ModelAssetInstance {
         let mut meshes = ModelAssetInstanceMeshes {
             meshes: Vec::default(),
             materials: Vec::default(),
@@ -200,12 +273,24 @@ impl ModelAsset {
             flags: Vec::default(),
         };
 
+        let mut skinned = ModelAssetInstanceSkinned {
+            meshes: Vec::default(),
+            materials: Vec::default(),
+            models: Vec::default(),
+            rendering_mode: Vec::default(),
+            flags: Vec::default(),
+            skins: Vec::default(),
+            animators: Vec::default(),
+        };
+
         fn traverse(
             parent_model: Mat4,
             node: &Node,
             asset: &ModelAsset,
             meshes: &mut ModelAssetInstanceMeshes,
+            skinned: &mut ModelAssetInstanceSkinned,
             assets: &Assets,
+            factory: &Factory,
         ) {
             match &node.data {
                 NodeData::Empty => {}
@@ -222,17 +307,55 @@ impl ModelAsset {
                         meshes.rendering_mode.push(material_asset.render_mode());
                     }
                 }
+                NodeData::SkinnedMeshGroup { mesh_group, skin } => {
+                    let mesh_group = &asset.mesh_groups[*mesh_group];
+                    let skin = &asset.skins[*skin];
+                    for instance in &mesh_group.0 {
+                        let material = &asset.materials[instance.material as usize];
+                        let mesh = &asset.meshes[instance.mesh as usize];
+                        let mesh = assets.get(mesh).unwrap().mesh.clone();
+                        let mesh = match factory.create_skinned_mesh(&mesh) {
+                            Ok(mesh) => mesh,
+                            Err(err) => {
+                                warn!("Could not create skinned mesh: {err}");
+                                continue;
+                            }
+                        };
+
+                        let material_asset = assets.get(material).unwrap();
+                        skinned.meshes.push(mesh);
+                        skinned.materials.push(material_asset.instance.clone());
+                        // Joints are placed relative to the model, so the transform of the node
+                        // holding the mesh is ignored like in GLTF
+                        skinned.models.push(Model(Mat4::IDENTITY));
+                        skinned.flags.push(RenderFlags::SHADOW_CASTER);
+                        skinned.rendering_mode.push(material_asset.render_mode());
+                        skinned.skins.push(Skin(skin.skeleton.clone()));
+                        skinned.animators.push(match skin.clips.first() {
+                            Some(clip) => Animator::new(clip.clone()),
+                            None => Animator::default(),
+                        });
+                    }
+                }
             }
 
             for child in &node.children {
-                traverse(node.model.0, child, asset, meshes, assets);
+                traverse(node.model.0, child, asset, meshes, skinned, assets, factory);
             }
         }
 
         for root in &self.roots {
-            traverse(Mat4::IDENTITY, root, self, &mut meshes, assets);
+            traverse(
+                Mat4::IDENTITY,
+                root,
+                self,
+                &mut meshes,
+                &mut skinned,
+                assets,
+                factory,
+            );
         }
 
-        ModelAssetInstance { meshes }
+        ModelAssetInstance { meshes, skinned }
     }
 }
@@ -1,19 +1,106 @@
+use ard_ecs::prelude::*;
+use ard_math::{UVec4, Vec2};
 use ard_pal::prelude::{
-    CommandBuffer, ComputePassDispatch, ComputePipeline, ComputePipelineCreateInfo, Context,
-    Shader, ShaderCreateInfo,
+    CommandBuffer, ComputePipeline, ComputePipelineCreateInfo, Context, Shader, ShaderCreateInfo,
 };
 use ard_render_base::Frame;
 use ard_render_si::{
     bindings::Layouts,
-    consts::{CAMERA_FROXELS_HEIGHT, CAMERA_FROXELS_WIDTH},
+    consts::{
+        CAMERA_FROXELS_DEPTH, CAMERA_FROXELS_HEIGHT, CAMERA_FROXELS_WIDTH, DEPTH_SLICING_LINEAR,
+        DEPTH_SLICING_LOGARITHMIC,
+    },
 };
 
-use crate::ubo::CameraUbo;
+use crate::{ubo::CameraUbo, Camera};
 
 pub struct FroxelGenPipeline {
     pipeline: ComputePipeline,
 }
 
+/// Dimensions of the froxel grid lights are clustered into. The grid covers the camera frustum
+/// from the near to the far plane.
+///
+/// Each dimension is clamped between 1 and the matching `CAMERA_FROXELS_*` constant.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FroxelSettings {
+    /// Horizontal tiles across the screen.
+    pub width: u32,
+    /// Vertical tiles across the screen.
+    pub height: u32,
+    /// Depth slices between the near and far planes.
+    pub depth: u32,
+    pub depth_slicing: DepthSlicing,
+}
+
+/// How the froxel grid is divided along the view direction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DepthSlicing {
+    /// Slices grow exponentially with distance, matching how froxels grow on screen. Best for
+    /// large scenes where most lights are near the camera.
+    #[default]
+    Logarithmic,
+    /// Slices are evenly spaced. Works better when the far plane is close and lights are spread
+    /// through the whole frustum.
+    Linear,
+}
+
+impl Default for FroxelSettings {
+    fn default() -> Self {
+        Self {
+            width: 32,
+            height: 18,
+            depth: 24,
+            depth_slicing: DepthSlicing::Logarithmic,
+        }
+    }
+}
+
+impl FroxelSettings {
+    /// Largest supported grid.
+    pub const MAX_DIMS: (u32, u32, u32) = (
+        CAMERA_FROXELS_WIDTH as u32,
+        CAMERA_FROXELS_HEIGHT as u32,
+        CAMERA_FROXELS_DEPTH as u32,
+    );
+
+    /// The grid dimensions, clamped to the largest supported grid.
+    pub fn dims(&self) -> (u32, u32, u32) {
+        let (max_width, max_height, max_depth) = Self::MAX_DIMS;
+        (
+            self.width.clamp(1, max_width),
+            self.height.clamp(1, max_height),
+            self.depth.clamp(1, max_depth),
+        )
+    }
+
+    /// Dimensions and depth slicing scheme as stored in `GpuCamera::cluster_dims`.
+    pub fn gpu_dims(&self) -> UVec4 {
+        let (width, height, depth) = self.dims();
+        let slicing = match self.depth_slicing {
+            DepthSlicing::Logarithmic => DEPTH_SLICING_LOGARITHMIC,
+            DepthSlicing::Linear => DEPTH_SLICING_LINEAR,
+        };
+        UVec4::new(width, height, depth, slicing)
+    }
+
+    /// Scale and bias mapping view depth to a depth slice for the given camera. The slice is
+    /// `depth * scale - bias`, where `depth` is log depth with logarithmic slicing.
+    pub fn scale_bias(&self, camera: &Camera) -> Vec2 {
+        let depth = self.dims().2 as f32;
+        match self.depth_slicing {
+            DepthSlicing::Logarithmic => {
+                let log_range = (camera.far / camera.near).ln();
+                Vec2::new(depth / log_range, (depth * camera.near.ln()) / log_range)
+            }
+            DepthSlicing::Linear => {
+                let range = camera.far - camera.near;
+                Vec2::new(depth / range, (depth * camera.near) / range)
+            }
+        }
+    }
+}
+
 impl FroxelGenPipeline {
     pub fn new(ctx: &Context, layouts: &Layouts) -> Self {
         let module = Shader::new(
@@ -43,7 +130,7 @@ impl FroxelGenPipeline {
     pub fn regen<'a>(&self, frame: Frame, commands: &mut CommandBuffer<'a>, camera: &'a CameraUbo) {
         commands.compute_pass(&self.pipeline, Some("froxel_gen"), |pass| {
             pass.bind_sets(0, vec![camera.froxel_regen_set(frame)]);
-            ComputePassDispatch::Inline(1, 1, 1)
+            pass.dispatch(1, 1, 1);
         });
     }
 }
@@ -1,8 +1,9 @@
 use ard_ecs::prelude::Component;
 use ard_math::{Mat4, Vec2, Vec3, Vec3A, Vec4, Vec4Swizzles};
 use ard_render_objects::RenderFlags;
-use ard_render_si::{consts::CAMERA_FROXELS_DEPTH, types::GpuCamera};
+use ard_render_si::types::GpuCamera;
 use ard_transform::Model;
+use froxels::FroxelSettings;
 
 pub mod active;
 pub mod froxels;
@@ -57,7 +58,33 @@ impl Camera {
 
     /// Makes a GPU compatible version of the camera given render target dimensions and a model
     /// matrix describing the orientation of the camera.
-    pub fn into_gpu_struct(&self, width: f32, height: f32, model: Model) -> GpuCamera {
+    ///
+    /// `jitter` is a subpixel offset in NDC applied to the projection. The frustum is built from
+    /// the unjittered projection so culling results don't change with the jitter.
+    ///
+    /// The light cluster parameters use the default froxel grid. Use
+    /// [`into_clustered_gpu_struct`](Self::into_clustered_gpu_struct) for cameras that cluster
+    /// lights.
+    pub fn into_gpu_struct(
+        &self,
+        width: f32,
+        height: f32,
+        model: Model,
+        jitter: Vec2,
+    ) -> GpuCamera {
+        self.into_clustered_gpu_struct(width, height, model, jitter, &FroxelSettings::default())
+    }
+
+    /// Same as [`into_gpu_struct`](Self::into_gpu_struct), but with the light cluster parameters
+    /// of the given froxel grid.
+    pub fn into_clustered_gpu_struct(
+        &self,
+        width: f32,
+        height: f32,
+        model: Model,
+        jitter: Vec2,
+        froxels: &FroxelSettings,
+    ) -> GpuCamera {
         debug_assert_ne!(width, 0.0);
         debug_assert_ne!(height, 0.0);
 
@@ -71,7 +98,12 @@ impl Camera {
             up,
         );
         let aspect_ratio = width / height;
-        let projection = Mat4::perspective_infinite_reverse_lh(self.fov, aspect_ratio, self.near);
+        let unjittered = Mat4::perspective_infinite_reverse_lh(self.fov, aspect_ratio, self.near);
+
+        // W is view space Z, so offsetting these by the jitter offsets NDC by the jitter
+        let mut projection = unjittered;
+        projection.z_axis.x += jitter.x;
+        projection.z_axis.y += jitter.y;
         let vp = projection * view;
 
         GpuCamera {
@@ -82,17 +114,16 @@ impl Camera {
             view_inv: view.inverse(),
             projection_inv: projection.inverse(),
             vp_inv: vp.inverse(),
-            frustum: vp.into(),
+            frustum: (unjittered * view).into(),
             position: Vec4::new(position.x, position.y, position.z, 1.0),
             last_position: Vec4::new(position.x, position.y, position.z, 1.0),
             forward: Vec4::new(forward.x, forward.y, forward.z, 0.0),
             aspect_ratio,
             near_clip: self.near,
             far_clip: self.far,
-            cluster_scale_bias: Vec2::new(
-                (CAMERA_FROXELS_DEPTH as f32) / (self.far / self.near).ln(),
-                ((CAMERA_FROXELS_DEPTH as f32) * self.near.ln()) / (self.far / self.near).ln(),
-            ),
+            cluster_scale_bias: froxels.scale_bias(self),
+            cluster_dims: froxels.gpu_dims(),
+            jitter,
         }
     }
 }
@@ -74,8 +74,12 @@ impl RenderTarget {
                 load_op: LoadOp::Clear(ClearColor::D32S32(0.0, 0)),
                 store_op: StoreOp::Store,
                 samples: MultiSamples::Count1,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
             }),
             depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         }
     }
 
@@ -120,8 +124,12 @@ impl RenderTarget {
                 load_op,
                 store_op: StoreOp::Store,
                 samples: self.samples,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
             }),
             depth_stencil_resolve_attachment: dsra,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         }
     }
 
@@ -165,6 +173,8 @@ impl RenderTarget {
             color_resolve_attachments: Vec::default(),
             depth_stencil_attachment: None,
             depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         }
     }
 
@@ -302,8 +312,12 @@ impl RenderTarget {
                 load_op: LoadOp::Load,
                 store_op: StoreOp::DontCare,
                 samples: self.samples,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
             }),
             depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         }
     }
 
@@ -375,8 +389,12 @@ impl RenderTarget {
                 load_op: LoadOp::Load,
                 store_op,
                 samples: self.samples,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
             }),
             depth_stencil_resolve_attachment: depth_resolve,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         }
     }
 
@@ -390,6 +408,30 @@ impl RenderTarget {
         self.samples
     }
 
+    /// Clamps `samples` to the highest sample count supported by every multi-sampled attachment,
+    /// so 8x falls back to 4x on hardware that can't do 8x.
+    pub fn supported_samples(ctx: &Context, samples: MultiSamples) -> MultiSamples {
+        let properties = ctx.properties();
+        [
+            Self::COLOR_TARGET_FORMAT,
+            Self::THIN_G_TARGET_FORMAT,
+            Self::VEL_TARGET_FORMAT,
+            Self::NORM_TARGET_FORMAT,
+        ]
+        .into_iter()
+        .map(|format| {
+            properties.max_supported_samples(
+                format,
+                TextureUsage::COLOR_ATTACHMENT | TextureUsage::SAMPLED,
+            )
+        })
+        .chain(std::iter::once(properties.max_supported_samples(
+            Self::DEPTH_FORMAT,
+            TextureUsage::DEPTH_STENCIL_ATTACHMENT | TextureUsage::SAMPLED,
+        )))
+        .fold(samples, MultiSamples::min)
+    }
+
     #[inline(always)]
     pub fn color_target(&self) -> &Texture {
         match &self.attachments {
@@ -495,6 +537,9 @@ impl RenderTarget {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("color_target".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -520,6 +565,9 @@ impl RenderTarget {
                 queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("depth_target".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -540,6 +588,9 @@ impl RenderTarget {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("thin_g_target".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -562,6 +613,9 @@ impl RenderTarget {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("vel_target".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -584,6 +638,9 @@ impl RenderTarget {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("norm_target".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -606,6 +663,9 @@ impl RenderTarget {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("linear_color".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -621,11 +681,16 @@ impl RenderTarget {
                 array_elements: 1,
                 mip_levels: 1,
                 sample_count: MultiSamples::Count1,
-                texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::SAMPLED,
+                texture_usage: TextureUsage::COLOR_ATTACHMENT
+                    | TextureUsage::SAMPLED
+                    | TextureUsage::TRANSFER_SRC,
                 memory_usage: MemoryUsage::GpuOnly,
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("entities_target".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -662,6 +727,9 @@ impl RenderTarget {
                         queue_types: QueueTypes::MAIN,
                         sharing_mode: SharingMode::Exclusive,
                         debug_name: Some("color_resolve".to_owned()),
+                        sparse: false,
+                        initial_data: None,
+                        memory_fallback: MemoryFallback::None,
                     },
                 )
                 .unwrap(),
@@ -679,11 +747,15 @@ impl RenderTarget {
                         sample_count: MultiSamples::Count1,
                         texture_usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT
                             | TextureUsage::SAMPLED
+                            | TextureUsage::TRANSFER_SRC
                             | TextureUsage::TRANSFER_DST,
                         memory_usage: MemoryUsage::GpuOnly,
                         queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                         sharing_mode: SharingMode::Exclusive,
                         debug_name: Some("depth_resolve".to_owned()),
+                        sparse: false,
+                        initial_data: None,
+                        memory_fallback: MemoryFallback::None,
                     },
                 )
                 .unwrap(),
@@ -706,6 +778,9 @@ impl RenderTarget {
                         queue_types: QueueTypes::MAIN,
                         sharing_mode: SharingMode::Exclusive,
                         debug_name: Some("thin_g_resolve".to_owned()),
+                        sparse: false,
+                        initial_data: None,
+                        memory_fallback: MemoryFallback::None,
                     },
                 )
                 .unwrap(),
@@ -728,6 +803,9 @@ impl RenderTarget {
                         queue_types: QueueTypes::MAIN,
                         sharing_mode: SharingMode::Exclusive,
                         debug_name: Some("vel_resolve".to_owned()),
+                        sparse: false,
+                        initial_data: None,
+                        memory_fallback: MemoryFallback::None,
                     },
                 )
                 .unwrap(),
@@ -750,6 +828,9 @@ impl RenderTarget {
                         queue_types: QueueTypes::MAIN,
                         sharing_mode: SharingMode::Exclusive,
                         debug_name: Some("norm_resolve".to_owned()),
+                        sparse: false,
+                        initial_data: None,
+                        memory_fallback: MemoryFallback::None,
                     },
                 )
                 .unwrap(),
@@ -1,10 +1,10 @@
 use std::ops::DerefMut;
 
 use ard_ecs::prelude::*;
-use ard_math::{Mat4, Vec3Swizzles, Vec4};
+use ard_math::{Mat4, Vec2, Vec3Swizzles, Vec4};
 use ard_pal::prelude::{
     Buffer, BufferCreateInfo, BufferUsage, Context, DescriptorSet, DescriptorSetCreateInfo,
-    DescriptorSetUpdate, DescriptorValue, MemoryUsage, QueueTypes, SharingMode,
+    DescriptorSetUpdate, DescriptorValue, MemoryFallback, MemoryUsage, QueueTypes, SharingMode,
 };
 use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
 use ard_render_si::{
@@ -13,13 +13,14 @@ use ard_render_si::{
 };
 use ard_transform::Model;
 
-use crate::Camera;
+use crate::{froxels::FroxelSettings, Camera};
 
 #[derive(Resource, Component)]
 pub struct CameraUbo {
     last_camera: Camera,
     last_model: Model,
     last_dims: (u32, u32),
+    last_froxels: FroxelSettings,
     /// The actual UBO.
     ubo: Buffer,
     /// Froxels for light binning.
@@ -44,6 +45,7 @@ impl CameraUbo {
                 queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: Some("camera_ubo".to_owned()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -62,6 +64,7 @@ impl CameraUbo {
                 queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: Some("camera_froxels".to_owned()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -72,6 +75,7 @@ impl CameraUbo {
                 DescriptorSetCreateInfo {
                     layout: layouts.camera.clone(),
                     debug_name: Some(format!("camera_set_{frame_idx}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -106,6 +110,7 @@ impl CameraUbo {
                         DescriptorSetCreateInfo {
                             layout: layouts.froxel_gen.clone(),
                             debug_name: Some(format!("froxel_regen_set_{frame_idx}")),
+                            variable_count: None,
                         },
                     )
                     .unwrap();
@@ -143,6 +148,7 @@ impl CameraUbo {
             },
             last_model: Model(Mat4::IDENTITY),
             last_dims: (0, 0),
+            last_froxels: FroxelSettings::default(),
             ubo,
             froxels,
             sets,
@@ -181,20 +187,35 @@ impl CameraUbo {
         !self.froxel_regen_sets.is_empty() && self.froxel_regen
     }
 
-    pub fn update(&mut self, frame: Frame, value: &Camera, width: u32, height: u32, model: Model) {
-        self.froxel_regen = false;
-
-        if self.last_camera.needs_froxel_regen(value) || self.last_dims != (width, height) {
-            self.froxel_regen = true;
-        }
+    /// Updates the camera for `frame`. `jitter` is the subpixel offset of the projection in NDC.
+    /// Last frame's view-projection is never jittered, so velocities can be computed by removing
+    /// the jitter from the current position.
+    ///
+    /// Froxels are regenerated if the projection or `froxels` changed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        frame: Frame,
+        value: &Camera,
+        width: u32,
+        height: u32,
+        model: Model,
+        jitter: Vec2,
+        froxels: &FroxelSettings,
+    ) {
+        self.froxel_regen = self.last_camera.needs_froxel_regen(value)
+            || self.last_dims != (width, height)
+            || self.last_froxels.dims() != froxels.dims()
+            || self.last_froxels.depth_slicing != froxels.depth_slicing;
 
         let last_vp = self
             .last_camera
-            .into_gpu_struct(width as f32, height as f32, self.last_model)
+            .into_gpu_struct(width as f32, height as f32, self.last_model, Vec2::ZERO)
             .vp;
         let last_position = Vec4::from((self.last_model.position().xyz(), 1.0));
 
         self.last_dims = (width, height);
+        self.last_froxels = *froxels;
         self.last_model = model;
         self.last_camera = value.clone();
 
@@ -218,7 +239,8 @@ impl CameraUbo {
         println!("{}", o.z);
         */
 
-        let mut new_gpu_cam = value.into_gpu_struct(width as f32, height as f32, model);
+        let mut new_gpu_cam =
+            value.into_clustered_gpu_struct(width as f32, height as f32, model, jitter, froxels);
         new_gpu_cam.last_vp = last_vp;
         new_gpu_cam.last_position = last_position;
 
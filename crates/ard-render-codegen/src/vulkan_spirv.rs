@@ -18,11 +18,16 @@ pub fn compile_shader(
     }
 
     // Construct define arguments
-    let mut def_args = Vec::with_capacity(defines.len());
+    let mut def_args = Vec::with_capacity(defines.len() + 1);
     for def in defines {
         def_args.push(format!("-D{def}"));
     }
 
+    // GPU asserts are only compiled into debug builds. See `gpu_assert.glsl` in `ard-render`.
+    if std::env::var("PROFILE").as_deref() == Ok("debug") {
+        def_args.push("-DARD_GPU_ASSERTS".to_owned());
+    }
+
     // Create path if it doesn't exist yet
     let mut path_to_out = output_path.clone();
     path_to_out.pop();
@@ -1,18 +1,22 @@
 use ard_pal::prelude::*;
 
-use crate::{shape::DebugShapeVertex, DebugDraw};
+use ard_math::Vec3;
+
+use crate::{shape::DebugShapeVertex, DebugDrawing};
 
 const DEFAULT_CAP: usize = 128;
 
 pub struct DebugVertexBuffer {
     buffer: Buffer,
     vertex_count: usize,
+    depth_tested_count: usize,
 }
 
 impl DebugVertexBuffer {
     pub fn new(ctx: &Context) -> Self {
         DebugVertexBuffer {
             vertex_count: 0,
+            depth_tested_count: 0,
             buffer: Buffer::new(
                 ctx.clone(),
                 BufferCreateInfo {
@@ -23,6 +27,7 @@ impl DebugVertexBuffer {
                     queue_types: QueueTypes::MAIN,
                     sharing_mode: SharingMode::Exclusive,
                     debug_name: Some("debug_vertex_buffer".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -34,13 +39,39 @@ impl DebugVertexBuffer {
         &self.buffer
     }
 
+    /// Total number of vertices written.
     #[inline(always)]
     pub fn vertex_count(&self) -> usize {
         self.vertex_count
     }
 
-    pub fn write_draws(&mut self, draws: &[DebugDraw]) {
-        self.vertex_count = draws.iter().map(|d| d.shape.vertex_count()).sum();
+    /// Number of vertices at the start of the buffer that are depth tested. The rest of the
+    /// vertices are drawn on top of everything.
+    #[inline(always)]
+    pub fn depth_tested_count(&self) -> usize {
+        self.depth_tested_count
+    }
+
+    /// Writes the shapes and text of `drawing`, growing the buffer if needed. Text faces the
+    /// plane spanned by `right` and `up`.
+    pub fn write_draws(&mut self, drawing: &DebugDrawing, right: Vec3, up: Vec3) {
+        let count = |depth_test: bool| -> usize {
+            let shapes: usize = drawing
+                .draws()
+                .iter()
+                .filter(|d| d.depth_test == depth_test)
+                .map(|d| d.shape.vertex_count())
+                .sum();
+            let texts: usize = drawing
+                .texts()
+                .iter()
+                .filter(|t| t.depth_test == depth_test)
+                .map(|t| t.vertex_count())
+                .sum();
+            shapes + texts
+        };
+        self.depth_tested_count = count(true);
+        self.vertex_count = self.depth_tested_count + count(false);
 
         let new_size = self.vertex_count * std::mem::size_of::<DebugShapeVertex>();
         if let Some(new_buffer) = Buffer::expand(&self.buffer, new_size as u64, false) {
@@ -50,10 +81,26 @@ impl DebugVertexBuffer {
         let mut view = self.buffer.write(0).unwrap();
         let slice: &mut [DebugShapeVertex] = bytemuck::cast_slice_mut(&mut view[0..new_size]);
 
+        // Depth tested vertices go first so each group can be drawn with a single draw call
         let mut start = 0;
-        draws.iter().for_each(|draw| {
-            draw.shape.write_vertices(&mut slice[start..], draw.color);
-            start += draw.shape.vertex_count();
-        });
+        for depth_test in [true, false] {
+            for draw in drawing
+                .draws()
+                .iter()
+                .filter(|d| d.depth_test == depth_test)
+            {
+                draw.shape.write_vertices(&mut slice[start..], draw.color);
+                start += draw.shape.vertex_count();
+            }
+
+            for text in drawing
+                .texts()
+                .iter()
+                .filter(|t| t.depth_test == depth_test)
+            {
+                text.write_vertices(&mut slice[start..], right, up);
+                start += text.vertex_count();
+            }
+        }
     }
 }
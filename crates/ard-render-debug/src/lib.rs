@@ -1,21 +1,48 @@
 pub mod buffer;
 pub mod shape;
+pub mod text;
+
+use std::num::NonZeroUsize;
 
 use ard_ecs::prelude::*;
-use ard_math::Vec4;
+use ard_math::{Mat4, Quat, Vec3, Vec4};
 use shape::Shape;
 
+/// Debug shapes and text to draw for a single frame. Anything queued is drawn with the next
+/// rendered frame and then cleared.
 #[derive(Resource, Default)]
 pub struct DebugDrawing {
     draws: Vec<DebugDraw>,
+    texts: Vec<DebugText>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct DebugDraw {
     pub color: Vec4,
     pub shape: Shape,
+    /// If the shape should be hidden behind scene geometry. Depth tested shapes are drawn with
+    /// the scene, so they are affected by image effects like tonemapping.
+    pub depth_test: bool,
+}
+
+/// Text drawn in world space, facing the camera.
+#[derive(Debug, Clone)]
+pub struct DebugText {
+    /// Center of the text.
+    pub position: Vec3,
+    pub text: String,
+    /// Height of each character in world units.
+    pub height: f32,
+    pub color: Vec4,
+    pub depth_test: bool,
 }
 
+/// Number of segments used for curved shapes drawn with the helper functions.
+const DEFAULT_SEGMENTS: NonZeroUsize = NonZeroUsize::new(32).unwrap();
+
+/// Height of text drawn with [`DebugDrawing::text_3d`].
+const DEFAULT_TEXT_HEIGHT: f32 = 0.25;
+
 impl DebugDrawing {
     #[inline(always)]
     pub fn draws(&self) -> &[DebugDraw] {
@@ -23,12 +50,126 @@ impl DebugDrawing {
     }
 
     #[inline(always)]
-    pub fn draw(&mut self, draw: DebugDraw) {
+    pub fn texts(&self) -> &[DebugText] {
+        &self.texts
+    }
+
+    #[inline(always)]
+    pub fn draw(&mut self, draw: DebugDraw) -> &mut DebugDraw {
         self.draws.push(draw);
+        self.draws.last_mut().unwrap()
+    }
+
+    #[inline(always)]
+    pub fn text(&mut self, text: DebugText) -> &mut DebugText {
+        self.texts.push(text);
+        self.texts.last_mut().unwrap()
+    }
+
+    /// Draws a line from `a` to `b`.
+    #[inline(always)]
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: Vec4) -> &mut DebugDraw {
+        self.shape(Shape::Line { start: a, end: b }, color)
+    }
+
+    /// Draws an axis aligned bounding box.
+    #[inline(always)]
+    pub fn aabb(&mut self, min: Vec3, max: Vec3, color: Vec4) -> &mut DebugDraw {
+        self.shape(
+            Shape::Box {
+                min_pt: min,
+                max_pt: max,
+                model: Mat4::IDENTITY,
+            },
+            color,
+        )
+    }
+
+    #[inline(always)]
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec4) -> &mut DebugDraw {
+        self.shape(
+            Shape::Sphere {
+                radius,
+                model: Mat4::from_translation(center),
+                segments: DEFAULT_SEGMENTS,
+            },
+            color,
+        )
+    }
+
+    /// Draws a capsule around the line from `a` to `b`.
+    pub fn capsule(&mut self, a: Vec3, b: Vec3, radius: f32, color: Vec4) -> &mut DebugDraw {
+        let axis = b - a;
+        let rotation = match axis.try_normalize() {
+            Some(dir) => Quat::from_rotation_arc(Vec3::Y, dir),
+            None => Quat::IDENTITY,
+        };
+
+        self.shape(
+            Shape::Capsule {
+                radius,
+                height: axis.length(),
+                model: Mat4::from_rotation_translation(rotation, (a + b) * 0.5),
+                segments: DEFAULT_SEGMENTS,
+            },
+            color,
+        )
+    }
+
+    /// Draws the frustum of a view projection matrix. The matrix must have a finite far plane,
+    /// so it can't be used with the infinite projection of a camera.
+    #[inline(always)]
+    pub fn frustum(&mut self, view_proj: Mat4, color: Vec4) -> &mut DebugDraw {
+        self.shape(Shape::frustum(view_proj), color)
+    }
+
+    /// Draws `text` centered at `position`.
+    #[inline(always)]
+    pub fn text_3d(
+        &mut self,
+        position: Vec3,
+        text: impl Into<String>,
+        color: Vec4,
+    ) -> &mut DebugText {
+        self.text(DebugText {
+            position,
+            text: text.into(),
+            height: DEFAULT_TEXT_HEIGHT,
+            color,
+            depth_test: false,
+        })
     }
 
     #[inline(always)]
     pub fn clear(&mut self) {
-        self.draws.clear()
+        self.draws.clear();
+        self.texts.clear();
+    }
+
+    #[inline(always)]
+    fn shape(&mut self, shape: Shape, color: Vec4) -> &mut DebugDraw {
+        self.draw(DebugDraw {
+            color,
+            shape,
+            depth_test: false,
+        })
+    }
+}
+
+impl DebugDraw {
+    /// Hides the shape behind scene geometry.
+    #[inline(always)]
+    pub fn depth_tested(&mut self) -> &mut Self {
+        self.depth_test = true;
+        self
+    }
+}
+
+impl DebugText {
+    /// Hides the text behind scene geometry.
+    #[inline(always)]
+    pub fn depth_tested(&mut self) -> &mut Self {
+        self.depth_test = true;
+        self
     }
 }
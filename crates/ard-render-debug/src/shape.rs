@@ -37,6 +37,11 @@ pub enum Shape {
         model: Mat4,
         segments: NonZeroUsize,
     },
+    /// Corners are indexed with the X, Y, and Z bits as `x * 4 + y * 2 + z`, where a `0` bit is
+    /// the minimum in NDC space.
+    Frustum {
+        corners: [Vec3; 8],
+    },
 }
 
 #[repr(C)]
@@ -50,6 +55,23 @@ unsafe impl Pod for DebugShapeVertex {}
 unsafe impl Zeroable for DebugShapeVertex {}
 
 impl Shape {
+    /// Frustum of a view projection matrix with depth in the range `0..1`.
+    pub fn frustum(view_proj: Mat4) -> Self {
+        let inv = view_proj.inverse();
+        let mut corners = [Vec3::ZERO; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let ndc = Vec4::new(
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 1 == 0 { 0.0 } else { 1.0 },
+                1.0,
+            );
+            let pt = inv * ndc;
+            *corner = pt.xyz() / pt.w;
+        }
+        Shape::Frustum { corners }
+    }
+
     #[inline(always)]
     pub fn vertex_count(&self) -> usize {
         match self {
@@ -64,6 +86,7 @@ impl Shape {
             Shape::Capsule { segments, .. } => {
                 (segments.get() * 4) + (((segments.get() + 1) / 2) * 8) + 8
             }
+            Shape::Frustum { .. } => 24,
         }
     }
 
@@ -438,6 +461,27 @@ impl Shape {
                     pt.position /= pt.position.w;
                 }
             }
+            Shape::Frustum { corners } => {
+                // Each edge connects two corners that differ by a single bit
+                let mut i = 0;
+                for (a, corner) in corners.iter().enumerate() {
+                    for bit in [1, 2, 4] {
+                        if a & bit != 0 {
+                            continue;
+                        }
+
+                        dst[i] = DebugShapeVertex {
+                            position: Vec4::from((*corner, 1.0)),
+                            color,
+                        };
+                        dst[i + 1] = DebugShapeVertex {
+                            position: Vec4::from((corners[a | bit], 1.0)),
+                            color,
+                        };
+                        i += 2;
+                    }
+                }
+            }
         }
     }
 }
@@ -0,0 +1,158 @@
+use ard_math::{Vec2, Vec3, Vec4};
+
+use crate::{shape::DebugShapeVertex, DebugText};
+
+// Segments of a sixteen segment display. Each glyph is a mask of the segments it lights up.
+const TOP_L: u16 = 1 << 0;
+const TOP_R: u16 = 1 << 1;
+const RIGHT_U: u16 = 1 << 2;
+const RIGHT_L: u16 = 1 << 3;
+const BOTTOM_R: u16 = 1 << 4;
+const BOTTOM_L: u16 = 1 << 5;
+const LEFT_L: u16 = 1 << 6;
+const LEFT_U: u16 = 1 << 7;
+const DIAG_UL: u16 = 1 << 8;
+const CENTER_U: u16 = 1 << 9;
+const DIAG_UR: u16 = 1 << 10;
+const MID_L: u16 = 1 << 11;
+const MID_R: u16 = 1 << 12;
+const DIAG_LR: u16 = 1 << 13;
+const CENTER_L: u16 = 1 << 14;
+const DIAG_LL: u16 = 1 << 15;
+
+const TOP: u16 = TOP_L | TOP_R;
+const BOTTOM: u16 = BOTTOM_L | BOTTOM_R;
+const MID: u16 = MID_L | MID_R;
+const LEFT: u16 = LEFT_U | LEFT_L;
+const RIGHT: u16 = RIGHT_U | RIGHT_L;
+const CENTER: u16 = CENTER_U | CENTER_L;
+const BOX: u16 = TOP | BOTTOM | LEFT | RIGHT;
+
+/// End points of each segment within a unit glyph cell, indexed by the bit of the segment.
+const SEGMENTS: [(Vec2, Vec2); 16] = [
+    (Vec2::new(0.0, 1.0), Vec2::new(0.5, 1.0)),
+    (Vec2::new(0.5, 1.0), Vec2::new(1.0, 1.0)),
+    (Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.5)),
+    (Vec2::new(1.0, 0.5), Vec2::new(1.0, 0.0)),
+    (Vec2::new(1.0, 0.0), Vec2::new(0.5, 0.0)),
+    (Vec2::new(0.5, 0.0), Vec2::new(0.0, 0.0)),
+    (Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.5)),
+    (Vec2::new(0.0, 0.5), Vec2::new(0.0, 1.0)),
+    (Vec2::new(0.0, 1.0), Vec2::new(0.5, 0.5)),
+    (Vec2::new(0.5, 1.0), Vec2::new(0.5, 0.5)),
+    (Vec2::new(1.0, 1.0), Vec2::new(0.5, 0.5)),
+    (Vec2::new(0.0, 0.5), Vec2::new(0.5, 0.5)),
+    (Vec2::new(0.5, 0.5), Vec2::new(1.0, 0.5)),
+    (Vec2::new(0.5, 0.5), Vec2::new(1.0, 0.0)),
+    (Vec2::new(0.5, 0.5), Vec2::new(0.5, 0.0)),
+    (Vec2::new(0.5, 0.5), Vec2::new(0.0, 0.0)),
+];
+
+/// Width of a glyph relative to its height.
+const GLYPH_WIDTH: f32 = 0.6;
+
+/// Horizontal distance between the start of each glyph relative to the height.
+const GLYPH_ADVANCE: f32 = 0.8;
+
+impl DebugText {
+    #[inline(always)]
+    pub fn vertex_count(&self) -> usize {
+        self.text
+            .chars()
+            .map(|c| glyph(c).count_ones() as usize * 2)
+            .sum()
+    }
+
+    /// Writes the text as lines in the plane spanned by `right` and `up`, which should be the
+    /// directions of the camera so the text faces it.
+    pub fn write_vertices(&self, dst: &mut [DebugShapeVertex], right: Vec3, up: Vec3) {
+        let glyph_count = self.text.chars().count();
+        if glyph_count == 0 {
+            return;
+        }
+
+        let width = self.height * (GLYPH_ADVANCE * (glyph_count - 1) as f32 + GLYPH_WIDTH);
+        let origin = self.position - (right * width * 0.5) - (up * self.height * 0.5);
+
+        let mut i = 0;
+        for (n, c) in self.text.chars().enumerate() {
+            let mask = glyph(c);
+            let cell = origin + right * (n as f32 * GLYPH_ADVANCE * self.height);
+
+            for (bit, (a, b)) in SEGMENTS.iter().enumerate() {
+                if mask & (1 << bit) == 0 {
+                    continue;
+                }
+
+                for (j, pt) in [a, b].into_iter().enumerate() {
+                    let position = cell
+                        + right * (pt.x * GLYPH_WIDTH * self.height)
+                        + up * (pt.y * self.height);
+                    dst[i + j] = DebugShapeVertex {
+                        position: Vec4::from((position, 1.0)),
+                        color: self.color,
+                    };
+                }
+                i += 2;
+            }
+        }
+    }
+}
+
+/// Segments lit for a character. Lowercase letters are drawn as uppercase and unsupported
+/// characters are drawn as a box.
+fn glyph(c: char) -> u16 {
+    match c.to_ascii_uppercase() {
+        ' ' => 0,
+        '0' => BOX | DIAG_UR | DIAG_LL,
+        '1' => RIGHT,
+        '2' => TOP | RIGHT_U | MID | LEFT_L | BOTTOM,
+        '3' => TOP | RIGHT | MID_R | BOTTOM,
+        '4' => LEFT_U | MID | RIGHT,
+        '5' => TOP | LEFT_U | MID | RIGHT_L | BOTTOM,
+        '6' => TOP | LEFT | MID | RIGHT_L | BOTTOM,
+        '7' => TOP | RIGHT,
+        '8' => BOX | MID,
+        '9' => TOP | LEFT_U | MID | RIGHT | BOTTOM,
+        'A' => TOP | LEFT | RIGHT | MID,
+        'B' => TOP | RIGHT | MID_R | CENTER | BOTTOM,
+        'C' => TOP | LEFT | BOTTOM,
+        'D' => TOP | RIGHT | CENTER | BOTTOM,
+        'E' => TOP | LEFT | MID_L | BOTTOM,
+        'F' => TOP | LEFT | MID_L,
+        'G' => TOP | LEFT | BOTTOM | RIGHT_L | MID_R,
+        'H' => LEFT | RIGHT | MID,
+        'I' => TOP | CENTER | BOTTOM,
+        'J' => RIGHT | BOTTOM | LEFT_L,
+        'K' => LEFT | MID_L | DIAG_UR | DIAG_LR,
+        'L' => LEFT | BOTTOM,
+        'M' => LEFT | RIGHT | DIAG_UL | DIAG_UR,
+        'N' => LEFT | RIGHT | DIAG_UL | DIAG_LR,
+        'O' => BOX,
+        'P' => TOP | LEFT | RIGHT_U | MID,
+        'Q' => BOX | DIAG_LR,
+        'R' => TOP | LEFT | RIGHT_U | MID | DIAG_LR,
+        'S' => TOP | LEFT_U | MID | RIGHT_L | BOTTOM,
+        'T' => TOP | CENTER,
+        'U' => LEFT | RIGHT | BOTTOM,
+        'V' => LEFT | DIAG_LL | DIAG_UR,
+        'W' => LEFT | RIGHT | DIAG_LL | DIAG_LR,
+        'X' => DIAG_UL | DIAG_UR | DIAG_LL | DIAG_LR,
+        'Y' => DIAG_UL | DIAG_UR | CENTER_L,
+        'Z' => TOP | DIAG_UR | DIAG_LL | BOTTOM,
+        '-' => MID,
+        '+' => MID | CENTER,
+        '=' => MID | BOTTOM,
+        '_' => BOTTOM,
+        '*' => MID | CENTER | DIAG_UL | DIAG_UR | DIAG_LL | DIAG_LR,
+        '/' => DIAG_UR | DIAG_LL,
+        '\\' => DIAG_UL | DIAG_LR,
+        '|' | ':' => CENTER,
+        '.' | ',' => BOTTOM_L,
+        '(' | '[' => TOP_R | CENTER | BOTTOM_R,
+        ')' | ']' => TOP_L | CENTER | BOTTOM_L,
+        '<' => DIAG_UR | DIAG_LR,
+        '>' => DIAG_UL | DIAG_LL,
+        _ => BOX,
+    }
+}
@@ -1,17 +1,56 @@
+use std::sync::Arc;
+
 use ard_core::core::Tick;
 use ard_ecs::prelude::*;
-use ard_input::{InputState, Key, MouseButton};
+use ard_input::{Clipboard, ImeEvent, InputState, Key, MouseButton, ScrollDelta};
+use ard_math::IVec2;
+use ard_pal::prelude::Texture;
+use ard_render_base::FRAMES_IN_FLIGHT;
 use ard_render_si::consts::GUI_SCENE_TEXTURE_ID;
-use ard_window::prelude::*;
+use ard_window::{prelude::*, window::WindowResizeConstraints};
+use egui::{ViewportCommand, ViewportId, ViewportIdMap};
 use view::GuiView;
 
 pub mod view;
 
+/// Immediate mode GUI shown in the primary window.
+///
+/// Deferred viewports (see [`egui::Context::show_viewport_deferred`]) are shown in windows of
+/// their own, which are created and destroyed as viewports come and go. Immediate viewports are
+/// not supported and are embedded in their parent instead.
 #[derive(Resource)]
 pub struct Gui {
     ctx: egui::Context,
-    input: egui::RawInput,
+    viewports: ViewportIdMap<GuiViewport>,
+    /// Windows of closed viewports, along with how many more frames they must be kept alive for.
+    retired_windows: Vec<(WindowId, usize)>,
     views: Vec<Box<dyn GuiView + 'static>>,
+    scroll_speed: f32,
+    next_user_texture: u64,
+    /// User texture changes waiting to be sent to the renderer.
+    user_textures: Vec<(egui::TextureId, Option<GuiUserTexture>)>,
+}
+
+struct GuiViewport {
+    window: WindowId,
+    parent: ViewportId,
+    input: egui::RawInput,
+    builder: egui::ViewportBuilder,
+    /// Shows the viewport. `None` for the root viewport, which shows the views instead.
+    ui: Option<Arc<egui::DeferredViewportUiCallback>>,
+    /// Commands waiting to be applied to the window.
+    commands: Vec<ViewportCommand>,
+    /// Last pointer position sent to egui.
+    pointer: Option<egui::Pos2>,
+    close_requested: bool,
+}
+
+/// Whether the GUI is using input this frame. Gameplay systems should ignore pointer or keyboard
+/// input while the GUI wants it.
+#[derive(Debug, Default, Copy, Clone, Resource)]
+pub struct GuiInputCapture {
+    pub wants_pointer: bool,
+    pub wants_keyboard: bool,
 }
 
 #[derive(Default, SystemState)]
@@ -19,9 +58,44 @@ pub struct GuiInputCaptureSystem;
 
 #[derive(Default)]
 pub struct GuiRunOutput {
-    pub full: egui::FullOutput,
+    /// Platform output of every viewport, merged.
+    pub platform_output: egui::PlatformOutput,
+    /// Texture changes from every viewport. Textures are shared by all viewports.
+    pub textures_delta: egui::TexturesDelta,
+    /// What to draw in each viewport, including the root.
+    pub viewports: ViewportIdMap<GuiViewportOutput>,
+    /// Changes to user textures, in the order they were made. `None` frees the texture.
+    pub user_textures: Vec<(egui::TextureId, Option<GuiUserTexture>)>,
+}
+
+/// A texture owned outside of the GUI, like a render target, that egui can draw.
+#[derive(Clone)]
+pub struct GuiUserTexture {
+    /// Must be usable as `TextureUsage::SAMPLED`. Colors are expected to be premultiplied.
+    pub texture: Arc<Texture>,
+    /// Array element of the texture to sample.
+    pub array_element: usize,
+    pub options: egui::TextureOptions,
+}
+
+pub struct GuiViewportOutput {
+    /// Window the viewport is shown in.
+    pub window: WindowId,
+    /// Size of the window in physical pixels.
+    pub size: (u32, u32),
     pub primitives: Vec<egui::ClippedPrimitive>,
     pub pixels_per_point: f32,
+    pub cursor_icon: egui::CursorIcon,
+    pub ime: Option<egui::output::IMEOutput>,
+}
+
+impl GuiViewportOutput {
+    /// Where the text cursor of the focused text field is, in physical pixels. `None` if no
+    /// text field in this viewport has focus.
+    pub fn ime_cursor_rect(&self) -> Option<egui::Rect> {
+        let ime = self.ime?;
+        Some(ime.cursor_rect * self.pixels_per_point)
+    }
 }
 
 impl Default for Gui {
@@ -50,6 +124,7 @@ impl Default for Gui {
         egui_phosphor::add_to_fonts(&mut fonts, egui_phosphor::Variant::Fill);
 
         ctx.set_fonts(fonts);
+        ctx.set_embed_viewports(false);
 
         // Define styling
         ctx.style_mut(|style| {
@@ -68,8 +143,16 @@ impl Default for Gui {
 
         Self {
             ctx,
-            input: Default::default(),
+            viewports: std::iter::once((
+                ViewportId::ROOT,
+                GuiViewport::new(WindowId::primary(), ViewportId::ROOT),
+            ))
+            .collect(),
+            retired_windows: Vec::default(),
             views: Vec::default(),
+            scroll_speed: Self::DEFAULT_SCROLL_SPEED,
+            next_user_texture: 0,
+            user_textures: Vec::default(),
         }
     }
 }
@@ -77,13 +160,202 @@ impl Default for Gui {
 impl Gui {
     pub const SCENE_TEXTURE: egui::TextureId = egui::TextureId::User(GUI_SCENE_TEXTURE_ID as u64);
 
+    /// Points scrolled per line of mouse wheel movement.
+    pub const DEFAULT_SCROLL_SPEED: f32 = 50.0 / 3.0;
+
     pub fn add_view(&mut self, view: impl GuiView + 'static) {
         self.views.push(Box::new(view));
     }
 
-    pub fn gather_input(&mut self, input: &InputState, window: &Window) {
+    /// Makes a texture drawable by egui (e.g. with [`egui::Image`]). The texture is kept alive
+    /// until it's freed with [`Gui::free_texture`].
+    pub fn register_texture(&mut self, texture: GuiUserTexture) -> egui::TextureId {
+        let id = egui::TextureId::User(self.next_user_texture);
+        self.next_user_texture += 1;
+        self.user_textures.push((id, Some(texture)));
+        id
+    }
+
+    /// Swaps the texture behind a registered id, like when a render target is resized.
+    pub fn replace_texture(&mut self, id: egui::TextureId, texture: GuiUserTexture) {
+        self.user_textures.push((id, Some(texture)));
+    }
+
+    /// Unregisters a texture. It is released once frames using it are done rendering.
+    pub fn free_texture(&mut self, id: egui::TextureId) {
+        self.user_textures.push((id, None));
+    }
+
+    #[inline]
+    pub fn scroll_speed(&self) -> f32 {
+        self.scroll_speed
+    }
+
+    /// Sets how many points are scrolled per line of mouse wheel movement. Touchpads report exact
+    /// distances and are unaffected.
+    #[inline]
+    pub fn set_scroll_speed(&mut self, points_per_line: f32) {
+        self.scroll_speed = points_per_line;
+    }
+
+    /// Routes input to the viewports. Pointer input goes to the viewport under the cursor and
+    /// keyboard input goes to the viewport whose window has focus.
+    pub fn gather_input(
+        &mut self,
+        input: &InputState,
+        windows: &Windows,
+        clipboard: &mut Clipboard,
+    ) {
+        // Modifiers
+        let mut modifiers = egui::Modifiers {
+            alt: input.key(Key::LAlt) || input.key(Key::RAlt),
+            ctrl: input.key(Key::LCtrl) || input.key(Key::RCtrl),
+            shift: input.key(Key::LShift) || input.key(Key::RShift),
+            mac_cmd: cfg!(target_os = "macos") && (input.key(Key::LWin) || input.key(Key::RWin)),
+            command: false,
+        };
+        modifiers.command = if cfg!(target_os = "macos") {
+            modifiers.mac_cmd
+        } else {
+            modifiers.ctrl
+        };
+
+        // Mouse movement. Each window has its own coordinates, so this is tracked per viewport
+        for viewport in self.viewports.values_mut() {
+            viewport.input.modifiers = modifiers;
+
+            let pointer = windows
+                .get(viewport.window)
+                .and_then(Window::cursor_position)
+                .map(|position| egui::Pos2::new(position.x, position.y));
+
+            if pointer != viewport.pointer {
+                viewport.input.events.push(match pointer {
+                    Some(position) => egui::Event::PointerMoved(position),
+                    None => egui::Event::PointerGone,
+                });
+                viewport.pointer = pointer;
+            }
+        }
+
+        // // Don't bother gathering input if the mouse is locked
+        // if window.set_cursor_grab() {
+        //     return;
+        // }
+
+        if let Some(id) = self.find_viewport(|viewport| viewport.pointer.is_some()) {
+            let pixels_per_point = self.ctx.pixels_per_point();
+            self.viewports.get_mut(&id).unwrap().gather_pointer_input(
+                input,
+                self.scroll_speed,
+                pixels_per_point,
+            );
+        }
+
+        let keyboard_viewport = self
+            .find_viewport(|viewport| windows.get(viewport.window).is_some_and(Window::is_focused))
+            .unwrap_or(ViewportId::ROOT);
+        self.viewports
+            .get_mut(&keyboard_viewport)
+            .unwrap()
+            .gather_keyboard_input(input, clipboard);
+    }
+
+    /// Lets the viewport shown in `window` know that the user wants to close it. It is up to
+    /// whoever shows the viewport to stop doing so.
+    pub fn request_close(&mut self, window: WindowId) {
+        if let Some(viewport) = self
+            .viewports
+            .values_mut()
+            .find(|viewport| viewport.window == window)
+        {
+            viewport.close_requested = true;
+        }
+    }
+
+    pub fn run(
+        &mut self,
+        tick: Tick,
+        commands: &Commands,
+        queries: &Queries<Everything>,
+        res: &Res<Everything>,
+    ) -> GuiRunOutput {
+        let mut output = GuiRunOutput {
+            user_textures: std::mem::take(&mut self.user_textures),
+            ..Default::default()
+        };
+
+        // The root viewport shows the views, which may access resources, so `Windows` can't be
+        // borrowed until it's done
+        let input = {
+            let windows = res.get::<Windows>().unwrap();
+            self.take_input(ViewportId::ROOT, tick, &windows)
+        };
+        let full = self.ctx.run(input, |ctx| {
+            for view in &mut self.views {
+                view.show(tick, ctx, commands, queries, res);
+            }
+        });
+
+        let mut windows = res.get_mut::<Windows>().unwrap();
+        self.sync_viewports(&full, &mut windows, true);
+        self.finish_pass(ViewportId::ROOT, full, &windows, &mut output);
+
+        // Deferred viewports are shown once their window exists
+        let deferred: Vec<_> = self
+            .viewports
+            .iter()
+            .filter(|(_, viewport)| windows.get(viewport.window).is_some())
+            .filter_map(|(id, viewport)| Some((*id, viewport.ui.clone()?)))
+            .collect();
+
+        for (id, ui) in deferred {
+            let input = self.take_input(id, tick, &windows);
+            let full = self.ctx.run(input, |ctx| ui(ctx));
+            self.sync_viewports(&full, &mut windows, false);
+            self.finish_pass(id, full, &windows, &mut output);
+        }
+
+        self.apply_viewport_commands(&mut windows);
+
+        // Windows are kept alive until the renderer is done presenting to them
+        self.retired_windows.retain_mut(|(window, frames)| {
+            *frames -= 1;
+            if *frames == 0 {
+                windows.destroy(*window);
+            }
+            *frames != 0
+        });
+
+        output
+    }
+
+    /// Detached viewports sit on top of the root window, so they are preferred over it.
+    fn find_viewport(&self, predicate: impl Fn(&GuiViewport) -> bool) -> Option<ViewportId> {
+        self.viewports
+            .iter()
+            .filter(|(_, viewport)| predicate(viewport))
+            .map(|(id, _)| *id)
+            .max_by_key(|id| *id != ViewportId::ROOT)
+    }
+
+    fn take_input(&mut self, id: ViewportId, tick: Tick, windows: &Windows) -> egui::RawInput {
+        let viewports = self
+            .viewports
+            .iter()
+            .map(|(id, viewport)| (*id, viewport.info(windows)))
+            .collect();
+
+        let viewport = self.viewports.get_mut(&id).unwrap();
+        viewport.close_requested = false;
+
+        let mut input = std::mem::take(&mut viewport.input);
+        input.viewport_id = id;
+        input.viewports = viewports;
+        input.predicted_dt = tick.0.as_secs_f32();
+
         // Canvas size hint
-        self.input.screen_rect = Some(egui::Rect {
+        input.screen_rect = windows.get(viewport.window).map(|window| egui::Rect {
             min: egui::Pos2::ZERO,
             max: egui::Pos2::new(
                 window.physical_width() as f32,
@@ -91,11 +363,214 @@ impl Gui {
             ),
         });
 
-        // // Don't bother gathering input if the mouse is locked
-        // if window.set_cursor_grab() {
-        //     return;
-        // }
+        input
+    }
+
+    /// Creates windows for new viewports and queues up changes to existing ones. Viewports that
+    /// are gone are only detected after the root pass, since that's where viewports are shown.
+    fn sync_viewports(&mut self, full: &egui::FullOutput, windows: &mut Windows, is_root: bool) {
+        for (id, output) in &full.viewport_output {
+            // The root window belongs to the application
+            if *id == ViewportId::ROOT {
+                continue;
+            }
+
+            let viewport = self.viewports.entry(*id).or_insert_with(|| {
+                let window = WindowId::new(id.0.value() as usize);
+                let mut viewport = GuiViewport::new(window, output.parent);
+
+                // The window of a viewport that was just closed can be reused as is
+                match self.retired_windows.iter().position(|(w, _)| *w == window) {
+                    Some(i) => {
+                        self.retired_windows.swap_remove(i);
+                    }
+                    None => windows.create(window, window_descriptor(&output.builder)),
+                }
 
+                viewport
+                    .commands
+                    .extend(viewport.builder.patch(output.builder.clone()).0);
+                viewport
+            });
+
+            let (commands, _) = viewport.builder.patch(output.builder.clone());
+            viewport.commands.extend(commands);
+            viewport.commands.extend(output.commands.iter().cloned());
+            if let Some(ui) = &output.viewport_ui_cb {
+                viewport.ui = Some(ui.clone());
+            }
+        }
+
+        if is_root {
+            let retired_windows = &mut self.retired_windows;
+            self.viewports.retain(|id, viewport| {
+                let keep = full.viewport_output.contains_key(id);
+                if !keep {
+                    retired_windows.push((viewport.window, FRAMES_IN_FLIGHT + 1));
+                }
+                keep
+            });
+        }
+    }
+
+    fn finish_pass(
+        &mut self,
+        id: ViewportId,
+        mut full: egui::FullOutput,
+        windows: &Windows,
+        output: &mut GuiRunOutput,
+    ) {
+        let window = self.viewports.get(&id).unwrap().window;
+        let size = windows
+            .get(window)
+            .map(|window| (window.physical_width(), window.physical_height()))
+            .unwrap_or_default();
+
+        let primitives = self
+            .ctx
+            .tessellate(std::mem::take(&mut full.shapes), full.pixels_per_point);
+
+        output.viewports.insert(
+            id,
+            GuiViewportOutput {
+                window,
+                size,
+                primitives,
+                pixels_per_point: full.pixels_per_point,
+                cursor_icon: full.platform_output.cursor_icon,
+                ime: full.platform_output.ime,
+            },
+        );
+        output.textures_delta.append(full.textures_delta);
+        output.platform_output.append(full.platform_output);
+    }
+
+    fn apply_viewport_commands(&mut self, windows: &mut Windows) {
+        // Viewport sizes and positions are in points
+        let zoom = self.ctx.zoom_factor();
+
+        for viewport in self.viewports.values_mut() {
+            let window = match windows.get_mut(viewport.window) {
+                Some(window) => window,
+                None => continue,
+            };
+
+            for command in viewport.commands.drain(..) {
+                match command {
+                    ViewportCommand::Close => viewport.close_requested = true,
+                    ViewportCommand::CancelClose => viewport.close_requested = false,
+                    ViewportCommand::Title(title) => window.set_title(title),
+                    ViewportCommand::OuterPosition(position) => window.set_position(IVec2::new(
+                        (position.x * zoom) as i32,
+                        (position.y * zoom) as i32,
+                    )),
+                    ViewportCommand::InnerSize(size) => {
+                        window.set_resolution(size.x * zoom, size.y * zoom)
+                    }
+                    ViewportCommand::MinInnerSize(size) => {
+                        let mut constraints = window.resize_constraints();
+                        constraints.min_width = size.x * zoom;
+                        constraints.min_height = size.y * zoom;
+                        window.set_resize_constraints(constraints);
+                    }
+                    ViewportCommand::MaxInnerSize(size) => {
+                        let mut constraints = window.resize_constraints();
+                        constraints.max_width = size.x * zoom;
+                        constraints.max_height = size.y * zoom;
+                        window.set_resize_constraints(constraints);
+                    }
+                    ViewportCommand::Resizable(resizable) => window.set_resizable(resizable),
+                    ViewportCommand::Decorations(decorations) => {
+                        window.set_decorations(decorations)
+                    }
+                    ViewportCommand::Minimized(minimized) => window.set_minimized(minimized),
+                    ViewportCommand::Maximized(maximized) => window.set_maximized(maximized),
+                    ViewportCommand::Fullscreen(fullscreen) => window.set_mode(if fullscreen {
+                        WindowMode::BorderlessFullscreen
+                    } else {
+                        WindowMode::Windowed
+                    }),
+                    // Everything else is either handled through the platform output or isn't
+                    // supported
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+impl GuiViewport {
+    fn new(window: WindowId, parent: ViewportId) -> Self {
+        Self {
+            window,
+            parent,
+            input: egui::RawInput::default(),
+            builder: egui::ViewportBuilder::default(),
+            ui: None,
+            commands: Vec::default(),
+            pointer: None,
+            close_requested: false,
+        }
+    }
+
+    fn info(&self, windows: &Windows) -> egui::ViewportInfo {
+        let window = windows.get(self.window);
+        egui::ViewportInfo {
+            parent: Some(self.parent),
+            title: window.map(|window| window.title().to_owned()),
+            events: if self.close_requested {
+                vec![egui::ViewportEvent::Close]
+            } else {
+                Vec::default()
+            },
+            outer_rect: window.and_then(|window| {
+                let position = window.position()?;
+                Some(egui::Rect::from_min_size(
+                    egui::Pos2::new(position.x as f32, position.y as f32),
+                    egui::Vec2::new(
+                        window.physical_width() as f32,
+                        window.physical_height() as f32,
+                    ),
+                ))
+            }),
+            focused: window.map(Window::is_focused),
+            ..Default::default()
+        }
+    }
+
+    fn gather_pointer_input(
+        &mut self,
+        input: &InputState,
+        scroll_speed: f32,
+        pixels_per_point: f32,
+    ) {
+        // Scrolling. Egui zooms instead of scrolling when the command modifier is held
+        for delta in input.scroll_deltas() {
+            let delta = match *delta {
+                ScrollDelta::Lines(x, y) => egui::Vec2::new(x as f32, y as f32) * scroll_speed,
+                ScrollDelta::Pixels(x, y) => egui::Vec2::new(x as f32, y as f32) / pixels_per_point,
+            };
+
+            self.input.events.push(egui::Event::MouseWheel {
+                delta,
+                unit: egui::MouseWheelUnit::Point,
+                modifiers: self.input.modifiers,
+            });
+        }
+
+        if input.pinch_delta() != 0.0 {
+            self.input
+                .events
+                .push(egui::Event::Zoom((input.pinch_delta() as f32).exp()));
+        }
+
+        // Mouse buttons
+        self.handle_mouse_button(MouseButton::Left, egui::PointerButton::Primary, input);
+        self.handle_mouse_button(MouseButton::Right, egui::PointerButton::Secondary, input);
+        self.handle_mouse_button(MouseButton::Middle, egui::PointerButton::Middle, input);
+    }
+
+    fn gather_keyboard_input(&mut self, input: &InputState, clipboard: &mut Clipboard) {
         // Copy-paste and text input
         if !input.input_string().is_empty() {
             let mut final_txt = String::with_capacity(input.input_string().bytes().len());
@@ -119,34 +594,33 @@ impl Gui {
             self.input.events.push(egui::Event::Text(final_txt));
         }
 
-        // Modifiers
-        self.input.modifiers.alt |= input.key(Key::LAlt) || input.key(Key::RAlt);
-        self.input.modifiers.ctrl |= input.key(Key::LCtrl) || input.key(Key::RCtrl);
-        self.input.modifiers.shift |= input.key(Key::LShift) || input.key(Key::RShift);
-
-        // Mouse buttons
-        self.handle_mouse_button(MouseButton::Left, egui::PointerButton::Primary, input);
-        self.handle_mouse_button(MouseButton::Right, egui::PointerButton::Secondary, input);
-        self.handle_mouse_button(MouseButton::Middle, egui::PointerButton::Middle, input);
+        // Text composition
+        self.input
+            .events
+            .extend(input.ime_events().iter().map(|event| {
+                egui::Event::Ime(match event {
+                    ImeEvent::Enabled => egui::ImeEvent::Enabled,
+                    ImeEvent::Preedit(text) => egui::ImeEvent::Preedit(text.clone()),
+                    ImeEvent::Commit(text) => egui::ImeEvent::Commit(text.clone()),
+                    ImeEvent::Disabled => egui::ImeEvent::Disabled,
+                })
+            }));
+
+        // Clipboard shortcuts
+        if self.input.modifiers.command {
+            if input.key_down(Key::C) {
+                self.input.events.push(egui::Event::Copy);
+            }
 
-        // Mouse movement
-        let (del_x, del_y) = input.mouse_delta();
-        if del_x != 0.0 || del_y != 0.0 {
-            let (x, y) = input.mouse_pos();
-            self.input
-                .events
-                .push(egui::Event::PointerMoved(egui::Pos2::new(
-                    x as f32, y as f32,
-                )));
-        }
+            if input.key_down(Key::X) {
+                self.input.events.push(egui::Event::Cut);
+            }
 
-        let (del_x, del_y) = input.mouse_scroll();
-        if del_x != 0.0 || del_y != 0.0 {
-            self.input.events.push(egui::Event::MouseWheel {
-                delta: egui::Vec2::new(del_x as f32, del_y as f32),
-                unit: egui::MouseWheelUnit::Point,
-                modifiers: egui::Modifiers::NONE,
-            });
+            if input.key_down_repeat(Key::V) {
+                if let Some(text) = clipboard.get_text() {
+                    self.input.events.push(egui::Event::Paste(text));
+                }
+            }
         }
 
         // Keyboard input
@@ -186,40 +660,17 @@ impl Gui {
         keyboard_input(Key::PageDown, egui::Key::PageDown, input, &mut self.input);
     }
 
-    pub fn run(
-        &mut self,
-        tick: Tick,
-        commands: &Commands,
-        queries: &Queries<Everything>,
-        res: &Res<Everything>,
-    ) -> GuiRunOutput {
-        self.input.predicted_dt = tick.0.as_secs_f32();
-        let mut full = self.ctx.run(std::mem::take(&mut self.input), |ctx| {
-            for view in &mut self.views {
-                view.show(tick, ctx, commands, queries, res);
-            }
-        });
-        let primitives = self
-            .ctx
-            .tessellate(std::mem::take(&mut full.shapes), full.pixels_per_point);
-
-        GuiRunOutput {
-            full,
-            primitives,
-            pixels_per_point: self.ctx.pixels_per_point(),
-        }
-    }
-
     fn handle_mouse_button(
         &mut self,
         button: MouseButton,
         egui_button: egui::PointerButton,
         input: &InputState,
     ) {
+        let pos = self.pointer.unwrap_or_default();
+
         if input.mouse_button_down(button) {
-            let (x, y) = input.mouse_pos();
             self.input.events.push(egui::Event::PointerButton {
-                pos: egui::Pos2::new(x as f32, y as f32),
+                pos,
                 button: egui_button,
                 pressed: true,
                 modifiers: self.input.modifiers,
@@ -227,9 +678,8 @@ impl Gui {
         }
 
         if input.mouse_button_up(button) {
-            let (x, y) = input.mouse_pos();
             self.input.events.push(egui::Event::PointerButton {
-                pos: egui::Pos2::new(x as f32, y as f32),
+                pos,
                 button: egui_button,
                 pressed: false,
                 modifiers: self.input.modifiers,
@@ -238,19 +688,66 @@ impl Gui {
     }
 }
 
+/// Describes the window for a new viewport. Changes made after creation are applied through
+/// viewport commands instead.
+fn window_descriptor(builder: &egui::ViewportBuilder) -> WindowDescriptor {
+    let default = WindowDescriptor::default();
+    let mut resize_constraints = WindowResizeConstraints::default();
+    if let Some(size) = builder.min_inner_size {
+        resize_constraints.min_width = size.x;
+        resize_constraints.min_height = size.y;
+    }
+    if let Some(size) = builder.max_inner_size {
+        resize_constraints.max_width = size.x;
+        resize_constraints.max_height = size.y;
+    }
+
+    WindowDescriptor {
+        title: builder.title.clone().unwrap_or(default.title),
+        width: builder.inner_size.map_or(default.width, |size| size.x),
+        height: builder.inner_size.map_or(default.height, |size| size.y),
+        resize_constraints,
+        resizable: builder.resizable.unwrap_or(default.resizable),
+        decorations: builder.decorations.unwrap_or(default.decorations),
+        ..default
+    }
+}
+
 impl GuiInputCaptureSystem {
+    #[allow(clippy::type_complexity)]
     fn tick(
         &mut self,
         _: Tick,
         _: Commands,
         _: Queries<()>,
-        res: Res<(Write<Gui>, Read<InputState>, Read<Windows>)>,
+        res: Res<(
+            Write<Gui>,
+            Read<InputState>,
+            Read<Windows>,
+            Write<Clipboard>,
+            Write<GuiInputCapture>,
+        )>,
     ) {
         let mut gui = res.get_mut::<Gui>().unwrap();
         let input = res.get::<InputState>().unwrap();
         let windows = res.get::<Windows>().unwrap();
-        let window = windows.get(WindowId::primary()).unwrap();
-        gui.gather_input(&input, window);
+        let mut clipboard = res.get_mut::<Clipboard>().unwrap();
+        gui.gather_input(&input, &windows, &mut clipboard);
+
+        *res.get_mut::<GuiInputCapture>().unwrap() = GuiInputCapture {
+            wants_pointer: gui.ctx.wants_pointer_input(),
+            wants_keyboard: gui.ctx.wants_keyboard_input(),
+        };
+    }
+
+    fn window_closed(
+        &mut self,
+        evt: WindowClosed,
+        _: Commands,
+        _: Queries<()>,
+        res: Res<(Write<Gui>,)>,
+    ) {
+        res.get_mut::<Gui>().unwrap().request_close(evt.0);
     }
 }
 
@@ -258,6 +755,7 @@ impl From<GuiInputCaptureSystem> for System {
     fn from(state: GuiInputCaptureSystem) -> Self {
         SystemBuilder::new(state)
             .with_handler(GuiInputCaptureSystem::tick)
+            .with_handler(GuiInputCaptureSystem::window_closed)
             .build()
     }
 }
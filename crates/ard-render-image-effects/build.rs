@@ -146,6 +146,13 @@ fn main() {
         &[],
     );
 
+    ard_render_codegen::vulkan_spirv::compile_shader(
+        "./shaders/taa_resolve.comp",
+        PathBuf::from(&out_dir).join("taa_resolve.comp.spv"),
+        &["./shaders/"],
+        &[],
+    );
+
     ard_render_codegen::vulkan_spirv::compile_shader(
         "./shaders/lxaa.frag",
         PathBuf::from(&out_dir).join("lxaa.frag.spv"),
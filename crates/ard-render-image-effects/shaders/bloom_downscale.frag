@@ -9,6 +9,20 @@ layout(location = 0) out vec4 FRAGMENT_COLOR;
 
 layout(location = 0) in vec2 UV;
 
+layout(push_constant) uniform constants {
+    BloomDownscalePushConstants consts;
+};
+
+/// Keeps the part of a color brighter than the threshold, with a quadratic curve around the
+/// threshold so bright regions don't get hard edges.
+vec3 apply_threshold(vec3 color) {
+    const float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - consts.threshold + consts.knee, 0.0, 2.0 * consts.knee);
+    soft = (soft * soft) / (4.0 * consts.knee + 0.00001);
+    const float contribution = max(soft, brightness - consts.threshold) / max(brightness, 0.00001);
+    return color * contribution;
+}
+
 void main() {
     const uvec2 dim = textureSize(screen_tex, 0).xy;
 
@@ -58,5 +72,9 @@ void main() {
     downsample += (b+d+f+h)*0.0625;
     downsample += (j+k+l+m)*0.125;
 
+    if (consts.apply_threshold != 0) {
+        downsample = apply_threshold(downsample);
+    }
+
     FRAGMENT_COLOR = vec4(downsample, 1);
 }
\ No newline at end of file
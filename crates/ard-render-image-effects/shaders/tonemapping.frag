@@ -15,16 +15,100 @@ layout(push_constant) uniform constants {
     ToneMappingPushConstants consts;
 };
 
+const mat3 REC709_TO_REC2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+// Encodes linear luminance in nits with the ST 2084 (PQ) transfer function.
+vec3 pq_encode(vec3 nits) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+
+    vec3 y = pow(clamp(nits / 10000.0, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+// Fit of the ACES filmic curve by Krzysztof Narkowicz.
+vec3 tonemap_aces(vec3 color) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+// Reinhard applied to luminance so saturated colors keep their hue.
+vec3 tonemap_reinhard(vec3 color) {
+    const float lum = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return color / (1.0 + lum);
+}
+
+// Khronos PBR neutral. Leaves colors untouched until they get close to white.
+vec3 tonemap_neutral(vec3 color) {
+    const float start_compression = 0.8 - 0.04;
+    const float desaturation = 0.15;
+
+    const float x = min(color.r, min(color.g, color.b));
+    const float offset = x < 0.08 ? x - 6.25 * x * x : 0.04;
+    color -= offset;
+
+    const float peak = max(color.r, max(color.g, color.b));
+    if (peak < start_compression) {
+        return color;
+    }
+
+    const float d = 1.0 - start_compression;
+    const float new_peak = 1.0 - d * d / (peak + d - start_compression);
+    color *= new_peak / peak;
+
+    const float g = 1.0 - 1.0 / (desaturation * (peak - new_peak) + 1.0);
+    return mix(color, vec3(new_peak), g);
+}
+
 void main() {
     vec3 color = texture(screen_tex, UV).rgb;
-    vec3 bloom = texture(bloom_image, UV).rgb;
-    vec3 sun_shafts = texture(sun_shafts_image, UV).rgb;
-
-    // Tonemapping with adaptive luminance
-    color = mix(color, bloom, 0.05);
-    color += 0.2 * sun_shafts;
-    color = vec3(1.0) - exp(-color * (consts.exposure / luminance));
-    color = pow(color, vec3(1.0 / consts.gamma));
+
+    if (consts.bypass == 0) {
+        vec3 bloom = texture(bloom_image, UV).rgb;
+        vec3 sun_shafts = texture(sun_shafts_image, UV).rgb;
+
+        // Tonemapping with adaptive luminance
+        color += consts.bloom_intensity * bloom;
+        color += 0.2 * sun_shafts;
+        color *= consts.exposure / luminance;
+
+        switch (consts.tonemapper) {
+        case TONEMAPPER_REINHARD:
+            color = tonemap_reinhard(color);
+            break;
+        case TONEMAPPER_NEUTRAL:
+            color = tonemap_neutral(color);
+            break;
+        default:
+            color = tonemap_aces(color);
+            break;
+        }
+    }
+
+    // Encode for the color space of the surface
+    switch (consts.output_color_space) {
+    case OUTPUT_COLOR_SPACE_HDR_10:
+        color = pq_encode(REC709_TO_REC2020 * color * HDR_PAPER_WHITE_NITS);
+        break;
+    case OUTPUT_COLOR_SPACE_SC_RGB:
+        // scRGB is linear with 1.0 mapped to 80 nits
+        color *= HDR_PAPER_WHITE_NITS / 80.0;
+        break;
+    default:
+        color = pow(color, vec3(1.0 / consts.gamma));
+        break;
+    }
 
     FRAGMENT_COLOR = vec4(color, 1.0);
 }
\ No newline at end of file
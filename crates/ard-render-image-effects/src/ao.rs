@@ -92,6 +92,7 @@ pub const AO_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -107,6 +108,7 @@ const NEAREST_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -122,6 +124,7 @@ const LINEAR_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -137,6 +140,7 @@ const PREFILTERED_DEPTH_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -152,6 +156,7 @@ const NOISE_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::Repeat,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -177,6 +182,9 @@ impl AmbientOcclusion {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("ssao_noise".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -363,7 +371,7 @@ impl AmbientOcclusion {
 
                 let dispatch_x = width.div_ceil(WORK_GROUP_SIZE * 2);
                 let dispatch_y = height.div_ceil(WORK_GROUP_SIZE * 2);
-                ComputePassDispatch::Inline(dispatch_x, dispatch_y, 1)
+                pass.dispatch(dispatch_x, dispatch_y, 1);
             },
         );
 
@@ -373,7 +381,7 @@ impl AmbientOcclusion {
 
             let dispatch_x = width.div_ceil(WORK_GROUP_SIZE);
             let dispatch_y = height.div_ceil(WORK_GROUP_SIZE);
-            ComputePassDispatch::Inline(dispatch_x, dispatch_y, 1)
+            pass.dispatch(dispatch_x, dispatch_y, 1);
         });
 
         commands.compute_pass(&self.denoise, Some("ao_denoise"), |pass| {
@@ -382,7 +390,7 @@ impl AmbientOcclusion {
 
             let dispatch_x = width.div_ceil(WORK_GROUP_SIZE * 2);
             let dispatch_y = height.div_ceil(WORK_GROUP_SIZE);
-            ComputePassDispatch::Inline(dispatch_x, dispatch_y, 1)
+            pass.dispatch(dispatch_x, dispatch_y, 1);
         });
 
         commands.compute_pass(&self.filter, Some("ao_horz_bilateral_filter"), |pass| {
@@ -391,7 +399,7 @@ impl AmbientOcclusion {
 
             let dispatch_x = width.div_ceil(WORK_GROUP_SIZE);
             let dispatch_y = height.div_ceil(WORK_GROUP_SIZE);
-            ComputePassDispatch::Inline(dispatch_x, dispatch_y, 1)
+            pass.dispatch(dispatch_x, dispatch_y, 1);
         });
 
         consts[0].blur_dir = IVec2::new(0, 1);
@@ -402,7 +410,7 @@ impl AmbientOcclusion {
 
             let dispatch_x = width.div_ceil(WORK_GROUP_SIZE);
             let dispatch_y = height.div_ceil(WORK_GROUP_SIZE);
-            ComputePassDispatch::Inline(dispatch_x, dispatch_y, 1)
+            pass.dispatch(dispatch_x, dispatch_y, 1);
         });
     }
 }
@@ -425,6 +433,9 @@ impl AoImage {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("ao_prefiltered_depth_image".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -445,6 +456,9 @@ impl AoImage {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("ao_edges".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -465,6 +479,9 @@ impl AoImage {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("ao_image".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -475,6 +492,7 @@ impl AoImage {
                 DescriptorSetCreateInfo {
                     layout: ao.depth_prefilter_layout.clone(),
                     debug_name: Some("ao_depth_prefilter_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -536,6 +554,7 @@ impl AoImage {
                 DescriptorSetCreateInfo {
                     layout: ao.main_pass_layout.clone(),
                     debug_name: Some("ao_main_pass_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -550,6 +569,7 @@ impl AoImage {
                         base_mip: 0,
                         mip_count: prefiltered_depth.mip_count(),
                         sampler: PREFILTERED_DEPTH_SAMPLER,
+                        aspect: None,
                     },
                 },
                 DescriptorSetUpdate {
@@ -561,6 +581,7 @@ impl AoImage {
                         base_mip: 0,
                         mip_count: 1,
                         sampler: NOISE_SAMPLER,
+                        aspect: None,
                     },
                 },
                 DescriptorSetUpdate {
@@ -592,6 +613,7 @@ impl AoImage {
                 DescriptorSetCreateInfo {
                     layout: ao.denoise_layout.clone(),
                     debug_name: Some("ao_main_pass_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -606,6 +628,7 @@ impl AoImage {
                         base_mip: 0,
                         mip_count: 1,
                         sampler: LINEAR_SAMPLER,
+                        aspect: None,
                     },
                 },
                 DescriptorSetUpdate {
@@ -617,6 +640,7 @@ impl AoImage {
                         base_mip: 0,
                         mip_count: 1,
                         sampler: LINEAR_SAMPLER,
+                        aspect: None,
                     },
                 },
                 DescriptorSetUpdate {
@@ -639,6 +663,7 @@ impl AoImage {
                 DescriptorSetCreateInfo {
                     layout: ao.filter_layout.clone(),
                     debug_name: Some("ao_horz_bilateral_filter_pass_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -673,6 +698,7 @@ impl AoImage {
                 DescriptorSetCreateInfo {
                     layout: ao.filter_layout.clone(),
                     debug_name: Some("ao_vert_bilateral_filter_pass_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -728,6 +754,7 @@ impl AoImage {
                 sampler: NEAREST_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
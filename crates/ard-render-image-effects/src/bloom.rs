@@ -1,6 +1,6 @@
 use ard_pal::prelude::*;
 use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
-use ard_render_si::bindings::*;
+use ard_render_si::{bindings::*, types::GpuBloomDownscalePushConstants};
 use ordered_float::NotNan;
 
 const BLOOM_IMAGE_FORMAT: Format = Format::Rgba16SFloat;
@@ -14,6 +14,7 @@ pub const BLOOM_SAMPLE_FILTER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: None,
@@ -80,8 +81,12 @@ impl Bloom {
                         ..Default::default()
                     }],
                 },
-                push_constants_size: None,
+                push_constants_size: Some(
+                    std::mem::size_of::<GpuBloomDownscalePushConstants>() as u32
+                ),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some("bloom_downscale_pipeline".into()),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -125,7 +130,9 @@ impl Bloom {
                     }],
                 },
                 push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some("bloom_upscale_pipeline".into()),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -196,6 +203,9 @@ impl Bloom {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("bloom_image".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -217,6 +227,7 @@ impl Bloom {
                     DescriptorSetCreateInfo {
                         layout: layout.clone(),
                         debug_name: Some(format!("bloom_downscale_set_{i}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap();
@@ -231,6 +242,7 @@ impl Bloom {
                             sampler: BLOOM_SAMPLE_FILTER,
                             base_mip: i - 1,
                             mip_count: 1,
+                            aspect: None,
                         },
                     }]);
                 }
@@ -258,6 +270,7 @@ impl Bloom {
                     DescriptorSetCreateInfo {
                         layout: layout.clone(),
                         debug_name: Some(format!("bloom_upscale_set_{i}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap();
@@ -271,6 +284,7 @@ impl Bloom {
                         sampler: BLOOM_SAMPLE_FILTER,
                         base_mip: i + 1,
                         mip_count: 1,
+                        aspect: None,
                     },
                 }]);
 
@@ -291,16 +305,32 @@ impl Bloom {
                 sampler: BLOOM_SAMPLE_FILTER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
 
-    pub fn render<'a>(&'a self, frame: Frame, commands: &mut CommandBuffer<'a>) {
+    /// Pixels contribute to bloom based on how far their brightness is above `threshold`, with a
+    /// soft transition of width `knee` around it.
+    pub fn render<'a>(
+        &'a self,
+        frame: Frame,
+        commands: &mut CommandBuffer<'a>,
+        threshold: f32,
+        knee: f32,
+    ) {
         // Perform downscaling
         self.downscale_sets[usize::from(frame)]
             .iter()
             .enumerate()
             .for_each(|(mip, set)| {
+                // The threshold only applies when reading from the source image
+                let params = [GpuBloomDownscalePushConstants {
+                    threshold,
+                    knee: knee.max(0.0),
+                    apply_threshold: (mip == 0) as u32,
+                }];
+
                 commands.render_pass(
                     RenderPassDescriptor {
                         color_attachments: vec![ColorAttachment {
@@ -316,11 +346,14 @@ impl Bloom {
                         depth_stencil_attachment: None,
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     Some("bloom_downscale"),
                     |pass| {
                         pass.bind_pipeline(self.downscale.clone());
                         pass.bind_sets(0, vec![set]);
+                        pass.push_constants(bytemuck::cast_slice(&params));
                         pass.draw(3, 1, 0, 0);
                     },
                 );
@@ -347,6 +380,8 @@ impl Bloom {
                         depth_stencil_attachment: None,
                         color_resolve_attachments: Vec::default(),
                         depth_stencil_resolve_attachment: None,
+                        view_mask: 0,
+                        correlation_masks: Vec::default(),
                     },
                     Some("bloom_upscale"),
                     |pass| {
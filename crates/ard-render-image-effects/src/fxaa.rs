@@ -21,6 +21,7 @@ const SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     border_color: None,
@@ -77,7 +78,9 @@ impl Fxaa {
                     }],
                 },
                 push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some("fxaa_pipeline".into()),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -111,6 +114,8 @@ impl Fxaa {
                 depth_stencil_attachment: None,
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("FXAA"),
             |pass| {
@@ -132,6 +137,7 @@ impl FxaaSets {
                         DescriptorSetCreateInfo {
                             layout: fxaa.layout.clone(),
                             debug_name: Some(format!("fxaa_set_{i}")),
+                            variable_count: None,
                         },
                     )
                     .unwrap()
@@ -150,6 +156,7 @@ impl FxaaSets {
                 sampler: SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -4,4 +4,5 @@ pub mod fxaa;
 pub mod lxaa;
 pub mod smaa;
 pub mod sun_shafts2;
+pub mod taa;
 pub mod tonemapping;
@@ -25,6 +25,7 @@ const LXAA_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -88,7 +89,9 @@ impl Lxaa {
                     }],
                 },
                 push_constants_size: Some(std::mem::size_of::<GpuLxaaPushConstants>() as u32),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some("lxaa_pipeline".into()),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -99,6 +102,7 @@ impl Lxaa {
                 DescriptorSetCreateInfo {
                     layout: layouts.lxaa.clone(),
                     debug_name: Some("lxaa_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap()
@@ -126,6 +130,7 @@ impl Lxaa {
                 sampler: LXAA_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -157,6 +162,8 @@ impl Lxaa {
                 depth_stencil_attachment: None,
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("lxaa"),
             |pass| {
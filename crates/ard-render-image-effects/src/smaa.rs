@@ -41,6 +41,7 @@ pub const SMAA_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -89,6 +90,9 @@ impl Smaa {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("smaa_search_tex".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -124,6 +128,9 @@ impl Smaa {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("smaa_area_tex".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -172,6 +179,7 @@ impl Smaa {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("smaa_edges_indirect".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -285,7 +293,9 @@ impl Smaa {
                     }],
                 },
                 push_constants_size: Some(std::mem::size_of::<GpuSmaaPushConstants>() as u32),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some("smaa_blend_pipeline".into()),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -296,6 +306,7 @@ impl Smaa {
                 DescriptorSetCreateInfo {
                     layout: layouts.smaa_reset_edges.clone(),
                     debug_name: Some("smaa_reset_edges_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -318,6 +329,7 @@ impl Smaa {
                 DescriptorSetCreateInfo {
                     layout: layouts.smaa_edge_detect.clone(),
                     debug_name: Some("smaa_edge_detect_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -340,6 +352,7 @@ impl Smaa {
                 DescriptorSetCreateInfo {
                     layout: layouts.smaa_weights.clone(),
                     debug_name: Some("smaa_weights_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -354,6 +367,7 @@ impl Smaa {
                         sampler: SMAA_SAMPLER,
                         base_mip: 0,
                         mip_count: 1,
+                        aspect: None,
                     },
                 },
                 DescriptorSetUpdate {
@@ -365,6 +379,7 @@ impl Smaa {
                         sampler: SMAA_SAMPLER,
                         base_mip: 0,
                         mip_count: 1,
+                        aspect: None,
                     },
                 },
             ]);
@@ -378,6 +393,7 @@ impl Smaa {
                 DescriptorSetCreateInfo {
                     layout: layouts.smaa_blend.clone(),
                     debug_name: Some("smaa_blend_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap()
@@ -446,6 +462,7 @@ impl Smaa {
                     sampler: SMAA_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -468,6 +485,7 @@ impl Smaa {
                     sampler: SMAA_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -491,6 +509,7 @@ impl Smaa {
                     sampler: SMAA_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -502,6 +521,7 @@ impl Smaa {
                     sampler: SMAA_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -535,7 +555,7 @@ impl Smaa {
             Some("smaa_reset_edges"),
             |pass| {
                 pass.bind_sets(0, vec![&self.reset_edges_sets[frame]]);
-                ComputePassDispatch::Inline(1, 1, 1)
+                pass.dispatch(1, 1, 1);
             },
         );
 
@@ -543,43 +563,26 @@ impl Smaa {
         commands.compute_pass(&self.edges_pipeline, Some("smaa_edge_detect"), |pass| {
             pass.bind_sets(0, vec![&self.edges_sets[frame]]);
             pass.push_constants(bytemuck::cast_slice(&consts));
-            ComputePassDispatch::Inline(
+            pass.dispatch(
                 width.div_ceil(WORK_GROUP_SIZE_2D),
                 height.div_ceil(WORK_GROUP_SIZE_2D),
                 1,
-            )
+            );
         });
 
         // Reset blend texture
-        commands.render_pass(
-            RenderPassDescriptor {
-                color_attachments: vec![ColorAttachment {
-                    dst: ColorAttachmentDestination::Texture {
-                        texture: &self.blend_tex,
-                        array_element: 0,
-                        mip_level: 0,
-                    },
-                    load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0)),
-                    store_op: StoreOp::Store,
-                    samples: MultiSamples::Count1,
-                }],
-                depth_stencil_attachment: None,
-                color_resolve_attachments: Vec::default(),
-                depth_stencil_resolve_attachment: None,
-            },
-            Some("smaa_blend_reset"),
-            |_| {},
+        commands.clear_texture(
+            &self.blend_tex,
+            0,
+            0,
+            ClearColor::RgbaF32(0.0, 0.0, 0.0, 0.0),
         );
 
         // Create blend texture
         commands.compute_pass(&self.weights_pipeline, Some("smaa_weights"), |pass| {
             pass.bind_sets(0, vec![&self.weights_sets[frame]]);
             pass.push_constants(bytemuck::cast_slice(&consts));
-            ComputePassDispatch::Indirect {
-                buffer: &self.edges_indirect,
-                array_element: 0,
-                offset: 0,
-            }
+            pass.dispatch_indirect(&self.edges_indirect, 0, 0);
         });
 
         // Perform final blending onto the surface
@@ -594,6 +597,8 @@ impl Smaa {
                 depth_stencil_attachment: None,
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("smaa_blend"),
             |pass| {
@@ -622,6 +627,9 @@ impl Smaa {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("smaa_edges_tex".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -641,11 +649,14 @@ impl Smaa {
                 sample_count: MultiSamples::Count1,
                 texture_usage: TextureUsage::STORAGE
                     | TextureUsage::SAMPLED
-                    | TextureUsage::COLOR_ATTACHMENT,
+                    | TextureUsage::TRANSFER_DST,
                 memory_usage: MemoryUsage::GpuOnly,
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("smaa_blend_tex".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -662,6 +673,7 @@ impl Smaa {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("smaa_detected_edges".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
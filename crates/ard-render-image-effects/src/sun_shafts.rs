@@ -22,6 +22,7 @@ const DEPTH_SRC_IMAGE_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: None,
@@ -37,6 +38,7 @@ const SHADOW_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToBorder,
     anisotropy: None,
     compare: Some(CompareOp::Less),
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: Some(BorderColor::FloatOpaqueWhite),
@@ -85,6 +87,7 @@ impl SunShafts {
                     DescriptorSetCreateInfo {
                         layout: layouts.sun_shafts.clone(),
                         debug_name: Some(format!("sun_shafts_{frame}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -134,6 +137,7 @@ impl SunShafts {
                     sampler: DEPTH_SRC_IMAGE_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -163,6 +167,7 @@ impl SunShafts {
                 sampler: SHADOW_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         });
 
@@ -193,7 +198,7 @@ impl SunShafts {
             pass.push_constants(bytemuck::cast_slice(&params));
 
             let (width, height, _) = self.texture.dims();
-            ComputePassDispatch::Inline(width.div_ceil(BLOCK_SIZE), height.div_ceil(BLOCK_SIZE), 1)
+            pass.dispatch(width.div_ceil(BLOCK_SIZE), height.div_ceil(BLOCK_SIZE), 1);
         });
     }
 
@@ -214,6 +219,9 @@ impl SunShafts {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sun_shafts".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
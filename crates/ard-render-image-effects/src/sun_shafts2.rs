@@ -53,6 +53,7 @@ const DEPTH_SRC_IMAGE_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: None,
@@ -68,6 +69,7 @@ const SHADOW_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToBorder,
     anisotropy: None,
     compare: Some(CompareOp::Less),
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: Some(BorderColor::FloatOpaqueWhite),
@@ -92,6 +94,7 @@ impl SunShafts {
                 queue_types: QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sun_shaft_indirect_dispatch".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -206,6 +209,7 @@ impl SunShafts {
                 DescriptorSetCreateInfo {
                     layout: layouts.sun_shaft_line_setup.clone(),
                     debug_name: Some(format!("sun_shaft_line_setup_set_{i}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -246,6 +250,7 @@ impl SunShafts {
                 DescriptorSetCreateInfo {
                     layout: layouts.sun_shaft_refine.clone(),
                     debug_name: Some(format!("sun_shaft_refine_set_{i}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -286,6 +291,7 @@ impl SunShafts {
                 DescriptorSetCreateInfo {
                     layout: layouts.sun_shaft_sample.clone(),
                     debug_name: Some(format!("sun_shaft_sample_set_{i}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -318,6 +324,7 @@ impl SunShafts {
                 DescriptorSetCreateInfo {
                     layout: layouts.sun_shaft_interpolation.clone(),
                     debug_name: Some(format!("sun_shaft_interpolation_set_{i}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -481,6 +488,7 @@ impl SunShafts {
                     sampler: DEPTH_SRC_IMAGE_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -521,6 +529,7 @@ impl SunShafts {
                 sampler: SHADOW_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         });
         self.sample_sets[usize::from(frame)].update(&updates);
@@ -543,6 +552,7 @@ impl SunShafts {
                     sampler: DEPTH_SRC_IMAGE_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -588,11 +598,11 @@ impl SunShafts {
                 );
                 pass.push_constants(bytemuck::cast_slice(&params));
 
-                ComputePassDispatch::Inline(
+                pass.dispatch(
                     (self.sample_count as u32).div_ceil(WORK_GROUP_SIZE),
                     (self.line_count as u32).div_ceil(WORK_GROUP_SIZE),
                     1,
-                )
+                );
             },
         );
 
@@ -603,11 +613,11 @@ impl SunShafts {
             );
             pass.push_constants(bytemuck::cast_slice(&params));
 
-            ComputePassDispatch::Inline(
+            pass.dispatch(
                 (self.sample_count as u32).div_ceil(WORK_GROUP_SIZE),
                 (self.line_count as u32).div_ceil(WORK_GROUP_SIZE),
                 1,
-            )
+            );
         });
 
         commands.compute_pass(&self.sample_pipeline, Some("sun_shaft_sampling"), |pass| {
@@ -617,11 +627,7 @@ impl SunShafts {
             );
             pass.push_constants(bytemuck::cast_slice(&params));
 
-            ComputePassDispatch::Indirect {
-                buffer: &self.sample_dispatch_buffer,
-                array_element: 0,
-                offset: 0,
-            }
+            pass.dispatch_indirect(&self.sample_dispatch_buffer, 0, 0);
         });
 
         commands.compute_pass(
@@ -637,11 +643,11 @@ impl SunShafts {
                 );
                 pass.push_constants(bytemuck::cast_slice(&params));
 
-                ComputePassDispatch::Inline(
+                pass.dispatch(
                     (width as u32).div_ceil(WORK_GROUP_SIZE),
                     (height as u32).div_ceil(WORK_GROUP_SIZE),
                     1,
-                )
+                );
             },
         );
     }
@@ -668,6 +674,7 @@ impl SunShafts {
                 queue_types: QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sun_shaft_epipolar_lines".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -684,6 +691,7 @@ impl SunShafts {
                 queue_types: QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sun_shaft_epipolar_sample_indices".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -706,6 +714,9 @@ impl SunShafts {
                 queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sun_shafts".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
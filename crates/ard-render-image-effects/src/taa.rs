@@ -0,0 +1,278 @@
+use ard_ecs::prelude::*;
+use ard_math::{UVec2, Vec2};
+use ard_pal::prelude::*;
+use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
+use ard_render_camera::target::RenderTarget;
+use ard_render_si::{bindings::*, types::*};
+use ordered_float::NotNan;
+
+#[derive(Copy, Clone, Resource)]
+pub struct TaaSettings {
+    pub enabled: bool,
+    /// How much of the history is kept each frame. Higher values are smoother, but ghost more
+    /// behind moving objects.
+    pub history_weight: f32,
+}
+
+pub struct Taa {
+    pipeline: ComputePipeline,
+    sets: [DescriptorSet; FRAMES_IN_FLIGHT],
+    /// Resolved images to ping-pong between. The image written last frame is the history.
+    history: [Texture; 2],
+    /// Index of the history image written this frame.
+    dst_idx: usize,
+    /// Index into the jitter sequence.
+    sample_idx: usize,
+    /// Indicates the history images hold a resolved image of the previous frame.
+    history_valid: bool,
+    /// Indicates the history must be discarded when resolving this frame.
+    reset_history: bool,
+}
+
+const WORK_GROUP_SIZE: u32 = 8;
+
+/// Number of subpixel offsets before the jitter sequence repeats.
+const JITTER_SAMPLE_COUNT: usize = 8;
+
+const TAA_SAMPLER: Sampler = Sampler {
+    min_filter: Filter::Linear,
+    mag_filter: Filter::Linear,
+    mipmap_filter: Filter::Nearest,
+    address_u: SamplerAddressMode::ClampToEdge,
+    address_v: SamplerAddressMode::ClampToEdge,
+    address_w: SamplerAddressMode::ClampToEdge,
+    anisotropy: None,
+    compare: None,
+    reduction_mode: None,
+    min_lod: unsafe { NotNan::new_unchecked(0.0) },
+    max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
+    unnormalize_coords: false,
+    border_color: None,
+};
+
+impl Default for TaaSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            history_weight: 0.9,
+        }
+    }
+}
+
+impl Taa {
+    pub fn new(ctx: &Context, layouts: &Layouts, dims: (u32, u32)) -> Self {
+        let pipeline = ComputePipeline::new(
+            ctx.clone(),
+            ComputePipelineCreateInfo {
+                layouts: vec![layouts.taa.clone()],
+                module: Shader::new(
+                    ctx.clone(),
+                    ShaderCreateInfo {
+                        code: include_bytes!(concat!(env!("OUT_DIR"), "./taa_resolve.comp.spv")),
+                        debug_name: Some("taa_resolve_shader".into()),
+                    },
+                )
+                .unwrap(),
+                work_group_size: (WORK_GROUP_SIZE, WORK_GROUP_SIZE, 1),
+                push_constants_size: Some(std::mem::size_of::<GpuTaaPushConstants>() as u32),
+                debug_name: Some("taa_resolve_pipeline".into()),
+            },
+        )
+        .unwrap();
+
+        let sets = std::array::from_fn(|frame_idx| {
+            DescriptorSet::new(
+                ctx.clone(),
+                DescriptorSetCreateInfo {
+                    layout: layouts.taa.clone(),
+                    debug_name: Some(format!("taa_set_{frame_idx}")),
+                    variable_count: None,
+                },
+            )
+            .unwrap()
+        });
+
+        Self {
+            pipeline,
+            sets,
+            history: Self::create_history(ctx, dims),
+            dst_idx: 0,
+            sample_idx: 0,
+            history_valid: false,
+            reset_history: true,
+        }
+    }
+
+    /// The image resolved this frame.
+    #[inline(always)]
+    pub fn image(&self) -> &Texture {
+        &self.history[self.dst_idx]
+    }
+
+    /// Subpixel offset of the projection for the current frame in NDC.
+    pub fn jitter(&self, dims: (u32, u32)) -> Vec2 {
+        // Halton sequences start at zero, which would give a bias toward the corner
+        let idx = self.sample_idx + 1;
+        let offset = Vec2::new(halton(idx, 2), halton(idx, 3)) - Vec2::splat(0.5);
+        (offset * 2.0) / Vec2::new(dims.0 as f32, dims.1 as f32)
+    }
+
+    /// Reallocates the history images. History is discarded on the next resolve.
+    pub fn resize(&mut self, ctx: &Context, dims: (u32, u32)) {
+        self.history = Self::create_history(ctx, dims);
+        self.history_valid = false;
+    }
+
+    /// Discards the history on the next resolve. Must be called whenever a frame is rendered
+    /// without a resolve, or the resolve doesn't write a usable history.
+    #[inline(always)]
+    pub fn invalidate_history(&mut self) {
+        self.history_valid = false;
+    }
+
+    /// Swaps the history images, advances the jitter sequence, and binds the images needed for
+    /// the resolve. Must be called once before each frame that is resolved.
+    pub fn prepare(&mut self, frame: Frame, target: &RenderTarget) {
+        let frame = usize::from(frame);
+
+        self.reset_history = !self.history_valid;
+        self.history_valid = true;
+        self.dst_idx = (self.dst_idx + 1) % self.history.len();
+        self.sample_idx = (self.sample_idx + 1) % JITTER_SAMPLE_COUNT;
+
+        let src_idx = (self.dst_idx + 1) % self.history.len();
+
+        self.sets[frame].update(&[
+            DescriptorSetUpdate {
+                binding: TAA_SET_COLOR_BINDING,
+                array_element: 0,
+                value: DescriptorValue::Texture {
+                    texture: target.final_color(),
+                    array_element: 0,
+                    sampler: TAA_SAMPLER,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: TAA_SET_HISTORY_BINDING,
+                array_element: 0,
+                value: DescriptorValue::Texture {
+                    texture: &self.history[src_idx],
+                    array_element: 0,
+                    sampler: TAA_SAMPLER,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: TAA_SET_VEL_BINDING,
+                array_element: 0,
+                value: DescriptorValue::Texture {
+                    texture: target.final_vel(),
+                    array_element: 0,
+                    sampler: TAA_SAMPLER,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: TAA_SET_DEPTH_BINDING,
+                array_element: 0,
+                value: DescriptorValue::Texture {
+                    texture: target.final_depth(),
+                    array_element: 0,
+                    sampler: TAA_SAMPLER,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: TAA_SET_DST_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageImage {
+                    texture: &self.history[self.dst_idx],
+                    array_element: 0,
+                    mip: 0,
+                },
+            },
+        ]);
+    }
+
+    /// Resolves the current frame into [`image`](Self::image). When `show_motion_vectors` is
+    /// set, the motion vectors are written instead.
+    pub fn render<'a>(
+        &'a self,
+        frame: Frame,
+        commands: &mut CommandBuffer<'a>,
+        settings: &TaaSettings,
+        near_clip: f32,
+        show_motion_vectors: bool,
+    ) {
+        let (width, height, _) = self.image().dims();
+
+        let consts = [GpuTaaPushConstants {
+            target_dims: UVec2::new(width, height),
+            inv_target_dims: Vec2::new(1.0 / width as f32, 1.0 / height as f32),
+            history_weight: settings.history_weight.clamp(0.0, 1.0),
+            near_clip,
+            reset_history: self.reset_history as u32,
+            show_motion_vectors: show_motion_vectors as u32,
+        }];
+
+        commands.compute_pass(&self.pipeline, Some("taa_resolve"), |pass| {
+            pass.bind_sets(0, vec![&self.sets[usize::from(frame)]]);
+            pass.push_constants(bytemuck::cast_slice(&consts));
+            pass.dispatch(
+                width.div_ceil(WORK_GROUP_SIZE),
+                height.div_ceil(WORK_GROUP_SIZE),
+                1,
+            );
+        });
+    }
+
+    fn create_history(ctx: &Context, dims: (u32, u32)) -> [Texture; 2] {
+        std::array::from_fn(|i| {
+            Texture::new(
+                ctx.clone(),
+                TextureCreateInfo {
+                    format: RenderTarget::COLOR_TARGET_FORMAT,
+                    ty: TextureType::Type2D,
+                    width: dims.0,
+                    height: dims.1,
+                    depth: 1,
+                    array_elements: 1,
+                    mip_levels: 1,
+                    sample_count: MultiSamples::Count1,
+                    texture_usage: TextureUsage::STORAGE | TextureUsage::SAMPLED,
+                    memory_usage: MemoryUsage::GpuOnly,
+                    queue_types: QueueTypes::MAIN,
+                    sharing_mode: SharingMode::Exclusive,
+                    debug_name: Some(format!("taa_history_{i}")),
+                    sparse: false,
+                    initial_data: None,
+                    memory_fallback: MemoryFallback::None,
+                },
+            )
+            .unwrap()
+        })
+    }
+}
+
+/// Element `index` of the Halton sequence with the given `base`, on the range [0, 1).
+fn halton(mut index: usize, base: usize) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    result
+}
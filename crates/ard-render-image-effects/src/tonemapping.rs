@@ -6,6 +6,7 @@ use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
 use ard_render_camera::ubo::CameraUbo;
 use ard_render_si::{bindings::*, consts::*, types::*};
 use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
 
 use crate::bloom::BLOOM_SAMPLE_FILTER;
 
@@ -20,6 +21,7 @@ const HISTOGRAM_SRC_IMAGE_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: None,
@@ -35,19 +37,43 @@ const TONEMAPPING_SRC_IMAGE_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: None,
     unnormalize_coords: false,
 };
 
-#[derive(Copy, Clone, Resource)]
+/// Settings for the post-processing chain of bloom, auto exposure and tonemapping.
+#[derive(Copy, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TonemappingSettings {
     pub min_luminance: f32,
     pub max_luminance: f32,
     pub gamma: f32,
     pub exposure: f32,
+    /// How quickly exposure adapts to changes in scene luminance.
     pub auto_exposure_rate: f32,
+    pub tonemapper: Tonemapper,
+    /// Brightness where pixels begin contributing to bloom.
+    pub bloom_threshold: f32,
+    /// Width of the soft transition around `bloom_threshold`. `0.0` is a hard cutoff.
+    pub bloom_knee: f32,
+    /// How much bloom is added to the scene.
+    pub bloom_intensity: f32,
+    /// Skips bloom, auto exposure, and tonemapping, presenting the scene color as is. Useful for
+    /// debug views that show unlit values.
+    pub bypass: bool,
+}
+
+/// Operator used to map HDR scene color to the range of the display.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tonemapper {
+    #[default]
+    Aces,
+    Reinhard,
+    /// Khronos PBR neutral. Keeps the colors of materials accurate until they approach white.
+    Neutral,
 }
 
 impl Default for TonemappingSettings {
@@ -58,6 +84,11 @@ impl Default for TonemappingSettings {
             gamma: 2.2,
             exposure: 0.5,
             auto_exposure_rate: 4.0,
+            tonemapper: Tonemapper::default(),
+            bloom_threshold: 1.0,
+            bloom_knee: 0.5,
+            bloom_intensity: 0.05,
+            bypass: false,
         }
     }
 }
@@ -89,6 +120,7 @@ impl Tonemapping {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("luminance_histogram".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -103,6 +135,7 @@ impl Tonemapping {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("luminance".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -208,7 +241,9 @@ impl Tonemapping {
                     push_constants_size: Some(
                         std::mem::size_of::<GpuToneMappingPushConstants>() as u32
                     ),
+                    compile_mode: PipelineCompileMode::Blocking,
                     debug_name: Some("tonemapping_pipeline".into()),
+                    dynamic_states: DynamicStates::empty(),
                 },
             )
             .unwrap();
@@ -219,6 +254,7 @@ impl Tonemapping {
                 DescriptorSetCreateInfo {
                     layout: layouts.histogram_gen.clone(),
                     debug_name: Some(format!("histogram_gen_set_{frame}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -241,6 +277,7 @@ impl Tonemapping {
                 DescriptorSetCreateInfo {
                     layout: layouts.adaptive_lum.clone(),
                     debug_name: Some("luminance_gen_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -273,6 +310,7 @@ impl Tonemapping {
                 DescriptorSetCreateInfo {
                     layout: layouts.tonemapping.clone(),
                     debug_name: Some(format!("tonemapping_set_{frame}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -312,6 +350,7 @@ impl Tonemapping {
                 sampler: BLOOM_SAMPLE_FILTER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -326,6 +365,7 @@ impl Tonemapping {
                 sampler: BLOOM_SAMPLE_FILTER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -342,6 +382,7 @@ impl Tonemapping {
                 sampler: HISTOGRAM_SRC_IMAGE_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
 
@@ -355,6 +396,7 @@ impl Tonemapping {
                     sampler: TONEMAPPING_SRC_IMAGE_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -366,11 +408,15 @@ impl Tonemapping {
                     sampler: TONEMAPPING_SRC_IMAGE_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
     }
 
+    /// `color_space` is the color space of the surface being presented to, which determines how
+    /// the output is encoded.
+    #[allow(clippy::too_many_arguments)]
     pub fn render<'a>(
         &'a self,
         frame: Frame,
@@ -378,6 +424,7 @@ impl Tonemapping {
         camera: &'a CameraUbo,
         dst: ColorAttachmentDestination<'a>,
         settings: &TonemappingSettings,
+        color_space: ColorSpace,
         dt: Duration,
     ) {
         let lum_diff = (settings.max_luminance - settings.min_luminance).max(0.0001);
@@ -397,32 +444,47 @@ impl Tonemapping {
         let tonemapping_params = [GpuToneMappingPushConstants {
             exposure: settings.exposure,
             gamma: settings.gamma,
-        }];
-
-        // Adaptive luminance
-        commands.compute_pass(
-            &self.histogram_gen_pipeline,
-            Some("adaptive_lum_histogram_gen"),
-            |pass| {
-                pass.bind_sets(0, vec![&self.histogram_sets[usize::from(frame)]]);
-                pass.push_constants(bytemuck::cast_slice(&histogram_params));
-                ComputePassDispatch::Inline(
-                    self.screen_size.0.div_ceil(HISTOGRAM_GEN_BLOCK_SIZE),
-                    self.screen_size.1.div_ceil(HISTOGRAM_GEN_BLOCK_SIZE),
-                    1,
-                )
+            output_color_space: match color_space {
+                ColorSpace::SrgbNonlinear => OUTPUT_COLOR_SPACE_SRGB,
+                ColorSpace::Hdr10St2084 => OUTPUT_COLOR_SPACE_HDR_10,
+                ColorSpace::ExtendedSrgbLinear => OUTPUT_COLOR_SPACE_SC_RGB,
             },
-        );
-
-        commands.compute_pass(
-            &self.luminance_comp_pipeline,
-            Some("adaptive_lum_compute"),
-            |pass| {
-                pass.bind_sets(0, vec![&self.luminance_set]);
-                pass.push_constants(bytemuck::cast_slice(&lum_params));
-                ComputePassDispatch::Inline(1, 1, 1)
+            tonemapper: match settings.tonemapper {
+                Tonemapper::Aces => TONEMAPPER_ACES,
+                Tonemapper::Reinhard => TONEMAPPER_REINHARD,
+                Tonemapper::Neutral => TONEMAPPER_NEUTRAL,
             },
-        );
+            bloom_intensity: settings.bloom_intensity,
+            bypass: settings.bypass as u32,
+        }];
+
+        // Adaptive luminance. Not needed when bypassed, which also keeps the exposure from
+        // adapting to the debug view.
+        if !settings.bypass {
+            commands.compute_pass(
+                &self.histogram_gen_pipeline,
+                Some("adaptive_lum_histogram_gen"),
+                |pass| {
+                    pass.bind_sets(0, vec![&self.histogram_sets[usize::from(frame)]]);
+                    pass.push_constants(bytemuck::cast_slice(&histogram_params));
+                    pass.dispatch(
+                        self.screen_size.0.div_ceil(HISTOGRAM_GEN_BLOCK_SIZE),
+                        self.screen_size.1.div_ceil(HISTOGRAM_GEN_BLOCK_SIZE),
+                        1,
+                    );
+                },
+            );
+
+            commands.compute_pass(
+                &self.luminance_comp_pipeline,
+                Some("adaptive_lum_compute"),
+                |pass| {
+                    pass.bind_sets(0, vec![&self.luminance_set]);
+                    pass.push_constants(bytemuck::cast_slice(&lum_params));
+                    pass.dispatch(1, 1, 1);
+                },
+            );
+        }
 
         // Tonemapping pass
         commands.render_pass(
@@ -436,6 +498,8 @@ impl Tonemapping {
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_attachment: None,
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("tonemapping"),
             |pass| {
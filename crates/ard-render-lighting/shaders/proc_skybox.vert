@@ -75,7 +75,8 @@ void main() {
 #if defined(COLOR_PASS)
     const vec3 prv_pos = DIR + camera[gl_ViewIndex].last_position.xyz;
     const vec4 prv_ndc_pos = camera[gl_ViewIndex].last_vp * vec4(prv_pos, 1.0);
-    CUR_POS = ndc_pos;
+    // Remove the jitter so velocities match the unjittered last position
+    CUR_POS = vec4(ndc_pos.xy - (camera[gl_ViewIndex].jitter * ndc_pos.w), ndc_pos.zw);
     PRV_POS = prv_ndc_pos;
 #endif
 
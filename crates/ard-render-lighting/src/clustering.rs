@@ -1,9 +1,10 @@
 use ard_pal::prelude::*;
+use ard_render_camera::froxels::FroxelSettings;
 use ard_render_si::{
     bindings::{
         Layouts, LIGHT_CLUSTERING_SET_LIGHTS_BINDING, LIGHT_CLUSTERING_SET_LIGHT_CLUSTERS_BINDING,
     },
-    consts::{CAMERA_FROXELS_DEPTH, CAMERA_FROXELS_HEIGHT, CAMERA_FROXELS_WIDTH},
+    consts::{CAMERA_FROXELS_HEIGHT, CAMERA_FROXELS_WIDTH},
     types::GpuLightClusteringPushConstants,
 };
 
@@ -51,7 +52,10 @@ impl LightClusteringPipeline {
         commands: &mut CommandBuffer<'a>,
         set: &'a LightClusteringSet,
         camera_set: &'a DescriptorSet,
+        froxels: &FroxelSettings,
     ) {
+        let (_, _, depth) = froxels.dims();
+
         commands.compute_pass(&self.pipeline, Some("light_clustering"), |pass| {
             pass.bind_sets(0, vec![camera_set, set.get()]);
 
@@ -59,7 +63,7 @@ impl LightClusteringPipeline {
                 total_lights: set.light_count() as u32,
             }];
             pass.push_constants(bytemuck::cast_slice(&constants));
-            ComputePassDispatch::Inline(1, 1, CAMERA_FROXELS_DEPTH as u32)
+            pass.dispatch(1, 1, depth);
         });
     }
 }
@@ -72,6 +76,7 @@ impl LightClusteringSet {
                 DescriptorSetCreateInfo {
                     layout: layouts.light_clustering.clone(),
                     debug_name: Some(format!("light_clustering_set")),
+                    variable_count: None,
                 },
             )
             .unwrap(),
@@ -100,9 +105,12 @@ impl LightClusteringSet {
         }]);
     }
 
-    pub fn bind_lights(&mut self, lights: &Lights) {
-        self.light_count = lights.light_count();
+    #[inline(always)]
+    pub fn set_light_count(&mut self, light_count: usize) {
+        self.light_count = light_count;
+    }
 
+    pub fn bind_lights(&mut self, lights: &Lights) {
         self.set.update(&[DescriptorSetUpdate {
             binding: LIGHT_CLUSTERING_SET_LIGHTS_BINDING,
             array_element: 0,
@@ -2,14 +2,11 @@ use ard_ecs::prelude::*;
 use ard_math::{Vec3, Vec4, Vec4Swizzles};
 use ard_render_si::types::GpuGlobalLighting;
 
-use crate::shadows::ShadowCascadeSettings;
-
 #[derive(Resource, Clone)]
 pub struct GlobalLighting {
     ambient_color_intensity: Vec4,
     sun_color_intensity: Vec4,
     sun_direction: Vec4,
-    cascades: Vec<ShadowCascadeSettings>,
 }
 
 impl Default for GlobalLighting {
@@ -18,40 +15,6 @@ impl Default for GlobalLighting {
             ambient_color_intensity: Vec4::new(1.0, 1.0, 1.0, 0.2),
             sun_color_intensity: Vec4::new(1.0, 0.98, 0.92, 32.0),
             sun_direction: Vec4::new(1.0, -1.0, 1.0, 0.0).normalize(),
-            cascades: vec![
-                ShadowCascadeSettings {
-                    min_depth_bias: 0.0,
-                    max_depth_bias: 0.005,
-                    normal_bias: 0.1,
-                    filter_size: 2.0,
-                    resolution: 4096,
-                    end_distance: 15.0,
-                },
-                ShadowCascadeSettings {
-                    min_depth_bias: 0.005,
-                    max_depth_bias: 0.05,
-                    normal_bias: 0.1,
-                    filter_size: 2.0,
-                    resolution: 4096,
-                    end_distance: 40.0,
-                },
-                ShadowCascadeSettings {
-                    min_depth_bias: 0.005,
-                    max_depth_bias: 0.05,
-                    normal_bias: 0.1,
-                    filter_size: 2.0,
-                    resolution: 4096,
-                    end_distance: 100.0,
-                },
-                ShadowCascadeSettings {
-                    min_depth_bias: 0.005,
-                    max_depth_bias: 0.05,
-                    normal_bias: 0.25,
-                    filter_size: 2.0,
-                    resolution: 4096,
-                    end_distance: 300.0,
-                },
-            ],
         }
     }
 }
@@ -62,6 +25,7 @@ impl GlobalLighting {
             ambient_color_intensity: self.ambient_color_intensity,
             sun_color_intensity: self.sun_color_intensity,
             sun_direction: self.sun_direction,
+            light_cluster_heatmap: 0,
         }
     }
 
@@ -90,11 +54,6 @@ impl GlobalLighting {
         self.sun_direction.xyz().normalize()
     }
 
-    #[inline]
-    pub fn shadow_cascades(&self) -> &[ShadowCascadeSettings] {
-        &self.cascades
-    }
-
     #[inline]
     pub fn set_ambient_color(&mut self, color: Vec3) {
         self.ambient_color_intensity = Vec4::from((color, self.ambient_color_intensity.w));
@@ -122,9 +81,4 @@ impl GlobalLighting {
             0.0,
         ));
     }
-
-    #[inline]
-    pub fn set_shadow_cascade_settings(&mut self, cascades: &[ShadowCascadeSettings]) {
-        self.cascades = cascades.into();
-    }
 }
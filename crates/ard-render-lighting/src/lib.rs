@@ -1,10 +1,11 @@
 use ard_ecs::component::Component;
 use ard_math::{Vec3, Vec4};
-use ard_render_si::types::GpuLight;
+use ard_render_si::{consts::NO_LOCAL_SHADOW, types::GpuLight};
 
 pub mod clustering;
 pub mod global;
 pub mod lights;
+pub mod local_shadows;
 pub mod proc_skybox;
 pub mod reflections;
 pub mod shadows;
@@ -36,6 +37,7 @@ impl Light {
                 color_intensity: Vec4::new(color.x, color.y, color.z, intensity),
                 position_range: Vec4::new(position.x, position.y, position.z, range),
                 direction_angle: Vec4::NEG_ONE,
+                shadow_index: NO_LOCAL_SHADOW,
             },
             Light::Spot {
                 color,
@@ -46,6 +48,7 @@ impl Light {
                 color_intensity: Vec4::new(color.x, color.y, color.z, intensity),
                 position_range: Vec4::new(position.x, position.y, position.z, range),
                 direction_angle: Vec4::new(direction.x, direction.y, direction.z, half_angle),
+                shadow_index: NO_LOCAL_SHADOW,
             },
         }
     }
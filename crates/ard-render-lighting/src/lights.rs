@@ -1,8 +1,9 @@
 use ard_core::core::Disabled;
 use ard_ecs::{entity::Entity, resource::Resource};
+use ard_math::Vec3;
 use ard_pal::prelude::*;
 use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
-use ard_render_camera::ubo::CameraUbo;
+use ard_render_camera::{froxels::FroxelSettings, ubo::CameraUbo};
 use ard_render_si::{
     bindings::Layouts,
     types::{GpuGlobalLighting, GpuLight, GpuLightTable},
@@ -33,6 +34,17 @@ pub struct Lights {
     global_properties: GlobalLighting,
     lights: Buffer,
     count: usize,
+    /// Enabled lights in the order they were written to the light buffer.
+    instances: Vec<LightInstance>,
+}
+
+/// A light captured from the primary ECS.
+#[derive(Debug, Copy, Clone)]
+pub struct LightInstance {
+    pub entity: Entity,
+    pub light: Light,
+    pub position: Vec3,
+    pub direction: Vec3,
 }
 
 impl LightClusters {
@@ -44,9 +56,10 @@ impl LightClusters {
                 array_elements: 1,
                 buffer_usage: BufferUsage::STORAGE_BUFFER,
                 memory_usage: MemoryUsage::GpuOnly,
-                queue_types: QueueTypes::MAIN,
+                queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: Some("light_clusters".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -71,10 +84,13 @@ impl LightClusters {
         &self.clusters
     }
 
+    /// Updates the light count and rebinds the light buffer of `frame` if it was expanded.
     #[inline(always)]
     pub fn update_set(&mut self, frame: Frame, lights: &Lights) {
+        let set = &mut self.sets[usize::from(frame)];
+        set.set_light_count(lights.light_count());
         if lights.buffer_expanded > 0 {
-            self.sets[usize::from(frame)].bind_lights(lights);
+            set.bind_lights(lights);
         }
     }
 
@@ -84,11 +100,13 @@ impl LightClusters {
         commands: &mut CommandBuffer<'a>,
         frame: Frame,
         camera: &'a CameraUbo,
+        froxels: &FroxelSettings,
     ) {
         self.pipeline.cluster(
             commands,
             &self.sets[usize::from(frame)],
             camera.get_set(frame),
+            froxels,
         );
     }
 }
@@ -103,9 +121,10 @@ impl Lights {
                     array_elements: 1,
                     buffer_usage: BufferUsage::STORAGE_BUFFER,
                     memory_usage: MemoryUsage::CpuToGpu,
-                    queue_types: QueueTypes::MAIN,
+                    queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                     sharing_mode: SharingMode::Concurrent,
                     debug_name: Some("lights".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -120,10 +139,12 @@ impl Lights {
                     queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                     sharing_mode: SharingMode::Concurrent,
                     debug_name: Some("global_lighting".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
             count: 0,
+            instances: Vec::default(),
             buffer_expanded: 2,
         }
     }
@@ -153,9 +174,32 @@ impl Lights {
         self.count
     }
 
-    pub fn update_global(&mut self, global: &GlobalLighting) {
+    /// Enabled lights, indexed the same as the light buffer.
+    #[inline(always)]
+    pub fn instances(&self) -> &[LightInstance] {
+        &self.instances
+    }
+
+    /// Assigns the first local shadow view of the light at `idx`.
+    pub fn set_shadow_index(&mut self, idx: usize, shadow_index: u32) {
+        let instance = &self.instances[idx];
+        let mut gpu_light = instance
+            .light
+            .to_gpu_light(instance.position, instance.direction);
+        gpu_light.shadow_index = shadow_index;
+
+        let mut view = self.lights.write(0).unwrap();
+        view.set_as_array(gpu_light, idx);
+    }
+
+    /// Updates global lighting. `cluster_heatmap` replaces lit surfaces with the number of lights
+    /// in their cluster.
+    pub fn update_global(&mut self, global: &GlobalLighting, cluster_heatmap: bool) {
+        let mut gpu_global = global.to_gpu();
+        gpu_global.light_cluster_heatmap = cluster_heatmap as u32;
+
         let mut global_view = self.global.write(0).unwrap();
-        global_view.set_as_array(global.to_gpu(), 0);
+        global_view.set_as_array(gpu_global, 0);
         self.global_properties = global.clone();
     }
 
@@ -174,17 +218,26 @@ impl Lights {
 
         let mut view = self.lights.write(0).unwrap();
         self.count = 0;
+        self.instances.clear();
 
-        for (_, (light, mdl), disabled) in lights.into_iter() {
+        for (entity, (light, mdl), disabled) in lights.into_iter() {
             if disabled.is_some() {
                 continue;
             }
 
+            let instance = LightInstance {
+                entity,
+                light: *light,
+                position: mdl.position().into(),
+                direction: mdl.forward(),
+            };
+
             view.set_as_array(
-                light.to_gpu_light(mdl.position().into(), mdl.forward()),
+                light.to_gpu_light(instance.position, instance.direction),
                 self.count,
             );
 
+            self.instances.push(instance);
             self.count += 1;
         }
     }
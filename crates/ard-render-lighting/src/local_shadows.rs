@@ -0,0 +1,565 @@
+use ard_ecs::entity::Entity;
+use ard_math::{Mat4, UVec4, Vec2, Vec3, Vec4, Vec4Swizzles};
+use ard_pal::prelude::*;
+use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
+use ard_render_camera::Camera;
+use ard_render_si::{consts::*, types::*};
+use ard_transform::Model;
+use rustc_hash::FxHashSet;
+
+use crate::{lights::Lights, shadows::LocalShadowSettings, Light};
+
+/// Near clipping plane of every local shadow view.
+const LOCAL_SHADOW_NEAR: f32 = 0.05;
+
+/// Priority multiplier for lights that were shadowed last frame.
+const SHADOWED_PRIORITY_BIAS: f32 = 1.5;
+
+/// Directions of the views of a point light. The lighting pass picks the face with the same
+/// ordering, so this must not change.
+const CUBE_FACES: [Vec3; 6] = [
+    Vec3::X,
+    Vec3::NEG_X,
+    Vec3::Y,
+    Vec3::NEG_Y,
+    Vec3::Z,
+    Vec3::NEG_Z,
+];
+
+/// Assigns tiles of the local shadow atlas to point and spot lights and holds the views rendered
+/// into them.
+pub struct LocalShadows {
+    /// Per view data read by the lighting pass. One array element per frame in flight.
+    buffer: Buffer,
+    allocator: ShadowAtlasAllocator,
+    views: Vec<LocalShadowView>,
+    /// Lights that were shadowed last frame. They're favored when ranking lights so lights near
+    /// the edge of the budget don't flicker between being shadowed and unshadowed.
+    shadowed: FxHashSet<Entity>,
+    candidates: Vec<ShadowCandidate>,
+    statistics: LocalShadowStatistics,
+}
+
+/// A view of a light rendered into the local shadow atlas.
+#[derive(Copy, Clone)]
+pub struct LocalShadowView {
+    pub tile: AtlasTile,
+    pub camera: GpuCamera,
+}
+
+/// Local shadow usage of a frame.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalShadowStatistics {
+    /// Lights given tiles in the atlas.
+    pub lights_shadowed: usize,
+    /// Visible lights that didn't fit in the atlas, so they were rendered without shadows.
+    pub lights_unshadowed: usize,
+    /// Atlas tiles rendered. Point lights use six tiles and spot lights use one.
+    pub tiles_used: usize,
+    /// Fraction of the atlas covered by tiles.
+    pub atlas_usage: f32,
+}
+
+/// A square region of the shadow atlas in texels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AtlasTile {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+}
+
+/// Quad-tree allocator handing out square, power of two sized tiles of the shadow atlas.
+pub struct ShadowAtlasAllocator {
+    size: u32,
+    nodes: Vec<AtlasNode>,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum AtlasNode {
+    Free,
+    Used,
+    /// The node is split into four quadrants. Children are stored contiguously in row major
+    /// order.
+    Split {
+        first_child: usize,
+    },
+}
+
+struct ShadowCandidate {
+    idx: usize,
+    entity: Entity,
+    coverage: f32,
+    priority: f32,
+}
+
+impl LocalShadows {
+    pub fn new(ctx: &Context, settings: &LocalShadowSettings) -> Self {
+        Self {
+            buffer: Buffer::new(
+                ctx.clone(),
+                BufferCreateInfo {
+                    size: (std::mem::size_of::<GpuLocalShadow>() * MAX_LOCAL_SHADOW_VIEWS) as u64,
+                    array_elements: FRAMES_IN_FLIGHT,
+                    buffer_usage: BufferUsage::STORAGE_BUFFER,
+                    memory_usage: MemoryUsage::CpuToGpu,
+                    queue_types: QueueTypes::MAIN,
+                    sharing_mode: SharingMode::Concurrent,
+                    debug_name: Some("local_shadows".into()),
+                    memory_fallback: MemoryFallback::None,
+                },
+            )
+            .unwrap(),
+            allocator: ShadowAtlasAllocator::new(settings.atlas_size),
+            views: Vec::default(),
+            shadowed: FxHashSet::default(),
+            candidates: Vec::default(),
+            statistics: LocalShadowStatistics::default(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Views to render into the atlas this frame.
+    #[inline(always)]
+    pub fn views(&self) -> &[LocalShadowView] {
+        &self.views
+    }
+
+    #[inline(always)]
+    pub fn statistics(&self) -> LocalShadowStatistics {
+        self.statistics
+    }
+
+    /// Ranks lights by how much of the screen they cover, assigns atlas tiles to as many as fit,
+    /// and writes the shadow index of each shadowed light. Lights that don't fit are left
+    /// unshadowed.
+    pub fn update(
+        &mut self,
+        frame: Frame,
+        settings: &LocalShadowSettings,
+        lights: &mut Lights,
+        camera: &Camera,
+        camera_model: Model,
+        screen_dims: (u32, u32),
+    ) {
+        let last_shadowed = std::mem::take(&mut self.shadowed);
+        self.views.clear();
+        self.candidates.clear();
+        self.statistics = LocalShadowStatistics::default();
+        self.allocator.reset(settings.atlas_size);
+
+        if settings.enabled {
+            let main_camera = camera.into_gpu_struct(
+                screen_dims.0.max(1) as f32,
+                screen_dims.1.max(1) as f32,
+                camera_model,
+                Vec2::ZERO,
+            );
+
+            for (idx, instance) in lights.instances().iter().enumerate() {
+                let range = match instance.light {
+                    Light::Point { range, .. } | Light::Spot { range, .. } => range,
+                };
+
+                let coverage = screen_coverage(&main_camera, instance.position, range);
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                let priority = if last_shadowed.contains(&instance.entity) {
+                    coverage * SHADOWED_PRIORITY_BIAS
+                } else {
+                    coverage
+                };
+
+                self.candidates.push(ShadowCandidate {
+                    idx,
+                    entity: instance.entity,
+                    coverage,
+                    priority,
+                });
+            }
+
+            // Ties are broken by entity so the order is the same every frame
+            self.candidates.sort_unstable_by(|a, b| {
+                b.priority
+                    .total_cmp(&a.priority)
+                    .then(a.entity.id().cmp(&b.entity.id()))
+            });
+
+            let min_tile = settings.min_tile_size.max(1).next_power_of_two();
+            let max_tile = settings.max_tile_size.max(min_tile).next_power_of_two();
+            let mut tiles = Vec::with_capacity(CUBE_FACES.len());
+
+            for candidate in &self.candidates {
+                let instance = lights.instances()[candidate.idx];
+                let view_count = match instance.light {
+                    Light::Point { .. } => CUBE_FACES.len(),
+                    Light::Spot { .. } => 1,
+                };
+
+                if self.views.len() + view_count > MAX_LOCAL_SHADOW_VIEWS {
+                    self.statistics.lights_unshadowed += 1;
+                    continue;
+                }
+
+                // Tiles scale with the size of the light on screen, and shrink until they fit
+                let mut tile_size = ((max_tile as f32 * candidate.coverage.sqrt()) as u32)
+                    .clamp(min_tile, max_tile)
+                    .next_power_of_two();
+
+                let fits = loop {
+                    if self
+                        .allocator
+                        .allocate_many(tile_size, view_count, &mut tiles)
+                    {
+                        break true;
+                    }
+
+                    if tile_size <= min_tile {
+                        break false;
+                    }
+
+                    tile_size /= 2;
+                };
+
+                if !fits {
+                    self.statistics.lights_unshadowed += 1;
+                    continue;
+                }
+
+                lights.set_shadow_index(candidate.idx, self.views.len() as u32);
+                self.shadowed.insert(instance.entity);
+                self.statistics.lights_shadowed += 1;
+
+                match instance.light {
+                    Light::Point { range, .. } => {
+                        for (tile, dir) in tiles.iter().zip(CUBE_FACES.iter()) {
+                            self.views.push(LocalShadowView {
+                                tile: *tile,
+                                camera: view_camera(
+                                    instance.position,
+                                    *dir,
+                                    std::f32::consts::FRAC_PI_2,
+                                    range,
+                                ),
+                            });
+                        }
+                    }
+                    Light::Spot {
+                        range, half_angle, ..
+                    } => {
+                        let fov = (2.0 * half_angle).clamp(0.01, std::f32::consts::PI - 0.01);
+                        self.views.push(LocalShadowView {
+                            tile: tiles[0],
+                            camera: view_camera(
+                                instance.position,
+                                instance.direction.try_normalize().unwrap_or(Vec3::Z),
+                                fov,
+                                range,
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Write in the views for the lighting pass
+        let atlas_size = self.allocator.size() as f32;
+        let mut used_texels = 0.0;
+        let mut buffer_view = self.buffer.write(usize::from(frame)).unwrap();
+        for (i, view) in self.views.iter().enumerate() {
+            let tile = view.tile;
+            used_texels += (tile.size * tile.size) as f32;
+
+            buffer_view.set_as_array(
+                GpuLocalShadow {
+                    vp: view.camera.vp,
+                    atlas_rect: Vec4::new(
+                        tile.x as f32 / atlas_size,
+                        tile.y as f32 / atlas_size,
+                        tile.size as f32 / atlas_size,
+                        tile.size as f32 / atlas_size,
+                    ),
+                    normal_bias: settings.normal_bias,
+                    filter_radius_uv: settings.filter_size.max(0.0) / atlas_size,
+                },
+                i,
+            );
+        }
+
+        self.statistics.tiles_used = self.views.len();
+        self.statistics.atlas_usage = used_texels / (atlas_size * atlas_size);
+    }
+}
+
+impl ShadowAtlasAllocator {
+    pub fn new(size: u32) -> Self {
+        Self {
+            size: size.max(1).next_power_of_two(),
+            nodes: vec![AtlasNode::Free],
+        }
+    }
+
+    /// Width and height of the atlas in texels.
+    #[inline(always)]
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Frees every tile and changes the size of the atlas. The size is rounded up to a power of
+    /// two.
+    pub fn reset(&mut self, size: u32) {
+        self.size = size.max(1).next_power_of_two();
+        self.nodes.clear();
+        self.nodes.push(AtlasNode::Free);
+    }
+
+    /// Allocates a tile of the given size, rounded up to a power of two. Returns `None` if there
+    /// isn't enough space.
+    pub fn allocate(&mut self, size: u32) -> Option<AtlasTile> {
+        let size = size.max(1).next_power_of_two();
+        if size > self.size {
+            return None;
+        }
+
+        self.allocate_in(0, 0, 0, self.size, size)
+    }
+
+    /// Allocates `count` tiles of the same size into `tiles`. If they don't all fit, nothing is
+    /// allocated and `false` is returned.
+    pub fn allocate_many(&mut self, size: u32, count: usize, tiles: &mut Vec<AtlasTile>) -> bool {
+        tiles.clear();
+        let checkpoint = if count > 1 {
+            Some(self.nodes.clone())
+        } else {
+            None
+        };
+
+        for _ in 0..count {
+            match self.allocate(size) {
+                Some(tile) => tiles.push(tile),
+                None => {
+                    if let Some(nodes) = checkpoint {
+                        self.nodes = nodes;
+                    }
+                    tiles.clear();
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn allocate_in(
+        &mut self,
+        node: usize,
+        x: u32,
+        y: u32,
+        node_size: u32,
+        size: u32,
+    ) -> Option<AtlasTile> {
+        match self.nodes[node] {
+            AtlasNode::Used => None,
+            AtlasNode::Free if node_size == size => {
+                self.nodes[node] = AtlasNode::Used;
+                Some(AtlasTile { x, y, size })
+            }
+            AtlasNode::Free => {
+                let first_child = self.nodes.len();
+                self.nodes.extend([AtlasNode::Free; 4]);
+                self.nodes[node] = AtlasNode::Split { first_child };
+                self.allocate_in(node, x, y, node_size, size)
+            }
+            // A split node has at least one used quadrant, so it can't hold a tile its own size
+            AtlasNode::Split { .. } if node_size == size => None,
+            AtlasNode::Split { first_child } => {
+                let half = node_size / 2;
+                (0..4).find_map(|i| {
+                    self.allocate_in(
+                        first_child + i as usize,
+                        x + ((i % 2) * half),
+                        y + ((i / 2) * half),
+                        half,
+                        size,
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Fraction of the screen height covered by the bounding sphere of a light, squared. Lights
+/// outside of the camera frustum cover nothing.
+fn screen_coverage(camera: &GpuCamera, position: Vec3, range: f32) -> f32 {
+    // Same planes the task shaders cull against
+    let center = Vec4::from((position, 1.0));
+    if camera.frustum.planes[..5]
+        .iter()
+        .any(|plane| plane.dot(center) < -range)
+    {
+        return 0.0;
+    }
+
+    let dist = position.distance(camera.position.xyz());
+    if dist <= range {
+        return 1.0;
+    }
+
+    let radius = camera.projection.y_axis.y * range / ((dist * dist) - (range * range)).sqrt();
+    (radius * radius).min(1.0)
+}
+
+/// Perspective camera looking from a light along `dir`. Depth is not reversed, to match the
+/// shadow passes.
+fn view_camera(position: Vec3, dir: Vec3, fov: f32, range: f32) -> GpuCamera {
+    let up = if dir.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let far = range.max(LOCAL_SHADOW_NEAR + 0.001);
+    let view = Mat4::look_at_lh(position, position + dir, up);
+    let proj = Mat4::perspective_lh(fov, 1.0, LOCAL_SHADOW_NEAR, far);
+    let vp = proj * view;
+
+    GpuCamera {
+        view,
+        projection: proj,
+        vp,
+        last_vp: vp,
+        view_inv: view.inverse(),
+        projection_inv: proj.inverse(),
+        vp_inv: vp.inverse(),
+        frustum: GpuFrustum::from(vp),
+        position: Vec4::from((position, 1.0)),
+        last_position: Vec4::from((position, 1.0)),
+        forward: Vec4::from((dir, 0.0)),
+        aspect_ratio: 1.0,
+        near_clip: LOCAL_SHADOW_NEAR,
+        far_clip: far,
+        cluster_scale_bias: Vec2::ONE,
+        cluster_dims: UVec4::ZERO,
+        jitter: Vec2::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: &AtlasTile, b: &AtlasTile) -> bool {
+        a.x < b.x + b.size && b.x < a.x + a.size && a.y < b.y + b.size && b.y < a.y + a.size
+    }
+
+    #[test]
+    fn mixed_tile_sizes_fill_the_atlas() {
+        let mut atlas = ShadowAtlasAllocator::new(1024);
+        let sizes = [512]
+            .into_iter()
+            .chain([256; 4])
+            .chain([128; 16])
+            .chain([300]);
+
+        let mut tiles = Vec::default();
+        for size in sizes {
+            tiles.push(atlas.allocate(size).unwrap());
+        }
+
+        // Sizes are rounded up to a power of two
+        assert_eq!(tiles.last().unwrap().size, 512);
+
+        let mut area = 0;
+        for (i, tile) in tiles.iter().enumerate() {
+            assert!(tile.x + tile.size <= 1024 && tile.y + tile.size <= 1024);
+            assert!(tiles[..i].iter().all(|other| !overlaps(tile, other)));
+            area += tile.size * tile.size;
+        }
+        assert_eq!(area, 1024 * 1024);
+        assert_eq!(atlas.allocate(1), None);
+    }
+
+    #[test]
+    fn allocate_many_rolls_back_when_only_some_fit() {
+        let mut atlas = ShadowAtlasAllocator::new(1024);
+        for _ in 0..3 {
+            atlas.allocate(512).unwrap();
+        }
+
+        // Only four of the six tiles fit in the remaining quadrant
+        let mut tiles = Vec::default();
+        assert!(!atlas.allocate_many(256, 6, &mut tiles));
+        assert!(tiles.is_empty());
+
+        // The quadrant must be left unsplit
+        assert_eq!(
+            atlas.allocate(512),
+            Some(AtlasTile {
+                x: 512,
+                y: 512,
+                size: 512
+            })
+        );
+    }
+
+    #[test]
+    fn allocate_many_allocates_every_tile() {
+        let mut atlas = ShadowAtlasAllocator::new(1024);
+        let mut tiles = Vec::default();
+        assert!(atlas.allocate_many(256, 6, &mut tiles));
+        assert_eq!(tiles.len(), 6);
+        assert!(tiles.iter().all(|tile| tile.size == 256));
+    }
+
+    #[test]
+    fn reset_changes_the_size() {
+        let mut atlas = ShadowAtlasAllocator::new(1024);
+        atlas.allocate(1024).unwrap();
+        assert_eq!(atlas.allocate(1), None);
+
+        atlas.reset(2048);
+        assert_eq!(atlas.size(), 2048);
+        assert_eq!(
+            atlas.allocate(2048),
+            Some(AtlasTile {
+                x: 0,
+                y: 0,
+                size: 2048
+            })
+        );
+
+        atlas.reset(300);
+        assert_eq!(atlas.size(), 512);
+        assert_eq!(atlas.allocate(1024), None);
+        assert!(atlas.allocate(512).is_some());
+    }
+
+    #[test]
+    fn split_node_cant_hold_a_tile_its_own_size() {
+        let mut atlas = ShadowAtlasAllocator::new(1024);
+        assert_eq!(
+            atlas.allocate(256),
+            Some(AtlasTile {
+                x: 0,
+                y: 0,
+                size: 256
+            })
+        );
+
+        // The first quadrant is split, so larger tiles skip it
+        for (x, y) in [(512, 0), (0, 512), (512, 512)] {
+            assert_eq!(atlas.allocate(512), Some(AtlasTile { x, y, size: 512 }));
+        }
+        assert_eq!(atlas.allocate(512), None);
+
+        // The rest of the split quadrant is still usable
+        assert_eq!(
+            atlas.allocate(256),
+            Some(AtlasTile {
+                x: 256,
+                y: 0,
+                size: 256
+            })
+        );
+    }
+}
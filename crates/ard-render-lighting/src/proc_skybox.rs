@@ -1,6 +1,6 @@
 use std::ops::{DerefMut, Div};
 
-use ard_math::{Mat4, Vec2, Vec3, Vec4};
+use ard_math::{Mat4, UVec4, Vec2, Vec3, Vec4};
 use ard_pal::prelude::*;
 use ard_render_base::Frame;
 use ard_render_camera::ubo::CameraUbo;
@@ -20,6 +20,7 @@ pub const DI_MAP_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -74,6 +75,7 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("diffuse_irradiance_samples".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -89,6 +91,7 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("prefiltering_matrices".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -109,6 +112,7 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sky_box".into()),
+                initial_data: None,
             },
         )
         .unwrap();
@@ -128,6 +132,7 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("prefiltered_env_map".into()),
+                initial_data: None,
             },
         )
         .unwrap();
@@ -145,6 +150,7 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("di_map".into()),
+                initial_data: None,
             },
         )
         .unwrap();
@@ -165,6 +171,9 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("brdf_lut".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -233,6 +242,8 @@ impl ProceduralSkyBox {
                     near_clip: 1.0,
                     far_clip: 1.0,
                     cluster_scale_bias: Vec2::ONE,
+                    cluster_dims: UVec4::ZERO,
+                    jitter: Vec2::ZERO,
                 },
                 i,
             );
@@ -282,6 +293,9 @@ impl ProceduralSkyBox {
                     depth_compare: CompareOp::Equal,
                     min_depth: 0.0,
                     max_depth: 1.0,
+                    stencil_test: false,
+                    stencil_front: StencilOpState::default(),
+                    stencil_back: StencilOpState::default(),
                 }),
                 color_blend: ColorBlendState {
                     attachments: vec![ColorBlendAttachment {
@@ -296,7 +310,9 @@ impl ProceduralSkyBox {
                 push_constants_size: Some(
                     std::mem::size_of::<GpuSkyBoxRenderPushConstants>() as u32
                 ),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("sky_box_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -348,6 +364,9 @@ impl ProceduralSkyBox {
                     depth_compare: CompareOp::Equal,
                     min_depth: 0.0,
                     max_depth: 1.0,
+                    stencil_test: false,
+                    stencil_front: StencilOpState::default(),
+                    stencil_back: StencilOpState::default(),
                 }),
                 color_blend: ColorBlendState {
                     attachments: vec![
@@ -384,7 +403,9 @@ impl ProceduralSkyBox {
                 push_constants_size: Some(
                     std::mem::size_of::<GpuSkyBoxRenderPushConstants>() as u32
                 ),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("color_pass_sky_box_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -394,6 +415,7 @@ impl ProceduralSkyBox {
             DescriptorSetCreateInfo {
                 layout: layouts.di_render.clone(),
                 debug_name: Some("di_render_set".into()),
+                variable_count: None,
             },
         )
         .unwrap();
@@ -447,7 +469,9 @@ impl ProceduralSkyBox {
                     }],
                 },
                 push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("di_render_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -477,6 +501,7 @@ impl ProceduralSkyBox {
             DescriptorSetCreateInfo {
                 layout: layouts.di_gather.clone(),
                 debug_name: Some("di_gather_set".into()),
+                variable_count: None,
             },
         )
         .unwrap();
@@ -521,6 +546,7 @@ impl ProceduralSkyBox {
             DescriptorSetCreateInfo {
                 layout: layouts.di_par_reduce.clone(),
                 debug_name: Some("di_par_reduce_set".into()),
+                variable_count: None,
             },
         )
         .unwrap();
@@ -586,7 +612,9 @@ impl ProceduralSkyBox {
                 push_constants_size: Some(
                     std::mem::size_of::<GpuEnvPrefilterPushConstants>() as u32
                 ),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("environment_map_prefiltering_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -600,6 +628,7 @@ impl ProceduralSkyBox {
                     DescriptorSetCreateInfo {
                         layout: layouts.env_prefilter.clone(),
                         debug_name: Some("environment_map_prefiltering_set".into()),
+                        variable_count: None,
                     },
                 )
                 .unwrap();
@@ -680,7 +709,7 @@ impl ProceduralSkyBox {
                 sun_direction: Vec4::from((sun_direction, 0.0)),
             }];
             pass.push_constants(bytemuck::cast_slice(&constants));
-            ComputePassDispatch::Inline(1, DIFFUSE_IRRADIANCE_SAMPLE_DIM as u32, 1)
+            pass.dispatch(1, DIFFUSE_IRRADIANCE_SAMPLE_DIM as u32, 1);
         });
 
         // Reduce to prefiltering matrices
@@ -694,7 +723,7 @@ impl ProceduralSkyBox {
                     construct_prefiltering_matrices: 0,
                 }];
                 pass.push_constants(bytemuck::cast_slice(&constants));
-                ComputePassDispatch::Inline(DIFFUSE_IRRADIANCE_SAMPLE_DIM as u32, 1, 1)
+                pass.dispatch(DIFFUSE_IRRADIANCE_SAMPLE_DIM as u32, 1, 1);
             },
         );
 
@@ -708,7 +737,7 @@ impl ProceduralSkyBox {
                     construct_prefiltering_matrices: 1,
                 }];
                 pass.push_constants(bytemuck::cast_slice(&constants));
-                ComputePassDispatch::Inline(1, 1, 1)
+                pass.dispatch(1, 1, 1);
             },
         );
 
@@ -728,6 +757,8 @@ impl ProceduralSkyBox {
                 depth_stencil_attachment: None,
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0b111111,
+                correlation_masks: Vec::default(),
             },
             Some("di_render"),
             |pass| {
@@ -765,6 +796,8 @@ impl ProceduralSkyBox {
                 depth_stencil_attachment: None,
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0b111111,
+                correlation_masks: Vec::default(),
             },
             Some("sky_box_render"),
             |pass| {
@@ -859,6 +892,8 @@ impl ProceduralSkyBox {
                     depth_stencil_attachment: None,
                     color_resolve_attachments: Vec::default(),
                     depth_stencil_resolve_attachment: None,
+                    view_mask: 0b111111,
+                    correlation_masks: Vec::default(),
                 },
                 Some("prefiltered_env_map"),
                 |pass| {
@@ -915,6 +950,7 @@ impl ProceduralSkyBox {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("env_prefilter_buffer".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
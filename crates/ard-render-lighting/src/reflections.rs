@@ -70,6 +70,7 @@ const REFLECTION_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -221,6 +222,7 @@ impl Reflections {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("reflection_indirect_dispatch".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -230,6 +232,7 @@ impl Reflections {
             DescriptorSetCreateInfo {
                 layout: layouts.reflection_reset.clone(),
                 debug_name: Some("reflection_reset_set".into()),
+                variable_count: None,
             },
         )
         .unwrap();
@@ -249,6 +252,7 @@ impl Reflections {
                 DescriptorSetCreateInfo {
                     layout: layouts.reflection_tile_classifier.clone(),
                     debug_name: Some("reflection_tile_classifier_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -271,6 +275,7 @@ impl Reflections {
                 DescriptorSetCreateInfo {
                     layout: layouts.reflection_accum.clone(),
                     debug_name: Some("reflection_accum_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap()
@@ -282,6 +287,7 @@ impl Reflections {
                 DescriptorSetCreateInfo {
                     layout: layouts.reflection_apply.clone(),
                     debug_name: Some("reflection_apply_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap()
@@ -293,6 +299,7 @@ impl Reflections {
                 DescriptorSetCreateInfo {
                     layout: layouts.reflections_pass.clone(),
                     debug_name: Some("reflection_pass_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap()
@@ -364,6 +371,9 @@ impl Reflections {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("reflections_target".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -382,6 +392,7 @@ impl Reflections {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("tile_buffer".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -401,6 +412,7 @@ impl Reflections {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("active_tiles_buffer".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -555,6 +567,7 @@ impl Reflections {
                     sampler: REFLECTION_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -569,6 +582,7 @@ impl Reflections {
                     sampler: REFLECTION_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -600,6 +614,7 @@ impl Reflections {
                 sampler: REFLECTION_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
 
@@ -622,6 +637,7 @@ impl Reflections {
                     sampler: REFLECTION_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -633,6 +649,7 @@ impl Reflections {
                     sampler: REFLECTION_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
             DescriptorSetUpdate {
@@ -712,28 +729,28 @@ impl Reflections {
             pass.bind_sets(0, vec![&self.classify_sets[frame_idx]]);
             pass.push_constants(bytemuck::cast_slice(&classify_consts));
             // NOTE: SSR needs a dispatch over texels, unlike the others which dispatch over tiles
-            ComputePassDispatch::Inline(
+            pass.dispatch(
                 self.target.dims().0.div_ceil(TILE_SIZE),
                 self.target.dims().1.div_ceil(TILE_SIZE),
                 1,
-            )
+            );
         });
 
         // Determine which tiles need to be raytraced
         commands.compute_pass(&self.reset_pipeline, Some("ssr_reset"), |pass| {
             pass.bind_sets(0, vec![&self.reset_set]);
-            ComputePassDispatch::Inline(1, 1, 1)
+            pass.dispatch(1, 1, 1);
         });
 
         classify_consts[0].classify_ty = ClassifyType::Rt as u32;
         commands.compute_pass(&self.classify_pipeline, Some("rt_clasify"), |pass| {
             pass.bind_sets(0, vec![&self.classify_sets[frame_idx]]);
             pass.push_constants(bytemuck::cast_slice(&classify_consts));
-            ComputePassDispatch::Inline(
+            pass.dispatch(
                 self.target.dims().0.div_ceil(TILE_SIZE).div_ceil(TILE_SIZE),
                 self.target.dims().1.div_ceil(TILE_SIZE).div_ceil(TILE_SIZE),
                 1,
-            )
+            );
         });
 
         // Trace rays
@@ -764,9 +781,9 @@ impl Reflections {
                         offset: 0,
                     },
                     shader_binding_table: self.rt_pipeline.sbt(),
-                    raygen_offset: self.rt_pipeline.raygen_offset(),
-                    miss_offset: self.rt_pipeline.miss_offset(),
-                    hit_range: self.rt_pipeline.hit_range(),
+                    raygen: self.rt_pipeline.raygen_region(),
+                    miss: self.rt_pipeline.miss_region(),
+                    hit: self.rt_pipeline.hit_region(),
                 }
             },
         );
@@ -779,11 +796,11 @@ impl Reflections {
             |pass| {
                 pass.bind_sets(0, vec![&self.classify_sets[frame_idx]]);
                 pass.push_constants(bytemuck::cast_slice(&classify_consts));
-                ComputePassDispatch::Inline(
+                pass.dispatch(
                     self.target.dims().0.div_ceil(TILE_SIZE).div_ceil(TILE_SIZE),
                     self.target.dims().1.div_ceil(TILE_SIZE).div_ceil(TILE_SIZE),
                     1,
-                )
+                );
             },
         );
 
@@ -791,22 +808,22 @@ impl Reflections {
         commands.compute_pass(&self.accum_pipeline, Some("accum_reflections"), |pass| {
             pass.bind_sets(0, vec![&self.accum_sets[frame_idx]]);
             pass.push_constants(bytemuck::cast_slice(&ssr_consts));
-            ComputePassDispatch::Inline(
+            pass.dispatch(
                 self.target.dims().0.div_ceil(TILE_SIZE),
                 self.target.dims().1.div_ceil(TILE_SIZE),
                 1,
-            )
+            );
         });
 
         // Apply reflection lighting
         commands.compute_pass(&self.apply_pipeline, Some("apply_reflections"), |pass| {
             pass.bind_sets(0, vec![&self.apply_sets[frame_idx]]);
             pass.push_constants(bytemuck::cast_slice(&ssr_consts));
-            ComputePassDispatch::Inline(
+            pass.dispatch(
                 self.canvas_size.0.div_ceil(TILE_SIZE),
                 self.canvas_size.1.div_ceil(TILE_SIZE),
                 1,
-            )
+            );
         });
     }
 }
@@ -1,24 +1,146 @@
 use std::ops::DerefMut;
 
-use ard_math::{Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+use ard_ecs::prelude::*;
+use ard_math::{Mat4, UVec4, Vec2, Vec3, Vec4, Vec4Swizzles};
 use ard_pal::prelude::*;
 use ard_render_camera::Camera;
 use ard_render_si::{consts::*, types::*};
 use ard_transform::Model;
+use serde::{Deserialize, Serialize};
 
 pub struct SunShadowsUbo {
     ubo: Buffer,
     cameras: [GpuCamera; MAX_SHADOW_CASCADES],
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Sun shadow configuration.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowSettings {
+    /// Distance from the camera where sun shadows end.
+    pub max_distance: f32,
+    /// Blend between uniform (`0.0`) and logarithmic (`1.0`) cascade splits. Logarithmic splits
+    /// give more resolution to nearby cascades.
+    pub split_lambda: f32,
+    /// Settings for each cascade, ordered from nearest to farthest. Only the first
+    /// `MAX_SHADOW_CASCADES` are used, and at least one cascade must be provided.
+    pub cascades: Vec<ShadowCascadeSettings>,
+    /// Shadows of point and spot lights.
+    pub local: LocalShadowSettings,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ShadowCascadeSettings {
-    pub min_depth_bias: f32,
-    pub max_depth_bias: f32,
+    /// Width and height of the shadow map in texels.
+    pub resolution: u32,
+    /// Constant depth bias applied while rendering the shadow map, in units of the smallest
+    /// representable depth value.
+    pub depth_bias: f32,
+    /// Depth bias applied while rendering the shadow map, scaled by the depth slope of each
+    /// triangle.
+    pub slope_bias: f32,
+    /// World space distance shadow receivers are offset along their normal.
     pub normal_bias: f32,
+    /// Radius of the PCF kernel in texels.
     pub filter_size: f32,
-    pub resolution: u32,
-    pub end_distance: f32,
+}
+
+/// Point and spot light shadows, which share a single shadow atlas.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalShadowSettings {
+    pub enabled: bool,
+    /// Width and height of the shadow atlas in texels.
+    pub atlas_size: u32,
+    /// Tile size given to a light covering the whole screen. Smaller lights get smaller tiles.
+    /// Rounded up to a power of two.
+    pub max_tile_size: u32,
+    /// Smallest tile size a light can be given. Rounded up to a power of two.
+    pub min_tile_size: u32,
+    /// Constant depth bias applied while rendering the atlas, in units of the smallest
+    /// representable depth value.
+    pub depth_bias: f32,
+    /// Depth bias applied while rendering the atlas, scaled by the depth slope of each triangle.
+    pub slope_bias: f32,
+    /// World space distance shadow receivers are offset along their normal.
+    pub normal_bias: f32,
+    /// Radius of the PCF kernel in texels.
+    pub filter_size: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            max_distance: 300.0,
+            split_lambda: 0.85,
+            cascades: vec![ShadowCascadeSettings::default(); MAX_SHADOW_CASCADES],
+            local: LocalShadowSettings::default(),
+        }
+    }
+}
+
+impl Default for LocalShadowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            atlas_size: 4096,
+            max_tile_size: 1024,
+            min_tile_size: 128,
+            depth_bias: 1.0,
+            slope_bias: 1.5,
+            normal_bias: 0.02,
+            filter_size: 1.5,
+        }
+    }
+}
+
+impl Default for ShadowCascadeSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 4096,
+            depth_bias: 2.0,
+            slope_bias: 2.5,
+            normal_bias: 0.1,
+            filter_size: 3.0,
+        }
+    }
+}
+
+impl ShadowSettings {
+    /// Maximum number of cascades that can be rendered.
+    pub const MAX_CASCADES: usize = MAX_SHADOW_CASCADES;
+
+    /// The cascades that will be rendered.
+    #[inline]
+    pub fn active_cascades(&self) -> &[ShadowCascadeSettings] {
+        &self.cascades[..self.cascades.len().min(MAX_SHADOW_CASCADES)]
+    }
+
+    /// Sets the number of cascades, clamped between one and `MAX_SHADOW_CASCADES`. New cascades
+    /// copy the settings of the farthest cascade.
+    pub fn set_cascade_count(&mut self, count: usize) {
+        let count = count.clamp(1, MAX_SHADOW_CASCADES);
+        let last = self.cascades.last().copied().unwrap_or_default();
+        self.cascades.resize(count, last);
+    }
+
+    /// Distance from the camera where each active cascade ends, using the practical split scheme.
+    pub fn split_distances(&self, near: f32) -> [f32; MAX_SHADOW_CASCADES] {
+        let count = self.active_cascades().len();
+        let far = self.max_distance.max(near + 0.0001);
+        let lambda = self.split_lambda.clamp(0.0, 1.0);
+
+        let mut splits = [far; MAX_SHADOW_CASCADES];
+        for (i, split) in splits.iter_mut().enumerate().take(count) {
+            let p = (i + 1) as f32 / count as f32;
+            let log = near * (far / near).powf(p);
+            let uniform = near + ((far - near) * p);
+            *split = (lambda * log) + ((1.0 - lambda) * uniform);
+        }
+
+        splits
+    }
 }
 
 impl SunShadowsUbo {
@@ -34,6 +156,7 @@ impl SunShadowsUbo {
                     queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                     sharing_mode: SharingMode::Concurrent,
                     debug_name: Some("sun_shadows_ubo".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -55,6 +178,8 @@ impl SunShadowsUbo {
                 near_clip: 1.0,
                 far_clip: 1.0,
                 cluster_scale_bias: Vec2::ONE,
+                cluster_dims: UVec4::ZERO,
+                jitter: Vec2::ZERO,
             }),
         };
 
@@ -80,41 +205,54 @@ impl SunShadowsUbo {
 
     pub fn update(
         &mut self,
-        cascades: &[ShadowCascadeSettings],
+        settings: &ShadowSettings,
+        debug_cascades: bool,
         light_dir: Vec3,
         camera: &Camera,
         camera_model: Model,
         camera_aspect: f32,
     ) {
-        debug_assert!(cascades.len() <= MAX_SHADOW_CASCADES);
+        let cascades = settings.active_cascades();
+        let splits = settings.split_distances(camera.near);
 
         let mut buff_view = self.ubo.write(0).unwrap();
         let ubo = &mut bytemuck::cast_slice_mut::<_, GpuSunShadows>(buff_view.deref_mut())[0];
 
         ubo.count = cascades.len() as u32;
+        ubo.debug_cascades = debug_cascades as u32;
+
+        // Bounding view matrix of the camera
+        let cam_position: Vec3 = camera_model.position().into();
+        let cam_forward = camera_model.forward().try_normalize().unwrap_or(Vec3::Z);
+        let cam_up = camera_model.up();
+        let camera_view = Mat4::look_at_lh(
+            cam_position,
+            cam_position + cam_forward,
+            cam_up.try_normalize().unwrap_or(Vec3::Y),
+        );
+
+        // Light space with a fixed origin, so snapping in it is consistent between frames. The
+        // up vector must not be parallel to the light.
+        let light_up = if light_dir.y.abs() > 0.99 {
+            Vec3::Z
+        } else {
+            Vec3::Y
+        };
+        let light_space = Mat4::look_at_lh(Vec3::ZERO, light_dir, light_up);
+        let light_space_inv = light_space.inverse();
 
         let mut last_cascade_end = camera.near;
         for (i, cascade) in cascades.iter().enumerate() {
             let lin_near = last_cascade_end;
-            let lin_far = cascade.end_distance.max(lin_near + 0.0001);
+            let lin_far = splits[i].max(lin_near + 0.0001);
             last_cascade_end = lin_far;
 
-            // Bounding view matrix of the camera for the cascade
-            let cam_position: Vec3 = camera_model.position().into();
-            let cam_forward = camera_model.forward().try_normalize().unwrap_or(Vec3::Z);
-            let cam_up = camera_model.up();
-            let camera_view = Mat4::look_at_lh(
-                cam_position,
-                cam_position + cam_forward,
-                cam_up.try_normalize().unwrap_or(Vec3::Y),
-            );
-
             // Limited projection matrix for the camera
             let camera_proj = Mat4::perspective_lh(camera.fov, camera_aspect, lin_near, lin_far);
             let camera_vp = camera_proj * camera_view;
             let camera_vp_inv = camera_vp.inverse();
 
-            // Determine the position of the four corners of the frustum
+            // Determine the position of the eight corners of the frustum
             let mut corners = [Vec4::ZERO; 8];
             for x in 0..2 {
                 for y in 0..2 {
@@ -126,32 +264,6 @@ impl SunShadowsUbo {
                 }
             }
 
-            // Find the farthest corners of the frustum
-            // NOTE: O(N^2) for this. Kind of sucks. Maybe we can do better?
-            let mut min = 0;
-            let mut max = 0;
-            let mut dist = 0.0;
-
-            for (i, corner_i) in corners.iter().enumerate() {
-                for (j, corner_j) in corners.iter().enumerate() {
-                    let new_dist = (*corner_i - *corner_j).length();
-                    if new_dist > dist {
-                        min = i;
-                        max = j;
-                        dist = new_dist;
-                    }
-                }
-            }
-
-            let min = corners[min];
-            let max = corners[max];
-
-            // Find the radius of the frustum and the number of texels per world unit
-            let radius = (max - min).length() / 2.0;
-            let texels_per_unit = cascade.resolution as f32 / (radius * 2.0);
-            let scaling =
-                Mat4::from_scale(Vec3::new(texels_per_unit, texels_per_unit, texels_per_unit));
-
             // Compute the center of the frustum by averaging all the points.
             let mut center = Vec3::ZERO;
             for corner in &corners {
@@ -159,22 +271,27 @@ impl SunShadowsUbo {
             }
             center /= 8.0;
 
-            // Clamp the center of the frustum to be a multiple of the texel size
-            let look_at = scaling * Mat4::look_at_lh(Vec3::ZERO, light_dir, Vec3::Y);
-            let look_at_inv = look_at.inverse();
+            // Bounding sphere of the frustum. The distance from the center to the corners doesn't
+            // change as the camera rotates, so rounding it up removes floating point noise and
+            // keeps the cascade covering a constant area.
+            let radius = corners
+                .iter()
+                .map(|corner| (corner.xyz() - center).length())
+                .fold(0.0, f32::max);
+            let radius = (radius * 16.0).ceil() / 16.0;
 
-            let mut new_center = Vec4::new(center.x, center.y, center.z, 1.0);
-            new_center = look_at * new_center;
-            new_center.x = new_center.x.floor();
-            new_center.y = new_center.y.floor();
-            new_center = look_at_inv * new_center;
-            center = new_center.xyz();
+            // Snap the center to a multiple of the texel size so the shadow map moves in whole
+            // texels and doesn't shimmer.
+            let texel_size = (2.0 * radius) / cascade.resolution.max(1) as f32;
+            let mut light_center = light_space * Vec4::from((center, 1.0));
+            light_center.x = (light_center.x / texel_size).floor() * texel_size;
+            light_center.y = (light_center.y / texel_size).floor() * texel_size;
+            center = (light_space_inv * light_center).xyz();
 
             // Compute the view and projection matrices matrix for the light
             let eye = center;
-            let view = Mat4::look_at_lh(eye, eye + light_dir, Vec3::Y);
+            let view = Mat4::look_at_lh(eye, eye + light_dir, light_up);
             let proj = Mat4::orthographic_lh(-radius, radius, -radius, radius, -radius, radius);
-
             // Construct the frustum planes for culling. We set the back plane to 0 so that we
             // never cull objects behind the view.
             let vp = proj * view;
@@ -197,19 +314,19 @@ impl SunShadowsUbo {
                 near_clip: 1.0,
                 far_clip: 1.0,
                 cluster_scale_bias: Vec2::ONE,
+                cluster_dims: UVec4::ZERO,
+                jitter: Vec2::ZERO,
             };
 
             ubo.cascades[i] = GpuShadowCascade {
                 vp,
                 view,
                 proj,
-                // TODO: Make sun size configurable
-                uv_size: cascade.filter_size / (Vec2::ONE * 2.0 * radius),
+                filter_radius_uv: Vec2::splat(
+                    cascade.filter_size.max(0.0) / cascade.resolution.max(1) as f32,
+                ),
                 far_plane: lin_far,
-                min_depth_bias: cascade.min_depth_bias,
-                max_depth_bias: cascade.max_depth_bias,
                 normal_bias: cascade.normal_bias,
-                depth_range: 2.0 * radius,
             };
         }
     }
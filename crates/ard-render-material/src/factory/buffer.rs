@@ -1,5 +1,6 @@
 use ard_pal::prelude::{
-    Buffer, BufferCreateInfo, BufferUsage, Context, MemoryUsage, QueueTypes, SharingMode,
+    Buffer, BufferCreateInfo, BufferUsage, Context, MemoryFallback, MemoryUsage, QueueTypes,
+    SharingMode,
 };
 use ard_render_base::{resource::ResourceAllocator, Frame, FRAMES_IN_FLIGHT};
 
@@ -42,6 +43,7 @@ impl MaterialBuffer {
                     queue_types: QueueTypes::MAIN,
                     sharing_mode: SharingMode::Exclusive,
                     debug_name: Some(debug_name),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 
 use ard_pal::prelude::{
     Context, DescriptorSet, DescriptorSetCreateInfo, DescriptorSetLayout, DescriptorSetUpdate,
-    DescriptorValue,
+    DescriptorValue, DynamicStates,
 };
 use ard_render_base::resource::ResourceAllocator;
 use ard_render_base::Frame;
@@ -54,6 +54,8 @@ pub struct PassDefinition {
     pub has_depth_stencil_attachment: bool,
     /// The number of color attachments this pass has.
     pub color_attachment_count: usize,
+    /// Pipeline state set while recording the pass instead of when the pipeline is created.
+    pub dynamic_states: DynamicStates,
 }
 
 pub struct RtPassDefinition {
@@ -88,6 +90,7 @@ impl MaterialFactory {
                 DescriptorSetCreateInfo {
                     layout: layout.clone(),
                     debug_name: Some("texture_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -25,6 +25,7 @@ impl MaterialSet {
                 DescriptorSetCreateInfo {
                     layout,
                     debug_name: Some(format!("material_set_({data_size})_{frame:?}")),
+                    variable_count: None,
                 },
             )
             .unwrap(),
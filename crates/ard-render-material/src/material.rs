@@ -3,9 +3,9 @@ use std::{collections::BTreeMap, sync::Mutex};
 use ard_formats::vertex::{VertexAttribute, VertexLayout};
 use ard_pal::prelude::{
     ColorBlendState, Context, DepthStencilState, GraphicsPipeline, GraphicsPipelineCreateError,
-    GraphicsPipelineCreateInfo, MeshShadingShader, PipelineLibraryInfo, RasterizationState,
-    RayTracingPipeline, RayTracingPipelineCreateInfo, RayTracingShaderGroup, RayTracingShaderStage,
-    ShaderStage, ShaderStages, VertexInputState,
+    GraphicsPipelineCreateInfo, MeshShadingShader, PipelineCompileMode, PipelineLibraryInfo,
+    RasterizationState, RayTracingPipeline, RayTracingPipelineCreateInfo, RayTracingShaderGroup,
+    RayTracingShaderStage, ShaderStage, ShaderStages, VertexInputState,
 };
 use ard_render_base::{
     resource::{ResourceAllocator, ResourceHandle, ResourceId},
@@ -151,8 +151,9 @@ impl Material {
 
 impl MaterialResource {
     /// Group per combination of vertex layout and rendering mode. We have 3 rendering modes, and
-    /// all meshes have positions and normals, so we can get rid of two there.
-    pub const RT_GROUPS_PER_RENDERING_MODE: usize = (1 << (VertexAttribute::COUNT - 2));
+    /// all meshes have positions and normals, so we can get rid of two there. Skinned meshes are
+    /// shaded like any other mesh, so the joints and weights are ignored as well.
+    pub const RT_GROUPS_PER_RENDERING_MODE: usize = (1 << (VertexAttribute::COUNT - 4));
     pub const RT_GROUPS_PER_MATERIAL: usize = 3 * Self::RT_GROUPS_PER_RENDERING_MODE;
 
     pub fn new(
@@ -300,7 +301,11 @@ impl MaterialResource {
                         push_constants_size: Some(
                             std::mem::size_of::<GpuDrawPushConstants>() as u32
                         ),
+                        // Objects using a variant are skipped until it compiles instead of
+                        // stalling the frame
+                        compile_mode: PipelineCompileMode::Async { fallback: None },
                         debug_name: variant_desc.debug_name,
+                        dynamic_states: pass.dynamic_states,
                     },
                 )?,
                 pass_id: variant_desc.pass_id,
@@ -371,7 +376,7 @@ impl MaterialResource {
 
     #[inline(always)]
     pub const fn to_group_idx(mode: RenderingMode, layout: VertexLayout) -> usize {
-        let offset = (layout.bits() >> 2) as usize;
+        let offset = (layout.difference(VertexLayout::SKIN).bits() >> 2) as usize;
         let base = mode as usize * MaterialResource::RT_GROUPS_PER_RENDERING_MODE;
         base + offset
     }
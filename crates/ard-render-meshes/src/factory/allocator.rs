@@ -37,6 +37,7 @@ impl BufferBlockAllocator {
                 queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: debug_name.clone(),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -89,6 +90,7 @@ impl BufferBlockAllocator {
                 queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: self.debug_name.clone(),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
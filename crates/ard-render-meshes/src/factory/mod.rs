@@ -55,6 +55,8 @@ pub struct MeshUpload {
     pub vertex_staging: Buffer,
     pub vertex_offsets: HashMap<VertexAttribute, u32>,
     pub index_staging: Buffer,
+    /// Indices relative to the first vertex of the mesh. Only for skinned meshes.
+    pub local_index_staging: Option<Buffer>,
     pub vertex_count: usize,
     pub meshlet_staging: Buffer,
     pub meshlet_count: usize,
@@ -68,6 +70,9 @@ pub struct MeshBlock {
     vb: BufferBlock,
     ib: BufferBlock,
     mb: BufferBlock,
+    /// Indices relative to the first vertex of the mesh. Skinned meshes use these to build the
+    /// BLAS' of their instances, whose vertices live elsewhere in the vertex buffers.
+    lib: Option<BufferBlock>,
 }
 
 impl MeshFactory {
@@ -111,6 +116,7 @@ impl MeshFactory {
                 DescriptorSetCreateInfo {
                     layout: layouts.mesh_data.clone(),
                     debug_name: Some("mesh_data_set".into()),
+                    variable_count: None,
                 },
             )
             .unwrap()
@@ -131,6 +137,7 @@ impl MeshFactory {
                     queue_types: QueueTypes::MAIN,
                     sharing_mode: SharingMode::Concurrent,
                     debug_name: Some("mesh_info".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -217,7 +224,22 @@ impl MeshFactory {
             }
         };
 
-        MeshBlock { layout, vb, ib, mb }
+        let lib = if layout.contains(VertexLayout::SKIN) {
+            match self.index_allocator.allocate(index_count) {
+                Some(lib) => Some(lib),
+                None => panic!("ran out of index memory"),
+            }
+        } else {
+            None
+        };
+
+        MeshBlock {
+            layout,
+            vb,
+            ib,
+            mb,
+            lib,
+        }
     }
 
     /// Free an allocated mesh block.
@@ -225,6 +247,24 @@ impl MeshFactory {
         self.index_allocator.free(block.ib);
         self.vertex_allocator.free(block.vb);
         self.meshlet_allocator.free(block.mb);
+        if let Some(lib) = block.lib {
+            self.index_allocator.free(lib);
+        }
+    }
+
+    /// Allocate vertices without any index or meshlet data. Used for the deformed vertices of
+    /// skinned mesh instances.
+    pub fn allocate_vertices(&mut self, vertex_count: usize) -> BufferBlock {
+        match self.vertex_allocator.allocate(vertex_count) {
+            Some(vb) => vb,
+            None => panic!("ran out of vertex memory"),
+        }
+    }
+
+    /// Free vertices allocated with [`allocate_vertices`](Self::allocate_vertices).
+    #[inline(always)]
+    pub fn free_vertices(&mut self, block: BufferBlock) {
+        self.vertex_allocator.free(block);
     }
 
     /// Flushes all mesh info to the GPU for a particular frame in flight.
@@ -331,6 +371,21 @@ impl MeshFactory {
             len: upload.index_staging.size(),
         });
 
+        if let (Some(staging), Some(lib)) = (
+            &upload.local_index_staging,
+            upload.block.local_index_block(),
+        ) {
+            commands.copy_buffer_to_buffer(CopyBufferToBuffer {
+                src: staging,
+                src_array_element: 0,
+                src_offset: 0,
+                dst: self.index_allocator.buffer(),
+                dst_array_element: 0,
+                dst_offset: lib.base() as u64 * MeshData::INDEX_SIZE as u64,
+                len: staging.size(),
+            });
+        }
+
         // Copy meshlet data
         commands.copy_buffer_to_buffer(CopyBufferToBuffer {
             src: &upload.meshlet_staging,
@@ -375,6 +430,11 @@ impl MeshBlock {
         self.mb
     }
 
+    #[inline(always)]
+    pub fn local_index_block(&self) -> Option<BufferBlock> {
+        self.lib
+    }
+
     #[inline(always)]
     pub fn layout(&self) -> VertexLayout {
         self.layout
@@ -1,3 +1,4 @@
 pub mod factory;
 pub mod mesh;
+pub mod skinned;
 pub mod vertices;
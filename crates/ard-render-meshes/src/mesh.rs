@@ -15,7 +15,7 @@ use ard_formats::{
 use ard_math::{UVec4, Vec4Swizzles};
 use ard_pal::prelude::*;
 use ard_render_base::resource::{ResourceHandle, ResourceId};
-use ard_render_si::types::{GpuMeshInfo, GpuMeshlet, GpuObjectBounds};
+use ard_render_si::types::{GpuMeshInfo, GpuMeshLod, GpuMeshlet, GpuObjectBounds};
 use thiserror::Error;
 
 use crate::factory::{MeshBlock, MeshFactory, MeshUpload};
@@ -51,10 +51,13 @@ pub struct MeshResource {
     pub bounds: ObjectBounds,
     pub index_count: usize,
     pub vertex_count: usize,
+    /// Number of meshlets in the most detailed level, which is the most any level draws.
     pub meshlet_count: usize,
     pub blas: BottomLevelAccelerationStructure,
     pub blas_ref: Arc<AtomicU64>,
-    pub blas_scratch: Option<Box<Buffer>>,
+    /// Meshlets of the most detailed level, kept for skinned meshes so their instances can
+    /// build their own BLAS'. Empty for meshes that aren't skinned.
+    pub skin_meshlets: Vec<Meshlet>,
     /// Indicates tht the mesh has been uploaded to the GPU and is ready to be rendered.
     pub mesh_ready: bool,
     pub blas_ready: bool,
@@ -127,6 +130,9 @@ impl MeshResource {
         // Create staging buffers
         let (vertex_staging, vertex_offsets) = data.vertex_staging(ctx);
         let index_staging = data.index_staging(ctx, block.vertex_block().base() as usize);
+        let local_index_staging = block
+            .local_index_block()
+            .map(|_| data.index_staging(ctx, 0));
         let meshlet_staging = Self::meshlet_staging(ctx, data.bounds(), &block, data.meshlets());
 
         let bounds = *data.bounds();
@@ -151,33 +157,21 @@ impl MeshResource {
         )
         .unwrap();
 
-        let scratch = Box::new(
-            Buffer::new(
-                ctx.clone(),
-                BufferCreateInfo {
-                    size: blas.scratch_buffer_size(),
-                    array_elements: 1,
-                    buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_SCRATCH,
-                    memory_usage: MemoryUsage::GpuOnly,
-                    queue_types: QueueTypes::MAIN,
-                    sharing_mode: SharingMode::Exclusive,
-                    debug_name: Some("mesh_blas_scratch".into()),
-                },
-            )
-            .unwrap(),
-        );
-
         Ok((
             MeshResource {
                 version: u32::MAX,
                 block,
                 index_count: data.index_count(),
                 vertex_count: data.vertex_count(),
-                meshlet_count: data.meshlet_count(),
+                meshlet_count: data.lods()[0].meshlet_count as usize,
                 bounds,
                 blas,
                 blas_ref: Arc::new(AtomicU64::new(0)),
-                blas_scratch: Some(scratch),
+                skin_meshlets: if block.local_index_block().is_some() {
+                    data.blas_meshlets().to_vec()
+                } else {
+                    Vec::default()
+                },
                 mesh_ready: false,
                 blas_ready: false,
             },
@@ -185,6 +179,7 @@ impl MeshResource {
                 vertex_staging,
                 vertex_offsets,
                 index_staging,
+                local_index_staging,
                 vertex_count: data.vertex_count(),
                 block,
                 meshlet_staging,
@@ -198,8 +193,21 @@ impl MeshResource {
                 first_index: block.index_block().base(),
                 vertex_offset: block.vertex_block().base() as i32,
                 meshlet_offset: block.meshlet_block().base(),
-                meshlet_count: data.meshlet_count() as u32,
+                meshlet_count: data.lods()[0].meshlet_count,
                 index_count: data.index_count() as u32,
+                lod_count: data.lods().len() as u32,
+                lods: std::array::from_fn(|i| match data.lods().get(i) {
+                    Some(lod) => GpuMeshLod {
+                        meshlet_offset: lod.meshlet_offset,
+                        meshlet_count: lod.meshlet_count,
+                        error: lod.error,
+                    },
+                    None => GpuMeshLod {
+                        meshlet_offset: 0,
+                        meshlet_count: 0,
+                        error: 0.0,
+                    },
+                }),
             },
         ))
     }
@@ -220,6 +228,7 @@ impl MeshResource {
                 queue_types: QueueTypes::TRANSFER,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("vertex_staging".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -247,7 +256,10 @@ impl MeshResource {
                 | (f32_to_unorm8_ceil(max_pt.y) << 16)
                 | (f32_to_unorm8_ceil(max_pt.z) << 24);
 
-            meshlet_slice[i] = GpuMeshlet { data }
+            meshlet_slice[i] = GpuMeshlet {
+                data,
+                cone: meshlet.cone.pack(),
+            }
         });
 
         std::mem::drop(view);
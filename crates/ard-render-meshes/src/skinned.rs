@@ -0,0 +1,134 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use ard_ecs::prelude::Component;
+use ard_formats::{
+    mesh::{meshlet_blas_geometries, MeshData},
+    vertex::{VertexAttribute, VertexLayout},
+};
+use ard_math::Vec4;
+use ard_pal::prelude::*;
+use ard_render_base::resource::{ResourceAllocator, ResourceHandle, ResourceId};
+use thiserror::Error;
+
+use crate::{
+    factory::{allocator::BufferBlock, MeshFactory},
+    mesh::{Mesh, MeshResource},
+};
+
+#[derive(Debug, Error)]
+pub enum SkinnedMeshCreateError {
+    #[error("mesh does not have joints and weights")]
+    NotSkinned,
+    #[error("mesh does not exist")]
+    InvalidMesh,
+}
+
+/// An instance of a skinned mesh. Each instance has its own copy of the vertices of the mesh,
+/// which are deformed by the joints of the entity every frame, and its own BLAS.
+#[derive(Clone, Component)]
+pub struct SkinnedMesh {
+    mesh: Mesh,
+    blas: Arc<AtomicU64>,
+    rebase: i32,
+    handle: ResourceHandle,
+}
+
+pub struct SkinnedMeshResource {
+    /// The mesh being deformed. Held so the mesh outlives its instances.
+    pub mesh: Mesh,
+    /// Deformed vertices of the instance.
+    pub vertex_block: BufferBlock,
+    pub vertex_count: usize,
+    /// Offset from the first vertex of the mesh to the first vertex of the instance.
+    pub rebase: i32,
+    /// Rebuilt every frame from the deformed vertices.
+    pub blas: BottomLevelAccelerationStructure,
+    /// Zero until the BLAS has been built for the first time.
+    pub blas_ref: Arc<AtomicU64>,
+}
+
+impl SkinnedMesh {
+    pub fn new(handle: ResourceHandle, mesh: Mesh, blas: Arc<AtomicU64>, rebase: i32) -> Self {
+        SkinnedMesh {
+            mesh,
+            blas,
+            rebase,
+            handle,
+        }
+    }
+
+    #[inline(always)]
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    #[inline(always)]
+    pub fn id(&self) -> ResourceId {
+        self.handle.id()
+    }
+
+    #[inline(always)]
+    pub fn blas(&self) -> u64 {
+        self.blas.load(Ordering::Relaxed)
+    }
+
+    /// Offset from the first vertex of the mesh to the first vertex of the instance.
+    #[inline(always)]
+    pub fn rebase(&self) -> i32 {
+        self.rebase
+    }
+}
+
+impl SkinnedMeshResource {
+    pub fn new(
+        mesh: &Mesh,
+        meshes: &ResourceAllocator<MeshResource>,
+        ctx: &Context,
+        factory: &mut MeshFactory,
+    ) -> Result<Self, SkinnedMeshCreateError> {
+        if !mesh.layout().contains(VertexLayout::SKIN) {
+            return Err(SkinnedMeshCreateError::NotSkinned);
+        }
+
+        let mesh_rsc = match meshes.get(mesh.id()) {
+            Some(mesh_rsc) => mesh_rsc,
+            None => return Err(SkinnedMeshCreateError::InvalidMesh),
+        };
+
+        // Skinned meshes always have local indices
+        let local_indices = mesh_rsc.block.local_index_block().unwrap();
+        let vertex_block = factory.allocate_vertices(mesh_rsc.vertex_count);
+
+        let blas = BottomLevelAccelerationStructure::new(
+            ctx.clone(),
+            BottomLevelAccelerationStructureCreateInfo {
+                flags: BuildAccelerationStructureFlags::PREFER_FAST_BUILD,
+                data: BottomLevelAccelerationStructureData::Geometry(meshlet_blas_geometries(
+                    &mesh_rsc.skin_meshlets,
+                    factory.vertex_buffer().buffer(VertexAttribute::Position),
+                    0,
+                    vertex_block.base() as u64 * std::mem::size_of::<Vec4>() as u64,
+                    factory.index_buffer(),
+                    0,
+                    local_indices.base() as u64 * MeshData::INDEX_SIZE as u64,
+                )),
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some("skinned_mesh_blas".into()),
+            },
+        )
+        .unwrap();
+
+        Ok(Self {
+            mesh: mesh.clone(),
+            vertex_block,
+            vertex_count: mesh_rsc.vertex_count,
+            rebase: vertex_block.base() as i32 - mesh_rsc.block.vertex_block().base() as i32,
+            blas,
+            blas_ref: Arc::new(AtomicU64::new(0)),
+        })
+    }
+}
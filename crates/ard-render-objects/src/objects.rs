@@ -5,17 +5,20 @@ use ard_core::{
 };
 use ard_ecs::prelude::Entity;
 use ard_log::info;
-use ard_math::{Vec4, Vec4Swizzles};
+use ard_math::{UVec4, Vec4, Vec4Swizzles};
 use ard_pal::prelude::{
-    Buffer, BufferCreateInfo, BufferUsage, BufferWriteView, Context, MemoryUsage, QueueTypes,
-    SharingMode,
+    Buffer, BufferCreateInfo, BufferUsage, BufferWriteView, Context, MemoryFallback, MemoryUsage,
+    QueueTypes, SharingMode,
 };
 use ard_render_base::{
     resource::{ResourceAllocator, ResourceId},
     Frame, RenderingMode,
 };
 use ard_render_material::{material::MaterialResource, material_instance::MaterialInstance};
-use ard_render_meshes::mesh::{Mesh, MeshResource};
+use ard_render_meshes::{
+    mesh::{Mesh, MeshResource},
+    skinned::SkinnedMesh,
+};
 use ard_transform::Model;
 use rustc_hash::FxHashMap;
 
@@ -88,6 +91,7 @@ impl RenderObjects {
                     queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                     sharing_mode: SharingMode::Concurrent,
                     debug_name: Some("object_data".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -168,6 +172,20 @@ impl RenderObjects {
                 Option<&'a Disabled>,
             ),
         >,
+        skinned_objs: impl ExactSizeIterator<
+            Item = (
+                Entity,
+                (
+                    &'a SkinnedMesh,
+                    &'a MaterialInstance,
+                    &'a Model,
+                    &'a PrevFrameModel,
+                    &'a RenderingMode,
+                    &'a RenderFlags,
+                ),
+                Option<&'a Disabled>,
+            ),
+        >,
         static_dirty: &DirtyStaticListener,
     ) {
         // Update dirty flags
@@ -232,6 +250,7 @@ impl RenderObjects {
                     frame,
                     e,
                     (mesh, mat, mdl, &PrevFrameModel(mdl.0), mode, flags),
+                    None,
                     self.static_objects.get_mut(&group).unwrap(),
                 );
             }
@@ -259,6 +278,21 @@ impl RenderObjects {
                 frame,
                 e,
                 (mesh, mat, mdl, prev_mdl, mode, flags),
+                None,
+                &mut self.dynamic_objects,
+            );
+        }
+
+        // Skinned geometry deforms every frame, so it is always dynamic
+        for (e, (skinned, mat, mdl, prev_mdl, mode, flags), disabled) in skinned_objs {
+            if disabled.is_some() {
+                continue;
+            }
+            Self::write_renderable(
+                frame,
+                e,
+                (skinned.mesh(), mat, mdl, prev_mdl, mode, flags),
+                Some(skinned),
                 &mut self.dynamic_objects,
             );
         }
@@ -298,6 +332,7 @@ impl RenderObjects {
             &RenderingMode,
             &RenderFlags,
         ),
+        skinned: Option<&SkinnedMesh>,
         set: &mut ObjectSet,
     ) {
         let (mesh, mat, mdl, prev_mdl, mode, flags) = query;
@@ -309,8 +344,12 @@ impl RenderObjects {
         let new_radius = bounding_sphere.w * mdl.scale().max_element();
         bounding_sphere = Vec4::from((new_center.xyz(), new_radius));
 
-        // Lookup BLAS (might not be ready yet, but 0 values are allowed by the spec).
-        let blas = mesh.blas();
+        // Lookup BLAS (might not be ready yet, but 0 values are allowed by the spec). Skinned
+        // meshes use the BLAS of their instance which is built from the deformed vertices.
+        let blas = match skinned {
+            Some(skinned) => skinned.blas(),
+            None => mesh.blas(),
+        };
 
         // SBT offsets conversion
         let sbt = (usize::from(mat.material().id()) * MaterialResource::RT_GROUPS_PER_MATERIAL)
@@ -339,11 +378,17 @@ impl RenderObjects {
                 .data_ptrs()
                 .map(|ptrs| ptrs[usize::from(frame)])
                 .unwrap_or_default(),
+            // Refer to "ard_render_si types" for the layout of this field.
+            skinning: match skinned {
+                Some(skinned) => UVec4::new(skinned.rebase() as u32, 1, 0, 0),
+                None => UVec4::ZERO,
+            },
         };
 
         // If the mesh being used is missing it's final BLAS, we mark the entity for a later update
-        // when the BLAS is ready.
-        if blas == 0 {
+        // when the BLAS is ready. Skinned objects are rewritten every frame, so they pick up
+        // their BLAS on their own.
+        if blas == 0 && skinned.is_none() {
             set.missing_blas.push(set.data.len());
         }
 
@@ -0,0 +1,29 @@
+use std::{env, path::PathBuf};
+
+fn main() {
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+
+    println!("cargo:rerun-if-changed=./shaders/");
+    println!("cargo:rurun-if-changed={}", ard_render_si::GLSL_INCLUDE_DIR);
+
+    ard_render_codegen::vulkan_spirv::compile_shader(
+        "./shaders/particle_sim.comp",
+        PathBuf::from(&out_dir).join("particle_sim.comp.spv"),
+        &["./shaders/", "../ard-render/shaders/"],
+        &[],
+    );
+
+    ard_render_codegen::vulkan_spirv::compile_shader(
+        "./shaders/particles.vert",
+        PathBuf::from(&out_dir).join("particles.vert.spv"),
+        &["./shaders/"],
+        &[],
+    );
+
+    ard_render_codegen::vulkan_spirv::compile_shader(
+        "./shaders/particles.frag",
+        PathBuf::from(&out_dir).join("particles.frag.spv"),
+        &["./shaders/"],
+        &[],
+    );
+}
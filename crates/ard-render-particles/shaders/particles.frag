@@ -0,0 +1,29 @@
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_EXT_scalar_block_layout : enable
+
+#define ARD_SET_CAMERA 0
+#define ARD_SET_PARTICLE_RENDER 1
+#include "ard_bindings.glsl"
+
+layout(location = 0) in vec2 UV;
+layout(location = 1) in vec4 COLOR;
+layout(location = 2) in float VIEW_DEPTH;
+
+layout(location = 0) out vec4 OUT_COLOR;
+
+void main() {
+    // Round particles with soft edges
+    const float falloff = 1.0 - dot(UV, UV);
+    if (falloff <= 0.0) {
+        discard;
+    }
+
+    // Fade out where the particle intersects geometry instead of showing a hard edge. Depth is
+    // reverse Z with an infinite far plane, so linear depth is `near / depth`.
+    const float raw_depth = texelFetch(scene_depth, ivec2(gl_FragCoord.xy), 0).r;
+    const float scene_view_depth = camera[0].near_clip / max(raw_depth, 0.000001);
+    const float soft = clamp((scene_view_depth - VIEW_DEPTH) / PARTICLE_SOFT_DISTANCE, 0.0, 1.0);
+
+    OUT_COLOR = vec4(COLOR.rgb, COLOR.a * falloff * soft);
+}
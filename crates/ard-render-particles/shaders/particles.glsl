@@ -0,0 +1,60 @@
+#ifndef _ARD_PARTICLES
+#define _ARD_PARTICLES
+
+#define PI 3.14159265359
+
+// Random number generation using pcg32i_random_t, using inc = 1.
+float rng_float(inout uint rng_state) {
+    rng_state = rng_state * 747796405 + 1;
+    uint word = ((rng_state >> ((rng_state >> 28) + 4)) ^ rng_state) * 277803737;
+    word = (word >> 22) ^ word;
+    return float(word) / 4294967295.0;
+}
+
+vec3 random_unit_vector(inout uint rng_state) {
+    const float z = rng_float(rng_state) * 2.0 - 1.0;
+    const float phi = rng_float(rng_state) * 2.0 * PI;
+    const float r = sqrt(max(1.0 - z * z, 0.0));
+    return vec3(r * cos(phi), r * sin(phi), z);
+}
+
+/// Uniformly distributed direction within `half_angle` radians of `dir`.
+vec3 random_in_cone(const vec3 dir, const float half_angle, inout uint rng_state) {
+    const float cos_theta = mix(1.0, cos(half_angle), rng_float(rng_state));
+    const float sin_theta = sqrt(max(1.0 - cos_theta * cos_theta, 0.0));
+    const float phi = rng_float(rng_state) * 2.0 * PI;
+
+    const vec3 up = abs(dir.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    const vec3 tangent = normalize(cross(up, dir));
+    const vec3 bitangent = cross(dir, tangent);
+
+    return normalize(
+        (tangent * cos(phi) + bitangent * sin(phi)) * sin_theta + dir * cos_theta
+    );
+}
+
+/// Smooth vector field the curl noise is derived from.
+vec3 noise_potential(const vec3 p) {
+    return vec3(
+        sin(p.y + cos(p.z * 1.31)) + 0.5 * sin(p.z * 2.17 + 1.7),
+        sin(p.z + cos(p.x * 1.73)) + 0.5 * sin(p.x * 2.31 + 4.1),
+        sin(p.x + cos(p.y * 1.13)) + 0.5 * sin(p.y * 1.97 + 2.9)
+    );
+}
+
+/// Curl of `noise_potential`. Being the curl of a field, it's divergence free, so particles
+/// swirl around without bunching up.
+vec3 curl_noise(const vec3 p) {
+    const float e = 0.01;
+    const vec3 dx = vec3(e, 0.0, 0.0);
+    const vec3 dy = vec3(0.0, e, 0.0);
+    const vec3 dz = vec3(0.0, 0.0, e);
+
+    const vec3 px = noise_potential(p + dx) - noise_potential(p - dx);
+    const vec3 py = noise_potential(p + dy) - noise_potential(p - dy);
+    const vec3 pz = noise_potential(p + dz) - noise_potential(p - dz);
+
+    return vec3(py.z - pz.y, pz.x - px.z, px.y - py.x) / (2.0 * e);
+}
+
+#endif
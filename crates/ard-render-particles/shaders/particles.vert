@@ -0,0 +1,36 @@
+#version 450 core
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_EXT_scalar_block_layout : enable
+
+#define ARD_SET_CAMERA 0
+#define ARD_SET_PARTICLE_RENDER 1
+#include "ard_bindings.glsl"
+
+layout(location = 0) out vec2 OUT_UV;
+layout(location = 1) out vec4 OUT_COLOR;
+layout(location = 2) out float OUT_VIEW_DEPTH;
+
+const vec2 CORNERS[6] = vec2[](
+    vec2(-1.0, -1.0),
+    vec2(1.0, -1.0),
+    vec2(1.0, 1.0),
+    vec2(-1.0, -1.0),
+    vec2(1.0, 1.0),
+    vec2(-1.0, 1.0)
+);
+
+void main() {
+    const ParticleInstance instance = instances[gl_InstanceIndex];
+    const vec2 corner = CORNERS[gl_VertexIndex];
+
+    // Billboards face the camera
+    const vec3 right = camera[0].view_inv[0].xyz;
+    const vec3 up = camera[0].view_inv[1].xyz;
+    const vec3 position = instance.position_size.xyz
+        + (right * corner.x + up * corner.y) * (instance.position_size.w * 0.5);
+
+    gl_Position = camera[0].vp * vec4(position, 1.0);
+    OUT_UV = corner;
+    OUT_COLOR = instance.color;
+    OUT_VIEW_DEPTH = gl_Position.w;
+}
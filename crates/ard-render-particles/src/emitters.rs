@@ -0,0 +1,49 @@
+use ard_core::core::Disabled;
+use ard_ecs::entity::Entity;
+use ard_transform::Model;
+
+use crate::ParticleEmitter;
+
+/// Particle emitters captured from the primary ECS.
+#[derive(Default)]
+pub struct ParticleEmitters {
+    emitters: Vec<CapturedEmitter>,
+}
+
+pub struct CapturedEmitter {
+    pub entity: Entity,
+    pub emitter: ParticleEmitter,
+    pub model: Model,
+}
+
+impl ParticleEmitters {
+    #[inline(always)]
+    pub fn emitters(&self) -> &[CapturedEmitter] {
+        &self.emitters
+    }
+
+    pub fn update<'a>(
+        &mut self,
+        emitters: impl Iterator<
+            Item = (
+                Entity,
+                (&'a ParticleEmitter, &'a Model),
+                Option<&'a Disabled>,
+            ),
+        >,
+    ) {
+        self.emitters.clear();
+
+        for (entity, (emitter, model), disabled) in emitters {
+            if disabled.is_some() {
+                continue;
+            }
+
+            self.emitters.push(CapturedEmitter {
+                entity,
+                emitter: emitter.clone(),
+                model: *model,
+            });
+        }
+    }
+}
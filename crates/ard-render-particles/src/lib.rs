@@ -0,0 +1,93 @@
+use ard_ecs::prelude::*;
+use ard_math::{Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+pub mod emitters;
+pub mod renderer;
+
+/// Emits particles simulated on the GPU from the position of the entity. Particles are emitted
+/// around the up direction of the entity and simulated in world space.
+#[derive(Debug, Component, Serialize, Deserialize, Clone)]
+pub struct ParticleEmitter {
+    /// Particles emitted per second.
+    pub rate: f32,
+    /// Most particles alive at once. When full, the oldest particles are replaced by new ones.
+    pub max_particles: u32,
+    /// Seconds each particle lives for.
+    pub lifetime: f32,
+    /// Initial speed of particles along their emission direction.
+    pub speed: f32,
+    /// Angle in radians around the emission direction particles are emitted within.
+    pub spread: f32,
+    /// Radius of the sphere particles spawn in.
+    pub radius: f32,
+    /// World space acceleration applied to every particle.
+    pub gravity: Vec3,
+    /// How quickly particles slow down.
+    pub drag: f32,
+    /// Acceleration from curl noise. Zero disables the noise.
+    pub noise_strength: f32,
+    /// Spatial frequency of the curl noise.
+    pub noise_frequency: f32,
+    /// How quickly the curl noise changes over time.
+    pub noise_speed: f32,
+    /// Size of particles when they spawn.
+    pub start_size: f32,
+    /// Size of particles when they die.
+    pub end_size: f32,
+    /// Color of particles when they spawn. Alpha is the opacity.
+    pub start_color: Vec4,
+    /// Color of particles when they die.
+    pub end_color: Vec4,
+}
+
+/// Particle usage of the most recently completed frame.
+#[derive(Debug, Default, Clone)]
+pub struct ParticleStatistics {
+    /// Total number of particles shared by every emitter.
+    pub budget: usize,
+    /// Particles reserved by emitters. Emitters reserve room for their `max_particles`.
+    pub reserved: usize,
+    /// Emitters that weren't simulated because they didn't fit in the budget.
+    pub over_budget: usize,
+    pub emitters: Vec<EmitterStatistics>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EmitterStatistics {
+    pub entity: Entity,
+    /// Particles reserved by the emitter.
+    pub capacity: usize,
+    /// Particles alive when the frame was simulated.
+    pub live: usize,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            rate: 32.0,
+            max_particles: 256,
+            lifetime: 4.0,
+            speed: 2.0,
+            spread: 0.4,
+            radius: 0.1,
+            gravity: Vec3::new(0.0, -1.0, 0.0),
+            drag: 0.5,
+            noise_strength: 0.0,
+            noise_frequency: 1.0,
+            noise_speed: 0.5,
+            start_size: 0.2,
+            end_size: 0.05,
+            start_color: Vec4::ONE,
+            end_color: Vec4::new(1.0, 1.0, 1.0, 0.0),
+        }
+    }
+}
+
+impl ParticleStatistics {
+    /// Total number of live particles.
+    #[inline(always)]
+    pub fn live(&self) -> usize {
+        self.emitters.iter().map(|emitter| emitter.live).sum()
+    }
+}
@@ -0,0 +1,631 @@
+use std::time::Duration;
+
+use ard_alloc::buddy::{BuddyAllocator, BuddyBlock};
+use ard_ecs::entity::Entity;
+use ard_math::{Vec3, Vec4};
+use ard_pal::prelude::*;
+use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
+use ard_render_camera::{target::RenderTarget, ubo::CameraUbo};
+use ard_render_si::{
+    bindings::*,
+    consts::*,
+    types::{
+        GpuParticle, GpuParticleDrawArgs, GpuParticleEmitter, GpuParticleInstance,
+        GpuParticleSimPushConstants,
+    },
+};
+use ordered_float::NotNan;
+use rustc_hash::FxHashMap;
+
+use crate::{emitters::ParticleEmitters, EmitterStatistics, ParticleStatistics};
+
+/// Particles are allocated to emitters in multiples of this.
+const POOL_BLOCK_SIZE: usize = PARTICLE_SIM_GROUP_SIZE as usize;
+
+/// Vertices of each particle billboard.
+const BILLBOARD_VERTEX_COUNT: u32 = 6;
+
+const DEPTH_SAMPLER: Sampler = Sampler {
+    min_filter: Filter::Nearest,
+    mag_filter: Filter::Nearest,
+    mipmap_filter: Filter::Nearest,
+    address_u: SamplerAddressMode::ClampToEdge,
+    address_v: SamplerAddressMode::ClampToEdge,
+    address_w: SamplerAddressMode::ClampToEdge,
+    anisotropy: None,
+    compare: None,
+    reduction_mode: None,
+    min_lod: unsafe { NotNan::new_unchecked(0.0) },
+    max_lod: None,
+    unnormalize_coords: false,
+    border_color: None,
+};
+
+/// Simulates particles in async compute and draws them as billboards in the transparent pass.
+///
+/// Every emitter gets a range of a shared particle pool, used as a ring buffer. The simulation
+/// compacts live particles into an instance buffer along with the indirect draw that renders
+/// them, so the CPU never needs to know how many particles are alive.
+pub struct ParticleRenderer {
+    sim_pipeline: ComputePipeline,
+    render_pipeline: GraphicsPipeline,
+    /// Simulation state of every particle. Only used by the compute queue, so it persists
+    /// between frames in flight.
+    _particles: Buffer,
+    /// Live particles written by the simulation, per frame in flight.
+    _instances: Buffer,
+    /// Draw of the live particles, per frame in flight.
+    draw_args: Buffer,
+    /// Emitters simulated each frame in flight.
+    emitters: Buffer,
+    /// Live particles of each emitter, per frame in flight. Read back once the frame completes.
+    live_counts: Buffer,
+    /// Copy of the scene depth after the depth prepass. The depth target is bound while drawing
+    /// particles, so it can't be sampled for soft particles.
+    scene_depth: Texture,
+    sim_sets: [DescriptorSet; FRAMES_IN_FLIGHT],
+    render_sets: [DescriptorSet; FRAMES_IN_FLIGHT],
+    allocator: BuddyAllocator,
+    states: FxHashMap<Entity, EmitterState>,
+    /// Statistics of each frame in flight, completed once the frame is read back.
+    statistics: [ParticleStatistics; FRAMES_IN_FLIGHT],
+    /// Largest capacity of the emitters simulated each frame in flight.
+    max_capacity: [u32; FRAMES_IN_FLIGHT],
+    /// Number of times `prepare` was called. Used to find removed emitters.
+    generation: u64,
+    /// Seconds since the renderer was created.
+    time: f32,
+}
+
+struct EmitterState {
+    block: BuddyBlock,
+    capacity: u32,
+    /// Slot of the ring the next particle is spawned in.
+    cursor: u32,
+    /// Fractional particles left over from previous frames.
+    to_spawn: f32,
+    /// The slots of the emitter must be cleared before they're used.
+    reset: bool,
+    generation: u64,
+}
+
+impl ParticleRenderer {
+    pub fn new(ctx: &Context, layouts: &Layouts, dims: (u32, u32)) -> Self {
+        let module = Shader::new(
+            ctx.clone(),
+            ShaderCreateInfo {
+                code: include_bytes!(concat!(env!("OUT_DIR"), "./particle_sim.comp.spv")),
+                debug_name: Some("particle_sim_shader".into()),
+            },
+        )
+        .unwrap();
+
+        let sim_pipeline =
+            ComputePipeline::new(
+                ctx.clone(),
+                ComputePipelineCreateInfo {
+                    layouts: vec![layouts.particle_sim.clone()],
+                    module,
+                    work_group_size: (PARTICLE_SIM_GROUP_SIZE, 1, 1),
+                    push_constants_size: Some(
+                        std::mem::size_of::<GpuParticleSimPushConstants>() as u32
+                    ),
+                    debug_name: Some("particle_sim_pipeline".into()),
+                },
+            )
+            .unwrap();
+
+        let render_pipeline = Self::create_render_pipeline(ctx, layouts);
+
+        let particles = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: (std::mem::size_of::<GpuParticle>() * MAX_PARTICLES) as u64,
+                array_elements: 1,
+                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some("particles".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        let instances = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: (std::mem::size_of::<GpuParticleInstance>() * MAX_PARTICLES) as u64,
+                array_elements: FRAMES_IN_FLIGHT,
+                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Concurrent,
+                debug_name: Some("particle_instances".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        // Reset from the CPU since the compute queue can't fill buffers
+        let draw_args = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: std::mem::size_of::<GpuParticleDrawArgs>() as u64,
+                array_elements: FRAMES_IN_FLIGHT,
+                buffer_usage: BufferUsage::STORAGE_BUFFER | BufferUsage::INDIRECT_BUFFER,
+                memory_usage: MemoryUsage::CpuToGpu,
+                queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Concurrent,
+                debug_name: Some("particle_draw_args".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        let emitters = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: (std::mem::size_of::<GpuParticleEmitter>() * MAX_PARTICLE_EMITTERS) as u64,
+                array_elements: FRAMES_IN_FLIGHT,
+                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                memory_usage: MemoryUsage::CpuToGpu,
+                queue_types: QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some("particle_emitters".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        let mut live_counts = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: (std::mem::size_of::<u32>() * MAX_PARTICLE_EMITTERS) as u64,
+                array_elements: FRAMES_IN_FLIGHT,
+                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                memory_usage: MemoryUsage::GpuToCpu,
+                queue_types: QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some("particle_live_counts".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        for frame_idx in 0..FRAMES_IN_FLIGHT {
+            live_counts.write(frame_idx).unwrap().fill(0);
+        }
+
+        let sim_sets = std::array::from_fn(|frame_idx| {
+            let mut set = DescriptorSet::new(
+                ctx.clone(),
+                DescriptorSetCreateInfo {
+                    layout: layouts.particle_sim.clone(),
+                    debug_name: Some(format!("particle_sim_set_{frame_idx}")),
+                    variable_count: None,
+                },
+            )
+            .unwrap();
+
+            set.update(&[
+                DescriptorSetUpdate {
+                    binding: PARTICLE_SIM_SET_EMITTERS_BINDING,
+                    array_element: 0,
+                    value: DescriptorValue::StorageBuffer {
+                        buffer: &emitters,
+                        array_element: frame_idx,
+                    },
+                },
+                DescriptorSetUpdate {
+                    binding: PARTICLE_SIM_SET_PARTICLES_BINDING,
+                    array_element: 0,
+                    value: DescriptorValue::StorageBuffer {
+                        buffer: &particles,
+                        array_element: 0,
+                    },
+                },
+                DescriptorSetUpdate {
+                    binding: PARTICLE_SIM_SET_INSTANCES_BINDING,
+                    array_element: 0,
+                    value: DescriptorValue::StorageBuffer {
+                        buffer: &instances,
+                        array_element: frame_idx,
+                    },
+                },
+                DescriptorSetUpdate {
+                    binding: PARTICLE_SIM_SET_DRAW_ARGS_BINDING,
+                    array_element: 0,
+                    value: DescriptorValue::StorageBuffer {
+                        buffer: &draw_args,
+                        array_element: frame_idx,
+                    },
+                },
+                DescriptorSetUpdate {
+                    binding: PARTICLE_SIM_SET_LIVE_COUNTS_BINDING,
+                    array_element: 0,
+                    value: DescriptorValue::StorageBuffer {
+                        buffer: &live_counts,
+                        array_element: frame_idx,
+                    },
+                },
+            ]);
+
+            set
+        });
+
+        let render_sets = std::array::from_fn(|frame_idx| {
+            let mut set = DescriptorSet::new(
+                ctx.clone(),
+                DescriptorSetCreateInfo {
+                    layout: layouts.particle_render.clone(),
+                    debug_name: Some(format!("particle_render_set_{frame_idx}")),
+                    variable_count: None,
+                },
+            )
+            .unwrap();
+
+            set.update(&[DescriptorSetUpdate {
+                binding: PARTICLE_RENDER_SET_INSTANCES_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: &instances,
+                    array_element: frame_idx,
+                },
+            }]);
+
+            set
+        });
+
+        Self {
+            sim_pipeline,
+            render_pipeline,
+            _particles: particles,
+            _instances: instances,
+            draw_args,
+            emitters,
+            live_counts,
+            scene_depth: Self::create_scene_depth(ctx, dims),
+            sim_sets,
+            render_sets,
+            allocator: BuddyAllocator::new(POOL_BLOCK_SIZE, MAX_PARTICLES / POOL_BLOCK_SIZE),
+            states: FxHashMap::default(),
+            statistics: std::array::from_fn(|_| ParticleStatistics::default()),
+            max_capacity: [0; FRAMES_IN_FLIGHT],
+            generation: 0,
+            time: 0.0,
+        }
+    }
+
+    pub fn resize(&mut self, ctx: &Context, dims: (u32, u32)) {
+        self.scene_depth = Self::create_scene_depth(ctx, dims);
+    }
+
+    /// Takes the statistics of the last time `frame` was simulated. Must only be called once the
+    /// GPU is done with `frame`.
+    pub fn take_statistics(&mut self, frame: Frame) -> ParticleStatistics {
+        let frame_idx = usize::from(frame);
+        let mut statistics = std::mem::take(&mut self.statistics[frame_idx]);
+
+        let view = self.live_counts.read(frame_idx).unwrap();
+        let live_counts: &[u32] = bytemuck::cast_slice(&view[..]);
+        for (emitter, live) in statistics.emitters.iter_mut().zip(live_counts) {
+            emitter.live = *live as usize;
+        }
+
+        statistics
+    }
+
+    /// Allocates particles for new emitters, frees the particles of removed emitters, and writes
+    /// out the emitters to simulate this frame.
+    pub fn prepare(&mut self, frame: Frame, emitters: &ParticleEmitters, dt: Duration) {
+        let frame_idx = usize::from(frame);
+        let dt = dt.as_secs_f32();
+        // Wrapped so the noise doesn't lose precision over long sessions
+        self.time = (self.time + dt) % 3600.0;
+        self.generation += 1;
+
+        self.render_sets[frame_idx].update(&[DescriptorSetUpdate {
+            binding: PARTICLE_RENDER_SET_SCENE_DEPTH_BINDING,
+            array_element: 0,
+            value: DescriptorValue::Texture {
+                texture: &self.scene_depth,
+                array_element: 0,
+                sampler: DEPTH_SAMPLER,
+                base_mip: 0,
+                mip_count: 1,
+                aspect: None,
+            },
+        }]);
+
+        let mut view = self.emitters.write(frame_idx).unwrap();
+        let statistics = &mut self.statistics[frame_idx];
+        statistics.budget = MAX_PARTICLES;
+        statistics.over_budget = 0;
+        statistics.emitters.clear();
+        let mut max_capacity = 0;
+
+        for captured in emitters.emitters() {
+            let emitter = &captured.emitter;
+            let capacity = (emitter.max_particles as usize).min(MAX_PARTICLES) as u32;
+
+            if capacity == 0 {
+                continue;
+            }
+
+            if statistics.emitters.len() == MAX_PARTICLE_EMITTERS {
+                statistics.over_budget += 1;
+                continue;
+            }
+
+            // Emitters that changed size are reallocated
+            let resized = self
+                .states
+                .get(&captured.entity)
+                .is_some_and(|state| state.capacity != capacity);
+
+            if resized {
+                let state = self.states.remove(&captured.entity).unwrap();
+                self.allocator.free(state.block);
+            }
+
+            let state = match self.states.get_mut(&captured.entity) {
+                Some(state) => state,
+                None => {
+                    let block = match self.allocator.allocate(capacity as usize) {
+                        Some(block) => block,
+                        None => {
+                            statistics.over_budget += 1;
+                            continue;
+                        }
+                    };
+
+                    self.states.entry(captured.entity).or_insert(EmitterState {
+                        block,
+                        capacity,
+                        cursor: 0,
+                        to_spawn: 0.0,
+                        reset: true,
+                        generation: 0,
+                    })
+                }
+            };
+
+            state.generation = self.generation;
+            state.to_spawn += emitter.rate.max(0.0) * dt;
+            let spawn = state.to_spawn.floor();
+            state.to_spawn -= spawn;
+            let spawn = (spawn as u32).min(capacity);
+
+            view.set_as_array(
+                GpuParticleEmitter {
+                    position_radius: Vec4::from((
+                        Vec3::from(captured.model.position()),
+                        emitter.radius,
+                    )),
+                    direction_spread: Vec4::from((
+                        captured.model.up().normalize_or_zero(),
+                        emitter.spread,
+                    )),
+                    gravity_drag: Vec4::from((emitter.gravity, emitter.drag.max(0.0))),
+                    noise: Vec4::new(
+                        emitter.noise_strength,
+                        emitter.noise_frequency,
+                        emitter.noise_speed,
+                        0.0,
+                    ),
+                    start_color: emitter.start_color,
+                    end_color: emitter.end_color,
+                    size_speed_lifetime: Vec4::new(
+                        emitter.start_size,
+                        emitter.end_size,
+                        emitter.speed,
+                        emitter.lifetime,
+                    ),
+                    spawn: [spawn, state.cursor, capacity, state.block.base()].into(),
+                    info: [
+                        state.reset as u32,
+                        (self.generation as u32).wrapping_mul(0x9E3779B9),
+                        0,
+                        0,
+                    ]
+                    .into(),
+                },
+                statistics.emitters.len(),
+            );
+
+            state.cursor = (state.cursor + spawn) % capacity;
+            state.reset = false;
+            max_capacity = max_capacity.max(capacity);
+
+            statistics.emitters.push(EmitterStatistics {
+                entity: captured.entity,
+                capacity: capacity as usize,
+                live: 0,
+            });
+        }
+
+        // Free the particles of removed emitters
+        let generation = self.generation;
+        let allocator = &mut self.allocator;
+        self.states.retain(|_, state| {
+            if state.generation != generation {
+                allocator.free(state.block);
+                return false;
+            }
+            true
+        });
+
+        statistics.reserved = self
+            .states
+            .values()
+            .map(|state| state.capacity as usize)
+            .sum();
+        self.max_capacity[frame_idx] = max_capacity;
+
+        self.live_counts.write(frame_idx).unwrap().fill(0);
+        self.draw_args.write(frame_idx).unwrap().set_as_array(
+            GpuParticleDrawArgs {
+                vertex_count: BILLBOARD_VERTEX_COUNT,
+                instance_count: 0,
+                first_vertex: 0,
+                first_instance: 0,
+            },
+            0,
+        );
+    }
+
+    /// Emits and simulates particles. Meant to be recorded on the compute queue.
+    pub fn simulate<'a>(&'a self, commands: &mut CommandBuffer<'a>, frame: Frame, dt: Duration) {
+        let frame_idx = usize::from(frame);
+        let emitter_count = self.statistics[frame_idx].emitters.len();
+        if emitter_count == 0 {
+            return;
+        }
+
+        let group_count = self.max_capacity[frame_idx].div_ceil(PARTICLE_SIM_GROUP_SIZE);
+
+        commands.compute_pass(&self.sim_pipeline, Some("particle_sim"), |pass| {
+            pass.bind_sets(0, vec![&self.sim_sets[frame_idx]]);
+
+            let constants = [GpuParticleSimPushConstants {
+                dt: dt.as_secs_f32(),
+                time: self.time,
+                emitter_count: emitter_count as u32,
+            }];
+            pass.push_constants(bytemuck::cast_slice(&constants));
+            pass.dispatch(group_count, emitter_count as u32, 1);
+        });
+    }
+
+    /// Copies the scene depth for soft particles. Must be called after the depth prepass.
+    pub fn copy_scene_depth<'a>(&'a self, commands: &mut CommandBuffer<'a>, depth: &'a Texture) {
+        commands.copy_texture_to_texture(CopyTextureToTexture {
+            src: depth,
+            src_offset: (0, 0, 0),
+            src_mip_level: 0,
+            src_array_element: 0,
+            dst: &self.scene_depth,
+            dst_offset: (0, 0, 0),
+            dst_mip_level: 0,
+            dst_array_element: 0,
+            extent: depth.dims(),
+        });
+    }
+
+    /// Draws the simulated particles. Must be called within a pass using the scene depth buffer.
+    pub fn render<'a>(&'a self, frame: Frame, pass: &mut RenderPass<'a>, camera: &'a CameraUbo) {
+        let frame_idx = usize::from(frame);
+        if self.statistics[frame_idx].emitters.is_empty() {
+            return;
+        }
+
+        pass.bind_pipeline(self.render_pipeline.clone());
+        pass.bind_sets(0, vec![camera.get_set(frame), &self.render_sets[frame_idx]]);
+        pass.draw_indirect(
+            &self.draw_args,
+            frame_idx,
+            0,
+            1,
+            std::mem::size_of::<GpuParticleDrawArgs>() as u64,
+        );
+    }
+
+    fn create_render_pipeline(ctx: &Context, layouts: &Layouts) -> GraphicsPipeline {
+        let vertex = Shader::new(
+            ctx.clone(),
+            ShaderCreateInfo {
+                code: include_bytes!(concat!(env!("OUT_DIR"), "./particles.vert.spv")),
+                debug_name: Some("particles_vertex_shader".into()),
+            },
+        )
+        .unwrap();
+
+        let fragment = Shader::new(
+            ctx.clone(),
+            ShaderCreateInfo {
+                code: include_bytes!(concat!(env!("OUT_DIR"), "./particles.frag.spv")),
+                debug_name: Some("particles_fragment_shader".into()),
+            },
+        )
+        .unwrap();
+
+        GraphicsPipeline::new(
+            ctx.clone(),
+            GraphicsPipelineCreateInfo {
+                stages: ShaderStages::Traditional {
+                    vertex,
+                    fragment: Some(fragment),
+                },
+                layouts: vec![layouts.camera.clone(), layouts.particle_render.clone()],
+                vertex_input: VertexInputState {
+                    attributes: Vec::default(),
+                    bindings: Vec::default(),
+                    topology: PrimitiveTopology::TriangleList,
+                },
+                rasterization: RasterizationState {
+                    polygon_mode: PolygonMode::Fill,
+                    cull_mode: CullMode::None,
+                    front_face: FrontFace::CounterClockwise,
+                },
+                // Reverse Z. Particles don't write depth so they don't hide each other.
+                depth_stencil: Some(DepthStencilState {
+                    depth_clamp: false,
+                    depth_test: true,
+                    depth_write: false,
+                    depth_compare: CompareOp::GreaterOrEqual,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                    stencil_test: false,
+                    stencil_front: StencilOpState::default(),
+                    stencil_back: StencilOpState::default(),
+                }),
+                color_blend: ColorBlendState {
+                    attachments: vec![ColorBlendAttachment {
+                        blend: true,
+                        write_mask: ColorComponents::R
+                            | ColorComponents::G
+                            | ColorComponents::B
+                            | ColorComponents::A,
+                        color_blend_op: BlendOp::Add,
+                        src_color_blend_factor: BlendFactor::SrcAlpha,
+                        dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                        alpha_blend_op: BlendOp::Add,
+                        src_alpha_blend_factor: BlendFactor::One,
+                        dst_alpha_blend_factor: BlendFactor::Zero,
+                    }],
+                },
+                push_constants_size: None,
+                compile_mode: PipelineCompileMode::Blocking,
+                debug_name: Some("particles_pipeline".into()),
+                dynamic_states: DynamicStates::empty(),
+            },
+        )
+        .unwrap()
+    }
+
+    fn create_scene_depth(ctx: &Context, dims: (u32, u32)) -> Texture {
+        Texture::new(
+            ctx.clone(),
+            TextureCreateInfo {
+                format: RenderTarget::DEPTH_FORMAT,
+                ty: TextureType::Type2D,
+                width: dims.0,
+                height: dims.1,
+                depth: 1,
+                array_elements: 1,
+                mip_levels: 1,
+                sample_count: MultiSamples::Count1,
+                texture_usage: TextureUsage::SAMPLED | TextureUsage::TRANSFER_DST,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some("particle_scene_depth".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap()
+    }
+}
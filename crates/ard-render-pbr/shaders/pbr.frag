@@ -30,9 +30,13 @@
 void main() {
     const PbrMaterial data = object_data[vs_in.slots.w].material.mat;
 
+#if ARD_VS_HAS_UV0
+    const vec2 uv = mat2(data.uv_transform) * vs_in.uv + data.uv_offset.xy;
+#endif
+
 // Get color from diffuse texture
 #if ARD_VS_HAS_UV0
-    const vec4 color = sample_texture_default(vs_in.slots.x, vs_in.uv, vec4(1)) * data.color;
+    const vec4 color = sample_texture_default(vs_in.slots.x, uv, vec4(1)) * data.color;
     
     // Alpha-Cutoff
     #if defined(ALPHA_CUTOFF_PASS)
@@ -56,10 +60,10 @@ void main() {
 
     // Prefetch textures
     #if ARD_VS_HAS_UV0
-        const vec4 mr_map = sample_texture_default(vs_in.slots.y, vs_in.uv, vec4(1.0));
+        const vec4 mr_map = sample_texture_default(vs_in.slots.y, uv, vec4(1.0));
     #endif
     #if ARD_VS_HAS_TANGENT && ARD_VS_HAS_UV0
-        vec3 N = sample_texture_default(vs_in.slots.z, vs_in.uv, vec4(0.5, 0.5, 1.0, 0.0)).xyz;
+        vec3 N = sample_texture_default(vs_in.slots.z, uv, vec4(0.5, 0.5, 1.0, 0.0)).xyz;
     #endif
 
     // Apply material properties from texture
@@ -106,7 +110,8 @@ void main() {
     last_pos.y = -last_pos.y;
     last_pos = (last_pos + vec2(1.0)) * vec2(0.5);
 
-    vec2 cur_pos = ndc_position;
+    // Last position is never jittered, so the jitter is removed to get the true velocity
+    vec2 cur_pos = ndc_position - camera[gl_ViewIndex].jitter;
     cur_pos.y = -cur_pos.y;
     cur_pos = (cur_pos + vec2(1.0)) * vec2(0.5);
 
@@ -118,6 +123,23 @@ void main() {
 #if !defined(TRANSPARENT_PREPASS)
     const vec2 screen_uv = ndc_position * vec2(0.5) + vec2(0.5);
 
+    // Emissive and occlusion maps aren't needed by any other pass, so they are looked up here
+    // instead of being passed down from the task shader
+    #if ARD_VS_HAS_UV0
+        const uint textures_slot = uint(object_data[vs_in.slots.w].textures);
+        const vec3 emissive = data.emissive.rgb * sample_texture_default(
+            uint(texture_slots[textures_slot][3]), uv, vec4(1.0)
+        ).rgb;
+        const float occlusion = mix(
+            1.0,
+            sample_texture_default(uint(texture_slots[textures_slot][4]), uv, vec4(1.0)).r,
+            data.occlusion_strength
+        );
+    #else
+        const vec3 emissive = data.emissive.rgb;
+        const float occlusion = 1.0;
+    #endif
+
     // Default color is ambient
     const vec3 diffuse = color.rgb
         * texture(di_map, N).rgb 
@@ -126,7 +148,7 @@ void main() {
     const vec3 ambient = global_lighting.ambient_color_intensity.a * kD * diffuse;
 
     vec4 final_color = vec4(
-        texture(ao_image, vec2(screen_uv.x, 1.0 - screen_uv.y)).r * vec3(ambient),
+        occlusion * texture(ao_image, vec2(screen_uv.x, 1.0 - screen_uv.y)).r * vec3(ambient),
         color.a
     );
 
@@ -161,7 +183,9 @@ void main() {
         if (dist_to_light < light.position_range.w) {
             final_color += vec4(light_fragment(
                 light.color_intensity.rgb,
-                light_attenuation(dist_to_light, light.position_range.w) * light.color_intensity.w,
+                light_attenuation(dist_to_light, light.position_range.w)
+                    * light.color_intensity.w
+                    * compute_local_shadow_factor(light, N),
                 color.rgb,
                 roughness,
                 metallic,
@@ -194,6 +218,19 @@ void main() {
     
     // Ambient occlusion term used here
 
+    // Emission isn't affected by lighting
+    final_color.rgb += emissive;
+
+    // Tint by the shadow cascade when debugging
+    if (sun_shadow_info.debug_cascades != 0) {
+        final_color.rgb *= shadow_cascade_debug_color(get_shadow_cascade());
+    }
+
+    // Replace the color with the number of lights in the cluster when debugging
+    if (global_lighting.light_cluster_heatmap != 0) {
+        final_color.rgb = light_cluster_heatmap_color(uint(light_index));
+    }
+
     OUT_COLOR = final_color;
 #endif
 #endif
@@ -6,7 +6,7 @@
 #extension GL_EXT_mesh_shader: require
 #extension GL_EXT_control_flow_attributes : enable
 
-#define ARD_TEXTURE_COUNT 3
+#define ARD_TEXTURE_COUNT 5
 #define MESH_SHADER
 #define ArdMaterialData PbrMaterial
 #include "pbr_common.glsl"
@@ -137,10 +137,12 @@ void main() {
         */
 
         // Read attributes
-        const vec4 ard_position = v_positions[vertex_offset + vert_idx];
-        const uvec2 ard_normal_raw = v_normals[vertex_offset + vert_idx];
+        // Skinned objects have their own copy of the deformed attributes
+        const uint skinned_idx = uint(int(vertex_offset + vert_idx) + payload.vertex_rebase);
+        const vec4 ard_position = v_positions[skinned_idx];
+        const uvec2 ard_normal_raw = v_normals[skinned_idx];
 #if ARD_VS_HAS_TANGENT && ARD_VS_HAS_UV0    
-        const uvec2 ard_tangent_raw = v_tangents[vertex_offset + vert_idx];
+        const uvec2 ard_tangent_raw = v_tangents[skinned_idx];
 #endif
         
 #if ARD_VS_HAS_UV0
@@ -7,7 +7,7 @@
 #extension GL_EXT_mesh_shader: require
 #extension GL_EXT_control_flow_attributes : enable
 
-#define ARD_TEXTURE_COUNT 3
+#define ARD_TEXTURE_COUNT 5
 #define TASK_SHADER
 #define ArdMaterialData PbrMaterial
 #include "pbr_common.glsl"
@@ -64,7 +64,7 @@ BoundingBox transform_bounding_box(mat4 view_model, vec3 min_pt, vec3 max_pt) {
     return bb;
 }
 
-bool is_visible(vec3 center, float radius, vec3 min_pt, vec3 max_pt, mat4 view_model) {
+bool in_frustum(vec3 center, float radius) {
     // NOTE: We are only checking the first five planes because we're using an infinite perspective
     // matrix, meaning all objects are always within the final plane. If that should ever change,
     // make sure to add back in the check for the final plane.
@@ -76,7 +76,10 @@ bool is_visible(vec3 center, float radius, vec3 min_pt, vec3 max_pt, mat4 view_m
         }
     }
 
-    // Occlusion culling
+    return true;
+}
+
+bool is_occluded(vec3 min_pt, vec3 max_pt, mat4 view_model) {
 #if defined(DEPTH_PREPASS) || defined(TRANSPARENT_PASS) || defined(ENTITY_PASS)
     BoundingBox bb = transform_bounding_box(view_model, min_pt, max_pt);
 
@@ -98,13 +101,54 @@ bool is_visible(vec3 center, float radius, vec3 min_pt, vec3 max_pt, mat4 view_m
     float depth = textureLod(hzb_image, (bb.max_pt + bb.min_pt) * 0.5, level).x;
     depth = camera[0].near_clip / depth;
 
-    // Check for visibility
-    return bb.depth <= depth;
+    // Occluded if the nearest point is behind the HZB
+    return bb.depth > depth;
 #else
-    return true;
+    return false;
 #endif
 }
 
+bool is_visible(vec3 center, float radius, vec3 min_pt, vec3 max_pt, mat4 view_model) {
+    return in_frustum(center, radius) && !is_occluded(min_pt, max_pt, view_model);
+}
+
+/// Checks if the camera can see the front of any triangle in a meshlet. Everything is in object
+/// space.
+bool is_cone_visible(uint cone_packed, vec3 center, float radius, vec3 camera_pos) {
+    const vec4 cone = unpackSnorm4x8(cone_packed);
+
+    // Disabled cones have no axis
+    if (cone.w >= 1.0) {
+        return true;
+    }
+
+    const vec3 to_center = center - camera_pos;
+    return dot(to_center, normalize(cone.xyz)) < cone.w * length(to_center) + radius;
+}
+
+/// Picks the least detailed level of a mesh whose error covers at most `MESH_LOD_PIXEL_ERROR`
+/// pixels at the nearest point of the bounding sphere. The center is in world space.
+uint select_lod(const uint mesh_id, vec3 center, float radius, float max_scale_axis) {
+    const float dist = max(
+        length(center - camera[0].position.xyz) - radius,
+        camera[0].near_clip
+    );
+
+    // Pixels covered by a unit of length at that distance
+    const float pixels_per_unit = camera[0].projection[1][1] * consts.render_area.y * 0.5 / dist;
+
+    uint lod = 0;
+    for (uint i = 1; i < mesh_info[mesh_id].lod_count; i++) {
+        const float error = mesh_info[mesh_id].lods[i].error * max_scale_axis * pixels_per_unit;
+        if (error > MESH_LOD_PIXEL_ERROR) {
+            break;
+        }
+        lod = i;
+    }
+
+    return lod;
+}
+
 void manual_payload(const ObjectId id) {
     const uint textures_slot = object_data[id.data_idx].textures;
     payload.meshlet_base = 1 + id.meshlet_base;
@@ -115,6 +159,7 @@ void manual_payload(const ObjectId id) {
 #endif
     payload.normal = mat3(object_data[id.data_idx].model_inv);
     payload.object_id = id.data_idx;
+    payload.vertex_rebase = int(object_data[id.data_idx].skinning.x);
 #if defined(ENTITY_PASS)
     payload.entity = object_data[id.data_idx].entity;
 #endif
@@ -138,10 +183,16 @@ void manual_payload(const ObjectId id) {
     shared mat4 s_view_model;
     shared ObjectBounds s_obj_bounds;
     shared uint s_data_idx;
+    shared int s_vertex_rebase;
+    shared bool s_skinned;
     shared uint s_meshlet_offset;
     shared uint s_meshlet_count;
+    shared uint s_lod_meshlet_base;
     shared uint s_output_base;
     shared float s_max_scale_axis;
+#if defined(DEPTH_PREPASS) || defined(TRANSPARENT_PASS) || defined(ENTITY_PASS)
+    shared vec3 s_camera_pos;
+#endif
 #if defined(ENTITY_PASS)
     shared uint s_entity;
 #endif
@@ -188,7 +239,6 @@ void main() {
         const uint textures_slot = object_data[id.data_idx].textures;
         const uint mesh_id = object_data[id.data_idx].mesh;
         const uint meshlet_offset = mesh_info[mesh_id].meshlet_offset;
-        const uint meshlet_count = mesh_info[mesh_id].meshlet_count;
         const mat4 view_model = camera[0].view * mat4(
             vec4(model_mat[0], 0.0),
             vec4(model_mat[1], 0.0),
@@ -198,6 +248,8 @@ void main() {
 #if defined(ENTITY_PASS)
         const uint entity = object_data[id.data_idx].entity;
 #endif
+        // Skinned vertices can move outside of the bounds of the mesh, so they aren't culled
+        const bool skinned = object_data[id.data_idx].skinning.y != 0;
 
         // Compute bounds
         const ObjectBounds obj_bounds = mesh_info[mesh_id].bounds;
@@ -216,13 +268,32 @@ void main() {
         obj_center = (model_mat * vec4(obj_center, 1.0)).xyz;
 
         // Do culling
-        s_visible = is_visible(
-            obj_center, 
-            obj_radius, 
+        const bool frustum_visible = skinned || in_frustum(obj_center, obj_radius);
+        s_visible = frustum_visible && (skinned || !is_occluded(
             obj_bounds.min_pt.xyz, 
             obj_bounds.max_pt.xyz, 
             view_model
+        ));
+
+        // Record why the object was culled
+        atomicAdd(culling_stats.instances_tested, 1);
+        if (!frustum_visible) {
+            atomicAdd(culling_stats.frustum_culled, 1);
+        } else if (!s_visible) {
+            atomicAdd(culling_stats.occlusion_culled, 1);
+        }
+
+        // Shadows use an orthographic projection, so they always use the most detailed level
+#if defined(DEPTH_PREPASS) || defined(TRANSPARENT_PASS) || defined(ENTITY_PASS)
+        const uint lod = skinned ? 0 : select_lod(
+            mesh_id,
+            obj_center,
+            max_scale_axis * length(obj_bounds.max_pt.xyz - obj_bounds.min_pt.xyz) * 0.5,
+            max_scale_axis
         );
+#else
+        const uint lod = 0;
+#endif
 
         // If we aren't visible, write out that we have 0 meshlets
         if (!s_visible) {
@@ -239,9 +310,17 @@ void main() {
         s_output_base = id.meshlet_base;
         s_obj_bounds = obj_bounds;
         s_data_idx = id.data_idx;
+        s_vertex_rebase = int(object_data[id.data_idx].skinning.x);
+        s_skinned = skinned;
         s_meshlet_offset = meshlet_offset;
-        s_meshlet_count = meshlet_count;
+        s_meshlet_count = mesh_info[mesh_id].lods[lod].meshlet_count;
+        s_lod_meshlet_base = mesh_info[mesh_id].lods[lod].meshlet_offset;
         s_max_scale_axis = max_scale_axis;
+#if defined(DEPTH_PREPASS) || defined(TRANSPARENT_PASS) || defined(ENTITY_PASS)
+        // Cone culling is done in object space so non-uniform scaling doesn't skew the cones
+        const mat4x3 model_inv = transpose(object_data[id.data_idx].model_inv);
+        s_camera_pos = model_inv * vec4(camera[0].position.xyz, 1.0);
+#endif
 #if defined(ENTITY_PASS)
         s_entity = entity;
 #endif
@@ -267,6 +346,7 @@ void main() {
     const ObjectBounds obj_bounds = s_obj_bounds;
     const uint meshlet_offset = s_meshlet_offset;
     const uint meshlet_count = s_meshlet_count;
+    const uint lod_meshlet_base = s_lod_meshlet_base;
     const uint output_base = s_output_base;
     const float max_scale_axis = s_max_scale_axis;
     const vec3 bounds_range = obj_bounds.max_pt.xyz - obj_bounds.min_pt.xyz;
@@ -277,13 +357,17 @@ void main() {
     const uint iters = (meshlet_count + (TS_INVOCATIONS - 1)) / TS_INVOCATIONS;
     for (uint i = 0; i < iters; i++) {
         // Determine meshlet index. Skip if OOB.
-        const uint meshlet_idx = (i * TS_INVOCATIONS) + gl_LocalInvocationIndex;
-        if (meshlet_idx >= meshlet_count) {
+        const uint lod_meshlet_idx = (i * TS_INVOCATIONS) + gl_LocalInvocationIndex;
+        if (lod_meshlet_idx >= meshlet_count) {
             continue;
         }
 
+        // Meshlets are referenced relative to the first level
+        const uint meshlet_idx = lod_meshlet_base + lod_meshlet_idx;
+
         // Read in the meshlet this invocation is looking at.
         const uvec2 bounds_packed = v_meshlets[meshlet_offset + meshlet_idx].data.zw;
+        const uint cone_packed = v_meshlets[meshlet_offset + meshlet_idx].cone;
 
         // Unpack meshlet bounds.
         const vec4 xunpacked = unpackUnorm4x8(bounds_packed.x);
@@ -297,16 +381,29 @@ void main() {
         vec3 meshlet_center = (meshlet_max_pt + meshlet_min_pt) * 0.5;
         const float meshlet_radius = 
             (-max_scale_axis * length(meshlet_max_pt - meshlet_center)) - 0.05;
+
+        // Cone culling uses the bounds in object space
+#if defined(DEPTH_PREPASS) || defined(TRANSPARENT_PASS) || defined(ENTITY_PASS)
+        const bool facing = is_cone_visible(
+            cone_packed,
+            meshlet_center,
+            length(meshlet_max_pt - meshlet_center),
+            s_camera_pos
+        );
+#else
+        const bool facing = true;
+#endif
+
         meshlet_center = model_mat * vec4(meshlet_center, 1.0);
 
         // Perform culling
-        const bool visible = is_visible(
+        const bool visible = s_skinned || (facing && is_visible(
             meshlet_center, 
             meshlet_radius, 
             meshlet_min_pt, 
             meshlet_max_pt, 
             view_model
-        );
+        ));
         
         // Vote on visibility
         uvec4 valid_votes = subgroupBallot(visible);
@@ -325,6 +422,7 @@ void main() {
     // Write to payload and emit tasks
     if (gl_LocalInvocationIndex == 0) {
         output_ids[output_base] = uint16_t(output_meshlet_count);
+        atomicAdd(culling_stats.meshlets_drawn, output_meshlet_count);
 
         payload.meshlet_base = 1 + output_base;
         payload.meshlet_info_base = meshlet_offset;  
@@ -334,6 +432,7 @@ void main() {
 #endif
         payload.normal = s_normal_mat;
         payload.object_id = s_data_idx;
+        payload.vertex_rebase = s_vertex_rebase;
 #if defined(ENTITY_PASS)
         payload.entity = s_entity;
 #endif
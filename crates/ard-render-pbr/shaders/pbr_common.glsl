@@ -65,6 +65,8 @@ struct MsPayload {
     uint object_id;
     uint meshlet_base;
     uint meshlet_info_base;
+    // Offset from the vertices of the mesh to the skinned vertices of the object.
+    int vertex_rebase;
 #if defined(ENTITY_PASS)
     uint entity;
 #endif
@@ -121,23 +123,16 @@ float light_attenuation(float x, float range) {
 ///
 /// `cascade` - Index of the shadow cascade to sample.
 /// `uv` - UV coordinate within the cascade to sample.
-/// `bias` - Sampling bias.
 /// `filter_radius_uv` - UV radius to perform PCF within.
 /// `z_receiver` - Z coordinate in light space for the shadow receiver.
-float sample_shadow_map(int layer, vec2 uv, float bias, vec2 filter_radius_uv, float z_receiver) {
+float sample_shadow_map(int layer, vec2 uv, vec2 filter_radius_uv, float z_receiver) {
     float shadow = 0.0;
-    const vec3 jcoord = vec3(
-        (vs_in.world_space_position.xz + vec2(vs_in.world_space_position.yy)) * 100.0, 0.0
-    );
-    const vec2 sm_coord = uv;
-    const vec4 fr_uv2 = vec4(filter_radius_uv, filter_radius_uv);
-    const float shadow_bias = z_receiver - bias;
 
     // Take half the shadow samples first
     for (uint i = 0; i < SUN_SHADOW_KERNEL_SIZE / 2; i++) {
         const vec2 kernel_off = unpackSnorm2x16(sun_shadow_info.kernel[i]);
         vec2 offset = filter_radius_uv * kernel_off;
-        shadow += texture(shadow_cascades[layer], vec3(uv + offset, shadow_bias)).r;
+        shadow += texture(shadow_cascades[layer], vec3(uv + offset, z_receiver)).r;
     }
 
     // If we have a full dark shadow, early out
@@ -149,12 +144,34 @@ float sample_shadow_map(int layer, vec2 uv, float bias, vec2 filter_radius_uv, f
     for (uint i = SUN_SHADOW_KERNEL_SIZE / 2; i < SUN_SHADOW_KERNEL_SIZE; i++) {
         const vec2 kernel_off = unpackSnorm2x16(sun_shadow_info.kernel[i]);
         vec2 offset = filter_radius_uv * kernel_off;
-        shadow += texture(shadow_cascades[layer], vec3(uv + offset, shadow_bias)).r;
+        shadow += texture(shadow_cascades[layer], vec3(uv + offset, z_receiver)).r;
     }
 
     return shadow / float(SUN_SHADOW_KERNEL_SIZE);
 }
 
+/// Index of the shadow cascade covering the fragment, or the cascade count if the fragment is
+/// outside of every cascade.
+int get_shadow_cascade() {
+    for (int i = 0; i < sun_shadow_info.count; ++i) {
+        if (vs_in.view_space_position.z < sun_shadow_info.cascades[i].far_plane) {
+            return i;
+        }
+    }
+    return int(sun_shadow_info.count);
+}
+
+/// Color to tint fragments shaded by the given shadow cascade with when debugging cascades.
+vec3 shadow_cascade_debug_color(int layer) {
+    switch (layer) {
+        case 0: return vec3(1.0, 0.0, 0.0);
+        case 1: return vec3(0.0, 1.0, 0.0);
+        case 2: return vec3(0.0, 0.0, 1.0);
+        case 3: return vec3(1.0, 1.0, 0.0);
+        default: return vec3(1.0);
+    }
+}
+
 /// Calculates the shadowing factor of the fragment with the given surface normal.
 ///
 /// NOTE: Even though this is called the "shadow factor", really what it's getting is the
@@ -163,13 +180,7 @@ float sample_shadow_map(int layer, vec2 uv, float bias, vec2 filter_radius_uv, f
 /// `normal` - Surface normal.
 float compute_shadow_factor(vec3 normal) {
     // Determine which cascade to use
-    int layer = int(sun_shadow_info.count);
-    for (int i = 0; i < sun_shadow_info.count; ++i) {
-        if (vs_in.view_space_position.z < sun_shadow_info.cascades[i].far_plane) {
-            layer = i;
-            break;
-        }
-    }
+    const int layer = get_shadow_cascade();
 
     // Outside shadow bounds
     if (layer == sun_shadow_info.count) {
@@ -183,27 +194,80 @@ float compute_shadow_factor(vec3 normal) {
             1.0
         );
 
-    float NoL = dot(normal, global_lighting.sun_direction.xyz);
-    float bias = max(
-        sun_shadow_info.cascades[layer].max_depth_bias * (1.0 - NoL), 
-        sun_shadow_info.cascades[layer].min_depth_bias
-    ) * (1.0 / sun_shadow_info.cascades[layer].depth_range);
-
     vec3 proj_coords = frag_pos_light_space.xyz / frag_pos_light_space.w;
     proj_coords.xy = proj_coords.xy * 0.5 + 0.5;
     proj_coords.y = 1.0 - proj_coords.y;
 
-    vec2 filter_radius_uv = 0.01 * sun_shadow_info.cascades[layer].uv_size;
-
-	// Filtering
+    // Depth bias is applied when rendering the shadow map, so the receiver depth is used as is
 	return sample_shadow_map(
         layer,
         proj_coords.xy, 
-        bias, 
-        filter_radius_uv, 
+        sun_shadow_info.cascades[layer].filter_radius_uv, 
         proj_coords.z
     );
 }
+
+/// Index of the cube face of a point light shadow containing a direction. Matches the order the
+/// faces are rendered in (+X, -X, +Y, -Y, +Z, -Z).
+uint point_shadow_face(vec3 light_to_frag) {
+    const vec3 a = abs(light_to_frag);
+    if (a.x >= a.y && a.x >= a.z) {
+        return light_to_frag.x >= 0.0 ? 0 : 1;
+    } else if (a.y >= a.z) {
+        return light_to_frag.y >= 0.0 ? 2 : 3;
+    }
+    return light_to_frag.z >= 0.0 ? 4 : 5;
+}
+
+/// Calculates the shadowing factor of the fragment for a point or spot light. Returns `1.0` if
+/// the light is unshadowed.
+///
+/// `light` - The light to get the shadow of.
+/// `normal` - Surface normal.
+float compute_local_shadow_factor(Light light, vec3 normal) {
+    if (light.shadow_index == NO_LOCAL_SHADOW) {
+        return 1.0;
+    }
+
+    // Point lights have one view per cube face
+    uint view_idx = light.shadow_index;
+    if (light.direction_angle.w < 0.0) {
+        view_idx += point_shadow_face(vs_in.world_space_position - light.position_range.xyz);
+    }
+
+    const LocalShadow view = local_shadows[view_idx];
+    const vec4 frag_pos_light_space = view.vp
+        * vec4(vs_in.world_space_position + (view.normal_bias * normalize(normal)), 1.0);
+
+    vec3 proj_coords = frag_pos_light_space.xyz / frag_pos_light_space.w;
+    if (frag_pos_light_space.w <= 0.0
+        || any(greaterThan(abs(proj_coords.xy), vec2(1.0)))
+        || proj_coords.z > 1.0
+    ) {
+        return 1.0;
+    }
+
+    proj_coords.xy = proj_coords.xy * 0.5 + 0.5;
+    proj_coords.y = 1.0 - proj_coords.y;
+
+    // Keep the filter inside of the tile so neighboring tiles don't bleed in
+    const vec2 filter_radius_uv = vec2(view.filter_radius_uv);
+    const vec2 tile_min = view.atlas_rect.xy + filter_radius_uv;
+    const vec2 tile_max = view.atlas_rect.xy + view.atlas_rect.zw - filter_radius_uv;
+    const vec2 uv = clamp(
+        view.atlas_rect.xy + (proj_coords.xy * view.atlas_rect.zw),
+        tile_min,
+        tile_max
+    );
+
+    float shadow = 0.0;
+    for (uint i = 0; i < SUN_SHADOW_KERNEL_SIZE; i++) {
+        const vec2 offset = filter_radius_uv * unpackSnorm2x16(sun_shadow_info.kernel[i]);
+        shadow += texture(local_shadow_atlas, vec3(uv + offset, proj_coords.z)).r;
+    }
+
+    return shadow / float(SUN_SHADOW_KERNEL_SIZE);
+}
 #endif
 
 /// Computes lighting from a generic source.
@@ -241,16 +305,32 @@ vec3 light_fragment(
 /// Get the cluster ID for the given screen coordinate.
 #if !defined(TASK_SHADER) && !defined(PATH_TRACE_PASS) && !defined(REFLECTIONS_PASS)
 uvec3 get_cluster_id(vec2 uv, float depth) {
+    const uvec4 dims = camera[gl_ViewIndex].cluster_dims;
+    const vec2 scale_bias = camera[gl_ViewIndex].cluster_scale_bias;
+    const float slice_depth = dims.w == DEPTH_SLICING_LINEAR ? depth : log(depth);
     return uvec3(
-        clamp(uint(uv.x * float(CAMERA_FROXELS_WIDTH)), 0, CAMERA_FROXELS_WIDTH - 1),
-        clamp(uint(uv.y * float(CAMERA_FROXELS_HEIGHT)), 0, CAMERA_FROXELS_HEIGHT - 1),
-        clamp(
-            uint(log(depth) * camera[gl_ViewIndex].cluster_scale_bias.x - camera[gl_ViewIndex].cluster_scale_bias.y), 
-            0,
-            CAMERA_FROXELS_DEPTH - 1
-        )
+        clamp(uint(uv.x * float(dims.x)), 0, dims.x - 1),
+        clamp(uint(uv.y * float(dims.y)), 0, dims.y - 1),
+        clamp(uint(max(slice_depth * scale_bias.x - scale_bias.y, 0.0)), 0, dims.z - 1)
     );
 }
+
+/// Color to shade fragments with when showing the light cluster heatmap. Goes from blue through
+/// green to red as the cluster fills up, and is white for full clusters.
+vec3 light_cluster_heatmap_color(uint light_count) {
+    if (light_count == 0) {
+        return vec3(0.0);
+    }
+
+    if (light_count >= MAX_LIGHTS_PER_CLUSTER) {
+        return vec3(1.0);
+    }
+
+    const float t = clamp(float(light_count) / LIGHT_CLUSTER_HEATMAP_SCALE, 0.0, 1.0);
+    return t < 0.5 
+        ? mix(vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0), t * 2.0)
+        : mix(vec3(0.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), (t - 0.5) * 2.0);
+}
 #endif
 
 vec4 sample_texture_default_bias(uint id, vec2 uv, float bias, vec4 def) {
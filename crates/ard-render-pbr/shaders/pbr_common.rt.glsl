@@ -90,6 +90,7 @@ vec4 compute_brdf_pdf(
 VertexAttribs get_vertex_attribs(
     const mat3 normal_mat,
     const uint index_base,
+    const int vertex_rebase,
     vec2 attribs
 ) {
     vec3 normals[3];
@@ -104,8 +105,10 @@ VertexAttribs get_vertex_attribs(
     [[unroll]]
     for (uint i = 0; i < 3; ++i) {
         const uint index = v_indices[index_base + i];
+        // Skinned objects have their own copy of the deformed attributes
+        const uint skinned_index = uint(int(index) + vertex_rebase);
 
-        const uvec2 ard_normal_raw = v_normals[index];
+        const uvec2 ard_normal_raw = v_normals[skinned_index];
         vec4 ard_normal = vec4(
             unpackSnorm2x16(ard_normal_raw.x),
             unpackSnorm2x16(ard_normal_raw.y)
@@ -118,7 +121,7 @@ VertexAttribs get_vertex_attribs(
         #endif
 
         #if ARD_VS_HAS_TANGENT
-            const uvec2 ard_tangent_raw = v_tangents[index];
+            const uvec2 ard_tangent_raw = v_tangents[skinned_index];
             vec4 ard_tangent = vec4(
                 unpackSnorm2x16(ard_tangent_raw.x),
                 unpackSnorm2x16(ard_tangent_raw.y)
@@ -1,10 +1,11 @@
 use std::collections::{BTreeMap, HashMap};
 
 use ard_formats::vertex::VertexLayout;
+use ard_math::{Mat3, Vec4};
 use ard_pal::prelude::{
     BlendFactor, BlendOp, ColorBlendAttachment, ColorBlendState, ColorComponents, CompareOp,
     CullMode, DepthStencilState, FrontFace, GraphicsProperties, PolygonMode, RasterizationState,
-    ShaderStage,
+    ShaderStage, StencilOpState,
 };
 use ard_render_base::shader_variant::ShaderVariant;
 use ard_render_material::{
@@ -23,10 +24,12 @@ use ard_render_si::{consts::*, types::GpuPbrMaterial};
 
 pub type PbrMaterialData = GpuPbrMaterial;
 
-pub const PBR_MATERIAL_TEXTURE_COUNT: usize = 3;
+pub const PBR_MATERIAL_TEXTURE_COUNT: usize = 5;
 pub const PBR_MATERIAL_DIFFUSE_SLOT: TextureSlot = TextureSlot(0);
 pub const PBR_MATERIAL_NORMAL_SLOT: TextureSlot = TextureSlot(1);
 pub const PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT: TextureSlot = TextureSlot(2);
+pub const PBR_MATERIAL_EMISSIVE_SLOT: TextureSlot = TextureSlot(3);
+pub const PBR_MATERIAL_OCCLUSION_SLOT: TextureSlot = TextureSlot(4);
 
 /// Creates the PBR material given functions that can create shader modules and materials (this is
 /// probably going to be a wrapper for the factories shader creation function).
@@ -103,6 +106,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::Greater,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState::default(),
             debug_name: "pbr_hzb_pass_pipeline".into(),
@@ -124,6 +130,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::Less,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState::default(),
             debug_name: "pbr_shadow_opaque_pass_pipeline".into(),
@@ -145,6 +154,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::Less,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState::default(),
             debug_name: "pbr_shadow_alpha_cutoff_pass_pipeline".into(),
@@ -166,6 +178,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState::default(),
             debug_name: "pbr_depth_opaque_prepass_pipeline".into(),
@@ -187,6 +202,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState::default(),
             debug_name: "pbr_depth_alpha_cutoff_prepass_pipeline".into(),
@@ -208,6 +226,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![ColorBlendAttachment {
@@ -235,6 +256,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![ColorBlendAttachment {
@@ -262,6 +286,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![ColorBlendAttachment {
@@ -289,6 +316,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::Equal,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![
@@ -333,6 +363,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::Equal,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![
@@ -377,6 +410,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![
@@ -421,6 +457,9 @@ pub fn create_pbr_material(
                 depth_compare: CompareOp::GreaterOrEqual,
                 min_depth: 0.0,
                 max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
             }),
             color_blend: ColorBlendState {
                 attachments: vec![ColorBlendAttachment {
@@ -520,6 +559,21 @@ pub fn create_pbr_material(
     })
 }
 
+/// Splits a 2D UV transform into the `uv_transform` (column-major 2x2) and `uv_offset` fields of
+/// [`PbrMaterialData`].
+#[inline(always)]
+pub fn pbr_uv_transform(transform: Mat3) -> (Vec4, Vec4) {
+    (
+        Vec4::new(
+            transform.x_axis.x,
+            transform.x_axis.y,
+            transform.y_axis.x,
+            transform.y_axis.y,
+        ),
+        transform.z_axis.truncate().extend(0.0).extend(0.0),
+    )
+}
+
 #[inline(always)]
 pub fn invocations_per_task(props: &GraphicsProperties) -> u32 {
     props
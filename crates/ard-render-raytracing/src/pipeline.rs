@@ -1,5 +1,3 @@
-use std::ops::Range;
-
 use ard_pal::prelude::*;
 use ard_render_base::resource::ResourceAllocator;
 use ard_render_material::{
@@ -26,15 +24,14 @@ pub struct RayTracingMaterialPipeline {
     raygen: Shader,
     miss: Shader,
     last_material_count: usize,
-    table_bases: TableBases,
+    table_regions: TableRegions,
 }
 
 #[derive(Clone, Copy)]
-struct TableBases {
-    pub hit_start: u64,
-    pub hit_end: u64,
-    pub raygen: u64,
-    pub miss: u64,
+struct TableRegions {
+    pub raygen: ShaderBindingTableRegion,
+    pub miss: ShaderBindingTableRegion,
+    pub hit: ShaderBindingTableRegion,
 }
 
 impl RayTracingMaterialPipeline {
@@ -47,7 +44,7 @@ impl RayTracingMaterialPipeline {
         let last_material_count = create_info.materials.allocated();
 
         let pipeline = Self::create_pipeline(ctx, create_info);
-        let (sbt, table_bases) = Self::create_sbt(ctx, &pipeline, last_material_count);
+        let (sbt, table_regions) = Self::create_sbt(ctx, &pipeline, last_material_count);
 
         Self {
             sbt,
@@ -58,7 +55,7 @@ impl RayTracingMaterialPipeline {
             raygen,
             miss,
             last_material_count,
-            table_bases,
+            table_regions,
         }
     }
 
@@ -68,18 +65,18 @@ impl RayTracingMaterialPipeline {
     }
 
     #[inline(always)]
-    pub fn raygen_offset(&self) -> u64 {
-        self.table_bases.raygen
+    pub fn raygen_region(&self) -> ShaderBindingTableRegion {
+        self.table_regions.raygen
     }
 
     #[inline(always)]
-    pub fn miss_offset(&self) -> u64 {
-        self.table_bases.miss
+    pub fn miss_region(&self) -> ShaderBindingTableRegion {
+        self.table_regions.miss
     }
 
     #[inline(always)]
-    pub fn hit_range(&self) -> Range<u64> {
-        self.table_bases.hit_start..self.table_bases.hit_end
+    pub fn hit_region(&self) -> ShaderBindingTableRegion {
+        self.table_regions.hit
     }
 
     #[inline(always)]
@@ -110,9 +107,9 @@ impl RayTracingMaterialPipeline {
             },
         );
 
-        let (sbt, table_bases) = Self::create_sbt(ctx, &self.pipeline, materials.allocated());
+        let (sbt, table_regions) = Self::create_sbt(ctx, &self.pipeline, materials.allocated());
         self.sbt = sbt;
-        self.table_bases = table_bases;
+        self.table_regions = table_regions;
 
         self.last_material_count = materials.allocated();
     }
@@ -185,73 +182,43 @@ impl RayTracingMaterialPipeline {
         ctx: &Context,
         pipeline: &RayTracingPipeline,
         material_count: usize,
-    ) -> (Buffer, TableBases) {
+    ) -> (Buffer, TableRegions) {
         let sbt_data = pipeline.shader_binding_table_data();
 
-        let mut table_bases = TableBases {
-            // Raygen shader comes first.
-            raygen: 0,
-            // Followed by the miss shader.
-            miss: sbt_data
-                .aligned_size
-                .next_multiple_of(sbt_data.base_alignment),
-            hit_start: 0,
-            hit_end: 0,
-        };
-        // Followed by every materials variants.
-        table_bases.hit_start = table_bases.miss
-            + sbt_data
-                .aligned_size
-                .next_multiple_of(sbt_data.base_alignment);
-        table_bases.hit_end = table_bases.hit_start
-            + (material_count as u64
-                * MaterialResource::RT_GROUPS_PER_MATERIAL as u64
-                * sbt_data.aligned_size);
+        // Raygen and miss groups come first, followed by every materials variants.
+        let mut builder = SbtBuilder::new(&sbt_data);
+        builder.raygen(0, &[]).miss(1, &[]);
+        for i in 0..(material_count * MaterialResource::RT_GROUPS_PER_MATERIAL) {
+            builder.hit(2 + i, &[]);
+        }
+        let table = builder.build().unwrap();
 
         let mut buffer = Buffer::new(
             ctx.clone(),
             BufferCreateInfo {
-                size: table_bases.hit_end,
+                size: table.data.len() as u64,
                 array_elements: 1,
                 buffer_usage: BufferUsage::SHADER_BINDING_TABLE | BufferUsage::DEVICE_ADDRESS,
                 memory_usage: MemoryUsage::CpuToGpu,
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("sbt".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
 
         let mut view = buffer.write(0).unwrap();
-
-        // Copy in raygen
-        let dst_begin = table_bases.raygen as usize;
-        let dst_end = (table_bases.raygen + sbt_data.entry_size) as usize;
-
-        let src_begin = 0;
-        let src_end = sbt_data.entry_size as usize;
-        view[dst_begin..dst_end].copy_from_slice(&sbt_data.raw[src_begin..src_end]);
-
-        // Copy in miss
-        let dst_begin = table_bases.miss as usize;
-        let dst_end = (table_bases.miss + sbt_data.entry_size) as usize;
-
-        let src_begin = sbt_data.entry_size as usize;
-        let src_end = 2 * sbt_data.entry_size as usize;
-        view[dst_begin..dst_end].copy_from_slice(&sbt_data.raw[src_begin..src_end]);
-
-        // Copy in SBT hit group entries, respecting alignment
-        for i in 0..(material_count * MaterialResource::RT_GROUPS_PER_MATERIAL) {
-            let dst_begin = table_bases.hit_start as usize + (i * sbt_data.aligned_size as usize);
-            let dst_end = dst_begin + sbt_data.entry_size as usize;
-
-            let src_begin = (2 + i) * sbt_data.entry_size as usize;
-            let src_end = src_begin + sbt_data.entry_size as usize;
-            view[dst_begin..dst_end].copy_from_slice(&sbt_data.raw[src_begin..src_end]);
-        }
-
+        view[..table.data.len()].copy_from_slice(&table.data);
         std::mem::drop(view);
 
-        (buffer, table_bases)
+        (
+            buffer,
+            TableRegions {
+                raygen: table.raygen,
+                miss: table.miss,
+                hit: table.hit,
+            },
+        )
     }
 }
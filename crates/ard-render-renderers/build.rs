@@ -14,8 +14,8 @@ fn main() {
     );
 
     ard_render_codegen::vulkan_spirv::compile_shader(
-        "./shaders/entity_select.comp",
-        PathBuf::from(&out_dir).join("entity_select.comp.spv"),
+        "./shaders/skinning.comp",
+        PathBuf::from(&out_dir).join("skinning.comp.spv"),
         &["./shaders/"],
         &[],
     );
@@ -34,6 +34,13 @@ fn main() {
         &[],
     );
 
+    ard_render_codegen::vulkan_spirv::compile_shader(
+        "./shaders/gui.frag",
+        PathBuf::from(&out_dir).join("gui_dual_src.frag.spv"),
+        &["./shaders/"],
+        &["DUAL_SOURCE_BLEND"],
+    );
+
     ard_render_codegen::vulkan_spirv::compile_shader(
         "./shaders/debug.vert",
         PathBuf::from(&out_dir).join("debug.vert.spv"),
@@ -5,7 +5,11 @@
 #define ARD_SET_GUI 0
 #include "ard_bindings.glsl"
 
-layout(location = 0) out vec4 OUT_COLOR;
+layout(location = 0, index = 0) out vec4 OUT_COLOR;
+#ifdef DUAL_SOURCE_BLEND
+// Per channel coverage used to blend `OUT_COLOR` with the destination.
+layout(location = 0, index = 1) out vec4 OUT_COVERAGE;
+#endif
 
 layout(location = 0) in vec4 IN_COLOR;
 layout(location = 1) in vec2 IN_UV;
@@ -22,8 +26,13 @@ void main() {
     }
     else
     {
-        color = texture(font_texture, IN_UV);
+        color = texture(gui_textures[consts.texture_id], IN_UV);
     }
     
     OUT_COLOR = IN_COLOR * color;
+
+#ifdef DUAL_SOURCE_BLEND
+    // Textures are premultiplied with uniform coverage across channels
+    OUT_COVERAGE = vec4(OUT_COLOR.a);
+#endif
 }
\ No newline at end of file
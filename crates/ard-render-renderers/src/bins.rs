@@ -59,6 +59,16 @@ pub struct RenderArgs<'a, 'b> {
     pub texture_factory: &'a TextureFactory,
     pub meshes: &'a ResourceAllocator<MeshResource>,
     pub materials: &'a ResourceAllocator<MaterialResource>,
+    /// Depth bias applied after every pipeline bind. Only valid for passes whose pipelines enable
+    /// [`DynamicStates::DEPTH_BIAS`].
+    pub depth_bias: Option<DepthBias>,
+}
+
+/// Constant and slope scaled depth bias of a pass.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DepthBias {
+    pub constant: f32,
+    pub slope: f32,
 }
 
 /// A draw bin represents a set of draw groups that have the same vertex layout and material,
@@ -416,7 +426,7 @@ impl DrawBins {
                 variant_id = variant.id;
 
                 // Bind variant pipeline
-                args.pass.bind_pipeline(variant.pipeline.clone());
+                args.bind_pipeline(variant.pipeline.clone());
                 rebound_material = true;
 
                 // Bind global sets
@@ -447,7 +457,7 @@ impl DrawBins {
                         variant_id = variant.id;
 
                         // Bind variant pipeline
-                        args.pass.bind_pipeline(variant.pipeline.clone());
+                        args.bind_pipeline(variant.pipeline.clone());
 
                         // Bind global sets
                         if !has_bound_global {
@@ -484,6 +494,14 @@ impl DrawBins {
 }
 
 impl<'a, 'b> RenderArgs<'a, 'b> {
+    fn bind_pipeline(&mut self, pipeline: GraphicsPipeline) {
+        self.pass.bind_pipeline(pipeline);
+
+        if let Some(bias) = self.depth_bias {
+            self.pass.set_depth_bias(bias.constant, 0.0, bias.slope);
+        }
+    }
+
     fn bind_global(&mut self) {
         self.pass.bind_sets(
             0,
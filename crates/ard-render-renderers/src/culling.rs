@@ -0,0 +1,152 @@
+use ard_pal::prelude::*;
+use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
+use ard_render_si::{consts::*, types::GpuCullingStats};
+
+/// Number of passes that record culling statistics.
+pub const CULLING_STATS_PASS_COUNT: usize = 3 + MAX_SHADOW_CASCADES;
+
+const STATS_SIZE: u64 = std::mem::size_of::<GpuCullingStats>() as u64;
+
+/// Passes whose task shaders cull objects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CullingPass {
+    DepthPrepass,
+    Entities,
+    ShadowCascade(usize),
+    LocalShadows,
+}
+
+/// Culling counters of a single pass.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CullingStatistics {
+    /// Objects whose visibility was checked.
+    pub instances_tested: u32,
+    /// Objects outside of the camera frustum.
+    pub frustum_culled: u32,
+    /// Objects inside of the frustum but hidden by the HZB.
+    pub occlusion_culled: u32,
+    /// Meshlets drawn after meshlet culling.
+    pub meshlets_drawn: u32,
+}
+
+/// Culling counters written by the task shaders of every culling pass.
+///
+/// Each frame in flight has its own copy of the counters, which are zeroed when the frame begins
+/// and read back once the frame slot comes around again, so reading never waits on the GPU.
+pub struct CullingStatsBuffer {
+    counters: Buffer,
+}
+
+impl CullingPass {
+    #[inline(always)]
+    fn slot(self) -> usize {
+        match self {
+            CullingPass::DepthPrepass => 0,
+            CullingPass::Entities => 1,
+            CullingPass::ShadowCascade(cascade) => 2 + cascade,
+            CullingPass::LocalShadows => 2 + MAX_SHADOW_CASCADES,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            CullingPass::DepthPrepass => "depth_prepass".into(),
+            CullingPass::Entities => "entities".into(),
+            CullingPass::ShadowCascade(cascade) => format!("shadow_cascade_{cascade}"),
+            CullingPass::LocalShadows => "local_shadows".into(),
+        }
+    }
+
+    fn from_slot(slot: usize) -> Self {
+        match slot {
+            0 => CullingPass::DepthPrepass,
+            1 => CullingPass::Entities,
+            slot if slot == 2 + MAX_SHADOW_CASCADES => CullingPass::LocalShadows,
+            slot => CullingPass::ShadowCascade(slot - 2),
+        }
+    }
+}
+
+impl From<GpuCullingStats> for CullingStatistics {
+    fn from(value: GpuCullingStats) -> Self {
+        Self {
+            instances_tested: value.instances_tested,
+            frustum_culled: value.frustum_culled,
+            occlusion_culled: value.occlusion_culled,
+            meshlets_drawn: value.meshlets_drawn,
+        }
+    }
+}
+
+impl CullingStatsBuffer {
+    pub fn new(ctx: &Context) -> Self {
+        let mut counters = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: STATS_SIZE,
+                array_elements: FRAMES_IN_FLIGHT * CULLING_STATS_PASS_COUNT,
+                buffer_usage: BufferUsage::STORAGE_BUFFER | BufferUsage::TRANSFER_DST,
+                memory_usage: MemoryUsage::GpuToCpu,
+                queue_types: QueueTypes::MAIN,
+                sharing_mode: SharingMode::Exclusive,
+                debug_name: Some("culling_stats".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        for i in 0..counters.array_elements() {
+            counters.write(i).unwrap().fill(0);
+        }
+
+        Self { counters }
+    }
+
+    /// The descriptor value `pass` binds to its culling stats binding.
+    #[inline(always)]
+    pub fn descriptor_value(&self, frame: Frame, pass: CullingPass) -> DescriptorValue<'_> {
+        DescriptorValue::StorageBuffer {
+            buffer: &self.counters,
+            array_element: Self::array_element(frame, pass.slot()),
+        }
+    }
+
+    /// Zeroes the counters of `frame`. Must be recorded before any culling pass of the frame.
+    pub fn reset<'a>(&'a self, commands: &mut CommandBuffer<'a>, frame: Frame) {
+        for slot in 0..CULLING_STATS_PASS_COUNT {
+            commands.fill_buffer(
+                &self.counters,
+                Self::array_element(frame, slot),
+                0,
+                STATS_SIZE,
+                0,
+            );
+        }
+    }
+
+    /// Reads the counters written the last time `frame` was rendered. Passes that didn't test
+    /// any objects are skipped. Must only be called once the GPU is done with `frame`.
+    pub fn read(&self, frame: Frame) -> Vec<(String, CullingStatistics)> {
+        (0..CULLING_STATS_PASS_COUNT)
+            .filter_map(|slot| {
+                let view = self
+                    .counters
+                    .read(Self::array_element(frame, slot))
+                    .unwrap();
+                let stats: GpuCullingStats =
+                    bytemuck::pod_read_unaligned(&view[..STATS_SIZE as usize]);
+
+                if stats.instances_tested == 0 {
+                    return None;
+                }
+
+                Some((CullingPass::from_slot(slot).name(), stats.into()))
+            })
+            .collect()
+    }
+
+    #[inline(always)]
+    fn array_element(frame: Frame, slot: usize) -> usize {
+        (usize::from(frame) * CULLING_STATS_PASS_COUNT) + slot
+    }
+}
@@ -1,4 +1,4 @@
-use std::mem::offset_of;
+use std::{mem::offset_of, ops::Range};
 
 use ard_pal::prelude::*;
 use ard_render_base::Frame;
@@ -7,7 +7,10 @@ use ard_render_debug::{buffer::DebugVertexBuffer, shape::DebugShapeVertex};
 use ard_render_si::bindings::Layouts;
 
 pub struct DebugRenderer {
+    /// Draws on top of everything.
     pipeline: GraphicsPipeline,
+    /// Draws behind scene geometry. Must be used in a pass with the scene depth buffer.
+    depth_tested_pipeline: GraphicsPipeline,
 }
 
 impl DebugRenderer {
@@ -30,7 +33,51 @@ impl DebugRenderer {
         )
         .unwrap();
 
-        let pipeline = GraphicsPipeline::new(
+        let pipeline = Self::create_pipeline(
+            ctx,
+            layouts,
+            vertex.clone(),
+            fragment.clone(),
+            None,
+            "debug_drawing_pipeline",
+        );
+
+        // Reverse Z, so closer depths are larger. Depth isn't written so lines don't hide each
+        // other.
+        let depth_tested_pipeline = Self::create_pipeline(
+            ctx,
+            layouts,
+            vertex,
+            fragment,
+            Some(DepthStencilState {
+                depth_clamp: false,
+                depth_test: true,
+                depth_write: false,
+                depth_compare: CompareOp::GreaterOrEqual,
+                min_depth: 0.0,
+                max_depth: 1.0,
+                stencil_test: false,
+                stencil_front: StencilOpState::default(),
+                stencil_back: StencilOpState::default(),
+            }),
+            "debug_drawing_depth_tested_pipeline",
+        );
+
+        Self {
+            pipeline,
+            depth_tested_pipeline,
+        }
+    }
+
+    fn create_pipeline(
+        ctx: &Context,
+        layouts: &Layouts,
+        vertex: Shader,
+        fragment: Shader,
+        depth_stencil: Option<DepthStencilState>,
+        debug_name: &str,
+    ) -> GraphicsPipeline {
+        GraphicsPipeline::new(
             ctx.clone(),
             GraphicsPipelineCreateInfo {
                 stages: ShaderStages::Traditional {
@@ -65,7 +112,7 @@ impl DebugRenderer {
                     cull_mode: CullMode::None,
                     front_face: FrontFace::CounterClockwise,
                 },
-                depth_stencil: None,
+                depth_stencil,
                 color_blend: ColorBlendState {
                     attachments: vec![ColorBlendAttachment {
                         blend: true,
@@ -82,14 +129,34 @@ impl DebugRenderer {
                     }],
                 },
                 push_constants_size: None,
-                debug_name: Some("debug_drawing_pipeline".into()),
+                compile_mode: PipelineCompileMode::Blocking,
+                debug_name: Some(debug_name.into()),
+                dynamic_states: DynamicStates::empty(),
             },
         )
-        .unwrap();
+        .unwrap()
+    }
 
-        Self { pipeline }
+    /// Draws the depth tested debug shapes. Must be called within a pass using the scene depth
+    /// buffer.
+    pub fn render_depth_tested<'a>(
+        &'a self,
+        frame: Frame,
+        pass: &mut RenderPass<'a>,
+        vertices: &'a DebugVertexBuffer,
+        camera: &'a CameraUbo,
+    ) {
+        Self::draw(
+            &self.depth_tested_pipeline,
+            frame,
+            pass,
+            vertices,
+            camera,
+            0..vertices.depth_tested_count(),
+        );
     }
 
+    /// Draws the debug shapes that aren't depth tested.
     pub fn render<'a>(
         &'a self,
         frame: Frame,
@@ -97,11 +164,29 @@ impl DebugRenderer {
         vertices: &'a DebugVertexBuffer,
         camera: &'a CameraUbo,
     ) {
-        if vertices.vertex_count() == 0 {
+        Self::draw(
+            &self.pipeline,
+            frame,
+            pass,
+            vertices,
+            camera,
+            vertices.depth_tested_count()..vertices.vertex_count(),
+        );
+    }
+
+    fn draw<'a>(
+        pipeline: &GraphicsPipeline,
+        frame: Frame,
+        pass: &mut RenderPass<'a>,
+        vertices: &'a DebugVertexBuffer,
+        camera: &'a CameraUbo,
+        range: Range<usize>,
+    ) {
+        if range.is_empty() {
             return;
         }
 
-        pass.bind_pipeline(self.pipeline.clone());
+        pass.bind_pipeline(pipeline.clone());
         pass.bind_sets(0, vec![camera.get_set(frame)]);
         pass.bind_vertex_buffers(
             0,
@@ -111,6 +196,6 @@ impl DebugRenderer {
                 offset: 0,
             }],
         );
-        pass.draw(vertices.vertex_count(), 1, 0, 0);
+        pass.draw(range.len(), 1, range.start, 0);
     }
 }
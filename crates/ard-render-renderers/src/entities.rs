@@ -1,7 +1,7 @@
 use ard_ecs::prelude::*;
 use ard_math::{Vec2, Vec3A};
 use ard_pal::prelude::*;
-use ard_render_base::{resource::ResourceAllocator, Frame};
+use ard_render_base::{resource::ResourceAllocator, Frame, FRAMES_IN_FLIGHT};
 use ard_render_camera::ubo::CameraUbo;
 use ard_render_material::{
     factory::MaterialFactory, material::MaterialResource,
@@ -12,12 +12,12 @@ use ard_render_objects::{
     objects::RenderObjects,
     set::{RenderableSet, RenderableSetUpdate},
 };
-use ard_render_si::{bindings::*, types::*};
+use ard_render_si::bindings::*;
 use ard_render_textures::{factory::TextureFactory, texture::TextureResource};
-use ordered_float::NotNan;
 
 use crate::{
     bins::{DrawBins, RenderArgs},
+    culling::CullingStatsBuffer,
     highz::HzbImage,
     ids::RenderIds,
     passes::{
@@ -43,9 +43,8 @@ pub struct EntityIdRenderer {
     bins: DrawBins,
     set: RenderableSet,
     entity_pass_sets: EntityPassSets,
-    entity_select_pipeline: ComputePipeline,
-    entity_select_set: DescriptorSet,
-    selected_entity: Buffer,
+    /// Entity IDs copied out of the ID image, with one element per frame in flight.
+    picked_entities: Buffer,
 }
 
 pub struct EntityIdRenderArgs<'a, 'b> {
@@ -61,87 +60,30 @@ pub struct EntityIdRenderArgs<'a, 'b> {
     pub materials: &'a ResourceAllocator<MaterialResource>,
 }
 
-const ENTITY_SELECT_SAMPLER: Sampler = Sampler {
-    min_filter: Filter::Nearest,
-    mag_filter: Filter::Nearest,
-    mipmap_filter: Filter::Nearest,
-    address_u: SamplerAddressMode::ClampToEdge,
-    address_v: SamplerAddressMode::ClampToEdge,
-    address_w: SamplerAddressMode::ClampToEdge,
-    anisotropy: None,
-    compare: None,
-    min_lod: unsafe { NotNan::new_unchecked(0.0) },
-    max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
-    unnormalize_coords: false,
-    border_color: None,
-};
-
 impl EntityIdRenderer {
     pub fn new(ctx: &Context, layouts: &Layouts) -> Self {
-        let module = Shader::new(
-            ctx.clone(),
-            ShaderCreateInfo {
-                code: include_bytes!(concat!(env!("OUT_DIR"), "./entity_select.comp.spv")),
-                debug_name: Some("entity_select_shader".into()),
-            },
-        )
-        .unwrap();
-
-        let entity_select_pipeline = ComputePipeline::new(
-            ctx.clone(),
-            ComputePipelineCreateInfo {
-                layouts: vec![layouts.entity_select.clone()],
-                module,
-                work_group_size: (1, 1, 1),
-                push_constants_size: Some(
-                    std::mem::size_of::<GpuEntitySelectPushConstants>() as u32
-                ),
-                debug_name: Some("entity_select_pipeline".into()),
-            },
-        )
-        .unwrap();
-
-        let selected_entity = Buffer::new(
+        let picked_entities = Buffer::new(
             ctx.clone(),
             BufferCreateInfo {
                 size: std::mem::size_of::<u32>() as u64,
-                array_elements: 1,
-                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                array_elements: FRAMES_IN_FLIGHT,
+                buffer_usage: BufferUsage::TRANSFER_DST,
                 memory_usage: MemoryUsage::GpuToCpu,
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
-                debug_name: Some("selected_entity_buffer".into()),
+                debug_name: Some("picked_entities_buffer".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
 
-        let mut entity_select_set = DescriptorSet::new(
-            ctx.clone(),
-            DescriptorSetCreateInfo {
-                layout: layouts.entity_select.clone(),
-                debug_name: Some("entity_select_set".into()),
-            },
-        )
-        .unwrap();
-
-        entity_select_set.update(&[DescriptorSetUpdate {
-            binding: ENTITY_SELECT_SET_DST_BINDING,
-            array_element: 0,
-            value: DescriptorValue::StorageBuffer {
-                buffer: &selected_entity,
-                array_element: 0,
-            },
-        }]);
-
         Self {
             ctx: ctx.clone(),
             ids: RenderIds::new(ctx),
             bins: DrawBins::new(),
             set: RenderableSet::default(),
-            selected_entity,
+            picked_entities,
             entity_pass_sets: EntityPassSets::new(ctx, layouts),
-            entity_select_pipeline,
-            entity_select_set,
         }
     }
 
@@ -205,23 +147,13 @@ impl EntityIdRenderer {
         frame: Frame,
         objects: &RenderObjects,
         hzb_image: &HzbImage,
-        entity_image: &Texture,
+        culling_stats: &CullingStatsBuffer,
     ) {
         self.entity_pass_sets
             .update_object_data_bindings(frame, objects.object_data(), &self.ids);
         self.entity_pass_sets.update_hzb_binding(frame, hzb_image);
-
-        self.entity_select_set.update(&[DescriptorSetUpdate {
-            binding: ENTITY_SELECT_SET_SRC_BINDING,
-            array_element: 0,
-            value: DescriptorValue::Texture {
-                texture: entity_image,
-                array_element: 0,
-                sampler: ENTITY_SELECT_SAMPLER,
-                base_mip: 0,
-                mip_count: 1,
-            },
-        }])
+        self.entity_pass_sets
+            .update_culling_stats_binding(frame, culling_stats);
     }
 
     pub fn render<'a>(&'a self, frame: Frame, args: EntityIdRenderArgs<'a, '_>) {
@@ -240,6 +172,7 @@ impl EntityIdRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_dynamic_opaque_bins(RenderArgs {
@@ -256,6 +189,7 @@ impl EntityIdRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_static_alpha_cutoff_bins(RenderArgs {
@@ -272,6 +206,7 @@ impl EntityIdRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_dynamic_alpha_cutoff_bins(RenderArgs {
@@ -288,6 +223,7 @@ impl EntityIdRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_transparent_bins(RenderArgs {
@@ -304,25 +240,43 @@ impl EntityIdRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
     }
 
-    pub fn select_entity<'a>(&'a self, commands: &mut CommandBuffer<'a>, uv: Vec2) {
-        commands.compute_pass(
-            &self.entity_select_pipeline,
-            Some("select_entity"),
-            |pass| {
-                let constants = [GpuEntitySelectPushConstants { uv }];
-                pass.bind_sets(0, vec![&self.entity_select_set]);
-                pass.push_constants(bytemuck::cast_slice(&constants));
-                ComputePassDispatch::Inline(1, 1, 1)
+    /// Copies the entity ID under `uv` into the readback slot for `frame`. The result is read
+    /// with [`EntityIdRenderer::read_back_picked_entity`] once the GPU has finished the frame.
+    pub fn pick_entity<'a>(
+        &'a self,
+        frame: Frame,
+        commands: &mut CommandBuffer<'a>,
+        entity_ids: &'a Texture,
+        uv: Vec2,
+    ) {
+        let (width, height, _) = entity_ids.dims();
+        let x = ((uv.x.clamp(0.0, 1.0) * width as f32) as u32).min(width.saturating_sub(1));
+        let y = ((uv.y.clamp(0.0, 1.0) * height as f32) as u32).min(height.saturating_sub(1));
+
+        commands.copy_texture_to_buffer(
+            &self.picked_entities,
+            entity_ids,
+            BufferTextureCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                buffer_array_element: usize::from(frame),
+                texture_offset: (x, y, 0),
+                texture_extent: (1, 1, 1),
+                texture_mip_level: 0,
+                texture_array_element: 0,
             },
         );
     }
 
-    // NOTE: This function will stall rendering when called.
-    pub fn read_back_selected_entity(&self) -> Option<Entity> {
-        let view = self.selected_entity.read(0).unwrap();
+    /// Reads back the entity picked during `frame`. Must only be called once the GPU has
+    /// finished the frame, which is the case when the frame is next begun.
+    pub fn read_back_picked_entity(&self, frame: Frame) -> Option<Entity> {
+        let view = self.picked_entities.read(usize::from(frame)).unwrap();
         let u32_slice: &[u32] = bytemuck::cast_slice(view.as_ref());
         Entity::try_from(u32_slice[0])
             .ok()
@@ -33,6 +33,7 @@ impl GlobalSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.global.clone(),
                         debug_name: Some(format!("global_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -61,6 +62,7 @@ impl GlobalSets {
                         sampler: SHADOW_SAMPLER,
                         base_mip: 0,
                         mip_count: 1,
+                        aspect: None,
                     },
                 }
             } else {
@@ -118,6 +120,7 @@ impl GlobalSets {
                     sampler: DI_MAP_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -134,6 +137,7 @@ impl GlobalSets {
                 sampler: AO_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -0,0 +1,83 @@
+use ard_pal::prelude::*;
+use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
+use ard_render_si::{bindings::*, types::GpuAssertRecord};
+
+/// The record is read as a single std430 struct, which is padded to a multiple of 16 bytes.
+const RECORD_SIZE: u64 = (std::mem::size_of::<GpuAssertRecord>() as u64).next_multiple_of(16);
+
+/// Failure records written by GPU asserts, with one element per frame in flight.
+///
+/// Shaders using asserts bind [`GpuAsserts::set`] at `ARD_SET_GPU_ASSERTS`. See
+/// `gpu_assert.glsl` in `ard-render` for the shader side.
+pub struct GpuAsserts {
+    records: Buffer,
+    sets: [DescriptorSet; FRAMES_IN_FLIGHT],
+}
+
+impl GpuAsserts {
+    pub fn new(ctx: &Context, layouts: &Layouts) -> Self {
+        let mut records = Buffer::new(
+            ctx.clone(),
+            BufferCreateInfo {
+                size: RECORD_SIZE,
+                array_elements: FRAMES_IN_FLIGHT,
+                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                memory_usage: MemoryUsage::GpuToCpu,
+                queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Concurrent,
+                debug_name: Some("gpu_assert_records".into()),
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap();
+
+        for frame_idx in 0..FRAMES_IN_FLIGHT {
+            records.write(frame_idx).unwrap().fill(0);
+        }
+
+        let sets = std::array::from_fn(|frame_idx| {
+            let mut set = DescriptorSet::new(
+                ctx.clone(),
+                DescriptorSetCreateInfo {
+                    layout: layouts.gpu_asserts.clone(),
+                    debug_name: Some(format!("gpu_asserts_set_{frame_idx}")),
+                    variable_count: None,
+                },
+            )
+            .unwrap();
+
+            set.update(&[DescriptorSetUpdate {
+                binding: GPU_ASSERTS_SET_GPU_ASSERT_BUFFER_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: &records,
+                    array_element: frame_idx,
+                },
+            }]);
+
+            set
+        });
+
+        Self { records, sets }
+    }
+
+    #[inline(always)]
+    pub fn set(&self, frame: Frame) -> &DescriptorSet {
+        &self.sets[usize::from(frame)]
+    }
+
+    /// Takes the failure record written the last time `frame` was rendered, if any assert failed,
+    /// and resets it. Must only be called once the GPU is done with `frame`.
+    pub fn take_failure(&mut self, frame: Frame) -> Option<GpuAssertRecord> {
+        let mut view = self.records.write(usize::from(frame)).unwrap();
+        let record: GpuAssertRecord =
+            bytemuck::pod_read_unaligned(&view[..std::mem::size_of::<GpuAssertRecord>()]);
+
+        if record.failures == 0 {
+            return None;
+        }
+
+        view.fill(0);
+        Some(record)
+    }
+}
@@ -1,13 +1,17 @@
+use std::{collections::HashMap, sync::Arc};
+
 use ard_log::warn;
 use ard_math::{UVec2, Vec2};
 use ard_pal::prelude::*;
 use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
-use ard_render_gui::GuiRunOutput;
-use ard_render_si::{bindings::*, types::*};
+use ard_render_gui::{Gui, GuiRunOutput, GuiUserTexture, GuiViewportOutput};
+use ard_render_si::{bindings::*, consts::*, types::*};
+use egui::{ViewportId, ViewportIdMap};
 use ordered_float::NotNan;
 
 const DEFAULT_VB_SIZE: u64 = 256;
 const DEFAULT_IB_SIZE: u64 = 256;
+const DEFAULT_TEXTURE_CAPACITY: usize = 16;
 
 const FONT_SAMPLER: Sampler = Sampler {
     min_filter: Filter::Linear,
@@ -18,6 +22,7 @@ const FONT_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     border_color: None,
@@ -26,22 +31,55 @@ const FONT_SAMPLER: Sampler = Sampler {
 
 pub struct GuiDrawPrepare<'a> {
     pub frame: Frame,
-    pub canvas_size: (u32, u32),
+    pub token: &'a FrameToken,
     pub scene_texture: (&'a Texture, usize),
     pub gui_output: &'a mut GuiRunOutput,
 }
 
 pub struct GuiRenderer {
     ctx: Context,
-    font_texture: Texture,
-    vertex_buffer: Buffer,
-    index_buffer: Buffer,
+    layout: DescriptorSetLayout,
     font_pipeline: GraphicsPipeline,
-    sets: [DescriptorSet; FRAMES_IN_FLIGHT],
-    draw_calls: Vec<DrawCall>,
+    sets: [GuiSet; FRAMES_IN_FLIGHT],
+    viewports: ViewportIdMap<ViewportDraws>,
+    textures: HashMap<egui::TextureId, GuiTexture>,
+    /// Slots of the texture array that can be reused.
+    free_slots: Vec<usize>,
+    /// Number of slots ever handed out.
+    slot_count: usize,
     texture_deltas: Vec<TextureDelta>,
 }
 
+struct GuiSet {
+    set: DescriptorSet,
+    /// Size of the texture array of the set.
+    capacity: usize,
+    /// Slots that changed since the set was last used.
+    dirty: Vec<usize>,
+}
+
+struct GuiTexture {
+    /// Index into the texture array.
+    slot: usize,
+    texture: Arc<Texture>,
+    array_element: usize,
+    sampler: Sampler,
+}
+
+/// Geometry of a single viewport. Textures are shared between viewports.
+struct ViewportDraws {
+    vertices: RingBuffer,
+    indices: RingBuffer,
+    /// Array element and offset of this frame's vertices and indices.
+    vertex_region: (usize, u64),
+    index_region: (usize, u64),
+    /// Geometry of every mesh, gathered so it can be written in one go.
+    vertex_data: Vec<egui::epaint::Vertex>,
+    index_data: Vec<u32>,
+    draw_calls: Vec<DrawCall>,
+    screen_size: (u32, u32),
+}
+
 struct DrawCall {
     vertex_offset: isize,
     index_offset: usize,
@@ -51,12 +89,13 @@ struct DrawCall {
 }
 
 struct TextureDelta {
+    texture: Arc<Texture>,
     /// Start position in the texture to apply the delta.
-    pub pos: UVec2,
+    pos: UVec2,
     /// Size of the region to copy.
-    pub size: UVec2,
+    size: UVec2,
     /// Staging buffer with the image data.
-    pub buffer: Buffer,
+    buffer: Buffer,
 }
 
 impl GuiRenderer {
@@ -70,10 +109,18 @@ impl GuiRenderer {
         )
         .unwrap();
 
+        // Text is blended per channel when supported, so the font atlas can hold subpixel
+        // coverage
+        let dual_source = ctx.properties().features.dual_source_blend;
+
         let fragment = Shader::new(
             ctx.clone(),
             ShaderCreateInfo {
-                code: include_bytes!(concat!(env!("OUT_DIR"), "./gui.frag.spv")),
+                code: if dual_source {
+                    include_bytes!(concat!(env!("OUT_DIR"), "./gui_dual_src.frag.spv")) as &[u8]
+                } else {
+                    include_bytes!(concat!(env!("OUT_DIR"), "./gui.frag.spv"))
+                },
                 debug_name: Some("gui_fragment_shader".into()),
             },
         )
@@ -133,315 +180,365 @@ impl GuiRenderer {
                         blend: true,
                         color_blend_op: BlendOp::Add,
                         src_color_blend_factor: BlendFactor::One,
-                        dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                        dst_color_blend_factor: if dual_source {
+                            BlendFactor::OneMinusSrc1Color
+                        } else {
+                            BlendFactor::OneMinusSrcAlpha
+                        },
                         alpha_blend_op: BlendOp::Add,
                         src_alpha_blend_factor: BlendFactor::OneMinusDstAlpha,
                         dst_alpha_blend_factor: BlendFactor::One,
                     }],
                 },
                 push_constants_size: Some(std::mem::size_of::<GpuGuiPushConstants>() as u32),
+                compile_mode: PipelineCompileMode::Blocking,
                 debug_name: Some(String::from("egui_font_pipeline")),
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
 
-        let font_texture = Texture::new(
-            ctx.clone(),
-            TextureCreateInfo {
-                format: Format::Rgba8Unorm,
-                ty: TextureType::Type2D,
-                width: 1,
-                height: 1,
-                depth: 1,
-                array_elements: 1,
-                mip_levels: 1,
-                texture_usage: TextureUsage::TRANSFER_DST | TextureUsage::SAMPLED,
-                memory_usage: MemoryUsage::GpuOnly,
-                sample_count: MultiSamples::Count1,
-                queue_types: QueueTypes::MAIN,
-                sharing_mode: SharingMode::Exclusive,
-                debug_name: Some(String::from("egui_font_texture")),
-            },
-        )
-        .unwrap();
-
-        let vertex_buffer = Buffer::new(
-            ctx.clone(),
-            BufferCreateInfo {
-                size: std::mem::size_of::<egui::epaint::Vertex>() as u64 * DEFAULT_VB_SIZE,
-                array_elements: FRAMES_IN_FLIGHT,
-                buffer_usage: BufferUsage::VERTEX_BUFFER,
-                memory_usage: MemoryUsage::CpuToGpu,
-                sharing_mode: SharingMode::Exclusive,
-                queue_types: QueueTypes::MAIN,
-                debug_name: Some(String::from("egui_vertex_buffer")),
-            },
-        )
-        .unwrap();
-
-        let index_buffer = Buffer::new(
-            ctx.clone(),
-            BufferCreateInfo {
-                size: std::mem::size_of::<u32>() as u64 * DEFAULT_IB_SIZE,
-                array_elements: FRAMES_IN_FLIGHT,
-                buffer_usage: BufferUsage::INDEX_BUFFER,
-                memory_usage: MemoryUsage::CpuToGpu,
-                sharing_mode: SharingMode::Exclusive,
-                queue_types: QueueTypes::MAIN,
-                debug_name: Some(String::from("egui_index_buffer")),
-            },
-        )
-        .unwrap();
-
-        let sets = std::array::from_fn(|i| {
-            let mut set = DescriptorSet::new(
-                ctx.clone(),
-                DescriptorSetCreateInfo {
-                    layout: layouts.gui.clone(),
-                    debug_name: Some(format!("font_set_{i}")),
-                },
-            )
-            .unwrap();
-
-            set.update(&[DescriptorSetUpdate {
-                binding: GUI_SET_FONT_BINDING,
-                array_element: 0,
-                value: DescriptorValue::Texture {
-                    texture: &font_texture,
-                    array_element: 0,
-                    sampler: FONT_SAMPLER,
-                    base_mip: 0,
-                    mip_count: 1,
-                },
-            }]);
-
-            set
+        let sets = std::array::from_fn(|i| GuiSet {
+            set: Self::create_set(ctx, &layouts.gui, i, DEFAULT_TEXTURE_CAPACITY),
+            capacity: DEFAULT_TEXTURE_CAPACITY,
+            dirty: Vec::default(),
         });
 
         Self {
             ctx: ctx.clone(),
-            font_texture,
-            vertex_buffer,
-            index_buffer,
+            layout: layouts.gui.clone(),
             font_pipeline,
             sets,
-            draw_calls: Vec::default(),
+            viewports: ViewportIdMap::default(),
+            textures: HashMap::default(),
+            free_slots: Vec::default(),
+            slot_count: 0,
             texture_deltas: Vec::default(),
         }
     }
 
     pub fn prepare(&mut self, args: GuiDrawPrepare) {
-        // Bind the scene texture
-        self.sets[usize::from(args.frame)].update(&[DescriptorSetUpdate {
-            binding: GUI_SET_SCENE_BINDING,
-            array_element: 0,
-            value: DescriptorValue::Texture {
-                texture: args.scene_texture.0,
-                array_element: args.scene_texture.1,
-                sampler: FONT_SAMPLER,
-                base_mip: 0,
-                mip_count: 1,
-            },
-        }]);
-
-        // Update the lengths of index and vertex buffers if needed
-        let mut vb_size_req = 0;
-        let mut ib_size_req = 0;
-        for primitive in &args.gui_output.primitives {
-            match &primitive.primitive {
-                egui::epaint::Primitive::Mesh(mesh) => {
-                    vb_size_req +=
-                        (mesh.vertices.len() * std::mem::size_of::<egui::epaint::Vertex>()) as u64;
-                    ib_size_req += (mesh.indices.len() * std::mem::size_of::<u32>()) as u64;
-                }
-                egui::epaint::Primitive::Callback(_) => warn!("unsupported egui callback"),
-            }
+        self.update_user_textures(&mut args.gui_output.user_textures);
+        self.set_textures(&args.gui_output.textures_delta);
+        self.update_set(args.frame, args.scene_texture);
+
+        // Viewports that were closed no longer need their buffers
+        self.viewports
+            .retain(|id, _| args.gui_output.viewports.contains_key(id));
+
+        for (id, output) in &args.gui_output.viewports {
+            self.viewports
+                .entry(*id)
+                .or_insert_with(|| ViewportDraws::new(&self.ctx, *id))
+                .prepare(args.token, output, &self.textures);
         }
 
-        // Resize buffers if needed
-        if let Some(new_vb) = Buffer::expand(&self.vertex_buffer, vb_size_req, false) {
-            self.vertex_buffer = new_vb;
+        // Draws for this frame are recorded, so freed textures can be unbound. Sets hold on to
+        // what's bound to them, so textures stay alive until no frame in flight uses them.
+        for id in &args.gui_output.textures_delta.free {
+            self.free(*id);
         }
-        if let Some(new_ib) = Buffer::expand(&self.index_buffer, ib_size_req, false) {
-            self.index_buffer = new_ib;
-        }
-
-        // Prepare draw calls
-        let ppp = args.gui_output.pixels_per_point;
-        let mut vb_offset = 0;
-        let mut ib_offset = 0;
-
-        self.draw_calls.clear();
-
-        let mut vb_view = self.vertex_buffer.write(usize::from(args.frame)).unwrap();
-        let vb_slice = bytemuck::cast_slice_mut::<_, egui::epaint::Vertex>(vb_view.as_mut());
-        let mut ib_view = self.index_buffer.write(usize::from(args.frame)).unwrap();
-        let ib_slice = bytemuck::cast_slice_mut::<_, u32>(ib_view.as_mut());
-
-        for primitive in &args.gui_output.primitives {
-            let mesh = match &primitive.primitive {
-                egui::epaint::Primitive::Mesh(mesh) => mesh,
-                egui::epaint::Primitive::Callback(_) => continue,
-            };
-
-            vb_slice[vb_offset..(vb_offset + mesh.vertices.len())].copy_from_slice(&mesh.vertices);
-            ib_slice[ib_offset..(ib_offset + mesh.indices.len())].copy_from_slice(&mesh.indices);
-
-            let clip_min_x = ppp * primitive.clip_rect.min.x;
-            let clip_min_y = ppp * primitive.clip_rect.min.y;
-            let clip_max_x = ppp * primitive.clip_rect.max.x;
-            let clip_max_y = ppp * primitive.clip_rect.max.y;
-
-            let clip_min_x = clip_min_x.clamp(0.0, args.canvas_size.0 as f32);
-            let clip_min_y = clip_min_y.clamp(0.0, args.canvas_size.1 as f32);
-            let clip_max_x = clip_max_x.clamp(clip_min_x, args.canvas_size.0 as f32);
-            let clip_max_y = clip_max_y.clamp(clip_min_y, args.canvas_size.1 as f32);
-
-            let clip_min_x = clip_min_x.round() as u32;
-            let clip_min_y = clip_min_y.round() as u32;
-            let clip_max_x = clip_max_x.round() as u32;
-            let clip_max_y = clip_max_y.round() as u32;
+    }
 
-            self.draw_calls.push(DrawCall {
-                vertex_offset: vb_offset as isize,
-                index_offset: ib_offset,
-                index_count: mesh.indices.len(),
-                scissor: Scissor {
-                    x: clip_min_x as i32,
-                    y: clip_min_y as i32,
-                    width: clip_max_x - clip_min_x,
-                    height: clip_max_y - clip_min_y,
-                },
-                texture_id: match mesh.texture_id {
-                    egui::TextureId::Managed(_) => 0,
-                    egui::TextureId::User(id) => id as u32,
+    /// Applies partial texture updates. Must be recorded before the GUI is rendered.
+    pub fn update_textures<'a>(&'a self, commands: &mut CommandBuffer<'a>) {
+        self.texture_deltas.iter().for_each(|delta| {
+            commands.copy_buffer_to_texture(
+                &delta.texture,
+                &delta.buffer,
+                BufferTextureCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    buffer_array_element: 0,
+                    texture_offset: (delta.pos.x, delta.pos.y, 0),
+                    texture_extent: (delta.size.x, delta.size.y, 1),
+                    texture_mip_level: 0,
+                    texture_array_element: 0,
                 },
-            });
+            );
+        });
+    }
 
-            vb_offset += mesh.vertices.len();
-            ib_offset += mesh.indices.len();
+    fn create_set(
+        ctx: &Context,
+        layout: &DescriptorSetLayout,
+        frame: usize,
+        capacity: usize,
+    ) -> DescriptorSet {
+        DescriptorSet::new(
+            ctx.clone(),
+            DescriptorSetCreateInfo {
+                layout: layout.clone(),
+                debug_name: Some(format!("gui_set_{frame}")),
+                variable_count: Some(capacity),
+            },
+        )
+        .unwrap()
+    }
+
+    fn update_user_textures(
+        &mut self,
+        changes: &mut Vec<(egui::TextureId, Option<GuiUserTexture>)>,
+    ) {
+        for (id, texture) in changes.drain(..) {
+            match texture {
+                Some(texture) => self.insert(
+                    id,
+                    texture.texture,
+                    texture.array_element,
+                    to_sampler(texture.options),
+                ),
+                None => self.free(id),
+            }
         }
+    }
 
-        // Handle texture updates
+    /// Creates new managed textures and queues up partial updates to existing ones.
+    fn set_textures(&mut self, delta: &egui::TexturesDelta) {
         self.texture_deltas.clear();
-        for (id, delta) in &args.gui_output.full.textures_delta.set {
-            match id {
-                egui::TextureId::Managed(id) => {
-                    if *id != 0 {
-                        warn!("no support for non-font textures in egui.");
-                        continue;
-                    }
-                }
-                egui::TextureId::User(_) => {
-                    warn!("no support for user textures in egui");
-                    continue;
-                }
-            }
 
-            let pos = UVec2::from(delta.pos.unwrap_or_default().map(|val| val as u32));
+        let mut uploads = Vec::default();
+        for (id, delta) in &delta.set {
             let size = UVec2::new(delta.image.width() as u32, delta.image.height() as u32);
             let data = match &delta.image {
-                egui::ImageData::Color(_) => {
-                    warn!("no support for non-font textures in egui.");
-                    continue;
-                }
+                egui::ImageData::Color(img) => bytemuck::cast_slice(&img.pixels).to_vec(),
                 egui::ImageData::Font(img) => img
                     .srgba_pixels(None)
-                    .map(|color| color.to_array())
+                    .flat_map(|color| color.to_array())
                     .collect::<Vec<_>>(),
             };
 
-            // If the texture size mismatches, we recreate and rebind the texture
-            let (width, height, _) = self.font_texture.dims();
-            if width < size.x || height < size.y {
-                self.font_texture = Texture::new(
-                    self.ctx.clone(),
-                    TextureCreateInfo {
-                        format: Format::Rgba8Unorm,
-                        ty: TextureType::Type2D,
-                        width: size.x,
-                        height: size.y,
-                        depth: 1,
-                        array_elements: 1,
-                        mip_levels: 1,
-                        texture_usage: TextureUsage::TRANSFER_DST | TextureUsage::SAMPLED,
-                        memory_usage: MemoryUsage::GpuOnly,
-                        sharing_mode: SharingMode::Exclusive,
-                        queue_types: QueueTypes::MAIN,
-                        sample_count: MultiSamples::Count1,
-                        debug_name: Some(String::from("egui_font_texture")),
-                    },
-                )
-                .unwrap();
-
-                self.sets.iter_mut().for_each(|set| {
-                    set.update(&[DescriptorSetUpdate {
-                        binding: 0,
-                        array_element: 0,
-                        value: DescriptorValue::Texture {
-                            texture: &self.font_texture,
-                            array_element: 0,
-                            sampler: FONT_SAMPLER,
-                            base_mip: 0,
-                            mip_count: 1,
-                        },
-                    }]);
+            // Partial updates are copied on the main queue since it owns the texture
+            if let Some(pos) = delta.pos {
+                let texture = match self.textures.get(id) {
+                    Some(texture) => texture,
+                    None => {
+                        warn!("partial update to unknown egui texture {id:?}");
+                        continue;
+                    }
+                };
+
+                self.texture_deltas.push(TextureDelta {
+                    texture: texture.texture.clone(),
+                    pos: UVec2::new(pos[0] as u32, pos[1] as u32),
+                    size,
+                    buffer: Buffer::new_staging(
+                        self.ctx.clone(),
+                        QueueType::Main,
+                        Some(String::from("egui_texture_delta_staging")),
+                        &data,
+                    )
+                    .unwrap(),
                 });
+                continue;
             }
 
-            self.texture_deltas.push(TextureDelta {
-                pos,
-                size,
-                buffer: Buffer::new_staging(
-                    self.ctx.clone(),
-                    QueueType::Main,
-                    Some(String::from("texture_del_staging_buffer")),
-                    bytemuck::cast_slice(&data),
-                )
-                .unwrap(),
-            })
+            // Whole images get a new texture, uploaded on the transfer queue
+            let texture = Texture::new(
+                self.ctx.clone(),
+                TextureCreateInfo {
+                    format: Format::Rgba8Unorm,
+                    ty: TextureType::Type2D,
+                    width: size.x,
+                    height: size.y,
+                    depth: 1,
+                    array_elements: 1,
+                    mip_levels: 1,
+                    texture_usage: TextureUsage::TRANSFER_DST | TextureUsage::SAMPLED,
+                    memory_usage: MemoryUsage::GpuOnly,
+                    sample_count: MultiSamples::Count1,
+                    queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
+                    sharing_mode: SharingMode::Exclusive,
+                    debug_name: Some(format!("egui_texture_{id:?}")),
+                    sparse: false,
+                    initial_data: None,
+                    memory_fallback: MemoryFallback::None,
+                },
+            )
+            .unwrap();
+
+            let staging = Buffer::new_staging(
+                self.ctx.clone(),
+                QueueType::Transfer,
+                Some(String::from("egui_texture_staging")),
+                &data,
+            )
+            .unwrap();
+
+            let texture = Arc::new(texture);
+            uploads.push((texture.clone(), staging, size));
+            self.insert(*id, texture, 0, to_sampler(delta.options));
         }
-    }
 
-    pub fn update_textures<'a>(&'a self, commands: &mut CommandBuffer<'a>) {
-        self.texture_deltas.iter().for_each(|delta| {
+        if uploads.is_empty() {
+            return;
+        }
+
+        let mut commands = self.ctx.transfer().command_buffer();
+        for (texture, staging, size) in &uploads {
             commands.copy_buffer_to_texture(
-                &self.font_texture,
-                &delta.buffer,
+                texture,
+                staging,
                 BufferTextureCopy {
                     buffer_offset: 0,
                     buffer_row_length: 0,
                     buffer_image_height: 0,
                     buffer_array_element: 0,
-                    texture_offset: (delta.pos.x, delta.pos.y, 0),
-                    texture_extent: (delta.size.x, delta.size.y, 1),
+                    texture_offset: (0, 0, 0),
+                    texture_extent: (size.x, size.y, 1),
                     texture_mip_level: 0,
                     texture_array_element: 0,
                 },
             );
+            commands.set_texture_usage(texture, TextureUsage::SAMPLED, 0..1, 0..1);
+            commands.transfer_texture_ownership(
+                texture,
+                0,
+                0,
+                1,
+                QueueType::Main,
+                Some(TextureUsage::SAMPLED),
+            );
+        }
+        self.ctx
+            .transfer()
+            .submit(Some("egui_texture_upload"), commands);
+    }
+
+    /// Binds a texture to an id, replacing the texture already bound to it.
+    fn insert(
+        &mut self,
+        id: egui::TextureId,
+        texture: Arc<Texture>,
+        array_element: usize,
+        sampler: Sampler,
+    ) {
+        let slot = match self.textures.get(&id) {
+            Some(old) => old.slot,
+            None => match self.free_slots.pop() {
+                Some(slot) => slot,
+                None => {
+                    if self.slot_count == MAX_GUI_TEXTURES {
+                        warn!("out of egui texture slots. dropping texture {id:?}");
+                        return;
+                    }
+                    self.slot_count += 1;
+                    self.slot_count - 1
+                }
+            },
+        };
+
+        self.sets.iter_mut().for_each(|set| set.dirty.push(slot));
+        self.textures.insert(
+            id,
+            GuiTexture {
+                slot,
+                texture,
+                array_element,
+                sampler,
+            },
+        );
+    }
+
+    fn free(&mut self, id: egui::TextureId) {
+        if let Some(texture) = self.textures.remove(&id) {
+            self.free_slots.push(texture.slot);
+        }
+    }
+
+    /// Brings the set for the frame up to date, growing it if it can't fit every slot.
+    fn update_set(&mut self, frame: Frame, scene_texture: (&Texture, usize)) {
+        let gui_set = &mut self.sets[usize::from(frame)];
+
+        if gui_set.capacity < self.slot_count {
+            gui_set.capacity = self
+                .slot_count
+                .next_power_of_two()
+                .clamp(DEFAULT_TEXTURE_CAPACITY, MAX_GUI_TEXTURES);
+            gui_set.set = Self::create_set(
+                &self.ctx,
+                &self.layout,
+                usize::from(frame),
+                gui_set.capacity,
+            );
+            gui_set.dirty = self.textures.values().map(|texture| texture.slot).collect();
+        }
+
+        let mut updates = Vec::with_capacity(gui_set.dirty.len() + 1);
+        updates.push(DescriptorSetUpdate {
+            binding: GUI_SET_SCENE_BINDING,
+            array_element: 0,
+            value: DescriptorValue::Texture {
+                texture: scene_texture.0,
+                array_element: scene_texture.1,
+                sampler: FONT_SAMPLER,
+                base_mip: 0,
+                mip_count: 1,
+                aspect: None,
+            },
         });
+
+        // Slots that were freed without being reused are left as is, since they won't be drawn
+        let slots: HashMap<_, _> = self
+            .textures
+            .values()
+            .map(|texture| (texture.slot, texture))
+            .collect();
+        for slot in gui_set.dirty.drain(..) {
+            let texture = match slots.get(&slot) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            updates.push(DescriptorSetUpdate {
+                binding: GUI_SET_TEXTURES_BINDING,
+                array_element: slot,
+                value: DescriptorValue::Texture {
+                    texture: &texture.texture,
+                    array_element: texture.array_element,
+                    sampler: texture.sampler,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            });
+        }
+
+        gui_set.set.update(&updates);
     }
 
-    pub fn render<'a>(&'a self, frame: Frame, screen_size: (u32, u32), pass: &mut RenderPass<'a>) {
+    pub fn render<'a>(&'a self, frame: Frame, viewport: ViewportId, pass: &mut RenderPass<'a>) {
+        let draws = match self.viewports.get(&viewport) {
+            Some(draws) => draws,
+            None => return,
+        };
+
         pass.bind_pipeline(self.font_pipeline.clone());
-        pass.bind_sets(0, vec![&self.sets[usize::from(frame)]]);
+        pass.bind_sets(0, vec![&self.sets[usize::from(frame)].set]);
         pass.bind_vertex_buffers(
             0,
             vec![VertexBind {
-                buffer: &self.vertex_buffer,
-                array_element: usize::from(frame),
-                offset: 0,
+                buffer: draws.vertices.buffer(),
+                array_element: draws.vertex_region.0,
+                offset: draws.vertex_region.1,
             }],
         );
-        pass.bind_index_buffer(&self.index_buffer, usize::from(frame), 0, IndexType::U32);
+        pass.bind_index_buffer(
+            draws.indices.buffer(),
+            draws.index_region.0,
+            draws.index_region.1,
+            IndexType::U32,
+        );
 
+        let screen_size = draws.screen_size;
         let mut constants = [GpuGuiPushConstants {
             screen_size: Vec2::new(screen_size.0 as f32, screen_size.1 as f32),
             texture_id: 0,
         }];
         pass.push_constants(bytemuck::cast_slice(&constants));
 
-        self.draw_calls.iter().for_each(|draw| {
+        draws.draw_calls.iter().for_each(|draw| {
             if draw.scissor.width == 0 || draw.scissor.height == 0 {
                 return;
             }
@@ -451,7 +548,8 @@ impl GuiRenderer {
                 pass.push_constants(bytemuck::cast_slice(&constants));
             }
 
-            pass.set_scissor(0, draw.scissor);
+            pass.set_scissor(0, draw.scissor)
+                .expect("gui scissors are clamped to the output");
             pass.draw_indexed(
                 draw.index_count,
                 1,
@@ -462,3 +560,136 @@ impl GuiRenderer {
         });
     }
 }
+
+impl ViewportDraws {
+    fn new(ctx: &Context, viewport: ViewportId) -> Self {
+        let vertices = RingBuffer::new(
+            ctx.clone(),
+            RingBufferCreateInfo {
+                size: std::mem::size_of::<egui::epaint::Vertex>() as u64 * DEFAULT_VB_SIZE,
+                buffer_usage: BufferUsage::VERTEX_BUFFER,
+                frames_in_flight: FRAMES_IN_FLIGHT,
+                queue_types: QueueTypes::MAIN,
+                debug_name: Some(format!("egui_vertex_buffer_{:x}", viewport.0.value())),
+            },
+        )
+        .unwrap();
+
+        let indices = RingBuffer::new(
+            ctx.clone(),
+            RingBufferCreateInfo {
+                size: std::mem::size_of::<u32>() as u64 * DEFAULT_IB_SIZE,
+                buffer_usage: BufferUsage::INDEX_BUFFER,
+                frames_in_flight: FRAMES_IN_FLIGHT,
+                queue_types: QueueTypes::MAIN,
+                debug_name: Some(format!("egui_index_buffer_{:x}", viewport.0.value())),
+            },
+        )
+        .unwrap();
+
+        Self {
+            vertices,
+            indices,
+            vertex_region: (0, 0),
+            index_region: (0, 0),
+            vertex_data: Vec::default(),
+            index_data: Vec::default(),
+            draw_calls: Vec::default(),
+            screen_size: (0, 0),
+        }
+    }
+
+    fn prepare(
+        &mut self,
+        token: &FrameToken,
+        output: &GuiViewportOutput,
+        textures: &HashMap<egui::TextureId, GuiTexture>,
+    ) {
+        self.screen_size = output.size;
+
+        // Prepare draw calls
+        let ppp = output.pixels_per_point;
+
+        self.draw_calls.clear();
+        self.vertex_data.clear();
+        self.index_data.clear();
+
+        for primitive in &output.primitives {
+            let mesh = match &primitive.primitive {
+                egui::epaint::Primitive::Mesh(mesh) => mesh,
+                egui::epaint::Primitive::Callback(_) => {
+                    warn!("unsupported egui callback");
+                    continue;
+                }
+            };
+
+            let texture_id = if mesh.texture_id == Gui::SCENE_TEXTURE {
+                GUI_SCENE_TEXTURE_ID
+            } else {
+                match textures.get(&mesh.texture_id) {
+                    Some(texture) => texture.slot as u32,
+                    None => continue,
+                }
+            };
+
+            let clip_min_x = ppp * primitive.clip_rect.min.x;
+            let clip_min_y = ppp * primitive.clip_rect.min.y;
+            let clip_max_x = ppp * primitive.clip_rect.max.x;
+            let clip_max_y = ppp * primitive.clip_rect.max.y;
+
+            let clip_min_x = clip_min_x.clamp(0.0, output.size.0 as f32);
+            let clip_min_y = clip_min_y.clamp(0.0, output.size.1 as f32);
+            let clip_max_x = clip_max_x.clamp(clip_min_x, output.size.0 as f32);
+            let clip_max_y = clip_max_y.clamp(clip_min_y, output.size.1 as f32);
+
+            let clip_min_x = clip_min_x.round() as u32;
+            let clip_min_y = clip_min_y.round() as u32;
+            let clip_max_x = clip_max_x.round() as u32;
+            let clip_max_y = clip_max_y.round() as u32;
+
+            self.draw_calls.push(DrawCall {
+                vertex_offset: self.vertex_data.len() as isize,
+                index_offset: self.index_data.len(),
+                index_count: mesh.indices.len(),
+                scissor: Scissor {
+                    x: clip_min_x as i32,
+                    y: clip_min_y as i32,
+                    width: clip_max_x - clip_min_x,
+                    height: clip_max_y - clip_min_y,
+                },
+                texture_id,
+            });
+
+            self.vertex_data.extend_from_slice(&mesh.vertices);
+            self.index_data.extend_from_slice(&mesh.indices);
+        }
+
+        self.vertex_region = self
+            .vertices
+            .write(token, bytemuck::cast_slice(&self.vertex_data));
+        self.index_region = self
+            .indices
+            .write(token, bytemuck::cast_slice(&self.index_data));
+    }
+}
+
+fn to_sampler(options: egui::TextureOptions) -> Sampler {
+    let filter = |filter| match filter {
+        egui::TextureFilter::Nearest => Filter::Nearest,
+        egui::TextureFilter::Linear => Filter::Linear,
+    };
+    let address_mode = match options.wrap_mode {
+        egui::TextureWrapMode::ClampToEdge => SamplerAddressMode::ClampToEdge,
+        egui::TextureWrapMode::Repeat => SamplerAddressMode::Repeat,
+        egui::TextureWrapMode::MirroredRepeat => SamplerAddressMode::MirroredRepeat,
+    };
+
+    Sampler {
+        min_filter: filter(options.minification),
+        mag_filter: filter(options.magnification),
+        address_u: address_mode,
+        address_v: address_mode,
+        address_w: address_mode,
+        ..FONT_SAMPLER
+    }
+}
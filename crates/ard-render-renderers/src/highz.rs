@@ -27,6 +27,7 @@ const HZB_INPUT_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     unnormalize_coords: true,
@@ -69,8 +70,10 @@ impl HzbRenderer {
         commands: &mut CommandBuffer<'a>,
         image: &'a HzbImage,
     ) {
-        for (i, set) in image.sets[usize::from(frame)].iter().enumerate() {
-            commands.compute_pass(&self.pipeline, Some("hzb_gen"), |pass| {
+        // Every mip reads the one before it. The dependency between them is handled by the
+        // compute pass, so the whole chain is generated in a single pass.
+        commands.compute_pass(&self.pipeline, Some("hzb_gen"), |pass| {
+            for (i, set) in image.sets[usize::from(frame)].iter().enumerate() {
                 // Determine the size of this mip level
                 let (mut src_width, mut src_height, _) = image.src_dims;
                 let dst_width = (src_width >> (i + 1)).max(1);
@@ -88,13 +91,13 @@ impl HzbRenderer {
                 // Send constants and dispatch
                 pass.bind_sets(0, vec![set]);
                 pass.push_constants(bytemuck::cast_slice(&constants));
-                ComputePassDispatch::Inline(
+                pass.dispatch(
                     dst_width.div_ceil(HZB_GEN_KERNEL_SIZE as u32 / 2).max(1),
                     dst_height.div_ceil(HZB_GEN_KERNEL_SIZE as u32 / 2).max(1),
                     1,
-                )
-            });
-        }
+                );
+            }
+        });
     }
 }
 
@@ -117,6 +120,9 @@ impl HzbImage {
                 queue_types: QueueTypes::COMPUTE | QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some(String::from("hzb_image")),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -130,6 +136,7 @@ impl HzbImage {
                     DescriptorSetCreateInfo {
                         layout: renderer.layout.clone(),
                         debug_name: Some(format!("hzb_mip_set_{i}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap();
@@ -156,6 +163,7 @@ impl HzbImage {
                                 sampler: HZB_INPUT_SAMPLER,
                                 base_mip: i - 1,
                                 mip_count: 1,
+                                aspect: None,
                             },
                         },
                         DescriptorSetUpdate {
@@ -200,6 +208,7 @@ impl HzbImage {
                 sampler: HZB_INPUT_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -217,6 +226,7 @@ impl HzbImage {
                 address_w: SamplerAddressMode::ClampToEdge,
                 anisotropy: None,
                 compare: None,
+                reduction_mode: None,
                 min_lod: NotNan::new(0.0).unwrap(),
                 max_lod: None,
                 border_color: None,
@@ -224,6 +234,7 @@ impl HzbImage {
             },
             base_mip: 0,
             mip_count: self.image.mip_count(),
+            aspect: None,
         }
     }
 
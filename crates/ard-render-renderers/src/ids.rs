@@ -25,6 +25,7 @@ impl RenderIds {
                     queue_types: QueueTypes::MAIN,
                     sharing_mode: SharingMode::Exclusive,
                     debug_name: Some("input_ids".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -38,6 +39,7 @@ impl RenderIds {
                     queue_types: QueueTypes::MAIN,
                     sharing_mode: SharingMode::Exclusive,
                     debug_name: Some("output_ids".into()),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
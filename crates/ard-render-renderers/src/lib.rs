@@ -1,12 +1,16 @@
 pub mod bins;
+pub mod culling;
 pub mod debug;
 pub mod entities;
+pub mod gpu_assert;
 pub mod gui;
 pub mod highz;
 pub mod ids;
+pub mod local_shadow;
 pub mod passes;
 pub mod pathtracer;
 pub mod raytrace;
 pub mod scene;
 pub mod shadow;
+pub mod skinning;
 pub mod state;
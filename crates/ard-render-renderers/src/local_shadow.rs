@@ -0,0 +1,376 @@
+use ard_ecs::resource::Resource;
+use ard_math::{Vec2, Vec3A};
+use ard_pal::prelude::*;
+use ard_render_base::{resource::ResourceAllocator, Frame};
+use ard_render_camera::{ubo::CameraUbo, Camera};
+use ard_render_lighting::{
+    lights::Lights,
+    local_shadows::{LocalShadowStatistics, LocalShadows},
+    shadows::LocalShadowSettings,
+};
+use ard_render_material::{
+    factory::MaterialFactory, material::MaterialResource,
+    material_instance::MaterialInstanceResource,
+};
+use ard_render_meshes::{factory::MeshFactory, mesh::MeshResource};
+use ard_render_objects::{
+    objects::RenderObjects,
+    set::{RenderableSet, RenderableSetUpdate},
+};
+use ard_render_si::bindings::Layouts;
+use ard_render_textures::{factory::TextureFactory, texture::TextureResource};
+use ard_transform::Model;
+
+use crate::{
+    bins::{DepthBias, DrawBins, RenderArgs},
+    culling::{CullingPass, CullingStatsBuffer},
+    ids::RenderIds,
+    passes::{shadow::ShadowPassSets, SHADOW_ALPHA_CUTOFF_PASS_ID, SHADOW_OPAQUE_PASS_ID},
+};
+
+/// Local shadows are rendered with a perspective projection, which needs more depth precision
+/// than the orthographic cascades.
+pub const LOCAL_SHADOW_ATLAS_FORMAT: Format = Format::D32Sfloat;
+
+/// Point and spot light shadow renderer. Every shadowed light gets tiles in a single atlas.
+#[derive(Resource)]
+pub struct LocalShadowsRenderer {
+    ctx: Context,
+    ids: RenderIds,
+    set: RenderableSet,
+    bins: DrawBins,
+    atlas: Texture,
+    shadows: LocalShadows,
+    views: Vec<LocalShadowRenderData>,
+    depth_bias: DepthBias,
+}
+
+pub struct LocalShadowRenderArgs<'a, 'b> {
+    pub commands: &'b mut CommandBuffer<'a>,
+    pub mesh_factory: &'a MeshFactory,
+    pub material_factory: &'a MaterialFactory,
+    pub texture_factory: &'a TextureFactory,
+    pub meshes: &'a ResourceAllocator<MeshResource>,
+    pub materials: &'a ResourceAllocator<MaterialResource>,
+}
+
+struct LocalShadowRenderData {
+    camera: CameraUbo,
+    sets: ShadowPassSets,
+}
+
+impl LocalShadowsRenderer {
+    pub fn new(ctx: &Context, settings: &LocalShadowSettings) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            ids: RenderIds::new(ctx),
+            set: RenderableSet::default(),
+            bins: DrawBins::new(),
+            atlas: Self::create_atlas(ctx, settings.atlas_size),
+            shadows: LocalShadows::new(ctx, settings),
+            views: Vec::default(),
+            depth_bias: DepthBias {
+                constant: settings.depth_bias,
+                slope: settings.slope_bias,
+            },
+        }
+    }
+
+    #[inline]
+    pub fn atlas(&self) -> &Texture {
+        &self.atlas
+    }
+
+    #[inline]
+    pub fn local_shadows(&self) -> &Buffer {
+        self.shadows.buffer()
+    }
+
+    #[inline]
+    pub fn statistics(&self) -> LocalShadowStatistics {
+        self.shadows.statistics()
+    }
+
+    /// Applies new settings. Returns `true` if the atlas was recreated, in which case it must be
+    /// rebound.
+    pub fn update_settings(&mut self, ctx: &Context, settings: &LocalShadowSettings) -> bool {
+        self.depth_bias = DepthBias {
+            constant: settings.depth_bias,
+            slope: settings.slope_bias,
+        };
+
+        let atlas_size = settings.atlas_size.max(1).next_power_of_two();
+        if self.atlas.dims().0 == atlas_size {
+            return false;
+        }
+
+        self.atlas = Self::create_atlas(ctx, atlas_size);
+        true
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload(
+        &mut self,
+        frame: Frame,
+        objects: &RenderObjects,
+        textures: &ResourceAllocator<TextureResource>,
+        meshes: &ResourceAllocator<MeshResource>,
+        materials: &ResourceAllocator<MaterialResource>,
+        material_instances: &ResourceAllocator<MaterialInstanceResource>,
+        view_location: Vec3A,
+    ) {
+        puffin::profile_function!();
+
+        RenderableSetUpdate::new(&mut self.set)
+            .with_opaque()
+            .with_alpha_cutout()
+            .update(
+                view_location,
+                objects,
+                meshes,
+                false,
+                |_| true,
+                |_| true,
+                |_| true,
+            );
+
+        let _buffer_expanded = self.ids.upload(frame, objects.static_dirty(), &self.set);
+
+        self.bins.gen_bins(
+            frame,
+            self.set.groups()[self.set.static_group_ranges().opaque.clone()].iter(),
+            self.set.groups()[self.set.static_group_ranges().alpha_cutout.clone()].iter(),
+            self.set.groups()[self.set.dynamic_group_ranges().opaque.clone()].iter(),
+            self.set.groups()[self.set.dynamic_group_ranges().alpha_cutout.clone()].iter(),
+            std::iter::empty(),
+            textures,
+            meshes,
+            materials,
+            material_instances,
+        );
+    }
+
+    /// Picks the lights to shadow this frame and writes their views. Must be called before
+    /// [`update_bindings`](Self::update_bindings) so every view has a set to bind.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_views(
+        &mut self,
+        frame: Frame,
+        layouts: &Layouts,
+        settings: &LocalShadowSettings,
+        lights: &mut Lights,
+        camera: &Camera,
+        camera_model: Model,
+        screen_dims: (u32, u32),
+    ) {
+        puffin::profile_function!();
+
+        self.shadows
+            .update(frame, settings, lights, camera, camera_model, screen_dims);
+
+        // Views are kept around once created, since the number of shadowed lights is usually
+        // stable between frames
+        let ctx = &self.ctx;
+        let view_count = self.shadows.views().len();
+        if self.views.len() < view_count {
+            self.views
+                .resize_with(view_count, || LocalShadowRenderData::new(ctx, layouts));
+        }
+
+        for (view, data) in self.shadows.views().iter().zip(self.views.iter_mut()) {
+            data.camera.update_raw(frame, &view.camera, 0);
+        }
+    }
+
+    pub fn update_bindings(
+        &mut self,
+        frame: Frame,
+        objects: &RenderObjects,
+        culling_stats: &CullingStatsBuffer,
+    ) {
+        let view_count = self.shadows.views().len();
+        self.views[..view_count].iter_mut().for_each(|view| {
+            view.sets
+                .update_object_data_bindings(frame, objects.object_data(), &self.ids);
+            view.sets
+                .update_culling_stats_binding(frame, culling_stats, CullingPass::LocalShadows);
+        });
+    }
+
+    /// Renders every view into its tile of the atlas.
+    pub fn render<'a>(&'a self, frame: Frame, args: LocalShadowRenderArgs<'a, '_>) {
+        let views = self.shadows.views();
+
+        // The atlas is cleared even if there's nothing to render so it's always initialized
+        if views.is_empty() {
+            args.commands.render_pass(
+                self.pass_descriptor(LoadOp::Clear(ClearColor::D32S32(1.0, 0))),
+                Some("render_local_shadows"),
+                |_| {},
+            );
+            return;
+        }
+
+        for (i, (view, data)) in views.iter().zip(self.views.iter()).enumerate() {
+            let load_op = if i == 0 {
+                LoadOp::Clear(ClearColor::D32S32(1.0, 0))
+            } else {
+                LoadOp::Load
+            };
+            let tile = view.tile;
+            let render_area = Vec2::splat(tile.size as f32);
+
+            args.commands.render_pass(
+                self.pass_descriptor(load_op),
+                Some("render_local_shadows"),
+                |pass| {
+                    pass.set_viewport(Viewport {
+                        x: tile.x as f32,
+                        y: tile.y as f32,
+                        width: tile.size as f32,
+                        height: tile.size as f32,
+                        min_depth: 0.0,
+                        max_depth: 1.0,
+                    })
+                    .unwrap();
+
+                    pass.set_scissor(
+                        0,
+                        Scissor {
+                            x: tile.x as i32,
+                            y: tile.y as i32,
+                            width: tile.size,
+                            height: tile.size,
+                        },
+                    )
+                    .unwrap();
+
+                    self.bins.render_static_opaque_bins(RenderArgs {
+                        ctx: &self.ctx,
+                        pass_id: SHADOW_OPAQUE_PASS_ID,
+                        frame,
+                        render_area,
+                        lock_culling: false,
+                        camera: &data.camera,
+                        global_set: data.sets.get_set(frame),
+                        pass,
+                        mesh_factory: args.mesh_factory,
+                        material_factory: args.material_factory,
+                        texture_factory: args.texture_factory,
+                        meshes: args.meshes,
+                        materials: args.materials,
+                        depth_bias: Some(self.depth_bias),
+                    });
+
+                    self.bins.render_dynamic_opaque_bins(RenderArgs {
+                        ctx: &self.ctx,
+                        pass_id: SHADOW_OPAQUE_PASS_ID,
+                        frame,
+                        render_area,
+                        lock_culling: false,
+                        camera: &data.camera,
+                        global_set: data.sets.get_set(frame),
+                        pass,
+                        mesh_factory: args.mesh_factory,
+                        material_factory: args.material_factory,
+                        texture_factory: args.texture_factory,
+                        meshes: args.meshes,
+                        materials: args.materials,
+                        depth_bias: Some(self.depth_bias),
+                    });
+
+                    self.bins.render_static_alpha_cutoff_bins(RenderArgs {
+                        ctx: &self.ctx,
+                        pass_id: SHADOW_ALPHA_CUTOFF_PASS_ID,
+                        frame,
+                        render_area,
+                        lock_culling: false,
+                        camera: &data.camera,
+                        global_set: data.sets.get_set(frame),
+                        pass,
+                        mesh_factory: args.mesh_factory,
+                        material_factory: args.material_factory,
+                        texture_factory: args.texture_factory,
+                        meshes: args.meshes,
+                        materials: args.materials,
+                        depth_bias: Some(self.depth_bias),
+                    });
+
+                    self.bins.render_dynamic_alpha_cutoff_bins(RenderArgs {
+                        ctx: &self.ctx,
+                        pass_id: SHADOW_ALPHA_CUTOFF_PASS_ID,
+                        frame,
+                        render_area,
+                        lock_culling: false,
+                        camera: &data.camera,
+                        global_set: data.sets.get_set(frame),
+                        pass,
+                        mesh_factory: args.mesh_factory,
+                        material_factory: args.material_factory,
+                        texture_factory: args.texture_factory,
+                        meshes: args.meshes,
+                        materials: args.materials,
+                        depth_bias: Some(self.depth_bias),
+                    });
+                },
+            );
+        }
+    }
+
+    fn pass_descriptor(&self, load_op: LoadOp) -> RenderPassDescriptor<'_> {
+        RenderPassDescriptor {
+            color_attachments: Vec::default(),
+            color_resolve_attachments: Vec::default(),
+            depth_stencil_attachment: Some(DepthStencilAttachment {
+                dst: DepthStencilAttachmentDestination::Texture {
+                    texture: &self.atlas,
+                    array_element: 0,
+                    mip_level: 0,
+                },
+                load_op,
+                store_op: StoreOp::Store,
+                samples: MultiSamples::Count1,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
+            }),
+            depth_stencil_resolve_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
+        }
+    }
+
+    fn create_atlas(ctx: &Context, size: u32) -> Texture {
+        let size = size.max(1).next_power_of_two();
+        Texture::new(
+            ctx.clone(),
+            TextureCreateInfo {
+                format: LOCAL_SHADOW_ATLAS_FORMAT,
+                ty: TextureType::Type2D,
+                width: size,
+                height: size,
+                depth: 1,
+                array_elements: 1,
+                mip_levels: 1,
+                sample_count: MultiSamples::Count1,
+                texture_usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT | TextureUsage::SAMPLED,
+                memory_usage: MemoryUsage::GpuOnly,
+                queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
+                sharing_mode: SharingMode::Concurrent,
+                debug_name: Some("local_shadow_atlas".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
+            },
+        )
+        .unwrap()
+    }
+}
+
+impl LocalShadowRenderData {
+    fn new(ctx: &Context, layouts: &Layouts) -> Self {
+        Self {
+            camera: CameraUbo::new(ctx, false, layouts),
+            sets: ShadowPassSets::new(ctx, layouts),
+        }
+    }
+}
@@ -9,6 +9,7 @@ use ard_render_si::{bindings::*, consts::*};
 
 use crate::{
     ids::RenderIds,
+    local_shadow::LocalShadowsRenderer,
     shadow::{SunShadowsRenderer, SHADOW_SAMPLER},
 };
 
@@ -25,6 +26,7 @@ impl ColorPassSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.color_pass.clone(),
                         debug_name: Some(format!("color_pass_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -48,6 +50,7 @@ impl ColorPassSets {
                         sampler: SHADOW_SAMPLER,
                         base_mip: 0,
                         mip_count: 1,
+                        aspect: None,
                     },
                 }
             } else {
@@ -65,6 +68,36 @@ impl ColorPassSets {
         set.update(&shadow_cascades_update);
     }
 
+    pub fn update_local_shadow_bindings(
+        &mut self,
+        frame: Frame,
+        local_shadows: &LocalShadowsRenderer,
+    ) {
+        let set = &mut self.sets[usize::from(frame)];
+        set.update(&[
+            DescriptorSetUpdate {
+                binding: COLOR_PASS_SET_LOCAL_SHADOW_ATLAS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::Texture {
+                    texture: local_shadows.atlas(),
+                    array_element: 0,
+                    sampler: SHADOW_SAMPLER,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: COLOR_PASS_SET_LOCAL_SHADOWS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: local_shadows.local_shadows(),
+                    array_element: usize::from(frame),
+                },
+            },
+        ]);
+    }
+
     pub fn update_sky_box_bindings(&mut self, frame: Frame, proc_skybox: &ProceduralSkyBox) {
         let set = &mut self.sets[usize::from(frame)];
         set.update(&[
@@ -99,6 +132,7 @@ impl ColorPassSets {
                     sampler: DI_MAP_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -115,6 +149,7 @@ impl ColorPassSets {
                 sampler: AO_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -2,7 +2,11 @@ use ard_pal::prelude::*;
 use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
 use ard_render_si::bindings::*;
 
-use crate::{highz::HzbImage, ids::RenderIds};
+use crate::{
+    culling::{CullingPass, CullingStatsBuffer},
+    highz::HzbImage,
+    ids::RenderIds,
+};
 
 pub struct DepthPrepassSets {
     sets: [DescriptorSet; FRAMES_IN_FLIGHT],
@@ -17,6 +21,7 @@ impl DepthPrepassSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.depth_prepass.clone(),
                         debug_name: Some(format!("depth_prepass_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -68,6 +73,19 @@ impl DepthPrepassSets {
         ]);
     }
 
+    pub fn update_culling_stats_binding(
+        &mut self,
+        frame: Frame,
+        culling_stats: &CullingStatsBuffer,
+    ) {
+        let set = &mut self.sets[usize::from(frame)];
+        set.update(&[DescriptorSetUpdate {
+            binding: DEPTH_PREPASS_SET_CULLING_STATS_BINDING,
+            array_element: 0,
+            value: culling_stats.descriptor_value(frame, CullingPass::DepthPrepass),
+        }]);
+    }
+
     #[inline(always)]
     pub fn get_set(&self, frame: Frame) -> &DescriptorSet {
         &self.sets[usize::from(frame)]
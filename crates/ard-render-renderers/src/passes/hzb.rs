@@ -17,6 +17,7 @@ impl HzbPassSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.hzb_pass.clone(),
                         debug_name: Some(format!("hzb_pass_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -1,4 +1,5 @@
 use ard_math::Vec2;
+use ard_pal::prelude::DynamicStates;
 use ard_render_material::factory::{MaterialFactory, PassDefinition, PassId, RtPassDefinition};
 use ard_render_si::{bindings::Layouts, types::*};
 
@@ -60,6 +61,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 0,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -77,6 +79,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 0,
+                dynamic_states: DynamicStates::DEPTH_BIAS,
             },
         )
         .unwrap();
@@ -94,6 +97,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 0,
+                dynamic_states: DynamicStates::DEPTH_BIAS,
             },
         )
         .unwrap();
@@ -111,6 +115,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 0,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -128,6 +133,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 0,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -145,6 +151,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 1,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -162,6 +169,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 1,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -179,6 +187,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 1,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -196,6 +205,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 4,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -213,6 +223,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 4,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -230,6 +241,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 4,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -247,6 +259,7 @@ pub fn define_passes(factory: &mut MaterialFactory, layouts: &Layouts) {
                 ],
                 has_depth_stencil_attachment: true,
                 color_attachment_count: 1,
+                dynamic_states: DynamicStates::empty(),
             },
         )
         .unwrap();
@@ -19,6 +19,7 @@ impl PathTracerPassSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.path_tracer_pass.clone(),
                         debug_name: Some(format!("path_tracer_pass_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
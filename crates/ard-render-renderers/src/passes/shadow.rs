@@ -2,7 +2,10 @@ use ard_pal::prelude::*;
 use ard_render_base::{Frame, FRAMES_IN_FLIGHT};
 use ard_render_si::bindings::*;
 
-use crate::ids::RenderIds;
+use crate::{
+    culling::{CullingPass, CullingStatsBuffer},
+    ids::RenderIds,
+};
 
 pub struct ShadowPassSets {
     sets: [DescriptorSet; FRAMES_IN_FLIGHT],
@@ -17,6 +20,7 @@ impl ShadowPassSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.shadow_pass.clone(),
                         debug_name: Some(format!("shadow_pass_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -59,6 +63,20 @@ impl ShadowPassSets {
         ]);
     }
 
+    pub fn update_culling_stats_binding(
+        &mut self,
+        frame: Frame,
+        culling_stats: &CullingStatsBuffer,
+        pass: CullingPass,
+    ) {
+        let set = &mut self.sets[usize::from(frame)];
+        set.update(&[DescriptorSetUpdate {
+            binding: SHADOW_PASS_SET_CULLING_STATS_BINDING,
+            array_element: 0,
+            value: culling_stats.descriptor_value(frame, pass),
+        }]);
+    }
+
     #[inline(always)]
     pub fn get_set(&self, frame: Frame) -> &DescriptorSet {
         &self.sets[usize::from(frame)]
@@ -10,6 +10,7 @@ use ard_render_si::{bindings::*, consts::*};
 use crate::{
     highz::HzbImage,
     ids::RenderIds,
+    local_shadow::LocalShadowsRenderer,
     shadow::{SunShadowsRenderer, SHADOW_SAMPLER},
 };
 
@@ -26,6 +27,7 @@ impl TransparentPassSets {
                     DescriptorSetCreateInfo {
                         layout: layouts.transparent_pass.clone(),
                         debug_name: Some(format!("transparent_pass_set_{frame_idx}")),
+                        variable_count: None,
                     },
                 )
                 .unwrap()
@@ -49,6 +51,7 @@ impl TransparentPassSets {
                         sampler: SHADOW_SAMPLER,
                         base_mip: 0,
                         mip_count: 1,
+                        aspect: None,
                     },
                 }
             } else {
@@ -66,6 +69,36 @@ impl TransparentPassSets {
         set.update(&shadow_cascades_update);
     }
 
+    pub fn update_local_shadow_bindings(
+        &mut self,
+        frame: Frame,
+        local_shadows: &LocalShadowsRenderer,
+    ) {
+        let set = &mut self.sets[usize::from(frame)];
+        set.update(&[
+            DescriptorSetUpdate {
+                binding: TRANSPARENT_PASS_SET_LOCAL_SHADOW_ATLAS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::Texture {
+                    texture: local_shadows.atlas(),
+                    array_element: 0,
+                    sampler: SHADOW_SAMPLER,
+                    base_mip: 0,
+                    mip_count: 1,
+                    aspect: None,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: TRANSPARENT_PASS_SET_LOCAL_SHADOWS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: local_shadows.local_shadows(),
+                    array_element: usize::from(frame),
+                },
+            },
+        ]);
+    }
+
     pub fn update_sky_box_bindings(&mut self, frame: Frame, proc_skybox: &ProceduralSkyBox) {
         let set = &mut self.sets[usize::from(frame)];
         set.update(&[
@@ -100,6 +133,7 @@ impl TransparentPassSets {
                     sampler: DI_MAP_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             },
         ]);
@@ -116,6 +150,7 @@ impl TransparentPassSets {
                 sampler: AO_SAMPLER,
                 base_mip: 0,
                 mip_count: 1,
+                aspect: None,
             },
         }]);
     }
@@ -177,9 +177,9 @@ impl PathTracer {
                     self.image.dims().2,
                 ),
                 shader_binding_table: self.pipeline.sbt(),
-                raygen_offset: self.pipeline.raygen_offset(),
-                miss_offset: self.pipeline.miss_offset(),
-                hit_range: self.pipeline.hit_range(),
+                raygen: self.pipeline.raygen_region(),
+                miss: self.pipeline.miss_region(),
+                hit: self.pipeline.hit_region(),
             }
         });
     }
@@ -201,6 +201,9 @@ impl PathTracer {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("pathtracer_tex".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
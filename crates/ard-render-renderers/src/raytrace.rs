@@ -18,6 +18,10 @@ pub struct RaytracedRenderer {
     tlas: TopLevelAccelerationStructure,
     set: RenderableSet,
     capacity: usize,
+    /// Instance count the TLAS was last built with. `None` if the TLAS hasn't been built yet.
+    built_instance_count: Option<usize>,
+    /// Indicates that the TLAS only needs to be refit this frame instead of rebuilt.
+    refit: bool,
 }
 
 impl RaytracedRenderer {
@@ -29,11 +33,13 @@ impl RaytracedRenderer {
             objects: Self::create_object_buffer(ctx, DEFAULT_OBJECTS_CAP),
             scratch_buffer: Self::create_scratch_buffer(
                 ctx,
-                tlas.scratch_buffer_size().max(DEFAULT_SCRATCH_SIZE),
+                Self::scratch_size(&tlas).max(DEFAULT_SCRATCH_SIZE),
             ),
             tlas,
             capacity: DEFAULT_OBJECTS_CAP,
             set: RenderableSet::default(),
+            built_instance_count: None,
+            refit: false,
         }
     }
 
@@ -94,10 +100,17 @@ impl RaytracedRenderer {
             self.objects = Self::create_object_buffer(&self.ctx, new_cap);
             self.tlas = Self::create_tlas(&self.ctx, new_cap);
             self.scratch_buffer =
-                Self::create_scratch_buffer(&self.ctx, self.tlas.scratch_buffer_size());
+                Self::create_scratch_buffer(&self.ctx, Self::scratch_size(&self.tlas));
             self.capacity = new_cap;
+            self.built_instance_count = None;
         }
 
+        // Instances can move without a full rebuild, but refits require the instance count to
+        // match the last build
+        let instance_count = self.set.ids().len();
+        self.refit = self.built_instance_count == Some(instance_count);
+        self.built_instance_count = Some(instance_count);
+
         // Base address of the object data
         let base = objects.object_data().device_ref(0);
 
@@ -113,14 +126,32 @@ impl RaytracedRenderer {
     }
 
     pub fn build<'a>(&'a self, commands: &mut CommandBuffer<'a>, frame: Frame) {
-        commands.build_top_level_acceleration_structure(
-            &self.tlas,
-            self.set.ids().len(),
-            &self.scratch_buffer,
-            0,
-            &self.objects,
-            usize::from(frame),
-        );
+        if self.refit {
+            commands.update_top_level_acceleration_structure(
+                &self.tlas,
+                self.set.ids().len(),
+                &self.scratch_buffer,
+                0,
+                &self.objects,
+                usize::from(frame),
+            );
+        } else {
+            commands.build_top_level_acceleration_structure(
+                &self.tlas,
+                self.set.ids().len(),
+                &self.scratch_buffer,
+                0,
+                &self.objects,
+                usize::from(frame),
+            );
+        }
+    }
+
+    /// Scratch memory needed for both full builds and refits of `tlas`.
+    #[inline(always)]
+    fn scratch_size(tlas: &TopLevelAccelerationStructure) -> u64 {
+        tlas.scratch_buffer_size()
+            .max(tlas.update_scratch_buffer_size())
     }
 
     fn create_scratch_buffer(ctx: &Context, size: u64) -> Buffer {
@@ -134,6 +165,7 @@ impl RaytracedRenderer {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("tlas_scratch".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -151,6 +183,7 @@ impl RaytracedRenderer {
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("rt_object_ptrs".into()),
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
@@ -160,7 +193,8 @@ impl RaytracedRenderer {
         TopLevelAccelerationStructure::new(
             ctx.clone(),
             TopLevelAccelerationStructureCreateInfo {
-                flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE,
+                flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE
+                    | BuildAccelerationStructureFlags::ALLOW_UPDATE,
                 capacity: cap,
                 queue_types: QueueTypes::MAIN,
                 sharing_mode: SharingMode::Exclusive,
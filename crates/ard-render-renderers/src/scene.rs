@@ -17,6 +17,7 @@ use ard_render_textures::{factory::TextureFactory, texture::TextureResource};
 
 use crate::{
     bins::{DrawBins, RenderArgs},
+    culling::CullingStatsBuffer,
     highz::HzbImage,
     ids::RenderIds,
     passes::{
@@ -135,7 +136,13 @@ impl SceneRenderer {
         );
     }
 
-    pub fn update_bindings(&mut self, frame: Frame, objects: &RenderObjects, hzb_image: &HzbImage) {
+    pub fn update_bindings(
+        &mut self,
+        frame: Frame,
+        objects: &RenderObjects,
+        hzb_image: &HzbImage,
+        culling_stats: &CullingStatsBuffer,
+    ) {
         self.hzb_pass_sets
             .update_object_data_bindings(frame, objects.object_data(), &self.ids);
 
@@ -145,6 +152,8 @@ impl SceneRenderer {
             &self.ids,
         );
         self.depth_prepass_sets.update_hzb_binding(frame, hzb_image);
+        self.depth_prepass_sets
+            .update_culling_stats_binding(frame, culling_stats);
 
         self.color_sets
             .update_object_data_bindings(frame, objects.object_data(), &self.ids);
@@ -170,6 +179,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
     }
 
@@ -189,6 +199,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_dynamic_opaque_bins(RenderArgs {
@@ -205,6 +216,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_static_alpha_cutoff_bins(RenderArgs {
@@ -221,6 +233,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_dynamic_alpha_cutoff_bins(RenderArgs {
@@ -237,6 +250,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
     }
 
@@ -255,6 +269,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_dynamic_opaque_bins(RenderArgs {
@@ -271,6 +286,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_static_alpha_cutoff_bins(RenderArgs {
@@ -287,6 +303,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_dynamic_alpha_cutoff_bins(RenderArgs {
@@ -303,6 +320,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
 
         self.bins.render_transparent_bins(RenderArgs {
@@ -319,6 +337,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
     }
 
@@ -337,6 +356,7 @@ impl SceneRenderer {
             texture_factory: args.texture_factory,
             meshes: args.meshes,
             materials: args.materials,
+            depth_bias: None,
         });
     }
 }
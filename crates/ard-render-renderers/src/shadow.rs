@@ -1,9 +1,9 @@
 use ard_ecs::resource::Resource;
-use ard_math::{Vec2, Vec3, Vec3A};
+use ard_math::{Mat4, Vec2, Vec3, Vec3A};
 use ard_pal::prelude::*;
 use ard_render_base::{resource::ResourceAllocator, Frame, FRAMES_IN_FLIGHT};
 use ard_render_camera::{ubo::CameraUbo, Camera};
-use ard_render_lighting::shadows::{ShadowCascadeSettings, SunShadowsUbo};
+use ard_render_lighting::shadows::{ShadowSettings, SunShadowsUbo};
 use ard_render_material::{
     factory::MaterialFactory, material::MaterialResource,
     material_instance::MaterialInstanceResource,
@@ -19,7 +19,8 @@ use ard_transform::Model;
 use ordered_float::NotNan;
 
 use crate::{
-    bins::{DrawBins, RenderArgs},
+    bins::{DepthBias, DrawBins, RenderArgs},
+    culling::{CullingPass, CullingStatsBuffer},
     ids::RenderIds,
     passes::{shadow::ShadowPassSets, SHADOW_ALPHA_CUTOFF_PASS_ID, SHADOW_OPAQUE_PASS_ID},
 };
@@ -37,6 +38,7 @@ pub(crate) const SHADOW_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToBorder,
     anisotropy: None,
     compare: Some(CompareOp::Less),
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: Some(unsafe { NotNan::new_unchecked(0.0) }),
     border_color: Some(BorderColor::FloatOpaqueWhite),
@@ -69,6 +71,7 @@ struct ShadowCascadeRenderData {
     image: Texture,
     camera: CameraUbo,
     sets: ShadowPassSets,
+    depth_bias: DepthBias,
 }
 
 impl SunShadowsRenderer {
@@ -89,6 +92,9 @@ impl SunShadowsRenderer {
                 queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: Some("empty_shadow_map".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -108,8 +114,12 @@ impl SunShadowsRenderer {
                     load_op: LoadOp::Clear(ClearColor::D32S32(1.0, 0)),
                     store_op: StoreOp::Store,
                     samples: MultiSamples::Count1,
+                    stencil_load_op: LoadOp::DontCare,
+                    stencil_store_op: StoreOp::DontCare,
                 }),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             None,
             |_| {},
@@ -155,6 +165,17 @@ impl SunShadowsRenderer {
         self.cascades.get(i).map(|cascade| &cascade.image)
     }
 
+    /// View projection matrix of a cascade as of the last call to `update_cascade_views`.
+    #[inline]
+    pub fn cascade_view_proj(&self, frame: Frame, i: usize) -> Option<Mat4> {
+        if i >= self.cascades.len() {
+            return None;
+        }
+        self.ubo[usize::from(frame)]
+            .camera(i)
+            .map(|camera| camera.vp)
+    }
+
     pub fn upload(
         &mut self,
         frame: Frame,
@@ -203,11 +224,12 @@ impl SunShadowsRenderer {
         &mut self,
         ctx: &Context,
         layouts: &Layouts,
-        cascades: &[ShadowCascadeSettings],
+        settings: &ShadowSettings,
     ) -> bool {
+        let cascades = settings.active_cascades();
         let mut needs_resize = self.cascades.len() != cascades.len();
 
-        let mut i = cascades.len();
+        let mut i = self.cascades.len();
         self.cascades.resize_with(cascades.len(), || {
             let new_cascade = ShadowCascadeRenderData::new(ctx, layouts, cascades[i].resolution);
             i += 1;
@@ -218,19 +240,38 @@ impl SunShadowsRenderer {
             if orig_cascade.resize(ctx, new_cascade.resolution) {
                 needs_resize = true;
             }
+
+            orig_cascade.depth_bias = DepthBias {
+                constant: new_cascade.depth_bias,
+                slope: new_cascade.slope_bias,
+            };
         }
 
         needs_resize
     }
 
-    pub fn update_bindings(&mut self, frame: Frame, objects: &RenderObjects) {
-        self.cascades.iter_mut().for_each(|cascade| {
-            cascade
-                .sets
-                .update_object_data_bindings(frame, objects.object_data(), &self.ids);
-        });
+    pub fn update_bindings(
+        &mut self,
+        frame: Frame,
+        objects: &RenderObjects,
+        culling_stats: &CullingStatsBuffer,
+    ) {
+        self.cascades
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, cascade)| {
+                cascade
+                    .sets
+                    .update_object_data_bindings(frame, objects.object_data(), &self.ids);
+                cascade.sets.update_culling_stats_binding(
+                    frame,
+                    culling_stats,
+                    CullingPass::ShadowCascade(i),
+                );
+            });
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_cascade_views(
         &mut self,
         frame: Frame,
@@ -238,10 +279,12 @@ impl SunShadowsRenderer {
         camera_model: Model,
         screen_dims: (u32, u32),
         light_dir: Vec3,
-        cascades: &[ShadowCascadeSettings],
+        settings: &ShadowSettings,
+        debug_cascades: bool,
     ) {
         self.ubo[usize::from(frame)].update(
-            cascades,
+            settings,
+            debug_cascades,
             light_dir,
             camera,
             camera_model,
@@ -276,8 +319,12 @@ impl SunShadowsRenderer {
                     load_op: LoadOp::Clear(ClearColor::D32S32(1.0, 0)),
                     store_op: StoreOp::Store,
                     samples: MultiSamples::Count1,
+                    stencil_load_op: LoadOp::DontCare,
+                    stencil_store_op: StoreOp::DontCare,
                 }),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("render_shadows"),
             |pass| {
@@ -296,6 +343,7 @@ impl SunShadowsRenderer {
                     texture_factory: args.texture_factory,
                     meshes: args.meshes,
                     materials: args.materials,
+                    depth_bias: Some(cascade.depth_bias),
                 });
 
                 self.bins.render_dynamic_opaque_bins(RenderArgs {
@@ -312,6 +360,7 @@ impl SunShadowsRenderer {
                     texture_factory: args.texture_factory,
                     meshes: args.meshes,
                     materials: args.materials,
+                    depth_bias: Some(cascade.depth_bias),
                 });
 
                 self.bins.render_static_alpha_cutoff_bins(RenderArgs {
@@ -328,6 +377,7 @@ impl SunShadowsRenderer {
                     texture_factory: args.texture_factory,
                     meshes: args.meshes,
                     materials: args.materials,
+                    depth_bias: Some(cascade.depth_bias),
                 });
 
                 self.bins.render_dynamic_alpha_cutoff_bins(RenderArgs {
@@ -344,6 +394,7 @@ impl SunShadowsRenderer {
                     texture_factory: args.texture_factory,
                     meshes: args.meshes,
                     materials: args.materials,
+                    depth_bias: Some(cascade.depth_bias),
                 });
             },
         );
@@ -356,6 +407,7 @@ impl ShadowCascadeRenderData {
             image: Self::create_image(ctx, resolution),
             camera: CameraUbo::new(ctx, false, layouts),
             sets: ShadowPassSets::new(ctx, layouts),
+            depth_bias: DepthBias::default(),
         }
     }
 
@@ -385,6 +437,9 @@ impl ShadowCascadeRenderData {
                 queue_types: QueueTypes::MAIN | QueueTypes::COMPUTE,
                 sharing_mode: SharingMode::Concurrent,
                 debug_name: Some("shadow_cascade".into()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap()
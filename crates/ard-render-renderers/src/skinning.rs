@@ -0,0 +1,331 @@
+use std::{ops::DerefMut, sync::atomic::Ordering};
+
+use ard_animation::animator::JointPalette;
+use ard_core::core::Disabled;
+use ard_ecs::prelude::Entity;
+use ard_formats::vertex::{VertexAttribute, VertexLayout};
+use ard_math::Mat4;
+use ard_pal::prelude::*;
+use ard_render_base::{resource::ResourceAllocator, Frame, FRAMES_IN_FLIGHT};
+use ard_render_meshes::{
+    factory::MeshFactory,
+    mesh::MeshResource,
+    skinned::{SkinnedMesh, SkinnedMeshResource},
+};
+use ard_render_si::{bindings::*, consts::*, types::GpuSkinningPushConstants};
+
+/// Skinned meshes captured from the primary ECS.
+#[derive(Default)]
+pub struct SkinnedMeshes {
+    instances: Vec<CapturedSkin>,
+    /// Joint matrices of every instance.
+    palettes: Vec<Mat4>,
+}
+
+pub struct CapturedSkin {
+    pub skinned: SkinnedMesh,
+    /// First joint matrix of the instance in the palette.
+    pub palette_offset: u32,
+    pub joint_count: u32,
+}
+
+/// Deforms the vertices of skinned meshes using their joint palettes, and rebuilds the BLAS' of
+/// every skinned instance so ray tracing sees the deformed geometry.
+pub struct SkinningRenderer {
+    ctx: Context,
+    pipeline: ComputePipeline,
+    palettes: [Buffer; FRAMES_IN_FLIGHT],
+    sets: [DescriptorSet; FRAMES_IN_FLIGHT],
+    /// Scratch memory for rebuilding BLAS'.
+    scratch: Option<Buffer>,
+    /// Instances to skin this frame.
+    dispatches: Vec<SkinDispatch>,
+}
+
+struct SkinDispatch {
+    skinned: SkinnedMesh,
+    constants: GpuSkinningPushConstants,
+}
+
+const DEFAULT_PALETTE_CAP: usize = 256;
+
+impl SkinnedMeshes {
+    #[inline(always)]
+    pub fn instances(&self) -> &[CapturedSkin] {
+        &self.instances
+    }
+
+    #[inline(always)]
+    pub fn palettes(&self) -> &[Mat4] {
+        &self.palettes
+    }
+
+    pub fn update<'a>(
+        &mut self,
+        skinned: impl Iterator<
+            Item = (
+                Entity,
+                (&'a SkinnedMesh, Option<&'a JointPalette>),
+                Option<&'a Disabled>,
+            ),
+        >,
+    ) {
+        self.instances.clear();
+        self.palettes.clear();
+
+        for (_, (skinned, palette), disabled) in skinned {
+            if disabled.is_some() {
+                continue;
+            }
+
+            let palette = palette.map(|palette| palette.0.as_slice()).unwrap_or(&[]);
+            self.instances.push(CapturedSkin {
+                skinned: skinned.clone(),
+                palette_offset: self.palettes.len() as u32,
+                joint_count: palette.len() as u32,
+            });
+            self.palettes.extend_from_slice(palette);
+        }
+    }
+}
+
+impl SkinningRenderer {
+    pub fn new(ctx: &Context, layouts: &Layouts) -> Self {
+        let module = Shader::new(
+            ctx.clone(),
+            ShaderCreateInfo {
+                code: include_bytes!(concat!(env!("OUT_DIR"), "./skinning.comp.spv")),
+                debug_name: Some("skinning_shader".into()),
+            },
+        )
+        .unwrap();
+
+        let pipeline = ComputePipeline::new(
+            ctx.clone(),
+            ComputePipelineCreateInfo {
+                layouts: vec![layouts.skinning.clone()],
+                module,
+                work_group_size: (SKINNING_GROUP_SIZE, 1, 1),
+                push_constants_size: Some(std::mem::size_of::<GpuSkinningPushConstants>() as u32),
+                debug_name: Some("skinning_pipeline".into()),
+            },
+        )
+        .unwrap();
+
+        let palettes = std::array::from_fn(|frame_idx| {
+            Buffer::new(
+                ctx.clone(),
+                BufferCreateInfo {
+                    size: (std::mem::size_of::<Mat4>() * DEFAULT_PALETTE_CAP) as u64,
+                    array_elements: 1,
+                    buffer_usage: BufferUsage::STORAGE_BUFFER,
+                    memory_usage: MemoryUsage::CpuToGpu,
+                    queue_types: QueueTypes::MAIN,
+                    sharing_mode: SharingMode::Exclusive,
+                    debug_name: Some(format!("joint_palettes_{frame_idx}")),
+                    memory_fallback: MemoryFallback::None,
+                },
+            )
+            .unwrap()
+        });
+
+        let sets = std::array::from_fn(|frame_idx| {
+            DescriptorSet::new(
+                ctx.clone(),
+                DescriptorSetCreateInfo {
+                    layout: layouts.skinning.clone(),
+                    debug_name: Some(format!("skinning_set_{frame_idx}")),
+                    variable_count: None,
+                },
+            )
+            .unwrap()
+        });
+
+        Self {
+            ctx: ctx.clone(),
+            pipeline,
+            palettes,
+            sets,
+            scratch: None,
+            dispatches: Vec::default(),
+        }
+    }
+
+    /// Uploads the joint palettes of every skinned mesh and determines which ones can be skinned
+    /// this frame. Instances whose mesh hasn't finished uploading are skipped.
+    pub fn prepare(
+        &mut self,
+        frame: Frame,
+        skinned: &SkinnedMeshes,
+        mesh_factory: &MeshFactory,
+        meshes: &ResourceAllocator<MeshResource>,
+        skinned_meshes: &ResourceAllocator<SkinnedMeshResource>,
+    ) {
+        let frame_idx = usize::from(frame);
+
+        // Upload palettes
+        let req_size = std::mem::size_of_val(skinned.palettes()) as u64;
+        if let Some(new_buffer) = Buffer::expand(&self.palettes[frame_idx], req_size, false) {
+            self.palettes[frame_idx] = new_buffer;
+        }
+
+        let mut view = self.palettes[frame_idx].write(0).unwrap();
+        bytemuck::cast_slice_mut::<_, Mat4>(view.deref_mut())[..skinned.palettes().len()]
+            .copy_from_slice(skinned.palettes());
+        std::mem::drop(view);
+
+        // Find instances to skin
+        self.dispatches.clear();
+        for instance in skinned.instances() {
+            let skinned_rsc = match skinned_meshes.get(instance.skinned.id()) {
+                Some(skinned_rsc) => skinned_rsc,
+                None => continue,
+            };
+
+            let mesh = match meshes.get(skinned_rsc.mesh.id()) {
+                Some(mesh) if mesh.mesh_ready => mesh,
+                _ => continue,
+            };
+
+            self.dispatches.push(SkinDispatch {
+                skinned: instance.skinned.clone(),
+                constants: GpuSkinningPushConstants {
+                    src_vertex_offset: mesh.block.vertex_block().base(),
+                    dst_vertex_offset: skinned_rsc.vertex_block.base(),
+                    vertex_count: skinned_rsc.vertex_count as u32,
+                    palette_offset: instance.palette_offset,
+                    joint_count: instance.joint_count,
+                    has_tangents: mesh.block.layout().contains(VertexLayout::TANGENT) as u32,
+                },
+            });
+        }
+
+        // Make sure the scratch buffer can rebuild every BLAS
+        let blases: Vec<_> = self
+            .dispatches
+            .iter()
+            .map(|dispatch| &skinned_meshes.get(dispatch.skinned.id()).unwrap().blas)
+            .collect();
+        let scratch_size = BottomLevelAccelerationStructure::batch_scratch_size(&blases);
+        let has_room = self
+            .scratch
+            .as_ref()
+            .map(|scratch| scratch.size() >= scratch_size)
+            .unwrap_or(false);
+
+        if scratch_size > 0 && !has_room {
+            self.scratch = Some(
+                Buffer::new(
+                    self.ctx.clone(),
+                    BufferCreateInfo {
+                        size: scratch_size,
+                        array_elements: 1,
+                        buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_SCRATCH,
+                        memory_usage: MemoryUsage::GpuOnly,
+                        queue_types: QueueTypes::MAIN,
+                        sharing_mode: SharingMode::Exclusive,
+                        debug_name: Some("skinned_blas_scratch".into()),
+                        memory_fallback: MemoryFallback::None,
+                    },
+                )
+                .unwrap(),
+            );
+        }
+
+        // Bind buffers. The vertex buffers never move, but the palette might have.
+        let vertices = mesh_factory.vertex_buffer();
+        self.sets[frame_idx].update(&[
+            DescriptorSetUpdate {
+                binding: SKINNING_SET_POSITIONS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: vertices.buffer(VertexAttribute::Position),
+                    array_element: 0,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: SKINNING_SET_NORMALS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: vertices.buffer(VertexAttribute::Normal),
+                    array_element: 0,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: SKINNING_SET_TANGENTS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: vertices.buffer(VertexAttribute::Tangent),
+                    array_element: 0,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: SKINNING_SET_JOINTS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: vertices.buffer(VertexAttribute::Joints),
+                    array_element: 0,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: SKINNING_SET_WEIGHTS_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: vertices.buffer(VertexAttribute::Weights),
+                    array_element: 0,
+                },
+            },
+            DescriptorSetUpdate {
+                binding: SKINNING_SET_PALETTE_BINDING,
+                array_element: 0,
+                value: DescriptorValue::StorageBuffer {
+                    buffer: &self.palettes[frame_idx],
+                    array_element: 0,
+                },
+            },
+        ]);
+    }
+
+    /// Skins every instance found in [`prepare`](Self::prepare) and rebuilds their BLAS'. Must
+    /// happen before the TLAS is built.
+    pub fn skin<'a>(
+        &'a self,
+        frame: Frame,
+        commands: &mut CommandBuffer<'a>,
+        skinned_meshes: &'a ResourceAllocator<SkinnedMeshResource>,
+    ) {
+        if self.dispatches.is_empty() {
+            return;
+        }
+
+        commands.compute_pass(&self.pipeline, Some("skinning"), |pass| {
+            pass.bind_sets(0, vec![&self.sets[usize::from(frame)]]);
+            for dispatch in &self.dispatches {
+                pass.push_constants(bytemuck::cast_slice(&[dispatch.constants]));
+                pass.dispatch(
+                    dispatch
+                        .constants
+                        .vertex_count
+                        .div_ceil(SKINNING_GROUP_SIZE),
+                    1,
+                    1,
+                );
+            }
+        });
+
+        let blases: Vec<_> = self
+            .dispatches
+            .iter()
+            .map(|dispatch| &skinned_meshes.get(dispatch.skinned.id()).unwrap().blas)
+            .collect();
+        commands.build_blas_batch(&blases, self.scratch.as_ref().unwrap(), 0);
+
+        // Objects pick up the BLAS the next time they're captured
+        self.dispatches.iter().for_each(|dispatch| {
+            let skinned_rsc = skinned_meshes.get(dispatch.skinned.id()).unwrap();
+            skinned_rsc
+                .blas_ref
+                .store(skinned_rsc.blas.device_ref(), Ordering::Relaxed);
+        });
+    }
+}
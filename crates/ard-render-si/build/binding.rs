@@ -14,6 +14,16 @@ pub struct GpuBinding {
     stage: ShaderStage,
     count: String,
     data: GpuBindingData,
+    #[serde(default)]
+    flags: Vec<GpuBindingFlag>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GpuBindingFlag {
+    UpdateAfterBind,
+    PartiallyBound,
+    /// Must only be used on the last binding of a set.
+    VariableCount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,7 +40,9 @@ pub enum GpuBindingData {
     UTexture(String),
     ITexture(String),
     CubeMap(String),
+    CubeMapArray(String),
     UnboundedTextureArray(String),
+    ShadowTexture(String),
     ShadowTextureArray(String),
     StorageImage {
         field_name: String,
@@ -97,6 +109,20 @@ impl GpuBinding {
     pub fn data(&self) -> &GpuBindingData {
         &self.data
     }
+
+    pub fn flags(&self) -> &[GpuBindingFlag] {
+        &self.flags
+    }
+}
+
+impl GpuBindingFlag {
+    pub fn to_rust(self) -> &'static str {
+        match self {
+            GpuBindingFlag::UpdateAfterBind => "DescriptorBindingFlags::UPDATE_AFTER_BIND",
+            GpuBindingFlag::PartiallyBound => "DescriptorBindingFlags::PARTIALLY_BOUND",
+            GpuBindingFlag::VariableCount => "DescriptorBindingFlags::VARIABLE_COUNT",
+        }
+    }
 }
 
 impl GpuStorageImageFormat {
@@ -192,8 +192,10 @@ impl<W: Write> DescriptorSetCodeGen for GlslSetsCodeGen<W> {
             | GpuBindingData::UTexture(_)
             | GpuBindingData::ITexture(_)
             | GpuBindingData::UnboundedTextureArray(_)
+            | GpuBindingData::ShadowTexture(_)
             | GpuBindingData::ShadowTextureArray(_)
-            | GpuBindingData::CubeMap(_) => {
+            | GpuBindingData::CubeMap(_)
+            | GpuBindingData::CubeMapArray(_) => {
                 write!(
                     self.writer,
                     "layout(set = {}, binding = {}) ",
@@ -290,9 +292,15 @@ impl<W: Write> DescriptorSetCodeGen for GlslSetsCodeGen<W> {
             GpuBindingData::CubeMap(field_name) => {
                 writeln!(self.writer, "uniform samplerCube {field_name};\n").unwrap();
             }
+            GpuBindingData::CubeMapArray(field_name) => {
+                writeln!(self.writer, "uniform samplerCubeArray {field_name};\n").unwrap();
+            }
             GpuBindingData::UnboundedTextureArray(field_name) => {
                 writeln!(self.writer, "uniform sampler2D {field_name}[];\n").unwrap();
             }
+            GpuBindingData::ShadowTexture(field_name) => {
+                writeln!(self.writer, "uniform sampler2DShadow {field_name};\n").unwrap();
+            }
             GpuBindingData::ShadowTextureArray(field_name) => {
                 let count = binding.count();
                 writeln!(
@@ -135,8 +135,10 @@ impl<W: Write> RustSetsCodeGen<W> {
             | GpuBindingData::UTexture(_)
             | GpuBindingData::ITexture(_)
             | GpuBindingData::UnboundedTextureArray(_)
+            | GpuBindingData::ShadowTexture(_)
             | GpuBindingData::ShadowTextureArray(_) => "DescriptorType::Texture".to_owned(),
             GpuBindingData::CubeMap(_) => "DescriptorType::CubeMap".to_owned(),
+            GpuBindingData::CubeMapArray(_) => "DescriptorType::CubeMapArray".to_owned(),
             GpuBindingData::StorageImage { access, .. } => {
                 format!(
                     "DescriptorType::StorageImage(AccessType::{:?})",
@@ -151,6 +153,17 @@ impl<W: Write> RustSetsCodeGen<W> {
         writeln!(writer, "count: {},", binding.count()).unwrap();
         writeln!(writer, "ty: {ty},").unwrap();
         writeln!(writer, "stage: ShaderStage::{:?},", binding.stage()).unwrap();
+        let flags = if binding.flags().is_empty() {
+            "DescriptorBindingFlags::empty()".to_owned()
+        } else {
+            binding
+                .flags()
+                .iter()
+                .map(|flag| flag.to_rust())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        };
+        writeln!(writer, "flags: {flags},").unwrap();
         writeln!(writer, "}},").unwrap();
     }
 }
@@ -226,7 +239,12 @@ impl<W: Write> DescriptorSetCodeGen for RustSetsCodeGen<W> {
             Self::write_binding_rust(&mut self.writer, binding, &binding_consts[i]);
         }
 
-        writeln!(self.writer, "], }}, ).unwrap() }} }}\n").unwrap();
+        writeln!(
+            self.writer,
+            "], push: false, debug_name: Some(\"{}\".into()), }}, ).unwrap() }} }}\n",
+            set_name
+        )
+        .unwrap();
     }
 }
 
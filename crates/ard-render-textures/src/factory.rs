@@ -1,11 +1,11 @@
-use std::ops::{Div, Shr};
+use std::ops::Div;
 
-use ard_formats::texture::MipType;
+use ard_formats::texture::{MipType, TextureData};
 use ard_log::warn;
 use ard_pal::prelude::{
-    AnisotropyLevel, Blit, BlitDestination, BlitSource, Buffer, BufferTextureCopy, CommandBuffer,
-    Context, DescriptorSet, DescriptorSetCreateInfo, DescriptorSetUpdate, DescriptorValue, Filter,
-    Format, MemoryUsage, MultiSamples, QueueType, QueueTypes, Sampler, SamplerAddressMode,
+    Blit, BlitDestination, BlitSource, Buffer, BufferTextureCopy, CommandBuffer, Context,
+    DescriptorSet, DescriptorSetCreateInfo, DescriptorSetUpdate, DescriptorValue, Filter, Format,
+    MemoryFallback, MemoryUsage, MultiSamples, QueueType, QueueTypes, Sampler, SamplerAddressMode,
     SharingMode, Texture, TextureCreateInfo, TextureType, TextureUsage,
 };
 use ard_render_base::{
@@ -21,10 +21,9 @@ use crate::texture::TextureResource;
 type PalTexture = ard_pal::prelude::Texture;
 
 pub struct TextureFactory {
+    ctx: Context,
     /// Default error texture.
     error_tex: PalTexture,
-    /// Current anisotropy level.
-    anisotropy: Option<AnisotropyLevel>,
     /// Bindless texture set per frame in flight.
     sets: [DescriptorSet; FRAMES_IN_FLIGHT],
     new_textures: [Vec<ResourceId>; FRAMES_IN_FLIGHT],
@@ -39,16 +38,11 @@ pub enum MipUpdate {
 }
 
 pub struct TextureUpload {
-    pub staging: Buffer,
+    pub data: TextureData,
     pub mip_type: MipType,
     pub loaded_mips: u32,
 }
 
-pub struct TextureMipUpload {
-    pub staging: Buffer,
-    pub mip_level: u32,
-}
-
 const DEFAULT_SAMPLER: Sampler = Sampler {
     min_filter: Filter::Nearest,
     mag_filter: Filter::Nearest,
@@ -58,6 +52,7 @@ const DEFAULT_SAMPLER: Sampler = Sampler {
     address_w: SamplerAddressMode::ClampToEdge,
     anisotropy: None,
     compare: None,
+    reduction_mode: None,
     min_lod: unsafe { NotNan::new_unchecked(0.0) },
     max_lod: None,
     unnormalize_coords: false,
@@ -74,6 +69,7 @@ impl TextureFactory {
                 DescriptorSetCreateInfo {
                     layout: layouts.textures.clone(),
                     debug_name: Some(format!("texture_set_{frame_idx}")),
+                    variable_count: None,
                 },
             )
             .unwrap();
@@ -88,6 +84,7 @@ impl TextureFactory {
                         sampler: DEFAULT_SAMPLER,
                         base_mip: 0,
                         mip_count: 1,
+                        aspect: None,
                     },
                 })
                 .collect();
@@ -98,9 +95,9 @@ impl TextureFactory {
         });
 
         Self {
+            ctx: ctx.clone(),
             sets,
             error_tex,
-            anisotropy: Some(AnisotropyLevel::X16),
             new_textures: Default::default(),
             dropped_textures: Default::default(),
             mip_updates: Default::default(),
@@ -130,9 +127,30 @@ impl TextureFactory {
         self.mip_updates.iter_mut().for_each(|l| l.push(update));
     }
 
+    /// Rebinds every uploaded texture so their samplers pick up the current
+    /// [`default_anisotropy`](Context::default_anisotropy) of the context. Each frame's set is
+    /// rewritten the next time its bindings are updated.
+    pub fn refresh_samplers(&mut self, textures: &ResourceAllocator<TextureResource>) {
+        // Textures that are still uploading are bound once they're ready
+        let ids: Vec<_> = textures
+            .all()
+            .iter()
+            .enumerate()
+            .filter(|(_, texture)| match &texture.resource {
+                Some(texture) => texture.loaded_mips != 0,
+                None => false,
+            })
+            .map(|(id, _)| ResourceId::from(id))
+            .collect();
+        self.new_textures
+            .iter_mut()
+            .for_each(|l| l.extend_from_slice(&ids));
+    }
+
     /// Binds ready textures to the main set for the given frame and unbinds destroyed textures.
     pub fn update_bindings(&mut self, frame: Frame, textures: &ResourceAllocator<TextureResource>) {
         let frame = usize::from(frame);
+        let anisotropy = self.ctx.default_anisotropy();
         let cap = self.new_textures[frame].len()
             + self.dropped_textures[frame].len()
             + self.mip_updates[frame].len();
@@ -156,6 +174,7 @@ impl TextureFactory {
                     sampler: DEFAULT_SAMPLER,
                     base_mip: 0,
                     mip_count: 1,
+                    aspect: None,
                 },
             });
         });
@@ -189,11 +208,12 @@ impl TextureFactory {
                         address_v: texture.sampler.address_v,
                         address_w: SamplerAddressMode::ClampToEdge,
                         anisotropy: if texture.sampler.anisotropy {
-                            self.anisotropy
+                            anisotropy
                         } else {
                             None
                         },
                         compare: None,
+                        reduction_mode: None,
                         min_lod: NotNan::new(0.0).unwrap(),
                         max_lod: None,
                         unnormalize_coords: false,
@@ -201,6 +221,7 @@ impl TextureFactory {
                     },
                     base_mip: base_mip as usize,
                     mip_count: mip_count as usize,
+                    aspect: None,
                 },
             });
         });
@@ -240,11 +261,12 @@ impl TextureFactory {
                                 address_v: texture.sampler.address_v,
                                 address_w: SamplerAddressMode::ClampToEdge,
                                 anisotropy: if texture.sampler.anisotropy {
-                                    self.anisotropy
+                                    anisotropy
                                 } else {
                                     None
                                 },
                                 compare: None,
+                                reduction_mode: None,
                                 min_lod: NotNan::new(0.0).unwrap(),
                                 max_lod: None,
                                 unnormalize_coords: false,
@@ -252,6 +274,7 @@ impl TextureFactory {
                             },
                             base_mip: base_mip as usize,
                             mip_count: mip_count as usize,
+                            aspect: None,
                         },
                     });
                 },
@@ -263,32 +286,16 @@ impl TextureFactory {
         self.sets[frame].update(&updates);
     }
 
-    /// Records a command to upload a texture to the factory and generate mip levels.
+    /// Records commands to generate the mip chain of a texture from its highest detail mip
+    /// level, which must already be uploaded.
     ///
     /// ## Note
-    /// `commands` must have graphics and transfer operation support.
-    pub fn upload_gen_mip<'a>(
+    /// `commands` must have graphics operation support.
+    pub fn generate_mips<'a>(
         commands: &mut CommandBuffer<'a>,
         texture: &'a Texture,
         mip_count: u32,
-        upload: &'a TextureUpload,
     ) {
-        // Staging buffer has the highest detail mip level
-        commands.copy_buffer_to_texture(
-            texture,
-            &upload.staging,
-            BufferTextureCopy {
-                buffer_offset: 0,
-                buffer_row_length: 0,
-                buffer_image_height: 0,
-                buffer_array_element: 0,
-                texture_offset: (0, 0, 0),
-                texture_extent: texture.dims(),
-                texture_mip_level: 0,
-                texture_array_element: 0,
-            },
-        );
-
         // Blit each image in the mip chain
         let (mut mip_width, mut mip_height, _) = texture.dims();
         for i in 1..mip_count {
@@ -313,91 +320,7 @@ impl TextureFactory {
             mip_height = height;
         }
 
-        commands.set_texture_usage(texture, TextureUsage::SAMPLED, 0, 0, mip_count as usize);
-    }
-
-    /// Records a command to upload a lowest detail mip level of a texture.
-    ///
-    /// ## Note
-    /// `commands` must have transfer operation support.
-    pub fn upload<'a>(
-        commands: &mut CommandBuffer<'a>,
-        texture: &'a Texture,
-        mip_level: u32,
-        upload: &'a TextureUpload,
-    ) {
-        let (mut width, mut height, _) = texture.dims();
-        width = width.shr(mip_level).max(1);
-        height = height.shr(mip_level).max(1);
-
-        // Staging buffer has the lowest detail mip level
-        commands.copy_buffer_to_texture(
-            texture,
-            &upload.staging,
-            BufferTextureCopy {
-                buffer_offset: 0,
-                buffer_row_length: 0,
-                buffer_image_height: 0,
-                buffer_array_element: 0,
-                texture_offset: (0, 0, 0),
-                texture_extent: (width, height, 1),
-                texture_mip_level: mip_level as usize,
-                texture_array_element: 0,
-            },
-        );
-
-        commands.set_texture_usage(texture, TextureUsage::SAMPLED, 0, mip_level, 1);
-
-        commands.transfer_texture_ownership(
-            texture,
-            0,
-            mip_level as usize,
-            1,
-            QueueType::Main,
-            Some(TextureUsage::SAMPLED),
-        );
-    }
-
-    /// Records a command to upload a texture mip level.
-    ///
-    /// ## Note
-    /// `commands` must have transfer operation support.
-    pub fn upload_mip<'a>(
-        commands: &mut CommandBuffer<'a>,
-        texture: &'a Texture,
-        upload: &'a TextureMipUpload,
-    ) {
-        let (width, height, _) = texture.dims();
-
-        commands.copy_buffer_to_texture(
-            texture,
-            &upload.staging,
-            BufferTextureCopy {
-                buffer_offset: 0,
-                buffer_row_length: 0,
-                buffer_image_height: 0,
-                buffer_array_element: 0,
-                texture_offset: (0, 0, 0),
-                texture_extent: (
-                    width.shr(upload.mip_level).max(1),
-                    height.shr(upload.mip_level).max(1),
-                    1,
-                ),
-                texture_mip_level: upload.mip_level as usize,
-                texture_array_element: 0,
-            },
-        );
-
-        commands.set_texture_usage(texture, TextureUsage::SAMPLED, 0, upload.mip_level, 1);
-
-        commands.transfer_texture_ownership(
-            texture,
-            0,
-            upload.mip_level as usize,
-            1,
-            QueueType::Main,
-            Some(TextureUsage::SAMPLED),
-        );
+        commands.set_texture_usage(texture, TextureUsage::SAMPLED, 0..1, 0..mip_count as usize);
     }
 
     fn create_error_texture(ctx: &Context) -> PalTexture {
@@ -425,6 +348,9 @@ impl TextureFactory {
                 queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
                 sharing_mode: SharingMode::Exclusive,
                 debug_name: Some("error_texture".to_owned()),
+                sparse: false,
+                initial_data: None,
+                memory_fallback: MemoryFallback::None,
             },
         )
         .unwrap();
@@ -444,7 +370,7 @@ impl TextureFactory {
                 texture_array_element: 0,
             },
         );
-        commands.set_texture_usage(&tex, TextureUsage::SAMPLED, 0, 0, 1);
+        commands.set_texture_usage(&tex, TextureUsage::SAMPLED, 0..1, 0..1);
         commands.transfer_texture_ownership(
             &tex,
             0,
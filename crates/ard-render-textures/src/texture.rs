@@ -1,7 +1,8 @@
 use ard_formats::texture::{MipType, Sampler, TextureSource};
+use ard_log::warn;
 use ard_pal::prelude::{
-    Buffer, BufferCreateError, Context, MemoryUsage, MultiSamples, QueueType, QueueTypes,
-    SharingMode, TextureType, TextureUsage,
+    BufferCreateError, Context, MemoryFallback, MemoryUsage, MultiSamples, QueueTypes, SharingMode,
+    TextureType, TextureUsage,
 };
 use ard_render_base::resource::{ResourceHandle, ResourceId};
 use thiserror::*;
@@ -9,7 +10,7 @@ use thiserror::*;
 use crate::factory::TextureUpload;
 
 type PalTexture = ard_pal::prelude::Texture;
-type PalTextureCreateInfo = ard_pal::prelude::TextureCreateInfo;
+type PalTextureCreateInfo = ard_pal::prelude::TextureCreateInfo<'static>;
 type PalTextureCreateError = ard_pal::prelude::TextureCreateError;
 
 pub struct TextureCreateInfo<T> {
@@ -84,41 +85,50 @@ impl TextureResource {
             return Err(TextureCreateError::InvalidMipCount(max_mip_levels));
         }
 
-        let texture = PalTexture::new(
-            ctx.clone(),
-            PalTextureCreateInfo {
-                format: data.format(),
-                ty: TextureType::Type2D,
-                width,
-                height,
-                depth: 1,
-                array_elements: 1,
-                mip_levels: create_info.mip_count,
-                sample_count: MultiSamples::Count1,
-                texture_usage: TextureUsage::TRANSFER_SRC
-                    | TextureUsage::TRANSFER_DST
-                    | TextureUsage::SAMPLED,
-                memory_usage: MemoryUsage::GpuOnly,
-                queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
-                sharing_mode: SharingMode::Exclusive,
-                debug_name: create_info.debug_name,
-            },
-        )?;
+        let texture_info = |memory_fallback| PalTextureCreateInfo {
+            format: data.format(),
+            ty: TextureType::Type2D,
+            width,
+            height,
+            depth: 1,
+            array_elements: 1,
+            mip_levels: create_info.mip_count,
+            sample_count: MultiSamples::Count1,
+            texture_usage: TextureUsage::TRANSFER_SRC
+                | TextureUsage::TRANSFER_DST
+                | TextureUsage::SAMPLED,
+            memory_usage: MemoryUsage::GpuOnly,
+            queue_types: QueueTypes::MAIN | QueueTypes::TRANSFER,
+            sharing_mode: SharingMode::Exclusive,
+            debug_name: create_info.debug_name.clone(),
+            sparse: false,
+            initial_data: None,
+            memory_fallback,
+        };
+
+        let texture = match PalTexture::new(ctx.clone(), texture_info(MemoryFallback::None)) {
+            Ok(texture) => texture,
+            // The whole mip chain lives in a single allocation, so there are no individual mips
+            // that could be evicted to make room. Host memory is slower to sample from, but
+            // keeps the texture usable.
+            Err(PalTextureCreateError::OutOfMemory { requested, heap }) => {
+                warn!(
+                    "Out of device memory allocating `{requested}` bytes from heap `{heap}` for \
+                    texture `{:?}`. Falling back to system memory.",
+                    create_info.debug_name
+                );
+                PalTexture::new(ctx.clone(), texture_info(MemoryFallback::AllowSystemMemory))?
+            }
+            Err(err) => return Err(err.into()),
+        };
 
-        let (loaded_mips, staging_queue): (u32, QueueType) = match create_info.mip_type {
+        let loaded_mips = match create_info.mip_type {
             // All mips will be available when the texture is ready
-            MipType::Generate => ((1 << create_info.mip_count as u32) - 1, QueueType::Main),
+            MipType::Generate => (1 << create_info.mip_count as u32) - 1,
             // Only the lowest detail mip is loaded.
-            MipType::Upload(_, _) => (1 << (create_info.mip_count as u32 - 1), QueueType::Transfer),
+            MipType::Upload(_, _) => 1 << (create_info.mip_count as u32 - 1),
         };
 
-        let staging = Buffer::new_staging(
-            ctx.clone(),
-            staging_queue,
-            Some("texture_staging".to_owned()),
-            data.raw(),
-        )?;
-
         Ok((
             Self {
                 texture,
@@ -128,7 +138,7 @@ impl TextureResource {
                 version: u32::MAX,
             },
             TextureUpload {
-                staging,
+                data,
                 mip_type: create_info.mip_type,
                 loaded_mips,
             },
@@ -0,0 +1,42 @@
+#ifndef _ARD_GPU_ASSERT
+#define _ARD_GPU_ASSERT
+
+/// GPU asserts. Only enabled in debug builds, where `ARD_GPU_ASSERTS` is defined by the shader
+/// build scripts. Otherwise, `ARD_ASSERT` compiles to nothing.
+///
+/// To use asserts, define `ARD_SET_GPU_ASSERTS` to the set index of the `GpuAsserts` layout
+/// before including `ard_bindings.glsl`, then include this file after it. The first failure of
+/// each frame records the line, the value passed to the assert, and `ARD_ASSERT_INVOCATION`,
+/// which defaults to `gl_GlobalInvocationID`. Stages without it must define their own, like
+/// `gl_LaunchIDEXT` for ray tracing.
+///
+///     ARD_ASSERT(draw_idx < draw_count, draw_idx);
+
+#if defined(ARD_GPU_ASSERTS)
+
+#ifndef ARD_SET_GPU_ASSERTS
+    #error "`ARD_SET_GPU_ASSERTS` must be defined to use GPU asserts"
+#endif
+
+#ifndef ARD_ASSERT_INVOCATION
+    #define ARD_ASSERT_INVOCATION gl_GlobalInvocationID
+#endif
+
+void ard_assert_failed(const uint line, const uint value) {
+    // Only the first failure records where it happened
+    if (atomicAdd(gpu_assert.failures, 1) == 0) {
+        gpu_assert.invocation = uvec4(uvec3(ARD_ASSERT_INVOCATION), 0);
+        gpu_assert.line = line;
+        gpu_assert.value = value;
+    }
+}
+
+#define ARD_ASSERT(COND, VALUE) if (!(COND)) { ard_assert_failed(__LINE__, uint(VALUE)); }
+
+#else
+
+#define ARD_ASSERT(COND, VALUE)
+
+#endif
+
+#endif
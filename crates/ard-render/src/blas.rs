@@ -1,9 +1,8 @@
 use std::{collections::VecDeque, vec::Drain};
 
 use ard_pal::prelude::{
-    BottomLevelAccelerationStructure, BottomLevelAccelerationStructureCreateInfo,
-    BottomLevelAccelerationStructureData, Buffer, BuildAccelerationStructureFlags, Context,
-    QueueTypes, SharingMode,
+    BottomLevelAccelerationStructure, Buffer, BufferCreateInfo, BufferUsage, Context,
+    MemoryFallback, MemoryUsage, QueueTypes, SharingMode,
 };
 use ard_render_base::{
     resource::{ResourceAllocator, ResourceId},
@@ -17,17 +16,20 @@ const BLAS_BUILD_PER_PROCESS: usize = 16;
 pub struct PendingBlasBuilder {
     /// Meshes pending BLAS construction.
     new_pending: VecDeque<PendingBlasBuild>,
+    /// Meshes that have been built and are waiting on their compacted size.
+    compact_waiting: Vec<ResourceId>,
     /// Meshes pending BLAS compaction.
     compact_pending: [Vec<PendingBlasCompact>; FRAMES_IN_FLIGHT],
     /// Meshes that have been compacted and need to have their BLAS' swapped out.
     swap_pending: [Vec<PendingBlasSwap>; FRAMES_IN_FLIGHT],
     /// The meshes to build BLAS' for this frame.
     to_build: Vec<PendingBlasBuild>,
+    /// Scratch memory shared by every BLAS built in a frame.
+    scratch: Option<Buffer>,
 }
 
 pub struct PendingBlasBuild {
     pub mesh_id: ResourceId,
-    pub scratch: Box<Buffer>,
 }
 
 pub struct PendingBlasCompact {
@@ -47,6 +49,13 @@ impl PendingBlasBuilder {
         &self.to_build
     }
 
+    /// Scratch buffer large enough to build every BLAS in [`to_build`](Self::to_build) as a
+    /// batch. `None` if nothing has been built yet.
+    #[inline(always)]
+    pub fn scratch(&self) -> Option<&Buffer> {
+        self.scratch.as_ref()
+    }
+
     /// List of BLAS' to compact this frame.
     pub fn to_compact(&self, frame: Frame) -> &[PendingBlasCompact] {
         &self.compact_pending[usize::from(frame)]
@@ -57,11 +66,10 @@ impl PendingBlasBuilder {
         self.swap_pending[usize::from(frame)].drain(..)
     }
 
-    /// Appends a new mesh and it's scratch buffer to have its BLAS built and then compacted.
+    /// Appends a new mesh to have its BLAS built and then compacted.
     #[inline(always)]
-    pub fn append(&mut self, mesh_id: ResourceId, scratch: Box<Buffer>) {
-        self.new_pending
-            .push_back(PendingBlasBuild { mesh_id, scratch });
+    pub fn append(&mut self, mesh_id: ResourceId) {
+        self.new_pending.push_back(PendingBlasBuild { mesh_id });
     }
 
     /// Takes some pending meshes and creates a list of them to build.
@@ -77,29 +85,76 @@ impl PendingBlasBuilder {
         let rng = ..self.new_pending.len().min(BLAS_BUILD_PER_PROCESS);
         self.to_build = self.new_pending.drain(rng).collect();
 
-        // Take meshes that were built last frame and construct their BLAS destinations.
-        self.compact_pending[usize::from(frame)]
-            .iter_mut()
-            .for_each(|pending| {
-                let compact_size = match meshes.get(pending.mesh_id) {
-                    Some(mesh) => mesh.blas.compacted_size(),
-                    None => return,
-                };
-
-                pending.dst = Some(
-                    BottomLevelAccelerationStructure::new(
-                        ctx.clone(),
-                        BottomLevelAccelerationStructureCreateInfo {
-                            flags: BuildAccelerationStructureFlags::PREFER_FAST_TRACE,
-                            data: BottomLevelAccelerationStructureData::CompactDst(compact_size),
-                            queue_types: QueueTypes::MAIN,
-                            sharing_mode: SharingMode::Exclusive,
-                            debug_name: Some("mesh_blas_compact".into()),
-                        },
+        // Make sure the scratch buffer can fit the whole batch
+        let blases: Vec<_> = self
+            .to_build
+            .iter()
+            .filter_map(|pending| meshes.get(pending.mesh_id))
+            .map(|mesh| &mesh.blas)
+            .collect();
+        let scratch_size = BottomLevelAccelerationStructure::batch_scratch_size(&blases);
+        let has_room = self
+            .scratch
+            .as_ref()
+            .map(|scratch| scratch.size() >= scratch_size)
+            .unwrap_or(false);
+
+        if scratch_size > 0 && !has_room {
+            self.scratch = Some(
+                Buffer::new(
+                    ctx.clone(),
+                    BufferCreateInfo {
+                        size: scratch_size,
+                        array_elements: 1,
+                        buffer_usage: BufferUsage::ACCELERATION_STRUCTURE_SCRATCH,
+                        memory_usage: MemoryUsage::GpuOnly,
+                        queue_types: QueueTypes::MAIN,
+                        sharing_mode: SharingMode::Exclusive,
+                        debug_name: Some("mesh_blas_scratch".into()),
+                        memory_fallback: MemoryFallback::None,
+                    },
+                )
+                .unwrap(),
+            );
+        }
+
+        // Meshes that were dropped no longer need compacting
+        self.compact_waiting
+            .retain(|mesh_id| meshes.get(*mesh_id).is_some());
+
+        // Construct BLAS destinations for every mesh whose compacted size is ready. The rest
+        // are checked again next frame.
+        let blases: Vec<_> = self
+            .compact_waiting
+            .iter()
+            .map(|mesh_id| &meshes.get(*mesh_id).unwrap().blas)
+            .collect();
+        let sizes = ctx.poll_compacted_sizes(&blases);
+
+        let compact_pending = &mut self.compact_pending[usize::from(frame)];
+        let mut sizes = sizes.into_iter();
+        self.compact_waiting.retain(|mesh_id| {
+            let size = match sizes.next().unwrap() {
+                Some(size) => size,
+                None => return true,
+            };
+
+            let src = &meshes.get(*mesh_id).unwrap().blas;
+            compact_pending.push(PendingBlasCompact {
+                mesh_id: *mesh_id,
+                dst: Some(
+                    src.new_compact_dst_with_size(
+                        size,
+                        QueueTypes::MAIN,
+                        SharingMode::Exclusive,
+                        Some("mesh_blas_compact".into()),
                     )
                     .unwrap(),
-                );
+                ),
             });
+
+            false
+        });
     }
 
     /// Take this frames BLAS' that were built/compacted and construct new lists.
@@ -119,12 +174,8 @@ impl PendingBlasBuilder {
             });
         }
 
-        // Take the meshes that were just built and set them up for compaction next frame
-        for pending_build in self.to_build.drain(..) {
-            compact_pending.push(PendingBlasCompact {
-                mesh_id: pending_build.mesh_id,
-                dst: None,
-            });
-        }
+        // Take the meshes that were just built and compact them once their size is ready
+        self.compact_waiting
+            .extend(self.to_build.drain(..).map(|pending| pending.mesh_id));
     }
 }
@@ -5,6 +5,8 @@ use ard_render_camera::target::RenderTarget;
 use ard_render_image_effects::ao::{AmbientOcclusion, AoImage};
 use ard_render_renderers::highz::{HzbImage, HzbRenderer};
 
+use crate::{frame::WindowInfo, surface::WindowSurface};
+
 #[derive(Resource)]
 pub(crate) struct Canvas {
     /// The render target to draw to for the canvas.
@@ -13,36 +15,26 @@ pub(crate) struct Canvas {
     hzb: HzbImage,
     /// AO image.
     ao: AoImage,
-    /// Surface being rendered to.
-    surface: Surface,
-    /// Surface image for the current frame.
-    image: Option<SurfaceImage>,
+    /// Surface of the window being rendered to.
+    surface: WindowSurface,
     /// Size of the canvas.
     size: (u32, u32),
-    /// Presentation mode being used.
-    present_mode: PresentMode,
-    /// Surface image format.
-    format: Format,
 }
 
 impl Canvas {
     pub fn new(
         ctx: &Context,
-        surface: Surface,
+        surface: WindowSurface,
         dims: (u32, u32),
-        present_mode: PresentMode,
         hzb_render: &HzbRenderer,
         ao: &AmbientOcclusion,
     ) -> Self {
         let mut canvas = Self {
-            image: None,
             render_target: RenderTarget::new(ctx, dims, MultiSamples::Count1),
             hzb: HzbImage::new(hzb_render, dims.0, dims.1),
             ao: AoImage::new(ao, dims),
             size: dims,
             surface,
-            present_mode,
-            format: Format::Bgra8Unorm,
         };
         canvas.update_bindings();
         canvas
@@ -53,6 +45,11 @@ impl Canvas {
         self.size
     }
 
+    #[inline(always)]
+    pub fn surface(&self) -> &WindowSurface {
+        &self.surface
+    }
+
     #[inline(always)]
     pub fn render_target(&self) -> &RenderTarget {
         &self.render_target
@@ -89,17 +86,15 @@ impl Canvas {
         );
     }
 
-    pub fn acquire_image(&mut self) {
-        self.image = Some(self.surface.acquire_image().unwrap());
+    #[inline(always)]
+    pub fn acquire_image(&mut self, window: &WindowInfo) {
+        self.surface.acquire_image(window);
     }
 
     /// Gets the current surface image.
-    ///
-    /// # Note
-    /// This will panic if `image` is `None`. This is really just for convenience since it should
-    /// never be `None` when the render ECS is executing.
+    #[inline(always)]
     pub fn image(&self) -> &SurfaceImage {
-        self.image.as_ref().unwrap()
+        self.surface.image()
     }
 
     /// Updates the canvas with a new size. Does nothing if the size is matching.
@@ -113,6 +108,7 @@ impl Canvas {
         dims: (u32, u32),
         samples: MultiSamples,
     ) -> bool {
+        let samples = RenderTarget::supported_samples(ctx, samples);
         if dims == self.size && samples == self.render_target.samples() {
             return false;
         }
@@ -128,28 +124,9 @@ impl Canvas {
 
     /// Presents the currently active surface image and optionally resizes the surface to meet the
     /// window size if needed.
+    #[inline(always)]
     pub fn present(&mut self, ctx: &Context, window_size: (u32, u32)) {
-        puffin::profile_function!();
-
-        let image = match self.image.take() {
-            Some(image) => image,
-            None => return,
-        };
-
-        if let SurfacePresentSuccess::Invalidated =
-            ctx.present().present(&self.surface, image).unwrap()
-        {
-            let present_mode = self.present_mode;
-            let format = self.format;
-            self.surface
-                .update_config(SurfaceConfiguration {
-                    width: window_size.0,
-                    height: window_size.1,
-                    present_mode,
-                    format,
-                })
-                .unwrap();
-        }
+        self.surface.present(ctx, window_size);
     }
 
     fn update_bindings(&mut self) {
@@ -1,4 +1,4 @@
-use ard_log::info;
+use ard_log::{error, info};
 use ard_math::{Mat4, Vec2, Vec4};
 use ard_pal::prelude::*;
 use ard_render_base::{resource::ResourceAllocator, Frame, FRAMES_IN_FLIGHT};
@@ -6,6 +6,7 @@ use ard_render_camera::{
     active::ActiveCamera, froxels::FroxelGenPipeline, target::RenderTarget, ubo::CameraUbo, Camera,
     CameraClearColor,
 };
+use ard_render_debug::buffer::DebugVertexBuffer;
 use ard_render_image_effects::{
     ao::{AmbientOcclusion, AoSettings},
     bloom::Bloom,
@@ -13,40 +14,59 @@ use ard_render_image_effects::{
     lxaa::Lxaa,
     smaa::Smaa,
     sun_shafts2::SunShafts,
+    taa::Taa,
     tonemapping::Tonemapping,
 };
 use ard_render_lighting::{
     lights::LightClusters, proc_skybox::ProceduralSkyBox, reflections::Reflections,
+    shadows::LocalShadowSettings,
 };
 use ard_render_material::{factory::MaterialFactory, material::MaterialResource};
 use ard_render_meshes::{factory::MeshFactory, mesh::MeshResource};
 use ard_render_objects::RenderFlags;
+use ard_render_particles::renderer::ParticleRenderer;
 use ard_render_renderers::{
+    culling::CullingStatsBuffer,
     debug::DebugRenderer,
-    entities::{EntityIdRenderArgs, EntityIdRenderer, EntitySelected, SelectEntity},
+    entities::{EntityIdRenderArgs, EntityIdRenderer, SelectEntity},
+    gpu_assert::GpuAsserts,
     gui::{GuiDrawPrepare, GuiRenderer},
     highz::HzbRenderer,
+    local_shadow::{LocalShadowRenderArgs, LocalShadowsRenderer},
     pathtracer::PathTracer,
     raytrace::RaytracedRenderer,
     scene::{SceneRenderArgs, SceneRenderer},
     shadow::{ShadowRenderArgs, SunShadowsRenderer},
+    skinning::SkinningRenderer,
 };
 use ard_render_si::{bindings::Layouts, consts::*};
 use ard_render_textures::factory::TextureFactory;
 use ard_transform::Model;
+use ard_window::prelude::WindowId;
+use egui::ViewportId;
 use raw_window_handle::HasDisplayHandle;
+use rustc_hash::FxHashMap;
 
-use crate::{canvas::Canvas, factory::Factory, frame::FrameData, RenderPlugin};
+use crate::{
+    canvas::Canvas, factory::Factory, frame::FrameData, surface::WindowSurface, RenderPlugin,
+};
 
 pub(crate) struct RenderEcs {
     layouts: Layouts,
     canvas: Option<Canvas>,
+    /// Surfaces of windows showing detached GUI viewports.
+    gui_surfaces: FxHashMap<WindowId, WindowSurface>,
     camera: CameraUbo,
     scene_renderer: SceneRenderer,
     sun_shadows_renderer: SunShadowsRenderer,
+    local_shadows_renderer: LocalShadowsRenderer,
     hzb_render: HzbRenderer,
     entity_renderer: EntityIdRenderer,
     debug_renderer: DebugRenderer,
+    gpu_asserts: GpuAsserts,
+    culling_stats: CullingStatsBuffer,
+    particles: ParticleRenderer,
+    skinning: SkinningRenderer,
     rt_render: RaytracedRenderer,
     gui_renderer: GuiRenderer,
     lighting: LightClusters,
@@ -54,6 +74,7 @@ pub(crate) struct RenderEcs {
     _fxaa: Fxaa,
     lxaa: Lxaa,
     smaa: Smaa,
+    taa: Taa,
     bloom: Bloom,
     sun_shafts: SunShafts,
     tonemapping: Tonemapping,
@@ -65,6 +86,15 @@ pub(crate) struct RenderEcs {
     ctx: Context,
 }
 
+/// Colors of the shadow cascade frusta drawn with [`crate::DebugSettings::draw_shadow_cascades`].
+/// Matches the tint of [`crate::DebugSettings::show_shadow_cascades`].
+const SHADOW_CASCADE_COLORS: [Vec4; 4] = [
+    Vec4::new(1.0, 0.0, 0.0, 1.0),
+    Vec4::new(0.0, 1.0, 0.0, 1.0),
+    Vec4::new(0.0, 0.0, 1.0, 1.0),
+    Vec4::new(1.0, 1.0, 0.0, 1.0),
+];
+
 const DEFAULT_ACTIVE_CAMERA: ActiveCamera = ActiveCamera {
     camera: Camera {
         near: 0.01,
@@ -84,8 +114,12 @@ impl RenderEcs {
             ard_pal::backend::VulkanBackend::new(ard_pal::backend::VulkanBackendCreateInfo {
                 app_name: String::from("ard"),
                 engine_name: String::from("ard"),
-                display_handle,
+                display_handle: Some(display_handle),
                 debug: plugin.debug,
+                debug_config: Default::default(),
+                pipeline_cache_path: plugin.pipeline_cache_path.clone(),
+                device_memory_budget: None,
+                adapter_preference: plugin.adapter_preference.clone(),
             })
             .unwrap()
         };
@@ -95,6 +129,7 @@ impl RenderEcs {
 
         // Create our graphics context
         let ctx = Context::new(backend);
+        ctx.set_default_anisotropy(Some(AnisotropyLevel::X16));
 
         let layouts = Layouts::new(&ctx);
         let factory = Factory::new(ctx.clone(), &layouts);
@@ -106,6 +141,8 @@ impl RenderEcs {
 
         let mut scene_renderer = SceneRenderer::new(&ctx, &layouts);
         let sun_shadows_renderer = SunShadowsRenderer::new(&ctx, &layouts, MAX_SHADOW_CASCADES);
+        let local_shadows_renderer =
+            LocalShadowsRenderer::new(&ctx, &LocalShadowSettings::default());
         let gui_renderer = GuiRenderer::new(&ctx, &layouts);
         let rt_render = RaytracedRenderer::new(&ctx);
         let mut path_tracer = PathTracer::new(
@@ -122,6 +159,7 @@ impl RenderEcs {
         let bloom = Bloom::new(&ctx, &layouts, window_size, 6);
         let sun_shafts = SunShafts::new(&ctx, &layouts, window_size);
         let smaa = Smaa::new(&ctx, &layouts, window_size);
+        let taa = Taa::new(&ctx, &layouts, window_size);
         let mut reflections = Reflections::new(
             &ctx,
             &layouts,
@@ -145,6 +183,14 @@ impl RenderEcs {
                 .transparent_pass_sets_mut()
                 .update_sun_shadow_bindings(frame, &sun_shadows_renderer);
 
+            scene_renderer
+                .color_pass_sets_mut()
+                .update_local_shadow_bindings(frame, &local_shadows_renderer);
+
+            scene_renderer
+                .transparent_pass_sets_mut()
+                .update_local_shadow_bindings(frame, &local_shadows_renderer);
+
             scene_renderer
                 .color_pass_sets_mut()
                 .update_sky_box_bindings(frame, &proc_skybox);
@@ -172,9 +218,11 @@ impl RenderEcs {
             Self {
                 froxels: FroxelGenPipeline::new(&ctx, &layouts),
                 canvas: None,
+                gui_surfaces: FxHashMap::default(),
                 camera: CameraUbo::new(&ctx, true, &layouts),
                 scene_renderer,
                 sun_shadows_renderer,
+                local_shadows_renderer,
                 rt_render,
                 gui_renderer,
                 entity_renderer,
@@ -183,9 +231,14 @@ impl RenderEcs {
                 path_tracer,
                 reflections,
                 debug_renderer,
+                gpu_asserts: GpuAsserts::new(&ctx, &layouts),
+                culling_stats: CullingStatsBuffer::new(&ctx),
+                particles: ParticleRenderer::new(&ctx, &layouts, window_size),
+                skinning: SkinningRenderer::new(&ctx, &layouts),
                 _fxaa: fxaa,
                 lxaa,
                 smaa,
+                taa,
                 sun_shafts,
                 ao,
                 tonemapping,
@@ -204,7 +257,7 @@ impl RenderEcs {
         &self.ctx
     }
 
-    pub fn render(&mut self, mut frame: FrameData) -> FrameData {
+    pub fn render(&mut self, mut frame: FrameData, token: &FrameToken) -> FrameData {
         puffin::GlobalProfiler::lock().new_frame();
         puffin::profile_function!();
 
@@ -212,42 +265,61 @@ impl RenderEcs {
         // frame.select_entity = Some(SelectEntity(Vec2::ONE * 0.5));
         self.factory.process(frame.frame);
 
+        // The frame has finished on the GPU by now, so reading back the last pick won't stall
+        if std::mem::take(&mut frame.pick_issued) {
+            frame.picked = Some(self.entity_renderer.read_back_picked_entity(frame.frame));
+        }
+
+        // Same for asserts. Only the first failure is recorded, so it's reported once per frame
+        if let Some(failure) = self.gpu_asserts.take_failure(frame.frame) {
+            error!(
+                "GPU assert failed {} time(s). First failure at line {} with value {} in \
+                invocation {}.",
+                failure.failures,
+                failure.line,
+                failure.value,
+                failure.invocation.truncate(),
+            );
+        }
+
+        frame.particle_statistics = self.particles.take_statistics(frame.frame);
+
+        // Culling counters are recorded as scope data so captures show them over time
+        frame.culling_statistics = self.culling_stats.read(frame.frame);
+        for (pass, stats) in &frame.culling_statistics {
+            puffin::profile_scope!(
+                "culling_statistics",
+                format!(
+                    "{pass}: {} tested, {} frustum culled, {} occlusion culled, {} meshlets",
+                    stats.instances_tested,
+                    stats.frustum_culled,
+                    stats.occlusion_culled,
+                    stats.meshlets_drawn,
+                )
+            );
+        }
+
         // If there is no window size, there is no window to render to.
         let window = match frame.window.as_ref() {
             Some(window) => window,
             None => return frame,
         };
 
+        // The canvas must be recreated if the window it presents to was replaced
+        if let Some(canvas) = &self.canvas {
+            if !canvas.surface().presents_to(window) {
+                self.canvas = None;
+            }
+        }
+
         // If there is no canvas, we must create one
         let (canvas, new_canvas) = match &mut self.canvas {
             Some(canvas) => (canvas, false),
             None => {
-                let surface = Surface::new(
-                    self.ctx.clone(),
-                    SurfaceCreateInfo {
-                        config: SurfaceConfiguration {
-                            width: window.size.0,
-                            height: window.size.1,
-                            present_mode: frame.present_settings.present_mode,
-                            format: Format::Bgra8Unorm,
-                        },
-                        window: WindowSource::<winit::window::Window>::Raw {
-                            window: window.window_handle,
-                            display: window.display_handle,
-                        },
-                        debug_name: Some(String::from("primary_surface")),
-                    },
-                )
-                .unwrap();
-
-                let canvas = Canvas::new(
-                    &self.ctx,
-                    surface,
-                    window.size,
-                    frame.present_settings.present_mode,
-                    &self.hzb_render,
-                    &self.ao,
-                );
+                let surface = WindowSurface::new(&self.ctx, window, &frame.present_settings);
+
+                let canvas =
+                    Canvas::new(&self.ctx, surface, window.size, &self.hzb_render, &self.ao);
 
                 self.canvas = Some(canvas);
                 (self.canvas.as_mut().unwrap(), true)
@@ -266,8 +338,10 @@ impl RenderEcs {
             self.bloom.resize(&self.ctx, frame.canvas_size, 6);
             self.sun_shafts.resize(&self.ctx, frame.canvas_size);
             self.smaa.resize(&self.ctx, frame.canvas_size);
+            self.taa.resize(&self.ctx, frame.canvas_size);
             self.path_tracer.resize(&self.ctx, frame.canvas_size);
             self.reflections.resize(&self.ctx, frame.canvas_size);
+            self.particles.resize(&self.ctx, frame.canvas_size);
 
             for frame in 0..FRAMES_IN_FLIGHT {
                 let frame = Frame::from(frame);
@@ -287,7 +361,7 @@ impl RenderEcs {
         let new_shadow_cascades = self.sun_shadows_renderer.update_cascade_settings(
             &self.ctx,
             &self.layouts,
-            frame.lights.global().shadow_cascades(),
+            &frame.shadow_settings,
         );
 
         if new_shadow_cascades {
@@ -302,6 +376,22 @@ impl RenderEcs {
             }
         }
 
+        // Rebind the local shadow atlas if it was resized
+        if self
+            .local_shadows_renderer
+            .update_settings(&self.ctx, &frame.shadow_settings.local)
+        {
+            for i in 0..FRAMES_IN_FLIGHT {
+                let frame = Frame::from(i);
+                self.scene_renderer
+                    .color_pass_sets_mut()
+                    .update_local_shadow_bindings(frame, &self.local_shadows_renderer);
+                self.scene_renderer
+                    .transparent_pass_sets_mut()
+                    .update_local_shadow_bindings(frame, &self.local_shadows_renderer);
+            }
+        }
+
         // Update lights if needed
         if frame.lights.buffer_expanded() || new_shadow_cascades {
             self.scene_renderer
@@ -339,17 +429,58 @@ impl RenderEcs {
             ),
         );
 
-        canvas.acquire_image();
+        canvas.acquire_image(window);
 
         // Reborrow canvas immutably
         let canvas = self.canvas.as_ref().unwrap();
 
+        // Surfaces of windows that closed or were replaced are dropped before acquiring images
+        self.gui_surfaces.retain(|_, surface| {
+            frame
+                .gui_windows
+                .iter()
+                .any(|(_, window)| surface.presents_to(window))
+        });
+
+        for (_, window) in &frame.gui_windows {
+            self.gui_surfaces
+                .entry(window.id)
+                .or_insert_with(|| WindowSurface::new(&self.ctx, window, &frame.present_settings))
+                .acquire_image(window);
+        }
+
+        // The path tracer has its own accumulation, so TAA is skipped while it's presented. The
+        // motion vector view still needs the resolve, but not the jitter.
+        let taa_enabled = frame.taa_settings.enabled && !frame.path_tracer_settings.enabled;
+        let show_motion_vectors = frame.debug_settings.show_motion_vectors;
+        if taa_enabled || show_motion_vectors {
+            self.taa.prepare(frame.frame, canvas.render_target());
+        }
+
+        // The history is only usable if this frame is resolved normally
+        if !taa_enabled || show_motion_vectors {
+            self.taa.invalidate_history();
+        }
+
+        let jitter = if taa_enabled {
+            self.taa.jitter(canvas.size())
+        } else {
+            Vec2::ZERO
+        };
+
         // Update the camera
         let main_camera = match frame.active_cameras.main_camera() {
             Some(camera) => {
                 let (width, height) = canvas.size();
-                self.camera
-                    .update(frame.frame, &camera.camera, width, height, camera.model);
+                self.camera.update(
+                    frame.frame,
+                    &camera.camera,
+                    width,
+                    height,
+                    camera.model,
+                    jitter,
+                    &frame.froxel_settings,
+                );
                 camera
             }
             None => &DEFAULT_ACTIVE_CAMERA,
@@ -362,6 +493,7 @@ impl RenderEcs {
         let texture_factory = self.factory.inner.texture_factory.lock().unwrap();
         let material_factory = self.factory.inner.material_factory.lock().unwrap();
         let meshes = self.factory.inner.meshes.lock().unwrap();
+        let skinned_meshes = self.factory.inner.skinned_meshes.lock().unwrap();
         let materials = self.factory.inner.materials.lock().unwrap();
         let material_instances = self.factory.inner.material_instances.lock().unwrap();
         let mut pending_blas = self.factory.inner.pending_blas.lock().unwrap();
@@ -406,26 +538,72 @@ impl RenderEcs {
             view_location,
         );
 
+        self.local_shadows_renderer.upload(
+            frame.frame,
+            &frame.object_data,
+            &textures,
+            &meshes,
+            &materials,
+            &material_instances,
+            view_location,
+        );
+
         std::mem::drop(textures);
         std::mem::drop(material_instances);
 
         self.rt_render
             .upload(frame.frame, view_location, &frame.object_data, &meshes);
 
+        self.particles
+            .prepare(frame.frame, &frame.particle_emitters, frame.dt);
+
+        self.skinning.prepare(
+            frame.frame,
+            &frame.skinned_meshes,
+            &mesh_factory,
+            &meshes,
+            &skinned_meshes,
+        );
+
+        // Pick shadowed lights before the lights are bound, since it writes their shadow indices
+        self.local_shadows_renderer.update_views(
+            frame.frame,
+            &self.layouts,
+            &frame.shadow_settings.local,
+            &mut frame.lights,
+            &main_camera.camera,
+            main_camera.model,
+            canvas.size(),
+        );
+        frame.local_shadow_statistics = self.local_shadows_renderer.statistics();
+
         // Update sets and bindings
         self.lighting.update_set(frame.frame, &frame.lights);
 
-        self.scene_renderer
-            .update_bindings(frame.frame, &frame.object_data, canvas.hzb());
+        self.scene_renderer.update_bindings(
+            frame.frame,
+            &frame.object_data,
+            canvas.hzb(),
+            &self.culling_stats,
+        );
 
-        self.sun_shadows_renderer
-            .update_bindings(frame.frame, &frame.object_data);
+        self.sun_shadows_renderer.update_bindings(
+            frame.frame,
+            &frame.object_data,
+            &self.culling_stats,
+        );
+
+        self.local_shadows_renderer.update_bindings(
+            frame.frame,
+            &frame.object_data,
+            &self.culling_stats,
+        );
 
         self.entity_renderer.update_bindings(
             frame.frame,
             &frame.object_data,
             canvas.hzb(),
-            canvas.render_target().entity_ids(),
+            &self.culling_stats,
         );
 
         self.path_tracer
@@ -447,13 +625,30 @@ impl RenderEcs {
             main_camera.model,
             canvas.size(),
             frame.lights.global().sun_direction(),
-            frame.lights.global().shadow_cascades(),
+            &frame.shadow_settings,
+            frame.debug_settings.show_shadow_cascades,
         );
 
+        // Draw the shadow cascade frusta before the debug draws are written out
+        if frame.debug_settings.draw_shadow_cascades {
+            for i in 0..self.sun_shadows_renderer.cascade_count() {
+                if let Some(vp) = self.sun_shadows_renderer.cascade_view_proj(frame.frame, i) {
+                    let color = SHADOW_CASCADE_COLORS[i % SHADOW_CASCADE_COLORS.len()];
+                    frame.debug_draws.frustum(vp, color);
+                }
+            }
+        }
+
+        frame.debug_vertices.write_draws(
+            &frame.debug_draws,
+            main_camera.model.right(),
+            main_camera.model.up(),
+        );
+        frame.debug_draws.clear();
+
         self.gui_renderer.prepare(GuiDrawPrepare {
             frame: frame.frame,
-            // We always render to native resolution for the GUI.
-            canvas_size: window.size,
+            token,
             scene_texture: (
                 canvas.render_target().linear_color(),
                 if frame.smaa_settings.enabled == frame.lxaa_settings.enabled {
@@ -467,7 +662,9 @@ impl RenderEcs {
 
         // If path tracing is enabled, we want to use that image instead of
         // the main color image
-        let final_color_src = if frame.path_tracer_settings.enabled {
+        let final_color_src = if show_motion_vectors || taa_enabled {
+            self.taa.image()
+        } else if frame.path_tracer_settings.enabled {
             self.path_tracer.image()
         } else {
             canvas.render_target().final_color()
@@ -487,33 +684,32 @@ impl RenderEcs {
         let mut main_cb = self.ctx.main().command_buffer();
         // let mut compute_cb = self.ctx.main().command_buffer();
 
-        // Build BLAS'
-        pending_blas.to_build().iter().for_each(|blas| {
-            let mesh = match meshes.get(blas.mesh_id) {
-                Some(mesh) => mesh,
-                None => return,
-            };
+        // Culling passes accumulate into the counters of this frame
+        self.culling_stats.reset(&mut main_cb, frame.frame);
 
-            main_cb.build_bottom_level_acceleration_structure(&mesh.blas, &blas.scratch, 0);
-        });
+        // Deform skinned meshes and rebuild their BLAS'
+        self.skinning
+            .skin(frame.frame, &mut main_cb, &skinned_meshes);
 
-        // Compact BLAS'
-        pending_blas
-            .to_compact(frame.frame)
+        // Build BLAS'
+        let to_build: Vec<_> = pending_blas
+            .to_build()
             .iter()
-            .for_each(|blas| {
-                let src = match meshes.get(blas.mesh_id) {
-                    Some(src) => &src.blas,
-                    None => return,
-                };
-
-                let dst = match blas.dst.as_ref() {
-                    Some(dst) => dst,
-                    None => return,
-                };
+            .filter_map(|blas| meshes.get(blas.mesh_id))
+            .map(|mesh| &mesh.blas)
+            .collect();
+        if let Some(scratch) = pending_blas.scratch() {
+            main_cb.build_blas_batch(&to_build, scratch, 0);
+        }
 
-                main_cb.compact_acceleration_structure(src, dst);
-            });
+        // Compact BLAS'
+        main_cb.compact_blas_batch(pending_blas.to_compact(frame.frame).iter().filter_map(
+            |blas| {
+                let src = &meshes.get(blas.mesh_id)?.blas;
+                let dst = blas.dst.as_ref()?;
+                Some((src, dst))
+            },
+        ));
 
         // Build TLAS
         self.rt_render.build(&mut main_cb, frame.frame);
@@ -556,7 +752,7 @@ impl RenderEcs {
 
         // Phase 2:
         //      Main: Render shadows.
-        //      Comp: Generate HZB, generate main draw calls.
+        //      Comp: Generate HZB, generate main draw calls, simulate particles.
         let mut main_cb = self.ctx.main().command_buffer();
         let mut compute_cb = self.ctx.compute().command_buffer();
 
@@ -564,6 +760,7 @@ impl RenderEcs {
             &mut main_cb,
             &frame,
             &self.sun_shadows_renderer,
+            &self.local_shadows_renderer,
             &materials,
             &meshes,
             &mesh_factory,
@@ -574,6 +771,8 @@ impl RenderEcs {
         self.generate_hzb(&mut compute_cb, canvas, &frame);
         self.generate_froxels(&mut compute_cb, &frame);
         self.cluster_lights(&mut compute_cb, &frame);
+        self.particles
+            .simulate(&mut compute_cb, frame.frame, frame.dt);
 
         self.ctx()
             .main()
@@ -584,18 +783,25 @@ impl RenderEcs {
         let mut cb = self.ctx.main().command_buffer();
 
         // Perform the depth prepass
-        Self::depth_prepass(
-            &mut cb,
-            &frame,
-            canvas,
-            &self.camera,
-            &self.scene_renderer,
-            &materials,
-            &meshes,
-            &mesh_factory,
-            &material_factory,
-            &texture_factory,
-        );
+        let statistics = frame.debug_settings.pipeline_statistics;
+        Self::with_statistics(&mut cb, statistics, "depth_prepass", |cb| {
+            Self::depth_prepass(
+                cb,
+                &frame,
+                canvas,
+                &self.camera,
+                &self.scene_renderer,
+                &materials,
+                &meshes,
+                &mesh_factory,
+                &material_factory,
+                &texture_factory,
+            );
+        });
+
+        // Particles fade out against the depth of the scene
+        self.particles
+            .copy_scene_depth(&mut cb, canvas.render_target().final_depth());
 
         // Render entity IDs if requested
         let mut temp_depth = None;
@@ -624,6 +830,13 @@ impl RenderEcs {
             &frame.ao_settings,
         );
 
+        // Nothing reads the AO image until the opaque pass, so the passes in between can run
+        // while the AO writes finish instead of stalling on them at the opaque pass
+        let split_ao_barrier = !frame.debug_settings.disable_split_barriers;
+        if split_ao_barrier {
+            cb.signal_event(Self::ao_event_resources(canvas));
+        }
+
         /*
         // Hand off sun shafts for async compute
         self.sun_shafts
@@ -646,34 +859,45 @@ impl RenderEcs {
             &frame.sun_shafts_settings,
         );
 
+        if split_ao_barrier {
+            cb.wait_event(Self::ao_event_resources(canvas));
+        }
+
         // Render opaque and alpha masked geometry
-        Self::render_opaque(
-            &mut cb,
-            &frame,
-            canvas,
-            &self.camera,
-            &self.scene_renderer,
-            &self.proc_skybox,
-            &materials,
-            &meshes,
-            &mesh_factory,
-            &material_factory,
-            &texture_factory,
-        );
+        Self::with_statistics(&mut cb, statistics, "opaque", |cb| {
+            Self::render_opaque(
+                cb,
+                &frame,
+                canvas,
+                &self.camera,
+                &self.scene_renderer,
+                &self.proc_skybox,
+                &materials,
+                &meshes,
+                &mesh_factory,
+                &material_factory,
+                &texture_factory,
+            );
+        });
 
         // Render transparent geometry
-        Self::render_transparent(
-            &mut cb,
-            &frame,
-            canvas,
-            &self.camera,
-            &self.scene_renderer,
-            &materials,
-            &meshes,
-            &mesh_factory,
-            &material_factory,
-            &texture_factory,
-        );
+        Self::with_statistics(&mut cb, statistics, "transparent", |cb| {
+            Self::render_transparent(
+                cb,
+                &frame,
+                canvas,
+                &self.camera,
+                &self.scene_renderer,
+                &self.particles,
+                &self.debug_renderer,
+                &frame.debug_vertices,
+                &materials,
+                &meshes,
+                &mesh_factory,
+                &material_factory,
+                &texture_factory,
+            );
+        });
 
         // Render reflections
         self.reflections.render(
@@ -699,7 +923,7 @@ impl RenderEcs {
         );
         */
 
-        self.ctx().main().submit(Some("Phase 3"), cb);
+        frame.statistics_job = Some(self.ctx().main().submit(Some("Phase 3"), cb));
 
         std::mem::drop(mesh_factory);
         std::mem::drop(texture_factory);
@@ -774,14 +998,35 @@ impl RenderEcs {
             ),
         };
 
-        // Apply image effects to the final render target
-        self.bloom.render(frame.frame, &mut cb);
+        // Resolve TAA before image effects so they see the anti-aliased HDR image
+        if taa_enabled || show_motion_vectors {
+            self.taa.render(
+                frame.frame,
+                &mut cb,
+                &frame.taa_settings,
+                main_camera.camera.near,
+                show_motion_vectors,
+            );
+        }
+
+        // Apply image effects to the final render target. Motion vectors are shown as is.
+        let mut tonemapping_settings = frame.tonemapping_settings;
+        tonemapping_settings.bypass |= show_motion_vectors;
+        if !tonemapping_settings.bypass {
+            self.bloom.render(
+                frame.frame,
+                &mut cb,
+                tonemapping_settings.bloom_threshold,
+                tonemapping_settings.bloom_knee,
+            );
+        }
         self.tonemapping.render(
             frame.frame,
             &mut cb,
             &self.camera,
             tonemapping_dst,
-            &frame.tonemapping_settings,
+            &tonemapping_settings,
+            canvas.surface().color_space(),
             frame.dt,
         );
 
@@ -811,6 +1056,8 @@ impl RenderEcs {
                 depth_stencil_attachment: None,
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("debug_drawing"),
             |pass| {
@@ -831,30 +1078,59 @@ impl RenderEcs {
                 color_resolve_attachments: Vec::default(),
                 depth_stencil_attachment: None,
                 depth_stencil_resolve_attachment: None,
+                view_mask: 0,
+                correlation_masks: Vec::default(),
             },
             Some("gui_rendering"),
             |pass| {
-                self.gui_renderer.render(frame.frame, window.size, pass);
+                self.gui_renderer
+                    .render(frame.frame, ViewportId::ROOT, pass);
             },
         );
 
+        // Render detached GUI viewports into their own windows
+        for (viewport, window) in &frame.gui_windows {
+            cb.render_pass(
+                RenderPassDescriptor {
+                    color_attachments: vec![ColorAttachment {
+                        dst: ColorAttachmentDestination::SurfaceImage(
+                            self.gui_surfaces[&window.id].image(),
+                        ),
+                        load_op: LoadOp::Clear(ClearColor::RgbaF32(0.0, 0.0, 0.0, 1.0)),
+                        store_op: StoreOp::Store,
+                        samples: MultiSamples::Count1,
+                    }],
+                    color_resolve_attachments: Vec::default(),
+                    depth_stencil_attachment: None,
+                    depth_stencil_resolve_attachment: None,
+                    view_mask: 0,
+                    correlation_masks: Vec::default(),
+                },
+                Some("gui_viewport_rendering"),
+                |pass| {
+                    self.gui_renderer.render(frame.frame, *viewport, pass);
+                },
+            );
+        }
+
         // Submit for rendering
         frame.job = Some(self.ctx.main().submit(Some("primary"), cb));
 
-        // If we selected an entity this frame, read it back
-        if frame.select_entity.is_some() {
-            if let Some(entity) = self.entity_renderer.read_back_selected_entity() {
-                frame.selected_entity = Some(EntitySelected(entity));
-            }
-            frame.select_entity = None;
-        }
+        // Picks are read back the next time this frame is rendered, once the GPU is done with it
+        frame.pick_issued = frame.select_entity.take().is_some();
 
         // Reborrow canvas as mut
         let canvas = self.canvas.as_mut().unwrap();
 
-        // Present the surface image
+        // Present the surface images
         canvas.present(&self.ctx, window.size);
 
+        for (_, window) in &frame.gui_windows {
+            if let Some(surface) = self.gui_surfaces.get_mut(&window.id) {
+                surface.present(&self.ctx, window.size);
+            }
+        }
+
         frame
     }
 
@@ -957,8 +1233,26 @@ impl RenderEcs {
     fn cluster_lights<'a>(&'a self, commands: &mut CommandBuffer<'a>, frame_data: &FrameData) {
         puffin::profile_function!();
 
-        self.lighting
-            .cluster(commands, frame_data.frame, &self.camera);
+        self.lighting.cluster(
+            commands,
+            frame_data.frame,
+            &self.camera,
+            &frame_data.froxel_settings,
+        );
+    }
+
+    /// Records `region` within a pipeline statistics region if `enabled` is set.
+    fn with_statistics<'a>(
+        commands: &mut CommandBuffer<'a>,
+        enabled: bool,
+        label: &'a str,
+        region: impl FnOnce(&mut CommandBuffer<'a>),
+    ) {
+        if enabled {
+            commands.statistics(label, region);
+        } else {
+            region(commands);
+        }
     }
 
     #[inline(never)]
@@ -1053,6 +1347,9 @@ impl RenderEcs {
                     queue_types: QueueTypes::MAIN,
                     sharing_mode: SharingMode::Exclusive,
                     debug_name: Some("entity_id_pass_depth_buffer".into()),
+                    sparse: false,
+                    initial_data: None,
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -1068,6 +1365,8 @@ impl RenderEcs {
             load_op: LoadOp::Clear(ClearColor::D32S32(0.0, 0)),
             store_op: StoreOp::DontCare,
             samples: MultiSamples::Count1,
+            stencil_load_op: LoadOp::DontCare,
+            stencil_store_op: StoreOp::DontCare,
         });
 
         commands.render_pass(pass, Some("entity_id_pass"), |pass| {
@@ -1088,7 +1387,23 @@ impl RenderEcs {
             );
         });
 
-        entity_render.select_entity(commands, uv);
+        entity_render.pick_entity(
+            frame_data.frame,
+            commands,
+            canvas.render_target().entity_ids(),
+            uv,
+        );
+    }
+
+    /// The AO image, which is synchronized with a split barrier between generating it and the
+    /// opaque pass.
+    fn ao_event_resources(canvas: &Canvas) -> Vec<EventResource<'_>> {
+        let texture = canvas.ao().texture();
+        vec![EventResource::Texture {
+            texture,
+            array_elements: 0..1,
+            mips: 0..texture.mip_count(),
+        }]
     }
 
     #[inline(never)]
@@ -1124,6 +1439,7 @@ impl RenderEcs {
         commands: &mut CommandBuffer<'a>,
         frame_data: &FrameData,
         shadow_renderer: &'a SunShadowsRenderer,
+        local_shadows_renderer: &'a LocalShadowsRenderer,
         materials: &'a ResourceAllocator<MaterialResource>,
         meshes: &'a ResourceAllocator<MeshResource>,
         mesh_factory: &'a MeshFactory,
@@ -1147,6 +1463,18 @@ impl RenderEcs {
             );
         }
 
+        local_shadows_renderer.render(
+            frame_data.frame,
+            LocalShadowRenderArgs {
+                commands,
+                mesh_factory,
+                material_factory,
+                texture_factory,
+                meshes,
+                materials,
+            },
+        );
+
         // shadow_renderer.transfer_ownership(frame_data.frame, commands, QueueType::Compute);
     }
 
@@ -1211,6 +1539,9 @@ impl RenderEcs {
         canvas: &'a Canvas,
         camera: &'a CameraUbo,
         scene_render: &'a SceneRenderer,
+        particles: &'a ParticleRenderer,
+        debug_render: &'a DebugRenderer,
+        debug_vertices: &'a DebugVertexBuffer,
         materials: &'a ResourceAllocator<MaterialResource>,
         meshes: &'a ResourceAllocator<MeshResource>,
         mesh_factory: &'a MeshFactory,
@@ -1241,6 +1572,11 @@ impl RenderEcs {
                         materials,
                     },
                 );
+
+                particles.render(frame_data.frame, pass, camera);
+
+                // Depth tested debug shapes are drawn here since the scene depth is bound
+                debug_render.render_depth_tested(frame_data.frame, pass, debug_vertices, camera);
             },
         );
     }
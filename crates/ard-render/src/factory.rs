@@ -8,9 +8,19 @@ use crate::{
     staging::{Staging, StagingRequest, StagingResource},
 };
 use ard_ecs::prelude::*;
-use ard_formats::{mesh::MeshData, meshlet::Meshlet, texture::TextureSource};
-use ard_pal::prelude::{Buffer, Context, QueueType};
-use ard_render_base::{resource::ResourceAllocator, Frame};
+use ard_formats::{
+    mesh::MeshData,
+    meshlet::Meshlet,
+    texture::{MipType, TextureSource},
+};
+use ard_pal::prelude::{
+    AnisotropyLevel, Context, Format, QueueType, StagingBelt, StagingBeltCreateInfo,
+    StagingTextureUpload, SubmitError, TextureUsage,
+};
+use ard_render_base::{
+    resource::{ResourceAllocator, ResourceId},
+    Frame,
+};
 use ard_render_material::{
     factory::{MaterialFactory, MaterialFactoryConfig},
     material::{Material, MaterialCreateError, MaterialCreateInfo, MaterialResource},
@@ -23,10 +33,11 @@ use ard_render_material::{
 use ard_render_meshes::{
     factory::{MeshFactory, MeshFactoryConfig},
     mesh::{Mesh, MeshCreateError, MeshCreateInfo, MeshResource},
+    skinned::{SkinnedMesh, SkinnedMeshCreateError, SkinnedMeshResource},
 };
 use ard_render_si::{bindings::Layouts, consts::*};
 use ard_render_textures::{
-    factory::{MipUpdate, TextureFactory, TextureMipUpload},
+    factory::{MipUpdate, TextureFactory},
     texture::{Texture, TextureCreateError, TextureCreateInfo, TextureResource},
 };
 use bytemuck::{Pod, Zeroable};
@@ -37,9 +48,13 @@ pub const MAX_SHADERS: usize = 1024;
 pub const MAX_MATERIALS: usize = 512;
 pub const MAX_MATERIAL_INSTANCES: usize = 2048;
 pub const MAX_MESHES: usize = 2048;
+pub const MAX_SKINNED_MESHES: usize = 512;
 pub const MAX_CUBE_MAPS: usize = 128;
 pub const MAX_CAMERAS: usize = 32;
 
+/// Size of each chunk of staging memory texture data is uploaded through.
+const TEXTURE_BELT_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
 #[derive(Clone, Resource)]
 pub struct Factory {
     pbr_material: Material,
@@ -53,10 +68,13 @@ pub(crate) struct FactoryInner {
     pub(crate) texture_factory: Mutex<TextureFactory>,
     pub(crate) material_factory: Mutex<MaterialFactory>,
     pub(crate) meshes: Mutex<ResourceAllocator<MeshResource>>,
+    pub(crate) skinned_meshes: Mutex<ResourceAllocator<SkinnedMeshResource>>,
     pub(crate) materials: Mutex<ResourceAllocator<MaterialResource>>,
     pub(crate) material_instances: Mutex<ResourceAllocator<MaterialInstanceResource>>,
     pub(crate) pending_blas: Mutex<PendingBlasBuilder>,
     staging: Mutex<Staging>,
+    /// Uploads texture mips, keyed by the id and version of the texture they're for.
+    texture_belt: StagingBelt<(ResourceId, u32)>,
     ctx: Context,
 }
 
@@ -64,7 +82,19 @@ impl Factory {
     pub(crate) fn new(ctx: Context, layouts: &Layouts) -> Self {
         let inner = Arc::new(FactoryInner {
             staging: Mutex::new(Staging::new(ctx.clone())),
+            texture_belt: StagingBelt::new(
+                ctx.clone(),
+                StagingBeltCreateInfo {
+                    chunk_size: TEXTURE_BELT_CHUNK_SIZE,
+                    debug_name: Some(String::from("texture_staging_belt")),
+                },
+            ),
             meshes: Mutex::new(ResourceAllocator::new(MAX_MESHES, DROP_LATENCY, false)),
+            skinned_meshes: Mutex::new(ResourceAllocator::new(
+                MAX_SKINNED_MESHES,
+                DROP_LATENCY,
+                false,
+            )),
             textures: Mutex::new(ResourceAllocator::new(MAX_TEXTURES, DROP_LATENCY, false)),
             shaders: Mutex::new(ResourceAllocator::new(MAX_SHADERS, DROP_LATENCY, false)),
             materials: Mutex::new(ResourceAllocator::new(MAX_MATERIALS, DROP_LATENCY, true)),
@@ -135,6 +165,7 @@ impl Factory {
         let mut texture_factory = self.inner.texture_factory.lock().unwrap();
         let mut material_factory = self.inner.material_factory.lock().unwrap();
         let mut static_meshes = self.inner.meshes.lock().unwrap();
+        let mut skinned_meshes = self.inner.skinned_meshes.lock().unwrap();
         let mut materials = self.inner.materials.lock().unwrap();
         let mut material_instances = self.inner.material_instances.lock().unwrap();
         let mut pending_blas = self.inner.pending_blas.lock().unwrap();
@@ -142,6 +173,13 @@ impl Factory {
 
         // Check for new upload requests
         staging.upload(&mut mesh_factory, &textures, &static_meshes);
+        self.inner.texture_belt.flush(|&(id, version)| {
+            // Textures that were dropped or replaced don't need their data anymore
+            if textures.version_of(id)? != version {
+                return None;
+            }
+            textures.get(id).map(|texture| &texture.texture)
+        });
 
         // Check if any uploads are complete, and if they are, handle them appropriately
         staging.flush_complete_uploads(false, |resc| match resc {
@@ -159,7 +197,7 @@ impl Factory {
                 let mesh = static_meshes.get_mut(id).unwrap();
 
                 mesh.mesh_ready = true;
-                pending_blas.append(id, mesh.blas_scratch.take().unwrap());
+                pending_blas.append(id);
             }
             StagingResource::Texture {
                 id,
@@ -226,7 +264,15 @@ impl Factory {
         mesh_factory.flush_mesh_info(frame);
         mesh_factory.check_rebind(frame);
 
-        // Drop pending resources
+        // Drop pending resources. Skinned meshes go first since they hold on to their meshes.
+        skinned_meshes.drop_pending(
+            frame,
+            |_, skinned| {
+                mesh_factory.free_vertices(skinned.vertex_block);
+            },
+            |_, _| {},
+            |_, _| true,
+        );
         static_meshes.drop_pending(
             frame,
             |_, mesh| {
@@ -275,6 +321,12 @@ impl Factory {
         self.inner.create_mesh(create_info)
     }
 
+    /// Creates an instance of a skinned mesh. The mesh must have been created with joints and
+    /// weights.
+    pub fn create_skinned_mesh(&self, mesh: &Mesh) -> Result<SkinnedMesh, SkinnedMeshCreateError> {
+        self.inner.create_skinned_mesh(mesh)
+    }
+
     pub fn create_texture<T: TextureSource>(
         &self,
         create_info: TextureCreateInfo<T>,
@@ -333,6 +385,33 @@ impl Factory {
         self.inner
             .set_material_texture_slot(material_instance, slot, texture)
     }
+
+    /// Sets the anisotropy level used by textures that enable anisotropic filtering. The level
+    /// is clamped to what the device supports. Every loaded texture is rebound with the new
+    /// level over the next few frames, so materials don't need to be recreated.
+    pub fn set_texture_anisotropy(&self, level: Option<AnisotropyLevel>) {
+        self.inner.ctx.set_default_anisotropy(level);
+        let textures = self.inner.textures.lock().unwrap();
+        self.inner
+            .texture_factory
+            .lock()
+            .unwrap()
+            .refresh_samplers(&textures);
+    }
+
+    /// Returns the error that caused the device to be lost, if it has been. Nothing will be
+    /// rendered after this point, so the application should save what it can and exit.
+    #[inline(always)]
+    pub fn device_lost(&self) -> Option<SubmitError> {
+        self.inner.ctx.device_lost()
+    }
+
+    /// Asks RenderDoc to capture the next presented frame. Does nothing if RenderDoc isn't
+    /// attached. See [`Context::trigger_capture`].
+    #[inline(always)]
+    pub fn trigger_capture(&self) {
+        self.inner.ctx.trigger_capture();
+    }
 }
 
 impl FactoryInner {
@@ -365,19 +444,36 @@ impl FactoryInner {
         staging.add(StagingRequest::Mesh {
             id: handle.id(),
             version,
-            upload,
+            upload: Box::new(upload),
         });
         std::mem::drop(staging);
 
         Ok(Mesh::new(handle, layout, blas_ref, bounds))
     }
 
+    fn create_skinned_mesh(&self, mesh: &Mesh) -> Result<SkinnedMesh, SkinnedMeshCreateError> {
+        let mut mesh_factory = self.mesh_factory.lock().unwrap();
+        let static_meshes = self.meshes.lock().unwrap();
+
+        let skinned = SkinnedMeshResource::new(mesh, &static_meshes, &self.ctx, &mut mesh_factory)?;
+        std::mem::drop(static_meshes);
+        std::mem::drop(mesh_factory);
+
+        let blas_ref = skinned.blas_ref.clone();
+        let rebase = skinned.rebase;
+        let handle = self.skinned_meshes.lock().unwrap().insert(skinned);
+
+        Ok(SkinnedMesh::new(handle, mesh.clone(), blas_ref, rebase))
+    }
+
     fn create_texture<T: TextureSource>(
         &self,
         create_info: TextureCreateInfo<T>,
     ) -> Result<Texture, TextureCreateError<T>> {
         // Create the texture instance
         let (texture, upload) = TextureResource::new(&self.ctx, create_info)?;
+        let dims = texture.texture.dims();
+        let mip_levels = texture.mip_levels;
 
         // Create the resource handle
         let mut textures = self.textures.lock().unwrap();
@@ -387,13 +483,48 @@ impl FactoryInner {
         std::mem::drop(textures);
 
         // Submit the upload request
-        let mut staging = self.staging.lock().unwrap();
-        staging.add(StagingRequest::Texture {
-            id: handle.id(),
-            version,
-            upload,
-        });
-        std::mem::drop(staging);
+        let id = handle.id();
+        match upload.mip_type {
+            MipType::Generate => {
+                // The highest detail mip is uploaded first, and the rest of the chain is
+                // generated from it once the upload is complete
+                let staging_handle = self.texture_belt.upload_texture(mip_upload(
+                    (id, version),
+                    upload.data.format(),
+                    dims,
+                    0,
+                    upload.data.raw(),
+                ))?;
+
+                self.staging.lock().unwrap().add_after_belt_upload(
+                    staging_handle,
+                    StagingRequest::Texture {
+                        id,
+                        version,
+                        loaded_mips: upload.loaded_mips,
+                    },
+                );
+            }
+            MipType::Upload(_, _) => {
+                let mip_level = mip_levels - 1;
+                let staging_handle = self.texture_belt.upload_texture(mip_upload(
+                    (id, version),
+                    upload.data.format(),
+                    dims,
+                    mip_level,
+                    upload.data.raw(),
+                ))?;
+
+                self.staging.lock().unwrap().add_belt_upload(
+                    staging_handle,
+                    StagingResource::Texture {
+                        id,
+                        version,
+                        loaded_mips: upload.loaded_mips,
+                    },
+                );
+            }
+        }
 
         Ok(Texture::new(handle))
     }
@@ -450,6 +581,8 @@ impl FactoryInner {
     }
 
     fn load_texture_mip(&self, texture: &Texture, level: usize, source: impl TextureSource) {
+        puffin::profile_function!();
+
         let data = source.into_texture_data().unwrap();
 
         let id = texture.id();
@@ -466,25 +599,29 @@ impl FactoryInner {
         assert_eq!(data.width(), width >> level);
         assert_eq!(data.height(), height >> level);
         assert_eq!(data.format(), texture_inner.texture.format());
+        let format = texture_inner.texture.format();
         std::mem::drop(textures);
 
-        let staging_buffer = Buffer::new_staging(
-            self.ctx.clone(),
-            QueueType::Transfer,
-            Some(format!("texture_{id:?}_mip_level_{level}_staging")),
-            data.raw(),
-        )
-        .unwrap();
+        let staging_handle = self
+            .texture_belt
+            .upload_texture(mip_upload(
+                (id, version),
+                format,
+                (width, height, 1),
+                level as u32,
+                data.raw(),
+            ))
+            .unwrap();
 
         let mut staging = self.staging.lock().unwrap();
-        staging.add(StagingRequest::TextureMip {
-            id,
-            version,
-            upload: TextureMipUpload {
-                staging: staging_buffer,
+        staging.add_belt_upload(
+            staging_handle,
+            StagingResource::TextureMip {
+                id,
+                version,
                 mip_level: level as u32,
             },
-        });
+        );
     }
 
     fn set_material_data(
@@ -532,3 +669,28 @@ impl FactoryInner {
         }
     }
 }
+
+/// Describes the upload of a mip level of a texture through the texture staging belt. The mip is
+/// handed to the main queue, ready to be sampled.
+fn mip_upload(
+    key: (ResourceId, u32),
+    format: Format,
+    dims: (u32, u32, u32),
+    mip_level: u32,
+    data: &[u8],
+) -> StagingTextureUpload<'_, (ResourceId, u32)> {
+    StagingTextureUpload {
+        texture: key,
+        format,
+        extent: (
+            (dims.0 >> mip_level).max(1),
+            (dims.1 >> mip_level).max(1),
+            1,
+        ),
+        mip_level: mip_level as usize,
+        array_element: 0,
+        data,
+        usage: TextureUsage::SAMPLED,
+        owner: Some(QueueType::Main),
+    }
+}
@@ -4,19 +4,24 @@ use ard_core::stat::DirtyStaticListener;
 use ard_ecs::prelude::*;
 use ard_pal::prelude::*;
 use ard_render_base::Frame;
-use ard_render_camera::active::ActiveCameras;
-use ard_render_debug::buffer::DebugVertexBuffer;
+use ard_render_camera::{active::ActiveCameras, froxels::FroxelSettings};
+use ard_render_debug::{buffer::DebugVertexBuffer, DebugDrawing};
 use ard_render_gui::GuiRunOutput;
 use ard_render_image_effects::{
     ao::AoSettings, lxaa::LxaaSettings, smaa::SmaaSettings, sun_shafts2::SunShaftsSettings,
-    tonemapping::TonemappingSettings,
+    taa::TaaSettings, tonemapping::TonemappingSettings,
+};
+use ard_render_lighting::{
+    lights::Lights, local_shadows::LocalShadowStatistics, shadows::ShadowSettings,
 };
-use ard_render_lighting::lights::Lights;
 use ard_render_objects::objects::RenderObjects;
+use ard_render_particles::{emitters::ParticleEmitters, ParticleStatistics};
 use ard_render_renderers::{
-    entities::{EntitySelected, SelectEntity},
-    pathtracer::PathTracerSettings,
+    culling::CullingStatistics, entities::SelectEntity, pathtracer::PathTracerSettings,
+    skinning::SkinnedMeshes,
 };
+use ard_window::prelude::WindowId;
+use egui::ViewportId;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 
 use crate::{DebugSettings, MsaaSettings, PresentationSettings};
@@ -32,37 +37,72 @@ pub struct FrameDataInner {
     pub present_scene: bool,
     /// Listener for dirty static objects.
     pub dirty_static: DirtyStaticListener,
-    /// The job of the currently processing frame.
+    /// The job of the currently processing frame. Handed off to `Context::end_frame` once the
+    /// frame is submitted.
     pub job: Option<Job>,
+    /// Time the render thread spent waiting on the GPU before this frame could begin.
+    pub gpu_wait_time: Duration,
+    /// The job containing the pipeline statistics regions of the currently processing frame.
+    pub statistics_job: Option<Job>,
+    /// Pipeline statistics read back from the last frame that completed.
+    pub pipeline_statistics: Vec<(String, PipelineStatistics)>,
     /// Gui output to be rendered.
     pub gui_output: GuiRunOutput,
     /// Object data captured from the primary ECS.
     pub object_data: RenderObjects,
     /// Lights captured from the primary ECS.
     pub lights: Lights,
+    /// Particle emitters captured from the primary ECS.
+    pub particle_emitters: ParticleEmitters,
+    /// Particle usage read back from the last time this frame was rendered.
+    pub particle_statistics: ParticleStatistics,
+    /// Culling counters of each culling pass read back from the last time this frame was
+    /// rendered.
+    pub culling_statistics: Vec<(String, CullingStatistics)>,
+    /// Local shadow atlas usage of the last time this frame was rendered.
+    pub local_shadow_statistics: LocalShadowStatistics,
+    /// Skinned meshes and their joint palettes captured from the primary ECS.
+    pub skinned_meshes: SkinnedMeshes,
+    /// Debug shapes captured from the primary ECS.
+    pub debug_draws: DebugDrawing,
     /// Debug drawing vertex buffer.
     pub debug_vertices: DebugVertexBuffer,
     pub present_settings: PresentationSettings,
+    pub shadow_settings: ShadowSettings,
+    pub froxel_settings: FroxelSettings,
     pub tonemapping_settings: TonemappingSettings,
     pub ao_settings: AoSettings,
     pub sun_shafts_settings: SunShaftsSettings,
     pub smaa_settings: SmaaSettings,
     pub lxaa_settings: LxaaSettings,
+    pub taa_settings: TaaSettings,
     pub msaa_settings: MsaaSettings,
     pub debug_settings: DebugSettings,
     pub path_tracer_settings: PathTracerSettings,
+    /// Point on the canvas to pick an entity at this frame.
     pub select_entity: Option<SelectEntity>,
-    pub selected_entity: Option<EntitySelected>,
+    /// Set on the render thread when this frame picked an entity, so the result can be read back
+    /// the next time the frame is rendered.
+    pub pick_issued: bool,
+    /// Result of the pick read back this frame. The inner value is `None` if there was no entity
+    /// at the point.
+    pub picked: Option<Option<Entity>>,
     /// Active cameras captured from the primary ECS.
     pub active_cameras: ActiveCameras,
     /// Physical size of the surface window for this frame.
     pub window: Option<WindowInfo>,
+    /// Windows of detached GUI viewports, which are presented to alongside the surface window.
+    pub gui_windows: Vec<(ViewportId, WindowInfo)>,
     /// The requested canvas size for this frame.
     pub canvas_size: (u32, u32),
 }
 
 pub struct WindowInfo {
+    /// The window being presented to.
+    pub id: WindowId,
     pub size: (u32, u32),
+    /// If presentation should wait for vertical blanking.
+    pub vsync: bool,
     pub window_handle: RawWindowHandle,
     pub display_handle: RawDisplayHandle,
 }
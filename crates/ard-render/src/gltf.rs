@@ -0,0 +1,208 @@
+use ard_formats::{
+    import::ImportSettings,
+    mesh::{MeshData, MeshDataBuilder},
+    meshlet::MeshletStats,
+    vertex::{VertexAttribute, VertexLayout},
+};
+use ard_gltf::{GltfImportItem, GltfImportStream, GltfMesh, GltfModelParseError};
+use ard_math::{Mat4, Vec3, Vec4};
+use ard_render_meshes::mesh::{Mesh, MeshCreateError, MeshCreateInfo};
+use thiserror::Error;
+
+use crate::factory::Factory;
+
+/// Uploads the meshes of a [`GltfImportStream`] as they're decoded, so they can be rendered
+/// before the rest of the model has loaded.
+pub struct GltfMeshUploader {
+    stream: GltfImportStream,
+    /// Meshes uploaded so far, indexed by their index in the stream.
+    meshes: Vec<Option<Mesh>>,
+    settings: ImportSettings,
+    meshlets: MeshletStats,
+}
+
+#[derive(Debug, Error)]
+pub enum GltfUploadError {
+    #[error("unable to load item: {0}")]
+    Load(#[from] GltfModelParseError),
+    #[error("unable to create mesh {index}: {err}")]
+    Mesh { index: usize, err: MeshCreateError },
+}
+
+impl GltfMeshUploader {
+    /// `settings` are used to build the meshlets and levels of detail of every mesh.
+    pub fn new(stream: GltfImportStream, settings: ImportSettings) -> Self {
+        let mut meshes = Vec::with_capacity(stream.mesh_count);
+        meshes.resize_with(stream.mesh_count, || None);
+        Self {
+            stream,
+            meshes,
+            settings,
+            meshlets: MeshletStats::default(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn stream(&self) -> &GltfImportStream {
+        &self.stream
+    }
+
+    /// Meshes uploaded so far, indexed by their index in the stream. Meshes that haven't been
+    /// decoded yet or failed to upload are `None`.
+    #[inline(always)]
+    pub fn meshes(&self) -> &[Option<Mesh>] {
+        &self.meshes
+    }
+
+    /// Meshlets built for the meshes decoded so far.
+    #[inline(always)]
+    pub fn meshlet_stats(&self) -> MeshletStats {
+        self.meshlets
+    }
+
+    /// `true` once every item has been received from the stream.
+    #[inline(always)]
+    pub fn is_finished(&self) -> bool {
+        self.stream.is_finished()
+    }
+
+    /// Uploads every mesh that has been decoded since the last poll without waiting for more.
+    ///
+    /// Materials and textures are returned for the caller to create, since how images are
+    /// decoded is up to the caller.
+    pub fn poll(&mut self, factory: &Factory) -> Vec<Result<GltfImportItem, GltfUploadError>> {
+        let mut items = Vec::default();
+
+        while let Some(item) = self.stream.try_next() {
+            let (index, mesh) = match item {
+                Ok(GltfImportItem::Mesh { index, mesh }) => (index, mesh),
+                other => {
+                    items.push(other.map_err(GltfUploadError::from));
+                    continue;
+                }
+            };
+
+            let data = match gltf_mesh_data(mesh, &self.settings) {
+                Ok(data) => data,
+                Err(err) => {
+                    items.push(Err(GltfUploadError::Mesh { index, err }));
+                    continue;
+                }
+            };
+            let upload_size = upload_size(&data);
+            self.meshlets.merge(&MeshletStats::from_meshlets(
+                data.meshlets(),
+                &self.settings.meshlets,
+            ));
+
+            match factory.create_mesh(MeshCreateInfo {
+                debug_name: Some(format!("gltf_mesh_{index}")),
+                data,
+            }) {
+                Ok(mesh) => {
+                    self.meshes[index] = Some(mesh);
+                    self.stream.progress_handle().report_upload(upload_size);
+                }
+                Err(err) => items.push(Err(GltfUploadError::Mesh { index, err })),
+            }
+        }
+
+        items
+    }
+
+    /// Stops the import and destroys every mesh that was uploaded.
+    #[inline(always)]
+    pub fn cancel(self) {
+        // Dropping the stream cancels it, and dropping the meshes releases them like any other
+        std::mem::drop(self);
+    }
+
+    /// Takes the uploaded meshes. If the stream isn't finished, the rest of the import is
+    /// cancelled.
+    #[inline(always)]
+    pub fn into_meshes(self) -> Vec<Option<Mesh>> {
+        self.meshes
+    }
+}
+
+/// Converts an imported mesh into the data used by the renderer. GLTF uses the opposite
+/// handedness, so positions, normals, and tangents are mirrored.
+fn gltf_mesh_data(
+    mut mesh: GltfMesh,
+    settings: &ImportSettings,
+) -> Result<MeshData, MeshCreateError> {
+    if mesh.positions.is_empty() {
+        return Err(MeshCreateError::NoVertices);
+    }
+
+    if mesh.indices.is_empty() {
+        return Err(MeshCreateError::NoIndices);
+    }
+
+    let inv_handed = Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+    let mirror = |vectors: &mut Vec<Vec4>| vectors.iter_mut().for_each(|v| *v = inv_handed * *v);
+    mirror(&mut mesh.positions);
+    if let Some(normals) = &mut mesh.normals {
+        mirror(normals);
+    }
+    if let Some(tangents) = &mut mesh.tangents {
+        mirror(tangents);
+    }
+
+    let mut layout = VertexLayout::POSITION | VertexLayout::NORMAL;
+    if mesh.tangents.is_some() {
+        layout |= VertexLayout::TANGENT;
+    }
+    if mesh.uv0.is_some() {
+        layout |= VertexLayout::UV0;
+    }
+    if mesh.uv1.is_some() {
+        layout |= VertexLayout::UV1;
+    }
+    if mesh.joints.is_some() && mesh.weights.is_some() {
+        layout |= VertexLayout::SKIN;
+    }
+
+    let vertex_count = mesh.positions.len();
+    let mut builder = MeshDataBuilder::new(layout, vertex_count, mesh.indices.len())
+        .add_positions(&mesh.positions)
+        .add_indices(&mesh.indices)
+        .meshlet_settings(settings.meshlets);
+
+    if let Some(lods) = settings.lods {
+        builder = builder.lod_settings(lods);
+    }
+
+    builder = match &mesh.normals {
+        Some(normals) => builder.add_vec4_normals(normals),
+        None => builder.add_vec4_normals(&vec![Vec4::new(0.0, 0.0, 1.0, 0.0); vertex_count]),
+    };
+
+    if let Some(tangents) = &mesh.tangents {
+        builder = builder.add_vec4_tangents(tangents);
+    }
+
+    if let Some(uv0) = &mesh.uv0 {
+        builder = builder.add_vec2_uvs(uv0, 0);
+    }
+
+    if let Some(uv1) = &mesh.uv1 {
+        builder = builder.add_vec2_uvs(uv1, 1);
+    }
+
+    if let (Some(joints), Some(weights)) = (&mesh.joints, &mesh.weights) {
+        builder = builder.add_joints(joints).add_weights(weights);
+    }
+
+    Ok(builder.build())
+}
+
+/// Number of bytes of vertex and index data uploaded for a mesh.
+fn upload_size(data: &MeshData) -> u64 {
+    let vertex_size: usize = data
+        .layout()
+        .iter()
+        .map(|bit| VertexAttribute::try_from(bit).unwrap().size())
+        .sum();
+    (vertex_size * data.vertex_count() + MeshData::INDEX_SIZE * data.index_count()) as u64
+}
@@ -1,11 +1,14 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
 use ard_core::prelude::*;
 use ard_ecs::prelude::*;
+use ard_math::Vec2;
 use ard_pal::prelude::*;
 use ard_render_debug::DebugDrawing;
-use ard_render_gui::{Gui, GuiInputCaptureSystem};
-use ard_render_lighting::global::GlobalLighting;
+use ard_render_gui::{Gui, GuiInputCapture, GuiInputCaptureSystem};
+use ard_render_lighting::{global::GlobalLighting, local_shadows::LocalShadowStatistics};
+use ard_render_particles::ParticleStatistics;
+use ard_render_renderers::culling::CullingStatistics;
 use ard_window::prelude::*;
 use system::RenderSystem;
 
@@ -14,12 +17,16 @@ pub mod canvas;
 pub mod ecs;
 pub mod factory;
 pub mod frame;
+pub mod gltf;
 pub mod staging;
+pub mod surface;
 pub mod system;
+pub use ard_render_camera::froxels::{DepthSlicing, FroxelSettings};
 pub use ard_render_image_effects::{
     ao::AoSettings, lxaa::LxaaSettings, smaa::SmaaSettings, sun_shafts2::SunShaftsSettings,
-    tonemapping::TonemappingSettings,
+    taa::TaaSettings, tonemapping::TonemappingSettings,
 };
+pub use ard_render_lighting::shadows::ShadowSettings;
 pub use ard_render_renderers::pathtracer::PathTracerSettings;
 
 #[derive(Clone, Copy)]
@@ -29,8 +36,12 @@ pub struct RendererSettings {
     pub present_scene: bool,
     /// Time between frame draws. `None` indicates no render limiting.
     pub render_time: Option<Duration>,
-    /// Preferred presentation mode.
+    /// Preferred presentation mode. Only used while the window has vsync enabled. Otherwise,
+    /// [`PresentMode::Immediate`] is used.
     pub present_mode: PresentMode,
+    /// Preferred color space of presented images. Falls back to [`ColorSpace::SrgbNonlinear`] if
+    /// the display doesn't support it.
+    pub output_color_space: ColorSpace,
     /// Super resolution scale factor. A value of `1.0` means no super sampling is performed.
     pub render_scale: f32,
     /// Width and height of the renderer image. `None` indicates the dimensions should match that
@@ -45,6 +56,7 @@ pub struct CanvasSize(pub Option<(u32, u32)>);
 #[derive(Resource, Default, Clone, Copy)]
 pub struct PresentationSettings {
     pub present_mode: PresentMode,
+    pub output_color_space: ColorSpace,
     /// Time between frame draws. `None` indicates no render limiting.
     pub render_time: Option<Duration>,
 }
@@ -52,6 +64,86 @@ pub struct PresentationSettings {
 #[derive(Resource, Default, Clone, Copy)]
 pub struct DebugSettings {
     pub lock_culling: bool,
+    /// Collect pipeline statistics for the main render passes. Results are written to
+    /// [`RenderStatistics`].
+    pub pipeline_statistics: bool,
+    /// Synchronize the AO image right before the opaque pass instead of splitting the barrier
+    /// around the passes in between. Useful for comparing GPU stalls in a profiler capture.
+    pub disable_split_barriers: bool,
+    /// Draw the frustum of each sun shadow cascade with the debug shapes.
+    pub draw_shadow_cascades: bool,
+    /// Tint lit surfaces by the sun shadow cascade that shades them. Red, green, blue, and yellow
+    /// are the first through fourth cascades.
+    pub show_shadow_cascades: bool,
+    /// Present the motion vectors of the scene instead of the scene itself. Red and green are
+    /// horizontal and vertical motion, where `0.5` is no motion.
+    pub show_motion_vectors: bool,
+    /// Shade opaque and transparent surfaces by how many lights are in their light cluster.
+    /// Blue, green, and red go from few to many lights, and white clusters are full.
+    pub show_light_clusters: bool,
+}
+
+/// Statistics of the most recently completed frame.
+#[derive(Resource, Default, Clone)]
+pub struct RenderStatistics {
+    /// Pipeline statistics for each main render pass. Only collected when
+    /// [`DebugSettings::pipeline_statistics`] is enabled.
+    pub pipeline: Vec<(String, PipelineStatistics)>,
+    /// Time the render thread spent waiting on the GPU before the frame could begin. Consistently
+    /// high values mean rendering is GPU bound.
+    pub gpu_wait_time: Duration,
+    /// Particle budget usage and live particles of each emitter.
+    pub particles: ParticleStatistics,
+    /// Objects and meshlets culled by each culling pass. Passes that didn't run are omitted.
+    pub culling: Vec<(String, CullingStatistics)>,
+    /// Point and spot lights given shadows and how much of the shadow atlas they use.
+    pub local_shadows: LocalShadowStatistics,
+}
+
+/// Picks the entity under a point on the canvas by reading back the entity ID image.
+///
+/// A pick is resolved once the GPU has finished the frame it was made in, so the result arrives a
+/// few frames after the request instead of stalling rendering.
+#[derive(Resource, Default, Clone)]
+pub struct ScenePicker {
+    /// Point waiting to be sent with the next frame.
+    request: Option<Vec2>,
+    /// Number of picks sent to the renderer that haven't been read back yet.
+    in_flight: usize,
+    result: Option<Entity>,
+}
+
+impl ScenePicker {
+    /// Requests the entity at `(x, y)`, given as UV coordinates on the canvas. Requests made
+    /// before the next frame is rendered replace each other.
+    #[inline(always)]
+    pub fn request_pick(&mut self, x: f32, y: f32) {
+        self.request = Some(Vec2::new(x, y));
+    }
+
+    /// Takes the entity hit by the most recently resolved pick. `None` if no pick has resolved
+    /// since the last poll or if the pick didn't hit anything.
+    #[inline(always)]
+    pub fn poll_result(&mut self) -> Option<Entity> {
+        self.result.take()
+    }
+
+    /// `true` while a requested pick hasn't been resolved.
+    #[inline(always)]
+    pub fn is_pending(&self) -> bool {
+        self.request.is_some() || self.in_flight > 0
+    }
+
+    pub(crate) fn take_request(&mut self) -> Option<Vec2> {
+        let request = self.request.take();
+        self.in_flight += request.is_some() as usize;
+        request
+    }
+
+    pub(crate) fn resolve(&mut self, picked: Option<Entity>) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        self.result = picked;
+    }
 }
 
 #[derive(Resource, Clone, Copy)]
@@ -64,22 +156,32 @@ pub struct RenderPlugin {
     pub window: WindowId,
     pub settings: RendererSettings,
     pub debug: bool,
+    /// File the pipeline cache is persisted to between runs. `None` disables persistence.
+    pub pipeline_cache_path: Option<PathBuf>,
+    /// Which GPU to render with.
+    pub adapter_preference: AdapterPreference,
 }
 
 impl Plugin for RenderPlugin {
     fn build(&mut self, app: &mut AppBuilder) {
         app.add_resource(self.clone());
         app.add_resource(GlobalLighting::default());
+        app.add_resource(ShadowSettings::default());
+        app.add_resource(FroxelSettings::default());
         app.add_resource(TonemappingSettings::default());
         app.add_resource(AoSettings::default());
         app.add_resource(SunShaftsSettings::default());
         app.add_resource(SmaaSettings::default());
         app.add_resource(LxaaSettings::default());
+        app.add_resource(TaaSettings::default());
         app.add_resource(MsaaSettings::default());
         app.add_resource(DebugSettings::default());
+        app.add_resource(RenderStatistics::default());
+        app.add_resource(ScenePicker::default());
         app.add_resource(PathTracerSettings::default());
         app.add_resource(DebugDrawing::default());
         app.add_resource(Gui::default());
+        app.add_resource(GuiInputCapture::default());
         app.add_system(GuiInputCaptureSystem);
         app.add_startup_function(late_render_init);
     }
@@ -100,6 +202,7 @@ fn late_render_init(app: &mut App) {
 
     app.resources.add(PresentationSettings {
         present_mode: plugin.settings.present_mode,
+        output_color_space: plugin.settings.output_color_space,
         render_time: plugin.settings.render_time,
     });
     app.resources.add(plugin.settings.canvas_size);
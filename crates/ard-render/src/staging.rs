@@ -1,16 +1,12 @@
 use std::collections::VecDeque;
 
-use ard_formats::texture::MipType;
 use ard_pal::prelude::*;
 use ard_render_base::resource::{ResourceAllocator, ResourceId};
 use ard_render_meshes::{
     factory::{MeshFactory, MeshUpload},
     mesh::MeshResource,
 };
-use ard_render_textures::{
-    factory::{TextureFactory, TextureMipUpload, TextureUpload},
-    texture::TextureResource,
-};
+use ard_render_textures::{factory::TextureFactory, texture::TextureResource};
 
 // TODO: Make this configurable.
 const UPLOAD_BUDGET: u64 = 4 * 1024 * 1024;
@@ -18,6 +14,10 @@ const UPLOAD_BUDGET: u64 = 4 * 1024 * 1024;
 pub(crate) struct Staging {
     ctx: Context,
     uploads: Vec<Upload>,
+    /// Uploads made through the texture staging belt and the resources they complete.
+    belt_uploads: Vec<(StagingHandle, StagingResource)>,
+    /// Requests waiting on an upload made through the texture staging belt before they can begin.
+    belt_dependents: Vec<(StagingHandle, StagingRequest)>,
     pending: VecDeque<StagingRequest>,
 }
 
@@ -25,17 +25,14 @@ pub(crate) enum StagingRequest {
     Mesh {
         id: ResourceId,
         version: u32,
-        upload: MeshUpload,
+        upload: Box<MeshUpload>,
     },
+    /// A texture whose mip chain is generated from its highest detail mip, which must already be
+    /// uploaded.
     Texture {
         id: ResourceId,
         version: u32,
-        upload: TextureUpload,
-    },
-    TextureMip {
-        id: ResourceId,
-        version: u32,
-        upload: TextureMipUpload,
+        loaded_mips: u32,
     },
 }
 
@@ -78,6 +75,8 @@ impl Staging {
         Staging {
             ctx,
             uploads: Vec::default(),
+            belt_uploads: Vec::default(),
+            belt_dependents: Vec::default(),
             pending: VecDeque::default(),
         }
     }
@@ -86,6 +85,16 @@ impl Staging {
         self.pending.push_back(request);
     }
 
+    /// Tracks an upload made through the texture staging belt.
+    pub fn add_belt_upload(&mut self, handle: StagingHandle, resource: StagingResource) {
+        self.belt_uploads.push((handle, resource));
+    }
+
+    /// Queues a request once an upload made through the texture staging belt is complete.
+    pub fn add_after_belt_upload(&mut self, handle: StagingHandle, request: StagingRequest) {
+        self.belt_dependents.push((handle, request));
+    }
+
     /// Checks if any uploads are complete. Runs a closure for each resource that is complete.
     pub fn flush_complete_uploads(
         &mut self,
@@ -101,11 +110,16 @@ impl Staging {
                         continue;
                     }
                 }
-                if upload.transfer_job.poll_status() == JobStatus::Complete {
-                    to_remove.push(i);
-                    for resource in &upload.resources {
-                        on_complete(*resource);
+                match upload.transfer_job.poll_status() {
+                    JobStatus::Complete => {
+                        to_remove.push(i);
+                        for resource in &upload.resources {
+                            on_complete(*resource);
+                        }
                     }
+                    // The upload will never finish, so it's dropped without completing
+                    JobStatus::DeviceLost => to_remove.push(i),
+                    JobStatus::Running => {}
                 }
             }
 
@@ -119,6 +133,25 @@ impl Staging {
         for i in to_remove.into_iter().rev() {
             self.uploads.swap_remove(i);
         }
+
+        // Belt uploads that weren't flushed yet have nothing to wait on, so they're kept even
+        // when blocking
+        self.belt_uploads.retain(|(handle, resource)| {
+            let status = if blocking {
+                handle.wait_on(None)
+            } else {
+                handle.poll_status()
+            };
+
+            match status {
+                JobStatus::Complete => {
+                    on_complete(*resource);
+                    false
+                }
+                JobStatus::DeviceLost => false,
+                JobStatus::Running => true,
+            }
+        });
     }
 
     /// Begin pending uploads.
@@ -128,6 +161,22 @@ impl Staging {
         textures: &ResourceAllocator<TextureResource>,
         meshes: &ResourceAllocator<MeshResource>,
     ) {
+        // Move requests whose belt uploads have finished into the pending queue
+        let mut i = 0;
+        while i < self.belt_dependents.len() {
+            match self.belt_dependents[i].0.poll_status() {
+                JobStatus::Complete => {
+                    let (_, request) = self.belt_dependents.swap_remove(i);
+                    self.pending.push_back(request);
+                }
+                // The upload will never finish, so the request is dropped
+                JobStatus::DeviceLost => {
+                    self.belt_dependents.swap_remove(i);
+                }
+                JobStatus::Running => i += 1,
+            }
+        }
+
         if self.pending.is_empty() {
             return;
         }
@@ -170,8 +219,8 @@ impl Staging {
                 }
                 StagingRequest::Texture {
                     id,
-                    upload,
                     version,
+                    loaded_mips,
                 } => {
                     let texture = match textures.get(*id) {
                         Some(texture) => texture,
@@ -183,46 +232,15 @@ impl Staging {
                         continue;
                     }
 
-                    match upload.mip_type {
-                        MipType::Generate => TextureFactory::upload_gen_mip(
-                            commands.main(),
-                            &texture.texture,
-                            texture.mip_levels,
-                            upload,
-                        ),
-                        MipType::Upload(_, _) => TextureFactory::upload(
-                            commands.transfer(),
-                            &texture.texture,
-                            texture.mip_levels.saturating_sub(1),
-                            upload,
-                        ),
-                    }
+                    TextureFactory::generate_mips(
+                        commands.main(),
+                        &texture.texture,
+                        texture.mip_levels,
+                    );
                     StagingResource::Texture {
                         id: *id,
                         version: *version,
-                        loaded_mips: upload.loaded_mips,
-                    }
-                }
-                StagingRequest::TextureMip {
-                    id,
-                    version,
-                    upload,
-                } => {
-                    let texture = match textures.get(*id) {
-                        Some(texture) => texture,
-                        // Texture was dropped so upload is no longer needed
-                        None => continue,
-                    };
-
-                    if textures.version_of(*id).unwrap() != *version {
-                        continue;
-                    }
-
-                    TextureFactory::upload_mip(commands.transfer(), &texture.texture, upload);
-                    StagingResource::TextureMip {
-                        id: *id,
-                        version: *version,
-                        mip_level: upload.mip_level,
+                        loaded_mips: *loaded_mips,
                     }
                 }
             };
@@ -284,8 +302,8 @@ impl StagingRequest {
             StagingRequest::Mesh { upload, .. } => {
                 upload.index_staging.size() + upload.vertex_staging.size()
             }
-            StagingRequest::Texture { upload, .. } => upload.staging.size(),
-            StagingRequest::TextureMip { upload, .. } => upload.staging.size(),
+            // Mip generation only reads data that's already on the GPU
+            StagingRequest::Texture { .. } => 0,
         }
     }
 }
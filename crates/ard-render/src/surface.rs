@@ -0,0 +1,164 @@
+use ard_pal::prelude::*;
+use ard_window::prelude::WindowId;
+
+use crate::{frame::WindowInfo, PresentationSettings};
+
+/// A surface presenting to a single window. Each window gets its own surface, so windows can
+/// be resized, presented, or destroyed without affecting each other.
+pub(crate) struct WindowSurface {
+    /// The window being presented to.
+    window: WindowId,
+    /// Raw handle of the window the surface was created with. Used to detect when a window is
+    /// recreated with the same ID.
+    window_handle: raw_window_handle::RawWindowHandle,
+    /// Surface being rendered to.
+    surface: Surface,
+    /// Surface image for the current frame.
+    image: Option<SurfaceImage>,
+    /// Presentation mode used while vsync is enabled.
+    present_mode: PresentMode,
+    /// If vsync is currently enabled.
+    vsync: bool,
+    /// Formats and color spaces to request from the surface, in order of preference.
+    preferred_formats: Vec<(Format, ColorSpace)>,
+}
+
+unsafe impl Send for WindowSurface {}
+
+impl WindowSurface {
+    pub fn new(ctx: &Context, window: &WindowInfo, settings: &PresentationSettings) -> Self {
+        let preferred_formats = Self::preferred_formats(settings.output_color_space);
+        let surface = Surface::new(
+            ctx.clone(),
+            SurfaceCreateInfo {
+                config: SurfaceConfiguration {
+                    width: window.size.0,
+                    height: window.size.1,
+                    present_mode: Self::select_present_mode(settings.present_mode, window.vsync),
+                    preferred_formats: preferred_formats.clone(),
+                },
+                window: WindowSource::<winit::window::Window>::Raw {
+                    window: window.window_handle,
+                    display: window.display_handle,
+                },
+                debug_name: Some(if window.id.is_primary() {
+                    String::from("primary_surface")
+                } else {
+                    format!("{:?}_surface", window.id)
+                }),
+            },
+        )
+        .unwrap();
+
+        Self {
+            window: window.id,
+            window_handle: window.window_handle,
+            surface,
+            image: None,
+            present_mode: settings.present_mode,
+            vsync: window.vsync,
+            preferred_formats,
+        }
+    }
+
+    /// Presentation mode to use when vsync is enabled or disabled.
+    #[inline(always)]
+    fn select_present_mode(present_mode: PresentMode, vsync: bool) -> PresentMode {
+        if vsync {
+            present_mode
+        } else {
+            PresentMode::Immediate
+        }
+    }
+
+    /// HDR output needs a format with enough precision for the color space. SDR is always
+    /// requested last in case the display doesn't support HDR.
+    fn preferred_formats(color_space: ColorSpace) -> Vec<(Format, ColorSpace)> {
+        let mut formats = match color_space {
+            ColorSpace::SrgbNonlinear => Vec::with_capacity(1),
+            ColorSpace::Hdr10St2084 => vec![(Format::Rgb10A2Unorm, color_space)],
+            ColorSpace::ExtendedSrgbLinear => vec![(Format::Rgba16SFloat, color_space)],
+        };
+        formats.push((Format::Bgra8Unorm, ColorSpace::SrgbNonlinear));
+        formats
+    }
+
+    /// Returns `true` if this surface presents to the provided window.
+    #[inline(always)]
+    pub fn presents_to(&self, window: &WindowInfo) -> bool {
+        self.window == window.id && self.window_handle == window.window_handle
+    }
+
+    #[inline(always)]
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.surface.dimensions()
+    }
+
+    /// Color space the surface ended up using, which might not be the one requested.
+    #[inline(always)]
+    pub fn color_space(&self) -> ColorSpace {
+        self.surface.color_space()
+    }
+
+    /// Acquires a new image to render to. If the surface was invalidated, it is reconfigured to
+    /// match the window size first.
+    ///
+    /// No images are pending presentation at this point, so it's also where changes to vsync are
+    /// applied. The old swapchain is handed to the new one, so the switch doesn't drop frames.
+    pub fn acquire_image(&mut self, window: &WindowInfo) {
+        if window.vsync != self.vsync {
+            self.vsync = window.vsync;
+            self.reconfigure(window.size);
+        }
+
+        // The surface might be invalidated by the time the reconfigured swapchain is used, so
+        // acquisition is retried once
+        let image = match self.surface.acquire_image() {
+            Ok(image) => image,
+            Err(SurfaceImageAcquireError::Invalidated) => {
+                self.reconfigure(window.size);
+                self.surface.acquire_image().unwrap()
+            }
+            Err(err) => panic!("unable to acquire surface image: {err}"),
+        };
+        self.image = Some(image);
+    }
+
+    /// Gets the current surface image.
+    ///
+    /// # Note
+    /// This will panic if `image` is `None`. This is really just for convenience since it should
+    /// never be `None` when the render ECS is executing.
+    #[inline(always)]
+    pub fn image(&self) -> &SurfaceImage {
+        self.image.as_ref().unwrap()
+    }
+
+    /// Presents the currently active surface image and optionally resizes the surface to meet the
+    /// window size if needed.
+    pub fn present(&mut self, ctx: &Context, window_size: (u32, u32)) {
+        puffin::profile_function!();
+
+        let image = match self.image.take() {
+            Some(image) => image,
+            None => return,
+        };
+
+        if let SurfacePresentSuccess::Invalidated =
+            ctx.present().present(&self.surface, image).unwrap()
+        {
+            self.reconfigure(window_size);
+        }
+    }
+
+    fn reconfigure(&mut self, window_size: (u32, u32)) {
+        self.surface
+            .update_config(SurfaceConfiguration {
+                width: window_size.0,
+                height: window_size.1,
+                present_mode: Self::select_present_mode(self.present_mode, self.vsync),
+                preferred_formats: self.preferred_formats.clone(),
+            })
+            .unwrap();
+    }
+}
@@ -1,24 +1,36 @@
 use std::time::Duration;
 
+use ard_animation::animator::JointPalette;
 use ard_core::prelude::*;
 use ard_ecs::prelude::*;
+use ard_input::Clipboard;
+use ard_math::IVec2;
 use ard_physics::engine::PhysicsSystem;
 use ard_render_base::{Frame, PreRender, RenderingMode, FRAMES_IN_FLIGHT};
 use ard_render_camera::{
     active::{ActiveCamera, ActiveCameras},
+    froxels::FroxelSettings,
     Camera,
 };
 use ard_render_debug::{buffer::DebugVertexBuffer, DebugDrawing};
 use ard_render_gui::{Gui, GuiRunOutput};
 use ard_render_image_effects::{
     ao::AoSettings, lxaa::LxaaSettings, smaa::SmaaSettings, sun_shafts2::SunShaftsSettings,
-    tonemapping::TonemappingSettings,
+    taa::TaaSettings, tonemapping::TonemappingSettings,
+};
+use ard_render_lighting::{
+    global::GlobalLighting, lights::Lights, local_shadows::LocalShadowStatistics,
+    shadows::ShadowSettings, Light,
 };
-use ard_render_lighting::{global::GlobalLighting, lights::Lights, Light};
 use ard_render_material::material_instance::MaterialInstance;
-use ard_render_meshes::mesh::Mesh;
+use ard_render_meshes::{mesh::Mesh, skinned::SkinnedMesh};
 use ard_render_objects::{objects::RenderObjects, PrevFrameModel, RenderFlags};
-use ard_render_renderers::{entities::SelectEntity, pathtracer::PathTracerSettings};
+use ard_render_particles::{emitters::ParticleEmitters, ParticleEmitter, ParticleStatistics};
+use ard_render_renderers::{
+    entities::{EntitySelected, SelectEntity},
+    pathtracer::PathTracerSettings,
+    skinning::SkinnedMeshes,
+};
 use ard_transform::{system::ModelUpdateSystem, Model};
 use ard_window::prelude::*;
 use crossbeam_channel::{self, Receiver, Sender};
@@ -28,7 +40,8 @@ use crate::{
     ecs::RenderEcs,
     factory::Factory,
     frame::{FrameData, FrameDataInner, WindowInfo},
-    CanvasSize, DebugSettings, MsaaSettings, PresentationSettings, RenderPlugin,
+    CanvasSize, DebugSettings, MsaaSettings, PresentationSettings, RenderPlugin, RenderStatistics,
+    ScenePicker,
 };
 
 #[derive(SystemState)]
@@ -69,6 +82,7 @@ impl RenderSystem {
         let render_time = plugin.settings.render_time;
         let present_scene = plugin.settings.present_scene;
         let present_mode = plugin.settings.present_mode;
+        let output_color_space = plugin.settings.output_color_space;
         let window_id = plugin.window;
         let (render_ecs, factory) = RenderEcs::new(plugin, display_handle);
 
@@ -83,24 +97,39 @@ impl RenderSystem {
                     gui_output: GuiRunOutput::default(),
                     object_data: RenderObjects::new(render_ecs.ctx().clone()),
                     lights: Lights::new(render_ecs.ctx()),
+                    particle_emitters: ParticleEmitters::default(),
+                    particle_statistics: ParticleStatistics::default(),
+                    culling_statistics: Vec::default(),
+                    local_shadow_statistics: LocalShadowStatistics::default(),
+                    skinned_meshes: SkinnedMeshes::default(),
+                    debug_draws: DebugDrawing::default(),
                     debug_vertices: DebugVertexBuffer::new(render_ecs.ctx()),
                     present_settings: PresentationSettings {
                         present_mode,
+                        output_color_space,
                         render_time,
                     },
                     debug_settings: DebugSettings::default(),
+                    shadow_settings: ShadowSettings::default(),
+                    froxel_settings: FroxelSettings::default(),
                     tonemapping_settings: TonemappingSettings::default(),
                     ao_settings: AoSettings::default(),
                     sun_shafts_settings: SunShaftsSettings::default(),
                     smaa_settings: SmaaSettings::default(),
                     lxaa_settings: LxaaSettings::default(),
+                    taa_settings: TaaSettings::default(),
                     msaa_settings: MsaaSettings::default(),
                     path_tracer_settings: PathTracerSettings::default(),
                     active_cameras: ActiveCameras::default(),
                     select_entity: None,
-                    selected_entity: None,
+                    pick_issued: false,
+                    picked: None,
                     job: None,
+                    gpu_wait_time: Duration::ZERO,
+                    statistics_job: None,
+                    pipeline_statistics: Vec::default(),
                     window: None,
+                    gui_windows: Vec::default(),
                     canvas_size: (16, 16),
                 }))
                 .unwrap();
@@ -200,15 +229,29 @@ impl RenderSystem {
 
         let window_handle = window.window_handle();
         let display_handle = window.display_handle();
+        let vsync = window.vsync();
 
         std::mem::drop(windows);
         std::mem::drop(debug_draw);
 
         let mut frame = self.complete_frames.recv().unwrap();
 
-        // If an entity was selected, send the event
-        if let Some(evt) = frame.selected_entity.take() {
-            commands.events.submit(evt);
+        // If a pick was resolved, hand it to the picker and send the event if it hit something
+        if let Some(picked) = frame.picked.take() {
+            if let Some(entity) = picked {
+                commands.events.submit(EntitySelected(entity));
+            }
+            res.get_mut::<ScenePicker>().unwrap().resolve(picked);
+        }
+
+        // Publish statistics from the last completed frame
+        {
+            let mut statistics = res.get_mut::<RenderStatistics>().unwrap();
+            statistics.pipeline = std::mem::take(&mut frame.pipeline_statistics);
+            statistics.gpu_wait_time = frame.gpu_wait_time;
+            statistics.particles = std::mem::take(&mut frame.particle_statistics);
+            statistics.culling = std::mem::take(&mut frame.culling_statistics);
+            statistics.local_shadows = frame.local_shadow_statistics;
         }
 
         // Capture active cameras
@@ -261,12 +304,27 @@ impl RenderSystem {
             Read<Disabled>,
         )>();
 
+        // Skinned objects use `SkinnedMesh` instead of `Mesh`, so they're never static.
+        let skinned_objs = queries.make::<(
+            Entity,
+            (
+                Read<SkinnedMesh>,
+                Read<MaterialInstance>,
+                Read<Model>,
+                Read<PrevFrameModel>,
+                Read<RenderingMode>,
+                Read<RenderFlags>,
+            ),
+            Read<Disabled>,
+        )>();
+
         let lights = queries.make::<(Entity, (Read<Light>, Read<Model>), Read<Disabled>)>();
 
         frame.object_data.upload_objects(
             frame.frame,
             static_objs,
             dynamic_objs,
+            skinned_objs,
             &frame.dirty_static,
         );
 
@@ -282,62 +340,93 @@ impl RenderSystem {
         // Update lighting
         let global_lighting = res.get::<GlobalLighting>().unwrap();
         frame.lights.update(lights);
-        frame.lights.update_global(&global_lighting);
+        frame.lights.update_global(
+            &global_lighting,
+            res.get::<DebugSettings>().unwrap().show_light_clusters,
+        );
         std::mem::drop(global_lighting);
 
+        // Capture particle emitters
+        frame.particle_emitters.update(queries.make::<(
+            Entity,
+            (Read<ParticleEmitter>, Read<Model>),
+            Read<Disabled>,
+        )>());
+
+        // Capture skinned meshes along with their poses
+        frame.skinned_meshes.update(queries.make::<(
+            Entity,
+            (Read<SkinnedMesh>, Option<Read<JointPalette>>),
+            Read<Disabled>,
+        )>());
+
         // Render GUI
         let mut gui = res.get_mut::<Gui>().unwrap();
         frame.gui_output = gui.run(Tick(evt.0), &commands, &queries, &res);
 
-        // Capture debugging draws.
+        // Capture debugging draws. They're written out on the render thread so renderer
+        // visualizations can be added to them.
         let mut debug_draws = res.get_mut::<DebugDrawing>().unwrap();
-        frame.debug_vertices.write_draws(debug_draws.draws());
+        std::mem::swap(&mut *debug_draws, &mut frame.debug_draws);
         debug_draws.clear();
 
-        // Set cursor icon
+        // Set cursor icons and keep IME candidate windows next to the text cursor
         let mut windows = res.get_mut::<Windows>().unwrap();
-        let window = windows.get_mut(self.surface_window).unwrap();
-        window.set_cursor_icon(match frame.gui_output.full.platform_output.cursor_icon {
-            egui::CursorIcon::Default => CursorIcon::Default,
-            egui::CursorIcon::None => CursorIcon::Default,
-            egui::CursorIcon::ContextMenu => CursorIcon::ContextMenu,
-            egui::CursorIcon::Help => CursorIcon::Help,
-            egui::CursorIcon::PointingHand => CursorIcon::Pointer,
-            egui::CursorIcon::Progress => CursorIcon::Progress,
-            egui::CursorIcon::Wait => CursorIcon::Wait,
-            egui::CursorIcon::Cell => CursorIcon::Cell,
-            egui::CursorIcon::Crosshair => CursorIcon::Crosshair,
-            egui::CursorIcon::Text => CursorIcon::Text,
-            egui::CursorIcon::VerticalText => CursorIcon::VerticalText,
-            egui::CursorIcon::Alias => CursorIcon::Alias,
-            egui::CursorIcon::Copy => CursorIcon::Copy,
-            egui::CursorIcon::Move => CursorIcon::Move,
-            egui::CursorIcon::NoDrop => CursorIcon::NoDrop,
-            egui::CursorIcon::NotAllowed => CursorIcon::NotAllowed,
-            egui::CursorIcon::Grab => CursorIcon::Grab,
-            egui::CursorIcon::Grabbing => CursorIcon::Grabbing,
-            egui::CursorIcon::AllScroll => CursorIcon::AllScroll,
-            egui::CursorIcon::ResizeHorizontal => CursorIcon::EwResize,
-            egui::CursorIcon::ResizeNeSw => CursorIcon::NeswResize,
-            egui::CursorIcon::ResizeNwSe => CursorIcon::NwseResize,
-            egui::CursorIcon::ResizeVertical => CursorIcon::NsResize,
-            egui::CursorIcon::ResizeEast => CursorIcon::EResize,
-            egui::CursorIcon::ResizeSouthEast => CursorIcon::SeResize,
-            egui::CursorIcon::ResizeSouth => CursorIcon::SResize,
-            egui::CursorIcon::ResizeSouthWest => CursorIcon::SwResize,
-            egui::CursorIcon::ResizeWest => CursorIcon::WResize,
-            egui::CursorIcon::ResizeNorthWest => CursorIcon::NwResize,
-            egui::CursorIcon::ResizeNorth => CursorIcon::NResize,
-            egui::CursorIcon::ResizeNorthEast => CursorIcon::NeResize,
-            egui::CursorIcon::ResizeColumn => CursorIcon::ColResize,
-            egui::CursorIcon::ResizeRow => CursorIcon::RowResize,
-            egui::CursorIcon::ZoomIn => CursorIcon::ZoomIn,
-            egui::CursorIcon::ZoomOut => CursorIcon::ZoomOut,
-        });
+        for viewport in frame.gui_output.viewports.values() {
+            let window = match windows.get_mut(viewport.window) {
+                Some(window) => window,
+                None => continue,
+            };
+
+            window.set_cursor_icon(to_cursor_icon(viewport.cursor_icon));
+
+            if let Some(rect) = viewport.ime_cursor_rect() {
+                window.set_ime_cursor_area(
+                    IVec2::new(rect.min.x as i32, rect.min.y as i32),
+                    IVec2::new(rect.width() as i32, rect.height() as i32),
+                );
+            }
+        }
+
+        // Forward anything copied from the GUI to the clipboard
+        let copied_text = &frame.gui_output.platform_output.copied_text;
+        if !copied_text.is_empty() {
+            res.get_mut::<Clipboard>().unwrap().set_text(copied_text);
+        }
+
+        // Detached GUI viewports are drawn into their own windows
+        frame.gui_windows.clear();
+        for (id, viewport) in &frame.gui_output.viewports {
+            if *id == egui::ViewportId::ROOT {
+                continue;
+            }
+
+            let window = match windows.get(viewport.window) {
+                Some(window) => window,
+                None => continue,
+            };
+
+            if window.physical_width() == 0 || window.physical_height() == 0 {
+                continue;
+            }
+
+            frame.gui_windows.push((
+                *id,
+                WindowInfo {
+                    id: viewport.window,
+                    size: (window.physical_width(), window.physical_height()),
+                    vsync: window.vsync(),
+                    window_handle: window.window_handle(),
+                    display_handle: window.display_handle(),
+                },
+            ));
+        }
 
         // Prepare data for the render thread
         frame.window = Some(WindowInfo {
+            id: self.surface_window,
             size: (physical_width, physical_height),
+            vsync,
             window_handle,
             display_handle,
         });
@@ -347,15 +436,23 @@ impl RenderSystem {
             .0
             .unwrap_or((physical_width, physical_height));
         frame.dt = evt.0;
+        frame
+            .shadow_settings
+            .clone_from(&res.get::<ShadowSettings>().unwrap());
+        frame.froxel_settings = *res.get::<FroxelSettings>().unwrap();
         frame.tonemapping_settings = *res.get::<TonemappingSettings>().unwrap();
         frame.ao_settings = *res.get::<AoSettings>().unwrap();
         frame.sun_shafts_settings = *res.get::<SunShaftsSettings>().unwrap();
         frame.smaa_settings = *res.get::<SmaaSettings>().unwrap();
         frame.lxaa_settings = *res.get::<LxaaSettings>().unwrap();
+        frame.taa_settings = *res.get::<TaaSettings>().unwrap();
         frame.msaa_settings = *res.get::<MsaaSettings>().unwrap();
         frame.debug_settings = *res.get::<DebugSettings>().unwrap();
         frame.path_tracer_settings = *res.get::<PathTracerSettings>().unwrap();
-        frame.select_entity = self.select_entity.take();
+        frame.select_entity = match res.get_mut::<ScenePicker>().unwrap().take_request() {
+            Some(uv) => Some(SelectEntity(uv)),
+            None => self.select_entity.take(),
+        };
 
         // Send a message to the render thread to begin rendering the frame
         let _ = self.messages.send(RenderSystemMessage::RenderFrame(frame));
@@ -366,18 +463,29 @@ impl RenderSystem {
         messages: Receiver<RenderSystemMessage>,
         complete_frames: Sender<FrameData>,
     ) {
+        let ctx = ecs.ctx().clone();
+        ctx.set_frames_in_flight(FRAMES_IN_FLIGHT);
+
         loop {
             match messages.recv() {
                 Ok(msg) => match msg {
                     RenderSystemMessage::Shutdown => return,
                     RenderSystemMessage::RenderFrame(mut frame) => {
-                        // Wait for the frame to finish rendering.
-                        if let Some(job) = frame.job.take() {
-                            job.wait_on(None);
-                        }
+                        // Wait for a frame slot to free up. Frames are recycled in the order
+                        // they're rendered, so this is also the last use of this frame's data.
+                        let token = ctx.begin_frame();
+                        frame.gpu_wait_time = token.wait_time();
+
+                        // Statistics are submitted before the frame job, so they're complete too
+                        frame.pipeline_statistics = frame
+                            .statistics_job
+                            .take()
+                            .map(|job| job.statistics())
+                            .unwrap_or_default();
 
                         // Render the frame
-                        let frame = ecs.render(frame);
+                        let mut frame = ecs.render(frame, &token);
+                        ctx.end_frame(token, frame.job.take());
 
                         // Put it back into the queue
                         let _ = complete_frames.send(frame);
@@ -410,3 +518,43 @@ impl From<RenderSystem> for System {
             .build()
     }
 }
+
+fn to_cursor_icon(icon: egui::CursorIcon) -> CursorIcon {
+    match icon {
+        egui::CursorIcon::Default => CursorIcon::Default,
+        egui::CursorIcon::None => CursorIcon::Default,
+        egui::CursorIcon::ContextMenu => CursorIcon::ContextMenu,
+        egui::CursorIcon::Help => CursorIcon::Help,
+        egui::CursorIcon::PointingHand => CursorIcon::Pointer,
+        egui::CursorIcon::Progress => CursorIcon::Progress,
+        egui::CursorIcon::Wait => CursorIcon::Wait,
+        egui::CursorIcon::Cell => CursorIcon::Cell,
+        egui::CursorIcon::Crosshair => CursorIcon::Crosshair,
+        egui::CursorIcon::Text => CursorIcon::Text,
+        egui::CursorIcon::VerticalText => CursorIcon::VerticalText,
+        egui::CursorIcon::Alias => CursorIcon::Alias,
+        egui::CursorIcon::Copy => CursorIcon::Copy,
+        egui::CursorIcon::Move => CursorIcon::Move,
+        egui::CursorIcon::NoDrop => CursorIcon::NoDrop,
+        egui::CursorIcon::NotAllowed => CursorIcon::NotAllowed,
+        egui::CursorIcon::Grab => CursorIcon::Grab,
+        egui::CursorIcon::Grabbing => CursorIcon::Grabbing,
+        egui::CursorIcon::AllScroll => CursorIcon::AllScroll,
+        egui::CursorIcon::ResizeHorizontal => CursorIcon::EwResize,
+        egui::CursorIcon::ResizeNeSw => CursorIcon::NeswResize,
+        egui::CursorIcon::ResizeNwSe => CursorIcon::NwseResize,
+        egui::CursorIcon::ResizeVertical => CursorIcon::NsResize,
+        egui::CursorIcon::ResizeEast => CursorIcon::EResize,
+        egui::CursorIcon::ResizeSouthEast => CursorIcon::SeResize,
+        egui::CursorIcon::ResizeSouth => CursorIcon::SResize,
+        egui::CursorIcon::ResizeSouthWest => CursorIcon::SwResize,
+        egui::CursorIcon::ResizeWest => CursorIcon::WResize,
+        egui::CursorIcon::ResizeNorthWest => CursorIcon::NwResize,
+        egui::CursorIcon::ResizeNorth => CursorIcon::NResize,
+        egui::CursorIcon::ResizeNorthEast => CursorIcon::NeResize,
+        egui::CursorIcon::ResizeColumn => CursorIcon::ColResize,
+        egui::CursorIcon::ResizeRow => CursorIcon::RowResize,
+        egui::CursorIcon::ZoomIn => CursorIcon::ZoomIn,
+        egui::CursorIcon::ZoomOut => CursorIcon::ZoomOut,
+    }
+}
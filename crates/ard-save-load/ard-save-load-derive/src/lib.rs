@@ -0,0 +1,583 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident, Lit, Meta,
+    NestedMeta, Result, Type, Visibility,
+};
+
+/// Derives `SaveLoad` by generating a descriptor type that mirrors the type with every field
+/// replaced by its intermediate.
+///
+/// - `#[save_load(version = N)]` on the type sets the version written with saved data.
+/// - `#[save_load(migrate)]` on the type calls `Migrate::migrate` on data from older versions.
+/// - `#[save_load(since = N, default = "expr")]` on a field marks it as added in version `N`.
+///   Data from older versions gets `expr`, which must evaluate to the field's intermediate, or
+///   `Default::default()` if no default is given.
+/// - `#[save_load(on_missing = "null" | "skip_entity" | "error")]` on an `Entity` field saves it
+///   as an entity reference and chooses what happens when the entity is missing while loading.
+/// - `#[save_load(since = N)]` on an enum variant marks it as added in version `N`.
+#[proc_macro_derive(SaveLoad, attributes(save_load))]
+pub fn save_load(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    impl_save_load(&ast)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[derive(Default)]
+struct SaveLoadAttributes {
+    version: Option<u32>,
+    migrate: bool,
+    since: Option<u32>,
+    default: Option<Expr>,
+    on_missing: Option<TokenStream2>,
+}
+
+struct FieldInfo {
+    vis: Visibility,
+    ident: Option<Ident>,
+    ty: Type,
+    since: u32,
+    default: Option<Expr>,
+    /// Name used in errors about the field.
+    name: String,
+    /// Set if the field is an entity reference.
+    on_missing: Option<TokenStream2>,
+}
+
+struct VariantInfo {
+    ident: Ident,
+    since: u32,
+    fields: FieldsInfo,
+}
+
+enum FieldsInfo {
+    Named(Vec<FieldInfo>),
+    Unnamed(Vec<FieldInfo>),
+    Unit,
+}
+
+fn impl_save_load(ast: &DeriveInput) -> Result<TokenStream2> {
+    if !ast.generics.params.is_empty() {
+        return Err(Error::new(
+            ast.generics.span(),
+            "`SaveLoad` can't be derived for generic types",
+        ));
+    }
+
+    let attrs = parse_attributes(&ast.attrs)?;
+    if attrs.since.is_some() || attrs.default.is_some() || attrs.on_missing.is_some() {
+        return Err(Error::new(
+            ast.ident.span(),
+            "only `version` and `migrate` are allowed on types",
+        ));
+    }
+
+    let version = attrs.version.unwrap_or(1);
+    if version == 0 {
+        return Err(Error::new(ast.ident.span(), "versions start at 1"));
+    }
+
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let descriptor = format_ident!("{}SaveDescriptor", name);
+    let old_descriptor = |v: u32| format_ident!("{}SaveDescriptorV{}", name, v);
+    let descriptor_doc = format!("Saved form of [`{name}`] at version {version}.");
+    // Older descriptors are named like the latest one, since some formats store the name
+    let descriptor_name = descriptor.to_string();
+
+    let (definitions, save_body, load_body, upgrades) = match &ast.data {
+        Data::Struct(data) => {
+            let fields = fields_info(&data.fields, version, "")?;
+
+            let definition = |ident: &Ident, v: u32| {
+                let body = definition_body(&fields, v);
+                let semi = match &fields {
+                    FieldsInfo::Named(_) => quote! {},
+                    _ => quote! { ; },
+                };
+                quote! { struct #ident #body #semi }
+            };
+
+            let latest_body = definition(&descriptor, version);
+            let definitions = quote! {
+                #[doc = #descriptor_doc]
+                #[derive(::ard_save_load::serde::Serialize, ::ard_save_load::serde::Deserialize)]
+                #[serde(crate = "::ard_save_load::serde")]
+                #vis #latest_body
+            };
+
+            let old_definitions = (1..version).map(|v| {
+                let body = definition(&old_descriptor(v), v);
+                quote! {
+                    #[derive(::ard_save_load::serde::Deserialize)]
+                    #[serde(crate = "::ard_save_load::serde", rename = #descriptor_name)]
+                    #body
+                }
+            });
+
+            let save_pattern = pattern(quote! { Self }, &fields, version);
+            let save_construct = construct(quote! { #descriptor }, &fields, version, save_field);
+            let save_body = quote! {
+                let #save_pattern = self;
+                #save_construct
+            };
+
+            let load_pattern = pattern(quote! { #descriptor }, &fields, version);
+            let load_construct = construct(quote! { Self }, &fields, version, load_field);
+            let load_body = quote! {
+                let #load_pattern = intermediate;
+                #load_construct
+            };
+
+            let upgrades: Vec<_> = (1..version)
+                .map(|v| {
+                    let old = old_descriptor(v);
+                    let old_pattern = pattern(quote! { #old }, &fields, v);
+                    let latest = construct(quote! { #descriptor }, &fields, v, |i, _| {
+                        let binding = binding(i);
+                        quote! { #binding }
+                    });
+                    (
+                        old,
+                        quote! {
+                            let #old_pattern = old;
+                            #latest
+                        },
+                    )
+                })
+                .collect();
+
+            (
+                quote! {
+                    #definitions
+                    #(#old_definitions)*
+                },
+                save_body,
+                load_body,
+                upgrades,
+            )
+        }
+        Data::Enum(data) => {
+            let variants = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let attrs = parse_attributes(&variant.attrs)?;
+                    if attrs.version.is_some()
+                        || attrs.migrate
+                        || attrs.default.is_some()
+                        || attrs.on_missing.is_some()
+                    {
+                        return Err(Error::new(
+                            variant.ident.span(),
+                            "only `since` is allowed on variants",
+                        ));
+                    }
+                    Ok(VariantInfo {
+                        ident: variant.ident.clone(),
+                        since: check_since(attrs.since, version, variant.ident.span())?,
+                        fields: fields_info(
+                            &variant.fields,
+                            version,
+                            &format!("{}.", variant.ident),
+                        )?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let definition = |ident: &Ident, v: u32| {
+                let variants = variants.iter().filter(|var| var.since <= v).map(|var| {
+                    let var_ident = &var.ident;
+                    let body = definition_body(&var.fields, v);
+                    quote! { #var_ident #body }
+                });
+                quote! { enum #ident { #(#variants,)* } }
+            };
+
+            let latest_body = definition(&descriptor, version);
+            let definitions = quote! {
+                #[doc = #descriptor_doc]
+                #[derive(::ard_save_load::serde::Serialize, ::ard_save_load::serde::Deserialize)]
+                #[serde(crate = "::ard_save_load::serde")]
+                #vis #latest_body
+            };
+
+            let old_definitions = (1..version).map(|v| {
+                let body = definition(&old_descriptor(v), v);
+                quote! {
+                    #[derive(::ard_save_load::serde::Deserialize)]
+                    #[serde(crate = "::ard_save_load::serde", rename = #descriptor_name)]
+                    #body
+                }
+            });
+
+            let save_arms = variants.iter().map(|var| {
+                let var_ident = &var.ident;
+                let pattern = pattern(quote! { Self::#var_ident }, &var.fields, version);
+                let construct = construct(
+                    quote! { #descriptor::#var_ident },
+                    &var.fields,
+                    version,
+                    save_field,
+                );
+                quote! { #pattern => #construct }
+            });
+            let save_body = quote! {
+                match self {
+                    #(#save_arms,)*
+                }
+            };
+
+            let load_arms = variants.iter().map(|var| {
+                let var_ident = &var.ident;
+                let pattern = pattern(quote! { #descriptor::#var_ident }, &var.fields, version);
+                let construct = construct(
+                    quote! { Self::#var_ident },
+                    &var.fields,
+                    version,
+                    load_field,
+                );
+                quote! { #pattern => #construct }
+            });
+            let load_body = quote! {
+                match intermediate {
+                    #(#load_arms,)*
+                }
+            };
+
+            let upgrades: Vec<_> = (1..version)
+                .map(|v| {
+                    let old = old_descriptor(v);
+                    let arms = variants.iter().filter(|var| var.since <= v).map(|var| {
+                        let var_ident = &var.ident;
+                        let pattern = pattern(quote! { #old::#var_ident }, &var.fields, v);
+                        let construct = construct(
+                            quote! { #descriptor::#var_ident },
+                            &var.fields,
+                            v,
+                            |i, _| {
+                                let binding = binding(i);
+                                quote! { #binding }
+                            },
+                        );
+                        quote! { #pattern => #construct }
+                    });
+                    let upgrade = quote! {
+                        match old {
+                            #(#arms,)*
+                        }
+                    };
+                    (old, upgrade)
+                })
+                .collect();
+
+            (
+                quote! {
+                    #definitions
+                    #(#old_definitions)*
+                },
+                save_body,
+                load_body,
+                upgrades,
+            )
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                ast.ident.span(),
+                "`SaveLoad` can't be derived for unions",
+            ))
+        }
+    };
+
+    let upgrade_arms = upgrades.iter().enumerate().map(|(i, (old, upgrade))| {
+        let v = i as u32 + 1;
+        let migrate = if attrs.migrate {
+            quote! { <Self as ::ard_save_load::Migrate>::migrate(#v, &mut value); }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #v => Ok(F::deserialize::<Vec<#old>>(raw)?
+                .into_iter()
+                .map(|old| {
+                    #[allow(unused_mut)]
+                    let mut value = { #upgrade };
+                    #migrate
+                    value
+                })
+                .collect()),
+        }
+    });
+
+    Ok(quote! {
+        #definitions
+
+        impl ::ard_save_load::SaveLoad for #name {
+            type Intermediate = #descriptor;
+
+            const VERSION: u32 = #version;
+
+            #[allow(unused_variables)]
+            fn save(&self, ctx: &mut ::ard_save_load::SaveContext) -> Self::Intermediate {
+                #save_body
+            }
+
+            #[allow(unused_variables)]
+            fn load(
+                ctx: &mut ::ard_save_load::LoadContext,
+                intermediate: Self::Intermediate,
+            ) -> Self {
+                #load_body
+            }
+
+            fn deserialize_version<F: ::ard_save_load::format::SaveFormat>(
+                version: u32,
+                raw: Vec<u8>,
+            ) -> Result<Vec<Self::Intermediate>, F::DeserializeError> {
+                match version {
+                    #(#upgrade_arms)*
+                    _ => F::deserialize(raw),
+                }
+            }
+        }
+    })
+}
+
+fn parse_attributes(attrs: &[Attribute]) -> Result<SaveLoadAttributes> {
+    let mut out = SaveLoadAttributes::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("save_load")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected `save_load(...)`")),
+        };
+
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("version") => {
+                    out.version = Some(parse_u32(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("since") => {
+                    out.since = Some(parse_u32(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("default") => {
+                    out.default = Some(match &nv.lit {
+                        Lit::Str(lit) => lit.parse::<Expr>()?,
+                        lit => return Err(Error::new(lit.span(), "expected a string")),
+                    });
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("on_missing") => {
+                    let policy = match &nv.lit {
+                        Lit::Str(lit) => match lit.value().as_str() {
+                            "null" => quote! { Null },
+                            "skip_entity" => quote! { SkipEntity },
+                            "error" => quote! { Error },
+                            _ => {
+                                return Err(Error::new(
+                                    lit.span(),
+                                    "expected `null`, `skip_entity`, or `error`",
+                                ))
+                            }
+                        },
+                        lit => return Err(Error::new(lit.span(), "expected a string")),
+                    };
+                    out.on_missing =
+                        Some(quote! { ::ard_save_load::entity_map::OnMissing::#policy });
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("migrate") => {
+                    out.migrate = true;
+                }
+                other => return Err(Error::new(other.span(), "unknown `save_load` attribute")),
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn parse_u32(lit: &Lit) -> Result<u32> {
+    match lit {
+        Lit::Int(lit) => lit.base10_parse(),
+        lit => Err(Error::new(lit.span(), "expected an integer")),
+    }
+}
+
+fn check_since(since: Option<u32>, version: u32, span: proc_macro2::Span) -> Result<u32> {
+    let since = since.unwrap_or(1);
+    if since == 0 || since > version {
+        return Err(Error::new(
+            span,
+            format!("`since` must be between 1 and the type's version ({version})"),
+        ));
+    }
+    Ok(since)
+}
+
+/// `prefix` is prepended to field names in errors, such as the variant of an enum.
+fn fields_info(fields: &Fields, version: u32, prefix: &str) -> Result<FieldsInfo> {
+    let info = |fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>| {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let attrs = parse_attributes(&field.attrs)?;
+                if attrs.version.is_some() || attrs.migrate {
+                    return Err(Error::new(
+                        field.span(),
+                        "only `since`, `default`, and `on_missing` are allowed on fields",
+                    ));
+                }
+                if attrs.default.is_some() && attrs.since.is_none() {
+                    return Err(Error::new(field.span(), "`default` requires `since`"));
+                }
+                Ok(FieldInfo {
+                    vis: field.vis.clone(),
+                    ident: field.ident.clone(),
+                    ty: field.ty.clone(),
+                    since: check_since(attrs.since, version, field.span())?,
+                    default: attrs.default,
+                    name: match &field.ident {
+                        Some(ident) => format!("{prefix}{ident}"),
+                        None => format!("{prefix}{i}"),
+                    },
+                    on_missing: attrs.on_missing,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+
+    Ok(match fields {
+        Fields::Named(fields) => FieldsInfo::Named(info(&fields.named)?),
+        Fields::Unnamed(fields) => FieldsInfo::Unnamed(info(&fields.unnamed)?),
+        Fields::Unit => FieldsInfo::Unit,
+    })
+}
+
+fn binding(i: usize) -> Ident {
+    format_ident!("__field{}", i)
+}
+
+/// Type of a field in the descriptor. Entity references are saved as mapped entities, since
+/// entities have no intermediate of their own.
+fn intermediate_ty(field: &FieldInfo) -> TokenStream2 {
+    let ty = &field.ty;
+    match &field.on_missing {
+        Some(_) => quote! { ::ard_save_load::entity_map::MappedEntity },
+        None => quote! { <#ty as ::ard_save_load::SaveLoad>::Intermediate },
+    }
+}
+
+fn save_field(i: usize, field: &FieldInfo) -> TokenStream2 {
+    let binding = binding(i);
+    let ty = &field.ty;
+    match &field.on_missing {
+        Some(_) => quote! { ctx.entity_map.to_map(*#binding) },
+        None => quote! { <#ty as ::ard_save_load::SaveLoad>::save(#binding, ctx) },
+    }
+}
+
+fn load_field(i: usize, field: &FieldInfo) -> TokenStream2 {
+    let binding = binding(i);
+    let ty = &field.ty;
+    let name = &field.name;
+    match &field.on_missing {
+        Some(on_missing) => quote! { ctx.resolve_entity(#binding, #on_missing, #name) },
+        None => quote! { <#ty as ::ard_save_load::SaveLoad>::load(ctx, #binding) },
+    }
+}
+
+/// Body of a descriptor definition containing the fields that existed at version `v`.
+fn definition_body(fields: &FieldsInfo, v: u32) -> TokenStream2 {
+    let field_defs = |fields: &[FieldInfo]| {
+        fields
+            .iter()
+            .filter(|f| f.since <= v)
+            .map(|f| {
+                let vis = &f.vis;
+                let ident = f.ident.iter();
+                let ty = intermediate_ty(f);
+                quote! { #vis #(#ident:)* #ty }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    match fields {
+        FieldsInfo::Named(fields) => {
+            let defs = field_defs(fields);
+            quote! { { #(#defs,)* } }
+        }
+        FieldsInfo::Unnamed(fields) => {
+            let defs = field_defs(fields);
+            quote! { ( #(#defs,)* ) }
+        }
+        FieldsInfo::Unit => quote! {},
+    }
+}
+
+/// Pattern binding every field that existed at version `v` to its binding.
+fn pattern(path: TokenStream2, fields: &FieldsInfo, v: u32) -> TokenStream2 {
+    match fields {
+        FieldsInfo::Named(fields) => {
+            let binds = fields
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.since <= v)
+                .map(|(i, f)| {
+                    let ident = f.ident.as_ref().unwrap();
+                    let binding = binding(i);
+                    quote! { #ident: #binding }
+                });
+            quote! { #path { #(#binds,)* } }
+        }
+        FieldsInfo::Unnamed(fields) => {
+            let binds = fields
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.since <= v)
+                .map(|(i, _)| binding(i));
+            quote! { #path ( #(#binds,)* ) }
+        }
+        FieldsInfo::Unit => path,
+    }
+}
+
+/// Constructs a value with every field. Fields that existed at version `v` are produced by
+/// `value` and newer fields get their defaults.
+fn construct(
+    path: TokenStream2,
+    fields: &FieldsInfo,
+    v: u32,
+    value: impl Fn(usize, &FieldInfo) -> TokenStream2,
+) -> TokenStream2 {
+    let values = |fields: &[FieldInfo]| {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                if f.since <= v {
+                    value(i, f)
+                } else {
+                    match &f.default {
+                        Some(default) => quote! { #default },
+                        None => quote! { ::std::default::Default::default() },
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    match fields {
+        FieldsInfo::Named(fields) => {
+            let idents = fields.iter().map(|f| f.ident.as_ref().unwrap());
+            let values = values(fields);
+            quote! { #path { #(#idents: #values,)* } }
+        }
+        FieldsInfo::Unnamed(fields) => {
+            let values = values(fields);
+            quote! { #path ( #(#values,)* ) }
+        }
+        FieldsInfo::Unit => path,
+    }
+}
@@ -2,9 +2,22 @@ use ard_ecs::entity::Entity;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct MappedEntity(pub u32);
 
+/// What to do when a saved entity reference can't be resolved while loading, such as when the
+/// entity it refers to wasn't saved.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OnMissing {
+    /// Substitute `Entity::null()`.
+    #[default]
+    Null,
+    /// Don't load the entity holding the reference.
+    SkipEntity,
+    /// Abort the load.
+    Error,
+}
+
 pub struct EntityMap {
     src_to_dst: FxHashMap<Entity, MappedEntity>,
     dst_to_src: Vec<Entity>,
@@ -72,9 +85,12 @@ impl EntityMap {
 
     #[inline(always)]
     pub fn from_map(&self, mapped: MappedEntity) -> Entity {
-        self.dst_to_src
-            .get(mapped.0 as usize)
-            .cloned()
-            .unwrap_or(Entity::null())
+        self.resolve(mapped).unwrap_or(Entity::null())
+    }
+
+    /// Gets the entity a mapped entity refers to, or `None` if it isn't in the map.
+    #[inline(always)]
+    pub fn resolve(&self, mapped: MappedEntity) -> Option<Entity> {
+        self.dst_to_src.get(mapped.0 as usize).cloned()
     }
 }
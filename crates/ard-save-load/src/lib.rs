@@ -1,20 +1,55 @@
 use ard_assets::manager::Assets;
-use entity_map::EntityMap;
+use ard_ecs::entity::Entity;
+use entity_map::{EntityMap, MappedEntity, OnMissing};
+use format::SaveFormat;
+use overrides::Overrides;
 use serde::{de::DeserializeOwned, Serialize};
 
+// Lets the derive refer to this crate by name from within it
+extern crate self as ard_save_load;
+
+pub use ard_save_load_derive::SaveLoad;
+#[doc(hidden)]
+pub use serde;
+
 pub mod entity_map;
 pub mod format;
 pub mod load_data;
 pub mod loader;
+pub mod overrides;
 pub mod save_data;
 pub mod saver;
 
+#[cfg(test)]
+mod tests;
+
 pub trait SaveLoad {
     type Intermediate: Serialize + DeserializeOwned;
 
+    /// Version of `Intermediate` that is saved alongside it. This must be increased whenever
+    /// `Intermediate` changes such that older data can no longer be deserialized as it.
+    const VERSION: u32 = 1;
+
     fn save(&self, ctx: &mut SaveContext) -> Self::Intermediate;
 
     fn load(ctx: &mut LoadContext, intermediate: Self::Intermediate) -> Self;
+
+    /// Deserializes intermediates that were saved when `VERSION` was `version`. `version` is
+    /// never newer than `VERSION`.
+    fn deserialize_version<F: SaveFormat>(
+        version: u32,
+        raw: Vec<u8>,
+    ) -> Result<Vec<Self::Intermediate>, F::DeserializeError> {
+        let _ = version;
+        F::deserialize(raw)
+    }
+}
+
+/// Fixes up data saved by an older version of a type deriving [`SaveLoad`] with
+/// `#[save_load(migrate)]`.
+pub trait Migrate: SaveLoad {
+    /// Called after fields added since `old_version` have been given their defaults.
+    fn migrate(old_version: u32, value: &mut Self::Intermediate);
 }
 
 impl<T: Serialize + DeserializeOwned + Clone> SaveLoad for T {
@@ -39,4 +74,54 @@ pub struct SaveContext {
 pub struct LoadContext {
     pub entity_map: EntityMap,
     pub assets: Assets,
+    missing: MissingEntities,
+    overrides: Overrides,
+    /// Mapped entities of the set being loaded.
+    set: Vec<MappedEntity>,
+}
+
+/// Entity references that couldn't be resolved while loading a set of entities.
+#[derive(Default)]
+struct MissingEntities {
+    /// Index within the set of the entity being loaded.
+    current: usize,
+    /// Indices within the set of entities that must be skipped.
+    skipped: Vec<usize>,
+    /// Field of the first reference that aborts the load.
+    error: Option<&'static str>,
+}
+
+impl LoadContext {
+    pub(crate) fn new(entity_map: EntityMap, assets: Assets, overrides: Overrides) -> Self {
+        Self {
+            entity_map,
+            assets,
+            missing: MissingEntities::default(),
+            overrides,
+            set: Vec::default(),
+        }
+    }
+
+    /// Resolves an entity reference held by `field` of the object being loaded. If the entity is
+    /// missing, `Entity::null()` is returned and `on_missing` decides what happens to the load.
+    pub fn resolve_entity(
+        &mut self,
+        mapped: MappedEntity,
+        on_missing: OnMissing,
+        field: &'static str,
+    ) -> Entity {
+        if let Some(entity) = self.entity_map.resolve(mapped) {
+            return entity;
+        }
+
+        match on_missing {
+            OnMissing::Null => {}
+            OnMissing::SkipEntity => self.missing.skipped.push(self.missing.current),
+            OnMissing::Error => {
+                self.missing.error.get_or_insert(field);
+            }
+        }
+
+        Entity::null()
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use ard_assets::manager::Assets;
 use ard_ecs::{
@@ -7,24 +7,27 @@ use ard_ecs::{
     prelude::*,
     tag::{pack::TagPack, TagCollection, TagCollectionId},
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     entity_map::EntityMap,
     format::SaveFormat,
     loader::{ComponentLoader, GenericComponentLoader, GenericTagLoader, TagLoader},
+    overrides::Overrides,
     save_data::SaveData,
     LoadContext, SaveLoad,
 };
 
 pub struct Loader<F: SaveFormat> {
     meta_data: FxHashMap<String, LoadingMetaData<F>>,
+    overrides: Overrides,
 }
 
 impl<F: SaveFormat> Default for Loader<F> {
     fn default() -> Self {
         Self {
             meta_data: FxHashMap::default(),
+            overrides: Overrides::default(),
         }
     }
 }
@@ -34,9 +37,9 @@ impl<F: SaveFormat + 'static> Loader<F> {
         self.meta_data.insert(
             C::NAME.into(),
             LoadingMetaData::Component {
-                new_loader: |ctx, raw| {
+                new_loader: |ctx, version, raw| {
                     let mut loader = ComponentLoader::<F, C>::default();
-                    loader.deserialize_all(ctx, raw)?;
+                    loader.deserialize_all(ctx, version, raw)?;
                     Ok(Box::new(loader))
                 },
             },
@@ -48,9 +51,9 @@ impl<F: SaveFormat + 'static> Loader<F> {
         self.meta_data.insert(
             T::NAME.into(),
             LoadingMetaData::Tag {
-                new_loader: |ctx, raw| {
+                new_loader: |ctx, version, raw| {
                     let mut loader = TagLoader::<F, T>::default();
-                    loader.deserialize_all(ctx, raw)?;
+                    loader.deserialize_all(ctx, version, raw)?;
                     Ok(Box::new(loader))
                 },
             },
@@ -58,15 +61,26 @@ impl<F: SaveFormat + 'static> Loader<F> {
         self
     }
 
+    /// Values to replace in the loaded components and tags.
+    #[inline(always)]
+    pub fn overrides(mut self, overrides: Overrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
     pub fn load(
         self,
         data: SaveData,
         assets: Assets,
         commands: &EntityCommands,
-    ) -> Result<(), F::DeserializeError> {
+    ) -> Result<(), LoadError<F>> {
         self.load_with_external(data, assets, commands, None, &[])
     }
 
+    /// Entities holding a missing entity reference with [`OnMissing::SkipEntity`] get no
+    /// components or tags. They're destroyed unless they were given in `load_into`.
+    ///
+    /// [`OnMissing::SkipEntity`]: crate::entity_map::OnMissing::SkipEntity
     pub fn load_with_external(
         self,
         data: SaveData,
@@ -74,7 +88,7 @@ impl<F: SaveFormat + 'static> Loader<F> {
         commands: &EntityCommands,
         load_into: Option<&[Entity]>,
         external: &[Entity],
-    ) -> Result<(), F::DeserializeError> {
+    ) -> Result<(), LoadError<F>> {
         let entities = match load_into {
             Some(entities) => {
                 assert_eq!(entities.len(), data.entity_count);
@@ -93,38 +107,101 @@ impl<F: SaveFormat + 'static> Loader<F> {
             entity_map.to_map(*e);
         });
 
-        let mut ctx = LoadContext { entity_map, assets };
-
-        for archetype in data.archetypes {
-            let remapped_entities: Vec<_> = archetype
-                .entities
-                .into_iter()
-                .map(|e| ctx.entity_map.from_map(e))
-                .collect();
-
-            let loaders = archetype
-                .buffers
-                .into_iter()
-                .map(|buffer| {
-                    let meta = self.meta_data.get(&buffer.type_name).unwrap();
-                    match meta {
-                        LoadingMetaData::Component { new_loader, .. } => {
-                            new_loader(&mut ctx, buffer.raw)
+        let mut ctx = LoadContext::new(entity_map, assets, self.overrides);
+        let mut skipped = FxHashSet::<Entity>::default();
+
+        let archetypes = data
+            .archetypes
+            .into_iter()
+            .map(|archetype| {
+                let remapped_entities: Vec<_> = archetype
+                    .entities
+                    .iter()
+                    .copied()
+                    .map(|e| ctx.entity_map.from_map(e))
+                    .collect();
+
+                ctx.set = archetype.entities;
+                let loaders = archetype
+                    .buffers
+                    .into_iter()
+                    .map(|buffer| {
+                        let meta = self.meta_data.get(&buffer.type_name).unwrap();
+                        match meta {
+                            LoadingMetaData::Component { new_loader, .. } => {
+                                new_loader(&mut ctx, buffer.version, buffer.raw)
+                            }
+                            _ => unreachable!(),
                         }
-                        _ => unreachable!(),
-                    }
-                })
-                .collect::<Result<Vec<_>, _>>();
-
-            let loaders = match loaders {
-                Ok(loaders) => loaders,
-                Err(err) => {
-                    if load_into.is_none() {
-                        commands.destroy(&entities);
-                    }
-                    return Err(err);
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                skipped.extend(ctx.missing.skipped.drain(..).map(|i| remapped_entities[i]));
+                Ok((remapped_entities, loaders))
+            })
+            .collect::<Result<Vec<_>, _>>();
+
+        let archetypes = match archetypes {
+            Ok(archetypes) => archetypes,
+            Err(err) => {
+                if load_into.is_none() {
+                    commands.destroy(&entities);
                 }
-            };
+                return Err(err);
+            }
+        };
+
+        let collections = data
+            .collections
+            .into_iter()
+            .map(|collection| {
+                let remapped_entities: Vec<_> = collection
+                    .entities
+                    .iter()
+                    .copied()
+                    .map(|e| ctx.entity_map.from_map(e))
+                    .collect();
+
+                ctx.set = collection.entities;
+                let loaders = collection
+                    .buffers
+                    .into_iter()
+                    .map(|buffer| {
+                        let meta = self.meta_data.get(&buffer.type_name).unwrap();
+                        match meta {
+                            LoadingMetaData::Tag { new_loader, .. } => {
+                                new_loader(&mut ctx, buffer.version, buffer.raw)
+                            }
+                            _ => unreachable!(),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                skipped.extend(ctx.missing.skipped.drain(..).map(|i| remapped_entities[i]));
+                Ok((remapped_entities, loaders))
+            })
+            .collect::<Result<Vec<_>, _>>();
+
+        let collections = match collections {
+            Ok(collections) => collections,
+            Err(err) => {
+                if load_into.is_none() {
+                    commands.destroy(&entities);
+                }
+                return Err(err);
+            }
+        };
+
+        // Nothing is sent to the world until everything has loaded, so skipped entities can be
+        // left out of every set they belong to
+        for (mut remapped_entities, mut loaders) in archetypes {
+            if let Some(keep) = remove_skipped(&skipped, &mut remapped_entities) {
+                loaders.iter_mut().for_each(|loader| loader.retain(&keep));
+            }
+
+            if remapped_entities.is_empty() {
+                continue;
+            }
 
             commands.set_components(
                 &remapped_entities,
@@ -135,34 +212,14 @@ impl<F: SaveFormat + 'static> Loader<F> {
             );
         }
 
-        for collection in data.collections {
-            let remapped_entities: Vec<_> = collection
-                .entities
-                .into_iter()
-                .map(|e| ctx.entity_map.from_map(e))
-                .collect();
-
-            let loaders = collection
-                .buffers
-                .into_iter()
-                .map(|buffer| {
-                    let meta = self.meta_data.get(&buffer.type_name).unwrap();
-                    match meta {
-                        LoadingMetaData::Tag { new_loader, .. } => new_loader(&mut ctx, buffer.raw),
-                        _ => unreachable!(),
-                    }
-                })
-                .collect::<Result<Vec<_>, _>>();
-
-            let loaders = match loaders {
-                Ok(loaders) => loaders,
-                Err(err) => {
-                    if load_into.is_none() {
-                        commands.destroy(&entities);
-                    }
-                    return Err(err);
-                }
-            };
+        for (mut remapped_entities, mut loaders) in collections {
+            if let Some(keep) = remove_skipped(&skipped, &mut remapped_entities) {
+                loaders.iter_mut().for_each(|loader| loader.retain(&keep));
+            }
+
+            if remapped_entities.is_empty() {
+                continue;
+            }
 
             commands.set_tags(
                 &remapped_entities,
@@ -173,23 +230,130 @@ impl<F: SaveFormat + 'static> Loader<F> {
             );
         }
 
+        if load_into.is_none() && !skipped.is_empty() {
+            commands.destroy(&skipped.into_iter().collect::<Vec<_>>());
+        }
+
         Ok(())
     }
 }
 
+/// Removes skipped entities from a set. Returns which entities of the set were kept, or `None`
+/// if none were removed.
+fn remove_skipped(skipped: &FxHashSet<Entity>, entities: &mut Vec<Entity>) -> Option<Vec<bool>> {
+    if skipped.is_empty() {
+        return None;
+    }
+
+    let keep: Vec<_> = entities.iter().map(|e| !skipped.contains(e)).collect();
+    if keep.iter().all(|keep| *keep) {
+        return None;
+    }
+
+    let mut kept = keep.iter();
+    entities.retain(|_| *kept.next().unwrap());
+    Some(keep)
+}
+
+pub enum LoadError<F: SaveFormat> {
+    Deserialize(F::DeserializeError),
+    /// A field with [`OnMissing::Error`] referred to an entity that couldn't be resolved.
+    ///
+    /// [`OnMissing::Error`]: crate::entity_map::OnMissing::Error
+    MissingEntity {
+        type_name: String,
+        field: String,
+    },
+    /// An override couldn't be applied.
+    Override {
+        type_name: String,
+        path: String,
+        reason: String,
+    },
+    /// The data was saved by a newer version of the type than the one loading it.
+    UnsupportedVersion {
+        type_name: String,
+        version: u32,
+        supported: u32,
+    },
+}
+
+impl<F: SaveFormat> fmt::Debug for LoadError<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize(err) => f.debug_tuple("Deserialize").field(err).finish(),
+            Self::Override {
+                type_name,
+                path,
+                reason,
+            } => f
+                .debug_struct("Override")
+                .field("type_name", type_name)
+                .field("path", path)
+                .field("reason", reason)
+                .finish(),
+            Self::MissingEntity { type_name, field } => f
+                .debug_struct("MissingEntity")
+                .field("type_name", type_name)
+                .field("field", field)
+                .finish(),
+            Self::UnsupportedVersion {
+                type_name,
+                version,
+                supported,
+            } => f
+                .debug_struct("UnsupportedVersion")
+                .field("type_name", type_name)
+                .field("version", version)
+                .field("supported", supported)
+                .finish(),
+        }
+    }
+}
+
+impl<F: SaveFormat> fmt::Display for LoadError<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize(err) => write!(f, "unable to deserialize: {err}"),
+            Self::Override {
+                type_name,
+                path,
+                reason,
+            } => write!(f, "unable to override `{path}` of `{type_name}`: {reason}"),
+            Self::MissingEntity { type_name, field } => write!(
+                f,
+                "`{type_name}::{field}` refers to an entity that wasn't loaded"
+            ),
+            Self::UnsupportedVersion {
+                type_name,
+                version,
+                supported,
+            } => write!(
+                f,
+                "`{type_name}` was saved at version {version} but only versions up to \
+                {supported} are supported"
+            ),
+        }
+    }
+}
+
+impl<F: SaveFormat> std::error::Error for LoadError<F> {}
+
 #[derive(Clone, Copy)]
 enum LoadingMetaData<F: SaveFormat> {
     Component {
         new_loader: fn(
             &mut LoadContext,
+            u32,
             Vec<u8>,
-        ) -> Result<Box<dyn GenericComponentLoader<F>>, F::DeserializeError>,
+        ) -> Result<Box<dyn GenericComponentLoader<F>>, LoadError<F>>,
     },
     Tag {
         new_loader: fn(
             &mut LoadContext,
+            u32,
             Vec<u8>,
-        ) -> Result<Box<dyn GenericTagLoader<F>>, F::DeserializeError>,
+        ) -> Result<Box<dyn GenericTagLoader<F>>, LoadError<F>>,
     },
 }
 
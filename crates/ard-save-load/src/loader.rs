@@ -1,6 +1,6 @@
 use std::any::TypeId;
 
-use crate::{format::SaveFormat, LoadContext, SaveLoad};
+use crate::{format::SaveFormat, load_data::LoadError, LoadContext, SaveLoad};
 use ard_ecs::{
     archetype::{storage::AnyArchetypeStorage, Archetypes},
     prelude::*,
@@ -43,8 +43,12 @@ pub trait GenericComponentLoader<F: SaveFormat>: Send + Sync {
     fn deserialize_all(
         &mut self,
         ctx: &mut LoadContext,
+        version: u32,
         raw: Vec<u8>,
-    ) -> Result<(), F::DeserializeError>;
+    ) -> Result<(), LoadError<F>>;
+
+    /// Drops loaded components whose entry in `keep` is `false`.
+    fn retain(&mut self, keep: &[bool]);
 
     fn move_into(&mut self, archetypes: &Archetypes, idx: usize);
 }
@@ -55,8 +59,12 @@ pub trait GenericTagLoader<F: SaveFormat>: Send + Sync {
     fn deserialize_all(
         &mut self,
         ctx: &mut LoadContext,
+        version: u32,
         raw: Vec<u8>,
-    ) -> Result<(), F::DeserializeError>;
+    ) -> Result<(), LoadError<F>>;
+
+    /// Drops loaded tags whose entry in `keep` is `false`.
+    fn retain(&mut self, keep: &[bool]);
 
     fn move_into(&mut self, tags: &mut Tags, entities: &[Entity]);
 }
@@ -71,13 +79,19 @@ impl<F: SaveFormat + 'static, C: Component + SaveLoad + 'static> GenericComponen
     fn deserialize_all(
         &mut self,
         ctx: &mut LoadContext,
+        version: u32,
         raw: Vec<u8>,
-    ) -> Result<(), F::DeserializeError> {
-        let intermediates = F::deserialize::<Vec<C::Intermediate>>(raw)?;
-        self.to_load = intermediates.into_iter().map(|i| C::load(ctx, i)).collect();
+    ) -> Result<(), LoadError<F>> {
+        let intermediates = deserialize_intermediates::<F, C>(C::NAME, version, raw)?;
+        self.to_load = load_intermediates(ctx, C::NAME, intermediates)?;
         Ok(())
     }
 
+    fn retain(&mut self, keep: &[bool]) {
+        let mut keep = keep.iter();
+        self.to_load.retain(|_| *keep.next().unwrap());
+    }
+
     fn move_into(&mut self, archetypes: &Archetypes, idx: usize) {
         let mut buffer = archetypes.get_storage::<C>().unwrap().get_mut(idx);
         buffer.extend(std::mem::take(&mut self.to_load).into_iter());
@@ -103,13 +117,19 @@ impl<F: SaveFormat + 'static, T: Tag + SaveLoad + 'static> GenericTagLoader<F> f
     fn deserialize_all(
         &mut self,
         ctx: &mut LoadContext,
+        version: u32,
         raw: Vec<u8>,
-    ) -> Result<(), F::DeserializeError> {
-        let intermediates = F::deserialize::<Vec<T::Intermediate>>(raw)?;
-        self.to_load = intermediates.into_iter().map(|i| T::load(ctx, i)).collect();
+    ) -> Result<(), LoadError<F>> {
+        let intermediates = deserialize_intermediates::<F, T>(T::NAME, version, raw)?;
+        self.to_load = load_intermediates(ctx, T::NAME, intermediates)?;
         Ok(())
     }
 
+    fn retain(&mut self, keep: &[bool]) {
+        let mut keep = keep.iter();
+        self.to_load.retain(|_| *keep.next().unwrap());
+    }
+
     fn move_into(&mut self, tags: &mut Tags, entities: &[Entity]) {
         let mut storage = tags.get_storage_or_default_mut::<T>();
         for (i, tag) in self.to_load.drain(..).enumerate() {
@@ -117,3 +137,58 @@ impl<F: SaveFormat + 'static, T: Tag + SaveLoad + 'static> GenericTagLoader<F> f
         }
     }
 }
+
+/// Deserializes a buffer of intermediates saved at `version`, migrating them if they're older
+/// than [`SaveLoad::VERSION`].
+pub fn deserialize_intermediates<F: SaveFormat, T: SaveLoad>(
+    type_name: &str,
+    version: u32,
+    raw: Vec<u8>,
+) -> Result<Vec<T::Intermediate>, LoadError<F>> {
+    if version > T::VERSION {
+        return Err(LoadError::UnsupportedVersion {
+            type_name: type_name.to_owned(),
+            version,
+            supported: T::VERSION,
+        });
+    }
+
+    if version == T::VERSION {
+        F::deserialize(raw).map_err(LoadError::Deserialize)
+    } else {
+        T::deserialize_version::<F>(version, raw).map_err(LoadError::Deserialize)
+    }
+}
+
+/// Loads each intermediate of a set, applying overrides and tracking which entity of the set is
+/// being loaded so missing entity references can be attributed to it.
+fn load_intermediates<F: SaveFormat, T: SaveLoad>(
+    ctx: &mut LoadContext,
+    type_name: &str,
+    intermediates: Vec<T::Intermediate>,
+) -> Result<Vec<T>, LoadError<F>> {
+    intermediates
+        .into_iter()
+        .enumerate()
+        .map(|(i, intermediate)| {
+            let intermediate = ctx
+                .overrides
+                .apply(ctx.set[i], type_name, intermediate)
+                .map_err(|(path, reason)| LoadError::Override {
+                    type_name: type_name.to_owned(),
+                    path,
+                    reason,
+                })?;
+
+            ctx.missing.current = i;
+            let value = T::load(ctx, intermediate);
+            match ctx.missing.error.take() {
+                Some(field) => Err(LoadError::MissingEntity {
+                    type_name: type_name.to_owned(),
+                    field: field.to_owned(),
+                }),
+                None => Ok(value),
+            }
+        })
+        .collect()
+}
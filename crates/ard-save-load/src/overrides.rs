@@ -0,0 +1,136 @@
+use ron::Value;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::entity_map::MappedEntity;
+
+/// Replaces a value within a component or tag as it's loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Override {
+    /// Entity in the saved data the override applies to.
+    pub entity: MappedEntity,
+    /// Name of the component or tag type.
+    pub type_name: String,
+    /// Path to the value within the type's intermediate. Segments are separated by `.` and are
+    /// either field names or indices into tuples and sequences. An empty path replaces the whole
+    /// intermediate.
+    pub path: String,
+    /// The new value, written as RON.
+    pub value: String,
+}
+
+/// A set of overrides, keyed by entity, type, and path.
+///
+/// Overrides are applied by converting the intermediate to a RON value, so they don't support
+/// intermediates containing enums.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Overrides(Vec<Override>);
+
+impl Overrides {
+    /// Overrides the value at `path` in the intermediate of `type_name` on `entity`, replacing
+    /// any existing override for the same value.
+    pub fn set(
+        &mut self,
+        entity: MappedEntity,
+        type_name: &str,
+        path: &str,
+        value: &impl Serialize,
+    ) -> Result<(), ron::Error> {
+        let value = ron::to_string(value)?;
+
+        match self
+            .0
+            .iter_mut()
+            .find(|o| o.entity == entity && o.type_name == type_name && o.path == path)
+        {
+            Some(existing) => existing.value = value,
+            None => self.0.push(Override {
+                entity,
+                type_name: type_name.to_owned(),
+                path: path.to_owned(),
+                value,
+            }),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the override of the value at `path`, returning it if there was one.
+    pub fn remove(
+        &mut self,
+        entity: MappedEntity,
+        type_name: &str,
+        path: &str,
+    ) -> Option<Override> {
+        let idx = self
+            .0
+            .iter()
+            .position(|o| o.entity == entity && o.type_name == type_name && o.path == path)?;
+        Some(self.0.remove(idx))
+    }
+
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = &Override> {
+        self.0.iter()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Applies every override for `type_name` on `entity` to `intermediate`. On failure, the
+    /// path of the override that couldn't be applied is returned along with the reason.
+    pub(crate) fn apply<T: Serialize + DeserializeOwned>(
+        &self,
+        entity: MappedEntity,
+        type_name: &str,
+        intermediate: T,
+    ) -> Result<T, (String, String)> {
+        let mut overrides = self
+            .0
+            .iter()
+            .filter(|o| o.entity == entity && o.type_name == type_name)
+            .peekable();
+
+        // Most intermediates have no overrides, so skip the conversion
+        if overrides.peek().is_none() {
+            return Ok(intermediate);
+        }
+
+        let mut root = ron::to_string(&intermediate)
+            .map_err(|err| err.to_string())
+            .and_then(|s| ron::from_str::<Value>(&s).map_err(|err| err.to_string()))
+            .map_err(|err| (String::default(), err))?;
+
+        for o in overrides {
+            let new_value = ron::from_str::<Value>(&o.value)
+                .map_err(|err| (o.path.clone(), err.to_string()))?;
+            let dst = value_at(&mut root, &o.path)
+                .ok_or_else(|| (o.path.clone(), "no value at path".to_owned()))?;
+            *dst = new_value;
+        }
+
+        root.into_rust::<T>()
+            .map_err(|err| (String::default(), err.to_string()))
+    }
+}
+
+fn value_at<'a>(mut value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        // Optional values are looked through so their contents can be overridden
+        while let Value::Option(Some(inner)) = value {
+            value = inner;
+        }
+
+        value = match value {
+            Value::Map(map) => map
+                .iter_mut()
+                .find(|(key, _)| matches!(key, Value::String(key) if key == segment))
+                .map(|(_, value)| value)?,
+            Value::Seq(seq) => seq.get_mut(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
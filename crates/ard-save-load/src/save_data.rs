@@ -38,6 +38,7 @@ impl<F: SaveFormat + 'static> Saver<F> {
             TypeId::of::<T>(),
             SavingMetaData::Info {
                 type_name: T::NAME.to_owned(),
+                version: T::VERSION,
                 new_saver: || Box::new(ComponentSaver::<F, T>::default()),
             },
         );
@@ -49,6 +50,7 @@ impl<F: SaveFormat + 'static> Saver<F> {
             TypeId::of::<T>(),
             SavingMetaData::Info {
                 type_name: T::NAME.to_owned(),
+                version: T::VERSION,
                 new_saver: || Box::new(TagSaver::<F, T>::default()),
             },
         );
@@ -178,6 +180,7 @@ enum SavingMetaData<F: SaveFormat> {
     Ignore,
     Info {
         type_name: String,
+        version: u32,
         new_saver: fn() -> Box<dyn GenericSaver<F>>,
     },
 }
@@ -206,13 +209,16 @@ impl<F: SaveFormat> SavingData<F> {
             .savers
             .into_iter()
             .map(|(ty, mut buff_saver)| {
-                let type_name = match saver.meta_data.get(&ty).unwrap() {
+                let (type_name, version) = match saver.meta_data.get(&ty).unwrap() {
                     SavingMetaData::Ignore => unreachable!(),
-                    SavingMetaData::Info { type_name, .. } => type_name.clone(),
+                    SavingMetaData::Info {
+                        type_name, version, ..
+                    } => (type_name.clone(), *version),
                 };
 
                 Ok(SavedDataBuffer {
                     type_name,
+                    version,
                     raw: buff_saver.serialize_all()?,
                 })
             })
@@ -263,5 +269,13 @@ pub struct SavedSet {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SavedDataBuffer {
     pub type_name: String,
+    /// [`SaveLoad::VERSION`] of the type when the buffer was saved. Data saved before versions
+    /// were recorded is version 1.
+    #[serde(default = "first_version")]
+    pub version: u32,
     pub raw: Vec<u8>,
 }
+
+fn first_version() -> u32 {
+    1
+}
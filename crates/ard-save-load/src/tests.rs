@@ -0,0 +1,449 @@
+use ard_assets::manager::Assets;
+use ard_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entity_map::MappedEntity,
+    format::{Bincode, Ron, SaveFormat},
+    load_data::{LoadError, Loader},
+    loader::deserialize_intermediates,
+    overrides::Overrides,
+    save_data::Saver,
+    Migrate, SaveLoad,
+};
+
+mod v1 {
+    use crate::SaveLoad;
+
+    #[derive(SaveLoad)]
+    pub struct Light {
+        pub intensity: f32,
+        pub color: [f32; 3],
+    }
+
+    #[derive(SaveLoad)]
+    pub enum Shape {
+        Sphere { radius: f32 },
+        Cuboid([f32; 3]),
+        Empty,
+    }
+}
+
+mod v2 {
+    use crate::SaveLoad;
+
+    #[derive(SaveLoad)]
+    #[save_load(version = 2, migrate)]
+    pub struct Light {
+        pub intensity: f32,
+        pub color: [f32; 3],
+        #[save_load(since = 2, default = "10.0")]
+        pub range: f32,
+        #[save_load(since = 2)]
+        pub shadows: bool,
+    }
+
+    #[derive(SaveLoad)]
+    #[save_load(version = 2)]
+    pub enum Shape {
+        Sphere {
+            radius: f32,
+        },
+        #[save_load(since = 2)]
+        Capsule {
+            radius: f32,
+            height: f32,
+        },
+        Cuboid([f32; 3], #[save_load(since = 2, default = "0.5")] f32),
+        Empty,
+    }
+}
+
+impl Migrate for v2::Light {
+    fn migrate(old_version: u32, value: &mut Self::Intermediate) {
+        // Intensity used to be stored in hundreds of lumens
+        if old_version < 2 {
+            value.intensity *= 100.0;
+        }
+    }
+}
+
+fn save<F: SaveFormat, T: Serialize>(intermediates: Vec<T>) -> Vec<u8> {
+    F::serialize(&intermediates).unwrap()
+}
+
+fn load_v1_light<F: SaveFormat>() {
+    let raw = save::<F, _>(vec![
+        v1::LightSaveDescriptor {
+            intensity: 2.0,
+            color: [1.0, 0.5, 0.25],
+        },
+        v1::LightSaveDescriptor {
+            intensity: 0.5,
+            color: [0.0, 0.0, 1.0],
+        },
+    ]);
+
+    let lights =
+        deserialize_intermediates::<F, v2::Light>("Light", v1::Light::VERSION, raw).unwrap();
+
+    assert_eq!(lights.len(), 2);
+    assert_eq!(lights[0].intensity, 200.0);
+    assert_eq!(lights[0].color, [1.0, 0.5, 0.25]);
+    assert_eq!(lights[0].range, 10.0);
+    assert!(!lights[0].shadows);
+    assert_eq!(lights[1].intensity, 50.0);
+    assert_eq!(lights[1].color, [0.0, 0.0, 1.0]);
+    assert_eq!(lights[1].range, 10.0);
+}
+
+fn load_v1_shape<F: SaveFormat>() {
+    let raw = save::<F, _>(vec![
+        v1::ShapeSaveDescriptor::Sphere { radius: 3.0 },
+        v1::ShapeSaveDescriptor::Cuboid([1.0, 2.0, 3.0]),
+        v1::ShapeSaveDescriptor::Empty,
+    ]);
+
+    let shapes =
+        deserialize_intermediates::<F, v2::Shape>("Shape", v1::Shape::VERSION, raw).unwrap();
+
+    assert_eq!(shapes.len(), 3);
+    assert!(matches!(
+        shapes[0],
+        v2::ShapeSaveDescriptor::Sphere { radius } if radius == 3.0
+    ));
+    assert!(matches!(
+        shapes[1],
+        v2::ShapeSaveDescriptor::Cuboid(extents, rounding)
+            if extents == [1.0, 2.0, 3.0] && rounding == 0.5
+    ));
+    assert!(matches!(shapes[2], v2::ShapeSaveDescriptor::Empty));
+}
+
+#[test]
+fn load_v1_struct_into_v2_bincode() {
+    load_v1_light::<Bincode>();
+}
+
+#[test]
+fn load_v1_struct_into_v2_ron() {
+    load_v1_light::<Ron>();
+}
+
+#[test]
+fn load_v1_enum_into_v2_bincode() {
+    load_v1_shape::<Bincode>();
+}
+
+#[test]
+fn load_v1_enum_into_v2_ron() {
+    load_v1_shape::<Ron>();
+}
+
+#[test]
+fn load_current_version() {
+    let raw = save::<Bincode, _>(vec![v2::ShapeSaveDescriptor::Capsule {
+        radius: 1.0,
+        height: 4.0,
+    }]);
+
+    let shapes =
+        deserialize_intermediates::<Bincode, v2::Shape>("Shape", v2::Shape::VERSION, raw).unwrap();
+
+    assert!(matches!(
+        shapes[0],
+        v2::ShapeSaveDescriptor::Capsule { radius, height } if radius == 1.0 && height == 4.0
+    ));
+}
+
+#[test]
+fn newer_version_is_an_error() {
+    let raw = save::<Bincode, _>(vec![v2::LightSaveDescriptor {
+        intensity: 1.0,
+        color: [1.0; 3],
+        range: 5.0,
+        shadows: true,
+    }]);
+
+    let err = deserialize_intermediates::<Bincode, v1::Light>("Light", v2::Light::VERSION, raw)
+        .err()
+        .unwrap();
+
+    assert!(matches!(
+        err,
+        LoadError::UnsupportedVersion {
+            version: 2,
+            supported: 1,
+            ..
+        }
+    ));
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+struct Id(u32);
+
+#[derive(Component, SaveLoad)]
+struct FollowOrNull {
+    #[save_load(on_missing = "null")]
+    target: Entity,
+}
+
+#[derive(Component, SaveLoad)]
+struct FollowOrSkip {
+    #[save_load(on_missing = "skip_entity")]
+    target: Entity,
+}
+
+#[derive(Component, SaveLoad)]
+enum FollowOrError {
+    Nothing,
+    Entity(#[save_load(on_missing = "error")] Entity),
+}
+
+/// Creates a target with ID 0, a follower of the target with ID 1, and a follower of the first
+/// follower with ID 2. Only the followers are saved, so the first follower's reference dangles
+/// when loaded.
+fn save_followers<C: Component + SaveLoad + 'static>(
+    assets: &Assets,
+    follow: impl Fn(Entity) -> C,
+) -> crate::save_data::SaveData {
+    let mut world = World::new();
+
+    let mut target = [Entity::null()];
+    world
+        .entities()
+        .commands()
+        .create((vec![Id(0)],), &mut target);
+
+    let mut first = [Entity::null()];
+    world
+        .entities()
+        .commands()
+        .create((vec![Id(1)], vec![follow(target[0])]), &mut first);
+
+    let mut second = [Entity::null()];
+    world
+        .entities()
+        .commands()
+        .create((vec![Id(2)], vec![follow(first[0])]), &mut second);
+
+    world.process_entities();
+
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    let (data, _) = Saver::<Bincode>::default()
+        .include_component::<Id>()
+        .include_component::<C>()
+        .save(assets.clone(), &queries, &[first[0], second[0]])
+        .unwrap();
+
+    data
+}
+
+/// Loads the followers, returning the world they were loaded into.
+fn load_followers<C: Component + SaveLoad + 'static>(
+    assets: &Assets,
+    data: crate::save_data::SaveData,
+) -> (World, Result<(), LoadError<Bincode>>) {
+    let mut world = World::new();
+    let res = Loader::<Bincode>::default()
+        .load_component::<Id>()
+        .load_component::<C>()
+        .load(data, assets.clone(), world.entities().commands());
+    world.process_entities();
+    (world, res)
+}
+
+fn loaded_ids(world: &World) -> Vec<(Entity, Id)> {
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    let mut ids: Vec<_> = queries
+        .make::<(Entity, (Read<Id>,))>()
+        .map(|(entity, (id,))| (entity, *id))
+        .collect();
+    ids.sort_by_key(|(_, id)| id.0);
+    ids
+}
+
+#[test]
+fn missing_entity_becomes_null() {
+    let assets = Assets::new();
+    let data = save_followers(&assets, |target| FollowOrNull { target });
+    let (world, res) = load_followers::<FollowOrNull>(&assets, data);
+    res.unwrap();
+
+    let ids = loaded_ids(&world);
+    assert_eq!(ids.len(), 2);
+    assert_eq!(ids[0].1, Id(1));
+    assert_eq!(ids[1].1, Id(2));
+
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    let first = queries.get::<Read<FollowOrNull>>(ids[0].0).unwrap();
+    let second = queries.get::<Read<FollowOrNull>>(ids[1].0).unwrap();
+    assert_eq!(first.target, Entity::null());
+    assert_eq!(second.target, ids[0].0);
+}
+
+#[test]
+fn missing_entity_skips_entity() {
+    let assets = Assets::new();
+    let data = save_followers(&assets, |target| FollowOrSkip { target });
+    let (world, res) = load_followers::<FollowOrSkip>(&assets, data);
+    res.unwrap();
+
+    let ids = loaded_ids(&world);
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0].1, Id(2));
+}
+
+#[test]
+fn missing_entity_aborts_load() {
+    let assets = Assets::new();
+    let data = save_followers(&assets, FollowOrError::Entity);
+    let (world, res) = load_followers::<FollowOrError>(&assets, data);
+
+    match res {
+        Err(LoadError::MissingEntity { type_name, field }) => {
+            assert_eq!(type_name, "FollowOrError");
+            assert_eq!(field, "Entity.0");
+        }
+        _ => panic!("expected a missing entity error"),
+    }
+    assert!(loaded_ids(&world).is_empty());
+}
+
+#[test]
+fn resolvable_entities_load_with_error_policy() {
+    let assets = Assets::new();
+    let mut world = World::new();
+
+    let mut entities = [Entity::null(); 2];
+    world.entities().commands().create(
+        (
+            vec![Id(0), Id(1)],
+            vec![FollowOrError::Nothing, FollowOrError::Nothing],
+        ),
+        &mut entities,
+    );
+    world.process_entities();
+    world
+        .entities()
+        .commands()
+        .add_component(entities[1], FollowOrError::Entity(entities[0]));
+    world.process_entities();
+
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    let (data, _) = Saver::<Bincode>::default()
+        .include_component::<Id>()
+        .include_component::<FollowOrError>()
+        .save(assets.clone(), &queries, &entities)
+        .unwrap();
+
+    let (world, res) = load_followers::<FollowOrError>(&assets, data);
+    res.unwrap();
+
+    let ids = loaded_ids(&world);
+    assert_eq!(ids.len(), 2);
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    let follow = queries.get::<Read<FollowOrError>>(ids[1].0).unwrap();
+    assert!(matches!(*follow, FollowOrError::Entity(target) if *target == ids[0].0));
+}
+
+#[derive(Component, SaveLoad)]
+struct Tint {
+    color: [f32; 3],
+    strength: Option<f32>,
+}
+
+fn save_tints(assets: &Assets) -> crate::save_data::SaveData {
+    let mut world = World::new();
+    let mut entities = [Entity::null(); 2];
+    world.entities().commands().create(
+        (
+            vec![Id(0), Id(1)],
+            vec![
+                Tint {
+                    color: [1.0; 3],
+                    strength: Some(1.0),
+                },
+                Tint {
+                    color: [0.0; 3],
+                    strength: None,
+                },
+            ],
+        ),
+        &mut entities,
+    );
+    world.process_entities();
+
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    Saver::<Bincode>::default()
+        .include_component::<Id>()
+        .include_component::<Tint>()
+        .save(assets.clone(), &queries, &entities)
+        .unwrap()
+        .0
+}
+
+fn load_tints(assets: &Assets, overrides: Overrides) -> (World, Result<(), LoadError<Bincode>>) {
+    let mut world = World::new();
+    let res = Loader::<Bincode>::default()
+        .load_component::<Id>()
+        .load_component::<Tint>()
+        .overrides(overrides)
+        .load(
+            save_tints(assets),
+            assets.clone(),
+            world.entities().commands(),
+        );
+    world.process_entities();
+    (world, res)
+}
+
+#[test]
+fn overrides_replace_loaded_values() {
+    let assets = Assets::new();
+    let mut overrides = Overrides::default();
+    overrides
+        .set(MappedEntity(0), "Tint", "color.1", &0.5_f32)
+        .unwrap();
+    overrides
+        .set(MappedEntity(0), "Tint", "strength", &Some(2.0_f32))
+        .unwrap();
+    overrides.set(MappedEntity(1), "Id", "", &Id(7)).unwrap();
+
+    let (world, res) = load_tints(&assets, overrides);
+    res.unwrap();
+
+    let ids = loaded_ids(&world);
+    assert_eq!(ids[0].1, Id(0));
+    assert_eq!(ids[1].1, Id(7));
+
+    let queries = Queries::<Everything>::new(world.tags(), world.archetypes(), world.entities());
+    let first = queries.get::<Read<Tint>>(ids[0].0).unwrap();
+    let second = queries.get::<Read<Tint>>(ids[1].0).unwrap();
+    assert_eq!(first.color, [1.0, 0.5, 1.0]);
+    assert_eq!(first.strength, Some(2.0));
+    assert_eq!(second.color, [0.0; 3]);
+    assert_eq!(second.strength, None);
+}
+
+#[test]
+fn override_of_missing_path_is_an_error() {
+    let assets = Assets::new();
+    let mut overrides = Overrides::default();
+    overrides
+        .set(MappedEntity(1), "Tint", "color.3", &0.5_f32)
+        .unwrap();
+
+    let (world, res) = load_tints(&assets, overrides);
+
+    match res {
+        Err(LoadError::Override {
+            type_name, path, ..
+        }) => {
+            assert_eq!(type_name, "Tint");
+            assert_eq!(path, "color.3");
+        }
+        _ => panic!("expected an override error"),
+    }
+    assert!(loaded_ids(&world).is_empty());
+}
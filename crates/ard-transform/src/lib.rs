@@ -3,7 +3,10 @@ pub mod system;
 use ard_core::{app::AppBuilder, plugin::Plugin, prelude::Destroy};
 use ard_ecs::{prelude::*, system::data::SystemData};
 use ard_math::*;
-use ard_save_load::{entity_map::MappedEntity, LoadContext, SaveContext, SaveLoad};
+use ard_save_load::{
+    entity_map::{MappedEntity, OnMissing},
+    LoadContext, SaveContext, SaveLoad,
+};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::ops::Mul;
@@ -159,7 +162,7 @@ impl SaveLoad for Parent {
     }
 
     fn load(ctx: &mut LoadContext, intermediate: Self::Intermediate) -> Self {
-        Self(ctx.entity_map.from_map(intermediate.0))
+        Self(ctx.resolve_entity(intermediate.0, OnMissing::Null, "0"))
     }
 }
 
@@ -172,10 +175,11 @@ impl SaveLoad for Children {
 
     fn load(ctx: &mut LoadContext, intermediate: Self::Intermediate) -> Self {
         Self(
+            // Children that weren't loaded are dropped instead of left as null entities
             intermediate
                 .0
                 .into_iter()
-                .map(|e| ctx.entity_map.from_map(e))
+                .filter_map(|e| ctx.entity_map.resolve(e))
                 .collect(),
         )
     }
@@ -0,0 +1,35 @@
+use ard_input::{Clipboard, ClipboardBackend};
+use ard_log::warn;
+
+/// Clipboard backed by the operating system.
+pub struct SystemClipboard(arboard::Clipboard);
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, arboard::Error> {
+        Ok(Self(arboard::Clipboard::new()?))
+    }
+
+    /// Creates a clipboard resource using the system clipboard, falling back to one local to the
+    /// application if the system clipboard is unavailable.
+    pub fn or_local() -> Clipboard {
+        match Self::new() {
+            Ok(clipboard) => Clipboard::new(clipboard),
+            Err(err) => {
+                warn!("system clipboard unavailable: {err}");
+                Clipboard::default()
+            }
+        }
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.0.get_text().ok()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        if let Err(err) = self.0.set_text(text) {
+            warn!("unable to copy to clipboard: {err}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod clipboard;
 pub mod runner;
 pub mod window;
 pub mod windows;
@@ -17,7 +18,8 @@ use std::path::PathBuf;
 
 use ard_core::prelude::*;
 use ard_ecs::{prelude::*, resource::res::Res, system::commands::Commands};
-use ard_input::InputState;
+use ard_input::{ActionMap, InputState};
+use clipboard::SystemClipboard;
 use prelude::WindowId;
 use window::WindowDescriptor;
 
@@ -59,6 +61,8 @@ impl Default for WindowPlugin {
 impl Plugin for WindowPlugin {
     fn build(&mut self, app: &mut AppBuilder) {
         app.add_resource(InputState::default());
+        app.add_resource(ActionMap::default());
+        app.add_resource(SystemClipboard::or_local());
         app.add_resource(self.clone());
         app.with_runner(runner::winit_runner);
     }
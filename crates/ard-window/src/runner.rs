@@ -2,7 +2,8 @@ use std::{cmp::Ordering, time::Instant};
 
 use ard_core::prelude::*;
 use ard_ecs::prelude::*;
-use ard_input::{InputState, Key, MouseButton};
+use ard_input::{ImeEvent, InputState, Key, MouseButton, ScrollDelta};
+use ard_math::{IVec2, Vec2};
 
 use winit::{
     application::ApplicationHandler,
@@ -27,9 +28,7 @@ impl ApplicationHandler for WinitApp {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let mut windows = self.resources.get_mut::<Windows>().unwrap();
         windows.add_pending(event_loop);
-        for window in windows.iter_mut() {
-            window.apply_commands();
-        }
+        windows.apply_commands(|evt| self.dispatcher.submit(evt));
     }
 
     fn device_event(
@@ -70,20 +69,20 @@ impl ApplicationHandler for WinitApp {
 
     fn window_event(
         &mut self,
-        event_loop: &winit::event_loop::ActiveEventLoop,
+        _event_loop: &winit::event_loop::ActiveEventLoop,
         window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
         let mut windows = self.resources.get_mut::<Windows>().unwrap();
         let mut input = self.resources.get_mut::<InputState>().unwrap();
 
-        // Get the Ard ID of the window
-        let ard_id = windows.winit_to_ard_id(window_id).unwrap();
+        // Events might still arrive for windows that were just destroyed
+        let ard_id = match windows.winit_to_ard_id(window_id) {
+            Some(id) => id,
+            None => return,
+        };
         let window = windows.get_mut(ard_id).unwrap();
 
-        // Keep requesting redraw
-        window.winit_window.request_redraw();
-
         match event {
             WindowEvent::Resized(dims) => {
                 self.dispatcher.submit(WindowResized {
@@ -93,6 +92,9 @@ impl ApplicationHandler for WinitApp {
                 });
                 window.update_actual_size_from_backend(dims.width, dims.height);
             }
+            WindowEvent::Moved(position) => {
+                window.update_actual_position_from_backend(IVec2::new(position.x, position.y));
+            }
             WindowEvent::CloseRequested => {
                 self.dispatcher.submit(WindowClosed(ard_id));
             }
@@ -103,22 +105,39 @@ impl ApplicationHandler for WinitApp {
                 });
             }
             WindowEvent::KeyboardInput { event, .. } => {
+                // Control characters (backspace, delete, etc.) are reported through key events
                 if let Some(text) = event.text {
-                    for ch in text.chars() {
-                        if ch != '\u{7f}' {
-                            input.signal_character(ch);
-                        }
-                    }
+                    text.chars()
+                        .filter(|ch| !ch.is_control())
+                        .for_each(|ch| input.signal_character(ch));
                 }
             }
+            WindowEvent::Ime(ime) => input.signal_ime(match ime {
+                winit::event::Ime::Enabled => ImeEvent::Enabled,
+                winit::event::Ime::Preedit(text, _) => ImeEvent::Preedit(text),
+                winit::event::Ime::Commit(text) => ImeEvent::Commit(text),
+                winit::event::Ime::Disabled => ImeEvent::Disabled,
+            }),
+            WindowEvent::Focused(focused) => {
+                window.update_focused_status_from_backend(focused);
+            }
             WindowEvent::CursorMoved { position, .. } => {
+                window.update_cursor_position_from_backend(Some(Vec2::new(
+                    position.x as f32,
+                    position.y as f32,
+                )));
                 input.signal_mouse_pos((position.x, position.y));
             }
+            WindowEvent::CursorLeft { .. } => {
+                window.update_cursor_position_from_backend(None);
+            }
             WindowEvent::MouseWheel { delta, .. } => match delta {
                 winit::event::MouseScrollDelta::LineDelta(h, v) => {
+                    input.signal_scroll_delta(ScrollDelta::Lines(h as f64, v as f64));
                     input.signal_scroll((h as f64, v as f64));
                 }
                 winit::event::MouseScrollDelta::PixelDelta(pos) => {
+                    input.signal_scroll_delta(ScrollDelta::Pixels(pos.x, pos.y));
                     let pos = pos.to_logical::<f64>(window.scale_factor());
                     match pos.x.partial_cmp(&0.0) {
                         Some(Ordering::Greater) => input.signal_scroll((1.0, 0.0)),
@@ -132,6 +151,9 @@ impl ApplicationHandler for WinitApp {
                     }
                 }
             },
+            WindowEvent::PinchGesture { delta, .. } if delta.is_finite() => {
+                input.signal_pinch(delta);
+            }
             WindowEvent::MouseInput { state, button, .. } => {
                 if let Some(button) = winit_to_ard_mouse_button(button) {
                     match state {
@@ -144,40 +166,47 @@ impl ApplicationHandler for WinitApp {
                     }
                 }
             }
-            WindowEvent::RedrawRequested => {
-                // Check if `Stop` was requested
-                if self.resources.get::<ArdCoreState>().unwrap().stopping() {
-                    event_loop.exit();
-
-                    // Handle `Stopping` event
-                    self.dispatcher.run(&mut self.world, &self.resources);
-                } else {
-                    // Create windows if needed and request redraw from the primary window
-                    windows.add_pending(event_loop);
-
-                    // Run window commands
-                    for window in windows.iter_mut() {
-                        window.apply_commands();
-                    }
+            _ => {}
+        }
+    }
 
-                    // Drop so systems in the dispatcher can access these
-                    std::mem::drop(windows);
-                    std::mem::drop(input);
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // Ticks happen once per iteration of the event loop instead of once per window redraw so
+        // that the frame rate doesn't depend on how many windows are open
+        if self.resources.get::<ArdCoreState>().unwrap().stopping() {
+            event_loop.exit();
 
-                    // Compute delta time and submit tick
-                    let now = Instant::now();
-                    self.dispatcher.submit(Tick(now.duration_since(self.last)));
-                    self.last = now;
+            // Handle `Stopping` event
+            self.dispatcher.run(&mut self.world, &self.resources);
+            return;
+        }
 
-                    // Dispatch until events are cleared
-                    self.dispatcher.run(&mut self.world, &self.resources);
+        // Create windows if needed
+        let mut windows = self.resources.get_mut::<Windows>().unwrap();
+        windows.add_pending(event_loop);
 
-                    // Reset input state
-                    self.resources.get_mut::<InputState>().unwrap().flush();
-                }
-            }
-            _ => {}
-        }
+        // Run window commands
+        windows.apply_commands(|evt| self.dispatcher.submit(evt));
+
+        // Drop so systems in the dispatcher can access this
+        std::mem::drop(windows);
+
+        // Compute delta time and submit tick
+        let now = Instant::now();
+        self.dispatcher.submit(Tick(now.duration_since(self.last)));
+        self.last = now;
+
+        // Dispatch until events are cleared
+        self.dispatcher.run(&mut self.world, &self.resources);
+
+        // Reset input state
+        self.resources.get_mut::<InputState>().unwrap().flush();
+
+        // Windows closed during the frame are destroyed once nothing is using them
+        self.resources
+            .get_mut::<Windows>()
+            .unwrap()
+            .remove_destroyed();
     }
 }
 
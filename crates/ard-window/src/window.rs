@@ -1,10 +1,12 @@
 use ard_math::{IVec2, Vec2};
 use winit::{
-    dpi::{LogicalPosition, Position},
+    dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position},
     raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle},
-    window::{CursorGrabMode, CursorIcon},
+    window::{CursorGrabMode, CursorIcon, Fullscreen},
 };
 
+use crate::windows::{get_best_videomode, get_fitting_videomode};
+
 pub struct Window {
     pub(crate) winit_window: winit::window::Window,
     window_handle: RawWindowHandle,
@@ -25,6 +27,7 @@ pub struct Window {
     cursor_locked: bool,
     cursor_position: Option<Vec2>,
     cursor_icon: CursorIcon,
+    ime_cursor_area: Option<(IVec2, IVec2)>,
     focused: bool,
     mode: WindowMode,
     command_queue: Vec<WindowCommand>,
@@ -115,6 +118,13 @@ pub enum WindowCommand {
     SetPosition {
         position: IVec2,
     },
+    SetImeAllowed {
+        allowed: bool,
+    },
+    SetImeCursorArea {
+        position: IVec2,
+        size: IVec2,
+    },
     SetResizeConstraints {
         resize_constraints: WindowResizeConstraints,
     },
@@ -174,6 +184,7 @@ impl Window {
             cursor_locked: window_descriptor.cursor_locked,
             cursor_position: None,
             cursor_icon: CursorIcon::Default,
+            ime_cursor_area: None,
             focused: true,
             mode: window_descriptor.mode,
             command_queue: Vec::new(),
@@ -277,6 +288,7 @@ impl Window {
     /// Modifies the minimum and maximum window bounds for resizing in logical pixels.
     #[inline]
     pub fn set_resize_constraints(&mut self, resize_constraints: WindowResizeConstraints) {
+        self.resize_constraints = resize_constraints;
         self.command_queue
             .push(WindowCommand::SetResizeConstraints { resize_constraints });
     }
@@ -432,9 +444,32 @@ impl Window {
     }
 
     pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        if self.cursor_icon == icon {
+            return;
+        }
+
+        self.cursor_icon = icon;
         self.command_queue.push(WindowCommand::SetCursor { icon });
     }
 
+    /// Allows or disallows IME composition. IME is allowed by default.
+    pub fn set_ime_allowed(&mut self, allowed: bool) {
+        self.command_queue
+            .push(WindowCommand::SetImeAllowed { allowed });
+    }
+
+    /// Moves the IME candidate window next to the given area of the client area, in physical
+    /// pixels. This is usually the text cursor of whatever widget has focus.
+    pub fn set_ime_cursor_area(&mut self, position: IVec2, size: IVec2) {
+        if self.ime_cursor_area == Some((position, size)) {
+            return;
+        }
+
+        self.ime_cursor_area = Some((position, size));
+        self.command_queue
+            .push(WindowCommand::SetImeCursorArea { position, size });
+    }
+
     #[inline]
     pub fn update_focused_status_from_backend(&mut self, focused: bool) {
         self.focused = focused;
@@ -458,15 +493,56 @@ impl Window {
         });
     }
 
-    #[inline]
-    pub fn apply_commands(&mut self) {
-        for command in self.command_queue.drain(..) {
+    /// Applies all queued commands to the backing window.
+    ///
+    /// Returns the new physical size of the client area if the window was resized immediately.
+    /// Platforms that resize asynchronously report the new size through a regular resize event
+    /// instead.
+    pub fn apply_commands(&mut self) -> Option<(u32, u32)> {
+        let mut resized = None;
+
+        for command in std::mem::take(&mut self.command_queue) {
             match command {
-                WindowCommand::SetWindowMode { .. } => todo!(),
+                WindowCommand::SetWindowMode { mode, resolution } => {
+                    let monitor = self
+                        .winit_window
+                        .current_monitor()
+                        .or_else(|| self.winit_window.primary_monitor());
+
+                    let fullscreen = match mode {
+                        WindowMode::Windowed => None,
+                        WindowMode::BorderlessFullscreen => Some(Fullscreen::Borderless(monitor)),
+                        WindowMode::Fullscreen { use_size } => monitor
+                            .filter(|monitor| monitor.video_modes().next().is_some())
+                            .map(|monitor| {
+                                Fullscreen::Exclusive(if use_size {
+                                    get_fitting_videomode(&monitor, resolution.0, resolution.1)
+                                } else {
+                                    get_best_videomode(&monitor)
+                                })
+                            }),
+                    };
+
+                    self.winit_window.set_fullscreen(fullscreen);
+                }
                 WindowCommand::SetTitle { title } => self.winit_window.set_title(title.as_str()),
-                WindowCommand::SetScaleFactor { .. } => todo!(),
-                WindowCommand::SetResolution { .. } => todo!(),
-                WindowCommand::SetVsync { .. } => todo!(),
+                // Winit has no notion of a scale factor override. The override only changes how
+                // logical sizes are converted, which the `SetResolution` that always follows
+                // this command takes care of.
+                WindowCommand::SetScaleFactor { .. } => {}
+                WindowCommand::SetResolution {
+                    logical_resolution: (width, height),
+                    scale_factor,
+                } => {
+                    let size = LogicalSize::new(width as f64, height as f64)
+                        .to_physical::<u32>(scale_factor);
+                    if let Some(size) = self.winit_window.request_inner_size(size) {
+                        self.update_actual_size_from_backend(size.width, size.height);
+                        resized = Some((size.width, size.height));
+                    }
+                }
+                // Vsync is applied by the renderer the next time it presents to the window
+                WindowCommand::SetVsync { .. } => {}
                 WindowCommand::SetResizable { resizable } => {
                     self.winit_window.set_resizable(resizable)
                 }
@@ -502,12 +578,38 @@ impl Window {
                 WindowCommand::SetMinimized { minimized } => {
                     self.winit_window.set_minimized(minimized)
                 }
-                WindowCommand::SetPosition { .. } => todo!(),
-                WindowCommand::SetResizeConstraints { .. } => {
-                    todo!()
+                WindowCommand::SetPosition { position } => self
+                    .winit_window
+                    .set_outer_position(PhysicalPosition::new(position.x, position.y)),
+                WindowCommand::SetImeAllowed { allowed } => {
+                    self.winit_window.set_ime_allowed(allowed)
+                }
+                WindowCommand::SetImeCursorArea { position, size } => {
+                    self.winit_window.set_ime_cursor_area(
+                        PhysicalPosition::new(position.x, position.y),
+                        PhysicalSize::new(size.x.max(0) as u32, size.y.max(0) as u32),
+                    )
+                }
+                WindowCommand::SetResizeConstraints { resize_constraints } => {
+                    let constraints = resize_constraints.check_constraints();
+                    self.winit_window.set_min_inner_size(Some(LogicalSize::new(
+                        constraints.min_width as f64,
+                        constraints.min_height as f64,
+                    )));
+                    self.winit_window.set_max_inner_size(
+                        (constraints.max_width.is_finite() && constraints.max_height.is_finite())
+                            .then(|| {
+                                LogicalSize::new(
+                                    constraints.max_width as f64,
+                                    constraints.max_height as f64,
+                                )
+                            }),
+                    );
                 }
             }
         }
+
+        resized
     }
 
     #[inline]
@@ -9,6 +9,7 @@ use winit::{
 use crate::{
     prelude::WindowId,
     window::{Window, WindowDescriptor, WindowMode},
+    WindowResized,
 };
 
 #[derive(Resource)]
@@ -16,6 +17,7 @@ pub struct Windows {
     windows: FxHashMap<WindowId, Window>,
     winit_to_ard: FxHashMap<winit::window::WindowId, WindowId>,
     to_create: Vec<PendingWindow>,
+    to_destroy: Vec<WindowId>,
     display_handle: OwnedDisplayHandle,
 }
 
@@ -30,6 +32,7 @@ impl Windows {
             windows: FxHashMap::default(),
             winit_to_ard: FxHashMap::default(),
             to_create: Vec::default(),
+            to_destroy: Vec::default(),
             display_handle,
         }
     }
@@ -48,6 +51,13 @@ impl Windows {
         self.to_create.push(PendingWindow { id, descriptor });
     }
 
+    /// Closes a window. The window is destroyed at the end of the current frame, so any surfaces
+    /// presenting to it must be dropped by then. Other windows are unaffected.
+    #[inline]
+    pub fn destroy(&mut self, id: WindowId) {
+        self.to_destroy.push(id);
+    }
+
     #[inline]
     pub fn winit_to_ard_id(&self, id: winit::window::WindowId) -> Option<WindowId> {
         self.winit_to_ard.get(&id).copied()
@@ -73,6 +83,28 @@ impl Windows {
         self.windows.values_mut()
     }
 
+    /// Applies queued commands to every window, submitting a resize event for any window whose
+    /// size changed immediately.
+    pub(crate) fn apply_commands(&mut self, mut on_resize: impl FnMut(WindowResized)) {
+        for (id, window) in self.windows.iter_mut() {
+            if let Some((width, height)) = window.apply_commands() {
+                on_resize(WindowResized {
+                    id: *id,
+                    width,
+                    height,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn remove_destroyed(&mut self) {
+        for id in self.to_destroy.drain(..) {
+            if let Some(window) = self.windows.remove(&id) {
+                self.winit_to_ard.remove(&window.winit_window.id());
+            }
+        }
+    }
+
     pub(crate) fn add_pending(&mut self, event_loop: &ActiveEventLoop) {
         self.to_create.drain(..).for_each(|pending| {
             assert!(
@@ -135,6 +167,7 @@ impl Windows {
 
             let window = event_loop.create_window(attributes).unwrap();
             window.set_cursor_visible(pending.descriptor.cursor_visible);
+            window.set_ime_allowed(true);
             window
                 .set_cursor_grab(if pending.descriptor.cursor_locked {
                     CursorGrabMode::Locked
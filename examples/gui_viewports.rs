@@ -0,0 +1,149 @@
+//! Tearing a GUI panel off into a window of its own.
+//!
+//! Press "Detach" to move the debug panel into a second window. Pressing "Attach", closing the
+//! window, or dragging it back over the main window puts the panel back.
+
+use std::sync::{Arc, Mutex};
+
+use ard_core::prelude::*;
+use ard_ecs::prelude::*;
+use ard_pal::prelude::*;
+use ard_render::{CanvasSize, RenderPlugin, RendererSettings};
+use ard_render_gui::{view::GuiView, Gui};
+use ard_window::prelude::*;
+
+/// Deferred viewports are shown from a callback that must be `'static`, so the panel state is
+/// shared with it.
+#[derive(Default)]
+struct PanelState {
+    detached: bool,
+    /// Where the detached window was last frame. Used to tell when it's being dragged.
+    last_position: Option<egui::Pos2>,
+    frame_time: f32,
+    scale: f32,
+    notes: String,
+}
+
+#[derive(Default)]
+struct DebugPanel {
+    state: Arc<Mutex<PanelState>>,
+}
+
+impl GuiView for DebugPanel {
+    fn show(
+        &mut self,
+        tick: Tick,
+        ctx: &egui::Context,
+        _commands: &Commands,
+        _queries: &Queries<Everything>,
+        _res: &Res<Everything>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.frame_time = tick.0.as_secs_f32() * 1000.0;
+
+        if !state.detached {
+            egui::Window::new("Debug").show(ctx, |ui| {
+                panel_ui(ui, &mut state);
+                if ui.button("Detach").clicked() {
+                    state.detached = true;
+                    state.last_position = None;
+                }
+            });
+            return;
+        }
+        std::mem::drop(state);
+
+        // Open the window next to the main one so it doesn't immediately count as dropped on it
+        let position = ctx
+            .input(|input| input.viewport().outer_rect)
+            .map(|rect| rect.right_top() + egui::vec2(16.0, 0.0))
+            .unwrap_or_default();
+
+        let state = self.state.clone();
+        ctx.show_viewport_deferred(
+            egui::ViewportId::from_hash_of("debug_panel"),
+            egui::ViewportBuilder::default()
+                .with_title("Debug")
+                .with_position(position)
+                .with_inner_size([320.0, 240.0]),
+            move |ctx, _| {
+                let mut state = state.lock().unwrap();
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    panel_ui(ui, &mut state);
+                    if ui.button("Attach").clicked() {
+                        state.detached = false;
+                    }
+                });
+
+                let (close_requested, position, main_window) = ctx.input(|input| {
+                    (
+                        input.viewport().close_requested(),
+                        input.viewport().outer_rect.map(|rect| rect.min),
+                        input
+                            .raw
+                            .viewports
+                            .get(&egui::ViewportId::ROOT)
+                            .and_then(|info| info.outer_rect),
+                    )
+                });
+
+                // Dropping the window onto the main window reattaches the panel
+                let moved = state.last_position.is_some() && position != state.last_position;
+                let over_main = matches!(
+                    (position, main_window),
+                    (Some(position), Some(main_window)) if main_window.contains(position)
+                );
+                state.last_position = position;
+
+                if close_requested || (moved && over_main) {
+                    state.detached = false;
+                }
+            },
+        );
+    }
+}
+
+fn panel_ui(ui: &mut egui::Ui, state: &mut PanelState) {
+    ui.label(format!("Frame Time: {:.2}ms", state.frame_time));
+    ui.add(egui::Slider::new(&mut state.scale, 0.0..=10.0).text("Scale"));
+    ui.text_edit_singleline(&mut state.notes);
+}
+
+fn main() {
+    AppBuilder::new(ard_log::LevelFilter::Info)
+        .add_plugin(ArdCorePlugin)
+        .add_plugin(WindowPlugin {
+            add_primary_window: Some(WindowDescriptor {
+                title: String::from("GUI Viewports"),
+                resizable: true,
+                width: 1280.0,
+                height: 720.0,
+                ..Default::default()
+            }),
+            exit_on_close: true,
+        })
+        .add_plugin(RenderPlugin {
+            window: WindowId::primary(),
+            settings: RendererSettings {
+                present_scene: true,
+                render_time: None,
+                present_mode: PresentMode::Mailbox,
+                output_color_space: ColorSpace::SrgbNonlinear,
+                render_scale: 1.0,
+                canvas_size: CanvasSize(None),
+            },
+            debug: false,
+            pipeline_cache_path: None,
+            adapter_preference: Default::default(),
+        })
+        .add_startup_function(setup)
+        .run();
+}
+
+fn setup(app: &mut App) {
+    app.resources
+        .get_mut::<Gui>()
+        .unwrap()
+        .add_view(DebugPanel::default());
+}
@@ -0,0 +1,323 @@
+//! Thousands of moving point lights over a floor, for stress testing light clustering.
+//!
+//! Use the "Lights" panel to change how many lights there are, resize the cluster grid, and
+//! toggle the light cluster heatmap.
+
+use ard_core::prelude::*;
+use ard_ecs::prelude::*;
+use ard_math::*;
+use ard_pal::prelude::*;
+use ard_render::{
+    factory::Factory, CanvasSize, DebugSettings, DepthSlicing, FroxelSettings, RenderPlugin,
+    RendererSettings,
+};
+use ard_render_base::RenderingMode;
+use ard_render_camera::{Camera, CameraClearColor};
+use ard_render_gui::{view::GuiView, Gui};
+use ard_render_lighting::Light;
+use ard_render_meshes::{mesh::MeshCreateInfo, vertices::VertexAttributes};
+use ard_render_objects::RenderFlags;
+use ard_render_pbr::PbrMaterialData;
+use ard_transform::Model;
+use ard_window::prelude::*;
+use rand::prelude::*;
+
+const INITIAL_LIGHT_COUNT: usize = 4096;
+const MAX_LIGHT_COUNT: usize = 16384;
+const LIGHT_AREA_MIN: Vec3 = Vec3::new(-50.0, 0.5, -50.0);
+const LIGHT_AREA_MAX: Vec3 = Vec3::new(50.0, 6.0, 50.0);
+
+/// Velocity of a light bouncing around inside the light area.
+#[derive(Debug, Component, Copy, Clone)]
+struct Wander(Vec3);
+
+#[derive(SystemState, Default)]
+struct LightWanderer;
+
+impl LightWanderer {
+    fn on_tick(
+        &mut self,
+        evt: Tick,
+        _: Commands,
+        queries: Queries<(Write<Model>, Write<Wander>)>,
+        _: Res<()>,
+    ) {
+        let dt = evt.0.as_secs_f32();
+
+        for (mdl, wander) in queries.make::<(Write<Model>, Write<Wander>)>() {
+            let mut position = Vec3::from(mdl.position()) + wander.0 * dt;
+
+            // Bounce off the edges of the area
+            for axis in 0..3 {
+                if position[axis] < LIGHT_AREA_MIN[axis] || position[axis] > LIGHT_AREA_MAX[axis] {
+                    wander.0[axis] = -wander.0[axis];
+                    position[axis] =
+                        position[axis].clamp(LIGHT_AREA_MIN[axis], LIGHT_AREA_MAX[axis]);
+                }
+            }
+
+            *mdl = Model(Mat4::from_translation(position));
+        }
+    }
+}
+
+impl From<LightWanderer> for System {
+    fn from(wanderer: LightWanderer) -> Self {
+        SystemBuilder::new(wanderer)
+            .with_handler(LightWanderer::on_tick)
+            .build()
+    }
+}
+
+struct LightsPanel {
+    rng: ThreadRng,
+    lights: Vec<Entity>,
+    target_count: usize,
+    range: f32,
+    intensity: f32,
+}
+
+impl LightsPanel {
+    fn spawn_lights(&mut self, commands: &Commands, count: usize) {
+        let mut pack = (
+            Vec::with_capacity(count),
+            Vec::with_capacity(count),
+            Vec::with_capacity(count),
+        );
+
+        for _ in 0..count {
+            pack.0.push(Light::Point {
+                color: Vec3::new(
+                    self.rng.gen_range(0.3..1.0),
+                    self.rng.gen_range(0.3..1.0),
+                    self.rng.gen_range(0.3..1.0),
+                ),
+                range: self.range,
+                intensity: self.intensity,
+            });
+
+            pack.1.push(Model(Mat4::from_translation(Vec3::new(
+                self.rng.gen_range(LIGHT_AREA_MIN.x..LIGHT_AREA_MAX.x),
+                self.rng.gen_range(LIGHT_AREA_MIN.y..LIGHT_AREA_MAX.y),
+                self.rng.gen_range(LIGHT_AREA_MIN.z..LIGHT_AREA_MAX.z),
+            ))));
+
+            let dir = Vec3::new(
+                self.rng.gen_range(-1.0..1.0),
+                self.rng.gen_range(-0.2..0.2),
+                self.rng.gen_range(-1.0..1.0),
+            );
+            pack.2.push(Wander(
+                dir.normalize_or_zero() * self.rng.gen_range(1.0..6.0),
+            ));
+        }
+
+        let first = self.lights.len();
+        self.lights.resize(first + count, Entity::null());
+        commands.entities.create(pack, &mut self.lights[first..]);
+    }
+
+    fn despawn_lights(&mut self, commands: &Commands, count: usize) {
+        let first = self.lights.len() - count;
+        commands.entities.destroy(&self.lights[first..]);
+        self.lights.truncate(first);
+    }
+}
+
+impl Default for LightsPanel {
+    fn default() -> Self {
+        Self {
+            rng: rand::thread_rng(),
+            lights: Vec::default(),
+            target_count: INITIAL_LIGHT_COUNT,
+            range: 4.0,
+            intensity: 16.0,
+        }
+    }
+}
+
+impl GuiView for LightsPanel {
+    fn show(
+        &mut self,
+        tick: Tick,
+        ctx: &egui::Context,
+        commands: &Commands,
+        _queries: &Queries<Everything>,
+        res: &Res<Everything>,
+    ) {
+        let mut debug = res.get_mut::<DebugSettings>().unwrap();
+        let mut froxels = res.get_mut::<FroxelSettings>().unwrap();
+
+        egui::Window::new("Lights").show(ctx, |ui| {
+            egui::Grid::new("_lights_grid").show(ui, |ui| {
+                ui.label("Frame Time");
+                ui.label(format!("{:.2} ms", tick.0.as_secs_f32() * 1000.0));
+                ui.end_row();
+
+                ui.label("Light Count");
+                ui.add(egui::Slider::new(
+                    &mut self.target_count,
+                    0..=MAX_LIGHT_COUNT,
+                ));
+                ui.end_row();
+
+                ui.label("Heatmap");
+                ui.add(egui::Checkbox::new(&mut debug.show_light_clusters, ""));
+                ui.end_row();
+
+                ui.label("Cluster Width");
+                ui.add(egui::Slider::new(
+                    &mut froxels.width,
+                    1..=FroxelSettings::MAX_DIMS.0,
+                ));
+                ui.end_row();
+
+                ui.label("Cluster Height");
+                ui.add(egui::Slider::new(
+                    &mut froxels.height,
+                    1..=FroxelSettings::MAX_DIMS.1,
+                ));
+                ui.end_row();
+
+                ui.label("Cluster Depth");
+                ui.add(egui::Slider::new(
+                    &mut froxels.depth,
+                    1..=FroxelSettings::MAX_DIMS.2,
+                ));
+                ui.end_row();
+
+                ui.label("Depth Slicing");
+                egui::ComboBox::from_id_source("_depth_slicing")
+                    .selected_text(format!("{:?}", froxels.depth_slicing))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut froxels.depth_slicing,
+                            DepthSlicing::Logarithmic,
+                            "Logarithmic",
+                        );
+                        ui.selectable_value(
+                            &mut froxels.depth_slicing,
+                            DepthSlicing::Linear,
+                            "Linear",
+                        );
+                    });
+                ui.end_row();
+            });
+        });
+
+        let count = self.lights.len();
+        if self.target_count > count {
+            self.spawn_lights(commands, self.target_count - count);
+        } else if self.target_count < count {
+            self.despawn_lights(commands, count - self.target_count);
+        }
+    }
+}
+
+fn main() {
+    AppBuilder::new(ard_log::LevelFilter::Info)
+        .add_plugin(ArdCorePlugin)
+        .add_plugin(WindowPlugin {
+            add_primary_window: Some(WindowDescriptor {
+                title: String::from("Light Stress Test"),
+                resizable: true,
+                width: 1280.0,
+                height: 720.0,
+                ..Default::default()
+            }),
+            exit_on_close: true,
+        })
+        .add_plugin(RenderPlugin {
+            window: WindowId::primary(),
+            settings: RendererSettings {
+                present_scene: true,
+                render_time: None,
+                present_mode: PresentMode::Immediate,
+                output_color_space: ColorSpace::SrgbNonlinear,
+                render_scale: 1.0,
+                canvas_size: CanvasSize(None),
+            },
+            debug: false,
+            pipeline_cache_path: None,
+            adapter_preference: Default::default(),
+        })
+        .add_system(LightWanderer)
+        .add_startup_function(setup)
+        .run();
+}
+
+fn setup(app: &mut App) {
+    let factory = app.resources.get::<Factory>().unwrap();
+
+    app.resources
+        .get_mut::<Gui>()
+        .unwrap()
+        .add_view(LightsPanel::default());
+
+    // Floor for the lights to shine on
+    let quad = factory
+        .create_mesh(MeshCreateInfo {
+            debug_name: Some("floor".to_owned()),
+            data: VertexAttributes {
+                indices: [1u32, 0, 2, 2, 0, 3].as_slice(),
+                positions: &[
+                    Vec4::new(-1.0, 0.0, -1.0, 1.0),
+                    Vec4::new(-1.0, 0.0, 1.0, 1.0),
+                    Vec4::new(1.0, 0.0, 1.0, 1.0),
+                    Vec4::new(1.0, 0.0, -1.0, 1.0),
+                ],
+                normals: &[Vec4::new(0.0, 1.0, 0.0, 0.0); 4],
+                tangents: None,
+                uv0: None,
+                uv1: None,
+            },
+        })
+        .unwrap();
+
+    let material = factory.create_pbr_material_instance().unwrap();
+    factory.set_material_data(
+        &material,
+        &PbrMaterialData {
+            alpha_cutoff: 0.0,
+            color: Vec4::new(0.5, 0.5, 0.5, 1.0),
+            metallic: 0.0,
+            roughness: 0.6,
+            occlusion_strength: 0.0,
+            emissive: Vec4::ZERO,
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
+        },
+    );
+
+    app.world.entities().commands().create(
+        (
+            vec![quad],
+            vec![material],
+            vec![Model(Mat4::from_scale(Vec3::new(60.0, 1.0, 60.0)))],
+            vec![RenderingMode::Opaque],
+            vec![RenderFlags::empty()],
+            vec![Static(0)],
+        ),
+        &mut [],
+    );
+    app.resources.get_mut::<DirtyStatic>().unwrap().signal(0);
+
+    // Camera looking over the whole area
+    let position = Vec3::new(0.0, 45.0, -75.0);
+    app.world.entities().commands().create(
+        (
+            vec![Camera {
+                near: 0.03,
+                far: 250.0,
+                fov: 70.0_f32.to_radians(),
+                order: 0,
+                clear_color: CameraClearColor::Color(Vec4::ZERO),
+                flags: RenderFlags::empty(),
+            }],
+            vec![Model(
+                Mat4::look_at_lh(position, Vec3::ZERO, Vec3::Y).inverse(),
+            )],
+        ),
+        &mut [],
+    );
+}
@@ -7,20 +7,24 @@ use ard_input::{InputState, Key};
 use ard_math::*;
 use ard_pal::prelude::*;
 use ard_render::{
-    factory::Factory, system::PostRender, CanvasSize, DebugSettings, MsaaSettings, RenderPlugin,
-    RendererSettings,
+    factory::Factory, system::PostRender, CanvasSize, DebugSettings, DepthSlicing, FroxelSettings,
+    MsaaSettings, RenderPlugin, RenderStatistics, RendererSettings, ShadowSettings,
 };
 use ard_render_assets::{model::ModelAsset, RenderAssetsPlugin};
 use ard_render_base::RenderingMode;
 use ard_render_camera::{Camera, CameraClearColor};
 use ard_render_gui::{view::GuiView, Gui};
 use ard_render_image_effects::{
-    ao::AoSettings, smaa::SmaaSettings, sun_shafts2::SunShaftsSettings,
-    tonemapping::TonemappingSettings,
+    ao::AoSettings,
+    smaa::SmaaSettings,
+    sun_shafts2::SunShaftsSettings,
+    taa::TaaSettings,
+    tonemapping::{Tonemapper, TonemappingSettings},
 };
 use ard_render_lighting::{global::GlobalLighting, Light};
 use ard_render_meshes::{mesh::MeshCreateInfo, vertices::VertexAttributes};
 use ard_render_objects::{Model, RenderFlags};
+use ard_render_particles::ParticleEmitter;
 use ard_render_pbr::PbrMaterialData;
 use ard_render_renderers::pathtracer::PathTracerSettings;
 use ard_window::prelude::*;
@@ -197,13 +201,19 @@ impl GuiView for TestingGui {
         let mut ambient_color = lighting.ambient_color().to_array();
         let mut ambient_intensity = lighting.ambient_intensity();
 
+        let mut shadows = res.get_mut::<ShadowSettings>().unwrap();
+        let mut froxels = res.get_mut::<FroxelSettings>().unwrap();
         let mut tonemapping = res.get_mut::<TonemappingSettings>().unwrap();
         let mut ao = res.get_mut::<AoSettings>().unwrap();
         let mut sun_shafts = res.get_mut::<SunShaftsSettings>().unwrap();
         let mut smaa = res.get_mut::<SmaaSettings>().unwrap();
+        let mut taa = res.get_mut::<TaaSettings>().unwrap();
         let mut msaa = res.get_mut::<MsaaSettings>().unwrap();
         let mut debug = res.get_mut::<DebugSettings>().unwrap();
         let mut pt = res.get_mut::<PathTracerSettings>().unwrap();
+        let statistics = res.get::<RenderStatistics>().unwrap();
+        let mut windows = res.get_mut::<Windows>().unwrap();
+        let window = windows.get_mut(WindowId::primary()).unwrap();
 
         if self.ui_visible {
             egui::Window::new("Welcome").open(&mut self.welcome_open).show(ctx, |ui| {
@@ -273,25 +283,42 @@ impl GuiView for TestingGui {
                     });
 
                     egui::CollapsingHeader::new("Shadows").show(ui, |ui| {
-                        let mut cascades: Vec<_> = lighting.shadow_cascades().into();
+                        egui::Grid::new("_shadow_settings_grid").show(ui, |ui| {
+                            ui.label("Cascades");
+                            let mut count = shadows.cascades.len();
+                            ui.add(egui::Slider::new(
+                                &mut count,
+                                1..=ShadowSettings::MAX_CASCADES,
+                            ));
+                            shadows.set_cascade_count(count);
+                            ui.end_row();
+
+                            ui.label("Max Distance");
+                            ui.add(egui::Slider::new(&mut shadows.max_distance, 10.0..=1000.0));
+                            ui.end_row();
+
+                            ui.label("Split Lambda");
+                            ui.add(egui::Slider::new(&mut shadows.split_lambda, 0.0..=1.0));
+                            ui.end_row();
+                        });
 
-                        for (i, cascade) in cascades.iter_mut().enumerate() {
+                        for (i, cascade) in shadows.cascades.iter_mut().enumerate() {
                             egui::CollapsingHeader::new(format!("Cascade {i}")).show_unindented(
                                 ui,
                                 |ui| {
                                     egui::Grid::new(format!("_shadow_cascade_{i}_settings_grid"))
                                         .show(ui, |ui| {
-                                            ui.label("Min Depth Bias");
+                                            ui.label("Depth Bias");
                                             ui.add(egui::Slider::new(
-                                                &mut cascade.min_depth_bias,
-                                                0.0..=8.0,
+                                                &mut cascade.depth_bias,
+                                                0.0..=16.0,
                                             ));
                                             ui.end_row();
 
-                                            ui.label("Max Depth Bias");
+                                            ui.label("Slope Bias");
                                             ui.add(egui::Slider::new(
-                                                &mut cascade.max_depth_bias,
-                                                0.0..=8.0,
+                                                &mut cascade.slope_bias,
+                                                0.0..=16.0,
                                             ));
                                             ui.end_row();
 
@@ -309,15 +336,10 @@ impl GuiView for TestingGui {
                                             ));
                                             ui.end_row();
 
-                                            ui.label("End Distance");
-                                            ui.add(egui::DragValue::new(&mut cascade.end_distance));
-                                            ui.end_row();
-
                                             ui.label("Resolution");
                                             ui.add(egui::DragValue::new(&mut cascade.resolution));
                                             ui.end_row();
 
-                                            cascade.end_distance = cascade.end_distance.max(0.0);
                                             cascade.resolution =
                                                 cascade.resolution.clamp(1024, 8192);
                                         });
@@ -325,7 +347,100 @@ impl GuiView for TestingGui {
                             );
                         }
 
-                        lighting.set_shadow_cascade_settings(&cascades);
+                        egui::CollapsingHeader::new("Point and Spot Lights").show_unindented(
+                            ui,
+                            |ui| {
+                                let local = &mut shadows.local;
+                                egui::Grid::new("_local_shadow_settings_grid").show(ui, |ui| {
+                                    ui.label("Enabled");
+                                    ui.add(egui::Checkbox::new(&mut local.enabled, ""));
+                                    ui.end_row();
+
+                                    ui.label("Atlas Size");
+                                    egui::ComboBox::from_id_source("_local_shadow_atlas_size")
+                                        .selected_text(local.atlas_size.to_string())
+                                        .show_ui(ui, |ui| {
+                                            for size in [1024, 2048, 4096, 8192] {
+                                                ui.selectable_value(
+                                                    &mut local.atlas_size,
+                                                    size,
+                                                    size.to_string(),
+                                                );
+                                            }
+                                        });
+                                    ui.end_row();
+
+                                    ui.label("Max Tile Size");
+                                    ui.add(egui::Slider::new(
+                                        &mut local.max_tile_size,
+                                        local.min_tile_size..=local.atlas_size,
+                                    ));
+                                    ui.end_row();
+
+                                    ui.label("Min Tile Size");
+                                    ui.add(egui::Slider::new(&mut local.min_tile_size, 16..=1024));
+                                    ui.end_row();
+
+                                    ui.label("Depth Bias");
+                                    ui.add(egui::Slider::new(&mut local.depth_bias, 0.0..=16.0));
+                                    ui.end_row();
+
+                                    ui.label("Slope Bias");
+                                    ui.add(egui::Slider::new(&mut local.slope_bias, 0.0..=16.0));
+                                    ui.end_row();
+
+                                    ui.label("Normal Bias");
+                                    ui.add(egui::Slider::new(&mut local.normal_bias, 0.0..=0.5));
+                                    ui.end_row();
+
+                                    ui.label("Filter Size");
+                                    ui.add(egui::Slider::new(&mut local.filter_size, 0.0..=8.0));
+                                    ui.end_row();
+                                });
+                            },
+                        );
+                    });
+
+                    egui::CollapsingHeader::new("Light Clusters").show_unindented(ui, |ui| {
+                        egui::Grid::new("_light_cluster_settings_grid").show(ui, |ui| {
+                            ui.label("Width");
+                            ui.add(egui::Slider::new(
+                                &mut froxels.width,
+                                1..=FroxelSettings::MAX_DIMS.0,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Height");
+                            ui.add(egui::Slider::new(
+                                &mut froxels.height,
+                                1..=FroxelSettings::MAX_DIMS.1,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Depth");
+                            ui.add(egui::Slider::new(
+                                &mut froxels.depth,
+                                1..=FroxelSettings::MAX_DIMS.2,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Depth Slicing");
+                            egui::ComboBox::from_id_source("_light_cluster_depth_slicing")
+                                .selected_text(format!("{:?}", froxels.depth_slicing))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut froxels.depth_slicing,
+                                        DepthSlicing::Logarithmic,
+                                        "Logarithmic",
+                                    );
+                                    ui.selectable_value(
+                                        &mut froxels.depth_slicing,
+                                        DepthSlicing::Linear,
+                                        "Linear",
+                                    );
+                                });
+                            ui.end_row();
+                        });
                     });
 
                     egui::CollapsingHeader::new("Tonemapping").show_unindented(ui, |ui| {
@@ -358,6 +473,50 @@ impl GuiView for TestingGui {
                                 0.01..=8.0,
                             ));
                             ui.end_row();
+
+                            ui.label("Tonemapper");
+                            egui::ComboBox::new("_tonemapper_setting", "")
+                                .selected_text(format!("{:?}", tonemapping.tonemapper))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut tonemapping.tonemapper,
+                                        Tonemapper::Aces,
+                                        "Aces",
+                                    );
+                                    ui.selectable_value(
+                                        &mut tonemapping.tonemapper,
+                                        Tonemapper::Reinhard,
+                                        "Reinhard",
+                                    );
+                                    ui.selectable_value(
+                                        &mut tonemapping.tonemapper,
+                                        Tonemapper::Neutral,
+                                        "Neutral",
+                                    );
+                                });
+                            ui.end_row();
+
+                            ui.label("Bloom Threshold");
+                            ui.add(egui::Slider::new(
+                                &mut tonemapping.bloom_threshold,
+                                0.0..=8.0,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Bloom Knee");
+                            ui.add(egui::Slider::new(&mut tonemapping.bloom_knee, 0.0..=4.0));
+                            ui.end_row();
+
+                            ui.label("Bloom Intensity");
+                            ui.add(egui::Slider::new(
+                                &mut tonemapping.bloom_intensity,
+                                0.0..=1.0,
+                            ));
+                            ui.end_row();
+
+                            ui.label("Bypass");
+                            ui.add(egui::Checkbox::new(&mut tonemapping.bypass, ""));
+                            ui.end_row();
                         });
 
                         tonemapping.min_luminance =
@@ -443,6 +602,14 @@ impl GuiView for TestingGui {
                                 ui.add(egui::Checkbox::new(&mut smaa.enabled, ""));
                                 ui.end_row();
 
+                                ui.label("TAA Enabled");
+                                ui.add(egui::Checkbox::new(&mut taa.enabled, ""));
+                                ui.end_row();
+
+                                ui.label("TAA History Weight");
+                                ui.add(egui::Slider::new(&mut taa.history_weight, 0.0..=1.0));
+                                ui.end_row();
+
                                 ui.label("MSAA Setting");
                                 egui::ComboBox::new("_msaa_setting", "")
                                     .selected_text(format!("{:?}", msaa.samples))
@@ -472,6 +639,21 @@ impl GuiView for TestingGui {
                         },
                     );
 
+                    egui::CollapsingHeader::new("Window Settings").show_unindented(ui, |ui| {
+                        egui::Grid::new("_window_settings_grid").show(ui, |ui| {
+                            let mut borderless = window.mode() == WindowMode::BorderlessFullscreen;
+                            ui.label("Borderless Fullscreen");
+                            if ui.add(egui::Checkbox::new(&mut borderless, "")).changed() {
+                                window.set_mode(if borderless {
+                                    WindowMode::BorderlessFullscreen
+                                } else {
+                                    WindowMode::Windowed
+                                });
+                            }
+                            ui.end_row();
+                        });
+                    });
+
                     egui::CollapsingHeader::new("Path Tracer Settings").show_unindented(ui, |ui| {
                         egui::Grid::new("_path_tracer_settings_grid").show(ui, |ui| {
                             ui.label("Enabled");
@@ -485,7 +667,135 @@ impl GuiView for TestingGui {
                             ui.label("Lock Culling");
                             ui.add(egui::Checkbox::new(&mut debug.lock_culling, ""));
                             ui.end_row();
+
+                            ui.label("Pipeline Statistics");
+                            ui.add(egui::Checkbox::new(&mut debug.pipeline_statistics, ""));
+                            ui.end_row();
+
+                            ui.label("Shadow Cascades");
+                            ui.add(egui::Checkbox::new(&mut debug.draw_shadow_cascades, ""));
+                            ui.end_row();
+
+                            ui.label("Cascade Colors");
+                            ui.add(egui::Checkbox::new(&mut debug.show_shadow_cascades, ""));
+                            ui.end_row();
+
+                            ui.label("Light Heatmap");
+                            ui.add(egui::Checkbox::new(&mut debug.show_light_clusters, ""));
+                            ui.end_row();
+
+                            ui.label("Motion Vectors");
+                            ui.add(egui::Checkbox::new(&mut debug.show_motion_vectors, ""));
+                            ui.end_row();
+
+                            ui.label("GPU Wait");
+                            ui.label(format!(
+                                "{:.2} ms",
+                                statistics.gpu_wait_time.as_secs_f32() * 1000.0
+                            ));
+                            ui.end_row();
+
+                            let particles = &statistics.particles;
+                            ui.label("Particles");
+                            ui.label(format!(
+                                "{} live / {} reserved / {} budget",
+                                particles.live(),
+                                particles.reserved,
+                                particles.budget,
+                            ));
+                            ui.end_row();
+
+                            if particles.over_budget > 0 {
+                                ui.label("Emitters Over Budget");
+                                ui.label(particles.over_budget.to_string());
+                                ui.end_row();
+                            }
                         });
+
+                        egui::CollapsingHeader::new("Particle Emitters").show(ui, |ui| {
+                            egui::Grid::new("_particle_emitters_grid")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("Entity");
+                                    ui.label("Live");
+                                    ui.label("Capacity");
+                                    ui.end_row();
+
+                                    for emitter in statistics.particles.emitters.iter() {
+                                        ui.label(format!("{:?}", emitter.entity));
+                                        ui.label(emitter.live.to_string());
+                                        ui.label(emitter.capacity.to_string());
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+
+                        egui::CollapsingHeader::new("Local Shadows").show(ui, |ui| {
+                            let local = statistics.local_shadows;
+                            egui::Grid::new("_local_shadow_statistics_grid")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("Shadowed Lights");
+                                    ui.label(local.lights_shadowed.to_string());
+                                    ui.end_row();
+
+                                    ui.label("Unshadowed Lights");
+                                    ui.label(local.lights_unshadowed.to_string());
+                                    ui.end_row();
+
+                                    ui.label("Tiles");
+                                    ui.label(local.tiles_used.to_string());
+                                    ui.end_row();
+
+                                    ui.label("Atlas Usage");
+                                    ui.label(format!("{:.1}%", local.atlas_usage * 100.0));
+                                    ui.end_row();
+                                });
+                        });
+
+                        egui::CollapsingHeader::new("Culling").show(ui, |ui| {
+                            egui::Grid::new("_culling_statistics_grid")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("Pass");
+                                    ui.label("Tested");
+                                    ui.label("Frustum");
+                                    ui.label("Occlusion");
+                                    ui.label("Meshlets");
+                                    ui.end_row();
+
+                                    for (pass, stats) in statistics.culling.iter() {
+                                        ui.label(pass);
+                                        ui.label(stats.instances_tested.to_string());
+                                        ui.label(stats.frustum_culled.to_string());
+                                        ui.label(stats.occlusion_culled.to_string());
+                                        ui.label(stats.meshlets_drawn.to_string());
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+
+                        if debug.pipeline_statistics {
+                            egui::Grid::new("_pipeline_statistics_grid")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("Pass");
+                                    ui.label("Task");
+                                    ui.label("Mesh");
+                                    ui.label("Clipped");
+                                    ui.label("Fragment");
+                                    ui.end_row();
+
+                                    for (pass, stats) in statistics.pipeline.iter() {
+                                        ui.label(pass);
+                                        ui.label(stats.task_shader_invocations.to_string());
+                                        ui.label(stats.mesh_shader_invocations.to_string());
+                                        ui.label(stats.clipping_primitives.to_string());
+                                        ui.label(stats.fragment_shader_invocations.to_string());
+                                        ui.end_row();
+                                    }
+                                });
+                        }
                     });
                 });
         }
@@ -564,10 +874,13 @@ fn main() {
                 present_scene: true,
                 render_time: None,
                 present_mode: PresentMode::Mailbox,
+                output_color_space: ColorSpace::SrgbNonlinear,
                 render_scale: 1.0,
                 canvas_size: CanvasSize(None),
             },
             debug: true,
+            pipeline_cache_path: None,
+            adapter_preference: Default::default(),
         })
         .add_plugin(RenderAssetsPlugin)
         .add_system(FrameRate::default())
@@ -590,7 +903,7 @@ fn setup(app: &mut App) {
     assets.wait_for_load(&sphere_model);
 
     // Instantiate models
-    let instance = assets.get(&bistro_model).unwrap().instantiate(&assets);
+    let instance = assets.get(&bistro_model).unwrap().instantiate(&assets, &factory);
     app.world.entities().commands().create(
         (
             vec![Static(0); instance.meshes.meshes.len()],
@@ -639,6 +952,10 @@ fn setup(app: &mut App) {
                     color: Vec4::new(0.0, 0.0, 1.0, 1.0), // Vec4::new(0.95, 0.64, 0.54, 1.0),
                     metallic: y as f32 / (SPHERE_Y as f32 - 1.0),
                     roughness: (x as f32 / (SPHERE_X as f32 - 1.0)).max(0.045),
+                    occlusion_strength: 0.0,
+                    emissive: Vec4::ZERO,
+                    uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+                    uv_offset: Vec4::ZERO,
                 },
             );
 
@@ -743,6 +1060,10 @@ fn setup(app: &mut App) {
             color: Vec4::new(0.5, 0.5, 0.5, 1.0),
             metallic: 0.0,
             roughness: 1.0,
+            occlusion_strength: 0.0,
+            emissive: Vec4::ZERO,
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
         },
     );
 
@@ -761,6 +1082,38 @@ fn setup(app: &mut App) {
         &mut [],
     );
 
+    // Emissive quad that should be bright enough to bloom
+    let emissive_material = factory.create_pbr_material_instance().unwrap();
+    factory.set_material_data(
+        &emissive_material,
+        &PbrMaterialData {
+            alpha_cutoff: 0.0,
+            color: Vec4::new(0.0, 0.0, 0.0, 1.0),
+            metallic: 0.0,
+            roughness: 1.0,
+            occlusion_strength: 0.0,
+            emissive: Vec4::new(8.0, 4.0, 1.0, 0.0),
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
+        },
+    );
+
+    app.world.entities().commands().create(
+        (
+            vec![quad.clone()],
+            vec![emissive_material.clone()],
+            vec![Model(
+                Mat4::from_translation(Vec3::new(0.0, 2.0, 4.0))
+                    * Mat4::from_rotation_x(std::f32::consts::FRAC_PI_2)
+                    * Mat4::from_scale(Vec3::new(2.0, 1.0, 2.0)),
+            )],
+            vec![RenderingMode::Opaque],
+            vec![RenderFlags::empty()],
+            vec![Static(0)],
+        ),
+        &mut [],
+    );
+
     // Create a mesh
     let mesh = factory
         .create_mesh(MeshCreateInfo {
@@ -793,6 +1146,10 @@ fn setup(app: &mut App) {
             color: Vec4::new(1.0, 1.0, 1.0, 0.2),
             metallic: 0.0,
             roughness: 1.0,
+            occlusion_strength: 0.0,
+            emissive: Vec4::ZERO,
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
         },
     );
 
@@ -804,6 +1161,10 @@ fn setup(app: &mut App) {
             color: Vec4::new(1.0, 0.0, 0.0, 0.2),
             metallic: 0.0,
             roughness: 1.0,
+            occlusion_strength: 0.0,
+            emissive: Vec4::ZERO,
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
         },
     );
 
@@ -815,6 +1176,10 @@ fn setup(app: &mut App) {
             color: Vec4::new(0.0, 1.0, 0.0, 0.2),
             metallic: 0.0,
             roughness: 1.0,
+            occlusion_strength: 0.0,
+            emissive: Vec4::ZERO,
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
         },
     );
 
@@ -826,6 +1191,10 @@ fn setup(app: &mut App) {
             color: Vec4::new(0.0, 0.0, 1.0, 0.2),
             metallic: 0.0,
             roughness: 1.0,
+            occlusion_strength: 0.0,
+            emissive: Vec4::ZERO,
+            uv_transform: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            uv_offset: Vec4::ZERO,
         },
     );
 
@@ -846,6 +1215,31 @@ fn setup(app: &mut App) {
         &mut camera,
     );
 
+    // Create a particle emitter
+    app.world.entities().commands().create(
+        (
+            vec![ParticleEmitter {
+                rate: 400.0,
+                max_particles: 4096,
+                lifetime: 6.0,
+                speed: 1.5,
+                spread: 0.3,
+                radius: 0.25,
+                gravity: Vec3::new(0.0, 0.4, 0.0),
+                drag: 0.2,
+                noise_strength: 1.5,
+                noise_frequency: 0.5,
+                noise_speed: 0.3,
+                start_size: 0.15,
+                end_size: 0.6,
+                start_color: Vec4::new(1.0, 0.6, 0.2, 0.8),
+                end_color: Vec4::new(0.3, 0.3, 0.3, 0.0),
+            }],
+            vec![Model(Mat4::from_translation(Vec3::new(4.0, 0.0, 4.0)))],
+        ),
+        &mut [],
+    );
+
     app.dispatcher.add_system(CameraMover {
         cursor_locked: false,
         look_speed: 0.1,
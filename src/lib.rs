@@ -40,6 +40,10 @@ pub mod save_load {
     pub use ard_save_load::*;
 }
 
+pub mod prefab {
+    pub use ard_prefab::*;
+}
+
 pub mod physics {
     pub use ard_physics::*;
 }
@@ -65,6 +69,7 @@ pub mod render {
     pub use ard_render_material::material_instance::*;
     pub use ard_render_meshes::mesh::*;
     pub use ard_render_objects::*;
+    pub use ard_render_particles::*;
     pub use ard_render_pbr::*;
     pub use ard_render_renderers::entities::{EntitySelected, SelectEntity};
 }
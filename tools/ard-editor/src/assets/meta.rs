@@ -4,6 +4,7 @@ use ard_engine::{
     assets::asset::{Asset, AssetNameBuf},
     formats::texture::Sampler,
     game::save_data::SceneAsset,
+    prefab::asset::PrefabAsset,
     render::{
         material::MaterialAsset,
         mesh::MeshAsset,
@@ -25,6 +26,7 @@ pub enum MetaData {
     Model,
     Material,
     Scene,
+    Prefab,
     Texture(TextureImportSettings),
 }
 
@@ -50,6 +52,7 @@ pub enum AssetType {
     Texture,
     Material,
     Scene,
+    Prefab,
 }
 
 impl MetaFile {
@@ -61,6 +64,7 @@ impl MetaData {
         match self {
             MetaData::Model => AssetType::Model,
             MetaData::Scene => AssetType::Scene,
+            MetaData::Prefab => AssetType::Prefab,
             MetaData::Material => AssetType::Material,
             MetaData::Texture { .. } => AssetType::Texture,
         }
@@ -134,6 +138,7 @@ impl<'a> TryFrom<&'a Path> for AssetType {
             | TextureAsset::EXTENSION => Ok(AssetType::Texture),
             MaterialAsset::EXTENSION => Ok(AssetType::Material),
             SceneAsset::EXTENSION => Ok(AssetType::Scene),
+            PrefabAsset::EXTENSION => Ok(AssetType::Prefab),
             _ => Err(anyhow::Error::msg("Unknown extension.")),
         }
     }
@@ -15,6 +15,7 @@ use ard_engine::{
     },
     game::save_data::SceneAssetHeader,
     log::info,
+    prefab::asset::PrefabAsset,
 };
 use enum_dispatch::enum_dispatch;
 use path_macro::path;
@@ -29,6 +30,7 @@ pub enum AssetHeader {
     TextureHeader,
     MaterialHeader(MaterialHeader<AssetNameBuf>),
     SceneAssetHeader,
+    PrefabAsset,
 }
 
 #[enum_dispatch(AssetHeader)]
@@ -140,6 +142,7 @@ impl AssetHeader {
             AssetType::Scene => {
                 Ok(bincode::deserialize_from::<_, SceneAssetHeader>(reader)?.into())
             }
+            AssetType::Prefab => Ok(bincode::deserialize_from::<_, PrefabAsset>(reader)?.into()),
         }
     }
 
@@ -152,6 +155,7 @@ impl AssetHeader {
             AssetHeader::TextureHeader(header) => bincode::serialize_into(writer, &header)?,
             AssetHeader::MaterialHeader(header) => bincode::serialize_into(writer, &header)?,
             AssetHeader::SceneAssetHeader(header) => bincode::serialize_into(writer, &header)?,
+            AssetHeader::PrefabAsset(prefab) => bincode::serialize_into(writer, &prefab)?,
         })
     }
 }
@@ -268,6 +272,8 @@ impl AssetOps for MaterialHeader<AssetNameBuf> {
                 diffuse_map,
                 normal_map,
                 metallic_roughness_map,
+                emissive_map,
+                occlusion_map,
                 ..
             } => {
                 if let Some(tex) = diffuse_map {
@@ -279,6 +285,12 @@ impl AssetOps for MaterialHeader<AssetNameBuf> {
                 if let Some(tex) = metallic_roughness_map {
                     func(tex)?;
                 }
+                if let Some(tex) = emissive_map {
+                    func(tex)?;
+                }
+                if let Some(tex) = occlusion_map {
+                    func(tex)?;
+                }
             }
         }
         Ok(())
@@ -329,3 +341,25 @@ impl AssetOps for SceneAssetHeader {
         Ok(())
     }
 }
+
+impl AssetOps for PrefabAsset {
+    fn save(&mut self, asset_name: &AssetName, ctx: &mut SaveContext) -> Result<()> {
+        let out_path = path!(ctx.package_root / asset_name);
+        let file = File::create(out_path)?;
+        let writer = BufWriter::new(file);
+        bincode::serialize_into(writer, self)?;
+        Ok(())
+    }
+
+    fn visit_data(&mut self, _func: impl FnMut(&mut AssetNameBuf) -> Result<()>) -> Result<()> {
+        Ok(())
+    }
+
+    // Nested prefabs are shared with other prefabs, so they aren't owned by this one
+    fn visit_sub_assets(
+        &mut self,
+        _func: impl FnMut(&mut AssetNameBuf) -> Result<()>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
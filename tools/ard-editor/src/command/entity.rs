@@ -15,7 +15,7 @@ use rustc_hash::FxHashSet;
 
 use crate::{camera::SceneViewCamera, scene_graph::SceneGraph, selected::Selected};
 
-use super::EditorCommand;
+use super::{EditorCommand, EditorCommands, Gesture};
 
 pub const INSTANTIATE_DISTANCE: f32 = 8.0;
 
@@ -38,6 +38,22 @@ pub struct SetParentCommand {
     old_index: usize,
 }
 
+/// Restores an entity to snapshots taken before and after an edit made directly to its
+/// components.
+pub struct ModifyEntity {
+    entity: Entity,
+    before: TransientEntities,
+    after: TransientEntities,
+    gesture: Option<Gesture>,
+}
+
+/// Tracks a continuous edit made directly to an entity, like dragging a value, and submits it as
+/// a [`ModifyEntity`].
+#[derive(Default)]
+pub struct EntityEdit {
+    edit: Option<(Entity, Gesture, TransientEntities)>,
+}
+
 pub struct PasteEntity {
     transient: TransientEntities,
     parent: Option<Entity>,
@@ -74,6 +90,119 @@ impl EditorCommand for DestroyEntity {
         let dirty_static = res.get::<DirtyStatic>().unwrap();
         std::mem::take(&mut self.transient).load_internal(commands, &dirty_static, assets);
     }
+
+    fn forget(
+        &mut self,
+        commands: &Commands,
+        _queries: &Queries<Everything>,
+        _res: &Res<Everything>,
+    ) {
+        commands
+            .entities
+            .destroy(self.transient.internal_entities());
+    }
+}
+
+impl ModifyEntity {
+    /// `before` must be a snapshot from [`ModifyEntity::snapshot`] taken before the entity was
+    /// modified. The entity is snapshotted again when the command is applied.
+    pub fn new(entity: Entity, before: TransientEntities) -> Self {
+        Self {
+            entity,
+            before,
+            after: TransientEntities::default(),
+            gesture: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn snapshot(
+        entity: Entity,
+        queries: &Queries<Everything>,
+        assets: Assets,
+    ) -> TransientEntities {
+        TransientEntities::new(&[entity], queries, assets)
+    }
+
+    #[inline(always)]
+    pub fn with_gesture(mut self, gesture: Gesture) -> Self {
+        self.gesture = Some(gesture);
+        self
+    }
+}
+
+impl EditorCommand for ModifyEntity {
+    fn apply(
+        &mut self,
+        _commands: &Commands,
+        queries: &Queries<Everything>,
+        res: &Res<Everything>,
+    ) {
+        // The edit has already been made, so we only need to see what it did
+        let assets = res.get::<Assets>().unwrap().clone();
+        self.after = Self::snapshot(self.entity, queries, assets);
+    }
+
+    fn redo(&mut self, commands: &Commands, _queries: &Queries<Everything>, res: &Res<Everything>) {
+        let assets = res.get::<Assets>().unwrap().clone();
+        let dirty_static = res.get::<DirtyStatic>().unwrap();
+        self.after
+            .clone()
+            .load_internal(commands, &dirty_static, assets);
+    }
+
+    fn undo(&mut self, commands: &Commands, _queries: &Queries<Everything>, res: &Res<Everything>) {
+        let assets = res.get::<Assets>().unwrap().clone();
+        let dirty_static = res.get::<DirtyStatic>().unwrap();
+        self.before
+            .clone()
+            .load_internal(commands, &dirty_static, assets);
+    }
+
+    #[inline(always)]
+    fn gesture(&self) -> Option<Gesture> {
+        self.gesture
+    }
+}
+
+impl EntityEdit {
+    /// Snapshots `entity` unless an edit of it is already in progress. Must be called before the
+    /// entity is modified, and while none of its components are borrowed mutably.
+    pub fn begin(&mut self, entity: Entity, queries: &Queries<Everything>, res: &Res<Everything>) {
+        if matches!(self.edit, Some((edited, _, _)) if edited == entity) {
+            return;
+        }
+
+        let assets = res.get::<Assets>().unwrap().clone();
+        self.edit = Some((
+            entity,
+            Gesture::next(),
+            ModifyEntity::snapshot(entity, queries, assets),
+        ));
+    }
+
+    /// Submits the changes made since the edit began. Changes submitted during the same edit are
+    /// merged into one undo entry.
+    pub fn submit(&self, res: &Res<Everything>) {
+        let (entity, gesture, before) = match &self.edit {
+            Some(edit) => edit,
+            None => return,
+        };
+
+        res.get_mut::<EditorCommands>()
+            .unwrap()
+            .submit(ModifyEntity::new(*entity, before.clone()).with_gesture(*gesture));
+    }
+
+    #[inline(always)]
+    pub fn end(&mut self) {
+        self.edit = None;
+    }
+
+    #[inline(always)]
+    pub fn is_editing(&self) -> bool {
+        self.edit.is_some()
+    }
 }
 
 impl EditorCommand for CreateEmptyEntity {
@@ -223,6 +352,18 @@ impl PasteEntity {
             parent,
         }
     }
+
+    fn attach(&self, commands: &Commands, res: &Res<Everything>) {
+        commands.entities.add_component(
+            self.pasted[0],
+            SetParent {
+                new_parent: self.parent,
+                index: usize::MAX,
+            },
+        );
+
+        *res.get_mut::<Selected>().unwrap() = Selected::Entity(self.pasted[0]);
+    }
 }
 
 impl EditorCommand for PasteEntity {
@@ -235,27 +376,22 @@ impl EditorCommand for PasteEntity {
             .clone()
             .load_new(commands, &dirty_static, assets);
 
-        commands.entities.add_component(
-            self.pasted[0],
-            SetParent {
-                new_parent: self.parent,
-                index: usize::MAX,
-            },
-        );
+        self.attach(commands, res);
+    }
 
-        *res.get_mut::<Selected>().unwrap() = Selected::Entity(self.pasted[0]);
+    fn redo(&mut self, commands: &Commands, _: &Queries<Everything>, res: &Res<Everything>) {
+        // Load back into the originally pasted entities so references to them stay valid
+        let assets = res.get::<Assets>().unwrap().clone();
+        let dirty_static = res.get::<DirtyStatic>().unwrap();
+        std::mem::take(&mut self.transient).load_internal(commands, &dirty_static, assets);
+
+        self.attach(commands, res);
     }
 
     fn undo(&mut self, commands: &Commands, queries: &Queries<Everything>, res: &Res<Everything>) {
         let assets = res.get::<Assets>().unwrap().clone();
         self.transient = TransientEntities::new(&self.pasted, queries, assets);
-        commands.entities.set_components(
-            &self.pasted,
-            EmptyComponentPack {
-                count: self.pasted.len(),
-            },
-        );
-        self.pasted.clear();
+        self.transient.store(commands, res);
     }
 
     fn clear(
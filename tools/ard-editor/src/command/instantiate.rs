@@ -3,12 +3,14 @@ use ard_engine::{
     assets::manager::Assets,
     core::{core::Name, stat::DirtyStatic},
     ecs::prelude::*,
+    log::error,
     math::Mat4,
     render::{
         loader::{MaterialHandle, MeshHandle},
         model::{ModelAsset, Node, NodeData},
         PrevFrameModel, RenderFlags,
     },
+    save_load::{format::Ron, overrides::Overrides},
     transform::{Children, Model, Parent, Position, Rotation, Scale},
 };
 
@@ -43,6 +45,19 @@ impl EditorCommand for InstantiateCommand {
                 let model = assets.get(&handle).unwrap();
                 self.roots = instantiate_model(&model, commands, &assets);
             }
+            InstantiateAssetHandle::Prefab(handle) => {
+                let assets = res.get::<Assets>().unwrap();
+                let prefab = assets.get(handle).unwrap();
+                match prefab.instantiate(
+                    crate::ser::loader::<Ron>,
+                    &assets,
+                    &commands.entities,
+                    Overrides::default(),
+                ) {
+                    Ok(instantiation) => self.roots = instantiation.roots,
+                    Err(err) => error!("Could not instantiate prefab: {err}"),
+                }
+            }
         }
     }
 
@@ -146,7 +161,8 @@ fn instantiate_model(model: &ModelAsset, commands: &Commands, assets: &Assets) -
                     },
                 });
             }
-            NodeData::MeshGroup(mesh_group) => {
+            // Scenes can't save skinned meshes yet, so they're placed in their bind pose
+            NodeData::MeshGroup(mesh_group) | NodeData::SkinnedMeshGroup { mesh_group, .. } => {
                 let mesh_group = &asset.mesh_groups[*mesh_group];
                 assert!(!mesh_group.0.is_empty());
 
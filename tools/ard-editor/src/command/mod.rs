@@ -1,24 +1,39 @@
 pub mod entity;
 pub mod instantiate;
 
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use ard_engine::{
     core::core::Tick,
     ecs::prelude::*,
-    input::{InputState, Key},
+    input::{ActionMap, Binding, InputState, Key, Modifier},
+    render::GuiInputCapture,
 };
 
-#[derive(Resource, Default)]
+/// Name of the input context holding the editor's bindings.
+pub const EDITOR_INPUT_CONTEXT: &str = "editor";
+pub const UNDO_ACTION: &str = "undo";
+pub const REDO_ACTION: &str = "redo";
+
+#[derive(Resource)]
 pub struct EditorCommands {
     pending: VecDeque<Box<dyn EditorCommand>>,
-    stack: Vec<Box<dyn EditorCommand>>,
+    stack: VecDeque<Box<dyn EditorCommand>>,
     undone_stack: Vec<Box<dyn EditorCommand>>,
+    max_depth: usize,
 }
 
 #[derive(SystemState)]
 pub struct EditorCommandSystem;
 
+/// Identifies a continuous edit, like a gizmo drag. Consecutive commands with the same gesture
+/// are merged into a single entry on the undo stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gesture(u64);
+
 pub trait EditorCommand: Send + Sync + 'static {
     fn apply(&mut self, commands: &Commands, queries: &Queries<Everything>, res: &Res<Everything>);
 
@@ -28,6 +43,7 @@ pub trait EditorCommand: Send + Sync + 'static {
 
     fn undo(&mut self, commands: &Commands, queries: &Queries<Everything>, res: &Res<Everything>);
 
+    /// Called on commands that have been undone when they can no longer be redone.
     fn clear(
         &mut self,
         _commands: &Commands,
@@ -35,14 +51,94 @@ pub trait EditorCommand: Send + Sync + 'static {
         _res: &Res<Everything>,
     ) {
     }
+
+    /// Called on commands that fall off the bottom of the undo stack when it's full.
+    fn forget(
+        &mut self,
+        _commands: &Commands,
+        _queries: &Queries<Everything>,
+        _res: &Res<Everything>,
+    ) {
+    }
+
+    #[inline(always)]
+    fn gesture(&self) -> Option<Gesture> {
+        None
+    }
+
+    /// Called instead of applying a new command that is part of the same gesture as this one. The
+    /// new command is dropped afterwards.
+    fn coalesce(
+        &mut self,
+        commands: &Commands,
+        queries: &Queries<Everything>,
+        res: &Res<Everything>,
+    ) {
+        self.apply(commands, queries, res);
+    }
+}
+
+impl Gesture {
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for EditorCommands {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_DEPTH)
+    }
 }
 
 impl EditorCommands {
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// `max_depth` is how many commands can be undone. It must be at least one.
+    pub fn new(max_depth: usize) -> Self {
+        assert!(max_depth > 0);
+        Self {
+            pending: VecDeque::default(),
+            stack: VecDeque::default(),
+            undone_stack: Vec::default(),
+            max_depth,
+        }
+    }
+
     #[inline(always)]
     pub fn submit(&mut self, command: impl EditorCommand) {
         self.pending.push_back(Box::new(command));
     }
 
+    #[inline(always)]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Commands beyond the new depth are forgotten the next time commands are processed.
+    #[inline(always)]
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        assert!(max_depth > 0);
+        self.max_depth = max_depth;
+    }
+
+    /// Adds the undo and redo bindings to the editor's input context.
+    pub fn bind_inputs(actions: &mut ActionMap) {
+        actions
+            .context_mut(EDITOR_INPUT_CONTEXT)
+            .bind(
+                UNDO_ACTION,
+                Binding::new(Key::Z).with_modifier(Modifier::Ctrl),
+            )
+            .bind(
+                REDO_ACTION,
+                Binding::new(Key::Z)
+                    .with_modifier(Modifier::Ctrl)
+                    .with_modifier(Modifier::Shift),
+            );
+        actions.push_context(EDITOR_INPUT_CONTEXT);
+    }
+
     pub fn reset_all(
         &mut self,
         commands: &Commands,
@@ -89,20 +185,37 @@ impl EditorCommandSystem {
                 })
         }
 
-        // Apply new commands
-        editor_commands
-            .stack
-            .extend(editor_commands.pending.drain(..).map(|mut command| {
+        // Apply new commands, merging those that continue the gesture on top of the stack
+        while let Some(mut command) = editor_commands.pending.pop_front() {
+            let continues_gesture = command.gesture().is_some()
+                && editor_commands.stack.back().and_then(|top| top.gesture()) == command.gesture();
+
+            if continues_gesture {
+                let top = editor_commands.stack.back_mut().unwrap();
+                top.coalesce(&commands, &queries, &res);
+            } else {
                 command.apply(&commands, &queries, &res);
-                command
-            }));
+                editor_commands.stack.push_back(command);
+            }
+        }
+
+        while editor_commands.stack.len() > editor_commands.max_depth {
+            let mut command = editor_commands.stack.pop_front().unwrap();
+            command.forget(&commands, &queries, &res);
+        }
+
+        // Don't undo scene edits while the GUI is using the keyboard, like when typing in a field
+        if res.get::<GuiInputCapture>().unwrap().wants_keyboard {
+            return;
+        }
 
         let input = res.get::<InputState>().unwrap();
-        let undo = input.key(Key::LCtrl) && input.key_down_repeat(Key::Z);
-        let redo = input.key(Key::LCtrl) && input.key_down_repeat(Key::R);
+        let actions = res.get::<ActionMap>().unwrap();
+        let undo = actions.action_down(&input, UNDO_ACTION);
+        let redo = actions.action_down(&input, REDO_ACTION);
 
         if undo {
-            if let Some(mut command) = editor_commands.stack.pop() {
+            if let Some(mut command) = editor_commands.stack.pop_back() {
                 command.undo(&commands, &queries, &res);
                 editor_commands.undone_stack.push(command);
             }
@@ -111,7 +224,7 @@ impl EditorCommandSystem {
         if redo {
             if let Some(mut command) = editor_commands.undone_stack.pop() {
                 command.redo(&commands, &queries, &res);
-                editor_commands.stack.push(command);
+                editor_commands.stack.push_back(command);
             }
         }
     }
@@ -0,0 +1,76 @@
+use ard_engine::{core::prelude::*, ecs::prelude::*, log::*, render::factory::Factory};
+use camino::Utf8PathBuf;
+
+use crate::{
+    assets::CurrentAssetPath,
+    scene_graph::SceneGraph,
+    tasks::{save::SaveSceneTask, EditorTask},
+};
+
+/// Saves the active scene and exits the editor if the device is lost.
+///
+/// Nothing can be drawn without a device, so there is no way to ask the user what to do. The
+/// scene is saved as a new "recovered" scene next to the active one instead, so the original is
+/// never overwritten with a partially edited scene.
+#[derive(Default, SystemState)]
+pub struct DeviceLostSystem {
+    handled: bool,
+}
+
+impl DeviceLostSystem {
+    fn tick(
+        &mut self,
+        _: Tick,
+        commands: Commands,
+        queries: Queries<Everything>,
+        res: Res<Everything>,
+    ) {
+        if self.handled {
+            return;
+        }
+
+        let err = match res.get::<Factory>().unwrap().device_lost() {
+            Some(err) => err,
+            None => return,
+        };
+        self.handled = true;
+        error!("{err}. Saving the scene before exiting.");
+
+        let active_scene = res.get::<SceneGraph>().unwrap().active_scene().cloned();
+        let (folder, name): (Utf8PathBuf, &str) = match &active_scene {
+            Some(scene) => (
+                scene.parent().map(|p| p.into()).unwrap_or_default(),
+                scene
+                    .file_name()
+                    .and_then(|name| name.strip_suffix(".save.meta"))
+                    .unwrap_or("scene"),
+            ),
+            None => (
+                res.get::<CurrentAssetPath>().unwrap().path().into(),
+                "scene",
+            ),
+        };
+        let name = format!("{name}_recovered");
+
+        let mut task = SaveSceneTask::new_named(folder, name.as_str());
+        let result = task
+            .pre_run(&commands, &queries, &res)
+            .and_then(|_| task.run())
+            .and_then(|_| task.complete(&commands, &queries, &res));
+
+        match result {
+            Ok(_) => info!("Scene saved as `{name}`."),
+            Err(err) => error!("Unable to save the scene: {err}"),
+        }
+
+        commands.events.submit(Stop);
+    }
+}
+
+impl From<DeviceLostSystem> for System {
+    fn from(value: DeviceLostSystem) -> Self {
+        SystemBuilder::new(value)
+            .with_handler(DeviceLostSystem::tick)
+            .build()
+    }
+}
@@ -232,6 +232,7 @@ impl AssetsView {
             AssetType::Model => egui_phosphor::fill::CUBE,
             AssetType::Texture => egui_phosphor::fill::FILE_IMAGE,
             AssetType::Scene => egui_phosphor::fill::GLOBE,
+            AssetType::Prefab => egui_phosphor::fill::PACKAGE,
             AssetType::Material => egui_phosphor::fill::SPHERE,
             AssetType::Mesh => egui_phosphor::regular::CUBE_TRANSPARENT,
         };
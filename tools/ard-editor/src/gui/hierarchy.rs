@@ -23,10 +23,7 @@ struct Rename {
 impl HierarchyView {
     pub fn show(&mut self, ctx: EditorViewContext) -> egui_tiles::UiResponse {
         let scene_graph = ctx.res.get::<SceneGraph>().unwrap();
-        let selected = match *ctx.res.get::<Selected>().unwrap() {
-            Selected::Entity(e) => Some(e),
-            _ => None,
-        };
+        let selected = ctx.res.get::<Selected>().unwrap().entities().to_vec();
 
         egui::ScrollArea::vertical()
             .auto_shrink(false)
@@ -38,7 +35,7 @@ impl HierarchyView {
                     queries: ctx.queries,
                     res: ctx.res,
                 };
-                self.show_entities(None, scene_graph.roots(), selected, ctx);
+                self.show_entities(None, scene_graph.roots(), &selected, ctx);
                 ui.allocate_response(ui.available_size(), egui::Sense::click())
             })
             .inner
@@ -57,7 +54,7 @@ impl HierarchyView {
         &mut self,
         mut parent: Option<Entity>,
         entities: &[Entity],
-        selected: Option<Entity>,
+        selected: &[Entity],
         ctx: EditorViewContext,
     ) {
         let frame = egui::Frame::none();
@@ -128,7 +125,7 @@ impl HierarchyView {
     fn show_entity(
         &mut self,
         entity: Entity,
-        selected: Option<Entity>,
+        selected: &[Entity],
         ctx: EditorViewContext,
     ) -> Option<egui::Response> {
         let children = match ctx.queries.get::<Read<Children>>(entity) {
@@ -161,7 +158,7 @@ impl HierarchyView {
                 |ui| {
                     ui.add({
                         let mut text = egui::RichText::new(name);
-                        if selected == Some(entity) {
+                        if selected.contains(&entity) {
                             text = text.strong();
                         }
                         egui::Label::new(text)
@@ -195,7 +192,11 @@ impl HierarchyView {
         let click_resp = response.interact(egui::Sense::click());
         if click_resp.clicked() || click_resp.secondary_clicked() {
             let mut selected = ctx.res.get_mut::<Selected>().unwrap();
-            *selected = Selected::Entity(entity);
+            if click_resp.clicked() && ctx.ui.input(|input| input.modifiers.shift) {
+                selected.toggle(entity);
+            } else {
+                *selected = Selected::Entity(entity);
+            }
         }
 
         response.context_menu(|ui| {
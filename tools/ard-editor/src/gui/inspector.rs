@@ -18,11 +18,13 @@ use ard_engine::{
     render::{
         factory::Factory,
         material::MaterialAsset,
+        pbr_uv_transform,
         prelude::{Filter, SamplerAddressMode},
         shape::Shape,
         texture::TextureAsset,
         DebugDraw, DebugDrawing, Mesh, PbrMaterialData, TextureSlot, PBR_MATERIAL_DIFFUSE_SLOT,
-        PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT, PBR_MATERIAL_NORMAL_SLOT,
+        PBR_MATERIAL_EMISSIVE_SLOT, PBR_MATERIAL_METALLIC_ROUGHNESS_SLOT, PBR_MATERIAL_NORMAL_SLOT,
+        PBR_MATERIAL_OCCLUSION_SLOT,
     },
     transform::Model,
 };
@@ -79,7 +81,7 @@ type AddComponentFn = Box<dyn Fn(Entity, &Commands, &Queries<Everything>, &Res<E
 impl Default for InspectorView {
     fn default() -> Self {
         let mut inspectors = Inspectors::default();
-        inspectors.with(TransformInspector);
+        inspectors.with(TransformInspector::default());
         inspectors.with(MaterialInspector);
         inspectors.with(ColliderInspector);
         inspectors.with(RigidBodyInspector);
@@ -212,6 +214,7 @@ impl InspectorView {
                     max_pt: bounds.max_pt.xyz(),
                     model: model.0,
                 },
+                depth_test: false,
             });
         }
 
@@ -443,6 +446,11 @@ impl InspectorView {
                         diffuse_map,
                         normal_map,
                         metallic_roughness_map,
+                        emissive,
+                        emissive_map,
+                        occlusion_strength,
+                        occlusion_map,
+                        uv_transform,
                     } => {
                         ui.label("Base Color");
                         let mut color = base_color.to_array();
@@ -464,6 +472,18 @@ impl InspectorView {
                         changed |= ui.add(egui::Slider::new(alpha_cutoff, 0.0..=1.0)).changed();
                         ui.end_row();
 
+                        ui.label("Emissive");
+                        let mut color = emissive.to_array();
+                        changed |= ui.color_edit_button_rgb(&mut color).changed();
+                        *emissive = color.into();
+                        ui.end_row();
+
+                        ui.label("Occlusion Strength");
+                        changed |= ui
+                            .add(egui::Slider::new(occlusion_strength, 0.0..=1.0))
+                            .changed();
+                        ui.end_row();
+
                         ui.label("Diffuse Map");
                         changed |= texture_input(
                             ui,
@@ -497,7 +517,30 @@ impl InspectorView {
                         );
                         ui.end_row();
 
+                        ui.label("Emissive Map");
+                        changed |= texture_input(
+                            ui,
+                            &assets,
+                            &factory,
+                            material,
+                            TextureSlot::from(PBR_MATERIAL_EMISSIVE_SLOT),
+                            emissive_map,
+                        );
+                        ui.end_row();
+
+                        ui.label("Occlusion Map");
+                        changed |= texture_input(
+                            ui,
+                            &assets,
+                            &factory,
+                            material,
+                            TextureSlot::from(PBR_MATERIAL_OCCLUSION_SLOT),
+                            occlusion_map,
+                        );
+                        ui.end_row();
+
                         if changed {
+                            let (uv_transform, uv_offset) = pbr_uv_transform(*uv_transform);
                             factory.set_material_data(
                                 &material.instance,
                                 &PbrMaterialData {
@@ -505,6 +548,10 @@ impl InspectorView {
                                     color: *base_color,
                                     metallic: *metallic,
                                     roughness: *roughness,
+                                    occlusion_strength: *occlusion_strength,
+                                    emissive: emissive.extend(0.0),
+                                    uv_transform,
+                                    uv_offset,
                                 },
                             );
                         }
@@ -568,6 +615,8 @@ impl InspectorChangeDetectSystem {
         let mismatch = match (selected, &mut inspected.obj) {
             // Both none. No mismatch
             (Selected::None, InspectedObject::None) => false,
+            // Multiple entities can't be inspected at once
+            (Selected::Entities(_), InspectedObject::None) => false,
             // Need to save if the entities mismatch
             (Selected::Entity(selected), InspectedObject::Entity(inspected)) => {
                 *selected != *inspected
@@ -606,6 +655,7 @@ impl InspectorChangeDetectSystem {
             inspected.obj = match selected {
                 Selected::None => InspectedObject::None,
                 Selected::Entity(entity) => InspectedObject::Entity(*entity),
+                Selected::Entities(_) => InspectedObject::None,
                 Selected::Asset(asset) => 'asset: {
                     let asset = match editor_assets.find_asset(asset) {
                         Some(asset) => asset,
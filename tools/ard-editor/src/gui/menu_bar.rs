@@ -1,7 +1,7 @@
 use ard_engine::{
     core::core::Stop,
     ecs::prelude::*,
-    render::{LxaaSettings, PathTracerSettings, SmaaSettings},
+    render::{factory::Factory, LxaaSettings, PathTracerSettings, SmaaSettings},
 };
 
 use crate::{
@@ -12,6 +12,9 @@ use crate::{
 
 pub struct MenuBar;
 
+/// Captures the next frame in RenderDoc.
+const CAPTURE_FRAME_KEY: egui::Key = egui::Key::F12;
+
 impl MenuBar {
     pub fn show(
         &mut self,
@@ -20,6 +23,8 @@ impl MenuBar {
         _queries: &Queries<Everything>,
         res: &Res<Everything>,
     ) {
+        let mut capture_frame = ui.input(|input| input.key_pressed(CAPTURE_FRAME_KEY));
+
         egui::menu::bar(ui, |ui| {
             ui.menu_button("File", |ui| {
                 let editor_assets = res.get::<EditorAssets>().unwrap();
@@ -71,6 +76,22 @@ impl MenuBar {
                     smaa.edge_visualization = !smaa.edge_visualization;
                 }
             });
+
+            ui.menu_button("Debug", |ui| {
+                let button =
+                    egui::Button::new("Capture Frame").shortcut_text(ui.ctx().format_shortcut(
+                        &egui::KeyboardShortcut::new(egui::Modifiers::NONE, CAPTURE_FRAME_KEY),
+                    ));
+
+                if ui.add(button).clicked() {
+                    capture_frame = true;
+                    ui.close_menu();
+                }
+            });
         });
+
+        if capture_frame {
+            res.get::<Factory>().unwrap().trigger_capture();
+        }
     }
 }
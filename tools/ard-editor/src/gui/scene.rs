@@ -6,7 +6,7 @@ use ard_engine::{
     game::GameRunning,
     input::{InputState, Key},
     math::*,
-    render::{CanvasSize, Gui, SelectEntity},
+    render::{CanvasSize, Gui, ScenePicker},
     transform::{Position, Rotation},
 };
 
@@ -30,6 +30,9 @@ use super::{drag_drop::DragDropPayload, transform::TransformGizmo, EditorViewCon
 pub struct SceneView {
     gizmo: TransformGizmo,
     moving_time: f32,
+    /// Set while waiting on a pick. `true` if the picked entity is toggled in the selection
+    /// instead of replacing it.
+    pending_pick: Option<bool>,
 }
 
 impl SceneView {
@@ -56,6 +59,15 @@ impl SceneView {
                     queue.add(StartPlayTask::new(save_task));
                 }
             }
+
+            let mut depth_test = self.gizmo.depth_test();
+            if ui
+                .checkbox(&mut depth_test, "Depth Test Gizmo")
+                .on_hover_text("Hide gizmo handles behind scene geometry")
+                .changed()
+            {
+                self.gizmo.set_depth_test(depth_test);
+            }
         });
 
         // Update the canvas size to match the viewport
@@ -97,18 +109,44 @@ impl SceneView {
         // Entity selection
         if let Some(pos) = response.interact_pointer_pos() {
             if response.clicked() {
-                *ctx.res.get_mut::<Selected>().unwrap() = Selected::None;
+                let additive = ctx.ui.input(|input| input.modifiers.shift);
+                if !additive {
+                    *ctx.res.get_mut::<Selected>().unwrap() = Selected::None;
+                }
+
                 let norm_pos = pos - origin;
                 let uv = Vec2::new(
                     norm_pos.x.max(0.0) / canvas_size.x,
                     norm_pos.y.max(0.0) / canvas_size.y,
                 );
-                ctx.commands.events.submit(SelectEntity(uv));
+                ctx.res
+                    .get_mut::<ScenePicker>()
+                    .unwrap()
+                    .request_pick(uv.x, uv.y);
+                self.pending_pick = Some(additive);
+            }
+        }
+
+        // Apply the pick once the renderer has read it back
+        if let Some(additive) = self.pending_pick {
+            let mut picker = ctx.res.get_mut::<ScenePicker>().unwrap();
+            if let Some(entity) = picker.poll_result() {
+                let mut selected = ctx.res.get_mut::<Selected>().unwrap();
+                if additive {
+                    selected.toggle(entity);
+                } else {
+                    *selected = Selected::Entity(entity);
+                }
+            }
+
+            if !picker.is_pending() {
+                self.pending_pick = None;
             }
         }
 
+        self.gizmo.handle_shortcuts(ctx.ui, &response);
         self.gizmo
-            .show(&ctx, Vec2::new(canvas_size.x, canvas_size.y), response.rect);
+            .show(&ctx, Vec2::new(canvas_size.x, canvas_size.y), &response);
 
         self.move_camera(&ctx, response);
 
@@ -214,7 +252,7 @@ impl SceneView {
 
         let assets = res.get::<Assets>().unwrap();
         match &asset.meta_file().data {
-            MetaData::Model => {
+            MetaData::Model | MetaData::Prefab => {
                 let task_queue = res.get_mut::<TaskQueue>().unwrap();
                 task_queue.add(InstantiateTask::new(
                     asset.meta_file().clone(),
@@ -4,12 +4,12 @@ use ard_engine::{
     core::stat::Static,
     ecs::prelude::*,
     math::*,
-    render::Camera,
+    render::{Camera, DebugDrawing},
     transform::{Model, Parent, Position, Rotation, Scale},
 };
-use transform_gizmo_egui::{math::Transform, prelude::*};
+use transform_gizmo_egui::{gizmo::GizmoInteraction, math::Transform, prelude::*};
 
-use crate::{inspect::transform::EulerRotation, selected::Selected};
+use crate::{command::entity::EntityEdit, inspect::transform::EulerRotation, selected::Selected};
 
 use super::EditorViewContext;
 
@@ -19,10 +19,85 @@ pub struct TransformGizmo {
     transform: Transform,
     selected: Option<Entity>,
     in_use: bool,
+    edit: EntityEdit,
+    tool: GizmoTool,
+    /// Hides handles behind scene geometry. Off by default so handles inside or behind the
+    /// selected mesh can still be seen and grabbed.
+    depth_test: bool,
+}
+
+/// Which kind of transformation the gizmo performs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoTool {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
 }
 
 impl TransformGizmo {
-    pub fn show(&mut self, ctx: &EditorViewContext, canvas_size: Vec2, canvas_rect: Rect) {
+    /// Translation snap increment, in world units, used while `Ctrl` is held.
+    pub const SNAP_DISTANCE: f32 = 0.25;
+    /// Rotation snap increment, in radians, used while `Ctrl` is held.
+    pub const SNAP_ANGLE: f32 = std::f32::consts::PI / 12.0;
+    /// Scale snap increment used while `Ctrl` is held.
+    pub const SNAP_SCALE: f32 = 0.1;
+
+    #[inline(always)]
+    pub fn tool(&self) -> GizmoTool {
+        self.tool
+    }
+
+    #[inline(always)]
+    pub fn set_tool(&mut self, tool: GizmoTool) {
+        self.tool = tool;
+    }
+
+    #[inline(always)]
+    pub fn depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    #[inline(always)]
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    /// Switches tools with `W`, `E`, and `R`. Ignored while another widget has keyboard focus or
+    /// a drag is in progress.
+    pub fn handle_shortcuts(&mut self, ui: &egui::Ui, scene_view: &egui::Response) {
+        if self.in_use || scene_view.dragged() {
+            return;
+        }
+
+        let focused = ui.memory(|mem| mem.focused());
+        if focused.is_some() && focused != Some(scene_view.id) {
+            return;
+        }
+
+        ui.input(|input| {
+            if input.modifiers.any() {
+                return;
+            }
+
+            if input.key_pressed(egui::Key::W) {
+                self.tool = GizmoTool::Translate;
+            } else if input.key_pressed(egui::Key::E) {
+                self.tool = GizmoTool::Rotate;
+            } else if input.key_pressed(egui::Key::R) {
+                self.tool = GizmoTool::Scale;
+            }
+        });
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &EditorViewContext,
+        canvas_size: Vec2,
+        scene_view: &egui::Response,
+    ) {
+        let canvas_rect = scene_view.rect;
+
         let selected = ctx.res.get::<Selected>().unwrap();
 
         let selected_entity = match *selected {
@@ -30,6 +105,7 @@ impl TransformGizmo {
             _ => {
                 self.in_use = false;
                 self.selected = None;
+                self.edit.end();
                 return;
             }
         };
@@ -37,12 +113,14 @@ impl TransformGizmo {
         if !ctx.queries.is_alive(selected_entity) {
             self.in_use = false;
             self.selected = None;
+            self.edit.end();
             return;
         }
 
         if ctx.queries.get::<Read<Model>>(selected_entity).is_none() {
             self.in_use = false;
             self.selected = None;
+            self.edit.end();
             return;
         }
 
@@ -63,7 +141,8 @@ impl TransformGizmo {
             }
         }
 
-        let gpu_struct = camera.into_gpu_struct(canvas_size.x, canvas_size.y, model);
+        // The gizmo is drawn over the final image, so it uses the unjittered projection
+        let gpu_struct = camera.into_gpu_struct(canvas_size.x, canvas_size.y, model, Vec2::ZERO);
         let proj = Mat4::perspective_lh(
             camera.fov,
             gpu_struct.aspect_ratio,
@@ -75,14 +154,20 @@ impl TransformGizmo {
             view_matrix: gpu_struct.view.as_dmat4().into(),
             projection_matrix: proj.as_dmat4().into(),
             viewport: canvas_rect,
-            modes: GizmoMode::all(),
+            pixels_per_point: ctx.ui.ctx().pixels_per_point(),
+            modes: self.tool.modes(),
             orientation: GizmoOrientation::Global,
+            snapping: ctx.ui.input(|input| input.modifiers.ctrl),
+            snap_distance: Self::SNAP_DISTANCE,
+            snap_angle: Self::SNAP_ANGLE,
+            snap_scale: Self::SNAP_SCALE,
             ..Default::default()
         });
 
         if Some(selected_entity) != self.selected {
             self.selected = Some(selected_entity);
             self.in_use = false;
+            self.edit.end();
 
             let object_model = ctx.queries.get::<Read<Model>>(selected_entity).unwrap();
             self.transform = Transform::from_scale_rotation_translation(
@@ -99,6 +184,37 @@ impl TransformGizmo {
         let parent_model_inv = parent_model.inverse();
 
         let model = *ctx.queries.get::<Read<Model>>(selected_entity).unwrap();
+
+        // The gizmo only handles picking and dragging here. Its handles are drawn through the
+        // debug pass so they're depth tested against the scene instead of painted over it.
+        let interaction = ctx.ui.input(|input| {
+            let cursor_pos = input.pointer.hover_pos().unwrap_or_default();
+            self.gizmo.update(
+                GizmoInteraction {
+                    cursor_pos: (cursor_pos.x, cursor_pos.y),
+                    // Presses over windows covering the scene view, or outside of it, mustn't
+                    // grab a handle
+                    drag_started: scene_view.contains_pointer()
+                        && input.pointer.button_pressed(egui::PointerButton::Primary),
+                    dragging: input.pointer.button_down(egui::PointerButton::Primary),
+                },
+                &[self.transform],
+            )
+        });
+
+        self.draw_handles(
+            &mut ctx.res.get_mut::<DebugDrawing>().unwrap(),
+            gpu_struct.view,
+            proj,
+            canvas_rect.width(),
+        );
+
+        // The entity has to be snapshotted before we borrow its transform to modify it
+        let interacting = interaction.is_some();
+        if interacting {
+            self.edit.begin(selected_entity, ctx.queries, ctx.res);
+        }
+
         let mut position = ctx.queries.get::<Write<Position>>(selected_entity).unwrap();
         let mut rotation = ctx
             .queries
@@ -106,7 +222,7 @@ impl TransformGizmo {
             .unwrap();
         let mut scale = ctx.queries.get::<Write<Scale>>(selected_entity).unwrap();
 
-        if let Some((r, t)) = self.gizmo.interact(ctx.ui, &[self.transform]) {
+        if let Some((r, t)) = interaction {
             self.in_use = true;
 
             let new_t = t[0];
@@ -166,10 +282,130 @@ impl TransformGizmo {
             }
         }
 
+        if interacting {
+            std::mem::drop((position, rotation, scale));
+            self.edit.submit(ctx.res);
+        }
+
         ctx.ui.input(|input| {
             if input.pointer.primary_released() && self.in_use {
                 self.in_use = false;
+                self.edit.end();
             }
         });
     }
 }
+
+impl TransformGizmo {
+    /// Number of line segments used for each rotation ring.
+    const RING_SEGMENTS: usize = 48;
+
+    /// Draws the handles of the current tool in world space, sized to match where the gizmo
+    /// picks them on screen.
+    fn draw_handles(&self, debug: &mut DebugDrawing, view: Mat4, proj: Mat4, viewport_width: f32) {
+        let config = self.gizmo.config();
+        let visuals = &config.visuals;
+        let center = DVec3::from(self.transform.translation).as_vec3();
+
+        // Same scaling the gizmo uses to keep a constant size on screen
+        let clip_w = (proj * view * center.extend(1.0)).w;
+        let scale_factor = clip_w / proj.x_axis.x / viewport_width * 2.0;
+        let size = scale_factor * visuals.gizmo_size;
+        let inner_radius = size * 0.2;
+        let plane_size = scale_factor * (visuals.gizmo_size * 0.1 + visuals.stroke_width * 2.0);
+
+        let alpha = if self.gizmo.is_focused() {
+            visuals.highlight_alpha
+        } else {
+            visuals.inactive_alpha
+        };
+        let color = |c: egui::Color32| {
+            Vec4::new(
+                c.r() as f32 / 255.0,
+                c.g() as f32 / 255.0,
+                c.b() as f32 / 255.0,
+                alpha,
+            )
+        };
+
+        let axes = [
+            (Vec3::X, Vec3::Y, Vec3::Z, color(visuals.x_color)),
+            (Vec3::Y, Vec3::Z, Vec3::X, color(visuals.y_color)),
+            (Vec3::Z, Vec3::X, Vec3::Y, color(visuals.z_color)),
+        ];
+        let view_forward = view.inverse().z_axis.truncate().normalize();
+
+        match self.tool {
+            GizmoTool::Translate | GizmoTool::Scale => {
+                for (axis, bitangent, tangent, color) in axes {
+                    let end = center + axis * size;
+                    debug
+                        .line(center + axis * inner_radius, end, color)
+                        .depth_test = self.depth_test;
+
+                    if self.tool == GizmoTool::Scale {
+                        let half = Vec3::splat(plane_size * 0.25);
+                        debug.aabb(end - half, end + half, color).depth_test = self.depth_test;
+                    }
+
+                    // Plane handle for moving along the two other axes
+                    let plane_center = center + (bitangent + tangent) * size * 0.5;
+                    let a = bitangent * plane_size * 0.5;
+                    let b = tangent * plane_size * 0.5;
+                    let corners = [
+                        plane_center - a - b,
+                        plane_center + a - b,
+                        plane_center + a + b,
+                        plane_center - a + b,
+                    ];
+                    for i in 0..corners.len() {
+                        debug
+                            .line(corners[i], corners[(i + 1) % corners.len()], color)
+                            .depth_test = self.depth_test;
+                    }
+                }
+
+                let s_color = color(visuals.s_color);
+                self.draw_ring(debug, center, view_forward, inner_radius, s_color);
+            }
+            GizmoTool::Rotate => {
+                for (axis, _, _, color) in axes {
+                    self.draw_ring(debug, center, axis, size, color);
+                }
+
+                let outer_radius = scale_factor * (visuals.gizmo_size + visuals.stroke_width + 5.0);
+                let s_color = color(visuals.s_color);
+                self.draw_ring(debug, center, view_forward, outer_radius, s_color);
+            }
+        }
+    }
+
+    fn draw_ring(
+        &self,
+        debug: &mut DebugDrawing,
+        center: Vec3,
+        normal: Vec3,
+        radius: f32,
+        color: Vec4,
+    ) {
+        let (u, v) = normal.any_orthonormal_pair();
+        let point = |i: usize| {
+            let theta = i as f32 / Self::RING_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (u * theta.cos() + v * theta.sin()) * radius
+        };
+
+        for i in 0..Self::RING_SEGMENTS {
+            debug.line(point(i), point(i + 1), color).depth_test = self.depth_test;
+        }
+    }
+}
+
+impl GizmoTool {
+    fn modes(self) -> EnumSet<GizmoMode> {
+        match self {
+            GizmoTool::Translate => GizmoMode::all_translate(),
+            GizmoTool::Rotate => GizmoMode::all_rotate(),
+            GizmoTool::Scale => GizmoMode::all_scale(),
+        }
+    }
+}
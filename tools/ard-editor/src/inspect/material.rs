@@ -6,7 +6,11 @@ use ard_engine::{
     render::{loader::MaterialHandle, material::MaterialAsset, MaterialInstance, RenderingMode},
 };
 
-use crate::{assets::meta::AssetType, gui::util};
+use crate::{
+    assets::meta::AssetType,
+    command::{entity::ModifyEntity, EditorCommands},
+    gui::util,
+};
 
 use super::Inspector;
 
@@ -25,12 +29,10 @@ impl Inspector for MaterialInspector {
 
     fn show(&mut self, ctx: super::InspectorContext) {
         let assets = ctx.res.get::<Assets>().unwrap();
-        let mut handle = ctx
-            .queries
-            .get::<Write<MaterialHandle>>(ctx.entity)
-            .unwrap();
+        let handle = ctx.queries.get::<Read<MaterialHandle>>(ctx.entity).unwrap();
 
-        let _changed = util::drag_drop_asset_target(
+        let mut new_handle = None;
+        util::drag_drop_asset_target(
             ctx.ui,
             handle
                 .0
@@ -42,14 +44,31 @@ impl Inspector for MaterialInspector {
                 _ => false,
             },
             |asset| {
-                handle.0 =
-                    asset.and_then(|asset| assets.load::<MaterialAsset>(&asset.meta_file().baked));
-                ctx.commands
-                    .entities
-                    .remove_component::<MaterialInstance>(ctx.entity);
+                new_handle = Some(
+                    asset.and_then(|asset| assets.load::<MaterialAsset>(&asset.meta_file().baked)),
+                );
                 true
             },
         );
+        std::mem::drop(handle);
+
+        let new_handle = match new_handle {
+            Some(new_handle) => new_handle,
+            None => return,
+        };
+
+        let before = ModifyEntity::snapshot(ctx.entity, ctx.queries, assets.clone());
+        ctx.queries
+            .get::<Write<MaterialHandle>>(ctx.entity)
+            .unwrap()
+            .0 = new_handle;
+        ctx.commands
+            .entities
+            .remove_component::<MaterialInstance>(ctx.entity);
+        ctx.res
+            .get_mut::<EditorCommands>()
+            .unwrap()
+            .submit(ModifyEntity::new(ctx.entity, before));
     }
 
     fn remove(&mut self, ctx: super::InspectorContext) {
@@ -7,9 +7,23 @@ use ard_engine::{
     transform::{Position, Rotation, Scale},
 };
 
+use crate::command::entity::EntityEdit;
+
 use super::{Inspector, InspectorContext};
 
-pub struct TransformInspector;
+#[derive(Default)]
+pub struct TransformInspector {
+    edit: EntityEdit,
+    /// Set when a field was interacted with last frame, meaning it might be about to change.
+    armed: bool,
+}
+
+/// How the transform fields were interacted with this frame.
+#[derive(Default)]
+struct FieldInteraction {
+    active: bool,
+    changed: bool,
+}
 
 #[derive(Tag)]
 #[storage(UncommonStorage)]
@@ -33,6 +47,12 @@ impl Inspector for TransformInspector {
     }
 
     fn show(&mut self, ctx: InspectorContext) {
+        // The entity has to be snapshotted before the fields borrow its transform
+        if self.armed {
+            self.edit.begin(ctx.entity, ctx.queries, ctx.res);
+        }
+
+        let mut fields = FieldInteraction::default();
         let position = ctx.queries.get::<Write<Position>>(ctx.entity);
         let rotation = ctx
             .queries
@@ -50,11 +70,11 @@ impl Inspector for TransformInspector {
                         ui.label("Position");
                         ui.horizontal(|ui| {
                             ui.label("x");
-                            ui.add(egui::DragValue::new(&mut position.0.x));
+                            fields.track(ui.add(egui::DragValue::new(&mut position.0.x)));
                             ui.label("y");
-                            ui.add(egui::DragValue::new(&mut position.0.y));
+                            fields.track(ui.add(egui::DragValue::new(&mut position.0.y)));
                             ui.label("z");
-                            ui.add(egui::DragValue::new(&mut position.0.z));
+                            fields.track(ui.add(egui::DragValue::new(&mut position.0.z)));
                         });
 
                         ui.end_row();
@@ -82,11 +102,11 @@ impl Inspector for TransformInspector {
                         ui.label("Rotation");
                         ui.horizontal(|ui| {
                             ui.label("x");
-                            ui.add(egui::DragValue::new(&mut euler_rot.0.x));
+                            fields.track(ui.add(egui::DragValue::new(&mut euler_rot.0.x)));
                             ui.label("y");
-                            ui.add(egui::DragValue::new(&mut euler_rot.0.y));
+                            fields.track(ui.add(egui::DragValue::new(&mut euler_rot.0.y)));
                             ui.label("z");
-                            ui.add(egui::DragValue::new(&mut euler_rot.0.z));
+                            fields.track(ui.add(egui::DragValue::new(&mut euler_rot.0.z)));
                         });
 
                         if orig != euler_rot.0 {
@@ -105,16 +125,32 @@ impl Inspector for TransformInspector {
                         ui.label("Scale");
                         ui.horizontal(|ui| {
                             ui.label("x");
-                            ui.add(egui::DragValue::new(&mut scale.0.x));
+                            fields.track(ui.add(egui::DragValue::new(&mut scale.0.x)));
                             ui.label("y");
-                            ui.add(egui::DragValue::new(&mut scale.0.y));
+                            fields.track(ui.add(egui::DragValue::new(&mut scale.0.y)));
                             ui.label("z");
-                            ui.add(egui::DragValue::new(&mut scale.0.z));
+                            fields.track(ui.add(egui::DragValue::new(&mut scale.0.z)));
                         });
 
                         ui.end_row();
                     }
                 });
         });
+
+        if fields.changed {
+            self.edit.submit(ctx.res);
+        }
+
+        self.armed = fields.active;
+        if !fields.active {
+            self.edit.end();
+        }
+    }
+}
+
+impl FieldInteraction {
+    fn track(&mut self, response: egui::Response) {
+        self.active |= response.is_pointer_button_down_on() || response.has_focus();
+        self.changed |= response.changed();
     }
 }
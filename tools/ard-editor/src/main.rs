@@ -2,6 +2,7 @@ pub mod assets;
 pub mod camera;
 pub mod clipboard;
 pub mod command;
+pub mod device_lost;
 pub mod gui;
 pub mod inspect;
 pub mod refresher;
@@ -14,8 +15,10 @@ pub mod tasks;
 use ard_engine::assets::prelude::*;
 use ard_engine::core::prelude::*;
 use ard_engine::game::{GamePlugin, IsEditor};
+use ard_engine::input::ActionMap;
 use ard_engine::physics::PhysicsPlugin;
-use ard_engine::render::prelude::PresentMode;
+use ard_engine::prefab::asset::{PrefabAsset, PrefabLoader};
+use ard_engine::render::prelude::{ColorSpace, PresentMode};
 use ard_engine::render::{CanvasSize, Gui, RenderAssetsPlugin, RenderPlugin, RendererSettings};
 use ard_engine::transform::TransformPlugin;
 use ard_engine::window::prelude::*;
@@ -24,6 +27,7 @@ use assets::{AssetManifestLoader, CurrentAssetPath, EditorAssets, EditorAssetsMa
 use camera::SceneViewCamera;
 use clipboard::Clipboard;
 use command::{EditorCommandSystem, EditorCommands};
+use device_lost::DeviceLostSystem;
 use gui::inspector::{Inspected, InspectorChangeDetectSystem};
 use gui::EditorView;
 use refresher::RefresherSystem;
@@ -54,10 +58,13 @@ fn main() {
                 present_scene: false,
                 render_time: Some(std::time::Duration::from_secs_f32(1.0 / 60.0)),
                 present_mode: PresentMode::Mailbox,
+                output_color_space: ColorSpace::SrgbNonlinear,
                 render_scale: 1.0,
                 canvas_size: CanvasSize(Some((512, 512))),
             },
             debug: true,
+            pipeline_cache_path: Some("./pipeline_cache.bin".into()),
+            adapter_preference: Default::default(),
         })
         .add_plugin(RenderAssetsPlugin)
         .add_plugin(GamePlugin)
@@ -69,6 +76,7 @@ fn main() {
         .add_system(Shlooper::default())
         .add_system(RefresherSystem::default())
         .add_system(InspectorChangeDetectSystem)
+        .add_system(DeviceLostSystem::default())
         .add_resource(Inspected::default())
         .add_resource(SceneGraph::default())
         .add_resource(Selected::default())
@@ -82,7 +90,9 @@ fn main() {
 fn setup(app: &mut App) {
     let assets = app.resources.get::<Assets>().unwrap().clone();
     assets.register::<EditorAssetsManifest>(AssetManifestLoader);
+    assets.register::<PrefabAsset>(PrefabLoader);
     app.resources.add(EditorAssets::new(&assets).unwrap());
+    EditorCommands::bind_inputs(&mut app.resources.get_mut::<ActionMap>().unwrap());
 
     let (task_runner, task_gui, task_queue) = TaskRunner::new();
 
@@ -10,7 +10,7 @@ use ard_engine::{
         collider::{self, Collider, ColliderHandle},
         engine::PhysicsEngine,
     },
-    render::{DebugDraw, DebugDrawing, EntitySelected, PreRender},
+    render::{DebugDraw, DebugDrawing, PreRender},
     transform::{Model, Parent},
 };
 use camino::Utf8PathBuf;
@@ -29,26 +29,45 @@ pub enum Selected {
     #[default]
     None,
     Entity(Entity),
+    /// Multiple entities, made by shift-clicking. Always contains at least two entities.
+    Entities(Vec<Entity>),
     Asset(Utf8PathBuf),
 }
 
+impl Selected {
+    /// Every selected entity.
+    pub fn entities(&self) -> &[Entity] {
+        match self {
+            Selected::Entity(entity) => std::slice::from_ref(entity),
+            Selected::Entities(entities) => entities,
+            _ => &[],
+        }
+    }
+
+    /// Adds `entity` to the selected entities, or removes it if it's already selected.
+    pub fn toggle(&mut self, entity: Entity) {
+        let mut entities = self.entities().to_vec();
+        match entities.iter().position(|e| *e == entity) {
+            Some(idx) => {
+                entities.remove(idx);
+            }
+            None => entities.push(entity),
+        }
+
+        *self = match entities.len() {
+            0 => Selected::None,
+            1 => Selected::Entity(entities[0]),
+            _ => Selected::Entities(entities),
+        };
+    }
+}
+
 #[derive(SystemState)]
 pub struct SelectEntitySystem;
 
 const COLLIDER_GIZMO_COLOR: Vec4 = Vec4::new(0.0, 1.0, 0.0, 1.0);
 
 impl SelectEntitySystem {
-    fn selected_entity(
-        &mut self,
-        evt: EntitySelected,
-        _: Commands,
-        _: Queries<()>,
-        res: Res<(Write<Selected>,)>,
-    ) {
-        let mut selected = res.get_mut::<Selected>().unwrap();
-        *selected = Selected::Entity(evt.0);
-    }
-
     fn tick(
         &mut self,
         _: Tick,
@@ -140,6 +159,7 @@ impl SelectEntitySystem {
                         max_pt: *half_extents,
                         model,
                     },
+                    depth_test: false,
                 }),
                 collider::Shape::Ball { radius } => debug.draw(DebugDraw {
                     color: COLLIDER_GIZMO_COLOR,
@@ -148,6 +168,7 @@ impl SelectEntitySystem {
                         model,
                         segments: NonZeroUsize::new(32).unwrap(),
                     },
+                    depth_test: false,
                 }),
                 collider::Shape::Cylinder { height, radius } => debug.draw(DebugDraw {
                     color: COLLIDER_GIZMO_COLOR,
@@ -157,6 +178,7 @@ impl SelectEntitySystem {
                         model,
                         segments: NonZeroUsize::new(32).unwrap(),
                     },
+                    depth_test: false,
                 }),
                 collider::Shape::Cone { height, radius } => debug.draw(DebugDraw {
                     color: COLLIDER_GIZMO_COLOR,
@@ -166,6 +188,7 @@ impl SelectEntitySystem {
                         model,
                         segments: NonZeroUsize::new(32).unwrap(),
                     },
+                    depth_test: false,
                 }),
                 collider::Shape::Capsule { height, radius } => debug.draw(DebugDraw {
                     color: COLLIDER_GIZMO_COLOR,
@@ -175,8 +198,9 @@ impl SelectEntitySystem {
                         model,
                         segments: NonZeroUsize::new(32).unwrap(),
                     },
+                    depth_test: false,
                 }),
-            }
+            };
         }
     }
 }
@@ -184,7 +208,6 @@ impl SelectEntitySystem {
 impl From<SelectEntitySystem> for System {
     fn from(value: SelectEntitySystem) -> Self {
         SystemBuilder::new(value)
-            .with_handler(SelectEntitySystem::selected_entity)
             .with_handler(SelectEntitySystem::tick)
             .with_handler(SelectEntitySystem::pre_render)
             .build()
@@ -1,5 +1,8 @@
 use anyhow::Result;
-use ard_engine::{assets::prelude::*, ecs::prelude::*, log::warn, render::model::ModelAsset};
+use ard_engine::{
+    assets::prelude::*, ecs::prelude::*, log::warn, prefab::asset::PrefabAsset,
+    render::model::ModelAsset,
+};
 
 use crate::{
     assets::meta::{MetaData, MetaFile},
@@ -16,6 +19,7 @@ pub struct InstantiateTask {
 
 pub enum InstantiateAssetHandle {
     Model(Handle<ModelAsset>),
+    Prefab(Handle<PrefabAsset>),
 }
 
 impl InstantiateTask {
@@ -52,6 +56,19 @@ impl EditorTask for InstantiateTask {
                 self.assets.wait_for_load(&handle);
                 self.handle = Some(InstantiateAssetHandle::Model(handle));
             }
+            MetaData::Prefab => {
+                let handle = match self.assets.load::<PrefabAsset>(&self.asset.baked) {
+                    Some(handle) => handle,
+                    None => {
+                        return Err(anyhow::Error::msg(format!(
+                            "could not load {:?}",
+                            self.asset.baked
+                        )))
+                    }
+                };
+                self.assets.wait_for_load(&handle);
+                self.handle = Some(InstantiateAssetHandle::Prefab(handle));
+            }
             _ => {}
         }
 
@@ -4,7 +4,7 @@ use ard_engine::{
     assets::prelude::*,
     ecs::prelude::*,
     formats::material::{BlendType, MaterialHeader, MaterialType},
-    math::Vec4,
+    math::{Mat3, Vec3, Vec4},
     render::material::MaterialAsset,
 };
 use camino::Utf8PathBuf;
@@ -129,6 +129,11 @@ impl EditorTask for CreateMaterialTask {
                 diffuse_map: None,
                 normal_map: None,
                 metallic_roughness_map: None,
+                emissive: Vec3::ZERO,
+                emissive_map: None,
+                occlusion_strength: 1.0,
+                occlusion_map: None,
+                uv_transform: Mat3::IDENTITY,
             },
         };
 
@@ -13,7 +13,7 @@ use crate::{
         meta::{MetaData, MetaFile},
         CurrentAssetPath, EditorAssets,
     },
-    tasks::{EditorTask, TaskConfirmation},
+    tasks::{EditorTask, TaskConfirmation, TaskQueue},
 };
 
 use super::TaskState;
@@ -25,9 +25,16 @@ pub struct ModelImportTask {
     meta_rel_path: PathBuf,
     meta_dst_path: PathBuf,
     new_assets: Vec<AssetNameBuf>,
+    diagnostics: Vec<String>,
     state: TaskState,
 }
 
+/// Lists the warnings reported by the oven while importing a model.
+pub struct ModelDiagnosticsTask {
+    src_path: PathBuf,
+    diagnostics: Vec<String>,
+}
+
 impl ModelImportTask {
     pub fn new(path: PathBuf) -> Self {
         Self {
@@ -38,6 +45,7 @@ impl ModelImportTask {
             meta_rel_path: PathBuf::default(),
             meta_dst_path: PathBuf::default(),
             new_assets: Vec::default(),
+            diagnostics: Vec::default(),
         }
     }
 }
@@ -123,6 +131,15 @@ impl EditorTask for ModelImportTask {
             return Err(anyhow::Error::msg(err_msg));
         }
 
+        // Forward anything the oven had trouble with so it shows up in the editor
+        for warning in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("WARNING: "))
+        {
+            warn!("`{}`: {warning}", self.src_path.display());
+            self.diagnostics.push(warning.to_owned());
+        }
+
         self.state.set_completion(0.33);
 
         // Find the primary model asset
@@ -197,6 +214,50 @@ impl EditorTask for ModelImportTask {
 
         println!("Task complete...");
 
+        if !self.diagnostics.is_empty() {
+            res.get::<TaskQueue>().unwrap().add(ModelDiagnosticsTask {
+                src_path: self.src_path.clone(),
+                diagnostics: std::mem::take(&mut self.diagnostics),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl EditorTask for ModelDiagnosticsTask {
+    fn confirm_ui(&mut self, ui: &mut egui::Ui) -> Result<TaskConfirmation> {
+        ui.label(format!(
+            "`{}` was imported with {} warning(s):",
+            self.src_path.display(),
+            self.diagnostics.len()
+        ));
+
+        egui::ScrollArea::vertical()
+            .max_height(256.0)
+            .show(ui, |ui| {
+                for warning in &self.diagnostics {
+                    ui.label(warning);
+                }
+            });
+
+        if ui.button("Close").clicked() {
+            return Ok(TaskConfirmation::Cancel);
+        }
+
+        Ok(TaskConfirmation::Wait)
+    }
+
+    fn run(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn complete(
+        &mut self,
+        _commands: &Commands,
+        _queries: &Queries<Everything>,
+        _res: &Res<Everything>,
+    ) -> Result<()> {
         Ok(())
     }
 }
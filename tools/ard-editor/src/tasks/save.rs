@@ -50,6 +50,14 @@ impl SaveSceneTask {
         }
     }
 
+    /// Saves as a new scene with the given name without asking for confirmation.
+    pub fn new_named(containing_folder: impl Into<Utf8PathBuf>, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::new(containing_folder)
+        }
+    }
+
     pub fn new_overwrite(asset: &EditorAsset) -> Self {
         Self {
             assets: None,
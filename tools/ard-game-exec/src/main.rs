@@ -4,7 +4,7 @@ use ard_engine::game::save_data::{InitialSceneAsset, SceneAsset, INITIAL_SCENE_A
 use ard_engine::game::settings::GameSettings;
 use ard_engine::game::{GamePlugin, GameStart};
 use ard_engine::physics::PhysicsPlugin;
-use ard_engine::render::prelude::PresentMode;
+use ard_engine::render::prelude::{ColorSpace, PresentMode};
 use ard_engine::render::{CanvasSize, RenderAssetsPlugin, RenderPlugin, RendererSettings};
 use ard_engine::save_load::format::Ron;
 use ard_engine::transform::TransformPlugin;
@@ -32,10 +32,13 @@ fn main() {
                 present_scene: true,
                 render_time: None,
                 present_mode: PresentMode::Mailbox,
+                output_color_space: ColorSpace::SrgbNonlinear,
                 render_scale: 1.0,
                 canvas_size: CanvasSize(None),
             },
             debug: true,
+            pipeline_cache_path: Some("./pipeline_cache.bin".into()),
+            adapter_preference: Default::default(),
         })
         .add_plugin(RenderAssetsPlugin)
         .add_plugin(GamePlugin)
@@ -4,14 +4,18 @@ use std::ops::Div;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use ard_animation::clip::AnimationClip;
+use ard_animation::skeleton::Skeleton;
 use ard_assets::asset::{AssetName, AssetNameBuf};
 use ard_formats::material::{BlendType, MaterialHeader, MaterialType};
 use ard_formats::mesh::{MeshDataBuilder, MeshHeader};
-use ard_formats::model::{Light, MeshGroup, MeshInstance, ModelHeader, Node, NodeData};
+use ard_formats::meshlet::{Meshlet, MeshletSettings, MeshletStats};
+use ard_formats::model::{Light, MeshGroup, MeshInstance, ModelHeader, Node, NodeData, SkinHeader};
+use ard_formats::simplify::LodSettings;
 use ard_formats::texture::{Sampler, TextureData, TextureHeader};
 use ard_formats::vertex::VertexLayout;
-use ard_gltf::{GltfLight, GltfMesh, GltfTexture};
-use ard_math::{Mat4, Vec2, Vec3, Vec4};
+use ard_gltf::{GltfImportOptions, GltfLight, GltfMesh, GltfModel};
+use ard_math::{Mat4, Vec3, Vec4};
 use ard_pal::prelude::Format;
 use ard_transform::Model;
 use clap::Parser;
@@ -20,13 +24,13 @@ use image::GenericImageView;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to the model to bake.
+    /// Path to the model to bake. Either a `.glb` or a `.gltf` with external files.
     #[arg(short, long)]
     path: PathBuf,
     /// Output path for the model.
     #[arg(short, long)]
     out: Option<PathBuf>,
-    /// Compute tangents based on UVs.
+    /// Compute tangents based on UVs for meshes that don't have them.
     #[arg(long, default_value_t = false)]
     compute_tangents: bool,
     /// Compress textures.
@@ -35,15 +39,34 @@ struct Args {
     /// Use UUID file names.
     #[arg(long, default_value_t = false)]
     uuid_names: bool,
+    /// Maximum number of textures to decode at once. Lower values reduce peak memory usage.
+    /// Defaults to the number of threads.
+    #[arg(long)]
+    max_textures_in_flight: Option<usize>,
+    /// Maximum number of vertices per meshlet.
+    #[arg(long, default_value_t = Meshlet::MAX_VERTICES)]
+    max_meshlet_vertices: usize,
+    /// Maximum number of primitives per meshlet.
+    #[arg(long, default_value_t = Meshlet::MAX_PRIMITIVES)]
+    max_meshlet_primitives: usize,
+    /// Don't compute normal cones for meshlets, so they are never cone culled.
+    #[arg(long, default_value_t = false)]
+    no_meshlet_cones: bool,
+    /// Number of levels of detail to generate for each mesh, including the original. Fewer
+    /// levels are generated if a mesh can't be simplified further.
+    #[arg(long, default_value_t = 1)]
+    lods: usize,
+    /// Fraction of the triangles of the previous level each level of detail targets.
+    #[arg(long, default_value_t = 0.5)]
+    lod_triangle_ratio: f32,
+    /// Largest error of a level of detail relative to the bounding radius of its mesh.
+    #[arg(long, default_value_t = 0.02)]
+    lod_max_error: f32,
 }
 
 fn main() {
     let args = Args::parse();
 
-    // Load in the model
-    println!("Loading model...");
-    let bin = fs::read(&args.path).unwrap();
-
     // Output folder path
     let out_path = match &args.out {
         Some(path) => path.clone(),
@@ -60,10 +83,15 @@ fn main() {
 
     std::fs::create_dir_all(&out_path).unwrap();
 
-    // Parse the model
-    println!("Parsing model...");
-    let mut model = ard_gltf::GltfModel::from_slice(&bin).unwrap();
-    std::mem::drop(bin);
+    // Load and parse the model. External buffers and images are loaded relative to the model
+    println!("Loading model...");
+    let options = GltfImportOptions {
+        generate_tangents: args.compute_tangents,
+    };
+    let mut model = GltfModel::from_gltf_with_options(&args.path, options).unwrap();
+    for warning in model.diagnostics() {
+        println!("WARNING: {warning}");
+    }
 
     // For each texture, we mark if it was used in a way that needs a UNORM color format and not
     // SRGB.
@@ -94,20 +122,22 @@ fn main() {
 
     println!("Constructing header...");
     let mut header = create_header(&args, &out_path, &model, &texture_is_unorm, &texture_paths);
+    header.skins = save_skins(&args, &out_path, &model);
 
     // Save everything
     println!("Saving meshes and textures...");
-    let (mesh_headers, _) = rayon::join(
-        || save_meshes(&args, &out_path, std::mem::take(&mut model.meshes)),
-        || {
-            save_textures(
-                &args,
-                &out_path,
-                std::mem::take(&mut model.textures),
-                &texture_is_unorm,
-                &texture_paths,
-            )
-        },
+    let meshes = std::mem::take(&mut model.meshes);
+    let ((mesh_headers, meshlet_stats), _) = rayon::join(
+        || save_meshes(&args, &out_path, meshes),
+        || save_textures(&args, &out_path, &model, &texture_is_unorm, &texture_paths),
+    );
+
+    println!(
+        "Built {} meshlets with {:.1}% vertex and {:.1}% primitive occupancy. {} have no cone.",
+        meshlet_stats.meshlet_count,
+        meshlet_stats.vertex_occupancy() * 100.0,
+        meshlet_stats.primitive_occupancy() * 100.0,
+        meshlet_stats.disabled_cones,
     );
 
     // Save the header
@@ -186,6 +216,10 @@ fn create_header(
                 diffuse_map,
                 normal_map,
                 metallic_roughness_map,
+                emissive_factor,
+                emissive_map,
+                occlusion_strength,
+                occlusion_map,
                 blending,
             } => MaterialHeader {
                 blend_ty: match *blending {
@@ -199,15 +233,24 @@ fn create_header(
                     roughness: *roughness,
                     alpha_cutoff: *alpha_cutoff,
                     diffuse_map: diffuse_map
-                        .map(|v| PathBuf::from(texture_paths[v].file_name().unwrap())),
+                        .map(|v| PathBuf::from(texture_paths[v.texture].file_name().unwrap())),
                     normal_map: normal_map.map(|v| {
-                        texture_is_unorm[v].store(true, Ordering::Relaxed);
-                        PathBuf::from(texture_paths[v].file_name().unwrap())
+                        texture_is_unorm[v.texture].store(true, Ordering::Relaxed);
+                        PathBuf::from(texture_paths[v.texture].file_name().unwrap())
                     }),
                     metallic_roughness_map: metallic_roughness_map.map(|v| {
-                        texture_is_unorm[v].store(true, Ordering::Relaxed);
-                        PathBuf::from(texture_paths[v].file_name().unwrap())
+                        texture_is_unorm[v.texture].store(true, Ordering::Relaxed);
+                        PathBuf::from(texture_paths[v.texture].file_name().unwrap())
                     }),
+                    emissive: *emissive_factor,
+                    emissive_map: emissive_map
+                        .map(|v| PathBuf::from(texture_paths[v.texture].file_name().unwrap())),
+                    occlusion_strength: *occlusion_strength,
+                    occlusion_map: occlusion_map.map(|v| {
+                        texture_is_unorm[v.texture].store(true, Ordering::Relaxed);
+                        PathBuf::from(texture_paths[v.texture].file_name().unwrap())
+                    }),
+                    uv_transform: material.uv_transform(),
                 },
             },
         };
@@ -259,6 +302,12 @@ fn create_header(
             data: match &node.data {
                 ard_gltf::GltfNodeData::Empty => NodeData::Empty,
                 ard_gltf::GltfNodeData::MeshGroup(id) => NodeData::MeshGroup(*id as u32),
+                ard_gltf::GltfNodeData::SkinnedMeshGroup { mesh_group, skin } => {
+                    NodeData::SkinnedMeshGroup {
+                        mesh_group: *mesh_group as u32,
+                        skin: *skin as u32,
+                    }
+                }
                 ard_gltf::GltfNodeData::Light(id) => NodeData::Light(*id as u32),
             },
             children: Vec::with_capacity(node.children.len()),
@@ -295,9 +344,70 @@ fn create_header(
     header
 }
 
-fn save_meshes(args: &Args, out: &AssetName, meshes: Vec<GltfMesh>) -> Vec<AssetNameBuf> {
+/// Saves the skeleton of every skin along with clips for every animation that moves its joints.
+fn save_skins(args: &Args, out: &AssetName, model: &GltfModel) -> Vec<SkinHeader> {
+    let out_path = |path: AssetNameBuf, extension: &str| {
+        if args.uuid_names {
+            let mut path = AssetNameBuf::from(out);
+            path.push(format!("{}.{extension}", uuid::Uuid::new_v4()));
+            path
+        } else {
+            let mut dir = path.clone();
+            dir.pop();
+            std::fs::create_dir_all(&dir).unwrap();
+            path
+        }
+    };
+
+    let header_path = |path: &AssetNameBuf| {
+        if args.uuid_names {
+            AssetNameBuf::from(path.file_name().unwrap())
+        } else {
+            path.clone()
+        }
+    };
+
+    model
+        .skins
+        .iter()
+        .enumerate()
+        .map(|(i, skin)| {
+            let skeleton_path = out_path(ModelHeader::skeleton_path(out, i), "ard_skel");
+            let skeleton = Skeleton::from_gltf(skin, &model.roots);
+            let mut f = BufWriter::new(fs::File::create(&skeleton_path).unwrap());
+            bincode::serialize_into(&mut f, &skeleton).unwrap();
+
+            // Clips only contain channels for the joints of their skin, so animations that don't
+            // move the skin are skipped
+            let clips = model
+                .animations
+                .iter()
+                .map(|animation| AnimationClip::from_gltf(animation, skin))
+                .filter(|clip| !clip.channels().is_empty())
+                .enumerate()
+                .map(|(j, clip)| {
+                    let clip_path = out_path(ModelHeader::clip_path(out, i, j), "ard_anim");
+                    let mut f = BufWriter::new(fs::File::create(&clip_path).unwrap());
+                    bincode::serialize_into(&mut f, &clip).unwrap();
+                    header_path(&clip_path)
+                })
+                .collect();
+
+            SkinHeader {
+                skeleton: header_path(&skeleton_path),
+                clips,
+            }
+        })
+        .collect()
+}
+
+fn save_meshes(
+    args: &Args,
+    out: &AssetName,
+    meshes: Vec<GltfMesh>,
+) -> (Vec<AssetNameBuf>, MeshletStats) {
     use rayon::prelude::*;
-    meshes
+    let saved: Vec<_> = meshes
         .into_par_iter()
         .enumerate()
         .map(|(i, mesh)| {
@@ -310,10 +420,21 @@ fn save_meshes(args: &Args, out: &AssetName, meshes: Vec<GltfMesh>) -> Vec<Asset
             };
             save_mesh(args, &mesh_path, mesh)
         })
-        .collect()
+        .collect();
+
+    let mut stats = MeshletStats::default();
+    let paths = saved
+        .into_iter()
+        .map(|(path, mesh_stats)| {
+            stats.merge(&mesh_stats);
+            path
+        })
+        .collect();
+
+    (paths, stats)
 }
 
-fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> AssetNameBuf {
+fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> (AssetNameBuf, MeshletStats) {
     let (mesh_data_path, mesh_header_path) = if args.uuid_names {
         let mut mesh_data_path = AssetNameBuf::from(out);
         mesh_data_path.push(uuid::Uuid::new_v4().to_string());
@@ -343,7 +464,7 @@ fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> AssetNameBuf {
         t
     });
 
-    if mesh.tangents.is_some() || (args.compute_tangents && mesh.uv0.is_some()) {
+    if mesh.tangents.is_some() {
         vertex_layout |= VertexLayout::TANGENT;
     }
 
@@ -355,6 +476,10 @@ fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> AssetNameBuf {
         vertex_layout |= VertexLayout::UV1;
     }
 
+    if mesh.joints.is_some() && mesh.weights.is_some() {
+        vertex_layout |= VertexLayout::SKIN;
+    }
+
     // Build vertex data
     let mut mesh_data =
         MeshDataBuilder::new(vertex_layout, mesh.positions.len(), mesh.indices.len());
@@ -372,20 +497,11 @@ fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> AssetNameBuf {
     };
     mesh.normals = None;
 
-    // Check if we can compute tangents
-    if args.compute_tangents {
-        if let Some(uvs) = &mesh.uv0 {
-            let tangents = compute_tangents(&mesh.positions, uvs, &mesh.indices);
-            mesh_data = mesh_data.add_vec4_tangents(&tangents);
-        }
-    } else {
-        if let Some(tangents) = &mesh.tangents {
-            mesh_data = mesh_data.add_vec4_tangents(&tangents);
-        }
+    if let Some(tangents) = &mesh.tangents {
+        mesh_data = mesh_data.add_vec4_tangents(&tangents);
     }
     mesh.tangents = None;
 
-    // Clear these here since it's possible they might be used to compute tangents
     mesh.positions = Vec::default();
     mesh.indices = Vec::default();
 
@@ -399,8 +515,30 @@ fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> AssetNameBuf {
     }
     mesh.uv1 = None;
 
+    if vertex_layout.contains(VertexLayout::SKIN) {
+        mesh_data = mesh_data
+            .add_joints(mesh.joints.as_ref().unwrap())
+            .add_weights(mesh.weights.as_ref().unwrap());
+    }
+    mesh.joints = None;
+    mesh.weights = None;
+
     // Save the buffer
+    let meshlet_settings = MeshletSettings {
+        max_vertices: args.max_meshlet_vertices,
+        max_primitives: args.max_meshlet_primitives,
+        cone_culling: !args.no_meshlet_cones,
+    };
+    mesh_data = mesh_data.meshlet_settings(meshlet_settings);
+    if args.lods > 1 {
+        mesh_data = mesh_data.lod_settings(LodSettings {
+            levels: args.lods,
+            triangle_ratio: args.lod_triangle_ratio,
+            max_error: args.lod_max_error,
+        });
+    }
     let data = mesh_data.build();
+    let meshlet_stats = MeshletStats::from_meshlets(data.meshlets(), &meshlet_settings);
     let mut f = BufWriter::new(fs::File::create(&mesh_data_path).unwrap());
     bincode::serialize_into(&mut f, &data).unwrap();
 
@@ -410,33 +548,37 @@ fn save_mesh(args: &Args, out: &AssetName, mut mesh: GltfMesh) -> AssetNameBuf {
         index_count: data.index_count() as u32,
         vertex_count: data.vertex_count() as u32,
         meshlet_count: data.meshlet_count() as u32,
+        lod_count: data.lods().len() as u32,
         vertex_layout,
     };
     let mut f = BufWriter::new(fs::File::create(&mesh_header_path).unwrap());
     bincode::serialize_into(&mut f, &header).unwrap();
 
-    AssetNameBuf::from(mesh_header_path.file_name().unwrap())
+    (
+        AssetNameBuf::from(mesh_header_path.file_name().unwrap()),
+        meshlet_stats,
+    )
 }
 
 fn save_textures(
     args: &Args,
     out: &AssetName,
-    textures: Vec<GltfTexture>,
+    model: &GltfModel,
     texture_is_unorm: &[AtomicBool],
     texture_paths: &[AssetNameBuf],
 ) {
-    use rayon::prelude::*;
-    textures
-        .into_par_iter()
-        .enumerate()
-        .for_each(|(i, mut texture)| {
+    let max_in_flight = args
+        .max_textures_in_flight
+        .unwrap_or_else(rayon::current_num_threads);
+
+    model
+        .for_each_texture(max_in_flight, |i, texture, data| {
             // Parse the image
             let image_fmt = match texture.src_format {
                 ard_gltf::TextureSourceFormat::Png => image::ImageFormat::Png,
                 ard_gltf::TextureSourceFormat::Jpeg => image::ImageFormat::Jpeg,
             };
-            let image = image::load_from_memory_with_format(&texture.data, image_fmt).unwrap();
-            texture.data = Vec::default();
+            let image = image::load_from_memory_with_format(data, image_fmt).unwrap();
 
             let compress = args.compress_textures && texture_needs_compression(&image);
             let mip_count = texture_mip_count(&image, compress);
@@ -527,7 +669,8 @@ fn save_textures(
                 let mut f = BufWriter::new(fs::File::create(mip_out_path).unwrap());
                 bincode::serialize_into(&mut f, &tex_data).unwrap();
             }
-        });
+        })
+        .unwrap();
 }
 
 /// Helper to determine if a texture needs compression.
@@ -549,43 +692,3 @@ fn texture_mip_count(image: &image::DynamicImage, compressed: bool) -> usize {
         (width.max(height) as f32).log2() as usize + 1
     }
 }
-
-/// Helper to compute tangents from UVs and positions
-fn compute_tangents(positions: &[Vec4], uvs: &[Vec2], indices: &[u32]) -> Vec<Vec4> {
-    assert!(!positions.is_empty());
-    assert!(positions.len() == uvs.len());
-
-    let mut tangents = vec![Vec4::Z; positions.len()];
-
-    for tri in indices.chunks_exact(3) {
-        let p0 = &positions[tri[0] as usize];
-        let p1 = &positions[tri[1] as usize];
-        let p2 = &positions[tri[2] as usize];
-
-        let uv0 = &uvs[tri[0] as usize];
-        let uv1 = &uvs[tri[1] as usize];
-        let uv2 = &uvs[tri[2] as usize];
-
-        let edge1 = *p1 - *p0;
-        let edge2 = *p2 - *p0;
-
-        let delta_uv1 = *uv1 - *uv0;
-        let delta_uv2 = *uv2 - *uv0;
-
-        let f = 1.0 / ((delta_uv1.x * delta_uv2.y) - (delta_uv2.x * delta_uv1.y));
-
-        let t = Vec4::new(
-            f * (delta_uv2.y * edge1.x - delta_uv1.y * edge2.x),
-            f * (delta_uv2.y * edge1.y - delta_uv1.y * edge2.y),
-            f * (delta_uv2.y * edge1.z - delta_uv1.y * edge2.z),
-            0.0,
-        )
-        .normalize();
-
-        tangents[tri[0] as usize] = t;
-        tangents[tri[1] as usize] = t;
-        tangents[tri[2] as usize] = t;
-    }
-
-    tangents
-}
@@ -47,6 +47,9 @@ fn main() {
         engine_name: String::from("ard-engine"),
         window: &window,
         debug: true,
+        debug_config: Default::default(),
+        pipeline_cache_path: None,
+        device_memory_budget: None,
     })
     .unwrap();
 
@@ -167,6 +170,9 @@ fn load_image(pal: &Context, args: &Args) -> Texture {
             texture_usage: TextureUsage::SAMPLED | TextureUsage::TRANSFER_DST,
             memory_usage: MemoryUsage::GpuOnly,
             debug_name: Some(String::from("hdr_texture")),
+            sparse: false,
+            initial_data: None,
+            memory_fallback: MemoryFallback::None,
         },
     )
     .unwrap();
@@ -209,6 +215,7 @@ fn to_cube_map(pal: &Context, args: &Args, hdr_texture: &Texture) -> CubeMap {
                 | TextureUsage::TRANSFER_DST,
             memory_usage: MemoryUsage::GpuOnly,
             debug_name: Some(String::from("cubemap")),
+            initial_data: None,
         },
     )
     .unwrap();
@@ -221,7 +228,10 @@ fn to_cube_map(pal: &Context, args: &Args, hdr_texture: &Texture) -> CubeMap {
                 ty: DescriptorType::Texture,
                 count: 1,
                 stage: ShaderStage::Fragment,
+                flags: DescriptorBindingFlags::empty(),
             }],
+            push: false,
+            debug_name: None,
         },
     )
     .unwrap();
@@ -231,6 +241,7 @@ fn to_cube_map(pal: &Context, args: &Args, hdr_texture: &Texture) -> CubeMap {
         DescriptorSetCreateInfo {
             layout: layout.clone(),
             debug_name: Some(String::from("cube_map_gen_set")),
+            variable_count: None,
         },
     )
     .unwrap();
@@ -250,6 +261,7 @@ fn to_cube_map(pal: &Context, args: &Args, hdr_texture: &Texture) -> CubeMap {
                 address_w: SamplerAddressMode::ClampToEdge,
                 anisotropy: None,
                 compare: None,
+                reduction_mode: None,
                 min_lod: NotNan::new(0.0).unwrap(),
                 max_lod: None,
                 border_color: None,
@@ -257,6 +269,7 @@ fn to_cube_map(pal: &Context, args: &Args, hdr_texture: &Texture) -> CubeMap {
             },
             base_mip: 0,
             mip_count: 1,
+            aspect: None,
         },
     }]);
 
@@ -305,7 +318,9 @@ fn to_cube_map(pal: &Context, args: &Args, hdr_texture: &Texture) -> CubeMap {
                 }],
             },
             push_constants_size: Some(std::mem::size_of::<PushConstants>() as u32),
+            compile_mode: PipelineCompileMode::Blocking,
             debug_name: Some(String::from("eq_to_cube")),
+            dynamic_states: DynamicStates::empty(),
         },
     )
     .unwrap();
@@ -427,6 +442,7 @@ fn create_diffuse_irradiance(pal: &Context, cube_map: &CubeMap) -> CubeMap {
             texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::TRANSFER_SRC,
             memory_usage: MemoryUsage::GpuOnly,
             debug_name: Some(String::from("diffuse_irradiance_map")),
+            initial_data: None,
         },
     )
     .unwrap();
@@ -439,7 +455,10 @@ fn create_diffuse_irradiance(pal: &Context, cube_map: &CubeMap) -> CubeMap {
                 ty: DescriptorType::CubeMap,
                 count: 1,
                 stage: ShaderStage::Fragment,
+                flags: DescriptorBindingFlags::empty(),
             }],
+            push: false,
+            debug_name: None,
         },
     )
     .unwrap();
@@ -449,6 +468,7 @@ fn create_diffuse_irradiance(pal: &Context, cube_map: &CubeMap) -> CubeMap {
         DescriptorSetCreateInfo {
             layout: layout.clone(),
             debug_name: Some(String::from("diffuse_irradiance_gen_set")),
+            variable_count: None,
         },
     )
     .unwrap();
@@ -468,6 +488,7 @@ fn create_diffuse_irradiance(pal: &Context, cube_map: &CubeMap) -> CubeMap {
                 address_w: SamplerAddressMode::ClampToEdge,
                 anisotropy: None,
                 compare: None,
+                reduction_mode: None,
                 min_lod: NotNan::new(0.0).unwrap(),
                 max_lod: None,
                 border_color: None,
@@ -523,7 +544,9 @@ fn create_diffuse_irradiance(pal: &Context, cube_map: &CubeMap) -> CubeMap {
                 }],
             },
             push_constants_size: Some(std::mem::size_of::<PushConstants>() as u32),
+            compile_mode: PipelineCompileMode::Blocking,
             debug_name: Some(String::from("diffuse_irradiance_gen")),
+            dynamic_states: DynamicStates::empty(),
         },
     )
     .unwrap();
@@ -559,6 +582,7 @@ fn create_prefiltered_env_map(pal: &Context, cube_map: &CubeMap) -> CubeMap {
             texture_usage: TextureUsage::COLOR_ATTACHMENT | TextureUsage::TRANSFER_SRC,
             memory_usage: MemoryUsage::GpuOnly,
             debug_name: Some(String::from("prefiltered_environment_map")),
+            initial_data: None,
         },
     )
     .unwrap();
@@ -571,7 +595,10 @@ fn create_prefiltered_env_map(pal: &Context, cube_map: &CubeMap) -> CubeMap {
                 ty: DescriptorType::CubeMap,
                 count: 1,
                 stage: ShaderStage::Fragment,
+                flags: DescriptorBindingFlags::empty(),
             }],
+            push: false,
+            debug_name: None,
         },
     )
     .unwrap();
@@ -581,6 +608,7 @@ fn create_prefiltered_env_map(pal: &Context, cube_map: &CubeMap) -> CubeMap {
         DescriptorSetCreateInfo {
             layout: layout.clone(),
             debug_name: Some(String::from("prefiltered_env_map_gen_set")),
+            variable_count: None,
         },
     )
     .unwrap();
@@ -600,6 +628,7 @@ fn create_prefiltered_env_map(pal: &Context, cube_map: &CubeMap) -> CubeMap {
                 address_w: SamplerAddressMode::ClampToEdge,
                 anisotropy: None,
                 compare: None,
+                reduction_mode: None,
                 min_lod: NotNan::new(0.0).unwrap(),
                 max_lod: None,
                 border_color: None,
@@ -655,7 +684,9 @@ fn create_prefiltered_env_map(pal: &Context, cube_map: &CubeMap) -> CubeMap {
                 }],
             },
             push_constants_size: Some(std::mem::size_of::<PushConstants>() as u32),
+            compile_mode: PipelineCompileMode::Blocking,
             debug_name: Some(String::from("prefiltered_env_gen")),
+            dynamic_states: DynamicStates::empty(),
         },
     )
     .unwrap();
@@ -693,6 +724,7 @@ fn cube_maps_to_buffers(pal: &Context, cube_maps: &[&CubeMap]) -> Vec<Buffer> {
                     buffer_usage: BufferUsage::TRANSFER_DST,
                     memory_usage: MemoryUsage::GpuToCpu,
                     debug_name: Some(String::from("saved_cube_map")),
+                    memory_fallback: MemoryFallback::None,
                 },
             )
             .unwrap(),
@@ -711,6 +743,7 @@ fn cube_maps_to_buffers(pal: &Context, cube_maps: &[&CubeMap]) -> Vec<Buffer> {
                     buffer_array_element: 0,
                     cube_map_mip_level: mip,
                     cube_map_array_element: 0,
+                    face: None,
                 },
             );
             offset += cube_map_mip_size(cube_map.dim(), false, mip as u32);
@@ -946,6 +979,8 @@ fn draw_cube_faces<'a, 'b>(
                 store_op: StoreOp::Store,
             }],
             depth_stencil_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         },
         |pass| {
             pass.bind_pipeline(pipeline.clone());
@@ -968,6 +1003,8 @@ fn draw_cube_faces<'a, 'b>(
                 store_op: StoreOp::Store,
             }],
             depth_stencil_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         },
         |pass| {
             pass.bind_pipeline(pipeline.clone());
@@ -990,6 +1027,8 @@ fn draw_cube_faces<'a, 'b>(
                 store_op: StoreOp::Store,
             }],
             depth_stencil_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         },
         |pass| {
             pass.bind_pipeline(pipeline.clone());
@@ -1012,6 +1051,8 @@ fn draw_cube_faces<'a, 'b>(
                 store_op: StoreOp::Store,
             }],
             depth_stencil_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         },
         |pass| {
             pass.bind_pipeline(pipeline.clone());
@@ -1034,6 +1075,8 @@ fn draw_cube_faces<'a, 'b>(
                 store_op: StoreOp::Store,
             }],
             depth_stencil_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         },
         |pass| {
             pass.bind_pipeline(pipeline.clone());
@@ -1056,6 +1099,8 @@ fn draw_cube_faces<'a, 'b>(
                 store_op: StoreOp::Store,
             }],
             depth_stencil_attachment: None,
+            view_mask: 0,
+            correlation_masks: Vec::default(),
         },
         |pass| {
             pass.bind_pipeline(pipeline.clone());